@@ -0,0 +1,42 @@
+;; A component exporting morphir:ext/validator: IR longer than `{}` is valid,
+;; and any options fail the call with execution-failed("boom")
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (global $heap (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      (local.set $ptr (i32.and (i32.add (global.get $heap) (i32.const 7)) (i32.const -8)))
+      (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+    ;; err(execution-failed("boom"))
+    (data (i32.const 48) "\01\00\00\00\01\00\00\00\60\00\00\00\04\00\00\00")
+    (data (i32.const 96) "boom")
+    (func (export "validate") (param $ir i32) (param $ir_len i32) (param $opts i32) (param $opts_len i32) (result i32)
+      (if (i32.gt_u (local.get $opts_len) (i32.const 2)) (then (return (i32.const 48))))
+      ;; ok({valid, diagnostics: []})
+      (i32.store8 (i32.const 20) (i32.gt_u (local.get $ir_len) (i32.const 2)))
+      (i32.const 16)))
+  (core instance $i (instantiate $m))
+  (type $location' (record (field "file" string) (field "start-line" u32) (field "start-col" u32) (field "end-line" u32) (field "end-col" u32)))
+  (export $location "source-location" (type $location'))
+  (type $related' (record (field "location" $location) (field "message" string)))
+  (export $related "related-information" (type $related'))
+  (type $edit' (record (field "location" $location) (field "new-text" string)))
+  (export $edit "text-edit" (type $edit'))
+  (type $fix' (record (field "title" string) (field "edits" (list $edit))))
+  (export $fix "code-fix" (type $fix'))
+  (type $severity' (enum "error" "warning" "info" "hint"))
+  (export $severity "diagnostic-severity" (type $severity'))
+  (type $diagnostic' (record (field "severity" $severity) (field "code" (option string)) (field "message" string) (field "location" (option $location)) (field "related" (list $related)) (field "fixes" (list $fix))))
+  (export $diagnostic "diagnostic" (type $diagnostic'))
+  (type $request' (record (field "ir" string) (field "options" string)))
+  (export $request "validate-request" (type $request'))
+  (type $result' (record (field "valid" bool) (field "diagnostics" (list $diagnostic))))
+  (export $result "validate-result" (type $result'))
+  (type $error' (variant (case "unsupported" string) (case "execution-failed" string) (case "host" string)))
+  (export $error "extension-error" (type $error'))
+  (func $validate (param "request" $request) (result (result $result (error $error)))
+    (canon lift (core func $i "validate") (memory $i "memory") (realloc (func $i "realloc"))))
+  (instance $validator (export "validate" (func $validate)))
+  (export "morphir:ext/validator@0.1.0" (instance $validator)))
@@ -0,0 +1,548 @@
+//! Finding numeric literals in value definitions and lifting them into a
+//! configuration record.
+//!
+//! Every integer, float, and decimal literal in a value body is a candidate,
+//! except `0` and `1`, which are usually identities rather than parameters.
+//! Each constant gets a field name from where it appears: an operand of a
+//! comparison is a `threshold`, a factor of `*` or `/` a `rate`, a term of
+//! `+` or `-` an `offset`, named after the value definition and the other
+//! operand, e.g. `discountOrderTotalThreshold` for `orderTotal > 500` in
+//! `discount`. Literals bound by `let` or set as a record field are named
+//! after the binding or field.
+
+use crate::decision_tables::{operator, render};
+use morphir_core::ir::v4::{
+    self, Field, Literal, RecordFieldEntry, Type, TypeAttributes, TypeDefinition, Value,
+    ValueAttributes, ValueBody, ValueDefinition,
+};
+use morphir_core::naming::{FQName, Name, Path, SDK_PACKAGE};
+use serde::{Deserialize, Serialize};
+
+/// Kind of a numeric constant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConstantKind {
+    Int,
+    Float,
+    Decimal,
+}
+
+/// A literal found in rule logic
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Constant {
+    /// The value definition it appears in, e.g. `acme/shop:orders#discount`
+    pub value: String,
+    /// Field name in the configuration record, in camelCase
+    pub name: String,
+    pub kind: ConstantKind,
+    /// The literal, as written
+    pub literal: String,
+    /// The expression around the literal, for reviewers
+    pub context: String,
+    #[serde(skip)]
+    pub field: Name,
+    #[serde(skip)]
+    pub literal_value: Literal,
+    /// Type of the field: the literal's inferred type when the IR has one
+    #[serde(skip)]
+    pub tpe: Type,
+}
+
+/// Numeric literals in the value definitions of a package, by module and
+/// then by value in declaration order.
+///
+/// With `lift_into`, each literal is replaced by a field of that value, so
+/// the package reads its constants from the record built by
+/// [`config_module`].
+pub fn find_constants(
+    package_name: &str,
+    package: &mut v4::PackageDefinition,
+    include_trivial: bool,
+    lift_into: Option<&FQName>,
+) -> Vec<Constant> {
+    let package_path = Path::new(package_name);
+    let mut finder = Finder {
+        include_trivial,
+        lift_into,
+        constants: Vec::new(),
+        value: String::new(),
+        prefix: Vec::new(),
+    };
+    for (module_name, module) in package.modules.iter_mut() {
+        let module_path = Path::new(module_name);
+        for (value_name, definition) in module.value.values.iter_mut() {
+            let name = Name::from(value_name.as_str());
+            finder.value = FQName::new(package_path.clone(), module_path.clone(), name.clone())
+                .to_canonical_string();
+            finder.prefix = name.iter().map(str::to_string).collect();
+            finder.definition(&mut definition.value);
+        }
+    }
+    finder.constants
+}
+
+/// Module holding `constants` as a record: a type alias and a value, both
+/// named after the last segment of the module path, as in `config : Config`
+pub fn config_module(config: &FQName, constants: &[Constant]) -> v4::ModuleDefinition {
+    let config_type = Type::Reference(TypeAttributes::default(), config.clone(), vec![]);
+    let fields = constants
+        .iter()
+        .map(|c| Field {
+            name: c.field.clone(),
+            tpe: c.tpe.clone(),
+        })
+        .collect();
+    let record = Value::Record(
+        ValueAttributes::default(),
+        constants
+            .iter()
+            .map(|c| {
+                RecordFieldEntry(
+                    c.field.clone(),
+                    Value::Literal(ValueAttributes::default(), c.literal_value.clone()),
+                )
+            })
+            .collect(),
+    );
+
+    let local_name = config.local_name.to_string();
+    let mut module = v4::ModuleDefinition {
+        types: Default::default(),
+        values: Default::default(),
+        doc: Some("Tunable parameters of the package's rules, in one place.".to_string()),
+        ids: Default::default(),
+    };
+    module.types.insert(
+        local_name.clone(),
+        v4::AccessControlled::public(TypeDefinition::TypeAliasDefinition {
+            type_params: vec![],
+            type_expr: Type::Record(TypeAttributes::default(), fields),
+        }),
+    );
+    module.values.insert(
+        local_name,
+        v4::AccessControlled::public(ValueDefinition::new(vec![], config_type, record)),
+    );
+    module
+}
+
+/// SDK type of a literal with no inferred type
+fn literal_type(kind: ConstantKind) -> Type {
+    let (module, name) = match kind {
+        ConstantKind::Int => ("basics", "int"),
+        ConstantKind::Float => ("basics", "float"),
+        ConstantKind::Decimal => ("decimal", "decimal"),
+    };
+    Type::Reference(
+        TypeAttributes::default(),
+        FQName::new(Path::new(SDK_PACKAGE), Path::new(module), Name::from(name)),
+        vec![],
+    )
+}
+
+/// How a literal is used, from the expression around it
+struct Usage {
+    /// Words describing the literal, after the value's name
+    words: Vec<String>,
+    context: String,
+}
+
+struct Finder<'a> {
+    include_trivial: bool,
+    lift_into: Option<&'a FQName>,
+    constants: Vec<Constant>,
+    /// Canonical name of the value definition being walked
+    value: String,
+    /// Its name, as words to start field names with
+    prefix: Vec<String>,
+}
+
+impl Finder<'_> {
+    fn definition(&mut self, definition: &mut ValueDefinition) {
+        if let ValueBody::Expression(body) = &mut definition.body {
+            self.walk(body, None);
+        }
+    }
+
+    fn walk(&mut self, value: &mut Value, usage: Option<Usage>) {
+        if let Some((role, context)) = binary_role(value).map(|role| (role, render(value))) {
+            let Value::Apply(_, function, right) = value else {
+                unreachable!("binary operations are applications");
+            };
+            let Value::Apply(_, _, left) = function.as_mut() else {
+                unreachable!("binary operations have two arguments");
+            };
+            let (left_usage, right_usage) = match role {
+                Some(role) => (
+                    Some(operand_usage(right, role, &context)),
+                    Some(operand_usage(left, role, &context)),
+                ),
+                None => (None, None),
+            };
+            self.walk(left, left_usage);
+            self.walk(right, right_usage);
+            return;
+        }
+
+        match value {
+            Value::Literal(_, literal) => {
+                let Some(kind) = numeric_kind(literal) else {
+                    return;
+                };
+                if !self.include_trivial && is_trivial(literal) {
+                    return;
+                }
+                let literal = literal.clone();
+                self.literal(value, literal, kind, usage);
+            }
+            Value::Tuple(_, items) | Value::List(_, items) => {
+                for item in items {
+                    self.walk(item, None);
+                }
+            }
+            Value::Record(_, fields) => self.fields(fields),
+            Value::UpdateRecord(_, record, fields) => {
+                self.walk(record, None);
+                self.fields(fields);
+            }
+            Value::Field(_, record, _) => self.walk(record, None),
+            Value::Apply(_, function, argument) => {
+                self.walk(function, None);
+                self.walk(argument, None);
+            }
+            Value::Lambda(_, _, body) => self.walk(body, None),
+            Value::LetDefinition(_, name, definition, body) => {
+                if let ValueBody::Expression(bound) = &mut definition.body {
+                    let usage = Usage {
+                        words: name.iter().map(str::to_string).collect(),
+                        context: format!("let {} = {}", name.to_camel_case(), render(bound)),
+                    };
+                    self.walk(bound, Some(usage));
+                }
+                self.walk(body, None);
+            }
+            Value::LetRecursion(_, bindings, body) => {
+                for binding in bindings {
+                    self.definition(&mut binding.1);
+                }
+                self.walk(body, None);
+            }
+            Value::Destructure(_, _, bound, body) => {
+                self.walk(bound, None);
+                self.walk(body, None);
+            }
+            Value::IfThenElse(_, condition, then_branch, else_branch) => {
+                self.walk(condition, None);
+                self.walk(then_branch, None);
+                self.walk(else_branch, None);
+            }
+            Value::PatternMatch(_, subject, cases) => {
+                self.walk(subject, None);
+                for case in cases {
+                    self.walk(&mut case.1, None);
+                }
+            }
+            Value::Constructor(..)
+            | Value::Variable(..)
+            | Value::Reference(..)
+            | Value::FieldFunction(..)
+            | Value::Unit(..)
+            | Value::Hole(..)
+            | Value::Native(..)
+            | Value::External(..) => {}
+        }
+    }
+
+    /// Record the literal `value`, replacing it with a field of the
+    /// configuration record when lifting
+    fn literal(
+        &mut self,
+        value: &mut Value,
+        literal: Literal,
+        kind: ConstantKind,
+        usage: Option<Usage>,
+    ) {
+        let rendered = render(value);
+        let tpe = serde_json::from_value(value.attributes().inferred_type.clone())
+            .unwrap_or_else(|_| literal_type(kind));
+        let usage = usage.unwrap_or_else(|| Usage {
+            words: vec!["constant".to_string()],
+            context: rendered.clone(),
+        });
+        let field = self.field_name(usage.words);
+        if let Some(config) = self.lift_into {
+            let attributes = value.attributes().clone();
+            *value = Value::Field(
+                attributes.clone(),
+                Box::new(Value::Reference(attributes, config.clone())),
+                field.clone(),
+            );
+        }
+        self.constants.push(Constant {
+            value: self.value.clone(),
+            name: field.to_camel_case(),
+            kind,
+            literal: rendered,
+            context: usage.context,
+            field,
+            literal_value: literal,
+            tpe,
+        });
+    }
+
+    fn fields(&mut self, fields: &mut [RecordFieldEntry]) {
+        for RecordFieldEntry(name, value) in fields {
+            let usage = Usage {
+                words: name.iter().map(str::to_string).collect(),
+                context: format!("{} = {}", name.to_camel_case(), render(value)),
+            };
+            self.walk(value, Some(usage));
+        }
+    }
+
+    /// Field name from the value's name and `words`, numbered when taken
+    fn field_name(&self, words: Vec<String>) -> Name {
+        let mut words: Vec<String> = self
+            .prefix
+            .iter()
+            .cloned()
+            .chain(words)
+            .map(|word| word.to_lowercase())
+            .collect();
+        let taken = |name: &Name| self.constants.iter().any(|c| &c.field == name);
+        let name = Name::new(&words.iter().map(String::as_str).collect::<Vec<_>>());
+        if !taken(&name) {
+            return name;
+        }
+        words.push(String::new());
+        (2..)
+            .map(|n| {
+                *words.last_mut().expect("words end with the number") = n.to_string();
+                Name::new(&words.iter().map(String::as_str).collect::<Vec<_>>())
+            })
+            .find(|name| !taken(name))
+            .expect("some number is free")
+    }
+}
+
+/// For an SDK infix operation `a op b`, the word naming its numeric
+/// operands, or `None` for operators whose operands are not parameters
+fn binary_role(value: &Value) -> Option<Option<&'static str>> {
+    let Value::Apply(_, function, _) = value else {
+        return None;
+    };
+    let Value::Apply(_, operator_ref, _) = function.as_ref() else {
+        return None;
+    };
+    let Value::Reference(_, fqname) = operator_ref.as_ref() else {
+        return None;
+    };
+    Some(match operator(fqname)? {
+        "<" | "<=" | ">" | ">=" | "==" | "/=" => Some("threshold"),
+        "*" | "/" => Some("rate"),
+        "+" | "-" => Some("offset"),
+        _ => None,
+    })
+}
+
+/// Usage of a literal whose other operand is `other`
+fn operand_usage(other: &Value, role: &str, context: &str) -> Usage {
+    let mut words: Vec<String> = describe(other)
+        .map(|name| name.iter().map(str::to_string).collect())
+        .unwrap_or_default();
+    words.push(role.to_string());
+    Usage {
+        words,
+        context: context.to_string(),
+    }
+}
+
+/// Name for an operand: the variable, field, or function it reads
+fn describe(value: &Value) -> Option<&Name> {
+    match value {
+        Value::Variable(_, name) | Value::Field(_, _, name) => Some(name),
+        Value::Reference(_, fqname) => Some(&fqname.local_name),
+        Value::Apply(_, function, _) => match function.as_ref() {
+            Value::Reference(_, fqname) if operator(fqname).is_none() => Some(&fqname.local_name),
+            Value::Apply(..) if binary_role(function).is_none() => describe(function),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn numeric_kind(literal: &Literal) -> Option<ConstantKind> {
+    match literal {
+        Literal::Integer(_) => Some(ConstantKind::Int),
+        Literal::Float(_) => Some(ConstantKind::Float),
+        Literal::Decimal(_) => Some(ConstantKind::Decimal),
+        Literal::Bool(_) | Literal::Char(_) | Literal::String(_) => None,
+    }
+}
+
+fn is_trivial(literal: &Literal) -> bool {
+    match literal {
+        Literal::Integer(n) => *n == 0 || *n == 1,
+        Literal::Float(f) => *f == 0.0 || *f == 1.0,
+        Literal::Decimal(d) => d
+            .parse::<f64>()
+            .is_ok_and(|value| value == 0.0 || value == 1.0),
+        Literal::Bool(_) | Literal::Char(_) | Literal::String(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn a() -> ValueAttributes {
+        ValueAttributes::default()
+    }
+
+    fn var(name: &str) -> Value {
+        Value::Variable(a(), Name::from(name))
+    }
+
+    fn int(n: i64) -> Value {
+        Value::Literal(a(), Literal::Integer(n))
+    }
+
+    fn call(function: &str, args: Vec<Value>) -> Value {
+        args.into_iter().fold(
+            Value::Reference(a(), FQName::from_canonical_string(function).unwrap()),
+            |f, arg| Value::Apply(a(), Box::new(f), Box::new(arg)),
+        )
+    }
+
+    fn package(body: Value) -> v4::PackageDefinition {
+        let mut package: v4::PackageDefinition = serde_json::from_value(serde_json::json!({
+            "modules": { "orders": { "access": "Public", "value": { "types": {}, "values": {} } } }
+        }))
+        .unwrap();
+        package
+            .modules
+            .get_mut("orders")
+            .unwrap()
+            .value
+            .values
+            .insert(
+                "discount".to_string(),
+                v4::AccessControlled::public(ValueDefinition::new(
+                    vec![],
+                    v4::Type::Unit(Default::default()),
+                    body,
+                )),
+            );
+        package
+    }
+
+    /// if orderTotal > 500 then orderTotal * 0.1 else if orderTotal > 100 then 5 else 0
+    fn body() -> Value {
+        Value::IfThenElse(
+            a(),
+            Box::new(call(
+                "morphir/sdk:basics#greater-than",
+                vec![var("orderTotal"), int(500)],
+            )),
+            Box::new(call(
+                "morphir/sdk:basics#multiply",
+                vec![var("orderTotal"), Value::Literal(a(), Literal::Float(0.1))],
+            )),
+            Box::new(Value::IfThenElse(
+                a(),
+                Box::new(call(
+                    "morphir/sdk:basics#greater-than",
+                    vec![var("orderTotal"), int(100)],
+                )),
+                Box::new(int(5)),
+                Box::new(int(0)),
+            )),
+        )
+    }
+
+    #[test]
+    fn test_constants_are_named_after_their_use() {
+        let constants = find_constants("acme/shop", &mut package(body()), false, None);
+        let found: Vec<(&str, &str, ConstantKind, &str)> = constants
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    c.literal.as_str(),
+                    c.kind,
+                    c.context.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "discountOrderTotalThreshold",
+                    "500",
+                    ConstantKind::Int,
+                    "orderTotal > 500"
+                ),
+                (
+                    "discountOrderTotalRate",
+                    "0.1",
+                    ConstantKind::Float,
+                    "orderTotal * 0.1"
+                ),
+                (
+                    "discountOrderTotalThreshold2",
+                    "100",
+                    ConstantKind::Int,
+                    "orderTotal > 100"
+                ),
+                ("discountConstant", "5", ConstantKind::Int, "5"),
+            ]
+        );
+        assert!(
+            constants
+                .iter()
+                .all(|c| c.value == "acme/shop:orders#discount")
+        );
+    }
+
+    #[test]
+    fn test_lifted_constants_are_read_from_the_config_record() {
+        let config = FQName::from_canonical_string("acme/shop:config#config").unwrap();
+        let mut package = package(body());
+        let constants = find_constants("acme/shop", &mut package, false, Some(&config));
+        assert_eq!(constants.len(), 4);
+
+        let discount = &package.modules["orders"].value.values["discount"].value;
+        let ValueBody::Expression(body) = &discount.body else {
+            panic!("expression body");
+        };
+        assert_eq!(
+            render(body),
+            "if orderTotal > config.discountOrderTotalThreshold then orderTotal * config.discountOrderTotalRate \
+             else if orderTotal > config.discountOrderTotalThreshold2 then config.discountConstant else 0"
+        );
+
+        let module = config_module(&config, &constants);
+        let Some(TypeDefinition::TypeAliasDefinition { type_expr, .. }) =
+            module.types.get("config").map(|t| &t.value)
+        else {
+            panic!("config type alias");
+        };
+        let Type::Record(_, fields) = type_expr else {
+            panic!("record type");
+        };
+        assert_eq!(
+            fields[1].name,
+            Name::new(&["discount", "order", "total", "rate"])
+        );
+        assert_eq!(fields[1].tpe, literal_type(ConstantKind::Float));
+        let ValueBody::Expression(record) = &module.values["config"].value.body else {
+            panic!("expression body");
+        };
+        assert_eq!(
+            render(record),
+            "{ discountOrderTotalThreshold = 500, discountOrderTotalRate = 0.1, \
+             discountOrderTotalThreshold2 = 100, discountConstant = 5 }"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! Constant extraction builtin extension.
+//!
+//! Reports the numeric literals embedded in rule logic, such as thresholds
+//! and rates, and optionally lifts them into a generated configuration
+//! module: a record with one field per constant, read by the rules in place
+//! of the literals. Business users then review every tunable parameter in
+//! one place.
+
+use crate::{BuiltinExtension, BuiltinInfo, ExtensionType, detect_ir_format};
+use anyhow::{Context, Result};
+use morphir_core::converter;
+use morphir_core::ir::{classic, v4};
+use morphir_core::naming::{FQName, Path};
+use morphir_ext_core::Envelope;
+use serde::{Deserialize, Serialize};
+
+mod extract;
+
+pub use extract::{Constant, ConstantKind, config_module, find_constants};
+
+/// Transform lifting literal constants into a configuration module.
+#[derive(Default)]
+pub struct ConstantsExtension;
+
+impl BuiltinExtension for ConstantsExtension {
+    fn execute_native(&self, input: &Envelope) -> Result<Envelope> {
+        let request: ConstantsRequest = input
+            .as_json()
+            .context("Failed to parse constant extraction request")?;
+
+        let result = transform(request)?;
+
+        Envelope::json(&result).context("Failed to create response envelope")
+    }
+
+    fn info(&self) -> BuiltinInfo {
+        BuiltinInfo {
+            id: "extract-constants".to_string(),
+            name: "Extract Constants".to_string(),
+            extension_type: ExtensionType::Transform,
+            description:
+                "Report literal constants in rule logic and lift them into a configuration module"
+                    .to_string(),
+        }
+    }
+}
+
+/// Options for the extraction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ConstantsOptions {
+    /// Rewrite the rules to read their constants from a configuration module;
+    /// otherwise only report them
+    #[serde(default)]
+    pub lift: bool,
+    /// Module to generate, as a path within the package
+    #[serde(default = "default_module")]
+    pub module: String,
+    /// Also report `0` and `1`
+    #[serde(default)]
+    pub include_trivial: bool,
+}
+
+fn default_module() -> String {
+    "config".to_string()
+}
+
+impl Default for ConstantsOptions {
+    fn default() -> Self {
+        Self {
+            lift: false,
+            module: default_module(),
+            include_trivial: false,
+        }
+    }
+}
+
+/// Request format for the transform operation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConstantsRequest {
+    /// Input IR (either Classic or V4 format)
+    pub ir: serde_json::Value,
+    #[serde(default)]
+    pub options: ConstantsOptions,
+}
+
+/// Response format for the transform operation, as returned by transform
+/// extensions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConstantsResponse {
+    /// Whether extraction succeeded
+    pub success: bool,
+    /// Transformed IR in V4 format, rewritten when lifting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ir: Option<serde_json::Value>,
+    /// Constants found, in the order they appear
+    #[serde(default)]
+    pub constants: Vec<serde_json::Value>,
+    /// Notes about the extraction, e.g. that no constants were found
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<ConstantsDiagnostic>,
+    /// Error message (if failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A diagnostic about the extraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstantsDiagnostic {
+    pub severity: String,
+    pub message: String,
+}
+
+impl ConstantsResponse {
+    fn failure(error: String) -> Self {
+        Self {
+            success: false,
+            ir: None,
+            constants: vec![],
+            diagnostics: vec![],
+            error: Some(error),
+        }
+    }
+}
+
+fn transform(request: ConstantsRequest) -> Result<ConstantsResponse> {
+    let (format_version, mut distribution) = if detect_ir_format(&request.ir) == "v4" {
+        let ir: v4::IRFile = serde_json::from_value(request.ir).context("Failed to parse V4 IR")?;
+        (ir.format_version, ir.distribution)
+    } else {
+        let dist: classic::Distribution =
+            serde_json::from_value(request.ir).context("Failed to parse Classic IR")?;
+        let ir = converter::classic_to_v4(&dist).ir;
+        (ir.format_version, ir.distribution)
+    };
+
+    let package_name = distribution.package_name().to_string();
+    let package = match &mut distribution {
+        v4::Distribution::Library(lib) => &mut lib.def,
+        v4::Distribution::Application(app) => &mut app.def,
+        v4::Distribution::Specs(_) => {
+            return Ok(ConstantsResponse::failure(
+                "Specs distributions carry no value definitions".to_string(),
+            ));
+        }
+    };
+
+    let options = request.options;
+    let module_path = Path::new(&options.module);
+    let Some(local_name) = module_path.segments.last().cloned() else {
+        return Ok(ConstantsResponse::failure(
+            "The configuration module needs a name".to_string(),
+        ));
+    };
+    let config = FQName::new(Path::new(&package_name), module_path.clone(), local_name);
+    if options.lift && package.modules.contains_key(&module_path.to_string()) {
+        return Ok(ConstantsResponse::failure(format!(
+            "Module {} already exists; choose another with the `module` option",
+            module_path
+        )));
+    }
+
+    let constants = find_constants(
+        &package_name,
+        package,
+        options.include_trivial,
+        options.lift.then_some(&config),
+    );
+
+    let mut diagnostics = Vec::new();
+    if constants.is_empty() {
+        diagnostics.push(ConstantsDiagnostic {
+            severity: "info".to_string(),
+            message: "No numeric constants found in value definitions".to_string(),
+        });
+    } else if options.lift {
+        package.modules.insert(
+            module_path.to_string(),
+            v4::AccessControlled::public(config_module(&config, &constants)),
+        );
+        diagnostics.push(ConstantsDiagnostic {
+            severity: "info".to_string(),
+            message: format!(
+                "Lifted {} constants into {}",
+                constants.len(),
+                config.to_canonical_string()
+            ),
+        });
+    }
+
+    let ir = v4::IRFile {
+        format_version,
+        distribution,
+    };
+    Ok(ConstantsResponse {
+        success: true,
+        ir: Some(serde_json::to_value(&ir).context("Failed to serialize IR")?),
+        constants: constants
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()
+            .context("Failed to serialize constants")?,
+        diagnostics,
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(options: serde_json::Value) -> Envelope {
+        // limit order = if order > 250 then 0.25 else 0.5, in module Rates of package Acme.
+        // Classic values are annotated with their types.
+        let sdk = |name: &str| {
+            serde_json::json!([
+                "Reference",
+                {},
+                [[["morphir"], ["s", "d", "k"]], [["basics"]], [name]],
+                []
+            ])
+        };
+        let (int, float, bool_type) = (sdk("int"), sdk("float"), sdk("bool"));
+        let function = serde_json::json!(["Function", {}, int, ["Function", {}, int, bool_type]]);
+        let float_literal = |f: f64| serde_json::json!(["Literal", float, ["FloatLiteral", f]]);
+        let greater_than = serde_json::json!([
+            "Apply",
+            ["Function", {}, int, bool_type],
+            [
+                "Reference",
+                function,
+                [
+                    [["morphir"], ["s", "d", "k"]],
+                    [["basics"]],
+                    ["greater", "than"]
+                ]
+            ],
+            ["Variable", int, ["order"]]
+        ]);
+        Envelope::json(&serde_json::json!({
+            "ir": {
+                "formatVersion": 3,
+                "distribution": ["Library", [["acme"]], [], {"modules": [
+                    [[["rates"]], {"access": "Public", "value": {
+                        "types": [],
+                        "values": [[["limit"], {"access": "Public", "value": {"doc": "", "value": {
+                            "inputTypes": [[["order"], int, int]],
+                            "outputType": float,
+                            "body": ["IfThenElse", float,
+                                ["Apply", bool_type, greater_than, ["Literal", int, ["WholeNumberLiteral", 250]]],
+                                float_literal(0.25), float_literal(0.5)]
+                        }}}]],
+                        "doc": null
+                    }}]
+                ]}]
+            },
+            "options": options
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_report_and_lift_constants_from_classic_ir() {
+        let output = ConstantsExtension
+            .execute_native(&request(serde_json::json!({})))
+            .unwrap();
+        let response: ConstantsResponse = output.as_json().unwrap();
+        assert!(response.success, "{:?}", response.error);
+        let names: Vec<&str> = response
+            .constants
+            .iter()
+            .map(|c| c["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["limitOrderThreshold", "limitConstant", "limitConstant2"]
+        );
+        assert_eq!(response.constants[0]["context"], "order > 250");
+
+        let output = ConstantsExtension
+            .execute_native(&request(serde_json::json!({"lift": true})))
+            .unwrap();
+        let response: ConstantsResponse = output.as_json().unwrap();
+        assert!(response.success, "{:?}", response.error);
+        let ir: v4::IRFile = serde_json::from_value(response.ir.unwrap()).unwrap();
+        let package = ir.distribution.definition().unwrap();
+        let config = &package.modules["config"].value;
+        assert!(config.values.contains_key("config"));
+        // Fields take the literals' inferred types
+        let Some(v4::TypeDefinition::TypeAliasDefinition {
+            type_expr: v4::Type::Record(_, fields),
+            ..
+        }) = config.types.get("config").map(|t| &t.value)
+        else {
+            panic!("config record type");
+        };
+        let threshold = fields
+            .iter()
+            .find(|f| f.name.to_camel_case() == "limitOrderThreshold")
+            .unwrap();
+        let v4::Type::Reference(_, int, _) = &threshold.tpe else {
+            panic!("reference type");
+        };
+        assert_eq!(int.to_canonical_string(), "morphir/sdk:basics#int");
+    }
+
+    #[test]
+    fn test_lifting_into_an_existing_module_fails() {
+        let output = ConstantsExtension
+            .execute_native(&request(
+                serde_json::json!({"lift": true, "module": "rates"}),
+            ))
+            .unwrap();
+        let response: ConstantsResponse = output.as_json().unwrap();
+        assert!(!response.success);
+        assert_eq!(
+            response.error.as_deref(),
+            Some("Module rates already exists; choose another with the `module` option")
+        );
+    }
+}
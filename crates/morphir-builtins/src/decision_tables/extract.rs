@@ -0,0 +1,481 @@
+//! Mining decision tables from value definitions.
+//!
+//! The body of a value definition is walked through its `let` bindings to
+//! the expression that produces its result. Each `if` in that position adds
+//! a condition column whose cells are `true` or `false`, and each `case`
+//! adds a column for its subject whose cells are the patterns. When the
+//! subject is a tuple matched against tuple patterns, as in
+//! `case (tier, region) of`, every element gets a column of its own. Every
+//! path through the tree becomes a rule; `-` marks a condition the rule does
+//! not depend on. Rules keep source order and the first matching rule wins,
+//! as in the code they were mined from.
+
+use morphir_core::ir::v4::{self, Literal, Pattern, PatternCase, Value, ValueBody};
+use morphir_core::naming::{FQName, Name, Path};
+use serde::Serialize;
+
+/// Cell for a condition a rule does not depend on
+pub const ANY: &str = "-";
+
+/// Rules mined from one value definition
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecisionTable {
+    /// The value the rules decide, e.g. `acme/shop:orders#discount`
+    pub name: String,
+    /// Condition columns, as expressions
+    pub conditions: Vec<String>,
+    /// Header of the outcome column: the value's name
+    pub output: String,
+    pub rules: Vec<Rule>,
+    #[serde(skip)]
+    pub module: Path,
+    #[serde(skip)]
+    pub value: Name,
+}
+
+/// One path through a decision tree
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rule {
+    /// One cell per condition column
+    pub conditions: Vec<String>,
+    pub outcome: String,
+}
+
+/// Decision tables for the value definitions of a package with at least
+/// `min_rules` rules, by module and then by value in declaration order
+pub fn extract_tables(
+    package_name: &str,
+    package: &v4::PackageDefinition,
+    min_rules: usize,
+) -> Vec<DecisionTable> {
+    let package_path = Path::new(package_name);
+    let mut tables = Vec::new();
+    for (module_name, module) in &package.modules {
+        let module_path = Path::new(module_name);
+        for (value_name, definition) in &module.value.values {
+            let ValueBody::Expression(body) = &definition.value.body else {
+                continue;
+            };
+            let value = Name::from(value_name.as_str());
+            let mut builder = TableBuilder::default();
+            builder.walk(body, &mut Vec::new());
+            if builder.columns.is_empty() || builder.rules.len() < min_rules.max(2) {
+                continue;
+            }
+            let fqname = FQName::new(package_path.clone(), module_path.clone(), value.clone());
+            tables.push(builder.finish(fqname.to_canonical_string(), module_path.clone(), value));
+        }
+    }
+    tables
+}
+
+#[derive(Default)]
+struct TableBuilder {
+    columns: Vec<String>,
+    /// Cells by column index, and the outcome, for each rule
+    rules: Vec<(Vec<(usize, String)>, String)>,
+}
+
+impl TableBuilder {
+    fn column(&mut self, condition: String) -> usize {
+        match self.columns.iter().position(|c| *c == condition) {
+            Some(index) => index,
+            None => {
+                self.columns.push(condition);
+                self.columns.len() - 1
+            }
+        }
+    }
+
+    fn walk(&mut self, value: &Value, path: &mut Vec<(usize, String)>) {
+        match value {
+            Value::IfThenElse(_, condition, then_branch, else_branch) => {
+                let column = self.column(render(condition));
+                for (cell, branch) in [("true", then_branch), ("false", else_branch)] {
+                    path.push((column, cell.to_string()));
+                    self.walk(branch, path);
+                    path.pop();
+                }
+            }
+            Value::PatternMatch(_, subject, cases) => {
+                for PatternCase(pattern, body) in cases {
+                    let depth = path.len();
+                    self.match_cells(subject, pattern, path);
+                    self.walk(body, path);
+                    path.truncate(depth);
+                }
+            }
+            Value::LetDefinition(_, _, _, body)
+            | Value::LetRecursion(_, _, body)
+            | Value::Destructure(_, _, _, body) => self.walk(body, path),
+            outcome => self.rules.push((path.clone(), render(outcome))),
+        }
+    }
+
+    /// Cells a case adds: one per matched tuple element, or one for the
+    /// subject. Wildcards match anything and add none.
+    fn match_cells(&mut self, subject: &Value, pattern: &Pattern, path: &mut Vec<(usize, String)>) {
+        match (subject, pattern) {
+            (_, Pattern::WildcardPattern(_)) => {}
+            (Value::Tuple(_, elements), Pattern::TuplePattern(_, patterns))
+                if elements.len() == patterns.len() =>
+            {
+                for (element, pattern) in elements.iter().zip(patterns) {
+                    self.match_cells(element, pattern, path);
+                }
+            }
+            _ => {
+                let column = self.column(render(subject));
+                path.push((column, render_pattern(pattern)));
+            }
+        }
+    }
+
+    fn finish(self, name: String, module: Path, value: Name) -> DecisionTable {
+        let width = self.columns.len();
+        let rules = self
+            .rules
+            .into_iter()
+            .map(|(cells, outcome)| {
+                let mut conditions = vec![ANY.to_string(); width];
+                for (column, cell) in cells {
+                    conditions[column] = cell;
+                }
+                Rule {
+                    conditions,
+                    outcome,
+                }
+            })
+            .collect();
+        DecisionTable {
+            name,
+            conditions: self.columns,
+            output: value.to_camel_case(),
+            rules,
+            module,
+            value,
+        }
+    }
+}
+
+/// Infix operator for a function of the Morphir SDK
+pub(crate) fn operator(function: &FQName) -> Option<&'static str> {
+    if !function.package_path.is_sdk() {
+        return None;
+    }
+    Some(match function.local_name.to_camel_case().as_str() {
+        "add" => "+",
+        "subtract" => "-",
+        "multiply" => "*",
+        "divide" => "/",
+        "integerDivide" => "//",
+        "lessThan" => "<",
+        "lessThanOrEqual" => "<=",
+        "greaterThan" => ">",
+        "greaterThanOrEqual" => ">=",
+        "equal" => "==",
+        "notEqual" => "/=",
+        "and" => "&&",
+        "or" => "||",
+        "append" => "++",
+        _ => return None,
+    })
+}
+
+/// Render an expression on one line, in Elm-like syntax
+pub fn render(value: &Value) -> String {
+    match value {
+        Value::Apply(..) => {
+            let mut args = Vec::new();
+            let mut function = value;
+            while let Value::Apply(_, f, arg) = function {
+                args.push(arg.as_ref());
+                function = f;
+            }
+            args.reverse();
+            if let (Value::Reference(_, fqname), [left, right]) = (function, args.as_slice())
+                && let Some(op) = operator(fqname)
+            {
+                return format!("{} {} {}", render_atom(left), op, render_atom(right));
+            }
+            std::iter::once(render_atom(function))
+                .chain(args.into_iter().map(render_atom))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+        Value::Literal(_, literal) => render_literal(literal),
+        Value::Constructor(_, fqname) => fqname.local_name.to_title_case(),
+        Value::Tuple(_, elements) => format!("({})", render_list(elements)),
+        Value::List(_, items) => format!("[{}]", render_list(items)),
+        Value::Record(_, fields) => render_fields(None, fields),
+        Value::UpdateRecord(_, record, fields) => render_fields(Some(render_atom(record)), fields),
+        Value::Variable(_, name) => name.to_camel_case(),
+        Value::Reference(_, fqname) => fqname.local_name.to_camel_case(),
+        Value::Field(_, record, field) => {
+            format!("{}.{}", render_atom(record), field.to_camel_case())
+        }
+        Value::FieldFunction(_, field) => format!(".{}", field.to_camel_case()),
+        Value::Lambda(_, pattern, body) => {
+            format!("\\{} -> {}", render_pattern(pattern), render(body))
+        }
+        Value::LetDefinition(_, name, _, body) => {
+            format!("let {} = … in {}", name.to_camel_case(), render(body))
+        }
+        Value::LetRecursion(_, _, body) | Value::Destructure(_, _, _, body) => {
+            format!("let … in {}", render(body))
+        }
+        Value::IfThenElse(_, condition, then_branch, else_branch) => format!(
+            "if {} then {} else {}",
+            render(condition),
+            render(then_branch),
+            render(else_branch)
+        ),
+        Value::PatternMatch(_, subject, cases) => format!(
+            "case {} of {}",
+            render(subject),
+            cases
+                .iter()
+                .map(|PatternCase(pattern, body)| format!(
+                    "{} -> {}",
+                    render_pattern(pattern),
+                    render(body)
+                ))
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+        Value::Unit(_) => "()".to_string(),
+        Value::Hole(..) => "?".to_string(),
+        Value::Native(_, fqname, _) => fqname.local_name.to_camel_case(),
+        Value::External(_, name, _) => name.clone(),
+    }
+}
+
+/// Render an expression, parenthesized unless it is atomic
+fn render_atom(value: &Value) -> String {
+    let rendered = render(value);
+    match value {
+        Value::Apply(..)
+        | Value::Lambda(..)
+        | Value::LetDefinition(..)
+        | Value::LetRecursion(..)
+        | Value::Destructure(..)
+        | Value::IfThenElse(..)
+        | Value::PatternMatch(..) => format!("({})", rendered),
+        Value::Literal(_, Literal::Integer(n)) if *n < 0 => format!("({})", rendered),
+        _ => rendered,
+    }
+}
+
+fn render_list(values: &[Value]) -> String {
+    values.iter().map(render).collect::<Vec<_>>().join(", ")
+}
+
+fn render_fields(base: Option<String>, fields: &[v4::RecordFieldEntry]) -> String {
+    let fields = fields
+        .iter()
+        .map(|v4::RecordFieldEntry(name, value)| {
+            format!("{} = {}", name.to_camel_case(), render(value))
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    match base {
+        Some(base) => format!("{{ {} | {} }}", base, fields),
+        None => format!("{{ {} }}", fields),
+    }
+}
+
+fn render_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Bool(true) => "True".to_string(),
+        Literal::Bool(false) => "False".to_string(),
+        Literal::Char(c) => format!("{:?}", c),
+        Literal::String(s) => format!("{:?}", s),
+        Literal::Integer(n) => n.to_string(),
+        Literal::Float(f) => format!("{:?}", f),
+        Literal::Decimal(d) => d.clone(),
+    }
+}
+
+/// Render a pattern in Elm-like syntax
+pub fn render_pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::WildcardPattern(_) => "_".to_string(),
+        Pattern::AsPattern(_, pattern, name) => {
+            format!(
+                "{} as {}",
+                render_pattern_atom(pattern),
+                name.to_camel_case()
+            )
+        }
+        Pattern::TuplePattern(_, patterns) => format!(
+            "({})",
+            patterns
+                .iter()
+                .map(render_pattern)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Pattern::ConstructorPattern(_, fqname, args) => {
+            std::iter::once(fqname.local_name.to_title_case())
+                .chain(args.iter().map(render_pattern_atom))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+        Pattern::EmptyListPattern(_) => "[]".to_string(),
+        Pattern::HeadTailPattern(_, head, tail) => {
+            format!("{} :: {}", render_pattern_atom(head), render_pattern(tail))
+        }
+        Pattern::LiteralPattern(_, literal) => render_literal(literal),
+        Pattern::UnitPattern(_) => "()".to_string(),
+    }
+}
+
+fn render_pattern_atom(pattern: &Pattern) -> String {
+    let rendered = render_pattern(pattern);
+    match pattern {
+        Pattern::ConstructorPattern(_, _, args) if !args.is_empty() => format!("({})", rendered),
+        Pattern::AsPattern(..) | Pattern::HeadTailPattern(..) => format!("({})", rendered),
+        _ => rendered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_core::ir::v4::ValueAttributes;
+
+    fn a() -> ValueAttributes {
+        ValueAttributes::default()
+    }
+
+    fn var(name: &str) -> Value {
+        Value::Variable(a(), Name::from(name))
+    }
+
+    fn field(record: &str, name: &str) -> Value {
+        Value::Field(a(), Box::new(var(record)), Name::from(name))
+    }
+
+    fn float(f: f64) -> Value {
+        Value::Literal(a(), Literal::Float(f))
+    }
+
+    fn call(function: &str, args: Vec<Value>) -> Value {
+        args.into_iter().fold(
+            Value::Reference(a(), FQName::from_canonical_string(function).unwrap()),
+            |f, arg| Value::Apply(a(), Box::new(f), Box::new(arg)),
+        )
+    }
+
+    fn ctor(name: &str) -> Pattern {
+        Pattern::ConstructorPattern(
+            a(),
+            FQName::from_canonical_string(&format!("acme/shop:orders#{}", name)).unwrap(),
+            vec![],
+        )
+    }
+
+    fn table(body: Value) -> Vec<DecisionTable> {
+        let package: v4::PackageDefinition = serde_json::from_value(serde_json::json!({
+            "modules": { "orders": { "access": "Public", "value": { "types": {}, "values": {} } } }
+        }))
+        .unwrap();
+        let mut package = package;
+        let module = &mut package.modules.get_mut("orders").unwrap().value;
+        module.values.insert(
+            "discount".to_string(),
+            v4::AccessControlled::public(v4::ValueDefinition::new(
+                vec![],
+                v4::Type::Unit(Default::default()),
+                body,
+            )),
+        );
+        extract_tables("acme/shop", &package, 3)
+    }
+
+    #[test]
+    fn test_case_on_tuple_and_nested_if_become_rules() {
+        // case (order.tier, order.region) of
+        //     (Gold, _) -> if order.total > 100.0 then 0.2 else 0.1
+        //     (_, Eu) -> 0.05
+        //     _ -> 0.0
+        let body = Value::PatternMatch(
+            a(),
+            Box::new(Value::Tuple(
+                a(),
+                vec![field("order", "tier"), field("order", "region")],
+            )),
+            vec![
+                PatternCase(
+                    Pattern::TuplePattern(a(), vec![ctor("gold"), Pattern::WildcardPattern(a())]),
+                    Value::IfThenElse(
+                        a(),
+                        Box::new(call(
+                            "morphir/sdk:basics#greater-than",
+                            vec![field("order", "total"), float(100.0)],
+                        )),
+                        Box::new(float(0.2)),
+                        Box::new(float(0.1)),
+                    ),
+                ),
+                PatternCase(
+                    Pattern::TuplePattern(a(), vec![Pattern::WildcardPattern(a()), ctor("eu")]),
+                    float(0.05),
+                ),
+                PatternCase(Pattern::WildcardPattern(a()), float(0.0)),
+            ],
+        );
+
+        let tables = table(body);
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.name, "acme/shop:orders#discount");
+        assert_eq!(table.output, "discount");
+        assert_eq!(
+            table.conditions,
+            vec!["order.tier", "order.total > 100.0", "order.region"]
+        );
+        let rows: Vec<(Vec<&str>, &str)> = table
+            .rules
+            .iter()
+            .map(|r| {
+                (
+                    r.conditions.iter().map(String::as_str).collect(),
+                    r.outcome.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (vec!["Gold", "true", "-"], "0.2"),
+                (vec!["Gold", "false", "-"], "0.1"),
+                (vec!["-", "-", "Eu"], "0.05"),
+                (vec!["-", "-", "-"], "0.0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_simple_conditionals_are_not_tables() {
+        let body = Value::IfThenElse(
+            a(),
+            Box::new(var("vip")),
+            Box::new(float(0.1)),
+            Box::new(float(0.0)),
+        );
+        assert!(table(body).is_empty());
+    }
+
+    #[test]
+    fn test_render_expressions() {
+        let value = call(
+            "acme/shop:pricing#apply-rate",
+            vec![
+                call("morphir/sdk:basics#add", vec![var("base"), var("fee")]),
+                Value::Literal(a(), Literal::Integer(-1)),
+            ],
+        );
+        assert_eq!(render(&value), "applyRate (base + fee) (-1)");
+    }
+}
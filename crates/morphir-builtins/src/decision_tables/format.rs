@@ -0,0 +1,177 @@
+//! Writing decision tables as CSV, Markdown, and JSON.
+//!
+//! Files are placed by package and module path in snake_case, e.g. the
+//! tables of `acme/shop:orders` go to `acme/shop/orders.md` and
+//! `acme/shop/orders.decisions.json`. CSV holds one table per file, so each
+//! value gets its own, e.g. `acme/shop/orders/discount.csv`.
+
+use super::extract::DecisionTable;
+use super::{DecisionTableArtifact, TableFormat};
+use morphir_core::naming::{Name, Path};
+
+/// Artifacts for `tables` in each of `formats`
+pub fn write_tables(
+    package_name: &str,
+    tables: &[DecisionTable],
+    formats: &[TableFormat],
+) -> Vec<DecisionTableArtifact> {
+    let package_dir = dir(&Path::new(package_name));
+    let mut modules: Vec<(&Path, Vec<&DecisionTable>)> = Vec::new();
+    for table in tables {
+        match modules
+            .iter_mut()
+            .find(|(module, _)| *module == &table.module)
+        {
+            Some((_, tables)) => tables.push(table),
+            None => modules.push((&table.module, vec![table])),
+        }
+    }
+
+    let mut artifacts = Vec::new();
+    for (module, tables) in modules {
+        let base = format!("{}/{}", package_dir, dir(module));
+        for format in formats {
+            match format {
+                TableFormat::Csv => {
+                    artifacts.extend(tables.iter().map(|table| DecisionTableArtifact {
+                        path: format!("{}/{}.csv", base, table.value.to_snake_case()),
+                        content: csv(table),
+                    }))
+                }
+                TableFormat::Markdown => artifacts.push(DecisionTableArtifact {
+                    path: format!("{}.md", base),
+                    content: markdown(package_name, module, &tables),
+                }),
+                TableFormat::Json => artifacts.push(DecisionTableArtifact {
+                    path: format!("{}.decisions.json", base),
+                    content: serde_json::to_string_pretty(&tables)
+                        .expect("decision tables serialize"),
+                }),
+            }
+        }
+    }
+    artifacts
+}
+
+fn dir(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(Name::to_snake_case)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// One header row, then a row per rule, quoted as in RFC 4180
+fn csv(table: &DecisionTable) -> String {
+    let mut lines = vec![csv_row(
+        table
+            .conditions
+            .iter()
+            .chain(std::iter::once(&table.output)),
+    )];
+    lines.extend(
+        table
+            .rules
+            .iter()
+            .map(|rule| csv_row(rule.conditions.iter().chain(std::iter::once(&rule.outcome)))),
+    );
+    lines.join("\r\n") + "\r\n"
+}
+
+fn csv_row<'a>(cells: impl Iterator<Item = &'a String>) -> String {
+    cells
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn markdown(package_name: &str, module: &Path, tables: &[&DecisionTable]) -> String {
+    let mut out = format!("# {}:{}\n", Path::new(package_name), module);
+    for table in tables {
+        out.push_str(&format!("\n## {}\n\n", table.output));
+        out.push_str("Rules apply in order; the first matching rule wins.\n\n");
+        let header: Vec<String> = std::iter::once("#".to_string())
+            .chain(table.conditions.iter().map(|c| markdown_cell(c)))
+            .chain(std::iter::once(markdown_cell(&table.output)))
+            .collect();
+        out.push_str(&format!("| {} |\n", header.join(" | ")));
+        out.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+        for (index, rule) in table.rules.iter().enumerate() {
+            let row: Vec<String> = std::iter::once((index + 1).to_string())
+                .chain(rule.conditions.iter().map(|c| markdown_cell(c)))
+                .chain(std::iter::once(markdown_cell(&rule.outcome)))
+                .collect();
+            out.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+    }
+    out
+}
+
+/// Cells are code, so `|` is the only character that needs escaping
+fn markdown_cell(text: &str) -> String {
+    format!("`{}`", text.replace('|', "\\|"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_tables::extract::Rule;
+
+    fn table() -> DecisionTable {
+        DecisionTable {
+            name: "acme/shop:orders#shipping".to_string(),
+            conditions: vec!["order.region".to_string(), "express || rush".to_string()],
+            output: "shipping".to_string(),
+            rules: vec![
+                Rule {
+                    conditions: vec!["Eu".to_string(), "true".to_string()],
+                    outcome: "\"courier, next day\"".to_string(),
+                },
+                Rule {
+                    conditions: vec!["-".to_string(), "-".to_string()],
+                    outcome: "\"post\"".to_string(),
+                },
+            ],
+            module: Path::new("orders"),
+            value: Name::from("shipping"),
+        }
+    }
+
+    #[test]
+    fn test_write_tables_in_every_format() {
+        let artifacts = write_tables(
+            "acme/shop",
+            &[table()],
+            &[TableFormat::Csv, TableFormat::Markdown, TableFormat::Json],
+        );
+        let paths: Vec<&str> = artifacts.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "acme/shop/orders/shipping.csv",
+                "acme/shop/orders.md",
+                "acme/shop/orders.decisions.json"
+            ]
+        );
+
+        assert_eq!(
+            artifacts[0].content,
+            "order.region,express || rush,shipping\r\nEu,true,\"\"\"courier, next day\"\"\"\r\n-,-,\"\"\"post\"\"\"\r\n"
+        );
+        assert_eq!(
+            artifacts[1].content,
+            "# acme/shop:orders\n\n## shipping\n\nRules apply in order; the first matching rule wins.\n\n\
+             | # | `order.region` | `express \\|\\| rush` | `shipping` |\n|---|---|---|---|\n\
+             | 1 | `Eu` | `true` | `\"courier, next day\"` |\n| 2 | `-` | `-` | `\"post\"` |\n"
+        );
+        let json: serde_json::Value = serde_json::from_str(&artifacts[2].content).unwrap();
+        assert_eq!(json[0]["name"], "acme/shop:orders#shipping");
+        assert_eq!(json[0]["rules"][1]["outcome"], "\"post\"");
+    }
+}
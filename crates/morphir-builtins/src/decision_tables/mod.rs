@@ -0,0 +1,215 @@
+//! Decision table builtin extension.
+//!
+//! Mines business rules from a distribution: value definitions whose result
+//! is decided by nested `if` and `case` expressions are flattened into
+//! decision tables, written as CSV, Markdown, or JSON.
+
+use crate::{BuiltinExtension, BuiltinInfo, ExtensionType, detect_ir_format};
+use anyhow::{Context, Result};
+use morphir_core::converter;
+use morphir_core::ir::{classic, v4};
+use morphir_ext_core::Envelope;
+use serde::{Deserialize, Serialize};
+
+mod extract;
+mod format;
+
+pub(crate) use extract::operator;
+pub use extract::{ANY, DecisionTable, Rule, extract_tables, render, render_pattern};
+pub use format::write_tables;
+
+/// Decision table backend for business rules.
+#[derive(Default)]
+pub struct DecisionTableExtension;
+
+impl BuiltinExtension for DecisionTableExtension {
+    fn execute_native(&self, input: &Envelope) -> Result<Envelope> {
+        let request: DecisionTableRequest = input
+            .as_json()
+            .context("Failed to parse decision table request")?;
+
+        let result = generate(request)?;
+
+        Envelope::json(&result).context("Failed to create response envelope")
+    }
+
+    fn info(&self) -> BuiltinInfo {
+        BuiltinInfo {
+            id: "decision-tables".to_string(),
+            name: "Decision Tables".to_string(),
+            extension_type: ExtensionType::Backend,
+            description: "Extract decision tables from nested conditionals in a distribution"
+                .to_string(),
+        }
+    }
+}
+
+/// File format for decision tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    Csv,
+    Markdown,
+    Json,
+}
+
+/// Options for the extracted tables, read from `[codegen.decision-tables]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DecisionTableOptions {
+    /// Formats to write each table in
+    #[serde(default = "default_formats")]
+    pub formats: Vec<TableFormat>,
+    /// Smallest number of rules worth a table; the default of 3 skips a
+    /// lone `if`, which reads better as code
+    #[serde(default = "default_min_rules")]
+    pub min_rules: usize,
+}
+
+fn default_formats() -> Vec<TableFormat> {
+    vec![TableFormat::Markdown, TableFormat::Csv, TableFormat::Json]
+}
+
+fn default_min_rules() -> usize {
+    3
+}
+
+impl Default for DecisionTableOptions {
+    fn default() -> Self {
+        Self {
+            formats: default_formats(),
+            min_rules: default_min_rules(),
+        }
+    }
+}
+
+/// Request format for the generate operation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecisionTableRequest {
+    /// Input IR (either Classic or V4 format)
+    pub ir: serde_json::Value,
+    #[serde(default)]
+    pub options: DecisionTableOptions,
+}
+
+/// Response format for the generate operation, as returned by backend
+/// extensions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecisionTableResponse {
+    /// Whether extraction succeeded
+    pub success: bool,
+    /// Files with the extracted tables
+    #[serde(default)]
+    pub artifacts: Vec<DecisionTableArtifact>,
+    /// Notes about the extraction, e.g. that no decisions were found
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<DecisionTableDiagnostic>,
+    /// Error message (if failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A generated file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionTableArtifact {
+    pub path: String,
+    pub content: String,
+}
+
+/// A diagnostic about the extraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionTableDiagnostic {
+    pub severity: String,
+    pub message: String,
+}
+
+fn generate(request: DecisionTableRequest) -> Result<DecisionTableResponse> {
+    let distribution = if detect_ir_format(&request.ir) == "v4" {
+        let ir: v4::IRFile = serde_json::from_value(request.ir).context("Failed to parse V4 IR")?;
+        ir.distribution
+    } else {
+        let dist: classic::Distribution =
+            serde_json::from_value(request.ir).context("Failed to parse Classic IR")?;
+        converter::classic_to_v4(&dist).ir.distribution
+    };
+
+    let Some(package) = distribution.definition() else {
+        return Ok(DecisionTableResponse {
+            success: false,
+            artifacts: vec![],
+            diagnostics: vec![],
+            error: Some("Specs distributions carry no value definitions".to_string()),
+        });
+    };
+    let package_name = distribution.package_name().to_string();
+    let tables = extract_tables(&package_name, package, request.options.min_rules);
+
+    let mut diagnostics = Vec::new();
+    if tables.is_empty() {
+        diagnostics.push(DecisionTableDiagnostic {
+            severity: "info".to_string(),
+            message: format!(
+                "No value definitions decide their result with {} or more rules",
+                request.options.min_rules
+            ),
+        });
+    }
+
+    Ok(DecisionTableResponse {
+        success: true,
+        artifacts: write_tables(&package_name, &tables, &request.options.formats),
+        diagnostics,
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_from_classic_ir() {
+        // rate a b = if a then 1 else if b then 2 else 3, in module Rates of package Acme.
+        // Classic values are annotated with their types.
+        let sdk = |name: &str| {
+            serde_json::json!([
+                "Reference",
+                {},
+                [[["morphir"], ["s", "d", "k"]], [["basics"]], [name]],
+                []
+            ])
+        };
+        let (int, bool_type) = (sdk("int"), sdk("bool"));
+        let literal = |n: i64| serde_json::json!(["Literal", int, ["WholeNumberLiteral", n]]);
+        let var = |name: &str| serde_json::json!(["Variable", bool_type, [name]]);
+        let request = serde_json::json!({
+            "ir": {
+                "formatVersion": 3,
+                "distribution": ["Library", [["acme"]], [], {"modules": [
+                    [[["rates"]], {"access": "Public", "value": {
+                        "types": [],
+                        "values": [[["rate"], {"access": "Public", "value": {"doc": "", "value": {
+                            "inputTypes": [[["a"], bool_type, bool_type], [["b"], bool_type, bool_type]],
+                            "outputType": int,
+                            "body": ["IfThenElse", int, var("a"), literal(1),
+                                ["IfThenElse", int, var("b"), literal(2), literal(3)]]
+                        }}}]],
+                        "doc": null
+                    }}]
+                ]}]
+            },
+            "options": {"formats": ["csv"]}
+        });
+        let output = DecisionTableExtension
+            .execute_native(&Envelope::json(&request).unwrap())
+            .unwrap();
+        let response: DecisionTableResponse = output.as_json().unwrap();
+        assert!(response.success, "{:?}", response.error);
+        assert_eq!(response.artifacts.len(), 1);
+        assert_eq!(response.artifacts[0].path, "acme/rates/rate.csv");
+        assert_eq!(
+            response.artifacts[0].content,
+            "a,b,rate\r\ntrue,-,1\r\nfalse,true,2\r\nfalse,false,3\r\n"
+        );
+    }
+}
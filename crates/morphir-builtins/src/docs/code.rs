@@ -0,0 +1,335 @@
+//! Pretty-printed declarations in Morphir's Elm-like syntax.
+//!
+//! Declarations are built as a sequence of segments so each page format can
+//! turn the references to documented types into links its own way.
+
+use morphir_core::ir::v4::{
+    Access, AccessControlled, Field, Type, TypeDefinition, ValueDefinition,
+};
+use morphir_core::naming::{FQName, Name};
+
+/// Width past which value signatures are broken onto one line per argument
+const MAX_SIGNATURE_WIDTH: usize = 80;
+
+/// A piece of a rendered declaration
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(String),
+    /// A type name, linked when the site documents the type
+    Reference(String, FQName),
+}
+
+/// A rendered declaration
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Code {
+    pub segments: Vec<Segment>,
+}
+
+impl Code {
+    fn text(&mut self, text: &str) {
+        match self.segments.last_mut() {
+            Some(Segment::Text(last)) => last.push_str(text),
+            _ => self.segments.push(Segment::Text(text.to_string())),
+        }
+    }
+
+    fn append(&mut self, other: Code) {
+        for segment in other.segments {
+            match segment {
+                Segment::Text(text) => self.text(&text),
+                reference => self.segments.push(reference),
+            }
+        }
+    }
+
+    /// The declaration as plain text
+    pub fn plain(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) | Segment::Reference(text, _) => text.as_str(),
+            })
+            .collect()
+    }
+
+    /// The distinct types the declaration refers to, in order
+    pub fn references(&self) -> Vec<&FQName> {
+        let mut references = Vec::new();
+        for segment in &self.segments {
+            if let Segment::Reference(_, fqname) = segment
+                && !references.contains(&fqname)
+            {
+                references.push(fqname);
+            }
+        }
+        references
+    }
+}
+
+/// Render `tpe`, e.g. `List Line -> { total : Int }`
+pub fn render_type(tpe: &Type) -> Code {
+    let mut code = Code::default();
+    write_type(&mut code, tpe);
+    code
+}
+
+fn write_type(code: &mut Code, tpe: &Type) {
+    match tpe {
+        Type::Variable(_, name) => code.text(&name.to_camel_case()),
+        Type::Reference(_, fqname, args) => {
+            code.segments.push(Segment::Reference(
+                fqname.local_name.to_title_case(),
+                fqname.clone(),
+            ));
+            for arg in args {
+                code.text(" ");
+                write_argument(code, arg);
+            }
+        }
+        Type::Tuple(_, elements) => {
+            code.text("( ");
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    code.text(", ");
+                }
+                write_type(code, element);
+            }
+            code.text(" )");
+        }
+        Type::Record(_, fields) if fields.is_empty() => code.text("{}"),
+        Type::Record(_, fields) => {
+            code.text("{ ");
+            write_fields(code, fields, ", ");
+            code.text(" }");
+        }
+        Type::ExtensibleRecord(_, variable, fields) => {
+            code.text(&format!("{{ {} | ", variable.to_camel_case()));
+            write_fields(code, fields, ", ");
+            code.text(" }");
+        }
+        Type::Function(_, argument, result) => {
+            write_function_argument(code, argument);
+            code.text(" -> ");
+            write_type(code, result);
+        }
+        Type::Unit(_) => code.text("()"),
+    }
+}
+
+/// Write a type argument, parenthesised unless it is a single word
+fn write_argument(code: &mut Code, tpe: &Type) {
+    match tpe {
+        Type::Reference(_, _, args) if !args.is_empty() => parenthesised(code, tpe),
+        Type::Function(..) => parenthesised(code, tpe),
+        _ => write_type(code, tpe),
+    }
+}
+
+/// Write a function input, parenthesised when it is a function itself
+fn write_function_argument(code: &mut Code, tpe: &Type) {
+    match tpe {
+        Type::Function(..) => parenthesised(code, tpe),
+        _ => write_type(code, tpe),
+    }
+}
+
+fn parenthesised(code: &mut Code, tpe: &Type) {
+    code.text("(");
+    write_type(code, tpe);
+    code.text(")");
+}
+
+fn write_fields(code: &mut Code, fields: &[Field], separator: &str) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            code.text(separator);
+        }
+        code.text(&format!("{} : ", field.name.to_camel_case()));
+        write_type(code, &field.tpe);
+    }
+}
+
+/// Signature of a value: its inputs, in order, then its output.
+///
+/// Signatures wider than 80 characters put each type on its own line.
+pub fn value_signature(name: &str, definition: &ValueDefinition) -> Code {
+    let mut parts: Vec<Code> = definition
+        .input_types
+        .values()
+        .map(|input| {
+            let mut code = Code::default();
+            write_function_argument(&mut code, &input.input_type);
+            code
+        })
+        .collect();
+    parts.push(render_type(&definition.output_type));
+
+    let name = Name::from(name).to_camel_case();
+    let width = name.len() + 3 + parts.iter().map(|p| p.plain().len() + 4).sum::<usize>();
+    let mut code = Code::default();
+    if width <= MAX_SIGNATURE_WIDTH {
+        code.text(&format!("{} : ", name));
+        for (i, part) in parts.into_iter().enumerate() {
+            if i > 0 {
+                code.text(" -> ");
+            }
+            code.append(part);
+        }
+    } else {
+        code.text(&format!("{} :", name));
+        for (i, part) in parts.into_iter().enumerate() {
+            code.text(if i == 0 { "\n    " } else { "\n    -> " });
+            code.append(part);
+        }
+    }
+    code
+}
+
+/// Declaration of a type in elm-format layout: records of a type alias and
+/// the constructors of a custom type each go on their own line. Custom types
+/// whose constructors are hidden are shown without them.
+pub fn type_declaration(name: &str, definition: &TypeDefinition, show_private: bool) -> Code {
+    let (keyword, params) = match definition {
+        TypeDefinition::TypeAliasDefinition { type_params, .. } => ("type alias", type_params),
+        TypeDefinition::CustomTypeDefinition { type_params, .. }
+        | TypeDefinition::IncompleteTypeDefinition { type_params, .. } => ("type", type_params),
+    };
+    let mut code = Code::default();
+    code.text(&format!("{} {}", keyword, Name::from(name).to_title_case()));
+    for param in params {
+        code.text(&format!(" {}", param.to_camel_case()));
+    }
+
+    match definition {
+        TypeDefinition::TypeAliasDefinition {
+            type_expr: Type::Record(_, fields),
+            ..
+        } if fields.len() > 1 => {
+            code.text(" =\n    { ");
+            write_fields(&mut code, fields, "\n    , ");
+            code.text("\n    }");
+        }
+        TypeDefinition::TypeAliasDefinition { type_expr, .. } => {
+            code.text(" =\n    ");
+            write_type(&mut code, type_expr);
+        }
+        TypeDefinition::CustomTypeDefinition { constructors, .. }
+            if is_visible(constructors, show_private) =>
+        {
+            for (i, constructor) in constructors.value.iter().enumerate() {
+                code.text(if i == 0 { "\n    = " } else { "\n    | " });
+                code.text(&constructor.name.to_title_case());
+                for arg in &constructor.args {
+                    code.text(" ");
+                    write_argument(&mut code, &arg.arg_type);
+                }
+            }
+        }
+        TypeDefinition::CustomTypeDefinition { .. }
+        | TypeDefinition::IncompleteTypeDefinition { .. } => {}
+    }
+    code
+}
+
+fn is_visible<T>(item: &AccessControlled<Vec<T>>, show_private: bool) -> bool {
+    !item.value.is_empty() && (show_private || item.access == Access::Public)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn type_def(value: serde_json::Value) -> TypeDefinition {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_type_declarations() {
+        let order = type_def(json!({ "TypeAliasDefinition": {
+            "typeParams": [],
+            "typeExp": { "Record": { "fields": {
+                "lines": { "Reference": {
+                    "fqname": "morphir/sdk:list#list",
+                    "args": ["acme:orders#line"]
+                } },
+                "total": "morphir/sdk:basics#int"
+            } } }
+        } }));
+        let code = type_declaration("order", &order, false);
+        assert_eq!(
+            code.plain(),
+            "type alias Order =\n    { lines : List Line\n    , total : Int\n    }"
+        );
+        assert_eq!(
+            code.references()
+                .into_iter()
+                .map(FQName::to_canonical_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "morphir/sdk:list#list",
+                "acme:orders#line",
+                "morphir/sdk:basics#int"
+            ]
+        );
+
+        let status = json!({ "CustomTypeDefinition": {
+            "typeParams": ["a"],
+            "constructors": { "access": "Public", "value": [
+                { "name": "pending", "args": [] },
+                { "name": "held", "args": [{ "name": "reason", "type": {
+                    "Reference": { "fqname": "morphir/sdk:maybe#maybe", "args": [{ "Variable": { "name": "a" } }] }
+                } }] }
+            ] }
+        } });
+        assert_eq!(
+            type_declaration("status", &type_def(status.clone()), false).plain(),
+            "type Status a\n    = Pending\n    | Held (Maybe a)"
+        );
+        let mut opaque = status;
+        opaque["CustomTypeDefinition"]["constructors"]["access"] = json!("Private");
+        assert_eq!(
+            type_declaration("status", &type_def(opaque.clone()), false).plain(),
+            "type Status a"
+        );
+        assert!(
+            type_declaration("status", &type_def(opaque), true)
+                .plain()
+                .contains("| Held")
+        );
+    }
+
+    #[test]
+    fn test_value_signatures_wrap() {
+        let definition = |inputs: serde_json::Value| -> ValueDefinition {
+            serde_json::from_value(json!({
+                "inputTypes": inputs,
+                "outputType": "morphir/sdk:basics#int",
+                "body": { "ExpressionBody": { "body": { "Variable": { "name": "x" } } } }
+            }))
+            .unwrap()
+        };
+        let short = definition(json!({
+            "order": { "type": "acme:orders#order" },
+            "discount": { "type": { "Function": {
+                "arg": "morphir/sdk:basics#int",
+                "result": "morphir/sdk:basics#int"
+            } } }
+        }));
+        assert_eq!(
+            value_signature("total", &short).plain(),
+            "total : (Int -> Int) -> Order -> Int"
+        );
+
+        let long = definition(json!({
+            "firstOrderOfTheCustomer": { "type": "acme:orders#first-order-of-the-customer" },
+            "secondOrderOfTheCustomer": { "type": "acme:orders#second-order-of-the-customer" },
+            "thirdOrderOfTheCustomer": { "type": "acme:orders#third-order-of-the-customer" }
+        }));
+        assert_eq!(
+            value_signature("compare-orders", &long).plain(),
+            "compareOrders :\n    FirstOrderOfTheCustomer\n    -> SecondOrderOfTheCustomer\n    \
+             -> ThirdOrderOfTheCustomer\n    -> Int"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Documentation builtin extension.
+//!
+//! Renders a static documentation site for a distribution, as HTML or
+//! Markdown: an index of the package's modules and a page per module listing
+//! its types and values with their pretty-printed declarations and doc
+//! comments. Types named in a declaration link to their own entry when the
+//! site documents them.
+//!
+//! Only public modules and definitions are documented unless `private` is
+//! set, as in `[codegen.docs]`:
+//!
+//! ```toml
+//! [codegen.docs]
+//! format = "markdown"
+//! private = true
+//! ```
+
+use crate::{BuiltinExtension, BuiltinInfo, ExtensionType, detect_ir_format};
+use anyhow::{Context, Result};
+use morphir_core::converter;
+use morphir_core::ir::{classic, v4};
+use morphir_ext_core::Envelope;
+use serde::{Deserialize, Serialize};
+
+mod code;
+mod site;
+
+pub use code::{Code, Segment, render_type, type_declaration, value_signature};
+pub use site::{DocsPage, generate_site};
+
+/// Documentation site backend.
+#[derive(Default)]
+pub struct DocsExtension;
+
+impl BuiltinExtension for DocsExtension {
+    fn execute_native(&self, input: &Envelope) -> Result<Envelope> {
+        let request: DocsRequest = input.as_json().context("Failed to parse docs request")?;
+
+        let result = generate(request)?;
+
+        Envelope::json(&result).context("Failed to create response envelope")
+    }
+
+    fn info(&self) -> BuiltinInfo {
+        BuiltinInfo {
+            id: "docs".to_string(),
+            name: "Documentation".to_string(),
+            extension_type: ExtensionType::Backend,
+            description: "Render an HTML or Markdown documentation site for a distribution"
+                .to_string(),
+        }
+    }
+}
+
+/// Format of the generated pages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocsFormat {
+    #[default]
+    Html,
+    Markdown,
+}
+
+impl DocsFormat {
+    /// File extension of the pages
+    pub fn extension(self) -> &'static str {
+        match self {
+            DocsFormat::Html => "html",
+            DocsFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Options for the generated site, read from `[codegen.docs]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocsOptions {
+    #[serde(default)]
+    pub format: DocsFormat,
+    /// Document private modules and definitions too
+    #[serde(default)]
+    pub private: bool,
+}
+
+/// Request format for the generate operation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocsRequest {
+    /// Input IR (either Classic or V4 format)
+    pub ir: serde_json::Value,
+    #[serde(default)]
+    pub options: DocsOptions,
+}
+
+/// Response format for the generate operation, as returned by backend
+/// extensions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocsResponse {
+    /// Whether generation succeeded
+    pub success: bool,
+    /// Pages of the site
+    #[serde(default)]
+    pub artifacts: Vec<DocsArtifact>,
+    /// Error message (if failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A generated file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocsArtifact {
+    pub path: String,
+    pub content: String,
+}
+
+fn generate(request: DocsRequest) -> Result<DocsResponse> {
+    let distribution = if detect_ir_format(&request.ir) == "v4" {
+        let ir: v4::IRFile = serde_json::from_value(request.ir).context("Failed to parse V4 IR")?;
+        ir.distribution
+    } else {
+        let dist: classic::Distribution =
+            serde_json::from_value(request.ir).context("Failed to parse Classic IR")?;
+        converter::classic_to_v4(&dist).ir.distribution
+    };
+
+    Ok(DocsResponse {
+        success: true,
+        artifacts: generate_site(&distribution, &request.options)
+            .into_iter()
+            .map(|page| DocsArtifact {
+                path: page.path,
+                content: page.content,
+            })
+            .collect(),
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// `acme` with a documented public `orders` module and a private
+    /// `internal` module
+    fn distribution() -> v4::Distribution {
+        serde_json::from_value(json!({ "Library": {
+            "packageName": "acme",
+            "dependencies": {},
+            "def": { "modules": {
+                "orders": { "access": "Public", "value": {
+                    "doc": "Orders and their lines.\n\nPrices are in cents.",
+                    "types": {
+                        "line": { "access": "Public", "doc": "One `line` of an order", "value": {
+                            "TypeAliasDefinition": {
+                                "typeParams": [],
+                                "typeExp": "morphir/sdk:basics#int"
+                            }
+                        } },
+                        "secret": { "access": "Private", "value": { "TypeAliasDefinition": {
+                            "typeParams": [],
+                            "typeExp": "morphir/sdk:basics#int"
+                        } } }
+                    },
+                    "values": {
+                        "total": { "access": "Public", "doc": "Sum of <all> lines\n\n    total [] == 0", "value": {
+                            "inputTypes": { "lines": { "type": { "Reference": {
+                                "fqname": "morphir/sdk:list#list",
+                                "args": ["acme:orders#line"]
+                            } } } },
+                            "outputType": "morphir/sdk:basics#int",
+                            "body": { "ExpressionBody": { "body": { "Variable": { "name": "lines" } } } }
+                        } }
+                    }
+                } },
+                "internal": { "access": "Private", "value": { "types": {}, "values": {} } }
+            } }
+        } }))
+        .unwrap()
+    }
+
+    fn page<'a>(pages: &'a [DocsPage], path: &str) -> &'a str {
+        &pages
+            .iter()
+            .find(|page| page.path == path)
+            .unwrap_or_else(|| panic!("no page {}", path))
+            .content
+    }
+
+    #[test]
+    fn test_html_site() {
+        let pages = generate_site(&distribution(), &DocsOptions::default());
+        let paths: Vec<&str> = pages.iter().map(|page| page.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["index.html", "modules/orders.html", "style.css"]
+        );
+
+        let index = page(&pages, "index.html");
+        assert!(index.contains(
+            "<li><a href=\"modules/orders.html\">Orders</a> — Orders and their lines.</li>"
+        ));
+
+        let orders = page(&pages, "modules/orders.html");
+        assert!(orders.contains("<link rel=\"stylesheet\" href=\"../style.css\">"));
+        assert!(orders.contains("<p>Prices are in cents.</p>"));
+        assert!(orders.contains(
+            "total : <span title=\"morphir/sdk:list#list\">List</span> \
+             <a href=\"orders.html#type-line\" title=\"acme:orders#line\">Line</a>"
+        ));
+        assert!(orders.contains("<p>One <code>line</code> of an order</p>"));
+        assert!(
+            orders
+                .contains("<p>Sum of &lt;all&gt; lines</p>\n<pre><code>total [] == 0</code></pre>")
+        );
+        assert!(!orders.contains("Secret"));
+    }
+
+    #[test]
+    fn test_markdown_site_with_private_definitions() {
+        let options = DocsOptions {
+            format: DocsFormat::Markdown,
+            private: true,
+        };
+        let pages = generate_site(&distribution(), &options);
+        let paths: Vec<&str> = pages.iter().map(|page| page.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["index.md", "modules/internal.md", "modules/orders.md"]
+        );
+
+        let orders = page(&pages, "modules/orders.md");
+        assert!(orders.contains(
+            "<a id=\"value-total\"></a>\n\n### total\n\n```elm\ntotal : List Line -> Int\n```\n\n\
+             See [Line](orders.md#type-line)\n\n"
+        ));
+        assert!(orders.contains("### Secret"));
+    }
+
+    #[test]
+    fn test_extension_generates_artifacts() {
+        let ir = json!({
+            "formatVersion": "4.0.0",
+            "distribution": serde_json::to_value(distribution()).unwrap()
+        });
+        let input = Envelope::json(&json!({ "ir": ir })).unwrap();
+        let output = DocsExtension.execute_native(&input).unwrap();
+        let response: DocsResponse = output.as_json().unwrap();
+        assert!(response.success);
+        assert_eq!(response.artifacts.len(), 3);
+    }
+}
@@ -0,0 +1,414 @@
+//! Pages of the documentation site.
+//!
+//! The site has an index of the package's modules and one page per module
+//! with its types and values, each with its declaration and doc comment.
+//! Types of the package named in a declaration link to their own entry;
+//! types of other packages are shown unlinked.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use morphir_core::ir::v4::{Access, AccessControlled, Distribution, ModuleDefinition};
+use morphir_core::naming::{FQName, Name, Path};
+
+use super::code::{Code, Segment, type_declaration, value_signature};
+use super::{DocsFormat, DocsOptions};
+
+/// Directory of the module pages, relative to the site root
+const MODULES_DIR: &str = "modules";
+
+const STYLESHEET: &str = "\
+body { margin: 0; display: flex; font-family: system-ui, sans-serif; line-height: 1.5; color: #1f2328; }
+nav { min-width: 14rem; padding: 1.5rem; background: #f6f8fa; border-right: 1px solid #d0d7de; }
+nav ul { list-style: none; padding: 0; }
+main { max-width: 52rem; padding: 1.5rem 2.5rem; }
+pre { padding: 0.75rem 1rem; background: #f6f8fa; border-radius: 6px; overflow-x: auto; }
+code { font-family: ui-monospace, monospace; }
+a { color: #0969da; text-decoration: none; }
+a:hover { text-decoration: underline; }
+section { border-top: 1px solid #d0d7de; margin-top: 1.5rem; }
+";
+
+/// A generated file of the site
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocsPage {
+    /// Path relative to the site root, e.g. `modules/orders.html`
+    pub path: String,
+    pub content: String,
+}
+
+/// A documented module
+struct ModulePage<'a> {
+    /// Title-case dotted name, e.g. `Orders.Pricing`
+    title: String,
+    /// File name stem, e.g. `orders.pricing`
+    slug: String,
+    module: &'a AccessControlled<ModuleDefinition>,
+}
+
+/// Generate every page of the site for `distribution`.
+///
+/// Specs distributions carry no definitions and produce only an index.
+pub fn generate_site(distribution: &Distribution, options: &DocsOptions) -> Vec<DocsPage> {
+    let package = distribution.package_name().to_string();
+    let visible = |access: &Access| options.private || *access == Access::Public;
+    let modules: Vec<(&String, ModulePage)> = distribution
+        .definition()
+        .map(|definition| {
+            definition
+                .modules
+                .iter()
+                .filter(|(_, module)| visible(&module.access))
+                .map(|(key, module)| {
+                    let path = Path::new(key);
+                    let title = path
+                        .segments
+                        .iter()
+                        .map(Name::to_title_case)
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    let slug = path
+                        .segments
+                        .iter()
+                        .map(Name::to_kebab_case)
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    (
+                        key,
+                        ModulePage {
+                            title,
+                            slug,
+                            module,
+                        },
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Where each documented type lives, relative to the modules directory
+    let extension = options.format.extension();
+    let mut links = HashMap::new();
+    for (key, page) in &modules {
+        for (name, definition) in &page.module.value.types {
+            if visible(&definition.access) {
+                links.insert(
+                    FQName::new(
+                        distribution.package_name().0.clone(),
+                        Path::new(key),
+                        Name::from(name),
+                    ),
+                    format!("{}.{}#{}", page.slug, extension, type_anchor(name)),
+                );
+            }
+        }
+    }
+
+    let site = Site {
+        package,
+        options,
+        links,
+        modules: modules.iter().map(|(_, page)| page).collect(),
+    };
+    let mut pages = vec![DocsPage {
+        path: format!("index.{}", extension),
+        content: site.index(),
+    }];
+    for (_, page) in &modules {
+        pages.push(DocsPage {
+            path: format!("{}/{}.{}", MODULES_DIR, page.slug, extension),
+            content: site.module(page),
+        });
+    }
+    if options.format == DocsFormat::Html {
+        pages.push(DocsPage {
+            path: "style.css".to_string(),
+            content: STYLESHEET.to_string(),
+        });
+    }
+    pages
+}
+
+fn type_anchor(name: &str) -> String {
+    format!("type-{}", Name::from(name).to_kebab_case())
+}
+
+fn value_anchor(name: &str) -> String {
+    format!("value-{}", Name::from(name).to_kebab_case())
+}
+
+/// A documented entry of a module page
+struct Entry<'a> {
+    anchor: String,
+    title: String,
+    code: Code,
+    doc: Option<&'a str>,
+}
+
+struct Site<'a> {
+    package: String,
+    options: &'a DocsOptions,
+    links: HashMap<FQName, String>,
+    modules: Vec<&'a ModulePage<'a>>,
+}
+
+impl Site<'_> {
+    fn visible(&self, access: &Access) -> bool {
+        self.options.private || *access == Access::Public
+    }
+
+    fn index(&self) -> String {
+        match self.options.format {
+            DocsFormat::Html => {
+                let mut body = format!("<h1>{}</h1>\n", escape(&self.package));
+                body.push_str("<ul>\n");
+                for page in &self.modules {
+                    let _ = write!(
+                        body,
+                        "<li><a href=\"{}/{}.html\">{}</a>",
+                        MODULES_DIR,
+                        page.slug,
+                        escape(&page.title)
+                    );
+                    if let Some(summary) = summary(module_doc(page.module)) {
+                        let _ = write!(body, " — {}", doc_inline_html(&summary));
+                    }
+                    body.push_str("</li>\n");
+                }
+                body.push_str("</ul>\n");
+                self.html_page(&self.package, "", &body)
+            }
+            DocsFormat::Markdown => {
+                let mut out = format!("# {}\n\n", self.package);
+                for page in &self.modules {
+                    let _ = write!(out, "- [{}]({}/{}.md)", page.title, MODULES_DIR, page.slug);
+                    if let Some(summary) = summary(module_doc(page.module)) {
+                        let _ = write!(out, " — {}", summary);
+                    }
+                    out.push('\n');
+                }
+                out
+            }
+        }
+    }
+
+    fn module(&self, page: &ModulePage) -> String {
+        let module = &page.module.value;
+        let types: Vec<Entry> = module
+            .types
+            .iter()
+            .filter(|(_, definition)| self.visible(&definition.access))
+            .map(|(name, definition)| Entry {
+                anchor: type_anchor(name),
+                title: Name::from(name).to_title_case(),
+                code: type_declaration(name, &definition.value, self.options.private),
+                doc: definition.doc.as_deref(),
+            })
+            .collect();
+        let values: Vec<Entry> = module
+            .values
+            .iter()
+            .filter(|(_, definition)| self.visible(&definition.access))
+            .map(|(name, definition)| Entry {
+                anchor: value_anchor(name),
+                title: Name::from(name).to_camel_case(),
+                code: value_signature(name, &definition.value),
+                doc: definition.doc.as_deref(),
+            })
+            .collect();
+        let sections = [("Types", types), ("Values", values)];
+
+        match self.options.format {
+            DocsFormat::Html => {
+                let mut body = format!("<h1>{}</h1>\n", escape(&page.title));
+                if let Some(doc) = module_doc(page.module) {
+                    body.push_str(&doc_html(doc));
+                }
+                for (heading, entries) in &sections {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    let _ = writeln!(body, "<h2>{}</h2>", heading);
+                    for entry in entries {
+                        let _ = write!(
+                            body,
+                            "<section id=\"{}\">\n<h3>{}</h3>\n<pre><code>{}</code></pre>\n",
+                            entry.anchor,
+                            escape(&entry.title),
+                            self.code_html(&entry.code)
+                        );
+                        if let Some(doc) = entry.doc {
+                            body.push_str(&doc_html(doc));
+                        }
+                        body.push_str("</section>\n");
+                    }
+                }
+                self.html_page(&page.title, "../", &body)
+            }
+            DocsFormat::Markdown => {
+                let mut out = format!("# {}\n\n", page.title);
+                if let Some(doc) = module_doc(page.module) {
+                    let _ = write!(out, "{}\n\n", doc.trim());
+                }
+                for (heading, entries) in &sections {
+                    if entries.is_empty() {
+                        continue;
+                    }
+                    let _ = write!(out, "## {}\n\n", heading);
+                    for entry in entries {
+                        let _ = write!(
+                            out,
+                            "<a id=\"{}\"></a>\n\n### {}\n\n```elm\n{}\n```\n\n",
+                            entry.anchor,
+                            entry.title,
+                            entry.code.plain()
+                        );
+                        let see_also: Vec<String> = entry
+                            .code
+                            .references()
+                            .into_iter()
+                            .filter_map(|fqname| {
+                                let href = self.links.get(fqname)?;
+                                Some(format!("[{}]({})", fqname.local_name.to_title_case(), href))
+                            })
+                            .collect();
+                        if !see_also.is_empty() {
+                            let _ = write!(out, "See {}\n\n", see_also.join(", "));
+                        }
+                        if let Some(doc) = entry.doc {
+                            let _ = write!(out, "{}\n\n", doc.trim());
+                        }
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    /// A declaration with its references to documented types linked
+    fn code_html(&self, code: &Code) -> String {
+        code.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => escape(text),
+                Segment::Reference(text, fqname) => match self.links.get(fqname) {
+                    Some(href) => format!(
+                        "<a href=\"{}\" title=\"{}\">{}</a>",
+                        href,
+                        escape(&fqname.to_canonical_string()),
+                        escape(text)
+                    ),
+                    None => format!(
+                        "<span title=\"{}\">{}</span>",
+                        escape(&fqname.to_canonical_string()),
+                        escape(text)
+                    ),
+                },
+            })
+            .collect()
+    }
+
+    /// `root` leads from the page back to the site root
+    fn html_page(&self, title: &str, root: &str, body: &str) -> String {
+        let mut nav = format!(
+            "<a href=\"{}index.html\"><strong>{}</strong></a>\n<ul>\n",
+            root,
+            escape(&self.package)
+        );
+        for page in &self.modules {
+            let _ = writeln!(
+                nav,
+                "<li><a href=\"{}{}/{}.html\">{}</a></li>",
+                root,
+                MODULES_DIR,
+                page.slug,
+                escape(&page.title)
+            );
+        }
+        nav.push_str("</ul>");
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{} · {}</title>\n<link rel=\"stylesheet\" href=\"{}style.css\">\n</head>\n\
+             <body>\n<nav>\n{}\n</nav>\n<main>\n{}</main>\n</body>\n</html>\n",
+            escape(title),
+            escape(&self.package),
+            root,
+            nav,
+            body
+        )
+    }
+}
+
+/// The module's documentation, from its definition or its access wrapper
+fn module_doc(module: &AccessControlled<ModuleDefinition>) -> Option<&str> {
+    module
+        .value
+        .doc
+        .as_deref()
+        .or(module.doc.as_deref())
+        .filter(|doc| !doc.trim().is_empty())
+}
+
+/// First paragraph of a doc comment, on one line
+fn summary(doc: Option<&str>) -> Option<String> {
+    let paragraph = doc?.trim().split("\n\n").next()?;
+    Some(paragraph.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Render a doc comment: paragraphs separated by blank lines, fenced or
+/// indented code blocks, and `inline code`
+fn doc_html(doc: &str) -> String {
+    let mut out = String::new();
+    let mut lines = doc.trim().lines().peekable();
+    while let Some(line) = lines.next() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.trim_start().starts_with("```") {
+            let block: Vec<&str> = lines
+                .by_ref()
+                .take_while(|line| !line.trim_start().starts_with("```"))
+                .collect();
+            let _ = writeln!(out, "<pre><code>{}</code></pre>", escape(&block.join("\n")));
+        } else if let Some(first) = line.strip_prefix("    ") {
+            let mut block = vec![first];
+            while let Some(next) = lines.peek().and_then(|next| next.strip_prefix("    ")) {
+                block.push(next);
+                lines.next();
+            }
+            let _ = writeln!(out, "<pre><code>{}</code></pre>", escape(&block.join("\n")));
+        } else {
+            let mut paragraph = vec![line.trim()];
+            while let Some(next) = lines.peek() {
+                if next.trim().is_empty() || next.trim_start().starts_with("```") {
+                    break;
+                }
+                paragraph.push(next.trim());
+                lines.next();
+            }
+            let _ = writeln!(out, "<p>{}</p>", doc_inline_html(&paragraph.join(" ")));
+        }
+    }
+    out
+}
+
+/// Escape a line of doc text, turning `backticks` into code spans
+fn doc_inline_html(text: &str) -> String {
+    text.split('`')
+        .enumerate()
+        .map(|(i, part)| {
+            if i % 2 == 1 {
+                format!("<code>{}</code>", escape(part))
+            } else {
+                escape(part)
+            }
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,238 @@
+//! JSON Schema builtin extension.
+//!
+//! Generates JSON Schema documents for the domain types (records, custom
+//! types, aliases) of a distribution, describing the JSON encoding `morphir
+//! run` reads and writes. This is a backend, unlike `morphir schema`, which
+//! describes the IR itself.
+
+use crate::{BuiltinExtension, BuiltinInfo, ExtensionType, detect_ir_format};
+use anyhow::{Context, Result};
+use morphir_core::converter;
+use morphir_core::ir::{classic, v4};
+use morphir_ext_core::Envelope;
+use serde::{Deserialize, Serialize};
+
+mod schema;
+
+pub use schema::{SchemaDocument, SchemaOutput, TypeSchemas, generate_schemas};
+
+/// JSON Schema backend for domain types.
+#[derive(Default)]
+pub struct JsonSchemaExtension;
+
+impl BuiltinExtension for JsonSchemaExtension {
+    fn execute_native(&self, input: &Envelope) -> Result<Envelope> {
+        let request: JsonSchemaRequest = input
+            .as_json()
+            .context("Failed to parse json-schema request")?;
+
+        let result = generate(request)?;
+
+        Envelope::json(&result).context("Failed to create response envelope")
+    }
+
+    fn info(&self) -> BuiltinInfo {
+        BuiltinInfo {
+            id: "json-schema".to_string(),
+            name: "JSON Schema".to_string(),
+            extension_type: ExtensionType::Backend,
+            description: "Generate JSON Schema documents for the types of a distribution"
+                .to_string(),
+        }
+    }
+}
+
+/// JSON Schema draft to target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Draft {
+    /// Draft 2020-12 (`$defs`, `prefixItems`)
+    #[default]
+    #[serde(rename = "2020-12", alias = "draft-2020-12")]
+    Draft2020_12,
+    /// Draft-07 (`definitions`, array-form `items`)
+    #[serde(rename = "07", alias = "7", alias = "draft-07")]
+    Draft07,
+}
+
+impl Draft {
+    /// Meta-schema URI for `$schema`
+    pub fn uri(self) -> &'static str {
+        match self {
+            Draft::Draft2020_12 => "https://json-schema.org/draft/2020-12/schema",
+            Draft::Draft07 => "http://json-schema.org/draft-07/schema#",
+        }
+    }
+
+    /// Keyword under which reusable schemas are kept
+    pub fn definitions_keyword(self) -> &'static str {
+        match self {
+            Draft::Draft2020_12 => "$defs",
+            Draft::Draft07 => "definitions",
+        }
+    }
+}
+
+/// How schemas refer to the other types of the package.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefStrategy {
+    /// One document for the package, types under `$defs` and referenced by `$ref`
+    #[default]
+    Definitions,
+    /// One document per public type, referenced types expanded in place
+    Inline,
+}
+
+/// Options for the generated schemas, read from `[codegen.json-schema]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonSchemaOptions {
+    #[serde(default)]
+    pub draft: Draft,
+    #[serde(default)]
+    pub refs: RefStrategy,
+}
+
+/// Request format for the generate operation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonSchemaRequest {
+    /// Input IR (either Classic or V4 format)
+    pub ir: serde_json::Value,
+    #[serde(default)]
+    pub options: JsonSchemaOptions,
+}
+
+/// Response format for the generate operation, as returned by backend
+/// extensions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonSchemaResponse {
+    /// Whether generation succeeded
+    pub success: bool,
+    /// Generated schema documents
+    #[serde(default)]
+    pub artifacts: Vec<SchemaArtifact>,
+    /// Warnings about types that could only be described loosely
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<SchemaDiagnostic>,
+    /// Error message (if failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A generated file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaArtifact {
+    pub path: String,
+    pub content: String,
+}
+
+/// A diagnostic about the generated schemas, naming a type whose schema
+/// accepts any value because it is defined elsewhere or has no JSON encoding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDiagnostic {
+    /// `warning`: such types get the permissive `{}` schema rather than
+    /// failing the request
+    pub severity: String,
+    pub message: String,
+}
+
+fn generate(request: JsonSchemaRequest) -> Result<JsonSchemaResponse> {
+    let distribution = if detect_ir_format(&request.ir) == "v4" {
+        let ir: v4::IRFile = serde_json::from_value(request.ir).context("Failed to parse V4 IR")?;
+        ir.distribution
+    } else {
+        let dist: classic::Distribution =
+            serde_json::from_value(request.ir).context("Failed to parse Classic IR")?;
+        converter::classic_to_v4(&dist).ir.distribution
+    };
+
+    let Some(package) = distribution.definition() else {
+        return Ok(JsonSchemaResponse {
+            success: false,
+            artifacts: vec![],
+            diagnostics: vec![],
+            error: Some("Specs distributions carry no type definitions".to_string()),
+        });
+    };
+    let output = generate_schemas(
+        &distribution.package_name().to_string(),
+        package,
+        &request.options,
+    );
+
+    let artifacts = output
+        .documents
+        .into_iter()
+        .map(|document| {
+            Ok(SchemaArtifact {
+                path: document.path,
+                content: serde_json::to_string_pretty(&document.schema)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+    let diagnostics = output
+        .warnings
+        .into_iter()
+        .map(|message| SchemaDiagnostic {
+            severity: "warning".to_string(),
+            message,
+        })
+        .collect();
+
+    Ok(JsonSchemaResponse {
+        success: true,
+        artifacts,
+        diagnostics,
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_schema_info() {
+        let info = JsonSchemaExtension.info();
+        assert_eq!(info.id, "json-schema");
+        assert_eq!(info.extension_type, ExtensionType::Backend);
+    }
+
+    #[test]
+    fn test_generate_from_classic_ir() {
+        // type alias Amount = Int, in module Billing of package Acme
+        let request = serde_json::json!({
+            "ir": {
+                "formatVersion": 3,
+                "distribution": ["Library", [["acme"]], [], {"modules": [
+                    [[["billing"]], {"access": "Public", "value": {
+                        "types": [[["amount"], {"access": "Public", "value": {"doc": "", "value":
+                            ["TypeAliasDefinition", [],
+                                ["Reference", {}, [[["morphir"], ["s", "d", "k"]], [["basics"]], ["int"]], []]]
+                        }}]],
+                        "values": [],
+                        "doc": null
+                    }}]
+                ]}]
+            },
+            "options": { "draft": "07" }
+        });
+        let output = JsonSchemaExtension
+            .execute_native(&Envelope::json(&request).unwrap())
+            .unwrap();
+        let response: JsonSchemaResponse = output.as_json().unwrap();
+        assert!(response.success, "{:?}", response.error);
+        assert!(
+            response.diagnostics.is_empty(),
+            "{:?}",
+            response.diagnostics
+        );
+        assert_eq!(response.artifacts[0].path, "acme.schema.json");
+
+        let schema: serde_json::Value =
+            serde_json::from_str(&response.artifacts[0].content).unwrap();
+        assert_eq!(
+            schema["definitions"]["Billing.Amount"],
+            serde_json::json!({ "type": "integer", "title": "Amount" })
+        );
+    }
+}
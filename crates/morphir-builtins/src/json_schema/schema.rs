@@ -0,0 +1,655 @@
+//! JSON Schema generation for the types of a V4 distribution.
+//!
+//! Schemas describe the JSON encoding the runtime reads and writes: records
+//! are objects keyed by camelCase field names, `Maybe` is the wrapped value or
+//! `null`, `Decimal` a string, `Dict` an array of `[key, value]` pairs, and
+//! other constructors the constructor name, or an array of the name followed
+//! by the arguments.
+
+use indexmap::{IndexMap, IndexSet};
+use morphir_core::ir::v4::{self, Access, Type, TypeDefinition};
+use morphir_core::naming::{Name, Path};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+use super::{Draft, JsonSchemaOptions, RefStrategy};
+
+/// A generated schema document
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDocument {
+    /// Output path of the document
+    pub path: String,
+    /// The schema
+    pub schema: Value,
+}
+
+/// Result of generating schemas for a package
+#[derive(Debug, Clone, Default)]
+pub struct SchemaOutput {
+    pub documents: Vec<SchemaDocument>,
+    /// Types that could only be described loosely, e.g. functions or
+    /// references to packages that are not available
+    pub warnings: Vec<String>,
+}
+
+/// Generate schema documents for the types of a package definition.
+///
+/// Every public type of a public module gets a schema; private types are
+/// included only as far as public ones refer to them. With
+/// [`RefStrategy::Definitions`] all schemas go in one document under
+/// `$defs` (`definitions` for draft-07); with [`RefStrategy::Inline`] each
+/// public type gets its own document with referenced types expanded in
+/// place, and only recursive references go through `$defs`.
+pub fn generate_schemas(
+    package_name: &str,
+    package: &v4::PackageDefinition,
+    options: &JsonSchemaOptions,
+) -> SchemaOutput {
+    let mut generator = Generator::new(package_name, package, options);
+    let roots: Vec<String> = generator
+        .types
+        .iter()
+        .filter(|(_, t)| t.public)
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    let documents = match options.refs {
+        RefStrategy::Definitions => {
+            generator.referenced.extend(roots);
+            let mut document = generator.header();
+            document.insert("title".to_string(), json!(package_name));
+            vec![generator.finish(document, package_name.replace('/', "."))]
+        }
+        RefStrategy::Inline => roots
+            .into_iter()
+            .map(|key| {
+                generator.referenced.clear();
+                generator.root = Some(key.clone());
+                let mut document = generator.header();
+                if let Value::Object(schema) = generator.definition(&key, &[]) {
+                    document.extend(schema);
+                }
+                generator.finish(document, key)
+            })
+            .collect(),
+    };
+
+    SchemaOutput {
+        documents,
+        warnings: generator.warnings,
+    }
+}
+
+/// Schemas for individual types of a package, for backends that embed them
+/// in a larger document such as an OpenAPI description.
+///
+/// Local types the schemas refer to are collected as definitions to place
+/// where `ref_prefix` points, e.g. `#/components/schemas/`.
+pub struct TypeSchemas<'a> {
+    generator: Generator<'a>,
+}
+
+impl<'a> TypeSchemas<'a> {
+    pub fn new(
+        package_name: &str,
+        package: &'a v4::PackageDefinition,
+        options: &'a JsonSchemaOptions,
+        ref_prefix: &str,
+    ) -> Self {
+        let mut generator = Generator::new(package_name, package, options);
+        generator.ref_prefix = ref_prefix.to_string();
+        TypeSchemas { generator }
+    }
+
+    /// Schema of a type, as it appears in the package
+    pub fn schema(&mut self, tpe: &Type) -> Value {
+        self.generator.type_schema(tpe, &HashMap::new())
+    }
+
+    /// Definitions of the local types referenced so far, by key, and the
+    /// warnings raised while generating
+    pub fn finish(mut self) -> (Map<String, Value>, Vec<String>) {
+        let definitions = self.generator.take_definitions();
+        (definitions, self.generator.warnings)
+    }
+}
+
+/// A type definition of the package being generated
+struct LocalType<'a> {
+    name: String,
+    doc: Option<&'a str>,
+    public: bool,
+    definition: &'a TypeDefinition,
+}
+
+struct Generator<'a> {
+    options: &'a JsonSchemaOptions,
+    package: String,
+    /// Type definitions by schema key, `Module.Path.TypeName`
+    types: IndexMap<String, LocalType<'a>>,
+    /// Keys referenced through `$ref` from the current document
+    referenced: IndexSet<String>,
+    /// Start of every `$ref` to a definition, followed by its key
+    ref_prefix: String,
+    /// Key of the type the current inline document describes
+    root: Option<String>,
+    /// Keys of the definitions being expanded, innermost last
+    stack: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(
+        package_name: &str,
+        package: &'a v4::PackageDefinition,
+        options: &'a JsonSchemaOptions,
+    ) -> Self {
+        let mut types = IndexMap::new();
+        for (module_name, module) in &package.modules {
+            let module_path = Path::new(module_name);
+            for (type_name, def) in &module.value.types {
+                let name = Name::from(type_name.as_str());
+                types.insert(
+                    schema_key(&module_path, &name),
+                    LocalType {
+                        name: name.to_title_case(),
+                        doc: def.doc.as_deref().filter(|doc| !doc.trim().is_empty()),
+                        public: module.access == Access::Public && def.access == Access::Public,
+                        definition: &def.value,
+                    },
+                );
+            }
+        }
+        Generator {
+            options,
+            package: Path::new(package_name).to_string(),
+            types,
+            referenced: IndexSet::new(),
+            ref_prefix: format!("#/{}/", options.draft.definitions_keyword()),
+            root: None,
+            stack: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn header(&self) -> Map<String, Value> {
+        let mut document = Map::new();
+        document.insert("$schema".to_string(), json!(self.options.draft.uri()));
+        document
+    }
+
+    /// Add the definitions the document refers to and wrap it up as a file
+    fn finish(&mut self, mut document: Map<String, Value>, name: String) -> SchemaDocument {
+        let defs = self.take_definitions();
+        if !defs.is_empty() {
+            document.insert(
+                self.options.draft.definitions_keyword().to_string(),
+                Value::Object(defs),
+            );
+        }
+        SchemaDocument {
+            path: format!("{}.schema.json", name),
+            schema: Value::Object(document),
+        }
+    }
+
+    /// Schemas of the referenced types, including the ones they refer to
+    fn take_definitions(&mut self) -> Map<String, Value> {
+        let mut defs = Map::new();
+        while let Some(key) = self
+            .referenced
+            .iter()
+            .find(|key| !defs.contains_key(key.as_str()))
+            .cloned()
+        {
+            let schema = self.definition(&key, &[]);
+            defs.insert(key, schema);
+        }
+        defs
+    }
+
+    fn warn(&mut self, message: String) {
+        let message = match self.stack.last() {
+            Some(key) => format!("{}: {}", key, message),
+            None => message,
+        };
+        if !self.warnings.contains(&message) {
+            self.warnings.push(message);
+        }
+    }
+
+    /// Schema of a local type applied to the given argument schemas
+    fn definition(&mut self, key: &str, args: &[Value]) -> Value {
+        let Some(local) = self.types.get(key) else {
+            return json!({});
+        };
+        let (title, doc, definition) = (local.name.clone(), local.doc, local.definition);
+        self.stack.push(key.to_string());
+
+        let bind = |params: &[Name]| -> HashMap<String, Value> {
+            params
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (p.to_kebab_case(), args.get(i).cloned().unwrap_or(json!({}))))
+                .collect()
+        };
+        let mut schema = match definition {
+            TypeDefinition::TypeAliasDefinition {
+                type_params,
+                type_expr,
+            } => self.type_schema(type_expr, &bind(type_params)),
+            TypeDefinition::CustomTypeDefinition {
+                type_params,
+                constructors,
+            } => {
+                let bindings = bind(type_params);
+                let constructors: Vec<(String, Vec<Value>)> = constructors
+                    .value
+                    .iter()
+                    .map(|c| {
+                        let args = c
+                            .args
+                            .iter()
+                            .map(|arg| self.type_schema(&arg.arg_type, &bindings))
+                            .collect();
+                        (c.name.to_title_case(), args)
+                    })
+                    .collect();
+                self.union_schema(constructors)
+            }
+            TypeDefinition::IncompleteTypeDefinition { .. } => {
+                self.warn("incomplete type definition, any value accepted".to_string());
+                json!({})
+            }
+        };
+        self.stack.pop();
+
+        if let Value::Object(map) = &mut schema {
+            map.insert("title".to_string(), json!(title));
+            if let Some(doc) = doc {
+                map.insert("description".to_string(), json!(doc.trim()));
+            }
+        }
+        schema
+    }
+
+    /// Reference to a local type's schema, recorded for the document's `$defs`
+    fn reference(&mut self, key: &str) -> Value {
+        if self.root.as_deref() == Some(key) {
+            return json!({ "$ref": "#" });
+        }
+        self.referenced.insert(key.to_string());
+        json!({ "$ref": format!("{}{}", self.ref_prefix, key) })
+    }
+
+    fn type_schema(&mut self, tpe: &Type, bindings: &HashMap<String, Value>) -> Value {
+        match tpe {
+            Type::Variable(_, name) => bindings
+                .get(&name.to_kebab_case())
+                .cloned()
+                .unwrap_or_else(|| json!({})),
+            Type::Reference(_, fqname, args) => {
+                let args: Vec<Value> = args
+                    .iter()
+                    .map(|arg| self.type_schema(arg, bindings))
+                    .collect();
+                if fqname.package_path.is_sdk() {
+                    return self.sdk_schema(
+                        &fqname.module_path.to_string(),
+                        &fqname.local_name.to_kebab_case(),
+                        args,
+                    );
+                }
+                let key = schema_key(&fqname.module_path, &fqname.local_name);
+                if fqname.package_path.to_string() != self.package || !self.types.contains_key(&key)
+                {
+                    self.warn(format!(
+                        "type `{}` is not defined in this package, any value accepted",
+                        fqname.to_canonical_string()
+                    ));
+                    return json!({});
+                }
+                // Generic types have no single schema to refer to, so their
+                // applications are expanded unless they recur
+                let inline = self.options.refs == RefStrategy::Inline || !args.is_empty();
+                if inline && !self.stack.contains(&key) {
+                    self.definition(&key, &args)
+                } else {
+                    self.reference(&key)
+                }
+            }
+            Type::Tuple(_, elements) => {
+                let items = elements
+                    .iter()
+                    .map(|t| self.type_schema(t, bindings))
+                    .collect();
+                self.tuple_schema(items)
+            }
+            Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+                let mut properties = Map::new();
+                let mut required = Vec::new();
+                for field in fields {
+                    let name = field.name.to_camel_case();
+                    if !is_optional(&field.tpe) {
+                        required.push(json!(name));
+                    }
+                    properties.insert(name, self.type_schema(&field.tpe, bindings));
+                }
+                let mut schema = json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                });
+                // Extensible records accept the fields of any record that extends them
+                if matches!(tpe, Type::Record(..)) {
+                    schema["additionalProperties"] = json!(false);
+                }
+                schema
+            }
+            Type::Function(..) => {
+                self.warn("function types have no JSON encoding".to_string());
+                json!({ "not": {} })
+            }
+            Type::Unit(_) => json!({ "type": "null" }),
+        }
+    }
+
+    fn sdk_schema(&mut self, module: &str, local: &str, mut args: Vec<Value>) -> Value {
+        let mut arg = |i: usize| {
+            args.get_mut(i)
+                .map(std::mem::take)
+                .unwrap_or_else(|| json!({}))
+        };
+        match (module, local) {
+            ("basics", "int") => json!({ "type": "integer" }),
+            ("basics", "float") => json!({ "type": "number" }),
+            ("basics", "bool") => json!({ "type": "boolean" }),
+            ("string", "string") => json!({ "type": "string" }),
+            ("char", "char") => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+            ("decimal", "decimal") => json!({ "type": ["string", "number"] }),
+            ("list", "list") => json!({ "type": "array", "items": arg(0) }),
+            ("maybe", "maybe") => json!({ "anyOf": [arg(0), { "type": "null" }] }),
+            ("dict", "dict") => {
+                let entry = self.tuple_schema(vec![arg(0), arg(1)]);
+                json!({ "type": "array", "items": entry })
+            }
+            ("result", "result") => {
+                let (error, value) = (arg(0), arg(1));
+                self.union_schema(vec![
+                    ("Ok".to_string(), vec![value]),
+                    ("Err".to_string(), vec![error]),
+                ])
+            }
+            _ => {
+                self.warn(format!(
+                    "no JSON encoding known for `morphir/sdk:{}#{}`, any value accepted",
+                    module, local
+                ));
+                json!({})
+            }
+        }
+    }
+
+    /// Fixed-length array with one schema per position
+    fn tuple_schema(&self, items: Vec<Value>) -> Value {
+        let len = items.len();
+        match self.options.draft {
+            Draft::Draft2020_12 => json!({
+                "type": "array",
+                "prefixItems": items,
+                "items": false,
+                "minItems": len,
+                "maxItems": len,
+            }),
+            Draft::Draft07 => json!({
+                "type": "array",
+                "items": items,
+                "additionalItems": false,
+                "minItems": len,
+                "maxItems": len,
+            }),
+        }
+    }
+
+    /// Constructors encoded as their name, or `[name, args...]`
+    fn union_schema(&self, constructors: Vec<(String, Vec<Value>)>) -> Value {
+        if constructors.iter().all(|(_, args)| args.is_empty()) {
+            let names: Vec<String> = constructors.into_iter().map(|(name, _)| name).collect();
+            return json!({ "type": "string", "enum": names });
+        }
+        let variants: Vec<Value> = constructors
+            .into_iter()
+            .map(|(name, args)| {
+                if args.is_empty() {
+                    json!({ "const": name })
+                } else {
+                    let items = std::iter::once(json!({ "const": name }))
+                        .chain(args)
+                        .collect();
+                    self.tuple_schema(items)
+                }
+            })
+            .collect();
+        json!({ "oneOf": variants })
+    }
+}
+
+/// Key of a type's schema: the title-cased module path and type name, e.g.
+/// `Orders.Pricing.OrderLine`
+fn schema_key(module_path: &Path, name: &Name) -> String {
+    module_path
+        .segments
+        .iter()
+        .chain(std::iter::once(name))
+        .map(Name::to_title_case)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Fields the runtime reads as `null` when missing
+fn is_optional(tpe: &Type) -> bool {
+    match tpe {
+        Type::Unit(_) => true,
+        Type::Reference(_, fqname, _) => {
+            fqname.package_path.is_sdk()
+                && fqname.module_path.to_string() == "maybe"
+                && fqname.local_name.to_kebab_case() == "maybe"
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package() -> v4::PackageDefinition {
+        serde_json::from_value(json!({
+            "modules": {
+                "orders": {
+                    "access": "Public",
+                    "value": {
+                        "types": {
+                            "order": {
+                                "access": "Public",
+                                "doc": "A customer order",
+                                "value": { "TypeAliasDefinition": {
+                                    "typeParams": [],
+                                    "typeExp": { "Record": { "fields": {
+                                        "id": { "Reference": { "fqname": "morphir/sdk:string#string" } },
+                                        "lines": { "Reference": {
+                                            "fqname": "morphir/sdk:list#list",
+                                            "args": [{ "Reference": { "fqname": "shop:orders#line" } }]
+                                        } },
+                                        "status": { "Reference": { "fqname": "shop:orders#status" } },
+                                        "note": { "Reference": {
+                                            "fqname": "morphir/sdk:maybe#maybe",
+                                            "args": [{ "Reference": { "fqname": "morphir/sdk:string#string" } }]
+                                        } }
+                                    } } }
+                                } }
+                            },
+                            "line": {
+                                "access": "Private",
+                                "value": { "TypeAliasDefinition": {
+                                    "typeParams": [],
+                                    "typeExp": { "Tuple": { "elements": [
+                                        { "Reference": { "fqname": "morphir/sdk:string#string" } },
+                                        { "Reference": { "fqname": "morphir/sdk:basics#int" } }
+                                    ] } }
+                                } }
+                            },
+                            "status": {
+                                "access": "Public",
+                                "value": { "CustomTypeDefinition": {
+                                    "typeParams": [],
+                                    "constructors": {
+                                        "access": "Public",
+                                        "value": [
+                                            { "name": "open", "args": [] },
+                                            { "name": "shipped", "args": [
+                                                { "name": "tracking", "type": { "Reference": { "fqname": "morphir/sdk:string#string" } } }
+                                            ] }
+                                        ]
+                                    }
+                                } }
+                            },
+                            "tree": {
+                                "access": "Public",
+                                "value": { "CustomTypeDefinition": {
+                                    "typeParams": [],
+                                    "constructors": {
+                                        "access": "Public",
+                                        "value": [
+                                            { "name": "leaf", "args": [] },
+                                            { "name": "node", "args": [
+                                                { "name": "children", "type": { "Reference": {
+                                                    "fqname": "morphir/sdk:list#list",
+                                                    "args": [{ "Reference": { "fqname": "shop:orders#tree" } }]
+                                                } } }
+                                            ] }
+                                        ]
+                                    }
+                                } }
+                            }
+                        },
+                        "values": {}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_definitions_strategy_bundles_public_types() {
+        let output = generate_schemas("shop", &package(), &JsonSchemaOptions::default());
+        assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+        assert_eq!(output.documents.len(), 1);
+
+        let document = &output.documents[0];
+        assert_eq!(document.path, "shop.schema.json");
+        let schema = &document.schema;
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        let defs = schema["$defs"].as_object().unwrap();
+        // Public types, and the private types they refer to
+        let keys: Vec<&str> = defs.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "Orders.Line",
+                "Orders.Order",
+                "Orders.Status",
+                "Orders.Tree"
+            ]
+        );
+
+        let order = &defs["Orders.Order"];
+        assert_eq!(order["description"], "A customer order");
+        assert_eq!(order["required"], json!(["id", "lines", "status"]));
+        assert_eq!(
+            order["properties"]["lines"]["items"],
+            json!({ "$ref": "#/$defs/Orders.Line" })
+        );
+        assert_eq!(
+            defs["Orders.Line"]["prefixItems"],
+            json!([{ "type": "string" }, { "type": "integer" }])
+        );
+        assert_eq!(
+            defs["Orders.Status"]["oneOf"][1]["prefixItems"][0],
+            json!({ "const": "Shipped" })
+        );
+    }
+
+    #[test]
+    fn test_inline_strategy_expands_references() {
+        let options = JsonSchemaOptions {
+            draft: Draft::Draft07,
+            refs: RefStrategy::Inline,
+        };
+        let output = generate_schemas("shop", &package(), &options);
+        let paths: Vec<&str> = output.documents.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "Orders.Order.schema.json",
+                "Orders.Status.schema.json",
+                "Orders.Tree.schema.json"
+            ]
+        );
+
+        let order = &output.documents[0].schema;
+        assert_eq!(order["$schema"], "http://json-schema.org/draft-07/schema#");
+        assert_eq!(order["title"], "Order");
+        assert!(order.get("definitions").is_none());
+        let line = &order["properties"]["lines"]["items"];
+        assert_eq!(line["title"], "Line");
+        assert_eq!(line["additionalItems"], false);
+        assert_eq!(
+            order["properties"]["status"]["oneOf"][0],
+            json!({ "const": "Open" })
+        );
+
+        // Recursive types refer back to the document root
+        let tree = &output.documents[2].schema;
+        assert_eq!(
+            tree["oneOf"][1]["items"][1]["items"],
+            json!({ "$ref": "#" })
+        );
+    }
+
+    #[test]
+    fn test_unencodable_types_are_reported() {
+        let package: v4::PackageDefinition = serde_json::from_value(json!({
+            "modules": { "api": { "access": "Public", "value": {
+                "types": { "handler": { "access": "Public", "value": { "TypeAliasDefinition": {
+                    "typeParams": [],
+                    "typeExp": { "Record": { "fields": {
+                        "request": { "Reference": { "fqname": "other:lib#request" } },
+                        "respond": { "Function": {
+                            "arg": { "Unit": {} },
+                            "result": { "Unit": {} }
+                        } }
+                    } } }
+                } } } },
+                "values": {}
+            } } }
+        }))
+        .unwrap();
+        let output = generate_schemas("shop", &package, &JsonSchemaOptions::default());
+
+        let handler = &output.documents[0].schema["$defs"]["Api.Handler"];
+        assert_eq!(handler["properties"]["request"], json!({}));
+        assert_eq!(handler["properties"]["respond"], json!({ "not": {} }));
+        assert_eq!(
+            output.warnings,
+            vec![
+                "Api.Handler: type `other:lib#request` is not defined in this package, any value accepted",
+                "Api.Handler: function types have no JSON encoding",
+            ]
+        );
+    }
+}
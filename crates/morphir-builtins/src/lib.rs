@@ -8,6 +8,12 @@
 //! # Builtins
 //!
 //! - `migrate`: IR version migration (v3 ↔ v4)
+//! - `json-schema`: JSON Schema documents for a distribution's types
+//! - `protobuf`: `.proto` files and gRPC services for a distribution
+//! - `decision-tables`: decision tables mined from nested conditionals
+//! - `extract-constants`: literal constants lifted into a configuration module
+//! - `lint`: configurable style and correctness rules for a distribution
+//! - `docs`: an HTML or Markdown documentation site for a distribution
 //!
 //! # Usage
 //!
@@ -40,8 +46,20 @@
 use anyhow::Result;
 use morphir_ext_core::Envelope;
 
+#[cfg(feature = "constants")]
+pub mod constants;
+#[cfg(feature = "decision-tables")]
+pub mod decision_tables;
+#[cfg(feature = "docs")]
+pub mod docs;
+#[cfg(feature = "json-schema")]
+pub mod json_schema;
+#[cfg(feature = "lint")]
+pub mod lint;
 #[cfg(feature = "migrate")]
 pub mod migrate;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
 
 pub mod registry;
 
@@ -106,3 +124,22 @@ impl std::fmt::Display for ExtensionType {
         }
     }
 }
+
+/// Detect if IR is in V4 format by checking for V4-specific markers.
+pub(crate) fn detect_ir_format(ir: &serde_json::Value) -> &'static str {
+    // V4 format has formatVersion >= 4 or formatVersion starting with "4"
+    if let Some(fv) = ir.get("formatVersion") {
+        if let Some(n) = fv.as_i64()
+            && n >= 4
+        {
+            return "v4";
+        }
+        if let Some(s) = fv.as_str()
+            && s.starts_with('4')
+        {
+            return "v4";
+        }
+    }
+    // Default to classic format
+    "classic"
+}
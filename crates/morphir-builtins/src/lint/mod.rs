@@ -0,0 +1,310 @@
+//! Lint builtin extension.
+//!
+//! Checks a distribution against rules of style and likely mistakes, such as
+//! unused type variables, pattern-match cases that can never match, and
+//! shadowed variables. Rules report at the level configured for them in the
+//! `[lint]` section of `morphir.toml`, or are turned off:
+//!
+//! ```toml
+//! [lint]
+//! max_nesting_depth = 3
+//!
+//! [lint.rules]
+//! shadowed-variable = "error"
+//! naming-convention = "off"
+//! ```
+//!
+//! Findings are reported as the same diagnostics as type checking.
+
+use crate::{BuiltinExtension, BuiltinInfo, ExtensionType, detect_ir_format};
+use anyhow::{Context, Result, bail};
+use morphir_common::config::{LintLevel, LintSection};
+use morphir_core::converter;
+use morphir_core::ir::v4::typecheck::{Diagnostic, Severity};
+use morphir_core::ir::{classic, v4};
+use morphir_core::naming::{FQName, Name, Path};
+use morphir_ext_core::Envelope;
+use serde::{Deserialize, Serialize};
+
+mod rules;
+
+pub use rules::{
+    DEFAULT_MAX_NESTING_DEPTH, DeepNesting, Finding, LintRule, NamingConvention, ShadowedVariable,
+    UnreachableCase, UnusedTypeVariable, all_rules,
+};
+
+/// Validator checking a distribution against the lint rules.
+#[derive(Default)]
+pub struct LintExtension;
+
+impl BuiltinExtension for LintExtension {
+    fn execute_native(&self, input: &Envelope) -> Result<Envelope> {
+        let request: LintRequest = input.as_json().context("Failed to parse lint request")?;
+
+        let result = lint(request)?;
+
+        Envelope::json(&result).context("Failed to create response envelope")
+    }
+
+    fn info(&self) -> BuiltinInfo {
+        BuiltinInfo {
+            id: "lint".to_string(),
+            name: "Lint".to_string(),
+            extension_type: ExtensionType::Validator,
+            description: "Check a distribution against configurable style and correctness rules"
+                .to_string(),
+        }
+    }
+}
+
+/// Request format for the lint operation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LintRequest {
+    /// Input IR (either Classic or V4 format)
+    pub ir: serde_json::Value,
+    /// Rule levels, as in the `[lint]` section of `morphir.toml`
+    #[serde(default)]
+    pub options: LintSection,
+}
+
+/// Response format for the lint operation.
+#[derive(Debug, Serialize)]
+pub struct LintResponse {
+    /// Whether no rule reported an error
+    pub success: bool,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Error message (if the IR could not be linted)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The configured rules, each with the severity it reports at
+pub struct Linter {
+    rules: Vec<(Box<dyn LintRule>, Severity)>,
+}
+
+impl Linter {
+    /// Build a linter from the `[lint]` section; rules it does not mention
+    /// report warnings. Unknown rule codes are an error, so typos do not
+    /// silently leave a rule at its default.
+    pub fn new(config: &LintSection) -> Result<Self> {
+        let rules = all_rules(
+            config
+                .max_nesting_depth
+                .unwrap_or(DEFAULT_MAX_NESTING_DEPTH),
+        );
+        for code in config.rules.keys() {
+            if !rules.iter().any(|rule| rule.code() == code) {
+                let available: Vec<&str> = rules.iter().map(|rule| rule.code()).collect();
+                bail!(
+                    "Unknown lint rule '{}'. Available rules: {}",
+                    code,
+                    available.join(", ")
+                );
+            }
+        }
+        let rules = rules
+            .into_iter()
+            .filter_map(|rule| {
+                let severity = match config.rules.get(rule.code()) {
+                    Some(LintLevel::Off) => return None,
+                    Some(LintLevel::Error) => Severity::Error,
+                    Some(LintLevel::Warning) | None => Severity::Warning,
+                };
+                Some((rule, severity))
+            })
+            .collect();
+        Ok(Self { rules })
+    }
+
+    /// Check every type and value definition of a distribution.
+    ///
+    /// Specs distributions carry no definitions, so they never produce
+    /// diagnostics.
+    pub fn lint(&self, distribution: &v4::Distribution) -> Vec<Diagnostic> {
+        let Some(package) = distribution.definition() else {
+            return Vec::new();
+        };
+        let package_name = &distribution.package_name().0;
+        let mut diagnostics = Vec::new();
+        for (module_key, module) in &package.modules {
+            let fqname = |name: &str| {
+                FQName::new(
+                    package_name.clone(),
+                    Path::new(module_key),
+                    Name::from(name),
+                )
+            };
+            for (rule, severity) in &self.rules {
+                let mut report = |name: &str, findings: Vec<Finding>| {
+                    diagnostics.extend(findings.into_iter().map(|finding| Diagnostic {
+                        severity: *severity,
+                        code: rule.code().to_string(),
+                        message: finding.message,
+                        definition: fqname(name),
+                        location: finding.location,
+                    }))
+                };
+                for (name, definition) in &module.value.types {
+                    report(name, rule.check_type(name, &definition.value));
+                }
+                for (name, definition) in &module.value.values {
+                    report(name, rule.check_value(name, &definition.value));
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+fn lint(request: LintRequest) -> Result<LintResponse> {
+    let distribution = if detect_ir_format(&request.ir) == "v4" {
+        let ir: v4::IRFile = serde_json::from_value(request.ir).context("Failed to parse V4 IR")?;
+        ir.distribution
+    } else {
+        let dist: classic::Distribution =
+            serde_json::from_value(request.ir).context("Failed to parse Classic IR")?;
+        converter::classic_to_v4(&dist).ir.distribution
+    };
+
+    let linter = match Linter::new(&request.options) {
+        Ok(linter) => linter,
+        Err(e) => {
+            return Ok(LintResponse {
+                success: false,
+                diagnostics: vec![],
+                error: Some(e.to_string()),
+            });
+        }
+    };
+    let diagnostics = linter.lint(&distribution);
+    Ok(LintResponse {
+        success: !diagnostics.iter().any(Diagnostic::is_error),
+        diagnostics,
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn var(name: &str) -> serde_json::Value {
+        json!({ "AsPattern": { "pattern": { "WildcardPattern": {} }, "name": name } })
+    }
+
+    /// One module with `type Box a = Box Int` and
+    /// `pick x = case x of y -> (\x -> x) | 0 -> 1`
+    fn distribution() -> v4::Distribution {
+        serde_json::from_value(json!({ "Library": {
+            "packageName": "acme",
+            "dependencies": {},
+            "def": { "modules": { "shapes": { "access": "Public", "value": {
+                "types": {
+                    "box": { "access": "Public", "value": { "CustomTypeDefinition": {
+                        "typeParams": ["a"],
+                        "constructors": { "access": "Public", "value": [
+                            { "name": "box", "args": [
+                                { "name": "value", "type": "morphir/sdk:basics#int" }
+                            ] }
+                        ] }
+                    } } }
+                },
+                "values": {
+                    "pick": { "access": "Public", "value": {
+                        "inputTypes": { "x": { "type": "morphir/sdk:basics#int" } },
+                        "outputType": "morphir/sdk:basics#int",
+                        "body": { "ExpressionBody": { "body": { "PatternMatch": {
+                            "subject": { "Variable": { "name": "x" } },
+                            "cases": [
+                                [var("y"), { "Lambda": {
+                                    "pattern": var("x"),
+                                    "body": { "Variable": { "name": "x" } }
+                                } }],
+                                [
+                                    { "LiteralPattern": { "literal": { "IntegerLiteral": { "value": 0 } } } },
+                                    { "Literal": { "literal": { "IntegerLiteral": { "value": 1 } } } }
+                                ]
+                            ]
+                        } } } }
+                    } }
+                }
+            } } } }
+        } }))
+        .unwrap()
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        let mut codes: Vec<&str> = diagnostics.iter().map(|d| d.code.as_str()).collect();
+        codes.sort();
+        codes
+    }
+
+    #[test]
+    fn test_default_rules_report_warnings() {
+        let diagnostics = Linter::new(&LintSection::default())
+            .unwrap()
+            .lint(&distribution());
+        assert_eq!(
+            codes(&diagnostics),
+            vec![
+                "shadowed-variable",
+                "unreachable-case",
+                "unused-type-variable"
+            ]
+        );
+        assert!(diagnostics.iter().all(|d| !d.is_error()));
+        let shadowed = diagnostics
+            .iter()
+            .find(|d| d.code == "shadowed-variable")
+            .unwrap();
+        assert_eq!(
+            shadowed.definition.to_canonical_string(),
+            "acme:shapes#pick"
+        );
+    }
+
+    #[test]
+    fn test_configured_levels_and_depth() {
+        let config: LintSection = serde_json::from_value(json!({
+            "max_nesting_depth": 0,
+            "rules": { "shadowed-variable": "error", "unreachable-case": "off" }
+        }))
+        .unwrap();
+        let diagnostics = Linter::new(&config).unwrap().lint(&distribution());
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["deep-nesting", "shadowed-variable", "unused-type-variable"]
+        );
+        assert!(
+            diagnostics
+                .iter()
+                .any(|d| d.code == "shadowed-variable" && d.is_error())
+        );
+
+        let config: LintSection =
+            serde_json::from_value(json!({ "rules": { "shadowing": "error" } })).unwrap();
+        let err = Linter::new(&config).err().unwrap();
+        assert!(err.to_string().starts_with("Unknown lint rule 'shadowing'"));
+    }
+
+    #[test]
+    fn test_naming_convention() {
+        let distribution = distribution();
+        let module = &distribution.definition().unwrap().modules["shapes"].value;
+        let finding = |name: &str| {
+            NamingConvention
+                .check_value(name, &module.values["pick"].value)
+                .into_iter()
+                .map(|f| f.message)
+                .next()
+        };
+        assert_eq!(finding("price-order"), None);
+        assert_eq!(
+            finding("priceOrder").unwrap(),
+            "`priceOrder` is not kebab-case; use `price-order`"
+        );
+        assert!(finding("2nd-price").is_some());
+    }
+}
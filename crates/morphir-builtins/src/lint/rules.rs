@@ -0,0 +1,501 @@
+//! Lint rules
+//!
+//! Each rule looks at one type or value definition at a time and reports
+//! what it finds as [`Finding`]s; the [`Linter`](super::Linter) turns them
+//! into diagnostics at the level configured for the rule.
+
+use morphir_core::ir::v4::{
+    Pattern, SourceLocation, Type, TypeDefinition, Value, ValueBody, ValueDefinition,
+};
+use morphir_core::naming::Name;
+use std::collections::HashSet;
+
+/// Deepest nesting of conditionals allowed unless configured otherwise
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 4;
+
+/// Something a rule found in a definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub message: String,
+    /// Location of the offending expression, if the IR records one
+    pub location: Option<SourceLocation>,
+}
+
+impl Finding {
+    fn new(message: String, location: Option<&SourceLocation>) -> Self {
+        Self {
+            message,
+            location: location.cloned(),
+        }
+    }
+}
+
+/// A check run over the definitions of a package
+pub trait LintRule: Send + Sync {
+    /// Code the rule reports under and is configured by, e.g.
+    /// `shadowed-variable`
+    fn code(&self) -> &'static str;
+
+    /// What the rule looks for
+    fn description(&self) -> &'static str;
+
+    /// Check the type definition stored under `name`
+    fn check_type(&self, _name: &str, _definition: &TypeDefinition) -> Vec<Finding> {
+        Vec::new()
+    }
+
+    /// Check the value definition stored under `name`
+    fn check_value(&self, _name: &str, _definition: &ValueDefinition) -> Vec<Finding> {
+        Vec::new()
+    }
+}
+
+/// Every rule, with conditionals allowed to nest `max_nesting_depth` deep
+pub fn all_rules(max_nesting_depth: usize) -> Vec<Box<dyn LintRule>> {
+    vec![
+        Box::new(UnusedTypeVariable),
+        Box::new(UnreachableCase),
+        Box::new(ShadowedVariable),
+        Box::new(DeepNesting {
+            max_depth: max_nesting_depth,
+        }),
+        Box::new(NamingConvention),
+    ]
+}
+
+/// Type parameters a type definition never refers to
+pub struct UnusedTypeVariable;
+
+impl LintRule for UnusedTypeVariable {
+    fn code(&self) -> &'static str {
+        "unused-type-variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "Type parameters that the definition of the type never uses"
+    }
+
+    fn check_type(&self, name: &str, definition: &TypeDefinition) -> Vec<Finding> {
+        let mut used = HashSet::new();
+        let (params, location) = match definition {
+            TypeDefinition::TypeAliasDefinition {
+                type_params,
+                type_expr,
+            } => {
+                type_variables(type_expr, &mut used);
+                (type_params, type_expr.attributes().source.as_ref())
+            }
+            TypeDefinition::CustomTypeDefinition {
+                type_params,
+                constructors,
+            } => {
+                for arg in constructors.value.iter().flat_map(|c| &c.args) {
+                    type_variables(&arg.arg_type, &mut used);
+                }
+                (type_params, None)
+            }
+            // Nothing is known about how an incomplete type uses its parameters
+            TypeDefinition::IncompleteTypeDefinition { .. } => return Vec::new(),
+        };
+        params
+            .iter()
+            .filter(|param| !used.contains(*param))
+            .map(|param| {
+                Finding::new(
+                    format!("Type variable `{}` of `{}` is never used", param, name),
+                    location,
+                )
+            })
+            .collect()
+    }
+}
+
+fn type_variables(tpe: &Type, out: &mut HashSet<Name>) {
+    match tpe {
+        Type::Variable(_, name) => {
+            out.insert(name.clone());
+        }
+        Type::Reference(_, _, args) | Type::Tuple(_, args) => {
+            for arg in args {
+                type_variables(arg, out);
+            }
+        }
+        Type::Record(_, fields) => {
+            for field in fields {
+                type_variables(&field.tpe, out);
+            }
+        }
+        Type::ExtensibleRecord(_, name, fields) => {
+            out.insert(name.clone());
+            for field in fields {
+                type_variables(&field.tpe, out);
+            }
+        }
+        Type::Function(_, argument, result) => {
+            type_variables(argument, out);
+            type_variables(result, out);
+        }
+        Type::Unit(_) => {}
+    }
+}
+
+/// Pattern-match cases that an earlier case already covers
+pub struct UnreachableCase;
+
+impl LintRule for UnreachableCase {
+    fn code(&self) -> &'static str {
+        "unreachable-case"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pattern-match cases that can never match because an earlier case covers them"
+    }
+
+    fn check_value(&self, _name: &str, definition: &ValueDefinition) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for_each_value(definition, &mut |value| {
+            let Value::PatternMatch(_, _, cases) = value else {
+                return;
+            };
+            for (index, case) in cases.iter().enumerate() {
+                if let Some(earlier) = cases[..index]
+                    .iter()
+                    .position(|earlier| covers(&earlier.0, &case.0))
+                {
+                    findings.push(Finding::new(
+                        format!(
+                            "Case {} can never match: case {} already covers it",
+                            index + 1,
+                            earlier + 1
+                        ),
+                        case.0
+                            .attributes()
+                            .source
+                            .as_ref()
+                            .or(case.1.attributes().source.as_ref()),
+                    ));
+                }
+            }
+        });
+        findings
+    }
+}
+
+/// Whether every value `pattern` matches is also matched by `earlier`
+fn covers(earlier: &Pattern, pattern: &Pattern) -> bool {
+    match (earlier, pattern) {
+        (Pattern::WildcardPattern(_), _) => true,
+        (Pattern::AsPattern(_, inner, _), _) => covers(inner, pattern),
+        (_, Pattern::AsPattern(_, inner, _)) => covers(earlier, inner),
+        (Pattern::TuplePattern(_, left), Pattern::TuplePattern(_, right)) => all_cover(left, right),
+        (Pattern::ConstructorPattern(_, a, left), Pattern::ConstructorPattern(_, b, right)) => {
+            a == b && all_cover(left, right)
+        }
+        (Pattern::HeadTailPattern(_, h1, t1), Pattern::HeadTailPattern(_, h2, t2)) => {
+            covers(h1, h2) && covers(t1, t2)
+        }
+        (Pattern::LiteralPattern(_, a), Pattern::LiteralPattern(_, b)) => a == b,
+        (Pattern::EmptyListPattern(_), Pattern::EmptyListPattern(_))
+        | (Pattern::UnitPattern(_), Pattern::UnitPattern(_)) => true,
+        _ => false,
+    }
+}
+
+fn all_cover(earlier: &[Pattern], patterns: &[Pattern]) -> bool {
+    earlier.len() == patterns.len() && earlier.iter().zip(patterns).all(|(e, p)| covers(e, p))
+}
+
+/// Bindings that hide a variable already in scope
+pub struct ShadowedVariable;
+
+impl LintRule for ShadowedVariable {
+    fn code(&self) -> &'static str {
+        "shadowed-variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "Variables bound with the name of a variable already in scope"
+    }
+
+    fn check_value(&self, _name: &str, definition: &ValueDefinition) -> Vec<Finding> {
+        let mut shadowing = Shadowing::default();
+        shadowing.definition(definition);
+        shadowing.findings
+    }
+}
+
+/// Walks a definition keeping track of the variables in scope
+#[derive(Default)]
+struct Shadowing {
+    scope: Vec<String>,
+    findings: Vec<Finding>,
+}
+
+impl Shadowing {
+    fn definition(&mut self, definition: &ValueDefinition) {
+        let mark = self.scope.len();
+        let location = body(definition).and_then(|b| b.attributes().source.as_ref());
+        for input in definition.input_types.keys() {
+            self.bind(&Name::from(input.as_str()), location);
+        }
+        if let Some(body) = body(definition) {
+            self.value(body);
+        }
+        self.scope.truncate(mark);
+    }
+
+    fn bind(&mut self, name: &Name, location: Option<&SourceLocation>) {
+        let name = name.to_kebab_case();
+        if self.scope.contains(&name) {
+            self.findings.push(Finding::new(
+                format!("`{}` shadows a variable of the same name", name),
+                location,
+            ));
+        }
+        self.scope.push(name);
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::AsPattern(attrs, inner, name) => {
+                self.pattern(inner);
+                self.bind(name, attrs.source.as_ref());
+            }
+            Pattern::TuplePattern(_, items) | Pattern::ConstructorPattern(_, _, items) => {
+                for item in items {
+                    self.pattern(item);
+                }
+            }
+            Pattern::HeadTailPattern(_, head, tail) => {
+                self.pattern(head);
+                self.pattern(tail);
+            }
+            Pattern::WildcardPattern(_)
+            | Pattern::EmptyListPattern(_)
+            | Pattern::LiteralPattern(_, _)
+            | Pattern::UnitPattern(_) => {}
+        }
+    }
+
+    fn value(&mut self, value: &Value) {
+        let mark = self.scope.len();
+        match value {
+            Value::Lambda(_, pattern, body) => {
+                self.pattern(pattern);
+                self.value(body);
+            }
+            Value::LetDefinition(attrs, name, definition, body) => {
+                self.definition(definition);
+                self.bind(name, attrs.source.as_ref());
+                self.value(body);
+            }
+            Value::LetRecursion(attrs, bindings, body) => {
+                for binding in bindings {
+                    self.bind(&binding.0, attrs.source.as_ref());
+                }
+                for binding in bindings {
+                    self.definition(&binding.1);
+                }
+                self.value(body);
+            }
+            Value::Destructure(_, pattern, bound, body) => {
+                self.value(bound);
+                self.pattern(pattern);
+                self.value(body);
+            }
+            Value::PatternMatch(_, subject, cases) => {
+                self.value(subject);
+                for case in cases {
+                    let mark = self.scope.len();
+                    self.pattern(&case.0);
+                    self.value(&case.1);
+                    self.scope.truncate(mark);
+                }
+            }
+            other => {
+                for child in children(other) {
+                    self.value(child);
+                }
+            }
+        }
+        self.scope.truncate(mark);
+    }
+}
+
+/// Conditionals nested deeper than a limit
+pub struct DeepNesting {
+    pub max_depth: usize,
+}
+
+impl LintRule for DeepNesting {
+    fn code(&self) -> &'static str {
+        "deep-nesting"
+    }
+
+    fn description(&self) -> &'static str {
+        "Value definitions nesting `if` and `case` expressions too deeply"
+    }
+
+    fn check_value(&self, name: &str, definition: &ValueDefinition) -> Vec<Finding> {
+        let mut deepest = (0, None);
+        if let Some(body) = body(definition) {
+            nesting(body, 0, &mut deepest);
+        }
+        let (depth, location) = deepest;
+        if depth <= self.max_depth {
+            return Vec::new();
+        }
+        vec![Finding::new(
+            format!(
+                "`{}` nests conditionals {} deep, more than the {} allowed",
+                name, depth, self.max_depth
+            ),
+            location,
+        )]
+    }
+}
+
+/// Record the deepest conditional below `value`, which sits inside `depth`
+/// conditionals. Conditions and subjects are evaluated before branching, so
+/// only the branches are nested one level further.
+fn nesting<'a>(value: &'a Value, depth: usize, deepest: &mut (usize, Option<&'a SourceLocation>)) {
+    let branches: Vec<&Value> = match value {
+        Value::IfThenElse(_, condition, then_branch, else_branch) => {
+            nesting(condition, depth, deepest);
+            vec![&**then_branch, &**else_branch]
+        }
+        Value::PatternMatch(_, subject, cases) => {
+            nesting(subject, depth, deepest);
+            cases.iter().map(|case| &case.1).collect()
+        }
+        other => {
+            for child in children(other) {
+                nesting(child, depth, deepest);
+            }
+            return;
+        }
+    };
+    if depth + 1 > deepest.0 {
+        *deepest = (depth + 1, value.attributes().source.as_ref());
+    }
+    for branch in branches {
+        nesting(branch, depth + 1, deepest);
+    }
+}
+
+/// Definition names that are not canonical kebab-case words
+pub struct NamingConvention;
+
+impl LintRule for NamingConvention {
+    fn code(&self) -> &'static str {
+        "naming-convention"
+    }
+
+    fn description(&self) -> &'static str {
+        "Type, constructor, and value names that are not kebab-case words of letters and digits"
+    }
+
+    fn check_type(&self, name: &str, definition: &TypeDefinition) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = naming_problem(name)
+            .map(|message| Finding::new(message, None))
+            .into_iter()
+            .collect();
+        if let TypeDefinition::CustomTypeDefinition { constructors, .. } = definition {
+            findings.extend(
+                constructors
+                    .value
+                    .iter()
+                    .filter_map(|c| naming_problem(&c.name.to_kebab_case()))
+                    .map(|message| Finding::new(message, None)),
+            );
+        }
+        findings
+    }
+
+    fn check_value(&self, name: &str, definition: &ValueDefinition) -> Vec<Finding> {
+        let location = body(definition).and_then(|b| b.attributes().source.as_ref());
+        naming_problem(name)
+            .map(|message| Finding::new(message, location))
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Why `name` breaks the naming convention, if it does
+fn naming_problem(name: &str) -> Option<String> {
+    let canonical = Name::from(name).to_kebab_case();
+    if canonical != name {
+        return Some(format!("`{}` is not kebab-case; use `{}`", name, canonical));
+    }
+    let conventional = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .split('-')
+            .all(|word| !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric()));
+    (!conventional).then(|| {
+        format!(
+            "`{}` should be made of words of letters and digits, starting with a letter",
+            name
+        )
+    })
+}
+
+fn body(definition: &ValueDefinition) -> Option<&Value> {
+    match &definition.body {
+        ValueBody::Expression(body) => Some(body),
+        _ => None,
+    }
+}
+
+/// Call `f` on every expression of `definition`, including the bodies of
+/// nested definitions
+fn for_each_value(definition: &ValueDefinition, f: &mut impl FnMut(&Value)) {
+    fn walk(value: &Value, f: &mut impl FnMut(&Value)) {
+        f(value);
+        for child in children(value) {
+            walk(child, f);
+        }
+    }
+    if let Some(body) = body(definition) {
+        walk(body, f);
+    }
+}
+
+/// The direct subexpressions of `value`, including the bodies of the
+/// definitions it introduces
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Tuple(_, items) | Value::List(_, items) => items.iter().collect(),
+        Value::Record(_, fields) => fields.iter().map(|field| &field.1).collect(),
+        Value::Field(_, record, _) => vec![&**record],
+        Value::Apply(_, function, argument) => vec![&**function, &**argument],
+        Value::Lambda(_, _, body) => vec![&**body],
+        Value::LetDefinition(_, _, definition, body) => self::body(definition)
+            .into_iter()
+            .chain([&**body])
+            .collect(),
+        Value::LetRecursion(_, bindings, body) => bindings
+            .iter()
+            .filter_map(|binding| self::body(&binding.1))
+            .chain([&**body])
+            .collect(),
+        Value::Destructure(_, _, bound, body) => vec![&**bound, &**body],
+        Value::IfThenElse(_, condition, then_branch, else_branch) => {
+            vec![&**condition, &**then_branch, &**else_branch]
+        }
+        Value::PatternMatch(_, subject, cases) => std::iter::once(&**subject)
+            .chain(cases.iter().map(|case| &case.1))
+            .collect(),
+        Value::UpdateRecord(_, record, fields) => std::iter::once(&**record)
+            .chain(fields.iter().map(|field| &field.1))
+            .collect(),
+        Value::Literal(..)
+        | Value::Constructor(..)
+        | Value::Variable(..)
+        | Value::Reference(..)
+        | Value::FieldFunction(..)
+        | Value::Unit(..)
+        | Value::Hole(..)
+        | Value::Native(..)
+        | Value::External(..) => Vec::new(),
+    }
+}
@@ -1,9 +1,13 @@
 //! Migrate builtin extension.
 //!
 //! Transforms Morphir IR between different versions (v3/classic ↔ v4).
+//! Decorations sent with the IR are returned with it unchanged.
 
-use crate::{BuiltinExtension, BuiltinInfo, ExtensionType};
+use crate::{BuiltinExtension, BuiltinInfo, ExtensionType, detect_ir_format};
 use anyhow::{Context, Result, bail};
+use morphir_core::converter;
+use morphir_core::ir::v4::serde_v4::omitting_empty_attributes;
+use morphir_core::ir::{classic, v4};
 use morphir_ext_core::Envelope;
 use serde::{Deserialize, Serialize};
 
@@ -53,6 +57,9 @@ pub struct MigrateRequest {
     /// Whether to use expanded (non-compact) format for V4
     #[serde(default)]
     pub expanded: bool,
+    /// Decorations of the input IR, to carry over to the output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decorations: Option<v4::Decorations>,
 }
 
 /// Response format for migrate operation.
@@ -67,6 +74,9 @@ pub struct MigrateResponse {
     pub source_format: String,
     /// Target format produced
     pub target_format: String,
+    /// Decorations of the migrated IR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decorations: Option<v4::Decorations>,
     /// Warnings during migration
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub warnings: Vec<String>,
@@ -75,25 +85,6 @@ pub struct MigrateResponse {
     pub error: Option<String>,
 }
 
-/// Detect if IR is in V4 format by checking for V4-specific markers.
-fn detect_ir_format(ir: &serde_json::Value) -> &'static str {
-    // V4 format has formatVersion >= 4 or formatVersion starting with "4"
-    if let Some(fv) = ir.get("formatVersion") {
-        if let Some(n) = fv.as_i64()
-            && n >= 4
-        {
-            return "v4";
-        }
-        if let Some(s) = fv.as_str()
-            && s.starts_with('4')
-        {
-            return "v4";
-        }
-    }
-    // Default to classic format
-    "classic"
-}
-
 /// Perform the actual migration logic.
 fn perform_migration(request: MigrateRequest) -> Result<MigrateResponse> {
     // Detect input format
@@ -114,31 +105,57 @@ fn perform_migration(request: MigrateRequest) -> Result<MigrateResponse> {
 
     let target_format = if target_v4 { "v4" } else { "classic" };
 
-    // Check if conversion is needed
-    if is_source_v4 != target_v4 {
-        // Cross-format conversion not yet implemented
+    // Same format → Just return input
+    if is_source_v4 == target_v4 {
         return Ok(MigrateResponse {
-            success: false,
-            ir: None,
+            success: true,
+            ir: Some(request.ir),
             source_format: source_format.to_string(),
             target_format: target_format.to_string(),
+            decorations: request.decorations,
             warnings: vec![],
-            error: Some(format!(
-                "{} -> {} conversion is not yet implemented. \
-                 The converter module is currently being updated.",
-                source_format, target_format
-            )),
+            error: None,
         });
     }
 
-    // Same format → Just return input
-    Ok(MigrateResponse {
-        success: true,
-        ir: Some(request.ir),
-        source_format: source_format.to_string(),
-        target_format: target_format.to_string(),
-        warnings: vec![],
-        error: None,
+    let decorations = request.decorations;
+    let converted = if is_source_v4 {
+        let ir: v4::IRFile = serde_json::from_value(request.ir).context("Failed to parse V4 IR")?;
+        converter::v4_to_classic(&ir)
+            .and_then(|conversion| Ok((serde_json::to_value(&conversion.ir)?, conversion.warnings)))
+    } else {
+        let dist: classic::Distribution =
+            serde_json::from_value(request.ir).context("Failed to parse Classic IR")?;
+        let conversion = converter::classic_to_v4(&dist);
+        let write = || serde_json::to_value(&conversion.ir);
+        // Empty attributes are left out unless expanded output is requested
+        let ir = if request.expanded {
+            write()
+        } else {
+            omitting_empty_attributes(write)
+        };
+        ir.map(|ir| (ir, conversion.warnings)).map_err(Into::into)
+    };
+
+    Ok(match converted {
+        Ok((ir, warnings)) => MigrateResponse {
+            success: true,
+            ir: Some(ir),
+            source_format: source_format.to_string(),
+            target_format: target_format.to_string(),
+            decorations,
+            warnings,
+            error: None,
+        },
+        Err(e) => MigrateResponse {
+            success: false,
+            ir: None,
+            source_format: source_format.to_string(),
+            target_format: target_format.to_string(),
+            decorations: None,
+            warnings: vec![],
+            error: Some(e.to_string()),
+        },
     })
 }
 
@@ -166,6 +183,7 @@ mod tests {
             }),
             target_version: "classic".to_string(),
             expanded: false,
+            decorations: None,
         };
 
         let input = Envelope::json(&request).unwrap();
@@ -176,4 +194,58 @@ mod tests {
         assert_eq!(response.source_format, "classic");
         assert_eq!(response.target_format, "classic");
     }
+
+    #[test]
+    fn test_migrate_classic_to_v4_and_back() {
+        let migrate = MigrateExtension;
+        let classic = serde_json::json!({
+            "formatVersion": 3,
+            "distribution": ["Library", [["test"]], [], {"modules": [
+                [[["orders"]], {"access": "Public", "value": {"types": [], "values": [], "doc": null}}]
+            ]}]
+        });
+
+        let mut decorations = v4::Decorations::default();
+        decorations.attach(
+            "owner",
+            &v4::DecorationTarget::Module(
+                morphir_core::naming::Path::new("test"),
+                morphir_core::naming::Path::new("orders"),
+            ),
+            serde_json::json!("sales"),
+        );
+        let request = MigrateRequest {
+            ir: classic.clone(),
+            target_version: "v4".to_string(),
+            expanded: false,
+            decorations: Some(decorations.clone()),
+        };
+        let output = migrate
+            .execute_native(&Envelope::json(&request).unwrap())
+            .unwrap();
+        let response: MigrateResponse = output.as_json().unwrap();
+        assert!(response.success, "{:?}", response.error);
+        assert_eq!(response.source_format, "classic");
+        assert_eq!(response.decorations, Some(decorations));
+        let v4_ir = response.ir.unwrap();
+        assert!(
+            v4_ir
+                .pointer("/distribution/Library/def/modules/orders")
+                .is_some()
+        );
+
+        let request = MigrateRequest {
+            ir: v4_ir,
+            target_version: "classic".to_string(),
+            expanded: false,
+            decorations: None,
+        };
+        let output = migrate
+            .execute_native(&Envelope::json(&request).unwrap())
+            .unwrap();
+        let response: MigrateResponse = output.as_json().unwrap();
+        assert!(response.success, "{:?}", response.error);
+        let back: classic::Distribution = serde_json::from_value(response.ir.unwrap()).unwrap();
+        assert_eq!(back, serde_json::from_value(classic).unwrap());
+    }
 }
@@ -0,0 +1,201 @@
+//! Protocol Buffers builtin extension.
+//!
+//! Generates `.proto` files for the domain types of a distribution, and for
+//! applications a gRPC service with one RPC per entry point, so modeled
+//! domains can be shared with gRPC services.
+
+use crate::{BuiltinExtension, BuiltinInfo, ExtensionType, detect_ir_format};
+use anyhow::{Context, Result};
+use morphir_core::converter;
+use morphir_core::ir::{classic, v4};
+use morphir_ext_core::Envelope;
+use serde::{Deserialize, Serialize};
+
+mod proto;
+
+pub use proto::{ProtoFile, ProtoOutput, generate_protos};
+
+/// Protocol Buffers backend for domain types.
+#[derive(Default)]
+pub struct ProtobufExtension;
+
+impl BuiltinExtension for ProtobufExtension {
+    fn execute_native(&self, input: &Envelope) -> Result<Envelope> {
+        let request: ProtobufRequest = input
+            .as_json()
+            .context("Failed to parse protobuf request")?;
+
+        let result = generate(request)?;
+
+        Envelope::json(&result).context("Failed to create response envelope")
+    }
+
+    fn info(&self) -> BuiltinInfo {
+        BuiltinInfo {
+            id: "protobuf".to_string(),
+            name: "Protocol Buffers".to_string(),
+            extension_type: ExtensionType::Backend,
+            description: "Generate .proto files and gRPC services for a distribution".to_string(),
+        }
+    }
+}
+
+/// Options for the generated files, read from `[codegen.protobuf]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtobufOptions {
+    /// Declare a service for the entry points of an application
+    #[serde(default = "default_true")]
+    pub services: bool,
+    /// Proto package to use instead of the Morphir package name
+    #[serde(default)]
+    pub package: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ProtobufOptions {
+    fn default() -> Self {
+        Self {
+            services: true,
+            package: None,
+        }
+    }
+}
+
+/// Request format for the generate operation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProtobufRequest {
+    /// Input IR (either Classic or V4 format)
+    pub ir: serde_json::Value,
+    #[serde(default)]
+    pub options: ProtobufOptions,
+}
+
+/// Response format for the generate operation, as returned by backend
+/// extensions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProtobufResponse {
+    /// Whether generation succeeded
+    pub success: bool,
+    /// Generated `.proto` files
+    #[serde(default)]
+    pub artifacts: Vec<ProtoArtifact>,
+    /// Warnings about types that were left out or could only be described
+    /// loosely
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<ProtoDiagnostic>,
+    /// Error message (if failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A generated file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoArtifact {
+    pub path: String,
+    pub content: String,
+}
+
+/// A diagnostic about the generated files, naming a type, field or RPC that
+/// Protocol Buffers cannot express and that was left out or widened to any
+/// value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoDiagnostic {
+    /// `warning`: the rest of the package was still generated. IR that cannot
+    /// be read at all fails the request with an `error` instead
+    pub severity: String,
+    pub message: String,
+}
+
+fn generate(request: ProtobufRequest) -> Result<ProtobufResponse> {
+    let distribution = if detect_ir_format(&request.ir) == "v4" {
+        let ir: v4::IRFile = serde_json::from_value(request.ir).context("Failed to parse V4 IR")?;
+        ir.distribution
+    } else {
+        let dist: classic::Distribution =
+            serde_json::from_value(request.ir).context("Failed to parse Classic IR")?;
+        converter::classic_to_v4(&dist).ir.distribution
+    };
+
+    let Some(package) = distribution.definition() else {
+        return Ok(ProtobufResponse {
+            success: false,
+            artifacts: vec![],
+            diagnostics: vec![],
+            error: Some("Specs distributions carry no type definitions".to_string()),
+        });
+    };
+    let output = generate_protos(
+        &distribution.package_name().to_string(),
+        package,
+        distribution.entry_points(),
+        &request.options,
+    );
+
+    Ok(ProtobufResponse {
+        success: true,
+        artifacts: output
+            .files
+            .into_iter()
+            .map(|file| ProtoArtifact {
+                path: file.path,
+                content: file.content,
+            })
+            .collect(),
+        diagnostics: output
+            .warnings
+            .into_iter()
+            .map(|message| ProtoDiagnostic {
+                severity: "warning".to_string(),
+                message,
+            })
+            .collect(),
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protobuf_info() {
+        let info = ProtobufExtension.info();
+        assert_eq!(info.id, "protobuf");
+        assert_eq!(info.extension_type, ExtensionType::Backend);
+    }
+
+    #[test]
+    fn test_generate_from_classic_ir() {
+        // type Color = Red | Green, in module Paint of package Acme
+        let request = serde_json::json!({
+            "ir": {
+                "formatVersion": 3,
+                "distribution": ["Library", [["acme"]], [], {"modules": [
+                    [[["paint"]], {"access": "Public", "value": {
+                        "types": [[["color"], {"access": "Public", "value": {"doc": "", "value":
+                            ["CustomTypeDefinition", [], {"access": "Public", "value": [
+                                [["red"], []],
+                                [["green"], []]
+                            ]}]
+                        }}]],
+                        "values": [],
+                        "doc": null
+                    }}]
+                ]}]
+            }
+        });
+        let output = ProtobufExtension
+            .execute_native(&Envelope::json(&request).unwrap())
+            .unwrap();
+        let response: ProtobufResponse = output.as_json().unwrap();
+        assert!(response.success, "{:?}", response.error);
+        assert_eq!(response.artifacts.len(), 1);
+        assert_eq!(response.artifacts[0].path, "acme/paint.proto");
+        assert!(response.artifacts[0].content.contains(
+            "enum Color {\n  COLOR_UNSPECIFIED = 0;\n  COLOR_RED = 1;\n  COLOR_GREEN = 2;\n}"
+        ));
+    }
+}
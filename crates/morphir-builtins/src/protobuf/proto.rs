@@ -0,0 +1,957 @@
+//! `.proto` generation for the types of a V4 distribution.
+//!
+//! Each module becomes a file whose package is the Morphir package and module
+//! path in snake_case, e.g. `acme/shop:orders` becomes `acme.shop.orders` in
+//! `acme/shop/orders.proto`. Records become messages, custom types whose
+//! constructors take no arguments become enums, and other custom types become
+//! a message with one nested message per constructor under a `oneof`. Aliases
+//! of anything but a record are expanded where they are used, since Protocol
+//! Buffers has no aliases.
+//!
+//! Field numbers follow declaration order, so reordering the fields of a
+//! record changes the wire format.
+
+use indexmap::IndexMap;
+use morphir_core::ir::v4::{self, EntryPoints, Type, TypeDefinition, ValueDefinition};
+use morphir_core::naming::{FQName, Name, Path};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::ProtobufOptions;
+
+const VALUE: &str = "google.protobuf.Value";
+const EMPTY: &str = "google.protobuf.Empty";
+
+/// A generated `.proto` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoFile {
+    /// Path of the file, following its package
+    pub path: String,
+    pub content: String,
+}
+
+/// Result of generating `.proto` files for a package
+#[derive(Debug, Clone, Default)]
+pub struct ProtoOutput {
+    pub files: Vec<ProtoFile>,
+    /// Types and fields that could only be described loosely or were left out
+    pub warnings: Vec<String>,
+}
+
+/// Generate `.proto` files for the types of a package definition.
+///
+/// With `entry_points`, and unless [`ProtobufOptions::services`] is off, a
+/// file for the package itself declares a service with one RPC per entry
+/// point, taking the target function's arguments and returning its result.
+pub fn generate_protos(
+    package_name: &str,
+    package: &v4::PackageDefinition,
+    entry_points: Option<&EntryPoints>,
+    options: &ProtobufOptions,
+) -> ProtoOutput {
+    let mut generator = Generator::new(package_name, package, options);
+    let mut files = Vec::new();
+
+    let modules: Vec<Path> = package.modules.keys().map(|m| Path::new(m)).collect();
+    for module in &modules {
+        let proto_package = generator.module_package(module);
+        generator.start_file(&proto_package);
+        let keys: Vec<(String, String)> = generator
+            .types
+            .keys()
+            .filter(|(m, _)| *m == module.to_string())
+            .cloned()
+            .collect();
+        let mut declarations = Vec::new();
+        for key in keys {
+            if let Some(declaration) = generator.declaration(&key) {
+                declarations.push(declaration);
+            }
+        }
+        if !declarations.is_empty() {
+            files.push(ProtoFile {
+                path: generator.file_path(&proto_package),
+                content: generator.render_file(&declarations),
+            });
+        }
+    }
+
+    if options.services
+        && let Some(entry_points) = entry_points.filter(|e| !e.is_empty())
+    {
+        let proto_package = generator.base.clone();
+        generator.start_file(&proto_package);
+        let declarations = generator.service(package_name, package, entry_points);
+        files.push(ProtoFile {
+            path: generator.file_path(&proto_package),
+            content: generator.render_file(&declarations),
+        });
+    }
+
+    ProtoOutput {
+        files,
+        warnings: generator.warnings,
+    }
+}
+
+/// A type definition of the package being generated
+struct LocalType<'a> {
+    module: Path,
+    name: Name,
+    doc: Option<&'a str>,
+    definition: &'a TypeDefinition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Label {
+    Singular,
+    Optional,
+    Repeated,
+}
+
+struct Field {
+    label: Label,
+    tpe: String,
+    name: String,
+    number: usize,
+}
+
+#[derive(Default)]
+struct Message {
+    name: String,
+    doc: Option<String>,
+    nested: Vec<Declaration>,
+    fields: Vec<Field>,
+    /// Fields of which at most one is set
+    oneof: Vec<Field>,
+}
+
+enum Declaration {
+    Message(Message),
+    Enum {
+        name: String,
+        doc: Option<String>,
+        values: Vec<String>,
+    },
+    Service {
+        name: String,
+        rpcs: Vec<(String, Option<String>)>,
+    },
+}
+
+struct Generator<'a> {
+    package: Path,
+    /// Proto package of the Morphir package, e.g. `acme.shop`
+    base: String,
+    /// Type definitions by module path and kebab-case type name
+    types: IndexMap<(String, String), LocalType<'a>>,
+    /// Proto package of the file being generated
+    current: String,
+    imports: BTreeSet<String>,
+    /// Aliases being expanded, innermost last
+    expanding: Vec<(String, String)>,
+    /// Type or entry point being generated, for warnings
+    context: String,
+    warnings: Vec<String>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(
+        package_name: &str,
+        package: &'a v4::PackageDefinition,
+        options: &ProtobufOptions,
+    ) -> Self {
+        let package_path = Path::new(package_name);
+        let base = options.package.clone().unwrap_or_else(|| {
+            package_path
+                .segments
+                .iter()
+                .map(Name::to_snake_case)
+                .collect::<Vec<_>>()
+                .join(".")
+        });
+        let mut types = IndexMap::new();
+        for (module_name, module) in &package.modules {
+            let module_path = Path::new(module_name);
+            for (type_name, def) in &module.value.types {
+                let name = Name::from(type_name.as_str());
+                types.insert(
+                    (module_path.to_string(), name.to_kebab_case()),
+                    LocalType {
+                        module: module_path.clone(),
+                        name,
+                        doc: def.doc.as_deref().filter(|doc| !doc.trim().is_empty()),
+                        definition: &def.value,
+                    },
+                );
+            }
+        }
+        Generator {
+            package: package_path,
+            base,
+            types,
+            current: String::new(),
+            imports: BTreeSet::new(),
+            expanding: Vec::new(),
+            context: String::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn module_package(&self, module: &Path) -> String {
+        std::iter::once(self.base.clone())
+            .chain(module.segments.iter().map(Name::to_snake_case))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    fn file_path(&self, proto_package: &str) -> String {
+        format!("{}.proto", proto_package.replace('.', "/"))
+    }
+
+    fn start_file(&mut self, proto_package: &str) {
+        self.current = proto_package.to_string();
+        self.imports.clear();
+    }
+
+    fn warn(&mut self, message: String) {
+        let message = if self.context.is_empty() {
+            message
+        } else {
+            format!("{}: {}", self.context, message)
+        };
+        if !self.warnings.contains(&message) {
+            self.warnings.push(message);
+        }
+    }
+
+    /// Top-level declaration for a type, if it gets one
+    fn declaration(&mut self, key: &(String, String)) -> Option<Declaration> {
+        let local = &self.types[key];
+        let (name, doc, definition) = (
+            local.name.to_title_case(),
+            local.doc.map(|d| d.trim().to_string()),
+            local.definition,
+        );
+        self.context = format!("{}.{}", local.module, name);
+        match definition {
+            TypeDefinition::TypeAliasDefinition {
+                type_params,
+                type_expr: Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields),
+            } if type_params.is_empty() => {
+                let mut message = Message {
+                    name,
+                    doc,
+                    ..Message::default()
+                };
+                for (i, field) in fields.iter().enumerate() {
+                    let field_name = field.name.to_snake_case();
+                    if let Some(field) = self.field(&field.tpe, field_name, i + 1, &mut message) {
+                        message.fields.push(field);
+                    }
+                }
+                Some(Declaration::Message(message))
+            }
+            // Other aliases are expanded where they are used
+            TypeDefinition::TypeAliasDefinition { type_params, .. } if type_params.is_empty() => {
+                None
+            }
+            TypeDefinition::CustomTypeDefinition {
+                type_params,
+                constructors,
+            } if type_params.is_empty() => {
+                let constructors = &constructors.value;
+                if constructors.iter().all(|c| c.args.is_empty()) {
+                    let prefix = Name::from(name.as_str()).to_snake_case().to_uppercase();
+                    let values = std::iter::once(format!("{}_UNSPECIFIED", prefix))
+                        .chain(constructors.iter().map(|c| {
+                            format!("{}_{}", prefix, c.name.to_snake_case().to_uppercase())
+                        }))
+                        .collect();
+                    return Some(Declaration::Enum { name, doc, values });
+                }
+                let mut message = Message {
+                    name,
+                    doc,
+                    ..Message::default()
+                };
+                for (i, constructor) in constructors.iter().enumerate() {
+                    let mut variant = Message {
+                        name: constructor.name.to_title_case(),
+                        ..Message::default()
+                    };
+                    for (j, arg) in constructor.args.iter().enumerate() {
+                        let arg_name = arg.name.to_snake_case();
+                        if let Some(field) =
+                            self.field(&arg.arg_type, arg_name, j + 1, &mut variant)
+                        {
+                            variant.fields.push(field);
+                        }
+                    }
+                    message.oneof.push(Field {
+                        label: Label::Singular,
+                        tpe: variant.name.clone(),
+                        name: constructor.name.to_snake_case(),
+                        number: i + 1,
+                    });
+                    message.nested.push(Declaration::Message(variant));
+                }
+                Some(Declaration::Message(message))
+            }
+            TypeDefinition::IncompleteTypeDefinition { .. } => {
+                self.warn("incomplete type definition, left out".to_string());
+                None
+            }
+            _ => {
+                self.warn(
+                    "generic types have no Protocol Buffers equivalent, left out".to_string(),
+                );
+                None
+            }
+        }
+    }
+
+    /// Service for the entry points, with a request and response message for
+    /// each RPC
+    fn service(
+        &mut self,
+        package_name: &str,
+        package: &v4::PackageDefinition,
+        entry_points: &EntryPoints,
+    ) -> Vec<Declaration> {
+        let service_name = format!(
+            "{}Service",
+            self.package
+                .segments
+                .last()
+                .map(Name::to_title_case)
+                .unwrap_or_default()
+        );
+        let mut rpcs = Vec::new();
+        let mut messages = Vec::new();
+        for (entry_name, entry_point) in entry_points {
+            self.context = format!("entry point {}", entry_name);
+            let Some(definition) = entry_point
+                .fqname()
+                .ok()
+                .and_then(|target| find_value(package_name, package, &target))
+            else {
+                self.warn(format!(
+                    "target `{}` is not a value defined in this package, left out",
+                    entry_point.target
+                ));
+                continue;
+            };
+            let rpc = Name::from(entry_name.as_str()).to_title_case();
+
+            let mut request = Message {
+                name: format!("{}Request", rpc),
+                ..Message::default()
+            };
+            for (i, (arg, input)) in definition.input_types.iter().enumerate() {
+                let arg_name = Name::from(arg.as_str()).to_snake_case();
+                if let Some(field) = self.field(&input.input_type, arg_name, i + 1, &mut request) {
+                    request.fields.push(field);
+                }
+            }
+            let mut response = Message {
+                name: format!("{}Response", rpc),
+                ..Message::default()
+            };
+            if let Some(field) = self.field(
+                &definition.output_type,
+                "result".to_string(),
+                1,
+                &mut response,
+            ) {
+                response.fields.push(field);
+            }
+
+            let doc = entry_point
+                .doc
+                .as_deref()
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(str::to_string);
+            rpcs.push((rpc, doc));
+            messages.push(Declaration::Message(request));
+            messages.push(Declaration::Message(response));
+        }
+        std::iter::once(Declaration::Service {
+            name: service_name,
+            rpcs,
+        })
+        .chain(messages)
+        .collect()
+    }
+
+    /// Field of `message` holding a value of `tpe`, or `None` if the type has
+    /// no encoding
+    fn field(
+        &mut self,
+        tpe: &Type,
+        name: String,
+        number: usize,
+        message: &mut Message,
+    ) -> Option<Field> {
+        let (label, tpe) = self.field_type(tpe, &name, message)?;
+        Some(Field {
+            label,
+            tpe,
+            name,
+            number,
+        })
+    }
+
+    fn field_type(
+        &mut self,
+        tpe: &Type,
+        field: &str,
+        message: &mut Message,
+    ) -> Option<(Label, String)> {
+        match tpe {
+            Type::Variable(_, name) => {
+                self.warn(format!(
+                    "type variable `{}` has no Protocol Buffers equivalent, any value accepted",
+                    name.to_camel_case()
+                ));
+                Some((Label::Singular, self.well_known(VALUE)))
+            }
+            Type::Reference(_, fqname, args) => {
+                if fqname.package_path.is_sdk() {
+                    return self.sdk_type(
+                        &fqname.module_path.to_string(),
+                        &fqname.local_name.to_kebab_case(),
+                        args,
+                        field,
+                        message,
+                    );
+                }
+                self.reference(fqname, field, message)
+            }
+            Type::Tuple(_, elements) => {
+                let mut tuple = Message {
+                    name: nested_name(message, field, ""),
+                    ..Message::default()
+                };
+                for (i, element) in elements.iter().enumerate() {
+                    if let Some(f) =
+                        self.field(element, format!("item{}", i + 1), i + 1, &mut tuple)
+                    {
+                        tuple.fields.push(f);
+                    }
+                }
+                Some((Label::Singular, push_nested(message, tuple)))
+            }
+            Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+                let mut record = Message {
+                    name: nested_name(message, field, ""),
+                    ..Message::default()
+                };
+                for (i, f) in fields.iter().enumerate() {
+                    if let Some(f) = self.field(&f.tpe, f.name.to_snake_case(), i + 1, &mut record)
+                    {
+                        record.fields.push(f);
+                    }
+                }
+                Some((Label::Singular, push_nested(message, record)))
+            }
+            Type::Function(..) => {
+                self.warn(format!(
+                    "field `{}` is a function, which has no encoding; left out",
+                    field
+                ));
+                None
+            }
+            Type::Unit(_) => Some((Label::Singular, self.well_known(EMPTY))),
+        }
+    }
+
+    fn reference(
+        &mut self,
+        fqname: &FQName,
+        field: &str,
+        message: &mut Message,
+    ) -> Option<(Label, String)> {
+        let key = (
+            fqname.module_path.to_string(),
+            fqname.local_name.to_kebab_case(),
+        );
+        let local = self
+            .types
+            .get(&key)
+            .filter(|_| fqname.package_path == self.package);
+        let Some(local) = local else {
+            self.warn(format!(
+                "type `{}` is not defined in this package, any value accepted",
+                fqname.to_canonical_string()
+            ));
+            return Some((Label::Singular, self.well_known(VALUE)));
+        };
+        let (module, name, definition) = (
+            local.module.clone(),
+            local.name.to_title_case(),
+            local.definition,
+        );
+        match definition {
+            TypeDefinition::TypeAliasDefinition {
+                type_params,
+                type_expr,
+            } if type_params.is_empty()
+                && !matches!(type_expr, Type::Record(..) | Type::ExtensibleRecord(..)) =>
+            {
+                if self.expanding.contains(&key) {
+                    self.warn(format!(
+                        "alias `{}` refers to itself, any value accepted",
+                        fqname.to_canonical_string()
+                    ));
+                    return Some((Label::Singular, self.well_known(VALUE)));
+                }
+                self.expanding.push(key);
+                let expanded = self.field_type(type_expr, field, message);
+                self.expanding.pop();
+                expanded
+            }
+            TypeDefinition::TypeAliasDefinition { type_params, .. }
+            | TypeDefinition::CustomTypeDefinition { type_params, .. }
+                if type_params.is_empty() =>
+            {
+                let proto_package = self.module_package(&module);
+                if proto_package == self.current {
+                    return Some((Label::Singular, name));
+                }
+                self.imports.insert(self.file_path(&proto_package));
+                Some((Label::Singular, format!(".{}.{}", proto_package, name)))
+            }
+            _ => {
+                self.warn(format!(
+                    "type `{}` is generic or incomplete, any value accepted",
+                    fqname.to_canonical_string()
+                ));
+                Some((Label::Singular, self.well_known(VALUE)))
+            }
+        }
+    }
+
+    fn sdk_type(
+        &mut self,
+        module: &str,
+        local: &str,
+        args: &[Type],
+        field: &str,
+        message: &mut Message,
+    ) -> Option<(Label, String)> {
+        let scalar = |t: &str| Some((Label::Singular, t.to_string()));
+        match (module, local) {
+            ("basics", "int") => scalar("int64"),
+            ("basics", "float") => scalar("double"),
+            ("basics", "bool") => scalar("bool"),
+            ("string", "string") | ("char", "char") => scalar("string"),
+            // Decimals keep their precision as strings, as in the runtime's JSON
+            ("decimal", "decimal") => scalar("string"),
+            ("list", "list") => {
+                let element = self.argument(args, 0, field, message)?;
+                let element = self.singular(element, field, "Item", message);
+                Some((Label::Repeated, element))
+            }
+            ("maybe", "maybe") => {
+                let (label, tpe) = self.argument(args, 0, field, message)?;
+                // An empty repeated field already stands for a missing list
+                match label {
+                    Label::Repeated => Some((label, tpe)),
+                    Label::Singular if !tpe.starts_with("map<") => Some((Label::Optional, tpe)),
+                    _ => Some((
+                        Label::Optional,
+                        self.singular((label, tpe), field, "Value", message),
+                    )),
+                }
+            }
+            ("dict", "dict") => {
+                let key = self.argument(args, 0, field, message)?;
+                let value = self.argument(args, 1, field, message)?;
+                let map_key = matches!(key.1.as_str(), "string" | "int64" | "bool");
+                if key.0 == Label::Singular && map_key {
+                    let value = self.singular(value, field, "Value", message);
+                    return Some((Label::Singular, format!("map<{}, {}>", key.1, value)));
+                }
+                let mut entry = Message {
+                    name: nested_name(message, field, "Entry"),
+                    ..Message::default()
+                };
+                entry.fields.push(labeled(key, "key", 1));
+                entry.fields.push(labeled(value, "value", 2));
+                Some((Label::Repeated, push_nested(message, entry)))
+            }
+            ("result", "result") => {
+                let error = self.argument(args, 0, field, message)?;
+                let value = self.argument(args, 1, field, message)?;
+                let mut result = Message {
+                    name: nested_name(message, field, "Result"),
+                    ..Message::default()
+                };
+                let value = self.singular(value, "ok", "Value", &mut result);
+                let error = self.singular(error, "err", "Value", &mut result);
+                result
+                    .oneof
+                    .push(labeled((Label::Singular, value), "ok", 1));
+                result
+                    .oneof
+                    .push(labeled((Label::Singular, error), "err", 2));
+                Some((Label::Singular, push_nested(message, result)))
+            }
+            _ => {
+                self.warn(format!(
+                    "no Protocol Buffers type known for `morphir/sdk:{}#{}`, any value accepted",
+                    module, local
+                ));
+                Some((Label::Singular, self.well_known(VALUE)))
+            }
+        }
+    }
+
+    /// Field type of a type argument; a missing argument accepts any value
+    fn argument(
+        &mut self,
+        args: &[Type],
+        i: usize,
+        field: &str,
+        message: &mut Message,
+    ) -> Option<(Label, String)> {
+        match args.get(i) {
+            Some(arg) => self.field_type(arg, field, message),
+            None => Some((Label::Singular, self.well_known(VALUE))),
+        }
+    }
+
+    /// Type usable where only a singular field may go, such as a list
+    /// element or map value: labeled types and maps are wrapped in a message
+    fn singular(
+        &mut self,
+        (label, tpe): (Label, String),
+        field: &str,
+        suffix: &str,
+        message: &mut Message,
+    ) -> String {
+        if label == Label::Singular && !tpe.starts_with("map<") {
+            return tpe;
+        }
+        let mut wrapper = Message {
+            name: nested_name(message, field, suffix),
+            ..Message::default()
+        };
+        wrapper.fields.push(labeled((label, tpe), "value", 1));
+        push_nested(message, wrapper)
+    }
+
+    /// Name of a well-known type, importing its file
+    fn well_known(&mut self, tpe: &str) -> String {
+        let file = match tpe {
+            EMPTY => "google/protobuf/empty.proto",
+            _ => "google/protobuf/struct.proto",
+        };
+        self.imports.insert(file.to_string());
+        tpe.to_string()
+    }
+
+    fn render_file(&self, declarations: &[Declaration]) -> String {
+        let mut out = String::new();
+        out.push_str("// Generated by morphir. Do not edit.\n\n");
+        out.push_str("syntax = \"proto3\";\n\n");
+        let _ = writeln!(out, "package {};", self.current);
+        if !self.imports.is_empty() {
+            out.push('\n');
+            for import in &self.imports {
+                let _ = writeln!(out, "import \"{}\";", import);
+            }
+        }
+        for declaration in declarations {
+            out.push('\n');
+            render(declaration, 0, &mut out);
+        }
+        out
+    }
+}
+
+fn labeled((label, tpe): (Label, String), name: &str, number: usize) -> Field {
+    Field {
+        label,
+        tpe,
+        name: name.to_string(),
+        number,
+    }
+}
+
+/// Name for a message nested in `message` on behalf of `field`, unique among
+/// its siblings
+fn nested_name(message: &Message, field: &str, suffix: &str) -> String {
+    let base = format!("{}{}", Name::from(field).to_title_case(), suffix);
+    let taken = |name: &str| {
+        message.nested.iter().any(|d| match d {
+            Declaration::Message(m) => m.name == name,
+            Declaration::Enum { name: n, .. } | Declaration::Service { name: n, .. } => n == name,
+        })
+    };
+    let mut name = base.clone();
+    let mut n = 2;
+    while taken(&name) {
+        name = format!("{}{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+fn push_nested(message: &mut Message, nested: Message) -> String {
+    let name = nested.name.clone();
+    message.nested.push(Declaration::Message(nested));
+    name
+}
+
+fn render_doc(doc: &Option<String>, indent: &str, out: &mut String) {
+    if let Some(doc) = doc {
+        for line in doc.lines() {
+            let _ = writeln!(out, "{}// {}", indent, line.trim_end());
+        }
+    }
+}
+
+fn render_field(field: &Field, indent: &str, out: &mut String) {
+    let label = match field.label {
+        Label::Singular => "",
+        Label::Optional => "optional ",
+        Label::Repeated => "repeated ",
+    };
+    let _ = writeln!(
+        out,
+        "{}{}{} {} = {};",
+        indent, label, field.tpe, field.name, field.number
+    );
+}
+
+fn render(declaration: &Declaration, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    match declaration {
+        Declaration::Message(message) => {
+            render_doc(&message.doc, &indent, out);
+            if message.nested.is_empty() && message.fields.is_empty() && message.oneof.is_empty() {
+                let _ = writeln!(out, "{}message {} {{}}", indent, message.name);
+                return;
+            }
+            let _ = writeln!(out, "{}message {} {{", indent, message.name);
+            for nested in &message.nested {
+                render(nested, depth + 1, out);
+            }
+            let inner = "  ".repeat(depth + 1);
+            for field in &message.fields {
+                render_field(field, &inner, out);
+            }
+            if !message.oneof.is_empty() {
+                let _ = writeln!(out, "{}oneof value {{", inner);
+                for field in &message.oneof {
+                    render_field(field, &"  ".repeat(depth + 2), out);
+                }
+                let _ = writeln!(out, "{}}}", inner);
+            }
+            let _ = writeln!(out, "{}}}", indent);
+        }
+        Declaration::Enum { name, doc, values } => {
+            render_doc(doc, &indent, out);
+            let _ = writeln!(out, "{}enum {} {{", indent, name);
+            for (i, value) in values.iter().enumerate() {
+                let _ = writeln!(out, "{}  {} = {};", indent, value, i);
+            }
+            let _ = writeln!(out, "{}}}", indent);
+        }
+        Declaration::Service { name, rpcs } => {
+            let _ = writeln!(out, "{}service {} {{", indent, name);
+            for (rpc, doc) in rpcs {
+                render_doc(doc, &format!("{}  ", indent), out);
+                let _ = writeln!(
+                    out,
+                    "{}  rpc {}({}Request) returns ({}Response);",
+                    indent, rpc, rpc, rpc
+                );
+            }
+            let _ = writeln!(out, "{}}}", indent);
+        }
+    }
+}
+
+/// Definition of a value of the package, by its fully qualified name
+fn find_value<'a>(
+    package_name: &str,
+    package: &'a v4::PackageDefinition,
+    target: &FQName,
+) -> Option<&'a ValueDefinition> {
+    if Path::new(package_name) != target.package_path {
+        return None;
+    }
+    let (_, module) = package
+        .modules
+        .iter()
+        .find(|(key, _)| Path::new(key) == target.module_path)?;
+    module
+        .value
+        .values
+        .iter()
+        .find(|(key, _)| Name::from(key.as_str()) == target.local_name)
+        .map(|(_, value)| &value.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn distribution() -> v4::Distribution {
+        let body = json!({"ExpressionBody": {"body": {"Literal": {"literal": {"IntegerLiteral": {"value": 0}}}}}});
+        serde_json::from_value(json!({ "Application": {
+            "packageName": "acme/shop",
+            "dependencies": {},
+            "def": { "modules": {
+                "orders": { "access": "Public", "value": {
+                    "types": {
+                        "order": { "access": "Public", "doc": "A customer order", "value": { "TypeAliasDefinition": {
+                            "typeParams": [],
+                            "typeExp": { "Record": { "fields": {
+                                "order-id": "acme/shop:orders#order-id",
+                                "lines": { "Reference": {
+                                    "fqname": "morphir/sdk:list#list",
+                                    "args": [{ "Tuple": { "elements": [
+                                        "morphir/sdk:string#string",
+                                        "morphir/sdk:basics#int"
+                                    ] } }]
+                                } },
+                                "status": "acme/shop:orders#status",
+                                "note": { "Reference": {
+                                    "fqname": "morphir/sdk:maybe#maybe",
+                                    "args": ["morphir/sdk:string#string"]
+                                } },
+                                "payment": "acme/shop:billing#payment"
+                            } } }
+                        } } },
+                        "order-id": { "access": "Public", "value": { "TypeAliasDefinition": {
+                            "typeParams": [],
+                            "typeExp": "morphir/sdk:string#string"
+                        } } },
+                        "status": { "access": "Public", "value": { "CustomTypeDefinition": {
+                            "typeParams": [],
+                            "constructors": { "access": "Public", "value": [
+                                { "name": "pending", "args": [] },
+                                { "name": "shipped", "args": [] }
+                            ] }
+                        } } }
+                    },
+                    "values": {
+                        "total": { "access": "Public", "value": {
+                            "inputTypes": { "order": { "type": "acme/shop:orders#order" } },
+                            "outputType": "morphir/sdk:decimal#decimal",
+                            "body": body
+                        } }
+                    }
+                } },
+                "billing": { "access": "Public", "value": {
+                    "types": {
+                        "payment": { "access": "Public", "value": { "CustomTypeDefinition": {
+                            "typeParams": [],
+                            "constructors": { "access": "Public", "value": [
+                                { "name": "cash", "args": [] },
+                                { "name": "card", "args": [
+                                    { "name": "number", "type": "morphir/sdk:string#string" }
+                                ] }
+                            ] }
+                        } } }
+                    },
+                    "values": {}
+                } }
+            } },
+            "entryPoints": {
+                "total": { "target": "acme/shop:orders#total", "kind": "handler", "doc": "Order total" }
+            }
+        } }))
+        .unwrap()
+    }
+
+    fn generate(options: &ProtobufOptions) -> ProtoOutput {
+        let dist = distribution();
+        generate_protos(
+            &dist.package_name().to_string(),
+            dist.definition().unwrap(),
+            dist.entry_points(),
+            options,
+        )
+    }
+
+    #[test]
+    fn test_records_and_unions_become_messages_and_enums() {
+        let output = generate(&ProtobufOptions::default());
+        assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+        let paths: Vec<&str> = output.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "acme/shop/billing.proto",
+                "acme/shop/orders.proto",
+                "acme/shop.proto"
+            ]
+        );
+
+        assert_eq!(
+            output.files[1].content,
+            r#"// Generated by morphir. Do not edit.
+
+syntax = "proto3";
+
+package acme.shop.orders;
+
+import "acme/shop/billing.proto";
+
+// A customer order
+message Order {
+  message Lines {
+    string item1 = 1;
+    int64 item2 = 2;
+  }
+  repeated Lines lines = 1;
+  optional string note = 2;
+  string order_id = 3;
+  .acme.shop.billing.Payment payment = 4;
+  Status status = 5;
+}
+
+enum Status {
+  STATUS_UNSPECIFIED = 0;
+  STATUS_PENDING = 1;
+  STATUS_SHIPPED = 2;
+}
+"#
+        );
+        assert!(output.files[0].content.contains(
+            "message Payment {\n  message Cash {}\n  message Card {\n    string number = 1;\n  }\n  oneof value {\n    Cash cash = 1;\n    Card card = 2;\n  }\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_entry_points_become_a_service() {
+        let output = generate(&ProtobufOptions::default());
+        let service = &output.files[2].content;
+        assert!(service.contains("package acme.shop;"));
+        assert!(service.contains("import \"acme/shop/orders.proto\";"));
+        assert!(service.contains(
+            "service ShopService {\n  // Order total\n  rpc Total(TotalRequest) returns (TotalResponse);\n}"
+        ));
+        assert!(
+            service.contains("message TotalRequest {\n  .acme.shop.orders.Order order = 1;\n}")
+        );
+        assert!(service.contains("message TotalResponse {\n  string result = 1;\n}"));
+
+        let output = generate(&ProtobufOptions {
+            services: false,
+            package: Some("com.acme.v1".to_string()),
+        });
+        let paths: Vec<&str> = output.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["com/acme/v1/billing.proto", "com/acme/v1/orders.proto"]
+        );
+    }
+}
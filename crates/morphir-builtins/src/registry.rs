@@ -24,12 +24,42 @@ impl BuiltinRegistry {
             let ext = MigrateExtension;
             registry.register(Box::new(ext));
         }
+        #[cfg(feature = "json-schema")]
+        {
+            use crate::json_schema::JsonSchemaExtension;
+            registry.register(Box::new(JsonSchemaExtension));
+        }
+        #[cfg(feature = "protobuf")]
+        {
+            use crate::protobuf::ProtobufExtension;
+            registry.register(Box::new(ProtobufExtension));
+        }
+        #[cfg(feature = "decision-tables")]
+        {
+            use crate::decision_tables::DecisionTableExtension;
+            registry.register(Box::new(DecisionTableExtension));
+        }
+        #[cfg(feature = "constants")]
+        {
+            use crate::constants::ConstantsExtension;
+            registry.register(Box::new(ConstantsExtension));
+        }
+        #[cfg(feature = "lint")]
+        {
+            use crate::lint::LintExtension;
+            registry.register(Box::new(LintExtension));
+        }
+        #[cfg(feature = "docs")]
+        {
+            use crate::docs::DocsExtension;
+            registry.register(Box::new(DocsExtension));
+        }
 
         registry
     }
 
-    /// Register a builtin extension.
-    fn register(&mut self, extension: Box<dyn BuiltinExtension>) {
+    /// Register a builtin extension, e.g. a backend from its own crate.
+    pub fn register(&mut self, extension: Box<dyn BuiltinExtension>) {
         let id = extension.info().id.clone();
         self.extensions.insert(id, extension);
     }
@@ -79,4 +109,55 @@ mod tests {
         let migrate = registry.get("migrate");
         assert!(migrate.is_some(), "Should find migrate builtin");
     }
+
+    #[test]
+    #[cfg(feature = "json-schema")]
+    fn test_get_json_schema() {
+        let registry = BuiltinRegistry::new();
+        assert!(
+            registry.contains("json-schema"),
+            "Should contain json-schema"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "protobuf")]
+    fn test_get_protobuf() {
+        let registry = BuiltinRegistry::new();
+        assert!(registry.contains("protobuf"), "Should contain protobuf");
+    }
+
+    #[test]
+    #[cfg(feature = "decision-tables")]
+    fn test_get_decision_tables() {
+        let registry = BuiltinRegistry::new();
+        assert!(
+            registry.contains("decision-tables"),
+            "Should contain decision-tables"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "constants")]
+    fn test_get_extract_constants() {
+        let registry = BuiltinRegistry::new();
+        assert!(
+            registry.contains("extract-constants"),
+            "Should contain extract-constants"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lint")]
+    fn test_get_lint() {
+        let registry = BuiltinRegistry::new();
+        assert!(registry.contains("lint"), "Should contain lint");
+    }
+
+    #[test]
+    #[cfg(feature = "docs")]
+    fn test_get_docs() {
+        let registry = BuiltinRegistry::new();
+        assert!(registry.contains("docs"), "Should contain docs");
+    }
 }
@@ -0,0 +1,192 @@
+//! Editing morphir.toml in place
+//!
+//! Edits go through `toml_edit`, so comments, ordering, and formatting of
+//! the rest of the file are kept.
+
+use anyhow::{Context, bail};
+use std::path::Path;
+use toml_edit::{Array, DocumentMut, Item, Table, value};
+
+/// Declare `name = "<requirement>"` in the `[dependencies]` table of the
+/// config file at `path` (or `[dev-dependencies]` when `dev` is set),
+/// replacing any existing declaration. Returns the declaration replaced.
+pub fn set_dependency(
+    path: &Path,
+    name: &str,
+    requirement: &str,
+    dev: bool,
+) -> crate::Result<Option<String>> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        bail!(
+            "{} is a legacy morphir.json; migrate it to morphir.toml to add dependencies",
+            path.display()
+        );
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut document: DocumentMut = content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let section = if dev {
+        "dev-dependencies"
+    } else {
+        "dependencies"
+    };
+    let table = document
+        .entry(section)
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .with_context(|| format!("[{}] in {} is not a table", section, path.display()))?;
+
+    let previous = table
+        .get(name)
+        .map(|item| item.to_string().trim().to_string());
+    table.insert(name, value(requirement));
+
+    std::fs::write(path, document.to_string())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(previous)
+}
+
+/// Set `[project] version` of the config file at `path` to `version`,
+/// keeping any comment after it
+pub fn set_project_version(path: &Path, version: &str) -> crate::Result<()> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        bail!(
+            "{} is a legacy morphir.json; migrate it to morphir.toml to set the version",
+            path.display()
+        );
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut document: DocumentMut = content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let project = document
+        .get_mut("project")
+        .and_then(Item::as_table_like_mut)
+        .with_context(|| format!("{} has no [project] table", path.display()))?;
+    let mut version = value(version);
+    if let (Some(previous), Some(version)) = (
+        project.get("version").and_then(Item::as_value),
+        version.as_value_mut(),
+    ) {
+        *version.decor_mut() = previous.decor().clone();
+    }
+    project.insert("version", version);
+
+    std::fs::write(path, document.to_string())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Add `member` to `[workspace] members` of the config file at `path`,
+/// unless one of the member patterns already matches it. Returns whether
+/// the file was changed.
+pub fn add_workspace_member(path: &Path, member: &str) -> crate::Result<bool> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut document: DocumentMut = content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let workspace = document
+        .get_mut("workspace")
+        .and_then(Item::as_table_like_mut)
+        .with_context(|| format!("{} has no [workspace] table", path.display()))?;
+    let members = workspace
+        .entry("members")
+        .or_insert(value(Array::new()))
+        .as_array_mut()
+        .with_context(|| format!("[workspace] members in {} is not an array", path.display()))?;
+
+    let covered = members.iter().filter_map(|m| m.as_str()).any(|pattern| {
+        pattern.trim_end_matches('/') == member
+            || glob::Pattern::new(pattern).is_ok_and(|p| p.matches(member))
+    });
+    if covered {
+        return Ok(false);
+    }
+    members.push(member);
+
+    std::fs::write(path, document.to_string())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DependencySpec, MorphirConfig};
+
+    #[test]
+    fn test_set_dependency_keeps_the_rest_of_the_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("morphir.toml");
+        let original = "# Project settings\n[project]\nname = \"acme/app\"\nversion = \"0.1.0\" # bumped by CI\n";
+        std::fs::write(&path, original)?;
+
+        assert_eq!(set_dependency(&path, "finos/sdk", "^1.2", false)?, None);
+        assert_eq!(
+            set_dependency(&path, "finos/sdk", "^1.3", false)?,
+            Some("\"^1.2\"".to_string())
+        );
+        set_dependency(&path, "acme/testing", "0.2", true)?;
+
+        let content = std::fs::read_to_string(&path)?;
+        assert!(content.starts_with(original));
+        let config = MorphirConfig::load(&path.to_path_buf())?;
+        assert!(matches!(
+            &config.dependencies["finos/sdk"],
+            DependencySpec::Version(version) if version == "^1.3"
+        ));
+        assert!(config.dev_dependencies.contains_key("acme/testing"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_workspace_member_skips_covered_members() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("morphir.toml");
+        std::fs::write(&path, "[workspace]\nmembers = [\"packages/*\"]\n")?;
+
+        assert!(!add_workspace_member(&path, "packages/core")?);
+        assert!(add_workspace_member(&path, "apps/web")?);
+        assert!(!add_workspace_member(&path, "apps/web")?);
+
+        let config = MorphirConfig::load(&path.to_path_buf())?;
+        assert_eq!(
+            config.workspace.unwrap().members,
+            vec!["packages/*".to_string(), "apps/web".to_string()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_project_version_keeps_its_comment() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("morphir.toml");
+        std::fs::write(
+            &path,
+            "[project]\nname = \"acme/app\"\nversion = \"0.1.0\" # bumped by CI\n",
+        )?;
+
+        set_project_version(&path, "0.2.0")?;
+        assert_eq!(
+            std::fs::read_to_string(&path)?,
+            "[project]\nname = \"acme/app\"\nversion = \"0.2.0\" # bumped by CI\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_dependency_rejects_legacy_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("morphir.json");
+        std::fs::write(&path, "{}").unwrap();
+        let err = set_dependency(&path, "finos/sdk", "^1", false).unwrap_err();
+        assert!(err.to_string().contains("legacy morphir.json"));
+    }
+}
@@ -0,0 +1,596 @@
+//! Effective configuration with per-key provenance
+//!
+//! [`ConfigResolver`] layers configuration, later layers overriding earlier
+//! ones key by key:
+//!
+//! 1. built-in defaults
+//! 2. the config file (`morphir.toml` or legacy `morphir.json`)
+//! 3. the selected `[profile.<name>]` table of the config file
+//! 4. `MORPHIR__<SECTION>__<KEY>` environment variables
+//! 5. command-line `key=value` overrides
+//!
+//! The resulting [`EffectiveConfig`] records which layer set each key, and
+//! can be dumped as JSON for `morphir config doctor` and daemon error reports.
+
+use super::MorphirConfig;
+use super::legacy::LegacyProjectConfig;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Environment variable selecting the configuration profile
+pub const PROFILE_ENV_VAR: &str = "MORPHIR_PROFILE";
+
+/// Prefix of environment variables that override configuration keys.
+///
+/// Path segments are separated by a double underscore, so
+/// `MORPHIR__IR__STRICT_MODE=true` sets `ir.strict_mode`.
+pub const ENV_PREFIX: &str = "MORPHIR__";
+
+/// Where a configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConfigSource {
+    /// Built-in default
+    Default,
+    /// Config file
+    File { path: PathBuf },
+    /// `[profile.<name>]` table of the config file
+    Profile { name: String, path: PathBuf },
+    /// Environment variable
+    Env { var: String },
+    /// Command-line override
+    Cli { arg: String },
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File { path } => write!(f, "file {}", path.display()),
+            ConfigSource::Profile { name, path } => {
+                write!(f, "profile `{}` in {}", name, path.display())
+            }
+            ConfigSource::Env { var } => write!(f, "env {}", var),
+            ConfigSource::Cli { arg } => write!(f, "--set {}", arg),
+        }
+    }
+}
+
+/// A configuration value and where it came from
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigEntry {
+    /// Effective value
+    pub value: toml::Value,
+    /// Layer that set the value
+    pub source: ConfigSource,
+    /// Earlier layers whose value was overridden, oldest first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub overridden: Vec<ConfigSource>,
+}
+
+/// Errors raised while resolving the effective configuration
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {message}")]
+    Read { path: PathBuf, message: String },
+
+    #[error("Failed to parse {origin}: {message}")]
+    Parse {
+        origin: ConfigSource,
+        message: String,
+    },
+
+    #[error("Profile `{name}` is not defined in {path}")]
+    UnknownProfile { name: String, path: PathBuf },
+
+    #[error("Invalid configuration: {message}")]
+    Invalid {
+        message: String,
+        /// Layered values that failed to deserialize, as produced by
+        /// [`EffectiveConfig::to_json`]
+        report: serde_json::Value,
+    },
+}
+
+impl ConfigError {
+    /// Machine-readable description of the error, including the layered
+    /// values when the merged configuration was invalid
+    pub fn to_json(&self) -> serde_json::Value {
+        let kind = match self {
+            ConfigError::Read { .. } => "read",
+            ConfigError::Parse { .. } => "parse",
+            ConfigError::UnknownProfile { .. } => "unknown_profile",
+            ConfigError::Invalid { .. } => "invalid",
+        };
+        let mut json = serde_json::json!({
+            "kind": kind,
+            "message": self.to_string(),
+        });
+        match self {
+            ConfigError::Parse { origin, .. } => {
+                json["source"] = serde_json::to_value(origin).unwrap_or_default();
+            }
+            ConfigError::Invalid { report, .. } => json["config"] = report.clone(),
+            _ => {}
+        }
+        json
+    }
+}
+
+/// Fully merged configuration with provenance for every key
+#[derive(Debug, Clone)]
+pub struct EffectiveConfig {
+    /// The merged configuration
+    pub config: MorphirConfig,
+    /// Selected profile, if any
+    pub profile: Option<String>,
+    /// Layers that contributed, in order of precedence (lowest first)
+    pub sources: Vec<ConfigSource>,
+    /// Every effective key (dotted path) with its value and source
+    pub entries: BTreeMap<String, ConfigEntry>,
+    /// Keys set by some layer that are not part of the configuration schema
+    /// and were ignored
+    pub unknown_keys: Vec<(String, ConfigSource)>,
+}
+
+impl EffectiveConfig {
+    /// Source of a key's effective value
+    pub fn source(&self, key: &str) -> Option<&ConfigSource> {
+        self.entries.get(key).map(|entry| &entry.source)
+    }
+
+    /// Machine-readable dump of the effective configuration
+    pub fn to_json(&self) -> serde_json::Value {
+        report(
+            self.profile.as_deref(),
+            &self.sources,
+            &self.entries,
+            &self.unknown_keys,
+        )
+    }
+}
+
+fn report(
+    profile: Option<&str>,
+    sources: &[ConfigSource],
+    entries: &BTreeMap<String, ConfigEntry>,
+    unknown_keys: &[(String, ConfigSource)],
+) -> serde_json::Value {
+    let unknown: Vec<serde_json::Value> = unknown_keys
+        .iter()
+        .map(|(key, source)| serde_json::json!({ "key": key, "source": source }))
+        .collect();
+    serde_json::json!({
+        "profile": profile,
+        "sources": sources,
+        "values": entries,
+        "unknown_keys": unknown,
+    })
+}
+
+/// Builder for the effective configuration
+#[derive(Debug, Clone, Default)]
+pub struct ConfigResolver {
+    file: Option<PathBuf>,
+    profile: Option<String>,
+    env: Vec<(String, String)>,
+    overrides: Vec<String>,
+}
+
+/// Provenance of a merged leaf value
+struct Provenance {
+    source: ConfigSource,
+    overridden: Vec<ConfigSource>,
+}
+
+impl ConfigResolver {
+    /// Create a resolver with only built-in defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the given config file
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Select a profile; takes precedence over `MORPHIR_PROFILE`
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Apply environment variables, typically `std::env::vars()`.
+    ///
+    /// Only `MORPHIR_PROFILE` and variables starting with `MORPHIR__` are used.
+    pub fn with_env(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env.extend(
+            vars.into_iter()
+                .filter(|(k, _)| k == PROFILE_ENV_VAR || k.starts_with(ENV_PREFIX)),
+        );
+        self.env.sort();
+        self
+    }
+
+    /// Apply a `key=value` override, e.g. `ir.strict_mode=true`
+    pub fn with_override(mut self, spec: impl Into<String>) -> Self {
+        self.overrides.push(spec.into());
+        self
+    }
+
+    /// Merge all layers into the effective configuration
+    pub fn resolve(&self) -> Result<EffectiveConfig, ConfigError> {
+        let mut layers: Vec<(ConfigSource, toml::Table)> = Vec::new();
+
+        let profile = self.profile.clone().or_else(|| {
+            self.env
+                .iter()
+                .find(|(k, _)| k == PROFILE_ENV_VAR)
+                .map(|(_, v)| v.clone())
+                .filter(|v| !v.is_empty())
+        });
+
+        let mut profiles = toml::Table::new();
+        if let Some(path) = &self.file {
+            let mut table = read_file(path)?;
+            if let Some(toml::Value::Table(defined)) = table.remove("profile") {
+                profiles = defined;
+            }
+            layers.push((ConfigSource::File { path: path.clone() }, table));
+        }
+        if let Some(name) = &profile {
+            let path = self.file.clone().unwrap_or_default();
+            match profiles.remove(name) {
+                Some(toml::Value::Table(table)) => {
+                    layers.push((
+                        ConfigSource::Profile {
+                            name: name.clone(),
+                            path,
+                        },
+                        table,
+                    ));
+                }
+                _ => {
+                    return Err(ConfigError::UnknownProfile {
+                        name: name.clone(),
+                        path,
+                    });
+                }
+            }
+        }
+        for (var, raw) in &self.env {
+            let Some(path) = var.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let segments: Vec<String> = path
+                .split("__")
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_lowercase())
+                .collect();
+            if segments.is_empty() {
+                continue;
+            }
+            layers.push((
+                ConfigSource::Env { var: var.clone() },
+                nested(&segments, parse_value(raw)),
+            ));
+        }
+        for spec in &self.overrides {
+            let source = ConfigSource::Cli { arg: spec.clone() };
+            let Some((key, raw)) = spec.split_once('=') else {
+                return Err(ConfigError::Parse {
+                    origin: source,
+                    message: "expected key=value".to_string(),
+                });
+            };
+            let segments: Vec<String> = key.trim().split('.').map(str::to_string).collect();
+            if segments.iter().any(String::is_empty) {
+                return Err(ConfigError::Parse {
+                    origin: source,
+                    message: format!("invalid key `{}`", key),
+                });
+            }
+            layers.push((source, nested(&segments, parse_value(raw.trim()))));
+        }
+
+        let mut merged = toml::Table::new();
+        let mut provenance = BTreeMap::new();
+        let sources: Vec<ConfigSource> = std::iter::once(ConfigSource::Default)
+            .chain(layers.iter().map(|(source, _)| source.clone()))
+            .collect();
+        for (source, table) in layers {
+            merge_table(&mut merged, table, "", &source, &mut provenance);
+        }
+
+        let config: MorphirConfig = match toml::Value::Table(merged.clone()).try_into() {
+            Ok(config) => config,
+            Err(e) => {
+                let mut leaves = Vec::new();
+                flatten(&toml::Value::Table(merged), String::new(), &mut leaves);
+                let entries = entries(leaves, &provenance);
+                return Err(ConfigError::Invalid {
+                    message: e.to_string(),
+                    report: report(profile.as_deref(), &sources, &entries, &[]),
+                });
+            }
+        };
+
+        let mut leaves = Vec::new();
+        if let Ok(effective) = toml::Value::try_from(&config) {
+            flatten(&effective, String::new(), &mut leaves);
+        }
+        let entries = entries(leaves, &provenance);
+        let unknown_keys = provenance
+            .into_iter()
+            .filter(|(key, _)| !entries.contains_key(key))
+            .map(|(key, p)| (key, p.source))
+            .collect();
+
+        Ok(EffectiveConfig {
+            config,
+            profile,
+            sources,
+            entries,
+            unknown_keys,
+        })
+    }
+}
+
+/// Read a config file as a TOML table; legacy JSON files are converted first
+fn read_file(path: &Path) -> Result<toml::Table, ConfigError> {
+    let content = std::fs::read_to_string(path).map_err(|e| ConfigError::Read {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    let origin = || ConfigSource::File {
+        path: path.to_path_buf(),
+    };
+    if path.extension().is_some_and(|ext| ext == "json") {
+        let legacy: LegacyProjectConfig =
+            serde_json::from_str(&content).map_err(|e| ConfigError::Parse {
+                origin: origin(),
+                message: e.to_string(),
+            })?;
+        let config: MorphirConfig = legacy.into();
+        return match toml::Value::try_from(&config) {
+            Ok(toml::Value::Table(table)) => Ok(table),
+            Ok(_) => Ok(toml::Table::new()),
+            Err(e) => Err(ConfigError::Parse {
+                origin: origin(),
+                message: e.to_string(),
+            }),
+        };
+    }
+    toml::from_str(&content).map_err(|e| ConfigError::Parse {
+        origin: origin(),
+        message: e.to_string(),
+    })
+}
+
+/// Parse an override value as TOML, falling back to a plain string
+fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Build `{ a = { b = value } }` from the path `a.b`
+fn nested(segments: &[String], value: toml::Value) -> toml::Table {
+    let mut value = value;
+    for segment in segments[1..].iter().rev() {
+        let mut table = toml::Table::new();
+        table.insert(segment.clone(), value);
+        value = toml::Value::Table(table);
+    }
+    let mut table = toml::Table::new();
+    table.insert(segments[0].clone(), value);
+    table
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// Collect the leaf values of a table as dotted paths
+fn flatten(value: &toml::Value, path: String, out: &mut Vec<(String, toml::Value)>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, v) in table {
+                flatten(v, join(&path, key), out);
+            }
+        }
+        other => out.push((path, other.clone())),
+    }
+}
+
+fn merge_table(
+    target: &mut toml::Table,
+    layer: toml::Table,
+    prefix: &str,
+    source: &ConfigSource,
+    provenance: &mut BTreeMap<String, Provenance>,
+) {
+    for (key, value) in layer {
+        let path = join(prefix, &key);
+        if let (Some(toml::Value::Table(existing)), toml::Value::Table(incoming)) =
+            (target.get_mut(&key), &value)
+        {
+            merge_table(existing, incoming.clone(), &path, source, provenance);
+            continue;
+        }
+
+        // The value replaces everything at this path
+        let nested_prefix = format!("{}.", path);
+        let replaced_keys: Vec<String> = provenance
+            .keys()
+            .filter(|k| **k == path || k.starts_with(&nested_prefix))
+            .cloned()
+            .collect();
+        let mut overridden: Vec<ConfigSource> = Vec::new();
+        for k in replaced_keys {
+            if let Some(p) = provenance.remove(&k) {
+                for s in p.overridden.into_iter().chain(std::iter::once(p.source)) {
+                    if !overridden.contains(&s) {
+                        overridden.push(s);
+                    }
+                }
+            }
+        }
+        let mut leaves = Vec::new();
+        flatten(&value, path, &mut leaves);
+        for (leaf, _) in leaves {
+            provenance.insert(
+                leaf,
+                Provenance {
+                    source: source.clone(),
+                    overridden: overridden.clone(),
+                },
+            );
+        }
+        target.insert(key, value);
+    }
+}
+
+fn entries(
+    leaves: Vec<(String, toml::Value)>,
+    provenance: &BTreeMap<String, Provenance>,
+) -> BTreeMap<String, ConfigEntry> {
+    leaves
+        .into_iter()
+        .map(|(key, value)| {
+            let entry = match provenance.get(&key) {
+                Some(p) => ConfigEntry {
+                    value,
+                    source: p.source.clone(),
+                    overridden: p.overridden.clone(),
+                },
+                None => ConfigEntry {
+                    value,
+                    source: ConfigSource::Default,
+                    overridden: Vec::new(),
+                },
+            };
+            (key, entry)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+[project]
+name = "my/pkg"
+version = "1.0.0"
+
+[ir]
+mode = "classic"
+
+[profile.ci.ir]
+strict_mode = true
+"#;
+
+    fn write_config(dir: &Path) -> PathBuf {
+        let path = dir.join("morphir.toml");
+        std::fs::write(&path, CONFIG).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_layers_record_provenance() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(dir.path());
+
+        let effective = ConfigResolver::new()
+            .with_file(&path)
+            .with_env(vec![
+                ("MORPHIR_PROFILE".to_string(), "ci".to_string()),
+                ("MORPHIR__IR__MODE".to_string(), "vfs".to_string()),
+                ("HOME".to_string(), "/root".to_string()),
+            ])
+            .with_override("ir.format_version=5")
+            .resolve()
+            .unwrap();
+
+        assert_eq!(effective.profile.as_deref(), Some("ci"));
+        let ir = effective.config.ir.as_ref().unwrap();
+        assert!(ir.strict_mode);
+        assert_eq!(ir.mode, "vfs");
+        assert_eq!(ir.format_version, 5);
+
+        assert_eq!(
+            effective.source("project.name"),
+            Some(&ConfigSource::File { path: path.clone() })
+        );
+        assert_eq!(
+            effective.source("ir.strict_mode"),
+            Some(&ConfigSource::Profile {
+                name: "ci".to_string(),
+                path: path.clone()
+            })
+        );
+        let mode = &effective.entries["ir.mode"];
+        assert_eq!(
+            mode.source,
+            ConfigSource::Env {
+                var: "MORPHIR__IR__MODE".to_string()
+            }
+        );
+        assert_eq!(mode.overridden, vec![ConfigSource::File { path }]);
+        assert_eq!(
+            effective.source("project.source_directory"),
+            Some(&ConfigSource::Default)
+        );
+
+        let json = effective.to_json();
+        assert_eq!(json["values"]["ir.format_version"]["source"]["kind"], "cli");
+    }
+
+    #[test]
+    fn test_unknown_keys_and_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(dir.path());
+
+        let effective = ConfigResolver::new()
+            .with_file(&path)
+            .with_override("ir.strictmode=true")
+            .resolve()
+            .unwrap();
+        assert_eq!(effective.unknown_keys.len(), 1);
+        assert_eq!(effective.unknown_keys[0].0, "ir.strictmode");
+
+        let missing = ConfigResolver::new()
+            .with_file(&path)
+            .with_profile("release")
+            .resolve();
+        assert!(matches!(missing, Err(ConfigError::UnknownProfile { .. })));
+    }
+
+    #[test]
+    fn test_invalid_config_reports_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(dir.path());
+
+        let error = ConfigResolver::new()
+            .with_file(&path)
+            .with_override("ir.format_version=\"four\"")
+            .resolve()
+            .unwrap_err();
+        let json = error.to_json();
+        assert_eq!(json["kind"], "invalid");
+        assert_eq!(
+            json["config"]["values"]["ir.format_version"]["value"],
+            "four"
+        );
+    }
+}
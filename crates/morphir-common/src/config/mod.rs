@@ -2,12 +2,17 @@
 //!
 //! Handles loading and parsing of Morphir configuration files (morphir.toml, morphir.json).
 
+pub mod edit;
+pub mod effective;
 pub mod legacy;
 pub mod model;
 
 use self::legacy::LegacyProjectConfig;
 use std::path::PathBuf;
 
+pub use self::effective::{
+    ConfigEntry, ConfigError, ConfigResolver, ConfigSource, EffectiveConfig,
+};
 pub use self::model::*;
 
 impl MorphirConfig {
@@ -60,6 +65,124 @@ exposed_modules = ["Foo", "Bar"]
         Ok(())
     }
 
+    #[test]
+    fn test_load_generate_profiles() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("morphir.toml");
+        std::fs::write(
+            &file_path,
+            r#"
+[generate.profiles.prod-ts]
+target = "typescript"
+output = "dist/ts"
+post_process = ["prettier --write ."]
+verify = true
+
+[generate.profiles.prod-ts.options]
+strict = true
+
+[generate.profiles.docs]
+target = "openapi"
+"#,
+        )?;
+
+        let config = MorphirConfig::load(&file_path)?;
+        let profile = config.generate_profile("prod-ts").unwrap();
+        assert_eq!(profile.target, "typescript");
+        assert_eq!(profile.output.as_deref(), Some("dist/ts"));
+        assert_eq!(profile.options["strict"], toml::Value::Boolean(true));
+        assert_eq!(profile.post_process, vec!["prettier --write ."]);
+        assert!(profile.verify && profile.verify_command.is_none());
+        let docs = config.generate_profile("docs").unwrap();
+        assert!(docs.input.is_none() && docs.post_process.is_empty() && !docs.verify);
+        assert!(config.generate_profile("missing").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_extension_permissions_and_limits() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("morphir.toml");
+        std::fs::write(
+            &file_path,
+            r#"
+[extensions.my-ext]
+path = "extensions/my-ext.wasm"
+
+[extensions.permissions.my-ext]
+filesystem = ["/workspace/src"]
+network = ["*.example.com"]
+
+[extensions.limits.my-ext]
+max_time_ms = 5000
+
+[extensions.runtime]
+my-ext = "component"
+"#,
+        )?;
+
+        let config = MorphirConfig::load(&file_path)?;
+        assert_eq!(config.extensions.specs.len(), 1);
+        assert!(config.extensions.specs["my-ext"].enabled);
+        let granted = &config.extensions.permissions["my-ext"];
+        assert_eq!(granted.filesystem, vec!["/workspace/src"]);
+        assert_eq!(granted.network, vec!["*.example.com"]);
+        assert!(granted.env.is_empty());
+        let limits = &config.extensions.limits["my-ext"];
+        assert_eq!(limits.max_time_ms, Some(5000));
+        assert_eq!(limits.max_memory_bytes, None);
+        assert_eq!(
+            config.extensions.runtime["my-ext"],
+            ExtensionRuntime::Component
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_pipelines() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("morphir.toml");
+        std::fs::write(
+            &file_path,
+            r#"
+[pipelines.release]
+stages = [
+    { frontend = "gleam" },
+    { transform = "prune", options = { keep = ["Orders"] } },
+    { backend = "typescript" },
+]
+"#,
+        )?;
+
+        let config = MorphirConfig::load(&file_path)?;
+        let stages = &config.pipelines["release"].stages;
+        let kinds: Vec<_> = stages
+            .iter()
+            .map(|s| (s.kind, s.extension.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (PipelineStageKind::Frontend, "gleam"),
+                (PipelineStageKind::Transform, "prune"),
+                (PipelineStageKind::Backend, "typescript"),
+            ]
+        );
+        assert!(stages[1].options.contains_key("keep"));
+
+        // Written back the way it was declared
+        let toml = toml::to_string(&config.pipelines["release"])?;
+        assert!(toml.contains("transform = \"prune\""), "{}", toml);
+
+        std::fs::write(
+            &file_path,
+            "[pipelines.broken]\nstages = [{ frontend = \"gleam\", backend = \"typescript\" }]\n",
+        )?;
+        let err = MorphirConfig::load(&file_path).unwrap_err();
+        assert!(format!("{:#}", err).contains("exactly one of"), "{:#}", err);
+        Ok(())
+    }
+
     #[test]
     fn test_load_legacy_json() -> anyhow::Result<()> {
         let json_content = r#"{
@@ -1,6 +1,7 @@
+use crate::registry::RegistryConfig;
 use crate::remote::config::RemoteSourceConfig;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 /// Root configuration from morphir.toml
@@ -30,10 +31,18 @@ pub struct MorphirConfig {
     #[serde(default)]
     pub codegen: Option<CodegenSection>,
 
+    /// Named generation profiles
+    #[serde(default)]
+    pub generate: Option<GenerateSection>,
+
     /// Remote source configuration
     #[serde(default)]
     pub sources: Option<RemoteSourceConfig>,
 
+    /// Package registry configuration
+    #[serde(default)]
+    pub registry: Option<RegistryConfig>,
+
     /// Dependencies
     #[serde(default)]
     pub dependencies: HashMap<String, DependencySpec>,
@@ -44,11 +53,31 @@ pub struct MorphirConfig {
 
     /// Extensions
     #[serde(default)]
-    pub extensions: HashMap<String, ExtensionSpec>,
+    pub extensions: ExtensionsSection,
 
     /// Tasks
     #[serde(default)]
     pub tasks: HashMap<String, TaskSpec>,
+
+    /// Named extension pipelines
+    #[serde(default)]
+    pub pipelines: HashMap<String, PipelineSpec>,
+
+    /// Daemon settings
+    #[serde(default)]
+    pub daemon: Option<DaemonSection>,
+
+    /// Message catalog settings
+    #[serde(default)]
+    pub messages: Option<MessagesSection>,
+
+    /// Dependency vendoring settings
+    #[serde(default)]
+    pub vendor: Option<VendorSection>,
+
+    /// IR linter settings
+    #[serde(default)]
+    pub lint: Option<LintSection>,
 }
 
 impl MorphirConfig {
@@ -189,6 +218,122 @@ fn default_output_format() -> String {
     "pretty".to_string()
 }
 
+/// [generate] section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerateSection {
+    /// Profiles by name, e.g. `[generate.profiles.prod-ts]`
+    #[serde(default)]
+    pub profiles: BTreeMap<String, GenerateProfile>,
+}
+
+/// A named set of `morphir generate` settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateProfile {
+    /// Target language or format
+    pub target: String,
+    /// Morphir IR file or directory, relative to the config file
+    pub input: Option<String>,
+    /// Output directory, relative to the config file
+    pub output: Option<String>,
+    /// Target settings, over those of `[codegen.<target>]`
+    #[serde(default)]
+    pub options: HashMap<String, toml::Value>,
+    /// Commands run in the output directory after generating, in order
+    #[serde(default)]
+    pub post_process: Vec<String>,
+    /// Check the output with the target's toolchain after generating
+    #[serde(default)]
+    pub verify: bool,
+    /// Checker to run instead of the target's own, e.g. `gleam check`
+    pub verify_command: Option<String>,
+}
+
+impl MorphirConfig {
+    /// The generation profile called `name`
+    pub fn generate_profile(&self, name: &str) -> Option<&GenerateProfile> {
+        self.generate.as_ref()?.profiles.get(name)
+    }
+}
+
+/// [daemon] section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DaemonSection {
+    /// Maximum number of requests executing at once across all extensions
+    pub max_concurrent_requests: Option<usize>,
+    /// Maximum number of requests executing at once for a single extension
+    pub max_concurrent_per_extension: Option<usize>,
+    /// Maximum number of queued requests before the daemon reports itself busy
+    pub max_queue_depth: Option<usize>,
+    /// Retry-after hint sent with "server busy" responses, in milliseconds
+    pub retry_after_ms: Option<u64>,
+    /// Run transforms and validators that have a builtin implementation
+    /// natively instead of loading their WASM (default `true`)
+    pub prefer_native_builtins: Option<bool>,
+    /// TCP port the daemon listens on, on the loopback interface
+    pub port: Option<u16>,
+    /// How long watched files must be quiet before a rebuild, in milliseconds
+    pub watch_debounce_ms: Option<u64>,
+    /// Rebuild projects when their watched files change (default `true`)
+    pub auto_rebuild: Option<bool>,
+    /// Time requests in flight are given to complete when the daemon shuts
+    /// down, in milliseconds (default 30000)
+    pub shutdown_timeout_ms: Option<u64>,
+}
+
+/// [messages] section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessagesSection {
+    /// Locale for CLI and diagnostic messages (e.g. "fr-CA"); overrides the environment
+    pub locale: Option<String>,
+    /// Directory containing override catalogs named `<locale>.toml`
+    pub catalog_dir: Option<String>,
+}
+
+/// [vendor] section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VendorSection {
+    /// Directory vendored dependencies are copied into, relative to the root
+    #[serde(default = "default_vendor_dir")]
+    pub directory: String,
+    /// Vendor extensions downloaded from a URL as well
+    #[serde(default)]
+    pub extensions: bool,
+}
+
+impl Default for VendorSection {
+    fn default() -> Self {
+        Self {
+            directory: default_vendor_dir(),
+            extensions: false,
+        }
+    }
+}
+
+fn default_vendor_dir() -> String {
+    "vendor".to_string()
+}
+
+/// [lint] section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintSection {
+    /// Level of each rule by its code, e.g. `shadowed-variable = "error"`;
+    /// rules not listed keep their default level
+    #[serde(default)]
+    pub rules: BTreeMap<String, LintLevel>,
+    /// Deepest nesting of conditionals allowed in a value definition
+    pub max_nesting_depth: Option<usize>,
+}
+
+/// Level a lint rule reports at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// The rule does not run
+    Off,
+    Warning,
+    Error,
+}
+
 /// Dependency specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -218,6 +363,61 @@ pub struct DetailedDependency {
     pub workspace: Option<bool>,
 }
 
+/// [extensions] section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExtensionsSection {
+    /// Capabilities granted to each extension, by extension ID
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub permissions: HashMap<String, ExtensionPermissions>,
+    /// Resource limits of each extension, by extension ID
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub limits: HashMap<String, ExtensionLimits>,
+    /// Runtime hosting each extension, by extension ID; others are detected
+    /// from their WASM
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub runtime: HashMap<String, ExtensionRuntime>,
+    /// Extensions, by name
+    #[serde(flatten)]
+    pub specs: HashMap<String, ExtensionSpec>,
+}
+
+/// [extensions.permissions.<id>] section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionPermissions {
+    /// Virtual paths the extension may mount, e.g. `/workspace/src`; a path
+    /// grants everything under it
+    #[serde(default)]
+    pub filesystem: Vec<String>,
+    /// Hosts the extension may reach; `*.example.com` grants subdomains and
+    /// `*` any host
+    #[serde(default)]
+    pub network: Vec<String>,
+    /// Environment variables the extension may read
+    #[serde(default)]
+    pub env: Vec<String>,
+}
+
+/// [extensions.limits.<id>] section; limits left out keep their defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionLimits {
+    /// Maximum memory in bytes (256 MB by default)
+    pub max_memory_bytes: Option<u64>,
+    /// Maximum wall-clock time of a request in milliseconds (30 s by default)
+    pub max_time_ms: Option<u64>,
+    /// Maximum fuel (instructions) a request may burn (100M by default)
+    pub max_fuel: Option<u64>,
+}
+
+/// Plugin style an extension is built as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionRuntime {
+    /// A core WASM module exporting the Extism JSON-RPC `handle` function
+    Extism,
+    /// A component exporting the `morphir:ext` compiler interfaces
+    Component,
+}
+
 /// Extension specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtensionSpec {
@@ -242,6 +442,110 @@ fn default_true() -> bool {
     true
 }
 
+/// [pipelines.<name>] section: extensions chained so that each stage runs
+/// on the IR the previous stage produced
+///
+/// ```toml
+/// [pipelines.release]
+/// stages = [
+///     { frontend = "gleam" },
+///     { transform = "prune", options = { keep = ["Orders"] } },
+///     { backend = "typescript" },
+/// ]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineSpec {
+    /// Description
+    pub description: Option<String>,
+    /// Stages, in the order they run
+    #[serde(default)]
+    pub stages: Vec<PipelineStage>,
+}
+
+/// Role an extension plays in a pipeline stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PipelineStageKind {
+    Frontend,
+    Transform,
+    Validator,
+    Backend,
+}
+
+/// A stage of a pipeline: one extension, named under its role
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawPipelineStage", into = "RawPipelineStage")]
+pub struct PipelineStage {
+    /// Role of the extension
+    pub kind: PipelineStageKind,
+    /// Extension identifier
+    pub extension: String,
+    /// Options passed to the extension with its input
+    pub options: HashMap<String, toml::Value>,
+}
+
+/// A pipeline stage as written in `morphir.toml`
+#[derive(Serialize, Deserialize)]
+struct RawPipelineStage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    frontend: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transform: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    validator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backend: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    options: HashMap<String, toml::Value>,
+}
+
+impl TryFrom<RawPipelineStage> for PipelineStage {
+    type Error = String;
+
+    fn try_from(raw: RawPipelineStage) -> Result<Self, Self::Error> {
+        let named = [
+            (PipelineStageKind::Frontend, raw.frontend),
+            (PipelineStageKind::Transform, raw.transform),
+            (PipelineStageKind::Validator, raw.validator),
+            (PipelineStageKind::Backend, raw.backend),
+        ];
+        let mut named = named
+            .into_iter()
+            .filter_map(|(kind, extension)| Some((kind, extension?)));
+        match (named.next(), named.next()) {
+            (Some((kind, extension)), None) => Ok(PipelineStage {
+                kind,
+                extension,
+                options: raw.options,
+            }),
+            _ => Err(
+                "a pipeline stage names exactly one of frontend, transform, validator, or backend"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+impl From<PipelineStage> for RawPipelineStage {
+    fn from(stage: PipelineStage) -> Self {
+        let mut raw = RawPipelineStage {
+            frontend: None,
+            transform: None,
+            validator: None,
+            backend: None,
+            options: stage.options,
+        };
+        let slot = match stage.kind {
+            PipelineStageKind::Frontend => &mut raw.frontend,
+            PipelineStageKind::Transform => &mut raw.transform,
+            PipelineStageKind::Validator => &mut raw.validator,
+            PipelineStageKind::Backend => &mut raw.backend,
+        };
+        *slot = Some(stage.extension);
+        raw
+    }
+}
+
 /// Task specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -0,0 +1,477 @@
+//! Document Tree layout of V4 distributions
+//!
+//! A document tree stores a distribution as one file per definition rather
+//! than as a single JSON blob, so that changing a definition rewrites only
+//! its file:
+//!
+//! ```text
+//! <root>/format.json
+//! <root>/.morphir-dist/pkg/<package>/<module>/module.json
+//! <root>/.morphir-dist/pkg/<package>/<module>/types/<type>.type.json
+//! <root>/.morphir-dist/pkg/<package>/<module>/values/<value>.value.json
+//! ```
+//!
+//! `format.json` records the format version, the kind of distribution, the
+//! package name and its dependencies, and the entry points of applications.
+//! Each module's `module.json` names the module and lists its definitions,
+//! which are stored as their access-controlled V4 JSON. Specification
+//! distributions store type and value specifications in the same places.
+//!
+//! [`DocumentTree`] writes and reads this layout over any [`Vfs`], so
+//! frontends, the loaders and tests agree on every path.
+
+use crate::vfs::{Vfs, VirtualPath};
+use anyhow::{Context, Result, anyhow, bail};
+use indexmap::IndexMap;
+use morphir_core::ir::v4;
+use morphir_core::naming::PackageName;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Layout named in `format.json`
+pub const LAYOUT: &str = "VfsMode";
+
+/// `format.json` at the root of a document tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Format {
+    #[serde(default)]
+    pub format_version: v4::FormatVersion,
+    /// Kind of distribution: `Library`, `Specs` or `Application`
+    pub distribution: String,
+    pub package_name: String,
+    #[serde(default = "layout")]
+    pub layout: String,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub dependencies: v4::Dependencies,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_points: Option<v4::EntryPoints>,
+}
+
+fn layout() -> String {
+    LAYOUT.to_string()
+}
+
+impl Format {
+    /// Format of a library without dependencies
+    pub fn library(package_name: &PackageName) -> Self {
+        Self {
+            format_version: v4::FormatVersion::default(),
+            distribution: "Library".to_string(),
+            package_name: package_name.to_string(),
+            layout: layout(),
+            dependencies: IndexMap::new(),
+            entry_points: None,
+        }
+    }
+
+    /// Format describing `distribution`, without its modules
+    pub fn of(distribution: &v4::Distribution) -> Self {
+        let (kind, dependencies, entry_points) = match distribution {
+            v4::Distribution::Library(lib) => ("Library", &lib.dependencies, None),
+            v4::Distribution::Specs(specs) => ("Specs", &specs.dependencies, None),
+            v4::Distribution::Application(app) => {
+                ("Application", &app.dependencies, Some(&app.entry_points))
+            }
+        };
+        Self {
+            distribution: kind.to_string(),
+            dependencies: dependencies.clone(),
+            entry_points: entry_points.cloned(),
+            ..Self::library(distribution.package_name())
+        }
+    }
+}
+
+/// `module.json` of a module in a document tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleManifest {
+    pub module: String,
+    /// Access of a module definition; modules without one are public
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access: Option<v4::Access>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    #[serde(default)]
+    pub types: Vec<String>,
+    #[serde(default)]
+    pub values: Vec<String>,
+    #[serde(
+        rename = "nodeIds",
+        default,
+        skip_serializing_if = "v4::NodeIds::is_empty"
+    )]
+    pub ids: v4::NodeIds,
+}
+
+/// A document tree rooted at a directory of a [`Vfs`]
+pub struct DocumentTree<'a, V: Vfs> {
+    vfs: &'a V,
+    root: PathBuf,
+}
+
+impl<'a, V: Vfs> DocumentTree<'a, V> {
+    pub fn new(vfs: &'a V, root: impl Into<PathBuf>) -> Self {
+        Self {
+            vfs,
+            root: root.into(),
+        }
+    }
+
+    /// Path of `format.json`
+    pub fn format_path(&self) -> PathBuf {
+        self.root.join("format.json")
+    }
+
+    /// Directory holding the modules of `package_name`
+    pub fn package_dir(&self, package_name: &str) -> PathBuf {
+        self.root
+            .join(".morphir-dist")
+            .join("pkg")
+            .join(package_name)
+    }
+
+    /// Directory holding the module `module_name` of `package_name`
+    pub fn module_dir(&self, package_name: &str, module_name: &str) -> PathBuf {
+        self.package_dir(package_name).join(module_name)
+    }
+
+    /// Whether the root holds a document tree
+    pub fn exists(&self) -> bool {
+        self.vfs.exists(&self.format_path())
+    }
+
+    /// Write every module of `ir_file` and its `format.json`.
+    ///
+    /// Files of modules already in the tree but not in `ir_file` are left
+    /// in place.
+    pub fn write(&self, ir_file: &v4::IRFile) -> Result<()> {
+        let mut format = Format::of(&ir_file.distribution);
+        format.format_version = ir_file.format_version.clone();
+        self.write_format(&format)?;
+        match &ir_file.distribution {
+            v4::Distribution::Library(v4::LibraryContent { def, .. })
+            | v4::Distribution::Application(v4::ApplicationContent { def, .. }) => {
+                for (name, module) in &def.modules {
+                    self.write_module(&format.package_name, name, module)?;
+                }
+            }
+            v4::Distribution::Specs(specs) => {
+                for (name, module) in &specs.spec.modules {
+                    self.write_module_specification(&format.package_name, name, module)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `format.json`
+    pub fn write_format(&self, format: &Format) -> Result<()> {
+        self.write_json(&self.format_path(), format)
+    }
+
+    /// Write the module `module_name` of `package_name`: its manifest and
+    /// a file per definition
+    pub fn write_module(
+        &self,
+        package_name: &str,
+        module_name: &str,
+        module: &v4::AccessControlled<v4::ModuleDefinition>,
+    ) -> Result<()> {
+        let dir = self.module_dir(package_name, module_name);
+        let definition = &module.value;
+        self.write_json(
+            &dir.join("module.json"),
+            &ModuleManifest {
+                module: module_name.to_string(),
+                access: Some(module.access.clone()),
+                doc: definition.doc.clone(),
+                types: definition.types.keys().cloned().collect(),
+                values: definition.values.keys().cloned().collect(),
+                ids: definition.ids.clone(),
+            },
+        )?;
+        self.write_definitions(&dir, &definition.types, &definition.values)
+    }
+
+    /// Write the specification of the module `module_name` of `package_name`
+    pub fn write_module_specification(
+        &self,
+        package_name: &str,
+        module_name: &str,
+        module: &v4::ModuleSpecification,
+    ) -> Result<()> {
+        let dir = self.module_dir(package_name, module_name);
+        self.write_json(
+            &dir.join("module.json"),
+            &ModuleManifest {
+                module: module_name.to_string(),
+                access: None,
+                doc: module.doc.clone(),
+                types: module.types.keys().cloned().collect(),
+                values: module.values.keys().cloned().collect(),
+                ids: Default::default(),
+            },
+        )?;
+        self.write_definitions(&dir, &module.types, &module.values)
+    }
+
+    fn write_definitions<T: Serialize, U: Serialize>(
+        &self,
+        dir: &Path,
+        types: &IndexMap<String, T>,
+        values: &IndexMap<String, U>,
+    ) -> Result<()> {
+        for (name, type_def) in types {
+            self.write_json(&type_path(dir, name), type_def)?;
+        }
+        for (name, value_def) in values {
+            self.write_json(&value_path(dir, name), value_def)?;
+        }
+        Ok(())
+    }
+
+    fn write_json(&self, path: &Path, value: &impl Serialize) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            self.vfs.create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(value)?;
+        self.vfs
+            .write_from_string(path, &content)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Reassemble the distribution stored in the tree
+    pub fn read(&self) -> Result<v4::IRFile> {
+        let format = self.read_format()?;
+        let package_name = PackageName::parse(&format.package_name);
+        let modules = self.module_dirs(&format.package_name)?;
+        let distribution = if format.distribution == "Specs" {
+            let mut spec = v4::PackageSpecification {
+                modules: IndexMap::new(),
+            };
+            for (name, dir) in modules {
+                spec.modules
+                    .insert(name, self.read_module_specification(&dir)?);
+            }
+            v4::Distribution::Specs(v4::SpecsContent {
+                package_name,
+                dependencies: format.dependencies,
+                spec,
+            })
+        } else {
+            let mut def = v4::PackageDefinition {
+                modules: IndexMap::new(),
+            };
+            for (name, dir) in modules {
+                def.modules.insert(name, self.read_module(&dir)?);
+            }
+            match format.distribution.as_str() {
+                "Library" => v4::Distribution::Library(v4::LibraryContent {
+                    package_name,
+                    dependencies: format.dependencies,
+                    def,
+                }),
+                "Application" => v4::Distribution::Application(v4::ApplicationContent {
+                    package_name,
+                    dependencies: format.dependencies,
+                    def,
+                    entry_points: format.entry_points.ok_or_else(|| {
+                        anyhow!("format.json of an application has no entryPoints")
+                    })?,
+                }),
+                kind => bail!("Unknown distribution kind in format.json: {}", kind),
+            }
+        };
+        Ok(v4::IRFile {
+            format_version: format.format_version,
+            distribution,
+        })
+    }
+
+    /// Read `format.json`
+    pub fn read_format(&self) -> Result<Format> {
+        self.read_json(&self.format_path())
+    }
+
+    /// Name and directory of every module of `package_name`, by directory
+    pub fn module_dirs(&self, package_name: &str) -> Result<Vec<(String, PathBuf)>> {
+        let pattern = format!(
+            "{}/**/module.json",
+            glob::Pattern::escape(VirtualPath::from_path(&self.package_dir(package_name)).as_str())
+        );
+        let mut manifests = self.vfs.glob(&pattern)?;
+        manifests.sort();
+        manifests
+            .into_iter()
+            .map(|path| {
+                let manifest: ModuleManifest = self.read_json(&path)?;
+                let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                Ok((manifest.module, dir))
+            })
+            .collect()
+    }
+
+    /// Assemble the module definition stored in `dir`
+    pub fn read_module(&self, dir: &Path) -> Result<v4::AccessControlled<v4::ModuleDefinition>> {
+        let manifest: ModuleManifest = self.read_json(&dir.join("module.json"))?;
+        let mut module = v4::ModuleDefinition {
+            types: IndexMap::new(),
+            values: IndexMap::new(),
+            doc: manifest.doc,
+            ids: manifest.ids,
+        };
+        for name in manifest.types {
+            module
+                .types
+                .insert(name.clone(), self.read_json(&type_path(dir, &name))?);
+        }
+        for name in manifest.values {
+            module
+                .values
+                .insert(name.clone(), self.read_json(&value_path(dir, &name))?);
+        }
+        Ok(v4::AccessControlled {
+            access: manifest.access.unwrap_or(v4::Access::Public),
+            doc: None,
+            value: module,
+        })
+    }
+
+    /// Assemble the module specification stored in `dir`
+    pub fn read_module_specification(&self, dir: &Path) -> Result<v4::ModuleSpecification> {
+        let manifest: ModuleManifest = self.read_json(&dir.join("module.json"))?;
+        let mut module = v4::ModuleSpecification {
+            types: IndexMap::new(),
+            values: IndexMap::new(),
+            doc: manifest.doc,
+        };
+        for name in manifest.types {
+            module
+                .types
+                .insert(name.clone(), self.read_json(&type_path(dir, &name))?);
+        }
+        for name in manifest.values {
+            module
+                .values
+                .insert(name.clone(), self.read_json(&value_path(dir, &name))?);
+        }
+        Ok(module)
+    }
+
+    fn read_json<T: DeserializeOwned>(&self, path: &Path) -> Result<T> {
+        let content = self
+            .vfs
+            .read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
+
+/// File of the type `name` in the module directory `dir`
+pub fn type_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join("types").join(format!("{}.type.json", name))
+}
+
+/// File of the value `name` in the module directory `dir`
+pub fn value_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join("values").join(format!("{}.value.json", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryVfs;
+    use morphir_core::ir::v4::text::parse_distribution;
+
+    const SHOP: &str = r#"library acme/shop
+
+
+module orders
+    {-| Orders and their lines. -}
+
+{-| One line of an order -}
+type alias Line =
+    { price : morphir/sdk:basics#Int
+    , quantity : morphir/sdk:basics#Int
+    }
+
+total (line : acme/shop:orders#Line) : morphir/sdk:basics#Int =
+    line.price
+
+
+private module pricing
+
+discount : morphir/sdk:basics#Int =
+    10
+
+
+dependency morphir/sdk
+
+
+module basics
+
+type Int
+"#;
+
+    fn shop() -> v4::IRFile {
+        v4::IRFile {
+            format_version: v4::FormatVersion::default(),
+            distribution: parse_distribution(SHOP).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_distribution_is_shredded_and_reassembled() {
+        let vfs = MemoryVfs::new();
+        let tree = DocumentTree::new(&vfs, "/out");
+        let ir_file = shop();
+        tree.write(&ir_file).unwrap();
+
+        let orders = Path::new("/out/.morphir-dist/pkg/acme/shop/orders");
+        assert!(vfs.exists(&orders.join("module.json")));
+        assert!(vfs.exists(&type_path(orders, "line")));
+        assert!(vfs.exists(&value_path(orders, "total")));
+        let format = tree.read_format().unwrap();
+        assert_eq!(format.distribution, "Library");
+        assert_eq!(format.layout, LAYOUT);
+        assert!(format.dependencies.contains_key("morphir/sdk"));
+
+        assert_eq!(tree.read().unwrap(), ir_file);
+
+        // The loader reads the tree back as the same distribution
+        let crate::loader::LoadedDistribution::V4(loaded) =
+            crate::loader::load_distribution(&vfs, Path::new("/out")).unwrap()
+        else {
+            panic!("Expected V4 distribution");
+        };
+        assert_eq!(loaded, ir_file);
+    }
+
+    #[test]
+    fn test_specifications_are_stored_in_the_same_places() {
+        let v4::Distribution::Library(lib) = shop().distribution else {
+            panic!("Expected library distribution");
+        };
+        let ir_file = v4::IRFile {
+            format_version: v4::FormatVersion::default(),
+            distribution: v4::Distribution::Specs(v4::SpecsContent {
+                package_name: lib.package_name,
+                dependencies: IndexMap::new(),
+                spec: lib.def.to_specification(),
+            }),
+        };
+        let vfs = MemoryVfs::new();
+        let tree = DocumentTree::new(&vfs, ".");
+        tree.write(&ir_file).unwrap();
+
+        assert!(vfs.exists(&value_path(
+            &tree.module_dir("acme/shop", "orders"),
+            "total"
+        )));
+        assert_eq!(tree.read().unwrap(), ir_file);
+    }
+}
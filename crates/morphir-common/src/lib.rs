@@ -1,9 +1,16 @@
 pub mod codegen;
 pub mod config;
+pub mod document_tree;
 pub mod loader;
+pub mod lockfile;
+pub mod messages;
 pub mod pipeline;
+pub mod registry;
 pub mod remote;
+pub mod vendor;
 pub mod vfs;
-pub use vfs::{FileMetadata, MemoryVfs, NotebookVfs, OsVfs, Vfs};
+pub use document_tree::DocumentTree;
+pub use messages::MessageCatalog;
+pub use vfs::{FileMetadata, MemoryVfs, NotebookVfs, OsVfs, Vfs, VirtualPath};
 
 pub type Result<T> = anyhow::Result<T>;
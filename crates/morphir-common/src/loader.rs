@@ -1,10 +1,25 @@
+use crate::document_tree::DocumentTree;
 use crate::remote::{RemoteSource, RemoteSourceResolver, ResolveOptions};
-use crate::vfs::{OsVfs, Vfs};
+use crate::vfs::{OsVfs, Vfs, VirtualPath};
 use anyhow::{Context, Result};
 use indexmap::IndexMap;
+use morphir_core::converter;
 use morphir_core::ir::{classic, v4};
-use morphir_core::naming::PackageName;
-use std::path::Path;
+use morphir_core::naming::{self, PackageName};
+use std::path::{Path, PathBuf};
+
+pub mod lazy;
+
+/// Name of the decorations file, after the IR file's own name
+const DECORATIONS_FILE: &str = "decorations.json";
+
+/// Name of the source map file, after the IR file's own name
+const SOURCE_MAP_FILE: &str = "sourcemap.json";
+
+/// Name of the refactor log file, after the IR file's own name
+const REFACTOR_LOG_FILE: &str = "refactors.json";
+
+pub use lazy::LazyDistribution;
 
 #[derive(Debug)]
 pub enum LoadedDistribution {
@@ -45,14 +60,31 @@ pub fn load_distribution_from_source_with_options(
     let remote_source =
         RemoteSource::parse(source).map_err(|e| anyhow::anyhow!("Invalid source: {}", e))?;
 
+    // A vendored copy recorded in the lockfile stands in for the remote source
+    let cwd = std::env::current_dir().ok();
+    let vendored = cwd
+        .as_ref()
+        .and_then(|dir| crate::lockfile::find_vendored(dir, &remote_source));
+
     let local_path = if remote_source.is_local() {
         std::path::PathBuf::from(source)
+    } else if let Some(vendored) = vendored {
+        vendored
     } else {
         let mut resolver = RemoteSourceResolver::with_defaults()
             .map_err(|e| anyhow::anyhow!("Failed to create source resolver: {}", e))?;
 
+        // Enforce a digest pinned in the source string or the lockfile
+        let sha256 = options
+            .sha256
+            .clone()
+            .or_else(|| crate::remote::integrity::split_digest(source).1)
+            .or_else(|| {
+                cwd.as_ref()
+                    .and_then(|dir| crate::lockfile::find_locked_digest(dir, &remote_source))
+            });
         resolver
-            .resolve(&remote_source, options)
+            .resolve(&remote_source, &options.clone().with_sha256(sha256))
             .map_err(|e| anyhow::anyhow!("Failed to resolve source: {}", e))?
     };
 
@@ -62,21 +94,22 @@ pub fn load_distribution_from_source_with_options(
 
 pub fn load_distribution(vfs: &impl Vfs, path: &Path) -> Result<LoadedDistribution> {
     if vfs.is_dir(path) {
+        let tree = DocumentTree::new(vfs, path);
+        if tree.exists() {
+            return Ok(LoadedDistribution::V4(tree.read()?));
+        }
         return load_v4_from_dir(vfs, path);
     }
 
     let content = vfs.read_to_string(path)?;
+    // Room for the words of a large file up front, rather than growing the
+    // interner as they arrive
+    naming::presize_for_document(content.len());
 
-    if let Ok(ir_file) = serde_json::from_str::<v4::IRFile>(&content) {
-        // Check if it's a V4 format based on format_version
-        let is_v4 = match &ir_file.format_version {
-            v4::FormatVersion::Integer(n) => *n >= 4,
-            v4::FormatVersion::String(s) => s.starts_with("4"),
-        };
-
-        if is_v4 {
-            return Ok(LoadedDistribution::V4(ir_file));
-        }
+    if let Ok(ir_file) = serde_json::from_str::<v4::IRFile>(&content)
+        && is_v4(&ir_file.format_version)
+    {
+        return Ok(LoadedDistribution::V4(ir_file));
     }
 
     let classic_dist: classic::Distribution = serde_json::from_str(&content)
@@ -85,21 +118,35 @@ pub fn load_distribution(vfs: &impl Vfs, path: &Path) -> Result<LoadedDistributi
     Ok(LoadedDistribution::Classic(classic_dist))
 }
 
+/// Whether `version` is that of a V4 distribution
+fn is_v4(version: &v4::FormatVersion) -> bool {
+    match version {
+        v4::FormatVersion::Integer(n) => *n >= 4,
+        v4::FormatVersion::String(s) => s.starts_with("4"),
+    }
+}
+
 fn load_v4_from_dir(vfs: &impl Vfs, path: &Path) -> Result<LoadedDistribution> {
-    // Read morphir.json from the directory root to get package name
+    // Read morphir.json from the directory root to get package name and, for
+    // applications, the entry points
     let morphir_json_path = path.join("morphir.json");
-    let package_name = if vfs.exists(&morphir_json_path) {
+    let config: serde_json::Value = if vfs.exists(&morphir_json_path) {
         let content = vfs.read_to_string(&morphir_json_path)?;
-        let config: serde_json::Value =
-            serde_json::from_str(&content).context("Failed to parse morphir.json")?;
-        config
-            .get("name")
-            .and_then(|n| n.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "unknown-package".to_string())
+        serde_json::from_str(&content).context("Failed to parse morphir.json")?
     } else {
-        "unknown-package".to_string()
+        serde_json::Value::Null
     };
+    let package_name = config
+        .get("name")
+        .and_then(|n| n.as_str())
+        .unwrap_or("unknown-package")
+        .to_string();
+    let entry_points: Option<v4::EntryPoints> = config
+        .get("entryPoints")
+        .cloned()
+        .map(serde_json::from_value)
+        .transpose()
+        .context("Failed to parse entryPoints in morphir.json")?;
 
     // Scan for module JSON files in src/ directory
     let src_path = path.join("src");
@@ -121,36 +168,169 @@ fn load_v4_from_dir(vfs: &impl Vfs, path: &Path) -> Result<LoadedDistribution> {
             }
 
             // Derive module name from path: src/Test/Module.json -> Test.Module
-            let relative = file_path.strip_prefix("src/").unwrap_or(&file_path);
+            let file_path = VirtualPath::from_path(&file_path);
+            let relative = file_path
+                .strip_prefix(&VirtualPath::new("src"))
+                .unwrap_or_else(|| file_path.clone());
             let module_name = relative
                 .with_extension("")
-                .to_string_lossy()
-                .replace('/', ".");
-
-            let module_def = v4::AccessControlled {
-                access: v4::Access::Public,
-                value: v4::ModuleDefinition {
-                    types: IndexMap::new(),
-                    values: IndexMap::new(),
-                    doc: None,
-                },
-            };
+                .segments()
+                .collect::<Vec<_>>()
+                .join(".");
+
+            let module_def = v4::AccessControlled::public(v4::ModuleDefinition {
+                types: IndexMap::new(),
+                values: IndexMap::new(),
+                doc: None,
+                ids: Default::default(),
+            });
             modules.insert(module_name, module_def);
         }
     }
 
     let ir_file = v4::IRFile {
         format_version: v4::FormatVersion::default(),
-        distribution: v4::Distribution::Library(v4::LibraryContent {
-            package_name: PackageName::parse(&package_name),
-            dependencies: IndexMap::new(),
-            def: v4::PackageDefinition { modules },
-        }),
+        distribution: match entry_points {
+            Some(entry_points) => v4::Distribution::Application(v4::ApplicationContent {
+                package_name: PackageName::parse(&package_name),
+                dependencies: IndexMap::new(),
+                def: v4::PackageDefinition { modules },
+                entry_points,
+            }),
+            None => v4::Distribution::Library(v4::LibraryContent {
+                package_name: PackageName::parse(&package_name),
+                dependencies: IndexMap::new(),
+                def: v4::PackageDefinition { modules },
+            }),
+        },
     };
 
     Ok(LoadedDistribution::V4(ir_file))
 }
 
+/// Load each dependency source and record its specification as a dependency
+/// of `distribution`.
+///
+/// Classic dependencies are converted to V4 first; only the public surface
+/// of each dependency is kept.
+pub fn attach_dependencies(distribution: &mut v4::Distribution, sources: &[String]) -> Result<()> {
+    for source in sources {
+        let dependency = match load_distribution_from_source(source)
+            .with_context(|| format!("Failed to load dependency {}", source))?
+        {
+            LoadedDistribution::V4(ir_file) => ir_file.distribution,
+            LoadedDistribution::Classic(dist) => converter::classic_to_v4(&dist).ir.distribution,
+        };
+        distribution.add_dependency(&dependency);
+    }
+    Ok(())
+}
+
+/// File holding the decorations of the IR at `path`: `<name>.decorations.json`
+/// next to an IR file, or `decorations.json` inside an IR directory
+pub fn decorations_path(vfs: &impl Vfs, path: &Path) -> PathBuf {
+    side_file(vfs, path, DECORATIONS_FILE)
+}
+
+/// File `name` belonging to the IR at `path`: `<stem>.<name>` next to an IR
+/// file, or `name` inside an IR directory
+fn side_file(vfs: &impl Vfs, path: &Path, name: &str) -> PathBuf {
+    if vfs.is_dir(path) {
+        return path.join(name);
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.{}", stem, name))
+}
+
+/// Load the decorations of the IR at `path`, if it has any
+pub fn load_decorations(vfs: &impl Vfs, path: &Path) -> Result<Option<v4::Decorations>> {
+    let decorations_path = decorations_path(vfs, path);
+    if !vfs.exists(&decorations_path) {
+        return Ok(None);
+    }
+    let content = vfs.read_to_string(&decorations_path)?;
+    let decorations = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", decorations_path.display()))?;
+    Ok(Some(decorations))
+}
+
+/// Write `decorations` next to the IR at `path`, returning the file written
+pub fn save_decorations(
+    vfs: &impl Vfs,
+    path: &Path,
+    decorations: &v4::Decorations,
+) -> Result<PathBuf> {
+    let decorations_path = decorations_path(vfs, path);
+    let content = serde_json::to_string_pretty(decorations)?;
+    vfs.write_from_string(&decorations_path, &content)?;
+    Ok(decorations_path)
+}
+
+/// File holding the source map of the IR at `path`: `<name>.sourcemap.json`
+/// next to an IR file, or `sourcemap.json` inside an IR directory
+pub fn source_map_path(vfs: &impl Vfs, path: &Path) -> PathBuf {
+    side_file(vfs, path, SOURCE_MAP_FILE)
+}
+
+/// Load the source map of the IR at `path`, if it has one
+pub fn load_source_map(vfs: &impl Vfs, path: &Path) -> Result<Option<v4::SourceMap>> {
+    let source_map_path = source_map_path(vfs, path);
+    if !vfs.exists(&source_map_path) {
+        return Ok(None);
+    }
+    let content = vfs.read_to_string(&source_map_path)?;
+    let source_map = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", source_map_path.display()))?;
+    Ok(Some(source_map))
+}
+
+/// Write `source_map` next to the IR at `path`, returning the file written.
+///
+/// Written without indentation, as source maps are read by tools rather
+/// than people.
+pub fn save_source_map(vfs: &impl Vfs, path: &Path, source_map: &v4::SourceMap) -> Result<PathBuf> {
+    let source_map_path = source_map_path(vfs, path);
+    let content = serde_json::to_string(source_map)?;
+    vfs.write_from_string(&source_map_path, &content)?;
+    Ok(source_map_path)
+}
+
+/// File holding the refactor log of the IR at `path`: `<name>.refactors.json`
+/// next to an IR file, or `refactors.json` inside an IR directory
+pub fn refactor_log_path(vfs: &impl Vfs, path: &Path) -> PathBuf {
+    side_file(vfs, path, REFACTOR_LOG_FILE)
+}
+
+/// Load the refactor log of the IR at `path`, empty if it has none
+pub fn load_refactor_log(vfs: &impl Vfs, path: &Path) -> Result<v4::RefactorLog> {
+    let log_path = refactor_log_path(vfs, path);
+    if !vfs.exists(&log_path) {
+        return Ok(v4::RefactorLog::default());
+    }
+    let content = vfs.read_to_string(&log_path)?;
+    let log = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", log_path.display()))?;
+    Ok(log)
+}
+
+/// Add `transaction` to the refactor log of the IR at `path`, returning the
+/// file written
+pub fn record_refactor(
+    vfs: &impl Vfs,
+    path: &Path,
+    transaction: v4::RefactorTransaction,
+) -> Result<PathBuf> {
+    let mut log = load_refactor_log(vfs, path)?;
+    log.record(transaction);
+    let log_path = refactor_log_path(vfs, path);
+    let content = serde_json::to_string_pretty(&log)?;
+    vfs.write_from_string(&log_path, &content)?;
+    Ok(log_path)
+}
+
 /// Load IR from a path and return as JSON value
 /// This is a convenience function for commands that need IR as JSON
 pub fn load_ir(path: &Path) -> Result<serde_json::Value> {
@@ -166,3 +346,157 @@ pub fn load_ir(path: &Path) -> Result<serde_json::Value> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryVfs;
+
+    #[test]
+    fn test_module_names_do_not_depend_on_path_separators() {
+        let vfs = MemoryVfs::new();
+        vfs.write_from_string(Path::new(r"src\Test\Module.json"), "{}")
+            .unwrap();
+        vfs.write_from_string(Path::new("src/Other/Module.json"), "{}")
+            .unwrap();
+
+        let LoadedDistribution::V4(ir_file) = load_v4_from_dir(&vfs, Path::new(".")).unwrap()
+        else {
+            panic!("Expected V4 distribution");
+        };
+        let v4::Distribution::Library(lib) = ir_file.distribution else {
+            panic!("Expected library distribution");
+        };
+        let mut names: Vec<_> = lib.def.modules.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, vec!["Other.Module", "Test.Module"]);
+    }
+
+    #[test]
+    fn test_entry_points_make_an_application() {
+        let vfs = MemoryVfs::new();
+        vfs.write_from_string(
+            Path::new("morphir.json"),
+            r#"{"name": "my/app", "entryPoints": {"main": {"target": "my/app:main#run", "kind": "main"}}}"#,
+        )
+        .unwrap();
+        vfs.write_from_string(Path::new("src/Main.json"), "{}")
+            .unwrap();
+
+        let LoadedDistribution::V4(ir_file) = load_v4_from_dir(&vfs, Path::new(".")).unwrap()
+        else {
+            panic!("Expected V4 distribution");
+        };
+        let v4::Distribution::Application(app) = ir_file.distribution else {
+            panic!("Expected application distribution");
+        };
+        assert_eq!(app.entry_points["main"].target, "my/app:main#run");
+        assert!(app.def.modules.contains_key("Main"));
+    }
+
+    #[test]
+    fn test_decorations_sit_next_to_the_ir() {
+        let vfs = MemoryVfs::new();
+        assert_eq!(
+            decorations_path(&vfs, Path::new("out/morphir-ir.json")),
+            Path::new("out/morphir-ir.decorations.json")
+        );
+        assert!(
+            load_decorations(&vfs, Path::new("morphir-ir.json"))
+                .unwrap()
+                .is_none()
+        );
+
+        let mut decorations = v4::Decorations::default();
+        let target = v4::DecorationTarget::Module(
+            morphir_core::naming::Path::new("my/app"),
+            morphir_core::naming::Path::new("main"),
+        );
+        decorations.attach("owner", &target, serde_json::json!("ops"));
+        let written = save_decorations(&vfs, Path::new("morphir-ir.json"), &decorations).unwrap();
+        assert_eq!(written, Path::new("morphir-ir.decorations.json"));
+        assert_eq!(
+            load_decorations(&vfs, Path::new("morphir-ir.json")).unwrap(),
+            Some(decorations)
+        );
+    }
+
+    #[test]
+    fn test_source_map_sits_next_to_the_ir() {
+        let vfs = MemoryVfs::new();
+        vfs.write_from_string(Path::new("out/morphir-ir.json"), "{}")
+            .unwrap();
+        assert_eq!(
+            source_map_path(&vfs, Path::new("out")),
+            Path::new("out/sourcemap.json")
+        );
+        assert!(
+            load_source_map(&vfs, Path::new("morphir-ir.json"))
+                .unwrap()
+                .is_none()
+        );
+
+        let mut source_map = v4::SourceMap::default();
+        source_map.record_value(
+            &morphir_core::naming::FQName::from_canonical_string("my/app:main#run").unwrap(),
+            &v4::NodePath::root(),
+            &v4::SourceLocation::new(1, 1, 3, 12).in_file("src/main.elm"),
+        );
+        let written = save_source_map(&vfs, Path::new("morphir-ir.json"), &source_map).unwrap();
+        assert_eq!(written, Path::new("morphir-ir.sourcemap.json"));
+        assert_eq!(
+            load_source_map(&vfs, Path::new("morphir-ir.json")).unwrap(),
+            Some(source_map)
+        );
+    }
+
+    #[test]
+    fn test_refactors_are_appended_to_the_log() {
+        let vfs = MemoryVfs::new();
+        let path = Path::new("morphir-ir.json");
+        assert!(
+            load_refactor_log(&vfs, path)
+                .unwrap()
+                .transactions
+                .is_empty()
+        );
+
+        let first = v4::RefactorTransaction::new();
+        let second = v4::RefactorTransaction::new();
+        record_refactor(&vfs, path, first.clone()).unwrap();
+        let written = record_refactor(&vfs, path, second.clone()).unwrap();
+        assert_eq!(written, Path::new("morphir-ir.refactors.json"));
+        assert_eq!(
+            load_refactor_log(&vfs, path).unwrap().transactions,
+            vec![first, second]
+        );
+    }
+
+    #[test]
+    fn test_attach_dependencies_keeps_specifications() {
+        let dir = tempfile::tempdir().unwrap();
+        let dep_path = dir.path().join("sdk.json");
+        std::fs::write(
+            &dep_path,
+            r#"{"formatVersion": "4.0.0", "distribution": {"Library": {
+                "packageName": "morphir/sdk",
+                "dependencies": {},
+                "def": {"modules": {"basics": {"access": "Public", "value": {"types": {}, "values": {}}}}}
+            }}}"#,
+        )
+        .unwrap();
+
+        let mut distribution = v4::Distribution::Library(v4::LibraryContent {
+            package_name: PackageName::parse("my/app"),
+            dependencies: IndexMap::new(),
+            def: v4::PackageDefinition {
+                modules: IndexMap::new(),
+            },
+        });
+        attach_dependencies(&mut distribution, &[dep_path.display().to_string()]).unwrap();
+
+        let spec = &distribution.dependencies()["morphir/sdk"];
+        assert!(spec.modules.contains_key("basics"));
+        assert!(attach_dependencies(&mut distribution, &["missing.json".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,440 @@
+//! Lazy loading of large V4 distributions
+//!
+//! [`load_distribution`](super::load_distribution) deserializes a whole
+//! distribution at once, which for IR files of hundreds of megabytes takes
+//! several times their size in memory. [`LazyDistribution`] instead indexes
+//! where each module is stored and deserializes a module only when it is
+//! asked for:
+//!
+//! - In a single JSON file, modules are indexed by the byte range of their
+//!   definition, found by scanning the file once without building it.
+//! - In a document tree, modules are indexed by the directory holding their
+//!   `module.json` manifest and their `types/` and `values/` files.
+//!
+//! Tools then walk [`LazyDistribution::modules`], holding one module in
+//! memory at a time.
+
+use crate::document_tree::DocumentTree;
+use crate::vfs::OsVfs;
+use anyhow::{Context, Result, anyhow, bail};
+use indexmap::IndexMap;
+use morphir_core::ir::v4;
+use serde::de::DeserializeOwned;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Where a module's definition is stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ModuleLocation {
+    /// Byte range of the module in the distribution file
+    Span { start: u64, len: u64 },
+    /// Directory of the module in a document tree
+    Dir(PathBuf),
+}
+
+/// A V4 distribution whose modules are read on demand
+#[derive(Debug, Clone)]
+pub struct LazyDistribution {
+    path: PathBuf,
+    format_version: v4::FormatVersion,
+    kind: String,
+    package_name: String,
+    modules: IndexMap<String, ModuleLocation>,
+}
+
+impl LazyDistribution {
+    /// Index the distribution at `path`: a V4 JSON file, or a document tree
+    /// with a `format.json` at its root.
+    pub fn open(path: &Path) -> Result<Self> {
+        if path.is_dir() {
+            return Self::open_tree(path);
+        }
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut scanner = Scanner::new(BufReader::new(file));
+        let mut distribution = Self {
+            path: path.to_path_buf(),
+            format_version: v4::FormatVersion::default(),
+            kind: String::new(),
+            package_name: String::new(),
+            modules: IndexMap::new(),
+        };
+        distribution
+            .index_file(&mut scanner)
+            .with_context(|| format!("Failed to index {}", path.display()))?;
+        Ok(distribution)
+    }
+
+    fn open_tree(root: &Path) -> Result<Self> {
+        let tree = DocumentTree::new(&OsVfs, root);
+        let format = tree.read_format()?;
+        let modules = tree
+            .module_dirs(&format.package_name)?
+            .into_iter()
+            .map(|(name, dir)| (name, ModuleLocation::Dir(dir)))
+            .collect();
+        Ok(Self {
+            path: root.to_path_buf(),
+            format_version: format.format_version,
+            kind: format.distribution,
+            package_name: format.package_name,
+            modules,
+        })
+    }
+
+    /// Format version the distribution declares
+    pub fn format_version(&self) -> &v4::FormatVersion {
+        &self.format_version
+    }
+
+    /// Kind of distribution: `Library` or `Application`
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Name of the package the distribution defines
+    pub fn package_name(&self) -> &str {
+        &self.package_name
+    }
+
+    /// Names of the modules, in the order they are stored
+    pub fn module_names(&self) -> impl Iterator<Item = &str> {
+        self.modules.keys().map(String::as_str)
+    }
+
+    /// Number of modules
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// Whether the distribution has no modules
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Read the module `name`, or `None` when there is no such module
+    pub fn load_module(
+        &self,
+        name: &str,
+    ) -> Result<Option<v4::AccessControlled<v4::ModuleDefinition>>> {
+        let Some(location) = self.modules.get(name) else {
+            return Ok(None);
+        };
+        let module = match location {
+            ModuleLocation::Span { start, len } => self.read_span(*start, *len),
+            ModuleLocation::Dir(dir) => DocumentTree::new(&OsVfs, &self.path).read_module(dir),
+        };
+        module
+            .map(Some)
+            .with_context(|| format!("Failed to load module {}", name))
+    }
+
+    /// Every module in turn, each read as the iterator reaches it
+    pub fn modules(
+        &self,
+    ) -> impl Iterator<Item = Result<(&str, v4::AccessControlled<v4::ModuleDefinition>)>> {
+        self.module_names().map(|name| {
+            let module = self
+                .load_module(name)?
+                .ok_or_else(|| anyhow!("Module {} disappeared", name))?;
+            Ok((name, module))
+        })
+    }
+
+    fn read_span<T: DeserializeOwned>(&self, start: u64, len: u64) -> Result<T> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(start))?;
+        let mut bytes = vec![0; len as usize];
+        file.read_exact(&mut bytes)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Record where each module of the V4 file read by `scanner` starts and ends
+    fn index_file<R: BufRead>(&mut self, scanner: &mut Scanner<R>) -> Result<()> {
+        scanner.expect(b'{')?;
+        let mut found = false;
+        while let Some(key) = scanner.next_key()? {
+            match key.as_str() {
+                "formatVersion" => {
+                    self.format_version = serde_json::from_slice(&scanner.capture_value()?)?;
+                    if !super::is_v4(&self.format_version) {
+                        bail!("Only V4 distributions can be loaded lazily");
+                    }
+                }
+                "distribution" => {
+                    self.index_distribution(scanner)?;
+                    found = true;
+                }
+                _ => scanner.skip_value()?,
+            }
+        }
+        if !found {
+            bail!("No distribution found");
+        }
+        Ok(())
+    }
+
+    fn index_distribution<R: BufRead>(&mut self, scanner: &mut Scanner<R>) -> Result<()> {
+        scanner.expect(b'{')?;
+        while let Some(kind) = scanner.next_key()? {
+            if kind != "Library" && kind != "Application" {
+                bail!("{} distributions have no module definitions", kind);
+            }
+            self.kind = kind;
+            scanner.expect(b'{')?;
+            while let Some(key) = scanner.next_key()? {
+                match key.as_str() {
+                    "packageName" => {
+                        self.package_name = serde_json::from_slice(&scanner.capture_value()?)?;
+                    }
+                    "def" => self.index_package(scanner)?,
+                    _ => scanner.skip_value()?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn index_package<R: BufRead>(&mut self, scanner: &mut Scanner<R>) -> Result<()> {
+        scanner.expect(b'{')?;
+        while let Some(key) = scanner.next_key()? {
+            if key != "modules" {
+                scanner.skip_value()?;
+                continue;
+            }
+            scanner.expect(b'{')?;
+            while let Some(name) = scanner.next_key()? {
+                let start = scanner.offset;
+                scanner.skip_value()?;
+                let len = scanner.offset - start;
+                self.modules
+                    .insert(name, ModuleLocation::Span { start, len });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the structure of a JSON document, keeping track of the byte offset,
+/// without building the values it skips
+struct Scanner<R> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R: BufRead> Scanner<R> {
+    fn new(reader: R) -> Self {
+        Self { reader, offset: 0 }
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    fn bump(&mut self) {
+        self.reader.consume(1);
+        self.offset += 1;
+    }
+
+    fn next(&mut self) -> Result<u8> {
+        let byte = self
+            .peek()?
+            .ok_or_else(|| anyhow!("Unexpected end of input at byte {}", self.offset))?;
+        self.bump();
+        Ok(byte)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<Option<u8>> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.bump();
+        }
+        Ok(None)
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        self.skip_whitespace()?;
+        let offset = self.offset;
+        let byte = self.next()?;
+        if byte != expected {
+            bail!(
+                "Expected '{}' at byte {}, found '{}'",
+                expected as char,
+                offset,
+                byte as char
+            );
+        }
+        Ok(())
+    }
+
+    /// Key of the next member of the object being read, leaving the scanner
+    /// at its value, or `None` once the object is closed
+    fn next_key(&mut self) -> Result<Option<String>> {
+        if self.skip_whitespace()? == Some(b',') {
+            self.bump();
+        }
+        if self.skip_whitespace()? == Some(b'}') {
+            self.bump();
+            return Ok(None);
+        }
+        let key = serde_json::from_slice(&self.capture_value()?)?;
+        self.expect(b':')?;
+        self.skip_whitespace()?;
+        Ok(Some(key))
+    }
+
+    /// Skip the rest of a string whose opening quote was read
+    fn skip_string(&mut self, mut out: Option<&mut Vec<u8>>) -> Result<()> {
+        loop {
+            let byte = self.next()?;
+            if let Some(out) = out.as_deref_mut() {
+                out.push(byte);
+            }
+            match byte {
+                b'"' => return Ok(()),
+                b'\\' => {
+                    let escaped = self.next()?;
+                    if let Some(out) = out.as_deref_mut() {
+                        out.push(escaped);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Result<()> {
+        self.read_value(None)
+    }
+
+    /// Bytes of the next value, for the small values that are kept
+    fn capture_value(&mut self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.read_value(Some(&mut bytes))?;
+        Ok(bytes)
+    }
+
+    fn read_value(&mut self, mut out: Option<&mut Vec<u8>>) -> Result<()> {
+        self.skip_whitespace()?;
+        let mut depth = 0usize;
+        loop {
+            let byte = self
+                .peek()?
+                .ok_or_else(|| anyhow!("Unexpected end of input at byte {}", self.offset))?;
+            match byte {
+                b',' | b':' | b'}' | b']' if depth == 0 => return Ok(()),
+                byte if byte.is_ascii_whitespace() && depth == 0 => return Ok(()),
+                _ => {}
+            }
+            self.bump();
+            if let Some(out) = out.as_deref_mut() {
+                out.push(byte);
+            }
+            match byte {
+                b'"' => self.skip_string(out.as_deref_mut())?,
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 && matches!(byte, b'"' | b'}' | b']') {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY: &str = r#"{
+        "formatVersion": "4.0.0",
+        "distribution": {"Library": {
+            "packageName": "my-org/shop",
+            "dependencies": {},
+            "def": {"modules": {
+                "orders": {"access": "Public", "value": {"types": {}, "values": {}, "doc": "Orders, \"quoted\" {not a brace}"}},
+                "pricing": {"access": "Private", "value": {"types": {}, "values": {}}}
+            }}
+        }}
+    }"#;
+
+    #[test]
+    fn test_modules_of_a_file_load_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("morphir-ir.json");
+        std::fs::write(&path, LIBRARY).unwrap();
+
+        let lazy = LazyDistribution::open(&path).unwrap();
+        assert_eq!(lazy.package_name(), "my-org/shop");
+        assert_eq!(lazy.kind(), "Library");
+        assert_eq!(
+            lazy.module_names().collect::<Vec<_>>(),
+            ["orders", "pricing"]
+        );
+
+        let orders = lazy.load_module("orders").unwrap().unwrap();
+        assert_eq!(
+            orders.value.doc.as_deref(),
+            Some("Orders, \"quoted\" {not a brace}")
+        );
+        assert!(lazy.load_module("nope").unwrap().is_none());
+
+        // Each module matches what the eager loader reads
+        let super::super::LoadedDistribution::V4(ir_file) =
+            super::super::load_distribution(&crate::vfs::OsVfs, &path).unwrap()
+        else {
+            panic!("Expected V4 distribution");
+        };
+        let modules = &ir_file.distribution.definition().unwrap().modules;
+        for entry in lazy.modules() {
+            let (name, module) = entry.unwrap();
+            assert_eq!(&module, &modules[name]);
+        }
+
+        std::fs::write(
+            &path,
+            r#"{"formatVersion": 3, "distribution": ["Library"]}"#,
+        )
+        .unwrap();
+        assert!(LazyDistribution::open(&path).is_err());
+    }
+
+    #[test]
+    fn test_modules_of_a_document_tree_load_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("format.json"),
+            r#"{"formatVersion": "4.0.0", "distribution": "Library", "packageName": "my-org/shop", "layout": "VfsMode"}"#,
+        )
+        .unwrap();
+        let module_dir = root.join(".morphir-dist/pkg/my-org/shop/orders");
+        std::fs::create_dir_all(module_dir.join("values")).unwrap();
+        std::fs::write(
+            module_dir.join("module.json"),
+            r#"{"module": "orders", "doc": "Orders", "types": [], "values": ["total"]}"#,
+        )
+        .unwrap();
+        let total = v4::AccessControlled::public(v4::ValueDefinition::new(
+            vec![],
+            v4::Type::Unit(Default::default()),
+            v4::Value::Literal(Default::default(), v4::Literal::Integer(1)),
+        ));
+        std::fs::write(
+            module_dir.join("values/total.value.json"),
+            serde_json::to_string(&total).unwrap(),
+        )
+        .unwrap();
+
+        let lazy = LazyDistribution::open(root).unwrap();
+        assert_eq!(lazy.package_name(), "my-org/shop");
+        assert_eq!(lazy.len(), 1);
+        let orders = lazy.load_module("orders").unwrap().unwrap();
+        assert_eq!(orders.value.doc.as_deref(), Some("Orders"));
+        assert_eq!(orders.value.values["total"], total);
+    }
+}
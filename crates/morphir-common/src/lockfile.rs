@@ -0,0 +1,189 @@
+//! The workspace lockfile, `morphir.lock`
+//!
+//! Records where each remote dependency, and optionally each extension, was
+//! resolved from. Once `morphir deps vendor` has copied them into the
+//! workspace, each entry also carries the vendored path, relative to the
+//! lockfile, and loading a locked source uses the vendored copy instead of the
+//! network. Each entry also records the SHA-256 digest of what was resolved,
+//! and later resolutions of the same source must match it.
+
+use crate::Result;
+use crate::remote::RemoteSource;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the lockfile, at the root of a workspace or project
+pub const LOCKFILE_NAME: &str = "morphir.lock";
+
+/// Format of the lockfile written by this version
+pub const LOCKFILE_VERSION: u32 = 1;
+
+const HEADER: &str = "# Generated by `morphir deps vendor`. Do not edit by hand.\n\n";
+
+/// Contents of `morphir.lock`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    /// Locked dependencies, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, LockedSource>,
+    /// Locked extensions, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, LockedSource>,
+}
+
+/// A resolved source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedSource {
+    /// Source it was resolved from, as a source string
+    pub source: String,
+    /// Vendored copy, relative to the lockfile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// SHA-256 digest of the resolved content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            dependencies: BTreeMap::new(),
+            extensions: BTreeMap::new(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Load the lockfile in `dir`; empty if there is none
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(LOCKFILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let lockfile: Self = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        if lockfile.version > LOCKFILE_VERSION {
+            anyhow::bail!(
+                "{} has format version {}; this morphir reads up to {}",
+                path.display(),
+                lockfile.version,
+                LOCKFILE_VERSION
+            );
+        }
+        Ok(lockfile)
+    }
+
+    /// Write the lockfile into `dir`
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(LOCKFILE_NAME);
+        let content = toml::to_string(self).context("Failed to serialize lockfile")?;
+        std::fs::write(&path, format!("{}{}", HEADER, content))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// The nearest lockfile at or above `start`, with its directory
+    pub fn discover(start: &Path) -> Option<(PathBuf, Self)> {
+        start
+            .ancestors()
+            .find(|dir| dir.join(LOCKFILE_NAME).is_file())
+            .and_then(|dir| Some((dir.to_path_buf(), Self::load(dir).ok()?)))
+    }
+
+    /// The vendored copy of the dependency resolved from `source`, for a
+    /// lockfile in `dir`, if it is present
+    pub fn vendored(&self, dir: &Path, source: &RemoteSource) -> Option<PathBuf> {
+        self.dependencies
+            .values()
+            .filter(|locked| RemoteSource::parse(&locked.source).ok().as_ref() == Some(source))
+            .find_map(|locked| locked.path.as_ref())
+            .map(|path| dir.join(path))
+            .filter(|path| path.exists())
+    }
+
+    /// The digest recorded for the dependency or extension resolved from
+    /// `source`
+    pub fn locked_digest(&self, source: &RemoteSource) -> Option<String> {
+        self.dependencies
+            .values()
+            .chain(self.extensions.values())
+            .filter(|locked| RemoteSource::parse(&locked.source).ok().as_ref() == Some(source))
+            .find_map(|locked| locked.sha256.clone())
+    }
+}
+
+/// The vendored copy of `source` recorded in the nearest lockfile at or
+/// above `start`, if any
+pub fn find_vendored(start: &Path, source: &RemoteSource) -> Option<PathBuf> {
+    let (dir, lockfile) = Lockfile::discover(start)?;
+    lockfile.vendored(&dir, source)
+}
+
+/// The digest of `source` recorded in the nearest lockfile at or above
+/// `start`, if any
+pub fn find_locked_digest(start: &Path, source: &RemoteSource) -> Option<String> {
+    Lockfile::discover(start)?.1.locked_digest(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lockfile_round_trips_and_finds_vendored_sources() {
+        let temp = tempfile::tempdir().unwrap();
+        let vendored = temp.path().join("vendor").join("finos-sdk");
+        std::fs::create_dir_all(&vendored).unwrap();
+        let mut lockfile = Lockfile::default();
+        lockfile.dependencies.insert(
+            "finos/sdk".to_string(),
+            LockedSource {
+                source: "github:finos/morphir-sdk@v1.0.0".to_string(),
+                path: Some(PathBuf::from("vendor/finos-sdk")),
+                sha256: Some("44136fa3".to_string()),
+            },
+        );
+        lockfile.dependencies.insert(
+            "finos/missing".to_string(),
+            LockedSource {
+                source: "https://example.com/ir.json".to_string(),
+                path: Some(PathBuf::from("vendor/finos-missing")),
+                sha256: None,
+            },
+        );
+        lockfile.save(temp.path()).unwrap();
+        let content = std::fs::read_to_string(temp.path().join(LOCKFILE_NAME)).unwrap();
+        assert!(content.starts_with("# Generated by `morphir deps vendor`"));
+        assert_eq!(Lockfile::load(temp.path()).unwrap(), lockfile);
+
+        let nested = temp.path().join("packages").join("core");
+        std::fs::create_dir_all(&nested).unwrap();
+        let sdk = RemoteSource::parse("github:finos/morphir-sdk@v1.0.0").unwrap();
+        assert_eq!(find_vendored(&nested, &sdk), Some(vendored));
+        let other = RemoteSource::parse("github:finos/morphir-sdk@v2.0.0").unwrap();
+        assert_eq!(find_vendored(&nested, &other), None);
+        // Not yet vendored, or deleted since
+        let missing = RemoteSource::parse("https://example.com/ir.json").unwrap();
+        assert_eq!(find_vendored(&nested, &missing), None);
+        assert_eq!(
+            find_locked_digest(&nested, &sdk),
+            Some("44136fa3".to_string())
+        );
+        assert_eq!(find_locked_digest(&nested, &missing), None);
+    }
+
+    #[test]
+    fn test_newer_lockfiles_are_rejected() {
+        let temp = tempfile::tempdir().unwrap();
+        assert_eq!(Lockfile::load(temp.path()).unwrap(), Lockfile::default());
+        std::fs::write(temp.path().join(LOCKFILE_NAME), "version = 99\n").unwrap();
+        let err = Lockfile::load(temp.path()).unwrap_err();
+        assert!(err.to_string().contains("format version 99"), "{}", err);
+    }
+}
@@ -0,0 +1,285 @@
+//! Message catalogs for CLI and validator diagnostics
+//!
+//! User-facing messages are looked up by ID (e.g. `cli.extension_error`) in a
+//! [`MessageCatalog`] instead of being hard-coded, so translated or
+//! policy-reworded messages can be shipped without changing the code.
+//!
+//! The English catalog is embedded. Override catalogs are TOML files named
+//! after the locale (`fr.toml`, `fr-CA.toml`) in the directory configured by
+//! `[messages] catalog_dir`, and are read through the [`Vfs`].
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::Result;
+use crate::config::MessagesSection;
+use crate::vfs::Vfs;
+
+/// Embedded default catalog
+const DEFAULT_CATALOG: &str = include_str!("en.toml");
+
+/// Locale of the embedded catalog
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Environment variable that overrides the locale
+pub const LOCALE_ENV_VAR: &str = "MORPHIR_LOCALE";
+
+/// Message templates keyed by message ID
+#[derive(Debug, Clone)]
+pub struct MessageCatalog {
+    locale: String,
+    messages: HashMap<String, String>,
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        Self::embedded()
+    }
+}
+
+impl MessageCatalog {
+    /// The embedded English catalog
+    pub fn embedded() -> Self {
+        Self::from_toml(DEFAULT_LOCALE, DEFAULT_CATALOG).expect("embedded catalog is valid TOML")
+    }
+
+    /// Parse a catalog from TOML; nested tables become dotted message IDs
+    pub fn from_toml(locale: &str, content: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(content)?;
+        let mut messages = HashMap::new();
+        flatten(&table, "", &mut messages)?;
+        Ok(Self {
+            locale: locale.to_string(),
+            messages,
+        })
+    }
+
+    /// Load the catalog for `locale`: the embedded catalog with any overrides
+    /// from `dir` applied, least specific first (`fr.toml`, then `fr-CA.toml`).
+    pub fn load(vfs: &impl Vfs, dir: &Path, locale: &str) -> Result<Self> {
+        let mut catalog = Self::embedded();
+        catalog.locale = locale.to_string();
+
+        for candidate in locale_fallbacks(locale).into_iter().rev() {
+            let path = dir.join(format!("{}.toml", candidate));
+            if !vfs.exists(&path) {
+                continue;
+            }
+            let content = vfs.read_to_string(&path)?;
+            let overrides = Self::from_toml(&candidate, &content)
+                .with_context(|| format!("Invalid message catalog {:?}", path))?;
+            catalog.merge(overrides);
+        }
+        Ok(catalog)
+    }
+
+    /// Load the catalog selected by configuration and environment.
+    ///
+    /// Relative catalog directories are resolved against `base_dir`.
+    pub fn from_config(
+        vfs: &impl Vfs,
+        base_dir: &Path,
+        section: Option<&MessagesSection>,
+    ) -> Result<Self> {
+        let locale = detect_locale(section);
+        match section.and_then(|s| s.catalog_dir.as_ref()) {
+            Some(dir) => Self::load(vfs, &base_dir.join(dir), &locale),
+            None => {
+                let mut catalog = Self::embedded();
+                catalog.locale = locale;
+                Ok(catalog)
+            }
+        }
+    }
+
+    /// Apply overrides from another catalog
+    pub fn merge(&mut self, other: MessageCatalog) {
+        self.messages.extend(other.messages);
+    }
+
+    /// Locale this catalog was loaded for
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Get the template for a message ID
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.messages.get(id).map(String::as_str)
+    }
+
+    /// Format a message, substituting `{name}` placeholders.
+    ///
+    /// Unknown IDs format to the ID itself so a missing entry is visible
+    /// rather than silently empty.
+    pub fn format(&self, id: &str, args: &[(&str, &str)]) -> String {
+        match self.get(id) {
+            Some(template) => substitute(template, args),
+            None => id.to_string(),
+        }
+    }
+
+    /// Reword a diagnostic reported with `code`, if the catalog has an entry
+    /// for `diagnostic.<code>`. The original text is available as `{message}`.
+    pub fn diagnostic(&self, code: Option<&str>, message: &str) -> String {
+        code.and_then(|code| self.get(&format!("diagnostic.{}", code)))
+            .map(|template| substitute(template, &[("message", message)]))
+            .unwrap_or_else(|| message.to_string())
+    }
+}
+
+/// Select the locale: `[messages] locale`, then `MORPHIR_LOCALE`, then the
+/// POSIX locale variables, then [`DEFAULT_LOCALE`].
+pub fn detect_locale(section: Option<&MessagesSection>) -> String {
+    let configured = section.and_then(|s| s.locale.clone());
+    let from_env = || {
+        [LOCALE_ENV_VAR, "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+    };
+    configured
+        .or_else(from_env)
+        .and_then(|value| normalize_locale(&value))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+/// Normalize a POSIX or BCP 47 locale (`fr_CA.UTF-8`, `fr-ca`) to `fr-CA`.
+///
+/// Returns `None` for the `C`/`POSIX` locales.
+fn normalize_locale(value: &str) -> Option<String> {
+    let value = value.split(['.', '@']).next().unwrap_or_default();
+    if value.is_empty() || value == "C" || value == "POSIX" {
+        return None;
+    }
+    let mut parts = value.split(['_', '-']);
+    let language = parts.next()?.to_ascii_lowercase();
+    match parts.next() {
+        Some(region) => Some(format!("{}-{}", language, region.to_ascii_uppercase())),
+        None => Some(language),
+    }
+}
+
+/// Locales to try for `locale`, most specific first (`fr-CA`, `fr`)
+fn locale_fallbacks(locale: &str) -> Vec<String> {
+    let mut candidates = vec![locale.to_string()];
+    if let Some((language, _)) = locale.split_once('-') {
+        candidates.push(language.to_string());
+    }
+    candidates
+}
+
+fn flatten(table: &toml::Table, prefix: &str, out: &mut HashMap<String, String>) -> Result<()> {
+    for (key, value) in table {
+        let id = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::String(template) => {
+                out.insert(id, template.clone());
+            }
+            toml::Value::Table(nested) => flatten(nested, &id, out)?,
+            other => anyhow::bail!(
+                "Message '{}' must be a string, found {}",
+                id,
+                other.type_str()
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn substitute(template: &str, args: &[(&str, &str)]) -> String {
+    let mut result = template.to_string();
+    for (name, value) in args {
+        result = result.replace(&format!("{{{}}}", name), value);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryVfs;
+
+    #[test]
+    fn test_embedded_catalog() {
+        let catalog = MessageCatalog::embedded();
+        assert_eq!(
+            catalog.format("cli.extension_error", &[("message", "boom")]),
+            "Extension error: boom"
+        );
+        assert_eq!(catalog.format("cli.unknown", &[]), "cli.unknown");
+    }
+
+    #[test]
+    fn test_overrides_apply_most_specific_last() {
+        let vfs = MemoryVfs::new();
+        vfs.write_from_string(
+            Path::new("messages/fr.toml"),
+            "[cli]\nextension_error = \"Erreur d'extension : {message}\"\nconfig_error = \"Erreur de configuration\"\n",
+        )
+        .unwrap();
+        vfs.write_from_string(
+            Path::new("messages/fr-CA.toml"),
+            "[cli]\nconfig_error = \"Erreur de configuration (CA)\"\n",
+        )
+        .unwrap();
+
+        let catalog = MessageCatalog::load(&vfs, Path::new("messages"), "fr-CA").unwrap();
+        assert_eq!(catalog.locale(), "fr-CA");
+        assert_eq!(
+            catalog.format("cli.config_error", &[]),
+            "Erreur de configuration (CA)"
+        );
+        assert_eq!(
+            catalog.format("cli.extension_error", &[("message", "x")]),
+            "Erreur d'extension : x"
+        );
+        // Not overridden: falls back to the embedded message
+        assert_eq!(
+            catalog.format("cli.filesystem_error", &[]),
+            "File system error"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_rewording() {
+        let catalog = MessageCatalog::from_toml(
+            "en",
+            "[diagnostic]\nW001 = \"Policy: parse output skipped ({message})\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.diagnostic(Some("W001"), "disk full"),
+            "Policy: parse output skipped (disk full)"
+        );
+        assert_eq!(catalog.diagnostic(Some("E999"), "as is"), "as is");
+        assert_eq!(catalog.diagnostic(None, "as is"), "as is");
+    }
+
+    #[test]
+    fn test_invalid_catalog_is_rejected() {
+        assert!(MessageCatalog::from_toml("en", "[cli]\nconfig_error = 1\n").is_err());
+    }
+
+    #[test]
+    fn test_locale_normalization() {
+        assert_eq!(normalize_locale("fr_CA.UTF-8"), Some("fr-CA".to_string()));
+        assert_eq!(normalize_locale("de-de"), Some("de-DE".to_string()));
+        assert_eq!(normalize_locale("ja"), Some("ja".to_string()));
+        assert_eq!(normalize_locale("C.UTF-8"), None);
+    }
+
+    #[test]
+    fn test_configured_locale_wins() {
+        let section = MessagesSection {
+            locale: Some("es_MX".to_string()),
+            catalog_dir: None,
+        };
+        assert_eq!(detect_locale(Some(&section)), "es-MX");
+    }
+}
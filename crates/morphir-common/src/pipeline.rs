@@ -4,8 +4,10 @@
 
 use crate::Result;
 
+pub mod cache;
 pub mod decorators;
 pub mod ir;
+pub mod parallel;
 
 /// A step in a transformation pipeline.
 ///
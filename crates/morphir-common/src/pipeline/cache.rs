@@ -0,0 +1,199 @@
+//! Content-addressed cache of compiled module IR
+//!
+//! A module's IR depends only on its source, the compiler that produced it,
+//! and the options it was compiled with, so those are hashed into a
+//! [`CacheKey`]. [`IrCache`] keeps the IR files produced for each key under
+//! `.morphir/cache/ir`, letting a build restore a module it has compiled
+//! before instead of compiling it again, even after switching branches or
+//! rebuilding from scratch.
+
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Hash of everything a module's IR depends on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// Key for the source at `path` with `content`, compiled by `compiler`
+    /// (e.g. `gleam@0.2.0`) with `options`
+    pub fn new(
+        compiler: &str,
+        options: &BTreeMap<String, serde_json::Value>,
+        path: &str,
+        content: &[u8],
+    ) -> Self {
+        let mut hasher = Sha256::new();
+        // Length-prefix each part so no two inputs hash the same bytes
+        for part in [
+            compiler.as_bytes(),
+            serde_json::to_string(options)
+                .unwrap_or_default()
+                .as_bytes(),
+            path.as_bytes(),
+            content,
+        ] {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        Self(
+            hasher
+                .finalize()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+        )
+    }
+
+    /// Hex digest
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// IR produced for one source module
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedModule {
+    /// Path of the source the module was compiled from
+    pub source: String,
+    /// Files written for the module, keyed by path relative to the compile
+    /// output directory
+    pub files: BTreeMap<String, String>,
+    /// Diagnostics reported for the module, as the compiler sent them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<serde_json::Value>,
+}
+
+/// Size of the cache on disk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    /// Number of cached modules
+    pub entries: usize,
+    /// Total size of the cached modules, in bytes
+    pub bytes: u64,
+}
+
+/// Cache of module IR keyed by content hashes
+#[derive(Debug, Clone)]
+pub struct IrCache {
+    dir: PathBuf,
+}
+
+impl IrCache {
+    /// Cache stored in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Cache of the workspace whose `.morphir` directory is `morphir_dir`
+    pub fn in_morphir_dir(morphir_dir: &Path) -> Self {
+        Self::new(morphir_dir.join("cache").join("ir"))
+    }
+
+    /// Directory the cache is stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        // Fan out by the first byte so no directory grows too large
+        self.dir.join(&key.0[..2]).join(format!("{}.json", key.0))
+    }
+
+    /// The module cached under `key`. Unreadable entries count as missing.
+    pub fn get(&self, key: &CacheKey) -> Option<CachedModule> {
+        let content = std::fs::read_to_string(self.entry_path(key)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Cache `module` under `key`
+    pub fn put(&self, key: &CacheKey, module: &CachedModule) -> Result<()> {
+        let path = self.entry_path(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename, so a concurrent build never reads half an entry
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec(module)?)?;
+        std::fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    /// Number and total size of the cached modules
+    pub fn stats(&self) -> Result<CacheStats> {
+        let mut stats = CacheStats::default();
+        if !self.dir.is_dir() {
+            return Ok(stats);
+        }
+        for shard in std::fs::read_dir(&self.dir)? {
+            let shard = shard?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(&shard)? {
+                let entry = entry?;
+                if entry.path().extension().is_some_and(|ext| ext == "json") {
+                    stats.entries += 1;
+                    stats.bytes += entry.metadata()?.len();
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Remove every cached module, returning what was removed
+    pub fn clear(&self) -> Result<CacheStats> {
+        let stats = self.stats()?;
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir)?;
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(compiler: &str, strict: bool, content: &str) -> CacheKey {
+        let options = BTreeMap::from([("strict".to_string(), strict.into())]);
+        CacheKey::new(compiler, &options, "src/orders.gleam", content.as_bytes())
+    }
+
+    #[test]
+    fn test_keys_cover_compiler_options_and_content() {
+        let base = key("gleam@0.2.0", true, "pub fn total() { 1 }");
+        assert_eq!(base, key("gleam@0.2.0", true, "pub fn total() { 1 }"));
+        assert_eq!(base.as_str().len(), 64);
+        assert_ne!(base, key("gleam@0.3.0", true, "pub fn total() { 1 }"));
+        assert_ne!(base, key("gleam@0.2.0", false, "pub fn total() { 1 }"));
+        assert_ne!(base, key("gleam@0.2.0", true, "pub fn total() { 2 }"));
+    }
+
+    #[test]
+    fn test_cache_round_trips_and_clears() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = IrCache::in_morphir_dir(temp.path());
+        let key = key("gleam@0.2.0", true, "pub fn total() { 1 }");
+        assert_eq!(cache.get(&key), None);
+        assert_eq!(cache.stats().unwrap(), CacheStats::default());
+
+        let module = CachedModule {
+            source: "src/orders.gleam".to_string(),
+            files: BTreeMap::from([("src/orders/module.json".to_string(), "{}".to_string())]),
+            diagnostics: vec![],
+        };
+        cache.put(&key, &module).unwrap();
+        assert_eq!(cache.get(&key), Some(module));
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.entries, 1);
+        assert!(stats.bytes > 0);
+
+        assert_eq!(cache.clear().unwrap(), stats);
+        assert_eq!(cache.get(&key), None);
+        assert!(!temp.path().join("cache/ir").exists());
+    }
+}
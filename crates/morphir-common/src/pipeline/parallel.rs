@@ -0,0 +1,85 @@
+//! Parallel compilation
+//!
+//! Frontends compile each source file on its own, so independent files can be
+//! processed concurrently. [`map_parallel`] runs a stage over its inputs on a
+//! bounded number of threads and returns the results in input order, so
+//! diagnostics merged from them come out the same however the work was
+//! scheduled.
+
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// Option frontends read the number of parallel jobs from
+pub const JOBS_OPTION: &str = "jobs";
+
+/// Number of threads to use for `jobs`; 0 means one per available CPU
+pub fn resolve_jobs(jobs: usize) -> usize {
+    if jobs > 0 {
+        return jobs;
+    }
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Number of jobs requested in compile `options`, 0 when not set
+pub fn jobs_option(options: &HashMap<String, serde_json::Value>) -> usize {
+    options
+        .get(JOBS_OPTION)
+        .and_then(|value| value.as_u64())
+        .map_or(0, |jobs| jobs as usize)
+}
+
+/// Apply `stage` to every item on up to `jobs` threads (0 for one per CPU),
+/// returning the results in the order of `items`.
+///
+/// With a single job, or where threads cannot be spawned, the items are
+/// processed one after the other on the calling thread.
+pub fn map_parallel<T, R, F>(items: &[T], jobs: usize, stage: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    let jobs = resolve_jobs(jobs).min(items.len());
+    if jobs <= 1 {
+        return items.iter().map(stage).collect();
+    }
+    match rayon::ThreadPoolBuilder::new().num_threads(jobs).build() {
+        Ok(pool) => pool.install(|| items.par_iter().map(&stage).collect()),
+        Err(_) => items.iter().map(stage).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_results_keep_input_order() {
+        let items: Vec<u64> = (0..32).collect();
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let results = map_parallel(&items, 4, |n| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            // Later items finish first
+            std::thread::sleep(Duration::from_millis(32 - n));
+            running.fetch_sub(1, Ordering::SeqCst);
+            n * 2
+        });
+        assert_eq!(results, items.iter().map(|n| n * 2).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 4);
+        assert_eq!(map_parallel(&items, 1, |n| *n), items);
+    }
+
+    #[test]
+    fn test_jobs_option() {
+        let mut options = HashMap::new();
+        assert_eq!(jobs_option(&options), 0);
+        options.insert(JOBS_OPTION.to_string(), serde_json::json!(3));
+        assert_eq!(jobs_option(&options), 3);
+        assert_eq!(resolve_jobs(3), 3);
+        assert!(resolve_jobs(0) >= 1);
+    }
+}
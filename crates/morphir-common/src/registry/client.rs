@@ -0,0 +1,413 @@
+//! HTTP client for a Morphir package registry.
+
+use crate::registry::error::{RegistryError, Result};
+use crate::registry::{
+    PackageInfo, PackageMetadata, PackageSummary, PackageVersion, Published, REGISTRY_URL_ENV,
+    RegistryConfig, select_version,
+};
+use crate::remote::auth::{Credential, CredentialResolver};
+use crate::remote::config::RemoteSourceConfig;
+use crate::remote::integrity::digest_path;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Name of the IR file of a cached package version.
+const IR_FILE: &str = "morphir-ir.json";
+
+/// Client for one package registry.
+pub struct RegistryClient {
+    /// Base URL, without a trailing slash.
+    url: String,
+
+    /// reqwest client.
+    client: reqwest::blocking::Client,
+
+    /// Token read from `tokenEnv`, preferred over `credentials`.
+    token: Option<String>,
+
+    /// Credentials sent to the registry host.
+    credentials: CredentialResolver,
+
+    /// Root of the package cache.
+    cache_dir: PathBuf,
+
+    /// Serve packages from the cache only.
+    offline: bool,
+}
+
+/// Body of a publish request.
+#[derive(Serialize)]
+struct PublishRequest<'a> {
+    metadata: &'a PackageMetadata,
+    ir: &'a serde_json::Value,
+}
+
+/// Body of a search response.
+#[derive(Deserialize)]
+struct SearchResponse {
+    packages: Vec<PackageSummary>,
+}
+
+impl RegistryClient {
+    /// Create a client for the configured registry, falling back to
+    /// `MORPHIR_REGISTRY_URL` when no URL is configured.
+    pub fn new(config: &RegistryConfig) -> Result<Self> {
+        let url = config
+            .url
+            .clone()
+            .or_else(|| std::env::var(REGISTRY_URL_ENV).ok())
+            .filter(|url| !url.is_empty())
+            .ok_or(RegistryError::NotConfigured)?;
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .user_agent(format!("morphir-cli/{}", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| {
+                RegistryError::NetworkError(format!("Failed to create HTTP client: {}", e))
+            })?;
+
+        let cache_dir = config.cache_directory.clone().unwrap_or_else(|| {
+            dirs::cache_dir()
+                .unwrap_or_else(|| PathBuf::from(".cache"))
+                .join("morphir")
+                .join("packages")
+        });
+
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            client,
+            token: config
+                .token_env
+                .as_deref()
+                .and_then(|name| std::env::var(name).ok()),
+            credentials: CredentialResolver::default(),
+            cache_dir,
+            offline: false,
+        })
+    }
+
+    /// Create a client for a workspace's `[registry]`, authenticating with
+    /// its `[sources.auth]` credentials and honouring its offline mode.
+    pub fn for_workspace(
+        registry: Option<&RegistryConfig>,
+        sources: Option<&RemoteSourceConfig>,
+    ) -> Result<Self> {
+        let client = Self::new(registry.unwrap_or(&RegistryConfig::default()))?;
+        let sources = sources.cloned().unwrap_or_default();
+        Ok(client
+            .with_offline(sources.is_offline())
+            .with_credentials(CredentialResolver::new(sources.auth)))
+    }
+
+    /// Authenticate requests with credentials from `credentials` when no
+    /// `tokenEnv` token is set.
+    pub fn with_credentials(mut self, credentials: CredentialResolver) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Serve packages from the cache only, never the network.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Base URL of the registry.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Search the registry for packages matching `query`.
+    pub fn search(&self, query: &str) -> Result<Vec<PackageSummary>> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/api/v1/packages", self.url),
+            &[("q", query)],
+        )
+        .map_err(|e| RegistryError::NetworkError(format!("Invalid registry URL: {}", e)))?;
+        let response = self.send(self.authorize(self.client.get(url)))?;
+        let body: SearchResponse = response.json().map_err(network_error)?;
+        Ok(body.packages)
+    }
+
+    /// Metadata and published versions of `name`.
+    pub fn package(&self, name: &str) -> Result<PackageInfo> {
+        let url = format!("{}/api/v1/packages/{}", self.url, name);
+        let response = self
+            .send(self.authorize(self.client.get(&url)))
+            .map_err(|e| not_found(e, name))?;
+        response.json().map_err(network_error)
+    }
+
+    /// The newest version of `name` that satisfies `requirement`.
+    pub fn resolve_version(&self, name: &str, requirement: &str) -> Result<PackageVersion> {
+        let info = self.package(name)?;
+        select_version(&info.versions, requirement)?
+            .cloned()
+            .ok_or_else(|| RegistryError::NoMatchingVersion {
+                name: name.to_string(),
+                requirement: requirement.to_string(),
+            })
+    }
+
+    /// Resolve `requirement` and download the IR of the chosen version,
+    /// returning the version and the path of its cached IR. Offline, only
+    /// cached versions are considered.
+    pub fn fetch(&self, name: &str, requirement: &str) -> Result<(PackageVersion, PathBuf)> {
+        if self.offline {
+            let cached = self.cached_versions(name);
+            let version = select_version(&cached, requirement)?
+                .cloned()
+                .ok_or_else(|| RegistryError::Offline(format!("{} {}", name, requirement)))?;
+            let path = self.ir_path(name, &version.version);
+            return Ok((version, path));
+        }
+        let version = self.resolve_version(name, requirement)?;
+        let path = self.download(name, &version)?;
+        Ok((version, path))
+    }
+
+    /// Download the IR of a published version into the cache, reusing a
+    /// cached copy that still matches the listed digest.
+    pub fn download(&self, name: &str, version: &PackageVersion) -> Result<PathBuf> {
+        let path = self.ir_path(name, &version.version);
+        if path.is_file() && matches_digest(&path, version.sha256.as_deref())? {
+            return Ok(path);
+        }
+        if self.offline {
+            return Err(RegistryError::Offline(format!(
+                "{} {}",
+                name, version.version
+            )));
+        }
+
+        let url = format!(
+            "{}/api/v1/packages/{}/{}/ir",
+            self.url, name, version.version
+        );
+        let response = self
+            .send(self.authorize(self.client.get(&url)))
+            .map_err(|e| not_found(e, &format!("{} {}", name, version.version)))?;
+        let bytes = response.bytes().map_err(network_error)?;
+
+        let parent = path.parent().expect("IR path has a parent");
+        std::fs::create_dir_all(parent)?;
+        let partial = parent.join(format!("{}.partial", IR_FILE));
+        std::fs::write(&partial, &bytes)?;
+        if let Some(expected) = version.sha256.as_deref() {
+            let actual = digest_path(&partial).map_err(|e| std::io::Error::other(e.to_string()))?;
+            if actual != expected.to_ascii_lowercase() {
+                std::fs::remove_file(&partial)?;
+                return Err(RegistryError::IntegrityMismatch {
+                    name: name.to_string(),
+                    version: version.version.clone(),
+                    expected: expected.to_string(),
+                    actual,
+                });
+            }
+        }
+        std::fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+
+    /// Publish `ir` as version `metadata.version` of `metadata.name`.
+    pub fn publish(&self, metadata: &PackageMetadata, ir: &serde_json::Value) -> Result<Published> {
+        metadata.validate()?;
+        let url = format!(
+            "{}/api/v1/packages/{}/{}",
+            self.url, metadata.name, metadata.version
+        );
+        let request = self.client.put(&url).json(&PublishRequest { metadata, ir });
+        let response = match self.send(self.authorize(request)) {
+            Err(RegistryError::HttpError { status: 409, .. }) => {
+                return Err(RegistryError::AlreadyPublished {
+                    name: metadata.name.clone(),
+                    version: metadata.version.clone(),
+                });
+            }
+            result => result?,
+        };
+        response.json().map_err(network_error)
+    }
+
+    /// Path of the cached IR of `name` at `version`.
+    fn ir_path(&self, name: &str, version: &str) -> PathBuf {
+        let host = reqwest::Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(String::from))
+            .unwrap_or_else(|| "registry".to_string());
+        let mut path = self.cache_dir.join(host);
+        // Scoped names (`org/package`) become nested directories
+        for part in name.split('/') {
+            path.push(part);
+        }
+        path.join(version).join(IR_FILE)
+    }
+
+    /// Versions of `name` in the cache.
+    fn cached_versions(&self, name: &str) -> Vec<PackageVersion> {
+        let ir_path = self.ir_path(name, "_");
+        let Some(package_dir) = ir_path.parent().and_then(Path::parent) else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(package_dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join(IR_FILE).is_file())
+            .map(|entry| PackageVersion {
+                version: entry.file_name().to_string_lossy().to_string(),
+                sha256: None,
+                yanked: false,
+            })
+            .collect()
+    }
+
+    /// Add the registry credential to `request`.
+    fn authorize(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> reqwest::blocking::RequestBuilder {
+        if let Some(token) = &self.token {
+            return request.bearer_auth(token);
+        }
+        match self.credentials.for_url(&self.url) {
+            Some(Credential::Bearer(token)) => request.bearer_auth(token),
+            Some(Credential::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            None => request,
+        }
+    }
+
+    /// Send `request`, turning error statuses into [`RegistryError::HttpError`].
+    fn send(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response> {
+        let response = request.send().map_err(network_error)?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|json| json.get("error")?.as_str().map(String::from))
+            .unwrap_or_else(|| {
+                status
+                    .canonical_reason()
+                    .unwrap_or("request failed")
+                    .to_string()
+            });
+        Err(RegistryError::HttpError {
+            status: status.as_u16(),
+            message,
+        })
+    }
+}
+
+/// Whether the file at `path` hashes to `expected`, when there is one.
+fn matches_digest(path: &Path, expected: Option<&str>) -> Result<bool> {
+    let Some(expected) = expected else {
+        return Ok(true);
+    };
+    let actual = digest_path(path).map_err(|e| std::io::Error::other(e.to_string()))?;
+    Ok(actual == expected.to_ascii_lowercase())
+}
+
+fn network_error(e: reqwest::Error) -> RegistryError {
+    RegistryError::NetworkError(e.to_string())
+}
+
+/// Report a 404 as a missing package.
+fn not_found(error: RegistryError, what: &str) -> RegistryError {
+    match error {
+        RegistryError::HttpError { status: 404, .. } => {
+            RegistryError::PackageNotFound(what.to_string())
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(cache: &Path) -> RegistryClient {
+        RegistryClient::new(&RegistryConfig {
+            url: Some("https://packages.example.com/".to_string()),
+            cache_directory: Some(cache.to_path_buf()),
+            ..Default::default()
+        })
+        .unwrap()
+        .with_offline(true)
+    }
+
+    #[test]
+    fn test_offline_fetch_serves_cached_versions() {
+        let temp = tempfile::tempdir().unwrap();
+        let client = client(temp.path());
+        assert_eq!(client.url(), "https://packages.example.com");
+
+        for version in ["1.0.0", "1.4.2", "2.0.0"] {
+            let path = client.ir_path("finos/sdk", version);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "{}").unwrap();
+        }
+        assert!(
+            client
+                .ir_path("finos/sdk", "1.0.0")
+                .starts_with(temp.path().join("packages.example.com").join("finos"))
+        );
+
+        let (version, path) = client.fetch("finos/sdk", "^1.0").unwrap();
+        assert_eq!(version.version, "1.4.2");
+        assert!(path.ends_with("1.4.2/morphir-ir.json"));
+
+        let err = client.fetch("finos/sdk", "^3").unwrap_err();
+        assert!(matches!(err, RegistryError::Offline(_)));
+        assert!(matches!(
+            client.fetch("finos/other", "latest").unwrap_err(),
+            RegistryError::Offline(_)
+        ));
+    }
+
+    #[test]
+    fn test_cached_download_is_checked_against_the_listed_digest() {
+        let temp = tempfile::tempdir().unwrap();
+        let client = client(temp.path());
+        let path = client.ir_path("sdk", "1.0.0");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "{}").unwrap();
+
+        // sha256("{}")
+        let digest = "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a";
+        let listed = PackageVersion {
+            version: "1.0.0".to_string(),
+            sha256: Some(digest.to_string()),
+            yanked: false,
+        };
+        assert_eq!(client.download("sdk", &listed).unwrap(), path);
+
+        // A modified copy is not reused, and offline it cannot be refetched
+        std::fs::write(&path, "{\"modified\":true}").unwrap();
+        assert!(matches!(
+            client.download("sdk", &listed).unwrap_err(),
+            RegistryError::Offline(_)
+        ));
+    }
+
+    #[test]
+    fn test_missing_url_is_reported() {
+        if std::env::var(REGISTRY_URL_ENV).is_ok() {
+            return;
+        }
+        assert!(matches!(
+            RegistryClient::new(&RegistryConfig::default()),
+            Err(RegistryError::NotConfigured)
+        ));
+    }
+}
@@ -0,0 +1,88 @@
+//! Error types for package registry operations.
+
+use thiserror::Error;
+
+/// Errors that can occur talking to a package registry.
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    /// No registry URL configured
+    #[error(
+        "No package registry configured; set `url` in the [registry] section of morphir.toml or MORPHIR_REGISTRY_URL"
+    )]
+    NotConfigured,
+
+    /// The registry answered with an error status
+    #[error("Registry error: {status} - {message}")]
+    HttpError {
+        /// HTTP status code
+        status: u16,
+        /// Error message
+        message: String,
+    },
+
+    /// Network error
+    #[error("Network error: {0}")]
+    NetworkError(String),
+
+    /// The registry has no such package
+    #[error("Package not found: {0}")]
+    PackageNotFound(String),
+
+    /// No published version satisfies a requirement
+    #[error("No version of {name} matches {requirement}")]
+    NoMatchingVersion {
+        /// Package name
+        name: String,
+        /// Version requirement
+        requirement: String,
+    },
+
+    /// A version or version requirement does not parse
+    #[error("Invalid version: {0}")]
+    InvalidVersion(String),
+
+    /// The version is already published
+    #[error("{name} {version} is already published")]
+    AlreadyPublished {
+        /// Package name
+        name: String,
+        /// Package version
+        version: String,
+    },
+
+    /// Package metadata is incomplete
+    #[error("Invalid package: {0}")]
+    InvalidPackage(String),
+
+    /// Downloaded IR does not match the digest the registry lists
+    #[error(
+        "Integrity check failed for {name} {version}: expected sha256 {expected}, got {actual}"
+    )]
+    IntegrityMismatch {
+        /// Package name
+        name: String,
+        /// Package version
+        version: String,
+        /// Digest listed by the registry
+        expected: String,
+        /// Digest of the downloaded IR
+        actual: String,
+    },
+
+    /// Offline mode is on and the package is not cached
+    #[error(
+        "{0} is not in the local package cache and offline mode is on; fetch it once while online to cache it"
+    )]
+    Offline(String),
+
+    /// IO error
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// JSON error
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// Result type for registry operations.
+pub type Result<T> = std::result::Result<T, RegistryError>;
@@ -0,0 +1,372 @@
+//! Package registry support for Morphir.
+//!
+//! A registry hosts published Morphir packages: the IR of each version
+//! together with its metadata from `[project]`. Dependencies declared with
+//! a plain version requirement (`"finos/morphir-sdk" = "^1.2"`) resolve
+//! against the configured registry; anything that parses as a remote
+//! source (`github:...`, URLs) keeps going through [`crate::remote`].
+//!
+//! # Configuration
+//!
+//! ```toml
+//! [registry]
+//! url = "https://packages.example.com"  # or MORPHIR_REGISTRY_URL
+//! tokenEnv = "MORPHIR_REGISTRY_TOKEN"   # otherwise [sources.auth], netrc, ...
+//! cacheDirectory = "~/.cache/morphir/packages"
+//! timeoutSecs = 30
+//! ```
+//!
+//! # Protocol
+//!
+//! | Request | Response |
+//! |---------|----------|
+//! | `GET /api/v1/packages?q=<query>` | `{"packages": [PackageSummary]}` |
+//! | `GET /api/v1/packages/<name>` | [`PackageInfo`] |
+//! | `GET /api/v1/packages/<name>/<version>/ir` | the IR, hashing to the listed `sha256` |
+//! | `PUT /api/v1/packages/<name>/<version>` with `{"metadata", "ir"}` | [`Published`], or 409 if the version exists |
+//! | `GET /api/v1/extensions/<name>` | [`ExtensionPackage`] |
+//!
+//! Errors may carry a JSON body `{"error": "<message>"}`. Downloaded IR is
+//! cached per registry host, name, and version; with offline mode on only
+//! cached versions are used.
+
+pub mod client;
+pub mod error;
+
+pub use client::RegistryClient;
+pub use error::{RegistryError, Result};
+
+use crate::config::{DependencySpec, ProjectSection};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// Environment variable naming the registry when `[registry] url` is unset.
+pub const REGISTRY_URL_ENV: &str = "MORPHIR_REGISTRY_URL";
+
+/// Requirement matching the newest published version.
+pub const LATEST: &str = "latest";
+
+/// Registry settings (`[registry]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryConfig {
+    /// Base URL of the registry.
+    pub url: Option<String>,
+
+    /// Environment variable holding a bearer token for the registry.
+    pub token_env: Option<String>,
+
+    /// Package cache directory (defaults to ~/.cache/morphir/packages).
+    pub cache_directory: Option<PathBuf>,
+
+    /// Request timeout in seconds.
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: u64,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            token_env: None,
+            cache_directory: None,
+            timeout_secs: default_timeout(),
+        }
+    }
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+/// Metadata published with a package version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageMetadata {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// Registry dependencies and their version requirements
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, String>,
+}
+
+impl PackageMetadata {
+    /// Metadata of the project described by `project`, with its registry
+    /// dependencies. Path, git, and URL dependencies are left out, since
+    /// consumers of the package could not resolve them.
+    pub fn from_project(
+        project: &ProjectSection,
+        dependencies: &HashMap<String, DependencySpec>,
+    ) -> Self {
+        Self {
+            name: project.name.clone(),
+            version: project.version.clone(),
+            description: project.description.clone(),
+            authors: project.authors.clone(),
+            license: project.license.clone(),
+            repository: project.repository.clone(),
+            dependencies: dependencies
+                .iter()
+                .filter_map(|(name, spec)| {
+                    registry_requirement(spec).map(|req| (name.clone(), req.to_string()))
+                })
+                .collect(),
+        }
+    }
+
+    /// Check that the metadata can be published.
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(RegistryError::InvalidPackage(
+                "package name is empty".to_string(),
+            ));
+        }
+        Version::parse(&self.version).map_err(|e| {
+            RegistryError::InvalidVersion(format!(
+                "{} is not a semantic version: {}",
+                self.version, e
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+/// A search result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageSummary {
+    pub name: String,
+    /// Newest published version
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A package and its published versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub versions: Vec<PackageVersion>,
+}
+
+/// One published version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageVersion {
+    pub version: String,
+    /// SHA-256 of the version's IR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Withdrawn versions are only used when asked for exactly
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+/// An extension and its published releases.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionPackage {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub versions: Vec<ExtensionRelease>,
+}
+
+/// One published release of an extension.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionRelease {
+    /// Version, digest of the WASM module, and whether it was withdrawn
+    #[serde(flatten)]
+    pub release: PackageVersion,
+    /// URL of the WASM module, absolute or relative to the registry
+    pub url: String,
+}
+
+/// Response to a publish.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Published {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// Version requirement of a dependency served by a registry: a plain
+/// version requirement, or a detailed spec with only a `version`.
+pub fn registry_requirement(spec: &DependencySpec) -> Option<&str> {
+    let requirement = match spec {
+        DependencySpec::Version(version) => version.as_str(),
+        DependencySpec::Detailed(detailed)
+            if detailed.git.is_none()
+                && detailed.path.is_none()
+                && detailed.workspace != Some(true) =>
+        {
+            detailed.version.as_deref()?
+        }
+        DependencySpec::Detailed(_) => return None,
+    };
+    (requirement == LATEST || VersionReq::parse(requirement).is_ok()).then_some(requirement)
+}
+
+/// The newest of `versions` satisfying `requirement` (`latest` for any).
+/// Yanked versions only match an exact `=` requirement.
+pub fn select_version<'a>(
+    versions: &'a [PackageVersion],
+    requirement: &str,
+) -> Result<Option<&'a PackageVersion>> {
+    let req = if requirement == LATEST {
+        VersionReq::STAR
+    } else {
+        VersionReq::parse(requirement)
+            .map_err(|e| RegistryError::InvalidVersion(format!("{}: {}", requirement, e)))?
+    };
+    let exact = requirement.trim_start().starts_with('=');
+    Ok(versions
+        .iter()
+        .filter(|candidate| exact || !candidate.yanked)
+        .filter_map(|candidate| {
+            Version::parse(&candidate.version)
+                .ok()
+                .filter(|version| req.matches(version))
+                .map(|version| (version, candidate))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, candidate)| candidate))
+}
+
+/// The newest of `releases` satisfying `requirement`, selected as
+/// [`select_version`] does.
+pub fn select_release<'a>(
+    releases: &'a [ExtensionRelease],
+    requirement: &str,
+) -> Result<Option<&'a ExtensionRelease>> {
+    let versions: Vec<PackageVersion> = releases.iter().map(|r| r.release.clone()).collect();
+    let selected = select_version(&versions, requirement)?.map(|v| v.version.clone());
+    Ok(selected.and_then(|version| releases.iter().find(|r| r.release.version == version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DetailedDependency;
+
+    fn version(version: &str, yanked: bool) -> PackageVersion {
+        PackageVersion {
+            version: version.to_string(),
+            sha256: None,
+            yanked,
+        }
+    }
+
+    #[test]
+    fn test_select_version() {
+        let versions = vec![
+            version("1.0.0", false),
+            version("1.2.0", false),
+            version("1.3.0", true),
+            version("2.0.0-rc.1", false),
+            version("not-semver", false),
+        ];
+        let selected = |req| {
+            select_version(&versions, req)
+                .unwrap()
+                .map(|v| v.version.as_str())
+        };
+
+        assert_eq!(selected("^1.0"), Some("1.2.0"));
+        assert_eq!(selected("latest"), Some("1.2.0"));
+        assert_eq!(selected("=1.3.0"), Some("1.3.0"));
+        assert_eq!(selected("~1.0"), Some("1.0.0"));
+        assert_eq!(selected("^3"), None);
+        assert!(select_version(&versions, "one point oh").is_err());
+    }
+
+    #[test]
+    fn test_select_release() {
+        let package: ExtensionPackage = serde_json::from_value(serde_json::json!({
+            "name": "morphir-ts",
+            "versions": [
+                {"version": "0.9.0", "url": "extensions/morphir-ts/0.9.0.wasm"},
+                {"version": "1.0.0", "sha256": "ab12", "url": "https://cdn.example.com/ts.wasm"},
+                {"version": "1.1.0", "yanked": true, "url": "extensions/morphir-ts/1.1.0.wasm"},
+            ],
+        }))
+        .unwrap();
+        let selected = select_release(&package.versions, "latest")
+            .unwrap()
+            .unwrap();
+        assert_eq!(selected.release.version, "1.0.0");
+        assert_eq!(selected.release.sha256.as_deref(), Some("ab12"));
+        assert_eq!(selected.url, "https://cdn.example.com/ts.wasm");
+        assert!(select_release(&package.versions, "^2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_registry_requirements_and_metadata() {
+        let detailed = |version: Option<&str>, git: Option<&str>| {
+            DependencySpec::Detailed(DetailedDependency {
+                version: version.map(String::from),
+                path: None,
+                git: git.map(String::from),
+                tag: None,
+                branch: None,
+                rev: None,
+                workspace: None,
+            })
+        };
+        let mut dependencies = HashMap::new();
+        dependencies.insert(
+            "finos/sdk".to_string(),
+            DependencySpec::Version("^1.2".to_string()),
+        );
+        dependencies.insert("acme/core".to_string(), detailed(Some("0.3"), None));
+        dependencies.insert(
+            "acme/git".to_string(),
+            detailed(Some("1.0"), Some("https://example.com/git.git")),
+        );
+        dependencies.insert(
+            "acme/remote".to_string(),
+            DependencySpec::Version("github:acme/remote@v1".to_string()),
+        );
+
+        let project: ProjectSection =
+            toml::from_str("name = \"acme/app\"\nversion = \"0.1.0\"\nlicense = \"Apache-2.0\"")
+                .unwrap();
+        let metadata = PackageMetadata::from_project(&project, &dependencies);
+        assert_eq!(
+            metadata.dependencies,
+            BTreeMap::from([
+                ("acme/core".to_string(), "0.3".to_string()),
+                ("finos/sdk".to_string(), "^1.2".to_string()),
+            ])
+        );
+        assert_eq!(metadata.license.as_deref(), Some("Apache-2.0"));
+        metadata.validate().unwrap();
+
+        let unversioned = PackageMetadata {
+            version: "next".to_string(),
+            ..metadata
+        };
+        assert!(matches!(
+            unversioned.validate(),
+            Err(RegistryError::InvalidVersion(_))
+        ));
+    }
+}
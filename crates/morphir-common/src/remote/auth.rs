@@ -0,0 +1,408 @@
+//! Credentials for private remote sources.
+//!
+//! Credentials are resolved per host, in order, from:
+//!
+//! 1. The `[sources.auth.hosts."<host>"]` section of `morphir.toml`
+//! 2. Environment variables: `MORPHIR_SOURCES_TOKEN_<HOST>` (the host
+//!    upper-cased with non-alphanumerics replaced by `_`), then the
+//!    well-known `GITHUB_TOKEN`/`GH_TOKEN`, `GITLAB_TOKEN`, and
+//!    `BITBUCKET_TOKEN` for their hosts
+//! 3. A matching `machine` entry in `~/.netrc` (or `$NETRC`)
+//! 4. `git credential fill`, when `gitCredentials` is enabled
+//!
+//! The HTTP fetcher sends the credential as an `Authorization` header and
+//! the git fetcher passes it to `git clone` as an extra HTTP header, so
+//! tokens never end up in URLs, remotes, or process arguments.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// Authentication settings (`[sources.auth]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthConfig {
+    /// Credentials per host name (e.g. `gitlab.com`).
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostCredentials>,
+
+    /// Whether to read credentials from the netrc file.
+    #[serde(default = "default_netrc")]
+    pub netrc: bool,
+
+    /// Whether to ask git's configured credential helpers.
+    #[serde(default)]
+    pub git_credentials: bool,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            hosts: BTreeMap::new(),
+            netrc: default_netrc(),
+            git_credentials: false,
+        }
+    }
+}
+
+fn default_netrc() -> bool {
+    true
+}
+
+/// Credentials configured for one host.
+///
+/// Secrets should be referenced through `tokenEnv`/`passwordEnv` rather
+/// than written into `morphir.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostCredentials {
+    /// Bearer token.
+    pub token: Option<String>,
+
+    /// Environment variable holding a bearer token.
+    pub token_env: Option<String>,
+
+    /// User name for basic authentication.
+    pub username: Option<String>,
+
+    /// Password for basic authentication.
+    pub password: Option<String>,
+
+    /// Environment variable holding the basic authentication password.
+    pub password_env: Option<String>,
+}
+
+/// A credential for a single host.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Credential {
+    /// `Authorization: Bearer <token>`
+    Bearer(String),
+    /// `Authorization: Basic <username:password>`
+    Basic {
+        /// User name
+        username: String,
+        /// Password or app password
+        password: String,
+    },
+}
+
+impl std::fmt::Debug for Credential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print secrets
+        match self {
+            Credential::Bearer(_) => write!(f, "Bearer(***)"),
+            Credential::Basic { username, .. } => write!(f, "Basic({}:***)", username),
+        }
+    }
+}
+
+impl Credential {
+    /// Basic credential for git, which has no bearer scheme over HTTPS.
+    /// Tokens are paired with the user name each host expects for them.
+    pub fn git_header(&self, host: &str) -> String {
+        let (username, password) = match self {
+            Credential::Basic { username, password } => (username.as_str(), password.as_str()),
+            Credential::Bearer(token) => (git_token_username(host), token.as_str()),
+        };
+        let encoded =
+            base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+        format!("Authorization: Basic {}", encoded)
+    }
+}
+
+/// User name paired with an access token in git-over-HTTPS basic auth.
+fn git_token_username(host: &str) -> &'static str {
+    match host {
+        "github.com" => "x-access-token",
+        "bitbucket.org" => "x-token-auth",
+        _ => "oauth2",
+    }
+}
+
+/// Resolves and memoizes credentials per host.
+#[derive(Debug, Default)]
+pub struct CredentialResolver {
+    config: AuthConfig,
+    cache: Mutex<HashMap<String, Option<Credential>>>,
+}
+
+impl CredentialResolver {
+    /// Create a resolver for the given settings.
+    pub fn new(config: AuthConfig) -> Self {
+        Self {
+            config,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Credential for the host of `url`, if any.
+    pub fn for_url(&self, url: &str) -> Option<Credential> {
+        let parsed = reqwest::Url::parse(url).ok()?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return None;
+        }
+        self.for_host(parsed.host_str()?)
+    }
+
+    /// Credential for `host`, if any.
+    pub fn for_host(&self, host: &str) -> Option<Credential> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .entry(host.to_string())
+            .or_insert_with(|| self.resolve(host, |name| std::env::var(name).ok()))
+            .clone()
+    }
+
+    /// Resolve a credential for `host`, reading environment variables
+    /// through `env`.
+    fn resolve(&self, host: &str, env: impl Fn(&str) -> Option<String>) -> Option<Credential> {
+        if let Some(configured) = self.config.hosts.get(host)
+            && let Some(credential) = configured_credential(configured, &env)
+        {
+            return Some(credential);
+        }
+
+        if let Some(token) = env_token_names(host).iter().find_map(|name| env(name)) {
+            return Some(Credential::Bearer(token));
+        }
+
+        if self.config.netrc
+            && let Some(path) = netrc_path(&env)
+            && let Ok(contents) = std::fs::read_to_string(path)
+            && let Some(credential) = netrc_credential(&contents, host)
+        {
+            return Some(credential);
+        }
+
+        if self.config.git_credentials {
+            return git_credential(host);
+        }
+
+        None
+    }
+}
+
+/// Credential from a `[sources.auth.hosts]` entry.
+fn configured_credential(
+    configured: &HostCredentials,
+    env: &impl Fn(&str) -> Option<String>,
+) -> Option<Credential> {
+    let token = configured
+        .token
+        .clone()
+        .or_else(|| configured.token_env.as_deref().and_then(env));
+    if let Some(token) = token {
+        return Some(Credential::Bearer(token));
+    }
+    let password = configured
+        .password
+        .clone()
+        .or_else(|| configured.password_env.as_deref().and_then(env))?;
+    Some(Credential::Basic {
+        username: configured.username.clone()?,
+        password,
+    })
+}
+
+/// Environment variables checked for a token, most specific first.
+fn env_token_names(host: &str) -> Vec<String> {
+    let mut names = vec![format!(
+        "MORPHIR_SOURCES_TOKEN_{}",
+        host.chars()
+            .map(|c| if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            })
+            .collect::<String>()
+    )];
+    let well_known: &[&str] = match host {
+        "github.com"
+        | "api.github.com"
+        | "gist.github.com"
+        | "raw.githubusercontent.com"
+        | "gist.githubusercontent.com" => &["GITHUB_TOKEN", "GH_TOKEN"],
+        "gitlab.com" => &["GITLAB_TOKEN"],
+        "bitbucket.org" | "api.bitbucket.org" => &["BITBUCKET_TOKEN"],
+        _ => &[],
+    };
+    names.extend(well_known.iter().map(|name| name.to_string()));
+    names
+}
+
+/// Location of the netrc file.
+fn netrc_path(env: &impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    env("NETRC")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".netrc")))
+}
+
+/// Credential for `host` from netrc contents, falling back to a `default`
+/// entry.
+fn netrc_credential(contents: &str, host: &str) -> Option<Credential> {
+    let mut tokens = contents.split_whitespace();
+    let mut matched = None;
+    let mut default = None;
+    let mut current: Option<(bool, Option<String>, Option<String>)> = None;
+
+    let mut finish = |entry: Option<(bool, Option<String>, Option<String>)>| {
+        if let Some((is_default, Some(login), Some(password))) = entry {
+            let credential = Credential::Basic {
+                username: login,
+                password,
+            };
+            if is_default {
+                default.get_or_insert(credential);
+            } else {
+                matched.get_or_insert(credential);
+            }
+        }
+    };
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => {
+                finish(current.take());
+                let machine = tokens.next().unwrap_or_default();
+                current = (machine == host).then_some((false, None, None));
+            }
+            "default" => {
+                finish(current.take());
+                current = Some((true, None, None));
+            }
+            "login" => {
+                let value = tokens.next().map(String::from);
+                if let Some(entry) = current.as_mut() {
+                    entry.1 = value;
+                }
+            }
+            "password" => {
+                let value = tokens.next().map(String::from);
+                if let Some(entry) = current.as_mut() {
+                    entry.2 = value;
+                }
+            }
+            "account" | "macdef" => {
+                tokens.next();
+            }
+            _ => {}
+        }
+    }
+    finish(current.take());
+
+    matched.or(default)
+}
+
+/// Ask git's credential helpers for `host`, without ever prompting.
+fn git_credential(host: &str) -> Option<Credential> {
+    use std::io::Write;
+
+    let mut child = Command::new("git")
+        .args(["credential", "fill"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child
+        .stdin
+        .take()?
+        .write_all(format!("protocol=https\nhost={}\n\n", host).as_bytes())
+        .ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let field = |name: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .map(String::from)
+    };
+    Some(Credential::Basic {
+        username: field("username")?,
+        password: field("password")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_config_then_env_resolution() {
+        let mut hosts = BTreeMap::new();
+        hosts.insert(
+            "artifacts.example.com".to_string(),
+            HostCredentials {
+                username: Some("ci".to_string()),
+                password_env: Some("ARTIFACTS_PASSWORD".to_string()),
+                ..Default::default()
+            },
+        );
+        let resolver = CredentialResolver::new(AuthConfig {
+            hosts,
+            netrc: false,
+            git_credentials: false,
+        });
+        let env = |name: &str| match name {
+            "ARTIFACTS_PASSWORD" => Some("s3cret".to_string()),
+            "GITLAB_TOKEN" => Some("glpat".to_string()),
+            "MORPHIR_SOURCES_TOKEN_GITLAB_COM" => Some("specific".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            resolver.resolve("artifacts.example.com", env),
+            Some(Credential::Basic {
+                username: "ci".to_string(),
+                password: "s3cret".to_string()
+            })
+        );
+        assert_eq!(
+            resolver.resolve("gitlab.com", env),
+            Some(Credential::Bearer("specific".to_string()))
+        );
+        assert_eq!(resolver.resolve("example.org", no_env), None);
+        assert_eq!(
+            Credential::Bearer("tok".to_string()).git_header("github.com"),
+            format!(
+                "Authorization: Basic {}",
+                base64::engine::general_purpose::STANDARD.encode("x-access-token:tok")
+            )
+        );
+    }
+
+    #[test]
+    fn test_netrc_credential() {
+        let netrc = "machine gitlab.com login alice password one\n\
+                     machine bitbucket.org\n  login bob\n  password two\n\
+                     default login anon password three\n";
+
+        assert_eq!(
+            netrc_credential(netrc, "bitbucket.org"),
+            Some(Credential::Basic {
+                username: "bob".to_string(),
+                password: "two".to_string()
+            })
+        );
+        assert_eq!(
+            netrc_credential(netrc, "example.com"),
+            Some(Credential::Basic {
+                username: "anon".to_string(),
+                password: "three".to_string()
+            })
+        );
+        assert_eq!(netrc_credential("machine a login x", "a"), None);
+    }
+}
@@ -29,6 +29,11 @@ pub struct CacheEntry {
 
     /// Path to the cached content relative to cache root.
     pub path: String,
+
+    /// Whether a trusted signature of the content was verified when it
+    /// was fetched.
+    #[serde(default)]
+    pub signed: bool,
 }
 
 /// Cache index storing metadata for all cached entries.
@@ -88,6 +93,8 @@ impl SourceCache {
             RemoteSource::Http { .. } => "http",
             RemoteSource::Git { .. } => "git",
             RemoteSource::GitHub { .. } => "github",
+            RemoteSource::GitLab { .. } => "gitlab",
+            RemoteSource::Bitbucket { .. } => "bitbucket",
             RemoteSource::Gist { .. } => "gist",
         };
 
@@ -125,6 +132,54 @@ impl SourceCache {
         None
     }
 
+    /// SHA-256 digest of a cached source, recorded when it was stored.
+    pub fn recorded_digest(&self, source: &RemoteSource) -> Option<String> {
+        self.entry(source)
+            .map(|entry| entry.content_hash)
+            .filter(|hash| !hash.is_empty())
+    }
+
+    /// Whether the signature of a cached source was verified when it was
+    /// fetched.
+    pub fn is_signed(&self, source: &RemoteSource) -> bool {
+        self.entry(source).is_some_and(|entry| entry.signed)
+    }
+
+    /// Record that the signature of a cached source was verified.
+    pub fn mark_signed(&mut self, source: &RemoteSource) -> Result<()> {
+        if self.index.is_none() {
+            self.index = Some(self.load_index()?);
+        }
+        if let Some(entry) = self
+            .index
+            .as_mut()
+            .and_then(|index| index.entries.get_mut(&source.cache_key()))
+        {
+            entry.signed = true;
+            self.save_index()?;
+        }
+        Ok(())
+    }
+
+    /// Index entry of a cached source.
+    fn entry(&self, source: &RemoteSource) -> Option<CacheEntry> {
+        let loaded;
+        let index = match &self.index {
+            Some(index) => index,
+            None => {
+                loaded = self.load_index().ok()?;
+                &loaded
+            }
+        };
+        index.entries.get(&source.cache_key()).cloned()
+    }
+
+    /// A cached source regardless of its age, for offline use.
+    pub fn get_any(&self, source: &RemoteSource) -> Option<PathBuf> {
+        let path = self.cache_path(source);
+        path.exists().then_some(path)
+    }
+
     /// Store content in the cache.
     pub fn put(&mut self, source: &RemoteSource, content_path: &Path) -> Result<PathBuf> {
         let cache_path = self.cache_path(source);
@@ -250,7 +305,7 @@ impl SourceCache {
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .map(|d| d.as_secs())
                 .unwrap_or(0),
-            content_hash: String::new(), // TODO: compute hash
+            content_hash: crate::remote::integrity::digest_path(cache_path)?,
             etag: None,
             size,
             path: cache_path
@@ -258,6 +313,7 @@ impl SourceCache {
                 .unwrap_or(cache_path)
                 .to_string_lossy()
                 .to_string(),
+            signed: false,
         };
 
         index.entries.insert(source.cache_key(), entry);
@@ -1,8 +1,29 @@
 //! Configuration for remote source access.
 
+use crate::remote::auth::AuthConfig;
+use crate::remote::integrity::IntegrityConfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Environment variable that turns on offline mode (`1` or `true`).
+pub const OFFLINE_ENV: &str = "MORPHIR_OFFLINE";
+
+/// Offline mode requested for the whole process (`--offline`).
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Turn offline mode on or off for every resolver in this process.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Whether offline mode was requested for this process, through
+/// [`set_offline`] or `MORPHIR_OFFLINE`.
+pub fn offline_requested() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+        || std::env::var(OFFLINE_ENV).is_ok_and(|value| value == "1" || value == "true")
+}
 
 /// Configuration for remote source access.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +33,10 @@ pub struct RemoteSourceConfig {
     #[serde(default = "default_enabled")]
     pub enabled: bool,
 
+    /// Resolve only from the local cache, never from the network.
+    #[serde(default)]
+    pub offline: bool,
+
     /// Allow list (glob patterns). If non-empty, only matching sources allowed.
     #[serde(default)]
     pub allow: Vec<String>,
@@ -31,17 +56,28 @@ pub struct RemoteSourceConfig {
     /// Network settings.
     #[serde(default)]
     pub network: NetworkConfig,
+
+    /// Credentials for private sources.
+    #[serde(default)]
+    pub auth: AuthConfig,
+
+    /// Signature checking.
+    #[serde(default)]
+    pub integrity: IntegrityConfig,
 }
 
 impl Default for RemoteSourceConfig {
     fn default() -> Self {
         Self {
             enabled: true,
+            offline: false,
             allow: Vec::new(),
             deny: Vec::new(),
             trusted_github_orgs: HashSet::new(),
             cache: CacheConfig::default(),
             network: NetworkConfig::default(),
+            auth: AuthConfig::default(),
+            integrity: IntegrityConfig::default(),
         }
     }
 }
@@ -122,15 +158,31 @@ impl RemoteSourceConfig {
         }
     }
 
+    /// Whether sources must come from the cache, by configuration or
+    /// because offline mode was requested for the process.
+    pub fn is_offline(&self) -> bool {
+        self.offline || offline_requested()
+    }
+
     /// Check if a URL matches the allow/deny patterns.
     pub fn is_allowed(&self, url: &str) -> bool {
+        self.is_allowed_any(&[url])
+    }
+
+    /// Check a source given in several equivalent forms (e.g. its canonical
+    /// URL and its `gitlab:` shorthand). A deny match on any form rejects
+    /// the source; an allow match on any form admits it.
+    pub fn is_allowed_any<S: AsRef<str>>(&self, forms: &[S]) -> bool {
         if !self.enabled {
             return false;
         }
 
         // Check deny list first (deny takes precedence)
         for pattern in &self.deny {
-            if Self::matches_pattern(pattern, url) {
+            if forms
+                .iter()
+                .any(|form| Self::matches_pattern(pattern, form.as_ref()))
+            {
                 return false;
             }
         }
@@ -142,7 +194,10 @@ impl RemoteSourceConfig {
 
         // Check allow list
         for pattern in &self.allow {
-            if Self::matches_pattern(pattern, url) {
+            if forms
+                .iter()
+                .any(|form| Self::matches_pattern(pattern, form.as_ref()))
+            {
                 return true;
             }
         }
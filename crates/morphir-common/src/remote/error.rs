@@ -69,6 +69,27 @@ pub enum RemoteSourceError {
         location: String,
     },
 
+    /// Content does not match its pinned digest
+    #[error("Integrity check failed for {location}: expected sha256 {expected}, got {actual}")]
+    IntegrityMismatch {
+        /// Source whose content was checked
+        location: String,
+        /// Pinned digest
+        expected: String,
+        /// Digest of the content
+        actual: String,
+    },
+
+    /// Source is not cached and network access is disabled
+    #[error(
+        "{0} is not in the local cache and offline mode is on; run `morphir source prefetch` while online to cache it"
+    )]
+    Offline(String),
+
+    /// Signature missing or invalid
+    #[error("Signature verification failed: {0}")]
+    SignatureError(String),
+
     /// JSON parsing error
     #[error("JSON parse error: {0}")]
     JsonError(#[from] serde_json::Error),
@@ -4,6 +4,7 @@
 //! for simplicity and to leverage the user's existing git configuration
 //! (credentials, SSH keys, etc.).
 
+use crate::remote::auth::CredentialResolver;
 use crate::remote::cache::SourceCache;
 use crate::remote::error::{RemoteSourceError, Result};
 use crate::remote::source::{GitRef, RemoteSource};
@@ -17,6 +18,9 @@ pub struct GitFetcher {
 
     /// Clone depth for shallow clones.
     depth: u32,
+
+    /// Credentials for cloning private repositories over HTTPS.
+    credentials: CredentialResolver,
 }
 
 impl GitFetcher {
@@ -25,6 +29,7 @@ impl GitFetcher {
         Self {
             shallow: true,
             depth: 1,
+            credentials: CredentialResolver::default(),
         }
     }
 
@@ -33,9 +38,16 @@ impl GitFetcher {
         Self {
             shallow: false,
             depth: 0,
+            credentials: CredentialResolver::default(),
         }
     }
 
+    /// Authenticate HTTPS clones with credentials from `credentials`.
+    pub fn with_credentials(mut self, credentials: CredentialResolver) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
     /// Check if git is available.
     pub fn is_available(&self) -> bool {
         Command::new("git")
@@ -56,6 +68,19 @@ impl GitFetcher {
         let mut cmd = Command::new("git");
         cmd.arg("clone");
 
+        // Pass credentials as an extra header through the environment so
+        // they stay out of the process arguments and the clone's config
+        if let Some(credential) = self.credentials.for_url(url)
+            && let Some(host) = reqwest::Url::parse(url)
+                .ok()
+                .and_then(|u| u.host_str().map(String::from))
+        {
+            cmd.env("GIT_CONFIG_COUNT", "1")
+                .env("GIT_CONFIG_KEY_0", "http.extraHeader")
+                .env("GIT_CONFIG_VALUE_0", credential.git_header(&host))
+                .env("GIT_TERMINAL_PROMPT", "0");
+        }
+
         // Add shallow clone options if enabled and not cloning a specific commit
         if self.shallow {
             let can_shallow = match reference {
@@ -173,6 +198,34 @@ impl GitFetcher {
         self.clone_to_cache(&url, reference, subpath, cache, source)
     }
 
+    /// Clone a GitLab repository using the shorthand.
+    pub fn clone_gitlab(
+        &self,
+        owner: &str,
+        repo: &str,
+        reference: Option<&GitRef>,
+        subpath: Option<&str>,
+        cache: &mut SourceCache,
+        source: &RemoteSource,
+    ) -> Result<PathBuf> {
+        let url = format!("https://gitlab.com/{}/{}.git", owner, repo);
+        self.clone_to_cache(&url, reference, subpath, cache, source)
+    }
+
+    /// Clone a Bitbucket repository using the shorthand.
+    pub fn clone_bitbucket(
+        &self,
+        owner: &str,
+        repo: &str,
+        reference: Option<&GitRef>,
+        subpath: Option<&str>,
+        cache: &mut SourceCache,
+        source: &RemoteSource,
+    ) -> Result<PathBuf> {
+        let url = format!("https://bitbucket.org/{}/{}.git", owner, repo);
+        self.clone_to_cache(&url, reference, subpath, cache, source)
+    }
+
     /// Fetch the latest changes for an existing clone.
     pub fn fetch(&self, repo_path: &PathBuf) -> Result<()> {
         let output = Command::new("git")
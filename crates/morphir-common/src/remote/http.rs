@@ -1,8 +1,10 @@
 //! HTTP/HTTPS fetching for remote sources.
 
+use crate::remote::auth::{Credential, CredentialResolver};
 use crate::remote::cache::SourceCache;
 use crate::remote::config::NetworkConfig;
 use crate::remote::error::{RemoteSourceError, Result};
+use crate::remote::source::GitRef;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
@@ -14,6 +16,9 @@ pub struct HttpFetcher {
 
     /// reqwest client.
     client: reqwest::blocking::Client,
+
+    /// Credentials sent to private hosts.
+    credentials: CredentialResolver,
 }
 
 impl HttpFetcher {
@@ -49,7 +54,30 @@ impl HttpFetcher {
             RemoteSourceError::NetworkError(format!("Failed to create HTTP client: {}", e))
         })?;
 
-        Ok(Self { config, client })
+        Ok(Self {
+            config,
+            client,
+            credentials: CredentialResolver::default(),
+        })
+    }
+
+    /// Authenticate requests with credentials from `credentials`.
+    pub fn with_credentials(mut self, credentials: CredentialResolver) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Start a GET request, authenticated when a credential is known for
+    /// the URL's host.
+    fn get(&self, url: &str) -> reqwest::blocking::RequestBuilder {
+        let request = self.client.get(url);
+        match self.credentials.for_url(url) {
+            Some(Credential::Bearer(token)) => request.bearer_auth(token),
+            Some(Credential::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            None => request,
+        }
     }
 
     /// Create a new HTTP fetcher with default configuration.
@@ -60,7 +88,6 @@ impl HttpFetcher {
     /// Fetch a URL and return the response body as bytes.
     pub fn fetch_bytes(&self, url: &str) -> Result<Vec<u8>> {
         let response = self
-            .client
             .get(url)
             .send()
             .map_err(|e| RemoteSourceError::NetworkError(format!("Request failed: {}", e)))?;
@@ -100,7 +127,18 @@ impl HttpFetcher {
         source: &crate::remote::source::RemoteSource,
     ) -> Result<PathBuf> {
         let bytes = self.fetch_bytes(url)?;
+        self.cache_bytes(url, &bytes, subpath, cache, source)
+    }
 
+    /// Cache content downloaded from `url`, extracting archives.
+    pub fn cache_bytes(
+        &self,
+        url: &str,
+        bytes: &[u8],
+        subpath: Option<&str>,
+        cache: &mut SourceCache,
+        source: &crate::remote::source::RemoteSource,
+    ) -> Result<PathBuf> {
         // Check if this is an archive
         let is_archive = url.ends_with(".zip")
             || url.ends_with(".tar.gz")
@@ -111,7 +149,7 @@ impl HttpFetcher {
             // Extract to temp, then cache the extracted content
             let temp_dir = tempfile::tempdir()?;
             let archive_path = temp_dir.path().join("archive");
-            std::fs::write(&archive_path, &bytes)?;
+            std::fs::write(&archive_path, bytes)?;
 
             let extract_dir = temp_dir.path().join("extracted");
             std::fs::create_dir_all(&extract_dir)?;
@@ -135,10 +173,47 @@ impl HttpFetcher {
             cache.put(source, &content_path)
         } else {
             // Cache the file directly
-            cache.put_bytes(source, &bytes)
+            cache.put_bytes(source, bytes)
         }
     }
 
+    /// Fetch a repository archive (a `.tar.gz` with a single top-level
+    /// directory, as served by GitLab and Bitbucket) and cache its contents.
+    pub fn fetch_repo_archive(
+        &self,
+        url: &str,
+        subpath: Option<&str>,
+        cache: &mut SourceCache,
+        source: &crate::remote::source::RemoteSource,
+    ) -> Result<PathBuf> {
+        let bytes = self.fetch_bytes(url)?;
+
+        let temp_dir = tempfile::tempdir()?;
+        let archive_path = temp_dir.path().join("archive.tar.gz");
+        std::fs::write(&archive_path, &bytes)?;
+
+        let extract_dir = temp_dir.path().join("extracted");
+        std::fs::create_dir_all(&extract_dir)?;
+        self.extract_tar_gz(&archive_path, &extract_dir)?;
+
+        // Archives wrap the repository in a `<repo>-<sha>/` directory
+        let root = single_root(&extract_dir)?;
+        let content_path = if let Some(sub) = subpath {
+            let sub_path = root.join(sub);
+            if !sub_path.exists() {
+                return Err(RemoteSourceError::PathNotFound {
+                    path: sub.to_string(),
+                    location: url.to_string(),
+                });
+            }
+            sub_path
+        } else {
+            root
+        };
+
+        cache.put(source, &content_path)
+    }
+
     /// Extract an archive to a destination directory.
     fn extract_archive(&self, archive_path: &PathBuf, dest: &PathBuf, url: &str) -> Result<()> {
         if url.ends_with(".zip") {
@@ -223,6 +298,92 @@ impl HttpFetcher {
     }
 }
 
+/// The only directory inside `dir`, or `dir` itself when it holds anything else.
+fn single_root(dir: &Path) -> Result<PathBuf> {
+    let entries: Vec<_> = std::fs::read_dir(dir)?.collect::<std::io::Result<_>>()?;
+    match entries.as_slice() {
+        [entry] if entry.path().is_dir() => Ok(entry.path()),
+        _ => Ok(dir.to_path_buf()),
+    }
+}
+
+/// Archive URL for a GitLab project through the GitLab REST API.
+pub fn gitlab_archive_url(owner: &str, repo: &str, reference: Option<&GitRef>) -> String {
+    let mut url = format!(
+        "https://gitlab.com/api/v4/projects/{}%2F{}/repository/archive.tar.gz",
+        owner, repo
+    );
+    if let Some(ref_) = reference {
+        url = format!("{}?sha={}", url, ref_);
+    }
+    url
+}
+
+/// Archive URL for a Bitbucket repository at `reference`.
+pub fn bitbucket_archive_url(owner: &str, repo: &str, reference: &str) -> String {
+    format!(
+        "https://bitbucket.org/{}/{}/get/{}.tar.gz",
+        owner, repo, reference
+    )
+}
+
+/// Fetch a GitLab repository as an archive.
+pub fn fetch_gitlab(
+    http: &HttpFetcher,
+    owner: &str,
+    repo: &str,
+    reference: Option<&GitRef>,
+    subpath: Option<&str>,
+    cache: &mut SourceCache,
+    source: &crate::remote::source::RemoteSource,
+) -> Result<PathBuf> {
+    let url = gitlab_archive_url(owner, repo, reference);
+    http.fetch_repo_archive(&url, subpath, cache, source)
+}
+
+/// Fetch a Bitbucket repository as an archive.
+///
+/// Without a reference, the repository's main branch is looked up through
+/// the Bitbucket API first, since archives are only served per reference.
+pub fn fetch_bitbucket(
+    http: &HttpFetcher,
+    owner: &str,
+    repo: &str,
+    reference: Option<&GitRef>,
+    subpath: Option<&str>,
+    cache: &mut SourceCache,
+    source: &crate::remote::source::RemoteSource,
+) -> Result<PathBuf> {
+    let reference = match reference {
+        Some(ref_) => ref_.to_string(),
+        None => {
+            let api_url = format!(
+                "https://api.bitbucket.org/2.0/repositories/{}/{}",
+                owner, repo
+            );
+            let bytes = http.fetch_bytes(&api_url)?;
+            let repository: serde_json::Value = serde_json::from_slice(&bytes).map_err(|e| {
+                RemoteSourceError::NetworkError(format!(
+                    "Failed to parse Bitbucket response: {}",
+                    e
+                ))
+            })?;
+            repository
+                .pointer("/mainbranch/name")
+                .and_then(|name| name.as_str())
+                .map(String::from)
+                .ok_or_else(|| {
+                    RemoteSourceError::NotFound(format!(
+                        "Bitbucket repository {}/{} has no main branch",
+                        owner, repo
+                    ))
+                })?
+        }
+    };
+    let url = bitbucket_archive_url(owner, repo, &reference);
+    http.fetch_repo_archive(&url, subpath, cache, source)
+}
+
 /// Fetch a GitHub Gist.
 pub fn fetch_gist(
     http: &HttpFetcher,
@@ -241,7 +402,6 @@ pub fn fetch_gist(
 
     // Fetch gist metadata
     let response = http
-        .client
         .get(&api_url)
         .header("Accept", "application/vnd.github.v3+json")
         .send()
@@ -310,5 +470,34 @@ mod tests {
         assert!(fetcher.is_ok());
     }
 
+    #[test]
+    fn test_repo_archive_urls() {
+        assert_eq!(
+            gitlab_archive_url("acme", "models", Some(&GitRef::Tag("v1.0".to_string()))),
+            "https://gitlab.com/api/v4/projects/acme%2Fmodels/repository/archive.tar.gz?sha=v1.0"
+        );
+        assert_eq!(
+            gitlab_archive_url("acme", "models", None),
+            "https://gitlab.com/api/v4/projects/acme%2Fmodels/repository/archive.tar.gz"
+        );
+        assert_eq!(
+            bitbucket_archive_url("acme", "models", "main"),
+            "https://bitbucket.org/acme/models/get/main.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_single_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("models-abc123")).unwrap();
+        assert_eq!(
+            single_root(dir.path()).unwrap(),
+            dir.path().join("models-abc123")
+        );
+
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+        assert_eq!(single_root(dir.path()).unwrap(), dir.path());
+    }
+
     // Note: Additional tests would require a mock HTTP server
 }
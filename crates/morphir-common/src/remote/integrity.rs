@@ -0,0 +1,251 @@
+//! Integrity verification for remote sources.
+//!
+//! Sources can be pinned to a SHA-256 digest, either with a `sha256=`
+//! item in the source fragment (`https://example.com/ir.json#sha256=...`,
+//! `https://example.com/ir.zip#path/ir.json&sha256=...`) or through the
+//! digest recorded in `morphir.lock`. Files are hashed directly; directories
+//! (clones and extracted archives) are hashed over their sorted relative
+//! paths and file contents, so the digest does not depend on timestamps.
+//!
+//! HTTP sources can also be signed, with a minisign signature at
+//! `<url>.minisig` or a sigstore bundle at `<url>.sigstore.json`. Signatures
+//! are checked on download, before the content enters the cache; cached
+//! content is re-hashed against the digest recorded when it was fetched
+//! before it is used again.
+
+use crate::remote::error::{RemoteSourceError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::process::Command;
+
+/// Fragment item pinning a source to a SHA-256 digest
+pub const SHA256_PREFIX: &str = "sha256=";
+
+/// Signature settings (`[sources.integrity]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityConfig {
+    /// Minisign public keys (base64) trusted to sign HTTP sources.
+    #[serde(default)]
+    pub minisign_keys: Vec<String>,
+
+    /// Sigstore signer identity trusted to sign HTTP sources.
+    pub sigstore: Option<SigstoreIdentity>,
+
+    /// Reject sources that have no valid signature.
+    #[serde(default)]
+    pub require_signatures: bool,
+}
+
+impl IntegrityConfig {
+    /// Whether any signature checking is configured.
+    pub fn checks_signatures(&self) -> bool {
+        !self.minisign_keys.is_empty() || self.sigstore.is_some() || self.require_signatures
+    }
+}
+
+/// Certificate identity a sigstore bundle must have been issued to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigstoreIdentity {
+    /// Signer identity (e.g. an email or workflow URL).
+    pub identity: String,
+
+    /// OIDC issuer of the identity.
+    pub issuer: String,
+}
+
+/// Split a `sha256=<hex>` item off the fragment of a source string,
+/// returning the source without it and the (lower-cased) digest.
+pub fn split_digest(input: &str) -> (String, Option<String>) {
+    let Some((base, fragment)) = input.split_once('#') else {
+        return (input.to_string(), None);
+    };
+    let mut digest = None;
+    let rest: Vec<&str> = fragment
+        .split('&')
+        .filter(|item| match item.strip_prefix(SHA256_PREFIX) {
+            Some(hex) => {
+                digest = Some(hex.to_ascii_lowercase());
+                false
+            }
+            None => true,
+        })
+        .collect();
+    let source = if rest.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}#{}", base, rest.join("&"))
+    };
+    (source, digest)
+}
+
+/// SHA-256 digest (lower-case hex) of a file or directory tree.
+pub fn digest_path(path: &Path) -> Result<String> {
+    if !path.is_dir() {
+        return Ok(hex(&Sha256::digest(std::fs::read(path)?)));
+    }
+
+    let mut files = Vec::new();
+    collect_files(path, path, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for relative in files {
+        let content = std::fs::read(path.join(&relative))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update(Sha256::digest(content));
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Relative paths (with `/` separators) of the files under `dir`, skipping
+/// `.git` metadata.
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.file_name().is_some_and(|name| name == ".git") {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let parts: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+/// Lowercase hex encoding of `bytes`, as digests are written.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Check that `path` hashes to `expected`.
+pub fn verify_digest(source: &str, path: &Path, expected: &str) -> Result<()> {
+    let actual = digest_path(path)?;
+    if actual != expected.to_ascii_lowercase() {
+        return Err(RemoteSourceError::IntegrityMismatch {
+            location: source.to_string(),
+            expected: expected.to_string(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Check a minisign `signature` of `content` against the trusted `keys`.
+pub fn verify_minisign(content: &[u8], signature: &str, keys: &[String]) -> Result<()> {
+    let signature = minisign_verify::Signature::decode(signature).map_err(|e| {
+        RemoteSourceError::SignatureError(format!("Invalid minisign signature: {}", e))
+    })?;
+    let mut last_error = "no minisign keys are configured".to_string();
+    for key in keys {
+        match minisign_verify::PublicKey::from_base64(key) {
+            Ok(public_key) => match public_key.verify(content, &signature, false) {
+                Ok(()) => return Ok(()),
+                Err(e) => last_error = e.to_string(),
+            },
+            Err(e) => last_error = format!("invalid minisign key {}: {}", key, e),
+        }
+    }
+    Err(RemoteSourceError::SignatureError(format!(
+        "minisign signature does not match a trusted key: {}",
+        last_error
+    )))
+}
+
+/// Check a sigstore `bundle` for `content` with `cosign verify-blob`.
+pub fn verify_sigstore(content: &[u8], bundle: &[u8], identity: &SigstoreIdentity) -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let blob = temp_dir.path().join("blob");
+    let bundle_path = temp_dir.path().join("bundle.sigstore.json");
+    std::fs::write(&blob, content)?;
+    std::fs::write(&bundle_path, bundle)?;
+
+    let output = Command::new("cosign")
+        .arg("verify-blob")
+        .arg("--bundle")
+        .arg(&bundle_path)
+        .arg("--certificate-identity")
+        .arg(&identity.identity)
+        .arg("--certificate-oidc-issuer")
+        .arg(&identity.issuer)
+        .arg(&blob)
+        .output()
+        .map_err(|e| {
+            RemoteSourceError::SignatureError(format!(
+                "Failed to run cosign (required for sigstore verification): {}",
+                e
+            ))
+        })?;
+
+    if !output.status.success() {
+        return Err(RemoteSourceError::SignatureError(format!(
+            "sigstore bundle rejected: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_digest() {
+        assert_eq!(
+            split_digest("https://example.com/ir.json#sha256=ABCD"),
+            (
+                "https://example.com/ir.json".to_string(),
+                Some("abcd".to_string())
+            )
+        );
+        assert_eq!(
+            split_digest("https://example.com/ir.zip#path/ir.json&sha256=abcd"),
+            (
+                "https://example.com/ir.zip#path/ir.json".to_string(),
+                Some("abcd".to_string())
+            )
+        );
+        assert_eq!(
+            split_digest("gist:abc123#file.json"),
+            ("gist:abc123#file.json".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_digests_of_files_and_trees() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("ir.json");
+        std::fs::write(&file, "{}").unwrap();
+        // sha256("{}")
+        let expected = "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a";
+        assert_eq!(digest_path(&file).unwrap(), expected);
+        verify_digest("ir.json", &file, &expected.to_uppercase()).unwrap();
+
+        let tree = temp.path().join("tree");
+        std::fs::create_dir_all(tree.join("src")).unwrap();
+        std::fs::create_dir_all(tree.join(".git")).unwrap();
+        std::fs::write(tree.join("src").join("a.json"), "a").unwrap();
+        std::fs::write(tree.join(".git").join("HEAD"), "ref").unwrap();
+        let before = digest_path(&tree).unwrap();
+        // Git metadata does not count
+        std::fs::write(tree.join(".git").join("HEAD"), "other").unwrap();
+        assert_eq!(digest_path(&tree).unwrap(), before);
+
+        std::fs::write(tree.join("src").join("a.json"), "b").unwrap();
+        let err = verify_digest("tree", &tree, &before).unwrap_err();
+        assert!(
+            matches!(err, RemoteSourceError::IntegrityMismatch { ref expected, .. } if *expected == before)
+        );
+        assert!(err.to_string().contains("Integrity check failed for tree"));
+    }
+}
@@ -1,7 +1,8 @@
 //! Remote source support for Morphir.
 //!
 //! This module provides functionality for fetching Morphir IR from remote sources,
-//! including HTTP/HTTPS, Git repositories, GitHub shorthand, and GitHub Gists.
+//! including HTTP/HTTPS, Git repositories, GitHub, GitLab, and Bitbucket
+//! shorthands, and GitHub Gists.
 //!
 //! # Overview
 //!
@@ -11,6 +12,8 @@
 //! - **HTTP/HTTPS**: `https://example.com/morphir-ir.json`
 //! - **Git repositories**: `https://github.com/org/repo.git`, `git@github.com:org/repo.git`
 //! - **GitHub shorthand**: `github:owner/repo`, `github:owner/repo@tag`, `github:owner/repo/path`
+//! - **GitLab shorthand**: `gitlab:owner/repo`, `gitlab:owner/repo/path@tag`
+//! - **Bitbucket shorthand**: `bitbucket:workspace/repo`, `bitbucket:workspace/repo@branch`
 //! - **GitHub Gists**: `gist:abc123`, `gist:abc123#filename.json`
 //!
 //! # Configuration
@@ -20,6 +23,7 @@
 //! ```toml
 //! [sources]
 //! enabled = true
+//! offline = false  # or `--offline` / MORPHIR_OFFLINE=1: resolve from the cache only
 //! allow = ["github:finos/*", "https://artifacts.example.com/*"]
 //! deny = ["*://untrusted.com/*"]
 //! trusted_github_orgs = ["finos", "morphir-org"]
@@ -31,6 +35,13 @@
 //!
 //! [sources.network]
 //! timeout_secs = 30
+//!
+//! [sources.auth.hosts."gitlab.example.com"]
+//! tokenEnv = "INTERNAL_GITLAB_TOKEN"
+//!
+//! [sources.integrity]
+//! minisignKeys = ["RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"]
+//! requireSignatures = true
 //! ```
 //!
 //! # Usage
@@ -55,17 +66,23 @@
 //! Remote sources are cached locally to improve performance and reduce network usage.
 //! The cache can be configured with size limits and TTL.
 
+pub mod auth;
 pub mod cache;
 pub mod config;
 pub mod error;
 pub mod git;
 pub mod http;
+pub mod integrity;
 pub mod resolver;
 pub mod source;
 
 // Re-exports for convenience
+pub use auth::{AuthConfig, Credential, CredentialResolver, HostCredentials};
 pub use cache::{CacheEntry, CacheStats, SourceCache};
-pub use config::{CacheConfig, NetworkConfig, RemoteSourceConfig};
+pub use config::{
+    CacheConfig, NetworkConfig, OFFLINE_ENV, RemoteSourceConfig, offline_requested, set_offline,
+};
 pub use error::{RemoteSourceError, Result};
+pub use integrity::{IntegrityConfig, SigstoreIdentity};
 pub use resolver::{RemoteSourceResolver, ResolveOptions};
 pub use source::{GitRef, RemoteSource};
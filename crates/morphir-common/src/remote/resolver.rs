@@ -4,11 +4,13 @@
 //! It handles caching, allow/deny lists, and delegates to the appropriate
 //! fetcher based on the source type.
 
+use crate::remote::auth::CredentialResolver;
 use crate::remote::cache::SourceCache;
 use crate::remote::config::RemoteSourceConfig;
 use crate::remote::error::{RemoteSourceError, Result};
 use crate::remote::git::GitFetcher;
-use crate::remote::http::{HttpFetcher, fetch_gist};
+use crate::remote::http::{HttpFetcher, fetch_bitbucket, fetch_gist, fetch_gitlab};
+use crate::remote::integrity::{split_digest, verify_digest, verify_minisign, verify_sigstore};
 use crate::remote::source::RemoteSource;
 use std::path::PathBuf;
 
@@ -23,6 +25,12 @@ pub struct ResolveOptions {
 
     /// Timeout override in seconds.
     pub timeout_secs: Option<u64>,
+
+    /// SHA-256 digest the resolved content must have.
+    pub sha256: Option<String>,
+
+    /// Resolve only from the cache, failing instead of fetching.
+    pub offline: bool,
 }
 
 impl ResolveOptions {
@@ -32,15 +40,31 @@ impl ResolveOptions {
             use_cache: true,
             force_refresh: false,
             timeout_secs: None,
+            sha256: None,
+            offline: false,
         }
     }
 
+    /// Resolve only from the cache.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Require the resolved content to have the given SHA-256 digest.
+    pub fn with_sha256(mut self, sha256: Option<String>) -> Self {
+        self.sha256 = sha256;
+        self
+    }
+
     /// Create options that skip the cache.
     pub fn no_cache() -> Self {
         Self {
             use_cache: false,
             force_refresh: false,
             timeout_secs: None,
+            sha256: None,
+            offline: false,
         }
     }
 
@@ -50,6 +74,8 @@ impl ResolveOptions {
             use_cache: true,
             force_refresh: true,
             timeout_secs: None,
+            sha256: None,
+            offline: false,
         }
     }
 }
@@ -76,8 +102,9 @@ impl RemoteSourceResolver {
     /// Create a new resolver with the given configuration.
     pub fn new(config: RemoteSourceConfig) -> Result<Self> {
         let cache = SourceCache::new(config.cache.clone())?;
-        let http = HttpFetcher::new(config.network.clone())?;
-        let git = GitFetcher::new();
+        let http = HttpFetcher::new(config.network.clone())?
+            .with_credentials(CredentialResolver::new(config.auth.clone()));
+        let git = GitFetcher::new().with_credentials(CredentialResolver::new(config.auth.clone()));
 
         Ok(Self {
             config,
@@ -106,27 +133,36 @@ impl RemoteSourceResolver {
             return true;
         }
 
-        // Check URL patterns
-        let url = source.to_url_string();
-        self.config.is_allowed(&url)
+        // Check URL patterns against the canonical URL and any shorthand
+        self.config.is_allowed_any(&source.match_forms())
     }
 
     /// Resolve a source string to a local path.
     ///
     /// This is a convenience method that parses the source and resolves it.
+    /// A `sha256=` digest in the source fragment is enforced unless the
+    /// options already pin one.
     pub fn resolve_string(
         &mut self,
         source_str: &str,
         options: &ResolveOptions,
     ) -> Result<PathBuf> {
-        let source = RemoteSource::parse(source_str)?;
+        let (source_str, digest) = split_digest(source_str);
+        let source = RemoteSource::parse(&source_str)?;
+        if options.sha256.is_none() && digest.is_some() {
+            let options = options.clone().with_sha256(digest);
+            return self.resolve(&source, &options);
+        }
         self.resolve(&source, options)
     }
 
     /// Resolve a source to a local path.
     ///
     /// This method handles caching, allow/deny checks, and delegates to
-    /// the appropriate fetcher based on the source type.
+    /// the appropriate fetcher based on the source type. Cached content is
+    /// re-hashed against the digest recorded when it was fetched, any digest
+    /// pinned in `options` is enforced, and, when signatures are required,
+    /// only copies whose signature was verified on fetch are served.
     pub fn resolve(&mut self, source: &RemoteSource, options: &ResolveOptions) -> Result<PathBuf> {
         // Check allow/deny lists
         if !self.is_allowed(source) {
@@ -138,27 +174,128 @@ impl RemoteSourceResolver {
             if !path.exists() {
                 return Err(RemoteSourceError::NotFound(path.display().to_string()));
             }
+            if let Some(expected) = &options.sha256 {
+                verify_digest(&source.to_string(), path, expected)?;
+            }
             return Ok(path.clone());
         }
 
-        // Check cache
-        if options.use_cache
-            && !options.force_refresh
-            && let Some(cached_path) = self.cache.get(source)
-        {
+        // Check cache. Offline, any cached copy will do, however old.
+        let offline = options.offline || self.config.is_offline();
+        let cached = if offline {
+            self.cache.get_any(source)
+        } else if options.use_cache && !options.force_refresh {
+            self.cache.get(source)
+        } else {
+            None
+        };
+        if let Some(cached_path) = cached {
+            if let Some(recorded) = self.cache.recorded_digest(source) {
+                verify_digest(&source.to_string(), &cached_path, &recorded).map_err(|err| {
+                    RemoteSourceError::CacheError(format!(
+                        "cached copy was modified after download ({}); re-fetch with --force-refresh",
+                        err
+                    ))
+                })?;
+            }
+            if let Some(expected) = &options.sha256 {
+                verify_digest(&source.to_string(), &cached_path, expected)?;
+            }
+            if self.config.integrity.require_signatures && !self.cache.is_signed(source) {
+                return Err(RemoteSourceError::SignatureError(format!(
+                    "cached copy of {} was not signature-checked when it was fetched; re-fetch with --force-refresh",
+                    source
+                )));
+            }
             return Ok(cached_path);
         }
 
+        if offline {
+            return Err(RemoteSourceError::Offline(source.to_string()));
+        }
+
+        let path = self.fetch(source)?;
+        if let Some(expected) = &options.sha256
+            && let Err(err) = verify_digest(&source.to_string(), &path, expected)
+        {
+            // Never leave unverified content behind for later cache hits
+            self.cache.remove(source)?;
+            return Err(err);
+        }
+        Ok(path)
+    }
+
+    /// Whether `source` has a valid copy in the cache.
+    pub fn is_cached(&self, source: &RemoteSource) -> bool {
+        self.cache.get(source).is_some()
+    }
+
+    /// Check the signatures of downloaded `content` from `url`, as
+    /// configured in `[sources.integrity]`, returning whether a trusted
+    /// signature was verified.
+    fn verify_signatures(&self, url: &str, content: &[u8]) -> Result<bool> {
+        let integrity = &self.config.integrity;
+        let mut verified = false;
+
+        if !integrity.minisign_keys.is_empty()
+            && let Ok(signature) = self.http.fetch_bytes(&format!("{}.minisig", url))
+        {
+            verify_minisign(
+                content,
+                &String::from_utf8_lossy(&signature),
+                &integrity.minisign_keys,
+            )?;
+            verified = true;
+        }
+
+        if let Some(identity) = &integrity.sigstore
+            && let Ok(bundle) = self.http.fetch_bytes(&format!("{}.sigstore.json", url))
+        {
+            verify_sigstore(content, &bundle, identity)?;
+            verified = true;
+        }
+
+        if integrity.require_signatures && !verified {
+            return Err(RemoteSourceError::SignatureError(format!(
+                "{} has no signature from a trusted key (looked for {}.minisig and {}.sigstore.json)",
+                url, url, url
+            )));
+        }
+        Ok(verified)
+    }
+
+    /// Fetch a source into the cache.
+    fn fetch(&mut self, source: &RemoteSource) -> Result<PathBuf> {
+        if self.config.integrity.require_signatures && !matches!(source, RemoteSource::Http { .. })
+        {
+            return Err(RemoteSourceError::SignatureError(format!(
+                "signatures are required, but can only be checked for HTTP sources, not {}",
+                source
+            )));
+        }
+
         // Fetch based on source type
         match source {
             RemoteSource::Local { path } => {
-                // Already handled above
+                // Already handled in `resolve`
                 Ok(path.clone())
             }
 
             RemoteSource::Http { url, subpath } => {
-                self.http
-                    .fetch_to_cache(url, subpath.as_deref(), &mut self.cache, source)
+                let content = self.http.fetch_bytes(url)?;
+                let signed = self.config.integrity.checks_signatures()
+                    && self.verify_signatures(url, &content)?;
+                let path = self.http.cache_bytes(
+                    url,
+                    &content,
+                    subpath.as_deref(),
+                    &mut self.cache,
+                    source,
+                )?;
+                if signed {
+                    self.cache.mark_signed(source)?;
+                }
+                Ok(path)
             }
 
             RemoteSource::Git {
@@ -187,6 +324,64 @@ impl RemoteSourceResolver {
                 source,
             ),
 
+            RemoteSource::GitLab {
+                owner,
+                repo,
+                reference,
+                subpath,
+            } => {
+                // Prefer the archive API; fall back to a clone when it is
+                // unavailable (e.g. private projects needing git credentials)
+                match fetch_gitlab(
+                    &self.http,
+                    owner,
+                    repo,
+                    reference.as_ref(),
+                    subpath.as_deref(),
+                    &mut self.cache,
+                    source,
+                ) {
+                    Err(err) if !matches!(err, RemoteSourceError::PathNotFound { .. }) => {
+                        self.git.clone_gitlab(
+                            owner,
+                            repo,
+                            reference.as_ref(),
+                            subpath.as_deref(),
+                            &mut self.cache,
+                            source,
+                        )
+                    }
+                    result => result,
+                }
+            }
+
+            RemoteSource::Bitbucket {
+                owner,
+                repo,
+                reference,
+                subpath,
+            } => match fetch_bitbucket(
+                &self.http,
+                owner,
+                repo,
+                reference.as_ref(),
+                subpath.as_deref(),
+                &mut self.cache,
+                source,
+            ) {
+                Err(err) if !matches!(err, RemoteSourceError::PathNotFound { .. }) => {
+                    self.git.clone_bitbucket(
+                        owner,
+                        repo,
+                        reference.as_ref(),
+                        subpath.as_deref(),
+                        &mut self.cache,
+                        source,
+                    )
+                }
+                result => result,
+            },
+
             RemoteSource::Gist {
                 id,
                 revision,
@@ -287,6 +482,102 @@ mod tests {
         assert!(resolver.is_allowed(&source));
     }
 
+    #[test]
+    fn test_allow_deny_for_gitlab_and_bitbucket() {
+        let config = RemoteSourceConfig {
+            allow: vec![
+                "gitlab:acme/*".to_string(),
+                "https://bitbucket.org/acme/*".to_string(),
+            ],
+            deny: vec!["bitbucket:acme/secret*".to_string()],
+            ..Default::default()
+        };
+
+        let resolver = RemoteSourceResolver::new(config).unwrap();
+
+        let allowed = |s: &str| resolver.is_allowed(&RemoteSource::parse(s).unwrap());
+        assert!(allowed("gitlab:acme/models@v1.0"));
+        assert!(!allowed("gitlab:other/models"));
+        assert!(allowed("bitbucket:acme/models"));
+        assert!(!allowed("bitbucket:acme/secrets"));
+    }
+
+    #[test]
+    fn test_pinned_digests_are_enforced() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("morphir-ir.json");
+        std::fs::write(&file, "{}").unwrap();
+        let digest = crate::remote::integrity::digest_path(&file).unwrap();
+        let mut resolver = RemoteSourceResolver::with_defaults().unwrap();
+
+        let pinned = format!("{}#sha256={}", file.display(), digest);
+        assert_eq!(
+            resolver
+                .resolve_string(&pinned, &ResolveOptions::new())
+                .unwrap(),
+            file
+        );
+
+        std::fs::write(&file, "[]").unwrap();
+        let err = resolver
+            .resolve_string(&pinned, &ResolveOptions::new())
+            .unwrap_err();
+        assert!(matches!(err, RemoteSourceError::IntegrityMismatch { .. }));
+    }
+
+    #[test]
+    fn test_offline_resolution_uses_only_the_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = RemoteSourceConfig {
+            offline: true,
+            ..Default::default()
+        };
+        config.cache.directory = Some(temp.path().join("cache"));
+        let mut cache = SourceCache::new(config.cache.clone()).unwrap();
+        let cached = RemoteSource::parse("https://example.com/cached.json").unwrap();
+        cache.put_bytes(&cached, b"{}").unwrap();
+        let mut resolver = RemoteSourceResolver::new(config).unwrap();
+
+        // Even a forced refresh is served from the cache
+        assert!(
+            resolver
+                .resolve(&cached, &ResolveOptions::force_refresh())
+                .is_ok()
+        );
+
+        let missing = RemoteSource::parse("https://example.com/missing.json").unwrap();
+        let err = resolver
+            .resolve(&missing, &ResolveOptions::new())
+            .unwrap_err();
+        assert!(matches!(err, RemoteSourceError::Offline(_)));
+        assert!(err.to_string().contains("morphir source prefetch"));
+    }
+
+    #[test]
+    fn test_unsigned_cache_hits_are_refused_when_signatures_are_required() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = RemoteSourceConfig {
+            offline: true,
+            ..Default::default()
+        };
+        config.cache.directory = Some(temp.path().join("cache"));
+        config.integrity.require_signatures = true;
+        let mut cache = SourceCache::new(config.cache.clone()).unwrap();
+        let source = RemoteSource::parse("https://example.com/signed.json").unwrap();
+        cache.put_bytes(&source, b"{}").unwrap();
+
+        // Cached before signatures were required
+        let mut resolver = RemoteSourceResolver::new(config.clone()).unwrap();
+        let err = resolver
+            .resolve(&source, &ResolveOptions::new())
+            .unwrap_err();
+        assert!(matches!(err, RemoteSourceError::SignatureError(_)));
+
+        cache.mark_signed(&source).unwrap();
+        let mut resolver = RemoteSourceResolver::new(config).unwrap();
+        assert!(resolver.resolve(&source, &ResolveOptions::new()).is_ok());
+    }
+
     #[test]
     fn test_disabled_config() {
         let config = RemoteSourceConfig::disabled();
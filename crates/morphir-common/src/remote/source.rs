@@ -45,6 +45,31 @@ pub enum RemoteSource {
         subpath: Option<String>,
     },
 
+    /// GitLab shorthand (gitlab:user/repo)
+    #[serde(rename = "gitlab")]
+    GitLab {
+        /// Repository owner (user or group)
+        owner: String,
+        /// Repository name
+        repo: String,
+        /// Git reference (branch, tag, or commit)
+        reference: Option<GitRef>,
+        /// Optional path within the repository
+        subpath: Option<String>,
+    },
+
+    /// Bitbucket shorthand (bitbucket:workspace/repo)
+    Bitbucket {
+        /// Workspace that owns the repository
+        owner: String,
+        /// Repository name
+        repo: String,
+        /// Git reference (branch, tag, or commit)
+        reference: Option<GitRef>,
+        /// Optional path within the repository
+        subpath: Option<String>,
+    },
+
     /// GitHub Gist (gist:id or gist:user/id)
     Gist {
         /// Gist ID
@@ -77,9 +102,15 @@ impl RemoteSource {
     /// - Git HTTPS: `https://github.com/org/repo.git`
     /// - Git SSH: `git@github.com:org/repo.git`
     /// - GitHub shorthand: `github:owner/repo`, `github:owner/repo@tag`, `github:owner/repo/path/to/file`
+    /// - GitLab shorthand: `gitlab:owner/repo`, `gitlab:owner/repo/path@tag`
+    /// - Bitbucket shorthand: `bitbucket:workspace/repo`, `bitbucket:workspace/repo/path@branch`
     /// - Gist: `gist:abc123`, `gist:user/abc123`, `gist:abc123#filename.json`
+    ///
+    /// A `sha256=` digest in the fragment is not part of the source; see
+    /// [`split_digest`](crate::remote::integrity::split_digest).
     pub fn parse(input: &str) -> Result<Self> {
-        let input = input.trim();
+        let (input, _) = crate::remote::integrity::split_digest(input.trim());
+        let input = input.as_str();
 
         if input.is_empty() {
             return Err(RemoteSourceError::InvalidFormat(
@@ -92,6 +123,14 @@ impl RemoteSource {
             return Self::parse_github(rest);
         }
 
+        if let Some(rest) = input.strip_prefix("gitlab:") {
+            return Self::parse_gitlab(rest);
+        }
+
+        if let Some(rest) = input.strip_prefix("bitbucket:") {
+            return Self::parse_bitbucket(rest);
+        }
+
         if let Some(rest) = input.strip_prefix("gist:") {
             return Self::parse_gist(rest);
         }
@@ -164,10 +203,48 @@ impl RemoteSource {
 
     /// Parse a GitHub shorthand.
     fn parse_github(input: &str) -> Result<Self> {
+        let (owner, repo, reference, subpath) = Self::parse_repo_shorthand(input, "GitHub")?;
+        Ok(RemoteSource::GitHub {
+            owner,
+            repo,
+            reference,
+            subpath,
+        })
+    }
+
+    /// Parse a GitLab shorthand.
+    fn parse_gitlab(input: &str) -> Result<Self> {
+        let (owner, repo, reference, subpath) = Self::parse_repo_shorthand(input, "GitLab")?;
+        Ok(RemoteSource::GitLab {
+            owner,
+            repo,
+            reference,
+            subpath,
+        })
+    }
+
+    /// Parse a Bitbucket shorthand.
+    fn parse_bitbucket(input: &str) -> Result<Self> {
+        let (owner, repo, reference, subpath) = Self::parse_repo_shorthand(input, "Bitbucket")?;
+        Ok(RemoteSource::Bitbucket {
+            owner,
+            repo,
+            reference,
+            subpath,
+        })
+    }
+
+    /// Parse the `owner/repo[/path][@ref]` part shared by the hosted
+    /// repository shorthands.
+    fn parse_repo_shorthand(
+        input: &str,
+        host: &str,
+    ) -> Result<(String, String, Option<GitRef>, Option<String>)> {
         if input.is_empty() {
-            return Err(RemoteSourceError::InvalidFormat(
-                "Empty GitHub reference".to_string(),
-            ));
+            return Err(RemoteSourceError::InvalidFormat(format!(
+                "Empty {} reference",
+                host
+            )));
         }
 
         let mut parts = input.to_string();
@@ -184,10 +261,10 @@ impl RemoteSource {
         // Split by /
         let segments: Vec<&str> = parts.split('/').collect();
 
-        if segments.len() < 2 {
+        if segments.len() < 2 || segments[0].is_empty() || segments[1].is_empty() {
             return Err(RemoteSourceError::InvalidFormat(format!(
-                "Invalid GitHub reference: {}. Expected format: owner/repo[/path][@ref]",
-                input
+                "Invalid {} reference: {}. Expected format: owner/repo[/path][@ref]",
+                host, input
             )));
         }
 
@@ -199,12 +276,7 @@ impl RemoteSource {
             subpath = Some(segments[2..].join("/"));
         }
 
-        Ok(RemoteSource::GitHub {
-            owner,
-            repo,
-            reference,
-            subpath,
-        })
+        Ok((owner, repo, reference, subpath))
     }
 
     /// Parse a Gist reference.
@@ -292,10 +364,28 @@ impl RemoteSource {
             RemoteSource::Http { .. } => "http",
             RemoteSource::Git { .. } => "git",
             RemoteSource::GitHub { .. } => "github",
+            RemoteSource::GitLab { .. } => "gitlab",
+            RemoteSource::Bitbucket { .. } => "bitbucket",
             RemoteSource::Gist { .. } => "gist",
         }
     }
 
+    /// Forms of this source that allow/deny patterns are matched against:
+    /// the canonical URL and, for shorthand sources, the shorthand itself.
+    pub fn match_forms(&self) -> Vec<String> {
+        let mut forms = vec![self.to_url_string()];
+        if matches!(
+            self,
+            RemoteSource::GitHub { .. }
+                | RemoteSource::GitLab { .. }
+                | RemoteSource::Bitbucket { .. }
+                | RemoteSource::Gist { .. }
+        ) {
+            forms.push(self.to_string());
+        }
+        forms
+    }
+
     /// Convert to a canonical URL string (for matching allow/deny patterns).
     pub fn to_url_string(&self) -> String {
         match self {
@@ -326,16 +416,19 @@ impl RemoteSource {
                 repo,
                 reference,
                 subpath,
-            } => {
-                let mut result = format!("https://github.com/{}/{}", owner, repo);
-                if let Some(sub) = subpath {
-                    result = format!("{}/{}", result, sub);
-                }
-                if let Some(ref_) = reference {
-                    result = format!("{}@{}", result, ref_);
-                }
-                result
-            }
+            } => repo_url("https://github.com", owner, repo, reference, subpath),
+            RemoteSource::GitLab {
+                owner,
+                repo,
+                reference,
+                subpath,
+            } => repo_url("https://gitlab.com", owner, repo, reference, subpath),
+            RemoteSource::Bitbucket {
+                owner,
+                repo,
+                reference,
+                subpath,
+            } => repo_url("https://bitbucket.org", owner, repo, reference, subpath),
             RemoteSource::Gist {
                 id,
                 revision,
@@ -384,16 +477,31 @@ impl fmt::Display for RemoteSource {
                 repo,
                 reference,
                 subpath,
-            } => {
-                write!(f, "github:{}/{}", owner, repo)?;
-                if let Some(sub) = subpath {
-                    write!(f, "/{}", sub)?;
-                }
-                if let Some(ref_) = reference {
-                    write!(f, "@{}", ref_)?;
-                }
-                Ok(())
-            }
+            } => write!(
+                f,
+                "{}",
+                repo_url("github:", owner, repo, reference, subpath)
+            ),
+            RemoteSource::GitLab {
+                owner,
+                repo,
+                reference,
+                subpath,
+            } => write!(
+                f,
+                "{}",
+                repo_url("gitlab:", owner, repo, reference, subpath)
+            ),
+            RemoteSource::Bitbucket {
+                owner,
+                repo,
+                reference,
+                subpath,
+            } => write!(
+                f,
+                "{}",
+                repo_url("bitbucket:", owner, repo, reference, subpath)
+            ),
             RemoteSource::Gist {
                 id,
                 revision,
@@ -412,6 +520,26 @@ impl fmt::Display for RemoteSource {
     }
 }
 
+/// Render a hosted repository as `{prefix}owner/repo[/path][@ref]`. URL
+/// prefixes are joined with a `/`, shorthand schemes (ending in `:`) are not.
+fn repo_url(
+    prefix: &str,
+    owner: &str,
+    repo: &str,
+    reference: &Option<GitRef>,
+    subpath: &Option<String>,
+) -> String {
+    let separator = if prefix.ends_with(':') { "" } else { "/" };
+    let mut result = format!("{}{}{}/{}", prefix, separator, owner, repo);
+    if let Some(sub) = subpath {
+        result = format!("{}/{}", result, sub);
+    }
+    if let Some(ref_) = reference {
+        result = format!("{}@{}", result, ref_);
+    }
+    result
+}
+
 impl fmt::Display for GitRef {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -482,6 +610,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_gitlab_and_bitbucket_shorthands() {
+        let source = RemoteSource::parse("gitlab:acme/models/ir/morphir-ir.json@v2.1").unwrap();
+        assert!(
+            matches!(source, RemoteSource::GitLab { ref owner, ref repo, reference: Some(GitRef::Tag(ref t)), subpath: Some(ref sub) }
+            if owner == "acme" && repo == "models" && t == "v2.1" && sub == "ir/morphir-ir.json")
+        );
+        assert_eq!(source.source_type(), "gitlab");
+        assert_eq!(
+            source.to_url_string(),
+            "https://gitlab.com/acme/models/ir/morphir-ir.json@v2.1"
+        );
+        assert_eq!(
+            source.to_string(),
+            "gitlab:acme/models/ir/morphir-ir.json@v2.1"
+        );
+
+        let source = RemoteSource::parse("bitbucket:acme/models@develop").unwrap();
+        assert!(
+            matches!(source, RemoteSource::Bitbucket { ref owner, ref repo, reference: Some(GitRef::Branch(ref b)), subpath: None }
+            if owner == "acme" && repo == "models" && b == "develop")
+        );
+        assert_eq!(
+            source.to_url_string(),
+            "https://bitbucket.org/acme/models@develop"
+        );
+        assert_eq!(
+            source.match_forms(),
+            vec![
+                "https://bitbucket.org/acme/models@develop".to_string(),
+                "bitbucket:acme/models@develop".to_string()
+            ]
+        );
+
+        assert!(RemoteSource::parse("gitlab:acme").is_err());
+        assert!(RemoteSource::parse("bitbucket:").is_err());
+    }
+
     #[test]
     fn test_parse_gist() {
         let source = RemoteSource::parse("gist:abc123").unwrap();
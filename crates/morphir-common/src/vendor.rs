@@ -0,0 +1,462 @@
+//! Vendoring remote dependencies into the workspace
+//!
+//! [`vendor`] resolves every remote dependency, and optionally every remote
+//! extension, and copies it under the `[vendor] directory` of the workspace
+//! (`vendor/` by default). The lockfile is rewritten to point at the copies,
+//! so builds no longer need the network and the vendored content can be
+//! reviewed in source control.
+//!
+//! ```toml
+//! [vendor]
+//! directory = "vendor"
+//! extensions = true
+//! ```
+
+use crate::Result;
+use crate::config::{DependencySpec, ExtensionSpec, VendorSection};
+use crate::lockfile::{LockedSource, Lockfile};
+use crate::remote::integrity::{digest_path, split_digest};
+use crate::remote::{GitRef, RemoteSource, RemoteSourceResolver, ResolveOptions};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// What is vendored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VendoredKind {
+    Dependency,
+    Extension,
+}
+
+/// A dependency or extension copied into the workspace
+#[derive(Debug, Clone, Serialize)]
+pub struct Vendored {
+    pub name: String,
+    pub kind: VendoredKind,
+    /// Source it was resolved from
+    pub source: String,
+    /// The copy, relative to the workspace root
+    pub path: PathBuf,
+}
+
+/// A dependency or extension left as it is
+#[derive(Debug, Clone, Serialize)]
+pub struct Skipped {
+    pub name: String,
+    pub kind: VendoredKind,
+    pub reason: String,
+}
+
+/// Outcome of vendoring
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VendorReport {
+    pub vendored: Vec<Vendored>,
+    pub skipped: Vec<Skipped>,
+    /// Copies of dependencies no longer declared, removed
+    pub removed: Vec<PathBuf>,
+}
+
+/// Remote source of a dependency, if it has one. Path and workspace
+/// dependencies are already local, and plain versions name no source.
+pub fn dependency_source(spec: &DependencySpec) -> std::result::Result<RemoteSource, String> {
+    match spec {
+        DependencySpec::Version(version) => RemoteSource::parse(version)
+            .ok()
+            .filter(|source| !source.is_local())
+            .ok_or_else(|| {
+                format!(
+                    "version {} names no source; registry dependencies cannot be vendored yet",
+                    version
+                )
+            }),
+        DependencySpec::Detailed(detailed) => {
+            if let Some(url) = &detailed.git {
+                let reference = detailed
+                    .rev
+                    .clone()
+                    .map(GitRef::Commit)
+                    .or_else(|| detailed.tag.clone().map(GitRef::Tag))
+                    .or_else(|| detailed.branch.clone().map(GitRef::Branch));
+                Ok(RemoteSource::Git {
+                    url: url.clone(),
+                    reference,
+                    subpath: None,
+                })
+            } else if detailed.path.is_some() {
+                Err("path dependencies are already local".to_string())
+            } else if detailed.workspace == Some(true) {
+                Err("workspace dependencies are already local".to_string())
+            } else {
+                Err("no git source or path".to_string())
+            }
+        }
+    }
+}
+
+/// Digest a dependency's source string pins with a `sha256=` fragment
+pub fn dependency_digest(spec: &DependencySpec) -> Option<String> {
+    match spec {
+        DependencySpec::Version(version) => split_digest(version).1,
+        DependencySpec::Detailed(_) => None,
+    }
+}
+
+/// Remote source of an extension, if it is downloaded from a URL
+pub fn extension_source(spec: &ExtensionSpec) -> std::result::Result<RemoteSource, String> {
+    let url = spec
+        .url
+        .as_ref()
+        .ok_or_else(|| "not downloaded from a URL".to_string())?;
+    RemoteSource::parse(url)
+        .ok()
+        .filter(|source| !source.is_local())
+        .ok_or_else(|| format!("{} is not a remote source", url))
+}
+
+/// Something to vendor: its kind and name, its remote source (or why it
+/// has none), and the digest its source string pins
+type Pending<'a> = (
+    VendoredKind,
+    &'a String,
+    std::result::Result<RemoteSource, String>,
+    Option<String>,
+);
+
+/// Copy the remote `dependencies`, and the remote `extensions` if the
+/// section asks for them, into the workspace at `root` and rewrite its
+/// lockfile to the copies
+pub fn vendor(
+    root: &Path,
+    section: &VendorSection,
+    dependencies: &BTreeMap<String, DependencySpec>,
+    extensions: &BTreeMap<String, ExtensionSpec>,
+    resolver: &mut RemoteSourceResolver,
+    options: &ResolveOptions,
+) -> Result<VendorReport> {
+    let previous = Lockfile::load(root)?;
+    let mut lockfile = Lockfile::default();
+    let mut report = VendorReport::default();
+    let directory = PathBuf::from(&section.directory);
+
+    let mut pending: Vec<Pending> = dependencies
+        .iter()
+        .map(|(name, spec)| {
+            (
+                VendoredKind::Dependency,
+                name,
+                dependency_source(spec),
+                dependency_digest(spec),
+            )
+        })
+        .collect();
+    if section.extensions {
+        pending.extend(
+            extensions
+                .iter()
+                .filter(|(_, spec)| spec.enabled)
+                .map(|(name, spec)| {
+                    (
+                        VendoredKind::Extension,
+                        name,
+                        extension_source(spec),
+                        spec.url.as_deref().and_then(|url| split_digest(url).1),
+                    )
+                }),
+        );
+    }
+
+    for (kind, name, source, pinned) in pending {
+        let source = match source {
+            Ok(source) => source,
+            Err(reason) => {
+                report.skipped.push(Skipped {
+                    name: name.clone(),
+                    kind,
+                    reason,
+                });
+                continue;
+            }
+        };
+        // A pinned digest, or else the one locked by an earlier run, must
+        // still match
+        let expected = options
+            .sha256
+            .clone()
+            .or(pinned)
+            .or_else(|| previous.locked_digest(&source));
+        let resolved = resolver
+            .resolve(&source, &options.clone().with_sha256(expected))
+            .with_context(|| format!("Failed to resolve {} from {}", name, source))?;
+        let sha256 = digest_path(&resolved)
+            .with_context(|| format!("Failed to hash {}", resolved.display()))?;
+
+        let target = vendor_dir(&directory, kind, name);
+        let copied = copy_into(&resolved, &root.join(&target), &file_name(&source))
+            .with_context(|| format!("Failed to vendor {} into {}", name, target.display()))?;
+        let path = match copied {
+            Some(file_name) => target.join(file_name),
+            None => target,
+        };
+        let locked = LockedSource {
+            source: source.to_string(),
+            path: Some(path.clone()),
+            sha256: Some(sha256),
+        };
+        match kind {
+            VendoredKind::Dependency => lockfile.dependencies.insert(name.clone(), locked),
+            VendoredKind::Extension => lockfile.extensions.insert(name.clone(), locked),
+        };
+        report.vendored.push(Vendored {
+            name: name.clone(),
+            kind,
+            source: source.to_string(),
+            path,
+        });
+    }
+
+    // Forget the copies of whatever is no longer vendored
+    let stale = previous
+        .dependencies
+        .keys()
+        .filter(|name| !lockfile.dependencies.contains_key(*name))
+        .map(|name| vendor_dir(&directory, VendoredKind::Dependency, name))
+        .chain(
+            previous
+                .extensions
+                .keys()
+                .filter(|name| !lockfile.extensions.contains_key(*name))
+                .map(|name| vendor_dir(&directory, VendoredKind::Extension, name)),
+        );
+    for dir in stale {
+        if root.join(&dir).is_dir() {
+            std::fs::remove_dir_all(root.join(&dir))
+                .with_context(|| format!("Failed to remove {}", dir.display()))?;
+            report.removed.push(dir);
+        }
+    }
+
+    lockfile.save(root)?;
+    Ok(report)
+}
+
+/// Directory of a vendored copy, relative to the workspace root
+fn vendor_dir(directory: &Path, kind: VendoredKind, name: &str) -> PathBuf {
+    let slug = name.replace(['/', '\\', ' ', ':'], "-");
+    match kind {
+        VendoredKind::Dependency => directory.join(slug),
+        VendoredKind::Extension => directory.join("extensions").join(slug),
+    }
+}
+
+/// Name for a vendored file from `source`. Cached files are named by their
+/// cache key, so the name comes from the source itself.
+fn file_name(source: &RemoteSource) -> String {
+    let named = match source {
+        RemoteSource::Http { url, subpath } => subpath
+            .as_deref()
+            .unwrap_or(url.split(['?', '#']).next().unwrap_or_default())
+            .rsplit('/')
+            .next()
+            .map(String::from),
+        RemoteSource::Gist { filename, .. } => filename.clone(),
+        _ => None,
+    };
+    named
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "morphir-ir.json".to_string())
+}
+
+/// Replace `target` with a copy of `source`. A file is copied into the
+/// directory as `file_name`, which is returned.
+fn copy_into(source: &Path, target: &Path, file_name: &str) -> Result<Option<String>> {
+    if target.exists() {
+        std::fs::remove_dir_all(target)?;
+    }
+    std::fs::create_dir_all(target)?;
+    if source.is_dir() {
+        copy_dir(source, target)?;
+        return Ok(None);
+    }
+    std::fs::copy(source, target.join(file_name))?;
+    Ok(Some(file_name.to_string()))
+}
+
+fn copy_dir(source: &Path, target: &Path) -> Result<()> {
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let destination = target.join(entry.file_name());
+        if path.is_dir() {
+            std::fs::create_dir_all(&destination)?;
+            copy_dir(&path, &destination)?;
+        } else {
+            std::fs::copy(&path, &destination)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DetailedDependency;
+    use crate::remote::{RemoteSourceConfig, SourceCache};
+
+    fn detailed(git: Option<&str>, path: Option<&str>) -> DependencySpec {
+        DependencySpec::Detailed(DetailedDependency {
+            version: None,
+            path: path.map(PathBuf::from),
+            git: git.map(String::from),
+            tag: Some("v1.2.0".to_string()),
+            branch: None,
+            rev: None,
+            workspace: None,
+        })
+    }
+
+    #[test]
+    fn test_only_remote_dependencies_have_a_source() {
+        assert_eq!(
+            dependency_source(&DependencySpec::Version(
+                "github:finos/morphir-sdk@v1.0.0".to_string()
+            ))
+            .unwrap()
+            .to_string(),
+            "github:finos/morphir-sdk@v1.0.0"
+        );
+        assert_eq!(
+            dependency_source(&detailed(Some("https://example.com/sdk.git"), None))
+                .unwrap()
+                .to_string(),
+            "https://example.com/sdk.git@v1.2.0"
+        );
+        assert!(
+            dependency_source(&DependencySpec::Version("^1.0".to_string()))
+                .unwrap_err()
+                .contains("registry")
+        );
+        assert!(dependency_source(&detailed(None, Some("../sdk"))).is_err());
+    }
+
+    #[test]
+    fn test_vendoring_copies_sources_and_rewrites_the_lockfile() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("workspace");
+        std::fs::create_dir_all(&root).unwrap();
+        // Stand-ins for resolved remote sources: the cache hands back local copies
+        let remote = temp.path().join("remote");
+        std::fs::create_dir_all(remote.join("sdk").join(".git")).unwrap();
+        std::fs::write(remote.join("sdk").join("morphir-ir.json"), "{}").unwrap();
+        std::fs::write(remote.join("ext.wasm"), b"\0asm").unwrap();
+
+        let mut config = RemoteSourceConfig::default();
+        config.cache.directory = Some(temp.path().join("cache"));
+        let mut cache = SourceCache::new(config.cache.clone()).unwrap();
+        let sdk = RemoteSource::parse("github:finos/morphir-sdk@v1.0.0").unwrap();
+        let ext = RemoteSource::parse("https://example.com/ext.wasm").unwrap();
+        cache.put(&sdk, &remote.join("sdk")).unwrap();
+        cache.put(&ext, &remote.join("ext.wasm")).unwrap();
+        let mut resolver = RemoteSourceResolver::new(config).unwrap();
+
+        // A copy left by an earlier run, for a dependency since removed
+        let mut previous = Lockfile::default();
+        previous.dependencies.insert(
+            "old/dep".to_string(),
+            LockedSource {
+                source: "github:old/dep".to_string(),
+                path: Some(PathBuf::from("vendor/old-dep")),
+                sha256: None,
+            },
+        );
+        previous.save(&root).unwrap();
+        std::fs::create_dir_all(root.join("vendor").join("old-dep")).unwrap();
+
+        let dependencies = BTreeMap::from([
+            (
+                "finos/sdk".to_string(),
+                DependencySpec::Version("github:finos/morphir-sdk@v1.0.0".to_string()),
+            ),
+            ("local/lib".to_string(), detailed(None, Some("../lib"))),
+        ]);
+        let extensions = BTreeMap::from([(
+            "my-ext".to_string(),
+            ExtensionSpec {
+                path: None,
+                url: Some("https://example.com/ext.wasm".to_string()),
+                command: None,
+                args: Vec::new(),
+                enabled: true,
+                config: Default::default(),
+            },
+        )]);
+        let section = VendorSection {
+            extensions: true,
+            ..VendorSection::default()
+        };
+        let report = vendor(
+            &root,
+            &section,
+            &dependencies,
+            &extensions,
+            &mut resolver,
+            &ResolveOptions::new(),
+        )
+        .unwrap();
+
+        assert_eq!(report.vendored.len(), 2);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].name, "local/lib");
+        assert_eq!(report.removed, vec![PathBuf::from("vendor/old-dep")]);
+        assert!(!root.join("vendor/old-dep").exists());
+        assert!(root.join("vendor/finos-sdk/morphir-ir.json").is_file());
+        assert!(!root.join("vendor/finos-sdk/.git").exists());
+        assert!(root.join("vendor/extensions/my-ext/ext.wasm").is_file());
+
+        let lockfile = Lockfile::load(&root).unwrap();
+        assert_eq!(
+            lockfile.dependencies["finos/sdk"],
+            LockedSource {
+                source: "github:finos/morphir-sdk@v1.0.0".to_string(),
+                path: Some(PathBuf::from("vendor/finos-sdk")),
+                sha256: Some(digest_path(&remote.join("sdk")).unwrap()),
+            }
+        );
+        assert_eq!(
+            lockfile.extensions["my-ext"].path,
+            Some(PathBuf::from("vendor/extensions/my-ext/ext.wasm"))
+        );
+        assert_eq!(
+            lockfile.vendored(&root, &sdk),
+            Some(root.join("vendor/finos-sdk"))
+        );
+
+        // Content that no longer matches the locked digest is rejected
+        std::fs::write(
+            resolver
+                .resolve(&sdk, &ResolveOptions::new())
+                .unwrap()
+                .join("morphir-ir.json"),
+            "{\"tampered\": true}",
+        )
+        .unwrap();
+        let err = vendor(
+            &root,
+            &section,
+            &dependencies,
+            &extensions,
+            &mut resolver,
+            &ResolveOptions::new(),
+        )
+        .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("modified after download"),
+            "{:#}",
+            err
+        );
+    }
+}
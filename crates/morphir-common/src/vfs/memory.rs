@@ -1,4 +1,4 @@
-use super::{FileMetadata, Vfs};
+use super::{FileMetadata, Vfs, VirtualPath};
 use std::collections::HashMap;
 use std::io::Result;
 use std::path::{Path, PathBuf};
@@ -15,18 +15,10 @@ impl MemoryVfs {
         Self::default()
     }
 
+    /// Keys are stored in normalized form so that `a\b` and `a/b` refer to
+    /// the same file regardless of the host platform.
     fn normalize_path(path: &Path) -> PathBuf {
-        let mut normalized = PathBuf::new();
-        for component in path.components() {
-            if let std::path::Component::CurDir = component {
-                continue;
-            }
-            normalized.push(component);
-        }
-        if normalized.as_os_str().is_empty() {
-            return PathBuf::from(".");
-        }
-        normalized
+        PathBuf::from(VirtualPath::from_path(path).as_str())
     }
 }
 
@@ -186,3 +178,25 @@ impl Vfs for MemoryVfs {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_and_unix_paths_share_keys() {
+        let vfs = MemoryVfs::new();
+        vfs.write_from_string(Path::new(r"src\Foo\Bar.json"), "{}")
+            .unwrap();
+
+        assert_eq!(
+            vfs.read_to_string(Path::new("src/Foo/Bar.json")).unwrap(),
+            "{}"
+        );
+        assert!(vfs.is_dir(Path::new("./src/Foo")));
+        assert_eq!(
+            vfs.glob("src/**/*.json").unwrap(),
+            vec![PathBuf::from("src/Foo/Bar.json")]
+        );
+    }
+}
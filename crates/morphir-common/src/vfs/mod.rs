@@ -1,5 +1,6 @@
 pub mod notebook;
 
+pub use morphir_core::vpath::VirtualPath;
 pub use notebook::NotebookVfs;
 
 use std::io::Result;
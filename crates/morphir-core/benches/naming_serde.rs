@@ -0,0 +1,179 @@
+//! Benchmarks for name serialization and V4 distribution round-trips.
+//!
+//! Naming serialization dominates IR I/O, so each name benchmark pairs the
+//! serde implementation with the allocating approach it replaced. To compare
+//! a change against the current tree:
+//!
+//! ```sh
+//! cargo bench -p morphir-core --bench naming_serde -- --save-baseline before
+//! # apply the change
+//! cargo bench -p morphir-core --bench naming_serde -- --baseline before
+//! ```
+//!
+//! `--features simd` adds simd-json to the `v4_read` group.
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use lasso::{Capacity, ThreadedRodeo};
+use morphir_core::converter;
+use morphir_core::ir::{classic, v4};
+use morphir_core::naming::interner::{self, DEFAULT_BYTES, DEFAULT_WORDS};
+use morphir_core::naming::{FQName, Name, Path};
+use std::hint::black_box;
+use std::num::NonZeroUsize;
+
+/// Classic fixture converted to V4 for the round-trip benchmark
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../morphir-tests/fixtures/classic/evaluator-tests.json"
+);
+
+fn names() -> Vec<Name> {
+    [
+        "value-in-usd",
+        "TestModule",
+        "aggregate-group-by-test",
+        "filterMap",
+        "x",
+    ]
+    .iter()
+    .map(|s| Name::from(s))
+    .collect()
+}
+
+fn bench_names(c: &mut Criterion) {
+    let names = names();
+    let json: Vec<String> = names
+        .iter()
+        .map(|n| serde_json::to_string(n).unwrap())
+        .collect();
+
+    let mut group = c.benchmark_group("name_serialize");
+    group.bench_function("joined_string", |b| {
+        b.iter(|| {
+            for name in &names {
+                let joined = name.iter().collect::<Vec<_>>().join("-").to_lowercase();
+                black_box(serde_json::to_string(&joined).unwrap());
+            }
+        })
+    });
+    group.bench_function("interned_words", |b| {
+        b.iter(|| {
+            for name in &names {
+                black_box(serde_json::to_string(name).unwrap());
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("name_deserialize");
+    group.bench_function("json_value", |b| {
+        b.iter(|| {
+            for text in &json {
+                let value: serde_json::Value = serde_json::from_str(text).unwrap();
+                black_box(Name::from(value.as_str().unwrap()));
+            }
+        })
+    });
+    group.bench_function("visitor", |b| {
+        b.iter(|| {
+            for text in &json {
+                black_box(serde_json::from_str::<Name>(text).unwrap());
+            }
+        })
+    });
+    group.finish();
+
+    let path = Path::new("morphir/examples/app");
+    c.bench_function("path_display", |b| b.iter(|| black_box(path.to_string())));
+
+    let fqname = serde_json::to_string("morphir/sdk:basics:value-in-usd").unwrap();
+    let mut group = c.benchmark_group("fqname_deserialize");
+    group.bench_function("owned_string", |b| {
+        b.iter(|| {
+            let s: String = serde_json::from_str(&fqname).unwrap();
+            black_box(FQName::try_from(s).unwrap())
+        })
+    });
+    group.bench_function("visitor", |b| {
+        b.iter(|| black_box(serde_json::from_str::<FQName>(&fqname).unwrap()))
+    });
+    group.finish();
+}
+
+fn bench_distribution(c: &mut Criterion) {
+    let content = std::fs::read_to_string(FIXTURE).expect("fixture is readable");
+    let dist: classic::Distribution = serde_json::from_str(&content).expect("valid Classic IR");
+    let ir = converter::classic_to_v4(&dist).ir;
+    let json = serde_json::to_string(&ir).unwrap();
+
+    let mut group = c.benchmark_group("v4_distribution");
+    group.sample_size(20);
+    group.bench_function("serialize", |b| {
+        b.iter(|| black_box(serde_json::to_string(&ir).unwrap()))
+    });
+    group.bench_function("deserialize", |b| {
+        b.iter(|| black_box(serde_json::from_str::<v4::IRFile>(&json).unwrap()))
+    });
+    group.bench_function("round_trip", |b| {
+        b.iter(|| {
+            let json = serde_json::to_string(&ir).unwrap();
+            black_box(serde_json::from_str::<v4::IRFile>(&json).unwrap())
+        })
+    });
+    group.finish();
+
+    // Reading the distribution through an intermediate `serde_json::Value`,
+    // as the V4 deserializers once did, against reading it directly
+    let mut group = c.benchmark_group("v4_read");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("via_value", |b| {
+        b.iter(|| {
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            black_box(serde_json::from_value::<v4::IRFile>(value).unwrap())
+        })
+    });
+    group.bench_function("direct", |b| {
+        b.iter_batched_ref(
+            || json.clone().into_bytes(),
+            |bytes| black_box(v4::read::from_slice(bytes).unwrap()),
+            BatchSize::LargeInput,
+        )
+    });
+    #[cfg(feature = "simd")]
+    group.bench_function("simd", |b| {
+        b.iter_batched_ref(
+            || json.clone().into_bytes(),
+            |bytes| black_box(simd_json::serde::from_slice::<v4::IRFile>(bytes).unwrap()),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+
+    // The words the distribution interned, interned again into a fresh
+    // arena that either starts minimal and grows or starts at the defaults
+    let words: Vec<&str> = interner::interner().strings().collect();
+    let mut group = c.benchmark_group("interner_capacity");
+    group.bench_function("grown", |b| {
+        b.iter(|| {
+            let rodeo: ThreadedRodeo = ThreadedRodeo::with_capacity(Capacity::minimal());
+            for word in &words {
+                black_box(rodeo.get_or_intern(word));
+            }
+        })
+    });
+    group.bench_function("presized", |b| {
+        b.iter(|| {
+            let bytes = NonZeroUsize::new(DEFAULT_BYTES).unwrap();
+            let rodeo: ThreadedRodeo =
+                ThreadedRodeo::with_capacity(Capacity::new(DEFAULT_WORDS, bytes));
+            for word in &words {
+                black_box(rodeo.get_or_intern(word));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_names, bench_distribution);
+criterion_main!(benches);
@@ -1,1834 +1,1490 @@
 //! Converter between Classic and V4 Morphir IR formats.
 //!
-//! This module provides bidirectional conversion between the Classic (V1/V2/V3)
-//! and V4 Morphir IR formats.
-
-use crate::ir::{classic, v4};
-use crate::naming::Name;
-use indexmap::IndexMap;
-use std::cell::RefCell;
-
-// Thread-local configuration for conversion options
-thread_local! {
-    static USE_COMPACT_FORMAT: RefCell<bool> = const { RefCell::new(true) };
-}
-
-/// Set whether to use compact format for V4 output.
-/// When false, uses expanded/verbose format.
-fn set_compact_format(compact: bool) {
-    USE_COMPACT_FORMAT.with(|f| *f.borrow_mut() = compact);
-}
-
-/// Check if compact format should be used.
-fn is_compact_format() -> bool {
-    USE_COMPACT_FORMAT.with(|f| *f.borrow())
+//! Converts whole distributions in both directions: types, values, patterns,
+//! access control, documentation, and dependency specifications.
+//!
+//! Names are normalized on the way: V4 keys are kebab-case (`business-terms`),
+//! Classic names are lists of lowercase words (`["business", "terms"]`), and
+//! the SDK package is `morphir/sdk` in V4 but `morphir/s-d-k` in Classic. The
+//! type annotations Classic attaches to every value node become the
+//! `inferredType` attribute in V4, and are read back from there.
+//!
+//! Some constructs exist on only one side. V4-only constructs (holes, native
+//! and external bodies, incomplete type definitions) have no Classic
+//! equivalent: the definitions using them are left out of the Classic output.
+//! Application entry points are dropped, and V4 decimal literals become Classic
+//! floats. Documentation of dependency specifications has no place in V4. Every
+//! such loss is reported as a warning on the [`Conversion`].
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let conversion = classic_to_v4(&classic_dist);
+//! for warning in &conversion.warnings {
+//!     eprintln!("warning: {}", warning);
+//! }
+//! let back = v4_to_classic(&conversion.ir)?;
+//! ```
+
+use crate::error::{Error, Result};
+use crate::ir::classic;
+use crate::ir::classic::Attrs;
+use crate::ir::v4;
+use crate::naming::{CLASSIC_SDK_PACKAGE, FQName, Name, PackageName, Path, SDK_PACKAGE, Word};
+use crate::{intern, resolve};
+
+/// Format version written for Classic output
+pub const CLASSIC_FORMAT_VERSION: u32 = 3;
+
+/// Classic value annotations: every value node carries its type
+type ClassicAnnotation = classic::Type<Attrs>;
+type ClassicType = classic::Type<Attrs>;
+type ClassicValue = classic::Value<Attrs, ClassicAnnotation>;
+type ClassicPattern = classic::Pattern<ClassicAnnotation>;
+
+/// The result of converting IR, with everything that could not be carried over.
+#[derive(Debug, Clone)]
+pub struct Conversion<T> {
+    /// The converted IR
+    pub ir: T,
+    /// Information lost in the conversion
+    pub warnings: Vec<String>,
 }
 
-// =============================================================================
-// Type Expression Conversion (Classic Array -> V4 Object Wrapper)
-// =============================================================================
+/// Convert a Classic distribution to a V4 IR file.
+pub fn classic_to_v4(dist: &classic::Distribution) -> Conversion<v4::IRFile> {
+    let mut cx = ToV4::default();
+    let classic::DistributionBody::Library(package_path, dependencies, def) = &dist.distribution;
 
-/// Convert a Classic type expression (array format) to V4 format (object wrapper).
-///
-/// Classic format: `["TypeName", {attrs}, ...args]`
-/// V4 format: `{ "TypeName": { field: value, ... } }`
-fn convert_type_expr_to_v4(json: &serde_json::Value) -> serde_json::Value {
-    let arr = match json.as_array() {
-        Some(a) if !a.is_empty() => a,
-        _ => return json.clone(), // Not an array or empty, return as-is
-    };
-
-    let tag = match arr[0].as_str() {
-        Some(t) => t,
-        None => return json.clone(), // First element not a string, return as-is
+    let dependencies = dependencies
+        .iter()
+        .map(|(path, spec)| (path_to_v4(path).to_string(), cx.package_spec(spec)))
+        .collect();
+    let def = cx.package_def(def);
+
+    let ir = v4::IRFile {
+        format_version: v4::FormatVersion::default(),
+        distribution: v4::Distribution::Library(v4::LibraryContent {
+            package_name: PackageName::new(path_to_v4(package_path)),
+            dependencies,
+            def,
+        }),
     };
-
-    match tag {
-        "Variable" => {
-            // Classic: ["Variable", {attrs}, ["name", "parts"]]
-            let name = if arr.len() > 2 {
-                extract_name_from_json(&arr[2])
-                    .map(|n| n.to_string())
-                    .unwrap_or_default()
-            } else {
-                String::new()
-            };
-            // Compact: bare name string; Expanded: {"Variable": {"name": name}}
-            if is_compact_format() {
-                serde_json::json!(name)
-            } else {
-                serde_json::json!({ "Variable": { "name": name } })
-            }
-        }
-        "Reference" => {
-            // Classic: ["Reference", {attrs}, [pkg_path, mod_path, local_name], [type_args]]
-            let fqname = if arr.len() > 2 {
-                extract_fqname_from_classic(&arr[2])
-            } else {
-                String::new()
-            };
-            let args: Vec<serde_json::Value> = if arr.len() > 3 {
-                arr[3]
-                    .as_array()
-                    .map(|a| a.iter().map(convert_type_expr_to_v4).collect())
-                    .unwrap_or_default()
-            } else {
-                vec![]
-            };
-            if is_compact_format() {
-                // Compact: bare fqname when no args, array [fqname, arg1, ...] with args
-                if args.is_empty() {
-                    serde_json::json!(fqname)
-                } else {
-                    let mut ref_arr = vec![serde_json::json!(fqname)];
-                    ref_arr.extend(args);
-                    serde_json::json!({ "Reference": ref_arr })
-                }
-            } else {
-                // Expanded: always object with fqname and args fields
-                serde_json::json!({ "Reference": { "fqname": fqname, "args": args } })
-            }
-        }
-        "Tuple" => {
-            // Classic: ["Tuple", {attrs}, [elements...]]
-            let elements: Vec<serde_json::Value> = if arr.len() > 2 {
-                arr[2]
-                    .as_array()
-                    .map(|a| a.iter().map(convert_type_expr_to_v4).collect())
-                    .unwrap_or_default()
-            } else {
-                vec![]
-            };
-            serde_json::json!({ "Tuple": { "elements": elements } })
-        }
-        "Record" => {
-            // Classic format can be either:
-            // 1. Array format: ["Record", {attrs}, [[["field", "name"], type_expr], ...]]
-            // 2. Object format: ["Record", {attrs}, [{"name": ["field"], "tpe": type_expr}, ...]]
-            let fields: IndexMap<String, serde_json::Value> = if arr.len() > 2 {
-                arr[2]
-                    .as_array()
-                    .map(|fields_arr| {
-                        fields_arr
-                            .iter()
-                            .filter_map(|field| {
-                                // Try object format first: {"name": [...], "tpe": ...}
-                                if let Some(obj) = field.as_object() {
-                                    let name = obj.get("name").and_then(extract_name_from_json)?;
-                                    let tpe = obj
-                                        .get("tpe")
-                                        .map(convert_type_expr_to_v4)
-                                        .unwrap_or(serde_json::Value::Null);
-                                    return Some((name.to_string(), tpe));
-                                }
-                                // Fall back to array format: [["name"], type_expr]
-                                let field_arr = field.as_array()?;
-                                if field_arr.len() < 2 {
-                                    return None;
-                                }
-                                let name = extract_name_from_json(&field_arr[0])?;
-                                let tpe = convert_type_expr_to_v4(&field_arr[1]);
-                                Some((name.to_string(), tpe))
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default()
-            } else {
-                IndexMap::new()
-            };
-            // Compact format: fields directly under Record
-            serde_json::json!({ "Record": fields })
-        }
-        "ExtensibleRecord" => {
-            // Classic format can be either:
-            // 1. Array format: ["ExtensibleRecord", {attrs}, ["var", "name"], [[field_name, type], ...]]
-            // 2. Object format: ["ExtensibleRecord", {attrs}, ["var"], [{"name": [...], "tpe": ...}, ...]]
-            let variable = if arr.len() > 2 {
-                extract_name_from_json(&arr[2])
-                    .map(|n| n.to_string())
-                    .unwrap_or_default()
-            } else {
-                String::new()
-            };
-            let fields: IndexMap<String, serde_json::Value> = if arr.len() > 3 {
-                arr[3]
-                    .as_array()
-                    .map(|fields_arr| {
-                        fields_arr
-                            .iter()
-                            .filter_map(|field| {
-                                // Try object format first: {"name": [...], "tpe": ...}
-                                if let Some(obj) = field.as_object() {
-                                    let name = obj.get("name").and_then(extract_name_from_json)?;
-                                    let tpe = obj
-                                        .get("tpe")
-                                        .map(convert_type_expr_to_v4)
-                                        .unwrap_or(serde_json::Value::Null);
-                                    return Some((name.to_string(), tpe));
-                                }
-                                // Fall back to array format: [["name"], type_expr]
-                                let field_arr = field.as_array()?;
-                                if field_arr.len() < 2 {
-                                    return None;
-                                }
-                                let name = extract_name_from_json(&field_arr[0])?;
-                                let tpe = convert_type_expr_to_v4(&field_arr[1]);
-                                Some((name.to_string(), tpe))
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default()
-            } else {
-                IndexMap::new()
-            };
-            serde_json::json!({ "ExtensibleRecord": { "variable": variable, "fields": fields } })
-        }
-        "Function" => {
-            // Classic: ["Function", {attrs}, arg_type, return_type]
-            let arg = if arr.len() > 2 {
-                convert_type_expr_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let result = if arr.len() > 3 {
-                convert_type_expr_to_v4(&arr[3])
-            } else {
-                serde_json::Value::Null
-            };
-            serde_json::json!({ "Function": { "arg": arg, "result": result } })
-        }
-        "Unit" => {
-            // Classic: ["Unit", {attrs}]
-            serde_json::json!({ "Unit": {} })
-        }
-        _ => {
-            // Unknown type tag, return as-is
-            json.clone()
-        }
+    Conversion {
+        ir,
+        warnings: cx.warnings,
     }
 }
 
-/// Extract FQName from Classic format and convert to canonical string.
-///
-/// Classic format: `[package_path, module_path, local_name]`
-/// where each path is `[["word1"], ["word2", "word3"]]`
-fn extract_fqname_from_classic(json: &serde_json::Value) -> String {
-    let arr = match json.as_array() {
-        Some(a) if a.len() >= 3 => a,
-        _ => return String::new(),
-    };
-
-    // Extract package path: [["word1"], ["word2"]] -> "word1/word2"
-    let package_path = extract_path_from_classic(&arr[0]);
-    // Extract module path: [["word1", "word2"]] -> "word1-word2"
-    let module_path = extract_path_from_classic(&arr[1]);
-    // Extract local name: ["word1", "word2"] -> "word1-word2"
-    let local_name = extract_name_from_json(&arr[2])
-        .map(|n| n.to_string())
-        .unwrap_or_default();
-
-    format!("{}:{}#{}", package_path, module_path, local_name)
-}
-
-/// Extract Path from Classic format and convert to canonical string.
-///
-/// Classic format: `[["word1"], ["word2", "word3"]]` -> "word1/word2-word3"
-fn extract_path_from_classic(json: &serde_json::Value) -> String {
-    json.as_array()
-        .map(|segments| {
-            segments
-                .iter()
-                .filter_map(|seg| extract_name_from_json(seg).map(|n| n.to_string()))
-                .collect::<Vec<_>>()
-                .join("/")
-        })
-        .unwrap_or_default()
-}
-
-/// Convert a Classic value expression (array format) to V4 format (object wrapper).
+/// Convert a V4 IR file to a Classic distribution.
 ///
-/// Classic format: `["ValueTag", {attrs}, ...args]`
-/// V4 format: `{ "ValueTag": { field: value, ... } }`
-fn convert_value_expr_to_v4(json: &serde_json::Value) -> serde_json::Value {
-    let arr = match json.as_array() {
-        Some(a) if !a.is_empty() => a,
-        _ => return json.clone(),
-    };
-
-    let tag = match arr[0].as_str() {
-        Some(t) => t,
-        None => return json.clone(),
-    };
-
-    match tag {
-        "Unit" => serde_json::json!({ "Unit": {} }),
-        "Literal" => {
-            // Classic: ["Literal", {attrs}, literal_value]
-            if arr.len() > 2 {
-                let literal = convert_literal_to_v4(&arr[2]);
-                serde_json::json!({ "Literal": { "value": literal } })
-            } else {
-                serde_json::json!({ "Literal": { "value": null } })
+/// Fails for Specs distributions, which Classic cannot represent.
+pub fn v4_to_classic(ir: &v4::IRFile) -> Result<Conversion<classic::Distribution>> {
+    let mut cx = ToClassic::default();
+    let (package_name, dependencies, def) = match &ir.distribution {
+        v4::Distribution::Library(lib) => (&lib.package_name, &lib.dependencies, &lib.def),
+        v4::Distribution::Application(app) => {
+            if !app.entry_points.is_empty() {
+                cx.warnings.push(format!(
+                    "Classic IR has no application distributions; {} entry point(s) dropped",
+                    app.entry_points.len()
+                ));
             }
+            (&app.package_name, &app.dependencies, &app.def)
         }
-        "Constructor" => {
-            // Classic: ["Constructor", {attrs}, fqname]
-            let fqname = if arr.len() > 2 {
-                extract_fqname_from_classic(&arr[2])
-            } else {
-                String::new()
-            };
-            serde_json::json!({ "Constructor": { "fqname": fqname } })
-        }
-        "Variable" => {
-            // Classic: ["Variable", {attrs}, ["name", "parts"]]
-            let name = if arr.len() > 2 {
-                extract_name_from_json(&arr[2])
-                    .map(|n| n.to_string())
-                    .unwrap_or_default()
-            } else {
-                String::new()
-            };
-            // Compact format: name directly under Variable
-            serde_json::json!({ "Variable": name })
-        }
-        "Reference" => {
-            // Classic: ["Reference", {attrs}, fqname]
-            // Value references use compact format: just the fqname string
-            let fqname = if arr.len() > 2 {
-                extract_fqname_from_classic(&arr[2])
-            } else {
-                String::new()
-            };
-            serde_json::json!({ "Reference": fqname })
+        v4::Distribution::Specs(_) => {
+            return Err(Error::InvalidIr(
+                "Classic IR has no specs distributions".to_string(),
+            ));
         }
-        "Tuple" => {
-            // Classic: ["Tuple", {attrs}, [elements...]]
-            let elements: Vec<serde_json::Value> = if arr.len() > 2 {
-                arr[2]
-                    .as_array()
-                    .map(|a| a.iter().map(convert_value_expr_to_v4).collect())
-                    .unwrap_or_default()
-            } else {
-                vec![]
-            };
-            serde_json::json!({ "Tuple": { "elements": elements } })
-        }
-        "List" => {
-            // Classic: ["List", {attrs}, [items...]]
-            let items: Vec<serde_json::Value> = if arr.len() > 2 {
-                arr[2]
-                    .as_array()
-                    .map(|a| a.iter().map(convert_value_expr_to_v4).collect())
-                    .unwrap_or_default()
-            } else {
-                vec![]
-            };
-            serde_json::json!({ "List": { "items": items } })
-        }
-        "Record" => {
-            // Classic format can be either:
-            // 1. Array format: ["Record", {attrs}, [[["field", "name"], value], ...]]
-            // 2. Object format: ["Record", {attrs}, [{"name": ["field"], "value": ...}, ...]]
-            let fields: IndexMap<String, serde_json::Value> = if arr.len() > 2 {
-                arr[2]
-                    .as_array()
-                    .map(|fields_arr| {
-                        fields_arr
-                            .iter()
-                            .filter_map(|field| {
-                                // Try object format first: {"name": [...], "value": ...}
-                                if let Some(obj) = field.as_object() {
-                                    let name = obj.get("name").and_then(extract_name_from_json)?;
-                                    let val = obj
-                                        .get("value")
-                                        .map(convert_value_expr_to_v4)
-                                        .unwrap_or(serde_json::Value::Null);
-                                    return Some((name.to_string(), val));
-                                }
-                                // Fall back to array format: [["name"], value_expr]
-                                let field_arr = field.as_array()?;
-                                if field_arr.len() < 2 {
-                                    return None;
-                                }
-                                let name = extract_name_from_json(&field_arr[0])?;
-                                let val = convert_value_expr_to_v4(&field_arr[1]);
-                                Some((name.to_string(), val))
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default()
-            } else {
-                IndexMap::new()
-            };
-            // Compact format: fields directly under Record
-            serde_json::json!({ "Record": fields })
-        }
-        "Field" => {
-            // Classic: ["Field", {attrs}, target_expr, ["field", "name"]]
-            let target = if arr.len() > 2 {
-                convert_value_expr_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let name = if arr.len() > 3 {
-                extract_name_from_json(&arr[3])
-                    .map(|n| n.to_string())
-                    .unwrap_or_default()
-            } else {
-                String::new()
-            };
-            serde_json::json!({ "Field": { "target": target, "name": name } })
-        }
-        "FieldFunction" => {
-            // Classic: ["FieldFunction", {attrs}, ["field", "name"]]
-            let name = if arr.len() > 2 {
-                extract_name_from_json(&arr[2])
-                    .map(|n| n.to_string())
-                    .unwrap_or_default()
-            } else {
-                String::new()
-            };
-            serde_json::json!({ "FieldFunction": { "name": name } })
-        }
-        "Apply" => {
-            // Classic: ["Apply", {attrs}, function, argument]
-            let function = if arr.len() > 2 {
-                convert_value_expr_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let argument = if arr.len() > 3 {
-                convert_value_expr_to_v4(&arr[3])
-            } else {
-                serde_json::Value::Null
-            };
-            serde_json::json!({ "Apply": { "function": function, "argument": argument } })
-        }
-        "Lambda" => {
-            // Classic: ["Lambda", {attrs}, pattern, body]
-            let pattern = if arr.len() > 2 {
-                convert_pattern_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let body = if arr.len() > 3 {
-                convert_value_expr_to_v4(&arr[3])
-            } else {
-                serde_json::Value::Null
-            };
-            serde_json::json!({ "Lambda": { "pattern": pattern, "body": body } })
-        }
-        "LetDefinition" => {
-            // Classic: ["LetDefinition", {attrs}, ["name"], def, in_expr]
-            let name = if arr.len() > 2 {
-                extract_name_from_json(&arr[2])
-                    .map(|n| n.to_string())
-                    .unwrap_or_default()
-            } else {
-                String::new()
-            };
-            let definition = if arr.len() > 3 {
-                convert_value_def_inline_to_v4(&arr[3])
-            } else {
-                serde_json::Value::Null
-            };
-            let in_expr = if arr.len() > 4 {
-                convert_value_expr_to_v4(&arr[4])
-            } else {
-                serde_json::Value::Null
-            };
-            serde_json::json!({ "LetDefinition": { "name": name, "definition": definition, "in": in_expr } })
-        }
-        "LetRecursion" => {
-            // Classic format can be either:
-            // 1. Array format: ["LetRecursion", {attrs}, [[name, def], ...], in_expr]
-            // 2. Object format: ["LetRecursion", {attrs}, [{"name": [...], "def": ...}, ...], in_expr]
-            let definitions: IndexMap<String, serde_json::Value> = if arr.len() > 2 {
-                arr[2]
-                    .as_array()
-                    .map(|defs| {
-                        defs.iter()
-                            .filter_map(|def_entry| {
-                                // Try object format first: {"name": [...], "def": ...}
-                                if let Some(obj) = def_entry.as_object() {
-                                    let name = obj.get("name").and_then(extract_name_from_json)?;
-                                    let def = obj
-                                        .get("def")
-                                        .map(convert_value_def_inline_to_v4)
-                                        .unwrap_or(serde_json::Value::Null);
-                                    return Some((name.to_string(), def));
-                                }
-                                // Fall back to array format: [["name"], def]
-                                let entry_arr = def_entry.as_array()?;
-                                if entry_arr.len() < 2 {
-                                    return None;
-                                }
-                                let name = extract_name_from_json(&entry_arr[0])?;
-                                let def = convert_value_def_inline_to_v4(&entry_arr[1]);
-                                Some((name.to_string(), def))
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default()
-            } else {
-                IndexMap::new()
-            };
-            let in_expr = if arr.len() > 3 {
-                convert_value_expr_to_v4(&arr[3])
-            } else {
-                serde_json::Value::Null
-            };
-            serde_json::json!({ "LetRecursion": { "definitions": definitions, "in": in_expr } })
-        }
-        "Destructure" => {
-            // Classic: ["Destructure", {attrs}, pattern, value_expr, in_expr]
-            let pattern = if arr.len() > 2 {
-                convert_pattern_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let value = if arr.len() > 3 {
-                convert_value_expr_to_v4(&arr[3])
-            } else {
-                serde_json::Value::Null
-            };
-            let in_expr = if arr.len() > 4 {
-                convert_value_expr_to_v4(&arr[4])
-            } else {
-                serde_json::Value::Null
-            };
-            serde_json::json!({ "Destructure": { "pattern": pattern, "value": value, "in": in_expr } })
-        }
-        "IfThenElse" => {
-            // Classic: ["IfThenElse", {attrs}, condition, then_branch, else_branch]
-            let condition = if arr.len() > 2 {
-                convert_value_expr_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let then_branch = if arr.len() > 3 {
-                convert_value_expr_to_v4(&arr[3])
-            } else {
-                serde_json::Value::Null
-            };
-            let else_branch = if arr.len() > 4 {
-                convert_value_expr_to_v4(&arr[4])
-            } else {
-                serde_json::Value::Null
-            };
-            serde_json::json!({ "IfThenElse": { "condition": condition, "then": then_branch, "else": else_branch } })
-        }
-        "PatternMatch" => {
-            // Classic: ["PatternMatch", {attrs}, match_expr, [[pattern, body], ...]]
-            let value = if arr.len() > 2 {
-                convert_value_expr_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let cases: Vec<serde_json::Value> = if arr.len() > 3 {
-                arr[3]
-                    .as_array()
-                    .map(|cases_arr| {
-                        cases_arr
-                            .iter()
-                            .filter_map(|case| {
-                                let case_arr = case.as_array()?;
-                                if case_arr.len() < 2 {
-                                    return None;
-                                }
-                                let pattern = convert_pattern_to_v4(&case_arr[0]);
-                                let body = convert_value_expr_to_v4(&case_arr[1]);
-                                Some(serde_json::json!({ "pattern": pattern, "body": body }))
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default()
-            } else {
-                vec![]
-            };
-            serde_json::json!({ "PatternMatch": { "value": value, "cases": cases } })
-        }
-        "UpdateRecord" => {
-            // Classic format can be either:
-            // 1. Array format: ["UpdateRecord", {attrs}, target, [[field_name, value], ...]]
-            // 2. Object format: ["UpdateRecord", {attrs}, target, [{"name": [...], "value": ...}, ...]]
-            let target = if arr.len() > 2 {
-                convert_value_expr_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let fields: IndexMap<String, serde_json::Value> = if arr.len() > 3 {
-                arr[3]
-                    .as_array()
-                    .map(|fields_arr| {
-                        fields_arr
-                            .iter()
-                            .filter_map(|field| {
-                                // Try object format first: {"name": [...], "value": ...}
-                                if let Some(obj) = field.as_object() {
-                                    let name = obj.get("name").and_then(extract_name_from_json)?;
-                                    let val = obj
-                                        .get("value")
-                                        .map(convert_value_expr_to_v4)
-                                        .unwrap_or(serde_json::Value::Null);
-                                    return Some((name.to_string(), val));
-                                }
-                                // Fall back to array format: [["name"], value_expr]
-                                let field_arr = field.as_array()?;
-                                if field_arr.len() < 2 {
-                                    return None;
-                                }
-                                let name = extract_name_from_json(&field_arr[0])?;
-                                let val = convert_value_expr_to_v4(&field_arr[1]);
-                                Some((name.to_string(), val))
-                            })
-                            .collect()
-                    })
-                    .unwrap_or_default()
-            } else {
-                IndexMap::new()
-            };
-            serde_json::json!({ "UpdateRecord": { "target": target, "fields": fields } })
-        }
-        _ => {
-            // Unknown value tag, return as-is
-            json.clone()
-        }
-    }
-}
-
-/// Convert a Classic pattern to V4 format.
-fn convert_pattern_to_v4(json: &serde_json::Value) -> serde_json::Value {
-    let arr = match json.as_array() {
-        Some(a) if !a.is_empty() => a,
-        _ => return json.clone(),
     };
 
-    let tag = match arr[0].as_str() {
-        Some(t) => t,
-        None => return json.clone(),
+    let dependencies = dependencies
+        .iter()
+        .map(|(key, spec)| (path_to_classic(&Path::new(key)), cx.package_spec(spec)))
+        .collect();
+    let def = cx.package_def(def);
+    cx.summarize();
+
+    let dist = classic::Distribution {
+        format_version: CLASSIC_FORMAT_VERSION,
+        distribution: classic::DistributionBody::Library(
+            path_to_classic(package_name.as_path()),
+            dependencies,
+            def,
+        ),
     };
-
-    match tag {
-        "WildcardPattern" => serde_json::json!({ "WildcardPattern": {} }),
-        "AsPattern" => {
-            // Classic: ["AsPattern", {attrs}, pattern, ["name"]]
-            let pattern = if arr.len() > 2 {
-                convert_pattern_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let name = if arr.len() > 3 {
-                extract_name_from_json(&arr[3])
-                    .map(|n| n.to_string())
-                    .unwrap_or_default()
-            } else {
-                String::new()
-            };
-            serde_json::json!({ "AsPattern": { "pattern": pattern, "name": name } })
-        }
-        "TuplePattern" => {
-            // Classic: ["TuplePattern", {attrs}, [patterns...]]
-            let patterns: Vec<serde_json::Value> = if arr.len() > 2 {
-                arr[2]
-                    .as_array()
-                    .map(|a| a.iter().map(convert_pattern_to_v4).collect())
-                    .unwrap_or_default()
-            } else {
-                vec![]
-            };
-            serde_json::json!({ "TuplePattern": { "patterns": patterns } })
-        }
-        "ConstructorPattern" => {
-            // Classic: ["ConstructorPattern", {attrs}, fqname, [arg_patterns...]]
-            let fqname = if arr.len() > 2 {
-                extract_fqname_from_classic(&arr[2])
-            } else {
-                String::new()
-            };
-            let args: Vec<serde_json::Value> = if arr.len() > 3 {
-                arr[3]
-                    .as_array()
-                    .map(|a| a.iter().map(convert_pattern_to_v4).collect())
-                    .unwrap_or_default()
-            } else {
-                vec![]
-            };
-            serde_json::json!({ "ConstructorPattern": { "fqname": fqname, "args": args } })
-        }
-        "EmptyListPattern" => serde_json::json!({ "EmptyListPattern": {} }),
-        "HeadTailPattern" => {
-            // Classic: ["HeadTailPattern", {attrs}, head_pattern, tail_pattern]
-            let head = if arr.len() > 2 {
-                convert_pattern_to_v4(&arr[2])
-            } else {
-                serde_json::Value::Null
-            };
-            let tail = if arr.len() > 3 {
-                convert_pattern_to_v4(&arr[3])
-            } else {
-                serde_json::Value::Null
-            };
-            serde_json::json!({ "HeadTailPattern": { "head": head, "tail": tail } })
-        }
-        "LiteralPattern" => {
-            // Classic: ["LiteralPattern", {attrs}, literal_value]
-            if arr.len() > 2 {
-                let literal = convert_literal_to_v4(&arr[2]);
-                serde_json::json!({ "LiteralPattern": { "value": literal } })
-            } else {
-                serde_json::json!({ "LiteralPattern": { "value": null } })
-            }
-        }
-        "UnitPattern" => serde_json::json!({ "UnitPattern": {} }),
-        _ => json.clone(),
-    }
+    Ok(Conversion {
+        ir: dist,
+        warnings: cx.warnings,
+    })
 }
 
-/// Convert a Classic literal to V4 format.
-fn convert_literal_to_v4(json: &serde_json::Value) -> serde_json::Value {
-    let arr = match json.as_array() {
-        Some(a) if !a.is_empty() => a,
-        _ => return json.clone(),
-    };
+// =============================================================================
+// Naming
+// =============================================================================
 
-    let tag = match arr[0].as_str() {
-        Some(t) => t,
-        None => return json.clone(),
-    };
+/// Lowercase form of an interned word, reusing the handle when the word is
+/// already lowercase
+fn lowercase(word: Word) -> Word {
+    let text = resolve(word);
+    if text.chars().any(char::is_uppercase) {
+        intern(&text.to_lowercase())
+    } else {
+        word
+    }
+}
 
-    match tag {
-        "BoolLiteral" => {
-            if arr.len() > 1 {
-                serde_json::json!({ "BoolLiteral": arr[1] })
-            } else {
-                serde_json::json!({ "BoolLiteral": false })
-            }
-        }
-        "CharLiteral" => {
-            if arr.len() > 1 {
-                serde_json::json!({ "CharLiteral": arr[1] })
-            } else {
-                serde_json::json!({ "CharLiteral": "" })
-            }
-        }
-        "StringLiteral" => {
-            if arr.len() > 1 {
-                serde_json::json!({ "StringLiteral": arr[1] })
-            } else {
-                serde_json::json!({ "StringLiteral": "" })
-            }
-        }
-        "WholeNumberLiteral" => {
-            if arr.len() > 1 {
-                serde_json::json!({ "WholeNumberLiteral": arr[1] })
-            } else {
-                serde_json::json!({ "WholeNumberLiteral": 0 })
-            }
-        }
-        "FloatLiteral" => {
-            if arr.len() > 1 {
-                serde_json::json!({ "FloatLiteral": arr[1] })
-            } else {
-                serde_json::json!({ "FloatLiteral": 0.0 })
-            }
-        }
-        "DecimalLiteral" => {
-            if arr.len() > 1 {
-                serde_json::json!({ "DecimalLiteral": arr[1] })
-            } else {
-                serde_json::json!({ "DecimalLiteral": "0" })
-            }
-        }
-        _ => json.clone(),
+fn name_to_v4(name: &classic::Name) -> Name {
+    // Classic words occasionally carry source casing (`TestModule`); split
+    // them the same way V4 names are split
+    Name {
+        words: name
+            .words
+            .iter()
+            .flat_map(|w| Name::from(resolve(*w)).words)
+            .map(lowercase)
+            .collect(),
     }
 }
 
-/// Convert a Classic inline value definition to V4 format.
-fn convert_value_def_inline_to_v4(json: &serde_json::Value) -> serde_json::Value {
-    // Classic format: {inputTypes: [...], outputType: ..., body: ...}
-    if let Some(obj) = json.as_object() {
-        let input_types: IndexMap<String, serde_json::Value> = obj
-            .get("inputTypes")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|input| {
-                        let input_arr = input.as_array()?;
-                        if input_arr.len() < 3 {
-                            return None;
-                        }
-                        let name = extract_name_from_json(&input_arr[0])?;
-                        let typ = convert_type_expr_to_v4(&input_arr[2]);
-                        Some((name.to_string(), typ))
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let output_type = obj
-            .get("outputType")
-            .map(convert_type_expr_to_v4)
-            .unwrap_or(serde_json::Value::Null);
-
-        let body = obj
-            .get("body")
-            .map(convert_value_expr_to_v4)
-            .unwrap_or(serde_json::Value::Null);
-
-        serde_json::json!({
-            "inputTypes": input_types,
-            "outputType": output_type,
-            "body": body
-        })
+fn path_to_v4(path: &classic::Path) -> Path {
+    let path = Path {
+        segments: path.segments.iter().map(name_to_v4).collect(),
+    };
+    // morphir-elm splits the SDK acronym into words (`morphir/s-d-k`)
+    if path.is_sdk() {
+        Path::new(SDK_PACKAGE)
     } else {
-        json.clone()
+        path
     }
 }
 
-/// Convert a Classic package to V4 format using compact (default) format.
-///
-/// # Arguments
-/// * `pkg` - The Classic package to convert
-///
-/// # Returns
-/// A V4 PackageDefinition with converted modules
-pub fn classic_to_v4(pkg: classic::Package) -> v4::PackageDefinition {
-    classic_to_v4_with_options(pkg, true)
+fn fqname_to_v4(fqname: &classic::FQName) -> FQName {
+    FQName::new(
+        path_to_v4(&fqname.package_path),
+        path_to_v4(&fqname.module_path),
+        name_to_v4(&fqname.local_name),
+    )
 }
 
-/// Convert a Classic package to V4 format with configurable output format.
-///
-/// # Arguments
-/// * `pkg` - The Classic package to convert
-/// * `compact` - If true, use compact format; if false, use expanded format
-///
-/// # Returns
-/// A V4 PackageDefinition with converted modules
-pub fn classic_to_v4_with_options(pkg: classic::Package, compact: bool) -> v4::PackageDefinition {
-    set_compact_format(compact);
-    let modules: IndexMap<String, v4::AccessControlledModuleDefinition> =
-        pkg.modules.into_iter().map(convert_module_to_v4).collect();
-    // Reset to default (compact) after conversion
-    set_compact_format(true);
-
-    v4::PackageDefinition { modules }
+/// Canonical V4 key for a Classic name
+fn key(name: &classic::Name) -> String {
+    name_to_v4(name).to_string()
 }
 
-/// Convert a V4 package to Classic format.
-///
-/// # Arguments
-/// * `pkg` - The V4 package to convert
-///
-/// # Returns
-/// A Classic Package with converted modules
-pub fn v4_to_classic(pkg: v4::PackageDefinition) -> classic::Package {
-    let modules = pkg
-        .modules
-        .into_iter()
-        .map(|(name, module)| convert_module_to_classic(name, module))
-        .collect();
-
-    classic::Package {
-        name: String::new(), // Name is stored at distribution level
-        modules,
+fn name_to_classic(name: &Name) -> classic::Name {
+    // V4 names may keep the case of their source (`TestModule`); Classic words
+    // are always lowercase
+    classic::Name {
+        words: name.words.iter().copied().map(lowercase).collect(),
     }
 }
 
-/// Convert a Classic module to V4 format (returns tuple for IndexMap collection)
-fn convert_module_to_v4(module: classic::Module) -> (String, v4::AccessControlledModuleDefinition) {
-    let access = convert_access_to_v4(&module.detail.access);
-    let types = convert_types_to_v4(&module.detail.value.types);
-    let values = convert_values_to_v4(&module.detail.value.values);
-
-    (
-        module.name.to_string(),
-        v4::AccessControlledModuleDefinition {
-            access,
-            value: v4::ModuleDefinition {
-                types,
-                values,
-                doc: module.detail.value.doc,
-            },
-        },
-    )
+fn path_to_classic(path: &Path) -> classic::Path {
+    let path = if path.is_sdk() {
+        &Path::new(CLASSIC_SDK_PACKAGE)
+    } else {
+        path
+    };
+    classic::Path::new(path.segments.iter().map(name_to_classic).collect())
 }
 
-/// Convert a V4 module to Classic Module
-fn convert_module_to_classic(
-    name: String,
-    access_controlled: v4::AccessControlledModuleDefinition,
-) -> classic::Module {
-    classic::Module {
-        name: crate::naming::Path::new(&name),
-        detail: classic::ModuleDetail {
-            access: convert_access_to_classic(&access_controlled.access),
-            value: classic::ModuleValue {
-                types: convert_types_to_classic(&access_controlled.value.types),
-                values: convert_values_to_classic(&access_controlled.value.values),
-                doc: access_controlled.value.doc,
-            },
-        },
-    }
+fn fqname_to_classic(fqname: &FQName) -> classic::FQName {
+    classic::FQName::new(
+        path_to_classic(&fqname.package_path),
+        path_to_classic(&fqname.module_path),
+        name_to_classic(&fqname.local_name),
+    )
 }
 
-/// Convert access string to V4 Access enum
-fn convert_access_to_v4(access: &str) -> v4::Access {
-    match access.to_lowercase().as_str() {
-        "private" => v4::Access::Private,
-        _ => v4::Access::Public,
-    }
+/// Classic name for a V4 key
+fn key_to_classic(key: &str) -> classic::Name {
+    name_to_classic(&Name::from(key))
 }
 
-/// Convert V4 Access enum to Classic access string
-fn convert_access_to_classic(access: &v4::Access) -> String {
+fn access_to_v4(access: &classic::Access) -> v4::Access {
     match access {
-        v4::Access::Public => "Public".to_string(),
-        v4::Access::Private => "Private".to_string(),
+        classic::Access::Public => v4::Access::Public,
+        classic::Access::Private => v4::Access::Private,
     }
 }
 
-/// Convert Classic types array to V4 type definitions.
-///
-/// Classic format: `[[[name_parts], {access, value}], ...]`
-/// V4 format: `IndexMap<String, AccessControlledTypeDefinition>`
-fn convert_types_to_v4(
-    types: &[serde_json::Value],
-) -> IndexMap<String, v4::AccessControlledTypeDefinition> {
-    types
-        .iter()
-        .filter_map(|type_val| {
-            // Classic format: [[name_parts], {access, value}]
-            let arr = type_val.as_array()?;
-            if arr.len() < 2 {
-                return None;
-            }
-
-            // Extract name from first element
-            let name = extract_name_from_json(&arr[0])?;
-
-            // Extract access-controlled definition from second element
-            let def_obj = arr[1].as_object()?;
-            let access_str = def_obj.get("access")?.as_str()?;
-            let value = def_obj.get("value")?.clone();
-
-            Some((
-                name.to_string(),
-                v4::AccessControlledTypeDefinition {
-                    access: convert_access_to_v4(access_str),
-                    value: convert_type_definition_to_v4(&value),
-                },
-            ))
-        })
-        .collect()
+fn access_to_classic(access: &v4::Access) -> classic::Access {
+    match access {
+        v4::Access::Public => classic::Access::Public,
+        v4::Access::Private => classic::Access::Private,
+    }
 }
 
-/// Convert V4 type definitions to Classic types array
-fn convert_types_to_classic(
-    types: &IndexMap<String, v4::AccessControlledTypeDefinition>,
-) -> Vec<serde_json::Value> {
-    types
-        .iter()
-        .map(|(name, access_controlled)| {
-            let name_obj = Name::from(name.as_str());
-            let name_json = serde_json::to_value(&name_obj).unwrap_or(serde_json::Value::Null);
-            let def_json = serde_json::json!({
-                "access": convert_access_to_classic(&access_controlled.access),
-                "value": convert_type_definition_to_classic(&access_controlled.value)
-            });
-
-            serde_json::json!([name_json, def_json])
-        })
-        .collect()
+fn doc_to_v4(doc: &str) -> Option<String> {
+    (!doc.is_empty()).then(|| doc.to_string())
 }
 
-/// Convert Classic values array to V4 value definitions.
-///
-/// Classic format: `[[[name_parts], {access, value: {doc, value: {inputTypes, outputType, body}}}], ...]`
-/// V4 format: `IndexMap<String, AccessControlledValueDefinition>`
-fn convert_values_to_v4(
-    values: &[serde_json::Value],
-) -> IndexMap<String, v4::AccessControlledValueDefinition> {
-    values
-        .iter()
-        .filter_map(|value_val| {
-            // Classic format: [[name_parts], {access, value: {doc, value: {...}}}]
-            let arr = value_val.as_array()?;
-            if arr.len() < 2 {
-                return None;
-            }
-
-            // Extract name from first element
-            let name = extract_name_from_json(&arr[0])?;
-
-            // Extract access-controlled definition from second element
-            let def_obj = arr[1].as_object()?;
-            let access_str = def_obj.get("access")?.as_str()?;
-
-            // The value contains {doc, value: {inputTypes, outputType, body}}
-            // We need to extract the inner 'value' field
-            let outer_value = def_obj.get("value")?.as_object()?;
-            let inner_value = outer_value.get("value")?.clone();
+// =============================================================================
+// Classic -> V4
+// =============================================================================
 
-            Some((
-                name.to_string(),
-                v4::AccessControlledValueDefinition {
-                    access: convert_access_to_v4(access_str),
-                    value: convert_value_definition_to_v4(&inner_value),
-                },
-            ))
-        })
-        .collect()
+#[derive(Default)]
+struct ToV4 {
+    warnings: Vec<String>,
 }
 
-/// Convert V4 value definitions to Classic values array
-fn convert_values_to_classic(
-    values: &IndexMap<String, v4::AccessControlledValueDefinition>,
-) -> Vec<serde_json::Value> {
-    values
-        .iter()
-        .map(|(name, access_controlled)| {
-            let name_obj = Name::from(name.as_str());
-            let name_json = serde_json::to_value(&name_obj).unwrap_or(serde_json::Value::Null);
-            let def_json = serde_json::json!({
-                "access": convert_access_to_classic(&access_controlled.access),
-                "value": convert_value_definition_to_classic(&access_controlled.value)
-            });
-
-            serde_json::json!([name_json, def_json])
-        })
-        .collect()
-}
-
-/// Extract a Name from JSON value (array of strings)
-fn extract_name_from_json(json: &serde_json::Value) -> Option<Name> {
-    let arr = json.as_array()?;
-    let words: Vec<String> = arr
-        .iter()
-        .filter_map(|v| v.as_str().map(String::from))
-        .collect();
+impl ToV4 {
+    fn package_def(
+        &mut self,
+        def: &classic::PackageDefinition<Attrs, ClassicAnnotation>,
+    ) -> v4::PackageDefinition {
+        let modules = def
+            .modules
+            .iter()
+            .map(|entry| {
+                let module = &entry.definition;
+                let value = v4::ModuleDefinition {
+                    types: module
+                        .value
+                        .types
+                        .iter()
+                        .map(|(name, def)| (key(name), self.access_controlled_type_def(def)))
+                        .collect(),
+                    values: module
+                        .value
+                        .values
+                        .iter()
+                        .map(|(name, def)| (key(name), self.access_controlled_value_def(def)))
+                        .collect(),
+                    doc: module.value.doc.clone(),
+                    ids: Default::default(),
+                };
+                (
+                    path_to_v4(&entry.path).to_string(),
+                    v4::AccessControlled {
+                        access: access_to_v4(&module.access),
+                        doc: None,
+                        value,
+                    },
+                )
+            })
+            .collect();
+        v4::PackageDefinition { modules }
+    }
 
-    if words.is_empty() {
-        None
-    } else {
-        Some(Name { words })
+    fn package_spec(
+        &mut self,
+        spec: &classic::PackageSpecification<Attrs>,
+    ) -> v4::PackageSpecification {
+        let mut dropped_docs = 0;
+        let modules = spec
+            .modules
+            .iter()
+            .map(|entry| {
+                let module = &entry.specification;
+                let types = module
+                    .types
+                    .iter()
+                    .map(|(name, documented)| {
+                        dropped_docs += usize::from(!documented.doc.is_empty());
+                        (key(name), type_spec_to_v4(&documented.value))
+                    })
+                    .collect();
+                let values = module
+                    .values
+                    .iter()
+                    .map(|(name, documented)| {
+                        dropped_docs += usize::from(!documented.doc.is_empty());
+                        (key(name), value_spec_to_v4(&documented.value))
+                    })
+                    .collect();
+                (
+                    path_to_v4(&entry.path).to_string(),
+                    v4::ModuleSpecification {
+                        types,
+                        values,
+                        doc: module.doc.clone(),
+                    },
+                )
+            })
+            .collect();
+        if dropped_docs > 0 {
+            self.warnings.push(format!(
+                "V4 specifications carry no per-definition documentation; {} doc comment(s) in dependencies dropped",
+                dropped_docs
+            ));
+        }
+        v4::PackageSpecification { modules }
     }
-}
 
-/// Convert a Classic type definition value to V4 TypeDefinition.
-///
-/// The type definition structure varies based on the kind (TypeAlias, CustomType, etc.)
-/// For now, we preserve the JSON structure as-is since both formats use compatible representations.
-fn convert_type_definition_to_v4(value: &serde_json::Value) -> v4::TypeDefinition {
-    // The value contains doc and a nested value structure
-    // Try to extract the actual type definition
-    if let Some(obj) = value.as_object()
-        && let Some(inner_value) = obj.get("value")
-        && let Some(arr) = inner_value.as_array()
-        && !arr.is_empty()
-    {
-        let tag = arr[0].as_str().unwrap_or("");
-        match tag {
-            "CustomTypeDefinition" => {
-                return convert_custom_type_to_v4(arr);
+    fn access_controlled_type_def(
+        &mut self,
+        def: &classic::AccessControlled<classic::Documented<classic::TypeDefinition<Attrs>>>,
+    ) -> v4::AccessControlled<v4::TypeDefinition> {
+        let value = match &def.value.value {
+            classic::TypeDefinition::Alias(params, tpe) => {
+                v4::TypeDefinition::TypeAliasDefinition {
+                    type_params: params.iter().map(name_to_v4).collect(),
+                    type_expr: type_to_v4(tpe),
+                }
             }
-            "TypeAliasDefinition" => {
-                return convert_type_alias_to_v4(arr);
+            classic::TypeDefinition::Custom(params, constructors) => {
+                v4::TypeDefinition::CustomTypeDefinition {
+                    type_params: params.iter().map(name_to_v4).collect(),
+                    constructors: v4::AccessControlled {
+                        access: access_to_v4(&constructors.access),
+                        doc: None,
+                        value: constructors
+                            .value
+                            .iter()
+                            .map(|ctor| v4::ConstructorDefinition {
+                                name: name_to_v4(&ctor.name),
+                                args: ctor
+                                    .args
+                                    .iter()
+                                    .map(|(name, tpe)| v4::ConstructorArg {
+                                        name: name_to_v4(name),
+                                        arg_type: type_to_v4(tpe),
+                                    })
+                                    .collect(),
+                            })
+                            .collect(),
+                    },
+                }
             }
-            _ => {}
+        };
+        v4::AccessControlled {
+            access: access_to_v4(&def.access),
+            doc: doc_to_v4(&def.value.doc),
+            value,
         }
     }
 
-    // Fallback: create empty type alias
-    v4::TypeDefinition::TypeAliasDefinition {
-        type_params: vec![],
-        type_expr: serde_json::Value::Null,
+    fn access_controlled_value_def(
+        &mut self,
+        def: &classic::AccessControlled<
+            classic::Documented<classic::ValueDefinition<Attrs, ClassicAnnotation>>,
+        >,
+    ) -> v4::AccessControlled<v4::ValueDefinition> {
+        let value_def = &def.value.value;
+        v4::AccessControlled {
+            access: access_to_v4(&def.access),
+            doc: doc_to_v4(&def.value.doc),
+            value: value_def_to_v4(
+                &value_def.input_types,
+                &value_def.output_type,
+                &value_def.body,
+            ),
+        }
     }
 }
 
-/// Convert V4 TypeDefinition to Classic JSON value
-fn convert_type_definition_to_classic(type_def: &v4::TypeDefinition) -> serde_json::Value {
-    match type_def {
-        v4::TypeDefinition::TypeAliasDefinition {
-            type_params,
-            type_expr,
-        } => {
-            let type_params_json: Vec<serde_json::Value> = type_params
-                .iter()
-                .map(|n| serde_json::to_value(n).unwrap_or(serde_json::Value::Null))
-                .collect();
-
-            serde_json::json!({
-                "value": ["TypeAliasDefinition", type_params_json, type_expr]
-            })
+fn type_to_v4(tpe: &ClassicType) -> v4::Type {
+    let attrs = v4::TypeAttributes::default;
+    match tpe {
+        classic::Type::Variable(_, name) => v4::Type::Variable(attrs(), name_to_v4(name)),
+        classic::Type::Reference(_, fqname, args) => v4::Type::Reference(
+            attrs(),
+            fqname_to_v4(fqname),
+            args.iter().map(type_to_v4).collect(),
+        ),
+        classic::Type::Tuple(_, elements) => {
+            v4::Type::Tuple(attrs(), elements.iter().map(type_to_v4).collect())
         }
-        v4::TypeDefinition::CustomTypeDefinition {
-            type_params,
-            constructors,
-        } => {
-            let type_params_json: Vec<serde_json::Value> = type_params
-                .iter()
-                .map(|n| serde_json::to_value(n).unwrap_or(serde_json::Value::Null))
-                .collect();
-
-            let constructors_json = convert_constructors_to_classic(constructors);
-
-            serde_json::json!({
-                "value": ["CustomTypeDefinition", type_params_json, constructors_json]
-            })
+        classic::Type::Record(_, fields) => v4::Type::Record(attrs(), fields_to_v4(fields)),
+        classic::Type::ExtensibleRecord(_, name, fields) => {
+            v4::Type::ExtensibleRecord(attrs(), name_to_v4(name), fields_to_v4(fields))
         }
+        classic::Type::Function(_, argument, result) => v4::Type::Function(
+            attrs(),
+            Box::new(type_to_v4(argument)),
+            Box::new(type_to_v4(result)),
+        ),
+        classic::Type::Unit(_) => v4::Type::Unit(attrs()),
     }
 }
 
-/// Convert Classic CustomTypeDefinition array to V4
-fn convert_custom_type_to_v4(arr: &[serde_json::Value]) -> v4::TypeDefinition {
-    // Format: ["CustomTypeDefinition", [type_params], {access, value: constructors}]
-    let type_params = if arr.len() > 1 {
-        extract_type_params(&arr[1])
-    } else {
-        vec![]
-    };
+fn fields_to_v4(fields: &[classic::Field<Attrs>]) -> Vec<v4::Field> {
+    fields
+        .iter()
+        .map(|field| v4::Field {
+            name: name_to_v4(&field.name),
+            tpe: type_to_v4(&field.ty),
+        })
+        .collect()
+}
 
-    let constructors = if arr.len() > 2 {
-        convert_constructors_to_v4(&arr[2])
-    } else {
-        v4::AccessControlledConstructors {
-            access: v4::Access::Public,
-            value: vec![],
+fn type_spec_to_v4(spec: &classic::TypeSpecification<Attrs>) -> v4::TypeSpecification {
+    match spec {
+        classic::TypeSpecification::Alias(params, tpe) => {
+            v4::TypeSpecification::TypeAliasSpecification {
+                type_params: params.iter().map(name_to_v4).collect(),
+                type_expr: type_to_v4(tpe),
+            }
+        }
+        classic::TypeSpecification::Opaque(params) => {
+            v4::TypeSpecification::OpaqueTypeSpecification {
+                type_params: params.iter().map(name_to_v4).collect(),
+            }
+        }
+        classic::TypeSpecification::Custom(params, constructors) => {
+            v4::TypeSpecification::CustomTypeSpecification {
+                type_params: params.iter().map(name_to_v4).collect(),
+                constructors: constructors
+                    .iter()
+                    .map(|ctor| v4::ConstructorSpecification {
+                        name: name_to_v4(&ctor.name),
+                        args: ctor
+                            .args
+                            .iter()
+                            .map(|(name, tpe)| v4::ConstructorArgSpec {
+                                name: name_to_v4(name),
+                                arg_type: type_to_v4(tpe),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            }
         }
-    };
-
-    v4::TypeDefinition::CustomTypeDefinition {
-        type_params,
-        constructors,
     }
 }
 
-/// Convert Classic TypeAliasDefinition array to V4
-fn convert_type_alias_to_v4(arr: &[serde_json::Value]) -> v4::TypeDefinition {
-    // Format: ["TypeAliasDefinition", [type_params], type_exp]
-    let type_params = if arr.len() > 1 {
-        extract_type_params(&arr[1])
-    } else {
-        vec![]
-    };
+fn value_spec_to_v4(spec: &classic::ValueSpecification<Attrs>) -> v4::ValueSpecification {
+    v4::ValueSpecification {
+        inputs: spec
+            .inputs
+            .iter()
+            .map(|param| (key(&param.name), type_to_v4(&param.ty)))
+            .collect(),
+        output: type_to_v4(&spec.output),
+    }
+}
 
-    let type_expr = if arr.len() > 2 {
-        convert_type_expr_to_v4(&arr[2])
-    } else {
-        serde_json::Value::Null
-    };
+/// Classic value annotations become the V4 `inferredType` attribute.
+fn value_attrs_to_v4(annotation: &ClassicAnnotation) -> v4::ValueAttributes {
+    v4::ValueAttributes {
+        inferred_type: serde_json::to_value(type_to_v4(annotation))
+            .unwrap_or(serde_json::Value::Null),
+        ..Default::default()
+    }
+}
 
-    v4::TypeDefinition::TypeAliasDefinition {
-        type_params,
-        type_expr,
+fn value_def_to_v4(
+    input_types: &[classic::value::ValueArgument<Attrs, ClassicAnnotation>],
+    output_type: &ClassicType,
+    body: &ClassicValue,
+) -> v4::ValueDefinition {
+    v4::ValueDefinition {
+        input_types: input_types
+            .iter()
+            .map(|arg| {
+                (
+                    key(&arg.name),
+                    v4::InputTypeEntry {
+                        type_attributes: Some(value_attrs_to_v4(&arg.annotation)),
+                        input_type: type_to_v4(&arg.ty),
+                    },
+                )
+            })
+            .collect(),
+        output_type: type_to_v4(output_type),
+        body: v4::ValueBody::Expression(value_to_v4(body)),
     }
 }
 
-/// Extract type parameters from JSON array as Name (kebab-case string serialization)
-fn extract_type_params(json: &serde_json::Value) -> Vec<Name> {
-    json.as_array()
-        .map(|arr| arr.iter().filter_map(extract_name_from_json).collect())
-        .unwrap_or_default()
+fn literal_to_v4(literal: &classic::Literal) -> v4::Literal {
+    match literal {
+        classic::Literal::Bool(b) => v4::Literal::Bool(*b),
+        classic::Literal::Char(c) => v4::Literal::Char(*c),
+        classic::Literal::String(s) => v4::Literal::String(s.clone()),
+        classic::Literal::WholeNumber(n) => v4::Literal::Integer(*n),
+        classic::Literal::Float(f) => v4::Literal::Float(*f),
+    }
 }
 
-/// Convert Classic constructors to V4 AccessControlledConstructors
-fn convert_constructors_to_v4(json: &serde_json::Value) -> v4::AccessControlledConstructors {
-    if let Some(obj) = json.as_object() {
-        let access = obj
-            .get("access")
-            .and_then(|a| a.as_str())
-            .map(convert_access_to_v4)
-            .unwrap_or(v4::Access::Public);
-
-        let constructors = obj
-            .get("value")
-            .and_then(|v| v.as_array())
-            .map(|arr| arr.iter().filter_map(convert_constructor_to_v4).collect())
-            .unwrap_or_default();
-
-        v4::AccessControlledConstructors {
-            access,
-            value: constructors,
-        }
-    } else {
-        v4::AccessControlledConstructors {
-            access: v4::Access::Public,
-            value: vec![],
+fn pattern_to_v4(pattern: &ClassicPattern) -> v4::Pattern {
+    match pattern {
+        classic::Pattern::Wildcard(a) => v4::Pattern::WildcardPattern(value_attrs_to_v4(a)),
+        classic::Pattern::As(a, inner, name) => v4::Pattern::AsPattern(
+            value_attrs_to_v4(a),
+            Box::new(pattern_to_v4(inner)),
+            name_to_v4(name),
+        ),
+        classic::Pattern::Tuple(a, elements) => v4::Pattern::TuplePattern(
+            value_attrs_to_v4(a),
+            elements.iter().map(pattern_to_v4).collect(),
+        ),
+        classic::Pattern::Constructor(a, fqname, args) => v4::Pattern::ConstructorPattern(
+            value_attrs_to_v4(a),
+            fqname_to_v4(fqname),
+            args.iter().map(pattern_to_v4).collect(),
+        ),
+        classic::Pattern::EmptyList(a) => v4::Pattern::EmptyListPattern(value_attrs_to_v4(a)),
+        classic::Pattern::HeadTail(a, head, tail) => v4::Pattern::HeadTailPattern(
+            value_attrs_to_v4(a),
+            Box::new(pattern_to_v4(head)),
+            Box::new(pattern_to_v4(tail)),
+        ),
+        classic::Pattern::Literal(a, literal) => {
+            v4::Pattern::LiteralPattern(value_attrs_to_v4(a), literal_to_v4(literal))
         }
+        classic::Pattern::Unit(a) => v4::Pattern::UnitPattern(value_attrs_to_v4(a)),
+        // V4 has no variable pattern: a variable is a wildcard bound to a name
+        classic::Pattern::Variable(a, name) => v4::Pattern::AsPattern(
+            value_attrs_to_v4(a),
+            Box::new(v4::Pattern::WildcardPattern(value_attrs_to_v4(a))),
+            name_to_v4(name),
+        ),
     }
 }
 
-/// Convert V4 AccessControlledConstructors to Classic JSON
-fn convert_constructors_to_classic(
-    constructors: &v4::AccessControlledConstructors,
-) -> serde_json::Value {
-    let ctors: Vec<serde_json::Value> = constructors
-        .value
-        .iter()
-        .map(|ctor| {
-            let name_json = serde_json::to_value(&ctor.name).unwrap_or(serde_json::Value::Null);
-            let args: Vec<serde_json::Value> = ctor
-                .args
-                .iter()
-                .map(|arg| {
-                    serde_json::json!([
-                        serde_json::to_value(&arg.name).unwrap_or(serde_json::Value::Null),
-                        arg.arg_type
-                    ])
+fn value_to_v4(value: &ClassicValue) -> v4::Value {
+    let boxed = |v: &ClassicValue| Box::new(value_to_v4(v));
+    let fields = |fields: &[(classic::Name, ClassicValue)]| {
+        fields
+            .iter()
+            .map(|(name, v)| v4::RecordFieldEntry(name_to_v4(name), value_to_v4(v)))
+            .collect()
+    };
+    match value {
+        classic::Value::Literal(a, literal) => {
+            v4::Value::Literal(value_attrs_to_v4(a), literal_to_v4(literal))
+        }
+        classic::Value::Constructor(a, fqname) => {
+            v4::Value::Constructor(value_attrs_to_v4(a), fqname_to_v4(fqname))
+        }
+        classic::Value::Tuple(a, elements) => v4::Value::Tuple(
+            value_attrs_to_v4(a),
+            elements.iter().map(value_to_v4).collect(),
+        ),
+        classic::Value::List(a, items) => v4::Value::List(
+            value_attrs_to_v4(a),
+            items.iter().map(value_to_v4).collect(),
+        ),
+        classic::Value::Record(a, entries) => {
+            v4::Value::Record(value_attrs_to_v4(a), fields(entries))
+        }
+        classic::Value::Variable(a, name) => {
+            v4::Value::Variable(value_attrs_to_v4(a), name_to_v4(name))
+        }
+        classic::Value::Reference(a, fqname) => {
+            v4::Value::Reference(value_attrs_to_v4(a), fqname_to_v4(fqname))
+        }
+        classic::Value::Field(a, subject, name) => {
+            v4::Value::Field(value_attrs_to_v4(a), boxed(subject), name_to_v4(name))
+        }
+        classic::Value::FieldFunction(a, name) => {
+            v4::Value::FieldFunction(value_attrs_to_v4(a), name_to_v4(name))
+        }
+        classic::Value::Apply(a, function, argument) => {
+            v4::Value::Apply(value_attrs_to_v4(a), boxed(function), boxed(argument))
+        }
+        classic::Value::Lambda(a, pattern, body) => {
+            v4::Value::Lambda(value_attrs_to_v4(a), pattern_to_v4(pattern), boxed(body))
+        }
+        classic::Value::LetDefinition(a, name, def, in_value) => v4::Value::LetDefinition(
+            value_attrs_to_v4(a),
+            name_to_v4(name),
+            Box::new(value_def_to_v4(
+                &def.input_types,
+                &def.output_type,
+                &def.body,
+            )),
+            boxed(in_value),
+        ),
+        classic::Value::LetRecursion(a, defs, in_value) => v4::Value::LetRecursion(
+            value_attrs_to_v4(a),
+            defs.iter()
+                .map(|(name, def)| {
+                    v4::LetBinding(
+                        name_to_v4(name),
+                        value_def_to_v4(&def.input_types, &def.output_type, &def.body),
+                    )
                 })
-                .collect();
+                .collect(),
+            boxed(in_value),
+        ),
+        classic::Value::Destructure(a, pattern, value, in_value) => v4::Value::Destructure(
+            value_attrs_to_v4(a),
+            pattern_to_v4(pattern),
+            boxed(value),
+            boxed(in_value),
+        ),
+        classic::Value::IfThenElse(a, condition, then_branch, else_branch) => {
+            v4::Value::IfThenElse(
+                value_attrs_to_v4(a),
+                boxed(condition),
+                boxed(then_branch),
+                boxed(else_branch),
+            )
+        }
+        classic::Value::PatternMatch(a, subject, cases) => v4::Value::PatternMatch(
+            value_attrs_to_v4(a),
+            boxed(subject),
+            cases
+                .iter()
+                .map(|(pattern, body)| v4::PatternCase(pattern_to_v4(pattern), value_to_v4(body)))
+                .collect(),
+        ),
+        classic::Value::Update(a, subject, entries) => {
+            v4::Value::UpdateRecord(value_attrs_to_v4(a), boxed(subject), fields(entries))
+        }
+        classic::Value::Unit(a) => v4::Value::Unit(value_attrs_to_v4(a)),
+    }
+}
 
-            serde_json::json!([name_json, args])
-        })
-        .collect();
+// =============================================================================
+// V4 -> Classic
+// =============================================================================
 
-    serde_json::json!({
-        "access": convert_access_to_classic(&constructors.access),
-        "value": ctors
-    })
+#[derive(Default)]
+struct ToClassic {
+    warnings: Vec<String>,
+    /// Value nodes without an inferred type, annotated with unit instead
+    untyped: usize,
+    /// Decimal literals written as floats
+    decimals: usize,
 }
 
-/// Convert a single constructor from Classic to V4
-fn convert_constructor_to_v4(json: &serde_json::Value) -> Option<v4::ConstructorDefinition> {
-    // Format: [[name_parts], [[arg_name, arg_type], ...]]
-    let arr = json.as_array()?;
-    if arr.len() < 2 {
-        return None;
+impl ToClassic {
+    fn summarize(&mut self) {
+        if self.untyped > 0 {
+            self.warnings.push(format!(
+                "{} value node(s) had no inferred type and were annotated with unit",
+                self.untyped
+            ));
+        }
+        if self.decimals > 0 {
+            self.warnings.push(format!(
+                "{} decimal literal(s) written as float literals",
+                self.decimals
+            ));
+        }
     }
 
-    let name = extract_name_from_json(&arr[0])?;
-
-    let args: Vec<v4::ConstructorArg> = arr[1]
-        .as_array()
-        .map(|args_arr| {
-            args_arr
-                .iter()
-                .filter_map(|arg| {
-                    let arg_arr = arg.as_array()?;
-                    if arg_arr.len() < 2 {
-                        return None;
-                    }
-                    let arg_name = extract_name_from_json(&arg_arr[0])?;
-                    let arg_type = convert_type_expr_to_v4(&arg_arr[1]);
-                    Some(v4::ConstructorArg {
-                        name: arg_name,
-                        arg_type,
+    fn package_def(
+        &mut self,
+        def: &v4::PackageDefinition,
+    ) -> classic::PackageDefinition<Attrs, ClassicAnnotation> {
+        let modules = def
+            .modules
+            .iter()
+            .map(|(module_key, module)| {
+                let types = module
+                    .value
+                    .types
+                    .iter()
+                    .filter_map(|(name, def)| {
+                        let converted = self.access_controlled_type_def(def);
+                        if converted.is_none() {
+                            self.warnings.push(format!(
+                                "type {}.{} is incomplete and was left out",
+                                module_key, name
+                            ));
+                        }
+                        Some((key_to_classic(name), converted?))
                     })
-                })
-                .collect()
-        })
-        .unwrap_or_default();
-
-    Some(v4::ConstructorDefinition { name, args })
-}
-
-/// Convert a Classic value definition to V4 ValueDefinition.
-fn convert_value_definition_to_v4(value: &serde_json::Value) -> v4::ValueDefinition {
-    // Classic format: {inputTypes: [[name, annotation_type, actual_type], ...], outputType: ..., body: ...}
-    // The annotation_type is the type annotation from source, actual_type is the inferred type
-    if let Some(obj) = value.as_object() {
-        let input_types: IndexMap<String, v4::InputTypeEntry> = obj
-            .get("inputTypes")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|input| {
-                        let input_arr = input.as_array()?;
-                        if input_arr.len() < 3 {
-                            return None;
+                    .collect();
+                let values = module
+                    .value
+                    .values
+                    .iter()
+                    .filter_map(|(name, def)| match self.access_controlled_value_def(def) {
+                        Ok(converted) => Some((key_to_classic(name), converted)),
+                        Err(construct) => {
+                            self.warnings.push(format!(
+                                "value {}.{} uses {}, which Classic IR cannot represent, and was left out",
+                                module_key, name, construct
+                            ));
+                            None
                         }
-                        let name = extract_name_from_json(&input_arr[0])?;
-                        // Element 1 is the type annotation - convert it to V4 format
-                        let annotation = convert_type_expr_to_v4(&input_arr[1]);
-                        // Element 2 is the actual/inferred input type
-                        let typ = convert_type_expr_to_v4(&input_arr[2]);
-                        Some((
-                            name.to_string(),
-                            v4::InputTypeEntry {
-                                // Store the converted type annotation if it's not empty
-                                type_attributes: if annotation.is_null()
-                                    || (annotation.is_object()
-                                        && annotation.as_object().is_none_or(|o| o.is_empty()))
-                                {
-                                    None
-                                } else {
-                                    Some(annotation)
-                                },
-                                input_type: typ,
-                            },
-                        ))
                     })
-                    .collect()
+                    .collect();
+                classic::ModuleEntry {
+                    path: path_to_classic(&Path::new(module_key)),
+                    definition: classic::AccessControlled {
+                        access: access_to_classic(&module.access),
+                        value: classic::ModuleDefinition {
+                            types,
+                            values,
+                            doc: module.value.doc.clone(),
+                        },
+                    },
+                }
             })
-            .unwrap_or_default();
-
-        let output_type = obj
-            .get("outputType")
-            .map(convert_type_expr_to_v4)
-            .unwrap_or(serde_json::Value::Null);
-
-        let body_json = obj
-            .get("body")
-            .map(convert_value_expr_to_v4)
-            .unwrap_or(serde_json::Value::Null);
-        let body = v4::ValueBody::ExpressionBody { body: body_json };
+            .collect();
+        classic::PackageDefinition { modules }
+    }
 
-        v4::ValueDefinition {
-            input_types,
-            output_type,
-            body,
-        }
-    } else {
-        v4::ValueDefinition {
-            input_types: IndexMap::new(),
-            output_type: serde_json::Value::Null,
-            body: v4::ValueBody::ExpressionBody {
-                body: serde_json::Value::Null,
-            },
-        }
+    fn package_spec(
+        &mut self,
+        spec: &v4::PackageSpecification,
+    ) -> classic::PackageSpecification<Attrs> {
+        let modules = spec
+            .modules
+            .iter()
+            .map(|(module_key, module)| classic::package::ModuleSpecEntry {
+                path: path_to_classic(&Path::new(module_key)),
+                specification: classic::ModuleSpecification {
+                    types: module
+                        .types
+                        .iter()
+                        .map(|(name, spec)| {
+                            (
+                                key_to_classic(name),
+                                classic::Documented::new("", type_spec_to_classic(spec)),
+                            )
+                        })
+                        .collect(),
+                    values: module
+                        .values
+                        .iter()
+                        .map(|(name, spec)| {
+                            (
+                                key_to_classic(name),
+                                classic::Documented::new("", value_spec_to_classic(spec)),
+                            )
+                        })
+                        .collect(),
+                    doc: module.doc.clone(),
+                },
+            })
+            .collect();
+        classic::PackageSpecification { modules }
     }
-}
 
-/// Convert V4 ValueDefinition to Classic JSON value
-fn convert_value_definition_to_classic(value_def: &v4::ValueDefinition) -> serde_json::Value {
-    let input_types: Vec<serde_json::Value> = value_def
-        .input_types
-        .iter()
-        .map(|(name, entry)| {
-            let name_obj = Name::from(name.as_str());
-            let attrs = entry
-                .type_attributes
-                .clone()
-                .unwrap_or(serde_json::json!({}));
-            serde_json::json!([
-                serde_json::to_value(&name_obj).unwrap_or(serde_json::Value::Null),
-                attrs,
-                entry.input_type
-            ])
+    /// Convert a type definition; `None` for incomplete definitions.
+    fn access_controlled_type_def(
+        &mut self,
+        def: &v4::AccessControlled<v4::TypeDefinition>,
+    ) -> Option<classic::AccessControlled<classic::Documented<classic::TypeDefinition<Attrs>>>>
+    {
+        let value = match &def.value {
+            v4::TypeDefinition::TypeAliasDefinition {
+                type_params,
+                type_expr,
+            } => classic::TypeDefinition::Alias(
+                type_params.iter().map(name_to_classic).collect(),
+                type_to_classic(type_expr),
+            ),
+            v4::TypeDefinition::CustomTypeDefinition {
+                type_params,
+                constructors,
+            } => classic::TypeDefinition::Custom(
+                type_params.iter().map(name_to_classic).collect(),
+                classic::AccessControlled {
+                    access: access_to_classic(&constructors.access),
+                    value: constructors
+                        .value
+                        .iter()
+                        .map(|ctor| classic::Constructor {
+                            name: name_to_classic(&ctor.name),
+                            args: ctor
+                                .args
+                                .iter()
+                                .map(|arg| {
+                                    (name_to_classic(&arg.name), type_to_classic(&arg.arg_type))
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                },
+            ),
+            v4::TypeDefinition::IncompleteTypeDefinition { .. } => return None,
+        };
+        Some(classic::AccessControlled {
+            access: access_to_classic(&def.access),
+            value: classic::Documented::new(def.doc.clone().unwrap_or_default(), value),
         })
-        .collect();
-
-    // Extract body from ValueBody wrapper
-    let body = match &value_def.body {
-        v4::ValueBody::ExpressionBody { body } => body.clone(),
-        v4::ValueBody::NativeBody { .. } => serde_json::Value::Null,
-        v4::ValueBody::ExternalBody { .. } => serde_json::Value::Null,
-        v4::ValueBody::IncompleteBody { .. } => serde_json::Value::Null,
-    };
+    }
 
-    serde_json::json!({
-        "inputTypes": input_types,
-        "outputType": value_def.output_type,
-        "body": body
-    })
-}
+    /// Convert a value definition, or name the V4-only construct preventing it.
+    fn access_controlled_value_def(
+        &mut self,
+        def: &v4::AccessControlled<v4::ValueDefinition>,
+    ) -> std::result::Result<
+        classic::AccessControlled<
+            classic::Documented<classic::ValueDefinition<Attrs, ClassicAnnotation>>,
+        >,
+        &'static str,
+    > {
+        let (input_types, output_type, body) = self.value_def(&def.value)?;
+        Ok(classic::AccessControlled {
+            access: access_to_classic(&def.access),
+            value: classic::Documented::new(
+                def.doc.clone().unwrap_or_default(),
+                classic::ValueDefinition {
+                    input_types,
+                    output_type,
+                    body,
+                },
+            ),
+        })
+    }
 
-// =============================================================================
-// Visitor-Based Converters (Phase 5)
-// =============================================================================
-//
-// The converters above work at the JSON level for backward compatibility.
-// These visitor-based converters work with strongly-typed IR structures.
-
-use crate::ir::attributes::{ClassicAttrs, TypeAttributes, ValueAttributes};
-use crate::ir::type_expr::Type;
-use crate::ir::value_expr::{HoleReason, NativeInfo, Value};
-use crate::naming::FQName;
-use crate::traversal::transform::{TypeTransformVisitor, ValueTransformVisitor};
-use std::fmt;
-
-/// Error type for visitor-based conversion failures.
-#[derive(Debug, Clone)]
-pub enum ConversionError {
-    /// V4-only construct cannot be downgraded to Classic format
-    CannotDowngrade(&'static str),
-    /// Generic conversion error with message
-    Message(String),
-}
+    #[allow(clippy::type_complexity)]
+    fn value_def(
+        &mut self,
+        def: &v4::ValueDefinition,
+    ) -> std::result::Result<
+        (
+            Vec<classic::value::ValueArgument<Attrs, ClassicAnnotation>>,
+            ClassicType,
+            ClassicValue,
+        ),
+        &'static str,
+    > {
+        let body = match &def.body {
+            v4::ValueBody::Expression(body) => self.value(body)?,
+            v4::ValueBody::Native(_) => return Err("a native body"),
+            v4::ValueBody::External { .. } => return Err("an external body"),
+            v4::ValueBody::Incomplete(_) => return Err("an incomplete body"),
+        };
+        let input_types = def
+            .input_types
+            .iter()
+            .map(|(name, entry)| classic::value::ValueArgument {
+                name: key_to_classic(name),
+                annotation: match &entry.type_attributes {
+                    Some(attrs) if !attrs.inferred_type.is_null() => self.annotation(attrs),
+                    // The declared type is the best annotation for an argument
+                    _ => type_to_classic(&entry.input_type),
+                },
+                ty: type_to_classic(&entry.input_type),
+            })
+            .collect();
+        Ok((input_types, type_to_classic(&def.output_type), body))
+    }
 
-impl fmt::Display for ConversionError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ConversionError::CannotDowngrade(name) => {
-                write!(
-                    f,
-                    "Cannot downgrade V4-only construct '{}' to Classic format",
-                    name
-                )
+    /// Classic annotation for a V4 node, read from its `inferredType`.
+    fn annotation(&mut self, attrs: &v4::ValueAttributes) -> ClassicAnnotation {
+        match serde_json::from_value::<v4::Type>(attrs.inferred_type.clone()) {
+            Ok(tpe) if !attrs.inferred_type.is_null() => type_to_classic(&tpe),
+            _ => {
+                self.untyped += 1;
+                classic::Type::Unit(Attrs::None)
             }
-            ConversionError::Message(msg) => write!(f, "{}", msg),
         }
     }
-}
-
-impl std::error::Error for ConversionError {}
 
-/// Visitor-based converter from Classic (serde_json::Value attrs) to V4 (TypeAttributes).
-///
-/// This converter transforms IR structures from Classic format (V1-V3) to V4 format.
-/// Classic uses empty `{}` for attributes, while V4 uses explicit TypeAttributes/ValueAttributes.
-///
-/// # Example
-/// ```ignore
-/// use morphir_ir::converter::ClassicToV4Converter;
-/// use morphir_ir::traversal::transform::TypeTransformVisitor;
-///
-/// let converter = ClassicToV4Converter;
-/// let v4_type = converter.transform_type(&classic_type)?;
-/// ```
-pub struct ClassicToV4Converter;
-
-impl TypeTransformVisitor<ClassicAttrs, TypeAttributes> for ClassicToV4Converter {
-    type Error = ConversionError;
-
-    fn transform_type_attrs(&self, attrs: &ClassicAttrs) -> Result<TypeAttributes, Self::Error> {
-        // Classic uses {} - convert to V4 TypeAttributes with empty fields
-        // Preserve any existing data in extensions
-        Ok(TypeAttributes {
-            source: None,
-            constraints: serde_json::Value::Null,
-            extensions: attrs.clone(),
-        })
+    fn literal(&mut self, literal: &v4::Literal) -> classic::Literal {
+        match literal {
+            v4::Literal::Bool(b) => classic::Literal::Bool(*b),
+            v4::Literal::Char(c) => classic::Literal::Char(*c),
+            v4::Literal::String(s) => classic::Literal::String(s.clone()),
+            v4::Literal::Integer(n) => classic::Literal::WholeNumber(*n),
+            v4::Literal::Float(f) => classic::Literal::Float(*f),
+            v4::Literal::Decimal(d) => {
+                self.decimals += 1;
+                classic::Literal::Float(d.parse().unwrap_or(f64::NAN))
+            }
+        }
     }
-}
-
-impl ValueTransformVisitor<ClassicAttrs, TypeAttributes, ClassicAttrs, ValueAttributes>
-    for ClassicToV4Converter
-{
-    type Error = ConversionError;
 
-    fn transform_type_attrs(&self, attrs: &ClassicAttrs) -> Result<TypeAttributes, Self::Error> {
-        <Self as TypeTransformVisitor<ClassicAttrs, TypeAttributes>>::transform_type_attrs(
-            self, attrs,
-        )
+    fn pattern(&mut self, pattern: &v4::Pattern) -> ClassicPattern {
+        match pattern {
+            v4::Pattern::WildcardPattern(a) => classic::Pattern::Wildcard(self.annotation(a)),
+            v4::Pattern::AsPattern(a, inner, name) => classic::Pattern::As(
+                self.annotation(a),
+                Box::new(self.pattern(inner)),
+                name_to_classic(name),
+            ),
+            v4::Pattern::TuplePattern(a, elements) => classic::Pattern::Tuple(
+                self.annotation(a),
+                elements.iter().map(|p| self.pattern(p)).collect(),
+            ),
+            v4::Pattern::ConstructorPattern(a, fqname, args) => classic::Pattern::Constructor(
+                self.annotation(a),
+                fqname_to_classic(fqname),
+                args.iter().map(|p| self.pattern(p)).collect(),
+            ),
+            v4::Pattern::EmptyListPattern(a) => classic::Pattern::EmptyList(self.annotation(a)),
+            v4::Pattern::HeadTailPattern(a, head, tail) => classic::Pattern::HeadTail(
+                self.annotation(a),
+                Box::new(self.pattern(head)),
+                Box::new(self.pattern(tail)),
+            ),
+            v4::Pattern::LiteralPattern(a, literal) => {
+                classic::Pattern::Literal(self.annotation(a), self.literal(literal))
+            }
+            v4::Pattern::UnitPattern(a) => classic::Pattern::Unit(self.annotation(a)),
+        }
     }
 
-    fn transform_value_attrs(&self, attrs: &ClassicAttrs) -> Result<ValueAttributes, Self::Error> {
-        // Classic uses {} - convert to V4 ValueAttributes with empty fields
-        Ok(ValueAttributes {
-            source: None,
-            inferred_type: serde_json::Value::Null,
-            extensions: attrs.clone(),
+    fn definition(
+        &mut self,
+        def: &v4::ValueDefinition,
+    ) -> std::result::Result<classic::Definition<Attrs, ClassicAnnotation>, &'static str> {
+        let (input_types, output_type, body) = self.value_def(def)?;
+        Ok(classic::Definition {
+            input_types,
+            output_type,
+            body: Box::new(body),
         })
     }
 
-    // Classic never has Hole/Native/External, so default implementations work.
-    // If we encounter them, it means the input is already V4 (error case).
-    fn transform_hole(
-        &self,
-        _attrs: &ClassicAttrs,
-        _reason: &HoleReason,
-        _expected_type: &Option<Box<Type<ClassicAttrs>>>,
-    ) -> Result<Value<TypeAttributes, ValueAttributes>, Self::Error> {
-        Err(ConversionError::Message(
-            "Unexpected Hole in Classic IR".to_string(),
-        ))
+    fn fields(
+        &mut self,
+        entries: &[v4::RecordFieldEntry],
+    ) -> std::result::Result<Vec<(classic::Name, ClassicValue)>, &'static str> {
+        entries
+            .iter()
+            .map(|v4::RecordFieldEntry(name, v)| Ok((name_to_classic(name), self.value(v)?)))
+            .collect()
     }
 
-    fn transform_native(
-        &self,
-        _attrs: &ClassicAttrs,
-        _fqname: &FQName,
-        _info: &NativeInfo,
-    ) -> Result<Value<TypeAttributes, ValueAttributes>, Self::Error> {
-        Err(ConversionError::Message(
-            "Unexpected Native in Classic IR".to_string(),
-        ))
+    fn boxed(&mut self, value: &v4::Value) -> std::result::Result<Box<ClassicValue>, &'static str> {
+        Ok(Box::new(self.value(value)?))
     }
 
-    fn transform_external(
-        &self,
-        _attrs: &ClassicAttrs,
-        _external_name: &str,
-        _target_platform: &str,
-    ) -> Result<Value<TypeAttributes, ValueAttributes>, Self::Error> {
-        Err(ConversionError::Message(
-            "Unexpected External in Classic IR".to_string(),
-        ))
+    fn value(&mut self, value: &v4::Value) -> std::result::Result<ClassicValue, &'static str> {
+        Ok(match value {
+            v4::Value::Literal(a, literal) => {
+                classic::Value::Literal(self.annotation(a), self.literal(literal))
+            }
+            v4::Value::Constructor(a, fqname) => {
+                classic::Value::Constructor(self.annotation(a), fqname_to_classic(fqname))
+            }
+            v4::Value::Tuple(a, elements) => classic::Value::Tuple(
+                self.annotation(a),
+                elements
+                    .iter()
+                    .map(|v| self.value(v))
+                    .collect::<std::result::Result<_, _>>()?,
+            ),
+            v4::Value::List(a, items) => classic::Value::List(
+                self.annotation(a),
+                items
+                    .iter()
+                    .map(|v| self.value(v))
+                    .collect::<std::result::Result<_, _>>()?,
+            ),
+            v4::Value::Record(a, entries) => {
+                classic::Value::Record(self.annotation(a), self.fields(entries)?)
+            }
+            v4::Value::Variable(a, name) => {
+                classic::Value::Variable(self.annotation(a), name_to_classic(name))
+            }
+            v4::Value::Reference(a, fqname) => {
+                classic::Value::Reference(self.annotation(a), fqname_to_classic(fqname))
+            }
+            v4::Value::Field(a, subject, name) => classic::Value::Field(
+                self.annotation(a),
+                self.boxed(subject)?,
+                name_to_classic(name),
+            ),
+            v4::Value::FieldFunction(a, name) => {
+                classic::Value::FieldFunction(self.annotation(a), name_to_classic(name))
+            }
+            v4::Value::Apply(a, function, argument) => classic::Value::Apply(
+                self.annotation(a),
+                self.boxed(function)?,
+                self.boxed(argument)?,
+            ),
+            v4::Value::Lambda(a, pattern, body) => {
+                classic::Value::Lambda(self.annotation(a), self.pattern(pattern), self.boxed(body)?)
+            }
+            v4::Value::LetDefinition(a, name, def, in_value) => classic::Value::LetDefinition(
+                self.annotation(a),
+                name_to_classic(name),
+                Box::new(self.definition(def)?),
+                self.boxed(in_value)?,
+            ),
+            v4::Value::LetRecursion(a, bindings, in_value) => classic::Value::LetRecursion(
+                self.annotation(a),
+                bindings
+                    .iter()
+                    .map(|v4::LetBinding(name, def)| {
+                        Ok((name_to_classic(name), Box::new(self.definition(def)?)))
+                    })
+                    .collect::<std::result::Result<_, _>>()?,
+                self.boxed(in_value)?,
+            ),
+            v4::Value::Destructure(a, pattern, value, in_value) => classic::Value::Destructure(
+                self.annotation(a),
+                self.pattern(pattern),
+                self.boxed(value)?,
+                self.boxed(in_value)?,
+            ),
+            v4::Value::IfThenElse(a, condition, then_branch, else_branch) => {
+                classic::Value::IfThenElse(
+                    self.annotation(a),
+                    self.boxed(condition)?,
+                    self.boxed(then_branch)?,
+                    self.boxed(else_branch)?,
+                )
+            }
+            v4::Value::PatternMatch(a, subject, cases) => classic::Value::PatternMatch(
+                self.annotation(a),
+                self.boxed(subject)?,
+                cases
+                    .iter()
+                    .map(|v4::PatternCase(pattern, body)| {
+                        Ok((self.pattern(pattern), self.value(body)?))
+                    })
+                    .collect::<std::result::Result<_, _>>()?,
+            ),
+            v4::Value::UpdateRecord(a, subject, entries) => classic::Value::Update(
+                self.annotation(a),
+                self.boxed(subject)?,
+                self.fields(entries)?,
+            ),
+            v4::Value::Unit(a) => classic::Value::Unit(self.annotation(a)),
+            v4::Value::Hole(..) => return Err("a hole"),
+            v4::Value::Native(..) => return Err("a native value"),
+            v4::Value::External(..) => return Err("an external value"),
+        })
     }
 }
 
-/// Visitor-based converter from V4 (TypeAttributes) to Classic (serde_json::Value).
-///
-/// This converter transforms IR structures from V4 format to Classic format (V1-V3).
-/// V4 structures are richer, so some information is lost (source locations, constraints).
-///
-/// # Note
-/// V4-only constructs (Hole, Native, External) cannot be downgraded and will return errors.
-///
-/// # Example
-/// ```ignore
-/// use morphir_ir::converter::V4ToClassicConverter;
-/// use morphir_ir::traversal::transform::TypeTransformVisitor;
-///
-/// let converter = V4ToClassicConverter;
-/// let classic_type = converter.transform_type(&v4_type)?;
-/// ```
-pub struct V4ToClassicConverter;
-
-impl TypeTransformVisitor<TypeAttributes, ClassicAttrs> for V4ToClassicConverter {
-    type Error = ConversionError;
-
-    fn transform_type_attrs(&self, attrs: &TypeAttributes) -> Result<ClassicAttrs, Self::Error> {
-        // V4 -> Classic loses source, constraints, inferred types
-        // Preserve extensions if any (otherwise empty object)
-        if attrs.extensions.is_null() {
-            Ok(serde_json::json!({}))
-        } else {
-            Ok(attrs.extensions.clone())
+fn type_to_classic(tpe: &v4::Type) -> ClassicType {
+    match tpe {
+        v4::Type::Variable(_, name) => classic::Type::Variable(Attrs::None, name_to_classic(name)),
+        v4::Type::Reference(_, fqname, args) => classic::Type::Reference(
+            Attrs::None,
+            fqname_to_classic(fqname),
+            args.iter().map(type_to_classic).collect(),
+        ),
+        v4::Type::Tuple(_, elements) => {
+            classic::Type::Tuple(Attrs::None, elements.iter().map(type_to_classic).collect())
+        }
+        v4::Type::Record(_, fields) => {
+            classic::Type::Record(Attrs::None, fields_to_classic(fields))
         }
+        v4::Type::ExtensibleRecord(_, name, fields) => classic::Type::ExtensibleRecord(
+            Attrs::None,
+            name_to_classic(name),
+            fields_to_classic(fields),
+        ),
+        v4::Type::Function(_, argument, result) => classic::Type::Function(
+            Attrs::None,
+            Box::new(type_to_classic(argument)),
+            Box::new(type_to_classic(result)),
+        ),
+        v4::Type::Unit(_) => classic::Type::Unit(Attrs::None),
     }
 }
 
-impl ValueTransformVisitor<TypeAttributes, ClassicAttrs, ValueAttributes, ClassicAttrs>
-    for V4ToClassicConverter
-{
-    type Error = ConversionError;
-
-    fn transform_type_attrs(&self, attrs: &TypeAttributes) -> Result<ClassicAttrs, Self::Error> {
-        <Self as TypeTransformVisitor<TypeAttributes, ClassicAttrs>>::transform_type_attrs(
-            self, attrs,
-        )
-    }
+fn fields_to_classic(fields: &[v4::Field]) -> Vec<classic::Field<Attrs>> {
+    fields
+        .iter()
+        .map(|field| classic::Field {
+            name: name_to_classic(&field.name),
+            ty: type_to_classic(&field.tpe),
+        })
+        .collect()
+}
 
-    fn transform_value_attrs(&self, attrs: &ValueAttributes) -> Result<ClassicAttrs, Self::Error> {
-        // V4 -> Classic loses source, inferred_type
-        // Preserve extensions if any (otherwise empty object)
-        if attrs.extensions.is_null() {
-            Ok(serde_json::json!({}))
-        } else {
-            Ok(attrs.extensions.clone())
+fn type_spec_to_classic(spec: &v4::TypeSpecification) -> classic::TypeSpecification<Attrs> {
+    match spec {
+        v4::TypeSpecification::TypeAliasSpecification {
+            type_params,
+            type_expr,
+        } => classic::TypeSpecification::Alias(
+            type_params.iter().map(name_to_classic).collect(),
+            type_to_classic(type_expr),
+        ),
+        v4::TypeSpecification::OpaqueTypeSpecification { type_params } => {
+            classic::TypeSpecification::Opaque(type_params.iter().map(name_to_classic).collect())
         }
+        v4::TypeSpecification::CustomTypeSpecification {
+            type_params,
+            constructors,
+        } => classic::TypeSpecification::Custom(
+            type_params.iter().map(name_to_classic).collect(),
+            constructors
+                .iter()
+                .map(|ctor| classic::Constructor {
+                    name: name_to_classic(&ctor.name),
+                    args: ctor
+                        .args
+                        .iter()
+                        .map(|arg| (name_to_classic(&arg.name), type_to_classic(&arg.arg_type)))
+                        .collect(),
+                })
+                .collect(),
+        ),
     }
+}
 
-    // V4-only variants cannot be downgraded
-    fn transform_hole(
-        &self,
-        _attrs: &ValueAttributes,
-        _reason: &HoleReason,
-        _expected_type: &Option<Box<Type<TypeAttributes>>>,
-    ) -> Result<Value<ClassicAttrs, ClassicAttrs>, Self::Error> {
-        Err(ConversionError::CannotDowngrade("Hole"))
-    }
-
-    fn transform_native(
-        &self,
-        _attrs: &ValueAttributes,
-        _fqname: &FQName,
-        _info: &NativeInfo,
-    ) -> Result<Value<ClassicAttrs, ClassicAttrs>, Self::Error> {
-        Err(ConversionError::CannotDowngrade("Native"))
-    }
-
-    fn transform_external(
-        &self,
-        _attrs: &ValueAttributes,
-        _external_name: &str,
-        _target_platform: &str,
-    ) -> Result<Value<ClassicAttrs, ClassicAttrs>, Self::Error> {
-        Err(ConversionError::CannotDowngrade("External"))
+fn value_spec_to_classic(spec: &v4::ValueSpecification) -> classic::ValueSpecification<Attrs> {
+    classic::ValueSpecification {
+        inputs: spec
+            .inputs
+            .iter()
+            .map(|(name, tpe)| classic::value::ValueParameter {
+                name: key_to_classic(name),
+                ty: type_to_classic(tpe),
+            })
+            .collect(),
+        output: type_to_classic(&spec.output),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::naming::Path;
+    use serde_json::json;
 
-    #[test]
-    fn test_access_conversion() {
-        assert_eq!(convert_access_to_v4("Public"), v4::Access::Public);
-        assert_eq!(convert_access_to_v4("public"), v4::Access::Public);
-        assert_eq!(convert_access_to_v4("Private"), v4::Access::Private);
-        assert_eq!(convert_access_to_v4("private"), v4::Access::Private);
-        assert_eq!(convert_access_to_v4("unknown"), v4::Access::Public);
-
-        assert_eq!(convert_access_to_classic(&v4::Access::Public), "Public");
-        assert_eq!(convert_access_to_classic(&v4::Access::Private), "Private");
+    fn cname(s: &str) -> classic::Name {
+        classic::Name::new(s.split('-'))
     }
 
-    #[test]
-    fn test_name_extraction() {
-        let json = serde_json::json!(["test", "name"]);
-        let name = extract_name_from_json(&json).unwrap();
-        assert_eq!(name.words, vec!["test", "name"]);
-
-        let empty = serde_json::json!([]);
-        assert!(extract_name_from_json(&empty).is_none());
+    fn cpath(s: &str) -> classic::Path {
+        classic::Path::new(s.split('/').map(cname).collect())
     }
 
-    #[test]
-    fn test_empty_package_conversion() {
-        let classic_pkg = classic::Package {
-            name: "test".to_string(),
-            modules: vec![],
-        };
+    fn int_type() -> ClassicType {
+        classic::Type::Reference(
+            Attrs::None,
+            classic::FQName::new(cpath("morphir/s-d-k"), cpath("basics"), cname("int")),
+            vec![],
+        )
+    }
 
-        let v4_pkg = classic_to_v4(classic_pkg);
-        assert!(v4_pkg.modules.is_empty());
+    fn public<T>(value: T) -> classic::AccessControlled<T> {
+        classic::AccessControlled {
+            access: classic::Access::Public,
+            value,
+        }
     }
 
-    #[test]
-    fn test_module_conversion_roundtrip() {
-        let classic_module = classic::Module {
-            name: Path::new("test/module"),
-            detail: classic::ModuleDetail {
-                access: "Public".to_string(),
-                value: classic::ModuleValue {
-                    types: vec![],
+    /// `increment x = case x of 0 -> 1; _ as n -> n + 1` plus a documented record alias
+    fn sample_classic() -> classic::Distribution {
+        let int = int_type();
+        let var = |n: &str| classic::Value::Variable(int.clone(), cname(n));
+        let body = classic::Value::PatternMatch(
+            int.clone(),
+            Box::new(var("x")),
+            vec![
+                (
+                    classic::Pattern::Literal(int.clone(), classic::Literal::WholeNumber(0)),
+                    classic::Value::Literal(int.clone(), classic::Literal::WholeNumber(1)),
+                ),
+                (
+                    classic::Pattern::As(
+                        int.clone(),
+                        Box::new(classic::Pattern::Wildcard(int.clone())),
+                        cname("n"),
+                    ),
+                    classic::Value::Apply(
+                        int.clone(),
+                        Box::new(classic::Value::Apply(
+                            classic::Type::Function(
+                                Attrs::None,
+                                Box::new(int.clone()),
+                                Box::new(int.clone()),
+                            ),
+                            Box::new(classic::Value::Reference(
+                                classic::Type::Unit(Attrs::None),
+                                classic::FQName::new(
+                                    cpath("morphir/s-d-k"),
+                                    cpath("basics"),
+                                    cname("add"),
+                                ),
+                            )),
+                            Box::new(var("n")),
+                        )),
+                        Box::new(classic::Value::Literal(
+                            int.clone(),
+                            classic::Literal::WholeNumber(1),
+                        )),
+                    ),
+                ),
+            ],
+        );
+        let value = classic::ValueDefinition {
+            input_types: vec![classic::value::ValueArgument {
+                name: cname("x"),
+                annotation: int.clone(),
+                ty: int.clone(),
+            }],
+            output_type: int.clone(),
+            body,
+        };
+        let alias = classic::TypeDefinition::Alias(
+            vec![],
+            classic::Type::Record(
+                Attrs::None,
+                vec![classic::Field {
+                    name: cname("total-amount"),
+                    ty: int.clone(),
+                }],
+            ),
+        );
+        let module = classic::ModuleDefinition {
+            types: vec![(
+                cname("order"),
+                public(classic::Documented::new("An order", alias)),
+            )],
+            values: vec![(
+                cname("increment"),
+                public(classic::Documented::new("", value)),
+            )],
+            doc: Some("Orders".to_string()),
+        };
+        let dependency = classic::PackageSpecification {
+            modules: vec![classic::package::ModuleSpecEntry {
+                path: cpath("basics"),
+                specification: classic::ModuleSpecification {
+                    types: vec![(
+                        cname("int"),
+                        classic::Documented::new("", classic::TypeSpecification::Opaque(vec![])),
+                    )],
                     values: vec![],
-                    doc: Some("Test module".to_string()),
+                    doc: None,
                 },
-            },
+            }],
         };
-
-        let (name, v4_module) = convert_module_to_v4(classic_module.clone());
-        let roundtrip = convert_module_to_classic(name, v4_module);
-
-        assert_eq!(roundtrip.name.to_string(), classic_module.name.to_string());
-        assert_eq!(roundtrip.detail.access, classic_module.detail.access);
-        assert_eq!(roundtrip.detail.value.doc, classic_module.detail.value.doc);
-    }
-
-    // ==========================================================================
-    // Visitor-based converter tests
-    // ==========================================================================
-
-    use crate::ir::attributes::{SourceLocation, TypeAttributes, ValueAttributes};
-    use crate::ir::type_expr::Type;
-    use crate::ir::value_expr::Value;
-    use crate::traversal::transform::TypeTransformVisitor;
-
-    #[test]
-    fn test_classic_to_v4_type_attrs() {
-        let converter = ClassicToV4Converter;
-        let classic_attrs = serde_json::json!({});
-
-        let v4_attrs =
-            TypeTransformVisitor::transform_type_attrs(&converter, &classic_attrs).unwrap();
-
-        assert!(v4_attrs.source.is_none());
-        assert!(v4_attrs.constraints.is_null());
-        assert_eq!(v4_attrs.extensions, serde_json::json!({}));
+        classic::Distribution {
+            format_version: CLASSIC_FORMAT_VERSION,
+            distribution: classic::DistributionBody::Library(
+                cpath("my/package"),
+                vec![(cpath("morphir/s-d-k"), dependency)],
+                classic::PackageDefinition {
+                    modules: vec![classic::ModuleEntry {
+                        path: cpath("order-book"),
+                        definition: public(module),
+                    }],
+                },
+            ),
+        }
     }
 
     #[test]
-    fn test_classic_to_v4_preserves_extensions() {
-        let converter = ClassicToV4Converter;
-        let classic_attrs = serde_json::json!({"custom": "data"});
-
-        let v4_attrs =
-            TypeTransformVisitor::transform_type_attrs(&converter, &classic_attrs).unwrap();
-
-        assert_eq!(v4_attrs.extensions, serde_json::json!({"custom": "data"}));
+    fn test_classic_to_v4_uses_kebab_keys_and_keeps_docs() {
+        let conversion = classic_to_v4(&sample_classic());
+        assert!(conversion.warnings.is_empty(), "{:?}", conversion.warnings);
+
+        let dist = &conversion.ir.distribution;
+        assert_eq!(dist.package_name().to_string(), "my/package");
+        assert!(dist.dependencies().contains_key("morphir/sdk"));
+
+        let module = &dist.definition().unwrap().modules["order-book"];
+        assert_eq!(module.value.doc.as_deref(), Some("Orders"));
+        assert_eq!(module.value.types["order"].doc.as_deref(), Some("An order"));
+        assert_eq!(module.value.values["increment"].doc, None);
+
+        let increment = &module.value.values["increment"].value;
+        let v4::ValueBody::Expression(v4::Value::PatternMatch(attrs, _, cases)) = &increment.body
+        else {
+            panic!("expected a pattern match body");
+        };
+        assert_eq!(
+            attrs.inferred_type,
+            serde_json::to_value(type_to_v4(&int_type())).unwrap()
+        );
+        assert!(matches!(
+            &cases[0].0,
+            v4::Pattern::LiteralPattern(_, v4::Literal::Integer(0))
+        ));
     }
 
     #[test]
-    fn test_classic_to_v4_type_variable() {
-        let converter = ClassicToV4Converter;
-        let classic_type: Type<serde_json::Value> =
-            Type::Variable(serde_json::json!({}), Name::from("a"));
-
-        let v4_type = TypeTransformVisitor::transform_type(&converter, &classic_type).unwrap();
-
-        if let Type::Variable(attrs, name) = v4_type {
-            assert!(attrs.source.is_none());
-            assert_eq!(name.to_string(), "a");
-        } else {
-            panic!("Expected Variable type");
-        }
+    fn test_classic_roundtrip_is_lossless() {
+        let classic = sample_classic();
+        let v4 = classic_to_v4(&classic);
+        let back = v4_to_classic(&v4.ir).unwrap();
+        assert!(back.warnings.is_empty(), "{:?}", back.warnings);
+        assert_eq!(back.ir, classic);
+
+        // The Classic output must load again
+        let json = serde_json::to_value(&back.ir).unwrap();
+        let reloaded: classic::Distribution = serde_json::from_value(json).unwrap();
+        assert_eq!(reloaded, classic);
     }
 
     #[test]
-    fn test_v4_to_classic_type_attrs() {
-        let converter = V4ToClassicConverter;
-        let v4_attrs = TypeAttributes {
-            source: Some(SourceLocation {
-                start_line: 1,
-                start_column: 0,
-                end_line: 1,
-                end_column: 10,
-            }),
-            constraints: serde_json::json!({"kind": "type"}),
-            extensions: serde_json::json!({}),
-        };
-
-        let classic_attrs =
-            TypeTransformVisitor::transform_type_attrs(&converter, &v4_attrs).unwrap();
+    fn test_sdk_package_spelling_roundtrips() {
+        let v4 = classic_to_v4(&sample_classic()).ir;
+        let increment = &v4.distribution.definition().unwrap().modules["order-book"]
+            .value
+            .values["increment"]
+            .value;
+        assert_eq!(
+            increment.output_type,
+            v4::Type::Reference(
+                v4::TypeAttributes::default(),
+                FQName::from_canonical_string("morphir/sdk:basics#int").unwrap(),
+                vec![],
+            )
+        );
 
-        // Source and constraints are lost, empty object returned
-        assert_eq!(classic_attrs, serde_json::json!({}));
+        let back = v4_to_classic(&v4).unwrap().ir;
+        let classic::DistributionBody::Library(_, dependencies, _) = &back.distribution;
+        assert_eq!(dependencies[0].0, cpath("morphir/s-d-k"));
+        assert_eq!(
+            type_to_classic(&increment.output_type),
+            int_type(),
+            "V4 SDK references are written with the morphir-elm spelling"
+        );
     }
 
     #[test]
-    fn test_v4_to_classic_preserves_extensions() {
-        let converter = V4ToClassicConverter;
-        let v4_attrs = TypeAttributes {
-            source: None,
-            constraints: serde_json::Value::Null,
-            extensions: serde_json::json!({"custom": "data"}),
-        };
-
-        let classic_attrs =
-            TypeTransformVisitor::transform_type_attrs(&converter, &v4_attrs).unwrap();
-
-        assert_eq!(classic_attrs, serde_json::json!({"custom": "data"}));
+    fn test_access_conversion() {
+        for access in [classic::Access::Public, classic::Access::Private] {
+            assert_eq!(access_to_classic(&access_to_v4(&access)), access);
+        }
+        assert_eq!(access_to_v4(&classic::Access::Private), v4::Access::Private);
     }
 
     #[test]
-    fn test_v4_to_classic_type_unit() {
-        let converter = V4ToClassicConverter;
-        let v4_type: Type<TypeAttributes> = Type::Unit(TypeAttributes::default());
-
-        let classic_type = TypeTransformVisitor::transform_type(&converter, &v4_type).unwrap();
-
-        if let Type::Unit(attrs) = classic_type {
-            assert_eq!(attrs, serde_json::json!({}));
-        } else {
-            panic!("Expected Unit type");
-        }
+    fn test_names_are_split_and_lowercased() {
+        let name = classic::Name::new(["TestModule", "name"]);
+        assert_eq!(key(&name), "test-module-name");
+        assert_eq!(key_to_classic("TestModule"), cname("test-module"));
+        assert_eq!(
+            path_to_v4(&cpath("my/order-book")).to_string(),
+            "my/order-book"
+        );
     }
 
     #[test]
-    fn test_v4_to_classic_value_unit() {
-        use crate::traversal::transform::ValueTransformVisitor;
-
-        let converter = V4ToClassicConverter;
-        let v4_value: Value<TypeAttributes, ValueAttributes> =
-            Value::Unit(ValueAttributes::default());
-
-        let classic_value = ValueTransformVisitor::transform_value(&converter, &v4_value).unwrap();
-
-        if let Value::Unit(attrs) = classic_value {
-            assert_eq!(attrs, serde_json::json!({}));
-        } else {
-            panic!("Expected Unit value");
-        }
+    fn test_empty_package_conversion() {
+        let classic = classic::Distribution {
+            format_version: CLASSIC_FORMAT_VERSION,
+            distribution: classic::DistributionBody::Library(
+                cpath("empty"),
+                vec![],
+                classic::PackageDefinition { modules: vec![] },
+            ),
+        };
+        let v4 = classic_to_v4(&classic);
+        assert!(v4.warnings.is_empty());
+        assert!(v4.ir.distribution.definition().unwrap().modules.is_empty());
+        assert_eq!(v4_to_classic(&v4.ir).unwrap().ir, classic);
     }
 
     #[test]
-    fn test_v4_to_classic_hole_error() {
-        use crate::ir::value_expr::HoleReason;
-        use crate::traversal::transform::ValueTransformVisitor;
-
-        let converter = V4ToClassicConverter;
-        let v4_value: Value<TypeAttributes, ValueAttributes> =
-            Value::Hole(ValueAttributes::default(), HoleReason::Draft, None);
-
-        let result = ValueTransformVisitor::transform_value(&converter, &v4_value);
-
-        assert!(result.is_err());
-        if let Err(ConversionError::CannotDowngrade(name)) = result {
-            assert_eq!(name, "Hole");
-        } else {
-            panic!("Expected CannotDowngrade error");
-        }
+    fn test_type_variable_and_unit_conversion() {
+        let variable = classic::Type::Variable(Attrs::None, cname("a"));
+        let v4::Type::Variable(attrs, name) = type_to_v4(&variable) else {
+            panic!("expected a type variable");
+        };
+        assert_eq!(attrs, v4::TypeAttributes::default());
+        assert_eq!(name.to_string(), "a");
+
+        let unit = v4::Type::Unit(v4::TypeAttributes {
+            extensions: json!({"custom": "data"}),
+            ..Default::default()
+        });
+        // Classic types carry no attributes
+        assert_eq!(type_to_classic(&unit), classic::Type::Unit(Attrs::None));
     }
 
     #[test]
-    fn test_conversion_error_display() {
-        let err = ConversionError::CannotDowngrade("Hole");
+    fn test_holes_are_left_out_of_classic() {
+        let mut ir = classic_to_v4(&sample_classic()).ir;
+        let v4::Distribution::Library(lib) = &mut ir.distribution else {
+            unreachable!()
+        };
+        lib.def.modules["order-book"].value.values["increment"]
+            .value
+            .body = v4::ValueBody::Expression(v4::Value::Hole(
+            v4::ValueAttributes::default(),
+            v4::HoleReason::Draft,
+            None,
+        ));
+
+        let conversion = v4_to_classic(&ir).unwrap();
+        let classic::DistributionBody::Library(_, _, def) = &conversion.ir.distribution;
+        assert!(def.modules[0].definition.value.values.is_empty());
         assert_eq!(
-            err.to_string(),
-            "Cannot downgrade V4-only construct 'Hole' to Classic format"
+            conversion.warnings,
+            vec![
+                "value order-book.increment uses a hole, which Classic IR cannot represent, and was left out"
+            ]
         );
-
-        let err = ConversionError::Message("Test error".to_string());
-        assert_eq!(err.to_string(), "Test error");
-    }
-
-    #[test]
-    fn test_convert_type_expr_variable_compact_format() {
-        // Classic Variable: ["Variable", {attrs}, ["name"]]
-        let classic_var = serde_json::json!(["Variable", {}, ["a"]]);
-
-        // Ensure compact mode is on (default)
-        set_compact_format(true);
-        let v4_json = convert_type_expr_to_v4(&classic_var);
-
-        // Compact: bare name string
-        assert_eq!(v4_json, serde_json::json!("a"));
     }
 
     #[test]
-    fn test_convert_type_expr_variable_expanded_format() {
-        // Classic Variable: ["Variable", {attrs}, ["name"]]
-        let classic_var = serde_json::json!(["Variable", {}, ["a"]]);
+    fn test_v4_type_output_compact_and_expanded() {
+        let list = classic::Type::Reference(
+            Attrs::None,
+            classic::FQName::new(cpath("morphir/s-d-k"), cpath("list"), cname("list")),
+            vec![classic::Type::Variable(Attrs::None, cname("a"))],
+        );
+        let v4 = type_to_v4(&list);
 
-        // Set expanded mode
-        set_compact_format(false);
-        let v4_json = convert_type_expr_to_v4(&classic_var);
+        let compact =
+            v4::serde_v4::omitting_empty_attributes(|| serde_json::to_value(&v4).unwrap());
+        assert_eq!(
+            compact,
+            json!({"Reference": {"fqname": "morphir/sdk:list#list", "args": [{"Variable": {"name": "a"}}]}})
+        );
 
-        // Expanded: {"Variable": {"name": name}}
-        assert_eq!(v4_json, serde_json::json!({"Variable": {"name": "a"}}));
+        let expanded = serde_json::to_value(&v4).unwrap();
+        assert_eq!(expanded.pointer("/Reference/attrs"), Some(&json!({})));
+        assert_eq!(
+            expanded.pointer("/Reference/args/0/Variable/attrs"),
+            Some(&json!({}))
+        );
 
-        // Reset to compact mode for other tests
-        set_compact_format(true);
+        // Both forms read back the same
+        let compact: v4::Type = serde_json::from_value(compact).unwrap();
+        let expanded: v4::Type = serde_json::from_value(expanded).unwrap();
+        assert_eq!(compact, v4);
+        assert_eq!(expanded, v4);
     }
 
     #[test]
-    fn test_convert_type_expr_reference_compact_format() {
-        // Classic Reference without args: ["Reference", {attrs}, [pkg_path, mod_path, local_name], []]
-        // Package path: [["morphir"]] -> "morphir"
-        // Module path: [["sdk"]] -> "sdk"
-        // Local name: ["int"] -> "int"
-        let classic_ref =
-            serde_json::json!(["Reference", {}, [[["morphir"]], [["sdk"]], ["int"]], []]);
-
-        set_compact_format(true);
-        let v4_json = convert_type_expr_to_v4(&classic_ref);
-
-        // Compact without args: bare fqname string
-        assert_eq!(v4_json, serde_json::json!("morphir:sdk#int"));
+    fn test_variable_pattern_becomes_named_wildcard() {
+        let pattern = classic::Pattern::Variable(int_type(), cname("n"));
+        let v4::Pattern::AsPattern(_, inner, name) = pattern_to_v4(&pattern) else {
+            panic!("expected an as-pattern");
+        };
+        assert!(matches!(*inner, v4::Pattern::WildcardPattern(_)));
+        assert_eq!(name.to_string(), "n");
     }
 
     #[test]
-    fn test_convert_type_expr_reference_with_args_compact_format() {
-        // Classic Reference with args: ["Reference", {}, [pkg_path, mod_path, local_name], [type_args]]
-        let classic_ref = serde_json::json!([
-            "Reference",
-            {},
-            [[["morphir"]], [["sdk"]], ["list"]],
-            [["Variable", {}, ["a"]]]
-        ]);
-
-        set_compact_format(true);
-        let v4_json = convert_type_expr_to_v4(&classic_ref);
-
-        // Compact with args: {"Reference": [fqname, arg1, ...]}
+    fn test_v4_only_constructs_are_reported() {
+        let mut ir = classic_to_v4(&sample_classic()).ir;
+        let v4::Distribution::Library(lib) = &mut ir.distribution else {
+            unreachable!()
+        };
+        let module = &mut lib.def.modules["order-book"].value;
+        let mut native = module.values["increment"].clone();
+        native.value.body = v4::ValueBody::Native(v4::NativeInfo {
+            hint: v4::NativeHint::Arithmetic,
+            description: None,
+        });
+        module.values.insert("native-add".to_string(), native);
+        module.values["increment"].value.body = v4::ValueBody::Expression(v4::Value::Literal(
+            v4::ValueAttributes::default(),
+            v4::Literal::Decimal("1.5".to_string()),
+        ));
+
+        let conversion = v4_to_classic(&ir).unwrap();
+        let classic::DistributionBody::Library(_, _, def) = &conversion.ir.distribution;
+        let names: Vec<_> = def.modules[0]
+            .definition
+            .value
+            .values
+            .iter()
+            .map(|(name, _)| key(name))
+            .collect();
+        assert_eq!(names, vec!["increment"]);
         assert_eq!(
-            v4_json,
-            serde_json::json!({"Reference": ["morphir:sdk#list", "a"]})
+            conversion.warnings,
+            vec![
+                "value order-book.native-add uses a native body, which Classic IR cannot represent, and was left out",
+                "1 value node(s) had no inferred type and were annotated with unit",
+                "1 decimal literal(s) written as float literals",
+            ]
         );
     }
 
     #[test]
-    fn test_convert_type_expr_reference_expanded_format() {
-        // Classic Reference with args
-        let classic_ref = serde_json::json!([
-            "Reference",
-            {},
-            [[["morphir"]], [["sdk"]], ["list"]],
-            [["Variable", {}, ["a"]]]
-        ]);
-
-        set_compact_format(false);
-        let v4_json = convert_type_expr_to_v4(&classic_ref);
-
-        // Expanded: {"Reference": {"fqname": ..., "args": [...]}}
-        assert_eq!(
-            v4_json,
-            serde_json::json!({
-                "Reference": {
-                    "fqname": "morphir:sdk#list",
-                    "args": [{"Variable": {"name": "a"}}]
+    fn test_specs_cannot_be_converted_to_classic() {
+        let ir: v4::IRFile = serde_json::from_value(json!({
+            "formatVersion": "4.0.0",
+            "distribution": {
+                "Specs": {
+                    "packageName": "my/package",
+                    "dependencies": {},
+                    "spec": {"modules": {}}
                 }
-            })
-        );
-
-        // Reset to compact mode
-        set_compact_format(true);
+            }
+        }))
+        .unwrap();
+        assert!(matches!(v4_to_classic(&ir), Err(Error::InvalidIr(_))));
     }
 }
@@ -13,6 +13,12 @@
 // Legacy support
 pub mod classic;
 
+// Structural comparison of IR documents
+pub mod diff;
+
+// JSON Schemas of the IR formats
+pub mod schema;
+
 // V4 is the primary format
 pub mod v4;
 
@@ -2,17 +2,19 @@
 //!
 //! Access control wrappers for the Classic Morphir IR format.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Access level - serialized as PascalCase ("Public" or "Private")
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum Access {
     Public,
     Private,
 }
 
 /// Access controlled content - serialized as {"access":"Public/Private","value":...}
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "AccessControlled_of_{A}")]
 pub struct AccessControlled<A> {
     pub access: Access,
     pub value: A,
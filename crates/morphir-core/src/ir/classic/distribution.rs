@@ -2,6 +2,7 @@
 //!
 //! Distribution wrapper for the Classic Morphir IR format.
 
+use schemars::JsonSchema;
 use serde::de::{self, IgnoredAny, SeqAccess, Visitor};
 use serde::ser::{SerializeTuple, Serializer};
 use serde::{Deserialize, Deserializer, Serialize};
@@ -14,7 +15,7 @@ use super::package::{PackageDefinition, PackageSpecification};
 use super::types::Type;
 
 /// Distribution of packages
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Distribution {
     pub format_version: u32,
@@ -2,10 +2,12 @@
 //!
 //! Documentation wrapper for the Classic Morphir IR format.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Type that represents a documented value.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "Documented_of_{A}")]
 pub struct Documented<A> {
     pub doc: String,
     pub value: A,
@@ -12,6 +12,7 @@ pub mod documented;
 pub mod literal;
 pub mod naming;
 pub mod pattern;
+pub mod schema;
 pub mod types;
 pub mod value;
 
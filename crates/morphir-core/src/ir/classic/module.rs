@@ -2,8 +2,10 @@
 //!
 //! Module structures for the Classic Morphir IR format.
 
+use schemars::JsonSchema;
 use serde::de::{self, IgnoredAny, SeqAccess, Visitor};
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
 use super::access::AccessControlled;
@@ -13,12 +15,24 @@ use super::types::TypeDefinition;
 use super::value::ValueDefinition;
 
 /// Module entry - [modulePath, AccessControlled<ModuleDefinition>]
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ModuleEntry<TA, VA> {
     pub path: Path,
     pub definition: AccessControlled<ModuleDefinition<TA, VA>>,
 }
 
+impl<TA: Serialize, VA: Serialize> Serialize for ModuleEntry<TA, VA> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.path)?;
+        tuple.serialize_element(&self.definition)?;
+        tuple.end()
+    }
+}
+
 impl<'de, TA: Deserialize<'de>, VA: Deserialize<'de>> Deserialize<'de> for ModuleEntry<TA, VA> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -63,7 +77,7 @@ pub type ModuleTypeDefinition<A> = (Name, AccessControlled<Documented<TypeDefini
 pub type ModuleValueDefinition<TA, VA> =
     (Name, AccessControlled<Documented<ValueDefinition<TA, VA>>>);
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 #[serde(bound(deserialize = "A: Deserialize<'de>"))]
 pub struct ModuleSpecification<A> {
@@ -75,7 +89,7 @@ pub struct ModuleSpecification<A> {
 }
 
 /// Module definition (full implementation)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 #[serde(bound(deserialize = "TA: Deserialize<'de>, VA: Deserialize<'de>"))]
 pub struct ModuleDefinition<TA, VA> {
@@ -12,6 +12,7 @@ use std::fmt;
 
 /// Classic Name - a list of words, serialized as ["word1", "word2"]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, JsonSchema)]
+#[schemars(transparent)]
 pub struct Name {
     #[schemars(with = "Vec<String>")]
     pub words: Vec<Word>,
@@ -130,6 +131,7 @@ impl<'de> Deserialize<'de> for Name {
 
 /// Classic Path - a list of Names, serialized as [["word1"], ["word2", "word3"]]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, JsonSchema)]
+#[schemars(transparent)]
 pub struct Path {
     pub segments: Vec<Name>,
 }
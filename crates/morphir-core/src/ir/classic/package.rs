@@ -1,22 +1,36 @@
 use super::module::{ModuleEntry, ModuleSpecification};
 use super::naming::Path;
+use schemars::JsonSchema;
 use serde::de::{self, IgnoredAny, SeqAccess, Visitor};
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 
 /// Package specification - contains a list of module specifications
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct PackageSpecification<A> {
     pub modules: Vec<ModuleSpecEntry<A>>,
 }
 
 /// Module specification entry - [modulePath, ModuleSpecification]
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ModuleSpecEntry<A> {
     pub path: Path,
     pub specification: ModuleSpecification<A>,
 }
 
+impl<A: Serialize> Serialize for ModuleSpecEntry<A> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.path)?;
+        tuple.serialize_element(&self.specification)?;
+        tuple.end()
+    }
+}
+
 impl<'de, A: Deserialize<'de>> Deserialize<'de> for ModuleSpecEntry<A> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -58,7 +72,7 @@ impl<'de, A: Deserialize<'de>> Deserialize<'de> for ModuleSpecEntry<A> {
 }
 
 /// Package definition - contains a list of module entries (full implementation)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct PackageDefinition<TA, VA> {
     pub modules: Vec<ModuleEntry<TA, VA>>,
 }
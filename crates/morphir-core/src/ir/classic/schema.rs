@@ -0,0 +1,388 @@
+//! JSON Schemas of the Classic types with hand-written serialization.
+//!
+//! Classic IR is written as arrays: names are lists of words, and
+//! expressions are `["Tag", attributes, ...]` arrays in the order of the
+//! variant's fields.
+
+use std::borrow::Cow;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+
+use super::AccessControlled;
+use super::attributes::Attrs;
+use super::distribution::DistributionBody;
+use super::literal::Literal;
+use super::module::{ModuleDefinition, ModuleEntry, ModuleSpecification};
+use super::naming::{FQName, Name, Path};
+use super::package::{ModuleSpecEntry, PackageDefinition, PackageSpecification};
+use super::pattern::Pattern;
+use super::types::{Constructor, Field, Type, TypeDefinition, TypeSpecification};
+use super::value::{Definition, Value, ValueArgument, ValueParameter};
+use crate::ir::schema::{empty_object, of, one_of, tagged_array, tuple};
+
+/// Id of `name` applied to the types with ids `params`
+fn generic_id(name: &str, params: &[Cow<'static, str>]) -> Cow<'static, str> {
+    format!("{}::{}<{}>", module_path!(), name, params.join(", ")).into()
+}
+
+impl JsonSchema for FQName {
+    fn schema_name() -> Cow<'static, str> {
+        "FQName".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let path = of::<Path>(generator);
+        tuple(vec![path.clone(), path, of::<Name>(generator)])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for Attrs<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "Attrs".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("Attrs", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        one_of(vec![empty_object(), of::<A>(generator)])
+    }
+}
+
+impl JsonSchema for Literal {
+    fn schema_name() -> Cow<'static, str> {
+        "Literal".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        tagged_array(vec![
+            ("BoolLiteral", vec![json_schema!({ "type": "boolean" })]),
+            (
+                "CharLiteral",
+                vec![json_schema!({ "type": "string", "minLength": 1, "maxLength": 1 })],
+            ),
+            ("StringLiteral", vec![json_schema!({ "type": "string" })]),
+            (
+                "WholeNumberLiteral",
+                vec![json_schema!({ "type": "integer" })],
+            ),
+            ("FloatLiteral", vec![json_schema!({ "type": "number" })]),
+        ])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for Type<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "Type".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("Type", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let a = of::<A>(generator);
+        let tpe = of::<Type<A>>(generator);
+        let types = of::<Vec<Type<A>>>(generator);
+        let fields = of::<Vec<Field<A>>>(generator);
+        let name = of::<Name>(generator);
+        tagged_array(vec![
+            (
+                "ExtensibleRecord",
+                vec![a.clone(), name.clone(), fields.clone()],
+            ),
+            ("Function", vec![a.clone(), tpe.clone(), tpe]),
+            ("Record", vec![a.clone(), fields]),
+            (
+                "Reference",
+                vec![a.clone(), of::<FQName>(generator), types.clone()],
+            ),
+            ("Tuple", vec![a.clone(), types]),
+            ("Unit", vec![a.clone()]),
+            ("Variable", vec![a, name]),
+        ])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for Field<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "Field".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("Field", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![of::<Name>(generator), of::<Type<A>>(generator)])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for Constructor<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "Constructor".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("Constructor", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![
+            of::<Name>(generator),
+            of::<Vec<(Name, Type<A>)>>(generator),
+        ])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for TypeSpecification<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "TypeSpecification".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("TypeSpecification", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let params = of::<Vec<Name>>(generator);
+        tagged_array(vec![
+            (
+                "TypeAliasSpecification",
+                vec![params.clone(), of::<Type<A>>(generator)],
+            ),
+            ("OpaqueTypeSpecification", vec![params.clone()]),
+            (
+                "CustomTypeSpecification",
+                vec![params, of::<Vec<Constructor<A>>>(generator)],
+            ),
+        ])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for TypeDefinition<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "TypeDefinition".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("TypeDefinition", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let params = of::<Vec<Name>>(generator);
+        tagged_array(vec![
+            (
+                "TypeAliasDefinition",
+                vec![params.clone(), of::<Type<A>>(generator)],
+            ),
+            (
+                "CustomTypeDefinition",
+                vec![
+                    params,
+                    of::<AccessControlled<Vec<Constructor<A>>>>(generator),
+                ],
+            ),
+        ])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for Pattern<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "Pattern".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("Pattern", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let a = of::<A>(generator);
+        let pattern = of::<Pattern<A>>(generator);
+        let patterns = of::<Vec<Pattern<A>>>(generator);
+        let name = of::<Name>(generator);
+        tagged_array(vec![
+            ("WildcardPattern", vec![a.clone()]),
+            ("AsPattern", vec![a.clone(), pattern.clone(), name.clone()]),
+            ("TuplePattern", vec![a.clone(), patterns.clone()]),
+            (
+                "ConstructorPattern",
+                vec![a.clone(), of::<FQName>(generator), patterns],
+            ),
+            ("EmptyListPattern", vec![a.clone()]),
+            ("HeadTailPattern", vec![a.clone(), pattern.clone(), pattern]),
+            ("LiteralPattern", vec![a.clone(), of::<Literal>(generator)]),
+            ("UnitPattern", vec![a.clone()]),
+            ("VariablePattern", vec![a, name]),
+        ])
+    }
+}
+
+impl<TA: JsonSchema, VA: JsonSchema> JsonSchema for Value<TA, VA> {
+    fn schema_name() -> Cow<'static, str> {
+        "Value".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("Value", &[TA::schema_id(), VA::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let va = of::<VA>(generator);
+        let value = of::<Value<TA, VA>>(generator);
+        let values = of::<Vec<Value<TA, VA>>>(generator);
+        let pattern = of::<Pattern<VA>>(generator);
+        let name = of::<Name>(generator);
+        let fqname = of::<FQName>(generator);
+        let fields = of::<Vec<(Name, Value<TA, VA>)>>(generator);
+        tagged_array(vec![
+            ("Apply", vec![va.clone(), value.clone(), value.clone()]),
+            ("Constructor", vec![va.clone(), fqname.clone()]),
+            (
+                "Destructure",
+                vec![va.clone(), pattern.clone(), value.clone(), value.clone()],
+            ),
+            ("Field", vec![va.clone(), value.clone(), name.clone()]),
+            ("FieldFunction", vec![va.clone(), name.clone()]),
+            (
+                "IfThenElse",
+                vec![va.clone(), value.clone(), value.clone(), value.clone()],
+            ),
+            ("Lambda", vec![va.clone(), pattern, value.clone()]),
+            (
+                "LetDefinition",
+                vec![
+                    va.clone(),
+                    name.clone(),
+                    of::<Definition<TA, VA>>(generator),
+                    value.clone(),
+                ],
+            ),
+            (
+                "LetRecursion",
+                vec![
+                    va.clone(),
+                    of::<Vec<(Name, Definition<TA, VA>)>>(generator),
+                    value.clone(),
+                ],
+            ),
+            ("List", vec![va.clone(), values.clone()]),
+            ("Literal", vec![va.clone(), of::<Literal>(generator)]),
+            (
+                "PatternMatch",
+                vec![
+                    va.clone(),
+                    value.clone(),
+                    of::<Vec<(Pattern<VA>, Value<TA, VA>)>>(generator),
+                ],
+            ),
+            ("Record", vec![va.clone(), fields.clone()]),
+            ("Tuple", vec![va.clone(), values]),
+            ("Unit", vec![va.clone()]),
+            ("Update", vec![va.clone(), value, fields]),
+            ("Variable", vec![va.clone(), name]),
+            ("Reference", vec![va, fqname]),
+        ])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for ValueParameter<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "ValueParameter".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("ValueParameter", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![of::<Name>(generator), of::<Type<A>>(generator)])
+    }
+}
+
+impl<TA: JsonSchema, VA: JsonSchema> JsonSchema for ValueArgument<TA, VA> {
+    fn schema_name() -> Cow<'static, str> {
+        "ValueArgument".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("ValueArgument", &[TA::schema_id(), VA::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![
+            of::<Name>(generator),
+            of::<VA>(generator),
+            of::<Type<TA>>(generator),
+        ])
+    }
+}
+
+impl<TA: JsonSchema, VA: JsonSchema> JsonSchema for ModuleEntry<TA, VA> {
+    fn schema_name() -> Cow<'static, str> {
+        "ModuleEntry".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("ModuleEntry", &[TA::schema_id(), VA::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![
+            of::<Path>(generator),
+            of::<AccessControlled<ModuleDefinition<TA, VA>>>(generator),
+        ])
+    }
+}
+
+impl<A: JsonSchema> JsonSchema for ModuleSpecEntry<A> {
+    fn schema_name() -> Cow<'static, str> {
+        "ModuleSpecEntry".into()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        generic_id("ModuleSpecEntry", &[A::schema_id()])
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![
+            of::<Path>(generator),
+            of::<ModuleSpecification<A>>(generator),
+        ])
+    }
+}
+
+impl JsonSchema for DistributionBody {
+    fn schema_name() -> Cow<'static, str> {
+        "DistributionBody".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tagged_array(vec![(
+            "Library",
+            vec![
+                of::<Path>(generator),
+                of::<Vec<(Path, PackageSpecification<Attrs>)>>(generator),
+                of::<PackageDefinition<Attrs, Type<Attrs>>>(generator),
+            ],
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::schema::classic_schema;
+
+    #[test]
+    fn test_schema_defines_the_distribution_body() {
+        let schema = classic_schema().to_value();
+        let body = &schema["$defs"]["DistributionBody"]["allOf"][0]["then"];
+        assert_eq!(body["prefixItems"][0]["const"], "Library");
+        assert_eq!(body["maxItems"], 4);
+        assert_eq!(
+            schema["$defs"]["Value"]["allOf"].as_array().unwrap().len(),
+            18
+        );
+    }
+}
@@ -3,6 +3,7 @@
 //! Value expressions for the Classic Morphir IR format (V1-V3 compatible).
 
 use super::naming::{FQName, Name};
+use schemars::JsonSchema;
 use serde::de::{self, IgnoredAny, SeqAccess, Visitor};
 use serde::ser::{SerializeTuple, Serializer};
 use serde::{Deserialize, Deserializer, Serialize};
@@ -499,12 +500,24 @@ impl<'de, TA: Deserialize<'de>, VA: Deserialize<'de>> Deserialize<'de> for Value
 // ----------------------------------------------------------------------------
 
 /// Value parameter - [name, type]
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ValueParameter<A> {
     pub name: Name,
     pub ty: Type<A>,
 }
 
+impl<A: Serialize> Serialize for ValueParameter<A> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&self.name)?;
+        tuple.serialize_element(&self.ty)?;
+        tuple.end()
+    }
+}
+
 impl<'de, A: Deserialize<'de>> Deserialize<'de> for ValueParameter<A> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -537,13 +550,26 @@ impl<'de, A: Deserialize<'de>> Deserialize<'de> for ValueParameter<A> {
 }
 
 /// Value argument - [name, va, type]
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ValueArgument<TA, VA> {
     pub name: Name,
     pub annotation: VA,
     pub ty: Type<TA>,
 }
 
+impl<TA: Serialize, VA: Serialize> Serialize for ValueArgument<TA, VA> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(3)?;
+        tuple.serialize_element(&self.name)?;
+        tuple.serialize_element(&self.annotation)?;
+        tuple.serialize_element(&self.ty)?;
+        tuple.end()
+    }
+}
+
 impl<'de, TA: Deserialize<'de>, VA: Deserialize<'de>> Deserialize<'de> for ValueArgument<TA, VA> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -583,7 +609,7 @@ impl<'de, TA: Deserialize<'de>, VA: Deserialize<'de>> Deserialize<'de> for Value
 }
 
 /// Value specification (inputs and output type)
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ValueSpecification<A> {
     pub inputs: Vec<ValueParameter<A>>,
@@ -591,7 +617,7 @@ pub struct ValueSpecification<A> {
 }
 
 /// Value definition (implementation)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ValueDefinition<TA, VA> {
     pub input_types: Vec<ValueArgument<TA, VA>>,
@@ -600,7 +626,7 @@ pub struct ValueDefinition<TA, VA> {
 }
 
 /// Definition used in Let bindings
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Definition<TA, VA> {
     pub input_types: Vec<ValueArgument<TA, VA>>,
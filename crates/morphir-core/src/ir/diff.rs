@@ -0,0 +1,248 @@
+//! Structural comparison of IR documents
+//!
+//! Compares two IR documents in their JSON form and finds the places where
+//! the actual document differs from the expected one, so a golden-output
+//! test can point at the definitions that changed instead of printing both
+//! documents in full.
+//!
+//! ```
+//! use morphir_core::ir::diff::diff_ir;
+//! use serde_json::json;
+//!
+//! let expected = json!({"modules": {"Orders": {"values": ["total"]}}});
+//! let actual = json!({"modules": {"Orders": {"values": ["sum"]}}});
+//! let differences = diff_ir(&expected, &actual);
+//! assert_eq!(
+//!     differences[0].to_string(),
+//!     r#".modules.Orders.values[0]: expected "total", got "sum""#
+//! );
+//! ```
+
+use std::fmt;
+
+use serde_json::Value;
+
+/// A step from a compared whole into one of its parts
+///
+/// [`Difference`] renders its path as these steps in a row, so each step
+/// displays as an accessor, e.g. `.total` or `[1]`.
+pub trait Segment: fmt::Display {
+    /// What the path names when it is empty, i.e. the whole differs
+    const ROOT: &'static str;
+}
+
+/// One step from a document into one of its parts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IrPathSegment {
+    /// Object member, by key
+    Key(String),
+    /// Array element
+    Index(usize),
+}
+
+impl Segment for IrPathSegment {
+    const ROOT: &'static str = "document";
+}
+
+impl fmt::Display for IrPathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IrPathSegment::Key(key)
+                if !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '-') =>
+            {
+                write!(f, ".{}", key)
+            }
+            IrPathSegment::Key(key) => write!(f, "[{}]", Value::String(key.clone())),
+            IrPathSegment::Index(i) => write!(f, "[{}]", i),
+        }
+    }
+}
+
+/// A place where an actual whole of `V`s differs from the expected one, at
+/// a path of `S` steps
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference<S, V> {
+    /// Path from the compared whole to the differing part; empty when the
+    /// wholes differ at the top
+    pub path: Vec<S>,
+    /// Expected part, `None` if the actual whole has a part the expected
+    /// one lacks
+    pub expected: Option<V>,
+    /// Actual part, `None` if the expected part is missing from the actual
+    /// whole
+    pub actual: Option<V>,
+}
+
+/// A place where the actual document differs from the expected one
+pub type IrDifference = Difference<IrPathSegment, Value>;
+
+impl<S: Segment, V> Difference<S, V> {
+    /// Path rendered as an accessor chain, e.g. `.modules.Orders.values[0]`
+    pub fn path_string(&self) -> String {
+        if self.path.is_empty() {
+            return S::ROOT.to_string();
+        }
+        self.path.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+impl<S: Segment, V: fmt::Display> fmt::Display for Difference<S, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path_string();
+        match (&self.expected, &self.actual) {
+            (Some(expected), Some(actual)) => {
+                write!(f, "{}: expected {}, got {}", path, expected, actual)
+            }
+            (Some(expected), None) => write!(f, "{}: missing, expected {}", path, expected),
+            (None, Some(actual)) => write!(f, "{}: unexpected {}", path, actual),
+            (None, None) => write!(f, "{}: differs", path),
+        }
+    }
+}
+
+/// Compare `actual` against `expected`, returning every difference.
+///
+/// Objects are compared member by member regardless of key order, arrays
+/// element by element, and numbers by value, so `1` and `1.0` are equal.
+/// Equal documents yield no differences.
+pub fn diff_ir(expected: &Value, actual: &Value) -> Vec<IrDifference> {
+    diff_ir_ignoring(expected, actual, &[])
+}
+
+/// Compare like [`diff_ir`], skipping object members named in `ignored`
+/// wherever they occur, e.g. source locations that vary between machines.
+pub fn diff_ir_ignoring(expected: &Value, actual: &Value, ignored: &[String]) -> Vec<IrDifference> {
+    let mut differences = Vec::new();
+    let mut path = Vec::new();
+    diff_into(expected, actual, ignored, &mut path, &mut differences);
+    differences
+}
+
+/// Compare the parts of two documents found at `segment`; a part present on
+/// only one side is a difference in itself.
+fn child(
+    segment: IrPathSegment,
+    expected: Option<&Value>,
+    actual: Option<&Value>,
+    ignored: &[String],
+    path: &mut Vec<IrPathSegment>,
+    differences: &mut Vec<IrDifference>,
+) {
+    path.push(segment);
+    match (expected, actual) {
+        (Some(e), Some(a)) => diff_into(e, a, ignored, path, differences),
+        _ => differences.push(IrDifference {
+            path: path.clone(),
+            expected: expected.cloned(),
+            actual: actual.cloned(),
+        }),
+    }
+    path.pop();
+}
+
+fn diff_into(
+    expected: &Value,
+    actual: &Value,
+    ignored: &[String],
+    path: &mut Vec<IrPathSegment>,
+    differences: &mut Vec<IrDifference>,
+) {
+    if expected == actual {
+        return;
+    }
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            let is_ignored = |key: &String| ignored.contains(key);
+            for (key, value) in e.iter().filter(|(key, _)| !is_ignored(key)) {
+                child(
+                    IrPathSegment::Key(key.clone()),
+                    Some(value),
+                    a.get(key),
+                    ignored,
+                    path,
+                    differences,
+                );
+            }
+            for (key, value) in a
+                .iter()
+                .filter(|(key, _)| !is_ignored(key) && !e.contains_key(*key))
+            {
+                child(
+                    IrPathSegment::Key(key.clone()),
+                    None,
+                    Some(value),
+                    ignored,
+                    path,
+                    differences,
+                );
+            }
+        }
+        (Value::Array(e), Value::Array(a)) => {
+            for i in 0..e.len().max(a.len()) {
+                child(
+                    IrPathSegment::Index(i),
+                    e.get(i),
+                    a.get(i),
+                    ignored,
+                    path,
+                    differences,
+                );
+            }
+        }
+        (Value::Number(e), Value::Number(a)) if e.as_f64() == a.as_f64() => {}
+        _ => differences.push(IrDifference {
+            path: path.clone(),
+            expected: Some(expected.clone()),
+            actual: Some(actual.clone()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_equal_documents_have_no_differences() {
+        let document = json!({"a": [1, {"b": null}], "c": "x"});
+        let reordered = json!({"c": "x", "a": [1.0, {"b": null}]});
+        assert!(diff_ir(&document, &reordered).is_empty());
+    }
+
+    #[test]
+    fn test_differences_point_at_nested_members() {
+        let expected = json!({
+            "modules": {"Orders": {"values": {"total": {"body": 1}}}},
+            "org.example/pkg": [1, 2],
+        });
+        let actual = json!({
+            "modules": {"Orders": {"values": {"total": {"body": 2}, "sum": {}}}},
+            "org.example/pkg": [1],
+        });
+
+        let messages: Vec<String> = diff_ir(&expected, &actual)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ".modules.Orders.values.total.body: expected 1, got 2",
+                ".modules.Orders.values.sum: unexpected {}",
+                "[\"org.example/pkg\"][1]: missing, expected 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignored_members_are_skipped() {
+        let expected = json!({"name": "total", "sourceLocation": {"line": 1}});
+        let actual = json!({"name": "total", "sourceLocation": {"line": 7}});
+        assert_eq!(diff_ir(&expected, &actual).len(), 1);
+        assert!(diff_ir_ignoring(&expected, &actual, &["sourceLocation".to_string()]).is_empty());
+    }
+}
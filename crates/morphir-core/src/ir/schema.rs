@@ -0,0 +1,143 @@
+//! JSON Schemas of the IR formats.
+//!
+//! [`v4_schema`] describes a V4 [`IRFile`](super::v4::IRFile) and
+//! [`classic_schema`] a Classic [`Distribution`](super::classic::Distribution),
+//! as this crate writes them. Readers accept more than that, such as V4
+//! shorthand strings for types, so a file that fails validation may still
+//! load.
+//!
+//! Types whose JSON follows their serde derive derive `JsonSchema` next to
+//! it. Types with hand-written serialization implement it in the `schema`
+//! module of their format, using the helpers here.
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde_json::{Map, Value};
+
+/// JSON Schema of a V4 IR file
+pub fn v4_schema() -> Schema {
+    root::<super::v4::IRFile>("Morphir IR V4")
+}
+
+/// JSON Schema of a Classic (V1-V3) IR distribution
+pub fn classic_schema() -> Schema {
+    root::<super::classic::Distribution>("Morphir IR Classic")
+}
+
+fn root<T: JsonSchema>(title: &str) -> Schema {
+    let mut schema = SchemaGenerator::default().into_root_schema_for::<T>();
+    schema.insert("title".to_string(), Value::String(title.to_string()));
+    schema
+}
+
+/// Object with `required` properties and `optional` ones that may be left out
+pub(crate) fn object(required: Vec<(&str, Schema)>, optional: Vec<(&str, Schema)>) -> Schema {
+    let names: Vec<&str> = required.iter().map(|(name, _)| *name).collect();
+    let properties: Map<String, Value> = required
+        .into_iter()
+        .chain(optional)
+        .map(|(name, schema)| (name.to_string(), schema.to_value()))
+        .collect();
+    json_schema!({
+        "type": "object",
+        "properties": properties,
+        "required": names,
+        "additionalProperties": false,
+    })
+}
+
+/// One of several `{ "Tag": content }` wrapper objects
+pub(crate) fn wrapper(variants: Vec<(&str, Schema)>) -> Schema {
+    one_of(
+        variants
+            .into_iter()
+            .map(|(tag, content)| object(vec![(tag, content)], vec![]))
+            .collect(),
+    )
+}
+
+/// One of several `["Tag", ...]` arrays.
+///
+/// The tag picks the variant with `if`/`then` rather than `oneOf`, so
+/// validators only descend into the variant that matches instead of trying
+/// every variant at every level of nesting.
+pub(crate) fn tagged_array(variants: Vec<(&str, Vec<Schema>)>) -> Schema {
+    let tags: Vec<&str> = variants.iter().map(|(tag, _)| *tag).collect();
+    let cases: Vec<Schema> = variants
+        .into_iter()
+        .map(|(tag, mut items)| {
+            items.insert(0, json_schema!({ "const": tag }));
+            json_schema!({
+                "if": { "prefixItems": [{ "const": tag }] },
+                "then": tuple(items),
+            })
+        })
+        .collect();
+    json_schema!({
+        "type": "array",
+        "prefixItems": [{ "enum": tags }],
+        "minItems": 1,
+        "allOf": cases,
+    })
+}
+
+/// Array of exactly `items`, in order
+pub(crate) fn tuple(items: Vec<Schema>) -> Schema {
+    let len = items.len();
+    json_schema!({
+        "type": "array",
+        "prefixItems": items,
+        "items": false,
+        "minItems": len,
+        "maxItems": len,
+    })
+}
+
+/// Object mapping any key to `values`
+pub(crate) fn map_of(values: Schema) -> Schema {
+    json_schema!({
+        "type": "object",
+        "additionalProperties": values,
+    })
+}
+
+/// String described by `description`
+pub(crate) fn string(description: &str) -> Schema {
+    json_schema!({
+        "type": "string",
+        "description": description,
+    })
+}
+
+/// Object with no properties
+pub(crate) fn empty_object() -> Schema {
+    json_schema!({
+        "type": "object",
+        "maxProperties": 0,
+    })
+}
+
+pub(crate) fn one_of(schemas: Vec<Schema>) -> Schema {
+    json_schema!({ "oneOf": schemas })
+}
+
+/// Schema of `T`, as a reference when `T` is not inlined
+pub(crate) fn of<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    generator.subschema_for::<T>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tagged_array_starts_with_the_tag() {
+        let schema = tagged_array(vec![("Unit", vec![empty_object()])]);
+        assert_eq!(schema.as_value()["prefixItems"][0]["enum"][0], "Unit");
+        let variant = &schema.as_value()["allOf"][0]["then"];
+        assert_eq!(
+            variant["prefixItems"][0],
+            serde_json::json!({ "const": "Unit" })
+        );
+        assert_eq!(variant["minItems"], 2);
+    }
+}
@@ -13,10 +13,34 @@ pub enum Access {
 /// Generic wrapper for access-controlled values
 ///
 /// This matches morphir-elm's AccessControlled type, which is a generic wrapper
-/// that can be applied to any type that needs access control.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// that can be applied to any type that needs access control. Type and value
+/// definitions also carry their documentation here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
+#[schemars(rename = "AccessControlled_of_{T}")]
 pub struct AccessControlled<T> {
     pub access: Access,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
     pub value: T,
 }
+
+impl<T> AccessControlled<T> {
+    /// Wrap a value with public access and no documentation
+    pub fn public(value: T) -> Self {
+        Self {
+            access: Access::Public,
+            doc: None,
+            value,
+        }
+    }
+
+    /// Wrap a value with private access and no documentation
+    pub fn private(value: T) -> Self {
+        Self {
+            access: Access::Private,
+            doc: None,
+            value,
+        }
+    }
+}
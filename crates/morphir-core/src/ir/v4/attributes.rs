@@ -7,15 +7,20 @@
 //! It also provides type aliases for ergonomic V4 usage:
 //! - `V4Type`, `V4Pattern`, `V4Value`, etc.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::types::Type;
 use super::value::Value;
+use crate::vpath::VirtualPath;
 
 /// Source location information for error messages and tooling.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceLocation {
+    /// Source file, relative to the project root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<VirtualPath>,
     /// Starting line number (1-indexed)
     pub start_line: u32,
     /// Starting column number (1-indexed)
@@ -32,7 +37,7 @@ pub struct SourceLocation {
 /// - Source location tracking for error messages
 /// - Type constraints for validation
 /// - Tool-specific extensions
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TypeAttributes {
     /// Source location where this type was defined
@@ -56,7 +61,7 @@ pub struct TypeAttributes {
 /// - Tool-specific extensions
 ///
 /// Note: `inferred_type` is stored as JSON until Phase 2 adds serde to Type<A>.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ValueAttributes {
     /// Source location where this value was defined
@@ -146,6 +151,7 @@ impl SourceLocation {
     /// Create a new source location
     pub fn new(start_line: u32, start_column: u32, end_line: u32, end_column: u32) -> Self {
         SourceLocation {
+            file: None,
             start_line,
             start_column,
             end_line,
@@ -153,9 +159,16 @@ impl SourceLocation {
         }
     }
 
+    /// Set the source file of this location
+    pub fn in_file(mut self, file: impl Into<VirtualPath>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
     /// Create a single-point source location
     pub fn point(line: u32, column: u32) -> Self {
         SourceLocation {
+            file: None,
             start_line: line,
             start_column: column,
             end_line: line,
@@ -221,6 +234,16 @@ mod tests {
         assert_eq!(loc.end_column, 10);
     }
 
+    #[test]
+    fn test_source_location_file_is_normalized() {
+        let loc = SourceLocation::point(1, 1).in_file(r"src\Foo\Bar.gleam");
+        let json = serde_json::to_value(&loc).unwrap();
+        assert_eq!(json["file"], "src/Foo/Bar.gleam");
+
+        let without_file = serde_json::to_value(SourceLocation::point(1, 1)).unwrap();
+        assert!(without_file.get("file").is_none());
+    }
+
     #[test]
     fn test_type_attributes_serialization() {
         let attrs = TypeAttributes::with_source(SourceLocation::new(1, 1, 2, 5));
@@ -0,0 +1,190 @@
+//! Canonical JSON form of V4 IR files.
+//!
+//! The same distribution can be written in many ways: modules, types, and
+//! values keep whatever order the frontend produced them in, and the JSON
+//! held in attributes keeps the key order of the tool that wrote it. That
+//! makes generated IR noisy to diff and unstable from one run to the next.
+//!
+//! [`canonicalize`] puts the keyed collections of an [`IRFile`] (modules,
+//! types, values, dependencies, entry points, and node IDs) in key order.
+//! [`to_canonical_string`] then serializes it, with `attrs` placed last in
+//! each node and the keys of the JSON they hold sorted, in one of two
+//! [`CanonicalStyle`]s:
+//!
+//! - [`CanonicalStyle::Expanded`] is indented, one entry per line, and keeps
+//!   empty attributes.
+//! - [`CanonicalStyle::Compact`] is a single line and leaves out empty
+//!   attributes, which the reader restores as defaults.
+//!
+//! The IR is first passed through the JSON reader, so it comes out as it
+//! would after a round trip. Writing a canonical file that was read back
+//! gives the same bytes.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let content = to_canonical_string(&ir_file, CanonicalStyle::Expanded)?;
+//! std::fs::write("morphir-ir.json", content)?;
+//! ```
+
+use indexmap::IndexMap;
+
+use super::IRFile;
+use super::distribution::Distribution;
+use super::module::{ModuleDefinition, ModuleSpecification};
+use super::node_id::NodeIds;
+use super::package::{PackageDefinition, PackageSpecification};
+use super::serde_v4::omitting_empty_attributes;
+
+/// Layout of canonical JSON
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CanonicalStyle {
+    /// Indented, keeping empty attributes
+    #[default]
+    Expanded,
+    /// Single line, leaving out empty attributes
+    Compact,
+}
+
+/// Put the keyed collections of `ir_file` in key order
+pub fn canonicalize(ir_file: &mut IRFile) {
+    match &mut ir_file.distribution {
+        Distribution::Library(lib) => {
+            sort_dependencies(&mut lib.dependencies);
+            sort_definition(&mut lib.def);
+        }
+        Distribution::Specs(specs) => {
+            sort_dependencies(&mut specs.dependencies);
+            sort_specification(&mut specs.spec);
+        }
+        Distribution::Application(app) => {
+            sort_dependencies(&mut app.dependencies);
+            sort_definition(&mut app.def);
+            app.entry_points.sort_keys();
+        }
+    }
+}
+
+/// Canonical JSON of `ir_file` in `style`, ending with a newline
+pub fn to_canonical_string(ir_file: &IRFile, style: CanonicalStyle) -> serde_json::Result<String> {
+    let mut ir_file: IRFile = serde_json::from_value(serde_json::to_value(ir_file)?)?;
+    canonicalize(&mut ir_file);
+    // Nodes serialize `attrs` last, and attribute JSON objects keep their
+    // keys sorted
+    let content = match style {
+        CanonicalStyle::Expanded => serde_json::to_string_pretty(&ir_file)?,
+        CanonicalStyle::Compact => omitting_empty_attributes(|| serde_json::to_string(&ir_file))?,
+    };
+    Ok(content + "\n")
+}
+
+/// Whether `content` is already the canonical JSON of `ir_file` in `style`
+pub fn is_canonical(content: &str, ir_file: &IRFile, style: CanonicalStyle) -> bool {
+    to_canonical_string(ir_file, style).is_ok_and(|canonical| canonical == content)
+}
+
+fn sort_dependencies(dependencies: &mut IndexMap<String, PackageSpecification>) {
+    dependencies.sort_keys();
+    dependencies.values_mut().for_each(sort_specification);
+}
+
+fn sort_definition(def: &mut PackageDefinition) {
+    def.modules.sort_keys();
+    for module in def.modules.values_mut() {
+        sort_module_definition(&mut module.value);
+    }
+}
+
+fn sort_specification(spec: &mut PackageSpecification) {
+    spec.modules.sort_keys();
+    spec.modules
+        .values_mut()
+        .for_each(sort_module_specification);
+}
+
+fn sort_module_definition(module: &mut ModuleDefinition) {
+    module.types.sort_keys();
+    module.values.sort_keys();
+    sort_node_ids(&mut module.ids);
+}
+
+fn sort_module_specification(module: &mut ModuleSpecification) {
+    module.types.sort_keys();
+    module.values.sort_keys();
+}
+
+fn sort_node_ids(ids: &mut NodeIds) {
+    ids.types.sort_keys();
+    ids.values.sort_keys();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::v4::FormatVersion;
+    use crate::ir::v4::text::parse_distribution;
+
+    const SHOP: &str = r#"library acme/shop
+
+
+module pricing
+
+discount : morphir/sdk:basics#Int =
+    10
+
+
+module orders
+
+type alias Line =
+    { quantity : morphir/sdk:basics#Int
+    , price : morphir/sdk:basics#Int
+    }
+
+total (line : acme/shop:orders#Line) : morphir/sdk:basics#Int =
+    line.price
+
+count : morphir/sdk:basics#Int =
+    1
+"#;
+
+    fn shop() -> IRFile {
+        IRFile {
+            format_version: FormatVersion::default(),
+            distribution: parse_distribution(SHOP).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_keys_are_sorted() {
+        let mut ir_file = shop();
+        canonicalize(&mut ir_file);
+        let def = ir_file.distribution.definition().unwrap();
+        assert_eq!(
+            def.modules.keys().collect::<Vec<_>>(),
+            ["orders", "pricing"]
+        );
+        let orders = &def.modules["orders"].value;
+        assert_eq!(orders.values.keys().collect::<Vec<_>>(), ["count", "total"]);
+    }
+
+    #[test]
+    fn test_canonical_output_is_stable_and_reads_back() {
+        let ir_file = shop();
+        for style in [CanonicalStyle::Expanded, CanonicalStyle::Compact] {
+            let content = to_canonical_string(&ir_file, style).unwrap();
+            let read: IRFile = serde_json::from_str(&content).unwrap();
+            assert!(is_canonical(&content, &read, style));
+            assert_eq!(to_canonical_string(&read, style).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_compact_style_drops_empty_attributes() {
+        let ir_file = shop();
+        let expanded = to_canonical_string(&ir_file, CanonicalStyle::Expanded).unwrap();
+        let compact = to_canonical_string(&ir_file, CanonicalStyle::Compact).unwrap();
+        assert!(expanded.contains("\"attrs\": {}"));
+        assert!(!compact.contains("\"attrs\""));
+        assert_eq!(compact.lines().count(), 1);
+    }
+}
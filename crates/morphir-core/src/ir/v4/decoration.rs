@@ -0,0 +1,351 @@
+//! Decorations: data that tools attach to the nodes of a distribution
+//!
+//! A decoration is a named kind of annotation, such as `lineage` or
+//! `owner`, holding a JSON value for each node it annotates. Decorations are
+//! not part of the IR: they live in a [`Decorations`] side table, stored in a
+//! file next to the IR, so tools can annotate a model without rewriting it
+//! and without every reader of the IR having to know about them.
+//!
+//! A node is named either by its stable [`NodeId`], which survives renames,
+//! or by its path: `package:module` for a module and the canonical FQName,
+//! `package:module#name`, for a type or value. Paths are kept in canonical
+//! form, so `acme/shop:orders#orderTotal` and `acme/shop:orders#order-total`
+//! name the same value.
+//!
+//! ```json
+//! {
+//!   "lineage": {
+//!     "nodes": { "9c3e…": { "source": "ledger" } },
+//!     "values": { "acme/shop:orders#total": { "source": "orders-db" } }
+//!   }
+//! }
+//! ```
+
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value as Json;
+
+use super::node_id::{NodeId, NodeKind, NodeLocation};
+use super::package::PackageDefinition;
+use crate::naming::{FQName, Name, PackageName, Path};
+
+/// Node a decoration is attached to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DecorationTarget {
+    /// Node with a stable identifier
+    Node(NodeId),
+    /// Module `module` of package `package`
+    Module(Path, Path),
+    /// Type definition
+    Type(FQName),
+    /// Value definition
+    Value(FQName),
+}
+
+impl DecorationTarget {
+    /// Target at `location` in package `package`, by path
+    pub fn at(package: &PackageName, location: &NodeLocation) -> Self {
+        let module = Path::new(&location.module);
+        let member = |name: &String| {
+            FQName::new(
+                package.as_path().clone(),
+                module.clone(),
+                Name::from(name.as_str()),
+            )
+        };
+        match (location.kind, &location.name) {
+            (NodeKind::Type, Some(name)) => DecorationTarget::Type(member(name)),
+            (NodeKind::Value, Some(name)) => DecorationTarget::Value(member(name)),
+            _ => DecorationTarget::Module(package.as_path().clone(), module.clone()),
+        }
+    }
+}
+
+/// Entries of one decoration, keyed by node identifier or path
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DecorationTable {
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub nodes: IndexMap<NodeId, Json>,
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "module_paths"
+    )]
+    pub modules: IndexMap<String, Json>,
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "member_paths"
+    )]
+    pub types: IndexMap<String, Json>,
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "member_paths"
+    )]
+    pub values: IndexMap<String, Json>,
+}
+
+impl DecorationTable {
+    /// Whether the decoration annotates no node
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+            && self.modules.is_empty()
+            && self.types.is_empty()
+            && self.values.is_empty()
+    }
+
+    fn get(&self, target: &DecorationTarget) -> Option<&Json> {
+        match target {
+            DecorationTarget::Node(id) => self.nodes.get(id),
+            DecorationTarget::Module(..) => self.modules.get(&key(target)),
+            DecorationTarget::Type(_) => self.types.get(&key(target)),
+            DecorationTarget::Value(_) => self.values.get(&key(target)),
+        }
+    }
+}
+
+/// Key of a path target
+fn key(target: &DecorationTarget) -> String {
+    match target {
+        DecorationTarget::Node(id) => id.to_string(),
+        DecorationTarget::Module(package, module) => format!("{}:{}", package, module),
+        DecorationTarget::Type(fqname) | DecorationTarget::Value(fqname) => {
+            fqname.to_canonical_string()
+        }
+    }
+}
+
+/// Module paths read from a file, in canonical form
+fn module_paths<'de, D>(deserializer: D) -> Result<IndexMap<String, Json>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let entries = IndexMap::<String, Json>::deserialize(deserializer)?;
+    entries
+        .into_iter()
+        .map(|(path, value)| match path.split_once(':') {
+            Some((package, module)) => Ok((
+                key(&DecorationTarget::Module(
+                    Path::new(package),
+                    Path::new(module),
+                )),
+                value,
+            )),
+            None => Err(serde::de::Error::custom(format!(
+                "invalid module path: {}",
+                path
+            ))),
+        })
+        .collect()
+}
+
+/// Type and value paths read from a file, in canonical form
+pub(super) fn member_paths<'de, D, V>(deserializer: D) -> Result<IndexMap<String, V>, D::Error>
+where
+    D: Deserializer<'de>,
+    V: Deserialize<'de>,
+{
+    let entries = IndexMap::<String, V>::deserialize(deserializer)?;
+    entries
+        .into_iter()
+        .map(|(path, value)| {
+            FQName::from_canonical_string(&path)
+                .map(|fqname| (fqname.to_canonical_string(), value))
+                .map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+/// Decorations of a distribution, keyed by decoration name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Decorations(pub IndexMap<String, DecorationTable>);
+
+impl Decorations {
+    /// Whether no node is decorated
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(DecorationTable::is_empty)
+    }
+
+    /// Names of the decorations
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Entries of decoration `decoration`
+    pub fn table(&self, decoration: &str) -> Option<&DecorationTable> {
+        self.0.get(decoration)
+    }
+
+    /// Attach `value` as decoration `decoration` of `target`, returning the
+    /// value it replaces
+    pub fn attach(
+        &mut self,
+        decoration: &str,
+        target: &DecorationTarget,
+        value: Json,
+    ) -> Option<Json> {
+        let table = self.0.entry(decoration.to_string()).or_default();
+        match target {
+            DecorationTarget::Node(id) => table.nodes.insert(id.clone(), value),
+            DecorationTarget::Module(..) => table.modules.insert(key(target), value),
+            DecorationTarget::Type(_) => table.types.insert(key(target), value),
+            DecorationTarget::Value(_) => table.values.insert(key(target), value),
+        }
+    }
+
+    /// Remove decoration `decoration` from `target`, returning its value
+    pub fn detach(&mut self, decoration: &str, target: &DecorationTarget) -> Option<Json> {
+        let table = self.0.get_mut(decoration)?;
+        let removed = match target {
+            DecorationTarget::Node(id) => table.nodes.shift_remove(id),
+            DecorationTarget::Module(..) => table.modules.shift_remove(&key(target)),
+            DecorationTarget::Type(_) => table.types.shift_remove(&key(target)),
+            DecorationTarget::Value(_) => table.values.shift_remove(&key(target)),
+        };
+        if table.is_empty() {
+            self.0.shift_remove(decoration);
+        }
+        removed
+    }
+
+    /// Value of decoration `decoration` on `target`
+    pub fn get(&self, decoration: &str, target: &DecorationTarget) -> Option<&Json> {
+        self.0.get(decoration)?.get(target)
+    }
+
+    /// Every decoration on `target`, by name
+    pub fn on(&self, target: &DecorationTarget) -> Vec<(&str, &Json)> {
+        self.0
+            .iter()
+            .filter_map(|(name, table)| Some((name.as_str(), table.get(target)?)))
+            .collect()
+    }
+
+    /// Every decoration on the node at `location` of package `package`,
+    /// whether attached to its identifier or to its path. Where both are,
+    /// the one attached to the identifier wins.
+    pub fn of_node(
+        &self,
+        package: &PackageName,
+        definition: &PackageDefinition,
+        location: &NodeLocation,
+    ) -> Vec<(&str, &Json)> {
+        let id = definition
+            .modules
+            .get(&location.module)
+            .and_then(|module| {
+                let ids = &module.value.ids;
+                match (location.kind, &location.name) {
+                    (NodeKind::Module, _) => ids.module.as_ref(),
+                    (NodeKind::Type, Some(name)) => ids.types.get(name),
+                    (NodeKind::Value, Some(name)) => ids.values.get(name),
+                    _ => None,
+                }
+            })
+            .map(|id| DecorationTarget::Node(id.clone()));
+        let path = DecorationTarget::at(package, location);
+        self.0
+            .iter()
+            .filter_map(|(name, table)| {
+                let value = id
+                    .as_ref()
+                    .and_then(|id| table.get(id))
+                    .or_else(|| table.get(&path))?;
+                Some((name.as_str(), value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::v4::text::parse_distribution;
+    use crate::ir::v4::{Distribution, IdStrategy};
+    use serde_json::json;
+
+    fn library() -> (PackageName, PackageDefinition) {
+        let distribution = parse_distribution(
+            "library acme/shop\n\nmodule orders\n\ntype Order\n    = Open\n\norder : acme/shop:orders#Order =\n    acme/shop:orders#Open\n",
+        )
+        .unwrap();
+        let Distribution::Library(lib) = distribution else {
+            panic!("expected a library");
+        };
+        (lib.package_name, lib.def)
+    }
+
+    #[test]
+    fn test_attach_and_query() {
+        let (package, mut definition) = library();
+        definition.assign_node_ids(&package.to_string(), IdStrategy::Derived);
+        let order = NodeLocation {
+            kind: NodeKind::Value,
+            module: "orders".to_string(),
+            name: Some("order".to_string()),
+        };
+        let id = definition.modules["orders"].value.ids.values["order"].clone();
+
+        let mut decorations = Decorations::default();
+        let by_path = DecorationTarget::at(&package, &order);
+        decorations.attach("owner", &by_path, json!("sales"));
+        decorations.attach("lineage", &by_path, json!({ "source": "csv" }));
+        decorations.attach(
+            "lineage",
+            &DecorationTarget::Node(id),
+            json!({ "source": "db" }),
+        );
+
+        // Types and values of the same name are decorated apart
+        let order_type = DecorationTarget::Type(
+            FQName::from_canonical_string("acme/shop:orders#Order").unwrap(),
+        );
+        assert!(decorations.on(&order_type).is_empty());
+        assert_eq!(
+            decorations.of_node(&package, &definition, &order),
+            [
+                ("owner", &json!("sales")),
+                ("lineage", &json!({ "source": "db" }))
+            ]
+        );
+
+        assert_eq!(decorations.detach("owner", &by_path), Some(json!("sales")));
+        assert_eq!(decorations.names().collect::<Vec<_>>(), ["lineage"]);
+    }
+
+    #[test]
+    fn test_paths_are_canonical_in_files() {
+        let json = json!({
+            "owner": {
+                "modules": { "acme/shop:Orders": "sales" },
+                "values": { "acme/shop:orders#orderTotal": "finance" }
+            }
+        });
+        let decorations: Decorations = serde_json::from_value(json).unwrap();
+        let total = DecorationTarget::Value(
+            FQName::from_canonical_string("acme/shop:orders#order-total").unwrap(),
+        );
+        assert_eq!(decorations.get("owner", &total), Some(&json!("finance")));
+        let orders = DecorationTarget::Module(Path::new("acme/shop"), Path::new("orders"));
+        assert_eq!(decorations.get("owner", &orders), Some(&json!("sales")));
+
+        let written = serde_json::to_value(&decorations).unwrap();
+        assert_eq!(
+            written,
+            json!({
+                "owner": {
+                    "modules": { "acme/shop:orders": "sales" },
+                    "values": { "acme/shop:orders#order-total": "finance" }
+                }
+            })
+        );
+        assert!(
+            serde_json::from_value::<Decorations>(json!({ "owner": { "values": { "nope": 1 } } }))
+                .is_err()
+        );
+    }
+}
@@ -4,13 +4,14 @@
 //! (LibraryContent, SpecsContent, ApplicationContent).
 
 use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::de::{self, Deserializer, MapAccess, Visitor};
 use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
 use super::package::{PackageDefinition, PackageSpecification};
-use crate::naming::PackageName;
+use crate::naming::{FQName, PackageName};
 
 /// Distribution enum - serializes as wrapper object format
 /// E.g., `{ "Library": { ... } }` or `{ "Specs": { ... } }`
@@ -21,6 +22,78 @@ pub enum Distribution {
     Application(ApplicationContent),
 }
 
+impl Distribution {
+    /// Name of the package this distribution describes
+    pub fn package_name(&self) -> &PackageName {
+        match self {
+            Distribution::Library(lib) => &lib.package_name,
+            Distribution::Specs(specs) => &specs.package_name,
+            Distribution::Application(app) => &app.package_name,
+        }
+    }
+
+    /// Specifications of the packages this distribution depends on
+    pub fn dependencies(&self) -> &Dependencies {
+        match self {
+            Distribution::Library(lib) => &lib.dependencies,
+            Distribution::Specs(specs) => &specs.dependencies,
+            Distribution::Application(app) => &app.dependencies,
+        }
+    }
+
+    /// Package definition, if the distribution carries one (Specs do not)
+    pub fn definition(&self) -> Option<&PackageDefinition> {
+        match self {
+            Distribution::Library(lib) => Some(&lib.def),
+            Distribution::Application(app) => Some(&app.def),
+            Distribution::Specs(_) => None,
+        }
+    }
+
+    /// Entry points of an Application distribution, if any
+    pub fn entry_points(&self) -> Option<&EntryPoints> {
+        match self {
+            Distribution::Application(app) => Some(&app.entry_points),
+            _ => None,
+        }
+    }
+
+    /// Specification of this package, as dependents compile against it.
+    ///
+    /// Specs distributions carry it directly; for libraries and applications
+    /// it is derived from the public part of the definition.
+    pub fn specification(&self) -> PackageSpecification {
+        match self {
+            Distribution::Specs(specs) => specs.spec.clone(),
+            Distribution::Library(lib) => lib.def.to_specification(),
+            Distribution::Application(app) => app.def.to_specification(),
+        }
+    }
+
+    /// Record `dependency` as a dependency of this distribution, keyed by its
+    /// package name. Only its specification is kept.
+    pub fn add_dependency(&mut self, dependency: &Distribution) {
+        let dependencies = match self {
+            Distribution::Library(lib) => &mut lib.dependencies,
+            Distribution::Specs(specs) => &mut specs.dependencies,
+            Distribution::Application(app) => &mut app.dependencies,
+        };
+        dependencies.insert(
+            dependency.package_name().to_string(),
+            dependency.specification(),
+        );
+    }
+
+    /// Kind name as used in the JSON wrapper: `Library`, `Specs`, or `Application`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Distribution::Library(_) => "Library",
+            Distribution::Specs(_) => "Specs",
+            Distribution::Application(_) => "Application",
+        }
+    }
+}
+
 impl Serialize for Distribution {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -64,24 +137,21 @@ impl<'de> Visitor<'de> for DistributionVisitor {
     where
         M: MapAccess<'de>,
     {
-        let (key, value): (String, serde_json::Value) = map
-            .next_entry()?
+        let key: String = map
+            .next_key()?
             .ok_or_else(|| de::Error::custom("expected distribution wrapper object"))?;
 
         match key.as_str() {
             "Library" => {
-                let content: LibraryContent =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: LibraryContent = map.next_value()?;
                 Ok(Distribution::Library(content))
             }
             "Specs" => {
-                let content: SpecsContent =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: SpecsContent = map.next_value()?;
                 Ok(Distribution::Specs(content))
             }
             "Application" => {
-                let content: ApplicationContent =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: ApplicationContent = map.next_value()?;
                 Ok(Distribution::Application(content))
             }
             _ => Err(de::Error::unknown_variant(
@@ -93,7 +163,7 @@ impl<'de> Visitor<'de> for DistributionVisitor {
 }
 
 /// Library distribution content
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LibraryContent {
     pub package_name: PackageName,
@@ -102,7 +172,7 @@ pub struct LibraryContent {
 }
 
 /// Specs distribution content (public interfaces only)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SpecsContent {
     pub package_name: PackageName,
@@ -111,7 +181,7 @@ pub struct SpecsContent {
 }
 
 /// Application distribution content
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ApplicationContent {
     pub package_name: PackageName,
@@ -127,7 +197,7 @@ pub type Dependencies = IndexMap<String, PackageSpecification>;
 pub type EntryPoints = IndexMap<String, EntryPoint>;
 
 /// Entry point definition
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EntryPoint {
     pub target: String, // FQName as canonical string
@@ -136,8 +206,15 @@ pub struct EntryPoint {
     pub doc: Option<String>,
 }
 
+impl EntryPoint {
+    /// Parse the target into an FQName
+    pub fn fqname(&self) -> Result<FQName, String> {
+        FQName::from_canonical_string(&self.target)
+    }
+}
+
 /// Entry point kind
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum EntryPointKind {
     Main,
@@ -148,6 +225,7 @@ pub enum EntryPointKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::v4::TypeSpecification;
     use crate::naming::Path;
 
     #[test]
@@ -163,4 +241,73 @@ mod tests {
         assert!(json.contains("\"Library\""));
         assert!(json.contains("packageName"));
     }
+
+    #[test]
+    fn test_distribution_application_accessors() {
+        let json = r#"{"Application": {
+            "packageName": "my/app",
+            "dependencies": {},
+            "def": {"modules": {}},
+            "entryPoints": {"main": {"target": "my/app:main#run", "kind": "main"}}
+        }}"#;
+        let dist: Distribution = serde_json::from_str(json).unwrap();
+        assert_eq!(dist.kind(), "Application");
+        assert_eq!(dist.package_name().to_string(), "my/app");
+        assert!(dist.definition().is_some());
+        let entry_points = dist.entry_points().unwrap();
+        let fqname = entry_points["main"].fqname().unwrap();
+        assert_eq!(fqname.to_canonical_string(), "my/app:main#run");
+    }
+
+    #[test]
+    fn test_add_dependency_keeps_public_specification() {
+        let dependency: Distribution = serde_json::from_str(
+            r#"{"Library": {
+                "packageName": "acme/money",
+                "dependencies": {},
+                "def": {"modules": {
+                    "currency": {"access": "Public", "value": {
+                        "types": {
+                            "code": {"access": "Public", "value": {"CustomTypeDefinition": {
+                                "typeParams": [],
+                                "constructors": {"access": "Private", "value": [{"name": "usd", "args": []}]}
+                            }}}
+                        },
+                        "values": {
+                            "rate": {"access": "Public", "value": {
+                                "inputTypes": {"code": {"type": "acme/money:currency#code"}},
+                                "outputType": "morphir/sdk:basics#float",
+                                "body": {"ExpressionBody": {"body": {"Literal": {"attributes": {}, "literal": {"FloatLiteral": {"value": 1.0}}}}}}
+                            }},
+                            "secret": {"access": "Private", "value": {
+                                "inputTypes": {},
+                                "outputType": "morphir/sdk:basics#float",
+                                "body": {"ExpressionBody": {"body": {"Literal": {"attributes": {}, "literal": {"FloatLiteral": {"value": 2.0}}}}}}
+                            }}
+                        }
+                    }},
+                    "internal": {"access": "Private", "value": {"types": {}, "values": {}}}
+                }}
+            }}"#,
+        )
+        .unwrap();
+        let mut dist = Distribution::Library(LibraryContent {
+            package_name: PackageName::new(Path::new("my/pkg")),
+            dependencies: IndexMap::new(),
+            def: PackageDefinition {
+                modules: IndexMap::new(),
+            },
+        });
+
+        dist.add_dependency(&dependency);
+
+        let spec = &dist.dependencies()["acme/money"];
+        assert_eq!(spec.modules.keys().collect::<Vec<_>>(), vec!["currency"]);
+        let currency = &spec.modules["currency"];
+        assert_eq!(currency.values.keys().collect::<Vec<_>>(), vec!["rate"]);
+        assert!(matches!(
+            currency.types["code"],
+            TypeSpecification::OpaqueTypeSpecification { .. }
+        ));
+    }
 }
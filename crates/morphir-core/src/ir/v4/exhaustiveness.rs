@@ -0,0 +1,610 @@
+//! Exhaustiveness and redundancy checking for pattern matches.
+//!
+//! Every `case` expression of a package is checked against the constructors
+//! of the types it matches on. A match that leaves values unhandled is an
+//! error listing the patterns it misses, such as `Just (Err _)`, and a case
+//! that the cases before it already cover is reported as redundant. Both use
+//! the usefulness algorithm of Maranget, "Warnings for pattern matching"
+//! (2007).
+//!
+//! Constructors are looked up in the package, the specifications of its
+//! dependencies, and the SDK's `Maybe` and `Result`. The constructors of a
+//! type the distribution does not describe are assumed to be the ones the
+//! match uses, so such matches are never reported as non-exhaustive. Lists
+//! are covered by `[]` and `_ :: _`, booleans by both literals; other
+//! literals always need a catch-all.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! for d in check_distribution(&ir_file.distribution) {
+//!     eprintln!("{}: {}", d.definition, d.message);
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use super::attributes::SourceLocation;
+use super::distribution::Distribution;
+use super::literal::Literal;
+use super::optimize::{children, expression};
+use super::pattern::Pattern;
+use super::typecheck::{Diagnostic, Severity};
+use super::types::{TypeDefinition, TypeSpecification};
+use super::value::{Value, ValueDefinition};
+use crate::naming::{FQName, Name, Path};
+
+/// Missing patterns listed in a diagnostic before the rest are elided
+const MAX_MISSING: usize = 5;
+
+/// Constructors and arities of the SDK types matched on most often, whose
+/// specification distributions rarely carry
+const SDK_TYPES: &[&[(&str, usize)]] = &[
+    &[
+        ("morphir/sdk:maybe#just", 1),
+        ("morphir/sdk:maybe#nothing", 0),
+    ],
+    &[("morphir/sdk:result#ok", 1), ("morphir/sdk:result#err", 1)],
+];
+
+/// Check every pattern match of a distribution.
+///
+/// Specs distributions carry no bodies, so they never produce diagnostics.
+pub fn check_distribution(dist: &Distribution) -> Vec<Diagnostic> {
+    let Some(def) = dist.definition() else {
+        return Vec::new();
+    };
+    let checker = ExhaustivenessChecker::new(dist);
+    let package = &dist.package_name().0;
+    let mut diagnostics = Vec::new();
+    for (module_key, module) in &def.modules {
+        for (value_key, value_def) in &module.value.values {
+            let fqname = FQName::new(
+                package.clone(),
+                Path::new(module_key),
+                Name::from(value_key.as_str()),
+            );
+            diagnostics.extend(checker.check_value_definition(&fqname, &value_def.value));
+        }
+    }
+    diagnostics
+}
+
+/// Checks pattern matches against the constructors known to a distribution.
+pub struct ExhaustivenessChecker {
+    /// The constructors of each type, with their arities
+    types: Vec<Vec<(FQName, usize)>>,
+    /// Index into `types` of the type of each constructor
+    constructors: HashMap<FQName, usize>,
+}
+
+impl ExhaustivenessChecker {
+    /// Collect the constructors of the package, its dependencies, and the SDK.
+    pub fn new(dist: &Distribution) -> Self {
+        let mut checker = Self {
+            types: Vec::new(),
+            constructors: HashMap::new(),
+        };
+        for constructors in SDK_TYPES {
+            checker.add_type(
+                constructors
+                    .iter()
+                    .filter_map(|(name, arity)| {
+                        FQName::from_canonical_string(name)
+                            .ok()
+                            .map(|name| (name, *arity))
+                    })
+                    .collect(),
+            );
+        }
+        for (dependency_key, spec) in dist.dependencies() {
+            let package = Path::new(dependency_key);
+            for (module_key, module) in &spec.modules {
+                for type_spec in module.types.values() {
+                    if let TypeSpecification::CustomTypeSpecification { constructors, .. } =
+                        type_spec
+                    {
+                        checker.add_type(
+                            constructors
+                                .iter()
+                                .map(|c| (member(&package, module_key, &c.name), c.args.len()))
+                                .collect(),
+                        );
+                    }
+                }
+            }
+        }
+        let package = &dist.package_name().0;
+        for (module_key, module) in dist.definition().iter().flat_map(|d| &d.modules) {
+            for type_def in module.value.types.values() {
+                if let TypeDefinition::CustomTypeDefinition { constructors, .. } = &type_def.value {
+                    checker.add_type(
+                        constructors
+                            .value
+                            .iter()
+                            .map(|c| (member(package, module_key, &c.name), c.args.len()))
+                            .collect(),
+                    );
+                }
+            }
+        }
+        checker
+    }
+
+    fn add_type(&mut self, constructors: Vec<(FQName, usize)>) {
+        for (name, _) in &constructors {
+            self.constructors.insert(name.clone(), self.types.len());
+        }
+        self.types.push(constructors);
+    }
+
+    /// The constructors of the type `name` is a constructor of
+    fn type_of(&self, name: &FQName) -> Option<&[(FQName, usize)]> {
+        self.constructors
+            .get(name)
+            .map(|&index| self.types[index].as_slice())
+    }
+
+    /// Check the pattern matches in the body of one value definition.
+    pub fn check_value_definition(
+        &self,
+        fqname: &FQName,
+        def: &ValueDefinition,
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if let Some(body) = expression(def) {
+            self.check_value(fqname, body, None, &mut diagnostics);
+        }
+        diagnostics
+    }
+
+    fn check_value(
+        &self,
+        fqname: &FQName,
+        value: &Value,
+        enclosing: Option<&SourceLocation>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let location = value.attributes().source.as_ref().or(enclosing);
+        if let Value::PatternMatch(_, _, cases) = value {
+            let diagnostic =
+                |severity, code: &str, message, location: Option<&SourceLocation>| Diagnostic {
+                    severity,
+                    code: code.to_string(),
+                    message,
+                    definition: fqname.clone(),
+                    location: location.cloned(),
+                };
+            let mut rows: Vec<Vec<Pat>> = Vec::new();
+            for (index, case) in cases.iter().enumerate() {
+                let row = vec![self.simplify(&case.0)];
+                if !self.useful(&rows, &row) {
+                    diagnostics.push(diagnostic(
+                        Severity::Warning,
+                        "redundant-case",
+                        format!(
+                            "Case {} is redundant: the cases before it match every value it does",
+                            index + 1
+                        ),
+                        case.0.attributes().source.as_ref().or(location),
+                    ));
+                }
+                rows.push(row);
+            }
+            let missing = self.missing(&rows, 1);
+            if !missing.is_empty() {
+                let mut patterns: Vec<String> = missing
+                    .iter()
+                    .take(MAX_MISSING)
+                    .map(|witness| format!("`{}`", render(&witness[0], false)))
+                    .collect();
+                if missing.len() > MAX_MISSING {
+                    patterns.push("…".to_string());
+                }
+                diagnostics.push(diagnostic(
+                    Severity::Error,
+                    "non-exhaustive-match",
+                    format!(
+                        "This pattern match does not cover every value; missing: {}",
+                        patterns.join(", ")
+                    ),
+                    location,
+                ));
+            }
+        }
+        for child in children(value) {
+            self.check_value(fqname, child, location, diagnostics);
+        }
+    }
+
+    /// The pattern as a constructor applied to subpatterns, or a wildcard
+    fn simplify(&self, pattern: &Pattern) -> Pat {
+        match pattern {
+            Pattern::WildcardPattern(_) => Pat::Wild,
+            Pattern::AsPattern(_, inner, _) => self.simplify(inner),
+            Pattern::TuplePattern(_, elements) => Pat::Ctor(
+                Ctor::Tuple(elements.len()),
+                elements.iter().map(|e| self.simplify(e)).collect(),
+            ),
+            Pattern::ConstructorPattern(_, name, args) => {
+                let arity = self
+                    .type_of(name)
+                    .and_then(|ctors| ctors.iter().find(|(c, _)| c == name))
+                    .map_or(args.len(), |(_, arity)| *arity);
+                // Tolerate a pattern with the wrong number of arguments
+                let mut args: Vec<Pat> = args.iter().map(|a| self.simplify(a)).collect();
+                args.resize(arity, Pat::Wild);
+                Pat::Ctor(Ctor::Named(name.clone(), arity), args)
+            }
+            Pattern::EmptyListPattern(_) => Pat::Ctor(Ctor::Nil, Vec::new()),
+            Pattern::HeadTailPattern(_, head, tail) => {
+                Pat::Ctor(Ctor::Cons, vec![self.simplify(head), self.simplify(tail)])
+            }
+            Pattern::LiteralPattern(_, literal) => {
+                Pat::Ctor(Ctor::Literal(literal.clone()), Vec::new())
+            }
+            Pattern::UnitPattern(_) => Pat::Ctor(Ctor::Unit, Vec::new()),
+        }
+    }
+
+    /// The constructors of the type of `heads`: `Ok` with all of them when
+    /// `heads` covers the type, otherwise `Err` with the ones missing, which
+    /// is empty when they cannot be listed (e.g. the integers not matched)
+    fn split(&self, heads: &[&Ctor]) -> Result<Vec<Ctor>, Vec<Ctor>> {
+        let Some(first) = heads.first() else {
+            return Err(Vec::new());
+        };
+        let all = match first {
+            Ctor::Named(name, _) => match self.type_of(name) {
+                Some(ctors) => ctors
+                    .iter()
+                    .map(|(name, arity)| Ctor::Named(name.clone(), *arity))
+                    .collect(),
+                None => {
+                    let mut all: Vec<Ctor> = Vec::new();
+                    for head in heads {
+                        if !all.contains(head) {
+                            all.push((*head).clone());
+                        }
+                    }
+                    all
+                }
+            },
+            Ctor::Tuple(_) | Ctor::Unit => vec![(*first).clone()],
+            Ctor::Nil | Ctor::Cons => vec![Ctor::Nil, Ctor::Cons],
+            Ctor::Literal(Literal::Bool(_)) => vec![
+                Ctor::Literal(Literal::Bool(true)),
+                Ctor::Literal(Literal::Bool(false)),
+            ],
+            Ctor::Literal(_) => return Err(Vec::new()),
+        };
+        let missing: Vec<Ctor> = all
+            .iter()
+            .filter(|ctor| !heads.contains(ctor))
+            .cloned()
+            .collect();
+        if missing.is_empty() {
+            Ok(all)
+        } else {
+            Err(missing)
+        }
+    }
+
+    /// Whether some value matched by `vector` is matched by no row
+    fn useful(&self, rows: &[Vec<Pat>], vector: &[Pat]) -> bool {
+        let Some((head, rest)) = vector.split_first() else {
+            return rows.is_empty();
+        };
+        match head {
+            Pat::Ctor(ctor, args) => {
+                self.useful(&specialize(rows, ctor), &[args.as_slice(), rest].concat())
+            }
+            Pat::Wild => match self.split(&heads(rows)) {
+                Ok(all) => all.iter().any(|ctor| {
+                    self.useful(
+                        &specialize(rows, ctor),
+                        &[wildcards(ctor.arity()), rest.to_vec()].concat(),
+                    )
+                }),
+                Err(_) => self.useful(&default(rows), rest),
+            },
+        }
+    }
+
+    /// Vectors of `width` patterns matching values no row matches, at most
+    /// one more than are reported
+    fn missing(&self, rows: &[Vec<Pat>], width: usize) -> Vec<Vec<Pat>> {
+        if width == 0 {
+            return if rows.is_empty() {
+                vec![Vec::new()]
+            } else {
+                Vec::new()
+            };
+        }
+        match self.split(&heads(rows)) {
+            Ok(all) => all
+                .iter()
+                .flat_map(|ctor| {
+                    let arity = ctor.arity();
+                    self.missing(&specialize(rows, ctor), arity + width - 1)
+                        .into_iter()
+                        .map(move |mut witness| {
+                            let rest = witness.split_off(arity);
+                            std::iter::once(Pat::Ctor(ctor.clone(), witness))
+                                .chain(rest)
+                                .collect()
+                        })
+                })
+                .take(MAX_MISSING + 1)
+                .collect(),
+            Err(missing) => {
+                let rests = self.missing(&default(rows), width - 1);
+                let heads: Vec<Pat> = if missing.is_empty() {
+                    vec![Pat::Wild]
+                } else {
+                    missing
+                        .into_iter()
+                        .map(|ctor| {
+                            let args = wildcards(ctor.arity());
+                            Pat::Ctor(ctor, args)
+                        })
+                        .collect()
+                };
+                heads
+                    .iter()
+                    .flat_map(|head| {
+                        rests.iter().map(move |rest| {
+                            std::iter::once(head.clone())
+                                .chain(rest.iter().cloned())
+                                .collect()
+                        })
+                    })
+                    .take(MAX_MISSING + 1)
+                    .collect()
+            }
+        }
+    }
+}
+
+fn member(package: &Path, module_key: &str, name: &Name) -> FQName {
+    FQName::new(package.clone(), Path::new(module_key), name.clone())
+}
+
+/// A pattern reduced to what matters for coverage
+#[derive(Debug, Clone, PartialEq)]
+enum Pat {
+    Wild,
+    Ctor(Ctor, Vec<Pat>),
+}
+
+/// Head of a pattern other than a wildcard
+#[derive(Debug, Clone, PartialEq)]
+enum Ctor {
+    /// A constructor of a custom type, with its arity
+    Named(FQName, usize),
+    Tuple(usize),
+    Unit,
+    Nil,
+    Cons,
+    Literal(Literal),
+}
+
+impl Ctor {
+    fn arity(&self) -> usize {
+        match self {
+            Ctor::Named(_, arity) | Ctor::Tuple(arity) => *arity,
+            Ctor::Cons => 2,
+            Ctor::Unit | Ctor::Nil | Ctor::Literal(_) => 0,
+        }
+    }
+}
+
+fn wildcards(count: usize) -> Vec<Pat> {
+    vec![Pat::Wild; count]
+}
+
+fn heads(rows: &[Vec<Pat>]) -> Vec<&Ctor> {
+    let mut heads: Vec<&Ctor> = Vec::new();
+    for row in rows {
+        if let Some(Pat::Ctor(ctor, _)) = row.first()
+            && !heads.contains(&ctor)
+        {
+            heads.push(ctor);
+        }
+    }
+    heads
+}
+
+/// The rows that match `ctor` first, with its arguments in its place
+fn specialize(rows: &[Vec<Pat>], ctor: &Ctor) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let (head, rest) = row.split_first()?;
+            match head {
+                Pat::Ctor(head, args) if head == ctor => Some([args.as_slice(), rest].concat()),
+                Pat::Ctor(..) => None,
+                Pat::Wild => Some([wildcards(ctor.arity()), rest.to_vec()].concat()),
+            }
+        })
+        .collect()
+}
+
+/// The rows that match anything first, without it
+fn default(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| match row.split_first()? {
+            (Pat::Wild, rest) => Some(rest.to_vec()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A pattern as written in Elm, parenthesized when it is an argument
+fn render(pattern: &Pat, argument: bool) -> String {
+    let Pat::Ctor(ctor, args) = pattern else {
+        return "_".to_string();
+    };
+    let rendered = match ctor {
+        Ctor::Named(name, _) => std::iter::once(name.local_name.to_title_case())
+            .chain(args.iter().map(|arg| render(arg, true)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Ctor::Tuple(_) => {
+            let elements: Vec<String> = args.iter().map(|arg| render(arg, false)).collect();
+            return format!("( {} )", elements.join(", "));
+        }
+        Ctor::Unit => return "()".to_string(),
+        Ctor::Nil => return "[]".to_string(),
+        Ctor::Cons => format!("{} :: {}", render(&args[0], true), render(&args[1], false)),
+        Ctor::Literal(literal) => return render_literal(literal),
+    };
+    if argument && !args.is_empty() {
+        format!("({})", rendered)
+    } else {
+        rendered
+    }
+}
+
+fn render_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Bool(true) => "True".to_string(),
+        Literal::Bool(false) => "False".to_string(),
+        Literal::Char(c) => format!("'{}'", c),
+        Literal::String(s) => format!("{:?}", s),
+        Literal::Integer(n) => n.to_string(),
+        Literal::Float(f) => f.to_string(),
+        Literal::Decimal(d) => d.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::{
+        attrs, fq, int as int_type, library, orders, public, status, variable,
+    };
+    use super::super::value::PatternCase;
+    use super::*;
+
+    fn wild() -> Pattern {
+        Pattern::WildcardPattern(attrs())
+    }
+
+    fn ctor(name: &str, args: Vec<Pattern>) -> Pattern {
+        Pattern::ConstructorPattern(attrs(), fq(name), args)
+    }
+
+    fn int(n: i64) -> Pattern {
+        Pattern::LiteralPattern(attrs(), Literal::Integer(n))
+    }
+
+    fn bool(b: bool) -> Pattern {
+        Pattern::LiteralPattern(attrs(), Literal::Bool(b))
+    }
+
+    /// `check x = case x of ...` with the given cases, in a library that
+    /// also defines `status = Active | Held Int`
+    fn check(cases: Vec<Pattern>) -> Vec<Diagnostic> {
+        let body = Value::PatternMatch(
+            attrs(),
+            Box::new(variable("x")),
+            cases
+                .into_iter()
+                .map(|pattern| PatternCase(pattern, Value::Unit(attrs())))
+                .collect(),
+        );
+        let mut dist = library(vec![(
+            "check",
+            ValueDefinition::new(vec![], int_type(), body),
+        )]);
+        orders(&mut dist)
+            .types
+            .insert("status".to_string(), public(status()));
+        check_distribution(&dist)
+    }
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<(&str, &str)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.code.as_str(), d.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_missing_constructors_are_listed() {
+        let diagnostics = check(vec![
+            ctor("my/pkg:orders#held", vec![int(0)]),
+            ctor("my/pkg:orders#active", vec![]),
+        ]);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(
+                "non-exhaustive-match",
+                "This pattern match does not cover every value; missing: `Held _`"
+            )]
+        );
+        assert!(diagnostics[0].is_error());
+        assert_eq!(
+            diagnostics[0].definition.to_canonical_string(),
+            "my/pkg:orders#check"
+        );
+
+        let diagnostics = check(vec![
+            ctor(
+                "morphir/sdk:maybe#just",
+                vec![ctor("morphir/sdk:result#ok", vec![wild()])],
+            ),
+            ctor("morphir/sdk:maybe#nothing", vec![]),
+        ]);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(
+                "non-exhaustive-match",
+                "This pattern match does not cover every value; missing: `Just (Err _)`"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_covered_matches_and_redundant_cases() {
+        assert!(check(vec![bool(true), bool(false)]).is_empty());
+        assert!(check(vec![int(1), wild()]).is_empty());
+        // Constructors of types the distribution does not describe
+        assert!(check(vec![ctor("other/pkg:shapes#circle", vec![wild()])]).is_empty());
+
+        let diagnostics = check(vec![bool(true), bool(false), wild()]);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(
+                "redundant-case",
+                "Case 3 is redundant: the cases before it match every value it does"
+            )]
+        );
+        assert!(!diagnostics[0].is_error());
+    }
+
+    #[test]
+    fn test_lists_and_tuples() {
+        let cons = |head, tail| Pattern::HeadTailPattern(attrs(), Box::new(head), Box::new(tail));
+        let diagnostics = check(vec![
+            Pattern::EmptyListPattern(attrs()),
+            cons(wild(), Pattern::EmptyListPattern(attrs())),
+        ]);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(
+                "non-exhaustive-match",
+                "This pattern match does not cover every value; missing: `_ :: _ :: _`"
+            )]
+        );
+
+        let pair = |a, b| Pattern::TuplePattern(attrs(), vec![a, b]);
+        let diagnostics = check(vec![pair(bool(true), wild()), pair(wild(), bool(true))]);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(
+                "non-exhaustive-match",
+                "This pattern match does not cover every value; missing: `( False, False )`"
+            )]
+        );
+    }
+}
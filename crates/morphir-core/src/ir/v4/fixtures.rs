@@ -0,0 +1,117 @@
+//! Shorthands for building V4 IR in tests
+//!
+//! Unit tests across the workspace build their distributions from the same
+//! few pieces. Tests of other crates reach them through the `test-fixtures`
+//! feature of morphir-core.
+
+use super::access::AccessControlled;
+use super::attributes::{TypeAttributes, ValueAttributes};
+use super::distribution::{Distribution, LibraryContent};
+use super::literal::Literal;
+use super::module::ModuleDefinition;
+use super::package::PackageDefinition;
+use super::text::parse_distribution;
+use super::types::{ConstructorArg, ConstructorDefinition, Type, TypeDefinition};
+use super::value::{InputType, Value, ValueDefinition};
+use crate::naming::{FQName, Name, PackageName, Path};
+use indexmap::IndexMap;
+
+/// Parse an FQName in canonical form (`my/pkg:orders#total`)
+pub fn fq(s: &str) -> FQName {
+    FQName::from_canonical_string(s).unwrap()
+}
+
+/// Empty value attributes
+pub fn attrs() -> ValueAttributes {
+    ValueAttributes::default()
+}
+
+pub fn public<T>(value: T) -> AccessControlled<T> {
+    AccessControlled::public(value)
+}
+
+/// Reference to the type `fqname`, without arguments
+pub fn named(fqname: &str) -> Type {
+    Type::reference(TypeAttributes::default(), fq(fqname), vec![])
+}
+
+/// The SDK `Int` type
+pub fn int() -> Type {
+    named("morphir/sdk:basics#int")
+}
+
+pub fn int_lit(n: i64) -> Value {
+    Value::literal(attrs(), Literal::Integer(n))
+}
+
+pub fn variable(name: &str) -> Value {
+    Value::Variable(attrs(), Name::from(name))
+}
+
+pub fn reference(fqname: &str) -> Value {
+    Value::Reference(attrs(), fq(fqname))
+}
+
+/// `function` applied to `args`, one at a time
+pub fn call(function: Value, args: Vec<Value>) -> Value {
+    args.into_iter().fold(function, |function, arg| {
+        Value::Apply(attrs(), Box::new(function), Box::new(arg))
+    })
+}
+
+pub fn input(name: &str, tpe: Type) -> InputType {
+    InputType::new(Name::from(name), attrs(), tpe)
+}
+
+/// Library `my/pkg` with a single module `orders` holding `values`
+pub fn library(values: Vec<(&str, ValueDefinition)>) -> Distribution {
+    let mut module = ModuleDefinition {
+        types: IndexMap::new(),
+        values: IndexMap::new(),
+        doc: None,
+        ids: Default::default(),
+    };
+    for (name, def) in values {
+        module.values.insert(name.to_string(), public(def));
+    }
+    let mut modules = IndexMap::new();
+    modules.insert("orders".to_string(), public(module));
+    Distribution::Library(LibraryContent {
+        package_name: PackageName::new(Path::new("my/pkg")),
+        dependencies: IndexMap::new(),
+        def: PackageDefinition { modules },
+    })
+}
+
+/// The custom type `status = Active | Held Int`
+pub fn status() -> TypeDefinition {
+    TypeDefinition::CustomTypeDefinition {
+        type_params: vec![],
+        constructors: public(vec![
+            ConstructorDefinition {
+                name: Name::from("active"),
+                args: vec![],
+            },
+            ConstructorDefinition {
+                name: Name::from("held"),
+                args: vec![ConstructorArg {
+                    name: Name::from("days"),
+                    arg_type: int(),
+                }],
+            },
+        ]),
+    }
+}
+
+/// The module `orders` of a distribution built by [`library`]
+pub fn orders(dist: &mut Distribution) -> &mut ModuleDefinition {
+    let Distribution::Library(lib) = dist else {
+        unreachable!("fixtures build libraries")
+    };
+    &mut lib.def.modules["orders"].value
+}
+
+/// Parse a distribution in the text format
+pub fn parse(text: &str) -> Distribution {
+    parse_distribution(text).unwrap()
+}
@@ -0,0 +1,612 @@
+//! Type-dependency and call graphs.
+//!
+//! Builds a graph over the definitions of a distribution with one node per
+//! fully-qualified name and one edge per reference: from a type to the types
+//! its definition mentions, or from a value to the values its body calls.
+//! Definitions of other packages appear as external nodes when referenced.
+//! The graph answers reachability queries, finds reference cycles, and
+//! renders itself as DOT or GraphML for visualization.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let graph = call_graph(&dist);
+//! for cycle in graph.cycles() {
+//!     println!("mutually recursive: {:?}", cycle);
+//! }
+//! let used = graph.reachable_from(&[FQName::from_canonical_string("my/pkg:orders#total")?]);
+//! ```
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use super::distribution::Distribution;
+use super::sample::{Reference, collect_type_definition_refs, collect_value_refs};
+use super::value::ValueBody;
+use crate::naming::{FQName, Name, Path};
+
+/// What the nodes and edges of a graph stand for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphKind {
+    /// Type definitions, with an edge to each type their definition mentions
+    Types,
+    /// Value definitions, with an edge to each value their body references
+    Calls,
+}
+
+impl GraphKind {
+    fn name(self) -> &'static str {
+        match self {
+            GraphKind::Types => "types",
+            GraphKind::Calls => "calls",
+        }
+    }
+}
+
+/// A directed graph of references between definitions.
+///
+/// Nodes keep the order of the definitions in the distribution, followed by
+/// external nodes in the order they are first referenced.
+#[derive(Debug, Clone)]
+pub struct DependencyGraph {
+    kind: GraphKind,
+    /// Every node, with whether it is defined outside the distribution
+    nodes: IndexMap<FQName, bool>,
+    /// Successors of each node, by node index
+    edges: Vec<BTreeSet<usize>>,
+}
+
+/// Build the type-dependency graph of a distribution.
+///
+/// Type aliases depend on the types of their expression and custom types on
+/// the types of their constructor arguments. Specs distributions carry no
+/// definitions and produce an empty graph.
+pub fn type_graph(distribution: &Distribution) -> DependencyGraph {
+    let mut graph = DependencyGraph::new(GraphKind::Types);
+    let mut references = Vec::new();
+    if let Some(package) = distribution.definition() {
+        let package_name = &distribution.package_name().0;
+        for (module_key, module) in &package.modules {
+            for (type_key, type_def) in &module.value.types {
+                let fqname = FQName::new(
+                    package_name.clone(),
+                    Path::new(module_key),
+                    Name::from(type_key),
+                );
+                let mut refs = Vec::new();
+                collect_type_definition_refs(&type_def.value, &mut refs);
+                references.push((graph.add_node(fqname, false), refs));
+            }
+        }
+    }
+    graph.add_references(references, |reference| match reference {
+        Reference::Type(fqname) => Some(fqname),
+        Reference::Value(_) | Reference::Constructor(_) => None,
+    });
+    graph
+}
+
+/// Build the call graph of a distribution.
+///
+/// Values depend on the values referenced anywhere in their body, including
+/// in let bindings and lambdas. Native and external values have no body and
+/// so no outgoing edges.
+pub fn call_graph(distribution: &Distribution) -> DependencyGraph {
+    let mut graph = DependencyGraph::new(GraphKind::Calls);
+    let mut references = Vec::new();
+    if let Some(package) = distribution.definition() {
+        let package_name = &distribution.package_name().0;
+        for (module_key, module) in &package.modules {
+            for (value_key, value_def) in &module.value.values {
+                let fqname = FQName::new(
+                    package_name.clone(),
+                    Path::new(module_key),
+                    Name::from(value_key),
+                );
+                let mut refs = Vec::new();
+                if let ValueBody::Expression(body) = &value_def.value.body {
+                    collect_value_refs(body, &mut refs);
+                }
+                references.push((graph.add_node(fqname, false), refs));
+            }
+        }
+    }
+    graph.add_references(references, |reference| match reference {
+        Reference::Value(fqname) => Some(fqname),
+        Reference::Type(_) | Reference::Constructor(_) => None,
+    });
+    graph
+}
+
+impl DependencyGraph {
+    fn new(kind: GraphKind) -> Self {
+        Self {
+            kind,
+            nodes: IndexMap::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    fn add_node(&mut self, name: FQName, external: bool) -> usize {
+        if let Some(index) = self.nodes.get_index_of(&name) {
+            return index;
+        }
+        self.nodes.insert(name, external);
+        self.edges.push(BTreeSet::new());
+        self.nodes.len() - 1
+    }
+
+    /// Add an edge from each source to the targets its references select.
+    ///
+    /// Runs once every definition has a node, so only targets defined
+    /// nowhere in the distribution become external nodes.
+    fn add_references(
+        &mut self,
+        references: Vec<(usize, Vec<Reference>)>,
+        target: impl Fn(Reference) -> Option<FQName>,
+    ) {
+        for (source, refs) in references {
+            for fqname in refs.into_iter().filter_map(&target) {
+                let index = self.add_node(fqname, true);
+                self.edges[source].insert(index);
+            }
+        }
+    }
+
+    /// Whether the nodes are types or values
+    pub fn kind(&self) -> GraphKind {
+        self.kind
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the graph has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Every node, in order
+    pub fn nodes(&self) -> impl Iterator<Item = &FQName> {
+        self.nodes.keys()
+    }
+
+    /// Whether `name` is a node of the graph
+    pub fn contains(&self, name: &FQName) -> bool {
+        self.nodes.contains_key(name)
+    }
+
+    /// Whether `name` is referenced by the distribution but defined outside it
+    pub fn is_external(&self, name: &FQName) -> bool {
+        self.nodes.get(name).copied().unwrap_or(false)
+    }
+
+    /// Every edge, as `(from, to)` pairs
+    pub fn edges(&self) -> impl Iterator<Item = (&FQName, &FQName)> {
+        self.edges
+            .iter()
+            .enumerate()
+            .flat_map(move |(from, targets)| {
+                targets
+                    .iter()
+                    .map(move |&to| (self.name(from), self.name(to)))
+            })
+    }
+
+    /// Nodes `name` references directly
+    pub fn dependencies(&self, name: &FQName) -> Vec<&FQName> {
+        match self.nodes.get_index_of(name) {
+            Some(index) => self.edges[index].iter().map(|&i| self.name(i)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Nodes that reference `name` directly
+    pub fn dependents(&self, name: &FQName) -> Vec<&FQName> {
+        let Some(index) = self.nodes.get_index_of(name) else {
+            return Vec::new();
+        };
+        self.edges
+            .iter()
+            .enumerate()
+            .filter(|(_, targets)| targets.contains(&index))
+            .map(|(from, _)| self.name(from))
+            .collect()
+    }
+
+    /// Every node reachable from `roots`, the roots included, in
+    /// breadth-first order. Roots that are not nodes are ignored.
+    pub fn reachable_from(&self, roots: &[FQName]) -> Vec<&FQName> {
+        self.reachable(
+            roots
+                .iter()
+                .filter_map(|root| self.nodes.get_index_of(root)),
+        )
+        .into_iter()
+        .map(|index| self.name(index))
+        .collect()
+    }
+
+    /// Whether a chain of references leads from `from` to `to`
+    pub fn reaches(&self, from: &FQName, to: &FQName) -> bool {
+        match (self.nodes.get_index_of(from), self.nodes.get_index_of(to)) {
+            (Some(from), Some(to)) => self.reachable([from]).contains(&to),
+            _ => false,
+        }
+    }
+
+    /// Reference cycles: each group of nodes that all reach one another,
+    /// and each node that references itself, in node order.
+    pub fn cycles(&self) -> Vec<Vec<&FQName>> {
+        let mut cycles: Vec<Vec<usize>> = self
+            .strongly_connected()
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || self.edges[component[0]].contains(&component[0])
+            })
+            .map(|mut component| {
+                component.sort_unstable();
+                component
+            })
+            .collect();
+        cycles.sort_unstable();
+        cycles
+            .into_iter()
+            .map(|cycle| cycle.into_iter().map(|index| self.name(index)).collect())
+            .collect()
+    }
+
+    /// The subgraph of the nodes reachable from `roots`
+    pub fn reachable_subgraph(&self, roots: &[FQName]) -> Self {
+        let keep: HashSet<&FQName> = self.reachable_from(roots).into_iter().collect();
+        self.subgraph(|name| keep.contains(name))
+    }
+
+    /// The subgraph without external nodes
+    pub fn without_external(&self) -> Self {
+        self.subgraph(|name| !self.is_external(name))
+    }
+
+    /// The subgraph of the nodes for which `keep` holds, with the edges
+    /// between them
+    pub fn subgraph(&self, keep: impl Fn(&FQName) -> bool) -> Self {
+        let mut graph = Self::new(self.kind);
+        let mut indices = vec![None; self.len()];
+        for (index, (name, external)) in self.nodes.iter().enumerate() {
+            if keep(name) {
+                indices[index] = Some(graph.add_node(name.clone(), *external));
+            }
+        }
+        for (from, targets) in self.edges.iter().enumerate() {
+            let Some(new_from) = indices[from] else {
+                continue;
+            };
+            graph.edges[new_from] = targets.iter().filter_map(|&to| indices[to]).collect();
+        }
+        graph
+    }
+
+    /// Render the graph in Graphviz DOT.
+    ///
+    /// External nodes are dashed and edges within a cycle are red.
+    pub fn to_dot(&self) -> String {
+        let cyclic = self.cycle_membership();
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph {} {{", self.kind.name());
+        let _ = writeln!(out, "  rankdir=LR;");
+        let _ = writeln!(out, "  node [shape=box];");
+        for (name, external) in &self.nodes {
+            let style = if *external { " [style=dashed]" } else { "" };
+            let _ = writeln!(out, "  {}{};", dot_id(name), style);
+        }
+        for (from, targets) in self.edges.iter().enumerate() {
+            for &to in targets {
+                let style = if cyclic[from].is_some() && cyclic[from] == cyclic[to] {
+                    " [color=red]"
+                } else {
+                    ""
+                };
+                let _ = writeln!(
+                    out,
+                    "  {} -> {}{};",
+                    dot_id(self.name(from)),
+                    dot_id(self.name(to)),
+                    style
+                );
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render the graph in GraphML, with an `external` attribute on nodes.
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        out.push_str(
+            "  <key id=\"external\" for=\"node\" attr.name=\"external\" attr.type=\"boolean\">\
+             <default>false</default></key>\n",
+        );
+        let _ = writeln!(
+            out,
+            "  <graph id=\"{}\" edgedefault=\"directed\">",
+            self.kind.name()
+        );
+        for (name, external) in &self.nodes {
+            let id = xml_escape(&name.to_canonical_string());
+            if *external {
+                let _ = writeln!(
+                    out,
+                    "    <node id=\"{}\"><data key=\"external\">true</data></node>",
+                    id
+                );
+            } else {
+                let _ = writeln!(out, "    <node id=\"{}\"/>", id);
+            }
+        }
+        for (from, to) in self.edges() {
+            let _ = writeln!(
+                out,
+                "    <edge source=\"{}\" target=\"{}\"/>",
+                xml_escape(&from.to_canonical_string()),
+                xml_escape(&to.to_canonical_string())
+            );
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    fn name(&self, index: usize) -> &FQName {
+        self.nodes
+            .get_index(index)
+            .map(|(name, _)| name)
+            .expect("edge to a node of the graph")
+    }
+
+    fn reachable(&self, roots: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let mut seen = vec![false; self.len()];
+        let mut order = Vec::new();
+        let mut queue: VecDeque<usize> = roots.into_iter().collect();
+        while let Some(index) = queue.pop_front() {
+            if std::mem::replace(&mut seen[index], true) {
+                continue;
+            }
+            order.push(index);
+            queue.extend(self.edges[index].iter().filter(|&&next| !seen[next]));
+        }
+        order
+    }
+
+    /// For each node, the index of the cycle it belongs to
+    fn cycle_membership(&self) -> Vec<Option<usize>> {
+        let mut membership = vec![None; self.len()];
+        for (cycle, names) in self.cycles().into_iter().enumerate() {
+            for name in names {
+                if let Some(index) = self.nodes.get_index_of(name) {
+                    membership[index] = Some(cycle);
+                }
+            }
+        }
+        membership
+    }
+
+    /// Strongly connected components, by Tarjan's algorithm.
+    ///
+    /// Iterative, so long chains of references cannot overflow the stack.
+    fn strongly_connected(&self) -> Vec<Vec<usize>> {
+        let mut tarjan = Tarjan {
+            index: vec![None; self.len()],
+            low: vec![0; self.len()],
+            on_stack: vec![false; self.len()],
+            stack: Vec::new(),
+            next: 0,
+        };
+        let mut components = Vec::new();
+
+        for start in 0..self.len() {
+            if tarjan.index[start].is_some() {
+                continue;
+            }
+            let mut work = vec![(start, self.edges[start].iter())];
+            tarjan.visit(start);
+            while let Some((node, successors)) = work.last_mut() {
+                let node = *node;
+                if let Some(&successor) = successors.next() {
+                    match tarjan.index[successor] {
+                        None => {
+                            tarjan.visit(successor);
+                            work.push((successor, self.edges[successor].iter()));
+                        }
+                        Some(index) if tarjan.on_stack[successor] => {
+                            tarjan.low[node] = tarjan.low[node].min(index);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+                work.pop();
+                if let Some(&(parent, _)) = work.last() {
+                    tarjan.low[parent] = tarjan.low[parent].min(tarjan.low[node]);
+                }
+                if tarjan.index[node] == Some(tarjan.low[node]) {
+                    components.push(tarjan.pop_component(node));
+                }
+            }
+        }
+        components
+    }
+}
+
+/// Bookkeeping of Tarjan's algorithm
+struct Tarjan {
+    /// Visit order of each node, once visited
+    index: Vec<Option<usize>>,
+    /// Lowest visit order reachable from each node
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next: usize,
+}
+
+impl Tarjan {
+    fn visit(&mut self, node: usize) {
+        self.index[node] = Some(self.next);
+        self.low[node] = self.next;
+        self.next += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+    }
+
+    /// Pop the component rooted at `root` off the stack
+    fn pop_component(&mut self, root: usize) -> Vec<usize> {
+        let mut component = Vec::new();
+        while let Some(member) = self.stack.pop() {
+            self.on_stack[member] = false;
+            component.push(member);
+            if member == root {
+                break;
+            }
+        }
+        component
+    }
+}
+
+fn dot_id(name: &FQName) -> String {
+    format!(
+        "\"{}\"",
+        name.to_canonical_string()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::fq;
+    use super::*;
+    use serde_json::json;
+
+    fn call(name: &str) -> serde_json::Value {
+        json!({ "Reference": { "fqname": name } })
+    }
+
+    fn value(body: serde_json::Value) -> serde_json::Value {
+        json!({ "access": "Public", "value": {
+            "inputTypes": {},
+            "outputType": "morphir/sdk:basics#int",
+            "body": { "ExpressionBody": { "body": body } }
+        } })
+    }
+
+    /// `even`/`odd` call each other, `total` calls `even` and SDK `add`, and
+    /// `order` is a record mentioning `line`
+    fn distribution() -> Distribution {
+        serde_json::from_value(json!({ "Library": {
+            "packageName": "acme",
+            "dependencies": {},
+            "def": { "modules": { "orders": { "access": "Public", "value": {
+                "types": {
+                    "order": { "access": "Public", "value": { "TypeAliasDefinition": {
+                        "typeParams": [],
+                        "typeExp": { "Record": { "fields": {
+                            "lines": { "Reference": {
+                                "fqname": "morphir/sdk:list#list",
+                                "args": ["acme:orders#line"]
+                            } }
+                        } } }
+                    } } },
+                    "line": { "access": "Public", "value": { "TypeAliasDefinition": {
+                        "typeParams": [],
+                        "typeExp": "morphir/sdk:basics#int"
+                    } } }
+                },
+                "values": {
+                    "total": value(json!({ "Apply": {
+                        "function": call("morphir/sdk:basics#add"),
+                        "argument": call("acme:orders#even")
+                    } })),
+                    "even": value(call("acme:orders#odd")),
+                    "odd": value(call("acme:orders#even"))
+                }
+            } } } }
+        } }))
+        .unwrap()
+    }
+
+    fn strings(names: Vec<&FQName>) -> Vec<String> {
+        names.into_iter().map(FQName::to_canonical_string).collect()
+    }
+
+    #[test]
+    fn test_call_graph_cycles_and_reachability() {
+        let graph = call_graph(&distribution());
+        assert_eq!(graph.len(), 4);
+        assert!(graph.is_external(&fq("morphir/sdk:basics#add")));
+        assert_eq!(
+            strings(graph.dependents(&fq("acme:orders#even"))),
+            vec!["acme:orders#odd", "acme:orders#total"]
+        );
+        assert_eq!(
+            graph.cycles(),
+            vec![vec![&fq("acme:orders#even"), &fq("acme:orders#odd")]]
+        );
+        assert!(graph.reaches(&fq("acme:orders#odd"), &fq("acme:orders#odd")));
+        assert!(!graph.reaches(&fq("acme:orders#even"), &fq("acme:orders#total")));
+        assert_eq!(
+            strings(graph.reachable_from(&[fq("acme:orders#odd")])),
+            vec!["acme:orders#odd", "acme:orders#even"]
+        );
+    }
+
+    #[test]
+    fn test_type_graph_and_subgraphs() {
+        let graph = type_graph(&distribution());
+        assert_eq!(
+            strings(graph.dependencies(&fq("acme:orders#order"))),
+            vec!["acme:orders#line", "morphir/sdk:list#list"]
+        );
+        assert!(graph.cycles().is_empty());
+
+        let local = graph.without_external();
+        assert_eq!(
+            strings(local.nodes().collect()),
+            vec!["acme:orders#line", "acme:orders#order"]
+        );
+        assert_eq!(local.edges().count(), 1);
+
+        let line = graph.reachable_subgraph(&[fq("acme:orders#line")]);
+        assert_eq!(line.len(), 2);
+        assert_eq!(line.edges().count(), 1);
+    }
+
+    #[test]
+    fn test_renderers() {
+        let graph = call_graph(&distribution());
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph calls {"));
+        assert!(dot.contains("\"morphir/sdk:basics#add\" [style=dashed];"));
+        assert!(dot.contains("\"acme:orders#even\" -> \"acme:orders#odd\" [color=red];"));
+        assert!(dot.contains("\"acme:orders#total\" -> \"acme:orders#even\";"));
+
+        let graphml = graph.to_graphml();
+        assert!(graphml.contains("<graph id=\"calls\" edgedefault=\"directed\">"));
+        assert!(graphml.contains(
+            "<node id=\"morphir/sdk:basics#add\"><data key=\"external\">true</data></node>"
+        ));
+        assert!(graphml.contains("<edge source=\"acme:orders#odd\" target=\"acme:orders#even\"/>"));
+    }
+}
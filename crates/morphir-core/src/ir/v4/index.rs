@@ -0,0 +1,472 @@
+//! Lookup of definitions by fully-qualified name.
+//!
+//! A [`DistributionIndex`] maps the FQName of every type, value, and
+//! constructor of a distribution and of its dependencies to its definition or
+//! specification, and records which definitions of the package refer to each
+//! name. Analyses that resolve many references, such as linters, checkers,
+//! and editors, build it once and share it instead of walking the module
+//! maps for every lookup.
+//!
+//! Names are compared by their lowercase words, so `userId` in a key and
+//! `user-id` or `UserId` in a reference resolve to the same definition, and
+//! the names the index returns are in that lowercase form.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let index = DistributionIndex::new(&ir_file.distribution);
+//! let total = FQName::from_canonical_string("acme/shop:orders#total")?;
+//! if let Some(value) = index.resolve_value(&total) {
+//!     println!("returns {:?}", value.output_type());
+//! }
+//! for user in index.referrers(&total) {
+//!     println!("used by {}", user);
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use super::access::AccessControlled;
+use super::distribution::Distribution;
+use super::module::ModuleSpecification;
+use super::sample::{
+    Reference, collect_signature_refs, collect_type_definition_refs, collect_value_refs,
+};
+use super::types::{Type, TypeDefinition, TypeSpecification};
+use super::value::{ValueBody, ValueDefinition, ValueSpecification};
+use crate::naming::{FQName, Name, Path};
+
+/// A type of the package, with its definition, or of a dependency, with its
+/// specification
+#[derive(Debug, Clone, Copy)]
+pub enum TypeEntry<'a> {
+    Definition(&'a AccessControlled<TypeDefinition>),
+    Specification(&'a TypeSpecification),
+}
+
+impl<'a> TypeEntry<'a> {
+    /// Names of the type's parameters
+    pub fn type_params(&self) -> &'a [Name] {
+        match self {
+            TypeEntry::Definition(def) => match &def.value {
+                TypeDefinition::TypeAliasDefinition { type_params, .. }
+                | TypeDefinition::CustomTypeDefinition { type_params, .. }
+                | TypeDefinition::IncompleteTypeDefinition { type_params, .. } => type_params,
+            },
+            TypeEntry::Specification(spec) => match spec {
+                TypeSpecification::TypeAliasSpecification { type_params, .. }
+                | TypeSpecification::OpaqueTypeSpecification { type_params }
+                | TypeSpecification::CustomTypeSpecification { type_params, .. } => type_params,
+            },
+        }
+    }
+}
+
+/// A value of the package, with its definition, or of a dependency, with
+/// its specification
+#[derive(Debug, Clone, Copy)]
+pub enum ValueEntry<'a> {
+    Definition(&'a AccessControlled<ValueDefinition>),
+    Specification(&'a ValueSpecification),
+}
+
+impl<'a> ValueEntry<'a> {
+    /// Input names and types, in order
+    pub fn inputs(&self) -> Vec<(&'a str, &'a Type)> {
+        match self {
+            ValueEntry::Definition(def) => def
+                .value
+                .input_types
+                .iter()
+                .map(|(name, input)| (name.as_str(), &input.input_type))
+                .collect(),
+            ValueEntry::Specification(spec) => spec
+                .inputs
+                .iter()
+                .map(|(name, tpe)| (name.as_str(), tpe))
+                .collect(),
+        }
+    }
+
+    pub fn output_type(&self) -> &'a Type {
+        match self {
+            ValueEntry::Definition(def) => &def.value.output_type,
+            ValueEntry::Specification(spec) => &spec.output,
+        }
+    }
+}
+
+/// A constructor of a custom type
+#[derive(Debug, Clone)]
+pub struct Constructor<'a> {
+    pub name: FQName,
+    /// The custom type the constructor builds
+    pub type_name: FQName,
+    /// Argument names and types, in order
+    pub args: Vec<(&'a Name, &'a Type)>,
+}
+
+/// Definitions of a distribution and its dependencies by FQName.
+#[derive(Debug, Clone)]
+pub struct DistributionIndex<'a> {
+    package: Path,
+    types: HashMap<FQName, TypeEntry<'a>>,
+    values: HashMap<FQName, ValueEntry<'a>>,
+    constructors: HashMap<FQName, Constructor<'a>>,
+    /// Constructors of each custom type, in declaration order
+    constructors_by_type: HashMap<FQName, Vec<FQName>>,
+    /// Definitions of the package referring to each name
+    referrers: HashMap<FQName, Vec<FQName>>,
+}
+
+impl<'a> DistributionIndex<'a> {
+    /// Index a distribution and the specifications of its dependencies.
+    ///
+    /// A dependency with the name of the package itself is ignored, so the
+    /// package's own definitions always win.
+    pub fn new(distribution: &'a Distribution) -> Self {
+        let package = canonical_path(&distribution.package_name().0);
+        let mut index = DistributionIndex {
+            package: package.clone(),
+            types: HashMap::new(),
+            values: HashMap::new(),
+            constructors: HashMap::new(),
+            constructors_by_type: HashMap::new(),
+            referrers: HashMap::new(),
+        };
+
+        for (dependency_key, spec) in distribution.dependencies() {
+            let dependency = canonical_path(&Path::new(dependency_key));
+            if dependency == package {
+                continue;
+            }
+            for (module_key, module) in &spec.modules {
+                index.add_specification(
+                    &dependency,
+                    &canonical_path(&Path::new(module_key)),
+                    module,
+                );
+            }
+        }
+
+        match distribution {
+            Distribution::Library(_) | Distribution::Application(_) => {
+                for (module_key, module) in distribution
+                    .definition()
+                    .into_iter()
+                    .flat_map(|d| &d.modules)
+                {
+                    let module_path = canonical_path(&Path::new(module_key));
+                    for (type_key, type_def) in &module.value.types {
+                        let name = member(&package, &module_path, type_key);
+                        if let TypeDefinition::CustomTypeDefinition { constructors, .. } =
+                            &type_def.value
+                        {
+                            for ctor in &constructors.value {
+                                let args = ctor
+                                    .args
+                                    .iter()
+                                    .map(|arg| (&arg.name, &arg.arg_type))
+                                    .collect();
+                                index.add_constructor(&name, &ctor.name, args);
+                            }
+                        }
+                        let mut refs = Vec::new();
+                        collect_type_definition_refs(&type_def.value, &mut refs);
+                        index.add_referrer(&name, refs);
+                        index.types.insert(name, TypeEntry::Definition(type_def));
+                    }
+                    for (value_key, value_def) in &module.value.values {
+                        let name = member(&package, &module_path, value_key);
+                        let mut refs = Vec::new();
+                        collect_signature_refs(&value_def.value, &mut refs);
+                        if let ValueBody::Expression(body) = &value_def.value.body {
+                            collect_value_refs(body, &mut refs);
+                        }
+                        index.add_referrer(&name, refs);
+                        index.values.insert(name, ValueEntry::Definition(value_def));
+                    }
+                }
+            }
+            Distribution::Specs(specs) => {
+                for (module_key, module) in &specs.spec.modules {
+                    index.add_specification(
+                        &package,
+                        &canonical_path(&Path::new(module_key)),
+                        module,
+                    );
+                }
+            }
+        }
+
+        index
+    }
+
+    fn add_specification(
+        &mut self,
+        package: &Path,
+        module_path: &Path,
+        module: &'a ModuleSpecification,
+    ) {
+        for (type_key, type_spec) in &module.types {
+            let name = member(package, module_path, type_key);
+            if let TypeSpecification::CustomTypeSpecification { constructors, .. } = type_spec {
+                for ctor in constructors {
+                    let args = ctor
+                        .args
+                        .iter()
+                        .map(|arg| (&arg.name, &arg.arg_type))
+                        .collect();
+                    self.add_constructor(&name, &ctor.name, args);
+                }
+            }
+            self.types.insert(name, TypeEntry::Specification(type_spec));
+        }
+        for (value_key, value_spec) in &module.values {
+            let name = member(package, module_path, value_key);
+            self.values
+                .insert(name, ValueEntry::Specification(value_spec));
+        }
+    }
+
+    fn add_constructor(
+        &mut self,
+        type_name: &FQName,
+        ctor_name: &Name,
+        args: Vec<(&'a Name, &'a Type)>,
+    ) {
+        let name = canonical(&FQName::new(
+            type_name.package_path.clone(),
+            type_name.module_path.clone(),
+            ctor_name.clone(),
+        ));
+        self.constructors_by_type
+            .entry(type_name.clone())
+            .or_default()
+            .push(name.clone());
+        self.constructors.insert(
+            name.clone(),
+            Constructor {
+                name,
+                type_name: type_name.clone(),
+                args,
+            },
+        );
+    }
+
+    /// Record `referrer` as referring to each of `refs`, once per name
+    fn add_referrer(&mut self, referrer: &FQName, refs: Vec<Reference>) {
+        for reference in refs {
+            let users = self
+                .referrers
+                .entry(canonical(reference.fqname()))
+                .or_default();
+            if !users.contains(referrer) {
+                users.push(referrer.clone());
+            }
+        }
+    }
+
+    /// Whether `fqname` names something in the indexed package rather than
+    /// in a dependency or elsewhere
+    pub fn is_local(&self, fqname: &FQName) -> bool {
+        canonical_path(&fqname.package_path) == self.package
+    }
+
+    /// Definition or specification of the type `fqname`
+    pub fn resolve_type(&self, fqname: &FQName) -> Option<TypeEntry<'a>> {
+        self.types.get(&canonical(fqname)).copied()
+    }
+
+    /// Definition or specification of the value `fqname`
+    pub fn resolve_value(&self, fqname: &FQName) -> Option<ValueEntry<'a>> {
+        self.values.get(&canonical(fqname)).copied()
+    }
+
+    /// The constructor `fqname`, with the type it belongs to
+    pub fn resolve_constructor(&self, fqname: &FQName) -> Option<&Constructor<'a>> {
+        self.constructors.get(&canonical(fqname))
+    }
+
+    /// Constructors of the custom type `type_fqname`, in declaration order.
+    ///
+    /// Empty for aliases, opaque and incomplete types, and unknown names.
+    pub fn constructors_of(&self, type_fqname: &FQName) -> Vec<&Constructor<'a>> {
+        self.constructors_by_type
+            .get(&canonical(type_fqname))
+            .into_iter()
+            .flatten()
+            .filter_map(|name| self.constructors.get(name))
+            .collect()
+    }
+
+    /// Types and values of the package whose definitions refer to `fqname`,
+    /// in the order they are defined.
+    ///
+    /// Values refer to the types of their signature and to the values and
+    /// constructors used in their body; types to the types they are built
+    /// from. Dependencies are specifications only and never refer to
+    /// anything.
+    pub fn referrers(&self, fqname: &FQName) -> &[FQName] {
+        self.referrers
+            .get(&canonical(fqname))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// FQNames of every indexed type
+    pub fn types(&self) -> impl Iterator<Item = &FQName> {
+        self.types.keys()
+    }
+
+    /// FQNames of every indexed value
+    pub fn values(&self) -> impl Iterator<Item = &FQName> {
+        self.values.keys()
+    }
+}
+
+/// `path` with its names in lowercase words, so that every spelling of a
+/// path compares equal
+fn canonical_path(path: &Path) -> Path {
+    Path::new(&path.to_string())
+}
+
+/// `fqname` with its names in lowercase words
+fn canonical(fqname: &FQName) -> FQName {
+    FQName::new(
+        canonical_path(&fqname.package_path),
+        canonical_path(&fqname.module_path),
+        Name::from(&fqname.local_name.to_string()),
+    )
+}
+
+/// Name of the member `key` of a module
+fn member(package: &Path, module_path: &Path, key: &str) -> FQName {
+    canonical(&FQName::new(
+        package.clone(),
+        module_path.clone(),
+        Name::from(key),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::fq;
+    use super::super::text::parse_distribution;
+    use super::*;
+
+    const SOURCE: &str = "library acme/shop
+
+module orders
+
+type Status
+    = Held (reason : morphir/sdk:string#String)
+    | Pending
+
+type alias Order =
+    { status : acme/shop:orders#Status
+    }
+
+release (order : acme/shop:orders#Order) : acme/shop:orders#Status =
+    case order.status of
+        acme/shop:orders#Held _ ->
+            acme/shop:orders#Pending
+        _ ->
+            acme/shop:orders#fallback
+
+fallback : acme/shop:orders#Status =
+    acme/shop:orders#Held (morphir/sdk:string#fromInt 1)
+
+
+dependency morphir/sdk
+
+
+module string
+
+opaque type String
+
+from-int (n : morphir/sdk:basics#Int) : morphir/sdk:string#String
+";
+
+    fn strings(names: &[FQName]) -> Vec<String> {
+        names.iter().map(FQName::to_canonical_string).collect()
+    }
+
+    #[test]
+    fn test_resolves_definitions_and_specifications() {
+        let distribution = parse_distribution(SOURCE).unwrap();
+        let index = DistributionIndex::new(&distribution);
+
+        let release = index
+            .resolve_value(&fq("acme/shop:orders#release"))
+            .unwrap();
+        assert!(matches!(release, ValueEntry::Definition(_)));
+        assert_eq!(release.inputs()[0].0, "order");
+
+        let from_int = index
+            .resolve_value(&fq("morphir/sdk:string#fromInt"))
+            .unwrap();
+        assert!(matches!(from_int, ValueEntry::Specification(_)));
+        assert!(matches!(
+            index.resolve_type(&fq("morphir/sdk:string#String")),
+            Some(TypeEntry::Specification(_))
+        ));
+        assert!(
+            index
+                .resolve_value(&fq("acme/shop:orders#missing"))
+                .is_none()
+        );
+        assert!(index.is_local(&fq("acme/shop:orders#release")));
+        assert!(!index.is_local(&fq("morphir/sdk:string#fromInt")));
+    }
+
+    #[test]
+    fn test_constructors_of_a_type() {
+        let distribution = parse_distribution(SOURCE).unwrap();
+        let index = DistributionIndex::new(&distribution);
+
+        let constructors = index.constructors_of(&fq("acme/shop:orders#Status"));
+        let names: Vec<String> = constructors
+            .iter()
+            .map(|c| c.name.to_canonical_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["acme/shop:orders#held", "acme/shop:orders#pending"]
+        );
+        assert_eq!(constructors[0].args.len(), 1);
+
+        let held = index
+            .resolve_constructor(&fq("acme/shop:orders#Held"))
+            .unwrap();
+        assert_eq!(held.type_name, fq("acme/shop:orders#status"));
+        assert!(
+            index
+                .constructors_of(&fq("acme/shop:orders#Order"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_referrers() {
+        let distribution = parse_distribution(SOURCE).unwrap();
+        let index = DistributionIndex::new(&distribution);
+
+        assert_eq!(
+            strings(index.referrers(&fq("acme/shop:orders#Status"))),
+            vec![
+                "acme/shop:orders#order",
+                "acme/shop:orders#release",
+                "acme/shop:orders#fallback"
+            ]
+        );
+        assert_eq!(
+            strings(index.referrers(&fq("acme/shop:orders#Held"))),
+            vec!["acme/shop:orders#release", "acme/shop:orders#fallback"]
+        );
+        assert_eq!(
+            strings(index.referrers(&fq("morphir/sdk:string#fromInt"))),
+            vec!["acme/shop:orders#fallback"]
+        );
+        assert!(index.referrers(&fq("acme/shop:orders#release")).is_empty());
+    }
+}
@@ -120,19 +120,17 @@ impl<'de> Visitor<'de> for LiteralVisitor {
             value: T,
         }
 
-        let (tag, value): (String, serde_json::Value) = map
-            .next_entry()?
+        let tag: String = map
+            .next_key()?
             .ok_or_else(|| de::Error::custom("expected object wrapper with single key"))?;
 
         match tag.as_str() {
             "BoolLiteral" => {
-                let content: LiteralValue<bool> =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: LiteralValue<bool> = map.next_value()?;
                 Ok(Literal::Bool(content.value))
             }
             "CharLiteral" => {
-                let content: LiteralValue<String> =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: LiteralValue<String> = map.next_value()?;
                 let c = content
                     .value
                     .chars()
@@ -141,23 +139,19 @@ impl<'de> Visitor<'de> for LiteralVisitor {
                 Ok(Literal::Char(c))
             }
             "StringLiteral" => {
-                let content: LiteralValue<String> =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: LiteralValue<String> = map.next_value()?;
                 Ok(Literal::String(content.value))
             }
             "IntegerLiteral" | "WholeNumberLiteral" => {
-                let content: LiteralValue<i64> =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: LiteralValue<i64> = map.next_value()?;
                 Ok(Literal::Integer(content.value))
             }
             "FloatLiteral" => {
-                let content: LiteralValue<f64> =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: LiteralValue<f64> = map.next_value()?;
                 Ok(Literal::Float(content.value))
             }
             "DecimalLiteral" => {
-                let content: LiteralValue<String> =
-                    serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: LiteralValue<String> = map.next_value()?;
                 Ok(Literal::Decimal(content.value))
             }
             _ => Err(de::Error::unknown_variant(
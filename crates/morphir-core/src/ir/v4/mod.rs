@@ -13,16 +13,38 @@ use serde::{Deserialize, Serialize};
 // Submodules - Core IR types
 pub mod access;
 pub mod attributes;
+pub mod canonical;
+pub mod decoration;
 pub mod distribution;
+pub mod exhaustiveness;
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixtures;
+pub mod graph;
+pub mod index;
 pub mod literal;
 pub mod module;
+pub mod node_id;
+pub mod optimize;
 pub mod package;
+pub mod parallel;
 pub mod pattern;
+pub mod query;
+pub mod read;
+pub mod refactor;
+pub mod sample;
+pub mod schema;
 pub mod serde_tagged;
 pub mod serde_v4;
+pub mod source_map;
+pub mod spec_diff;
+pub mod text;
 pub mod type_def;
+pub mod typecheck;
 pub mod types;
 pub mod value;
+pub mod version_impact;
+pub mod walk;
+pub mod wellformed;
 
 // Re-export naming types - Name now serializes as V4 canonical format (kebab-case string)
 pub use crate::naming::ModuleName;
@@ -44,24 +66,78 @@ pub use value::{
     ValueDefinition as ValueExprDefinition,
 };
 
+// Re-export the canonical JSON form
+pub use canonical::{CanonicalStyle, canonicalize, is_canonical, to_canonical_string};
+
+// Re-export decorations
+pub use decoration::{DecorationTable, DecorationTarget, Decorations};
+
+// Re-export the distribution walker
+pub use walk::{
+    DistributionVisitor, DistributionVisitorMut, VisitContext, Walk, walk_distribution,
+    walk_distribution_mut,
+};
+
+// Re-export parallel walkers
+pub use parallel::{par_transform_values, par_walk_modules};
+
+// Re-export source maps
+pub use source_map::{NodePath, SourceMap};
+
 // Re-export distribution types
 pub use distribution::{
     ApplicationContent, Dependencies, Distribution, EntryPoint, EntryPointKind, EntryPoints,
     LibraryContent, SpecsContent,
 };
 
+// Re-export dependency graphs
+pub use graph::{DependencyGraph, GraphKind, call_graph, type_graph};
+
+// Re-export the definition index
+pub use index::{DistributionIndex, TypeEntry, ValueEntry};
+
 // Re-export module types
 pub use module::{ModuleDefinition, ModuleSpecification};
 
+// Re-export stable node identifiers
+pub use node_id::{IdStrategy, NodeId, NodeIds, NodeKind, NodeLocation};
+
+// Re-export the optimizer
+pub use optimize::{
+    BetaReduction, ConstantFolding, Inlining, OptimizationPass, OptimizationReport, PassManager,
+    PassStats,
+};
+
 // Re-export package types
 pub use package::{PackageDefinition, PackageSpecification};
 
+// Re-export queries
+pub use query::{Query, QueryError, QueryTarget, query};
+
+// Re-export the IR reader
+pub use read::ReadError;
+
+// Re-export refactorings
+pub use refactor::{
+    Merge, RefactorError, RefactorLog, RefactorTransaction, Rename, RestructurePlan, Split,
+    SymbolKind, merge_modules, rename, restructure, split_module,
+};
+
+// Re-export specification differences
+pub use spec_diff::{ChangeKind, Impact, SpecChange, SpecDiff, SpecItem, diff_specifications};
+
+// Re-export the versioning impact analyzer
+pub use version_impact::{Bump, VersionImpact, version_impact};
+
 // Re-export type definition types
 pub use types::{
     ConstructorArg, ConstructorArgSpec, ConstructorDefinition, ConstructorSpecification,
     Incompleteness, TypeDefinition, TypeSpecification,
 };
 
+// Re-export the well-formedness check
+pub use wellformed::{Problem, check_well_formed};
+
 // Re-export value definition types
 pub use value::{
     HoleReason, InputTypeEntry, NativeHint, ValueBody, ValueDefinition, ValueSpecification,
@@ -75,7 +151,7 @@ pub use type_def::{
 };
 
 /// Top-level IR file structure
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct IRFile {
     pub format_version: FormatVersion,
@@ -83,7 +159,7 @@ pub struct IRFile {
 }
 
 /// Format version - accepts both string "4.0.0" and integer 4
-#[derive(Debug, Clone, PartialEq, JsonSchema)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FormatVersion {
     String(String),
     Integer(u32),
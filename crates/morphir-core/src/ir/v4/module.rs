@@ -3,14 +3,18 @@
 //! This module contains ModuleSpecification, ModuleDefinition, and related types.
 
 use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use super::access::AccessControlled;
-use super::types::{TypeDefinition, TypeSpecification};
+use super::access::{Access, AccessControlled};
+use super::node_id::NodeIds;
+use super::types::{
+    ConstructorArgSpec, ConstructorSpecification, TypeDefinition, TypeSpecification,
+};
 use super::value::{ValueDefinition, ValueSpecification};
 
 /// Module specification (public API only)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ModuleSpecification {
     pub types: IndexMap<String, TypeSpecification>,
@@ -20,11 +24,90 @@ pub struct ModuleSpecification {
 }
 
 /// Module definition
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ModuleDefinition {
     pub types: IndexMap<String, AccessControlled<TypeDefinition>>,
     pub values: IndexMap<String, AccessControlled<ValueDefinition>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub doc: Option<String>,
+    /// Stable identifiers of the module and its definitions, if assigned
+    #[serde(rename = "nodeIds", default, skip_serializing_if = "NodeIds::is_empty")]
+    pub ids: NodeIds,
+}
+
+impl ModuleDefinition {
+    /// Derive the public interface of this module.
+    ///
+    /// Private types and values are left out. Custom types whose constructors
+    /// are private, and incomplete types, become opaque.
+    pub fn to_specification(&self) -> ModuleSpecification {
+        let types = self
+            .types
+            .iter()
+            .filter(|(_, def)| def.access == Access::Public)
+            .map(|(name, def)| (name.clone(), type_specification(&def.value)))
+            .collect();
+        let values = self
+            .values
+            .iter()
+            .filter(|(_, def)| def.access == Access::Public)
+            .map(|(name, def)| {
+                let spec = ValueSpecification {
+                    inputs: def
+                        .value
+                        .input_types
+                        .iter()
+                        .map(|(name, entry)| (name.clone(), entry.input_type.clone()))
+                        .collect(),
+                    output: def.value.output_type.clone(),
+                };
+                (name.clone(), spec)
+            })
+            .collect();
+        ModuleSpecification {
+            types,
+            values,
+            doc: self.doc.clone(),
+        }
+    }
+}
+
+fn type_specification(def: &TypeDefinition) -> TypeSpecification {
+    match def {
+        TypeDefinition::TypeAliasDefinition {
+            type_params,
+            type_expr,
+        } => TypeSpecification::TypeAliasSpecification {
+            type_params: type_params.clone(),
+            type_expr: type_expr.clone(),
+        },
+        TypeDefinition::CustomTypeDefinition {
+            type_params,
+            constructors,
+        } if constructors.access == Access::Public => TypeSpecification::CustomTypeSpecification {
+            type_params: type_params.clone(),
+            constructors: constructors
+                .value
+                .iter()
+                .map(|ctor| ConstructorSpecification {
+                    name: ctor.name.clone(),
+                    args: ctor
+                        .args
+                        .iter()
+                        .map(|arg| ConstructorArgSpec {
+                            name: arg.name.clone(),
+                            arg_type: arg.arg_type.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        },
+        TypeDefinition::CustomTypeDefinition { type_params, .. }
+        | TypeDefinition::IncompleteTypeDefinition { type_params, .. } => {
+            TypeSpecification::OpaqueTypeSpecification {
+                type_params: type_params.clone(),
+            }
+        }
+    }
 }
@@ -0,0 +1,343 @@
+//! Stable node identifiers for Morphir IR V4
+//!
+//! FQNames change whenever a module or definition is renamed, so data keyed by
+//! them (decorations, test coverage, external annotations) is lost on every
+//! refactor. A [`NodeId`] is assigned once to a module, type, or value and is
+//! kept by the renames in this module, giving such data a key that survives.
+//!
+//! Identifiers are optional. They are stored in the module's [`NodeIds`]
+//! attributes and assigned on request with
+//! [`PackageDefinition::assign_node_ids`], either randomly or derived from a
+//! hash of the FQName the node had at the time, so repeated builds of the same
+//! model agree on them.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::module::ModuleDefinition;
+use super::package::PackageDefinition;
+
+/// Namespace of derived identifiers; changing it would change every derived id
+const NAMESPACE: Uuid = Uuid::from_bytes([
+    0x5b, 0x1a, 0x3c, 0x8e, 0x2f, 0x47, 0x5d, 0x0b, 0x9a, 0x61, 0x0e, 0x4d, 0x7c, 0x28, 0xf3, 0x95,
+]);
+
+/// Identifier of a module or definition that outlives renames
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(transparent)]
+pub struct NodeId(String);
+
+impl NodeId {
+    /// A new random identifier
+    pub fn random() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// Identifier derived from `key`, the same every time for the same key
+    pub fn derived(key: &str) -> Self {
+        Self(Uuid::new_v5(&NAMESPACE, key.as_bytes()).to_string())
+    }
+
+    /// Identifier as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<String> for NodeId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+/// How [`PackageDefinition::assign_node_ids`] creates identifiers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdStrategy {
+    /// Hash of the node's kind and FQName, reproducible across builds
+    #[default]
+    Derived,
+    /// Random UUID, revealing nothing about the node
+    Random,
+}
+
+/// Identifiers of a module and its definitions, keyed by definition name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeIds {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module: Option<NodeId>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub types: IndexMap<String, NodeId>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub values: IndexMap<String, NodeId>,
+}
+
+impl NodeIds {
+    /// Whether no identifier is assigned
+    pub fn is_empty(&self) -> bool {
+        self.module.is_none() && self.types.is_empty() && self.values.is_empty()
+    }
+}
+
+/// Kind of node an identifier belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Module,
+    Type,
+    Value,
+}
+
+/// Where a node currently is in a package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeLocation {
+    pub kind: NodeKind,
+    pub module: String,
+    /// Definition name; `None` for modules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Move the entry `old` of `map` to `new`, keeping its position
+fn rename_key<V>(map: &mut IndexMap<String, V>, old: &str, new: &str) -> bool {
+    if old == new || map.contains_key(new) {
+        return false;
+    }
+    let Some((index, _, value)) = map.shift_remove_full(old) else {
+        return false;
+    };
+    map.shift_insert(index, new.to_string(), value);
+    true
+}
+
+/// Name of the definition with identifier `id` in `ids`
+fn name_of<'a>(ids: &'a IndexMap<String, NodeId>, id: &NodeId) -> Option<&'a String> {
+    ids.iter()
+        .find(|(_, node)| *node == id)
+        .map(|(name, _)| name)
+}
+
+impl ModuleDefinition {
+    /// Rename the type `old` to `new`, keeping its identifier.
+    ///
+    /// Returns false, changing nothing, when there is no type `old` or a type
+    /// `new` already exists. References to the type are not rewritten.
+    pub fn rename_type(&mut self, old: &str, new: &str) -> bool {
+        if !rename_key(&mut self.types, old, new) {
+            return false;
+        }
+        rename_key(&mut self.ids.types, old, new);
+        true
+    }
+
+    /// Rename the value `old` to `new`, keeping its identifier.
+    ///
+    /// Returns false, changing nothing, when there is no value `old` or a value
+    /// `new` already exists. References to the value are not rewritten.
+    pub fn rename_value(&mut self, old: &str, new: &str) -> bool {
+        if !rename_key(&mut self.values, old, new) {
+            return false;
+        }
+        rename_key(&mut self.ids.values, old, new);
+        true
+    }
+}
+
+impl PackageDefinition {
+    /// Give every module, type, and value of the package without an identifier
+    /// one, returning how many were assigned. `package` is the package's name,
+    /// used to derive identifiers.
+    pub fn assign_node_ids(&mut self, package: &str, strategy: IdStrategy) -> usize {
+        let new_id = |kind: &str, key: String| match strategy {
+            IdStrategy::Derived => NodeId::derived(&format!("{}:{}", kind, key)),
+            IdStrategy::Random => NodeId::random(),
+        };
+        let mut assigned = 0;
+        for (module_name, module) in &mut self.modules {
+            let module = &mut module.value;
+            let prefix = format!("{}:{}", package, module_name);
+            if module.ids.module.is_none() {
+                module.ids.module = Some(new_id("module", prefix.clone()));
+                assigned += 1;
+            }
+            for name in module.types.keys() {
+                if !module.ids.types.contains_key(name) {
+                    let id = new_id("type", format!("{}#{}", prefix, name));
+                    module.ids.types.insert(name.clone(), id);
+                    assigned += 1;
+                }
+            }
+            for name in module.values.keys() {
+                if !module.ids.values.contains_key(name) {
+                    let id = new_id("value", format!("{}#{}", prefix, name));
+                    module.ids.values.insert(name.clone(), id);
+                    assigned += 1;
+                }
+            }
+            // Drop identifiers of definitions that no longer exist
+            module
+                .ids
+                .types
+                .retain(|name, _| module.types.contains_key(name));
+            module
+                .ids
+                .values
+                .retain(|name, _| module.values.contains_key(name));
+        }
+        assigned
+    }
+
+    /// Where the node with identifier `id` currently is
+    pub fn find_node(&self, id: &NodeId) -> Option<NodeLocation> {
+        self.modules.iter().find_map(|(module_name, module)| {
+            let ids = &module.value.ids;
+            let at = |kind, name: Option<&String>| NodeLocation {
+                kind,
+                module: module_name.clone(),
+                name: name.cloned(),
+            };
+            if ids.module.as_ref() == Some(id) {
+                return Some(at(NodeKind::Module, None));
+            }
+            name_of(&ids.types, id)
+                .map(|name| at(NodeKind::Type, Some(name)))
+                .or_else(|| name_of(&ids.values, id).map(|name| at(NodeKind::Value, Some(name))))
+        })
+    }
+
+    /// Rename the module `old` to `new`, keeping its identifiers and those of
+    /// its definitions. Returns false, changing nothing, when there is no
+    /// module `old` or a module `new` already exists.
+    pub fn rename_module(&mut self, old: &str, new: &str) -> bool {
+        rename_key(&mut self.modules, old, new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::v4::{
+        AccessControlled, Literal, Type, TypeDefinition, Value, ValueAttributes, ValueDefinition,
+    };
+
+    fn package() -> PackageDefinition {
+        let mut module = ModuleDefinition {
+            types: IndexMap::new(),
+            values: IndexMap::new(),
+            doc: None,
+            ids: NodeIds::default(),
+        };
+        module.types.insert(
+            "amount".to_string(),
+            AccessControlled::public(TypeDefinition::TypeAliasDefinition {
+                type_params: vec![],
+                type_expr: Type::Unit(Default::default()),
+            }),
+        );
+        for name in ["total", "fee"] {
+            module.values.insert(
+                name.to_string(),
+                AccessControlled::public(ValueDefinition::new(
+                    vec![],
+                    Type::Unit(Default::default()),
+                    Value::Literal(ValueAttributes::default(), Literal::Integer(1)),
+                )),
+            );
+        }
+        let mut modules = IndexMap::new();
+        modules.insert("orders".to_string(), AccessControlled::public(module));
+        PackageDefinition { modules }
+    }
+
+    #[test]
+    fn test_assigned_ids_are_stable_and_serialized() {
+        let mut derived = package();
+        assert_eq!(
+            derived.assign_node_ids("my-org/shop", IdStrategy::Derived),
+            4
+        );
+        assert_eq!(
+            derived.assign_node_ids("my-org/shop", IdStrategy::Derived),
+            0
+        );
+        let mut again = package();
+        again.assign_node_ids("my-org/shop", IdStrategy::Derived);
+        assert_eq!(derived, again);
+
+        let mut random = package();
+        random.assign_node_ids("my-org/shop", IdStrategy::Random);
+        let ids = &random.modules["orders"].value.ids;
+        assert_ne!(ids, &derived.modules["orders"].value.ids);
+        assert_ne!(ids.values["total"], ids.values["fee"]);
+
+        let json = serde_json::to_value(&derived).unwrap();
+        let total = derived.modules["orders"].value.ids.values["total"].clone();
+        assert_eq!(
+            json["modules"]["orders"]["value"]["nodeIds"]["values"]["total"],
+            total.as_str()
+        );
+        let back: PackageDefinition = serde_json::from_value(json).unwrap();
+        assert_eq!(back, derived);
+        // Packages without identifiers serialize as before
+        assert!(
+            serde_json::to_value(package()).unwrap()["modules"]["orders"]["value"]
+                .get("nodeIds")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_renames_keep_ids() {
+        let mut package = package();
+        package.assign_node_ids("my-org/shop", IdStrategy::Derived);
+        let ids = package.modules["orders"].value.ids.clone();
+        let total = ids.values["total"].clone();
+        let module_id = ids.module.clone().unwrap();
+
+        let orders = &mut package.modules["orders"].value;
+        assert!(orders.rename_value("total", "grand-total"));
+        assert!(!orders.rename_value("total", "sum"));
+        assert!(!orders.rename_value("fee", "grand-total"));
+        assert!(orders.rename_type("amount", "money"));
+        assert_eq!(
+            orders.values.keys().collect::<Vec<_>>(),
+            ["grand-total", "fee"]
+        );
+        assert!(package.rename_module("orders", "sales"));
+
+        assert_eq!(
+            package.find_node(&total),
+            Some(NodeLocation {
+                kind: NodeKind::Value,
+                module: "sales".to_string(),
+                name: Some("grand-total".to_string()),
+            })
+        );
+        assert_eq!(
+            package.find_node(&ids.types["amount"]).unwrap().name,
+            Some("money".to_string())
+        );
+        assert_eq!(
+            package.find_node(&module_id).unwrap().kind,
+            NodeKind::Module
+        );
+        assert_eq!(package.find_node(&NodeId::random()), None);
+        // Renamed nodes keep their identifiers when ids are assigned again
+        assert_eq!(
+            package.assign_node_ids("my-org/shop", IdStrategy::Derived),
+            0
+        );
+    }
+}
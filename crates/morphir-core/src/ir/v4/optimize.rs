@@ -0,0 +1,1001 @@
+//! Optimization passes for Morphir IR V4 values.
+//!
+//! A [`PassManager`] runs a sequence of [`OptimizationPass`]es over the value
+//! definitions of a package, round after round, until a round changes nothing
+//! or the limit on rounds is reached. Three passes are provided:
+//!
+//! - [`Inlining`] replaces references to small, non-recursive definitions of
+//!   the same package with their bodies.
+//! - [`BetaReduction`] replaces a lambda applied directly to an argument with
+//!   its body, the argument substituted for the parameter.
+//! - [`ConstantFolding`] replaces SDK arithmetic, comparisons, and boolean
+//!   operators applied to literals with their result, and conditionals on a
+//!   literal condition with the branch taken.
+//!
+//! The passes feed each other: an inlined function becomes a lambda, the
+//! lambda is reduced once applied, and the literal arguments it received can
+//! then be folded. Passes only rewrite what they can show to be equivalent;
+//! integer overflow, division by zero, and name capture are left alone.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let report = PassManager::standard().run(&mut ir_file.distribution);
+//! for pass in &report.passes {
+//!     println!("{}: {} rewrites", pass.pass, pass.rewrites);
+//! }
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use super::access::Access;
+use super::attributes::ValueAttributes;
+use super::distribution::Distribution;
+use super::literal::Literal;
+use super::package::PackageDefinition;
+use super::pattern::Pattern;
+use super::value::{Value, ValueBody, ValueDefinition};
+use crate::naming::{FQName, Name, PackageName, Path};
+
+const ADD: &str = "morphir/sdk:basics#add";
+const SUBTRACT: &str = "morphir/sdk:basics#subtract";
+const MULTIPLY: &str = "morphir/sdk:basics#multiply";
+const DIVIDE: &str = "morphir/sdk:basics#divide";
+const INTEGER_DIVIDE: &str = "morphir/sdk:basics#integer-divide";
+const NEGATE: &str = "morphir/sdk:basics#negate";
+const EQUAL: &str = "morphir/sdk:basics#equal";
+const NOT_EQUAL: &str = "morphir/sdk:basics#not-equal";
+const LESS_THAN: &str = "morphir/sdk:basics#less-than";
+const GREATER_THAN: &str = "morphir/sdk:basics#greater-than";
+const LESS_THAN_OR_EQUAL: &str = "morphir/sdk:basics#less-than-or-equal";
+const GREATER_THAN_OR_EQUAL: &str = "morphir/sdk:basics#greater-than-or-equal";
+const NOT: &str = "morphir/sdk:basics#not";
+const AND: &str = "morphir/sdk:basics#and";
+const OR: &str = "morphir/sdk:basics#or";
+const XOR: &str = "morphir/sdk:basics#xor";
+const APPEND: &str = "morphir/sdk:basics#append";
+const STRING_APPEND: &str = "morphir/sdk:string#append";
+
+/// Rounds run by default before the manager gives up on a fixed point.
+const DEFAULT_MAX_ROUNDS: usize = 8;
+
+/// Largest body, in expression nodes, inlined by default.
+const DEFAULT_INLINE_SIZE: usize = 12;
+
+/// A rewrite of the value definitions of a package.
+pub trait OptimizationPass {
+    /// Name the pass is reported under.
+    fn name(&self) -> &'static str;
+
+    /// Rewrite the definitions of `package`, named `package_name`, and
+    /// return the number of expressions rewritten.
+    fn run(&self, package_name: &PackageName, package: &mut PackageDefinition) -> usize;
+}
+
+/// Rewrites made by one pass over all rounds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PassStats {
+    pub pass: String,
+    pub rewrites: usize,
+}
+
+/// Outcome of running a [`PassManager`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OptimizationReport {
+    /// Rounds run, including the last one that changed nothing
+    pub rounds: usize,
+    /// Rewrites per pass, in the order the passes ran
+    pub passes: Vec<PassStats>,
+}
+
+impl OptimizationReport {
+    /// Rewrites made by all passes.
+    pub fn total(&self) -> usize {
+        self.passes.iter().map(|p| p.rewrites).sum()
+    }
+}
+
+/// Runs optimization passes to a fixed point.
+pub struct PassManager {
+    passes: Vec<Box<dyn OptimizationPass>>,
+    max_rounds: usize,
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl PassManager {
+    /// A manager with no passes.
+    pub fn new() -> Self {
+        Self {
+            passes: Vec::new(),
+            max_rounds: DEFAULT_MAX_ROUNDS,
+        }
+    }
+
+    /// A manager with inlining, beta reduction, and constant folding, in
+    /// that order.
+    pub fn standard() -> Self {
+        Self::new()
+            .with_pass(Inlining::default())
+            .with_pass(BetaReduction)
+            .with_pass(ConstantFolding)
+    }
+
+    /// Run `pass` after the passes added before it.
+    pub fn with_pass(mut self, pass: impl OptimizationPass + 'static) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Stop after `max_rounds` rounds even if the last one changed something.
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    /// Optimize the package definition of `distribution`; Specs
+    /// distributions have none and are left as they are.
+    pub fn run(&self, distribution: &mut Distribution) -> OptimizationReport {
+        match distribution {
+            Distribution::Library(lib) => self.run_package(&lib.package_name, &mut lib.def),
+            Distribution::Application(app) => self.run_package(&app.package_name, &mut app.def),
+            Distribution::Specs(_) => self.report(0, &[]),
+        }
+    }
+
+    /// Optimize `package`, named `package_name`.
+    pub fn run_package(
+        &self,
+        package_name: &PackageName,
+        package: &mut PackageDefinition,
+    ) -> OptimizationReport {
+        let mut rewrites = vec![0; self.passes.len()];
+        let mut rounds = 0;
+        while rounds < self.max_rounds {
+            rounds += 1;
+            let mut changed = false;
+            for (pass, count) in self.passes.iter().zip(rewrites.iter_mut()) {
+                let made = pass.run(package_name, package);
+                *count += made;
+                changed |= made > 0;
+            }
+            if !changed {
+                break;
+            }
+        }
+        self.report(rounds, &rewrites)
+    }
+
+    fn report(&self, rounds: usize, rewrites: &[usize]) -> OptimizationReport {
+        OptimizationReport {
+            rounds,
+            passes: self
+                .passes
+                .iter()
+                .enumerate()
+                .map(|(i, pass)| PassStats {
+                    pass: pass.name().to_string(),
+                    rewrites: rewrites.get(i).copied().unwrap_or(0),
+                })
+                .collect(),
+        }
+    }
+}
+
+// =============================================================================
+// Constant folding
+// =============================================================================
+
+/// Evaluates SDK operators applied to literals.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConstantFolding;
+
+impl OptimizationPass for ConstantFolding {
+    fn name(&self) -> &'static str {
+        "constant-folding"
+    }
+
+    fn run(&self, _package_name: &PackageName, package: &mut PackageDefinition) -> usize {
+        rewrite_package(package, &mut |_, value| match fold(value) {
+            Some(folded) => {
+                *value = folded;
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+fn fold(value: &Value) -> Option<Value> {
+    match value {
+        Value::IfThenElse(_, condition, then_branch, else_branch) => match condition.as_ref() {
+            Value::Literal(_, Literal::Bool(true)) => Some(then_branch.as_ref().clone()),
+            Value::Literal(_, Literal::Bool(false)) => Some(else_branch.as_ref().clone()),
+            _ => None,
+        },
+        Value::Apply(attrs, function, argument) => match function.as_ref() {
+            Value::Reference(_, op) => {
+                let Value::Literal(_, arg) = argument.as_ref() else {
+                    return None;
+                };
+                fold_unary(&key(op), arg).map(|lit| Value::Literal(attrs.clone(), lit))
+            }
+            Value::Apply(_, inner, left) => {
+                let Value::Reference(_, op) = inner.as_ref() else {
+                    return None;
+                };
+                let op = key(op);
+                let Value::Literal(_, left) = left.as_ref() else {
+                    return None;
+                };
+                match argument.as_ref() {
+                    Value::Literal(_, right) => {
+                        fold_binary(&op, left, right).map(|lit| Value::Literal(attrs.clone(), lit))
+                    }
+                    // `True && x` is `x` and `False && x` is `False`, likewise for `||`
+                    right => match (op.as_str(), left) {
+                        (AND, Literal::Bool(true)) | (OR, Literal::Bool(false)) => {
+                            Some(right.clone())
+                        }
+                        (AND, Literal::Bool(false)) | (OR, Literal::Bool(true)) => {
+                            Some(Value::Literal(attrs.clone(), left.clone()))
+                        }
+                        _ => None,
+                    },
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn fold_unary(op: &str, arg: &Literal) -> Option<Literal> {
+    match (op, arg) {
+        (NOT, Literal::Bool(b)) => Some(Literal::Bool(!b)),
+        (NEGATE, Literal::Integer(n)) => n.checked_neg().map(Literal::Integer),
+        (NEGATE, Literal::Float(x)) => Some(Literal::Float(-x)),
+        _ => None,
+    }
+}
+
+fn fold_binary(op: &str, left: &Literal, right: &Literal) -> Option<Literal> {
+    use Literal::{Bool, Float, Integer};
+    match (op, left, right) {
+        (ADD, Integer(a), Integer(b)) => a.checked_add(*b).map(Integer),
+        (SUBTRACT, Integer(a), Integer(b)) => a.checked_sub(*b).map(Integer),
+        (MULTIPLY, Integer(a), Integer(b)) => a.checked_mul(*b).map(Integer),
+        (INTEGER_DIVIDE, Integer(a), Integer(b)) if *b != 0 => a.checked_div(*b).map(Integer),
+        (ADD, Float(a), Float(b)) => finite(a + b),
+        (SUBTRACT, Float(a), Float(b)) => finite(a - b),
+        (MULTIPLY, Float(a), Float(b)) => finite(a * b),
+        (DIVIDE, Float(a), Float(b)) if *b != 0.0 => finite(a / b),
+        (APPEND | STRING_APPEND, Literal::String(a), Literal::String(b)) => {
+            Some(Literal::String(format!("{}{}", a, b)))
+        }
+        (AND, Bool(a), Bool(b)) => Some(Bool(*a && *b)),
+        (OR, Bool(a), Bool(b)) => Some(Bool(*a || *b)),
+        (XOR, Bool(a), Bool(b)) => Some(Bool(a != b)),
+        (EQUAL, a, b) if comparable(a, b) => Some(Bool(a == b)),
+        (NOT_EQUAL, a, b) if comparable(a, b) => Some(Bool(a != b)),
+        (LESS_THAN, a, b) => order(a, b).map(|o| Bool(o.is_lt())),
+        (GREATER_THAN, a, b) => order(a, b).map(|o| Bool(o.is_gt())),
+        (LESS_THAN_OR_EQUAL, a, b) => order(a, b).map(|o| Bool(o.is_le())),
+        (GREATER_THAN_OR_EQUAL, a, b) => order(a, b).map(|o| Bool(o.is_ge())),
+        _ => None,
+    }
+}
+
+fn finite(x: f64) -> Option<Literal> {
+    x.is_finite().then_some(Literal::Float(x))
+}
+
+/// Whether literal equality decides equality of the values. Decimals are
+/// not compared, since `1.0` and `1.00` are equal.
+fn comparable(a: &Literal, b: &Literal) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b) && !matches!(a, Literal::Decimal(_))
+}
+
+fn order(a: &Literal, b: &Literal) -> Option<Ordering> {
+    match (a, b) {
+        (Literal::Integer(a), Literal::Integer(b)) => Some(a.cmp(b)),
+        (Literal::Float(a), Literal::Float(b)) => a.partial_cmp(b),
+        (Literal::String(a), Literal::String(b)) => Some(a.cmp(b)),
+        (Literal::Char(a), Literal::Char(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+// =============================================================================
+// Beta reduction
+// =============================================================================
+
+/// Reduces lambdas applied directly to an argument.
+///
+/// `(\x -> body) arg` becomes `body` with `arg` in place of `x` when `arg` is
+/// a literal, variable, or reference, or when `x` is used at most once, so no
+/// work is duplicated. Lambdas with other patterns are left alone.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BetaReduction;
+
+impl OptimizationPass for BetaReduction {
+    fn name(&self) -> &'static str {
+        "beta-reduction"
+    }
+
+    fn run(&self, _package_name: &PackageName, package: &mut PackageDefinition) -> usize {
+        rewrite_package(package, &mut |_, value| match reduce(value) {
+            Some(reduced) => {
+                *value = reduced;
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+fn reduce(value: &Value) -> Option<Value> {
+    let Value::Apply(_, function, argument) = value else {
+        return None;
+    };
+    let Value::Lambda(_, pattern, body) = function.as_ref() else {
+        return None;
+    };
+    match pattern {
+        Pattern::WildcardPattern(_) => Some(body.as_ref().clone()),
+        Pattern::UnitPattern(_) if matches!(argument.as_ref(), Value::Unit(_)) => {
+            Some(body.as_ref().clone())
+        }
+        Pattern::AsPattern(_, inner, name)
+            if matches!(inner.as_ref(), Pattern::WildcardPattern(_)) =>
+        {
+            let trivial = matches!(
+                argument.as_ref(),
+                Value::Literal(..)
+                    | Value::Variable(..)
+                    | Value::Reference(..)
+                    | Value::Constructor(..)
+                    | Value::Unit(_)
+            );
+            if !trivial && occurrences(body, name) > 1 {
+                return None;
+            }
+            // A binder in the body named like a variable of the argument
+            // would capture it once substituted
+            let mut bound = HashSet::new();
+            binders(body, &mut bound);
+            let mut used = HashSet::new();
+            variables(argument, &mut used);
+            if !bound.is_disjoint(&used) {
+                return None;
+            }
+            let mut reduced = body.as_ref().clone();
+            substitute(&mut reduced, name, argument).then_some(reduced)
+        }
+        _ => None,
+    }
+}
+
+/// Replace the free occurrences of `name` in `value` with `replacement`.
+/// Returns false, leaving `value` partly rewritten, where scoping is too
+/// intricate to substitute safely.
+fn substitute(value: &mut Value, name: &Name, replacement: &Value) -> bool {
+    match value {
+        Value::Variable(_, n) if n == name => {
+            *value = replacement.clone();
+            true
+        }
+        Value::Lambda(_, pattern, body) => {
+            binds(pattern, name) || substitute(body, name, replacement)
+        }
+        Value::LetDefinition(_, n, def, body) => {
+            n != name
+                && substitute_definition(def, name, replacement)
+                && substitute(body, name, replacement)
+        }
+        Value::LetRecursion(_, bindings, body) => {
+            bindings.iter().all(|binding| &binding.0 != name)
+                && bindings
+                    .iter_mut()
+                    .all(|binding| substitute_definition(&mut binding.1, name, replacement))
+                && substitute(body, name, replacement)
+        }
+        Value::Destructure(_, pattern, bound, body) => {
+            substitute(bound, name, replacement)
+                && (binds(pattern, name) || substitute(body, name, replacement))
+        }
+        Value::PatternMatch(_, subject, cases) => {
+            substitute(subject, name, replacement)
+                && cases
+                    .iter_mut()
+                    .all(|case| binds(&case.0, name) || substitute(&mut case.1, name, replacement))
+        }
+        _ => children_mut(value)
+            .into_iter()
+            .all(|child| substitute(child, name, replacement)),
+    }
+}
+
+fn substitute_definition(def: &mut ValueDefinition, name: &Name, replacement: &Value) -> bool {
+    if input_names(def).any(|input| &input == name) {
+        return true;
+    }
+    match &mut def.body {
+        ValueBody::Expression(body) => substitute(body, name, replacement),
+        _ => true,
+    }
+}
+
+/// Uses of the variable `name` anywhere in `value`, shadowed or not.
+fn occurrences(value: &Value, name: &Name) -> usize {
+    let own = usize::from(matches!(value, Value::Variable(_, n) if n == name));
+    own + children(value)
+        .into_iter()
+        .map(|child| occurrences(child, name))
+        .sum::<usize>()
+}
+
+/// Names of the variables used in `value`.
+fn variables(value: &Value, names: &mut HashSet<Name>) {
+    if let Value::Variable(_, name) = value {
+        names.insert(name.clone());
+    }
+    for child in children(value) {
+        variables(child, names);
+    }
+}
+
+/// Names bound anywhere in `value`.
+fn binders(value: &Value, names: &mut HashSet<Name>) {
+    match value {
+        Value::Lambda(_, pattern, _) | Value::Destructure(_, pattern, _, _) => {
+            pattern_names(pattern, names)
+        }
+        Value::LetDefinition(_, name, def, _) => {
+            names.insert(name.clone());
+            names.extend(input_names(def));
+        }
+        Value::LetRecursion(_, bindings, _) => {
+            for binding in bindings {
+                names.insert(binding.0.clone());
+                names.extend(input_names(&binding.1));
+            }
+        }
+        Value::PatternMatch(_, _, cases) => {
+            for case in cases {
+                pattern_names(&case.0, names);
+            }
+        }
+        _ => {}
+    }
+    for child in children(value) {
+        binders(child, names);
+    }
+}
+
+fn pattern_names(pattern: &Pattern, names: &mut HashSet<Name>) {
+    match pattern {
+        Pattern::AsPattern(_, inner, name) => {
+            names.insert(name.clone());
+            pattern_names(inner, names);
+        }
+        Pattern::TuplePattern(_, elements) | Pattern::ConstructorPattern(_, _, elements) => {
+            for element in elements {
+                pattern_names(element, names);
+            }
+        }
+        Pattern::HeadTailPattern(_, head, tail) => {
+            pattern_names(head, names);
+            pattern_names(tail, names);
+        }
+        _ => {}
+    }
+}
+
+fn binds(pattern: &Pattern, name: &Name) -> bool {
+    let mut names = HashSet::new();
+    pattern_names(pattern, &mut names);
+    names.contains(name)
+}
+
+fn input_names(def: &ValueDefinition) -> impl Iterator<Item = Name> + '_ {
+    def.input_types
+        .keys()
+        .map(|input| Name::from(input.as_str()))
+}
+
+// =============================================================================
+// Inlining
+// =============================================================================
+
+/// Inlines small, non-recursive definitions of the package.
+///
+/// A reference to a definition whose body has at most `max_size` expression
+/// nodes and cannot reach itself through other definitions is replaced by
+/// the body, wrapped in a lambda per input. Bodies that refer to private
+/// members of their module are only inlined within that module.
+#[derive(Debug, Clone, Copy)]
+pub struct Inlining {
+    pub max_size: usize,
+}
+
+impl Default for Inlining {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_INLINE_SIZE,
+        }
+    }
+}
+
+/// A definition that can replace references to it.
+struct Inlinable {
+    module: String,
+    inputs: Vec<Name>,
+    body: Value,
+    /// Whether the body refers to members of its module that other modules
+    /// cannot see
+    module_private: bool,
+}
+
+impl OptimizationPass for Inlining {
+    fn name(&self) -> &'static str {
+        "inlining"
+    }
+
+    fn run(&self, package_name: &PackageName, package: &mut PackageDefinition) -> usize {
+        let candidates = self.candidates(&package_name.0, package);
+        if candidates.is_empty() {
+            return 0;
+        }
+        rewrite_package(package, &mut |module, value| {
+            let Value::Reference(attrs, fqname) = value else {
+                return false;
+            };
+            let Some(inlinable) = candidates.get(&key(fqname)) else {
+                return false;
+            };
+            if inlinable.module_private && inlinable.module != module {
+                return false;
+            }
+            *value = inlinable.inputs.iter().rev().enumerate().fold(
+                inlinable.body.clone(),
+                |body, (i, input)| {
+                    // The outermost lambda keeps the reference's attributes
+                    let lambda_attrs = if i + 1 == inlinable.inputs.len() {
+                        attrs.clone()
+                    } else {
+                        ValueAttributes::default()
+                    };
+                    Value::lambda(
+                        lambda_attrs,
+                        Pattern::as_pattern(
+                            ValueAttributes::default(),
+                            Pattern::wildcard(ValueAttributes::default()),
+                            input.clone(),
+                        ),
+                        body,
+                    )
+                },
+            );
+            true
+        })
+    }
+}
+
+impl Inlining {
+    fn candidates(&self, package: &Path, def: &PackageDefinition) -> HashMap<String, Inlinable> {
+        // References from each definition to other members of the package
+        let mut references: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut private = HashSet::new();
+        for (module_key, module) in &def.modules {
+            for (value_key, value) in &module.value.values {
+                let member = FQName::member_key(package, module_key, value_key);
+                if value.access == Access::Private {
+                    private.insert(member.clone());
+                }
+                let mut refs = HashSet::new();
+                if let ValueBody::Expression(body) = &value.value.body {
+                    local_references(body, package, &mut refs);
+                }
+                references.insert(member, refs);
+            }
+        }
+
+        let mut candidates = HashMap::new();
+        for (module_key, module) in &def.modules {
+            let module_path = Path::new(module_key);
+            for (value_key, value) in &module.value.values {
+                let ValueBody::Expression(body) = &value.value.body else {
+                    continue;
+                };
+                let member = FQName::member_key(package, module_key, value_key);
+                if size(body) > self.max_size || reaches(&references, &member, &member) {
+                    continue;
+                }
+                let module_private = references[&member]
+                    .iter()
+                    .any(|target| private.contains(target) || !references.contains_key(target))
+                    || constructs_in(body, package, &module_path);
+                candidates.insert(
+                    member,
+                    Inlinable {
+                        module: module_key.clone(),
+                        inputs: input_names(&value.value).collect(),
+                        body: body.clone(),
+                        module_private,
+                    },
+                );
+            }
+        }
+        candidates
+    }
+}
+
+/// Whether `to` can be reached from the references of `from`.
+fn reaches(references: &HashMap<String, HashSet<String>>, from: &str, to: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut stack: Vec<&str> = vec![from];
+    while let Some(member) = stack.pop() {
+        for target in references.get(member).into_iter().flatten() {
+            if target == to {
+                return true;
+            }
+            if seen.insert(target.as_str()) {
+                stack.push(target);
+            }
+        }
+    }
+    false
+}
+
+/// Keys of the members of `package` referenced in `value`.
+fn local_references(value: &Value, package: &Path, refs: &mut HashSet<String>) {
+    if let Value::Reference(_, fqname) = value
+        && &fqname.package_path == package
+    {
+        refs.insert(key(fqname));
+    }
+    for child in children(value) {
+        local_references(child, package, refs);
+    }
+}
+
+/// Whether `value` uses a constructor of `module`, whose constructors may be
+/// private to it.
+fn constructs_in(value: &Value, package: &Path, module: &Path) -> bool {
+    if let Value::Constructor(_, fqname) = value
+        && &fqname.package_path == package
+        && &fqname.module_path == module
+    {
+        return true;
+    }
+    children(value)
+        .into_iter()
+        .any(|child| constructs_in(child, package, module))
+}
+
+/// Number of expression nodes in `value`.
+fn size(value: &Value) -> usize {
+    1 + children(value).into_iter().map(size).sum::<usize>()
+}
+
+// =============================================================================
+// Walking
+// =============================================================================
+
+/// Rewrite every expression body of `package` bottom-up with `rewrite`,
+/// which is given the module key and an expression and returns whether it
+/// replaced the expression. Returns the number of replacements.
+fn rewrite_package(
+    package: &mut PackageDefinition,
+    rewrite: &mut dyn FnMut(&str, &mut Value) -> bool,
+) -> usize {
+    let mut count = 0;
+    for (module_key, module) in package.modules.iter_mut() {
+        for value in module.value.values.values_mut() {
+            if let ValueBody::Expression(body) = &mut value.value.body {
+                count += rewrite_bottom_up(body, &mut |value| rewrite(module_key, value));
+            }
+        }
+    }
+    count
+}
+
+fn rewrite_bottom_up(value: &mut Value, rewrite: &mut dyn FnMut(&mut Value) -> bool) -> usize {
+    let mut count = 0;
+    for child in children_mut(value) {
+        count += rewrite_bottom_up(child, rewrite);
+    }
+    if rewrite(value) {
+        count += 1;
+    }
+    count
+}
+
+/// Direct subexpressions of `value`, including the bodies of let definitions.
+pub(super) fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Tuple(_, elements) | Value::List(_, elements) => elements.iter().collect(),
+        Value::Record(_, fields) => fields.iter().map(|field| &field.1).collect(),
+        Value::Field(_, subject, _) => vec![subject],
+        Value::Apply(_, function, argument) => vec![function, argument],
+        Value::Lambda(_, _, body) => vec![body],
+        Value::LetDefinition(_, _, def, body) => {
+            let mut children: Vec<&Value> = expression(def).into_iter().collect();
+            children.push(body);
+            children
+        }
+        Value::LetRecursion(_, bindings, body) => {
+            let mut children: Vec<&Value> = bindings
+                .iter()
+                .filter_map(|binding| expression(&binding.1))
+                .collect();
+            children.push(body);
+            children
+        }
+        Value::Destructure(_, _, bound, body) => vec![bound, body],
+        Value::IfThenElse(_, condition, then_branch, else_branch) => {
+            vec![condition, then_branch, else_branch]
+        }
+        Value::PatternMatch(_, subject, cases) => {
+            let mut children: Vec<&Value> = vec![subject];
+            children.extend(cases.iter().map(|case| &case.1));
+            children
+        }
+        Value::UpdateRecord(_, subject, fields) => {
+            let mut children: Vec<&Value> = vec![subject];
+            children.extend(fields.iter().map(|field| &field.1));
+            children
+        }
+        Value::Literal(..)
+        | Value::Constructor(..)
+        | Value::Variable(..)
+        | Value::Reference(..)
+        | Value::FieldFunction(..)
+        | Value::Unit(_)
+        | Value::Hole(..)
+        | Value::Native(..)
+        | Value::External(..) => Vec::new(),
+    }
+}
+
+/// Direct subexpressions of `value`, mutably, in the order of [`children`].
+pub(super) fn children_mut(value: &mut Value) -> Vec<&mut Value> {
+    match value {
+        Value::Tuple(_, elements) | Value::List(_, elements) => elements.iter_mut().collect(),
+        Value::Record(_, fields) => fields.iter_mut().map(|field| &mut field.1).collect(),
+        Value::Field(_, subject, _) => vec![subject],
+        Value::Apply(_, function, argument) => vec![function, argument],
+        Value::Lambda(_, _, body) => vec![body],
+        Value::LetDefinition(_, _, def, body) => {
+            let mut children: Vec<&mut Value> = expression_mut(def).into_iter().collect();
+            children.push(body);
+            children
+        }
+        Value::LetRecursion(_, bindings, body) => {
+            let mut children: Vec<&mut Value> = bindings
+                .iter_mut()
+                .filter_map(|binding| expression_mut(&mut binding.1))
+                .collect();
+            children.push(body);
+            children
+        }
+        Value::Destructure(_, _, bound, body) => vec![bound, body],
+        Value::IfThenElse(_, condition, then_branch, else_branch) => {
+            vec![condition, then_branch, else_branch]
+        }
+        Value::PatternMatch(_, subject, cases) => {
+            let mut children: Vec<&mut Value> = vec![subject];
+            children.extend(cases.iter_mut().map(|case| &mut case.1));
+            children
+        }
+        Value::UpdateRecord(_, subject, fields) => {
+            let mut children: Vec<&mut Value> = vec![subject];
+            children.extend(fields.iter_mut().map(|field| &mut field.1));
+            children
+        }
+        Value::Literal(..)
+        | Value::Constructor(..)
+        | Value::Variable(..)
+        | Value::Reference(..)
+        | Value::FieldFunction(..)
+        | Value::Unit(_)
+        | Value::Hole(..)
+        | Value::Native(..)
+        | Value::External(..) => Vec::new(),
+    }
+}
+
+pub(super) fn expression(def: &ValueDefinition) -> Option<&Value> {
+    match &def.body {
+        ValueBody::Expression(body) => Some(body),
+        _ => None,
+    }
+}
+
+fn expression_mut(def: &mut ValueDefinition) -> Option<&mut Value> {
+    match &mut def.body {
+        ValueBody::Expression(body) => Some(body),
+        _ => None,
+    }
+}
+
+/// Normalized lookup key for an FQName, independent of the name casing and
+/// SDK spelling used in the IR.
+fn key(fqname: &FQName) -> String {
+    fqname.to_normalized_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::*;
+    use super::*;
+
+    fn lambda(name: &str, body: Value) -> Value {
+        Value::lambda(
+            attrs(),
+            Pattern::as_pattern(attrs(), Pattern::wildcard(attrs()), Name::from(name)),
+            body,
+        )
+    }
+
+    fn definition(inputs: &[&str], body: Value) -> ValueDefinition {
+        let inputs = inputs.iter().map(|name| input(name, int())).collect();
+        ValueDefinition::new(inputs, int(), body)
+    }
+
+    fn body<'a>(dist: &'a Distribution, name: &str) -> &'a Value {
+        expression(&dist.definition().unwrap().modules["orders"].value.values[name].value).unwrap()
+    }
+
+    #[test]
+    fn test_constant_folding_stops_at_overflow_and_division_by_zero() {
+        let mut dist = library(vec![
+            (
+                "total",
+                definition(
+                    &[],
+                    call(
+                        reference(ADD),
+                        vec![
+                            call(reference(MULTIPLY), vec![int_lit(6), int_lit(7)]),
+                            int_lit(1),
+                        ],
+                    ),
+                ),
+            ),
+            (
+                "check",
+                definition(
+                    &["x"],
+                    Value::if_then_else(
+                        attrs(),
+                        call(reference(LESS_THAN), vec![int_lit(1), int_lit(2)]),
+                        variable("x"),
+                        int_lit(0),
+                    ),
+                ),
+            ),
+            (
+                "overflow",
+                definition(
+                    &[],
+                    call(reference(ADD), vec![int_lit(i64::MAX), int_lit(1)]),
+                ),
+            ),
+            (
+                "by-zero",
+                definition(
+                    &[],
+                    call(reference(INTEGER_DIVIDE), vec![int_lit(1), int_lit(0)]),
+                ),
+            ),
+        ]);
+        let report = PassManager::new().with_pass(ConstantFolding).run(&mut dist);
+
+        assert_eq!(body(&dist, "total"), &int_lit(43));
+        assert_eq!(body(&dist, "check"), &variable("x"));
+        assert!(matches!(body(&dist, "overflow"), Value::Apply(..)));
+        assert!(matches!(body(&dist, "by-zero"), Value::Apply(..)));
+        assert_eq!(report.total(), 4);
+        assert_eq!(report.rounds, 2);
+    }
+
+    #[test]
+    fn test_beta_reduction_avoids_capture() {
+        // (\x -> x + 1) 2
+        let simple = Value::apply(
+            attrs(),
+            lambda("x", call(reference(ADD), vec![variable("x"), int_lit(1)])),
+            int_lit(2),
+        );
+        assert_eq!(
+            reduce(&simple),
+            Some(call(reference(ADD), vec![int_lit(2), int_lit(1)]))
+        );
+
+        // (\x -> \y -> x) y would turn the free `y` into the parameter
+        let capturing = Value::apply(
+            attrs(),
+            lambda("x", lambda("y", variable("x"))),
+            variable("y"),
+        );
+        assert_eq!(reduce(&capturing), None);
+
+        // (\x -> x + x) (f 1) would evaluate `f 1` twice
+        let duplicating = Value::apply(
+            attrs(),
+            lambda(
+                "x",
+                call(reference(ADD), vec![variable("x"), variable("x")]),
+            ),
+            call(reference("my/pkg:orders#f"), vec![int_lit(1)]),
+        );
+        assert_eq!(reduce(&duplicating), None);
+    }
+
+    #[test]
+    fn test_standard_passes_inline_reduce_and_fold() {
+        let mut dist = library(vec![
+            (
+                "discount",
+                definition(
+                    &["price"],
+                    call(reference(SUBTRACT), vec![variable("price"), int_lit(5)]),
+                ),
+            ),
+            (
+                "sale",
+                definition(
+                    &[],
+                    call(reference("my/pkg:orders#discount"), vec![int_lit(20)]),
+                ),
+            ),
+            (
+                "countdown",
+                definition(
+                    &["n"],
+                    call(
+                        reference("my/pkg:orders#countdown"),
+                        vec![call(reference(SUBTRACT), vec![variable("n"), int_lit(1)])],
+                    ),
+                ),
+            ),
+            (
+                "start",
+                definition(
+                    &[],
+                    call(reference("my/pkg:orders#countdown"), vec![int_lit(3)]),
+                ),
+            ),
+        ]);
+        let report = PassManager::standard().run(&mut dist);
+
+        assert_eq!(body(&dist, "sale"), &int_lit(15));
+        // Recursive definitions are never inlined
+        assert_eq!(
+            body(&dist, "start"),
+            &call(reference("my/pkg:orders#countdown"), vec![int_lit(3)])
+        );
+        let rewrites: Vec<(&str, usize)> = report
+            .passes
+            .iter()
+            .map(|p| (p.pass.as_str(), p.rewrites))
+            .collect();
+        assert_eq!(
+            rewrites,
+            vec![
+                ("inlining", 1),
+                ("beta-reduction", 1),
+                ("constant-folding", 1)
+            ]
+        );
+    }
+}
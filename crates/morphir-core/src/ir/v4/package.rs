@@ -3,21 +3,37 @@
 //! This module contains PackageDefinition, PackageSpecification, and related types.
 
 use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use super::access::AccessControlled;
+use super::access::{Access, AccessControlled};
 use super::module::{ModuleDefinition, ModuleSpecification};
 
 /// Package specification (for dependencies)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PackageSpecification {
     pub modules: IndexMap<String, ModuleSpecification>,
 }
 
 /// Package definition
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PackageDefinition {
     pub modules: IndexMap<String, AccessControlled<ModuleDefinition>>,
 }
+
+impl PackageDefinition {
+    /// Derive the specification dependents compile against: the public
+    /// interface of every public module.
+    pub fn to_specification(&self) -> PackageSpecification {
+        PackageSpecification {
+            modules: self
+                .modules
+                .iter()
+                .filter(|(_, module)| module.access == Access::Public)
+                .map(|(name, module)| (name.clone(), module.value.to_specification()))
+                .collect(),
+        }
+    }
+}
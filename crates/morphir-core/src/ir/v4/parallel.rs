@@ -0,0 +1,188 @@
+//! Walking the modules of a distribution in parallel.
+//!
+//! Module definitions are independent of each other, so analyses that look
+//! at one module at a time, such as lints, type checks, and dead code
+//! passes, can walk them concurrently. [`par_walk_modules`] walks each module
+//! with a visitor of its own and returns the visitors, for the caller to
+//! merge. [`par_transform_values`] changes every value definition of a
+//! package concurrently.
+//!
+//! Both take the number of threads to use, 0 meaning one per available CPU.
+//! With a single thread, or where threads cannot be spawned, the work is
+//! done on the calling thread. Results always come in the order of the IR,
+//! however the work was scheduled, so merging them gives the same output as
+//! a sequential walk.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let counts = par_walk_modules(&distribution, 0, |_context| CountValues(0));
+//! let total: usize = counts.iter().map(|count| count.0).sum();
+//! ```
+
+use rayon::prelude::*;
+
+use super::access::AccessControlled;
+use super::distribution::Distribution;
+use super::value::ValueDefinition;
+use super::walk::{DistributionVisitor, VisitContext, walk_module, walk_module_specification};
+
+/// Walk every module of the distribution's own package with a visitor made
+/// for it by `visitor`, on up to `jobs` threads, returning the visitors in
+/// module order.
+///
+/// Modules are those of the package definition, or of the specification
+/// for a Specs distribution. Dependencies are not walked.
+pub fn par_walk_modules<V, F>(distribution: &Distribution, jobs: usize, visitor: F) -> Vec<V>
+where
+    V: DistributionVisitor + Send,
+    F: Fn(&VisitContext) -> V + Sync + Send,
+{
+    let package = VisitContext::package(distribution.package_name().clone(), false);
+    match distribution {
+        Distribution::Library(_) | Distribution::Application(_) => {
+            let modules: Vec<_> = distribution
+                .definition()
+                .into_iter()
+                .flat_map(|definition| &definition.modules)
+                .collect();
+            map_parallel(modules, jobs, |(module_key, module)| {
+                let context = package.in_module(module_key);
+                let mut visitor = visitor(&context);
+                walk_module(&mut visitor, &context, module);
+                visitor
+            })
+        }
+        Distribution::Specs(specs) => {
+            let modules: Vec<_> = specs.spec.modules.iter().collect();
+            map_parallel(modules, jobs, |(module_key, module)| {
+                let context = package.in_module(module_key);
+                let mut visitor = visitor(&context);
+                walk_module_specification(&mut visitor, &context, module);
+                visitor
+            })
+        }
+    }
+}
+
+/// Apply `transform` to every value definition of the distribution's own
+/// package, on up to `jobs` threads, returning its results in definition
+/// order.
+///
+/// Specs distributions have no value definitions, so nothing is transformed.
+pub fn par_transform_values<R, F>(
+    distribution: &mut Distribution,
+    jobs: usize,
+    transform: F,
+) -> Vec<R>
+where
+    R: Send,
+    F: Fn(&VisitContext, &mut AccessControlled<ValueDefinition>) -> R + Sync + Send,
+{
+    let package = VisitContext::package(distribution.package_name().clone(), false);
+    let definition = match distribution {
+        Distribution::Library(lib) => &mut lib.def,
+        Distribution::Application(app) => &mut app.def,
+        Distribution::Specs(_) => return Vec::new(),
+    };
+    let values: Vec<_> = definition
+        .modules
+        .iter_mut()
+        .flat_map(|(module_key, module)| {
+            let context = package.in_module(module_key);
+            module
+                .value
+                .values
+                .iter_mut()
+                .map(move |(name, value)| (context.in_definition(name), value))
+        })
+        .collect();
+    map_parallel(values, jobs, |(context, value)| transform(&context, value))
+}
+
+/// Apply `f` to every item on up to `jobs` threads (0 for one per CPU),
+/// returning the results in the order of `items`
+fn map_parallel<T, R, F>(items: Vec<T>, jobs: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync + Send,
+{
+    let jobs = if jobs > 0 {
+        jobs
+    } else {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    };
+    let jobs = jobs.min(items.len());
+    if jobs <= 1 {
+        return items.into_iter().map(f).collect();
+    }
+    match rayon::ThreadPoolBuilder::new().num_threads(jobs).build() {
+        Ok(pool) => pool.install(|| items.into_par_iter().map(&f).collect()),
+        Err(_) => items.into_iter().map(f).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::v4::text::parse_distribution;
+    use crate::ir::v4::value::{Value, ValueBody};
+    use crate::ir::v4::walk::{Walk, walk_distribution};
+
+    fn distribution() -> Distribution {
+        let mut source = String::from("library acme/shop\n");
+        for module in ["orders", "billing", "stock", "returns"] {
+            source.push_str(&format!(
+                "\n\nmodule {}\n\none : morphir/sdk:basics#Int =\n    1\n\npair : ( morphir/sdk:basics#Int, morphir/sdk:basics#Int ) =\n    ( 1, 2 )\n",
+                module
+            ));
+        }
+        parse_distribution(&source).unwrap()
+    }
+
+    /// Values of each module, by definition
+    #[derive(Default)]
+    struct Values(Vec<String>);
+
+    impl DistributionVisitor for Values {
+        fn enter_value(&mut self, context: &VisitContext, _value: &Value) -> Walk {
+            let definition = context.definition.as_ref().unwrap();
+            self.0.push(definition.to_canonical_string());
+            Walk::Continue
+        }
+    }
+
+    #[test]
+    fn test_par_walk_modules_matches_walk() {
+        let distribution = distribution();
+        let mut sequential = Values::default();
+        walk_distribution(&mut sequential, &distribution);
+
+        for jobs in [1, 3, 0] {
+            let modules = par_walk_modules(&distribution, jobs, |context| {
+                assert!(context.module.is_some());
+                Values::default()
+            });
+            assert_eq!(modules.len(), 4);
+            let merged: Vec<String> = modules.into_iter().flat_map(|values| values.0).collect();
+            assert_eq!(merged, sequential.0);
+        }
+    }
+
+    #[test]
+    fn test_par_transform_values() {
+        let mut distribution = distribution();
+        let names = par_transform_values(&mut distribution, 2, |context, value| {
+            value.value.body = ValueBody::Expression(Value::Unit(Default::default()));
+            context.definition.as_ref().unwrap().to_canonical_string()
+        });
+        assert_eq!(names.len(), 8);
+        assert_eq!(names[0], "acme/shop:orders#one");
+        assert_eq!(names[7], "acme/shop:returns#pair");
+
+        let mut values = Values::default();
+        walk_distribution(&mut values, &distribution);
+        assert_eq!(values.0.len(), 8);
+    }
+}
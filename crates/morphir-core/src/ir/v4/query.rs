@@ -0,0 +1,990 @@
+//! Queries over the definitions of a distribution.
+//!
+//! A query selects definitions of one kind and filters them by their
+//! attributes, in the spirit of XPath:
+//!
+//! ```text
+//! values[output_type.reference = "morphir/sdk:basics#decimal"]
+//! types[form = "custom" and module ~ "orders*"].constructors.name
+//! values[arity > 3 or not references]
+//! ```
+//!
+//! - `modules`, `types`, `constructors`, `values`, and `definitions` (types
+//!   and values) select the nodes to filter. Each node is a JSON object; see
+//!   [`Query::run`] for its attributes.
+//! - `[...]` keeps the nodes for which the condition holds. Conditions
+//!   compare an attribute path to a string, number, `true`, `false`, or
+//!   `null` with `=`, `!=`, `<`, `<=`, `>`, `>=`, or `~` (a glob, where `*`
+//!   matches any text and `?` one character), and combine with `and`, `or`,
+//!   `not`, and parentheses. A path on its own holds when it leads to
+//!   anything other than `null`, `false`, or an empty list.
+//! - A path steps through lists, so `inputs.type.reference = "..."` holds
+//!   when any input has that type. `!=` holds when `=` does not.
+//! - A trailing `.path` returns the attribute instead of the whole node.
+//!
+//! Names are kebab-case and fully-qualified names canonical
+//! (`package:module#name`), as in the `.mir` text.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let query = Query::parse(r#"values[output_type.reference = "morphir/sdk:basics#decimal"]"#)?;
+//! for value in query.run(&dist) {
+//!     println!("{}", value["fqname"]);
+//! }
+//! ```
+
+use serde_json::{Value as Json, json};
+use thiserror::Error;
+
+use super::access::{Access, AccessControlled};
+use super::distribution::Distribution;
+use super::module::{ModuleDefinition, ModuleSpecification};
+use super::sample::{Reference, collect_type_definition_refs, collect_value_refs};
+use super::text;
+use super::types::{Field, Type, TypeDefinition, TypeSpecification};
+use super::value::{ValueBody, ValueDefinition, ValueSpecification};
+use crate::naming::{FQName, Name, Path};
+
+/// Error reading a query
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("column {column}: {message}")]
+pub struct QueryError {
+    pub column: usize,
+    pub message: String,
+}
+
+/// The definitions a query selects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryTarget {
+    Modules,
+    Types,
+    Constructors,
+    Values,
+    /// Types and values
+    Definitions,
+}
+
+impl QueryTarget {
+    fn named(name: &str) -> Option<Self> {
+        match name {
+            "modules" => Some(QueryTarget::Modules),
+            "types" => Some(QueryTarget::Types),
+            "constructors" => Some(QueryTarget::Constructors),
+            "values" => Some(QueryTarget::Values),
+            "definitions" => Some(QueryTarget::Definitions),
+            _ => None,
+        }
+    }
+}
+
+/// A parsed query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    target: QueryTarget,
+    filters: Vec<Condition>,
+    projection: Vec<String>,
+    dependencies: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    Exists(Vec<String>),
+    Compare(Vec<String>, Operator, Json),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Glob,
+}
+
+impl Query {
+    /// Read a query.
+    pub fn parse(source: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            end: source.chars().count() + 1,
+        };
+        let query = parser.query()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some((column, token)) => Err(QueryError {
+                column: *column,
+                message: format!("unexpected {}", token),
+            }),
+        }
+    }
+
+    /// Also select the definitions of the distribution's dependencies.
+    pub fn with_dependencies(mut self, dependencies: bool) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+
+    /// The nodes, or projected attributes, the query selects, in the order
+    /// of the distribution.
+    ///
+    /// Every node has `kind`, `package`, `module`, `name`, and `access`;
+    /// all but modules also have `fqname`. In addition:
+    ///
+    /// - modules: `doc`, and the names of their `types` and `values`
+    /// - types: `form` (`alias`, `custom`, `opaque`, or `incomplete`),
+    ///   `params`, `alias` (the aliased type), `constructors`, and
+    ///   `type_references`
+    /// - constructors: `type` (the fully-qualified name of their type) and
+    ///   `args`
+    /// - values: `inputs`, `arity`, `output_type`, `body` (`expression`,
+    ///   `native`, `external`, or `incomplete`), `references` (the values and
+    ///   constructors the body uses), and `type_references` (the types of
+    ///   the signature)
+    ///
+    /// Types appear as objects with their `kind` (`reference`, `variable`,
+    /// `tuple`, `record`, `extensible_record`, `function`, or `unit`), their
+    /// `text` in `.mir` syntax, and their parts: `reference` and `args`,
+    /// `name`, `elements`, `fields`, or `argument` and `result`.
+    ///
+    /// Dependencies only carry specifications, so their values have no
+    /// `body` or `references`.
+    pub fn run(&self, distribution: &Distribution) -> Vec<Json> {
+        let mut nodes = Vec::new();
+        let package = &distribution.package_name().0;
+        match distribution.definition() {
+            Some(definition) => {
+                for (name, module) in &definition.modules {
+                    module_definition_nodes(self.target, package, name, module, &mut nodes);
+                }
+            }
+            None => {
+                for (name, module) in &distribution.specification().modules {
+                    module_specification_nodes(self.target, package, name, module, &mut nodes);
+                }
+            }
+        }
+        if self.dependencies {
+            for (package, specification) in distribution.dependencies() {
+                let package = Path::new(package);
+                for (name, module) in &specification.modules {
+                    module_specification_nodes(self.target, &package, name, module, &mut nodes);
+                }
+            }
+        }
+
+        nodes
+            .into_iter()
+            .filter(|node| self.filters.iter().all(|filter| holds(filter, node)))
+            .flat_map(|node| {
+                if self.projection.is_empty() {
+                    vec![node]
+                } else {
+                    resolve(&node, &self.projection)
+                        .into_iter()
+                        .cloned()
+                        .collect()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Read `source` and run it on `distribution`.
+pub fn query(distribution: &Distribution, source: &str) -> Result<Vec<Json>, QueryError> {
+    Ok(Query::parse(source)?.run(distribution))
+}
+
+// =============================================================================
+// Nodes
+// =============================================================================
+
+fn module_definition_nodes(
+    target: QueryTarget,
+    package: &Path,
+    key: &str,
+    module: &AccessControlled<ModuleDefinition>,
+    nodes: &mut Vec<Json>,
+) {
+    let module_path = Path::new(key);
+    let definition = &module.value;
+    if target == QueryTarget::Modules {
+        nodes.push(json!({
+            "kind": "module",
+            "package": package.to_string(),
+            "module": module_path.to_string(),
+            "name": module_path.to_string(),
+            "access": access(&module.access),
+            "doc": definition.doc,
+            "types": definition.types.keys().map(|k| Name::from(k.as_str()).to_string()).collect::<Vec<_>>(),
+            "values": definition.values.keys().map(|k| Name::from(k.as_str()).to_string()).collect::<Vec<_>>(),
+        }));
+        return;
+    }
+    if matches!(
+        target,
+        QueryTarget::Types | QueryTarget::Constructors | QueryTarget::Definitions
+    ) {
+        for (name, type_def) in &definition.types {
+            let fqname = FQName::new(
+                package.clone(),
+                module_path.clone(),
+                Name::from(name.as_str()),
+            );
+            let constructors = match &type_def.value {
+                TypeDefinition::CustomTypeDefinition { constructors, .. } => constructors
+                    .value
+                    .iter()
+                    .map(|c| {
+                        let args = c.args.iter().map(|a| (&a.name, &a.arg_type));
+                        constructor(&fqname, &c.name, args, &constructors.access)
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if target == QueryTarget::Constructors {
+                nodes.extend(constructors);
+                continue;
+            }
+            let mut references = Vec::new();
+            collect_type_definition_refs(&type_def.value, &mut references);
+            let (form, params, alias) = match &type_def.value {
+                TypeDefinition::TypeAliasDefinition {
+                    type_params,
+                    type_expr,
+                } => ("alias", type_params, type_view(type_expr)),
+                TypeDefinition::CustomTypeDefinition { type_params, .. } => {
+                    ("custom", type_params, Json::Null)
+                }
+                TypeDefinition::IncompleteTypeDefinition { type_params, .. } => {
+                    ("incomplete", type_params, Json::Null)
+                }
+            };
+            nodes.push(type_node(
+                &fqname,
+                &type_def.access,
+                form,
+                params,
+                alias,
+                constructors,
+                &references,
+            ));
+        }
+    }
+    if matches!(target, QueryTarget::Values | QueryTarget::Definitions) {
+        for (name, value_def) in &definition.values {
+            let fqname = FQName::new(
+                package.clone(),
+                module_path.clone(),
+                Name::from(name.as_str()),
+            );
+            nodes.push(value_definition_node(&fqname, value_def));
+        }
+    }
+}
+
+fn module_specification_nodes(
+    target: QueryTarget,
+    package: &Path,
+    key: &str,
+    module: &ModuleSpecification,
+    nodes: &mut Vec<Json>,
+) {
+    let module_path = Path::new(key);
+    if target == QueryTarget::Modules {
+        nodes.push(json!({
+            "kind": "module",
+            "package": package.to_string(),
+            "module": module_path.to_string(),
+            "name": module_path.to_string(),
+            "access": access(&Access::Public),
+            "doc": module.doc,
+            "types": module.types.keys().map(|k| Name::from(k.as_str()).to_string()).collect::<Vec<_>>(),
+            "values": module.values.keys().map(|k| Name::from(k.as_str()).to_string()).collect::<Vec<_>>(),
+        }));
+        return;
+    }
+    if matches!(
+        target,
+        QueryTarget::Types | QueryTarget::Constructors | QueryTarget::Definitions
+    ) {
+        for (name, spec) in &module.types {
+            let fqname = FQName::new(
+                package.clone(),
+                module_path.clone(),
+                Name::from(name.as_str()),
+            );
+            let constructors = match spec {
+                TypeSpecification::CustomTypeSpecification { constructors, .. } => constructors
+                    .iter()
+                    .map(|c| {
+                        let args = c.args.iter().map(|a| (&a.name, &a.arg_type));
+                        constructor(&fqname, &c.name, args, &Access::Public)
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if target == QueryTarget::Constructors {
+                nodes.extend(constructors);
+                continue;
+            }
+            let mut references = Vec::new();
+            let (form, params, alias) = match spec {
+                TypeSpecification::TypeAliasSpecification {
+                    type_params,
+                    type_expr,
+                } => {
+                    collect_type_references(type_expr, &mut references);
+                    ("alias", type_params, type_view(type_expr))
+                }
+                TypeSpecification::OpaqueTypeSpecification { type_params } => {
+                    ("opaque", type_params, Json::Null)
+                }
+                TypeSpecification::CustomTypeSpecification {
+                    type_params,
+                    constructors,
+                } => {
+                    for arg in constructors.iter().flat_map(|c| &c.args) {
+                        collect_type_references(&arg.arg_type, &mut references);
+                    }
+                    ("custom", type_params, Json::Null)
+                }
+            };
+            nodes.push(type_node(
+                &fqname,
+                &Access::Public,
+                form,
+                params,
+                alias,
+                constructors,
+                &references,
+            ));
+        }
+    }
+    if matches!(target, QueryTarget::Values | QueryTarget::Definitions) {
+        for (name, spec) in &module.values {
+            let fqname = FQName::new(
+                package.clone(),
+                module_path.clone(),
+                Name::from(name.as_str()),
+            );
+            nodes.push(value_specification_node(&fqname, spec));
+        }
+    }
+}
+
+fn type_node(
+    fqname: &FQName,
+    type_access: &Access,
+    form: &str,
+    params: &[Name],
+    alias: Json,
+    constructors: Vec<Json>,
+    references: &[Reference],
+) -> Json {
+    let mut node = definition_node("type", fqname, type_access);
+    node["form"] = json!(form);
+    node["params"] = json!(params.iter().map(Name::to_string).collect::<Vec<_>>());
+    node["alias"] = alias;
+    node["constructors"] = Json::Array(constructors);
+    node["type_references"] = json!(distinct_fqnames(references));
+    node
+}
+
+fn constructor<'a>(
+    type_name: &FQName,
+    name: &Name,
+    args: impl Iterator<Item = (&'a Name, &'a Type)>,
+    constructors_access: &Access,
+) -> Json {
+    let fqname = FQName::new(
+        type_name.package_path.clone(),
+        type_name.module_path.clone(),
+        name.clone(),
+    );
+    let mut node = definition_node("constructor", &fqname, constructors_access);
+    node["type"] = json!(type_name.to_canonical_string());
+    node["args"] = json!(
+        args.map(|(name, tpe)| json!({"name": name.to_string(), "type": type_view(tpe)}))
+            .collect::<Vec<_>>()
+    );
+    node
+}
+
+fn value_definition_node(fqname: &FQName, definition: &AccessControlled<ValueDefinition>) -> Json {
+    let value = &definition.value;
+    let mut node = value_node(
+        fqname,
+        &definition.access,
+        value
+            .input_types
+            .iter()
+            .map(|(name, entry)| (name.as_str(), &entry.input_type)),
+        &value.output_type,
+    );
+    let (body, references) = match &value.body {
+        ValueBody::Expression(body) => {
+            let mut references = Vec::new();
+            collect_value_refs(body, &mut references);
+            ("expression", distinct_fqnames(&references))
+        }
+        ValueBody::Native(_) => ("native", Vec::new()),
+        ValueBody::External { .. } => ("external", Vec::new()),
+        ValueBody::Incomplete(_) => ("incomplete", Vec::new()),
+    };
+    node["body"] = json!(body);
+    node["references"] = json!(references);
+    node
+}
+
+fn value_specification_node(fqname: &FQName, specification: &ValueSpecification) -> Json {
+    value_node(
+        fqname,
+        &Access::Public,
+        specification
+            .inputs
+            .iter()
+            .map(|(name, tpe)| (name.as_str(), tpe)),
+        &specification.output,
+    )
+}
+
+fn value_node<'a>(
+    fqname: &FQName,
+    value_access: &Access,
+    inputs: impl Iterator<Item = (&'a str, &'a Type)>,
+    output: &Type,
+) -> Json {
+    let mut references = Vec::new();
+    let mut input_nodes = Vec::new();
+    for (name, tpe) in inputs {
+        collect_type_references(tpe, &mut references);
+        input_nodes.push(json!({"name": Name::from(name).to_string(), "type": type_view(tpe)}));
+    }
+    collect_type_references(output, &mut references);
+    let mut node = definition_node("value", fqname, value_access);
+    node["arity"] = json!(input_nodes.len());
+    node["inputs"] = Json::Array(input_nodes);
+    node["output_type"] = type_view(output);
+    node["type_references"] = json!(distinct_fqnames(&references));
+    node
+}
+
+fn definition_node(kind: &str, fqname: &FQName, definition_access: &Access) -> Json {
+    json!({
+        "kind": kind,
+        "fqname": fqname.to_canonical_string(),
+        "package": fqname.package_path.to_string(),
+        "module": fqname.module_path.to_string(),
+        "name": fqname.local_name.to_string(),
+        "access": access(definition_access),
+    })
+}
+
+fn type_view(tpe: &Type) -> Json {
+    let fields = |fields: &[Field]| {
+        fields
+            .iter()
+            .map(|f| json!({"name": f.name.to_string(), "type": type_view(&f.tpe)}))
+            .collect::<Vec<_>>()
+    };
+    let mut view = match tpe {
+        Type::Variable(_, name) => json!({"kind": "variable", "name": name.to_string()}),
+        Type::Reference(_, fqname, args) => json!({
+            "kind": "reference",
+            "reference": fqname.to_canonical_string(),
+            "args": args.iter().map(type_view).collect::<Vec<_>>(),
+        }),
+        Type::Tuple(_, elements) => json!({
+            "kind": "tuple",
+            "elements": elements.iter().map(type_view).collect::<Vec<_>>(),
+        }),
+        Type::Record(_, record_fields) => {
+            json!({"kind": "record", "fields": fields(record_fields)})
+        }
+        Type::ExtensibleRecord(_, name, record_fields) => json!({
+            "kind": "extensible_record",
+            "name": name.to_string(),
+            "fields": fields(record_fields),
+        }),
+        Type::Function(_, argument, result) => json!({
+            "kind": "function",
+            "argument": type_view(argument),
+            "result": type_view(result),
+        }),
+        Type::Unit(_) => json!({"kind": "unit"}),
+    };
+    view["text"] = json!(text::print_type(tpe));
+    view
+}
+
+fn collect_type_references(tpe: &Type, out: &mut Vec<Reference>) {
+    match tpe {
+        Type::Reference(_, fqname, args) => {
+            out.push(Reference::Type(fqname.clone()));
+            for arg in args {
+                collect_type_references(arg, out);
+            }
+        }
+        Type::Tuple(_, elements) => {
+            for element in elements {
+                collect_type_references(element, out);
+            }
+        }
+        Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+            for field in fields {
+                collect_type_references(&field.tpe, out);
+            }
+        }
+        Type::Function(_, argument, result) => {
+            collect_type_references(argument, out);
+            collect_type_references(result, out);
+        }
+        Type::Variable(..) | Type::Unit(_) => {}
+    }
+}
+
+/// Canonical names of `references`, without repeats, in first-use order
+fn distinct_fqnames(references: &[Reference]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for reference in references {
+        let (Reference::Type(fqname) | Reference::Value(fqname) | Reference::Constructor(fqname)) =
+            reference;
+        let name = fqname.to_canonical_string();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn access(access: &Access) -> &'static str {
+    match access {
+        Access::Public => "public",
+        Access::Private => "private",
+    }
+}
+
+// =============================================================================
+// Evaluation
+// =============================================================================
+
+fn holds(condition: &Condition, node: &Json) -> bool {
+    match condition {
+        Condition::And(left, right) => holds(left, node) && holds(right, node),
+        Condition::Or(left, right) => holds(left, node) || holds(right, node),
+        Condition::Not(inner) => !holds(inner, node),
+        Condition::Exists(path) => resolve(node, path)
+            .into_iter()
+            .any(|value| !matches!(value, Json::Null | Json::Bool(false))),
+        Condition::Compare(path, Operator::Ne, literal) => !resolve(node, path)
+            .into_iter()
+            .any(|value| value == literal),
+        Condition::Compare(path, operator, literal) => resolve(node, path)
+            .into_iter()
+            .any(|value| compare(value, *operator, literal)),
+    }
+}
+
+fn compare(value: &Json, operator: Operator, literal: &Json) -> bool {
+    use std::cmp::Ordering;
+
+    let ordering = match (value, literal) {
+        (Json::Number(a), Json::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Json::String(a), Json::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    match operator {
+        Operator::Eq => value == literal,
+        Operator::Ne => value != literal,
+        Operator::Lt => ordering == Some(Ordering::Less),
+        Operator::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Operator::Gt => ordering == Some(Ordering::Greater),
+        Operator::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        Operator::Glob => match (value, literal) {
+            (Json::String(text), Json::String(pattern)) => glob(pattern, text),
+            _ => false,
+        },
+    }
+}
+
+/// The values at `path` under `node`, stepping into every element of a list
+fn resolve<'a>(node: &'a Json, path: &[String]) -> Vec<&'a Json> {
+    match (node, path.split_first()) {
+        (Json::Array(elements), _) => elements
+            .iter()
+            .flat_map(|element| resolve(element, path))
+            .collect(),
+        (_, None) => vec![node],
+        (Json::Object(fields), Some((field, rest))) => fields
+            .get(field)
+            .map(|value| resolve(value, rest))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any text and `?` any
+/// one character
+fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*`, and the text it matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// =============================================================================
+// Parsing
+// =============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Literal(Json),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "'{}'", word),
+            Token::Literal(literal) => write!(f, "{}", literal),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+const SYMBOLS: &[&str] = &[
+    "!=", "<=", ">=", "=", "<", ">", "~", "[", "]", "(", ")", ".",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((column, Token::Word(chars[start..i].iter().collect())));
+        } else if c.is_ascii_digit()
+            || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+            let literal = serde_json::from_str(&number).map_err(|_| QueryError {
+                column,
+                message: format!("invalid number {}", number),
+            })?;
+            tokens.push((column, Token::Literal(literal)));
+        } else if c == '"' {
+            let mut string = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(QueryError {
+                            column,
+                            message: "unterminated string".to_string(),
+                        });
+                    }
+                    Some('"') => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        string.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(c) => {
+                        string.push(*c);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push((column, Token::Literal(Json::String(string))));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| QueryError {
+                    column,
+                    message: format!("unexpected '{}'", c),
+                })?;
+            i += symbol.len();
+            tokens.push((column, Token::Symbol(symbol)));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// Column just past the end of the source
+    end: usize,
+}
+
+impl Parser {
+    fn query(&mut self) -> Result<Query, QueryError> {
+        let (column, word) = self.word("a selector")?;
+        let target = QueryTarget::named(&word).ok_or_else(|| QueryError {
+            column,
+            message: format!(
+                "unknown selector '{}'; expected modules, types, constructors, values, or definitions",
+                word
+            ),
+        })?;
+        let mut filters = Vec::new();
+        while self.eat("[") {
+            filters.push(self.or()?);
+            self.expect("]")?;
+        }
+        let projection = if self.eat(".") {
+            self.path()?
+        } else {
+            Vec::new()
+        };
+        Ok(Query {
+            target,
+            filters,
+            projection,
+            dependencies: false,
+        })
+    }
+
+    fn or(&mut self) -> Result<Condition, QueryError> {
+        let mut condition = self.and()?;
+        while self.eat_word("or") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Condition, QueryError> {
+        let mut condition = self.unary()?;
+        while self.eat_word("and") {
+            condition = Condition::And(Box::new(condition), Box::new(self.unary()?));
+        }
+        Ok(condition)
+    }
+
+    fn unary(&mut self) -> Result<Condition, QueryError> {
+        if self.eat_word("not") {
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let condition = self.or()?;
+            self.expect(")")?;
+            return Ok(condition);
+        }
+        let path = self.path()?;
+        let operator = match self.peek() {
+            Some((_, Token::Symbol(symbol))) => match *symbol {
+                "=" => Operator::Eq,
+                "!=" => Operator::Ne,
+                "<" => Operator::Lt,
+                "<=" => Operator::Le,
+                ">" => Operator::Gt,
+                ">=" => Operator::Ge,
+                "~" => Operator::Glob,
+                _ => return Ok(Condition::Exists(path)),
+            },
+            _ => return Ok(Condition::Exists(path)),
+        };
+        self.position += 1;
+        let literal = match self.next() {
+            Some((_, Token::Literal(literal))) => literal,
+            Some((_, Token::Word(word))) if word == "true" => Json::Bool(true),
+            Some((_, Token::Word(word))) if word == "false" => Json::Bool(false),
+            Some((_, Token::Word(word))) if word == "null" => Json::Null,
+            other => return Err(self.unexpected(other, "a string, number, true, false, or null")),
+        };
+        if operator == Operator::Glob && !literal.is_string() {
+            return Err(QueryError {
+                column: self.tokens[self.position - 1].0,
+                message: "'~' needs a string pattern".to_string(),
+            });
+        }
+        Ok(Condition::Compare(path, operator, literal))
+    }
+
+    fn path(&mut self) -> Result<Vec<String>, QueryError> {
+        let mut path = vec![self.word("an attribute")?.1];
+        while self.eat(".") {
+            path.push(self.word("an attribute")?.1);
+        }
+        Ok(path)
+    }
+
+    fn word(&mut self, expected: &str) -> Result<(usize, String), QueryError> {
+        match self.next() {
+            Some((column, Token::Word(word))) => Ok((column, word)),
+            other => Err(self.unexpected(other, expected)),
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), QueryError> {
+        if self.eat(symbol) {
+            return Ok(());
+        }
+        let next = self.next();
+        Err(self.unexpected(next, &format!("'{}'", symbol)))
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some((_, Token::Symbol(s))) if *s == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn eat_word(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some((_, Token::Word(w))) if w == keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn peek(&self) -> Option<&(usize, Token)> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn unexpected(&self, found: Option<(usize, Token)>, expected: &str) -> QueryError {
+        match found {
+            Some((column, token)) => QueryError {
+                column,
+                message: format!("expected {}, found {}", expected, token),
+            },
+            None => QueryError {
+                column: self.end,
+                message: format!("expected {}, found the end of the query", expected),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::parse;
+    use super::*;
+
+    fn distribution() -> Distribution {
+        parse(
+            "library acme/shop\n\n\nmodule orders\n\ntype Status\n    = Open\n    | Closed (at : morphir/sdk:basics#Int)\n\ntotal (amount : morphir/sdk:decimal#Decimal) (rate : morphir/sdk:decimal#Decimal) : morphir/sdk:decimal#Decimal =\n    acme/shop:orders#tax-rate\n\nprivate tax-rate : morphir/sdk:basics#Float native arithmetic\n",
+        )
+    }
+
+    fn names(results: &[Json]) -> Vec<&str> {
+        results
+            .iter()
+            .map(|r| r["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_filters() {
+        let dist = distribution();
+        let run = |source: &str| query(&dist, source).unwrap();
+
+        assert_eq!(
+            names(&run(
+                r#"values[output_type.reference = "morphir/sdk:decimal#decimal"]"#
+            )),
+            ["total"]
+        );
+        assert_eq!(names(&run(r#"values[access != "public"]"#)), ["tax-rate"]);
+        assert_eq!(
+            names(&run(
+                r#"values[name ~ "t*" and (arity >= 2 or body = "native")]"#
+            )),
+            ["total", "tax-rate"]
+        );
+        assert_eq!(
+            names(&run(r#"values[references = "acme/shop:orders#tax-rate"]"#)),
+            ["total"]
+        );
+        assert_eq!(names(&run("values[not references]")), ["tax-rate"]);
+        assert_eq!(names(&run("constructors[args]")), ["closed"]);
+        assert_eq!(names(&run(r#"definitions[kind = "type"]"#)), ["status"]);
+        assert_eq!(names(&run("modules[values = \"total\"]")), ["orders"]);
+    }
+
+    #[test]
+    fn test_projection() {
+        let dist = distribution();
+        assert_eq!(
+            query(&dist, "types.constructors.name").unwrap(),
+            [json!("open"), json!("closed")]
+        );
+        assert_eq!(
+            query(&dist, r#"values[name = "total"].inputs.type.text"#).unwrap(),
+            [
+                json!("morphir/sdk:decimal#Decimal"),
+                json!("morphir/sdk:decimal#Decimal")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_and_glob() {
+        let error = Query::parse("functions").unwrap_err();
+        assert_eq!(error.column, 1);
+        assert!(error.message.contains("unknown selector"));
+        let error = Query::parse("values[name =]").unwrap_err();
+        assert_eq!(error.column, 14);
+        assert!(Query::parse("values[name ~ 3]").is_err());
+        assert!(Query::parse(r#"values[name = "x"#).is_err());
+        assert_eq!(Query::parse("values[").unwrap_err().column, 8);
+
+        assert!(glob("get-*-name", "get-user-name"));
+        assert!(glob("*", ""));
+        assert!(glob("a?c*", "abcdef"));
+        assert!(!glob("a*b", "acbc"));
+    }
+}
@@ -0,0 +1,94 @@
+//! Reading V4 IR files from JSON.
+//!
+//! The V4 deserializers read every node straight from the input, without
+//! first collecting it into a `serde_json::Value`, so [`from_slice`] makes a
+//! single pass over the document. With the `simd` feature it parses with
+//! simd-json instead of serde_json. Which of the two is faster depends on
+//! the input and the CPU, so compare them on your own IR with the `v4_read`
+//! benchmark before enabling it. simd-json works in place, so the input is
+//! borrowed mutably either way.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let mut content = std::fs::read("morphir-ir.json")?;
+//! let ir_file = read::from_slice(&mut content)?;
+//! ```
+
+use thiserror::Error;
+
+use super::IRFile;
+
+/// Error reading an IR file
+#[derive(Debug, Error)]
+pub enum ReadError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "simd")]
+    #[error(transparent)]
+    Simd(#[from] simd_json::Error),
+}
+
+/// Read an IR file from JSON, with simd-json when the `simd` feature is
+/// enabled. The contents of `json` are unspecified afterwards.
+pub fn from_slice(json: &mut [u8]) -> Result<IRFile, ReadError> {
+    #[cfg(feature = "simd")]
+    {
+        Ok(simd_json::serde::from_slice(json)?)
+    }
+    #[cfg(not(feature = "simd"))]
+    {
+        Ok(serde_json::from_slice(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::v4::{Distribution, ValueBody};
+
+    #[test]
+    fn test_from_slice_keeps_document_order() {
+        let json = r#"{
+            "formatVersion": "4.0.0",
+            "distribution": { "Library": {
+                "packageName": "acme/shop",
+                "dependencies": {},
+                "def": { "modules": { "orders": { "access": "Public", "value": {
+                    "types": {},
+                    "values": {
+                        "total": { "access": "Public", "value": {
+                            "inputTypes": {
+                                "lines": { "type": "morphir/sdk:basics#int" },
+                                "in": { "type": "morphir/sdk:basics#int" }
+                            },
+                            "outputType": "morphir/sdk:basics#int",
+                            "body": { "ExpressionBody": { "body": { "Variable": { "name": "in" } } } }
+                        } },
+                        "rate": { "access": "Private", "value": {
+                            "inputTypes": {},
+                            "outputType": "morphir/sdk:basics#float",
+                            "body": { "IncompleteBody": { "reason": { "UnresolvedReference": { "target": "acme/shop:orders#gone" } } } }
+                        } }
+                    }
+                } } } }
+            } }
+        }"#;
+        let mut bytes = json.as_bytes().to_vec();
+        let ir_file = from_slice(&mut bytes).unwrap();
+        let Distribution::Library(lib) = &ir_file.distribution else {
+            panic!("expected a library");
+        };
+        let module = &lib.def.modules["orders"].value;
+        assert!(module.values.keys().eq(["total", "rate"]));
+        let total = &module.values["total"].value;
+        assert!(total.input_types.keys().eq(["lines", "in"]));
+        assert!(matches!(
+            module.values["rate"].value.body,
+            ValueBody::Incomplete(_)
+        ));
+
+        assert_eq!(serde_json::from_str::<IRFile>(json).unwrap(), ir_file);
+        assert!(from_slice(&mut b"{}".to_vec()).is_err());
+    }
+}
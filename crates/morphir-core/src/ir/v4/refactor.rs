@@ -0,0 +1,1227 @@
+//! Refactorings of a distribution.
+//!
+//! A refactoring changes the definitions of a package and rewrites every
+//! reference to them, in types, values, patterns, holes, and entry points,
+//! so the package means the same thing afterwards. [`rename`] renames a
+//! module, type, constructor, or value, and [`restructure`] splits and merges
+//! modules by a [`RestructurePlan`]. Private definitions that a refactoring
+//! leaves referenced from another module are made public.
+//!
+//! Each refactoring is recorded as a [`RefactorTransaction`] with an id of
+//! its own. A reference a refactoring had to remove is left as a hole with
+//! [`HoleReason::DeletedDuringRefactor`] naming that id, and a
+//! [`RefactorLog`] kept next to the IR says what each transaction did.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let transaction = rename(
+//!     &mut distribution,
+//!     "acme/shop:orders#total",
+//!     "acme/shop:orders#grand-total",
+//!     None,
+//! )?;
+//! println!("{} references rewritten", transaction.references);
+//! ```
+
+use std::collections::HashMap;
+
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::access::{Access, AccessControlled};
+use super::distribution::Distribution;
+use super::module::ModuleDefinition;
+use super::package::PackageDefinition;
+use super::pattern::Pattern;
+use super::types::{Type, TypeDefinition};
+use super::value::{HoleReason, Value, ValueBody, ValueDefinition};
+use super::walk::{
+    DistributionVisitor, DistributionVisitorMut, VisitContext, Walk, walk_distribution,
+    walk_distribution_mut,
+};
+use crate::naming::{FQName, Name, Path};
+
+/// Kind of definition a refactoring changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Module,
+    Type,
+    Constructor,
+    Value,
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SymbolKind::Module => "module",
+            SymbolKind::Type => "type",
+            SymbolKind::Constructor => "constructor",
+            SymbolKind::Value => "value",
+        })
+    }
+}
+
+/// A definition given a new name, as `package:module` for modules and
+/// canonical FQNames otherwise
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rename {
+    pub kind: SymbolKind,
+    pub from: String,
+    pub to: String,
+}
+
+/// Record of one refactoring
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefactorTransaction {
+    pub tx_id: String,
+    /// Definitions renamed or moved, in the order they were changed
+    pub renames: Vec<Rename>,
+    /// Number of references rewritten
+    pub references: usize,
+    /// Private definitions made public, as their modules now refer to them
+    /// from elsewhere
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exposed: Vec<String>,
+}
+
+impl RefactorTransaction {
+    /// An empty transaction with a fresh id
+    pub fn new() -> Self {
+        Self {
+            tx_id: Uuid::new_v4().to_string(),
+            renames: Vec::new(),
+            references: 0,
+            exposed: Vec::new(),
+        }
+    }
+
+    /// Reason for a hole left where this transaction removed a reference
+    pub fn hole_reason(&self) -> HoleReason {
+        HoleReason::DeletedDuringRefactor {
+            tx_id: self.tx_id.clone(),
+        }
+    }
+}
+
+impl Default for RefactorTransaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The transactions applied to an IR, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RefactorLog {
+    pub transactions: Vec<RefactorTransaction>,
+}
+
+impl RefactorLog {
+    pub fn record(&mut self, transaction: RefactorTransaction) {
+        self.transactions.push(transaction);
+    }
+
+    pub fn transaction(&self, tx_id: &str) -> Option<&RefactorTransaction> {
+        self.transactions
+            .iter()
+            .find(|transaction| transaction.tx_id == tx_id)
+    }
+
+    /// The transaction that left a hole with `reason`, if it is logged
+    pub fn deleted_by(&self, reason: &HoleReason) -> Option<&RefactorTransaction> {
+        match reason {
+            HoleReason::DeletedDuringRefactor { tx_id } => self.transaction(tx_id),
+            _ => None,
+        }
+    }
+}
+
+/// Error refactoring a distribution
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RefactorError {
+    #[error("invalid name {0:?}: expected package:module or package:module#name")]
+    InvalidName(String),
+    #[error("{0} has no definitions to refactor")]
+    NoDefinitions(String),
+    #[error("{name} is not in package {package}")]
+    OtherPackage { name: String, package: String },
+    #[error("{0} is not defined")]
+    NotDefined(String),
+    #[error("{0} is already defined")]
+    AlreadyDefined(String),
+    #[error("{name} names a {}; pass the kind to rename", kinds.iter().map(ToString::to_string).collect::<Vec<_>>().join(" and a "))]
+    Ambiguous {
+        name: String,
+        kinds: Vec<SymbolKind>,
+    },
+    #[error("cannot rename a {from} to a {to}")]
+    KindMismatch { from: SymbolKind, to: SymbolKind },
+    #[error("constructor {from} cannot move out of module {module}")]
+    ConstructorMove { from: String, module: String },
+}
+
+/// Rename the module, type, constructor, or value `from` to `to`, rewriting
+/// every reference to it.
+///
+/// Modules are named `package:module` and definitions by their canonical
+/// FQName; both must be in the distribution's own package. A type or value
+/// may move to another existing module, taking a custom type's constructors
+/// with it. Where a type, value, and constructor share a name, `kind` says
+/// which to rename.
+pub fn rename(
+    distribution: &mut Distribution,
+    from: &str,
+    to: &str,
+    kind: Option<SymbolKind>,
+) -> Result<RefactorTransaction, RefactorError> {
+    let package = distribution.package_name().to_string();
+    let (from_module, from_name) = parse_symbol(from, &package)?;
+    let (to_module, to_name) = parse_symbol(to, &package)?;
+    let definition = definition_mut(distribution)?;
+
+    let mut renaming = Renaming::default();
+    let mut transaction = RefactorTransaction::new();
+    match (from_name, to_name) {
+        (None, None) => {
+            if kind.is_some_and(|kind| kind != SymbolKind::Module) {
+                return Err(RefactorError::KindMismatch {
+                    from: SymbolKind::Module,
+                    to: kind.unwrap(),
+                });
+            }
+            rename_module(definition, &package, &from_module, &to_module)?;
+            renaming.move_module(&package, &from_module, to_module.clone());
+            transaction.renames.push(Rename {
+                kind: SymbolKind::Module,
+                from: format!("{}:{}", package, from_module),
+                to: format!("{}:{}", package, to_module),
+            });
+        }
+        (Some(from_name), Some(to_name)) => {
+            let from = FQName::new(Path::new(&package), from_module, from_name);
+            let to = FQName::new(Path::new(&package), to_module, to_name);
+            let kind = match kind {
+                Some(kind) => kind,
+                None => definition_kind(definition, &from)?,
+            };
+            match kind {
+                SymbolKind::Module => {
+                    return Err(RefactorError::KindMismatch {
+                        from: SymbolKind::Type,
+                        to: SymbolKind::Module,
+                    });
+                }
+                SymbolKind::Type => {
+                    for (constructor_from, constructor_to) in move_type(definition, &from, &to)? {
+                        renaming.rename_value(&constructor_from, constructor_to.clone());
+                        transaction.renames.push(Rename {
+                            kind: SymbolKind::Constructor,
+                            from: constructor_from.to_canonical_string(),
+                            to: constructor_to.to_canonical_string(),
+                        });
+                    }
+                    renaming.rename_type(&from, to.clone());
+                }
+                SymbolKind::Constructor => {
+                    rename_constructor(definition, &from, &to)?;
+                    renaming.rename_value(&from, to.clone());
+                }
+                SymbolKind::Value => {
+                    move_value(definition, &from, &to)?;
+                    renaming.rename_value(&from, to.clone());
+                }
+            }
+            transaction.renames.insert(
+                0,
+                Rename {
+                    kind,
+                    from: from.to_canonical_string(),
+                    to: to.to_canonical_string(),
+                },
+            );
+        }
+        (Some(_), None) => {
+            return Err(RefactorError::KindMismatch {
+                from: SymbolKind::Value,
+                to: SymbolKind::Module,
+            });
+        }
+        (None, Some(_)) => {
+            return Err(RefactorError::KindMismatch {
+                from: SymbolKind::Module,
+                to: SymbolKind::Value,
+            });
+        }
+    }
+    transaction.references = renaming.apply(distribution);
+    transaction.exposed = expose_references(distribution);
+    Ok(transaction)
+}
+
+/// Modules to split and merge, by module path within the package
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestructurePlan {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub split: Vec<Split>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merge: Vec<Merge>,
+}
+
+/// Types and values of `module` to move, by name, keyed by the module to
+/// move them to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Split {
+    pub module: String,
+    pub into: IndexMap<String, Vec<String>>,
+}
+
+/// Modules whose definitions all move to `into`, after which they are
+/// removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Merge {
+    pub modules: Vec<String>,
+    pub into: String,
+}
+
+/// Move the named types and values of `module` to other modules, keyed by
+/// module path.
+pub fn split_module(
+    distribution: &mut Distribution,
+    module: &str,
+    into: IndexMap<String, Vec<String>>,
+) -> Result<RefactorTransaction, RefactorError> {
+    let split = Split {
+        module: module.to_string(),
+        into,
+    };
+    restructure(
+        distribution,
+        &RestructurePlan {
+            split: vec![split],
+            merge: Vec::new(),
+        },
+    )
+}
+
+/// Move every definition of `modules` to the module `into`, removing them.
+pub fn merge_modules(
+    distribution: &mut Distribution,
+    modules: &[&str],
+    into: &str,
+) -> Result<RefactorTransaction, RefactorError> {
+    let merge = Merge {
+        modules: modules.iter().map(ToString::to_string).collect(),
+        into: into.to_string(),
+    };
+    restructure(
+        distribution,
+        &RestructurePlan {
+            split: Vec::new(),
+            merge: vec![merge],
+        },
+    )
+}
+
+/// Apply the splits of `plan`, then its merges, as one transaction,
+/// rewriting every reference to the definitions moved.
+///
+/// Modules moved to are created where they do not exist yet, with the
+/// access of the module the definitions came from. A custom type takes its
+/// constructors with it. The distribution is left as it was if any step
+/// fails.
+pub fn restructure(
+    distribution: &mut Distribution,
+    plan: &RestructurePlan,
+) -> Result<RefactorTransaction, RefactorError> {
+    let package = distribution.package_name().to_string();
+    let mut restructured = distribution.clone();
+    let mut transaction = RefactorTransaction::new();
+    // Each step is rewritten before the next, whose names build on it
+    for split in &plan.split {
+        let renaming = split_step(
+            definition_mut(&mut restructured)?,
+            &package,
+            split,
+            &mut transaction,
+        )?;
+        transaction.references += renaming.apply(&mut restructured);
+    }
+    for merge in &plan.merge {
+        let renaming = merge_step(
+            definition_mut(&mut restructured)?,
+            &package,
+            merge,
+            &mut transaction,
+        )?;
+        transaction.references += renaming.apply(&mut restructured);
+    }
+    transaction.exposed = expose_references(&mut restructured);
+    *distribution = restructured;
+    Ok(transaction)
+}
+
+fn definition_mut(
+    distribution: &mut Distribution,
+) -> Result<&mut PackageDefinition, RefactorError> {
+    match distribution {
+        Distribution::Library(lib) => Ok(&mut lib.def),
+        Distribution::Application(app) => Ok(&mut app.def),
+        Distribution::Specs(specs) => {
+            Err(RefactorError::NoDefinitions(specs.package_name.to_string()))
+        }
+    }
+}
+
+/// Path of a module named in a plan
+fn module_path(module: &str) -> Result<Path, RefactorError> {
+    if module.is_empty() || module.contains([':', '#']) {
+        return Err(RefactorError::InvalidName(module.to_string()));
+    }
+    Ok(Path::new(module))
+}
+
+/// Create the module `to` if it does not exist, like the module `like`
+fn ensure_module(
+    definition: &mut PackageDefinition,
+    to: &Path,
+    like: &AccessControlled<ModuleDefinition>,
+) {
+    if module_key(&definition.modules, to).is_none() {
+        definition.modules.insert(
+            to.to_string(),
+            AccessControlled {
+                access: like.access.clone(),
+                doc: None,
+                value: ModuleDefinition {
+                    types: IndexMap::new(),
+                    values: IndexMap::new(),
+                    doc: None,
+                    ids: Default::default(),
+                },
+            },
+        );
+    }
+}
+
+fn split_step(
+    definition: &mut PackageDefinition,
+    package: &str,
+    split: &Split,
+    transaction: &mut RefactorTransaction,
+) -> Result<Renaming, RefactorError> {
+    let from_module = module_path(&split.module)?;
+    let from_key = module_key(&definition.modules, &from_module)
+        .ok_or_else(|| RefactorError::NotDefined(format!("{}:{}", package, from_module)))?;
+    let like = definition.modules[&from_key].clone();
+    let mut renaming = Renaming::default();
+    for (to_module, names) in &split.into {
+        let to_module = module_path(to_module)?;
+        ensure_module(definition, &to_module, &like);
+        for name in names {
+            let name = Name::from(name.as_str());
+            let from = FQName::new(Path::new(package), from_module.clone(), name.clone());
+            let to = FQName::new(Path::new(package), to_module.clone(), name);
+            let module = module(definition, &from)?;
+            let is_type = local_key(&module.types, &from.local_name).is_some();
+            let is_value = local_key(&module.values, &from.local_name).is_some();
+            if !is_type && !is_value {
+                return Err(RefactorError::NotDefined(from.to_canonical_string()));
+            }
+            if is_type {
+                let constructors = move_type(definition, &from, &to)?;
+                transaction.renames.push(Rename {
+                    kind: SymbolKind::Type,
+                    from: from.to_canonical_string(),
+                    to: to.to_canonical_string(),
+                });
+                renaming.rename_type(&from, to.clone());
+                for (constructor_from, constructor_to) in constructors {
+                    transaction.renames.push(Rename {
+                        kind: SymbolKind::Constructor,
+                        from: constructor_from.to_canonical_string(),
+                        to: constructor_to.to_canonical_string(),
+                    });
+                    renaming.rename_value(&constructor_from, constructor_to);
+                }
+            }
+            if is_value {
+                move_value(definition, &from, &to)?;
+                transaction.renames.push(Rename {
+                    kind: SymbolKind::Value,
+                    from: from.to_canonical_string(),
+                    to: to.to_canonical_string(),
+                });
+                renaming.rename_value(&from, to);
+            }
+        }
+    }
+    Ok(renaming)
+}
+
+fn merge_step(
+    definition: &mut PackageDefinition,
+    package: &str,
+    merge: &Merge,
+    transaction: &mut RefactorTransaction,
+) -> Result<Renaming, RefactorError> {
+    let to_module = module_path(&merge.into)?;
+    let mut renaming = Renaming::default();
+    for from_module in &merge.modules {
+        let from_module = module_path(from_module)?;
+        if from_module.to_string() == to_module.to_string() {
+            continue;
+        }
+        let from_key = module_key(&definition.modules, &from_module)
+            .ok_or_else(|| RefactorError::NotDefined(format!("{}:{}", package, from_module)))?;
+        let from = definition.modules.shift_remove(&from_key).unwrap();
+        ensure_module(definition, &to_module, &from);
+        let to_key = module_key(&definition.modules, &to_module).unwrap();
+        let to = &mut definition.modules[&to_key].value;
+        if to.doc.is_none() {
+            to.doc = from.value.doc.clone();
+        }
+        let defined =
+            |name: &Name| FQName::new(Path::new(package), to_module.clone(), name.clone());
+        for (key, tpe) in &from.value.types {
+            let name = Name::from(key.as_str());
+            if local_key(&to.types, &name).is_some() {
+                return Err(RefactorError::AlreadyDefined(
+                    defined(&name).to_canonical_string(),
+                ));
+            }
+            if let TypeDefinition::CustomTypeDefinition { constructors, .. } = &tpe.value {
+                for constructor in &constructors.value {
+                    if constructor_key(to, &constructor.name).is_some() {
+                        return Err(RefactorError::AlreadyDefined(
+                            defined(&constructor.name).to_canonical_string(),
+                        ));
+                    }
+                }
+            }
+        }
+        for key in from.value.values.keys() {
+            let name = Name::from(key.as_str());
+            if local_key(&to.values, &name).is_some() {
+                return Err(RefactorError::AlreadyDefined(
+                    defined(&name).to_canonical_string(),
+                ));
+            }
+        }
+        let from_definitions = from.value;
+        to.types.extend(from_definitions.types);
+        to.values.extend(from_definitions.values);
+        to.ids.types.extend(from_definitions.ids.types);
+        to.ids.values.extend(from_definitions.ids.values);
+
+        transaction.renames.push(Rename {
+            kind: SymbolKind::Module,
+            from: format!("{}:{}", package, from_module),
+            to: format!("{}:{}", package, to_module),
+        });
+        renaming.move_module(package, &from_module, to_module.clone());
+    }
+    Ok(renaming)
+}
+
+/// Make public every private type, constructor, and value of the package
+/// referred to from another of its modules, returning their names
+fn expose_references(distribution: &mut Distribution) -> Vec<String> {
+    let mut references = CrossModuleReferences {
+        package: distribution.package_name().to_string(),
+        ..Default::default()
+    };
+    walk_distribution(&mut references, distribution);
+    let Ok(definition) = definition_mut(distribution) else {
+        return Vec::new();
+    };
+
+    let mut exposed = Vec::new();
+    for fqname in &references.types {
+        if let Some(tpe) = module_definition(definition, fqname).and_then(|module| {
+            local_key(&module.types, &fqname.local_name).map(|key| &mut module.types[&key])
+        }) && tpe.access == Access::Private
+        {
+            tpe.access = Access::Public;
+            exposed.push(fqname.to_canonical_string());
+        }
+    }
+    for fqname in &references.constructors {
+        let Some(module) = module_definition(definition, fqname) else {
+            continue;
+        };
+        let Some((type_key, _)) = constructor_key(module, &fqname.local_name) else {
+            continue;
+        };
+        let tpe = &mut module.types[&type_key];
+        let mut changed = tpe.access == Access::Private;
+        tpe.access = Access::Public;
+        if let TypeDefinition::CustomTypeDefinition { constructors, .. } = &mut tpe.value {
+            changed |= constructors.access == Access::Private;
+            constructors.access = Access::Public;
+        }
+        if changed {
+            exposed.push(fqname.to_canonical_string());
+        }
+    }
+    for fqname in &references.values {
+        if let Some(value) = module_definition(definition, fqname).and_then(|module| {
+            local_key(&module.values, &fqname.local_name).map(|key| &mut module.values[&key])
+        }) && value.access == Access::Private
+        {
+            value.access = Access::Public;
+            exposed.push(fqname.to_canonical_string());
+        }
+    }
+    exposed
+}
+
+fn module_definition<'a>(
+    definition: &'a mut PackageDefinition,
+    fqname: &FQName,
+) -> Option<&'a mut ModuleDefinition> {
+    module_key(&definition.modules, &fqname.module_path)
+        .map(|key| &mut definition.modules[&key].value)
+}
+
+/// Definitions of the package referred to from one of its other modules
+#[derive(Default)]
+struct CrossModuleReferences {
+    package: String,
+    types: IndexSet<FQName>,
+    constructors: IndexSet<FQName>,
+    values: IndexSet<FQName>,
+}
+
+impl CrossModuleReferences {
+    fn crosses(&self, context: &VisitContext, fqname: &FQName) -> bool {
+        fqname.package_path.to_string() == self.package
+            && context
+                .module
+                .as_ref()
+                .is_some_and(|module| module.to_string() != fqname.module_path.to_string())
+    }
+}
+
+impl DistributionVisitor for CrossModuleReferences {
+    fn enter_type(&mut self, context: &VisitContext, tpe: &Type) -> Walk {
+        if let Type::Reference(_, fqname, _) = tpe
+            && self.crosses(context, fqname)
+        {
+            self.types.insert(fqname.clone());
+        }
+        Walk::Continue
+    }
+
+    fn enter_value(&mut self, context: &VisitContext, value: &Value) -> Walk {
+        match value {
+            Value::Reference(_, fqname) if self.crosses(context, fqname) => {
+                self.values.insert(fqname.clone());
+            }
+            Value::Constructor(_, fqname) if self.crosses(context, fqname) => {
+                self.constructors.insert(fqname.clone());
+            }
+            _ => {}
+        }
+        Walk::Continue
+    }
+
+    fn enter_pattern(&mut self, context: &VisitContext, pattern: &Pattern) -> Walk {
+        if let Pattern::ConstructorPattern(_, fqname, _) = pattern
+            && self.crosses(context, fqname)
+        {
+            self.constructors.insert(fqname.clone());
+        }
+        Walk::Continue
+    }
+}
+
+/// Module path and local name of `package:module` or `package:module#name`,
+/// which must be in `package`
+fn parse_symbol(symbol: &str, package: &str) -> Result<(Path, Option<Name>), RefactorError> {
+    let invalid = || RefactorError::InvalidName(symbol.to_string());
+    let (package_path, rest) = symbol.split_once(':').ok_or_else(invalid)?;
+    let (module_path, name) = match rest.split_once('#') {
+        Some((module_path, name)) => (module_path, Some(name)),
+        None => (rest, None),
+    };
+    if package_path.is_empty()
+        || module_path.is_empty()
+        || name.is_some_and(str::is_empty)
+        || rest.contains(':')
+    {
+        return Err(invalid());
+    }
+    if Path::new(package_path).to_string() != package {
+        return Err(RefactorError::OtherPackage {
+            name: symbol.to_string(),
+            package: package.to_string(),
+        });
+    }
+    Ok((Path::new(module_path), name.map(Name::from)))
+}
+
+/// Key of the module at `path`
+pub(super) fn module_key<V>(modules: &IndexMap<String, V>, path: &Path) -> Option<String> {
+    let path = path.to_string();
+    modules
+        .keys()
+        .find(|key| Path::new(key).to_string() == path)
+        .cloned()
+}
+
+/// Key of the definition named `name`
+pub(super) fn local_key<V>(definitions: &IndexMap<String, V>, name: &Name) -> Option<String> {
+    let name = name.to_string();
+    definitions
+        .keys()
+        .find(|key| Name::from(key.as_str()).to_string() == name)
+        .cloned()
+}
+
+/// Replace the key `from` of `map` with `to`, keeping its position
+fn rename_key<V>(map: &mut IndexMap<String, V>, from: &str, to: String) {
+    if let Some(index) = map.get_index_of(from) {
+        let (_, value) = map.shift_remove_index(index).unwrap();
+        map.shift_insert(index, to, value);
+    }
+}
+
+/// The module of the package defining `fqname`
+fn module<'a>(
+    definition: &'a PackageDefinition,
+    fqname: &FQName,
+) -> Result<&'a ModuleDefinition, RefactorError> {
+    module_key(&definition.modules, &fqname.module_path)
+        .map(|key| &definition.modules[&key].value)
+        .ok_or_else(|| RefactorError::NotDefined(fqname.to_canonical_string()))
+}
+
+fn module_mut<'a>(
+    definition: &'a mut PackageDefinition,
+    fqname: &FQName,
+) -> Result<&'a mut ModuleDefinition, RefactorError> {
+    let key = module_key(&definition.modules, &fqname.module_path)
+        .ok_or_else(|| RefactorError::NotDefined(fqname.to_canonical_string()))?;
+    Ok(&mut definition.modules[&key].value)
+}
+
+/// Key of the type defining the constructor `name`, and the constructor's
+/// index within it
+fn constructor_key(module: &ModuleDefinition, name: &Name) -> Option<(String, usize)> {
+    let name = name.to_string();
+    module.types.iter().find_map(|(key, tpe)| match &tpe.value {
+        TypeDefinition::CustomTypeDefinition { constructors, .. } => constructors
+            .value
+            .iter()
+            .position(|constructor| constructor.name.to_string() == name)
+            .map(|index| (key.clone(), index)),
+        _ => None,
+    })
+}
+
+/// What `fqname` names, if it names exactly one definition
+fn definition_kind(
+    definition: &PackageDefinition,
+    fqname: &FQName,
+) -> Result<SymbolKind, RefactorError> {
+    let module = module(definition, fqname)?;
+    let mut kinds = Vec::new();
+    if local_key(&module.types, &fqname.local_name).is_some() {
+        kinds.push(SymbolKind::Type);
+    }
+    if constructor_key(module, &fqname.local_name).is_some() {
+        kinds.push(SymbolKind::Constructor);
+    }
+    if local_key(&module.values, &fqname.local_name).is_some() {
+        kinds.push(SymbolKind::Value);
+    }
+    match kinds.len() {
+        0 => Err(RefactorError::NotDefined(fqname.to_canonical_string())),
+        1 => Ok(kinds[0]),
+        _ => Err(RefactorError::Ambiguous {
+            name: fqname.to_canonical_string(),
+            kinds,
+        }),
+    }
+}
+
+fn rename_module(
+    definition: &mut PackageDefinition,
+    package: &str,
+    from: &Path,
+    to: &Path,
+) -> Result<(), RefactorError> {
+    let from_key = module_key(&definition.modules, from)
+        .ok_or_else(|| RefactorError::NotDefined(format!("{}:{}", package, from)))?;
+    if let Some(to_key) = module_key(&definition.modules, to)
+        && to_key != from_key
+    {
+        return Err(RefactorError::AlreadyDefined(format!("{}:{}", package, to)));
+    }
+    rename_key(&mut definition.modules, &from_key, to.to_string());
+    Ok(())
+}
+
+/// Move the type `from` to `to`, returning the constructors it took to
+/// another module
+pub(super) fn move_type(
+    definition: &mut PackageDefinition,
+    from: &FQName,
+    to: &FQName,
+) -> Result<Vec<(FQName, FQName)>, RefactorError> {
+    let not_defined = || RefactorError::NotDefined(from.to_canonical_string());
+    let from_module = module(definition, from)?;
+    let from_key = local_key(&from_module.types, &from.local_name).ok_or_else(not_defined)?;
+    let to_module = module(definition, to)?;
+    if local_key(&to_module.types, &to.local_name).is_some_and(|key| {
+        from.module_path.to_string() != to.module_path.to_string() || key != from_key
+    }) {
+        return Err(RefactorError::AlreadyDefined(to.to_canonical_string()));
+    }
+
+    let same_module = from.module_path.to_string() == to.module_path.to_string();
+    let mut constructors = Vec::new();
+    if !same_module
+        && let TypeDefinition::CustomTypeDefinition {
+            constructors: defined,
+            ..
+        } = &from_module.types[&from_key].value
+    {
+        for constructor in &defined.value {
+            let moved = FQName::new(
+                to.package_path.clone(),
+                to.module_path.clone(),
+                constructor.name.clone(),
+            );
+            if constructor_key(to_module, &constructor.name).is_some() {
+                return Err(RefactorError::AlreadyDefined(moved.to_canonical_string()));
+            }
+            let from = FQName::new(
+                from.package_path.clone(),
+                from.module_path.clone(),
+                constructor.name.clone(),
+            );
+            constructors.push((from, moved));
+        }
+    }
+
+    let to_key = to.local_name.to_string();
+    let from_module = module_mut(definition, from)?;
+    if same_module {
+        rename_key(&mut from_module.types, &from_key, to_key.clone());
+        rename_key(&mut from_module.ids.types, &from_key, to_key);
+    } else {
+        let tpe = from_module.types.shift_remove(&from_key).unwrap();
+        let id = from_module.ids.types.shift_remove(&from_key);
+        let to_module = module_mut(definition, to)?;
+        to_module.types.insert(to_key.clone(), tpe);
+        if let Some(id) = id {
+            to_module.ids.types.insert(to_key, id);
+        }
+    }
+    Ok(constructors)
+}
+
+/// Move the value `from` to `to`
+pub(super) fn move_value(
+    definition: &mut PackageDefinition,
+    from: &FQName,
+    to: &FQName,
+) -> Result<(), RefactorError> {
+    let from_key = local_key(&module(definition, from)?.values, &from.local_name)
+        .ok_or_else(|| RefactorError::NotDefined(from.to_canonical_string()))?;
+    let same_module = from.module_path.to_string() == to.module_path.to_string();
+    if local_key(&module(definition, to)?.values, &to.local_name)
+        .is_some_and(|key| !same_module || key != from_key)
+    {
+        return Err(RefactorError::AlreadyDefined(to.to_canonical_string()));
+    }
+
+    let to_key = to.local_name.to_string();
+    let from_module = module_mut(definition, from)?;
+    if same_module {
+        rename_key(&mut from_module.values, &from_key, to_key.clone());
+        rename_key(&mut from_module.ids.values, &from_key, to_key);
+    } else {
+        let value: AccessControlled<ValueDefinition> =
+            from_module.values.shift_remove(&from_key).unwrap();
+        let id = from_module.ids.values.shift_remove(&from_key);
+        let to_module = module_mut(definition, to)?;
+        to_module.values.insert(to_key.clone(), value);
+        if let Some(id) = id {
+            to_module.ids.values.insert(to_key, id);
+        }
+    }
+    Ok(())
+}
+
+fn rename_constructor(
+    definition: &mut PackageDefinition,
+    from: &FQName,
+    to: &FQName,
+) -> Result<(), RefactorError> {
+    if from.module_path.to_string() != to.module_path.to_string() {
+        return Err(RefactorError::ConstructorMove {
+            from: from.to_canonical_string(),
+            module: from.module_path.to_string(),
+        });
+    }
+    let module = module_mut(definition, from)?;
+    let (type_key, index) = constructor_key(module, &from.local_name)
+        .ok_or_else(|| RefactorError::NotDefined(from.to_canonical_string()))?;
+    if constructor_key(module, &to.local_name)
+        .is_some_and(|existing| existing != (type_key.clone(), index))
+    {
+        return Err(RefactorError::AlreadyDefined(to.to_canonical_string()));
+    }
+    if let TypeDefinition::CustomTypeDefinition { constructors, .. } =
+        &mut module.types[&type_key].value
+    {
+        constructors.value[index].name = to.local_name.clone();
+    }
+    Ok(())
+}
+
+/// New names of renamed definitions, by canonical FQName, and of renamed
+/// modules, by `package:module`
+#[derive(Debug, Default)]
+pub(super) struct Renaming {
+    types: HashMap<String, FQName>,
+    values: HashMap<String, FQName>,
+    modules: HashMap<String, Path>,
+}
+
+impl Renaming {
+    pub(super) fn rename_type(&mut self, from: &FQName, to: FQName) {
+        self.types.insert(from.to_canonical_string(), to);
+    }
+
+    /// Rename a value or constructor
+    pub(super) fn rename_value(&mut self, from: &FQName, to: FQName) {
+        self.values.insert(from.to_canonical_string(), to);
+    }
+
+    pub(super) fn move_module(&mut self, package: &str, from: &Path, to: Path) {
+        self.modules.insert(format!("{}:{}", package, from), to);
+    }
+
+    /// New name of the type, or value and constructor, `fqname`
+    fn renamed(&self, fqname: &FQName, tpe: bool) -> Option<FQName> {
+        let definitions = if tpe { &self.types } else { &self.values };
+        if let Some(renamed) = definitions.get(&fqname.to_canonical_string()) {
+            return Some(renamed.clone());
+        }
+        let module = format!("{}:{}", fqname.package_path, fqname.module_path);
+        self.modules.get(&module).map(|module_path| {
+            FQName::new(
+                fqname.package_path.clone(),
+                module_path.clone(),
+                fqname.local_name.clone(),
+            )
+        })
+    }
+
+    /// Rewrite every reference to a renamed definition in `distribution`,
+    /// returning how many were rewritten
+    pub(super) fn apply(&self, distribution: &mut Distribution) -> usize {
+        let mut rewrite = Rewrite {
+            renaming: self,
+            references: 0,
+        };
+        walk_distribution_mut(&mut rewrite, distribution);
+        if let Distribution::Application(app) = distribution {
+            for entry_point in app.entry_points.values_mut() {
+                if let Some(renamed) = entry_point
+                    .fqname()
+                    .ok()
+                    .and_then(|fqname| self.renamed(&fqname, false))
+                {
+                    entry_point.target = renamed.to_canonical_string();
+                    rewrite.references += 1;
+                }
+            }
+        }
+        rewrite.references
+    }
+}
+
+/// Rewrites references with a [`Renaming`], counting them
+struct Rewrite<'a> {
+    renaming: &'a Renaming,
+    references: usize,
+}
+
+impl Rewrite<'_> {
+    fn rewrite(&mut self, fqname: &mut FQName, tpe: bool) {
+        if let Some(renamed) = self.renaming.renamed(fqname, tpe) {
+            *fqname = renamed;
+            self.references += 1;
+        }
+    }
+
+    /// Holes name what they could not resolve, which may have been renamed
+    /// since
+    fn rewrite_reason(&mut self, reason: &mut HoleReason) {
+        if let HoleReason::UnresolvedReference { target } = reason {
+            let tpe = self.renaming.renamed(target, false).is_none();
+            self.rewrite(target, tpe);
+        }
+    }
+}
+
+impl DistributionVisitorMut for Rewrite<'_> {
+    fn enter_value_definition(
+        &mut self,
+        _context: &VisitContext,
+        definition: &mut AccessControlled<ValueDefinition>,
+    ) -> Walk {
+        if let ValueBody::Incomplete(reason) = &mut definition.value.body {
+            self.rewrite_reason(reason);
+        }
+        Walk::Continue
+    }
+
+    fn enter_type(&mut self, _context: &VisitContext, tpe: &mut Type) -> Walk {
+        if let Type::Reference(_, fqname, _) = tpe {
+            self.rewrite(fqname, true);
+        }
+        Walk::Continue
+    }
+
+    fn enter_value(&mut self, _context: &VisitContext, value: &mut Value) -> Walk {
+        match value {
+            Value::Reference(_, fqname) | Value::Constructor(_, fqname) => {
+                self.rewrite(fqname, false)
+            }
+            Value::Native(_, fqname, _) => self.rewrite(fqname, false),
+            Value::Hole(_, reason, _) => self.rewrite_reason(reason),
+            _ => {}
+        }
+        Walk::Continue
+    }
+
+    fn enter_pattern(&mut self, _context: &VisitContext, pattern: &mut Pattern) -> Walk {
+        if let Pattern::ConstructorPattern(_, fqname, _) = pattern {
+            self.rewrite(fqname, false);
+        }
+        Walk::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::parse;
+    use super::*;
+    use crate::ir::v4::text::print_distribution;
+
+    fn application() -> Distribution {
+        parse(
+            "application acme/shop\n\nentry start main acme/shop:orders#total\n\n\nmodule orders\n\ntype Status\n    = Open\n    | Closed\n\ntotal (s : acme/shop:orders#Status) : morphir/sdk:basics#Int =\n    case s of\n        acme/shop:orders#Open ->\n            acme/shop:billing#fee\n        _ ->\n            0\n\nreopen : acme/shop:orders#Status =\n    acme/shop:orders#Open\n\n\nmodule billing\n\nfee : morphir/sdk:basics#Int =\n    acme/shop:orders#total acme/shop:orders#Closed\n",
+        )
+    }
+
+    #[test]
+    fn test_rename_value_rewrites_references() {
+        let mut distribution = application();
+        let transaction = rename(
+            &mut distribution,
+            "acme/shop:orders#total",
+            "acme/shop:orders#grand-total",
+            None,
+        )
+        .unwrap();
+        assert_eq!(transaction.references, 2);
+        assert_eq!(
+            transaction.renames,
+            vec![Rename {
+                kind: SymbolKind::Value,
+                from: "acme/shop:orders#total".to_string(),
+                to: "acme/shop:orders#grand-total".to_string(),
+            }]
+        );
+        let text = print_distribution(&distribution);
+        assert!(text.contains("entry start main acme/shop:orders#grand-total"));
+        assert!(text.contains("\ngrand-total (s : acme/shop:orders#Status)"));
+        assert!(text.contains("acme/shop:orders#grand-total acme/shop:orders#Closed"));
+        assert!(!text.contains("#total"));
+    }
+
+    #[test]
+    fn test_rename_type_and_constructor() {
+        let mut distribution = application();
+        let transaction = rename(
+            &mut distribution,
+            "acme/shop:orders#status",
+            "acme/shop:billing#state",
+            Some(SymbolKind::Type),
+        )
+        .unwrap();
+        // The type twice, and its constructors in a pattern and two values
+        assert_eq!(transaction.references, 5);
+        assert_eq!(transaction.renames.len(), 3);
+        assert_eq!(transaction.renames[1].kind, SymbolKind::Constructor);
+
+        let transaction = rename(
+            &mut distribution,
+            "acme/shop:billing#open",
+            "acme/shop:billing#pending",
+            None,
+        )
+        .unwrap();
+        assert_eq!(transaction.references, 2);
+        let text = print_distribution(&distribution);
+        assert!(text.contains("acme/shop:billing#State"));
+        assert!(text.contains("acme/shop:billing#Pending ->"));
+        assert!(!text.contains("orders#Open") && !text.contains("orders#Status"));
+
+        assert_eq!(
+            rename(
+                &mut distribution,
+                "acme/shop:billing#pending",
+                "acme/shop:billing#closed",
+                None,
+            ),
+            Err(RefactorError::AlreadyDefined(
+                "acme/shop:billing#closed".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_rename_module() {
+        let mut distribution = application();
+        let transaction = rename(
+            &mut distribution,
+            "acme/shop:billing",
+            "acme/shop:fees",
+            None,
+        )
+        .unwrap();
+        assert_eq!(transaction.references, 1);
+        let definition = distribution.definition().unwrap();
+        assert_eq!(
+            definition.modules.keys().collect::<Vec<_>>(),
+            ["orders", "fees"]
+        );
+        assert!(print_distribution(&distribution).contains("acme/shop:fees#fee"));
+
+        assert!(matches!(
+            rename(
+                &mut distribution,
+                "other/pkg:fees",
+                "acme/shop:billing",
+                None
+            ),
+            Err(RefactorError::OtherPackage { .. })
+        ));
+        assert!(matches!(
+            rename(
+                &mut distribution,
+                "acme/shop:fees",
+                "acme/shop:orders#fees",
+                None
+            ),
+            Err(RefactorError::KindMismatch { .. })
+        ));
+    }
+
+    fn library() -> Distribution {
+        parse(
+            "library acme/shop\n\n\nmodule orders\n\nprivate type Status\n    = Open\n    | Closed\n\nprivate fee : morphir/sdk:basics#Int =\n    1\n\ntotal (s : acme/shop:orders#Status) : morphir/sdk:basics#Int =\n    case s of\n        acme/shop:orders#Open ->\n            acme/shop:orders#fee\n        _ ->\n            acme/shop:billing#tax\n\n\nmodule billing\n\ntax : morphir/sdk:basics#Int =\n    2\n",
+        )
+    }
+
+    #[test]
+    fn test_split_module_exposes_what_stays_referenced() {
+        let mut distribution = library();
+        let transaction = split_module(
+            &mut distribution,
+            "orders",
+            IndexMap::from([(
+                "orders/fees".to_string(),
+                vec!["fee".to_string(), "status".to_string()],
+            )]),
+        )
+        .unwrap();
+        assert_eq!(transaction.references, 3);
+        assert_eq!(
+            transaction.exposed,
+            ["acme/shop:orders/fees#status", "acme/shop:orders/fees#fee"]
+        );
+        let text = print_distribution(&distribution);
+        assert!(text.contains(
+            "\n\n\nmodule orders/fees\n\ntype Status\n    = Open\n    | Closed\n\nfee : morphir/sdk:basics#Int"
+        ));
+        assert!(
+            text.contains("acme/shop:orders/fees#Open ->\n            acme/shop:orders/fees#fee")
+        );
+
+        let before = distribution.clone();
+        assert_eq!(
+            split_module(
+                &mut distribution,
+                "orders",
+                IndexMap::from([("billing".to_string(), vec!["missing".to_string()])]),
+            ),
+            Err(RefactorError::NotDefined(
+                "acme/shop:orders#missing".to_string()
+            ))
+        );
+        assert_eq!(distribution, before);
+    }
+
+    #[test]
+    fn test_merge_modules() {
+        let mut distribution = library();
+        let transaction = merge_modules(&mut distribution, &["billing"], "orders").unwrap();
+        assert_eq!(transaction.references, 1);
+        assert!(transaction.exposed.is_empty());
+        let definition = distribution.definition().unwrap();
+        assert_eq!(definition.modules.keys().collect::<Vec<_>>(), ["orders"]);
+        assert!(
+            definition.modules["orders"]
+                .value
+                .values
+                .contains_key("tax")
+        );
+        assert!(print_distribution(&distribution).contains("acme/shop:orders#tax"));
+
+        let mut distribution = library();
+        let plan: RestructurePlan = serde_json::from_str(
+            r#"{ "split": [{ "module": "orders", "into": { "fees": ["fee"] } }],
+                 "merge": [{ "modules": ["fees", "billing"], "into": "charges" }] }"#,
+        )
+        .unwrap();
+        let transaction = restructure(&mut distribution, &plan).unwrap();
+        assert_eq!(transaction.exposed, ["acme/shop:charges#fee"]);
+        let text = print_distribution(&distribution);
+        assert!(text.contains("acme/shop:charges#fee") && text.contains("acme/shop:charges#tax"));
+        assert_eq!(
+            distribution
+                .definition()
+                .unwrap()
+                .modules
+                .keys()
+                .collect::<Vec<_>>(),
+            ["orders", "charges"]
+        );
+    }
+
+    #[test]
+    fn test_log_finds_the_transaction_behind_a_hole() {
+        let mut log = RefactorLog::default();
+        let transaction = RefactorTransaction::new();
+        let reason = transaction.hole_reason();
+        log.record(transaction.clone());
+        assert_eq!(log.deleted_by(&reason), Some(&transaction));
+        assert_eq!(log.deleted_by(&HoleReason::Draft), None);
+
+        let json = serde_json::to_value(&log).unwrap();
+        assert_eq!(json[0]["txId"], transaction.tx_id);
+    }
+}
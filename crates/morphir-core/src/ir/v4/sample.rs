@@ -0,0 +1,999 @@
+//! Fixture extraction for bug minimization.
+//!
+//! Extracts a minimal, self-contained sub-distribution from a V4 distribution,
+//! starting from one or more root FQNames (e.g. the definition that triggered a
+//! failure). The dependency closure of the roots within the package is pulled in
+//! automatically; definitions beyond the configured depth keep their signatures
+//! but have their bodies replaced with holes, so the resulting fixture stays small
+//! enough to attach to an issue while still type-checking at the boundary.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let root = FQName::from_canonical_string("my/pkg:orders#total")?;
+//! let sample = sample_distribution(&dist, &[root], &SampleOptions::default())?;
+//! println!("{} definitions kept", sample.included.len());
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use indexmap::IndexMap;
+
+use super::access::AccessControlled;
+use super::distribution::{ApplicationContent, Dependencies, Distribution, LibraryContent};
+use super::module::ModuleDefinition;
+use super::package::{PackageDefinition, PackageSpecification};
+use super::pattern::Pattern;
+use super::types::{Type, TypeDefinition, TypeSpecification};
+use super::value::{HoleReason, Value, ValueBody, ValueDefinition};
+use crate::error::{Error, Result};
+use crate::naming::{FQName, Name, Path};
+
+/// Options controlling how a sample is extracted.
+#[derive(Debug, Clone, Default)]
+pub struct SampleOptions {
+    /// Maximum reference depth (from the roots) at which value bodies are kept.
+    ///
+    /// Values referenced beyond this depth are stubbed with a hole body. `None`
+    /// keeps the full dependency closure.
+    pub max_depth: Option<usize>,
+}
+
+/// The result of extracting a sample from a distribution.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// The extracted sub-distribution
+    pub distribution: Distribution,
+    /// Definitions kept with their full bodies
+    pub included: Vec<FQName>,
+    /// Value definitions kept as signatures with a hole body
+    pub stubbed: Vec<FQName>,
+    /// References that could not be resolved, within the package or against
+    /// the dependency specifications
+    pub missing: Vec<FQName>,
+}
+
+/// Extract a minimal sub-distribution reachable from `roots`.
+///
+/// Roots may name values, types, or constructors of the distribution's own
+/// package. Dependency specifications are trimmed to the modules actually
+/// referenced by the sample; references missing from them are reported like
+/// unresolved local references. Specs distributions carry no definitions and
+/// are rejected.
+pub fn sample_distribution(
+    dist: &Distribution,
+    roots: &[FQName],
+    options: &SampleOptions,
+) -> Result<Sample> {
+    let (package_name, dependencies, def) = match dist {
+        Distribution::Library(lib) => (&lib.package_name, &lib.dependencies, &lib.def),
+        Distribution::Application(app) => (&app.package_name, &app.dependencies, &app.def),
+        Distribution::Specs(_) => {
+            return Err(Error::InvalidIr(
+                "cannot sample a specs distribution: it has no definitions".to_string(),
+            ));
+        }
+    };
+
+    if roots.is_empty() {
+        return Err(Error::Validation(
+            "at least one root FQName is required to extract a sample".to_string(),
+        ));
+    }
+
+    let index = PackageIndex::new(&package_name.0, def);
+    let mut queue: VecDeque<(Item, usize)> = VecDeque::new();
+    for root in roots {
+        match index.resolve_root(root) {
+            Some(item) => queue.push_back((item, 0)),
+            None => {
+                return Err(Error::Validation(format!(
+                    "'{}' not found in package '{}'",
+                    root.to_canonical_string(),
+                    package_name
+                )));
+            }
+        }
+    }
+
+    let mut kept: HashMap<Item, Keep> = HashMap::new();
+    let mut missing: Vec<FQName> = Vec::new();
+    // Referenced dependency modules, by normalized package and module path
+    let mut external_modules: HashSet<(String, String)> = HashSet::new();
+
+    while let Some((item, depth)) = queue.pop_front() {
+        if kept.contains_key(&item) {
+            continue;
+        }
+
+        let mut refs = Vec::new();
+        let keep = match item.kind {
+            ItemKind::Type => {
+                let Some(type_def) = index.type_def(&item) else {
+                    missing.push(item.fqname.clone());
+                    continue;
+                };
+                collect_type_definition_refs(&type_def.value, &mut refs);
+                Keep::Full
+            }
+            ItemKind::Value => {
+                let Some(value_def) = index.value_def(&item) else {
+                    missing.push(item.fqname.clone());
+                    continue;
+                };
+                let within_depth = options.max_depth.is_none_or(|max| depth <= max);
+                collect_signature_refs(&value_def.value, &mut refs);
+                if within_depth {
+                    if let ValueBody::Expression(body) = &value_def.value.body {
+                        collect_value_refs(body, &mut refs);
+                    }
+                    Keep::Full
+                } else {
+                    Keep::Stub
+                }
+            }
+        };
+        kept.insert(item, keep);
+
+        for reference in refs {
+            let fqname = reference.fqname();
+            if !index.is_local(fqname) {
+                external_modules.insert((
+                    fqname.package_path.to_string(),
+                    fqname.module_path.to_string(),
+                ));
+                if !in_dependency_specification(dependencies, fqname) {
+                    missing.push(fqname.clone());
+                }
+                continue;
+            }
+            match index.resolve_reference(&reference) {
+                Some(next) => queue.push_back((next, depth + 1)),
+                None => missing.push(fqname.clone()),
+            }
+        }
+    }
+
+    let (sampled_def, included, stubbed) = index.build(&kept);
+    let sampled_deps: Dependencies = dependencies
+        .iter()
+        .filter_map(|(key, spec)| {
+            let package = Path::new(key).to_string();
+            let modules: IndexMap<_, _> = spec
+                .modules
+                .iter()
+                .filter(|(module_key, _)| {
+                    external_modules.contains(&(package.clone(), Path::new(module_key).to_string()))
+                })
+                .map(|(module_key, module)| (module_key.clone(), module.clone()))
+                .collect();
+            (!modules.is_empty()).then(|| (key.clone(), PackageSpecification { modules }))
+        })
+        .collect();
+
+    let distribution = match dist {
+        Distribution::Library(lib) => Distribution::Library(LibraryContent {
+            package_name: lib.package_name.clone(),
+            dependencies: sampled_deps,
+            def: sampled_def,
+        }),
+        Distribution::Application(app) => {
+            let kept_targets: HashSet<String> =
+                included.iter().map(|f| f.to_canonical_string()).collect();
+            let entry_points = app
+                .entry_points
+                .iter()
+                .filter(|(_, ep)| {
+                    FQName::from_canonical_string(&ep.target)
+                        .is_ok_and(|fq| kept_targets.contains(&fq.to_canonical_string()))
+                })
+                .map(|(k, ep)| (k.clone(), ep.clone()))
+                .collect();
+            Distribution::Application(ApplicationContent {
+                package_name: app.package_name.clone(),
+                dependencies: sampled_deps,
+                def: sampled_def,
+                entry_points,
+            })
+        }
+        Distribution::Specs(_) => unreachable!("rejected above"),
+    };
+
+    missing.sort_by_key(|f| f.to_canonical_string());
+    missing.dedup();
+
+    Ok(Sample {
+        distribution,
+        included,
+        stubbed,
+        missing,
+    })
+}
+
+/// Whether a reference outside the package resolves against the dependency
+/// specifications. Packages that are not dependencies at all (such as an
+/// implicit SDK) are assumed to resolve.
+fn in_dependency_specification(dependencies: &Dependencies, fqname: &FQName) -> bool {
+    let package = fqname.package_path.to_string();
+    let module = fqname.module_path.to_string();
+    let local = fqname.local_name.to_kebab_case();
+    let Some((_, spec)) = dependencies
+        .iter()
+        .find(|(key, _)| Path::new(key).to_string() == package)
+    else {
+        return true;
+    };
+    spec.modules
+        .iter()
+        .filter(|(module_key, _)| Path::new(module_key).to_string() == module)
+        .any(|(_, module)| {
+            let defines = |key: &String| Name::from(key.as_str()).to_kebab_case() == local;
+            module.types.keys().any(defines)
+                || module.values.keys().any(defines)
+                || module.types.values().any(|spec| match spec {
+                    TypeSpecification::CustomTypeSpecification { constructors, .. } => {
+                        constructors.iter().any(|c| c.name.to_kebab_case() == local)
+                    }
+                    _ => false,
+                })
+        })
+}
+
+/// Find canonical FQNames (`package:module#name`) mentioned in free text.
+///
+/// Used to seed a sample from a diagnostic message when no explicit root is given.
+pub fn fqnames_in_text(text: &str) -> Vec<FQName> {
+    let mut found = Vec::new();
+    for token in text.split(|c: char| c.is_whitespace() || "'\"`()[]{},;".contains(c)) {
+        let token = token.trim_end_matches(['.', ':']);
+        if token.contains(':')
+            && token.contains('#')
+            && let Ok(fqname) = FQName::from_canonical_string(token)
+            && !fqname.local_name.words.is_empty()
+            && !found.contains(&fqname)
+        {
+            found.push(fqname);
+        }
+    }
+    found
+}
+
+// ============================================================================
+// PACKAGE INDEX
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ItemKind {
+    Type,
+    Value,
+}
+
+/// A definition in the sampled package, keyed by its original map keys.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Item {
+    kind: ItemKind,
+    module_key: String,
+    local_key: String,
+    fqname: FQName,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Keep {
+    Full,
+    Stub,
+}
+
+/// Normalized lookup over a package definition.
+///
+/// Map keys in V4 distributions are not guaranteed to be in canonical form
+/// (frontends may emit snake_case or TitleCase), so lookups compare normalized
+/// kebab-case names and keep the original keys for reconstruction.
+struct PackageIndex<'a> {
+    package: String,
+    def: &'a PackageDefinition,
+    modules: HashMap<String, &'a str>,
+    types: HashMap<(String, String), &'a str>,
+    values: HashMap<(String, String), &'a str>,
+    constructors: HashMap<(String, String), &'a str>,
+}
+
+impl<'a> PackageIndex<'a> {
+    fn new(package: &Path, def: &'a PackageDefinition) -> Self {
+        let mut modules = HashMap::new();
+        let mut types = HashMap::new();
+        let mut values = HashMap::new();
+        let mut constructors = HashMap::new();
+
+        for (module_key, module) in &def.modules {
+            let module_norm = Path::new(module_key).to_string();
+            modules.insert(module_norm.clone(), module_key.as_str());
+            for (type_key, type_def) in &module.value.types {
+                types.insert(
+                    (module_norm.clone(), normalize_name(type_key)),
+                    type_key.as_str(),
+                );
+                if let TypeDefinition::CustomTypeDefinition {
+                    constructors: c, ..
+                } = &type_def.value
+                {
+                    for ctor in &c.value {
+                        constructors.insert(
+                            (module_norm.clone(), ctor.name.to_kebab_case()),
+                            type_key.as_str(),
+                        );
+                    }
+                }
+            }
+            for value_key in module.value.values.keys() {
+                values.insert(
+                    (module_norm.clone(), normalize_name(value_key)),
+                    value_key.as_str(),
+                );
+            }
+        }
+
+        Self {
+            package: package.to_string(),
+            def,
+            modules,
+            types,
+            values,
+            constructors,
+        }
+    }
+
+    fn is_local(&self, fqname: &FQName) -> bool {
+        fqname.package_path.to_string() == self.package
+    }
+
+    fn lookup_key(fqname: &FQName) -> (String, String) {
+        (
+            fqname.module_path.to_string(),
+            fqname.local_name.to_kebab_case(),
+        )
+    }
+
+    fn item(&self, kind: ItemKind, key: &(String, String), local_key: &str) -> Option<Item> {
+        let module_key = self.modules.get(&key.0)?;
+        Some(Item {
+            kind,
+            module_key: module_key.to_string(),
+            local_key: local_key.to_string(),
+            fqname: FQName::new(
+                Path::new(&self.package),
+                Path::new(module_key),
+                Name::from(local_key),
+            ),
+        })
+    }
+
+    fn find_value(&self, fqname: &FQName) -> Option<Item> {
+        let key = Self::lookup_key(fqname);
+        let local = self.values.get(&key)?;
+        self.item(ItemKind::Value, &key, local)
+    }
+
+    fn find_type(&self, fqname: &FQName) -> Option<Item> {
+        let key = Self::lookup_key(fqname);
+        let local = self.types.get(&key)?;
+        self.item(ItemKind::Type, &key, local)
+    }
+
+    fn find_constructor_type(&self, fqname: &FQName) -> Option<Item> {
+        let key = Self::lookup_key(fqname);
+        let local = self.constructors.get(&key)?;
+        self.item(ItemKind::Type, &key, local)
+    }
+
+    fn resolve_root(&self, fqname: &FQName) -> Option<Item> {
+        if !self.is_local(fqname) {
+            return None;
+        }
+        self.find_value(fqname)
+            .or_else(|| self.find_type(fqname))
+            .or_else(|| self.find_constructor_type(fqname))
+    }
+
+    fn resolve_reference(&self, reference: &Reference) -> Option<Item> {
+        match reference {
+            Reference::Type(fq) => self.find_type(fq),
+            Reference::Value(fq) => self.find_value(fq),
+            Reference::Constructor(fq) => self.find_constructor_type(fq),
+        }
+    }
+
+    fn module(&self, item: &Item) -> Option<&'a AccessControlled<ModuleDefinition>> {
+        self.def.modules.get(&item.module_key)
+    }
+
+    fn type_def(&self, item: &Item) -> Option<&'a AccessControlled<TypeDefinition>> {
+        self.module(item)?.value.types.get(&item.local_key)
+    }
+
+    fn value_def(&self, item: &Item) -> Option<&'a AccessControlled<ValueDefinition>> {
+        self.module(item)?.value.values.get(&item.local_key)
+    }
+
+    /// Rebuild a package definition containing only the kept items, preserving
+    /// the original module and definition order.
+    fn build(&self, kept: &HashMap<Item, Keep>) -> (PackageDefinition, Vec<FQName>, Vec<FQName>) {
+        let mut modules = IndexMap::new();
+        let mut included = Vec::new();
+        let mut stubbed = Vec::new();
+
+        for (module_key, module) in &self.def.modules {
+            let module_path = Path::new(module_key);
+            let fqname = |local: &str| {
+                FQName::new(
+                    Path::new(&self.package),
+                    module_path.clone(),
+                    Name::from(local),
+                )
+            };
+            let lookup = |kind: ItemKind, local: &str| {
+                kept.get(&Item {
+                    kind,
+                    module_key: module_key.clone(),
+                    local_key: local.to_string(),
+                    fqname: fqname(local),
+                })
+                .copied()
+            };
+
+            let mut types = IndexMap::new();
+            for (type_key, type_def) in &module.value.types {
+                if lookup(ItemKind::Type, type_key).is_some() {
+                    types.insert(type_key.clone(), type_def.clone());
+                    included.push(fqname(type_key));
+                }
+            }
+
+            let mut values = IndexMap::new();
+            for (value_key, value_def) in &module.value.values {
+                match lookup(ItemKind::Value, value_key) {
+                    Some(Keep::Full) => {
+                        values.insert(value_key.clone(), value_def.clone());
+                        included.push(fqname(value_key));
+                    }
+                    Some(Keep::Stub) => {
+                        values.insert(value_key.clone(), stub_value_definition(value_def));
+                        stubbed.push(fqname(value_key));
+                    }
+                    None => {}
+                }
+            }
+
+            if !types.is_empty() || !values.is_empty() {
+                // Kept definitions keep their identifiers
+                let mut ids = module.value.ids.clone();
+                ids.types.retain(|name, _| types.contains_key(name));
+                ids.values.retain(|name, _| values.contains_key(name));
+                modules.insert(
+                    module_key.clone(),
+                    AccessControlled {
+                        access: module.access.clone(),
+                        doc: module.doc.clone(),
+                        value: ModuleDefinition {
+                            types,
+                            values,
+                            doc: module.value.doc.clone(),
+                            ids,
+                        },
+                    },
+                );
+            }
+        }
+
+        (PackageDefinition { modules }, included, stubbed)
+    }
+}
+
+fn normalize_name(key: &str) -> String {
+    Name::from(key).to_kebab_case()
+}
+
+/// Keep the signature of a value definition and replace its body with a hole.
+fn stub_value_definition(
+    def: &AccessControlled<ValueDefinition>,
+) -> AccessControlled<ValueDefinition> {
+    AccessControlled {
+        access: def.access.clone(),
+        doc: def.doc.clone(),
+        value: ValueDefinition {
+            input_types: def.value.input_types.clone(),
+            output_type: def.value.output_type.clone(),
+            body: ValueBody::Incomplete(HoleReason::Draft),
+        },
+    }
+}
+
+// ============================================================================
+// REFERENCE COLLECTION
+// ============================================================================
+
+/// A reference from one definition to another.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Reference {
+    Type(FQName),
+    Value(FQName),
+    Constructor(FQName),
+}
+
+impl Reference {
+    pub(super) fn fqname(&self) -> &FQName {
+        match self {
+            Reference::Type(fq) | Reference::Value(fq) | Reference::Constructor(fq) => fq,
+        }
+    }
+}
+
+pub(super) fn collect_type_definition_refs(def: &TypeDefinition, out: &mut Vec<Reference>) {
+    match def {
+        TypeDefinition::TypeAliasDefinition { type_expr, .. } => collect_type_refs(type_expr, out),
+        TypeDefinition::CustomTypeDefinition { constructors, .. } => {
+            for ctor in &constructors.value {
+                for arg in &ctor.args {
+                    collect_type_refs(&arg.arg_type, out);
+                }
+            }
+        }
+        TypeDefinition::IncompleteTypeDefinition { .. } => {}
+    }
+}
+
+pub(super) fn collect_signature_refs(def: &ValueDefinition, out: &mut Vec<Reference>) {
+    for input in def.input_types.values() {
+        collect_type_refs(&input.input_type, out);
+    }
+    collect_type_refs(&def.output_type, out);
+}
+
+fn collect_type_refs(tpe: &Type, out: &mut Vec<Reference>) {
+    match tpe {
+        Type::Reference(_, fqname, args) => {
+            out.push(Reference::Type(fqname.clone()));
+            for arg in args {
+                collect_type_refs(arg, out);
+            }
+        }
+        Type::Tuple(_, elements) => {
+            for element in elements {
+                collect_type_refs(element, out);
+            }
+        }
+        Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+            for field in fields {
+                collect_type_refs(&field.tpe, out);
+            }
+        }
+        Type::Function(_, arg, result) => {
+            collect_type_refs(arg, out);
+            collect_type_refs(result, out);
+        }
+        Type::Variable(_, _) | Type::Unit(_) => {}
+    }
+}
+
+fn collect_pattern_refs(pattern: &Pattern, out: &mut Vec<Reference>) {
+    match pattern {
+        Pattern::ConstructorPattern(_, fqname, args) => {
+            out.push(Reference::Constructor(fqname.clone()));
+            for arg in args {
+                collect_pattern_refs(arg, out);
+            }
+        }
+        Pattern::AsPattern(_, inner, _) => collect_pattern_refs(inner, out),
+        Pattern::TuplePattern(_, elements) => {
+            for element in elements {
+                collect_pattern_refs(element, out);
+            }
+        }
+        Pattern::HeadTailPattern(_, head, tail) => {
+            collect_pattern_refs(head, out);
+            collect_pattern_refs(tail, out);
+        }
+        Pattern::WildcardPattern(_)
+        | Pattern::EmptyListPattern(_)
+        | Pattern::LiteralPattern(_, _)
+        | Pattern::UnitPattern(_) => {}
+    }
+}
+
+pub(super) fn collect_value_refs(value: &Value, out: &mut Vec<Reference>) {
+    match value {
+        Value::Reference(_, fqname) | Value::Native(_, fqname, _) => {
+            out.push(Reference::Value(fqname.clone()))
+        }
+        Value::Constructor(_, fqname) => out.push(Reference::Constructor(fqname.clone())),
+        Value::Tuple(_, elements) | Value::List(_, elements) => {
+            for element in elements {
+                collect_value_refs(element, out);
+            }
+        }
+        Value::Record(_, fields) => {
+            for field in fields {
+                collect_value_refs(&field.1, out);
+            }
+        }
+        Value::Field(_, target, _) => collect_value_refs(target, out),
+        Value::Apply(_, function, argument) => {
+            collect_value_refs(function, out);
+            collect_value_refs(argument, out);
+        }
+        Value::Lambda(_, pattern, body) => {
+            collect_pattern_refs(pattern, out);
+            collect_value_refs(body, out);
+        }
+        Value::LetDefinition(_, _, def, body) => {
+            collect_signature_refs(def, out);
+            if let ValueBody::Expression(expr) = &def.body {
+                collect_value_refs(expr, out);
+            }
+            collect_value_refs(body, out);
+        }
+        Value::LetRecursion(_, bindings, body) => {
+            for binding in bindings {
+                collect_signature_refs(&binding.1, out);
+                if let ValueBody::Expression(expr) = &binding.1.body {
+                    collect_value_refs(expr, out);
+                }
+            }
+            collect_value_refs(body, out);
+        }
+        Value::Destructure(_, pattern, bound, body) => {
+            collect_pattern_refs(pattern, out);
+            collect_value_refs(bound, out);
+            collect_value_refs(body, out);
+        }
+        Value::IfThenElse(_, cond, then_branch, else_branch) => {
+            collect_value_refs(cond, out);
+            collect_value_refs(then_branch, out);
+            collect_value_refs(else_branch, out);
+        }
+        Value::PatternMatch(_, subject, cases) => {
+            collect_value_refs(subject, out);
+            for case in cases {
+                collect_pattern_refs(&case.0, out);
+                collect_value_refs(&case.1, out);
+            }
+        }
+        Value::UpdateRecord(_, target, fields) => {
+            collect_value_refs(target, out);
+            for field in fields {
+                collect_value_refs(&field.1, out);
+            }
+        }
+        Value::Hole(_, _, Some(tpe)) => collect_type_refs(tpe, out),
+        Value::Literal(_, _)
+        | Value::Variable(_, _)
+        | Value::FieldFunction(_, _)
+        | Value::Unit(_)
+        | Value::Hole(_, _, None)
+        | Value::External(_, _, _) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::attributes::{TypeAttributes, ValueAttributes};
+    use super::super::fixtures::{fq, int as int_type, public, reference};
+    use super::super::types::{ConstructorArg, ConstructorDefinition};
+    use super::super::value::InputType;
+    use super::super::{EntryPoint, EntryPointKind, ModuleSpecification};
+    use super::*;
+    use crate::naming::PackageName;
+
+    fn constant(body: Value) -> AccessControlled<ValueDefinition> {
+        public(ValueDefinition::new(vec![], int_type(), body))
+    }
+
+    /// Package `my/pkg` with:
+    /// - `orders#total` -> `orders#subtotal` -> `pricing#rate`
+    /// - `orders#unrelated` (not reachable)
+    /// - `pricing#status` custom type with constructor `active`, used by `orders#check`
+    fn sample_library() -> Distribution {
+        let mut orders = ModuleDefinition {
+            types: IndexMap::new(),
+            values: IndexMap::new(),
+            doc: Some("Orders".to_string()),
+            ids: Default::default(),
+        };
+        orders.values.insert(
+            "total".to_string(),
+            constant(reference("my/pkg:orders#subtotal")),
+        );
+        orders.values.insert(
+            "subtotal".to_string(),
+            constant(reference("my/pkg:pricing#rate")),
+        );
+        orders.values.insert(
+            "unrelated".to_string(),
+            constant(Value::unit(ValueAttributes::default())),
+        );
+        orders.values.insert(
+            "check".to_string(),
+            public(ValueDefinition::new(
+                vec![InputType::new(
+                    Name::from("s"),
+                    ValueAttributes::default(),
+                    int_type(),
+                )],
+                int_type(),
+                Value::Constructor(ValueAttributes::default(), fq("my/pkg:pricing#active")),
+            )),
+        );
+
+        let mut pricing = ModuleDefinition {
+            types: IndexMap::new(),
+            values: IndexMap::new(),
+            doc: None,
+            ids: Default::default(),
+        };
+        pricing.values.insert(
+            "rate".to_string(),
+            constant(Value::literal(
+                ValueAttributes::default(),
+                super::super::Literal::Integer(3),
+            )),
+        );
+        pricing.types.insert(
+            "status".to_string(),
+            public(TypeDefinition::CustomTypeDefinition {
+                type_params: vec![],
+                constructors: public(vec![ConstructorDefinition {
+                    name: Name::from("active"),
+                    args: vec![ConstructorArg {
+                        name: Name::from("since"),
+                        arg_type: int_type(),
+                    }],
+                }]),
+            }),
+        );
+
+        let mut modules = IndexMap::new();
+        modules.insert("orders".to_string(), public(orders));
+        modules.insert("pricing".to_string(), public(pricing));
+
+        let mut dependencies = IndexMap::new();
+        dependencies.insert(
+            "morphir/sdk".to_string(),
+            PackageSpecification {
+                modules: IndexMap::from([
+                    (
+                        "basics".to_string(),
+                        ModuleSpecification {
+                            types: IndexMap::from([(
+                                "int".to_string(),
+                                TypeSpecification::OpaqueTypeSpecification {
+                                    type_params: vec![],
+                                },
+                            )]),
+                            values: IndexMap::new(),
+                            doc: None,
+                        },
+                    ),
+                    (
+                        "string".to_string(),
+                        ModuleSpecification {
+                            types: IndexMap::new(),
+                            values: IndexMap::new(),
+                            doc: None,
+                        },
+                    ),
+                ]),
+            },
+        );
+        dependencies.insert(
+            "other/pkg".to_string(),
+            PackageSpecification {
+                modules: IndexMap::new(),
+            },
+        );
+
+        Distribution::Library(LibraryContent {
+            package_name: PackageName::parse("my/pkg"),
+            dependencies,
+            def: PackageDefinition { modules },
+        })
+    }
+
+    fn library_def(dist: &Distribution) -> &LibraryContent {
+        match dist {
+            Distribution::Library(lib) => lib,
+            other => panic!("expected library, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sample_pulls_in_dependency_closure() {
+        let dist = sample_library();
+        let sample = sample_distribution(
+            &dist,
+            &[fq("my/pkg:orders#total")],
+            &SampleOptions::default(),
+        )
+        .unwrap();
+
+        let lib = library_def(&sample.distribution);
+        let orders = &lib.def.modules["orders"].value;
+        assert_eq!(
+            orders.values.keys().collect::<Vec<_>>(),
+            vec!["total", "subtotal"]
+        );
+        assert!(lib.def.modules["pricing"].value.values.contains_key("rate"));
+        assert!(lib.def.modules["pricing"].value.types.is_empty());
+        assert_eq!(sample.included.len(), 3);
+        assert!(sample.stubbed.is_empty());
+        assert!(sample.missing.is_empty());
+    }
+
+    #[test]
+    fn test_sample_trims_dependencies_to_referenced_packages() {
+        let dist = sample_library();
+        let sample = sample_distribution(
+            &dist,
+            &[fq("my/pkg:orders#total")],
+            &SampleOptions::default(),
+        )
+        .unwrap();
+
+        let lib = library_def(&sample.distribution);
+        assert_eq!(
+            lib.dependencies.keys().collect::<Vec<_>>(),
+            vec!["morphir/sdk"]
+        );
+    }
+
+    #[test]
+    fn test_sample_trims_dependency_specifications_to_referenced_modules() {
+        let mut dist = sample_library();
+        let Distribution::Library(lib) = &mut dist else {
+            unreachable!()
+        };
+        lib.def.modules["orders"].value.values["subtotal"]
+            .value
+            .output_type = Type::reference(
+            TypeAttributes::default(),
+            fq("morphir/sdk:basics#decimal"),
+            vec![],
+        );
+
+        let sample = sample_distribution(
+            &dist,
+            &[fq("my/pkg:orders#total")],
+            &SampleOptions::default(),
+        )
+        .unwrap();
+
+        let lib = library_def(&sample.distribution);
+        assert_eq!(
+            lib.dependencies["morphir/sdk"]
+                .modules
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["basics"]
+        );
+        assert_eq!(sample.missing, vec![fq("morphir/sdk:basics#decimal")]);
+    }
+
+    #[test]
+    fn test_sample_stubs_beyond_max_depth() {
+        let dist = sample_library();
+        let sample = sample_distribution(
+            &dist,
+            &[fq("my/pkg:orders#total")],
+            &SampleOptions { max_depth: Some(0) },
+        )
+        .unwrap();
+
+        let lib = library_def(&sample.distribution);
+        let subtotal = &lib.def.modules["orders"].value.values["subtotal"].value;
+        assert_eq!(subtotal.body, ValueBody::Incomplete(HoleReason::Draft));
+        assert_eq!(sample.stubbed, vec![fq("my/pkg:orders#subtotal")]);
+        // Stubbed bodies are not followed, so `pricing#rate` is not pulled in
+        assert!(!lib.def.modules.contains_key("pricing"));
+    }
+
+    #[test]
+    fn test_sample_constructor_pulls_in_owning_type() {
+        let dist = sample_library();
+        let sample = sample_distribution(
+            &dist,
+            &[fq("my/pkg:orders#check")],
+            &SampleOptions::default(),
+        )
+        .unwrap();
+
+        let lib = library_def(&sample.distribution);
+        assert!(
+            lib.def.modules["pricing"]
+                .value
+                .types
+                .contains_key("status")
+        );
+        assert!(sample.included.contains(&fq("my/pkg:pricing#status")));
+    }
+
+    #[test]
+    fn test_sample_records_missing_references() {
+        let mut dist = sample_library();
+        if let Distribution::Library(lib) = &mut dist {
+            lib.def.modules["orders"].value.values.insert(
+                "broken".to_string(),
+                constant(reference("my/pkg:gone#away")),
+            );
+        }
+        let sample = sample_distribution(
+            &dist,
+            &[fq("my/pkg:orders#broken")],
+            &SampleOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(sample.missing, vec![fq("my/pkg:gone#away")]);
+    }
+
+    #[test]
+    fn test_sample_unknown_root_is_an_error() {
+        let dist = sample_library();
+        let err = sample_distribution(
+            &dist,
+            &[fq("my/pkg:orders#nope")],
+            &SampleOptions::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("my/pkg:orders#nope"));
+    }
+
+    #[test]
+    fn test_sample_filters_application_entry_points() {
+        let Distribution::Library(lib) = sample_library() else {
+            unreachable!()
+        };
+        let mut entry_points = IndexMap::new();
+        entry_points.insert(
+            "total".to_string(),
+            EntryPoint {
+                target: "my/pkg:orders#total".to_string(),
+                kind: EntryPointKind::Main,
+                doc: None,
+            },
+        );
+        entry_points.insert(
+            "other".to_string(),
+            EntryPoint {
+                target: "my/pkg:orders#unrelated".to_string(),
+                kind: EntryPointKind::Command,
+                doc: None,
+            },
+        );
+        let dist = Distribution::Application(ApplicationContent {
+            package_name: lib.package_name,
+            dependencies: lib.dependencies,
+            def: lib.def,
+            entry_points,
+        });
+
+        let sample = sample_distribution(
+            &dist,
+            &[fq("my/pkg:orders#total")],
+            &SampleOptions::default(),
+        )
+        .unwrap();
+        let Distribution::Application(app) = sample.distribution else {
+            panic!("expected application distribution");
+        };
+        assert_eq!(app.entry_points.keys().collect::<Vec<_>>(), vec!["total"]);
+    }
+
+    #[test]
+    fn test_fqnames_in_text() {
+        let found = fqnames_in_text(
+            "Type mismatch in 'my/pkg:orders#total': expected my/pkg:pricing#rate.",
+        );
+        assert_eq!(
+            found,
+            vec![fq("my/pkg:orders#total"), fq("my/pkg:pricing#rate")]
+        );
+        assert!(fqnames_in_text("no names here: just text").is_empty());
+    }
+}
@@ -0,0 +1,524 @@
+//! JSON Schemas of the V4 types with hand-written serialization.
+//!
+//! Each schema mirrors the `Serialize` impl of its type in `serde_v4.rs`,
+//! `serde_tagged.rs`, or next to the type: expressions are `{ "Tag": { ... } }`
+//! wrapper objects whose `attrs` may be left out.
+
+use std::borrow::Cow;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+
+use super::attributes::{TypeAttributes, ValueAttributes};
+use super::distribution::{ApplicationContent, Distribution, LibraryContent, SpecsContent};
+use super::literal::Literal;
+use super::pattern::Pattern;
+use super::types::{Incompleteness, Type, TypeDefinition};
+use super::value::{
+    HoleReason, LetBinding, NativeHint, NativeInfo, PatternCase, RecordFieldEntry, Value,
+    ValueBody, ValueDefinition,
+};
+use super::{AccessControlled, ConstructorDefinition, FormatVersion};
+use crate::ir::schema::{empty_object, map_of, object, of, string, tuple, wrapper};
+use crate::naming::{FQName, Name};
+
+impl JsonSchema for FormatVersion {
+    fn schema_name() -> Cow<'static, str> {
+        "FormatVersion".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Format version, as a string like \"4.0.0\" or an integer like 4",
+            "type": ["string", "integer"],
+            "minimum": 0,
+        })
+    }
+}
+
+impl JsonSchema for Distribution {
+    fn schema_name() -> Cow<'static, str> {
+        "Distribution".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        wrapper(vec![
+            ("Library", of::<LibraryContent>(generator)),
+            ("Specs", of::<SpecsContent>(generator)),
+            ("Application", of::<ApplicationContent>(generator)),
+        ])
+    }
+}
+
+impl JsonSchema for TypeDefinition {
+    fn schema_name() -> Cow<'static, str> {
+        "TypeDefinition".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let type_params = of::<Vec<Name>>(generator);
+        wrapper(vec![
+            (
+                "TypeAliasDefinition",
+                object(
+                    vec![
+                        ("typeParams", type_params.clone()),
+                        ("typeExp", of::<Type>(generator)),
+                    ],
+                    vec![],
+                ),
+            ),
+            (
+                "CustomTypeDefinition",
+                object(
+                    vec![
+                        ("typeParams", type_params.clone()),
+                        (
+                            "constructors",
+                            of::<AccessControlled<Vec<ConstructorDefinition>>>(generator),
+                        ),
+                    ],
+                    vec![],
+                ),
+            ),
+            (
+                "IncompleteTypeDefinition",
+                object(
+                    vec![
+                        ("typeParams", type_params),
+                        ("incompleteness", of::<Incompleteness>(generator)),
+                    ],
+                    vec![],
+                ),
+            ),
+        ])
+    }
+}
+
+impl JsonSchema for Incompleteness {
+    fn schema_name() -> Cow<'static, str> {
+        "Incompleteness".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        wrapper(vec![
+            ("Draft", empty_object()),
+            ("Hole", of::<HoleReason>(generator)),
+        ])
+    }
+}
+
+impl JsonSchema for Type {
+    fn schema_name() -> Cow<'static, str> {
+        "Type".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let attrs = of::<TypeAttributes>(generator);
+        let node = |fields: Vec<(&'static str, Schema)>, optional: Vec<(&'static str, Schema)>| {
+            let optional = optional.into_iter().chain([("attrs", attrs.clone())]);
+            object(fields, optional.collect())
+        };
+        let tpe = of::<Type>(generator);
+        let types = of::<Vec<Type>>(generator);
+        let fields = map_of(tpe.clone());
+        let name = of::<Name>(generator);
+        let fqname = of::<FQName>(generator);
+        wrapper(vec![
+            ("Variable", node(vec![("name", name.clone())], vec![])),
+            (
+                "Reference",
+                node(vec![("fqname", fqname)], vec![("args", types.clone())]),
+            ),
+            ("Tuple", node(vec![("elements", types)], vec![])),
+            ("Record", node(vec![("fields", fields.clone())], vec![])),
+            (
+                "ExtensibleRecord",
+                node(vec![("variable", name), ("fields", fields)], vec![]),
+            ),
+            (
+                "Function",
+                node(vec![("arg", tpe.clone()), ("result", tpe)], vec![]),
+            ),
+            ("Unit", node(vec![], vec![])),
+        ])
+    }
+}
+
+impl JsonSchema for Pattern {
+    fn schema_name() -> Cow<'static, str> {
+        "Pattern".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let attrs = of::<ValueAttributes>(generator);
+        let node =
+            |fields: Vec<(&'static str, Schema)>| object(fields, vec![("attrs", attrs.clone())]);
+        let pattern = of::<Pattern>(generator);
+        let patterns = of::<Vec<Pattern>>(generator);
+        wrapper(vec![
+            ("WildcardPattern", node(vec![])),
+            (
+                "AsPattern",
+                node(vec![
+                    ("pattern", pattern.clone()),
+                    ("name", of::<Name>(generator)),
+                ]),
+            ),
+            ("TuplePattern", node(vec![("elements", patterns.clone())])),
+            (
+                "ConstructorPattern",
+                node(vec![
+                    ("fqname", of::<FQName>(generator)),
+                    ("args", patterns),
+                ]),
+            ),
+            ("EmptyListPattern", node(vec![])),
+            (
+                "HeadTailPattern",
+                node(vec![("head", pattern.clone()), ("tail", pattern)]),
+            ),
+            (
+                "LiteralPattern",
+                node(vec![("literal", of::<Literal>(generator))]),
+            ),
+            ("UnitPattern", node(vec![])),
+        ])
+    }
+}
+
+impl JsonSchema for Value {
+    fn schema_name() -> Cow<'static, str> {
+        "Value".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let attrs = of::<ValueAttributes>(generator);
+        let node = |fields: Vec<(&'static str, Schema)>, optional: Vec<(&'static str, Schema)>| {
+            let optional = optional.into_iter().chain([("attrs", attrs.clone())]);
+            object(fields, optional.collect())
+        };
+        let value = of::<Value>(generator);
+        let values = of::<Vec<Value>>(generator);
+        let pattern = of::<Pattern>(generator);
+        let name = of::<Name>(generator);
+        let fqname = of::<FQName>(generator);
+        let text = string("Name of the external function or platform");
+        wrapper(vec![
+            (
+                "Literal",
+                node(vec![("literal", of::<Literal>(generator))], vec![]),
+            ),
+            (
+                "Constructor",
+                node(vec![("fqname", fqname.clone())], vec![]),
+            ),
+            ("Tuple", node(vec![("elements", values.clone())], vec![])),
+            ("List", node(vec![("items", values)], vec![])),
+            (
+                "Record",
+                node(vec![("fields", map_of(value.clone()))], vec![]),
+            ),
+            ("Variable", node(vec![("name", name.clone())], vec![])),
+            ("Reference", node(vec![("fqname", fqname.clone())], vec![])),
+            (
+                "Field",
+                node(
+                    vec![("value", value.clone()), ("name", name.clone())],
+                    vec![],
+                ),
+            ),
+            ("FieldFunction", node(vec![("name", name.clone())], vec![])),
+            (
+                "Apply",
+                node(
+                    vec![("function", value.clone()), ("argument", value.clone())],
+                    vec![],
+                ),
+            ),
+            (
+                "Lambda",
+                node(
+                    vec![("pattern", pattern.clone()), ("body", value.clone())],
+                    vec![],
+                ),
+            ),
+            (
+                "LetDefinition",
+                node(
+                    vec![
+                        ("name", name),
+                        ("definition", of::<ValueDefinition>(generator)),
+                        ("body", value.clone()),
+                    ],
+                    vec![],
+                ),
+            ),
+            (
+                "LetRecursion",
+                node(
+                    vec![
+                        ("bindings", of::<Vec<LetBinding>>(generator)),
+                        ("body", value.clone()),
+                    ],
+                    vec![],
+                ),
+            ),
+            (
+                "Destructure",
+                node(
+                    vec![
+                        ("pattern", pattern),
+                        ("value", value.clone()),
+                        ("body", value.clone()),
+                    ],
+                    vec![],
+                ),
+            ),
+            (
+                "IfThenElse",
+                node(
+                    vec![
+                        ("condition", value.clone()),
+                        ("thenBranch", value.clone()),
+                        ("elseBranch", value.clone()),
+                    ],
+                    vec![],
+                ),
+            ),
+            (
+                "PatternMatch",
+                node(
+                    vec![
+                        ("subject", value.clone()),
+                        ("cases", of::<Vec<PatternCase>>(generator)),
+                    ],
+                    vec![],
+                ),
+            ),
+            (
+                "UpdateRecord",
+                node(
+                    vec![
+                        ("record", value),
+                        ("updates", of::<Vec<RecordFieldEntry>>(generator)),
+                    ],
+                    vec![],
+                ),
+            ),
+            ("Unit", node(vec![], vec![])),
+            (
+                "Hole",
+                node(
+                    vec![("reason", of::<HoleReason>(generator))],
+                    vec![("tpe", of::<Type>(generator))],
+                ),
+            ),
+            (
+                "Native",
+                node(
+                    vec![("fqname", fqname), ("info", of::<NativeInfo>(generator))],
+                    vec![],
+                ),
+            ),
+            (
+                "External",
+                node(
+                    vec![("externalName", text.clone()), ("targetPlatform", text)],
+                    vec![],
+                ),
+            ),
+        ])
+    }
+}
+
+impl JsonSchema for Literal {
+    fn schema_name() -> Cow<'static, str> {
+        "Literal".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let literal = |value: Schema| object(vec![("value", value)], vec![]);
+        wrapper(vec![
+            ("BoolLiteral", literal(json_schema!({ "type": "boolean" }))),
+            (
+                "CharLiteral",
+                literal(json_schema!({ "type": "string", "minLength": 1, "maxLength": 1 })),
+            ),
+            ("StringLiteral", literal(json_schema!({ "type": "string" }))),
+            (
+                "IntegerLiteral",
+                literal(json_schema!({ "type": "integer" })),
+            ),
+            ("FloatLiteral", literal(json_schema!({ "type": "number" }))),
+            (
+                "DecimalLiteral",
+                literal(string("Decimal number in its exact textual form")),
+            ),
+        ])
+    }
+}
+
+impl JsonSchema for ValueBody {
+    fn schema_name() -> Cow<'static, str> {
+        "ValueBody".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let text = string("Name of the external function or platform");
+        wrapper(vec![
+            (
+                "ExpressionBody",
+                object(vec![("body", of::<Value>(generator))], vec![]),
+            ),
+            ("NativeBody", of::<NativeInfo>(generator)),
+            (
+                "ExternalBody",
+                object(
+                    vec![("externalName", text.clone()), ("targetPlatform", text)],
+                    vec![],
+                ),
+            ),
+            (
+                "IncompleteBody",
+                object(vec![("reason", of::<HoleReason>(generator))], vec![]),
+            ),
+        ])
+    }
+}
+
+impl JsonSchema for NativeHint {
+    fn schema_name() -> Cow<'static, str> {
+        "NativeHint".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        wrapper(vec![
+            ("Arithmetic", empty_object()),
+            ("Comparison", empty_object()),
+            ("StringOp", empty_object()),
+            ("CollectionOp", empty_object()),
+            (
+                "PlatformSpecific",
+                object(
+                    vec![("platform", string("Platform identifier, e.g. \"wasm\""))],
+                    vec![],
+                ),
+            ),
+        ])
+    }
+}
+
+impl JsonSchema for HoleReason {
+    fn schema_name() -> Cow<'static, str> {
+        "HoleReason".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        wrapper(vec![
+            ("Draft", empty_object()),
+            (
+                "TypeMismatch",
+                object(
+                    vec![
+                        ("expected", string("Expected type")),
+                        ("found", string("Type found")),
+                    ],
+                    vec![],
+                ),
+            ),
+            (
+                "DeletedDuringRefactor",
+                object(
+                    vec![("tx-id", string("Refactoring that deleted the value"))],
+                    vec![],
+                ),
+            ),
+            (
+                "UnresolvedReference",
+                object(vec![("target", of::<FQName>(generator))], vec![]),
+            ),
+        ])
+    }
+}
+
+impl JsonSchema for PatternCase {
+    fn schema_name() -> Cow<'static, str> {
+        "PatternCase".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![of::<Pattern>(generator), of::<Value>(generator)])
+    }
+}
+
+impl JsonSchema for LetBinding {
+    fn schema_name() -> Cow<'static, str> {
+        "LetBinding".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![
+            of::<Name>(generator),
+            of::<ValueDefinition>(generator),
+        ])
+    }
+}
+
+impl JsonSchema for RecordFieldEntry {
+    fn schema_name() -> Cow<'static, str> {
+        "RecordFieldEntry".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        tuple(vec![of::<Name>(generator), of::<Value>(generator)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::schema::v4_schema;
+    use crate::ir::v4::text::parse_distribution;
+    use crate::ir::v4::{FormatVersion, IRFile};
+
+    #[test]
+    fn test_schema_defines_the_expression_types() {
+        let schema = v4_schema();
+        let defs = schema
+            .get("$defs")
+            .and_then(|defs| defs.as_object())
+            .unwrap();
+        for name in ["Distribution", "Type", "Pattern", "Value", "ValueBody"] {
+            assert!(defs.contains_key(name), "missing {name}");
+        }
+    }
+
+    #[test]
+    fn test_written_ir_has_the_tags_the_schema_names() {
+        let ir_file = IRFile {
+            format_version: FormatVersion::default(),
+            distribution: parse_distribution(
+                "library acme/shop\n\n\nmodule orders\n\ncount : morphir/sdk:basics#Int =\n    1\n",
+            )
+            .unwrap(),
+        };
+        let json = serde_json::to_value(&ir_file).unwrap();
+        let schema = v4_schema().to_value();
+        let tags = |def: &str| -> Vec<String> {
+            schema["$defs"][def]["oneOf"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .flat_map(|variant| variant["required"].as_array().unwrap().clone())
+                .map(|tag| tag.as_str().unwrap().to_string())
+                .collect()
+        };
+        let distribution = json["distribution"].as_object().unwrap();
+        assert!(tags("Distribution").contains(distribution.keys().next().unwrap()));
+        let count = &json["distribution"]["Library"]["def"]["modules"]["orders"]["value"]["values"]
+            ["count"]["value"];
+        let body = count["body"].as_object().unwrap();
+        assert!(tags("ValueBody").contains(body.keys().next().unwrap()));
+        let output = count["outputType"].as_object().unwrap();
+        assert!(tags("Type").contains(output.keys().next().unwrap()));
+    }
+}
@@ -25,7 +25,7 @@ use super::value::{
     HoleReason, InputType, LetBinding, NativeInfo, PatternCase, RecordFieldEntry, Value,
     ValueDefinition,
 };
-use crate::naming::{FQName, Name};
+use crate::naming::{FQName, Name, fqname};
 
 // =============================================================================
 // Type Serialization
@@ -71,8 +71,8 @@ impl<'de> Visitor<'de> for TypeVisitor {
     {
         use indexmap::IndexMap;
 
-        let (tag, value): (String, serde_json::Value) = map
-            .next_entry()?
+        let tag: String = map
+            .next_key()?
             .ok_or_else(|| de::Error::custom("expected object wrapper with single key"))?;
 
         match tag.as_str() {
@@ -80,11 +80,11 @@ impl<'de> Visitor<'de> for TypeVisitor {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    name: String,
+                    name: Name,
                     attrs: Option<TypeAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
-                let name = Name::from(content.name.as_str());
+                let content: Content = map.next_value()?;
+                let name = content.name;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Type::Variable(attrs, name))
             }
@@ -92,16 +92,15 @@ impl<'de> Visitor<'de> for TypeVisitor {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    fqname: String,
+                    #[serde(deserialize_with = "fqname::canonical::deserialize")]
+                    fqname: FQName,
                     args: Option<Vec<Type>>,
                     attrs: Option<TypeAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
-                let fqname = FQName::from_canonical_string(&content.fqname)
-                    .map_err(|e| de::Error::custom(format!("invalid FQName: {}", e)))?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 let args = content.args.unwrap_or_default();
-                Ok(Type::Reference(attrs, fqname, args))
+                Ok(Type::Reference(attrs, content.fqname, args))
             }
             "Tuple" => {
                 #[derive(Deserialize)]
@@ -110,7 +109,7 @@ impl<'de> Visitor<'de> for TypeVisitor {
                     elements: Vec<Type>,
                     attrs: Option<TypeAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Type::Tuple(attrs, content.elements))
             }
@@ -121,7 +120,7 @@ impl<'de> Visitor<'de> for TypeVisitor {
                     fields: IndexMap<String, Type>,
                     attrs: Option<TypeAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 let fields = content
                     .fields
@@ -141,7 +140,7 @@ impl<'de> Visitor<'de> for TypeVisitor {
                     fields: IndexMap<String, Type>,
                     attrs: Option<TypeAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 let variable = Name::from(content.variable.as_str());
                 let fields = content
@@ -162,7 +161,7 @@ impl<'de> Visitor<'de> for TypeVisitor {
                     result: Type,
                     attrs: Option<TypeAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Type::Function(
                     attrs,
@@ -176,7 +175,7 @@ impl<'de> Visitor<'de> for TypeVisitor {
                 struct Content {
                     attrs: Option<TypeAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Type::Unit(attrs))
             }
@@ -419,8 +418,8 @@ impl<'de> Visitor<'de> for PatternVisitor {
     where
         M: MapAccess<'de>,
     {
-        let (tag, value): (String, serde_json::Value) = map
-            .next_entry()?
+        let tag: String = map
+            .next_key()?
             .ok_or_else(|| de::Error::custom("expected object wrapper with single key"))?;
 
         match tag.as_str() {
@@ -430,7 +429,7 @@ impl<'de> Visitor<'de> for PatternVisitor {
                 struct Content {
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Pattern::WildcardPattern(attrs))
             }
@@ -439,12 +438,12 @@ impl<'de> Visitor<'de> for PatternVisitor {
                 #[serde(rename_all = "camelCase")]
                 struct Content {
                     pattern: Pattern,
-                    name: String,
+                    name: Name,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let name = Name::from(content.name.as_str());
+                let name = content.name;
                 Ok(Pattern::AsPattern(attrs, Box::new(content.pattern), name))
             }
             "TuplePattern" => {
@@ -454,7 +453,7 @@ impl<'de> Visitor<'de> for PatternVisitor {
                     elements: Vec<Pattern>,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Pattern::TuplePattern(attrs, content.elements))
             }
@@ -462,15 +461,18 @@ impl<'de> Visitor<'de> for PatternVisitor {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    fqname: String,
+                    #[serde(deserialize_with = "fqname::canonical::deserialize")]
+                    fqname: FQName,
                     args: Vec<Pattern>,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let fqname = FQName::from_canonical_string(&content.fqname)
-                    .map_err(|e| de::Error::custom(format!("invalid FQName: {}", e)))?;
-                Ok(Pattern::ConstructorPattern(attrs, fqname, content.args))
+                Ok(Pattern::ConstructorPattern(
+                    attrs,
+                    content.fqname,
+                    content.args,
+                ))
             }
             "EmptyListPattern" => {
                 #[derive(Deserialize)]
@@ -478,7 +480,7 @@ impl<'de> Visitor<'de> for PatternVisitor {
                 struct Content {
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Pattern::EmptyListPattern(attrs))
             }
@@ -490,7 +492,7 @@ impl<'de> Visitor<'de> for PatternVisitor {
                     tail: Pattern,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Pattern::HeadTailPattern(
                     attrs,
@@ -505,7 +507,7 @@ impl<'de> Visitor<'de> for PatternVisitor {
                     literal: Literal,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Pattern::LiteralPattern(attrs, content.literal))
             }
@@ -515,7 +517,7 @@ impl<'de> Visitor<'de> for PatternVisitor {
                 struct Content {
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Pattern::UnitPattern(attrs))
             }
@@ -916,8 +918,8 @@ impl<'de> Visitor<'de> for ValueVisitor {
     {
         use indexmap::IndexMap;
 
-        let (tag, value): (String, serde_json::Value) = map
-            .next_entry()?
+        let tag: String = map
+            .next_key()?
             .ok_or_else(|| de::Error::custom("expected object wrapper with single key"))?;
 
         match tag.as_str() {
@@ -928,7 +930,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     literal: Literal,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::Literal(attrs, content.literal))
             }
@@ -936,14 +938,13 @@ impl<'de> Visitor<'de> for ValueVisitor {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    fqname: String,
+                    #[serde(deserialize_with = "fqname::canonical::deserialize")]
+                    fqname: FQName,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let fqname = FQName::from_canonical_string(&content.fqname)
-                    .map_err(|e| de::Error::custom(format!("invalid FQName: {}", e)))?;
-                Ok(Value::Constructor(attrs, fqname))
+                Ok(Value::Constructor(attrs, content.fqname))
             }
             "Tuple" => {
                 #[derive(Deserialize)]
@@ -952,7 +953,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     elements: Vec<Value>,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::Tuple(attrs, content.elements))
             }
@@ -963,7 +964,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     items: Vec<Value>,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::List(attrs, content.items))
             }
@@ -974,7 +975,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     fields: IndexMap<String, Value>,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 let fields = content
                     .fields
@@ -987,50 +988,49 @@ impl<'de> Visitor<'de> for ValueVisitor {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    name: String,
+                    name: Name,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let name = Name::from(content.name.as_str());
+                let name = content.name;
                 Ok(Value::Variable(attrs, name))
             }
             "Reference" => {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    fqname: String,
+                    #[serde(deserialize_with = "fqname::canonical::deserialize")]
+                    fqname: FQName,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let fqname = FQName::from_canonical_string(&content.fqname)
-                    .map_err(|e| de::Error::custom(format!("invalid FQName: {}", e)))?;
-                Ok(Value::Reference(attrs, fqname))
+                Ok(Value::Reference(attrs, content.fqname))
             }
             "Field" => {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
                     value: Value,
-                    name: String,
+                    name: Name,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let name = Name::from(content.name.as_str());
+                let name = content.name;
                 Ok(Value::Field(attrs, Box::new(content.value), name))
             }
             "FieldFunction" => {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    name: String,
+                    name: Name,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let name = Name::from(content.name.as_str());
+                let name = content.name;
                 Ok(Value::FieldFunction(attrs, name))
             }
             "Apply" => {
@@ -1041,7 +1041,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     argument: Value,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::Apply(
                     attrs,
@@ -1057,7 +1057,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     body: Value,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::Lambda(
                     attrs,
@@ -1069,14 +1069,14 @@ impl<'de> Visitor<'de> for ValueVisitor {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    name: String,
+                    name: Name,
                     definition: ValueDefinition,
                     body: Value,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let name = Name::from(content.name.as_str());
+                let name = content.name;
                 Ok(Value::LetDefinition(
                     attrs,
                     name,
@@ -1092,7 +1092,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     body: Value,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::LetRecursion(
                     attrs,
@@ -1109,7 +1109,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     body: Value,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::Destructure(
                     attrs,
@@ -1127,7 +1127,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     else_branch: Value,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::IfThenElse(
                     attrs,
@@ -1144,7 +1144,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     cases: Vec<PatternCase>,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::PatternMatch(
                     attrs,
@@ -1160,7 +1160,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     updates: Vec<RecordFieldEntry>,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::UpdateRecord(
                     attrs,
@@ -1174,7 +1174,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                 struct Content {
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::Unit(attrs))
             }
@@ -1186,7 +1186,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     tpe: Option<Type>,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::Hole(
                     attrs,
@@ -1198,15 +1198,14 @@ impl<'de> Visitor<'de> for ValueVisitor {
                 #[derive(Deserialize)]
                 #[serde(rename_all = "camelCase")]
                 struct Content {
-                    fqname: String,
+                    #[serde(deserialize_with = "fqname::canonical::deserialize")]
+                    fqname: FQName,
                     info: NativeInfo,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
-                let fqname = FQName::from_canonical_string(&content.fqname)
-                    .map_err(|e| de::Error::custom(format!("invalid FQName: {}", e)))?;
-                Ok(Value::Native(attrs, fqname, content.info))
+                Ok(Value::Native(attrs, content.fqname, content.info))
             }
             "External" => {
                 #[derive(Deserialize)]
@@ -1216,7 +1215,7 @@ impl<'de> Visitor<'de> for ValueVisitor {
                     target_platform: String,
                     attrs: Option<ValueAttributes>,
                 }
-                let content: Content = serde_json::from_value(value).map_err(de::Error::custom)?;
+                let content: Content = map.next_value()?;
                 let attrs = content.attrs.unwrap_or_default();
                 Ok(Value::External(
                     attrs,
@@ -7,6 +7,8 @@
 //! This module provides serialization helpers for Type, Pattern, Value,
 //! and Literal using the V4 object wrapper format.
 
+use std::cell::Cell;
+
 use indexmap::IndexMap;
 use serde::Serialize;
 use serde::ser::{SerializeMap, Serializer};
@@ -19,6 +21,31 @@ use super::value::{
     HoleReason, LetBinding, NativeInfo, PatternCase, RecordFieldEntry, Value, ValueDefinition,
 };
 
+// =============================================================================
+// Attributes
+// =============================================================================
+
+thread_local! {
+    static OMIT_EMPTY_ATTRIBUTES: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` with empty `attrs` left out of the nodes it serializes.
+///
+/// Readers restore missing attributes as defaults, so the output reads back
+/// the same; it is only shorter.
+pub fn omitting_empty_attributes<R>(f: impl FnOnce() -> R) -> R {
+    let previous = OMIT_EMPTY_ATTRIBUTES.with(|omit| omit.replace(true));
+    let result = f();
+    OMIT_EMPTY_ATTRIBUTES.with(|omit| omit.set(previous));
+    result
+}
+
+/// Attributes to write for a node
+fn emitted<T: Default + PartialEq>(attrs: &T) -> Option<&T> {
+    let omit = OMIT_EMPTY_ATTRIBUTES.with(Cell::get) && *attrs == T::default();
+    (!omit).then_some(attrs)
+}
+
 // =============================================================================
 // Type V4 Serialization
 // =============================================================================
@@ -48,7 +75,7 @@ where
                 "Variable",
                 &VariableContent {
                     name: name.to_string(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -60,7 +87,7 @@ where
                 &ReferenceContent {
                     fqname: fqname.to_canonical_string(),
                     args,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -71,7 +98,7 @@ where
                 "Tuple",
                 &TupleContent {
                     elements,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -87,7 +114,7 @@ where
                 "Record",
                 &RecordContent {
                     fields: fields_map,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -103,7 +130,7 @@ where
                 &ExtensibleRecordContent {
                     variable: var.to_string(),
                     fields: fields_map,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -115,14 +142,19 @@ where
                 &FunctionContent {
                     arg: arg.as_ref(),
                     result: result.as_ref(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
         }
         Type::Unit(attrs) => {
             let mut map = serializer.serialize_map(Some(1))?;
-            map.serialize_entry("Unit", &UnitContent { attrs: Some(attrs) })?;
+            map.serialize_entry(
+                "Unit",
+                &UnitContent {
+                    attrs: emitted(attrs),
+                },
+            )?;
             map.end()
         }
     }
@@ -269,7 +301,9 @@ where
             let mut map = serializer.serialize_map(Some(1))?;
             map.serialize_entry(
                 "WildcardPattern",
-                &PatternAttrsContent { attrs: Some(attrs) },
+                &PatternAttrsContent {
+                    attrs: emitted(attrs),
+                },
             )?;
             map.end()
         }
@@ -280,7 +314,7 @@ where
                 &AsPatternContent {
                     pattern,
                     name: name.to_string(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -291,7 +325,7 @@ where
                 "TuplePattern",
                 &TuplePatternContent {
                     elements,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -303,7 +337,7 @@ where
                 &ConstructorPatternContent {
                     fqname: fqname.to_canonical_string(),
                     args,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -312,7 +346,9 @@ where
             let mut map = serializer.serialize_map(Some(1))?;
             map.serialize_entry(
                 "EmptyListPattern",
-                &PatternAttrsContent { attrs: Some(attrs) },
+                &PatternAttrsContent {
+                    attrs: emitted(attrs),
+                },
             )?;
             map.end()
         }
@@ -323,7 +359,7 @@ where
                 &HeadTailPatternContent {
                     head: head.as_ref(),
                     tail: tail.as_ref(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -334,14 +370,19 @@ where
                 "LiteralPattern",
                 &LiteralPatternContent {
                     literal: lit,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
         }
         Pattern::UnitPattern(attrs) => {
             let mut map = serializer.serialize_map(Some(1))?;
-            map.serialize_entry("UnitPattern", &PatternAttrsContent { attrs: Some(attrs) })?;
+            map.serialize_entry(
+                "UnitPattern",
+                &PatternAttrsContent {
+                    attrs: emitted(attrs),
+                },
+            )?;
             map.end()
         }
     }
@@ -440,7 +481,7 @@ where
                 "Literal",
                 &LiteralValueContent {
                     literal: lit,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -451,7 +492,7 @@ where
                 "Constructor",
                 &ConstructorValueContent {
                     fqname: fqname.to_canonical_string(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -462,7 +503,7 @@ where
                 "Tuple",
                 &TupleValueContent {
                     elements,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -473,7 +514,7 @@ where
                 "List",
                 &ListValueContent {
                     items,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -486,7 +527,7 @@ where
                 "Record",
                 &RecordValueContent {
                     fields: fields_map,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -497,7 +538,7 @@ where
                 "Variable",
                 &VariableValueContent {
                     name: name.to_string(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -508,7 +549,7 @@ where
                 "Reference",
                 &ReferenceValueContent {
                     fqname: fqname.to_canonical_string(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -520,7 +561,7 @@ where
                 &FieldValueContent {
                     value: value.as_ref(),
                     name: name.to_string(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -531,7 +572,7 @@ where
                 "FieldFunction",
                 &FieldFunctionContent {
                     name: name.to_string(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -543,7 +584,7 @@ where
                 &ApplyContent {
                     function: function.as_ref(),
                     argument: argument.as_ref(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -555,7 +596,7 @@ where
                 &LambdaContent {
                     pattern,
                     body: body.as_ref(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -568,7 +609,7 @@ where
                     name: name.to_string(),
                     definition: definition.as_ref(),
                     body: body.as_ref(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -580,7 +621,7 @@ where
                 &LetRecursionContent {
                     bindings,
                     body: body.as_ref(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -593,7 +634,7 @@ where
                     pattern,
                     value: value.as_ref(),
                     body: body.as_ref(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -606,7 +647,7 @@ where
                     condition: condition.as_ref(),
                     then_branch: then_branch.as_ref(),
                     else_branch: else_branch.as_ref(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -618,7 +659,7 @@ where
                 &PatternMatchContent {
                     subject: subject.as_ref(),
                     cases,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -630,14 +671,19 @@ where
                 &UpdateRecordContent {
                     record: record.as_ref(),
                     updates,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
         }
         Value::Unit(attrs) => {
             let mut map = serializer.serialize_map(Some(1))?;
-            map.serialize_entry("Unit", &ValueUnitContent { attrs: Some(attrs) })?;
+            map.serialize_entry(
+                "Unit",
+                &ValueUnitContent {
+                    attrs: emitted(attrs),
+                },
+            )?;
             map.end()
         }
         Value::Hole(attrs, reason, tpe) => {
@@ -647,7 +693,7 @@ where
                 &HoleContent {
                     reason,
                     tpe: tpe.as_ref().map(|t: &Box<_>| t.as_ref()),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -659,7 +705,7 @@ where
                 &NativeValueContent {
                     fqname: fqname.to_canonical_string(),
                     info,
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
@@ -671,7 +717,7 @@ where
                 &ExternalContent {
                     external_name: external_name.clone(),
                     target_platform: target_platform.clone(),
-                    attrs: Some(attrs),
+                    attrs: emitted(attrs),
                 },
             )?;
             map.end()
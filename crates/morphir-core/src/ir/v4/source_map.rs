@@ -0,0 +1,561 @@
+//! Source maps: where in the original source each node of a distribution
+//! came from
+//!
+//! Frontends record the file and the line and column range each node was
+//! read from in its `source` attribute, a [`SourceLocation`]. Kept in the IR,
+//! locations make it much larger and change it on every edit that only moves
+//! code around, so a [`SourceMap`] holds them apart, in a file next to the
+//! IR, and puts them back on the nodes when a tool needs them. The LSP and
+//! backends ask it where a definition, or a node inside one, came from.
+//!
+//! A node is named by the canonical FQName of the type or value definition
+//! it belongs to and its [`NodePath`] within that definition:
+//!
+//! | Path                | Node                                             |
+//! |---------------------|--------------------------------------------------|
+//! | *(empty)*           | the definition itself                            |
+//! | `/type`             | the type expression of a type alias              |
+//! | `/constructors/1/0` | the first argument of the second constructor     |
+//! | `/inputs/lines`     | the type of the input `lines`                    |
+//! | `/output`           | the output type                                  |
+//! | `/body/1/0`         | the first child of the second child of the body  |
+//!
+//! Children are numbered in the order they appear: the elements of a tuple
+//! or list, the fields of a record, the function then the argument of an
+//! application, the bound value then the body of a `let`, and so on. Only
+//! frontends record the definitions themselves, as no IR node carries their
+//! location.
+//!
+//! Locations are written as `[file, startLine, startColumn, endLine,
+//! endColumn]`, where `file` indexes the file table and is left out when the
+//! file is not known:
+//!
+//! ```json
+//! {
+//!   "files": ["src/orders.elm"],
+//!   "values": {
+//!     "acme/shop:orders#total": { "": [0, 12, 1, 14, 30], "/body": [0, 13, 5, 14, 30] }
+//!   }
+//! }
+//! ```
+
+use std::fmt;
+
+use indexmap::{IndexMap, IndexSet};
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+
+use super::attributes::SourceLocation;
+use super::decoration::member_paths;
+use super::optimize::{children, children_mut};
+use super::package::PackageDefinition;
+use super::types::{Type, TypeDefinition};
+use super::value::{Value, ValueBody, ValueDefinition};
+use crate::naming::{FQName, Name, PackageName, Path};
+use crate::vpath::VirtualPath;
+
+/// Path of a node within the definition it belongs to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NodePath(String);
+
+impl NodePath {
+    /// Path of the definition itself
+    pub fn root() -> Self {
+        NodePath::default()
+    }
+
+    /// Path of the child `segment` of this node
+    pub fn child(&self, segment: impl fmt::Display) -> Self {
+        NodePath(format!("{}/{}", self.0, segment))
+    }
+
+    /// Path of the node this one is a child of, or `None` for the root
+    pub fn parent(&self) -> Option<Self> {
+        self.0
+            .rsplit_once('/')
+            .map(|(parent, _)| NodePath(parent.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for NodePath {
+    fn from(path: &str) -> Self {
+        NodePath(path.trim_end_matches('/').to_string())
+    }
+}
+
+/// A location as stored in a source map, with its file as an index into the
+/// file table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    file: Option<usize>,
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+impl Serialize for Span {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let len = if self.file.is_some() { 5 } else { 4 };
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        if let Some(file) = self.file {
+            seq.serialize_element(&file)?;
+        }
+        seq.serialize_element(&self.start_line)?;
+        seq.serialize_element(&self.start_column)?;
+        seq.serialize_element(&self.end_line)?;
+        seq.serialize_element(&self.end_column)?;
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Span {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let numbers = Vec::<u32>::deserialize(deserializer)?;
+        let (file, range) = match numbers.as_slice() {
+            [file, range @ ..] if range.len() == 4 => (Some(*file as usize), range),
+            range if range.len() == 4 => (None, range),
+            _ => {
+                return Err(de::Error::custom(
+                    "expected [file, startLine, startColumn, endLine, endColumn]",
+                ));
+            }
+        };
+        Ok(Span {
+            file,
+            start_line: range[0],
+            start_column: range[1],
+            end_line: range[2],
+            end_column: range[3],
+        })
+    }
+}
+
+/// Locations of the nodes of a distribution, keyed by definition and path
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMap {
+    #[serde(default, skip_serializing_if = "IndexSet::is_empty")]
+    files: IndexSet<VirtualPath>,
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "member_paths"
+    )]
+    types: IndexMap<String, IndexMap<String, Span>>,
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        deserialize_with = "member_paths"
+    )]
+    values: IndexMap<String, IndexMap<String, Span>>,
+}
+
+impl SourceMap {
+    /// Locations recorded on the nodes of package `package`
+    pub fn collect(package: &PackageName, definition: &PackageDefinition) -> Self {
+        let mut map = SourceMap::default();
+        for (module_key, module) in &definition.modules {
+            let module_path = Path::new(module_key);
+            for (name, def) in &module.value.types {
+                let fqname = member(package, &module_path, name);
+                walk_type_definition(&def.value, &mut |path, location| {
+                    map.record_type(&fqname, path, location)
+                });
+            }
+            for (name, def) in &module.value.values {
+                let fqname = member(package, &module_path, name);
+                walk_value_definition(&def.value, &mut |path, location| {
+                    map.record_value(&fqname, path, location)
+                });
+            }
+        }
+        map
+    }
+
+    /// Move the locations recorded on the nodes of package `package` into a
+    /// source map, leaving the nodes without one
+    pub fn take(package: &PackageName, definition: &mut PackageDefinition) -> Self {
+        let map = SourceMap::collect(package, definition);
+        for module in definition.modules.values_mut() {
+            for def in module.value.types.values_mut() {
+                walk_type_definition_mut(&mut def.value, &mut |_, source| *source = None);
+            }
+            for def in module.value.values.values_mut() {
+                walk_value_definition_mut(&mut def.value, &mut |_, source| *source = None);
+            }
+        }
+        map
+    }
+
+    /// Put the locations of this map back on the nodes of package
+    /// `package`, returning how many nodes were given one. Nodes the map has
+    /// no entry for keep the location they have.
+    pub fn apply(&self, package: &PackageName, definition: &mut PackageDefinition) -> usize {
+        let mut count = 0;
+        for (module_key, module) in definition.modules.iter_mut() {
+            let module_path = Path::new(module_key);
+            for (name, def) in module.value.types.iter_mut() {
+                let Some(spans) = self
+                    .types
+                    .get(&member(package, &module_path, name).to_canonical_string())
+                else {
+                    continue;
+                };
+                walk_type_definition_mut(&mut def.value, &mut |path, source| {
+                    if let Some(span) = spans.get(path.as_str()) {
+                        *source = Some(self.location(span));
+                        count += 1;
+                    }
+                });
+            }
+            for (name, def) in module.value.values.iter_mut() {
+                let Some(spans) = self
+                    .values
+                    .get(&member(package, &module_path, name).to_canonical_string())
+                else {
+                    continue;
+                };
+                walk_value_definition_mut(&mut def.value, &mut |path, source| {
+                    if let Some(span) = spans.get(path.as_str()) {
+                        *source = Some(self.location(span));
+                        count += 1;
+                    }
+                });
+            }
+        }
+        count
+    }
+
+    /// Whether no location is recorded
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty() && self.values.is_empty()
+    }
+
+    /// Record that node `path` of type `fqname` came from `location`
+    pub fn record_type(&mut self, fqname: &FQName, path: &NodePath, location: &SourceLocation) {
+        let span = self.span(location);
+        self.types
+            .entry(fqname.to_canonical_string())
+            .or_default()
+            .insert(path.to_string(), span);
+    }
+
+    /// Record that node `path` of value `fqname` came from `location`
+    pub fn record_value(&mut self, fqname: &FQName, path: &NodePath, location: &SourceLocation) {
+        let span = self.span(location);
+        self.values
+            .entry(fqname.to_canonical_string())
+            .or_default()
+            .insert(path.to_string(), span);
+    }
+
+    /// Where node `path` of type `fqname` came from. A node with no location
+    /// of its own is placed at the closest enclosing node that has one.
+    pub fn type_source(&self, fqname: &FQName, path: &NodePath) -> Option<SourceLocation> {
+        self.locate(self.types.get(&fqname.to_canonical_string())?, path)
+    }
+
+    /// Where node `path` of value `fqname` came from. A node with no location
+    /// of its own is placed at the closest enclosing node that has one.
+    pub fn value_source(&self, fqname: &FQName, path: &NodePath) -> Option<SourceLocation> {
+        self.locate(self.values.get(&fqname.to_canonical_string())?, path)
+    }
+
+    fn locate(&self, spans: &IndexMap<String, Span>, path: &NodePath) -> Option<SourceLocation> {
+        let mut path = Some(path.clone());
+        while let Some(current) = path {
+            if let Some(span) = spans.get(current.as_str()) {
+                return Some(self.location(span));
+            }
+            path = current.parent();
+        }
+        None
+    }
+
+    fn span(&mut self, location: &SourceLocation) -> Span {
+        Span {
+            file: location
+                .file
+                .as_ref()
+                .map(|file| self.files.insert_full(file.clone()).0),
+            start_line: location.start_line,
+            start_column: location.start_column,
+            end_line: location.end_line,
+            end_column: location.end_column,
+        }
+    }
+
+    fn location(&self, span: &Span) -> SourceLocation {
+        SourceLocation {
+            file: span
+                .file
+                .and_then(|file| self.files.get_index(file))
+                .cloned(),
+            start_line: span.start_line,
+            start_column: span.start_column,
+            end_line: span.end_line,
+            end_column: span.end_column,
+        }
+    }
+}
+
+/// FQName of member `name` of module `module_path`
+fn member(package: &PackageName, module_path: &Path, name: &str) -> FQName {
+    FQName::new(
+        package.as_path().clone(),
+        module_path.clone(),
+        Name::from(name),
+    )
+}
+
+fn walk_type_definition(def: &TypeDefinition, visit: &mut dyn FnMut(&NodePath, &SourceLocation)) {
+    let root = NodePath::root();
+    match def {
+        TypeDefinition::TypeAliasDefinition { type_expr, .. } => {
+            walk_type(type_expr, root.child("type"), visit)
+        }
+        TypeDefinition::CustomTypeDefinition { constructors, .. } => {
+            let constructors_path = root.child("constructors");
+            for (i, constructor) in constructors.value.iter().enumerate() {
+                let constructor_path = constructors_path.child(i);
+                for (j, arg) in constructor.args.iter().enumerate() {
+                    walk_type(&arg.arg_type, constructor_path.child(j), visit);
+                }
+            }
+        }
+        TypeDefinition::IncompleteTypeDefinition { .. } => {}
+    }
+}
+
+fn walk_type_definition_mut(
+    def: &mut TypeDefinition,
+    visit: &mut dyn FnMut(&NodePath, &mut Option<SourceLocation>),
+) {
+    let root = NodePath::root();
+    match def {
+        TypeDefinition::TypeAliasDefinition { type_expr, .. } => {
+            walk_type_mut(type_expr, root.child("type"), visit)
+        }
+        TypeDefinition::CustomTypeDefinition { constructors, .. } => {
+            let constructors_path = root.child("constructors");
+            for (i, constructor) in constructors.value.iter_mut().enumerate() {
+                let constructor_path = constructors_path.child(i);
+                for (j, arg) in constructor.args.iter_mut().enumerate() {
+                    walk_type_mut(&mut arg.arg_type, constructor_path.child(j), visit);
+                }
+            }
+        }
+        TypeDefinition::IncompleteTypeDefinition { .. } => {}
+    }
+}
+
+fn walk_value_definition(def: &ValueDefinition, visit: &mut dyn FnMut(&NodePath, &SourceLocation)) {
+    let root = NodePath::root();
+    let inputs = root.child("inputs");
+    for (name, input) in &def.input_types {
+        walk_type(&input.input_type, inputs.child(name), visit);
+    }
+    walk_type(&def.output_type, root.child("output"), visit);
+    if let ValueBody::Expression(body) = &def.body {
+        walk_value(body, root.child("body"), visit);
+    }
+}
+
+fn walk_value_definition_mut(
+    def: &mut ValueDefinition,
+    visit: &mut dyn FnMut(&NodePath, &mut Option<SourceLocation>),
+) {
+    let root = NodePath::root();
+    let inputs = root.child("inputs");
+    for (name, input) in def.input_types.iter_mut() {
+        walk_type_mut(&mut input.input_type, inputs.child(name), visit);
+    }
+    walk_type_mut(&mut def.output_type, root.child("output"), visit);
+    if let ValueBody::Expression(body) = &mut def.body {
+        walk_value_mut(body, root.child("body"), visit);
+    }
+}
+
+fn walk_type(tpe: &Type, path: NodePath, visit: &mut dyn FnMut(&NodePath, &SourceLocation)) {
+    if let Some(source) = &tpe.attributes().source {
+        visit(&path, source);
+    }
+    for (i, child) in type_children(tpe).into_iter().enumerate() {
+        walk_type(child, path.child(i), visit);
+    }
+}
+
+fn walk_type_mut(
+    tpe: &mut Type,
+    path: NodePath,
+    visit: &mut dyn FnMut(&NodePath, &mut Option<SourceLocation>),
+) {
+    visit(&path, &mut tpe.attributes_mut().source);
+    for (i, child) in type_children_mut(tpe).into_iter().enumerate() {
+        walk_type_mut(child, path.child(i), visit);
+    }
+}
+
+fn walk_value(value: &Value, path: NodePath, visit: &mut dyn FnMut(&NodePath, &SourceLocation)) {
+    if let Some(source) = &value.attributes().source {
+        visit(&path, source);
+    }
+    for (i, child) in children(value).into_iter().enumerate() {
+        walk_value(child, path.child(i), visit);
+    }
+}
+
+fn walk_value_mut(
+    value: &mut Value,
+    path: NodePath,
+    visit: &mut dyn FnMut(&NodePath, &mut Option<SourceLocation>),
+) {
+    visit(&path, &mut value.attributes_mut().source);
+    for (i, child) in children_mut(value).into_iter().enumerate() {
+        walk_value_mut(child, path.child(i), visit);
+    }
+}
+
+/// Direct subexpressions of type `tpe`
+fn type_children(tpe: &Type) -> Vec<&Type> {
+    match tpe {
+        Type::Reference(_, _, args) | Type::Tuple(_, args) => args.iter().collect(),
+        Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+            fields.iter().map(|field| &field.tpe).collect()
+        }
+        Type::Function(_, arg, result) => vec![arg, result],
+        Type::Variable(..) | Type::Unit(_) => Vec::new(),
+    }
+}
+
+fn type_children_mut(tpe: &mut Type) -> Vec<&mut Type> {
+    match tpe {
+        Type::Reference(_, _, args) | Type::Tuple(_, args) => args.iter_mut().collect(),
+        Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+            fields.iter_mut().map(|field| &mut field.tpe).collect()
+        }
+        Type::Function(_, arg, result) => vec![arg, result],
+        Type::Variable(..) | Type::Unit(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::v4::Distribution;
+    use crate::ir::v4::text::parse_distribution;
+    use serde_json::json;
+
+    fn library() -> (PackageName, PackageDefinition) {
+        let distribution = parse_distribution(
+            "library acme/shop\n\nmodule orders\n\ntype alias Line =\n    { price : morphir/sdk:basics#Int\n    , quantity : morphir/sdk:basics#Int\n    }\n\nrefund (x : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =\n    morphir/sdk:basics#negate x\n",
+        )
+        .unwrap();
+        let Distribution::Library(lib) = distribution else {
+            panic!("expected a library");
+        };
+        (lib.package_name, lib.def)
+    }
+
+    fn fqname(canonical: &str) -> FQName {
+        FQName::from_canonical_string(canonical).unwrap()
+    }
+
+    #[test]
+    fn test_take_and_apply() {
+        let (package, mut definition) = library();
+        let file = VirtualPath::new("src/orders.elm");
+        let body_location = SourceLocation::new(8, 5, 8, 31).in_file(file.clone());
+        let quantity_location = SourceLocation::new(4, 18, 4, 39).in_file(file.clone());
+        {
+            let module = &mut definition.modules["orders"].value;
+            let refund = &mut module.values["refund"].value;
+            if let ValueBody::Expression(body) = &mut refund.body {
+                body.attributes_mut().source = Some(body_location.clone());
+                if let Value::Apply(_, _, argument) = body {
+                    argument.attributes_mut().source = Some(SourceLocation::point(8, 30));
+                }
+            }
+            if let TypeDefinition::TypeAliasDefinition { type_expr, .. } =
+                &mut module.types["line"].value
+                && let Type::Record(_, fields) = type_expr
+            {
+                fields[1].tpe.attributes_mut().source = Some(quantity_location.clone());
+            }
+        }
+        let with_sources = definition.clone();
+
+        let map = SourceMap::take(&package, &mut definition);
+        assert_eq!(
+            serde_json::to_value(&map).unwrap(),
+            json!({
+                "files": ["src/orders.elm"],
+                "types": { "acme/shop:orders#line": { "/type/1": [0, 4, 18, 4, 39] } },
+                "values": {
+                    "acme/shop:orders#refund": {
+                        "/body": [0, 8, 5, 8, 31],
+                        "/body/1": [8, 30, 8, 30]
+                    }
+                }
+            })
+        );
+        assert_eq!(
+            SourceMap::collect(&package, &definition),
+            SourceMap::default()
+        );
+
+        assert_eq!(map.apply(&package, &mut definition), 3);
+        assert_eq!(definition, with_sources);
+
+        let written = serde_json::to_string(&map).unwrap();
+        assert_eq!(serde_json::from_str::<SourceMap>(&written).unwrap(), map);
+    }
+
+    #[test]
+    fn test_lookup_falls_back_to_enclosing_node() {
+        let mut map = SourceMap::default();
+        let refund = fqname("acme/shop:orders#refund");
+        let whole = SourceLocation::new(7, 1, 8, 31).in_file("src/orders.elm");
+        map.record_value(&refund, &NodePath::root(), &whole);
+        let body = NodePath::root().child("body");
+        map.record_value(&refund, &body, &SourceLocation::new(8, 5, 8, 31));
+
+        assert_eq!(
+            map.value_source(&fqname("acme/shop:orders#Refund"), &"/body/0".into()),
+            Some(SourceLocation::new(8, 5, 8, 31))
+        );
+        assert_eq!(
+            map.value_source(&refund, &NodePath::root().child("output")),
+            Some(whole)
+        );
+        // Types and values of the same name are mapped apart
+        assert_eq!(map.type_source(&refund, &NodePath::root()), None);
+        assert_eq!(body.parent(), Some(NodePath::root()));
+        assert_eq!(NodePath::root().parent(), None);
+
+        assert!(
+            serde_json::from_value::<SourceMap>(
+                json!({ "values": { "acme/shop:orders#refund": { "": [1, 2] } } })
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,499 @@
+//! Differences between the public specifications of two packages
+//!
+//! Consumers of a published model package compile against its
+//! [`PackageSpecification`]: the public modules, their public types, the
+//! constructors of types that expose them, and the signatures of public
+//! values. [`diff_specifications`] compares two specifications and classifies
+//! each change by its [`Impact`] on those consumers. Private definitions and
+//! value bodies are not part of a specification, so changes to them never
+//! show up.
+//!
+//! Adding a module, type, or value is additive. Removing or changing one is
+//! breaking, and so is adding a constructor to a custom type, since consumers
+//! matching on the type no longer cover every case. Exposing the constructors
+//! of an opaque type is additive.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::module::ModuleSpecification;
+use super::package::PackageSpecification;
+use super::types::{ConstructorSpecification, Field, Type, TypeSpecification};
+use super::value::ValueSpecification;
+use crate::naming::Name;
+
+/// Effect of a change on the consumers of a package
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Impact {
+    /// Existing consumers keep compiling
+    Additive,
+    /// Existing consumers may no longer compile
+    Breaking,
+}
+
+/// What happened to a part of the specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Part of a specification that changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecItem {
+    Module,
+    Type,
+    Constructor,
+    Value,
+}
+
+/// A change to a public specification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecChange {
+    pub item: SpecItem,
+    pub kind: ChangeKind,
+    /// `module`, `module#name`, or `module#type.constructor`
+    pub path: String,
+    pub impact: Impact,
+    pub detail: String,
+}
+
+impl fmt::Display for SpecChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let item = match self.item {
+            SpecItem::Module => "module",
+            SpecItem::Type => "type",
+            SpecItem::Constructor => "constructor",
+            SpecItem::Value => "value",
+        };
+        write!(f, "{} {}: {}", item, self.path, self.detail)
+    }
+}
+
+/// Changes between two specifications, in module order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpecDiff {
+    pub changes: Vec<SpecChange>,
+}
+
+impl SpecDiff {
+    /// Whether the specifications are the same
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether any change breaks existing consumers
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|c| c.impact == Impact::Breaking)
+    }
+
+    /// Changes with `impact`
+    pub fn with_impact(&self, impact: Impact) -> impl Iterator<Item = &SpecChange> {
+        self.changes.iter().filter(move |c| c.impact == impact)
+    }
+
+    fn push(
+        &mut self,
+        item: SpecItem,
+        kind: ChangeKind,
+        path: String,
+        impact: Impact,
+        detail: impl Into<String>,
+    ) {
+        self.changes.push(SpecChange {
+            item,
+            kind,
+            path,
+            impact,
+            detail: detail.into(),
+        });
+    }
+}
+
+/// Compare the specification `new` of a package against its `old` one
+pub fn diff_specifications(old: &PackageSpecification, new: &PackageSpecification) -> SpecDiff {
+    let mut diff = SpecDiff::default();
+    for (name, old_module) in &old.modules {
+        match new.modules.get(name) {
+            Some(new_module) => diff_modules(&mut diff, name, old_module, new_module),
+            None => diff.push(
+                SpecItem::Module,
+                ChangeKind::Removed,
+                name.clone(),
+                Impact::Breaking,
+                "removed",
+            ),
+        }
+    }
+    for name in new.modules.keys() {
+        if !old.modules.contains_key(name) {
+            diff.push(
+                SpecItem::Module,
+                ChangeKind::Added,
+                name.clone(),
+                Impact::Additive,
+                "added",
+            );
+        }
+    }
+    diff
+}
+
+fn diff_modules(
+    diff: &mut SpecDiff,
+    module: &str,
+    old: &ModuleSpecification,
+    new: &ModuleSpecification,
+) {
+    for (name, old_type) in &old.types {
+        let path = format!("{}#{}", module, name);
+        match new.types.get(name) {
+            Some(new_type) => diff_types(diff, &path, old_type, new_type),
+            None => diff.push(
+                SpecItem::Type,
+                ChangeKind::Removed,
+                path,
+                Impact::Breaking,
+                "removed",
+            ),
+        }
+    }
+    for name in new.types.keys().filter(|n| !old.types.contains_key(*n)) {
+        diff.push(
+            SpecItem::Type,
+            ChangeKind::Added,
+            format!("{}#{}", module, name),
+            Impact::Additive,
+            "added",
+        );
+    }
+
+    for (name, old_value) in &old.values {
+        let path = format!("{}#{}", module, name);
+        match new.values.get(name) {
+            Some(new_value) if !same_signature(old_value, new_value) => diff.push(
+                SpecItem::Value,
+                ChangeKind::Changed,
+                path,
+                Impact::Breaking,
+                "signature changed",
+            ),
+            Some(_) => {}
+            None => diff.push(
+                SpecItem::Value,
+                ChangeKind::Removed,
+                path,
+                Impact::Breaking,
+                "removed",
+            ),
+        }
+    }
+    for name in new.values.keys().filter(|n| !old.values.contains_key(*n)) {
+        diff.push(
+            SpecItem::Value,
+            ChangeKind::Added,
+            format!("{}#{}", module, name),
+            Impact::Additive,
+            "added",
+        );
+    }
+}
+
+fn diff_types(diff: &mut SpecDiff, path: &str, old: &TypeSpecification, new: &TypeSpecification) {
+    use TypeSpecification::*;
+    let mut changed = |impact, detail: &str| {
+        diff.push(
+            SpecItem::Type,
+            ChangeKind::Changed,
+            path.to_string(),
+            impact,
+            detail,
+        )
+    };
+    let (old_params, new_params) = (type_params(old), type_params(new));
+    if old_params.len() != new_params.len() {
+        changed(Impact::Breaking, "type parameters changed");
+        return;
+    }
+    let params: Vec<(&Name, &Name)> = old_params.iter().zip(new_params).collect();
+    match (old, new) {
+        (
+            TypeAliasSpecification { type_expr: a, .. },
+            TypeAliasSpecification { type_expr: b, .. },
+        ) => {
+            if !same_type(a, b, &params) {
+                changed(Impact::Breaking, "aliased type changed");
+            }
+        }
+        (OpaqueTypeSpecification { .. }, OpaqueTypeSpecification { .. }) => {}
+        (OpaqueTypeSpecification { .. }, CustomTypeSpecification { .. }) => {
+            changed(Impact::Additive, "constructors exposed");
+        }
+        (CustomTypeSpecification { .. }, OpaqueTypeSpecification { .. }) => {
+            changed(Impact::Breaking, "constructors hidden");
+        }
+        (
+            CustomTypeSpecification {
+                constructors: old, ..
+            },
+            CustomTypeSpecification {
+                constructors: new, ..
+            },
+        ) => diff_constructors(diff, path, old, new, &params),
+        _ => changed(Impact::Breaking, "kind of type changed"),
+    }
+}
+
+fn diff_constructors(
+    diff: &mut SpecDiff,
+    path: &str,
+    old: &[ConstructorSpecification],
+    new: &[ConstructorSpecification],
+    params: &[(&Name, &Name)],
+) {
+    let find = |ctors: &'_ [ConstructorSpecification], name: &Name| {
+        ctors.iter().position(|c| &c.name == name)
+    };
+    for old_ctor in old {
+        let ctor_path = format!("{}.{}", path, old_ctor.name);
+        match find(new, &old_ctor.name).map(|i| &new[i]) {
+            Some(new_ctor) => {
+                let same_args = old_ctor.args.len() == new_ctor.args.len()
+                    && old_ctor
+                        .args
+                        .iter()
+                        .zip(&new_ctor.args)
+                        .all(|(a, b)| same_type(&a.arg_type, &b.arg_type, params));
+                if !same_args {
+                    diff.push(
+                        SpecItem::Constructor,
+                        ChangeKind::Changed,
+                        ctor_path,
+                        Impact::Breaking,
+                        "arguments changed",
+                    );
+                }
+            }
+            None => diff.push(
+                SpecItem::Constructor,
+                ChangeKind::Removed,
+                ctor_path,
+                Impact::Breaking,
+                "removed",
+            ),
+        }
+    }
+    for new_ctor in new.iter().filter(|c| find(old, &c.name).is_none()) {
+        diff.push(
+            SpecItem::Constructor,
+            ChangeKind::Added,
+            format!("{}.{}", path, new_ctor.name),
+            Impact::Breaking,
+            "added; pattern matches on the type no longer cover every case",
+        );
+    }
+}
+
+fn type_params(spec: &TypeSpecification) -> &[Name] {
+    match spec {
+        TypeSpecification::TypeAliasSpecification { type_params, .. }
+        | TypeSpecification::OpaqueTypeSpecification { type_params }
+        | TypeSpecification::CustomTypeSpecification { type_params, .. } => type_params,
+    }
+}
+
+/// Whether the values take and return the same types; input names do not
+/// matter to callers
+fn same_signature(old: &ValueSpecification, new: &ValueSpecification) -> bool {
+    old.inputs.len() == new.inputs.len()
+        && old
+            .inputs
+            .values()
+            .zip(new.inputs.values())
+            .all(|(a, b)| same_type(a, b, &[]))
+        && same_type(&old.output, &new.output, &[])
+}
+
+/// Whether `a` and `b` are the same type, ignoring attributes and field
+/// order, with the type parameters of `a` renamed to those of `b` by `params`
+fn same_type(a: &Type, b: &Type, params: &[(&Name, &Name)]) -> bool {
+    let all = |xs: &[Type], ys: &[Type]| {
+        xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| same_type(x, y, params))
+    };
+    let fields = |xs: &[Field], ys: &[Field]| {
+        xs.len() == ys.len()
+            && xs.iter().all(|x| {
+                ys.iter()
+                    .find(|y| y.name == x.name)
+                    .is_some_and(|y| same_type(&x.tpe, &y.tpe, params))
+            })
+    };
+    let same_var = |x: &Name, y: &Name| match params.iter().find(|(old, _)| *old == x) {
+        Some((_, new)) => *new == y,
+        None => x == y,
+    };
+    match (a, b) {
+        (Type::Variable(_, x), Type::Variable(_, y)) => same_var(x, y),
+        (Type::Reference(_, x, xs), Type::Reference(_, y, ys)) => x == y && all(xs, ys),
+        (Type::Tuple(_, xs), Type::Tuple(_, ys)) => all(xs, ys),
+        (Type::Record(_, xs), Type::Record(_, ys)) => fields(xs, ys),
+        (Type::ExtensibleRecord(_, x, xs), Type::ExtensibleRecord(_, y, ys)) => {
+            same_var(x, y) && fields(xs, ys)
+        }
+        (Type::Function(_, x, xr), Type::Function(_, y, yr)) => {
+            same_type(x, y, params) && same_type(xr, yr, params)
+        }
+        (Type::Unit(_), Type::Unit(_)) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::v4::{ConstructorArgSpec, TypeAttributes};
+    use crate::naming::FQName;
+    use indexmap::IndexMap;
+
+    fn int() -> Type {
+        Type::Reference(
+            TypeAttributes::default(),
+            FQName::from_canonical_string("morphir/sdk:basics#int").unwrap(),
+            vec![],
+        )
+    }
+
+    fn value(inputs: &[(&str, Type)], output: Type) -> ValueSpecification {
+        ValueSpecification {
+            inputs: inputs
+                .iter()
+                .map(|(name, t)| (name.to_string(), t.clone()))
+                .collect(),
+            output,
+        }
+    }
+
+    fn custom(ctors: &[&str]) -> TypeSpecification {
+        TypeSpecification::CustomTypeSpecification {
+            type_params: vec![],
+            constructors: ctors
+                .iter()
+                .map(|name| ConstructorSpecification {
+                    name: Name::from(name),
+                    args: vec![ConstructorArgSpec {
+                        name: Name::from("amount"),
+                        arg_type: int(),
+                    }],
+                })
+                .collect(),
+        }
+    }
+
+    fn package(
+        types: Vec<(&str, TypeSpecification)>,
+        values: Vec<(&str, ValueSpecification)>,
+    ) -> PackageSpecification {
+        let module = ModuleSpecification {
+            types: types.into_iter().map(|(n, t)| (n.to_string(), t)).collect(),
+            values: values
+                .into_iter()
+                .map(|(n, v)| (n.to_string(), v))
+                .collect(),
+            doc: None,
+        };
+        let mut modules = IndexMap::new();
+        modules.insert("orders".to_string(), module);
+        PackageSpecification { modules }
+    }
+
+    #[test]
+    fn test_changes_are_classified_by_impact() {
+        let old = package(
+            vec![
+                ("payment", custom(&["cash"])),
+                (
+                    "id",
+                    TypeSpecification::OpaqueTypeSpecification {
+                        type_params: vec![],
+                    },
+                ),
+            ],
+            vec![
+                ("total", value(&[("order", int())], int())),
+                ("fee", value(&[], int())),
+            ],
+        );
+        let new = package(
+            vec![
+                ("payment", custom(&["cash", "card"])),
+                ("id", custom(&["id"])),
+            ],
+            vec![
+                // Renaming an input does not change the signature
+                ("total", value(&[("o", int())], int())),
+                ("tax", value(&[], int())),
+            ],
+        );
+
+        let diff = diff_specifications(&old, &new);
+        let summary: Vec<(SpecItem, ChangeKind, &str, Impact)> = diff
+            .changes
+            .iter()
+            .map(|c| (c.item, c.kind, c.path.as_str(), c.impact))
+            .collect();
+        use ChangeKind::*;
+        use Impact::*;
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    SpecItem::Constructor,
+                    Added,
+                    "orders#payment.card",
+                    Breaking
+                ),
+                (SpecItem::Type, Changed, "orders#id", Additive),
+                (SpecItem::Value, Removed, "orders#fee", Breaking),
+                (SpecItem::Value, Added, "orders#tax", Additive),
+            ]
+        );
+        assert!(diff.is_breaking());
+        assert_eq!(diff.with_impact(Additive).count(), 2);
+        assert!(diff_specifications(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_additions_alone_are_not_breaking() {
+        let old = package(vec![], vec![("total", value(&[], int()))]);
+        let mut new = package(
+            vec![("amount", custom(&["amount"]))],
+            vec![("total", value(&[], int())), ("fee", value(&[], int()))],
+        );
+        let pricing = new.modules["orders"].clone();
+        new.modules.insert("pricing".to_string(), pricing);
+        let diff = diff_specifications(&old, &new);
+        assert_eq!(diff.changes.len(), 3);
+        assert!(!diff.is_breaking());
+
+        // Types are compared without their attributes
+        let mut located = int();
+        if let Type::Reference(attrs, _, _) = &mut located {
+            attrs.extensions = serde_json::json!({ "line": 3 });
+        }
+        let relocated = package(vec![], vec![("total", value(&[], located))]);
+        assert!(diff_specifications(&old, &relocated).is_empty());
+        let retyped = package(
+            vec![],
+            vec![("total", value(&[], Type::Unit(Default::default())))],
+        );
+        assert_eq!(
+            diff_specifications(&old, &retyped).changes[0].detail,
+            "signature changed"
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! Tokens of the `.mir` syntax
+
+use super::ParseError;
+
+/// Words that never name a variable, type parameter, or definition without
+/// backquotes
+pub(super) const RESERVED: &[&str] = &[
+    "alias",
+    "application",
+    "as",
+    "case",
+    "dependency",
+    "else",
+    "entry",
+    "external",
+    "if",
+    "in",
+    "incomplete",
+    "let",
+    "library",
+    "module",
+    "native",
+    "of",
+    "opaque",
+    "private",
+    "rec",
+    "specs",
+    "then",
+    "type",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Token {
+    /// Word starting in lowercase: a name, a path, or a keyword
+    Lower(String),
+    /// Word starting in uppercase: a type or constructor name, `True`, or
+    /// `False`
+    Upper(String),
+    /// Backquoted name, never a keyword
+    Quoted(String),
+    /// Fully-qualified name, `package:module#local`
+    Qualified(String),
+    Int(i64),
+    Float(f64),
+    Decimal(String),
+    Str(String),
+    Char(char),
+    /// Contents of a `{-| ... -}` comment
+    Doc(String),
+    Symbol(&'static str),
+    Eof,
+}
+
+/// A token and where it starts
+#[derive(Debug, Clone)]
+pub(super) struct Spanned {
+    pub token: Token,
+    pub line: usize,
+    pub column: usize,
+    /// No other token precedes it on its line
+    pub first_on_line: bool,
+    /// Whitespace or a comment precedes it
+    pub spaced: bool,
+}
+
+const SYMBOLS: &[&str] = &[
+    "->", "::", "(", ")", "[", "]", "{", "}", ",", "=", ":", "\\", "|", ".", "_", "?",
+];
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+/// Split `source` into tokens, ending with [`Token::Eof`]
+pub(super) fn tokenize(source: &str) -> Result<Vec<Spanned>, ParseError> {
+    let mut lexer = Lexer {
+        chars: source.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+    let mut last_line = 0;
+    loop {
+        let spaced = lexer.skip_trivia()?;
+        let (line, column) = (lexer.line, lexer.column);
+        let first_on_line = line != last_line;
+        last_line = line;
+        let token = match lexer.peek(0) {
+            None => Token::Eof,
+            Some(_) => lexer.token()?,
+        };
+        let end = token == Token::Eof;
+        tokens.push(Spanned {
+            token,
+            line,
+            column,
+            first_on_line,
+            spaced: spaced || tokens.is_empty(),
+        });
+        if end {
+            return Ok(tokens);
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek(0)?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek(i) == Some(c))
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    /// Skip whitespace and comments other than doc comments, returning
+    /// whether anything was skipped
+    fn skip_trivia(&mut self) -> Result<bool, ParseError> {
+        let start = self.pos;
+        loop {
+            match self.peek(0) {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('-') if self.peek(1) == Some('-') => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                }
+                Some('{') if self.peek(1) == Some('-') && self.peek(2) != Some('|') => {
+                    let (line, column) = (self.line, self.column);
+                    while !self.starts_with("-}") {
+                        if self.bump().is_none() {
+                            return Err(ParseError::new(line, column, "Unterminated comment"));
+                        }
+                    }
+                    self.bump();
+                    self.bump();
+                }
+                _ => return Ok(self.pos > start),
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<Token, ParseError> {
+        let c = self.peek(0).unwrap_or_default();
+        if self.starts_with("{-|") {
+            return self.doc();
+        }
+        if c.is_ascii_digit() || (c == '-' && self.peek(1).is_some_and(|c| c.is_ascii_digit())) {
+            return self.number();
+        }
+        if c.is_lowercase() {
+            let word = self.word(true);
+            if self.peek(0) == Some(':')
+                && self.peek(1).is_some_and(|c| is_word_char(c) || c == '#')
+            {
+                return self.qualified(word);
+            }
+            return Ok(Token::Lower(word));
+        }
+        if c.is_uppercase() {
+            return Ok(Token::Upper(self.word(false)));
+        }
+        match c {
+            '`' => {
+                self.bump();
+                let mut name = String::new();
+                loop {
+                    match self.bump() {
+                        Some('`') => return Ok(Token::Quoted(name)),
+                        Some('\n') | None => return Err(self.error("Unterminated backquoted name")),
+                        Some(c) => name.push(c),
+                    }
+                }
+            }
+            '"' => {
+                self.bump();
+                let mut text = String::new();
+                loop {
+                    match self.bump() {
+                        Some('"') => return Ok(Token::Str(text)),
+                        Some('\\') => text.push(self.escape()?),
+                        Some(c) => text.push(c),
+                        None => return Err(self.error("Unterminated string")),
+                    }
+                }
+            }
+            '\'' => {
+                self.bump();
+                let c = match self.bump() {
+                    Some('\\') => self.escape()?,
+                    Some(c) => c,
+                    None => return Err(self.error("Unterminated character")),
+                };
+                if self.bump() != Some('\'') {
+                    return Err(self.error("Expected ' to close the character"));
+                }
+                Ok(Token::Char(c))
+            }
+            _ => {
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|symbol| self.starts_with(symbol))
+                    .ok_or_else(|| self.error(format!("Unexpected character '{}'", c)))?;
+                for _ in 0..symbol.len() {
+                    self.bump();
+                }
+                Ok(Token::Symbol(symbol))
+            }
+        }
+    }
+
+    /// A word of letters, digits, and `_`, with inner `-` and, for paths,
+    /// `/` each followed by a letter or digit
+    fn word(&mut self, path: bool) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek(0) {
+            let joins = c == '-' || (path && c == '/');
+            if is_word_char(c)
+                || (joins && self.peek(1).is_some_and(|c| c.is_alphanumeric()) && !word.is_empty())
+            {
+                word.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        word
+    }
+
+    fn qualified(&mut self, package: String) -> Result<Token, ParseError> {
+        self.bump();
+        let module = self.word(true);
+        if self.bump() != Some('#') {
+            return Err(self.error("Expected '#' in a qualified name"));
+        }
+        let local = self.word(false);
+        if local.is_empty() {
+            return Err(self.error("Expected a name after '#'"));
+        }
+        Ok(Token::Qualified(format!(
+            "{}:{}#{}",
+            package, module, local
+        )))
+    }
+
+    fn number(&mut self) -> Result<Token, ParseError> {
+        let (line, column) = (self.line, self.column);
+        let mut text = String::new();
+        if self.peek(0) == Some('-') {
+            text.push('-');
+            self.bump();
+        }
+        let mut float = false;
+        loop {
+            match self.peek(0) {
+                Some(c) if c.is_ascii_digit() => {}
+                Some('.') if !float && self.peek(1).is_some_and(|c| c.is_ascii_digit()) => {
+                    float = true;
+                }
+                Some('e' | 'E')
+                    if self.peek(1).is_some_and(|c| c.is_ascii_digit())
+                        || (matches!(self.peek(1), Some('-' | '+'))
+                            && self.peek(2).is_some_and(|c| c.is_ascii_digit())) =>
+                {
+                    float = true;
+                    text.push(self.bump().unwrap_or_default());
+                }
+                _ => break,
+            }
+            text.push(self.bump().unwrap_or_default());
+        }
+        let invalid = |_| ParseError::new(line, column, format!("Invalid number {}", text));
+        if self.peek(0) == Some('d') && !self.peek(1).is_some_and(is_word_char) {
+            self.bump();
+            return Ok(Token::Decimal(text));
+        }
+        if float {
+            text.parse()
+                .map(Token::Float)
+                .map_err(|e: std::num::ParseFloatError| invalid(e.to_string()))
+        } else {
+            text.parse()
+                .map(Token::Int)
+                .map_err(|e: std::num::ParseIntError| invalid(e.to_string()))
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, ParseError> {
+        match self.bump() {
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some('0') => Ok('\0'),
+            Some(c @ ('\\' | '"' | '\'')) => Ok(c),
+            Some('u') if self.peek(0) == Some('{') => {
+                self.bump();
+                let mut hex = String::new();
+                while let Some(c) = self.bump() {
+                    if c == '}' {
+                        return u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.error(format!("Invalid escape \\u{{{}}}", hex)));
+                    }
+                    hex.push(c);
+                }
+                Err(self.error("Unterminated escape"))
+            }
+            _ => Err(self.error("Invalid escape")),
+        }
+    }
+
+    fn doc(&mut self) -> Result<Token, ParseError> {
+        let (line, column) = (self.line, self.column);
+        for _ in 0..3 {
+            self.bump();
+        }
+        let mut text = String::new();
+        loop {
+            if self.starts_with("-}") {
+                self.bump();
+                self.bump();
+                break;
+            }
+            match self.bump() {
+                Some('\\') if matches!(self.peek(0), Some('\\' | '}')) => {
+                    text.push(self.bump().unwrap_or_default());
+                }
+                Some(c) => text.push(c),
+                None => return Err(ParseError::new(line, column, "Unterminated doc comment")),
+            }
+        }
+        let text = text.strip_prefix(' ').unwrap_or(&text);
+        let text = text.strip_suffix(' ').unwrap_or(text);
+        Ok(Token::Doc(text.to_string()))
+    }
+}
+
+/// Doc comment for `doc`, escaped so that it ends where the doc does
+pub(super) fn doc_comment(doc: &str) -> String {
+    format!(
+        "{{-| {} -}}",
+        doc.replace('\\', "\\\\").replace("-}", "-\\}")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token> {
+        tokenize(source)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokens("order-total (x : morphir/sdk:basics#Int) -> -1.5 -- comment\n`in` 2.50d"),
+            vec![
+                Token::Lower("order-total".into()),
+                Token::Symbol("("),
+                Token::Lower("x".into()),
+                Token::Symbol(":"),
+                Token::Qualified("morphir/sdk:basics#Int".into()),
+                Token::Symbol(")"),
+                Token::Symbol("->"),
+                Token::Float(-1.5),
+                Token::Quoted("in".into()),
+                Token::Decimal("2.50".into()),
+                Token::Eof,
+            ]
+        );
+        assert_eq!(
+            tokens("{- note -} h :: t \"a\\n\\u{e9}\" 'x'"),
+            vec![
+                Token::Lower("h".into()),
+                Token::Symbol("::"),
+                Token::Lower("t".into()),
+                Token::Str("a\né".into()),
+                Token::Char('x'),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_doc_comments_round_trip() {
+        for doc in [
+            "Orders and their lines.",
+            "",
+            "a -} b \\ c\n\n  indented ",
+            " x",
+        ] {
+            assert_eq!(tokens(&doc_comment(doc))[0], Token::Doc(doc.to_string()));
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Textual surface syntax for Morphir IR (`.mir`)
+//!
+//! A canonical, human-readable rendering of a distribution, so models can be
+//! reviewed and diffed like code, and a parser reading it back. The syntax is
+//! Elm-like, with every reference fully qualified:
+//!
+//! ```text
+//! library acme/shop
+//!
+//!
+//! module orders
+//!     {-| Orders and their lines. -}
+//!
+//! {-| One line of an order -}
+//! type alias Line =
+//!     { price : morphir/sdk:basics#Int
+//!     , quantity : morphir/sdk:basics#Int
+//!     }
+//!
+//! total (lines : morphir/sdk:list#List acme/shop:orders#Line) : morphir/sdk:basics#Int =
+//!     morphir/sdk:list#sum (morphir/sdk:list#map .price lines)
+//!
+//!
+//! dependency morphir/sdk
+//!
+//!
+//! module list
+//!
+//! sum (list : morphir/sdk:list#List number) : number
+//! ```
+//!
+//! - Names are kebab-case; types and constructors are capitalised, so
+//!   `acme/shop:orders#Line` is a type or constructor and
+//!   `acme/shop:orders#total` a value. Keys that are not plain kebab-case
+//!   words, and names that are keywords, are backquoted: `` `orderTotal` ``.
+//! - Each declaration starts in the first column, and the lines continuing
+//!   it are indented. Inside an expression, the branches of a `case` and the
+//!   bindings of a `let` each start in the same column, as in Elm.
+//! - `private` marks private modules and declarations, and `opaque` custom
+//!   types whose constructors are private.
+//! - A `let` binds one value or pattern; mutually recursive definitions are
+//!   bound together by `let rec`.
+//! - Holes are written `?draft`, `?unresolved pkg:mod#name`,
+//!   `?deleted "tx"`, and `?mismatch "expected" "found"`, and values defined
+//!   natively or externally end in `native <hint>` or
+//!   `external "name" "platform"` instead of `= <body>`.
+//! - `{-| ... -}` documents the item that follows it; a module's own
+//!   documentation is indented under its header.
+//!
+//! Attributes, such as source locations and inferred types, and node ids are
+//! not part of the text: parsing leaves them empty. Comments, `--` and
+//! `{- ... -}`, are skipped.
+
+use thiserror::Error;
+
+use super::{
+    AccessControlled, Distribution, ModuleDefinition, ModuleSpecification, Pattern, Type,
+    TypeDefinition, TypeSpecification, Value, ValueDefinition, ValueSpecification,
+};
+use crate::naming::Name;
+
+mod lexer;
+mod parser;
+mod printer;
+
+/// File extension of the syntax
+pub const EXTENSION: &str = "mir";
+
+/// Error reading `.mir` text
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{line}:{column}: {message}")]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        ParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+/// `distribution` in canonical `.mir` layout
+pub fn print_distribution(distribution: &Distribution) -> String {
+    printer::distribution(distribution)
+}
+
+/// Read a distribution from `.mir` text
+pub fn parse_distribution(source: &str) -> Result<Distribution, ParseError> {
+    let mut parser = parser::Parser::new(source)?;
+    let distribution = parser.distribution()?;
+    parser.finish()?;
+    Ok(distribution)
+}
+
+/// `source` in canonical layout, without its comments
+pub fn format(source: &str) -> Result<String, ParseError> {
+    parse_distribution(source).map(|distribution| print_distribution(&distribution))
+}
+
+/// A module of a package, with its header and declarations
+pub fn print_module_definition(name: &str, module: &AccessControlled<ModuleDefinition>) -> String {
+    printer::module_definition(name, module)
+}
+
+/// A module of a dependency, with its header and declarations
+pub fn print_module_specification(name: &str, module: &ModuleSpecification) -> String {
+    printer::module_specification(name, module)
+}
+
+pub fn print_type_definition(name: &str, definition: &AccessControlled<TypeDefinition>) -> String {
+    printer::type_definition(name, definition)
+}
+
+pub fn print_type_specification(name: &str, specification: &TypeSpecification) -> String {
+    printer::type_specification(name, specification)
+}
+
+pub fn print_value_definition(
+    name: &str,
+    definition: &AccessControlled<ValueDefinition>,
+) -> String {
+    printer::value_definition(name, definition)
+}
+
+pub fn print_value_specification(name: &str, specification: &ValueSpecification) -> String {
+    printer::value_specification(name, specification)
+}
+
+pub fn print_type(tpe: &Type) -> String {
+    printer::type_text(tpe)
+}
+
+pub fn print_value(value: &Value) -> String {
+    printer::expression(value, 0)
+}
+
+pub fn print_pattern(pattern: &Pattern) -> String {
+    printer::pattern_text(pattern)
+}
+
+/// Read a type expression
+pub fn parse_type(source: &str) -> Result<Type, ParseError> {
+    let mut parser = parser::Parser::new(source)?;
+    let tpe = parser.type_expression()?;
+    parser.finish()?;
+    Ok(tpe)
+}
+
+/// Read a value expression
+pub fn parse_value(source: &str) -> Result<Value, ParseError> {
+    let mut parser = parser::Parser::new(source)?;
+    let value = parser.expression()?;
+    parser.finish()?;
+    Ok(value)
+}
+
+/// `name` with its words in lowercase, as capitalised names are read
+fn lowercase(name: &Name) -> Name {
+    let words: Vec<String> = name.iter().map(str::to_lowercase).collect();
+    Name::new(&words.iter().map(String::as_str).collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A library touching every construct of the syntax
+    fn library() -> Distribution {
+        serde_json::from_str(
+            r#"{ "Library": {
+            "packageName": "acme/shop",
+            "dependencies": { "morphir/sdk": { "modules": { "basics": {
+                "types": {
+                    "int": { "opaqueTypeSpecification": { "type_params": [] } },
+                    "order": { "customTypeSpecification": {
+                        "type_params": [],
+                        "constructors": [{ "name": "lt", "args": [] }, { "name": "eq", "args": [] }]
+                    } }
+                },
+                "values": {
+                    "add": {
+                        "inputs": { "a": "morphir/sdk:basics#int", "b": "morphir/sdk:basics#int" },
+                        "output": "morphir/sdk:basics#int"
+                    }
+                },
+                "doc": "Basic types"
+            } } } },
+            "def": { "modules": {
+                "orders": { "access": "Public", "value": {
+                    "doc": "Orders and their lines.",
+                    "types": {
+                        "line": { "access": "Public", "doc": "One line", "value": { "TypeAliasDefinition": {
+                            "typeParams": [],
+                            "typeExp": { "Record": { "fields": {
+                                "price": "morphir/sdk:basics#int",
+                                "quantity": "morphir/sdk:basics#int"
+                            } } }
+                        } } },
+                        "status": { "access": "Private", "value": { "CustomTypeDefinition": {
+                            "typeParams": ["a"],
+                            "constructors": { "access": "Private", "value": [
+                                { "name": "pending", "args": [] },
+                                { "name": "held", "args": [{ "name": "reason", "type": { "Reference": {
+                                    "fqname": "morphir/sdk:maybe#maybe",
+                                    "args": [{ "Variable": { "name": "a" } }]
+                                } } }] }
+                            ] }
+                        } } }
+                    },
+                    "values": {
+                        "orderTotal": { "access": "Public", "value": {
+                            "inputTypes": {
+                                "lines": { "type": { "Reference": {
+                                    "fqname": "morphir/sdk:list#list",
+                                    "args": ["acme/shop:orders#line"]
+                                } } },
+                                "in": { "type": { "Function": {
+                                    "arg": "morphir/sdk:basics#int",
+                                    "result": "morphir/sdk:basics#int"
+                                } } }
+                            },
+                            "outputType": "morphir/sdk:basics#int",
+                            "body": { "ExpressionBody": { "body": { "PatternMatch": {
+                                "subject": { "Variable": { "name": "lines" } },
+                                "cases": [
+                                    [{ "EmptyListPattern": {} }, { "Literal": { "literal": { "IntegerLiteral": { "value": -1 } } } }],
+                                    [{ "HeadTailPattern": {
+                                        "head": { "AsPattern": { "pattern": { "WildcardPattern": {} }, "name": "first" } },
+                                        "tail": { "WildcardPattern": {} }
+                                    } }, { "LetDefinition": {
+                                        "name": "price",
+                                        "definition": {
+                                            "inputTypes": {},
+                                            "outputType": "morphir/sdk:basics#int",
+                                            "body": { "ExpressionBody": { "body": { "Field": {
+                                                "value": { "Variable": { "name": "first" } },
+                                                "name": "price"
+                                            } } } }
+                                        },
+                                        "body": { "IfThenElse": {
+                                            "condition": { "Literal": { "literal": { "BoolLiteral": { "value": true } } } },
+                                            "thenBranch": { "Apply": {
+                                                "function": { "Apply": {
+                                                    "function": { "Reference": { "fqname": "morphir/sdk:basics#add" } },
+                                                    "argument": { "Variable": { "name": "price" } }
+                                                } },
+                                                "argument": { "Apply": {
+                                                    "function": { "Variable": { "name": "in" } },
+                                                    "argument": { "Literal": { "literal": { "FloatLiteral": { "value": 1.5 } } } }
+                                                } }
+                                            } },
+                                            "elseBranch": { "Record": { "fields": {
+                                                "note": { "Literal": { "literal": { "StringLiteral": { "value": "say \"hi\"\n" } } } },
+                                                "items": { "List": { "items": [
+                                                    { "Tuple": { "elements": [{ "Unit": {} }, { "Literal": { "literal": { "CharLiteral": { "value": "'" } } } }] } },
+                                                    { "Lambda": {
+                                                        "pattern": { "ConstructorPattern": {
+                                                            "fqname": "morphir/sdk:maybe#just",
+                                                            "args": [{ "LiteralPattern": { "literal": { "DecimalLiteral": { "value": "2.50" } } } }]
+                                                        } },
+                                                        "body": { "Constructor": { "fqname": "morphir/sdk:maybe#nothing" } }
+                                                    } },
+                                                    { "FieldFunction": { "name": "price" } },
+                                                    { "Hole": { "reason": { "UnresolvedReference": { "target": "acme/shop:orders#gone" } } } }
+                                                ] } }
+                                            } } }
+                                        } }
+                                    } }]
+                                ]
+                            } } } }
+                        } },
+                        "rate": { "access": "Private", "doc": "Per -} cent", "value": {
+                            "inputTypes": {},
+                            "outputType": "morphir/sdk:basics#float",
+                            "body": { "NativeBody": { "hint": { "Arithmetic": {} }, "description": "rate" } }
+                        } }
+                    }
+                } },
+                "internal": { "access": "Private", "value": { "types": {}, "values": {} } }
+            } }
+        } }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_print_layout() {
+        let text = print_distribution(&library());
+        assert!(text.starts_with(
+            "library acme/shop\n\n\nmodule orders\n    {-| Orders and their lines. -}\n"
+        ));
+        assert!(text.contains("\n\n\nprivate module internal\n"));
+        assert!(text.contains(
+            "{-| One line -}\ntype alias Line =\n    { price : morphir/sdk:basics#Int\n    , quantity : morphir/sdk:basics#Int\n    }"
+        ));
+        assert!(text.contains(
+            "private opaque type Status a\n    = Held (reason : morphir/sdk:maybe#Maybe a)\n    | Pending"
+        ) || text.contains(
+            "private opaque type Status a\n    = Pending\n    | Held (reason : morphir/sdk:maybe#Maybe a)"
+        ));
+        assert!(text.contains(
+            "`orderTotal` (lines : morphir/sdk:list#List acme/shop:orders#Line) (`in` : morphir/sdk:basics#Int -> morphir/sdk:basics#Int) : morphir/sdk:basics#Int =\n    case lines of\n        [] ->\n            -1\n        first :: _ ->\n            let\n                price : morphir/sdk:basics#Int =\n                    first.price\n            in\n            if True then\n                morphir/sdk:basics#add price (`in` 1.5)\n            else\n"
+        ));
+        assert!(text.contains("{-| Per -\\} cent -}\nprivate rate : morphir/sdk:basics#Float native arithmetic \"rate\""));
+        assert!(text.contains("\n\n\ndependency morphir/sdk\n\n\nmodule basics\n    {-| Basic types -}\n\nopaque type Int\n\ntype Order\n    = Lt\n    | Eq\n\nadd (a : morphir/sdk:basics#Int) (b : morphir/sdk:basics#Int) : morphir/sdk:basics#Int\n"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let distribution = library();
+        let text = print_distribution(&distribution);
+        let parsed = parse_distribution(&text).unwrap_or_else(|e| panic!("{}\n{}", e, text));
+        assert_eq!(print_distribution(&parsed), text);
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(&distribution).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_format_and_errors() {
+        let source = "library acme/shop\nmodule orders -- the only module\ntotal (x : morphir/sdk:basics#Int) : morphir/sdk:basics#Int = case x of\n  0 -> x\n  _ -> morphir/sdk:basics#negate x\n";
+        assert_eq!(
+            format(source).unwrap(),
+            "library acme/shop\n\n\nmodule orders\n\ntotal (x : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =\n    case x of\n        0 ->\n            x\n        _ ->\n            morphir/sdk:basics#negate x\n"
+        );
+
+        let error = parse_distribution(
+            "library acme/shop\nmodule orders\ntotal : morphir/sdk:basics#Int =\n    (1\n",
+        )
+        .unwrap_err();
+        assert_eq!((error.line, error.column), (5, 1));
+        assert_eq!(error.message, "Expected ')', found the end of the input");
+
+        assert_eq!(
+            parse_type("{ r | a : morphir/sdk:maybe#Maybe (x -> y) }").map(|t| print_type(&t)),
+            Ok("{ r | a : morphir/sdk:maybe#Maybe (x -> y) }".to_string())
+        );
+        assert!(parse_value("f (?draft : ( a, b ))").is_ok());
+    }
+}
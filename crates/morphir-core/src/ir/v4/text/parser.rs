@@ -0,0 +1,1186 @@
+//! Reading the `.mir` syntax back into IR
+
+use indexmap::IndexMap;
+
+use super::ParseError;
+use super::lexer::{RESERVED, Spanned, Token, tokenize};
+use crate::ir::v4::{
+    Access, AccessControlled, ApplicationContent, ConstructorArg, ConstructorArgSpec,
+    ConstructorDefinition, ConstructorSpecification, Dependencies, Distribution, EntryPoint,
+    EntryPointKind, EntryPoints, Field, HoleReason, Incompleteness, InputTypeEntry, LetBinding,
+    LibraryContent, Literal, ModuleDefinition, ModuleSpecification, NativeHint, NativeInfo,
+    NodeIds, PackageDefinition, PackageSpecification, Pattern, PatternCase, RecordFieldEntry,
+    SpecsContent, Type, TypeAttributes, TypeDefinition, TypeSpecification, Value, ValueAttributes,
+    ValueBody, ValueDefinition, ValueSpecification,
+};
+use crate::naming::{FQName, Name, PackageName, Path};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+pub(super) struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    /// Tokens starting a line at or left of this column end the construct
+    /// being parsed
+    limit: usize,
+}
+
+/// Modules of the distribution's own package, or of a dependency
+enum Package {
+    Definition(IndexMap<String, AccessControlled<ModuleDefinition>>),
+    Specification(IndexMap<String, ModuleSpecification>),
+}
+
+impl Parser {
+    pub(super) fn new(source: &str) -> Result<Self> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            limit: 0,
+        })
+    }
+
+    fn spanned(&self) -> &Spanned {
+        &self.tokens[self.pos]
+    }
+
+    fn peek(&self) -> &Token {
+        &self.spanned().token
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.pos + offset).min(last)].token
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token != Token::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    /// Whether the construct being parsed has ended: at the end of the
+    /// input, or at a line starting at or left of the limit
+    fn ended(&self) -> bool {
+        let spanned = self.spanned();
+        spanned.token == Token::Eof || (spanned.first_on_line && spanned.column <= self.limit)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        let spanned = self.spanned();
+        ParseError::new(spanned.line, spanned.column, message)
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let found = match self.peek() {
+            Token::Lower(word) | Token::Upper(word) => format!("'{}'", word),
+            Token::Quoted(word) => format!("`{}`", word),
+            Token::Qualified(name) => format!("'{}'", name),
+            Token::Int(n) => n.to_string(),
+            Token::Float(n) => n.to_string(),
+            Token::Decimal(n) => format!("{}d", n),
+            Token::Str(_) => "a string".to_string(),
+            Token::Char(_) => "a character".to_string(),
+            Token::Doc(_) => "a doc comment".to_string(),
+            Token::Symbol(symbol) => format!("'{}'", symbol),
+            Token::Eof => "the end of the input".to_string(),
+        };
+        self.error(format!("Expected {}, found {}", expected, found))
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Symbol(s) if *s == symbol)
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.is_symbol(symbol);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", symbol)))
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Lower(word) if word == keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", keyword)))
+        }
+    }
+
+    /// Run `parse` with `limit` as the offside column
+    fn with_limit<T>(
+        &mut self,
+        limit: usize,
+        parse: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let outer = std::mem::replace(&mut self.limit, limit);
+        let result = parse(self);
+        self.limit = outer;
+        result
+    }
+
+    pub(super) fn finish(&self) -> Result<()> {
+        match self.peek() {
+            Token::Eof => Ok(()),
+            _ => Err(self.unexpected("the end of the input")),
+        }
+    }
+
+    // Names
+
+    fn is_name(&self) -> bool {
+        match self.peek() {
+            Token::Lower(word) => !RESERVED.contains(&word.as_str()) && !word.contains('/'),
+            Token::Quoted(_) => true,
+            _ => false,
+        }
+    }
+
+    /// A map key: a plain word or a backquoted one
+    fn key(&mut self) -> Result<String> {
+        if !self.is_name() {
+            return Err(self.unexpected("a name"));
+        }
+        match self.advance() {
+            Token::Lower(word) | Token::Quoted(word) => Ok(word),
+            _ => unreachable!("checked by is_name"),
+        }
+    }
+
+    fn name(&mut self) -> Result<Name> {
+        self.key().map(|key| Name::from(&key))
+    }
+
+    /// A module or package key, `/`-separated
+    fn path_key(&mut self) -> Result<String> {
+        match self.peek().clone() {
+            Token::Lower(word) if !word.split('/').any(|s| RESERVED.contains(&s)) => {
+                self.advance();
+                Ok(word)
+            }
+            Token::Quoted(word) => {
+                self.advance();
+                Ok(word)
+            }
+            _ => Err(self.unexpected("a module or package name")),
+        }
+    }
+
+    /// The key of a type: capitalised, or backquoted
+    fn type_key(&mut self) -> Result<String> {
+        match self.peek().clone() {
+            Token::Upper(word) => {
+                self.advance();
+                Ok(super::lowercase(&Name::from(&word)).to_string())
+            }
+            Token::Quoted(word) => {
+                self.advance();
+                Ok(word)
+            }
+            _ => Err(self.unexpected("a type name")),
+        }
+    }
+
+    fn constructor_name(&mut self) -> Result<Name> {
+        match self.peek().clone() {
+            Token::Upper(word) => {
+                self.advance();
+                Ok(super::lowercase(&Name::from(&word)))
+            }
+            _ => Err(self.unexpected("a constructor name")),
+        }
+    }
+
+    /// A qualified name, and whether its local name is capitalised
+    fn qualified(&mut self) -> Result<(FQName, bool)> {
+        let Token::Qualified(text) = self.peek().clone() else {
+            return Err(self.unexpected("a qualified name"));
+        };
+        self.advance();
+        let fqname = FQName::from_canonical_string(&text).map_err(|e| self.error(e))?;
+        let upper = fqname
+            .local_name
+            .iter()
+            .next()
+            .is_some_and(|word| word.starts_with(char::is_uppercase));
+        Ok((
+            FQName::new(
+                fqname.package_path,
+                fqname.module_path,
+                super::lowercase(&fqname.local_name),
+            ),
+            upper,
+        ))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        match self.peek().clone() {
+            Token::Str(text) => {
+                self.advance();
+                Ok(text)
+            }
+            _ => Err(self.unexpected("a string")),
+        }
+    }
+
+    fn doc(&mut self) -> Option<String> {
+        match self.peek().clone() {
+            Token::Doc(doc) => {
+                self.advance();
+                Some(doc)
+            }
+            _ => None,
+        }
+    }
+
+    // Distributions
+
+    pub(super) fn distribution(&mut self) -> Result<Distribution> {
+        let kind = match self.peek() {
+            Token::Lower(word) if ["library", "application", "specs"].contains(&word.as_str()) => {
+                word.clone()
+            }
+            _ => return Err(self.unexpected("'library', 'application', or 'specs'")),
+        };
+        self.advance();
+        let package_name = PackageName::new(Path::new(&self.path_key()?));
+
+        let mut package = if kind == "specs" {
+            Package::Specification(IndexMap::new())
+        } else {
+            Package::Definition(IndexMap::new())
+        };
+        let mut entry_points = EntryPoints::new();
+        // Declarations go to the last dependency read, if any
+        let mut dependencies: IndexMap<String, Package> = IndexMap::new();
+        let mut module: Option<String> = None;
+
+        while *self.peek() != Token::Eof {
+            let doc = self.doc();
+            self.limit = 1;
+            if self.eat_keyword("entry") {
+                if kind != "application" {
+                    return Err(self.error("Only applications have entry points"));
+                }
+                let (name, entry) = self.entry_point(doc)?;
+                if entry_points.insert(name.clone(), entry).is_some() {
+                    return Err(self.error(format!("Duplicate entry point {}", name)));
+                }
+            } else if self.eat_keyword("dependency") {
+                let name = self.path_key()?;
+                let modules = Package::Specification(IndexMap::new());
+                if dependencies.insert(name.clone(), modules).is_some() {
+                    return Err(self.error(format!("Duplicate dependency {}", name)));
+                }
+                module = None;
+            } else {
+                let modules = match dependencies.last_mut() {
+                    Some((_, modules)) => modules,
+                    None => &mut package,
+                };
+                self.module_item(doc, modules, &mut module)?;
+            }
+            self.limit = 0;
+            if *self.peek() != Token::Eof && !self.spanned().first_on_line {
+                return Err(self.unexpected("a new line"));
+            }
+        }
+
+        let dependencies: Dependencies = dependencies
+            .into_iter()
+            .map(|(name, modules)| match modules {
+                Package::Specification(modules) => (name, PackageSpecification { modules }),
+                Package::Definition(_) => unreachable!("dependencies are specifications"),
+            })
+            .collect();
+        Ok(match package {
+            Package::Specification(modules) => Distribution::Specs(SpecsContent {
+                package_name,
+                dependencies,
+                spec: PackageSpecification { modules },
+            }),
+            Package::Definition(modules) if kind == "application" => {
+                Distribution::Application(ApplicationContent {
+                    package_name,
+                    dependencies,
+                    def: PackageDefinition { modules },
+                    entry_points,
+                })
+            }
+            Package::Definition(modules) => Distribution::Library(LibraryContent {
+                package_name,
+                dependencies,
+                def: PackageDefinition { modules },
+            }),
+        })
+    }
+
+    fn entry_point(&mut self, doc: Option<String>) -> Result<(String, EntryPoint)> {
+        let name = self.key()?;
+        let kind = match self.peek() {
+            Token::Lower(word) if word == "main" => EntryPointKind::Main,
+            Token::Lower(word) if word == "command" => EntryPointKind::Command,
+            Token::Lower(word) if word == "handler" => EntryPointKind::Handler,
+            _ => return Err(self.unexpected("'main', 'command', or 'handler'")),
+        };
+        self.advance();
+        let target = match self.peek().clone() {
+            Token::Qualified(target) | Token::Str(target) => target,
+            _ => return Err(self.unexpected("the entry point's target")),
+        };
+        self.advance();
+        Ok((name, EntryPoint { target, kind, doc }))
+    }
+
+    /// A module header or a declaration of the current module
+    fn module_item(
+        &mut self,
+        doc: Option<String>,
+        package: &mut Package,
+        module: &mut Option<String>,
+    ) -> Result<()> {
+        let private = self.eat_keyword("private");
+        let access = if private {
+            Access::Private
+        } else {
+            Access::Public
+        };
+
+        if self.eat_keyword("module") {
+            let name = self.path_key()?;
+            let module_doc = match self.peek() {
+                Token::Doc(_) if !self.ended() => self.doc(),
+                _ => None,
+            };
+            let duplicate = match package {
+                Package::Definition(modules) => modules
+                    .insert(
+                        name.clone(),
+                        AccessControlled {
+                            access,
+                            doc,
+                            value: ModuleDefinition {
+                                types: IndexMap::new(),
+                                values: IndexMap::new(),
+                                doc: module_doc,
+                                ids: NodeIds::default(),
+                            },
+                        },
+                    )
+                    .is_some(),
+                Package::Specification(_) if private => {
+                    return Err(self.error("Specifications have no private modules"));
+                }
+                Package::Specification(modules) => modules
+                    .insert(
+                        name.clone(),
+                        ModuleSpecification {
+                            types: IndexMap::new(),
+                            values: IndexMap::new(),
+                            doc: module_doc.or(doc),
+                        },
+                    )
+                    .is_some(),
+            };
+            if duplicate {
+                return Err(self.error(format!("Duplicate module {}", name)));
+            }
+            *module = Some(name);
+            return Ok(());
+        }
+
+        let Some(module) = module.as_ref() else {
+            return Err(self.error("Declarations must follow a module header"));
+        };
+        match package {
+            Package::Definition(modules) => {
+                let module = &mut modules[module.as_str()].value;
+                if self.is_type_declaration() {
+                    let (name, value) = self.type_definition()?;
+                    let definition = AccessControlled { access, doc, value };
+                    if module.types.insert(name.clone(), definition).is_some() {
+                        return Err(self.error(format!("Duplicate type {}", name)));
+                    }
+                } else {
+                    let name = self.key()?;
+                    let value = self.value_definition()?;
+                    let definition = AccessControlled { access, doc, value };
+                    if module.values.insert(name.clone(), definition).is_some() {
+                        return Err(self.error(format!("Duplicate value {}", name)));
+                    }
+                }
+            }
+            Package::Specification(_) if private => {
+                return Err(self.error("Specifications have no private declarations"));
+            }
+            Package::Specification(modules) => {
+                let module = &mut modules[module.as_str()];
+                if self.is_type_declaration() {
+                    let (name, spec) = self.type_specification()?;
+                    if module.types.insert(name.clone(), spec).is_some() {
+                        return Err(self.error(format!("Duplicate type {}", name)));
+                    }
+                } else {
+                    let name = self.key()?;
+                    let spec = self.value_specification()?;
+                    if module.values.insert(name.clone(), spec).is_some() {
+                        return Err(self.error(format!("Duplicate value {}", name)));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_type_declaration(&self) -> bool {
+        self.is_keyword("type") || self.is_keyword("opaque") || self.is_keyword("incomplete")
+    }
+
+    fn type_params(&mut self) -> Result<Vec<Name>> {
+        let mut params = Vec::new();
+        while !self.ended() && self.is_name() {
+            params.push(self.name()?);
+        }
+        Ok(params)
+    }
+
+    fn type_definition(&mut self) -> Result<(String, TypeDefinition)> {
+        if self.eat_keyword("incomplete") {
+            self.expect_keyword("type")?;
+            let name = self.type_key()?;
+            let type_params = self.type_params()?;
+            self.expect_symbol("=")?;
+            let incompleteness = if self.eat_symbol("?") {
+                Incompleteness::Hole(self.hole_reason()?)
+            } else {
+                self.expect_keyword("draft")?;
+                Incompleteness::Draft
+            };
+            return Ok((
+                name,
+                TypeDefinition::IncompleteTypeDefinition {
+                    type_params,
+                    incompleteness,
+                },
+            ));
+        }
+        let opaque = self.eat_keyword("opaque");
+        self.expect_keyword("type")?;
+        if !opaque && self.eat_keyword("alias") {
+            let name = self.type_key()?;
+            let type_params = self.type_params()?;
+            self.expect_symbol("=")?;
+            let type_expr = self.type_expression()?;
+            return Ok((
+                name,
+                TypeDefinition::TypeAliasDefinition {
+                    type_params,
+                    type_expr,
+                },
+            ));
+        }
+        let name = self.type_key()?;
+        let type_params = self.type_params()?;
+        let constructors = self
+            .constructors()?
+            .into_iter()
+            .map(|(name, args)| ConstructorDefinition {
+                name,
+                args: args
+                    .into_iter()
+                    .map(|(name, arg_type)| ConstructorArg { name, arg_type })
+                    .collect(),
+            })
+            .collect();
+        let access = if opaque {
+            Access::Private
+        } else {
+            Access::Public
+        };
+        Ok((
+            name,
+            TypeDefinition::CustomTypeDefinition {
+                type_params,
+                constructors: AccessControlled {
+                    access,
+                    doc: None,
+                    value: constructors,
+                },
+            },
+        ))
+    }
+
+    fn type_specification(&mut self) -> Result<(String, TypeSpecification)> {
+        if self.is_keyword("incomplete") {
+            return Err(self.error("Specifications have no incomplete types"));
+        }
+        if self.eat_keyword("opaque") {
+            self.expect_keyword("type")?;
+            let name = self.type_key()?;
+            let type_params = self.type_params()?;
+            return Ok((
+                name,
+                TypeSpecification::OpaqueTypeSpecification { type_params },
+            ));
+        }
+        self.expect_keyword("type")?;
+        if self.eat_keyword("alias") {
+            let name = self.type_key()?;
+            let type_params = self.type_params()?;
+            self.expect_symbol("=")?;
+            let type_expr = self.type_expression()?;
+            return Ok((
+                name,
+                TypeSpecification::TypeAliasSpecification {
+                    type_params,
+                    type_expr,
+                },
+            ));
+        }
+        let name = self.type_key()?;
+        let type_params = self.type_params()?;
+        let constructors = self
+            .constructors()?
+            .into_iter()
+            .map(|(name, args)| ConstructorSpecification {
+                name,
+                args: args
+                    .into_iter()
+                    .map(|(name, arg_type)| ConstructorArgSpec { name, arg_type })
+                    .collect(),
+            })
+            .collect();
+        Ok((
+            name,
+            TypeSpecification::CustomTypeSpecification {
+                type_params,
+                constructors,
+            },
+        ))
+    }
+
+    /// `= A (x : T) | B`, if present
+    #[allow(clippy::type_complexity)]
+    fn constructors(&mut self) -> Result<Vec<(Name, Vec<(Name, Type)>)>> {
+        let mut constructors = Vec::new();
+        if self.ended() || !self.eat_symbol("=") {
+            return Ok(constructors);
+        }
+        loop {
+            let name = self.constructor_name()?;
+            let mut args = Vec::new();
+            while !self.ended() && self.is_symbol("(") {
+                self.advance();
+                args.push(self.with_limit(0, |p| {
+                    let name = p.name()?;
+                    p.expect_symbol(":")?;
+                    let tpe = p.type_expression()?;
+                    p.expect_symbol(")")?;
+                    Ok((name, tpe))
+                })?);
+            }
+            constructors.push((name, args));
+            if self.ended() || !self.eat_symbol("|") {
+                return Ok(constructors);
+            }
+        }
+    }
+
+    /// `(x : T) (y : U) : V`
+    fn signature(&mut self) -> Result<(IndexMap<String, Type>, Type)> {
+        let mut inputs = IndexMap::new();
+        while self.is_symbol("(") {
+            self.advance();
+            let (name, tpe) = self.with_limit(0, |p| {
+                let name = p.key()?;
+                p.expect_symbol(":")?;
+                let tpe = p.type_expression()?;
+                p.expect_symbol(")")?;
+                Ok((name, tpe))
+            })?;
+            if inputs.insert(name.clone(), tpe).is_some() {
+                return Err(self.error(format!("Duplicate input {}", name)));
+            }
+        }
+        self.expect_symbol(":")?;
+        let output = self.type_expression()?;
+        Ok((inputs, output))
+    }
+
+    fn value_specification(&mut self) -> Result<ValueSpecification> {
+        let (inputs, output) = self.signature()?;
+        if !self.ended() {
+            return Err(self.error("Specifications have no bodies"));
+        }
+        Ok(ValueSpecification { inputs, output })
+    }
+
+    fn value_definition(&mut self) -> Result<ValueDefinition> {
+        let (inputs, output_type) = self.signature()?;
+        let body = if self.eat_symbol("=") {
+            ValueBody::Expression(self.expression()?)
+        } else if self.eat_keyword("native") {
+            ValueBody::Native(self.native_info()?)
+        } else if self.eat_keyword("external") {
+            ValueBody::External {
+                external_name: self.string()?,
+                target_platform: self.string()?,
+            }
+        } else if self.eat_keyword("incomplete") {
+            ValueBody::Incomplete(self.hole_reason()?)
+        } else {
+            return Err(self.unexpected("'=', 'native', 'external', or 'incomplete'"));
+        };
+        Ok(ValueDefinition {
+            input_types: inputs
+                .into_iter()
+                .map(|(name, input_type)| {
+                    let entry = InputTypeEntry {
+                        type_attributes: None,
+                        input_type,
+                    };
+                    (name, entry)
+                })
+                .collect(),
+            output_type,
+            body,
+        })
+    }
+
+    fn hole_reason(&mut self) -> Result<HoleReason> {
+        if self.eat_keyword("draft") {
+            Ok(HoleReason::Draft)
+        } else if self.eat_keyword("unresolved") {
+            Ok(HoleReason::UnresolvedReference {
+                target: self.qualified()?.0,
+            })
+        } else if self.eat_keyword("deleted") {
+            Ok(HoleReason::DeletedDuringRefactor {
+                tx_id: self.string()?,
+            })
+        } else if self.eat_keyword("mismatch") {
+            Ok(HoleReason::TypeMismatch {
+                expected: self.string()?,
+                found: self.string()?,
+            })
+        } else {
+            Err(self.unexpected("'draft', 'unresolved', 'deleted', or 'mismatch'"))
+        }
+    }
+
+    fn native_info(&mut self) -> Result<NativeInfo> {
+        let hint = match self.peek() {
+            Token::Lower(word) if word == "arithmetic" => NativeHint::Arithmetic,
+            Token::Lower(word) if word == "comparison" => NativeHint::Comparison,
+            Token::Lower(word) if word == "string" => NativeHint::StringOp,
+            Token::Lower(word) if word == "collection" => NativeHint::CollectionOp,
+            Token::Lower(word) if word == "platform" => {
+                self.advance();
+                NativeHint::PlatformSpecific {
+                    platform: self.string()?,
+                }
+            }
+            _ => {
+                return Err(self.unexpected(
+                    "'arithmetic', 'comparison', 'string', 'collection', or 'platform'",
+                ));
+            }
+        };
+        if !matches!(hint, NativeHint::PlatformSpecific { .. }) {
+            self.advance();
+        }
+        let description = match self.peek() {
+            Token::Str(_) if !self.ended() => Some(self.string()?),
+            _ => None,
+        };
+        Ok(NativeInfo { hint, description })
+    }
+
+    // Types
+
+    pub(super) fn type_expression(&mut self) -> Result<Type> {
+        let input = self.type_application()?;
+        if !self.ended() && self.eat_symbol("->") {
+            let output = self.type_expression()?;
+            return Ok(Type::Function(
+                TypeAttributes::default(),
+                Box::new(input),
+                Box::new(output),
+            ));
+        }
+        Ok(input)
+    }
+
+    fn type_application(&mut self) -> Result<Type> {
+        if !matches!(self.peek(), Token::Qualified(_)) {
+            return self.type_atom();
+        }
+        let (fqname, _) = self.qualified()?;
+        let mut args = Vec::new();
+        while !self.ended() && self.is_type_atom_start() {
+            args.push(self.type_atom()?);
+        }
+        Ok(Type::Reference(TypeAttributes::default(), fqname, args))
+    }
+
+    fn is_type_atom_start(&self) -> bool {
+        self.is_name()
+            || matches!(self.peek(), Token::Qualified(_))
+            || self.is_symbol("(")
+            || self.is_symbol("{")
+    }
+
+    fn type_atom(&mut self) -> Result<Type> {
+        let attrs = TypeAttributes::default();
+        if self.is_name() {
+            return Ok(Type::Variable(attrs, self.name()?));
+        }
+        if matches!(self.peek(), Token::Qualified(_)) {
+            let (fqname, _) = self.qualified()?;
+            return Ok(Type::Reference(attrs, fqname, Vec::new()));
+        }
+        if self.eat_symbol("(") {
+            return self.with_limit(0, |p| {
+                if p.eat_symbol(")") {
+                    return Ok(Type::Unit(attrs));
+                }
+                let first = p.type_expression()?;
+                if !p.is_symbol(",") {
+                    p.expect_symbol(")")?;
+                    return Ok(first);
+                }
+                let mut elements = vec![first];
+                while p.eat_symbol(",") {
+                    elements.push(p.type_expression()?);
+                }
+                p.expect_symbol(")")?;
+                Ok(Type::Tuple(attrs, elements))
+            });
+        }
+        if self.eat_symbol("{") {
+            return self.with_limit(0, |p| {
+                if p.eat_symbol("}") {
+                    return Ok(Type::Record(attrs, Vec::new()));
+                }
+                if p.is_name() && *p.peek_at(1) == Token::Symbol("|") {
+                    let variable = p.name()?;
+                    p.advance();
+                    let fields = p.field_types()?;
+                    return Ok(Type::ExtensibleRecord(attrs, variable, fields));
+                }
+                Ok(Type::Record(attrs, p.field_types()?))
+            });
+        }
+        Err(self.unexpected("a type"))
+    }
+
+    /// `a : T, b : U }`
+    fn field_types(&mut self) -> Result<Vec<Field>> {
+        let mut fields = Vec::new();
+        loop {
+            let name = self.name()?;
+            self.expect_symbol(":")?;
+            fields.push(Field::new(name, self.type_expression()?));
+            if !self.eat_symbol(",") {
+                self.expect_symbol("}")?;
+                return Ok(fields);
+            }
+        }
+    }
+
+    // Patterns
+
+    fn pattern(&mut self) -> Result<Pattern> {
+        let mut pattern = self.cons_pattern()?;
+        while !self.ended() && self.eat_keyword("as") {
+            let name = self.name()?;
+            pattern = Pattern::AsPattern(ValueAttributes::default(), Box::new(pattern), name);
+        }
+        Ok(pattern)
+    }
+
+    fn cons_pattern(&mut self) -> Result<Pattern> {
+        let head = self.constructor_pattern()?;
+        if !self.ended() && self.eat_symbol("::") {
+            let tail = self.cons_pattern()?;
+            return Ok(Pattern::HeadTailPattern(
+                ValueAttributes::default(),
+                Box::new(head),
+                Box::new(tail),
+            ));
+        }
+        Ok(head)
+    }
+
+    fn constructor_pattern(&mut self) -> Result<Pattern> {
+        if !matches!(self.peek(), Token::Qualified(_)) {
+            return self.atomic_pattern();
+        }
+        let (fqname, _) = self.qualified()?;
+        let mut args = Vec::new();
+        while !self.ended() && self.is_pattern_atom_start() {
+            args.push(self.atomic_pattern()?);
+        }
+        Ok(Pattern::ConstructorPattern(
+            ValueAttributes::default(),
+            fqname,
+            args,
+        ))
+    }
+
+    fn is_pattern_atom_start(&self) -> bool {
+        self.is_name()
+            || self.is_literal()
+            || matches!(self.peek(), Token::Qualified(_))
+            || ["_", "(", "["].iter().any(|symbol| self.is_symbol(symbol))
+    }
+
+    fn atomic_pattern(&mut self) -> Result<Pattern> {
+        let attrs = ValueAttributes::default();
+        if self.eat_symbol("_") {
+            return Ok(Pattern::WildcardPattern(attrs));
+        }
+        if self.is_name() {
+            let name = self.name()?;
+            return Ok(Pattern::AsPattern(
+                attrs.clone(),
+                Box::new(Pattern::WildcardPattern(attrs)),
+                name,
+            ));
+        }
+        if let Some(literal) = self.literal() {
+            return Ok(Pattern::LiteralPattern(attrs, literal));
+        }
+        if matches!(self.peek(), Token::Qualified(_)) {
+            let (fqname, _) = self.qualified()?;
+            return Ok(Pattern::ConstructorPattern(attrs, fqname, Vec::new()));
+        }
+        if self.eat_symbol("[") {
+            self.expect_symbol("]")?;
+            return Ok(Pattern::EmptyListPattern(attrs));
+        }
+        if self.eat_symbol("(") {
+            return self.with_limit(0, |p| {
+                if p.eat_symbol(")") {
+                    return Ok(Pattern::UnitPattern(attrs));
+                }
+                let first = p.pattern()?;
+                if !p.is_symbol(",") {
+                    p.expect_symbol(")")?;
+                    return Ok(first);
+                }
+                let mut elements = vec![first];
+                while p.eat_symbol(",") {
+                    elements.push(p.pattern()?);
+                }
+                p.expect_symbol(")")?;
+                Ok(Pattern::TuplePattern(attrs, elements))
+            });
+        }
+        Err(self.unexpected("a pattern"))
+    }
+
+    // Values
+
+    fn is_literal(&self) -> bool {
+        match self.peek() {
+            Token::Int(_)
+            | Token::Float(_)
+            | Token::Decimal(_)
+            | Token::Str(_)
+            | Token::Char(_) => true,
+            Token::Upper(word) => word == "True" || word == "False",
+            _ => false,
+        }
+    }
+
+    fn literal(&mut self) -> Option<Literal> {
+        if !self.is_literal() {
+            return None;
+        }
+        Some(match self.advance() {
+            Token::Int(n) => Literal::Integer(n),
+            Token::Float(n) => Literal::Float(n),
+            Token::Decimal(n) => Literal::Decimal(n),
+            Token::Str(text) => Literal::String(text),
+            Token::Char(c) => Literal::Char(c),
+            Token::Upper(word) => Literal::Bool(word == "True"),
+            _ => unreachable!("checked by is_literal"),
+        })
+    }
+
+    pub(super) fn expression(&mut self) -> Result<Value> {
+        let attrs = ValueAttributes::default();
+        if self.eat_keyword("if") {
+            let condition = self.expression()?;
+            self.expect_keyword("then")?;
+            let then_branch = self.expression()?;
+            self.expect_keyword("else")?;
+            let else_branch = self.expression()?;
+            return Ok(Value::IfThenElse(
+                attrs,
+                Box::new(condition),
+                Box::new(then_branch),
+                Box::new(else_branch),
+            ));
+        }
+        if self.eat_keyword("case") {
+            return self.case();
+        }
+        if self.eat_keyword("let") {
+            return self.let_expression();
+        }
+        if self.eat_symbol("\\") {
+            let pattern = self.atomic_pattern()?;
+            self.expect_symbol("->")?;
+            let body = self.expression()?;
+            return Ok(Value::Lambda(attrs, pattern, Box::new(body)));
+        }
+        let mut value = self.atom()?;
+        while !self.ended() && self.is_atom_start() {
+            let arg = self.atom()?;
+            value = Value::Apply(ValueAttributes::default(), Box::new(value), Box::new(arg));
+        }
+        Ok(value)
+    }
+
+    /// Column of the first line of a block: the branches of a `case` or the
+    /// bindings of a `let`
+    fn block_column(&self, what: &str) -> Result<usize> {
+        let spanned = self.spanned();
+        if spanned.token == Token::Eof || spanned.column <= self.limit {
+            return Err(self.unexpected(what));
+        }
+        Ok(spanned.column)
+    }
+
+    /// Whether the next token starts another line of the block at `column`
+    fn continues_block(&self, column: usize) -> bool {
+        let spanned = self.spanned();
+        spanned.token != Token::Eof && spanned.first_on_line && spanned.column == column
+    }
+
+    fn case(&mut self) -> Result<Value> {
+        let subject = self.expression()?;
+        self.expect_keyword("of")?;
+        let column = self.block_column("a case")?;
+        let mut cases = Vec::new();
+        loop {
+            cases.push(self.with_limit(column, |p| {
+                let pattern = p.pattern()?;
+                p.expect_symbol("->")?;
+                Ok(PatternCase(pattern, p.expression()?))
+            })?);
+            if !self.continues_block(column) {
+                break;
+            }
+        }
+        Ok(Value::PatternMatch(
+            ValueAttributes::default(),
+            Box::new(subject),
+            cases,
+        ))
+    }
+
+    fn let_expression(&mut self) -> Result<Value> {
+        let recursive = self.eat_keyword("rec");
+        let column = self.block_column("a binding")?;
+        let mut bindings = Vec::new();
+        loop {
+            bindings.push(self.with_limit(column, |p| p.binding())?);
+            if !self.continues_block(column) {
+                break;
+            }
+        }
+        self.expect_keyword("in")?;
+        let body = self.expression()?;
+        let attrs = ValueAttributes::default();
+
+        if recursive {
+            let bindings = bindings
+                .into_iter()
+                .map(|binding| match binding {
+                    Binding::Definition(name, def) => Ok(LetBinding(name, def)),
+                    Binding::Destructure(..) => {
+                        Err(self.error("The bindings of 'let rec' define values"))
+                    }
+                })
+                .collect::<Result<_>>()?;
+            return Ok(Value::LetRecursion(attrs, bindings, Box::new(body)));
+        }
+        Ok(bindings
+            .into_iter()
+            .rev()
+            .fold(body, |body, binding| match binding {
+                Binding::Definition(name, def) => {
+                    Value::LetDefinition(attrs.clone(), name, Box::new(def), Box::new(body))
+                }
+                Binding::Destructure(pattern, value) => {
+                    Value::Destructure(attrs.clone(), pattern, Box::new(value), Box::new(body))
+                }
+            }))
+    }
+
+    fn binding(&mut self) -> Result<Binding> {
+        if self.is_name() && matches!(self.peek_at(1), Token::Symbol("(" | ":")) {
+            let name = self.name()?;
+            let def = self.value_definition()?;
+            return Ok(Binding::Definition(name, def));
+        }
+        let pattern = self.pattern()?;
+        self.expect_symbol("=")?;
+        Ok(Binding::Destructure(pattern, self.expression()?))
+    }
+
+    fn is_atom_start(&self) -> bool {
+        self.is_name()
+            || self.is_literal()
+            || matches!(self.peek(), Token::Qualified(_))
+            || ["(", "[", "{", "?"]
+                .iter()
+                .any(|symbol| self.is_symbol(symbol))
+            || (self.is_symbol(".") && self.spanned().spaced)
+    }
+
+    /// A value and the fields accessed on it
+    fn atom(&mut self) -> Result<Value> {
+        let mut value = self.primary()?;
+        while self.is_symbol(".") && !self.spanned().spaced {
+            self.advance();
+            let field = self.name()?;
+            value = Value::Field(ValueAttributes::default(), Box::new(value), field);
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> Result<Value> {
+        let attrs = ValueAttributes::default();
+        if self.is_name() {
+            return Ok(Value::Variable(attrs, self.name()?));
+        }
+        if let Some(literal) = self.literal() {
+            return Ok(Value::Literal(attrs, literal));
+        }
+        if matches!(self.peek(), Token::Qualified(_)) {
+            let (fqname, upper) = self.qualified()?;
+            return Ok(if upper {
+                Value::Constructor(attrs, fqname)
+            } else {
+                Value::Reference(attrs, fqname)
+            });
+        }
+        if self.eat_keyword("native") {
+            let (fqname, _) = self.qualified()?;
+            return Ok(Value::Native(attrs, fqname, self.native_info()?));
+        }
+        if self.eat_keyword("external") {
+            let name = self.string()?;
+            return Ok(Value::External(attrs, name, self.string()?));
+        }
+        if self.eat_symbol("?") {
+            return Ok(Value::Hole(attrs, self.hole_reason()?, None));
+        }
+        if self.eat_symbol(".") {
+            return Ok(Value::FieldFunction(attrs, self.name()?));
+        }
+        if self.eat_symbol("(") {
+            return self.with_limit(0, |p| {
+                if p.eat_symbol(")") {
+                    return Ok(Value::Unit(attrs));
+                }
+                let first = p.expression()?;
+                if p.eat_symbol(":") {
+                    let Value::Hole(attrs, reason, None) = first else {
+                        return Err(p.error("Only holes are annotated with a type"));
+                    };
+                    let tpe = p.type_expression()?;
+                    p.expect_symbol(")")?;
+                    return Ok(Value::Hole(attrs, reason, Some(Box::new(tpe))));
+                }
+                if !p.is_symbol(",") {
+                    p.expect_symbol(")")?;
+                    return Ok(first);
+                }
+                let mut elements = vec![first];
+                while p.eat_symbol(",") {
+                    elements.push(p.expression()?);
+                }
+                p.expect_symbol(")")?;
+                Ok(Value::Tuple(attrs, elements))
+            });
+        }
+        if self.eat_symbol("[") {
+            return self.with_limit(0, |p| {
+                let mut elements = Vec::new();
+                if p.eat_symbol("]") {
+                    return Ok(Value::List(attrs, elements));
+                }
+                loop {
+                    elements.push(p.expression()?);
+                    if !p.eat_symbol(",") {
+                        p.expect_symbol("]")?;
+                        return Ok(Value::List(attrs, elements));
+                    }
+                }
+            });
+        }
+        if self.eat_symbol("{") {
+            return self.with_limit(0, |p| {
+                if p.eat_symbol("}") {
+                    return Ok(Value::Record(attrs, Vec::new()));
+                }
+                if p.is_name() && *p.peek_at(1) == Token::Symbol("=") {
+                    return Ok(Value::Record(attrs, p.field_values()?));
+                }
+                let record = p.atom()?;
+                p.expect_symbol("|")?;
+                let fields = p.field_values()?;
+                Ok(Value::UpdateRecord(attrs, Box::new(record), fields))
+            });
+        }
+        Err(self.unexpected("a value"))
+    }
+
+    /// `a = x, b = y }`
+    fn field_values(&mut self) -> Result<Vec<RecordFieldEntry>> {
+        let mut fields = Vec::new();
+        loop {
+            let name = self.name()?;
+            self.expect_symbol("=")?;
+            fields.push(RecordFieldEntry(name, self.expression()?));
+            if !self.eat_symbol(",") {
+                self.expect_symbol("}")?;
+                return Ok(fields);
+            }
+        }
+    }
+}
+
+/// A binding of a `let`
+enum Binding {
+    Definition(Name, ValueDefinition),
+    Destructure(Pattern, Value),
+}
@@ -0,0 +1,914 @@
+//! Canonical layout of the `.mir` syntax
+
+use super::lexer::{RESERVED, doc_comment};
+use crate::ir::v4::{
+    Access, AccessControlled, ConstructorArg, ConstructorArgSpec, Distribution, EntryPoint,
+    EntryPointKind, Field, HoleReason, Incompleteness, Literal, ModuleDefinition,
+    ModuleSpecification, NativeHint, NativeInfo, PackageSpecification, Pattern, Type,
+    TypeDefinition, TypeSpecification, Value, ValueBody, ValueDefinition, ValueSpecification,
+};
+use crate::naming::{FQName, Name, Path};
+
+/// Width past which expressions are broken over several lines
+const WIDTH: usize = 80;
+
+const INDENT: usize = 4;
+
+pub(super) fn distribution(distribution: &Distribution) -> String {
+    let (keyword, package_name) = match distribution {
+        Distribution::Library(lib) => ("library", &lib.package_name),
+        Distribution::Specs(specs) => ("specs", &specs.package_name),
+        Distribution::Application(app) => ("application", &app.package_name),
+    };
+    let mut sections = vec![format!("{} {}", keyword, path(package_name.as_path()))];
+
+    if let Some(entry_points) = distribution.entry_points() {
+        let entries: Vec<String> = entry_points
+            .iter()
+            .map(|(name, entry)| entry_point(name, entry))
+            .collect();
+        if !entries.is_empty() {
+            sections.push(entries.join("\n\n"));
+        }
+    }
+    match distribution {
+        Distribution::Library(lib) => sections.extend(definition_modules(&lib.def.modules)),
+        Distribution::Application(app) => sections.extend(definition_modules(&app.def.modules)),
+        Distribution::Specs(specs) => sections.extend(specification_modules(&specs.spec)),
+    }
+    for (name, spec) in distribution.dependencies() {
+        sections.push(format!("dependency {}", path_key(name)));
+        sections.extend(specification_modules(spec));
+    }
+    sections.join("\n\n\n") + "\n"
+}
+
+fn definition_modules(
+    modules: &indexmap::IndexMap<String, AccessControlled<ModuleDefinition>>,
+) -> impl Iterator<Item = String> + '_ {
+    modules
+        .iter()
+        .map(|(name, module)| module_definition(name, module))
+}
+
+fn specification_modules(spec: &PackageSpecification) -> impl Iterator<Item = String> + '_ {
+    spec.modules
+        .iter()
+        .map(|(name, module)| module_specification(name, module))
+}
+
+fn entry_point(name: &str, entry: &EntryPoint) -> String {
+    let kind = match entry.kind {
+        EntryPointKind::Main => "main",
+        EntryPointKind::Command => "command",
+        EntryPointKind::Handler => "handler",
+    };
+    let target = match FQName::from_canonical_string(&entry.target) {
+        Ok(fqname) if fqname.to_canonical_string() == entry.target => fqname_lower(&fqname),
+        _ => string_literal(&entry.target),
+    };
+    with_doc(
+        entry.doc.as_deref(),
+        format!("entry {} {} {}", key(name), kind, target),
+    )
+}
+
+pub(super) fn module_definition(name: &str, module: &AccessControlled<ModuleDefinition>) -> String {
+    let mut header = format!("{}module {}", private(&module.access), path_key(name));
+    if let Some(doc) = &module.value.doc {
+        header.push_str(&format!("\n{}{}", pad(INDENT), doc_comment(doc)));
+    }
+    let mut parts = vec![with_doc(module.doc.as_deref(), header)];
+    parts.extend(
+        module
+            .value
+            .types
+            .iter()
+            .map(|(name, def)| type_definition(name, def)),
+    );
+    parts.extend(
+        module
+            .value
+            .values
+            .iter()
+            .map(|(name, def)| value_definition(name, def)),
+    );
+    parts.join("\n\n")
+}
+
+pub(super) fn module_specification(name: &str, module: &ModuleSpecification) -> String {
+    let mut header = format!("module {}", path_key(name));
+    if let Some(doc) = &module.doc {
+        header.push_str(&format!("\n{}{}", pad(INDENT), doc_comment(doc)));
+    }
+    let mut parts = vec![header];
+    parts.extend(
+        module
+            .types
+            .iter()
+            .map(|(name, spec)| type_specification(name, spec)),
+    );
+    parts.extend(
+        module
+            .values
+            .iter()
+            .map(|(name, spec)| value_specification(name, spec)),
+    );
+    parts.join("\n\n")
+}
+
+pub(super) fn type_definition(name: &str, def: &AccessControlled<TypeDefinition>) -> String {
+    let access = private(&def.access);
+    let text = match &def.value {
+        TypeDefinition::TypeAliasDefinition {
+            type_params,
+            type_expr,
+        } => type_alias(access, name, type_params, type_expr),
+        TypeDefinition::CustomTypeDefinition {
+            type_params,
+            constructors,
+        } => {
+            let opaque = if constructors.access == Access::Private {
+                "opaque "
+            } else {
+                ""
+            };
+            let mut text = format!(
+                "{}{}type {}",
+                access,
+                opaque,
+                type_header(name, type_params)
+            );
+            let constructors: Vec<String> = constructors
+                .value
+                .iter()
+                .map(|constructor| {
+                    constructor_text(
+                        &constructor.name,
+                        constructor
+                            .args
+                            .iter()
+                            .map(|ConstructorArg { name, arg_type }| (name, arg_type)),
+                    )
+                })
+                .collect();
+            text.push_str(&constructor_block(&constructors));
+            text
+        }
+        TypeDefinition::IncompleteTypeDefinition {
+            type_params,
+            incompleteness,
+        } => {
+            let reason = match incompleteness {
+                Incompleteness::Draft => "draft".to_string(),
+                Incompleteness::Hole(reason) => format!("?{}", hole_reason(reason)),
+            };
+            format!(
+                "{}incomplete type {} = {}",
+                access,
+                type_header(name, type_params),
+                reason
+            )
+        }
+    };
+    with_doc(def.doc.as_deref(), text)
+}
+
+pub(super) fn type_specification(name: &str, spec: &TypeSpecification) -> String {
+    match spec {
+        TypeSpecification::TypeAliasSpecification {
+            type_params,
+            type_expr,
+        } => type_alias("", name, type_params, type_expr),
+        TypeSpecification::OpaqueTypeSpecification { type_params } => {
+            format!("opaque type {}", type_header(name, type_params))
+        }
+        TypeSpecification::CustomTypeSpecification {
+            type_params,
+            constructors,
+        } => {
+            let constructors: Vec<String> = constructors
+                .iter()
+                .map(|constructor| {
+                    constructor_text(
+                        &constructor.name,
+                        constructor
+                            .args
+                            .iter()
+                            .map(|ConstructorArgSpec { name, arg_type }| (name, arg_type)),
+                    )
+                })
+                .collect();
+            format!(
+                "type {}{}",
+                type_header(name, type_params),
+                constructor_block(&constructors)
+            )
+        }
+    }
+}
+
+fn type_alias(access: &str, name: &str, params: &[Name], tpe: &Type) -> String {
+    let body = match tpe {
+        Type::Record(_, fields) if fields.len() > 1 => {
+            let lines: Vec<String> = fields.iter().map(field_type).collect();
+            format!(
+                "{{ {}\n{}}}",
+                lines.join(&format!("\n{}, ", pad(INDENT))),
+                pad(INDENT)
+            )
+        }
+        _ => type_text(tpe),
+    };
+    format!(
+        "{}type alias {} =\n{}{}",
+        access,
+        type_header(name, params),
+        pad(INDENT),
+        body
+    )
+}
+
+fn type_header(name: &str, params: &[Name]) -> String {
+    let mut header = type_key(name);
+    for param in params {
+        header.push(' ');
+        header.push_str(&name_text(param));
+    }
+    header
+}
+
+fn constructor_text<'a>(name: &Name, args: impl Iterator<Item = (&'a Name, &'a Type)>) -> String {
+    let mut text = title(name);
+    for (name, tpe) in args {
+        text.push_str(&format!(" ({} : {})", name_text(name), type_text(tpe)));
+    }
+    text
+}
+
+fn constructor_block(constructors: &[String]) -> String {
+    constructors
+        .iter()
+        .enumerate()
+        .map(|(i, constructor)| {
+            format!(
+                "\n{}{} {}",
+                pad(INDENT),
+                if i == 0 { "=" } else { "|" },
+                constructor
+            )
+        })
+        .collect()
+}
+
+pub(super) fn value_definition(name: &str, def: &AccessControlled<ValueDefinition>) -> String {
+    let text = format!(
+        "{}{}",
+        private(&def.access),
+        definition(&key(name), &def.value, 0)
+    );
+    with_doc(def.doc.as_deref(), text)
+}
+
+pub(super) fn value_specification(name: &str, spec: &ValueSpecification) -> String {
+    let mut text = key(name);
+    for (input, tpe) in &spec.inputs {
+        text.push_str(&format!(" ({} : {})", key(input), type_text(tpe)));
+    }
+    text.push_str(&format!(" : {}", type_text(&spec.output)));
+    text
+}
+
+/// A value definition named `name`, top-level or bound by a `let`, starting
+/// on a line indented by `indent`
+fn definition(name: &str, def: &ValueDefinition, indent: usize) -> String {
+    let mut text = name.to_string();
+    for (input, entry) in &def.input_types {
+        text.push_str(&format!(
+            " ({} : {})",
+            key(input),
+            type_text(&entry.input_type)
+        ));
+    }
+    text.push_str(&format!(" : {}", type_text(&def.output_type)));
+    match &def.body {
+        ValueBody::Expression(body) => {
+            text.push_str(&format!(
+                " =\n{}{}",
+                pad(indent + INDENT),
+                expression(body, indent + INDENT)
+            ));
+        }
+        ValueBody::Native(info) => text.push_str(&format!(" native {}", native(info))),
+        ValueBody::External {
+            external_name,
+            target_platform,
+        } => text.push_str(&format!(
+            " external {} {}",
+            string_literal(external_name),
+            string_literal(target_platform)
+        )),
+        ValueBody::Incomplete(reason) => {
+            text.push_str(&format!(" incomplete {}", hole_reason(reason)))
+        }
+    }
+    text
+}
+
+fn with_doc(doc: Option<&str>, text: String) -> String {
+    match doc {
+        Some(doc) => format!("{}\n{}", doc_comment(doc), text),
+        None => text,
+    }
+}
+
+fn private(access: &Access) -> &'static str {
+    match access {
+        Access::Public => "",
+        Access::Private => "private ",
+    }
+}
+
+fn pad(indent: usize) -> String {
+    " ".repeat(indent)
+}
+
+// Names
+
+/// Whether `text` is written as a plain word: kebab-case, starting in
+/// lowercase, and not a keyword
+fn is_plain(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_lowercase())
+        && Name::from(text).to_string() == text
+        && !RESERVED.contains(&text)
+}
+
+/// A map key, backquoted unless it is written plainly
+fn key(text: &str) -> String {
+    if is_plain(text) {
+        text.to_string()
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+/// A module or package key, `/`-separated
+fn path_key(text: &str) -> String {
+    if !text.is_empty() && text.split('/').all(is_plain) {
+        text.to_string()
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+/// The key of a type, capitalised when that reads back as the same key
+fn type_key(text: &str) -> String {
+    if is_plain(text) {
+        title(&Name::from(text))
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+pub(super) fn name_text(name: &Name) -> String {
+    key(&name.to_string())
+}
+
+fn path(path: &Path) -> String {
+    path_key(&path.to_string())
+}
+
+/// `name` capitalised, as `LineItem`, or as `X-2` when joining the words
+/// would lose where they split
+pub(super) fn title(name: &Name) -> String {
+    let title = name.to_title_case();
+    if super::lowercase(&Name::from(&title)) == super::lowercase(name) {
+        title
+    } else {
+        let kebab = name.to_string();
+        let mut chars = kebab.chars();
+        chars
+            .next()
+            .map(|c| c.to_uppercase().collect::<String>() + chars.as_str())
+            .unwrap_or_default()
+    }
+}
+
+/// A type or constructor, `morphir/sdk:maybe#Maybe`
+fn fqname_title(fqname: &FQName) -> String {
+    format!(
+        "{}:{}#{}",
+        fqname.package_path,
+        fqname.module_path,
+        title(&fqname.local_name)
+    )
+}
+
+/// A value, `morphir/sdk:basics#add`
+fn fqname_lower(fqname: &FQName) -> String {
+    fqname.to_canonical_string()
+}
+
+// Types
+
+pub(super) fn type_text(tpe: &Type) -> String {
+    type_at(tpe, TypePosition::Top)
+}
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum TypePosition {
+    Top,
+    FunctionInput,
+    Argument,
+}
+
+fn type_at(tpe: &Type, position: TypePosition) -> String {
+    match tpe {
+        Type::Variable(_, name) => name_text(name),
+        Type::Reference(_, fqname, args) if args.is_empty() => fqname_title(fqname),
+        Type::Reference(_, fqname, args) => {
+            let mut text = fqname_title(fqname);
+            for arg in args {
+                text.push(' ');
+                text.push_str(&type_at(arg, TypePosition::Argument));
+            }
+            parenthesised_if(position == TypePosition::Argument, text)
+        }
+        Type::Tuple(_, elements) => {
+            let elements: Vec<String> = elements.iter().map(type_text).collect();
+            format!("( {} )", elements.join(", "))
+        }
+        Type::Record(_, fields) if fields.is_empty() => "{}".to_string(),
+        Type::Record(_, fields) => {
+            let fields: Vec<String> = fields.iter().map(field_type).collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        Type::ExtensibleRecord(_, variable, fields) => {
+            let fields: Vec<String> = fields.iter().map(field_type).collect();
+            format!("{{ {} | {} }}", name_text(variable), fields.join(", "))
+        }
+        Type::Function(_, input, output) => parenthesised_if(
+            position >= TypePosition::FunctionInput,
+            format!(
+                "{} -> {}",
+                type_at(input, TypePosition::FunctionInput),
+                type_text(output)
+            ),
+        ),
+        Type::Unit(_) => "()".to_string(),
+    }
+}
+
+fn field_type(field: &Field) -> String {
+    format!("{} : {}", name_text(&field.name), type_text(&field.tpe))
+}
+
+fn parenthesised_if(condition: bool, text: String) -> String {
+    if condition {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+// Values
+
+fn literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Bool(true) => "True".to_string(),
+        Literal::Bool(false) => "False".to_string(),
+        Literal::Char(c) => format!("'{}'", escape(&c.to_string(), '\'')),
+        Literal::String(text) => string_literal(text),
+        Literal::Integer(n) => n.to_string(),
+        Literal::Float(n) => format!("{:?}", n),
+        Literal::Decimal(n) => format!("{}d", n),
+    }
+}
+
+fn string_literal(text: &str) -> String {
+    format!("\"{}\"", escape(text, '"'))
+}
+
+fn escape(text: &str, quote: char) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\0' => escaped.push_str("\\0"),
+            '\\' => escaped.push_str("\\\\"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn hole_reason(reason: &HoleReason) -> String {
+    match reason {
+        HoleReason::Draft => "draft".to_string(),
+        HoleReason::UnresolvedReference { target } => {
+            format!("unresolved {}", fqname_lower(target))
+        }
+        HoleReason::DeletedDuringRefactor { tx_id } => {
+            format!("deleted {}", string_literal(tx_id))
+        }
+        HoleReason::TypeMismatch { expected, found } => format!(
+            "mismatch {} {}",
+            string_literal(expected),
+            string_literal(found)
+        ),
+    }
+}
+
+/// Hint and description of a native value
+fn native(info: &NativeInfo) -> String {
+    let hint = match &info.hint {
+        NativeHint::Arithmetic => "arithmetic".to_string(),
+        NativeHint::Comparison => "comparison".to_string(),
+        NativeHint::StringOp => "string".to_string(),
+        NativeHint::CollectionOp => "collection".to_string(),
+        NativeHint::PlatformSpecific { platform } => {
+            format!("platform {}", string_literal(platform))
+        }
+    };
+    match &info.description {
+        Some(description) => format!("{} {}", hint, string_literal(description)),
+        None => hint,
+    }
+}
+
+/// Whether `value` reads as one argument without parentheses
+fn is_atomic(value: &Value) -> bool {
+    !matches!(
+        value,
+        Value::Apply(..)
+            | Value::Lambda(..)
+            | Value::LetDefinition(..)
+            | Value::LetRecursion(..)
+            | Value::Destructure(..)
+            | Value::IfThenElse(..)
+            | Value::PatternMatch(..)
+            | Value::Native(..)
+            | Value::External(..)
+    )
+}
+
+/// `value` as an argument, starting on a line indented by `indent`
+fn atom(value: &Value, indent: usize) -> String {
+    let text = expression(value, indent);
+    parenthesised_atom(value, text, indent)
+}
+
+fn parenthesised_atom(value: &Value, text: String, indent: usize) -> String {
+    if is_atomic(value) {
+        text
+    } else if text.contains('\n') {
+        format!("({}\n{})", text, pad(indent))
+    } else {
+        format!("({})", text)
+    }
+}
+
+/// `value`, starting on a line indented by `indent`: on that line when it
+/// fits, and broken over several otherwise
+pub(super) fn expression(value: &Value, indent: usize) -> String {
+    flat(value, WIDTH.saturating_sub(indent)).unwrap_or_else(|| broken(value, indent))
+}
+
+/// Text of values that are never broken
+fn leaf(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::Literal(_, lit) => literal(lit),
+        Value::Constructor(_, fqname) => fqname_title(fqname),
+        Value::List(_, elements) if elements.is_empty() => "[]".to_string(),
+        Value::Record(_, fields) if fields.is_empty() => "{}".to_string(),
+        Value::Variable(_, name) => name_text(name),
+        Value::Reference(_, fqname) => fqname_lower(fqname),
+        Value::FieldFunction(_, field) => format!(".{}", name_text(field)),
+        Value::Unit(_) => "()".to_string(),
+        Value::Hole(_, reason, None) => format!("?{}", hole_reason(reason)),
+        Value::Hole(_, reason, Some(tpe)) => {
+            format!("(?{} : {})", hole_reason(reason), type_text(tpe))
+        }
+        Value::Native(_, fqname, info) => {
+            format!("native {} {}", fqname_lower(fqname), native(info))
+        }
+        Value::External(_, name, platform) => format!(
+            "external {} {}",
+            string_literal(name),
+            string_literal(platform)
+        ),
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// `value` on one line, if it can be written on one within `budget`
+/// characters. Stops as soon as the budget runs out, so that laying out
+/// deeply nested values stays linear.
+fn flat(value: &Value, budget: usize) -> Option<String> {
+    let text = match value {
+        Value::Tuple(_, elements) if !elements.is_empty() => {
+            flat_sequence("(", ")", elements.iter().map(|e| (None, e)), budget)?
+        }
+        Value::List(_, elements) if !elements.is_empty() => {
+            flat_sequence("[", "]", elements.iter().map(|e| (None, e)), budget)?
+        }
+        Value::Record(_, fields) if !fields.is_empty() => flat_sequence(
+            "{",
+            "}",
+            fields.iter().map(|f| (Some(f.name()), f.value())),
+            budget,
+        )?,
+        Value::Field(_, record, field) => {
+            format!("{}.{}", flat_atom(record, budget)?, name_text(field))
+        }
+        Value::Apply(..) => {
+            let (function, args) = application(value);
+            let mut text = flat_atom(function, budget)?;
+            for arg in args {
+                let arg = flat_atom(arg, budget.checked_sub(text.len() + 1)?)?;
+                text.push(' ');
+                text.push_str(&arg);
+            }
+            text
+        }
+        Value::Lambda(_, pattern, body) => {
+            let head = format!("\\{} -> ", pattern_at(pattern, PatternPosition::Atom));
+            let body = flat(body, budget.checked_sub(head.len())?)?;
+            head + &body
+        }
+        Value::UpdateRecord(_, record, fields) => {
+            let record = flat_atom(record, budget)?;
+            let fields = flat_sequence(
+                "",
+                "",
+                fields.iter().map(|f| (Some(f.name()), f.value())),
+                budget.checked_sub(record.len() + 6)?,
+            )?;
+            format!("{{ {} |{}}}", record, fields)
+        }
+        Value::LetDefinition(..)
+        | Value::LetRecursion(..)
+        | Value::Destructure(..)
+        | Value::IfThenElse(..)
+        | Value::PatternMatch(..) => return None,
+        _ => leaf(value)?,
+    };
+    (text.len() <= budget).then_some(text)
+}
+
+fn flat_atom(value: &Value, budget: usize) -> Option<String> {
+    let text = flat(value, budget)?;
+    let text = parenthesised_atom(value, text, 0);
+    (text.len() <= budget).then_some(text)
+}
+
+/// `open a, b close`, or `open x = a, y = b close` for named elements
+fn flat_sequence<'a>(
+    open: &str,
+    close: &str,
+    elements: impl Iterator<Item = (Option<&'a Name>, &'a Value)>,
+    budget: usize,
+) -> Option<String> {
+    let mut text = open.to_string();
+    for (i, (name, value)) in elements.enumerate() {
+        text.push_str(if i == 0 { " " } else { ", " });
+        if let Some(name) = name {
+            text.push_str(&format!("{} = ", name_text(name)));
+        }
+        text.push_str(&flat(value, budget.checked_sub(text.len())?)?);
+    }
+    text.push(' ');
+    text.push_str(close);
+    (text.len() <= budget).then_some(text)
+}
+
+/// The function an application applies and its arguments, in order
+fn application(value: &Value) -> (&Value, Vec<&Value>) {
+    let mut args = Vec::new();
+    let mut function = value;
+    while let Value::Apply(_, f, arg) = function {
+        args.push(arg.as_ref());
+        function = f;
+    }
+    args.reverse();
+    (function, args)
+}
+
+/// `value` over several lines, for when it does not fit on one
+fn broken(value: &Value, indent: usize) -> String {
+    let inner = indent + INDENT;
+    match value {
+        Value::Tuple(_, elements) => sequence("(", ")", elements, indent),
+        Value::List(_, elements) if !elements.is_empty() => sequence("[", "]", elements, indent),
+        Value::Record(_, fields) if !fields.is_empty() => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| field_value(field.name(), field.value(), indent))
+                .collect();
+            format!(
+                "{{ {}\n{}}}",
+                fields.join(&format!("\n{}, ", pad(indent))),
+                pad(indent)
+            )
+        }
+        Value::Field(_, record, field) => {
+            format!("{}.{}", atom(record, indent), name_text(field))
+        }
+        Value::Apply(..) => {
+            let (function, args) = application(value);
+            let mut text = atom(function, indent);
+            for arg in args {
+                text.push_str(&format!("\n{}{}", pad(inner), atom(arg, inner)));
+            }
+            text
+        }
+        Value::Lambda(_, pattern, body) => format!(
+            "\\{} ->\n{}{}",
+            pattern_at(pattern, PatternPosition::Atom),
+            pad(inner),
+            expression(body, inner)
+        ),
+        Value::LetDefinition(_, name, def, body) => let_block(
+            "let",
+            &[definition(&name_text(name), def, inner)],
+            body,
+            indent,
+        ),
+        Value::LetRecursion(_, bindings, body) => {
+            let bindings: Vec<String> = bindings
+                .iter()
+                .map(|binding| definition(&name_text(binding.name()), binding.definition(), inner))
+                .collect();
+            let_block("let rec", &bindings, body, indent)
+        }
+        Value::Destructure(_, pattern, value, body) => {
+            let binding = format!(
+                "{} =\n{}{}",
+                pattern_text(pattern),
+                pad(inner + INDENT),
+                expression(value, inner + INDENT)
+            );
+            let_block("let", &[binding], body, indent)
+        }
+        Value::IfThenElse(_, condition, then_branch, else_branch) => {
+            let mut text = format!(
+                "if {} then\n{}{}\n{}else",
+                expression(condition, indent),
+                pad(inner),
+                expression(then_branch, inner),
+                pad(indent)
+            );
+            if matches!(else_branch.as_ref(), Value::IfThenElse(..)) {
+                text.push_str(&format!(" {}", expression(else_branch, indent)));
+            } else {
+                text.push_str(&format!(
+                    "\n{}{}",
+                    pad(inner),
+                    expression(else_branch, inner)
+                ));
+            }
+            text
+        }
+        Value::PatternMatch(_, subject, cases) => {
+            let mut text = format!("case {} of", expression(subject, indent));
+            for case in cases {
+                text.push_str(&format!(
+                    "\n{}{} ->\n{}{}",
+                    pad(inner),
+                    pattern_text(case.pattern()),
+                    pad(inner + INDENT),
+                    expression(case.body(), inner + INDENT)
+                ));
+            }
+            text
+        }
+        Value::UpdateRecord(_, record, fields) => {
+            let record = atom(record, indent);
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| field_value(field.name(), field.value(), inner))
+                .collect();
+            format!(
+                "{{ {}\n{}| {}\n{}}}",
+                record,
+                pad(inner),
+                fields.join(&format!("\n{}, ", pad(inner))),
+                pad(indent)
+            )
+        }
+        _ => leaf(value).unwrap_or_default(),
+    }
+}
+
+/// Elements of a tuple or list, one per line
+fn sequence(open: &str, close: &str, elements: &[Value], indent: usize) -> String {
+    let elements: Vec<String> = elements
+        .iter()
+        .map(|element| expression(element, indent + 2))
+        .collect();
+    format!(
+        "{} {}\n{}{}",
+        open,
+        elements.join(&format!("\n{}, ", pad(indent))),
+        pad(indent),
+        close
+    )
+}
+
+/// `name = value` in a record, the value on its own line when it does not
+/// fit on the name's
+fn field_value(name: &Name, value: &Value, indent: usize) -> String {
+    let name = name_text(name);
+    match flat(value, WIDTH.saturating_sub(indent + 2 + name.len() + 3)) {
+        Some(text) => format!("{} = {}", name, text),
+        None => format!(
+            "{} =\n{}{}",
+            name,
+            pad(indent + 2 + INDENT),
+            expression(value, indent + 2 + INDENT)
+        ),
+    }
+}
+
+fn let_block(keyword: &str, bindings: &[String], body: &Value, indent: usize) -> String {
+    let bindings: Vec<String> = bindings
+        .iter()
+        .map(|binding| format!("{}{}", pad(indent + INDENT), binding))
+        .collect();
+    format!(
+        "{}\n{}\n{}in\n{}{}",
+        keyword,
+        bindings.join("\n"),
+        pad(indent),
+        pad(indent),
+        expression(body, indent)
+    )
+}
+
+// Patterns
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum PatternPosition {
+    Top,
+    /// Left of `as`: a `::` pattern
+    Cons,
+    /// Head of `::`: a constructor and its arguments
+    Head,
+    /// An argument
+    Atom,
+}
+
+pub(super) fn pattern_text(pattern: &Pattern) -> String {
+    pattern_at(pattern, PatternPosition::Top)
+}
+
+fn pattern_at(pattern: &Pattern, position: PatternPosition) -> String {
+    match pattern {
+        Pattern::WildcardPattern(_) => "_".to_string(),
+        Pattern::AsPattern(_, inner, name)
+            if matches!(inner.as_ref(), Pattern::WildcardPattern(_)) =>
+        {
+            name_text(name)
+        }
+        Pattern::AsPattern(_, inner, name) => parenthesised_if(
+            position >= PatternPosition::Cons,
+            format!(
+                "{} as {}",
+                pattern_at(inner, PatternPosition::Top),
+                name_text(name)
+            ),
+        ),
+        Pattern::TuplePattern(_, elements) => {
+            let elements: Vec<String> = elements.iter().map(pattern_text).collect();
+            format!("( {} )", elements.join(", "))
+        }
+        Pattern::ConstructorPattern(_, fqname, args) if args.is_empty() => fqname_title(fqname),
+        Pattern::ConstructorPattern(_, fqname, args) => {
+            let mut text = fqname_title(fqname);
+            for arg in args {
+                text.push(' ');
+                text.push_str(&pattern_at(arg, PatternPosition::Atom));
+            }
+            parenthesised_if(position >= PatternPosition::Atom, text)
+        }
+        Pattern::EmptyListPattern(_) => "[]".to_string(),
+        Pattern::HeadTailPattern(_, head, tail) => parenthesised_if(
+            position >= PatternPosition::Head,
+            format!(
+                "{} :: {}",
+                pattern_at(head, PatternPosition::Head),
+                pattern_at(tail, PatternPosition::Cons)
+            ),
+        ),
+        Pattern::LiteralPattern(_, lit) => literal(lit),
+        Pattern::UnitPattern(_) => "()".to_string(),
+    }
+}
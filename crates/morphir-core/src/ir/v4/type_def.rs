@@ -1,8 +1,9 @@
 //! Type definition types for Morphir IR V4
 
-use serde::de::{self, Deserializer};
+use serde::de::{self, Deserializer, IgnoredAny, MapAccess, Visitor};
 use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 use super::access::Access;
 use super::types::{ConstructorSpecification, Type};
@@ -106,28 +107,49 @@ impl<'de> Deserialize<'de> for TypeDefinition {
     where
         D: Deserializer<'de>,
     {
-        let value = serde_json::Value::deserialize(deserializer)?;
-        if let serde_json::Value::Object(map) = &value {
-            if let Some(content) = map.get("TypeAliasDefinition") {
-                let parsed: TypeAliasDefContent =
-                    serde_json::from_value(content.clone()).map_err(de::Error::custom)?;
-                return Ok(TypeDefinition::TypeAliasDefinition {
-                    type_params: parsed.type_params,
-                    type_expr: parsed.type_exp,
-                });
-            }
-            if let Some(content) = map.get("CustomTypeDefinition") {
-                let parsed: CustomTypeDefContent =
-                    serde_json::from_value(content.clone()).map_err(de::Error::custom)?;
-                return Ok(TypeDefinition::CustomTypeDefinition {
-                    type_params: parsed.type_params,
-                    constructors: parsed.constructors,
-                });
+        deserializer.deserialize_map(TypeDefinitionVisitor)
+    }
+}
+
+struct TypeDefinitionVisitor;
+
+impl<'de> Visitor<'de> for TypeDefinitionVisitor {
+    type Value = TypeDefinition;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("TypeAliasDefinition or CustomTypeDefinition wrapper")
+    }
+
+    // The first wrapper key wins; any other keys are skipped
+    fn visit_map<M>(self, mut map: M) -> Result<TypeDefinition, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut definition = None;
+        while let Some(tag) = map.next_key::<String>()? {
+            match tag.as_str() {
+                "TypeAliasDefinition" if definition.is_none() => {
+                    let parsed: TypeAliasDefContent = map.next_value()?;
+                    definition = Some(TypeDefinition::TypeAliasDefinition {
+                        type_params: parsed.type_params,
+                        type_expr: parsed.type_exp,
+                    });
+                }
+                "CustomTypeDefinition" if definition.is_none() => {
+                    let parsed: CustomTypeDefContent = map.next_value()?;
+                    definition = Some(TypeDefinition::CustomTypeDefinition {
+                        type_params: parsed.type_params,
+                        constructors: parsed.constructors,
+                    });
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
             }
         }
-        Err(de::Error::custom(
-            "expected TypeAliasDefinition or CustomTypeDefinition wrapper",
-        ))
+        definition.ok_or_else(|| {
+            de::Error::custom("expected TypeAliasDefinition or CustomTypeDefinition wrapper")
+        })
     }
 }
 
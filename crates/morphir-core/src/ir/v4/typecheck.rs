@@ -0,0 +1,1577 @@
+//! Type checking for Morphir IR V4 values.
+//!
+//! Walks the bodies of value definitions, infers the type of every expression
+//! with Hindley–Milner style unification, and checks the result against the
+//! declared signature of the definition. Problems are reported as
+//! [`Diagnostic`]s carrying the source location of the offending expression.
+//!
+//! Dependencies are checked against their specifications alone; no bodies are
+//! needed. A reference to a dependency member missing from its specification
+//! is an error, since it could not be linked.
+//!
+//! The checker is deliberately lenient where the IR does not carry enough
+//! information to decide: references to packages that are neither part of the
+//! distribution nor one of its dependencies, native and external bodies, and
+//! holes are assumed to have whatever type their context requires.
+//!
+//! Type variables in a declared signature are rigid inside the body of the
+//! definition, so `identity : a -> a` cannot return an `Int`. Those of let
+//! definitions, whose types frontends record from their own inference, are
+//! not. Following Elm, variables named `number…` only unify with `Int`,
+//! `Float`, or `Decimal`.
+//!
+//! SDK names are compared whichever way the IR spells the package: IR
+//! converted from morphir-elm has `morphir/s-d-k` for `morphir/sdk`.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let diagnostics = typecheck_distribution(&ir_file.distribution);
+//! for d in diagnostics.iter().filter(|d| d.is_error()) {
+//!     eprintln!("{}: {}", d.definition, d.message);
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+use super::access::AccessControlled;
+use super::attributes::{SourceLocation, ValueAttributes};
+use super::distribution::{Dependencies, Distribution, EntryPoints};
+use super::literal::Literal;
+use super::package::PackageDefinition;
+use super::parallel::par_walk_modules;
+use super::pattern::Pattern;
+use super::types::{Field, Type, TypeDefinition, TypeSpecification};
+use super::value::{LetBinding, PatternCase, RecordFieldEntry, Value, ValueBody, ValueDefinition};
+use super::walk::{DistributionVisitor, VisitContext, Walk};
+use crate::naming::{FQName, Name, Path};
+
+const BOOL: &str = "morphir/sdk:basics#bool";
+const INT: &str = "morphir/sdk:basics#int";
+const FLOAT: &str = "morphir/sdk:basics#float";
+const CHAR: &str = "morphir/sdk:char#char";
+const STRING: &str = "morphir/sdk:string#string";
+const DECIMAL: &str = "morphir/sdk:decimal#decimal";
+const LIST: &str = "morphir/sdk:list#list";
+
+/// Guards against cyclic type aliases.
+const MAX_ALIAS_DEPTH: usize = 32;
+
+/// Severity of a type checking diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found while type checking a value definition.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable diagnostic code, e.g. `type-mismatch`
+    pub code: String,
+    pub message: String,
+    /// The value definition the problem was found in
+    pub definition: FQName,
+    /// Location of the offending expression, or of its nearest located ancestor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<SourceLocation>,
+}
+
+impl Diagnostic {
+    /// Whether this diagnostic is an error (as opposed to a warning)
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Type check every value definition of a distribution.
+///
+/// Specs distributions carry no bodies, so they never produce diagnostics.
+pub fn typecheck_distribution(dist: &Distribution) -> Vec<Diagnostic> {
+    TypeChecker::new(dist).check_package()
+}
+
+/// Type checker for the definitions of one distribution.
+///
+/// Holds the signatures of all values, constructors, and type aliases visible
+/// to the package: its own definitions and those of its dependencies.
+pub struct TypeChecker<'a> {
+    env: Environment<'a>,
+    dist: &'a Distribution,
+    def: Option<&'a PackageDefinition>,
+    entry_points: Option<&'a EntryPoints>,
+}
+
+impl<'a> TypeChecker<'a> {
+    /// Build a checker for a distribution.
+    pub fn new(dist: &'a Distribution) -> Self {
+        let def = dist.definition();
+        Self {
+            env: Environment::new(&dist.package_name().0, def, dist.dependencies()),
+            dist,
+            def,
+            entry_points: dist.entry_points(),
+        }
+    }
+
+    /// Check all value definitions of the package, and the entry points of
+    /// an application.
+    pub fn check_package(&self) -> Vec<Diagnostic> {
+        let Some(def) = self.def else {
+            return Vec::new();
+        };
+        let mut diagnostics = self.check_entry_points(def);
+        for (module_key, module) in &def.modules {
+            for (value_key, value_def) in &module.value.values {
+                let fqname = FQName::new(
+                    self.env.package.clone(),
+                    Path::new(module_key),
+                    Name::from(value_key.as_str()),
+                );
+                diagnostics.extend(self.check_value_definition(&fqname, &value_def.value));
+            }
+        }
+        diagnostics
+    }
+
+    /// Like [`check_package`](Self::check_package), but checks the modules
+    /// on up to `jobs` threads (0 for one per CPU). The diagnostics come in
+    /// the same order.
+    pub fn par_check_package(&self, jobs: usize) -> Vec<Diagnostic> {
+        let Some(def) = self.def else {
+            return Vec::new();
+        };
+        let mut diagnostics = self.check_entry_points(def);
+        let modules = par_walk_modules(self.dist, jobs, |_| ModuleChecker {
+            checker: self,
+            diagnostics: Vec::new(),
+        });
+        diagnostics.extend(modules.into_iter().flat_map(|module| module.diagnostics));
+        diagnostics
+    }
+
+    /// Check that every entry point targets a value defined in the package.
+    fn check_entry_points(&self, def: &PackageDefinition) -> Vec<Diagnostic> {
+        let Some(entry_points) = self.entry_points else {
+            return Vec::new();
+        };
+        let defined: HashSet<String> = def
+            .modules
+            .iter()
+            .flat_map(|(module_key, module)| {
+                module.value.values.keys().map(move |value_key| {
+                    FQName::member_key(&self.env.package, module_key, value_key)
+                })
+            })
+            .collect();
+        let mut diagnostics = Vec::new();
+        for (name, entry_point) in entry_points {
+            let (code, message, definition) = match entry_point.fqname() {
+                Err(e) => (
+                    "invalid-entry-point",
+                    format!(
+                        "entry point `{}` has an invalid target `{}`: {}",
+                        name, entry_point.target, e
+                    ),
+                    FQName::new(
+                        self.env.package.clone(),
+                        Path::new(""),
+                        Name::from(name.as_str()),
+                    ),
+                ),
+                Ok(target) if !defined.contains(&key(&target)) => (
+                    "unknown-entry-point",
+                    format!(
+                        "entry point `{}` targets `{}`, which is not a value defined in this package",
+                        name, entry_point.target
+                    ),
+                    target,
+                ),
+                Ok(_) => continue,
+            };
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: code.to_string(),
+                message,
+                definition,
+                location: None,
+            });
+        }
+        diagnostics
+    }
+
+    /// Check a single value definition against its declared signature.
+    pub fn check_value_definition(
+        &self,
+        fqname: &FQName,
+        def: &ValueDefinition,
+    ) -> Vec<Diagnostic> {
+        let mut inference = Inference::new(&self.env, fqname.clone());
+        inference.check_definition(def, true);
+        inference.diagnostics
+    }
+}
+
+/// Checks the value definitions of one module for [`TypeChecker::par_check_package`]
+struct ModuleChecker<'c, 'a> {
+    checker: &'c TypeChecker<'a>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl DistributionVisitor for ModuleChecker<'_, '_> {
+    fn enter_value_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &AccessControlled<ValueDefinition>,
+    ) -> Walk {
+        if let Some(fqname) = &context.definition {
+            self.diagnostics.extend(
+                self.checker
+                    .check_value_definition(fqname, &definition.value),
+            );
+        }
+        Walk::Skip
+    }
+}
+
+// =============================================================================
+// Environment
+// =============================================================================
+
+/// Declared signature of a value: its input types and output type.
+struct Signature<'a> {
+    inputs: Vec<&'a Type>,
+    output: &'a Type,
+}
+
+struct Constructor<'a> {
+    type_key: String,
+    type_params: Vec<String>,
+    args: Vec<&'a Type>,
+}
+
+struct Alias<'a> {
+    params: Vec<String>,
+    body: &'a Type,
+}
+
+/// Values, constructors, and aliases visible to the package, keyed by
+/// normalized FQName.
+struct Environment<'a> {
+    package: Path,
+    package_key: String,
+    /// Packages whose specification was provided as a dependency
+    dependency_keys: HashSet<String>,
+    values: HashMap<String, Signature<'a>>,
+    constructors: HashMap<String, Constructor<'a>>,
+    aliases: HashMap<String, Alias<'a>>,
+}
+
+impl<'a> Environment<'a> {
+    fn new(
+        package: &Path,
+        def: Option<&'a PackageDefinition>,
+        dependencies: &'a Dependencies,
+    ) -> Self {
+        let mut env = Self {
+            package: package.clone(),
+            package_key: package.to_normalized_string(),
+            dependency_keys: HashSet::new(),
+            values: HashMap::new(),
+            constructors: HashMap::new(),
+            aliases: HashMap::new(),
+        };
+
+        for (dependency_key, spec) in dependencies {
+            let dependency = Path::new(dependency_key);
+            env.dependency_keys
+                .insert(dependency.to_normalized_string());
+            for (module_key, module) in &spec.modules {
+                for (type_key, type_spec) in &module.types {
+                    let key = FQName::member_key(&dependency, module_key, type_key);
+                    match type_spec {
+                        TypeSpecification::TypeAliasSpecification {
+                            type_params,
+                            type_expr,
+                        } => env.add_alias(key, type_params, type_expr),
+                        TypeSpecification::CustomTypeSpecification {
+                            type_params,
+                            constructors,
+                        } => {
+                            for ctor in constructors {
+                                env.constructors.insert(
+                                    FQName::member_key(
+                                        &dependency,
+                                        module_key,
+                                        &ctor.name.to_kebab_case(),
+                                    ),
+                                    Constructor {
+                                        type_key: key.clone(),
+                                        type_params: params(type_params),
+                                        args: ctor.args.iter().map(|a| &a.arg_type).collect(),
+                                    },
+                                );
+                            }
+                        }
+                        TypeSpecification::OpaqueTypeSpecification { .. } => {}
+                    }
+                }
+                for (value_key, value_spec) in &module.values {
+                    env.values.insert(
+                        FQName::member_key(&dependency, module_key, value_key),
+                        Signature {
+                            inputs: value_spec.inputs.values().collect(),
+                            output: &value_spec.output,
+                        },
+                    );
+                }
+            }
+        }
+
+        for (module_key, module) in def.iter().flat_map(|d| &d.modules) {
+            for (type_key, type_def) in &module.value.types {
+                let key = FQName::member_key(package, module_key, type_key);
+                match &type_def.value {
+                    TypeDefinition::TypeAliasDefinition {
+                        type_params,
+                        type_expr,
+                    } => env.add_alias(key, type_params, type_expr),
+                    TypeDefinition::CustomTypeDefinition {
+                        type_params,
+                        constructors,
+                    } => {
+                        for ctor in &constructors.value {
+                            env.constructors.insert(
+                                FQName::member_key(package, module_key, &ctor.name.to_kebab_case()),
+                                Constructor {
+                                    type_key: key.clone(),
+                                    type_params: params(type_params),
+                                    args: ctor.args.iter().map(|a| &a.arg_type).collect(),
+                                },
+                            );
+                        }
+                    }
+                    TypeDefinition::IncompleteTypeDefinition { .. } => {}
+                }
+            }
+            for (value_key, value_def) in &module.value.values {
+                env.values.insert(
+                    FQName::member_key(package, module_key, value_key),
+                    Signature {
+                        inputs: value_def
+                            .value
+                            .input_types
+                            .values()
+                            .map(|entry| &entry.input_type)
+                            .collect(),
+                        output: &value_def.value.output_type,
+                    },
+                );
+            }
+        }
+
+        env
+    }
+
+    fn add_alias(&mut self, key: String, type_params: &[Name], body: &'a Type) {
+        self.aliases.insert(
+            key,
+            Alias {
+                params: params(type_params),
+                body,
+            },
+        );
+    }
+
+    fn is_local(&self, fqname: &FQName) -> bool {
+        fqname.package_path.to_normalized_string() == self.package_key
+    }
+
+    fn is_dependency(&self, fqname: &FQName) -> bool {
+        self.dependency_keys
+            .contains(&fqname.package_path.to_normalized_string())
+    }
+}
+
+/// Normalized lookup key for an FQName.
+///
+/// Module and member keys in the IR are not canonical (frontends may emit
+/// snake_case or TitleCase), so keys are built from kebab-case names, and
+/// IR converted from morphir-elm spells the SDK `morphir/s-d-k`.
+fn key(fqname: &FQName) -> String {
+    fqname.to_normalized_string()
+}
+
+fn params(type_params: &[Name]) -> Vec<String> {
+    type_params.iter().map(Name::to_kebab_case).collect()
+}
+
+fn normalize_name(key: &str) -> String {
+    Name::from(key).to_kebab_case()
+}
+
+// =============================================================================
+// Inference
+// =============================================================================
+
+/// Internal type representation used during inference.
+#[derive(Debug, Clone, PartialEq)]
+enum Ty {
+    /// Unification variable
+    Var(u32),
+    /// Type variable from a declared signature, fixed inside its definition
+    Rigid(String),
+    /// Nominal type applied to arguments, keyed by normalized FQName
+    Con(String, Vec<Ty>),
+    Tuple(Vec<Ty>),
+    /// Record with its fields and, for extensible records, the row tail
+    Record(BTreeMap<String, Ty>, Option<Box<Ty>>),
+    Fun(Box<Ty>, Box<Ty>),
+    Unit,
+}
+
+enum Mismatch {
+    Types,
+    Infinite,
+}
+
+/// A local variable binding.
+enum Local<'v> {
+    /// Lambda argument, pattern variable, or definition input
+    Mono(Ty),
+    /// Let-bound definition; its declared signature is instantiated at each use
+    Declared(&'v ValueDefinition),
+}
+
+/// Inference state for one top-level value definition.
+struct Inference<'c, 'a, 'v> {
+    env: &'c Environment<'a>,
+    definition: FQName,
+    substitution: HashMap<u32, Ty>,
+    numeric: HashSet<u32>,
+    next_var: u32,
+    locals: Vec<(String, Local<'v>)>,
+    /// Rigid type variables in scope
+    rigid: Vec<String>,
+    /// Location of the innermost located expression being inferred
+    location: Option<SourceLocation>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'c, 'a, 'v> Inference<'c, 'a, 'v> {
+    fn new(env: &'c Environment<'a>, definition: FQName) -> Self {
+        Self {
+            env,
+            definition,
+            substitution: HashMap::new(),
+            numeric: HashSet::new(),
+            next_var: 0,
+            locals: Vec::new(),
+            rigid: Vec::new(),
+            location: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn fresh(&mut self) -> Ty {
+        self.next_var += 1;
+        Ty::Var(self.next_var)
+    }
+
+    fn fresh_numeric(&mut self) -> Ty {
+        self.next_var += 1;
+        self.numeric.insert(self.next_var);
+        Ty::Var(self.next_var)
+    }
+
+    fn rigid_vars(&self) -> HashMap<String, Ty> {
+        self.rigid
+            .iter()
+            .map(|name| (name.clone(), Ty::Rigid(name.clone())))
+            .collect()
+    }
+
+    // --- Converting IR types ---
+
+    fn convert(&mut self, tpe: &Type, vars: &mut HashMap<String, Ty>, rigid: bool) -> Ty {
+        self.convert_at(tpe, vars, rigid, 0)
+    }
+
+    fn convert_at(
+        &mut self,
+        tpe: &Type,
+        vars: &mut HashMap<String, Ty>,
+        rigid: bool,
+        depth: usize,
+    ) -> Ty {
+        match tpe {
+            Type::Variable(_, name) => self.type_variable(name, vars, rigid),
+            Type::Reference(_, fqname, args) => {
+                let args: Vec<Ty> = args
+                    .iter()
+                    .map(|arg| self.convert_at(arg, vars, rigid, depth))
+                    .collect();
+                let key = key(fqname);
+                match self.env.aliases.get(&key) {
+                    Some(alias) if depth < MAX_ALIAS_DEPTH && alias.params.len() == args.len() => {
+                        let mut alias_vars = alias.params.iter().cloned().zip(args).collect();
+                        self.convert_at(alias.body, &mut alias_vars, false, depth + 1)
+                    }
+                    _ => Ty::Con(key, args),
+                }
+            }
+            Type::Tuple(_, elements) => Ty::Tuple(
+                elements
+                    .iter()
+                    .map(|e| self.convert_at(e, vars, rigid, depth))
+                    .collect(),
+            ),
+            Type::Record(_, fields) => {
+                Ty::Record(self.convert_fields(fields, vars, rigid, depth), None)
+            }
+            Type::ExtensibleRecord(_, name, fields) => {
+                let tail = self.type_variable(name, vars, rigid);
+                let fields = self.convert_fields(fields, vars, rigid, depth);
+                Ty::Record(fields, Some(Box::new(tail)))
+            }
+            Type::Function(_, arg, result) => Ty::Fun(
+                Box::new(self.convert_at(arg, vars, rigid, depth)),
+                Box::new(self.convert_at(result, vars, rigid, depth)),
+            ),
+            Type::Unit(_) => Ty::Unit,
+        }
+    }
+
+    fn convert_fields(
+        &mut self,
+        fields: &[Field],
+        vars: &mut HashMap<String, Ty>,
+        rigid: bool,
+        depth: usize,
+    ) -> BTreeMap<String, Ty> {
+        fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.to_kebab_case(),
+                    self.convert_at(&f.tpe, vars, rigid, depth),
+                )
+            })
+            .collect()
+    }
+
+    fn type_variable(&mut self, name: &Name, vars: &mut HashMap<String, Ty>, rigid: bool) -> Ty {
+        let name = name.to_kebab_case();
+        if let Some(ty) = vars.get(&name) {
+            return ty.clone();
+        }
+        let ty = if rigid {
+            Ty::Rigid(name.clone())
+        } else if name.starts_with("number") {
+            self.fresh_numeric()
+        } else {
+            self.fresh()
+        };
+        vars.insert(name, ty.clone());
+        ty
+    }
+
+    fn function_type(&mut self, inputs: &[&Type], output: &Type) -> Ty {
+        let mut vars = self.rigid_vars();
+        let inputs: Vec<Ty> = inputs
+            .iter()
+            .map(|t| self.convert(t, &mut vars, false))
+            .collect();
+        let output = self.convert(output, &mut vars, false);
+        fold_function(inputs, output)
+    }
+
+    /// Instantiate a constructor: its argument types and the type it builds.
+    fn instantiate_constructor(&mut self, ctor: &Constructor) -> (Vec<Ty>, Ty) {
+        let mut vars = HashMap::new();
+        let params: Vec<Ty> = ctor
+            .type_params
+            .iter()
+            .map(|p| self.type_variable(&Name::from(p.as_str()), &mut vars, false))
+            .collect();
+        let args = ctor
+            .args
+            .iter()
+            .map(|t| self.convert(t, &mut vars, false))
+            .collect();
+        (args, Ty::Con(ctor.type_key.clone(), params))
+    }
+
+    // --- Definitions ---
+
+    /// Check a definition body against its declared signature.
+    ///
+    /// The type variables of a top-level signature are rigid. Frontends also
+    /// record the types they inferred for let definitions, and those may
+    /// have lost constraints along the way (morphir-elm types `n = 42` as a
+    /// bare `t0`), so the variables a let definition introduces are not.
+    fn check_definition(&mut self, def: &'v ValueDefinition, rigid: bool) {
+        let locals_mark = self.locals.len();
+        let rigid_mark = self.rigid.len();
+
+        let mut vars = self.rigid_vars();
+        let inputs: Vec<(String, Ty)> = def
+            .input_types
+            .iter()
+            .map(|(name, entry)| {
+                (
+                    normalize_name(name),
+                    self.convert(&entry.input_type, &mut vars, rigid),
+                )
+            })
+            .collect();
+        let output = self.convert(&def.output_type, &mut vars, rigid);
+        if rigid {
+            for name in vars.into_keys() {
+                if !self.rigid.contains(&name) {
+                    self.rigid.push(name);
+                }
+            }
+        }
+        for (name, ty) in inputs {
+            self.locals.push((name, Local::Mono(ty)));
+        }
+
+        if let ValueBody::Expression(body) = &def.body {
+            let found = self.infer(body);
+            self.expect(&output, &found, body.attributes());
+        }
+
+        self.locals.truncate(locals_mark);
+        self.rigid.truncate(rigid_mark);
+    }
+
+    fn lookup_local(&mut self, name: &str) -> Option<Ty> {
+        let index = self.locals.iter().rposition(|(n, _)| n == name)?;
+        match &self.locals[index].1 {
+            Local::Mono(ty) => Some(ty.clone()),
+            Local::Declared(def) => {
+                let def: &'v ValueDefinition = def;
+                let inputs: Vec<&Type> = def.input_types.values().map(|e| &e.input_type).collect();
+                Some(self.function_type(&inputs, &def.output_type))
+            }
+        }
+    }
+
+    // --- Values ---
+
+    fn infer(&mut self, value: &'v Value) -> Ty {
+        let attrs = value.attributes();
+        let outer = match &attrs.source {
+            Some(source) => self.location.replace(source.clone()),
+            None => self.location.clone(),
+        };
+        let ty = self.infer_value(value);
+        self.location = outer;
+        ty
+    }
+
+    fn infer_value(&mut self, value: &'v Value) -> Ty {
+        match value {
+            Value::Literal(_, literal) => self.literal_type(literal),
+            Value::Constructor(attrs, fqname) => match self.env.constructors.get(&key(fqname)) {
+                Some(ctor) => {
+                    let (args, result) = self.instantiate_constructor(ctor);
+                    fold_function(args, result)
+                }
+                None => {
+                    self.unresolved(attrs, fqname);
+                    self.fresh()
+                }
+            },
+            Value::Tuple(_, elements) => {
+                Ty::Tuple(elements.iter().map(|e| self.infer(e)).collect())
+            }
+            Value::List(_, items) => {
+                let element = self.fresh();
+                for item in items {
+                    let found = self.infer(item);
+                    self.expect(&element, &found, item.attributes());
+                }
+                Ty::Con(LIST.to_string(), vec![element])
+            }
+            Value::Record(_, fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|RecordFieldEntry(name, v)| (name.to_kebab_case(), self.infer(v)))
+                    .collect();
+                Ty::Record(fields, None)
+            }
+            Value::Variable(attrs, name) => match self.lookup_local(&name.to_kebab_case()) {
+                Some(ty) => ty,
+                None => {
+                    self.report(
+                        Severity::Error,
+                        "unknown-variable",
+                        format!("Variable `{}` is not in scope", name.to_camel_case()),
+                        attrs,
+                    );
+                    self.fresh()
+                }
+            },
+            Value::Reference(attrs, fqname) => match self.env.values.get(&key(fqname)) {
+                Some(sig) => {
+                    let inputs = sig.inputs.clone();
+                    let output = sig.output;
+                    self.function_type(&inputs, output)
+                }
+                None => {
+                    self.unresolved(attrs, fqname);
+                    self.fresh()
+                }
+            },
+            Value::Field(_, record, name) => {
+                let found = self.infer(record);
+                let field = self.fresh();
+                let row = self.fresh();
+                let expected = Ty::Record(
+                    BTreeMap::from([(name.to_kebab_case(), field.clone())]),
+                    Some(Box::new(row)),
+                );
+                self.expect(&expected, &found, record.attributes());
+                field
+            }
+            Value::FieldFunction(_, name) => {
+                let field = self.fresh();
+                let row = self.fresh();
+                let record = Ty::Record(
+                    BTreeMap::from([(name.to_kebab_case(), field.clone())]),
+                    Some(Box::new(row)),
+                );
+                Ty::Fun(Box::new(record), Box::new(field))
+            }
+            Value::Apply(attrs, function, argument) => {
+                let function_ty = self.infer(function);
+                let argument_ty = self.infer(argument);
+                match self.shallow(&function_ty) {
+                    Ty::Fun(param, result) => {
+                        self.expect(&param, &argument_ty, argument.attributes());
+                        *result
+                    }
+                    Ty::Var(_) => {
+                        let result = self.fresh();
+                        let expected = Ty::Fun(Box::new(argument_ty), Box::new(result.clone()));
+                        self.expect(&expected, &function_ty, function.attributes());
+                        result
+                    }
+                    other => {
+                        let found = self.display(&other);
+                        self.report(
+                            Severity::Error,
+                            "not-a-function",
+                            format!("This value has type `{}`, which is not a function", found),
+                            attrs,
+                        );
+                        self.fresh()
+                    }
+                }
+            }
+            Value::Lambda(_, pattern, body) => {
+                let mark = self.locals.len();
+                let argument = self.fresh();
+                self.bind_pattern(pattern, argument.clone());
+                let result = self.infer(body);
+                self.locals.truncate(mark);
+                Ty::Fun(Box::new(argument), Box::new(result))
+            }
+            Value::LetDefinition(_, name, def, body) => {
+                self.check_definition(def, false);
+                let mark = self.locals.len();
+                self.locals
+                    .push((name.to_kebab_case(), Local::Declared(def.as_ref())));
+                let ty = self.infer(body);
+                self.locals.truncate(mark);
+                ty
+            }
+            Value::LetRecursion(_, bindings, body) => {
+                let mark = self.locals.len();
+                for LetBinding(name, def) in bindings {
+                    self.locals
+                        .push((name.to_kebab_case(), Local::Declared(def)));
+                }
+                for LetBinding(_, def) in bindings {
+                    self.check_definition(def, false);
+                }
+                let ty = self.infer(body);
+                self.locals.truncate(mark);
+                ty
+            }
+            Value::Destructure(_, pattern, value, body) => {
+                let value_ty = self.infer(value);
+                let mark = self.locals.len();
+                self.bind_pattern(pattern, value_ty);
+                let ty = self.infer(body);
+                self.locals.truncate(mark);
+                ty
+            }
+            Value::IfThenElse(_, condition, then_branch, else_branch) => {
+                let condition_ty = self.infer(condition);
+                self.expect(&con(BOOL), &condition_ty, condition.attributes());
+                let then_ty = self.infer(then_branch);
+                let else_ty = self.infer(else_branch);
+                self.expect(&then_ty, &else_ty, else_branch.attributes());
+                then_ty
+            }
+            Value::PatternMatch(_, subject, cases) => {
+                let subject_ty = self.infer(subject);
+                let result = self.fresh();
+                for PatternCase(pattern, body) in cases {
+                    let mark = self.locals.len();
+                    self.bind_pattern(pattern, subject_ty.clone());
+                    let found = self.infer(body);
+                    self.expect(&result, &found, body.attributes());
+                    self.locals.truncate(mark);
+                }
+                result
+            }
+            Value::UpdateRecord(attrs, record, fields) => {
+                let record_ty = self.infer(record);
+                let fields = fields
+                    .iter()
+                    .map(|RecordFieldEntry(name, v)| (name.to_kebab_case(), self.infer(v)))
+                    .collect();
+                let row = self.fresh();
+                self.expect(&Ty::Record(fields, Some(Box::new(row))), &record_ty, attrs);
+                record_ty
+            }
+            Value::Unit(_) => Ty::Unit,
+            Value::Hole(_, _, Some(tpe)) => {
+                let mut vars = self.rigid_vars();
+                self.convert(tpe, &mut vars, false)
+            }
+            Value::Hole(_, _, None) | Value::Native(..) | Value::External(..) => self.fresh(),
+        }
+    }
+
+    fn literal_type(&mut self, literal: &Literal) -> Ty {
+        match literal {
+            Literal::Bool(_) => con(BOOL),
+            Literal::Char(_) => con(CHAR),
+            Literal::String(_) => con(STRING),
+            // Whole number literals are `number`, as in Elm
+            Literal::Integer(_) => self.fresh_numeric(),
+            Literal::Float(_) => con(FLOAT),
+            Literal::Decimal(_) => con(DECIMAL),
+        }
+    }
+
+    // --- Patterns ---
+
+    /// Check a pattern against the type of the value it matches and bind the
+    /// variables it introduces.
+    fn bind_pattern(&mut self, pattern: &Pattern, ty: Ty) {
+        let attrs = pattern.attributes();
+        match pattern {
+            Pattern::WildcardPattern(_) => {}
+            Pattern::AsPattern(_, inner, name) => {
+                self.bind_pattern(inner, ty.clone());
+                self.locals.push((name.to_kebab_case(), Local::Mono(ty)));
+            }
+            Pattern::TuplePattern(_, elements) => {
+                let element_tys: Vec<Ty> = elements.iter().map(|_| self.fresh()).collect();
+                self.expect(&ty, &Ty::Tuple(element_tys.clone()), attrs);
+                for (element, element_ty) in elements.iter().zip(element_tys) {
+                    self.bind_pattern(element, element_ty);
+                }
+            }
+            Pattern::ConstructorPattern(_, fqname, args) => {
+                let Some(ctor) = self.env.constructors.get(&key(fqname)) else {
+                    self.unresolved(attrs, fqname);
+                    for arg in args {
+                        let arg_ty = self.fresh();
+                        self.bind_pattern(arg, arg_ty);
+                    }
+                    return;
+                };
+                let (arg_tys, result) = self.instantiate_constructor(ctor);
+                self.expect(&ty, &result, attrs);
+                if arg_tys.len() != args.len() {
+                    self.report(
+                        Severity::Error,
+                        "constructor-arity",
+                        format!(
+                            "Constructor `{}` expects {} argument(s), but the pattern has {}",
+                            fqname.local_name.to_title_case(),
+                            arg_tys.len(),
+                            args.len()
+                        ),
+                        attrs,
+                    );
+                }
+                let mut arg_tys = arg_tys.into_iter();
+                for arg in args {
+                    let arg_ty = arg_tys.next().unwrap_or_else(|| self.fresh());
+                    self.bind_pattern(arg, arg_ty);
+                }
+            }
+            Pattern::EmptyListPattern(_) => {
+                let element = self.fresh();
+                self.expect(&ty, &Ty::Con(LIST.to_string(), vec![element]), attrs);
+            }
+            Pattern::HeadTailPattern(_, head, tail) => {
+                let element = self.fresh();
+                let list = Ty::Con(LIST.to_string(), vec![element.clone()]);
+                self.expect(&ty, &list, attrs);
+                self.bind_pattern(head, element);
+                self.bind_pattern(tail, list);
+            }
+            Pattern::LiteralPattern(_, literal) => {
+                let literal_ty = self.literal_type(literal);
+                self.expect(&ty, &literal_ty, attrs);
+            }
+            Pattern::UnitPattern(_) => self.expect(&ty, &Ty::Unit, attrs),
+        }
+    }
+
+    // --- Unification ---
+
+    /// Unify `found` with `expected`, reporting a diagnostic on failure.
+    fn expect(&mut self, expected: &Ty, found: &Ty, attrs: &ValueAttributes) {
+        match self.unify(expected, found) {
+            Ok(()) => {}
+            Err(Mismatch::Types) => {
+                let message = format!(
+                    "Type mismatch: expected `{}`, found `{}`",
+                    self.display(expected),
+                    self.display(found)
+                );
+                self.report(Severity::Error, "type-mismatch", message, attrs);
+            }
+            Err(Mismatch::Infinite) => {
+                let message = format!(
+                    "Infinite type: `{}` would have to contain itself to match `{}`",
+                    self.display(found),
+                    self.display(expected)
+                );
+                self.report(Severity::Error, "infinite-type", message, attrs);
+            }
+        }
+    }
+
+    /// Follow variable bindings at the top of a type.
+    fn shallow(&self, ty: &Ty) -> Ty {
+        let mut ty = ty;
+        while let Ty::Var(v) = ty {
+            match self.substitution.get(v) {
+                Some(bound) => ty = bound,
+                None => break,
+            }
+        }
+        ty.clone()
+    }
+
+    /// Apply the substitution everywhere in a type.
+    fn resolve(&self, ty: &Ty) -> Ty {
+        match self.shallow(ty) {
+            Ty::Con(name, args) => Ty::Con(name, args.iter().map(|a| self.resolve(a)).collect()),
+            Ty::Tuple(elements) => Ty::Tuple(elements.iter().map(|e| self.resolve(e)).collect()),
+            Ty::Record(fields, tail) => {
+                let (fields, tail) = self.flatten_record(fields, tail);
+                Ty::Record(
+                    fields
+                        .iter()
+                        .map(|(n, t)| (n.clone(), self.resolve(t)))
+                        .collect(),
+                    tail.map(Box::new),
+                )
+            }
+            Ty::Fun(arg, result) => Ty::Fun(
+                Box::new(self.resolve(&arg)),
+                Box::new(self.resolve(&result)),
+            ),
+            other => other,
+        }
+    }
+
+    /// Merge record extensions bound to the row tail into a single field map.
+    fn flatten_record(
+        &self,
+        mut fields: BTreeMap<String, Ty>,
+        mut tail: Option<Box<Ty>>,
+    ) -> (BTreeMap<String, Ty>, Option<Ty>) {
+        loop {
+            match tail.map(|t| self.shallow(&t)) {
+                Some(Ty::Record(more, next)) => {
+                    for (name, ty) in more {
+                        fields.entry(name).or_insert(ty);
+                    }
+                    tail = next;
+                }
+                other => return (fields, other),
+            }
+        }
+    }
+
+    fn unify(&mut self, a: &Ty, b: &Ty) -> Result<(), Mismatch> {
+        let a = self.shallow(a);
+        let b = self.shallow(b);
+        match (a, b) {
+            (Ty::Var(x), Ty::Var(y)) if x == y => Ok(()),
+            (Ty::Var(x), other) | (other, Ty::Var(x)) => self.bind(x, other),
+            (Ty::Rigid(x), Ty::Rigid(y)) if x == y => Ok(()),
+            (Ty::Con(x, xs), Ty::Con(y, ys)) if x == y && xs.len() == ys.len() => {
+                self.unify_all(&xs, &ys)
+            }
+            (Ty::Tuple(xs), Ty::Tuple(ys)) if xs.len() == ys.len() => self.unify_all(&xs, &ys),
+            (Ty::Fun(p1, r1), Ty::Fun(p2, r2)) => {
+                self.unify(&p1, &p2)?;
+                self.unify(&r1, &r2)
+            }
+            (Ty::Unit, Ty::Unit) => Ok(()),
+            (Ty::Record(f1, t1), Ty::Record(f2, t2)) => {
+                let (f1, t1) = self.flatten_record(f1, t1);
+                let (f2, t2) = self.flatten_record(f2, t2);
+                self.unify_records(f1, t1, f2, t2)
+            }
+            _ => Err(Mismatch::Types),
+        }
+    }
+
+    fn unify_all(&mut self, xs: &[Ty], ys: &[Ty]) -> Result<(), Mismatch> {
+        for (x, y) in xs.iter().zip(ys) {
+            self.unify(x, y)?;
+        }
+        Ok(())
+    }
+
+    /// Unify two records: shared fields must agree, and the fields only one
+    /// side has must be absorbed by the other side's row tail.
+    fn unify_records(
+        &mut self,
+        mut fields1: BTreeMap<String, Ty>,
+        tail1: Option<Ty>,
+        mut fields2: BTreeMap<String, Ty>,
+        tail2: Option<Ty>,
+    ) -> Result<(), Mismatch> {
+        let shared: Vec<String> = fields1
+            .keys()
+            .filter(|name| fields2.contains_key(*name))
+            .cloned()
+            .collect();
+        for name in shared {
+            let (Some(x), Some(y)) = (fields1.remove(&name), fields2.remove(&name)) else {
+                continue;
+            };
+            self.unify(&x, &y)?;
+        }
+
+        if fields1.is_empty() && fields2.is_empty() {
+            return match (tail1, tail2) {
+                (None, None) => Ok(()),
+                (Some(t), None) | (None, Some(t)) => {
+                    self.unify(&t, &Ty::Record(BTreeMap::new(), None))
+                }
+                (Some(x), Some(y)) => self.unify(&x, &y),
+            };
+        }
+
+        let rest = match (&tail1, &tail2) {
+            (Some(_), Some(_)) => Some(Box::new(self.fresh())),
+            _ => None,
+        };
+        self.absorb(tail1, Ty::Record(fields2, rest.clone()))?;
+        self.absorb(tail2, Ty::Record(fields1, rest))
+    }
+
+    fn absorb(&mut self, tail: Option<Ty>, extra: Ty) -> Result<(), Mismatch> {
+        match (tail, &extra) {
+            (Some(tail), _) => self.unify(&tail, &extra),
+            (None, Ty::Record(fields, None)) if fields.is_empty() => Ok(()),
+            (None, _) => Err(Mismatch::Types),
+        }
+    }
+
+    fn bind(&mut self, var: u32, ty: Ty) -> Result<(), Mismatch> {
+        if self.occurs(var, &ty) {
+            return Err(Mismatch::Infinite);
+        }
+        if self.numeric.contains(&var) {
+            match &ty {
+                Ty::Var(other) => {
+                    self.numeric.insert(*other);
+                }
+                Ty::Con(name, args) if args.is_empty() && is_number(name) => {}
+                Ty::Rigid(name) if name.starts_with("number") => {}
+                _ => return Err(Mismatch::Types),
+            }
+        }
+        self.substitution.insert(var, ty);
+        Ok(())
+    }
+
+    fn occurs(&self, var: u32, ty: &Ty) -> bool {
+        match self.shallow(ty) {
+            Ty::Var(v) => v == var,
+            Ty::Con(_, items) | Ty::Tuple(items) => items.iter().any(|t| self.occurs(var, t)),
+            Ty::Record(fields, tail) => {
+                fields.values().any(|t| self.occurs(var, t))
+                    || tail.is_some_and(|t| self.occurs(var, &t))
+            }
+            Ty::Fun(arg, result) => self.occurs(var, &arg) || self.occurs(var, &result),
+            Ty::Rigid(_) | Ty::Unit => false,
+        }
+    }
+
+    // --- Reporting ---
+
+    fn display(&self, ty: &Ty) -> String {
+        let ty = self.resolve(ty);
+        display_type(&ty, &self.numeric, false)
+    }
+
+    fn unresolved(&mut self, attrs: &ValueAttributes, fqname: &FQName) {
+        // A dependency specification is the whole public interface of that
+        // package: anything missing from it cannot be linked against.
+        if self.env.is_dependency(fqname) {
+            self.report(
+                Severity::Error,
+                "not-in-specification",
+                format!(
+                    "`{}` is not part of the specification of dependency `{}`",
+                    fqname.to_canonical_string(),
+                    fqname.package_path
+                ),
+                attrs,
+            );
+            return;
+        }
+        // References into packages outside the distribution (such as the SDK)
+        // cannot be checked and are trusted.
+        if self.env.is_local(fqname) {
+            self.report(
+                Severity::Warning,
+                "unresolved-reference",
+                format!(
+                    "Reference to `{}` could not be resolved",
+                    fqname.to_canonical_string()
+                ),
+                attrs,
+            );
+        }
+    }
+
+    fn report(&mut self, severity: Severity, code: &str, message: String, attrs: &ValueAttributes) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            code: code.to_string(),
+            message,
+            definition: self.definition.clone(),
+            location: attrs.source.clone().or_else(|| self.location.clone()),
+        });
+    }
+}
+
+fn con(name: &str) -> Ty {
+    Ty::Con(name.to_string(), Vec::new())
+}
+
+fn is_number(name: &str) -> bool {
+    name == INT || name == FLOAT || name == DECIMAL
+}
+
+fn fold_function(inputs: Vec<Ty>, output: Ty) -> Ty {
+    inputs.into_iter().rev().fold(output, |result, input| {
+        Ty::Fun(Box::new(input), Box::new(result))
+    })
+}
+
+/// Render a type in Elm-like syntax for diagnostics.
+fn display_type(ty: &Ty, numeric: &HashSet<u32>, nested: bool) -> String {
+    let parenthesize = |s: String| if nested { format!("({})", s) } else { s };
+    match ty {
+        Ty::Var(v) if numeric.contains(v) => format!("number{}", v),
+        Ty::Var(v) => format!("t{}", v),
+        Ty::Rigid(name) => Name::from(name.as_str()).to_camel_case(),
+        Ty::Con(name, args) => {
+            let local = name.rsplit('#').next().unwrap_or(name);
+            let local = Name::from(local).to_title_case();
+            if args.is_empty() {
+                local
+            } else {
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|a| display_type(a, numeric, true))
+                    .collect();
+                parenthesize(format!("{} {}", local, args.join(" ")))
+            }
+        }
+        Ty::Tuple(elements) => {
+            let elements: Vec<String> = elements
+                .iter()
+                .map(|e| display_type(e, numeric, false))
+                .collect();
+            format!("( {} )", elements.join(", "))
+        }
+        Ty::Record(fields, tail) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, t)| {
+                    format!(
+                        "{} : {}",
+                        Name::from(name.as_str()).to_camel_case(),
+                        display_type(t, numeric, false)
+                    )
+                })
+                .collect();
+            match tail {
+                Some(tail) => format!(
+                    "{{ {} | {} }}",
+                    display_type(tail, numeric, false),
+                    fields.join(", ")
+                ),
+                None if fields.is_empty() => "{}".to_string(),
+                None => format!("{{ {} }}", fields.join(", ")),
+            }
+        }
+        Ty::Fun(arg, result) => parenthesize(format!(
+            "{} -> {}",
+            display_type(arg, numeric, true),
+            display_type(result, numeric, false)
+        )),
+        Ty::Unit => "()".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::access::Access;
+    use super::super::attributes::TypeAttributes;
+    use super::super::distribution::{ApplicationContent, EntryPoint, EntryPointKind};
+    use super::super::fixtures::{self, *};
+    use super::*;
+    use crate::naming::PackageName;
+    use indexmap::IndexMap;
+
+    fn at(line: u32) -> ValueAttributes {
+        ValueAttributes {
+            source: Some(SourceLocation::point(line, 1).in_file("src/orders.elm")),
+            ..ValueAttributes::default()
+        }
+    }
+
+    fn var(name: &str) -> Type {
+        Type::variable(TypeAttributes::default(), Name::from(name))
+    }
+
+    fn string_lit(s: &str) -> Value {
+        Value::literal(attrs(), Literal::String(s.to_string()))
+    }
+
+    fn apply(function: Value, argument: Value) -> Value {
+        Value::Apply(attrs(), Box::new(function), Box::new(argument))
+    }
+
+    /// [`library`](fixtures::library) with `values`, plus `inc : Int -> Int`,
+    /// the alias `amount = Int`, and the custom type `status`.
+    fn library(values: Vec<(&str, ValueDefinition)>) -> Distribution {
+        let inc = ValueDefinition::new(vec![input("n", int())], int(), variable("n"));
+        let mut dist = fixtures::library([("inc", inc)].into_iter().chain(values).collect());
+        let orders = orders(&mut dist);
+        orders.types.insert(
+            "amount".to_string(),
+            public(TypeDefinition::TypeAliasDefinition {
+                type_params: vec![],
+                type_expr: int(),
+            }),
+        );
+        orders.types.insert("status".to_string(), public(status()));
+        dist
+    }
+
+    fn check(values: Vec<(&str, ValueDefinition)>) -> Vec<Diagnostic> {
+        typecheck_distribution(&library(values))
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_well_typed_definitions_have_no_diagnostics() {
+        let diagnostics = check(vec![
+            (
+                "total",
+                ValueDefinition::new(
+                    vec![],
+                    named("my/pkg:orders#amount"),
+                    apply(
+                        Value::Reference(attrs(), fq("my/pkg:orders#inc")),
+                        int_lit(1),
+                    ),
+                ),
+            ),
+            (
+                "identity",
+                ValueDefinition::new(vec![input("x", var("a"))], var("a"), variable("x")),
+            ),
+        ]);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_body_must_match_declared_output() {
+        let body = Value::literal(at(7), Literal::String("oops".to_string()));
+        let diagnostics = check(vec![("total", ValueDefinition::new(vec![], int(), body))]);
+
+        assert_eq!(codes(&diagnostics), vec!["type-mismatch"]);
+        let diagnostic = &diagnostics[0];
+        assert!(diagnostic.is_error());
+        assert_eq!(diagnostic.definition, fq("my/pkg:orders#total"));
+        assert_eq!(diagnostic.location.as_ref().unwrap().start_line, 7);
+        assert!(
+            diagnostic
+                .message
+                .contains("expected `Int`, found `String`")
+        );
+    }
+
+    #[test]
+    fn test_argument_type_mismatch_uses_enclosing_location() {
+        let body = Value::Apply(
+            at(3),
+            Box::new(Value::Reference(attrs(), fq("my/pkg:orders#inc"))),
+            Box::new(string_lit("one")),
+        );
+        let diagnostics = check(vec![("total", ValueDefinition::new(vec![], int(), body))]);
+
+        assert_eq!(codes(&diagnostics), vec!["type-mismatch"]);
+        assert_eq!(diagnostics[0].location.as_ref().unwrap().start_line, 3);
+    }
+
+    #[test]
+    fn test_signature_variables_are_rigid() {
+        let diagnostics = check(vec![(
+            "identity",
+            ValueDefinition::new(vec![input("x", var("a"))], var("a"), int_lit(1)),
+        )]);
+        assert_eq!(codes(&diagnostics), vec!["type-mismatch"]);
+    }
+
+    #[test]
+    fn test_let_definition_variables_are_flexible() {
+        // morphir-elm types `n = 42` as a bare `t0`
+        let n = ValueDefinition::new(vec![], var("t0"), int_lit(42));
+        let body = Value::LetDefinition(
+            attrs(),
+            Name::from("n"),
+            Box::new(n),
+            Box::new(variable("n")),
+        );
+        let diagnostics = check(vec![("answer", ValueDefinition::new(vec![], int(), body))]);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_classic_sdk_spelling_is_the_sdk() {
+        let diagnostics = check(vec![(
+            "answer",
+            ValueDefinition::new(vec![], named("morphir/s-d-k:basics#int"), int_lit(42)),
+        )]);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_if_condition_must_be_bool() {
+        let body = Value::if_then_else(attrs(), int_lit(1), int_lit(2), int_lit(3));
+        let diagnostics = check(vec![("pick", ValueDefinition::new(vec![], int(), body))]);
+        assert_eq!(codes(&diagnostics), vec!["type-mismatch"]);
+    }
+
+    #[test]
+    fn test_list_elements_must_agree() {
+        let body = Value::List(attrs(), vec![int_lit(1), string_lit("two")]);
+        let list_of_int = Type::reference(TypeAttributes::default(), fq(LIST), vec![int()]);
+        let diagnostics = check(vec![(
+            "items",
+            ValueDefinition::new(vec![], list_of_int, body),
+        )]);
+        assert_eq!(codes(&diagnostics), vec!["type-mismatch"]);
+    }
+
+    #[test]
+    fn test_field_access_on_records() {
+        let record = Type::record(
+            TypeAttributes::default(),
+            vec![Field {
+                name: Name::from("price"),
+                tpe: int(),
+            }],
+        );
+        let field =
+            |name: &str| Value::Field(attrs(), Box::new(variable("order")), Name::from(name));
+        let diagnostics = check(vec![
+            (
+                "price",
+                ValueDefinition::new(vec![input("order", record.clone())], int(), field("price")),
+            ),
+            (
+                "quantity",
+                ValueDefinition::new(vec![input("order", record)], int(), field("quantity")),
+            ),
+        ]);
+        assert_eq!(codes(&diagnostics), vec!["type-mismatch"]);
+        assert_eq!(diagnostics[0].definition, fq("my/pkg:orders#quantity"));
+    }
+
+    #[test]
+    fn test_constructor_patterns() {
+        let status = named("my/pkg:orders#status");
+        let case = |pattern: Pattern, body: Value| PatternCase(pattern, body);
+        let held = |args: Vec<Pattern>| {
+            Pattern::ConstructorPattern(attrs(), fq("my/pkg:orders#held"), args)
+        };
+        let body = Value::PatternMatch(
+            attrs(),
+            Box::new(variable("s")),
+            vec![
+                case(
+                    held(vec![Pattern::AsPattern(
+                        attrs(),
+                        Box::new(Pattern::WildcardPattern(attrs())),
+                        Name::from("days"),
+                    )]),
+                    variable("days"),
+                ),
+                case(held(vec![]), int_lit(0)),
+                case(Pattern::WildcardPattern(attrs()), int_lit(0)),
+            ],
+        );
+        let diagnostics = check(vec![(
+            "days",
+            ValueDefinition::new(vec![input("s", status)], int(), body),
+        )]);
+        assert_eq!(codes(&diagnostics), vec!["constructor-arity"]);
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        let body = Value::Tuple(
+            attrs(),
+            vec![
+                variable("missing"),
+                Value::Reference(attrs(), fq("my/pkg:orders#gone")),
+                Value::Reference(attrs(), fq("morphir/sdk:basics#add")),
+            ],
+        );
+        let tuple = Type::tuple(TypeAttributes::default(), vec![int(), int(), int()]);
+        let diagnostics = check(vec![("names", ValueDefinition::new(vec![], tuple, body))]);
+
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["unknown-variable", "unresolved-reference"]
+        );
+        assert!(diagnostics[0].is_error());
+        assert!(!diagnostics[1].is_error());
+    }
+
+    #[test]
+    fn test_dependencies_are_checked_against_their_specification() {
+        let Distribution::Library(mut money) = library(vec![]) else {
+            unreachable!()
+        };
+        money.package_name = PackageName::new(Path::new("acme/money"));
+        money.def.modules["orders"].value.values["inc"].access = Access::Private;
+        let mut dist = library(vec![(
+            "uses",
+            ValueDefinition::new(
+                vec![],
+                Type::tuple(TypeAttributes::default(), vec![named(INT), named(INT)]),
+                Value::Tuple(
+                    attrs(),
+                    vec![
+                        apply(
+                            Value::Constructor(attrs(), fq("acme/money:orders#held")),
+                            string_lit("soon"),
+                        ),
+                        apply(
+                            Value::Reference(attrs(), fq("acme/money:orders#inc")),
+                            int_lit(1),
+                        ),
+                    ],
+                ),
+            ),
+        )]);
+        dist.add_dependency(&Distribution::Library(money));
+
+        let diagnostics = typecheck_distribution(&dist);
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["type-mismatch", "not-in-specification", "type-mismatch"]
+        );
+        assert!(diagnostics[1].is_error());
+        assert!(diagnostics[1].message.contains("acme/money:orders#inc"));
+    }
+
+    #[test]
+    fn test_number_literals_only_unify_with_numbers() {
+        let diagnostics = check(vec![
+            (
+                "rate",
+                ValueDefinition::new(vec![], named(FLOAT), int_lit(2)),
+            ),
+            (
+                "flag",
+                ValueDefinition::new(vec![], named(BOOL), int_lit(1)),
+            ),
+        ]);
+        assert_eq!(codes(&diagnostics), vec!["type-mismatch"]);
+        assert_eq!(diagnostics[0].definition, fq("my/pkg:orders#flag"));
+    }
+
+    #[test]
+    fn test_par_check_package_matches_check_package() {
+        let dist = library(vec![
+            (
+                "rate",
+                ValueDefinition::new(vec![], named(FLOAT), int_lit(2)),
+            ),
+            (
+                "flag",
+                ValueDefinition::new(vec![], named(BOOL), int_lit(1)),
+            ),
+        ]);
+        let checker = TypeChecker::new(&dist);
+        let diagnostics = checker.check_package();
+        assert_eq!(codes(&diagnostics), vec!["type-mismatch"]);
+        assert_eq!(checker.par_check_package(2), diagnostics);
+    }
+
+    #[test]
+    fn test_entry_points_must_target_package_values() {
+        let Distribution::Library(lib) = library(vec![]) else {
+            unreachable!()
+        };
+        let entry = |target: &str| EntryPoint {
+            target: target.to_string(),
+            kind: EntryPointKind::Main,
+            doc: None,
+        };
+        let mut entry_points = IndexMap::new();
+        entry_points.insert("ok".to_string(), entry("my/pkg:orders#inc"));
+        entry_points.insert("missing".to_string(), entry("my/pkg:orders#ship"));
+        entry_points.insert("broken".to_string(), entry("not a name"));
+        let dist = Distribution::Application(ApplicationContent {
+            package_name: lib.package_name,
+            dependencies: lib.dependencies,
+            def: lib.def,
+            entry_points,
+        });
+
+        let diagnostics = typecheck_distribution(&dist);
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["unknown-entry-point", "invalid-entry-point"]
+        );
+        assert_eq!(diagnostics[0].definition, fq("my/pkg:orders#ship"));
+    }
+}
@@ -11,9 +11,11 @@
 //! let t: Type = Type::Unit(TypeAttributes::default());
 //! ```
 
-use serde::de::{self, Deserializer};
+use schemars::JsonSchema;
+use serde::de::{self, Deserializer, IgnoredAny, MapAccess, Visitor};
 use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 use super::access::AccessControlled;
 use super::attributes::TypeAttributes;
@@ -98,6 +100,19 @@ impl Type {
         }
     }
 
+    /// Get the attributes of this type, mutably
+    pub fn attributes_mut(&mut self) -> &mut TypeAttributes {
+        match self {
+            Type::Variable(a, _) => a,
+            Type::Reference(a, _, _) => a,
+            Type::Tuple(a, _) => a,
+            Type::Record(a, _) => a,
+            Type::ExtensibleRecord(a, _, _) => a,
+            Type::Function(a, _, _) => a,
+            Type::Unit(a) => a,
+        }
+    }
+
     /// Create a variable type
     pub fn variable(attrs: TypeAttributes, name: Name) -> Self {
         Type::Variable(attrs, name)
@@ -148,7 +163,7 @@ impl Field {
 /// Type specification (public API view of a type)
 // The variant names include "Specification" suffix as per the Morphir specification
 #[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum TypeSpecification {
     /// Type alias specification
@@ -167,7 +182,7 @@ pub enum TypeSpecification {
 }
 
 /// Constructor specification
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ConstructorSpecification {
     pub name: Name,
@@ -175,7 +190,7 @@ pub struct ConstructorSpecification {
 }
 
 /// Constructor argument specification
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ConstructorArgSpec {
     pub name: Name,
@@ -217,31 +232,43 @@ impl<'de> Deserialize<'de> for Incompleteness {
     where
         D: Deserializer<'de>,
     {
-        let value = serde_json::Value::deserialize(deserializer)?;
-        match &value {
-            serde_json::Value::Object(map) => {
-                if let Some((key, content)) = map.iter().next() {
-                    match key.as_str() {
-                        "Draft" => Ok(Incompleteness::Draft),
-                        "Hole" => {
-                            let reason: HoleReason = serde_json::from_value(content.clone())
-                                .map_err(de::Error::custom)?;
-                            Ok(Incompleteness::Hole(reason))
-                        }
-                        _ => Err(de::Error::unknown_variant(key, &["Draft", "Hole"])),
-                    }
-                } else {
-                    Err(de::Error::custom("empty object for Incompleteness"))
-                }
+        deserializer.deserialize_any(IncompletenessVisitor)
+    }
+}
+
+struct IncompletenessVisitor;
+
+impl<'de> Visitor<'de> for IncompletenessVisitor {
+    type Value = Incompleteness;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("object or string for Incompleteness")
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<Incompleteness, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let tag: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::custom("empty object for Incompleteness"))?;
+        let incompleteness = match tag.as_str() {
+            "Draft" => {
+                map.next_value::<IgnoredAny>()?;
+                Incompleteness::Draft
             }
-            // Also accept string format for backward compatibility (Draft only)
-            serde_json::Value::String(s) => match s.as_str() {
-                "Draft" => Ok(Incompleteness::Draft),
-                _ => Err(de::Error::unknown_variant(s, &["Draft"])),
-            },
-            _ => Err(de::Error::custom(
-                "expected object or string for Incompleteness",
-            )),
+            "Hole" => Incompleteness::Hole(map.next_value()?),
+            _ => return Err(de::Error::unknown_variant(&tag, &["Draft", "Hole"])),
+        };
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(incompleteness)
+    }
+
+    // Also accept string format for backward compatibility (Draft only)
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Incompleteness, E> {
+        match s {
+            "Draft" => Ok(Incompleteness::Draft),
+            _ => Err(de::Error::unknown_variant(s, &["Draft"])),
         }
     }
 }
@@ -350,41 +377,65 @@ impl<'de> Deserialize<'de> for TypeDefinition {
     where
         D: Deserializer<'de>,
     {
-        let value = serde_json::Value::deserialize(deserializer)?;
-        if let serde_json::Value::Object(map) = &value {
-            if let Some(content) = map.get("TypeAliasDefinition") {
-                let parsed: TypeAliasDefContent =
-                    serde_json::from_value(content.clone()).map_err(de::Error::custom)?;
-                return Ok(TypeDefinition::TypeAliasDefinition {
-                    type_params: parsed.type_params,
-                    type_expr: parsed.type_exp,
-                });
-            }
-            if let Some(content) = map.get("CustomTypeDefinition") {
-                let parsed: CustomTypeDefContent =
-                    serde_json::from_value(content.clone()).map_err(de::Error::custom)?;
-                return Ok(TypeDefinition::CustomTypeDefinition {
-                    type_params: parsed.type_params,
-                    constructors: parsed.constructors,
-                });
-            }
-            if let Some(content) = map.get("IncompleteTypeDefinition") {
-                let parsed: IncompleteTypeDefContent =
-                    serde_json::from_value(content.clone()).map_err(de::Error::custom)?;
-                return Ok(TypeDefinition::IncompleteTypeDefinition {
-                    type_params: parsed.type_params,
-                    incompleteness: parsed.incompleteness,
-                });
+        deserializer.deserialize_map(TypeDefinitionVisitor)
+    }
+}
+
+struct TypeDefinitionVisitor;
+
+impl<'de> Visitor<'de> for TypeDefinitionVisitor {
+    type Value = TypeDefinition;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(
+            "TypeAliasDefinition, CustomTypeDefinition, or IncompleteTypeDefinition wrapper",
+        )
+    }
+
+    // The first wrapper key wins; any other keys are skipped
+    fn visit_map<M>(self, mut map: M) -> Result<TypeDefinition, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut definition = None;
+        while let Some(tag) = map.next_key::<String>()? {
+            match tag.as_str() {
+                "TypeAliasDefinition" if definition.is_none() => {
+                    let parsed: TypeAliasDefContent = map.next_value()?;
+                    definition = Some(TypeDefinition::TypeAliasDefinition {
+                        type_params: parsed.type_params,
+                        type_expr: parsed.type_exp,
+                    });
+                }
+                "CustomTypeDefinition" if definition.is_none() => {
+                    let parsed: CustomTypeDefContent = map.next_value()?;
+                    definition = Some(TypeDefinition::CustomTypeDefinition {
+                        type_params: parsed.type_params,
+                        constructors: parsed.constructors,
+                    });
+                }
+                "IncompleteTypeDefinition" if definition.is_none() => {
+                    let parsed: IncompleteTypeDefContent = map.next_value()?;
+                    definition = Some(TypeDefinition::IncompleteTypeDefinition {
+                        type_params: parsed.type_params,
+                        incompleteness: parsed.incompleteness,
+                    });
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
             }
         }
-        Err(de::Error::custom(
-            "expected TypeAliasDefinition, CustomTypeDefinition, or IncompleteTypeDefinition wrapper",
-        ))
+        definition.ok_or_else(|| {
+            de::Error::custom(
+                "expected TypeAliasDefinition, CustomTypeDefinition, or IncompleteTypeDefinition wrapper",
+            )
+        })
     }
 }
 
 /// Constructor definition
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ConstructorDefinition {
     pub name: Name,
@@ -392,7 +443,7 @@ pub struct ConstructorDefinition {
 }
 
 /// Constructor argument
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ConstructorArg {
     pub name: Name,
@@ -22,15 +22,17 @@
 //! ```
 
 use indexmap::IndexMap;
-use serde::de::{self, Deserializer};
+use schemars::JsonSchema;
+use serde::de::{self, Deserializer, IgnoredAny, MapAccess, Visitor};
 use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 use super::attributes::ValueAttributes;
 use super::literal::Literal;
 use super::pattern::Pattern;
 use super::types::Type;
-use crate::naming::{FQName, Name};
+use crate::naming::{FQName, Name, fqname};
 
 // ============================================================================
 // VALUE EXPRESSIONS
@@ -194,7 +196,7 @@ pub enum NativeHint {
 }
 
 /// Information about a native operation (V4 only)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct NativeInfo {
     pub hint: NativeHint,
     pub description: Option<String>,
@@ -270,6 +272,33 @@ impl Value {
         }
     }
 
+    /// Get the attributes of this value, mutably
+    pub fn attributes_mut(&mut self) -> &mut ValueAttributes {
+        match self {
+            Value::Literal(a, _) => a,
+            Value::Constructor(a, _) => a,
+            Value::Tuple(a, _) => a,
+            Value::List(a, _) => a,
+            Value::Record(a, _) => a,
+            Value::Variable(a, _) => a,
+            Value::Reference(a, _) => a,
+            Value::Field(a, _, _) => a,
+            Value::FieldFunction(a, _) => a,
+            Value::Apply(a, _, _) => a,
+            Value::Lambda(a, _, _) => a,
+            Value::LetDefinition(a, _, _, _) => a,
+            Value::LetRecursion(a, _, _) => a,
+            Value::Destructure(a, _, _, _) => a,
+            Value::IfThenElse(a, _, _, _) => a,
+            Value::PatternMatch(a, _, _) => a,
+            Value::UpdateRecord(a, _, _) => a,
+            Value::Unit(a) => a,
+            Value::Hole(a, _, _) => a,
+            Value::Native(a, _, _) => a,
+            Value::External(a, _, _) => a,
+        }
+    }
+
     /// Create a literal value
     pub fn literal(attrs: ValueAttributes, lit: Literal) -> Self {
         Value::Literal(attrs, lit)
@@ -417,7 +446,7 @@ impl NativeInfo {
 // ============================================================================
 
 /// Value specification (just the signature)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ValueSpecification {
     pub inputs: IndexMap<String, Type>,
@@ -431,7 +460,7 @@ pub struct ValueSpecification {
 /// A value definition (function or constant)
 ///
 /// V4 format supports multiple body types (Expression, Native, External, Incomplete).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ValueDefinition {
     pub input_types: IndexMap<String, InputTypeEntry>,
@@ -440,7 +469,7 @@ pub struct ValueDefinition {
 }
 
 /// Input type entry
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct InputTypeEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -565,44 +594,69 @@ impl<'de> Deserialize<'de> for ValueBody {
     where
         D: Deserializer<'de>,
     {
-        let value = serde_json::Value::deserialize(deserializer)?;
-        if let serde_json::Value::Object(map) = &value {
-            if let Some(content) = map.get("ExpressionBody") {
-                let body_json = content
-                    .get("body")
-                    .ok_or_else(|| de::Error::missing_field("body"))?;
-                let body: Value =
-                    serde_json::from_value(body_json.clone()).map_err(de::Error::custom)?;
-                return Ok(ValueBody::Expression(body));
-            }
-            if let Some(content) = map.get("NativeBody") {
-                let parsed: NativeBodySerContent =
-                    serde_json::from_value(content.clone()).map_err(de::Error::custom)?;
-                return Ok(ValueBody::Native(NativeInfo {
-                    hint: parsed.hint,
-                    description: parsed.description,
-                }));
-            }
-            if let Some(content) = map.get("ExternalBody") {
-                let parsed: ExternalBodySerContent =
-                    serde_json::from_value(content.clone()).map_err(de::Error::custom)?;
-                return Ok(ValueBody::External {
-                    external_name: parsed.external_name,
-                    target_platform: parsed.target_platform,
-                });
-            }
-            if let Some(content) = map.get("IncompleteBody") {
-                let reason_val = content
-                    .get("reason")
-                    .ok_or_else(|| de::Error::missing_field("reason"))?;
-                let reason: HoleReason =
-                    serde_json::from_value(reason_val.clone()).map_err(de::Error::custom)?;
-                return Ok(ValueBody::Incomplete(reason));
+        deserializer.deserialize_map(ValueBodyVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+struct ExpressionBodyDeContent {
+    body: Value,
+}
+
+#[derive(Deserialize)]
+struct IncompleteBodyDeContent {
+    reason: HoleReason,
+}
+
+struct ValueBodyVisitor;
+
+impl<'de> Visitor<'de> for ValueBodyVisitor {
+    type Value = ValueBody;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("ExpressionBody, NativeBody, ExternalBody, or IncompleteBody wrapper")
+    }
+
+    // The first wrapper key wins; any other keys are skipped
+    fn visit_map<M>(self, mut map: M) -> Result<ValueBody, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut body = None;
+        while let Some(tag) = map.next_key::<String>()? {
+            match tag.as_str() {
+                "ExpressionBody" if body.is_none() => {
+                    let parsed: ExpressionBodyDeContent = map.next_value()?;
+                    body = Some(ValueBody::Expression(parsed.body));
+                }
+                "NativeBody" if body.is_none() => {
+                    let parsed: NativeBodySerContent = map.next_value()?;
+                    body = Some(ValueBody::Native(NativeInfo {
+                        hint: parsed.hint,
+                        description: parsed.description,
+                    }));
+                }
+                "ExternalBody" if body.is_none() => {
+                    let parsed: ExternalBodySerContent = map.next_value()?;
+                    body = Some(ValueBody::External {
+                        external_name: parsed.external_name,
+                        target_platform: parsed.target_platform,
+                    });
+                }
+                "IncompleteBody" if body.is_none() => {
+                    let parsed: IncompleteBodyDeContent = map.next_value()?;
+                    body = Some(ValueBody::Incomplete(parsed.reason));
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
             }
         }
-        Err(de::Error::custom(
-            "expected ExpressionBody, NativeBody, ExternalBody, or IncompleteBody wrapper",
-        ))
+        body.ok_or_else(|| {
+            de::Error::custom(
+                "expected ExpressionBody, NativeBody, ExternalBody, or IncompleteBody wrapper",
+            )
+        })
     }
 }
 
@@ -637,61 +691,67 @@ impl<'de> Deserialize<'de> for NativeHint {
     where
         D: Deserializer<'de>,
     {
-        let value = serde_json::Value::deserialize(deserializer)?;
-        match &value {
-            serde_json::Value::Object(map) => {
-                if let Some((key, content)) = map.iter().next() {
-                    match key.as_str() {
-                        "Arithmetic" => Ok(NativeHint::Arithmetic),
-                        "Comparison" => Ok(NativeHint::Comparison),
-                        "StringOp" => Ok(NativeHint::StringOp),
-                        "CollectionOp" => Ok(NativeHint::CollectionOp),
-                        "PlatformSpecific" => {
-                            let platform = content
-                                .get("platform")
-                                .and_then(|p| p.as_str())
-                                .unwrap_or("unknown")
-                                .to_string();
-                            Ok(NativeHint::PlatformSpecific { platform })
-                        }
-                        _ => Err(de::Error::unknown_variant(
-                            key,
-                            &[
-                                "Arithmetic",
-                                "Comparison",
-                                "StringOp",
-                                "CollectionOp",
-                                "PlatformSpecific",
-                            ],
-                        )),
-                    }
-                } else {
-                    Err(de::Error::custom("empty object for NativeHint"))
+        deserializer.deserialize_any(NativeHintVisitor)
+    }
+}
+
+const NATIVE_HINTS: &[&str] = &[
+    "Arithmetic",
+    "Comparison",
+    "StringOp",
+    "CollectionOp",
+    "PlatformSpecific",
+];
+
+#[derive(Deserialize)]
+struct PlatformSpecificContent {
+    platform: Option<String>,
+}
+
+struct NativeHintVisitor;
+
+impl<'de> Visitor<'de> for NativeHintVisitor {
+    type Value = NativeHint;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("object or string for NativeHint")
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<NativeHint, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let tag: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::custom("empty object for NativeHint"))?;
+        let hint = match tag.as_str() {
+            "PlatformSpecific" => {
+                let content: PlatformSpecificContent = map.next_value()?;
+                NativeHint::PlatformSpecific {
+                    platform: content.platform.unwrap_or_else(|| "unknown".to_string()),
                 }
             }
-            // Also accept string format for backward compatibility
-            serde_json::Value::String(s) => match s.as_str() {
-                "Arithmetic" => Ok(NativeHint::Arithmetic),
-                "Comparison" => Ok(NativeHint::Comparison),
-                "StringOp" => Ok(NativeHint::StringOp),
-                "CollectionOp" => Ok(NativeHint::CollectionOp),
-                "PlatformSpecific" => Ok(NativeHint::PlatformSpecific {
-                    platform: "unknown".to_string(),
-                }),
-                _ => Err(de::Error::unknown_variant(
-                    s,
-                    &[
-                        "Arithmetic",
-                        "Comparison",
-                        "StringOp",
-                        "CollectionOp",
-                        "PlatformSpecific",
-                    ],
-                )),
-            },
-            _ => Err(de::Error::custom(
-                "expected object or string for NativeHint",
-            )),
+            _ => {
+                let hint = self.visit_str(&tag)?;
+                map.next_value::<IgnoredAny>()?;
+                hint
+            }
+        };
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(hint)
+    }
+
+    // Also accept string format for backward compatibility
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<NativeHint, E> {
+        match s {
+            "Arithmetic" => Ok(NativeHint::Arithmetic),
+            "Comparison" => Ok(NativeHint::Comparison),
+            "StringOp" => Ok(NativeHint::StringOp),
+            "CollectionOp" => Ok(NativeHint::CollectionOp),
+            "PlatformSpecific" => Ok(NativeHint::PlatformSpecific {
+                platform: "unknown".to_string(),
+            }),
+            _ => Err(de::Error::unknown_variant(s, NATIVE_HINTS)),
         }
     }
 }
@@ -730,66 +790,89 @@ impl<'de> Deserialize<'de> for HoleReason {
     where
         D: Deserializer<'de>,
     {
-        let value = serde_json::Value::deserialize(deserializer)?;
-        match &value {
-            serde_json::Value::Object(map) => {
-                if let Some((key, content)) = map.iter().next() {
-                    match key.as_str() {
-                        "Draft" => Ok(HoleReason::Draft),
-                        "TypeMismatch" => {
-                            let expected = content
-                                .get("expected")
-                                .and_then(|v| v.as_str())
-                                .ok_or_else(|| de::Error::missing_field("expected"))?
-                                .to_string();
-                            let found = content
-                                .get("found")
-                                .and_then(|v| v.as_str())
-                                .ok_or_else(|| de::Error::missing_field("found"))?
-                                .to_string();
-                            Ok(HoleReason::TypeMismatch { expected, found })
-                        }
-                        "DeletedDuringRefactor" => {
-                            let tx_id = content
-                                .get("tx-id")
-                                .and_then(|v| v.as_str())
-                                .ok_or_else(|| de::Error::missing_field("tx-id"))?
-                                .to_string();
-                            Ok(HoleReason::DeletedDuringRefactor { tx_id })
-                        }
-                        "UnresolvedReference" => {
-                            let target = content
-                                .get("target")
-                                .and_then(|t| t.as_str())
-                                .ok_or_else(|| de::Error::missing_field("target"))?
-                                .to_string();
-                            Ok(HoleReason::UnresolvedReference {
-                                target: FQName::from_canonical_string(&target)
-                                    .map_err(de::Error::custom)?,
-                            })
-                        }
-                        _ => Err(de::Error::unknown_variant(
-                            key,
-                            &[
-                                "Draft",
-                                "TypeMismatch",
-                                "DeletedDuringRefactor",
-                                "UnresolvedReference",
-                            ],
-                        )),
-                    }
-                } else {
-                    Err(de::Error::custom("empty object for HoleReason"))
+        deserializer.deserialize_any(HoleReasonVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+struct TypeMismatchContent {
+    expected: String,
+    found: String,
+}
+
+#[derive(Deserialize)]
+struct DeletedDuringRefactorContent {
+    #[serde(rename = "tx-id")]
+    tx_id: String,
+}
+
+#[derive(Deserialize)]
+struct UnresolvedReferenceContent {
+    #[serde(deserialize_with = "fqname::canonical::deserialize")]
+    target: FQName,
+}
+
+struct HoleReasonVisitor;
+
+impl<'de> Visitor<'de> for HoleReasonVisitor {
+    type Value = HoleReason;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("object or string for HoleReason")
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<HoleReason, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let tag: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::custom("empty object for HoleReason"))?;
+        let reason = match tag.as_str() {
+            "Draft" => {
+                map.next_value::<IgnoredAny>()?;
+                HoleReason::Draft
+            }
+            "TypeMismatch" => {
+                let content: TypeMismatchContent = map.next_value()?;
+                HoleReason::TypeMismatch {
+                    expected: content.expected,
+                    found: content.found,
                 }
             }
-            // Also accept string format for backward compatibility (Draft only)
-            serde_json::Value::String(s) => match s.as_str() {
-                "Draft" => Ok(HoleReason::Draft),
-                _ => Err(de::Error::unknown_variant(s, &["Draft"])),
-            },
-            _ => Err(de::Error::custom(
-                "expected object or string for HoleReason",
-            )),
+            "DeletedDuringRefactor" => {
+                let content: DeletedDuringRefactorContent = map.next_value()?;
+                HoleReason::DeletedDuringRefactor {
+                    tx_id: content.tx_id,
+                }
+            }
+            "UnresolvedReference" => {
+                let content: UnresolvedReferenceContent = map.next_value()?;
+                HoleReason::UnresolvedReference {
+                    target: content.target,
+                }
+            }
+            _ => {
+                return Err(de::Error::unknown_variant(
+                    &tag,
+                    &[
+                        "Draft",
+                        "TypeMismatch",
+                        "DeletedDuringRefactor",
+                        "UnresolvedReference",
+                    ],
+                ));
+            }
+        };
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(reason)
+    }
+
+    // Also accept string format for backward compatibility (Draft only)
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<HoleReason, E> {
+        match s {
+            "Draft" => Ok(HoleReason::Draft),
+            _ => Err(de::Error::unknown_variant(s, &["Draft"])),
         }
     }
 }
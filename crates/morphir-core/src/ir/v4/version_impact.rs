@@ -0,0 +1,167 @@
+//! Semantic versioning impact of a new version of a package
+//!
+//! [`version_impact`] compares a distribution against the version of it
+//! published last and classifies the changes by the [`Bump`] they call for.
+//! Changes to the public specification are found with
+//! [`diff_specifications`]: a breaking change, such as removing an exposed
+//! value or changing its signature, calls for a major bump, and an additive
+//! one, such as exposing a new value, for a minor bump. Changes that leave
+//! the specification as it was, to value bodies or private definitions,
+//! call for a patch.
+//!
+//! While the major version is 0 the API is not yet stable, so each bump
+//! moves one place down: breaking changes bump the minor version and
+//! anything else the patch version.
+
+use std::fmt;
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use super::distribution::Distribution;
+use super::spec_diff::{Impact, SpecChange, diff_specifications};
+
+/// Part of a version a set of changes calls for bumping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    /// Nothing changed
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// The version after `previous` for changes calling for this bump,
+    /// dropping any pre-release and build metadata
+    pub fn apply(self, previous: &Version) -> Version {
+        let bump = match (self, previous.major) {
+            (Bump::Major, 0) => Bump::Minor,
+            (Bump::Minor, 0) => Bump::Patch,
+            (bump, _) => bump,
+        };
+        let (major, minor, patch) = (previous.major, previous.minor, previous.patch);
+        match bump {
+            Bump::None => previous.clone(),
+            Bump::Patch => Version::new(major, minor, patch + 1),
+            Bump::Minor => Version::new(major, minor + 1, 0),
+            Bump::Major => Version::new(major + 1, 0, 0),
+        }
+    }
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Bump::None => "none",
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        })
+    }
+}
+
+/// Changes between two versions of a package, and the bump they call for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionImpact {
+    pub bump: Bump,
+    /// Changes to the public specification
+    pub changes: Vec<SpecChange>,
+    /// Whether definitions changed without changing the specification
+    pub implementation_changed: bool,
+}
+
+impl VersionImpact {
+    /// The version to publish after `previous`
+    pub fn next_version(&self, previous: &Version) -> Version {
+        self.bump.apply(previous)
+    }
+
+    /// Whether `version` may follow `previous` with these changes: it must
+    /// be at least the version they call for
+    pub fn allows(&self, previous: &Version, version: &Version) -> bool {
+        *version >= self.next_version(previous)
+    }
+}
+
+/// Compare `new` against the version `old` published last
+pub fn version_impact(old: &Distribution, new: &Distribution) -> VersionImpact {
+    let diff = diff_specifications(&old.specification(), &new.specification());
+    let implementation_changed = old.definition() != new.definition();
+    let bump = if diff.is_breaking() {
+        Bump::Major
+    } else if diff.with_impact(Impact::Additive).next().is_some() {
+        Bump::Minor
+    } else if implementation_changed {
+        Bump::Patch
+    } else {
+        Bump::None
+    };
+    VersionImpact {
+        bump,
+        changes: diff.changes,
+        implementation_changed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::parse;
+    use super::*;
+
+    fn library(values: &str) -> Distribution {
+        parse(&format!(
+            "library acme/shop\n\n\nmodule orders\n\n{}\n\nprivate fee : morphir/sdk:basics#Int =\n    1\n",
+            values
+        ))
+    }
+
+    const TOTAL: &str = "total : morphir/sdk:basics#Int =\n    1";
+
+    #[test]
+    fn test_bumps_follow_the_api_changes() {
+        let old = library(TOTAL);
+        assert_eq!(version_impact(&old, &old).bump, Bump::None);
+
+        let body = library("total : morphir/sdk:basics#Int =\n    2");
+        let impact = version_impact(&old, &body);
+        assert_eq!(impact.bump, Bump::Patch);
+        assert!(impact.changes.is_empty() && impact.implementation_changed);
+
+        let added = library(&format!(
+            "{}\n\ncount : morphir/sdk:basics#Int =\n    0",
+            TOTAL
+        ));
+        assert_eq!(version_impact(&old, &added).bump, Bump::Minor);
+
+        let removed = library("count : morphir/sdk:basics#Int =\n    0");
+        let impact = version_impact(&old, &removed);
+        assert_eq!(impact.bump, Bump::Major);
+        assert_eq!(impact.changes.len(), 2);
+    }
+
+    #[test]
+    fn test_next_version() {
+        let version = |v: &str| Version::parse(v).unwrap();
+        assert_eq!(Bump::Major.apply(&version("1.4.2")), version("2.0.0"));
+        assert_eq!(Bump::Minor.apply(&version("1.4.2")), version("1.5.0"));
+        assert_eq!(
+            Bump::Patch.apply(&version("1.4.2-beta.1")),
+            version("1.4.3")
+        );
+        assert_eq!(Bump::None.apply(&version("1.4.2")), version("1.4.2"));
+        // Before 1.0 each bump moves one place down
+        assert_eq!(Bump::Major.apply(&version("0.3.1")), version("0.4.0"));
+        assert_eq!(Bump::Minor.apply(&version("0.3.1")), version("0.3.2"));
+
+        let impact = version_impact(
+            &library(TOTAL),
+            &library("count : morphir/sdk:basics#Int =\n    0"),
+        );
+        assert!(impact.allows(&version("1.4.2"), &version("2.0.0")));
+        assert!(impact.allows(&version("1.4.2"), &version("3.0.0")));
+        assert!(!impact.allows(&version("1.4.2"), &version("1.5.0")));
+    }
+}
@@ -0,0 +1,1059 @@
+//! Walking a whole distribution.
+//!
+//! [`walk_distribution`] visits every module, definition, type, value, and
+//! pattern of a distribution, calling the hooks of a [`DistributionVisitor`]
+//! on the way into each node and on the way out, with a [`VisitContext`]
+//! naming the package, module, and definition being walked.
+//! [`walk_distribution_mut`] does the same with a [`DistributionVisitorMut`],
+//! whose hooks may change or replace the nodes they are given: changes made
+//! on the way in are walked into, and changes made on the way out see
+//! children that have already been walked.
+//!
+//! Every hook does nothing by default, so a visitor implements only the ones
+//! it needs. An `enter_` hook returns [`Walk::Skip`] to leave out the children
+//! of its node; the matching `exit_` hook is called either way. The
+//! specifications of dependencies are only walked for visitors that ask for
+//! them with `dependencies`.
+//!
+//! Nodes are walked in the order they appear in the IR. Within a value
+//! definition that is the input types, the output type, then the body; let
+//! definitions inside a body are walked the same way.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! struct References(Vec<FQName>);
+//!
+//! impl DistributionVisitor for References {
+//!     fn enter_value(&mut self, _context: &VisitContext, value: &Value) -> Walk {
+//!         if let Value::Reference(_, fqname) = value {
+//!             self.0.push(fqname.clone());
+//!         }
+//!         Walk::Continue
+//!     }
+//! }
+//!
+//! let mut references = References(Vec::new());
+//! walk_distribution(&mut references, &ir_file.distribution);
+//! ```
+
+use super::access::AccessControlled;
+use super::distribution::{Dependencies, Distribution};
+use super::module::{ModuleDefinition, ModuleSpecification};
+use super::package::{PackageDefinition, PackageSpecification};
+use super::pattern::Pattern;
+use super::types::{Type, TypeDefinition, TypeSpecification};
+use super::value::{Value, ValueBody, ValueDefinition, ValueSpecification};
+use crate::naming::{FQName, Name, PackageName, Path};
+
+/// Whether to walk the children of a node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Walk {
+    /// Walk the children
+    #[default]
+    Continue,
+    /// Leave the children out
+    Skip,
+}
+
+/// Where in the distribution a node is
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisitContext {
+    /// Package being walked: the distribution's own, or a dependency
+    pub package: PackageName,
+    /// Whether the package is a dependency, known only by its specification
+    pub dependency: bool,
+    /// Module being walked
+    pub module: Option<Path>,
+    /// Type or value definition being walked
+    pub definition: Option<FQName>,
+}
+
+impl VisitContext {
+    pub(super) fn package(package: PackageName, dependency: bool) -> Self {
+        VisitContext {
+            package,
+            dependency,
+            module: None,
+            definition: None,
+        }
+    }
+
+    pub(super) fn in_module(&self, module_key: &str) -> Self {
+        VisitContext {
+            module: Some(Path::new(module_key)),
+            definition: None,
+            ..self.clone()
+        }
+    }
+
+    pub(super) fn in_definition(&self, name: &str) -> Self {
+        let module = self.module.clone().unwrap_or_else(|| Path::new(""));
+        VisitContext {
+            definition: Some(FQName::new(
+                self.package.as_path().clone(),
+                module,
+                Name::from(name),
+            )),
+            ..self.clone()
+        }
+    }
+}
+
+/// Hooks called while walking a distribution with [`walk_distribution`]
+#[allow(unused_variables)]
+pub trait DistributionVisitor {
+    /// Whether to walk the specifications of dependencies too
+    fn dependencies(&self) -> bool {
+        false
+    }
+
+    fn enter_distribution(&mut self, distribution: &Distribution) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_distribution(&mut self, distribution: &Distribution) {}
+
+    fn enter_module(
+        &mut self,
+        context: &VisitContext,
+        module: &AccessControlled<ModuleDefinition>,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_module(&mut self, context: &VisitContext, module: &AccessControlled<ModuleDefinition>) {
+    }
+
+    fn enter_module_specification(
+        &mut self,
+        context: &VisitContext,
+        module: &ModuleSpecification,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_module_specification(&mut self, context: &VisitContext, module: &ModuleSpecification) {}
+
+    fn enter_type_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &AccessControlled<TypeDefinition>,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_type_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &AccessControlled<TypeDefinition>,
+    ) {
+    }
+
+    fn enter_type_specification(
+        &mut self,
+        context: &VisitContext,
+        specification: &TypeSpecification,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_type_specification(
+        &mut self,
+        context: &VisitContext,
+        specification: &TypeSpecification,
+    ) {
+    }
+
+    fn enter_value_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &AccessControlled<ValueDefinition>,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_value_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &AccessControlled<ValueDefinition>,
+    ) {
+    }
+
+    fn enter_value_specification(
+        &mut self,
+        context: &VisitContext,
+        specification: &ValueSpecification,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_value_specification(
+        &mut self,
+        context: &VisitContext,
+        specification: &ValueSpecification,
+    ) {
+    }
+
+    fn enter_type(&mut self, context: &VisitContext, tpe: &Type) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_type(&mut self, context: &VisitContext, tpe: &Type) {}
+
+    fn enter_value(&mut self, context: &VisitContext, value: &Value) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_value(&mut self, context: &VisitContext, value: &Value) {}
+
+    fn enter_pattern(&mut self, context: &VisitContext, pattern: &Pattern) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_pattern(&mut self, context: &VisitContext, pattern: &Pattern) {}
+}
+
+/// Hooks called while walking a distribution with [`walk_distribution_mut`].
+/// Each may change or replace the node it is given.
+#[allow(unused_variables)]
+pub trait DistributionVisitorMut {
+    /// Whether to walk the specifications of dependencies too
+    fn dependencies(&self) -> bool {
+        false
+    }
+
+    fn enter_distribution(&mut self, distribution: &mut Distribution) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_distribution(&mut self, distribution: &mut Distribution) {}
+
+    fn enter_module(
+        &mut self,
+        context: &VisitContext,
+        module: &mut AccessControlled<ModuleDefinition>,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_module(
+        &mut self,
+        context: &VisitContext,
+        module: &mut AccessControlled<ModuleDefinition>,
+    ) {
+    }
+
+    fn enter_module_specification(
+        &mut self,
+        context: &VisitContext,
+        module: &mut ModuleSpecification,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_module_specification(
+        &mut self,
+        context: &VisitContext,
+        module: &mut ModuleSpecification,
+    ) {
+    }
+
+    fn enter_type_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &mut AccessControlled<TypeDefinition>,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_type_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &mut AccessControlled<TypeDefinition>,
+    ) {
+    }
+
+    fn enter_type_specification(
+        &mut self,
+        context: &VisitContext,
+        specification: &mut TypeSpecification,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_type_specification(
+        &mut self,
+        context: &VisitContext,
+        specification: &mut TypeSpecification,
+    ) {
+    }
+
+    fn enter_value_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &mut AccessControlled<ValueDefinition>,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_value_definition(
+        &mut self,
+        context: &VisitContext,
+        definition: &mut AccessControlled<ValueDefinition>,
+    ) {
+    }
+
+    fn enter_value_specification(
+        &mut self,
+        context: &VisitContext,
+        specification: &mut ValueSpecification,
+    ) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_value_specification(
+        &mut self,
+        context: &VisitContext,
+        specification: &mut ValueSpecification,
+    ) {
+    }
+
+    fn enter_type(&mut self, context: &VisitContext, tpe: &mut Type) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_type(&mut self, context: &VisitContext, tpe: &mut Type) {}
+
+    fn enter_value(&mut self, context: &VisitContext, value: &mut Value) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_value(&mut self, context: &VisitContext, value: &mut Value) {}
+
+    fn enter_pattern(&mut self, context: &VisitContext, pattern: &mut Pattern) -> Walk {
+        Walk::Continue
+    }
+
+    fn exit_pattern(&mut self, context: &VisitContext, pattern: &mut Pattern) {}
+}
+
+// =============================================================================
+// Walking
+// =============================================================================
+
+/// Walk `distribution` with `visitor`
+pub fn walk_distribution<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    distribution: &Distribution,
+) {
+    if visitor.enter_distribution(distribution) == Walk::Continue {
+        let context = VisitContext::package(distribution.package_name().clone(), false);
+        match distribution {
+            Distribution::Library(lib) => walk_package_definition(visitor, &context, &lib.def),
+            Distribution::Application(app) => walk_package_definition(visitor, &context, &app.def),
+            Distribution::Specs(specs) => {
+                walk_package_specification(visitor, &context, &specs.spec)
+            }
+        }
+        if visitor.dependencies() {
+            walk_dependencies(visitor, distribution.dependencies());
+        }
+    }
+    visitor.exit_distribution(distribution);
+}
+
+fn walk_dependencies<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    dependencies: &Dependencies,
+) {
+    for (package_key, spec) in dependencies {
+        let context = VisitContext::package(PackageName::parse(package_key), true);
+        walk_package_specification(visitor, &context, spec);
+    }
+}
+
+fn walk_package_definition<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    package: &PackageDefinition,
+) {
+    for (module_key, module) in &package.modules {
+        walk_module(visitor, &context.in_module(module_key), module);
+    }
+}
+
+/// Walk module definition `module`, with `context` naming it
+pub(super) fn walk_module<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    module: &AccessControlled<ModuleDefinition>,
+) {
+    if visitor.enter_module(context, module) == Walk::Continue {
+        for (name, definition) in &module.value.types {
+            let context = context.in_definition(name);
+            if visitor.enter_type_definition(&context, definition) == Walk::Continue {
+                walk_type_definition(visitor, &context, &definition.value);
+            }
+            visitor.exit_type_definition(&context, definition);
+        }
+        for (name, definition) in &module.value.values {
+            let context = context.in_definition(name);
+            if visitor.enter_value_definition(&context, definition) == Walk::Continue {
+                walk_value_definition(visitor, &context, &definition.value);
+            }
+            visitor.exit_value_definition(&context, definition);
+        }
+    }
+    visitor.exit_module(context, module);
+}
+
+fn walk_package_specification<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    package: &PackageSpecification,
+) {
+    for (module_key, module) in &package.modules {
+        walk_module_specification(visitor, &context.in_module(module_key), module);
+    }
+}
+
+/// Walk module specification `module`, with `context` naming it
+pub(super) fn walk_module_specification<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    module: &ModuleSpecification,
+) {
+    if visitor.enter_module_specification(context, module) == Walk::Continue {
+        for (name, specification) in &module.types {
+            let context = context.in_definition(name);
+            if visitor.enter_type_specification(&context, specification) == Walk::Continue {
+                walk_type_specification(visitor, &context, specification);
+            }
+            visitor.exit_type_specification(&context, specification);
+        }
+        for (name, specification) in &module.values {
+            let context = context.in_definition(name);
+            if visitor.enter_value_specification(&context, specification) == Walk::Continue {
+                for tpe in specification.inputs.values() {
+                    walk_type(visitor, &context, tpe);
+                }
+                walk_type(visitor, &context, &specification.output);
+            }
+            visitor.exit_value_specification(&context, specification);
+        }
+    }
+    visitor.exit_module_specification(context, module);
+}
+
+fn walk_type_definition<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    definition: &TypeDefinition,
+) {
+    match definition {
+        TypeDefinition::TypeAliasDefinition { type_expr, .. } => {
+            walk_type(visitor, context, type_expr)
+        }
+        TypeDefinition::CustomTypeDefinition { constructors, .. } => {
+            for constructor in &constructors.value {
+                for arg in &constructor.args {
+                    walk_type(visitor, context, &arg.arg_type);
+                }
+            }
+        }
+        TypeDefinition::IncompleteTypeDefinition { .. } => {}
+    }
+}
+
+fn walk_type_specification<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    specification: &TypeSpecification,
+) {
+    match specification {
+        TypeSpecification::TypeAliasSpecification { type_expr, .. } => {
+            walk_type(visitor, context, type_expr)
+        }
+        TypeSpecification::CustomTypeSpecification { constructors, .. } => {
+            for constructor in constructors {
+                for arg in &constructor.args {
+                    walk_type(visitor, context, &arg.arg_type);
+                }
+            }
+        }
+        TypeSpecification::OpaqueTypeSpecification { .. } => {}
+    }
+}
+
+fn walk_value_definition<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    definition: &ValueDefinition,
+) {
+    for input in definition.input_types.values() {
+        walk_type(visitor, context, &input.input_type);
+    }
+    walk_type(visitor, context, &definition.output_type);
+    if let ValueBody::Expression(body) = &definition.body {
+        walk_value(visitor, context, body);
+    }
+}
+
+/// Walk type expression `tpe` and its children with `visitor`
+pub fn walk_type<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    tpe: &Type,
+) {
+    if visitor.enter_type(context, tpe) == Walk::Continue {
+        match tpe {
+            Type::Reference(_, _, args) | Type::Tuple(_, args) => {
+                for arg in args {
+                    walk_type(visitor, context, arg);
+                }
+            }
+            Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+                for field in fields {
+                    walk_type(visitor, context, &field.tpe);
+                }
+            }
+            Type::Function(_, arg, result) => {
+                walk_type(visitor, context, arg);
+                walk_type(visitor, context, result);
+            }
+            Type::Variable(..) | Type::Unit(_) => {}
+        }
+    }
+    visitor.exit_type(context, tpe);
+}
+
+/// Walk value expression `value` and its children with `visitor`
+pub fn walk_value<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    value: &Value,
+) {
+    if visitor.enter_value(context, value) == Walk::Continue {
+        match value {
+            Value::Tuple(_, elements) | Value::List(_, elements) => {
+                for element in elements {
+                    walk_value(visitor, context, element);
+                }
+            }
+            Value::Record(_, fields) => {
+                for field in fields {
+                    walk_value(visitor, context, &field.1);
+                }
+            }
+            Value::Field(_, subject, _) => walk_value(visitor, context, subject),
+            Value::Apply(_, function, argument) => {
+                walk_value(visitor, context, function);
+                walk_value(visitor, context, argument);
+            }
+            Value::Lambda(_, pattern, body) => {
+                walk_pattern(visitor, context, pattern);
+                walk_value(visitor, context, body);
+            }
+            Value::LetDefinition(_, _, definition, body) => {
+                walk_value_definition(visitor, context, definition);
+                walk_value(visitor, context, body);
+            }
+            Value::LetRecursion(_, bindings, body) => {
+                for binding in bindings {
+                    walk_value_definition(visitor, context, &binding.1);
+                }
+                walk_value(visitor, context, body);
+            }
+            Value::Destructure(_, pattern, bound, body) => {
+                walk_pattern(visitor, context, pattern);
+                walk_value(visitor, context, bound);
+                walk_value(visitor, context, body);
+            }
+            Value::IfThenElse(_, condition, then_branch, else_branch) => {
+                walk_value(visitor, context, condition);
+                walk_value(visitor, context, then_branch);
+                walk_value(visitor, context, else_branch);
+            }
+            Value::PatternMatch(_, subject, cases) => {
+                walk_value(visitor, context, subject);
+                for case in cases {
+                    walk_pattern(visitor, context, &case.0);
+                    walk_value(visitor, context, &case.1);
+                }
+            }
+            Value::UpdateRecord(_, subject, fields) => {
+                walk_value(visitor, context, subject);
+                for field in fields {
+                    walk_value(visitor, context, &field.1);
+                }
+            }
+            Value::Hole(_, _, Some(expected)) => walk_type(visitor, context, expected),
+            Value::Literal(..)
+            | Value::Constructor(..)
+            | Value::Variable(..)
+            | Value::Reference(..)
+            | Value::FieldFunction(..)
+            | Value::Unit(_)
+            | Value::Hole(_, _, None)
+            | Value::Native(..)
+            | Value::External(..) => {}
+        }
+    }
+    visitor.exit_value(context, value);
+}
+
+/// Walk pattern `pattern` and its children with `visitor`
+pub fn walk_pattern<V: DistributionVisitor + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    pattern: &Pattern,
+) {
+    if visitor.enter_pattern(context, pattern) == Walk::Continue {
+        match pattern {
+            Pattern::AsPattern(_, inner, _) => walk_pattern(visitor, context, inner),
+            Pattern::TuplePattern(_, elements) | Pattern::ConstructorPattern(_, _, elements) => {
+                for element in elements {
+                    walk_pattern(visitor, context, element);
+                }
+            }
+            Pattern::HeadTailPattern(_, head, tail) => {
+                walk_pattern(visitor, context, head);
+                walk_pattern(visitor, context, tail);
+            }
+            Pattern::WildcardPattern(_)
+            | Pattern::EmptyListPattern(_)
+            | Pattern::LiteralPattern(..)
+            | Pattern::UnitPattern(_) => {}
+        }
+    }
+    visitor.exit_pattern(context, pattern);
+}
+
+// =============================================================================
+// Walking mutably
+// =============================================================================
+
+/// Walk `distribution` with `visitor`, which may change it
+pub fn walk_distribution_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    distribution: &mut Distribution,
+) {
+    if visitor.enter_distribution(distribution) == Walk::Continue {
+        let context = VisitContext::package(distribution.package_name().clone(), false);
+        let walk_dependencies = visitor.dependencies();
+        let dependencies = match distribution {
+            Distribution::Library(lib) => {
+                walk_package_definition_mut(visitor, &context, &mut lib.def);
+                &mut lib.dependencies
+            }
+            Distribution::Application(app) => {
+                walk_package_definition_mut(visitor, &context, &mut app.def);
+                &mut app.dependencies
+            }
+            Distribution::Specs(specs) => {
+                walk_package_specification_mut(visitor, &context, &mut specs.spec);
+                &mut specs.dependencies
+            }
+        };
+        if walk_dependencies {
+            for (package_key, spec) in dependencies.iter_mut() {
+                let context = VisitContext::package(PackageName::parse(package_key), true);
+                walk_package_specification_mut(visitor, &context, spec);
+            }
+        }
+    }
+    visitor.exit_distribution(distribution);
+}
+
+fn walk_package_definition_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    package: &mut PackageDefinition,
+) {
+    for (module_key, module) in package.modules.iter_mut() {
+        let context = context.in_module(module_key);
+        if visitor.enter_module(&context, module) == Walk::Continue {
+            for (name, definition) in module.value.types.iter_mut() {
+                let context = context.in_definition(name);
+                if visitor.enter_type_definition(&context, definition) == Walk::Continue {
+                    walk_type_definition_mut(visitor, &context, &mut definition.value);
+                }
+                visitor.exit_type_definition(&context, definition);
+            }
+            for (name, definition) in module.value.values.iter_mut() {
+                let context = context.in_definition(name);
+                if visitor.enter_value_definition(&context, definition) == Walk::Continue {
+                    walk_value_definition_mut(visitor, &context, &mut definition.value);
+                }
+                visitor.exit_value_definition(&context, definition);
+            }
+        }
+        visitor.exit_module(&context, module);
+    }
+}
+
+fn walk_package_specification_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    package: &mut PackageSpecification,
+) {
+    for (module_key, module) in package.modules.iter_mut() {
+        let context = context.in_module(module_key);
+        if visitor.enter_module_specification(&context, module) == Walk::Continue {
+            for (name, specification) in module.types.iter_mut() {
+                let context = context.in_definition(name);
+                if visitor.enter_type_specification(&context, specification) == Walk::Continue {
+                    walk_type_specification_mut(visitor, &context, specification);
+                }
+                visitor.exit_type_specification(&context, specification);
+            }
+            for (name, specification) in module.values.iter_mut() {
+                let context = context.in_definition(name);
+                if visitor.enter_value_specification(&context, specification) == Walk::Continue {
+                    for tpe in specification.inputs.values_mut() {
+                        walk_type_mut(visitor, &context, tpe);
+                    }
+                    walk_type_mut(visitor, &context, &mut specification.output);
+                }
+                visitor.exit_value_specification(&context, specification);
+            }
+        }
+        visitor.exit_module_specification(&context, module);
+    }
+}
+
+fn walk_type_definition_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    definition: &mut TypeDefinition,
+) {
+    match definition {
+        TypeDefinition::TypeAliasDefinition { type_expr, .. } => {
+            walk_type_mut(visitor, context, type_expr)
+        }
+        TypeDefinition::CustomTypeDefinition { constructors, .. } => {
+            for constructor in constructors.value.iter_mut() {
+                for arg in constructor.args.iter_mut() {
+                    walk_type_mut(visitor, context, &mut arg.arg_type);
+                }
+            }
+        }
+        TypeDefinition::IncompleteTypeDefinition { .. } => {}
+    }
+}
+
+fn walk_type_specification_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    specification: &mut TypeSpecification,
+) {
+    match specification {
+        TypeSpecification::TypeAliasSpecification { type_expr, .. } => {
+            walk_type_mut(visitor, context, type_expr)
+        }
+        TypeSpecification::CustomTypeSpecification { constructors, .. } => {
+            for constructor in constructors.iter_mut() {
+                for arg in constructor.args.iter_mut() {
+                    walk_type_mut(visitor, context, &mut arg.arg_type);
+                }
+            }
+        }
+        TypeSpecification::OpaqueTypeSpecification { .. } => {}
+    }
+}
+
+fn walk_value_definition_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    definition: &mut ValueDefinition,
+) {
+    for input in definition.input_types.values_mut() {
+        walk_type_mut(visitor, context, &mut input.input_type);
+    }
+    walk_type_mut(visitor, context, &mut definition.output_type);
+    if let ValueBody::Expression(body) = &mut definition.body {
+        walk_value_mut(visitor, context, body);
+    }
+}
+
+/// Walk type expression `tpe` and its children with `visitor`, which may
+/// change them
+pub fn walk_type_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    tpe: &mut Type,
+) {
+    if visitor.enter_type(context, tpe) == Walk::Continue {
+        match tpe {
+            Type::Reference(_, _, args) | Type::Tuple(_, args) => {
+                for arg in args.iter_mut() {
+                    walk_type_mut(visitor, context, arg);
+                }
+            }
+            Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+                for field in fields.iter_mut() {
+                    walk_type_mut(visitor, context, &mut field.tpe);
+                }
+            }
+            Type::Function(_, arg, result) => {
+                walk_type_mut(visitor, context, arg);
+                walk_type_mut(visitor, context, result);
+            }
+            Type::Variable(..) | Type::Unit(_) => {}
+        }
+    }
+    visitor.exit_type(context, tpe);
+}
+
+/// Walk value expression `value` and its children with `visitor`, which may
+/// change them
+pub fn walk_value_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    value: &mut Value,
+) {
+    if visitor.enter_value(context, value) == Walk::Continue {
+        match value {
+            Value::Tuple(_, elements) | Value::List(_, elements) => {
+                for element in elements.iter_mut() {
+                    walk_value_mut(visitor, context, element);
+                }
+            }
+            Value::Record(_, fields) => {
+                for field in fields.iter_mut() {
+                    walk_value_mut(visitor, context, &mut field.1);
+                }
+            }
+            Value::Field(_, subject, _) => walk_value_mut(visitor, context, subject),
+            Value::Apply(_, function, argument) => {
+                walk_value_mut(visitor, context, function);
+                walk_value_mut(visitor, context, argument);
+            }
+            Value::Lambda(_, pattern, body) => {
+                walk_pattern_mut(visitor, context, pattern);
+                walk_value_mut(visitor, context, body);
+            }
+            Value::LetDefinition(_, _, definition, body) => {
+                walk_value_definition_mut(visitor, context, definition);
+                walk_value_mut(visitor, context, body);
+            }
+            Value::LetRecursion(_, bindings, body) => {
+                for binding in bindings.iter_mut() {
+                    walk_value_definition_mut(visitor, context, &mut binding.1);
+                }
+                walk_value_mut(visitor, context, body);
+            }
+            Value::Destructure(_, pattern, bound, body) => {
+                walk_pattern_mut(visitor, context, pattern);
+                walk_value_mut(visitor, context, bound);
+                walk_value_mut(visitor, context, body);
+            }
+            Value::IfThenElse(_, condition, then_branch, else_branch) => {
+                walk_value_mut(visitor, context, condition);
+                walk_value_mut(visitor, context, then_branch);
+                walk_value_mut(visitor, context, else_branch);
+            }
+            Value::PatternMatch(_, subject, cases) => {
+                walk_value_mut(visitor, context, subject);
+                for case in cases.iter_mut() {
+                    walk_pattern_mut(visitor, context, &mut case.0);
+                    walk_value_mut(visitor, context, &mut case.1);
+                }
+            }
+            Value::UpdateRecord(_, subject, fields) => {
+                walk_value_mut(visitor, context, subject);
+                for field in fields.iter_mut() {
+                    walk_value_mut(visitor, context, &mut field.1);
+                }
+            }
+            Value::Hole(_, _, Some(expected)) => walk_type_mut(visitor, context, expected),
+            Value::Literal(..)
+            | Value::Constructor(..)
+            | Value::Variable(..)
+            | Value::Reference(..)
+            | Value::FieldFunction(..)
+            | Value::Unit(_)
+            | Value::Hole(_, _, None)
+            | Value::Native(..)
+            | Value::External(..) => {}
+        }
+    }
+    visitor.exit_value(context, value);
+}
+
+/// Walk pattern `pattern` and its children with `visitor`, which may change
+/// them
+pub fn walk_pattern_mut<V: DistributionVisitorMut + ?Sized>(
+    visitor: &mut V,
+    context: &VisitContext,
+    pattern: &mut Pattern,
+) {
+    if visitor.enter_pattern(context, pattern) == Walk::Continue {
+        match pattern {
+            Pattern::AsPattern(_, inner, _) => walk_pattern_mut(visitor, context, inner),
+            Pattern::TuplePattern(_, elements) | Pattern::ConstructorPattern(_, _, elements) => {
+                for element in elements.iter_mut() {
+                    walk_pattern_mut(visitor, context, element);
+                }
+            }
+            Pattern::HeadTailPattern(_, head, tail) => {
+                walk_pattern_mut(visitor, context, head);
+                walk_pattern_mut(visitor, context, tail);
+            }
+            Pattern::WildcardPattern(_)
+            | Pattern::EmptyListPattern(_)
+            | Pattern::LiteralPattern(..)
+            | Pattern::UnitPattern(_) => {}
+        }
+    }
+    visitor.exit_pattern(context, pattern);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::parse;
+    use super::*;
+    use crate::ir::v4::literal::Literal;
+
+    fn distribution() -> Distribution {
+        parse(
+            "library acme/shop\n\nmodule orders\n\ntype alias Line =\n    { price : morphir/sdk:basics#Int\n    }\n\ntotal (x : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =\n    case x of\n        0 ->\n            x\n        _ ->\n            morphir/sdk:basics#negate x\n\n\ndependency morphir/sdk\n\nmodule basics\n\nopaque type Int\n\nnegate (a : morphir/sdk:basics#Int) : morphir/sdk:basics#Int\n",
+        )
+    }
+
+    /// Records every hook called, with the definition it was called in
+    #[derive(Default)]
+    struct Trace {
+        dependencies: bool,
+        events: Vec<String>,
+    }
+
+    impl Trace {
+        fn push(&mut self, context: &VisitContext, event: &str) {
+            let at = context
+                .definition
+                .as_ref()
+                .map(|fqname| fqname.to_canonical_string())
+                .or_else(|| context.module.as_ref().map(ToString::to_string))
+                .unwrap_or_default();
+            self.events.push(format!("{} {}", event, at));
+        }
+    }
+
+    impl DistributionVisitor for Trace {
+        fn dependencies(&self) -> bool {
+            self.dependencies
+        }
+
+        fn enter_module(
+            &mut self,
+            context: &VisitContext,
+            _module: &AccessControlled<ModuleDefinition>,
+        ) -> Walk {
+            self.push(context, "module");
+            Walk::Continue
+        }
+
+        fn exit_module(
+            &mut self,
+            context: &VisitContext,
+            _module: &AccessControlled<ModuleDefinition>,
+        ) {
+            self.push(context, "/module");
+        }
+
+        fn enter_value_specification(
+            &mut self,
+            context: &VisitContext,
+            _specification: &ValueSpecification,
+        ) -> Walk {
+            assert!(context.dependency);
+            self.push(context, "spec");
+            Walk::Skip
+        }
+
+        fn enter_type(&mut self, context: &VisitContext, _tpe: &Type) -> Walk {
+            self.push(context, "type");
+            Walk::Continue
+        }
+
+        fn enter_value(&mut self, context: &VisitContext, value: &Value) -> Walk {
+            self.push(context, "value");
+            // Leave out the function of an application
+            if matches!(value, Value::Apply(..)) {
+                Walk::Skip
+            } else {
+                Walk::Continue
+            }
+        }
+
+        fn enter_pattern(&mut self, context: &VisitContext, _pattern: &Pattern) -> Walk {
+            self.push(context, "pattern");
+            Walk::Continue
+        }
+    }
+
+    #[test]
+    fn test_walk_order_and_context() {
+        let distribution = distribution();
+        let mut trace = Trace::default();
+        walk_distribution(&mut trace, &distribution);
+        let line = "acme/shop:orders#line";
+        let total = "acme/shop:orders#total";
+        assert_eq!(
+            trace.events,
+            [
+                "module orders".to_string(),
+                format!("type {}", line),
+                format!("type {}", line),
+                format!("type {}", total),
+                format!("type {}", total),
+                format!("value {}", total),
+                format!("value {}", total),
+                format!("pattern {}", total),
+                format!("value {}", total),
+                format!("pattern {}", total),
+                format!("value {}", total),
+                "/module orders".to_string(),
+            ]
+        );
+
+        let mut trace = Trace {
+            dependencies: true,
+            ..Trace::default()
+        };
+        walk_distribution(&mut trace, &distribution);
+        assert_eq!(
+            trace.events.last().map(String::as_str),
+            Some("spec morphir/sdk:basics#negate")
+        );
+    }
+
+    /// Replaces references to `negate` with a literal, on the way out
+    struct FoldNegate(usize);
+
+    impl DistributionVisitorMut for FoldNegate {
+        fn exit_value(&mut self, _context: &VisitContext, value: &mut Value) {
+            if let Value::Apply(attrs, function, _) = value
+                && matches!(&**function, Value::Reference(_, fqname)
+                    if fqname.to_canonical_string() == "morphir/sdk:basics#negate")
+            {
+                *value = Value::Literal(attrs.clone(), Literal::Integer(0));
+                self.0 += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_walk_mut_replaces_nodes() {
+        let mut distribution = distribution();
+        let mut fold = FoldNegate(0);
+        walk_distribution_mut(&mut fold, &mut distribution);
+        assert_eq!(fold.0, 1);
+
+        // The replaced node is walked out of, not into again
+        walk_distribution_mut(&mut fold, &mut distribution);
+        assert_eq!(fold.0, 1);
+    }
+}
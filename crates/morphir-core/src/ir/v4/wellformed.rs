@@ -0,0 +1,821 @@
+//! Structural well-formedness of V4 IR files.
+//!
+//! Checks what a reader cannot: that every type, value and constructor an IR
+//! file refers to exists, and that constructors and types are given as many
+//! arguments as they take. Problems carry a JSON Pointer (RFC 6901) into the
+//! IR document, so tools can point at the offending node even in files with
+//! no source locations.
+//!
+//! The document is walked as JSON rather than as the deserialized
+//! distribution, so pointers follow the file as written, including the
+//! string shorthand for type references. Nodes in a shape the walker does not
+//! know, such as Classic arrays, are skipped. Declarations are looked up in
+//! the distribution, which must be the one read from the document, possibly
+//! with more dependencies attached.
+//!
+//! References into the package are checked against its definitions and
+//! references into a dependency against its specification. References to
+//! packages that are neither, such as the SDK when no specification of it is
+//! attached, cannot be checked and are trusted.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let document: serde_json::Value = serde_json::from_str(&content)?;
+//! let ir_file: IRFile = serde_json::from_value(document.clone())?;
+//! for problem in check_well_formed(&document, &ir_file.distribution) {
+//!     eprintln!("{}: {}", problem.pointer, problem.message);
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::distribution::Distribution;
+use super::module::ModuleSpecification;
+use super::types::{Type, TypeDefinition, TypeSpecification};
+use crate::naming::{FQName, Name, Path};
+
+/// A structural problem found in an IR document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    /// Stable problem code, e.g. `unknown-type`
+    pub code: &'static str,
+    pub message: String,
+    /// JSON Pointer to the offending node
+    pub pointer: String,
+}
+
+/// Check the references of an IR document against the declarations of
+/// `distribution`.
+pub fn check_well_formed(document: &Value, distribution: &Distribution) -> Vec<Problem> {
+    let mut checker = Checker {
+        index: Index::new(distribution),
+        problems: Vec::new(),
+    };
+    if let Some((tag, content)) = document.get("distribution").and_then(tagged) {
+        let pointer = child(&child("", "distribution"), tag);
+        checker.distribution(tag, content, &pointer);
+    }
+    checker.problems
+}
+
+/// Escape `token` as one segment of a JSON Pointer
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Pointer to the member `token` of the node at `pointer`
+fn child(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, escape(token))
+}
+
+/// Pointer to the element `index` of the array at `pointer`
+fn element(pointer: &str, index: usize) -> String {
+    format!("{}/{}", pointer, index)
+}
+
+/// Tag and content of a `{ "Tag": content }` wrapper object
+fn tagged(json: &Value) -> Option<(&str, &Value)> {
+    let object = json.as_object()?;
+    if object.len() != 1 {
+        return None;
+    }
+    object
+        .iter()
+        .next()
+        .map(|(tag, content)| (tag.as_str(), content))
+}
+
+fn members(json: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    json.as_object().into_iter().flat_map(Map::iter)
+}
+
+fn elements(json: &Value) -> impl Iterator<Item = (usize, &Value)> {
+    json.as_array().into_iter().flatten().enumerate()
+}
+
+// =============================================================================
+// Declarations
+// =============================================================================
+
+/// Types, constructors, and values declared by a module, keyed by kebab-case
+/// name
+#[derive(Default)]
+struct ModuleIndex {
+    /// Types and the number of type parameters they take
+    types: HashMap<String, usize>,
+    /// Constructors and the number of arguments they take
+    constructors: HashMap<String, usize>,
+    values: HashSet<String>,
+    /// Whether some type has no known constructors, such as an incomplete
+    /// type, so a constructor missing from `constructors` may still exist
+    open: bool,
+}
+
+impl ModuleIndex {
+    /// An alias of a record type also names a constructor taking its fields
+    /// in order, as in Elm
+    fn record_constructor(&mut self, type_key: &str, type_expr: &Type) {
+        if let Type::Record(_, fields) = type_expr {
+            self.constructors.insert(normalize(type_key), fields.len());
+        }
+    }
+
+    fn from_specification(spec: &ModuleSpecification) -> Self {
+        let mut module = ModuleIndex::default();
+        for (type_key, type_spec) in &spec.types {
+            let params = match type_spec {
+                TypeSpecification::TypeAliasSpecification {
+                    type_params,
+                    type_expr,
+                } => {
+                    module.record_constructor(type_key, type_expr);
+                    type_params.len()
+                }
+                TypeSpecification::OpaqueTypeSpecification { type_params } => type_params.len(),
+                TypeSpecification::CustomTypeSpecification {
+                    type_params,
+                    constructors,
+                } => {
+                    for ctor in constructors {
+                        module
+                            .constructors
+                            .insert(ctor.name.to_kebab_case(), ctor.args.len());
+                    }
+                    type_params.len()
+                }
+            };
+            module.types.insert(normalize(type_key), params);
+        }
+        module.values = spec.values.keys().map(|key| normalize(key)).collect();
+        module
+    }
+}
+
+/// Declarations of the package and of each of its dependencies, keyed by
+/// package and module path
+struct Index {
+    packages: HashMap<String, HashMap<String, ModuleIndex>>,
+}
+
+impl Index {
+    fn new(distribution: &Distribution) -> Self {
+        let mut packages = HashMap::new();
+        for (dependency_key, spec) in distribution.dependencies() {
+            let modules = spec
+                .modules
+                .iter()
+                .map(|(module_key, module)| {
+                    (
+                        Path::new(module_key).to_string(),
+                        ModuleIndex::from_specification(module),
+                    )
+                })
+                .collect();
+            packages.insert(Path::new(dependency_key).to_string(), modules);
+        }
+
+        let modules = match distribution.definition() {
+            Some(def) => def
+                .modules
+                .iter()
+                .map(|(module_key, module)| {
+                    let mut index = ModuleIndex::default();
+                    for (type_key, type_def) in &module.value.types {
+                        let params = match &type_def.value {
+                            TypeDefinition::TypeAliasDefinition {
+                                type_params,
+                                type_expr,
+                            } => {
+                                index.record_constructor(type_key, type_expr);
+                                type_params.len()
+                            }
+                            TypeDefinition::CustomTypeDefinition {
+                                type_params,
+                                constructors,
+                            } => {
+                                for ctor in &constructors.value {
+                                    index
+                                        .constructors
+                                        .insert(ctor.name.to_kebab_case(), ctor.args.len());
+                                }
+                                type_params.len()
+                            }
+                            TypeDefinition::IncompleteTypeDefinition { type_params, .. } => {
+                                index.open = true;
+                                type_params.len()
+                            }
+                        };
+                        index.types.insert(normalize(type_key), params);
+                    }
+                    index.values = module
+                        .value
+                        .values
+                        .keys()
+                        .map(|key| normalize(key))
+                        .collect();
+                    (Path::new(module_key).to_string(), index)
+                })
+                .collect(),
+            None => distribution
+                .specification()
+                .modules
+                .iter()
+                .map(|(module_key, module)| {
+                    (
+                        Path::new(module_key).to_string(),
+                        ModuleIndex::from_specification(module),
+                    )
+                })
+                .collect(),
+        };
+        packages.insert(distribution.package_name().to_string(), modules);
+
+        Index { packages }
+    }
+}
+
+fn normalize(key: &str) -> String {
+    Name::from(key).to_kebab_case()
+}
+
+/// What a reference resolves to
+enum Lookup<'i> {
+    /// The package is not described by the distribution
+    Unchecked,
+    /// The package is known but has no such module
+    NoModule,
+    Module(&'i ModuleIndex),
+}
+
+// =============================================================================
+// Walking the document
+// =============================================================================
+
+struct Checker {
+    index: Index,
+    problems: Vec<Problem>,
+}
+
+impl Checker {
+    fn report(&mut self, code: &'static str, message: String, pointer: &str) {
+        self.problems.push(Problem {
+            code,
+            message,
+            pointer: pointer.to_string(),
+        });
+    }
+
+    fn distribution(&mut self, tag: &str, content: &Value, pointer: &str) {
+        match tag {
+            "Library" | "Application" => {
+                let modules = child(&child(pointer, "def"), "modules");
+                for (module_key, module) in members(&content["def"]["modules"]) {
+                    let module_pointer = child(&child(&modules, module_key), "value");
+                    self.module(&module["value"], &module_pointer, true);
+                }
+            }
+            "Specs" => {
+                let modules = child(&child(pointer, "spec"), "modules");
+                for (module_key, module) in members(&content["spec"]["modules"]) {
+                    self.module(module, &child(&modules, module_key), false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// A module definition, or a specification when `definition` is false
+    fn module(&mut self, module: &Value, pointer: &str, definition: bool) {
+        let types = child(pointer, "types");
+        for (type_key, type_json) in members(&module["types"]) {
+            let type_pointer = child(&types, type_key);
+            if definition {
+                self.type_definition(&type_json["value"], &child(&type_pointer, "value"));
+            } else {
+                self.type_definition(type_json, &type_pointer);
+            }
+        }
+        let values = child(pointer, "values");
+        for (value_key, value_json) in members(&module["values"]) {
+            let value_pointer = child(&values, value_key);
+            if definition {
+                self.value_definition(&value_json["value"], &child(&value_pointer, "value"));
+            } else {
+                self.value_specification(value_json, &value_pointer);
+            }
+        }
+    }
+
+    /// A type definition or specification
+    fn type_definition(&mut self, json: &Value, pointer: &str) {
+        let Some((tag, content)) = tagged(json) else {
+            return;
+        };
+        let pointer = child(pointer, tag);
+        match tag {
+            "TypeAliasDefinition" | "TypeAliasSpecification" => {
+                self.ty(&content["typeExp"], &child(&pointer, "typeExp"));
+            }
+            "CustomTypeDefinition" => {
+                let constructors = child(&child(&pointer, "constructors"), "value");
+                self.constructors(&content["constructors"]["value"], &constructors);
+            }
+            "CustomTypeSpecification" => {
+                self.constructors(&content["constructors"], &child(&pointer, "constructors"));
+            }
+            _ => {}
+        }
+    }
+
+    fn constructors(&mut self, json: &Value, pointer: &str) {
+        for (i, ctor) in elements(json) {
+            let args = child(&element(pointer, i), "args");
+            for (j, arg) in elements(&ctor["args"]) {
+                self.ty(&arg["type"], &child(&element(&args, j), "type"));
+            }
+        }
+    }
+
+    fn value_definition(&mut self, json: &Value, pointer: &str) {
+        let inputs = child(pointer, "inputTypes");
+        for (input_key, input) in members(&json["inputTypes"]) {
+            self.ty(&input["type"], &child(&child(&inputs, input_key), "type"));
+        }
+        self.ty(&json["outputType"], &child(pointer, "outputType"));
+        if let Some(body) = json["body"].get("ExpressionBody") {
+            let body_pointer = child(&child(pointer, "body"), "ExpressionBody");
+            self.value(&body["body"], &child(&body_pointer, "body"));
+        }
+    }
+
+    fn value_specification(&mut self, json: &Value, pointer: &str) {
+        let inputs = child(pointer, "inputs");
+        for (input_key, input) in members(&json["inputs"]) {
+            self.ty(input, &child(&inputs, input_key));
+        }
+        self.ty(&json["output"], &child(pointer, "output"));
+    }
+
+    fn ty(&mut self, json: &Value, pointer: &str) {
+        if let Some(shorthand) = json.as_str() {
+            if shorthand.contains(':') && shorthand.contains('#') {
+                self.type_reference(shorthand, 0, pointer);
+            }
+            return;
+        }
+        let Some((tag, content)) = tagged(json) else {
+            return;
+        };
+        let pointer = child(pointer, tag);
+        match tag {
+            "Reference" => {
+                if let Some(fqname) = content["fqname"].as_str() {
+                    let args = content["args"].as_array().map_or(0, Vec::len);
+                    self.type_reference(fqname, args, &pointer);
+                }
+                let args = child(&pointer, "args");
+                for (i, arg) in elements(&content["args"]) {
+                    self.ty(arg, &element(&args, i));
+                }
+            }
+            "Tuple" => {
+                let items = child(&pointer, "elements");
+                for (i, item) in elements(&content["elements"]) {
+                    self.ty(item, &element(&items, i));
+                }
+            }
+            "Record" | "ExtensibleRecord" => {
+                let fields = child(&pointer, "fields");
+                for (field_key, field) in members(&content["fields"]) {
+                    self.ty(field, &child(&fields, field_key));
+                }
+            }
+            "Function" => {
+                self.ty(&content["arg"], &child(&pointer, "arg"));
+                self.ty(&content["result"], &child(&pointer, "result"));
+            }
+            _ => {}
+        }
+    }
+
+    fn pattern(&mut self, json: &Value, pointer: &str) {
+        let Some((tag, content)) = tagged(json) else {
+            return;
+        };
+        let pointer = child(pointer, tag);
+        match tag {
+            "AsPattern" => self.pattern(&content["pattern"], &child(&pointer, "pattern")),
+            "TuplePattern" => {
+                let items = child(&pointer, "elements");
+                for (i, item) in elements(&content["elements"]) {
+                    self.pattern(item, &element(&items, i));
+                }
+            }
+            "ConstructorPattern" => {
+                if let Some(fqname) = content["fqname"].as_str() {
+                    let args = content["args"].as_array().map_or(0, Vec::len);
+                    if let Some(arity) = self.constructor(fqname, &pointer)
+                        && arity != args
+                    {
+                        self.report(
+                            "constructor-arity",
+                            format!(
+                                "`{}` takes {} argument(s) but the pattern has {}",
+                                fqname, arity, args
+                            ),
+                            &pointer,
+                        );
+                    }
+                }
+                let args = child(&pointer, "args");
+                for (i, arg) in elements(&content["args"]) {
+                    self.pattern(arg, &element(&args, i));
+                }
+            }
+            "HeadTailPattern" => {
+                self.pattern(&content["head"], &child(&pointer, "head"));
+                self.pattern(&content["tail"], &child(&pointer, "tail"));
+            }
+            _ => {}
+        }
+    }
+
+    fn value(&mut self, json: &Value, pointer: &str) {
+        let Some((tag, content)) = tagged(json) else {
+            return;
+        };
+        let pointer = child(pointer, tag);
+        match tag {
+            "Constructor" => {
+                if let Some(fqname) = content["fqname"].as_str() {
+                    self.constructor(fqname, &pointer);
+                }
+            }
+            "Reference" => {
+                if let Some(fqname) = content["fqname"].as_str() {
+                    self.value_reference(fqname, &pointer);
+                }
+            }
+            "Apply" => self.application(content, &pointer),
+            "Tuple" => {
+                let items = child(&pointer, "elements");
+                for (i, item) in elements(&content["elements"]) {
+                    self.value(item, &element(&items, i));
+                }
+            }
+            "List" => {
+                let items = child(&pointer, "items");
+                for (i, item) in elements(&content["items"]) {
+                    self.value(item, &element(&items, i));
+                }
+            }
+            "Record" => {
+                let fields = child(&pointer, "fields");
+                for (field_key, field) in members(&content["fields"]) {
+                    self.value(field, &child(&fields, field_key));
+                }
+            }
+            "Field" => self.value(&content["value"], &child(&pointer, "value")),
+            "Lambda" => {
+                self.pattern(&content["pattern"], &child(&pointer, "pattern"));
+                self.value(&content["body"], &child(&pointer, "body"));
+            }
+            "LetDefinition" => {
+                self.value_definition(&content["definition"], &child(&pointer, "definition"));
+                self.value(&content["body"], &child(&pointer, "body"));
+            }
+            "LetRecursion" => {
+                let bindings = child(&pointer, "bindings");
+                for (i, binding) in elements(&content["bindings"]) {
+                    self.value_definition(&binding[1], &element(&element(&bindings, i), 1));
+                }
+                self.value(&content["body"], &child(&pointer, "body"));
+            }
+            "Destructure" => {
+                self.pattern(&content["pattern"], &child(&pointer, "pattern"));
+                self.value(&content["value"], &child(&pointer, "value"));
+                self.value(&content["body"], &child(&pointer, "body"));
+            }
+            "IfThenElse" => {
+                for branch in ["condition", "thenBranch", "elseBranch"] {
+                    self.value(&content[branch], &child(&pointer, branch));
+                }
+            }
+            "PatternMatch" => {
+                self.value(&content["subject"], &child(&pointer, "subject"));
+                let cases = child(&pointer, "cases");
+                for (i, case) in elements(&content["cases"]) {
+                    let case_pointer = element(&cases, i);
+                    self.pattern(&case[0], &element(&case_pointer, 0));
+                    self.value(&case[1], &element(&case_pointer, 1));
+                }
+            }
+            "UpdateRecord" => {
+                self.value(&content["record"], &child(&pointer, "record"));
+                let updates = child(&pointer, "updates");
+                for (i, update) in elements(&content["updates"]) {
+                    self.value(&update[1], &element(&element(&updates, i), 1));
+                }
+            }
+            "Hole" => {
+                if let Some(tpe) = content.get("tpe") {
+                    self.ty(tpe, &child(&pointer, "tpe"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// An application, checked as a whole so a constructor given too many
+    /// arguments is reported once, at the outermost `Apply`
+    fn application(&mut self, content: &Value, pointer: &str) {
+        // Unwind `f a b` into `f` and `[a, b]`, keeping each node's pointer
+        let mut arguments = vec![(&content["argument"], child(pointer, "argument"))];
+        let mut function = (&content["function"], child(pointer, "function"));
+        while let Some(("Apply", inner)) = tagged(function.0) {
+            let inner_pointer = child(&function.1, "Apply");
+            arguments.push((&inner["argument"], child(&inner_pointer, "argument")));
+            function = (&inner["function"], child(&inner_pointer, "function"));
+        }
+        arguments.reverse();
+
+        match tagged(function.0) {
+            Some(("Constructor", ctor)) => {
+                let ctor_pointer = child(&function.1, "Constructor");
+                if let Some(fqname) = ctor["fqname"].as_str()
+                    && let Some(arity) = self.constructor(fqname, &ctor_pointer)
+                    && arguments.len() > arity
+                {
+                    self.report(
+                        "constructor-arity",
+                        format!(
+                            "`{}` takes {} argument(s) but is applied to {}",
+                            fqname,
+                            arity,
+                            arguments.len()
+                        ),
+                        pointer,
+                    );
+                }
+            }
+            _ => self.value(function.0, &function.1),
+        }
+        for (argument, argument_pointer) in arguments {
+            self.value(argument, &argument_pointer);
+        }
+    }
+
+    /// Resolve the module of `fqname`, reporting a missing module
+    fn module_of(&mut self, fqname: &FQName, text: &str, pointer: &str) -> Lookup<'_> {
+        let Some(modules) = self.index.packages.get(&fqname.package_path.to_string()) else {
+            return Lookup::Unchecked;
+        };
+        match modules.get(&fqname.module_path.to_string()) {
+            Some(module) => Lookup::Module(module),
+            None => {
+                self.problems.push(Problem {
+                    code: "unknown-module",
+                    message: format!(
+                        "`{}` refers to module `{}`, which package `{}` does not have",
+                        text, fqname.module_path, fqname.package_path
+                    ),
+                    pointer: pointer.to_string(),
+                });
+                Lookup::NoModule
+            }
+        }
+    }
+
+    fn type_reference(&mut self, text: &str, args: usize, pointer: &str) {
+        let Ok(fqname) = FQName::from_canonical_string(text) else {
+            return;
+        };
+        let params = match self.module_of(&fqname, text, pointer) {
+            Lookup::Module(module) => module
+                .types
+                .get(&fqname.local_name.to_kebab_case())
+                .copied(),
+            Lookup::Unchecked | Lookup::NoModule => return,
+        };
+        match params {
+            None => self.report(
+                "unknown-type",
+                format!("type `{}` does not exist", text),
+                pointer,
+            ),
+            Some(params) if params != args => self.report(
+                "type-arity",
+                format!(
+                    "`{}` takes {} type argument(s) but is given {}",
+                    text, params, args
+                ),
+                pointer,
+            ),
+            Some(_) => {}
+        }
+    }
+
+    fn value_reference(&mut self, text: &str, pointer: &str) {
+        let Ok(fqname) = FQName::from_canonical_string(text) else {
+            return;
+        };
+        let exists = match self.module_of(&fqname, text, pointer) {
+            Lookup::Module(module) => module.values.contains(&fqname.local_name.to_kebab_case()),
+            Lookup::Unchecked | Lookup::NoModule => return,
+        };
+        if !exists {
+            self.report(
+                "unknown-value",
+                format!("value `{}` does not exist", text),
+                pointer,
+            );
+        }
+    }
+
+    /// Number of arguments the constructor `text` takes, reporting it when
+    /// it does not exist; `None` when it is unknown or cannot be checked
+    fn constructor(&mut self, text: &str, pointer: &str) -> Option<usize> {
+        let fqname = FQName::from_canonical_string(text).ok()?;
+        let (arity, open) = match self.module_of(&fqname, text, pointer) {
+            Lookup::Module(module) => (
+                module
+                    .constructors
+                    .get(&fqname.local_name.to_kebab_case())
+                    .copied(),
+                module.open,
+            ),
+            Lookup::Unchecked | Lookup::NoModule => return None,
+        };
+        if arity.is_none() && !open {
+            self.report(
+                "unknown-constructor",
+                format!("constructor `{}` does not exist", text),
+                pointer,
+            );
+        }
+        arity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::text::parse_distribution;
+    use super::super::{FormatVersion, IRFile};
+    use super::*;
+
+    const SOURCE: &str = "library acme/shop
+
+module orders
+
+type Status a
+    = Held (reason : a)
+    | Pending
+
+type alias Id =
+    morphir/sdk:string#String
+
+type alias Line =
+    { price : morphir/sdk:basics#Int
+    }
+
+hold (s : acme/shop:orders#Status morphir/sdk:basics#Int) : morphir/sdk:basics#Int =
+    case s of
+        acme/shop:orders#Held r ->
+            r
+        acme/shop:orders#Pending ->
+            acme/shop:orders#zero
+
+zero : morphir/sdk:basics#Int =
+    0
+";
+
+    /// Problems in `source`, after `edit` changed its JSON
+    fn check(source: &str, edit: impl FnOnce(&mut Value)) -> Vec<Problem> {
+        let ir_file = IRFile {
+            format_version: FormatVersion::default(),
+            distribution: parse_distribution(source).unwrap(),
+        };
+        let mut document = serde_json::to_value(&ir_file).unwrap();
+        edit(&mut document);
+        let ir_file: IRFile = serde_json::from_value(document.clone()).unwrap();
+        check_well_formed(&document, &ir_file.distribution)
+    }
+
+    fn hold(document: &mut Value) -> &mut Value {
+        &mut document["distribution"]["Library"]["def"]["modules"]["orders"]["value"]["values"]["hold"]
+            ["value"]
+    }
+
+    #[test]
+    fn test_well_formed_ir_has_no_problems() {
+        assert_eq!(check(SOURCE, |_| {}), vec![]);
+    }
+
+    #[test]
+    fn test_dangling_references_point_at_the_reference() {
+        let problems = check(
+            &SOURCE
+                .replace("orders#zero", "orders#one")
+                .replace("orders#Status morphir", "billing#Status morphir"),
+            |_| {},
+        );
+        let codes: Vec<&str> = problems.iter().map(|p| p.code).collect();
+        assert_eq!(codes, vec!["unknown-module", "unknown-value"]);
+        assert_eq!(
+            problems[0].pointer,
+            "/distribution/Library/def/modules/orders/value/values/hold/value/inputTypes/s/type/Reference"
+        );
+        assert_eq!(
+            problems[1].pointer,
+            "/distribution/Library/def/modules/orders/value/values/hold/value/body/ExpressionBody/body/PatternMatch/cases/1/1/Reference"
+        );
+    }
+
+    #[test]
+    fn test_constructor_names_and_arities() {
+        let problems = check(
+            &SOURCE
+                .replace("orders#Held r", "orders#Held r r")
+                .replace("orders#Pending ->", "orders#Paused ->"),
+            |_| {},
+        );
+        let codes: Vec<&str> = problems.iter().map(|p| p.code).collect();
+        assert_eq!(codes, vec!["constructor-arity", "unknown-constructor"]);
+        assert!(
+            problems[0]
+                .pointer
+                .ends_with("/cases/0/0/ConstructorPattern")
+        );
+        assert!(problems[1].message.contains("acme/shop:orders#paused"));
+    }
+
+    #[test]
+    fn test_over_applied_constructor_is_reported_at_the_outermost_apply() {
+        let problems = check(
+            &SOURCE.replace(
+                "zero : morphir/sdk:basics#Int =\n    0",
+                "zero : morphir/sdk:basics#Int =\n    acme/shop:orders#Held 1 2 3",
+            ),
+            |_| {},
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].code, "constructor-arity");
+        assert!(problems[0].message.contains("applied to 3"));
+        assert!(
+            problems[0]
+                .pointer
+                .ends_with("/values/zero/value/body/ExpressionBody/body/Apply")
+        );
+    }
+
+    #[test]
+    fn test_record_aliases_are_constructors() {
+        let line = |args: &str| {
+            SOURCE.replace(
+                "zero : morphir/sdk:basics#Int =\n    0",
+                &format!("zero : acme/shop:orders#Line =\n    acme/shop:orders#Line{args}"),
+            )
+        };
+        assert_eq!(check(&line(" 1"), |_| {}), vec![]);
+        let problems = check(&line(" 1 2"), |_| {});
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].code, "constructor-arity");
+    }
+
+    #[test]
+    fn test_type_arity_and_shorthand_references() {
+        let problems = check(SOURCE, |document| {
+            hold(document)["outputType"] = Value::from("acme/shop:orders#Status");
+            hold(document)["inputTypes"]["s"]["type"]["Reference"]["args"] = Value::Array(vec![]);
+        });
+        let pointers: Vec<&str> = problems.iter().map(|p| p.pointer.as_str()).collect();
+        assert_eq!(
+            problems.iter().filter(|p| p.code == "type-arity").count(),
+            2
+        );
+        assert!(
+            pointers
+                .iter()
+                .any(|p| p.ends_with("/hold/value/outputType"))
+        );
+        assert!(pointers.iter().any(|p| p.ends_with("/s/type/Reference")));
+    }
+
+    #[test]
+    fn test_unknown_packages_are_trusted() {
+        let problems = check(
+            &SOURCE.replace("morphir/sdk:string#String", "other/pkg:strings#Text"),
+            |_| {},
+        );
+        assert_eq!(problems, vec![]);
+    }
+
+    #[test]
+    fn test_pointer_segments_are_escaped() {
+        assert_eq!(child("/modules", "a/b~c"), "/modules/a~1b~0c");
+    }
+}
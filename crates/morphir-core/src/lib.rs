@@ -1,13 +1,12 @@
-// TODO: These modules have pre-existing issues with undefined types
-// They need to be updated to use the refactored classic IR types
-// pub mod converter;
-// pub mod traversal;
-
+pub mod converter;
 pub mod error;
 pub mod ir;
 pub mod naming;
+pub mod traversal;
+pub mod vpath;
 
 pub use naming::{Word, intern, resolve};
+pub use vpath::VirtualPath;
 
 // Re-export commonly used items for convenience
 // pub mod visitor {
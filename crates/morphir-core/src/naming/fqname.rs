@@ -1,10 +1,16 @@
 use crate::naming::{name::Name, path::Path};
 use schemars::JsonSchema;
+use serde::de::{self, Visitor};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// FQName represents a Fully Qualified Name (PackagePath + ModulePath + LocalName).
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
-#[serde(try_from = "String", into = "String")]
+///
+/// Serializes as the classic `pkg:mod:local` string; [`canonical`] is the
+/// codec for the V4 `pkg:mod#local` form. Both deserialize straight into
+/// interned words, without an owned intermediate string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, JsonSchema)]
+#[schemars(with = "String")]
 pub struct FQName {
     pub package_path: Path,
     pub module_path: Path,
@@ -45,6 +51,25 @@ impl FQName {
         )
     }
 
+    /// V4 canonical string with the SDK package always spelled
+    /// `morphir/sdk`, for looking names up whichever spelling the IR uses
+    pub fn to_normalized_string(&self) -> String {
+        format!(
+            "{}:{}#{}",
+            self.package_path.to_normalized_string(),
+            self.module_path,
+            self.local_name
+        )
+    }
+
+    /// Normalized string of the member `local` of the module keyed
+    /// `module_key` in `package`, for looking up members of module maps
+    /// whose keys are not canonical
+    pub fn member_key(package: &Path, module_key: &str, local: &str) -> String {
+        FQName::new(package.clone(), Path::new(module_key), Name::from(local))
+            .to_normalized_string()
+    }
+
     /// Parse from V4 canonical string format: `package/path:module/path#local-name`
     pub fn from_canonical_string(s: &str) -> Result<Self, String> {
         // Split on ':' first, then '#' for the local name
@@ -91,6 +116,78 @@ impl TryFrom<String> for FQName {
     }
 }
 
+impl Serialize for FQName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+struct FQNameVisitor;
+
+impl<'de> Visitor<'de> for FQNameVisitor {
+    type Value = FQName;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("string for FQName")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<FQName, E> {
+        FQName::parse(s).ok_or_else(|| E::custom(format!("Invalid FQName string: {}", s)))
+    }
+}
+
+impl<'de> Deserialize<'de> for FQName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(FQNameVisitor)
+    }
+}
+
+/// Serde codec for the V4 canonical form, `package/path:module/path#local-name`,
+/// for use with `#[serde(with = "crate::naming::fqname::canonical")]`
+pub mod canonical {
+    use super::FQName;
+    use serde::de::{self, Visitor};
+    use std::fmt;
+
+    pub fn serialize<S>(fqname: &FQName, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(&format_args!(
+            "{}:{}#{}",
+            fqname.package_path, fqname.module_path, fqname.local_name
+        ))
+    }
+
+    struct CanonicalVisitor;
+
+    impl<'de> Visitor<'de> for CanonicalVisitor {
+        type Value = FQName;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("canonical FQName string")
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<FQName, E> {
+            FQName::from_canonical_string(s)
+                .map_err(|e| E::custom(format!("invalid FQName: {}", e)))
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<FQName, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(CanonicalVisitor)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,4 +206,36 @@ mod tests {
         let s = fq.to_string();
         assert_eq!(s, "my/pkg:my/mod:my-func");
     }
+
+    #[test]
+    fn test_sdk_spellings_normalize_alike() {
+        let classic = FQName::from_canonical_string("morphir/s-d-k:basics#int").unwrap();
+        let v4 = FQName::from_canonical_string("morphir/sdk:basics#int").unwrap();
+        assert!(classic.package_path.is_sdk() && v4.package_path.is_sdk());
+        assert_eq!(classic.to_normalized_string(), "morphir/sdk:basics#int");
+        assert_eq!(v4.to_normalized_string(), "morphir/sdk:basics#int");
+
+        let other = FQName::from_canonical_string("acme/sdk:basics#int").unwrap();
+        assert!(!other.package_path.is_sdk());
+        assert_eq!(other.to_normalized_string(), "acme/sdk:basics#int");
+    }
+
+    #[test]
+    fn test_fqname_serde() {
+        let fq = FQName::parse("my/pkg:my/mod:my-func").unwrap();
+        let json = serde_json::to_string(&fq).unwrap();
+        assert_eq!(json, "\"my/pkg:my/mod:my-func\"");
+        assert_eq!(serde_json::from_str::<FQName>(&json).unwrap(), fq);
+        assert!(serde_json::from_str::<FQName>("\"no-colons\"").is_err());
+
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper {
+            #[serde(with = "canonical")]
+            fqname: FQName,
+        }
+        let json = r#"{"fqname":"my/pkg:my/mod#my-func"}"#;
+        let wrapper: Wrapper = serde_json::from_str(json).unwrap();
+        assert_eq!(wrapper.fqname, fq);
+        assert_eq!(serde_json::to_string(&wrapper).unwrap(), json);
+    }
 }
@@ -1,11 +1,65 @@
-use lasso::{Spur, ThreadedRodeo};
+//! Global interner for the words of names.
+//!
+//! Every [`Name`](super::Name) holds its words as handles into one
+//! process-wide arena, so a word that appears a million times in a
+//! distribution is stored once. Deserializers intern words straight from
+//! the input, without an owned intermediate string.
+//!
+//! The arena starts sized for a typical distribution and grows as needed.
+//! Tools about to load something much larger can [`presize`] it first, and
+//! [`intern_stats`] reports how full it is.
+
+use lasso::{Capacity, Spur, ThreadedRodeo};
+use serde::Serialize;
+use std::num::NonZeroUsize;
 use std::sync::OnceLock;
 
 static INTERNER: OnceLock<ThreadedRodeo> = OnceLock::new();
 
+/// Distinct words the arena has room for before it grows.
+///
+/// Loading the evaluator test distribution (2 MB of V4 JSON) interns about
+/// 400 words, so models of that size never rehash the map.
+pub const DEFAULT_WORDS: usize = 1024;
+
+/// Bytes of word text the arena has room for before it allocates another
+/// bucket; the same load needs about 2 KB.
+pub const DEFAULT_BYTES: usize = 8 * 1024;
+
+/// Bytes of IR JSON per distinct word, as measured on the same load, with
+/// room to spare
+const DOCUMENT_BYTES_PER_WORD: usize = 2500;
+
+/// Average length of a word, with room to spare
+const BYTES_PER_WORD: usize = 8;
+
 /// Returns a reference to the global string interner.
 pub fn interner() -> &'static ThreadedRodeo {
-    INTERNER.get_or_init(ThreadedRodeo::new)
+    INTERNER.get_or_init(|| ThreadedRodeo::with_capacity(capacity(DEFAULT_WORDS, DEFAULT_BYTES)))
+}
+
+fn capacity(words: usize, bytes: usize) -> Capacity {
+    Capacity::new(words, NonZeroUsize::new(bytes).unwrap_or(NonZeroUsize::MIN))
+}
+
+/// Create the interner with room for `words` distinct words totalling
+/// `bytes`, instead of the defaults.
+///
+/// Only takes effect before the first word is interned; returns whether it
+/// did.
+pub fn presize(words: usize, bytes: usize) -> bool {
+    let mut created = false;
+    INTERNER.get_or_init(|| {
+        created = true;
+        ThreadedRodeo::with_capacity(capacity(words.max(DEFAULT_WORDS), bytes.max(DEFAULT_BYTES)))
+    });
+    created
+}
+
+/// [`presize`] the interner for loading IR JSON of `document_bytes` bytes
+pub fn presize_for_document(document_bytes: usize) -> bool {
+    let words = document_bytes / DOCUMENT_BYTES_PER_WORD;
+    presize(words, words * BYTES_PER_WORD)
 }
 
 /// A handle to an interned string (word).
@@ -20,3 +74,55 @@ pub fn intern(s: &str) -> Word {
 pub fn resolve(word: Word) -> &'static str {
     interner().resolve(&word)
 }
+
+/// How full the interner's arena is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InternStats {
+    /// Distinct words interned
+    pub words: usize,
+    /// Total length of the distinct words
+    pub word_bytes: usize,
+    /// Words the arena holds before its map grows
+    pub capacity: usize,
+    /// Bytes the arena has allocated for word text
+    pub arena_bytes: usize,
+}
+
+/// Statistics of the global interner.
+///
+/// Sums over every interned word, so it is meant for reports, not hot paths.
+pub fn intern_stats() -> InternStats {
+    let interner = interner();
+    InternStats {
+        words: interner.len(),
+        word_bytes: interner.strings().map(str::len).sum(),
+        capacity: interner.capacity(),
+        arena_bytes: interner.current_memory_usage(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_count_each_word_once() {
+        let before = intern_stats();
+        let word = "interner-stats-test-word";
+        intern(word);
+        intern(word);
+        let after = intern_stats();
+        assert!(after.words > before.words);
+        assert!(after.word_bytes >= before.word_bytes + word.len());
+        assert!(after.capacity >= after.words);
+        // Interning again never changes the handle
+        assert_eq!(intern(word), intern(word));
+    }
+
+    #[test]
+    fn test_presize_only_before_first_use() {
+        intern("in-use");
+        assert!(!presize(1_000_000, 1 << 20));
+    }
+}
@@ -9,11 +9,13 @@ pub mod qname;
 
 // Re-export common types
 pub use fqname::FQName;
-pub use interner::{Word, intern, resolve};
+pub use interner::{
+    InternStats, Word, intern, intern_stats, presize, presize_for_document, resolve,
+};
 pub use module_name::ModuleName;
 pub use name::Name;
 pub use package_name::PackageName;
-pub use path::Path;
+pub use path::{CLASSIC_SDK_PACKAGE, Path, SDK_PACKAGE};
 pub use qname::QName;
 
 /// Namespace for serialization codecs
@@ -21,14 +23,26 @@ pub mod codecs {
     /// Classic (Legacy/V3) serialization logic
     pub mod classic {
         use crate::naming::{Name, Path};
-        use serde::Serializer;
+        use serde::{Serialize, Serializer};
+
+        /// Words of a name as a Classic array
+        struct Words<'a>(&'a Name);
+
+        impl Serialize for Words<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.collect_seq(self.0.iter())
+            }
+        }
 
         pub fn serialize_name<S>(name: &Name, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
         {
             // Legacy Name: ["word", "word"]
-            serializer.collect_seq(&name.words)
+            Words(name).serialize(serializer)
         }
 
         pub fn serialize_path<S>(path: &Path, serializer: S) -> Result<S::Ok, S::Error>
@@ -36,8 +50,7 @@ pub mod codecs {
             S: Serializer,
         {
             // Legacy Path: [Name, Name] where Name is ["word", "word"]
-            let parts: Vec<&Vec<String>> = path.segments.iter().map(|n| &n.words).collect();
-            serializer.collect_seq(parts)
+            serializer.collect_seq(path.segments.iter().map(Words))
         }
     }
 }
@@ -1,52 +1,71 @@
-use schemars::JsonSchema;
+use super::interner::{Word, intern, resolve};
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::borrow::Cow;
+use std::fmt::{self, Write};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, JsonSchema)]
+/// A name made of words, e.g. `["value", "in", "usd"]`.
+///
+/// Words are interned, so names are cheap to clone and compare, and
+/// serialization writes the canonical kebab-case string straight from the
+/// interned words.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Name {
-    pub words: Vec<String>,
+    pub words: Vec<Word>,
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Name")
+            .field("words", &self.iter().collect::<Vec<_>>())
+            .finish()
+    }
 }
 
 impl Name {
     /// Create a new Name from a slice of words
     pub fn new(words: &[&str]) -> Self {
         Name {
-            words: words.iter().map(|s| s.to_string()).collect(),
+            words: words.iter().map(|s| intern(s)).collect(),
         }
     }
 
     /// Parse a Name from a string (kebab-case, snake_case, camelCase, etc)
     pub fn from(name: &str) -> Self {
         let mut words = Vec::new();
-        let mut current_word = String::new();
+        let mut start = None;
 
-        for c in name.chars() {
+        for (i, c) in name.char_indices() {
             if c == '_' || c == '-' || c == '/' || c == '.' || c == ':' {
-                if !current_word.is_empty() {
-                    words.push(current_word);
-                    current_word = String::new();
+                if let Some(s) = start.take() {
+                    words.push(intern(&name[s..i]));
                 }
             } else if c.is_uppercase() {
-                if !current_word.is_empty() {
-                    // Split on uppercase if we have a current word
-                    words.push(current_word);
-                    current_word = String::new();
+                // Split on uppercase if we have a current word
+                if let Some(s) = start {
+                    words.push(intern(&name[s..i]));
                 }
-                current_word.push(c);
-            } else {
-                current_word.push(c);
+                start = Some(i);
+            } else if start.is_none() {
+                start = Some(i);
             }
         }
-        if !current_word.is_empty() {
-            words.push(current_word);
+        if let Some(s) = start {
+            words.push(intern(&name[s..]));
         }
 
         Name { words }
     }
 
+    /// Words as strings
+    pub fn iter(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.words.iter().map(|w| resolve(*w))
+    }
+
     pub fn to_camel_case(&self) -> String {
         let mut result = String::new();
-        for (i, word) in self.words.iter().enumerate() {
+        for (i, word) in self.iter().enumerate() {
             if i == 0 {
                 result.push_str(&word.to_lowercase());
             } else {
@@ -61,16 +80,15 @@ impl Name {
     }
 
     pub fn to_snake_case(&self) -> String {
-        self.words.join("_").to_lowercase()
+        self.iter().collect::<Vec<_>>().join("_").to_lowercase()
     }
 
     pub fn to_kebab_case(&self) -> String {
-        self.words.join("-").to_lowercase()
+        self.to_string()
     }
 
     pub fn to_title_case(&self) -> String {
-        self.words
-            .iter()
+        self.iter()
             .map(|w| {
                 let mut chars = w.chars();
                 match chars.next() {
@@ -87,13 +105,74 @@ impl Name {
             .chars()
             .all(|c| c.is_lowercase() || c == '_')
     }
+
+    /// Write the kebab-case form without building intermediate strings
+    pub(crate) fn write_kebab(&self, f: &mut impl Write) -> fmt::Result {
+        for (i, word) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_char('-')?;
+            }
+            if word.chars().any(char::is_uppercase) {
+                for c in word.chars().flat_map(char::to_lowercase) {
+                    f.write_char(c)?;
+                }
+            } else {
+                f.write_str(word)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for Name {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Use kebab-case for canonical string representation to match test expectations
         // and standard path formatting
-        write!(f, "{}", self.to_kebab_case())
+        self.write_kebab(f)
+    }
+}
+
+/// Buffer for the kebab-case form of a name: on the stack for the usual short
+/// names, on the heap only for long ones
+pub(crate) enum KebabBuffer {
+    Inline([u8; KebabBuffer::INLINE], usize),
+    Heap(String),
+}
+
+impl KebabBuffer {
+    const INLINE: usize = 64;
+
+    pub(crate) fn new() -> Self {
+        KebabBuffer::Inline([0; Self::INLINE], 0)
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            // Only whole `&str`s are ever copied in, so the prefix is valid UTF-8
+            KebabBuffer::Inline(bytes, len) => {
+                std::str::from_utf8(&bytes[..*len]).expect("buffer holds whole strings")
+            }
+            KebabBuffer::Heap(s) => s,
+        }
+    }
+}
+
+impl Write for KebabBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self {
+            KebabBuffer::Inline(bytes, len) if *len + s.len() <= Self::INLINE => {
+                bytes[*len..*len + s.len()].copy_from_slice(s.as_bytes());
+                *len += s.len();
+            }
+            KebabBuffer::Inline(bytes, len) => {
+                let mut heap = String::with_capacity(*len + s.len());
+                heap.push_str(std::str::from_utf8(&bytes[..*len]).map_err(|_| fmt::Error)?);
+                heap.push_str(s);
+                *self = KebabBuffer::Heap(heap);
+            }
+            KebabBuffer::Heap(heap) => heap.push_str(s),
+        }
+        Ok(())
     }
 }
 
@@ -102,8 +181,79 @@ impl Serialize for Name {
     where
         S: serde::Serializer,
     {
-        // V4 canonical format: serialize as kebab-case string
-        serializer.serialize_str(&self.to_kebab_case())
+        // V4 canonical format: kebab-case string, written straight from the
+        // interned words
+        let mut buffer = KebabBuffer::new();
+        self.write_kebab(&mut buffer)
+            .map_err(|_| serde::ser::Error::custom("failed to format name"))?;
+        serializer.serialize_str(buffer.as_str())
+    }
+}
+
+impl JsonSchema for Name {
+    fn schema_name() -> Cow<'static, str> {
+        "Name".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "Kebab-case name, e.g. `value-in-usd`",
+        })
+    }
+}
+
+/// Deserializes one word straight into the interner, without an owned
+/// intermediate string
+pub(crate) struct InternedWord;
+
+impl<'de> DeserializeSeed<'de> for InternedWord {
+    type Value = Word;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Word, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for InternedWord {
+    type Value = Word;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a word")
+    }
+
+    fn visit_str<E: de::Error>(self, word: &str) -> Result<Word, E> {
+        Ok(intern(word))
+    }
+}
+
+struct NameVisitor;
+
+impl<'de> Visitor<'de> for NameVisitor {
+    type Value = Name;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("string or array for Name")
+    }
+
+    // V4 canonical string format: "testModule" or "my-function"
+    fn visit_str<E: de::Error>(self, name: &str) -> Result<Name, E> {
+        Ok(Name::from(name))
+    }
+
+    // Classic array format: ["test", "module"]
+    fn visit_seq<A>(self, mut seq: A) -> Result<Name, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut words = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(word) = seq.next_element_seed(InternedWord)? {
+            words.push(word);
+        }
+        Ok(Name { words })
     }
 }
 
@@ -112,25 +262,43 @@ impl<'de> Deserialize<'de> for Name {
     where
         D: serde::Deserializer<'de>,
     {
-        use serde::de;
-
         // Accept both array format (Classic) and string format (V4)
-        let value = serde_json::Value::deserialize(deserializer)?;
-        match value {
-            // V4 canonical string format: "testModule" or "my-function"
-            serde_json::Value::String(s) => Ok(Name::from(&s)),
-            // Classic array format: ["test", "module"]
-            serde_json::Value::Array(arr) => {
-                let words: Result<Vec<String>, _> = arr
-                    .into_iter()
-                    .map(|v| match v {
-                        serde_json::Value::String(s) => Ok(s),
-                        _ => Err(de::Error::custom("expected string in Name array")),
-                    })
-                    .collect();
-                Ok(Name { words: words? })
-            }
-            _ => Err(de::Error::custom("expected string or array for Name")),
-        }
+        deserializer.deserialize_any(NameVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_writes_kebab_case_from_words() {
+        let name = Name::from("valueInUSD");
+        assert_eq!(serde_json::to_string(&name).unwrap(), "\"value-in-u-s-d\"");
+
+        let parsed: Name = serde_json::from_str("\"value-in-u-s-d\"").unwrap();
+        assert_eq!(parsed, Name::new(&["value", "in", "u", "s", "d"]));
+        let classic: Name = serde_json::from_str(r#"["value", "in", "usd"]"#).unwrap();
+        assert_eq!(classic.to_string(), "value-in-usd");
+        assert!(serde_json::from_str::<Name>("42").is_err());
+
+        let long = Name::new(&["word"; 40]);
+        assert_eq!(
+            serde_json::to_string(&long).unwrap(),
+            format!("\"{}\"", long.to_kebab_case())
+        );
+    }
+
+    #[test]
+    fn test_from_splits_like_before() {
+        assert_eq!(
+            Name::from("TestModule").iter().collect::<Vec<_>>(),
+            vec!["Test", "Module"]
+        );
+        assert_eq!(
+            Name::from("__my--func_").iter().collect::<Vec<_>>(),
+            vec!["my", "func"]
+        );
+        assert_eq!(Name::from("TestModule").to_kebab_case(), "test-module");
     }
 }
@@ -11,6 +11,7 @@ use std::str::FromStr;
 /// Serializes as a canonical string (e.g., "my-org/my-lib") for V4 format.
 /// Deserializes from both string (V4) and array (Classic) formats.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, JsonSchema)]
+#[schemars(with = "String")]
 pub struct PackageName(pub Path);
 
 impl Serialize for PackageName {
@@ -19,7 +20,7 @@ impl Serialize for PackageName {
         S: serde::Serializer,
     {
         // Serialize as canonical string for V4 format
-        serializer.serialize_str(&self.0.to_string())
+        serializer.collect_str(&self.0)
     }
 }
 
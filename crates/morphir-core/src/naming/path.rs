@@ -1,9 +1,18 @@
 use super::Name;
 use schemars::JsonSchema;
+use serde::de::{self, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::fmt::{self, Write};
+
+/// The Morphir SDK package, as V4 IR spells it
+pub const SDK_PACKAGE: &str = "morphir/sdk";
+
+/// The SDK package as Classic IR from morphir-elm spells it, with the acronym
+/// split into words
+pub const CLASSIC_SDK_PACKAGE: &str = "morphir/s-d-k";
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, JsonSchema)]
+#[schemars(transparent)]
 pub struct Path {
     pub segments: Vec<Name>,
 }
@@ -17,16 +26,33 @@ impl Path {
     pub fn is_empty(&self) -> bool {
         self.segments.is_empty()
     }
+
+    /// Whether this is the Morphir SDK package, in either spelling
+    pub fn is_sdk(&self) -> bool {
+        matches!(self.to_string().as_str(), SDK_PACKAGE | CLASSIC_SDK_PACKAGE)
+    }
+
+    /// Canonical string of this path, with the SDK package always spelled
+    /// `morphir/sdk`
+    pub fn to_normalized_string(&self) -> String {
+        let path = self.to_string();
+        if path == CLASSIC_SDK_PACKAGE {
+            SDK_PACKAGE.to_string()
+        } else {
+            path
+        }
+    }
 }
 
 impl fmt::Display for Path {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let parts: Vec<String> = self
-            .segments
-            .iter()
-            .map(|n: &super::Name| n.to_kebab_case())
-            .collect();
-        write!(f, "{}", parts.join("/"))
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                f.write_char('/')?;
+            }
+            segment.write_kebab(f)?;
+        }
+        Ok(())
     }
 }
 
@@ -39,29 +65,39 @@ impl Serialize for Path {
     }
 }
 
+struct PathVisitor;
+
+impl<'de> Visitor<'de> for PathVisitor {
+    type Value = Path;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("string or array for Path")
+    }
+
+    // V4 canonical string format: "my-org/my-lib" or "test-package"
+    fn visit_str<E: de::Error>(self, path: &str) -> Result<Path, E> {
+        Ok(Path::new(path))
+    }
+
+    // Classic array format: [["my"], ["org"], ["my"], ["lib"]]
+    fn visit_seq<A>(self, mut seq: A) -> Result<Path, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut segments = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(segment) = seq.next_element::<Name>()? {
+            segments.push(segment);
+        }
+        Ok(Path { segments })
+    }
+}
+
 impl<'de> Deserialize<'de> for Path {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        use serde::de;
-
         // Accept both array format (Classic) and string format (V4)
-        let value = serde_json::Value::deserialize(deserializer)?;
-        match value {
-            // V4 canonical string format: "my-org/my-lib" or "test-package"
-            serde_json::Value::String(s) => Ok(Path::new(&s)),
-            // Classic array format: [["my"], ["org"], ["my"], ["lib"]]
-            serde_json::Value::Array(arr) => {
-                let segments: Result<Vec<Name>, _> = arr
-                    .into_iter()
-                    .map(|v| serde_json::from_value(v).map_err(de::Error::custom))
-                    .collect();
-                Ok(Path {
-                    segments: segments?,
-                })
-            }
-            _ => Err(de::Error::custom("expected string or array for Path")),
-        }
+        deserializer.deserialize_any(PathVisitor)
     }
 }
@@ -1,10 +1,12 @@
 use crate::naming::{name::Name, path::Path};
 use schemars::JsonSchema;
+use serde::de::{self, Visitor};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// QName represents a Qualified Name (Path + Name).
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
-#[serde(try_from = "String", into = "String")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, JsonSchema)]
+#[schemars(with = "String")]
 pub struct QName {
     pub module_path: Path,
     pub local_name: Name,
@@ -48,6 +50,38 @@ impl TryFrom<String> for QName {
     }
 }
 
+impl Serialize for QName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+struct QNameVisitor;
+
+impl<'de> Visitor<'de> for QNameVisitor {
+    type Value = QName;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("string for QName")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<QName, E> {
+        QName::parse(s).ok_or_else(|| E::custom(format!("Invalid QName string: {}", s)))
+    }
+}
+
+impl<'de> Deserialize<'de> for QName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(QNameVisitor)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
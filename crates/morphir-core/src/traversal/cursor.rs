@@ -1,47 +1,548 @@
-use crate::naming::Path;
+//! A zipper over V4 definitions.
+//!
+//! A [`Cursor`] holds a copy of a value or type definition and a focus on
+//! one of its nodes. It moves the focus to the parent, a child, or a sibling
+//! of the focused node, replaces the focused node, and finally rebuilds the
+//! definition with every replacement in place. The definition the cursor
+//! was made from is never changed, so refactoring tools can build their edit
+//! and decide afterwards whether to keep it.
+//!
+//! The children of a node are the values and types directly inside it, in
+//! the order they appear in the IR:
+//!
+//! - a value definition has its input types, its output type, and its body;
+//! - a type definition has the type of an alias, or the argument types of
+//!   its constructors;
+//! - a let definition inside a value has the children of the definition it
+//!   binds, then its body;
+//! - other values and types have their subexpressions.
+//!
+//! Patterns are not nodes of their own; they stay with the value holding
+//! them.
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let mut cursor = Cursor::from_value_definition(&definition);
+//! while cursor.advance() {
+//!     if let Some(Value::Reference(attrs, fqname)) = cursor.value()
+//!         && *fqname == old_name
+//!     {
+//!         let renamed = Value::Reference(attrs.clone(), new_name.clone());
+//!         cursor.replace(Node::Value(renamed)).unwrap();
+//!     }
+//! }
+//! let renamed = cursor.into_value_definition().unwrap();
+//! ```
 
-/// A Cursor for navigating the IR with context.
-#[derive(Debug, Clone, Default)]
+use std::mem;
+
+use thiserror::Error;
+
+use crate::ir::v4::attributes::{TypeAttributes, ValueAttributes};
+use crate::ir::v4::types::{Type, TypeDefinition};
+use crate::ir::v4::value::{Value, ValueBody, ValueDefinition};
+
+/// A node a [`Cursor`] can focus on
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    ValueDefinition(Box<ValueDefinition>),
+    TypeDefinition(Box<TypeDefinition>),
+    Value(Value),
+    Type(Type),
+}
+
+impl Node {
+    fn kind(&self) -> &'static str {
+        match self {
+            Node::ValueDefinition(_) => "value definition",
+            Node::TypeDefinition(_) => "type definition",
+            Node::Value(_) => "value",
+            Node::Type(_) => "type",
+        }
+    }
+}
+
+/// Error replacing the focused node with a node of another kind
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("cannot replace a {focus} with a {replacement}")]
+pub struct KindMismatch {
+    pub focus: &'static str,
+    pub replacement: &'static str,
+}
+
+/// A child of a node, borrowed mutably
+enum Slot<'a> {
+    Value(&'a mut Value),
+    Type(&'a mut Type),
+}
+
+impl Slot<'_> {
+    /// Move the child out, leaving a unit in its place
+    fn take(self) -> Node {
+        match self {
+            Slot::Value(value) => {
+                Node::Value(mem::replace(value, Value::Unit(ValueAttributes::default())))
+            }
+            Slot::Type(tpe) => Node::Type(mem::replace(tpe, Type::Unit(TypeAttributes::default()))),
+        }
+    }
+
+    /// Put `node` back in the place of the child
+    fn put(self, node: Node) {
+        match (self, node) {
+            (Slot::Value(slot), Node::Value(value)) => *slot = value,
+            (Slot::Type(slot), Node::Type(tpe)) => *slot = tpe,
+            _ => unreachable!("replacements are checked to be of the same kind"),
+        }
+    }
+}
+
+/// The parent of the focused node, with the focused node taken out of it
+#[derive(Debug, Clone)]
+struct Frame {
+    parent: Node,
+    index: usize,
+}
+
+/// A zipper focused on one node of a definition
+#[derive(Debug, Clone)]
 pub struct Cursor {
-    path_stack: Vec<String>,
-    depth: usize,
+    focus: Node,
+    frames: Vec<Frame>,
 }
 
 impl Cursor {
-    pub fn new() -> Self {
-        Self::default()
+    /// Cursor on a copy of `root`, focused on `root` itself
+    pub fn new(root: Node) -> Self {
+        Cursor {
+            focus: root,
+            frames: Vec::new(),
+        }
     }
 
-    /// Enter a named segment of the IR tree (pushes to path stack).
-    pub fn enter(&mut self, segment: &str) {
-        self.path_stack.push(segment.to_string());
-        self.depth += 1;
+    /// Cursor on a copy of value definition `definition`
+    pub fn from_value_definition(definition: &ValueDefinition) -> Self {
+        Cursor::new(Node::ValueDefinition(Box::new(definition.clone())))
     }
 
-    /// Exit the current segment (pops from path stack).
-    pub fn exit(&mut self) {
-        self.path_stack.pop();
-        self.depth -= 1;
+    /// Cursor on a copy of type definition `definition`
+    pub fn from_type_definition(definition: &TypeDefinition) -> Self {
+        Cursor::new(Node::TypeDefinition(Box::new(definition.clone())))
     }
 
-    /// Advance to the next sibling (currently a placeholder for sibling tracking).
-    pub fn next(&mut self) {
-        // Future: track index or sibling state
+    /// The focused node
+    pub fn focus(&self) -> &Node {
+        &self.focus
     }
 
-    /// Get the current traversal path as a Morphir Path.
-    pub fn path(&self) -> Path {
-        // Simple conversion: treating stack segments as path words
-        let segments: Vec<crate::naming::Name> = self
-            .path_stack
-            .iter()
-            .map(|s| crate::naming::Name::from(s.as_str()))
-            .collect();
-        Path { segments }
+    /// The focused node, if it is a value
+    pub fn value(&self) -> Option<&Value> {
+        match &self.focus {
+            Node::Value(value) => Some(value),
+            _ => None,
+        }
     }
 
-    /// Get current nesting depth.
+    /// The focused node, if it is a type
+    pub fn tpe(&self) -> Option<&Type> {
+        match &self.focus {
+            Node::Type(tpe) => Some(tpe),
+            _ => None,
+        }
+    }
+
+    /// How many nodes the focused node is nested in
     pub fn depth(&self) -> usize {
-        self.depth
+        self.frames.len()
+    }
+
+    /// Child indices leading from the root to the focused node
+    pub fn path(&self) -> Vec<usize> {
+        self.frames.iter().map(|frame| frame.index).collect()
+    }
+
+    /// Whether the focus is on the root
+    pub fn is_root(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Number of children of the focused node
+    pub fn child_count(&self) -> usize {
+        child_count(&self.focus)
+    }
+
+    /// Move the focus to child `index` of the focused node. Returns whether
+    /// there was one; the focus stays put when there was not.
+    pub fn child(&mut self, index: usize) -> bool {
+        let Some(child) = slots(&mut self.focus).into_iter().nth(index) else {
+            return false;
+        };
+        let child = child.take();
+        let parent = mem::replace(&mut self.focus, child);
+        self.frames.push(Frame { parent, index });
+        true
+    }
+
+    /// Move the focus to the parent of the focused node. Returns whether
+    /// there was one.
+    pub fn parent(&mut self) -> bool {
+        let Some(Frame { mut parent, index }) = self.frames.pop() else {
+            return false;
+        };
+        let child = mem::replace(
+            &mut self.focus,
+            Node::Value(Value::Unit(Default::default())),
+        );
+        if let Some(slot) = slots(&mut parent).into_iter().nth(index) {
+            slot.put(child);
+        }
+        self.focus = parent;
+        true
+    }
+
+    /// Move the focus to the next sibling of the focused node. Returns
+    /// whether there was one.
+    pub fn next_sibling(&mut self) -> bool {
+        self.sibling(1)
+    }
+
+    /// Move the focus to the previous sibling of the focused node. Returns
+    /// whether there was one.
+    pub fn prev_sibling(&mut self) -> bool {
+        self.sibling(-1)
+    }
+
+    fn sibling(&mut self, offset: isize) -> bool {
+        let Some(frame) = self.frames.last() else {
+            return false;
+        };
+        let Some(index) = frame.index.checked_add_signed(offset) else {
+            return false;
+        };
+        if index >= child_count(&frame.parent) {
+            return false;
+        }
+        self.parent();
+        self.child(index)
+    }
+
+    /// Move the focus to the next node in depth-first order: the first
+    /// child, else the next sibling, else the next sibling of the closest
+    /// ancestor that has one. Returns whether there was one; at the last
+    /// node the focus is left on the root.
+    pub fn advance(&mut self) -> bool {
+        if self.child(0) {
+            return true;
+        }
+        loop {
+            if self.next_sibling() {
+                return true;
+            }
+            if !self.parent() {
+                return false;
+            }
+        }
+    }
+
+    /// Move the focus to the next node in depth-first order that satisfies
+    /// `predicate`. Returns whether there was one.
+    pub fn find(&mut self, mut predicate: impl FnMut(&Node) -> bool) -> bool {
+        while self.advance() {
+            if predicate(&self.focus) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Replace the focused node with `node`, returning the node replaced.
+    ///
+    /// A value can only be replaced by a value, a type by a type, and a
+    /// definition by a definition of the same kind; otherwise nothing
+    /// changes.
+    pub fn replace(&mut self, node: Node) -> Result<Node, KindMismatch> {
+        if mem::discriminant(&self.focus) != mem::discriminant(&node) {
+            return Err(KindMismatch {
+                focus: self.focus.kind(),
+                replacement: node.kind(),
+            });
+        }
+        Ok(mem::replace(&mut self.focus, node))
+    }
+
+    /// Move the focus back to the root and return the rebuilt root node
+    pub fn into_root(mut self) -> Node {
+        while self.parent() {}
+        self.focus
+    }
+
+    /// The rebuilt value definition, if the cursor was made on one
+    pub fn into_value_definition(self) -> Option<ValueDefinition> {
+        match self.into_root() {
+            Node::ValueDefinition(definition) => Some(*definition),
+            _ => None,
+        }
+    }
+
+    /// The rebuilt type definition, if the cursor was made on one
+    pub fn into_type_definition(self) -> Option<TypeDefinition> {
+        match self.into_root() {
+            Node::TypeDefinition(definition) => Some(*definition),
+            _ => None,
+        }
+    }
+}
+
+fn child_count(node: &Node) -> usize {
+    match node {
+        Node::ValueDefinition(definition) => definition_child_count(definition),
+        Node::TypeDefinition(definition) => match &**definition {
+            TypeDefinition::TypeAliasDefinition { .. } => 1,
+            TypeDefinition::CustomTypeDefinition { constructors, .. } => constructors
+                .value
+                .iter()
+                .map(|constructor| constructor.args.len())
+                .sum(),
+            TypeDefinition::IncompleteTypeDefinition { .. } => 0,
+        },
+        Node::Type(tpe) => match tpe {
+            Type::Reference(_, _, args) | Type::Tuple(_, args) => args.len(),
+            Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => fields.len(),
+            Type::Function(..) => 2,
+            Type::Variable(..) | Type::Unit(_) => 0,
+        },
+        Node::Value(value) => match value {
+            Value::Tuple(_, elements) | Value::List(_, elements) => elements.len(),
+            Value::Record(_, fields) => fields.len(),
+            Value::Field(..) | Value::Lambda(..) => 1,
+            Value::Apply(..) | Value::Destructure(..) => 2,
+            Value::LetDefinition(_, _, definition, _) => definition_child_count(definition) + 1,
+            Value::LetRecursion(_, bindings, _) => {
+                bindings
+                    .iter()
+                    .map(|binding| definition_child_count(&binding.1))
+                    .sum::<usize>()
+                    + 1
+            }
+            Value::IfThenElse(..) => 3,
+            Value::PatternMatch(_, _, cases) => cases.len() + 1,
+            Value::UpdateRecord(_, _, fields) => fields.len() + 1,
+            Value::Hole(_, _, expected) => usize::from(expected.is_some()),
+            Value::Literal(..)
+            | Value::Constructor(..)
+            | Value::Variable(..)
+            | Value::Reference(..)
+            | Value::FieldFunction(..)
+            | Value::Unit(_)
+            | Value::Native(..)
+            | Value::External(..) => 0,
+        },
+    }
+}
+
+fn definition_child_count(definition: &ValueDefinition) -> usize {
+    let body = usize::from(matches!(definition.body, ValueBody::Expression(_)));
+    definition.input_types.len() + 1 + body
+}
+
+/// Children of `node`, in order
+fn slots(node: &mut Node) -> Vec<Slot<'_>> {
+    match node {
+        Node::ValueDefinition(definition) => definition_slots(definition),
+        Node::TypeDefinition(definition) => match &mut **definition {
+            TypeDefinition::TypeAliasDefinition { type_expr, .. } => vec![Slot::Type(type_expr)],
+            TypeDefinition::CustomTypeDefinition { constructors, .. } => constructors
+                .value
+                .iter_mut()
+                .flat_map(|constructor| constructor.args.iter_mut())
+                .map(|arg| Slot::Type(&mut arg.arg_type))
+                .collect(),
+            TypeDefinition::IncompleteTypeDefinition { .. } => Vec::new(),
+        },
+        Node::Type(tpe) => type_slots(tpe),
+        Node::Value(value) => value_slots(value),
+    }
+}
+
+fn definition_slots(definition: &mut ValueDefinition) -> Vec<Slot<'_>> {
+    let mut slots: Vec<Slot<'_>> = definition
+        .input_types
+        .values_mut()
+        .map(|input| Slot::Type(&mut input.input_type))
+        .collect();
+    slots.push(Slot::Type(&mut definition.output_type));
+    if let ValueBody::Expression(body) = &mut definition.body {
+        slots.push(Slot::Value(body));
+    }
+    slots
+}
+
+fn type_slots(tpe: &mut Type) -> Vec<Slot<'_>> {
+    match tpe {
+        Type::Reference(_, _, args) | Type::Tuple(_, args) => {
+            args.iter_mut().map(Slot::Type).collect()
+        }
+        Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => fields
+            .iter_mut()
+            .map(|field| Slot::Type(&mut field.tpe))
+            .collect(),
+        Type::Function(_, arg, result) => vec![Slot::Type(arg), Slot::Type(result)],
+        Type::Variable(..) | Type::Unit(_) => Vec::new(),
+    }
+}
+
+fn value_slots(value: &mut Value) -> Vec<Slot<'_>> {
+    match value {
+        Value::Tuple(_, elements) | Value::List(_, elements) => {
+            elements.iter_mut().map(Slot::Value).collect()
+        }
+        Value::Record(_, fields) => fields
+            .iter_mut()
+            .map(|field| Slot::Value(&mut field.1))
+            .collect(),
+        Value::Field(_, subject, _) => vec![Slot::Value(subject)],
+        Value::Apply(_, function, argument) => vec![Slot::Value(function), Slot::Value(argument)],
+        Value::Lambda(_, _, body) => vec![Slot::Value(body)],
+        Value::LetDefinition(_, _, definition, body) => {
+            let mut slots = definition_slots(definition);
+            slots.push(Slot::Value(body));
+            slots
+        }
+        Value::LetRecursion(_, bindings, body) => {
+            let mut slots: Vec<Slot<'_>> = bindings
+                .iter_mut()
+                .flat_map(|binding| definition_slots(&mut binding.1))
+                .collect();
+            slots.push(Slot::Value(body));
+            slots
+        }
+        Value::Destructure(_, _, bound, body) => vec![Slot::Value(bound), Slot::Value(body)],
+        Value::IfThenElse(_, condition, then_branch, else_branch) => vec![
+            Slot::Value(condition),
+            Slot::Value(then_branch),
+            Slot::Value(else_branch),
+        ],
+        Value::PatternMatch(_, subject, cases) => {
+            let mut slots = vec![Slot::Value(subject)];
+            slots.extend(cases.iter_mut().map(|case| Slot::Value(&mut case.1)));
+            slots
+        }
+        Value::UpdateRecord(_, subject, fields) => {
+            let mut slots = vec![Slot::Value(subject)];
+            slots.extend(fields.iter_mut().map(|field| Slot::Value(&mut field.1)));
+            slots
+        }
+        Value::Hole(_, _, Some(expected)) => vec![Slot::Type(expected)],
+        Value::Literal(..)
+        | Value::Constructor(..)
+        | Value::Variable(..)
+        | Value::Reference(..)
+        | Value::FieldFunction(..)
+        | Value::Unit(_)
+        | Value::Hole(_, _, None)
+        | Value::Native(..)
+        | Value::External(..) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::v4::Distribution;
+    use crate::ir::v4::fixtures::{parse, reference};
+
+    fn definition() -> ValueDefinition {
+        let distribution = parse(
+            "library acme/shop\n\nmodule orders\n\ntotal (x : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =\n    if x then\n        morphir/sdk:basics#negate x\n    else\n        x\n",
+        );
+        let Distribution::Library(lib) = distribution else {
+            panic!("expected a library");
+        };
+        lib.def.modules["orders"].value.values["total"]
+            .value
+            .clone()
+    }
+
+    #[test]
+    fn test_navigate_and_replace() {
+        let original = definition();
+        let mut cursor = Cursor::from_value_definition(&original);
+        // Input type, output type, body
+        assert_eq!(cursor.child_count(), 3);
+        assert!(!cursor.parent());
+        assert!(cursor.child(2));
+        assert!(matches!(cursor.value(), Some(Value::IfThenElse(..))));
+        assert!(cursor.child(1));
+        assert!(cursor.child(0));
+        assert_eq!(cursor.path(), [2, 1, 0]);
+        assert_eq!(
+            cursor.value(),
+            Some(&reference("morphir/sdk:basics#negate"))
+        );
+
+        let replaced = cursor
+            .replace(Node::Value(reference("morphir/sdk:basics#abs")))
+            .unwrap();
+        assert_eq!(
+            replaced,
+            Node::Value(reference("morphir/sdk:basics#negate"))
+        );
+        assert_eq!(
+            cursor
+                .replace(Node::Type(Type::Unit(TypeAttributes::default())))
+                .unwrap_err()
+                .to_string(),
+            "cannot replace a value with a type"
+        );
+
+        assert!(cursor.next_sibling());
+        assert!(matches!(cursor.value(), Some(Value::Variable(..))));
+        assert!(!cursor.next_sibling());
+        assert!(cursor.prev_sibling());
+        assert_eq!(cursor.value(), Some(&reference("morphir/sdk:basics#abs")));
+        assert!(!cursor.child(0));
+
+        let edited = cursor.into_value_definition().unwrap();
+        let ValueBody::Expression(Value::IfThenElse(_, _, then_branch, _)) = &edited.body else {
+            panic!("expected an if");
+        };
+        let Value::Apply(_, function, _) = &**then_branch else {
+            panic!("expected an application");
+        };
+        assert_eq!(**function, reference("morphir/sdk:basics#abs"));
+        // The definition the cursor was made from is unchanged
+        assert_eq!(original, definition());
+        assert_ne!(edited, original);
+    }
+
+    #[test]
+    fn test_next_walks_depth_first() {
+        let original = definition();
+        let mut cursor = Cursor::from_value_definition(&original);
+        let mut paths = Vec::new();
+        while cursor.advance() {
+            paths.push(cursor.path());
+        }
+        assert!(cursor.is_root());
+        assert_eq!(
+            paths,
+            [
+                vec![0],
+                vec![1],
+                vec![2],
+                vec![2, 0],
+                vec![2, 1],
+                vec![2, 1, 0],
+                vec![2, 1, 1],
+                vec![2, 2],
+            ]
+        );
+
+        let mut cursor = Cursor::from_value_definition(&original);
+        assert!(cursor.find(|node| matches!(node, Node::Value(Value::Apply(..)))));
+        assert_eq!(cursor.path(), [2, 1]);
+        assert!(!cursor.find(|node| matches!(node, Node::Value(Value::Apply(..)))));
+        assert_eq!(cursor.into_value_definition(), Some(original));
     }
 }
@@ -1,13 +1,3 @@
 pub mod cursor;
-pub mod transform;
-pub mod visitor;
-pub mod walker;
 
-pub use cursor::Cursor;
-pub use transform::{
-    PatternTransformVisitor, TypeTransformVisitor, ValueTransformVisitor,
-    transform_type_definition, walk_transform_pattern, walk_transform_type, walk_transform_value,
-    walk_transform_value_definition,
-};
-pub use visitor::Visitor;
-pub use walker::*;
+pub use cursor::{Cursor, KindMismatch, Node};
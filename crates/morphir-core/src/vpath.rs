@@ -0,0 +1,377 @@
+//! Platform-independent virtual paths
+//!
+//! [`VirtualPath`] is the normalized form of a file path used wherever a path
+//! ends up in IR, artifacts, diagnostics, or VFS keys. It always uses `/` as
+//! the separator, so a module at `src\Foo\Bar.gleam` on Windows and
+//! `src/Foo/Bar.gleam` on Unix produce the same module name and the same
+//! output. Convert back to a native path with [`VirtualPath::to_path_buf`]
+//! only at the point of touching the real file system.
+//!
+//! Normalization rules:
+//! - `\` is treated as a separator and converted to `/`
+//! - repeated separators and `.` segments are removed, as is a trailing `/`
+//! - `..` cancels the preceding segment; leading `..` on relative paths is kept
+//! - drive letters are upper-cased (`c:\src` becomes `C:/src`)
+//! - UNC paths keep their leading `//`, and the `\\?\` verbatim prefix is dropped
+//! - the empty path is `.`
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A normalized, `/`-separated path that is the same on every platform.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, JsonSchema)]
+pub struct VirtualPath(String);
+
+impl VirtualPath {
+    /// Normalize a path string from any platform.
+    pub fn new(path: impl AsRef<str>) -> Self {
+        let unified = path.as_ref().replace('\\', "/");
+
+        // Strip verbatim prefixes: //?/C:/x -> C:/x, //?/UNC/server/x -> //server/x
+        let unified = if let Some(rest) = unified.strip_prefix("//?/UNC/") {
+            format!("//{}", rest)
+        } else if let Some(rest) = unified.strip_prefix("//?/") {
+            rest.to_string()
+        } else {
+            unified
+        };
+
+        let (prefix, rest) = split_root(&unified);
+        let rooted = !prefix.is_empty();
+
+        let mut segments: Vec<&str> = Vec::new();
+        for segment in rest.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => match segments.last() {
+                    Some(&last) if last != ".." => {
+                        segments.pop();
+                    }
+                    // Cannot go above the root
+                    _ if rooted => {}
+                    _ => segments.push(".."),
+                },
+                _ => segments.push(segment),
+            }
+        }
+
+        let mut normalized = prefix;
+        normalized.push_str(&segments.join("/"));
+        if normalized.is_empty() {
+            normalized.push('.');
+        }
+        VirtualPath(normalized)
+    }
+
+    /// Normalize a native path.
+    pub fn from_path(path: &Path) -> Self {
+        Self::new(path.to_string_lossy())
+    }
+
+    /// The normalized path as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the path is rooted (`/`, a drive letter, or a UNC share).
+    pub fn is_absolute(&self) -> bool {
+        !self.root().is_empty()
+    }
+
+    /// The segments of the path, excluding any root.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        let (_, rest) = self.split();
+        rest.split('/').filter(|s| !s.is_empty() && *s != ".")
+    }
+
+    /// The last segment of the path, if it is a normal segment.
+    pub fn file_name(&self) -> Option<&str> {
+        self.segments().last().filter(|s| *s != "..")
+    }
+
+    /// The file name without its extension.
+    pub fn file_stem(&self) -> Option<&str> {
+        let name = self.file_name()?;
+        match name.rfind('.') {
+            Some(0) | None => Some(name),
+            Some(i) => Some(&name[..i]),
+        }
+    }
+
+    /// The extension of the file name, if any.
+    pub fn extension(&self) -> Option<&str> {
+        let name = self.file_name()?;
+        match name.rfind('.') {
+            Some(0) | None => None,
+            Some(i) => Some(&name[i + 1..]),
+        }
+    }
+
+    /// The parent path, or `None` for a root or an empty path.
+    pub fn parent(&self) -> Option<VirtualPath> {
+        self.file_name()?;
+        let segments: Vec<&str> = self.segments().collect();
+        let parent = segments[..segments.len() - 1].join("/");
+        Some(VirtualPath::new(format!("{}{}", self.root(), parent)))
+    }
+
+    /// Append a path. An absolute `other` replaces this path entirely.
+    pub fn join(&self, other: impl AsRef<str>) -> VirtualPath {
+        let other = VirtualPath::new(other);
+        if other.is_absolute() {
+            return other;
+        }
+        VirtualPath::new(format!("{}/{}", self.0, other.0))
+    }
+
+    /// The path relative to `base`, if `base` is a prefix of it.
+    pub fn strip_prefix(&self, base: &VirtualPath) -> Option<VirtualPath> {
+        if self.root() != base.root() {
+            return None;
+        }
+        let mut segments = self.segments();
+        for expected in base.segments() {
+            if segments.next() != Some(expected) {
+                return None;
+            }
+        }
+        Some(VirtualPath::new(segments.collect::<Vec<_>>().join("/")))
+    }
+
+    /// Replace the extension of the file name. An empty `extension` removes it.
+    pub fn with_extension(&self, extension: &str) -> VirtualPath {
+        let Some(stem) = self.file_stem() else {
+            return self.clone();
+        };
+        let file_name = if extension.is_empty() {
+            stem.to_string()
+        } else {
+            format!("{}.{}", stem, extension)
+        };
+        match self.parent() {
+            Some(parent) => parent.join(file_name),
+            None => VirtualPath::new(file_name),
+        }
+    }
+
+    /// Convert to a native path for the host platform.
+    pub fn to_path_buf(&self) -> PathBuf {
+        let root = self.root();
+        let mut path = if root.is_empty() {
+            PathBuf::new()
+        } else {
+            PathBuf::from(root)
+        };
+        for segment in self.segments() {
+            path.push(segment);
+        }
+        if path.as_os_str().is_empty() {
+            path.push(".");
+        }
+        path
+    }
+
+    fn root(&self) -> &str {
+        self.split().0
+    }
+
+    fn split(&self) -> (&str, &str) {
+        let len = root_len(&self.0);
+        self.0.split_at(len)
+    }
+}
+
+/// Length of the root prefix of a `/`-separated path string.
+fn root_len(path: &str) -> usize {
+    let bytes = path.as_bytes();
+    if path.starts_with("//") {
+        2
+    } else if path.starts_with('/') {
+        1
+    } else if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        // Drive-relative paths (`C:foo`) are treated as rooted
+        if bytes.get(2) == Some(&b'/') { 3 } else { 2 }
+    } else {
+        0
+    }
+}
+
+/// Split a raw path into its normalized root and the remainder.
+fn split_root(path: &str) -> (String, &str) {
+    let len = root_len(path);
+    let (root, rest) = path.split_at(len);
+    let root = match len {
+        2 if root == "//" => "//".to_string(),
+        2 | 3 => format!("{}:/", root[..1].to_ascii_uppercase()),
+        _ => root.to_string(),
+    };
+    (root, rest)
+}
+
+impl Default for VirtualPath {
+    fn default() -> Self {
+        VirtualPath(".".to_string())
+    }
+}
+
+impl fmt::Display for VirtualPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for VirtualPath {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(VirtualPath::new(s))
+    }
+}
+
+impl AsRef<str> for VirtualPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for VirtualPath {
+    fn from(s: &str) -> Self {
+        VirtualPath::new(s)
+    }
+}
+
+impl From<String> for VirtualPath {
+    fn from(s: String) -> Self {
+        VirtualPath::new(s)
+    }
+}
+
+impl From<&Path> for VirtualPath {
+    fn from(path: &Path) -> Self {
+        VirtualPath::from_path(path)
+    }
+}
+
+impl From<&PathBuf> for VirtualPath {
+    fn from(path: &PathBuf) -> Self {
+        VirtualPath::from_path(path)
+    }
+}
+
+impl Serialize for VirtualPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for VirtualPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(VirtualPath::new(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("src/Foo/Bar.gleam", "src/Foo/Bar.gleam")]
+    #[case(r"src\Foo\Bar.gleam", "src/Foo/Bar.gleam")]
+    #[case(r"src\Foo/Bar.gleam", "src/Foo/Bar.gleam")]
+    #[case("./src//Foo/./Bar.gleam/", "src/Foo/Bar.gleam")]
+    #[case("src/Foo/../Baz.gleam", "src/Baz.gleam")]
+    #[case("../shared/x", "../shared/x")]
+    #[case("a/../../b", "../b")]
+    #[case("/usr/lib", "/usr/lib")]
+    #[case("/../etc", "/etc")]
+    #[case(r"C:\Users\me\proj", "C:/Users/me/proj")]
+    #[case(r"c:\proj\..\other", "C:/other")]
+    #[case(r"\\server\share\file.txt", "//server/share/file.txt")]
+    #[case(r"\\?\C:\proj\src", "C:/proj/src")]
+    #[case(r"\\?\UNC\server\share", "//server/share")]
+    #[case("", ".")]
+    #[case(".", ".")]
+    #[case("/", "/")]
+    #[case(r"C:\", "C:/")]
+    fn test_normalization(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(VirtualPath::new(input).as_str(), expected);
+    }
+
+    #[test]
+    fn test_windows_and_unix_paths_are_equal() {
+        assert_eq!(
+            VirtualPath::new(r"src\Morphir\SDK\List.gleam"),
+            VirtualPath::new("src/Morphir/SDK/List.gleam")
+        );
+    }
+
+    #[rstest]
+    #[case("src/Foo/Bar.gleam")]
+    #[case(r"src\Foo\Bar.gleam")]
+    #[case("/tmp/out/gen.ts")]
+    #[case("../shared")]
+    #[case(".")]
+    fn test_native_round_trip(#[case] input: &str) {
+        let virtual_path = VirtualPath::new(input);
+        let native = virtual_path.to_path_buf();
+        assert_eq!(VirtualPath::from_path(&native), virtual_path);
+    }
+
+    #[test]
+    fn test_native_path_uses_platform_separator() {
+        let native = VirtualPath::new(r"src\Foo\Bar.gleam").to_path_buf();
+        let expected: PathBuf = ["src", "Foo", "Bar.gleam"].iter().collect();
+        assert_eq!(native, expected);
+    }
+
+    #[test]
+    fn test_file_name_parts() {
+        let path = VirtualPath::new(r"src\Foo\Bar.test.gleam");
+        assert_eq!(path.file_name(), Some("Bar.test.gleam"));
+        assert_eq!(path.file_stem(), Some("Bar.test"));
+        assert_eq!(path.extension(), Some("gleam"));
+        assert_eq!(path.parent(), Some(VirtualPath::new("src/Foo")));
+        assert_eq!(VirtualPath::new(".hidden").extension(), None);
+        assert_eq!(VirtualPath::new("/").file_name(), None);
+        assert_eq!(VirtualPath::new("/").parent(), None);
+    }
+
+    #[test]
+    fn test_join_and_strip_prefix() {
+        let src = VirtualPath::new(r"C:\proj\src");
+        let file = src.join(r"Foo\Bar.gleam");
+        assert_eq!(file.as_str(), "C:/proj/src/Foo/Bar.gleam");
+        assert_eq!(
+            file.strip_prefix(&src),
+            Some(VirtualPath::new("Foo/Bar.gleam"))
+        );
+        assert_eq!(file.strip_prefix(&VirtualPath::new("/proj")), None);
+        assert_eq!(src.join("/abs").as_str(), "/abs");
+    }
+
+    #[test]
+    fn test_with_extension() {
+        let path = VirtualPath::new("src/Foo/Bar.gleam");
+        assert_eq!(path.with_extension("json").as_str(), "src/Foo/Bar.json");
+        assert_eq!(path.with_extension("").as_str(), "src/Foo/Bar");
+        assert_eq!(
+            VirtualPath::new("Bar.gleam").with_extension("").as_str(),
+            "Bar"
+        );
+    }
+
+    #[test]
+    fn test_serde_normalizes_on_read() {
+        let path: VirtualPath = serde_json::from_str(r#""src\\Foo\\Bar.gleam""#).unwrap();
+        assert_eq!(path.as_str(), "src/Foo/Bar.gleam");
+        assert_eq!(
+            serde_json::to_string(&path).unwrap(),
+            r#""src/Foo/Bar.gleam""#
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Type checking IR migrated from morphir-elm
+//!
+//! morphir-elm spells the SDK package `morphir/s-d-k`; the checker must see
+//! its types as the SDK types it knows.
+
+use morphir_core::converter::classic_to_v4;
+use morphir_core::ir::classic::Distribution;
+use morphir_core::ir::v4::typecheck::typecheck_distribution;
+
+const EVALUATOR_TESTS: &str = "../morphir-tests/fixtures/classic/evaluator-tests.json";
+
+#[test]
+fn test_migrated_evaluator_tests_type_check() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(EVALUATOR_TESTS);
+    let content = std::fs::read_to_string(path).expect("Failed to read evaluator-tests.json");
+    // Parse via Value to stay clear of the recursion limit of from_str
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let classic: Distribution = serde_json::from_value(json).unwrap();
+    let v4 = classic_to_v4(&classic).ir;
+
+    let errors: Vec<String> = typecheck_distribution(&v4.distribution)
+        .into_iter()
+        .filter(|d| d.is_error())
+        .map(|d| format!("{}: {}", d.definition.to_canonical_string(), d.message))
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "{} errors:\n{}",
+        errors.len(),
+        errors.join("\n")
+    );
+}
@@ -0,0 +1,368 @@
+//! Writing backend artifacts to disk
+//!
+//! Backends return [`Artifact`]s with paths relative to the output directory.
+//! [`ArtifactWriter`] places them according to the directory conventions of
+//! the target language, adds the scaffolding files a project in that language
+//! needs, and rejects paths that would escape the output directory.
+
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use morphir_core::VirtualPath;
+use morphir_extension_sdk::types::Artifact;
+use tracing::debug;
+
+use crate::error::{DaemonError, Result};
+
+/// Directory conventions of a target language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetLayout {
+    /// Gleam project: modules under `src/`, `gleam.toml` at the root
+    Gleam,
+    /// TypeScript package: modules under `src/`, `package.json` and `tsconfig.json` at the root
+    TypeScript,
+    /// Python package: modules under a package directory with `__init__.py` files,
+    /// `pyproject.toml` at the root
+    Python,
+    /// Artifacts are written exactly where the backend put them
+    Plain,
+}
+
+impl TargetLayout {
+    /// Layout for a target name as used by `morphir generate --target`
+    pub fn for_target(target: &str) -> Self {
+        match target.to_ascii_lowercase().as_str() {
+            "gleam" => TargetLayout::Gleam,
+            "typescript" | "ts" => TargetLayout::TypeScript,
+            "python" | "py" => TargetLayout::Python,
+            _ => TargetLayout::Plain,
+        }
+    }
+
+    /// Extension of the source files this layout places
+    fn source_extension(self) -> Option<&'static str> {
+        match self {
+            TargetLayout::Gleam => Some("gleam"),
+            TargetLayout::TypeScript => Some("ts"),
+            TargetLayout::Python => Some("py"),
+            TargetLayout::Plain => None,
+        }
+    }
+}
+
+/// Files written by [`ArtifactWriter::write`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WrittenArtifacts {
+    /// Artifacts returned by the backend
+    pub artifacts: Vec<PathBuf>,
+    /// Scaffolding files created by the writer
+    pub scaffolding: Vec<PathBuf>,
+}
+
+/// Writes backend artifacts into an output directory
+#[derive(Debug, Clone)]
+pub struct ArtifactWriter {
+    root: PathBuf,
+    layout: TargetLayout,
+    package_name: String,
+}
+
+impl ArtifactWriter {
+    /// Create a writer for the given output directory and target language
+    pub fn new(root: impl Into<PathBuf>, target: &str) -> Self {
+        Self {
+            root: root.into(),
+            layout: TargetLayout::for_target(target),
+            package_name: "morphir_generated".to_string(),
+        }
+    }
+
+    /// Set the package name used in scaffolding files
+    pub fn with_package_name(mut self, name: impl Into<String>) -> Self {
+        self.package_name = name.into();
+        self
+    }
+
+    /// Get the output directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the target layout
+    pub fn layout(&self) -> TargetLayout {
+        self.layout
+    }
+
+    /// Validate an artifact path and map it to its place in the layout.
+    ///
+    /// Absolute paths and paths that leave the output directory are rejected.
+    pub fn resolve(&self, artifact_path: &str) -> Result<VirtualPath> {
+        let path = VirtualPath::new(artifact_path);
+        if path.is_absolute() || path.segments().next() == Some("..") || path.file_name().is_none()
+        {
+            return Err(DaemonError::ArtifactPath(artifact_path.to_string()));
+        }
+
+        let is_source = self.layout.source_extension().is_some()
+            && path.extension() == self.layout.source_extension();
+        if !is_source {
+            return Ok(path);
+        }
+
+        let source_root = match self.layout {
+            TargetLayout::Gleam | TargetLayout::TypeScript => "src".to_string(),
+            TargetLayout::Python => snake_case(&self.package_name),
+            TargetLayout::Plain => return Ok(path),
+        };
+        let first = path.segments().next();
+        if first == Some(source_root.as_str()) || first == Some("test") || first == Some("tests") {
+            Ok(path)
+        } else {
+            Ok(VirtualPath::new(&source_root).join(path.as_str()))
+        }
+    }
+
+    /// Write artifacts and any missing scaffolding files.
+    ///
+    /// All paths are validated before anything is written, so a single unsafe
+    /// path leaves the output directory untouched.
+    pub fn write(&self, artifacts: &[Artifact]) -> Result<WrittenArtifacts> {
+        let resolved = artifacts
+            .iter()
+            .map(|artifact| self.resolve(&artifact.path))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut written = WrittenArtifacts::default();
+        for (artifact, path) in artifacts.iter().zip(&resolved) {
+            let target = self.root.join(path.to_path_buf());
+            let content = if artifact.binary {
+                STANDARD.decode(&artifact.content).map_err(|e| {
+                    DaemonError::Extension(format!(
+                        "Invalid base64 content in artifact {}: {}",
+                        artifact.path, e
+                    ))
+                })?
+            } else {
+                artifact.content.clone().into_bytes()
+            };
+            write_file(&target, &content)?;
+            debug!("Wrote artifact {:?}", target);
+            written.artifacts.push(target);
+        }
+
+        for (path, content) in self.scaffolding(&resolved) {
+            let target = self.root.join(path.to_path_buf());
+            if resolved.contains(&path) || target.exists() {
+                continue;
+            }
+            write_file(&target, content.as_bytes())?;
+            debug!("Wrote scaffolding {:?}", target);
+            written.scaffolding.push(target);
+        }
+
+        Ok(written)
+    }
+
+    /// Scaffolding files for the layout, given the artifact paths being written
+    fn scaffolding(&self, resolved: &[VirtualPath]) -> Vec<(VirtualPath, String)> {
+        let snake = snake_case(&self.package_name);
+        match self.layout {
+            TargetLayout::Gleam => vec![(
+                VirtualPath::new("gleam.toml"),
+                format!(
+                    "name = \"{}\"\nversion = \"1.0.0\"\n\n[dependencies]\n\
+                     gleam_stdlib = \">= 0.34.0 and < 2.0.0\"\n",
+                    snake
+                ),
+            )],
+            TargetLayout::TypeScript => vec![
+                (
+                    VirtualPath::new("package.json"),
+                    format!(
+                        "{{\n  \"name\": \"{}\",\n  \"version\": \"0.1.0\",\n  \
+                         \"type\": \"module\",\n  \"main\": \"dist/index.js\",\n  \
+                         \"types\": \"dist/index.d.ts\",\n  \"scripts\": {{\n    \
+                         \"build\": \"tsc\"\n  }}\n}}\n",
+                        snake.replace('_', "-")
+                    ),
+                ),
+                (
+                    VirtualPath::new("tsconfig.json"),
+                    "{\n  \"compilerOptions\": {\n    \"target\": \"ES2020\",\n    \
+                     \"module\": \"ES2020\",\n    \"declaration\": true,\n    \
+                     \"strict\": true,\n    \"rootDir\": \"src\",\n    \
+                     \"outDir\": \"dist\"\n  },\n  \"include\": [\"src\"]\n}\n"
+                        .to_string(),
+                ),
+            ],
+            TargetLayout::Python => {
+                let mut files = vec![(
+                    VirtualPath::new("pyproject.toml"),
+                    format!(
+                        "[project]\nname = \"{}\"\nversion = \"0.1.0\"\n\n[build-system]\n\
+                         requires = [\"setuptools>=61\"]\nbuild-backend = \"setuptools.build_meta\"\n",
+                        snake.replace('_', "-")
+                    ),
+                )];
+                // Every directory holding Python modules needs an __init__.py
+                let mut packages: Vec<VirtualPath> = Vec::new();
+                for module in resolved.iter().filter(|p| p.extension() == Some("py")) {
+                    let mut dir = module.parent();
+                    while let Some(package) = dir.filter(|p| p.as_str() != ".") {
+                        dir = package.parent();
+                        packages.push(package);
+                    }
+                }
+                packages.sort();
+                packages.dedup();
+                for package in packages {
+                    files.push((package.join("__init__.py"), String::new()));
+                }
+                files
+            }
+            TargetLayout::Plain => Vec::new(),
+        }
+    }
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// Lowercase identifier with `_` separators, as used for Gleam and Python packages
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    let out = out.trim_end_matches('_').to_string();
+    if out.is_empty() {
+        "morphir_generated".to_string()
+    } else {
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn artifact(path: &str, content: &str) -> Artifact {
+        Artifact {
+            path: path.to_string(),
+            content: content.to_string(),
+            binary: false,
+        }
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_output() {
+        let writer = ArtifactWriter::new("/out", "gleam");
+        for path in [
+            "../escape.gleam",
+            "a/../../escape",
+            "/etc/passwd",
+            r"C:\x",
+            "",
+        ] {
+            assert!(
+                matches!(writer.resolve(path), Err(DaemonError::ArtifactPath(_))),
+                "{} should be rejected",
+                path
+            );
+        }
+        assert!(writer.resolve("a/../b.gleam").is_ok());
+    }
+
+    #[test]
+    fn test_unsafe_artifact_writes_nothing() {
+        let temp = tempdir().unwrap();
+        let writer = ArtifactWriter::new(temp.path(), "gleam");
+        let result = writer.write(&[
+            artifact("main.gleam", "pub fn main() { 1 }"),
+            artifact("../evil.gleam", ""),
+        ]);
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_gleam_layout() {
+        let temp = tempdir().unwrap();
+        let writer = ArtifactWriter::new(temp.path(), "gleam").with_package_name("my-org/Orders");
+        let written = writer
+            .write(&[
+                artifact(r"orders\pricing.gleam", "pub fn price() { 1 }"),
+                artifact("src/main.gleam", "pub fn main() { 1 }"),
+            ])
+            .unwrap();
+
+        assert!(temp.path().join("src/orders/pricing.gleam").exists());
+        assert!(temp.path().join("src/main.gleam").exists());
+        assert_eq!(written.scaffolding, vec![temp.path().join("gleam.toml")]);
+        let toml = std::fs::read_to_string(temp.path().join("gleam.toml")).unwrap();
+        assert!(toml.contains("name = \"my_org_orders\""));
+    }
+
+    #[test]
+    fn test_typescript_layout_keeps_backend_package_json() {
+        let temp = tempdir().unwrap();
+        let writer = ArtifactWriter::new(temp.path(), "typescript");
+        let written = writer
+            .write(&[
+                artifact("index.ts", "export {}"),
+                artifact("package.json", "{\"name\": \"custom\"}"),
+            ])
+            .unwrap();
+
+        assert!(temp.path().join("src/index.ts").exists());
+        assert_eq!(written.scaffolding, vec![temp.path().join("tsconfig.json")]);
+        let package = std::fs::read_to_string(temp.path().join("package.json")).unwrap();
+        assert!(package.contains("custom"));
+    }
+
+    #[test]
+    fn test_python_package_layout() {
+        let temp = tempdir().unwrap();
+        let writer = ArtifactWriter::new(temp.path(), "python").with_package_name("orders");
+        writer
+            .write(&[artifact("pricing/rules.py", "x = 1")])
+            .unwrap();
+
+        for path in [
+            "orders/pricing/rules.py",
+            "orders/pricing/__init__.py",
+            "orders/__init__.py",
+            "pyproject.toml",
+        ] {
+            assert!(temp.path().join(path).exists(), "{} missing", path);
+        }
+    }
+
+    #[test]
+    fn test_binary_artifacts_are_decoded() {
+        let temp = tempdir().unwrap();
+        let writer = ArtifactWriter::new(temp.path(), "wasm");
+        writer
+            .write(&[Artifact {
+                path: "module.wasm".to_string(),
+                content: STANDARD.encode([0u8, 97, 115, 109]),
+                binary: true,
+            }])
+            .unwrap();
+        assert_eq!(
+            std::fs::read(temp.path().join("module.wasm")).unwrap(),
+            vec![0u8, 97, 115, 109]
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! Auditing the identifiers a backend will emit
+//!
+//! Backends declare the [`IdentifierRules`] of their target language in their
+//! capabilities: its reserved words, its length limit, and the case each kind
+//! of name is written in. Before generating, [`audit_identifiers`] renders
+//! every module, type, constructor, field, value, and argument name of a
+//! package the way the backend will, and reports the names that will be
+//! escaped or shortened along with any that end up colliding in the same
+//! scope, so neither comes as a surprise in the generated code.
+//!
+//! The rules can be adjusted per target with `reserved_words` (added to the
+//! backend's), `escape_suffix`, and `max_identifier_length` options, e.g. in
+//! `[codegen.gleam]`.
+
+use std::fmt;
+
+use indexmap::IndexMap;
+use morphir_core::ir::v4::{PackageDefinition, Type, TypeDefinition};
+use morphir_core::naming::Name;
+use morphir_extension_sdk::types::{
+    Diagnostic, DiagnosticSeverity, Escape, IdentifierCase, IdentifierRules,
+};
+use serde::Serialize;
+
+/// Kind of name an identifier is emitted for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentifierKind {
+    Module,
+    Type,
+    Constructor,
+    Field,
+    Value,
+    Argument,
+}
+
+impl fmt::Display for IdentifierKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            IdentifierKind::Module => "module",
+            IdentifierKind::Type => "type",
+            IdentifierKind::Constructor => "constructor",
+            IdentifierKind::Field => "field",
+            IdentifierKind::Value => "value",
+            IdentifierKind::Argument => "argument",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// A name emitted under a different identifier than its case alone gives
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rename {
+    pub kind: IdentifierKind,
+    /// Where the name is defined, e.g. `orders#status.type`
+    pub path: String,
+    /// The identifier before escaping
+    pub original: String,
+    pub emitted: String,
+    pub escape: Escape,
+}
+
+/// Names of the same scope emitted as the same identifier
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Collision {
+    pub kind: IdentifierKind,
+    /// The scope the names share, e.g. `orders` for the values of a module
+    pub scope: String,
+    pub emitted: String,
+    /// The colliding names, as defined in the IR
+    pub names: Vec<String>,
+}
+
+/// Result of auditing a package's identifiers
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IdentifierAudit {
+    pub renames: Vec<Rename>,
+    pub collisions: Vec<Collision>,
+}
+
+impl IdentifierAudit {
+    /// Whether every name is emitted unchanged and without collisions
+    pub fn is_clean(&self) -> bool {
+        self.renames.is_empty() && self.collisions.is_empty()
+    }
+
+    /// Collisions as errors, since the generated code would not compile, and
+    /// renames as information
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let collisions = self.collisions.iter().map(|c| Diagnostic {
+            severity: DiagnosticSeverity::Error,
+            code: Some("identifier-collision".to_string()),
+            message: format!(
+                "{} names {} in {} are all emitted as `{}`",
+                c.kind,
+                c.names.join(", "),
+                c.scope,
+                c.emitted
+            ),
+            location: None,
+            related: Vec::new(),
+            fixes: Vec::new(),
+        });
+        let renames = self.renames.iter().map(|r| {
+            let (code, reason) = match r.escape {
+                Escape::Reserved => ("identifier-escaped", "is a reserved word"),
+                Escape::Shortened => ("identifier-shortened", "is too long"),
+            };
+            Diagnostic {
+                severity: DiagnosticSeverity::Info,
+                code: Some(code.to_string()),
+                message: format!(
+                    "{} `{}` {}; {} is emitted as `{}`",
+                    r.kind, r.original, reason, r.path, r.emitted
+                ),
+                location: None,
+                related: Vec::new(),
+                fixes: Vec::new(),
+            }
+        });
+        collisions.chain(renames).collect()
+    }
+}
+
+/// `rules` adjusted by the `reserved_words`, `escape_suffix`, and
+/// `max_identifier_length` generation options
+pub fn configured_rules(rules: &IdentifierRules, options: &serde_json::Value) -> IdentifierRules {
+    let mut rules = rules.clone();
+    if let Some(words) = options.get("reserved_words").and_then(|w| w.as_array()) {
+        rules
+            .reserved_words
+            .extend(words.iter().filter_map(|w| w.as_str()).map(String::from));
+    }
+    if let Some(suffix) = options.get("escape_suffix").and_then(|s| s.as_str()) {
+        rules.escape_suffix = suffix.to_string();
+    }
+    if let Some(max) = options
+        .get("max_identifier_length")
+        .and_then(|m| m.as_u64())
+    {
+        rules.max_length = Some(max as usize);
+    }
+    rules
+}
+
+/// `name` written in `case`
+pub fn render_name(name: &Name, case: IdentifierCase) -> String {
+    match case {
+        IdentifierCase::Snake => name.to_snake_case(),
+        IdentifierCase::Camel => name.to_camel_case(),
+        IdentifierCase::Title => name.to_title_case(),
+        IdentifierCase::Kebab => name.to_kebab_case(),
+    }
+}
+
+/// Audit the identifiers a backend following `rules` emits for `package`
+pub fn audit_identifiers(package: &PackageDefinition, rules: &IdentifierRules) -> IdentifierAudit {
+    let mut audit = Auditor {
+        rules,
+        audit: IdentifierAudit::default(),
+    };
+
+    let mut modules = audit.scope(IdentifierKind::Module, "the package");
+    for module_name in package.modules.keys() {
+        // Each segment of a module path is an identifier of its own
+        let emitted: Vec<String> = module_name
+            .split(['/', '.'])
+            .map(|segment| audit.emit(&modules, module_name, segment))
+            .collect();
+        modules.add(module_name, emitted.join("/"));
+    }
+    audit.finish(modules);
+
+    for (module_name, module) in &package.modules {
+        let module = &module.value;
+        let mut types = audit.scope(IdentifierKind::Type, module_name);
+        let mut constructors = audit.scope(IdentifierKind::Constructor, module_name);
+        for (type_name, definition) in &module.types {
+            let path = format!("{}#{}", module_name, type_name);
+            let emitted = audit.emit(&types, &path, type_name);
+            types.add(type_name, emitted);
+            match &definition.value {
+                TypeDefinition::TypeAliasDefinition {
+                    type_expr: Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields),
+                    ..
+                } => {
+                    let mut scope = audit.scope(IdentifierKind::Field, &path);
+                    for field in fields {
+                        let name = field.name.to_string();
+                        let emitted = audit.emit(&scope, &format!("{}.{}", path, name), &name);
+                        scope.add(&name, emitted);
+                    }
+                    audit.finish(scope);
+                }
+                TypeDefinition::CustomTypeDefinition {
+                    constructors: c, ..
+                } => {
+                    for constructor in &c.value {
+                        let name = constructor.name.to_string();
+                        let ctor_path = format!("{}#{}", module_name, name);
+                        let emitted = audit.emit(&constructors, &ctor_path, &name);
+                        constructors.add(&name, emitted);
+                    }
+                }
+                _ => {}
+            }
+        }
+        audit.finish(types);
+        audit.finish(constructors);
+
+        let mut values = audit.scope(IdentifierKind::Value, module_name);
+        for (value_name, definition) in &module.values {
+            let path = format!("{}#{}", module_name, value_name);
+            let emitted = audit.emit(&values, &path, value_name);
+            values.add(value_name, emitted);
+
+            let mut arguments = audit.scope(IdentifierKind::Argument, &path);
+            for argument in definition.value.input_types.keys() {
+                let emitted = audit.emit(&arguments, &format!("{}.{}", path, argument), argument);
+                arguments.add(argument, emitted);
+            }
+            audit.finish(arguments);
+        }
+        audit.finish(values);
+    }
+    audit.audit
+}
+
+/// Names of one scope by the identifier they are emitted as
+struct Scope {
+    kind: IdentifierKind,
+    name: String,
+    emitted: IndexMap<String, Vec<String>>,
+}
+
+impl Scope {
+    fn add(&mut self, name: &str, emitted: String) {
+        self.emitted
+            .entry(emitted)
+            .or_default()
+            .push(name.to_string());
+    }
+}
+
+struct Auditor<'a> {
+    rules: &'a IdentifierRules,
+    audit: IdentifierAudit,
+}
+
+impl Auditor<'_> {
+    fn scope(&self, kind: IdentifierKind, name: &str) -> Scope {
+        Scope {
+            kind,
+            name: name.to_string(),
+            emitted: IndexMap::new(),
+        }
+    }
+
+    /// The identifier emitted for `name` in `scope`, recording any escaping
+    fn emit(&mut self, scope: &Scope, path: &str, name: &str) -> String {
+        let case = match scope.kind {
+            IdentifierKind::Module => self.rules.module_case,
+            IdentifierKind::Type | IdentifierKind::Constructor => self.rules.type_case,
+            _ => self.rules.value_case,
+        };
+        let original = render_name(&Name::from(name), case);
+        let (emitted, escape) = self.rules.escape(&original);
+        if let Some(escape) = escape {
+            self.audit.renames.push(Rename {
+                kind: scope.kind,
+                path: path.to_string(),
+                original,
+                emitted: emitted.clone(),
+                escape,
+            });
+        }
+        emitted
+    }
+
+    fn finish(&mut self, scope: Scope) {
+        for (emitted, names) in scope.emitted {
+            if names.len() > 1 {
+                self.audit.collisions.push(Collision {
+                    kind: scope.kind,
+                    scope: scope.name.clone(),
+                    emitted,
+                    names,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_core::ir::v4::{
+        AccessControlled, ConstructorDefinition, Field, InputType, ModuleDefinition,
+        TypeAttributes, Value, ValueAttributes, ValueDefinition,
+    };
+
+    fn unit() -> Type {
+        Type::Unit(TypeAttributes::default())
+    }
+
+    fn value(inputs: &[&str]) -> AccessControlled<ValueDefinition> {
+        let inputs = inputs
+            .iter()
+            .map(|name| InputType(Name::from(name), ValueAttributes::default(), unit()))
+            .collect();
+        AccessControlled::public(ValueDefinition::new(
+            inputs,
+            unit(),
+            Value::Unit(ValueAttributes::default()),
+        ))
+    }
+
+    fn package() -> PackageDefinition {
+        let mut module = ModuleDefinition {
+            types: IndexMap::new(),
+            values: IndexMap::new(),
+            doc: None,
+            ids: Default::default(),
+        };
+        module.types.insert(
+            "order".to_string(),
+            AccessControlled::public(TypeDefinition::TypeAliasDefinition {
+                type_params: vec![],
+                type_expr: Type::Record(
+                    TypeAttributes::default(),
+                    vec![Field {
+                        name: Name::from("type"),
+                        tpe: unit(),
+                    }],
+                ),
+            }),
+        );
+        module.types.insert(
+            "status".to_string(),
+            AccessControlled::public(TypeDefinition::CustomTypeDefinition {
+                type_params: vec![],
+                constructors: AccessControlled::public(vec![ConstructorDefinition {
+                    name: Name::from("shipped-to-a-very-distant-warehouse"),
+                    args: vec![],
+                }]),
+            }),
+        );
+        module.values.insert("use".to_string(), value(&["fn"]));
+        module.values.insert("order-total".to_string(), value(&[]));
+        module.values.insert("orderTotal".to_string(), value(&[]));
+        let mut package = PackageDefinition {
+            modules: IndexMap::new(),
+        };
+        package
+            .modules
+            .insert("orders".to_string(), AccessControlled::public(module));
+        package
+    }
+
+    #[test]
+    fn test_reserved_long_and_colliding_names_are_reported() {
+        let mut rules = IdentifierRules::reserving(["type", "use", "fn"]);
+        rules.max_length = Some(20);
+        let audit = audit_identifiers(&package(), &rules);
+
+        let renamed: Vec<(&str, &str, Escape)> = audit
+            .renames
+            .iter()
+            .map(|r| (r.path.as_str(), r.emitted.as_str(), r.escape))
+            .collect();
+        assert_eq!(
+            renamed,
+            vec![
+                ("orders#order.type", "type_", Escape::Reserved),
+                (
+                    "orders#shipped-to-a-very-distant-warehouse",
+                    "ShippedToAVeryDistan",
+                    Escape::Shortened
+                ),
+                ("orders#use", "use_", Escape::Reserved),
+                ("orders#use.fn", "fn_", Escape::Reserved),
+            ]
+        );
+
+        let diagnostics = audit.diagnostics();
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(
+            diagnostics[0].message,
+            "value names order-total, orderTotal in orders are all emitted as `order_total`"
+        );
+        assert_eq!(
+            diagnostics[1].message,
+            "field `type` is a reserved word; orders#order.type is emitted as `type_`"
+        );
+    }
+
+    #[test]
+    fn test_options_adjust_the_declared_rules() {
+        let rules = configured_rules(
+            &IdentifierRules::reserving(["type"]),
+            &serde_json::json!({
+                "reserved_words": ["use"],
+                "escape_suffix": "_x",
+                "max_identifier_length": 5,
+            }),
+        );
+        assert_eq!(rules.reserved_words, vec!["type", "use"]);
+        assert_eq!(
+            rules.escape("use"),
+            ("use_x".to_string(), Some(Escape::Reserved))
+        );
+        assert_eq!(rules.escape("types"), ("types".to_string(), None));
+        assert_eq!(
+            rules.escape("type"),
+            ("typ_x".to_string(), Some(Escape::Shortened))
+        );
+
+        let audit = audit_identifiers(&package(), &IdentifierRules::default());
+        assert!(audit.renames.is_empty());
+        assert_eq!(audit.collisions.len(), 1);
+        assert!(!audit.is_clean());
+    }
+}
@@ -0,0 +1,199 @@
+//! Running external tools
+//!
+//! [`CommandRunner`] runs a tool, such as a target language's checker, in a
+//! directory the caller chooses. The tool sees none of the caller's
+//! environment apart from the variables needed to find and run programs, reads
+//! no input, and is killed once it runs past its timeout.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::error::{DaemonError, Result};
+
+/// Time a tool may run before it is killed
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Variables passed through from the caller's environment
+const INHERITED_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USERPROFILE",
+    "SYSTEMROOT",
+    "TEMP",
+    "TMP",
+    "TMPDIR",
+    "LANG",
+];
+
+/// Interval between checks on a running tool
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What a tool printed, and how it exited
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    pub success: bool,
+    /// Exit code, unless the tool was ended by a signal
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs external tools with a restricted environment and a timeout
+#[derive(Debug, Clone)]
+pub struct CommandRunner {
+    timeout: Duration,
+    env: BTreeMap<String, String>,
+}
+
+impl Default for CommandRunner {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            env: BTreeMap::new(),
+        }
+    }
+}
+
+impl CommandRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the time a tool may run before it is killed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Pass an environment variable to tools
+    pub fn with_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(name.into(), value.into());
+        self
+    }
+
+    /// Run `program` with `args` in `dir` and wait for it
+    pub fn run(&self, program: &str, args: &[String], dir: &Path) -> Result<CommandOutput> {
+        if !dir.is_dir() {
+            return Err(DaemonError::Tool(format!(
+                "Cannot run {} in {}: not a directory",
+                program,
+                dir.display()
+            )));
+        }
+        let inherited = INHERITED_ENV
+            .iter()
+            .filter_map(|name| Some((*name, std::env::var_os(name)?)));
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(dir)
+            .env_clear()
+            .envs(inherited)
+            .envs(&self.env)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    DaemonError::Tool(format!("{} is not installed or not on PATH", program))
+                }
+                _ => DaemonError::Tool(format!("Failed to run {}: {}", program, e)),
+            })?;
+
+        // Drain both pipes while waiting, so a chatty tool cannot block on a
+        // full pipe
+        let stdout = child.stdout.take().map(read_to_end);
+        let stderr = child.stderr.take().map(read_to_end);
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if started.elapsed() >= self.timeout {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(DaemonError::Tool(format!(
+                    "{} timed out after {}s",
+                    program,
+                    self.timeout.as_secs_f32()
+                )));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+        let collect = |handle: Option<std::thread::JoinHandle<String>>| {
+            handle
+                .and_then(|handle| handle.join().ok())
+                .unwrap_or_default()
+        };
+        Ok(CommandOutput {
+            success: status.success(),
+            code: status.code(),
+            stdout: collect(stdout),
+            stderr: collect(stderr),
+        })
+    }
+}
+
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["-c".to_string(), script.to_string()]
+    }
+
+    #[test]
+    fn test_tools_run_with_a_restricted_environment() {
+        let temp = tempfile::tempdir().unwrap();
+        // SAFETY: tests in this module do not read the variable concurrently
+        unsafe { std::env::set_var("MORPHIR_TEST_SECRET", "hidden") };
+        let runner = CommandRunner::new().with_env("MORPHIR_TARGET", "gleam");
+        let output = runner
+            .run(
+                "sh",
+                &sh(
+                    "pwd; echo \"[$MORPHIR_TEST_SECRET] [$MORPHIR_TARGET]\"; echo oops >&2; exit 3",
+                ),
+                temp.path(),
+            )
+            .unwrap();
+        assert!(!output.success);
+        assert_eq!(output.code, Some(3));
+        let lines: Vec<&str> = output.stdout.lines().collect();
+        assert_eq!(
+            Path::new(lines[0]).canonicalize().unwrap(),
+            temp.path().canonicalize().unwrap()
+        );
+        assert_eq!(lines[1], "[] [gleam]");
+        assert_eq!(output.stderr, "oops\n");
+    }
+
+    #[test]
+    fn test_missing_and_slow_tools_are_reported() {
+        let temp = tempfile::tempdir().unwrap();
+        let err = CommandRunner::new()
+            .run("morphir-no-such-tool", &[], temp.path())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Tool error: morphir-no-such-tool is not installed or not on PATH"
+        );
+
+        let err = CommandRunner::new()
+            .with_timeout(Duration::from_millis(100))
+            .run("sh", &sh("sleep 5"), temp.path())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Tool error: sh timed out after 0.1s");
+    }
+}
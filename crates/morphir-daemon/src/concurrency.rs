@@ -0,0 +1,302 @@
+//! Concurrency limits and backpressure for daemon work
+//!
+//! Every unit of extension work (a JSON-RPC call into a WASM plugin, or the
+//! instantiation of one) must hold a [`WorkPermit`]. Permits are bounded both
+//! globally and per extension, and callers that cannot get a permit right away
+//! wait in a bounded queue. When the queue is full the request is rejected with
+//! [`DaemonError::ServerBusy`], which maps to a JSON-RPC "server busy" error
+//! carrying a retry-after hint, instead of piling up unbounded work.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use morphir_common::config::DaemonSection;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+use crate::error::{DaemonError, Result};
+
+/// Concurrency limits for the daemon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcurrencyConfig {
+    /// Maximum number of requests executing at once across all extensions
+    pub max_concurrent: usize,
+    /// Maximum number of requests executing at once for a single extension
+    pub max_per_extension: usize,
+    /// Maximum number of requests waiting for a permit before new ones are rejected
+    pub max_queue_depth: usize,
+    /// Retry-after hint returned to rejected callers, in milliseconds
+    pub retry_after_ms: u64,
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        let cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+        Self {
+            max_concurrent: cpus,
+            max_per_extension: 2,
+            max_queue_depth: 64,
+            retry_after_ms: 500,
+        }
+    }
+}
+
+impl From<&DaemonSection> for ConcurrencyConfig {
+    fn from(section: &DaemonSection) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_concurrent: section
+                .max_concurrent_requests
+                .unwrap_or(defaults.max_concurrent)
+                .max(1),
+            max_per_extension: section
+                .max_concurrent_per_extension
+                .unwrap_or(defaults.max_per_extension)
+                .max(1),
+            max_queue_depth: section.max_queue_depth.unwrap_or(defaults.max_queue_depth),
+            retry_after_ms: section.retry_after_ms.unwrap_or(defaults.retry_after_ms),
+        }
+    }
+}
+
+/// Snapshot of limiter usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcurrencyStats {
+    /// Requests currently holding a permit
+    pub active: usize,
+    /// Requests waiting for a permit
+    pub queued: usize,
+    /// Global permits still available
+    pub available: usize,
+}
+
+/// Permit to execute one unit of work; released on drop
+#[derive(Debug)]
+pub struct WorkPermit {
+    _extension: Option<OwnedSemaphorePermit>,
+    _global: OwnedSemaphorePermit,
+}
+
+/// Decrements the queue depth when a waiting caller leaves the queue,
+/// including when its future is cancelled.
+struct QueueSlot<'a>(&'a AtomicUsize);
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Global and per-extension concurrency limiter with a bounded wait queue
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    config: ConcurrencyConfig,
+    global: Arc<Semaphore>,
+    per_extension: Mutex<HashMap<String, Arc<Semaphore>>>,
+    queued: AtomicUsize,
+}
+
+impl Default for ConcurrencyLimiter {
+    fn default() -> Self {
+        Self::new(ConcurrencyConfig::default())
+    }
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter with the given configuration
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+            per_extension: Mutex::new(HashMap::new()),
+            queued: AtomicUsize::new(0),
+            config,
+        }
+    }
+
+    /// Get the limiter configuration
+    pub fn config(&self) -> &ConcurrencyConfig {
+        &self.config
+    }
+
+    /// Current usage of the limiter
+    pub fn stats(&self) -> ConcurrencyStats {
+        let available = self.global.available_permits();
+        ConcurrencyStats {
+            active: self.config.max_concurrent.max(1) - available,
+            queued: self.queued.load(Ordering::SeqCst),
+            available,
+        }
+    }
+
+    fn extension_semaphore(&self, extension: &str) -> Arc<Semaphore> {
+        let mut map = self
+            .per_extension
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        map.entry(extension.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.config.max_per_extension.max(1))))
+            .clone()
+    }
+
+    /// Acquire a permit, waiting in the queue if necessary.
+    ///
+    /// The per-extension permit is taken before the global one so that a
+    /// saturated extension does not hold global capacity while it waits.
+    /// Returns [`DaemonError::ServerBusy`] if the wait queue is full.
+    pub async fn acquire(&self, extension: Option<&str>) -> Result<WorkPermit> {
+        let extension_sem = extension.map(|id| self.extension_semaphore(id));
+
+        // Fast path: both permits available without waiting
+        let extension_permit = match &extension_sem {
+            Some(sem) => match sem.clone().try_acquire_owned() {
+                Ok(permit) => Some(Some(permit)),
+                Err(_) => None,
+            },
+            None => Some(None),
+        };
+        if let Some(extension_permit) = extension_permit {
+            if let Ok(global) = self.global.clone().try_acquire_owned() {
+                return Ok(WorkPermit {
+                    _extension: extension_permit,
+                    _global: global,
+                });
+            }
+            // Hold on to the extension permit while waiting for global capacity
+            return self.wait(extension_permit, None).await;
+        }
+
+        self.wait(None, extension_sem).await
+    }
+
+    /// Slow path: join the bounded queue and wait for the remaining permits.
+    async fn wait(
+        &self,
+        extension_permit: Option<OwnedSemaphorePermit>,
+        extension_sem: Option<Arc<Semaphore>>,
+    ) -> Result<WorkPermit> {
+        let depth = self.queued.fetch_add(1, Ordering::SeqCst);
+        let _slot = QueueSlot(&self.queued);
+        if depth >= self.config.max_queue_depth {
+            debug!(
+                "Rejecting request: queue full ({} waiting)",
+                self.config.max_queue_depth
+            );
+            return Err(DaemonError::ServerBusy {
+                retry_after_ms: self.config.retry_after_ms,
+            });
+        }
+
+        let extension_permit = match (extension_permit, extension_sem) {
+            (Some(permit), _) => Some(permit),
+            (None, Some(sem)) => Some(sem.acquire_owned().await.map_err(closed)?),
+            (None, None) => None,
+        };
+        let global = self.global.clone().acquire_owned().await.map_err(closed)?;
+
+        Ok(WorkPermit {
+            _extension: extension_permit,
+            _global: global,
+        })
+    }
+}
+
+fn closed(_: tokio::sync::AcquireError) -> DaemonError {
+    DaemonError::Extension("Concurrency limiter closed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(
+        max_concurrent: usize,
+        max_per_extension: usize,
+        max_queue_depth: usize,
+    ) -> ConcurrencyConfig {
+        ConcurrencyConfig {
+            max_concurrent,
+            max_per_extension,
+            max_queue_depth,
+            retry_after_ms: 250,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_permits_are_released_on_drop() {
+        let limiter = ConcurrencyLimiter::new(config(1, 1, 0));
+        let permit = limiter.acquire(Some("gleam")).await.unwrap();
+        assert_eq!(limiter.stats().active, 1);
+        drop(permit);
+        assert_eq!(limiter.stats().active, 0);
+        assert!(limiter.acquire(Some("gleam")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_with_retry_after() {
+        let limiter = ConcurrencyLimiter::new(config(1, 1, 0));
+        let _held = limiter.acquire(None).await.unwrap();
+
+        let err = limiter.acquire(None).await.unwrap_err();
+        assert!(matches!(
+            err,
+            DaemonError::ServerBusy {
+                retry_after_ms: 250
+            }
+        ));
+        assert_eq!(limiter.stats().queued, 0);
+    }
+
+    #[tokio::test]
+    async fn test_per_extension_limit_does_not_block_other_extensions() {
+        let limiter = ConcurrencyLimiter::new(config(4, 1, 0));
+        let _gleam = limiter.acquire(Some("gleam")).await.unwrap();
+
+        assert!(matches!(
+            limiter.acquire(Some("gleam")).await,
+            Err(DaemonError::ServerBusy { .. })
+        ));
+        assert!(limiter.acquire(Some("typescript")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_proceeds_when_permit_frees() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(config(1, 1, 4)));
+        let held = limiter.acquire(Some("gleam")).await.unwrap();
+
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire(Some("gleam")).await.map(|_| ()) })
+        };
+        while limiter.stats().queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(limiter.stats().queued, 1);
+
+        drop(held);
+        waiter.await.unwrap().unwrap();
+        assert_eq!(limiter.stats().queued, 0);
+    }
+
+    #[test]
+    fn test_config_from_daemon_section() {
+        let section = DaemonSection {
+            max_concurrent_requests: Some(8),
+            max_concurrent_per_extension: Some(0),
+            max_queue_depth: None,
+            retry_after_ms: Some(1000),
+            ..Default::default()
+        };
+        let config = ConcurrencyConfig::from(&section);
+        assert_eq!(config.max_concurrent, 8);
+        assert_eq!(config.max_per_extension, 1);
+        assert_eq!(config.max_queue_depth, 64);
+        assert_eq!(config.retry_after_ms, 1000);
+    }
+}
@@ -1,5 +1,7 @@
 //! Error types for the Morphir daemon
 
+use crate::extensions::limits::Limit;
+use morphir_common::config::ConfigError;
 use thiserror::Error;
 
 /// Result type for daemon operations
@@ -16,6 +18,10 @@ pub enum DaemonError {
     #[error("Configuration error: {0}")]
     Config(String),
 
+    /// Configuration that failed to resolve; carries a machine-readable report
+    #[error(transparent)]
+    InvalidConfig(#[from] ConfigError),
+
     /// Project errors
     #[error("Project error: {0}")]
     Project(String),
@@ -28,6 +34,76 @@ pub enum DaemonError {
     #[error("Extension error: {0}")]
     Extension(String),
 
+    /// Extension that could not be installed
+    #[error("Install error: {0}")]
+    Install(String),
+
+    /// Extension that does not work with the SDK version the host speaks
+    #[error("Extension {extension} {version} is incompatible with this host: {reason}")]
+    IncompatibleExtension {
+        /// Extension identifier
+        extension: String,
+        /// Version of the extension
+        version: String,
+        /// Why, and what to do about it
+        reason: String,
+    },
+
+    /// Downloaded extension whose SHA-256 differs from the pinned or listed one
+    #[error("Integrity check failed for {origin}: expected sha256 {expected}, got {actual}")]
+    IntegrityMismatch {
+        /// Where the extension was downloaded from
+        origin: String,
+        /// Expected digest
+        expected: String,
+        /// Digest of the download
+        actual: String,
+    },
+
+    /// Extension request that exceeded one of the extension's resource limits
+    #[error("Extension {extension} exceeded its {limit} limit of {max} {}", .limit.unit())]
+    LimitExceeded {
+        /// Extension identifier
+        extension: String,
+        /// The limit exceeded
+        limit: Limit,
+        /// The limit's value, in its unit
+        max: u64,
+    },
+
+    /// Extensions whose declared `feeds` form a cycle, in feeding order
+    #[error("Extension pipeline has a cycle: {}", .0.join(" -> "))]
+    PipelineCycle(Vec<String>),
+
+    /// Workspace projects whose dependencies form a cycle, each depending on
+    /// the next
+    #[error("Project dependencies have a cycle: {}", .0.join(" -> "))]
+    ProjectCycle(Vec<String>),
+
+    /// Artifact path that is absolute or escapes the output directory
+    #[error("Unsafe artifact path: {0}")]
+    ArtifactPath(String),
+
+    /// External tool that is missing, could not start, or timed out
+    #[error("Tool error: {0}")]
+    Tool(String),
+
+    /// Too much work is queued; the caller should retry later
+    #[error("Server busy, retry after {retry_after_ms}ms")]
+    ServerBusy {
+        /// Suggested delay before retrying, in milliseconds
+        retry_after_ms: u64,
+    },
+
+    /// Error response from a running daemon
+    #[error("RPC error {code}: {message}")]
+    Rpc {
+        /// JSON-RPC error code
+        code: i32,
+        /// Error message
+        message: String,
+    },
+
     /// IO errors
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -48,3 +124,14 @@ pub enum DaemonError {
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
+
+impl DaemonError {
+    /// Machine-readable details for configuration errors, including the
+    /// layered configuration values and where each came from
+    pub fn config_report(&self) -> Option<serde_json::Value> {
+        match self {
+            DaemonError::InvalidConfig(e) => Some(e.to_json()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Live evaluation of model values
+//!
+//! A client or extension subscribes to a value of a project's model: an
+//! FQName, or the name of an entry point of an Application distribution, with
+//! its arguments. Arguments are given inline or read from JSON input files,
+//! one argument per file, after the inline ones. The daemon evaluates the
+//! value when subscribed, then again whenever the project's IR or one of the
+//! input files changes, and pushes each [`EvaluationResult`] to clients. This
+//! is what live dashboards and notebooks over Morphir logic are built on.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use morphir_common::loader::{LoadedDistribution, load_distribution};
+use morphir_common::vfs::OsVfs;
+use morphir_core::naming::FQName;
+use morphir_runtime::Evaluator;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::workspace::{FileChange, Project, Workspace};
+
+/// Stack size of evaluation threads; deep recursion in models needs more
+/// than the default
+const EVAL_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// What to evaluate, as requested by a subscriber
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationRequest {
+    /// Project whose model is evaluated
+    pub project: String,
+    /// FQName of the value, or the name of an entry point
+    pub target: String,
+    /// Arguments as JSON
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<Value>,
+    /// JSON files, relative to the project, each holding one more argument
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<PathBuf>,
+    /// IR to evaluate, relative to the project; the project's compile output
+    /// by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ir: Option<PathBuf>,
+}
+
+impl EvaluationRequest {
+    /// IR the request evaluates, for `project` of the workspace at `root`
+    pub fn ir_path(&self, root: &Path, project: &Project) -> Option<PathBuf> {
+        match &self.ir {
+            Some(ir) => Some(project.path.join(ir)),
+            None => project.compile_output(root),
+        }
+    }
+
+    /// Input files of the request, for `project`
+    pub fn input_paths(&self, project: &Project) -> Vec<PathBuf> {
+        self.inputs
+            .iter()
+            .map(|input| project.path.join(input))
+            .collect()
+    }
+}
+
+/// Result of evaluating a subscription, pushed as an `evaluation/result`
+/// notification
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationResult {
+    pub subscription: u64,
+    pub project: String,
+    pub target: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Values clients subscribed to, by subscription ID
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    next: u64,
+    requests: BTreeMap<u64, EvaluationRequest>,
+}
+
+impl Subscriptions {
+    /// Subscribe to `request`, returning the subscription ID
+    pub fn add(&mut self, request: EvaluationRequest) -> u64 {
+        self.next += 1;
+        self.requests.insert(self.next, request);
+        self.next
+    }
+
+    /// Cancel the subscription `id`; false if there was none
+    pub fn remove(&mut self, id: u64) -> bool {
+        self.requests.remove(&id).is_some()
+    }
+
+    /// The request subscribed to as `id`
+    pub fn get(&self, id: u64) -> Option<&EvaluationRequest> {
+        self.requests.get(&id)
+    }
+
+    /// Input files of every subscription, to watch for changes
+    pub fn input_paths(&self, workspace: &Workspace) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .requests
+            .values()
+            .filter_map(|request| {
+                let project = workspace.get_project(&request.project)?;
+                Some(request.input_paths(project))
+            })
+            .flatten()
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Subscriptions to re-evaluate once the IR of the `rebuilt` projects
+    /// changed and the files in `changes` did: those over a rebuilt project,
+    /// or with a changed input or IR file
+    pub fn affected(
+        &self,
+        workspace: &Workspace,
+        rebuilt: &HashSet<String>,
+        changes: &[FileChange],
+    ) -> Vec<(u64, EvaluationRequest)> {
+        self.requests
+            .iter()
+            .filter(|(_, request)| {
+                let Some(project) = workspace.get_project(&request.project) else {
+                    return false;
+                };
+                let mut watched = request.input_paths(project);
+                watched.extend(request.ir.as_ref().map(|ir| project.path.join(ir)));
+                rebuilt.contains(&request.project)
+                    || changes
+                        .iter()
+                        .any(|change| watched.iter().any(|path| change.path.starts_with(path)))
+            })
+            .map(|(id, request)| (*id, request.clone()))
+            .collect()
+    }
+}
+
+/// Evaluate the subscription `id` to `request` over `project` of the
+/// workspace at `root`. Failures are reported in the result.
+pub fn evaluate(
+    id: u64,
+    request: &EvaluationRequest,
+    root: &Path,
+    project: &Project,
+) -> EvaluationResult {
+    let outcome = evaluate_request(request, root, project);
+    EvaluationResult {
+        subscription: id,
+        project: request.project.clone(),
+        target: request.target.clone(),
+        success: outcome.is_ok(),
+        error: outcome.as_ref().err().cloned(),
+        value: outcome.ok(),
+    }
+}
+
+fn evaluate_request(
+    request: &EvaluationRequest,
+    root: &Path,
+    project: &Project,
+) -> std::result::Result<Value, String> {
+    let ir = request
+        .ir_path(root, project)
+        .ok_or_else(|| format!("No IR for {}: no frontend language", project.name))?;
+    let ir_file = match load_distribution(&OsVfs, &ir) {
+        Ok(LoadedDistribution::V4(ir_file)) => ir_file,
+        Ok(LoadedDistribution::Classic(_)) => {
+            return Err("Evaluation requires V4 IR".to_string());
+        }
+        Err(e) => return Err(format!("Failed to load {}: {}", ir.display(), e)),
+    };
+    let mut args = request.args.clone();
+    for input in request.input_paths(project) {
+        let content = std::fs::read_to_string(&input)
+            .map_err(|e| format!("Failed to read {}: {}", input.display(), e))?;
+        args.push(
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid JSON in {}: {}", input.display(), e))?,
+        );
+    }
+    let target = ir_file
+        .distribution
+        .entry_points()
+        .and_then(|entry_points| entry_points.get(&request.target))
+        .map(|entry_point| entry_point.fqname())
+        .unwrap_or_else(|| parse_fqname(&request.target))?;
+
+    let evaluation = std::thread::Builder::new()
+        .name("morphir-eval".to_string())
+        .stack_size(EVAL_STACK_SIZE)
+        .spawn(move || {
+            let evaluator = Evaluator::new(&ir_file.distribution);
+            let args = evaluator
+                .args_from_json(&target, &args)
+                .map_err(|e| e.to_string())?;
+            let value = evaluator
+                .evaluate(&target, args)
+                .map_err(|e| e.to_string())?;
+            Ok(value.to_json())
+        })
+        .map_err(|e| format!("Failed to start evaluation: {}", e))?;
+    evaluation
+        .join()
+        .unwrap_or_else(|_| Err("Evaluation panicked".to_string()))
+}
+
+fn parse_fqname(s: &str) -> std::result::Result<FQName, String> {
+    if s.contains('#') {
+        FQName::from_canonical_string(s)
+    } else {
+        FQName::parse(s).ok_or_else(|| {
+            format!(
+                "Invalid target '{}'. Expected an entry point, 'package:module#name', or 'package:module:name'",
+                s
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::workspace::ChangeKind;
+    use morphir_core::ir::v4::fixtures::{call, input, int, int_lit, library, reference, variable};
+    use morphir_core::ir::v4::{Distribution, IRFile, ValueDefinition};
+    use morphir_core::naming::PackageName;
+    use serde_json::json;
+
+    /// Write a library whose `orders#double n = n * 2` to `path`
+    pub(crate) fn write_library(path: &Path) {
+        let body = call(
+            reference("morphir/sdk:basics#multiply"),
+            vec![variable("n"), int_lit(2)],
+        );
+        let mut distribution = library(vec![(
+            "double",
+            ValueDefinition::new(vec![input("n", int())], int(), body),
+        )]);
+        if let Distribution::Library(lib) = &mut distribution {
+            lib.package_name = PackageName::parse("my-org/shop");
+        }
+        let ir = IRFile {
+            format_version: Default::default(),
+            distribution,
+        };
+        std::fs::write(path, serde_json::to_string(&ir).unwrap()).unwrap();
+    }
+
+    fn workspace(root: &Path) -> Workspace {
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("morphir.toml"),
+            "[project]\nname = \"my-org/shop\"\nversion = \"1.0.0\"\nsource_directory = \"src\"\n",
+        )
+        .unwrap();
+        Workspace::open(root.to_path_buf()).unwrap()
+    }
+
+    #[test]
+    fn test_evaluates_with_inline_and_file_arguments() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = workspace(temp.path());
+        let project = workspace.get_project("my-org/shop").unwrap();
+        write_library(&temp.path().join("ir.json"));
+        std::fs::write(temp.path().join("n.json"), "21").unwrap();
+
+        let mut request = EvaluationRequest {
+            project: "my-org/shop".to_string(),
+            target: "my-org/shop:orders#double".to_string(),
+            args: vec![],
+            inputs: vec![PathBuf::from("n.json")],
+            ir: Some(PathBuf::from("ir.json")),
+        };
+        let result = evaluate(1, &request, temp.path(), project);
+        assert_eq!(result.value, Some(json!(42)), "{:?}", result.error);
+        assert!(result.success);
+
+        request.inputs.clear();
+        request.args = vec![json!(4)];
+        assert_eq!(
+            evaluate(1, &request, temp.path(), project).value,
+            Some(json!(8))
+        );
+
+        request.target = "my-org/shop:orders#triple".to_string();
+        let result = evaluate(1, &request, temp.path(), project);
+        assert!(!result.success);
+        assert!(result.error.is_some());
+    }
+
+    #[test]
+    fn test_subscriptions_affected_by_rebuilds_and_inputs() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = workspace(temp.path());
+        let mut subscriptions = Subscriptions::default();
+        let request = |inputs: Vec<&str>| EvaluationRequest {
+            project: "my-org/shop".to_string(),
+            target: "my-org/shop:orders#double".to_string(),
+            args: vec![],
+            inputs: inputs.into_iter().map(PathBuf::from).collect(),
+            ir: None,
+        };
+        let plain = subscriptions.add(request(vec![]));
+        let with_input = subscriptions.add(request(vec!["fixtures/n.json"]));
+        assert_eq!(
+            subscriptions.input_paths(&workspace),
+            vec![temp.path().join("fixtures/n.json")]
+        );
+
+        let ids = |affected: Vec<(u64, EvaluationRequest)>| {
+            affected.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+        };
+        let change = FileChange {
+            kind: ChangeKind::Modified,
+            path: temp.path().join("fixtures/n.json"),
+        };
+        assert_eq!(
+            ids(subscriptions.affected(&workspace, &HashSet::new(), &[change])),
+            vec![with_input]
+        );
+        let rebuilt = HashSet::from(["my-org/shop".to_string()]);
+        assert_eq!(
+            ids(subscriptions.affected(&workspace, &rebuilt, &[])),
+            vec![plain, with_input]
+        );
+
+        assert!(subscriptions.remove(plain));
+        assert!(!subscriptions.remove(plain));
+        assert!(subscriptions.get(with_input).is_some());
+    }
+}
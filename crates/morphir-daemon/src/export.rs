@@ -0,0 +1,645 @@
+//! Read-only HTTP export of generated documentation and schemas
+//!
+//! Internal portals can point at a running daemon instead of a copy of the
+//! generated files. Each project of the workspace exposes one directory per
+//! export route:
+//!
+//! ```text
+//! GET /                                  projects and their routes
+//! GET /projects/acme-shop/schemas/       files of the route, as JSON
+//! GET /projects/acme-shop/schemas/acme.schema.json
+//! GET /projects/acme-shop/docs/index.html
+//! ```
+//!
+//! A route is served from a backend. Builtin backends run natively on the
+//! project's compiled IR, and their output is regenerated when the IR
+//! changes. Other backends are served from what `morphir generate` last
+//! wrote for them. Every file carries a strong `ETag`, so portals revalidate
+//! with `If-None-Match` and get `304 Not Modified` until the model changes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::header::{self, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use morphir_builtins::ExtensionType;
+use morphir_builtins::registry::BuiltinRegistry;
+use morphir_common::loader::load_ir;
+use morphir_ext_core::Envelope;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+use tracing::debug;
+
+use crate::error::{DaemonError, Result};
+use crate::workspace::Workspace;
+
+/// A directory of generated files served for every project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportRoute {
+    /// URL segment, e.g. `schemas`
+    pub name: String,
+    /// Backend producing the files, e.g. `json-schema`
+    pub backend: String,
+}
+
+impl ExportRoute {
+    pub fn new(name: impl Into<String>, backend: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            backend: backend.into(),
+        }
+    }
+}
+
+/// Where a project's model and generated files live
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportProject {
+    /// Project name, as in `morphir.toml`
+    pub name: String,
+    /// Compiled IR, a file or a directory
+    pub ir_path: PathBuf,
+    /// Directory holding one directory of `morphir generate` output per backend
+    pub generate_dir: PathBuf,
+}
+
+impl ExportProject {
+    /// URL segment for the project: its name with `/` replaced, as in the
+    /// `.morphir/out` directory
+    pub fn slug(&self) -> String {
+        self.name.replace(['/', ' ', '\\'], "-")
+    }
+
+    /// Projects of an open workspace, with their output under the
+    /// workspace's `.morphir/out` directory.
+    ///
+    /// The IR is the compile output for the project's frontend language, or
+    /// the only compile output when no language is configured.
+    pub fn from_workspace(workspace: &Workspace) -> Vec<ExportProject> {
+        let mut projects: Vec<ExportProject> = workspace
+            .projects
+            .values()
+            .map(|project| {
+                let out = workspace
+                    .root
+                    .join(".morphir")
+                    .join("out")
+                    .join(project.name.replace(['/', ' ', '\\'], "-"));
+                let compile_dir = out.join("compile");
+                let language = project
+                    .config
+                    .frontend
+                    .as_ref()
+                    .and_then(|f| f.language.clone())
+                    .or_else(|| only_subdirectory(&compile_dir))
+                    .unwrap_or_default();
+                ExportProject {
+                    name: project.name.clone(),
+                    ir_path: compile_dir.join(language),
+                    generate_dir: out.join("generate"),
+                }
+            })
+            .collect();
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        projects
+    }
+}
+
+fn only_subdirectory(dir: &Path) -> Option<String> {
+    let mut names = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned());
+    let name = names.next()?;
+    names.next().is_none().then_some(name)
+}
+
+/// An HTTP response, independent of the server serving it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    /// Strong entity tag of the body, quoted
+    pub etag: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl ExportResponse {
+    fn json(status: u16, value: &serde_json::Value) -> Self {
+        let body = serde_json::to_vec_pretty(value).expect("JSON values serialize");
+        Self {
+            status,
+            content_type: "application/json",
+            etag: Some(etag(&body)),
+            body,
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            etag: None,
+            ..Self::json(status, &serde_json::json!({ "error": message.into() }))
+        }
+    }
+
+    fn into_http(self) -> Response<Full<Bytes>> {
+        let mut response = Response::new(Full::new(Bytes::from(self.body)));
+        *response.status_mut() =
+            StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(self.content_type),
+        );
+        if let Some(etag) = self.etag.and_then(|e| HeaderValue::from_str(&e).ok()) {
+            headers.insert(header::ETAG, etag);
+            // Always revalidate: the files change whenever the model does
+            headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        }
+        if self.status == 405 {
+            headers.insert(header::ALLOW, HeaderValue::from_static("GET, HEAD"));
+        }
+        response
+    }
+}
+
+/// Files a builtin generated from one version of a project's model
+struct Generated {
+    model: Option<ModelStamp>,
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+/// Identifies a version of the model without reading it: the latest
+/// modification time and the total size of its files
+type ModelStamp = (SystemTime, u64);
+
+/// Read-only export of generated documentation and schemas.
+pub struct RestExport {
+    projects: Vec<ExportProject>,
+    routes: Vec<ExportRoute>,
+    builtins: BuiltinRegistry,
+    cache: Mutex<HashMap<(String, String), Arc<Generated>>>,
+}
+
+impl RestExport {
+    /// Export `projects` with the default routes: `docs` from the `docs`
+    /// backend and `schemas` from `json-schema`
+    pub fn new(projects: Vec<ExportProject>) -> Self {
+        Self {
+            projects,
+            routes: vec![
+                ExportRoute::new("docs", "docs"),
+                ExportRoute::new("schemas", "json-schema"),
+            ],
+            builtins: BuiltinRegistry::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Serve the output of `backend` under `name`, replacing a route of the
+    /// same name
+    pub fn with_route(mut self, name: impl Into<String>, backend: impl Into<String>) -> Self {
+        let route = ExportRoute::new(name, backend);
+        self.routes.retain(|r| r.name != route.name);
+        self.routes.push(route);
+        self
+    }
+
+    /// Answer one request. `if_none_match` is the request's `If-None-Match`
+    /// header, if any.
+    pub fn handle(&self, method: &str, path: &str, if_none_match: Option<&str>) -> ExportResponse {
+        if method != "GET" && method != "HEAD" {
+            return ExportResponse::error(
+                405,
+                format!("{} is not allowed; the export is read-only", method),
+            );
+        }
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if segments.iter().any(|s| *s == "." || *s == "..") {
+            return ExportResponse::error(400, "Paths may not contain `.` or `..` segments");
+        }
+
+        let response = match segments.as_slice() {
+            [] => ExportResponse::json(200, &self.index()),
+            ["projects", project, route, file @ ..] => {
+                self.file(project, route, file, path.ends_with('/'))
+            }
+            _ => ExportResponse::error(404, format!("No such resource: {}", path)),
+        };
+
+        match (&response.etag, if_none_match) {
+            (Some(etag), Some(tags)) if response.status == 200 && etag_matches(etag, tags) => {
+                ExportResponse {
+                    status: 304,
+                    body: Vec::new(),
+                    ..response
+                }
+            }
+            _ => response,
+        }
+    }
+
+    fn index(&self) -> serde_json::Value {
+        let projects: Vec<serde_json::Value> = self
+            .projects
+            .iter()
+            .map(|project| {
+                let routes: serde_json::Map<String, serde_json::Value> = self
+                    .routes
+                    .iter()
+                    .map(|route| {
+                        (
+                            route.name.clone(),
+                            format!("/projects/{}/{}/", project.slug(), route.name).into(),
+                        )
+                    })
+                    .collect();
+                serde_json::json!({ "name": project.name, "routes": routes })
+            })
+            .collect();
+        serde_json::json!({ "projects": projects })
+    }
+
+    fn file(&self, project: &str, route: &str, file: &[&str], directory: bool) -> ExportResponse {
+        let Some(project) = self.projects.iter().find(|p| p.slug() == project) else {
+            return ExportResponse::error(404, format!("No such project: {}", project));
+        };
+        let Some(route) = self.routes.iter().find(|r| r.name == route) else {
+            return ExportResponse::error(404, format!("No such export: {}", route));
+        };
+        let generated = match self.generated(project, route) {
+            Ok(generated) => generated,
+            Err(e) => {
+                return ExportResponse::error(
+                    503,
+                    format!(
+                        "{} of {} are not available: {}",
+                        route.name, project.name, e
+                    ),
+                );
+            }
+        };
+
+        let path = file.join("/");
+        if file.is_empty() || directory {
+            // A directory: its `index.html` when there is one, else a listing
+            let index = if path.is_empty() {
+                "index.html".to_string()
+            } else {
+                format!("{}/index.html", path)
+            };
+            if let Some(body) = generated.files.get(&index) {
+                return file_response(&index, body.clone());
+            }
+            let prefix = if path.is_empty() {
+                String::new()
+            } else {
+                format!("{}/", path)
+            };
+            let files: Vec<&String> = generated
+                .files
+                .keys()
+                .filter(|name| name.starts_with(&prefix))
+                .collect();
+            if files.is_empty() && !path.is_empty() {
+                return ExportResponse::error(404, format!("No such directory: {}", path));
+            }
+            return ExportResponse::json(200, &serde_json::json!({ "files": files }));
+        }
+        match generated.files.get(&path) {
+            Some(body) => file_response(&path, body.clone()),
+            None => ExportResponse::error(404, format!("No such file: {}", path)),
+        }
+    }
+
+    /// Files of `route` for the current model of `project`, regenerated when
+    /// the model changed since they were last generated
+    fn generated(&self, project: &ExportProject, route: &ExportRoute) -> Result<Arc<Generated>> {
+        let builtin = self
+            .builtins
+            .get(&route.backend)
+            .filter(|b| b.info().extension_type == ExtensionType::Backend);
+        let Some(builtin) = builtin else {
+            // Served as `morphir generate` left it
+            let dir = project.generate_dir.join(&route.backend);
+            if !dir.is_dir() {
+                return Err(DaemonError::Project(format!(
+                    "nothing generated yet; run `morphir generate --target {}`",
+                    route.backend
+                )));
+            }
+            let mut files = BTreeMap::new();
+            read_files(&dir, &dir, &mut files)?;
+            return Ok(Arc::new(Generated { model: None, files }));
+        };
+
+        let model = model_stamp(&project.ir_path);
+        let key = (project.name.clone(), route.name.clone());
+        if let Some(cached) = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|cached| model.is_some() && cached.model == model)
+        {
+            return Ok(Arc::clone(cached));
+        }
+
+        debug!(
+            "Generating {} for {} from {}",
+            route.name,
+            project.name,
+            project.ir_path.display()
+        );
+        let ir = load_ir(&project.ir_path)?;
+        let request = Envelope::json(&serde_json::json!({ "ir": ir, "options": {} }))
+            .map_err(|e| DaemonError::Extension(e.to_string()))?;
+        let response: serde_json::Value = builtin
+            .execute_native(&request)
+            .and_then(|response| response.as_json().map_err(anyhow::Error::from))?;
+        if response.get("success").and_then(|s| s.as_bool()) != Some(true) {
+            return Err(DaemonError::Extension(
+                response
+                    .get("error")
+                    .and_then(|e| e.as_str())
+                    .unwrap_or("generation failed")
+                    .to_string(),
+            ));
+        }
+        let files = response
+            .get("artifacts")
+            .and_then(|a| a.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|artifact| {
+                let path = artifact.get("path")?.as_str()?;
+                let content = artifact.get("content")?.as_str()?;
+                Some((path.to_string(), content.as_bytes().to_vec()))
+            })
+            .collect();
+
+        let generated = Arc::new(Generated { model, files });
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(key, Arc::clone(&generated));
+        Ok(generated)
+    }
+
+    /// Serve requests on `listener` until the task is dropped
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let export = Arc::clone(&self);
+            tokio::spawn(async move {
+                let service = service_fn(move |request: Request<Incoming>| {
+                    let export = Arc::clone(&export);
+                    async move {
+                        let method = request.method().as_str().to_string();
+                        let path = request.uri().path().to_string();
+                        let if_none_match = request
+                            .headers()
+                            .get(header::IF_NONE_MATCH)
+                            .and_then(|v| v.to_str().ok())
+                            .map(str::to_string);
+                        // Generation reads files and may take a while
+                        let response = tokio::task::spawn_blocking(move || {
+                            export.handle(&method, &path, if_none_match.as_deref())
+                        })
+                        .await
+                        .unwrap_or_else(|e| ExportResponse::error(500, e.to_string()));
+                        Ok::<_, Infallible>(response.into_http())
+                    }
+                });
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    debug!("Export connection closed: {}", e);
+                }
+            });
+        }
+    }
+}
+
+fn file_response(path: &str, body: Vec<u8>) -> ExportResponse {
+    ExportResponse {
+        status: 200,
+        content_type: content_type(path),
+        etag: Some(etag(&body)),
+        body,
+    }
+}
+
+fn content_type(path: &str) -> &'static str {
+    match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("json") => "application/json",
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("md") => "text/markdown; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("proto") | Some("txt") | Some("csv") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Strong entity tag: the start of the body's SHA-256
+fn etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hex)
+}
+
+/// Whether an `If-None-Match` header names `etag`; weak tags compare by
+/// their opaque part, as the header requires
+fn etag_matches(etag: &str, header: &str) -> bool {
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+/// Stamp of the model at `path`, a file or a directory of files
+fn model_stamp(path: &Path) -> Option<ModelStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.is_file() {
+        return Some((metadata.modified().ok()?, metadata.len()));
+    }
+    let mut stamp = (SystemTime::UNIX_EPOCH, 0);
+    for entry in std::fs::read_dir(path).ok()?.filter_map(|e| e.ok()) {
+        if let Some((modified, len)) = model_stamp(&entry.path()) {
+            stamp = (stamp.0.max(modified), stamp.1 + len);
+        }
+    }
+    Some(stamp)
+}
+
+/// Files under `dir`, keyed by their `/`-separated path relative to `root`
+fn read_files(root: &Path, dir: &Path, files: &mut BTreeMap<String, Vec<u8>>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let key = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(key, std::fs::read(&path)?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// A Classic IR with one type alias, `Amount = Int`, in module Billing
+    fn write_ir(path: &Path, type_name: &str) {
+        let ir = serde_json::json!({
+            "formatVersion": 3,
+            "distribution": ["Library", [["acme"]], [], {"modules": [
+                [[["billing"]], {"access": "Public", "value": {
+                    "types": [[[type_name], {"access": "Public", "value": {"doc": "", "value":
+                        ["TypeAliasDefinition", [],
+                            ["Reference", {}, [[["morphir"], ["s", "d", "k"]], [["basics"]], ["int"]], []]]
+                    }}]],
+                    "values": [],
+                    "doc": null
+                }}]
+            ]}]
+        });
+        std::fs::write(path, serde_json::to_vec(&ir).unwrap()).unwrap();
+    }
+
+    fn export(root: &Path) -> RestExport {
+        let out = root.join(".morphir/out/acme-billing");
+        std::fs::create_dir_all(out.join("compile/gleam")).unwrap();
+        std::fs::create_dir_all(out.join("generate/handbook/modules")).unwrap();
+        std::fs::write(out.join("generate/handbook/index.html"), "<h1>Billing</h1>").unwrap();
+        std::fs::write(
+            out.join("generate/handbook/modules/billing.html"),
+            "<h1>billing</h1>",
+        )
+        .unwrap();
+        write_ir(&out.join("compile/gleam/morphir-ir.json"), "amount");
+        RestExport::new(vec![ExportProject {
+            name: "acme/billing".to_string(),
+            ir_path: out.join("compile/gleam/morphir-ir.json"),
+            generate_dir: out.join("generate"),
+        }])
+        .with_route("handbook", "handbook")
+    }
+
+    #[test]
+    fn test_schemas_are_revalidated_and_regenerated_on_model_change() {
+        let temp = tempdir().unwrap();
+        let export = export(temp.path());
+
+        let listing = export.handle("GET", "/projects/acme-billing/schemas/", None);
+        assert_eq!(listing.status, 200);
+        let listing: serde_json::Value = serde_json::from_slice(&listing.body).unwrap();
+        assert_eq!(listing["files"], serde_json::json!(["acme.schema.json"]));
+
+        let schema = export.handle(
+            "GET",
+            "/projects/acme-billing/schemas/acme.schema.json",
+            None,
+        );
+        assert_eq!(schema.status, 200);
+        assert_eq!(schema.content_type, "application/json");
+        assert!(String::from_utf8_lossy(&schema.body).contains("Amount"));
+        let etag = schema.etag.clone().unwrap();
+
+        let cached = export.handle(
+            "GET",
+            "/projects/acme-billing/schemas/acme.schema.json",
+            Some(&format!("W/{}, \"other\"", etag)),
+        );
+        assert_eq!(cached.status, 304);
+        assert!(cached.body.is_empty());
+
+        // A new model changes the schema, so the old tag no longer matches
+        let ir_path = temp
+            .path()
+            .join(".morphir/out/acme-billing/compile/gleam/morphir-ir.json");
+        write_ir(&ir_path, "total-amount");
+        let changed = export.handle(
+            "GET",
+            "/projects/acme-billing/schemas/acme.schema.json",
+            Some(&etag),
+        );
+        assert_eq!(changed.status, 200);
+        assert_ne!(changed.etag.unwrap(), etag);
+        assert!(String::from_utf8_lossy(&changed.body).contains("TotalAmount"));
+    }
+
+    #[test]
+    fn test_generated_docs_are_served_read_only() {
+        let temp = tempdir().unwrap();
+        let export = export(temp.path());
+
+        let index = export.handle("GET", "/projects/acme-billing/handbook/", None);
+        assert_eq!(index.status, 200);
+        assert_eq!(index.content_type, "text/html; charset=utf-8");
+        assert_eq!(index.body, b"<h1>Billing</h1>");
+        let page = export.handle(
+            "HEAD",
+            "/projects/acme-billing/handbook/modules/billing.html",
+            None,
+        );
+        assert_eq!(page.status, 200);
+
+        // The docs backend is builtin, so its site is rendered from the IR
+        let docs = export.handle("GET", "/projects/acme-billing/docs/", None);
+        assert_eq!(docs.status, 200);
+        assert!(String::from_utf8_lossy(&docs.body).contains("href=\"modules/billing.html\""));
+        let page = export.handle(
+            "GET",
+            "/projects/acme-billing/docs/modules/billing.html",
+            None,
+        );
+        assert_eq!(page.status, 200);
+        assert!(String::from_utf8_lossy(&page.body).contains("Amount"));
+
+        let root: serde_json::Value =
+            serde_json::from_slice(&export.handle("GET", "/", None).body).unwrap();
+        assert_eq!(
+            root["projects"][0]["routes"]["docs"],
+            "/projects/acme-billing/docs/"
+        );
+
+        assert_eq!(
+            export
+                .handle("PUT", "/projects/acme-billing/docs/", None)
+                .status,
+            405
+        );
+        assert_eq!(
+            export
+                .handle("GET", "/projects/acme-billing/docs/../../compile", None)
+                .status,
+            400
+        );
+        assert_eq!(
+            export.handle("GET", "/projects/other/docs/", None).status,
+            404
+        );
+    }
+}
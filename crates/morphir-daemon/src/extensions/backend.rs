@@ -0,0 +1,150 @@
+//! Runtimes hosting extensions
+//!
+//! An extension is either an Extism plugin, a core WASM module answering
+//! JSON-RPC requests through its `handle` export, or a component exporting
+//! the `morphir:ext` compiler interfaces. Both implement
+//! [`ExtensionBackend`], so the registry hosts either without knowing which:
+//! requests go in as envelopes carrying the JSON-RPC params and come back as
+//! envelopes carrying the result.
+//!
+//! The runtime of an extension is detected from its WASM, or set per
+//! extension in `morphir.toml`:
+//!
+//! ```toml
+//! [extensions.runtime]
+//! my-extension = "component"
+//! ```
+
+use crate::error::{DaemonError, Result};
+use crate::extensions::component::ComponentBackend;
+use crate::extensions::container::{ExtensionContainer, ExtensionInfo, ExtensionType};
+use crate::extensions::host_functions::MorphirHostFunctions;
+use crate::extensions::permissions::PermissionGrants;
+use async_trait::async_trait;
+use morphir_common::config::ExtensionRuntime;
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::types::{ExtensionCapabilities, ResourceLimits};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+/// What an extension is loaded with
+pub struct LoadContext<'a> {
+    /// Host functions of the workspace the extension runs in
+    pub host_functions: MorphirHostFunctions,
+    /// Capabilities the workspace grants
+    pub grants: &'a PermissionGrants,
+    /// Resource limits of each call
+    pub limits: &'a ResourceLimits,
+}
+
+/// A loaded extension, whatever runtime hosts it
+#[async_trait]
+pub trait ExtensionBackend: Send + Sync {
+    /// Load the extension `id` from `wasm_bytes`
+    fn load(id: &str, wasm_bytes: &[u8], context: LoadContext<'_>) -> Result<Self>
+    where
+        Self: Sized;
+
+    /// The runtime hosting the extension
+    fn runtime(&self) -> ExtensionRuntime;
+
+    /// Get extension ID
+    fn id(&self) -> &str;
+
+    /// Get extension info
+    fn info(&self) -> &ExtensionInfo;
+
+    /// Get the capabilities the extension declared
+    fn capabilities(&self) -> &ExtensionCapabilities;
+
+    /// Get the resource limits the extension runs with
+    fn limits(&self) -> &ResourceLimits;
+
+    /// Check if extension supports a capability
+    fn supports(&self, ext_type: ExtensionType) -> bool {
+        self.info().types.contains(&ext_type)
+    }
+
+    /// Call `method` with the JSON-RPC params in `input`, answering with the
+    /// result in an envelope carrying the request's header
+    async fn invoke(&self, method: &str, input: &Envelope) -> Result<Envelope>;
+}
+
+impl dyn ExtensionBackend {
+    /// Call an extension method with JSON-RPC
+    pub async fn call<I: Serialize, O: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: I,
+    ) -> Result<O> {
+        let output = self.invoke(method, &Envelope::json(&params)?).await?;
+        output.as_json().map_err(|e| {
+            DaemonError::Extension(format!(
+                "Extension {} answered with an invalid envelope: {}",
+                self.id(),
+                e
+            ))
+        })
+    }
+}
+
+/// Runtime of the extension in `wasm_bytes`: components carry their own
+/// layer in the WASM header, or open with `(component` in the text format;
+/// anything else is an Extism plugin
+pub fn detect(wasm_bytes: &[u8]) -> ExtensionRuntime {
+    const COMPONENT_LAYER: [u8; 4] = [0x0d, 0x00, 0x01, 0x00];
+    let component = if wasm_bytes.starts_with(b"\0asm") {
+        wasm_bytes.get(4..8) == Some(&COMPONENT_LAYER)
+    } else {
+        let mut text = std::str::from_utf8(wasm_bytes).unwrap_or_default();
+        // Skip the line comments before the first form
+        while let Some(comment) = text.trim_start().strip_prefix(";;") {
+            text = comment.split_once('\n').map_or("", |(_, rest)| rest);
+        }
+        text.trim_start().starts_with("(component")
+    };
+    if component {
+        ExtensionRuntime::Component
+    } else {
+        ExtensionRuntime::Extism
+    }
+}
+
+/// Load the extension `id` from `wasm_bytes` with `runtime`, or the runtime
+/// detected from the bytes
+pub fn load(
+    id: &str,
+    wasm_bytes: &[u8],
+    runtime: Option<ExtensionRuntime>,
+    context: LoadContext<'_>,
+) -> Result<Arc<dyn ExtensionBackend>> {
+    Ok(match runtime.unwrap_or_else(|| detect(wasm_bytes)) {
+        ExtensionRuntime::Extism => Arc::new(ExtensionContainer::load(id, wasm_bytes, context)?),
+        ExtensionRuntime::Component => Arc::new(ComponentBackend::load(id, wasm_bytes, context)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_is_detected_from_the_wasm_header() {
+        assert_eq!(detect(b"\0asm\x01\0\0\0"), ExtensionRuntime::Extism);
+        assert_eq!(
+            detect(b"\0asm\x0d\0\x01\0\x07\x10"),
+            ExtensionRuntime::Component
+        );
+        assert_eq!(
+            detect(b"  (component (core module))"),
+            ExtensionRuntime::Component
+        );
+        assert_eq!(
+            detect(b";; a validator\n;;\n(component)"),
+            ExtensionRuntime::Component
+        );
+        assert_eq!(detect(b"(module)"), ExtensionRuntime::Extism);
+        assert_eq!(detect(b""), ExtensionRuntime::Extism);
+    }
+}
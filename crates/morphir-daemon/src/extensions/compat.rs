@@ -0,0 +1,235 @@
+//! Version negotiation between extensions and the host
+//!
+//! The host speaks the protocol of the SDK it was built with,
+//! [`HOST_SDK_VERSION`]. An extension declares the SDK versions it works
+//! with in its info:
+//!
+//! - `min_sdk_version`: the oldest SDK it needs; older hosts are refused
+//! - `max_sdk_version`: the newest SDK it supports; newer hosts are accepted
+//!   while they are semver-compatible with it (the same major version, or
+//!   the same minor version before 1.0)
+//!
+//! Without a `max_sdk_version`, the SDK version the extension was built with
+//! (`sdk_version`, set by `export_extension!`) bounds newer hosts the same
+//! way. An extension declaring none of them loads with any host.
+
+use crate::error::{DaemonError, Result};
+use crate::extensions::container::ExtensionInfo;
+use semver::Version;
+use serde::Serialize;
+
+/// SDK version the host speaks
+pub const HOST_SDK_VERSION: &str = morphir_extension_sdk::SDK_VERSION;
+
+/// Whether an extension works with a host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum Compatibility {
+    /// The host is within the versions the extension supports
+    Compatible,
+    /// The host is older than the extension requires
+    HostTooOld { required: String },
+    /// The host is newer than the extension supports
+    HostTooNew { supported: String },
+    /// A version the extension declares is not a semantic version
+    InvalidVersion { field: String, value: String },
+}
+
+impl Compatibility {
+    /// Whether the extension can be loaded
+    pub fn is_compatible(&self) -> bool {
+        matches!(self, Compatibility::Compatible)
+    }
+
+    /// Why the extension `id` cannot be loaded by a host speaking `host`,
+    /// and what to do about it
+    pub fn reason(&self, id: &str, host: &str) -> Option<String> {
+        match self {
+            Compatibility::Compatible => None,
+            Compatibility::HostTooOld { required } => Some(format!(
+                "it requires SDK {} or newer, but morphir speaks {}; upgrade morphir, or \
+                 install an older release with `morphir extension update {} --version <version>`",
+                required, host, id
+            )),
+            Compatibility::HostTooNew { supported } => Some(format!(
+                "it supports SDK up to {}, but morphir speaks {}; update it with \
+                 `morphir extension update {}`, or rebuild it against SDK {}",
+                supported, host, id, host
+            )),
+            Compatibility::InvalidVersion { field, value } => Some(format!(
+                "its {} {:?} is not a semantic version",
+                field, value
+            )),
+        }
+    }
+}
+
+/// Compatibility of an extension with a host speaking SDK `host`
+pub fn negotiate(host: &Version, info: &ExtensionInfo) -> Compatibility {
+    let declared = |field: &str, value: &Option<String>| match value {
+        None => Ok(None),
+        Some(value) => parse_version(value)
+            .map(|version| Some((version, value.clone())))
+            .ok_or_else(|| Compatibility::InvalidVersion {
+                field: field.to_string(),
+                value: value.clone(),
+            }),
+    };
+    let (min, max, built) = match (
+        declared("min_sdk_version", &info.min_sdk_version),
+        declared("max_sdk_version", &info.max_sdk_version),
+        declared("sdk_version", &info.sdk_version),
+    ) {
+        (Ok(min), Ok(max), Ok(built)) => (min, max, built),
+        (Err(invalid), _, _) | (_, Err(invalid), _) | (_, _, Err(invalid)) => return invalid,
+    };
+
+    if let Some((min, required)) = min
+        && compare(host, &min).is_lt()
+    {
+        return Compatibility::HostTooOld { required };
+    }
+    if let Some((max, supported)) = max.or(built)
+        && compare(host, &max).is_gt()
+        && !semver_compatible(&max, host)
+    {
+        return Compatibility::HostTooNew { supported };
+    }
+    Compatibility::Compatible
+}
+
+/// The SDK version the host speaks
+pub fn host_version() -> Version {
+    Version::parse(HOST_SDK_VERSION).expect("the SDK version is a semantic version")
+}
+
+/// Check that an extension works with this host before it is loaded
+pub fn check(id: &str, info: &ExtensionInfo) -> Result<()> {
+    match negotiate(&host_version(), info).reason(id, HOST_SDK_VERSION) {
+        None => Ok(()),
+        Some(reason) => Err(DaemonError::IncompatibleExtension {
+            extension: id.to_string(),
+            version: info.version.clone(),
+            reason,
+        }),
+    }
+}
+
+/// A version, allowing the minor and patch numbers to be left out
+/// (`1`, `0.2`)
+fn parse_version(value: &str) -> Option<Version> {
+    let value = value.trim().trim_start_matches('v');
+    let padded = match value.split('.').count() {
+        1 => format!("{}.0.0", value),
+        2 => format!("{}.0", value),
+        _ => value.to_string(),
+    };
+    Version::parse(&padded).ok()
+}
+
+/// Order of versions by their numbers, ignoring pre-releases, so that
+/// `0.3.0-dev` satisfies a minimum of `0.3.0`
+fn compare(a: &Version, b: &Version) -> std::cmp::Ordering {
+    (a.major, a.minor, a.patch).cmp(&(b.major, b.minor, b.patch))
+}
+
+/// Whether `newer` only adds to `base` under semver: the same major
+/// version, or the same minor version before 1.0
+fn semver_compatible(base: &Version, newer: &Version) -> bool {
+    base.major == newer.major && (base.major > 0 || base.minor == newer.minor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(min: Option<&str>, max: Option<&str>, built: Option<&str>) -> ExtensionInfo {
+        ExtensionInfo {
+            id: "ext".into(),
+            name: "Ext".into(),
+            version: "1.0.0".into(),
+            description: None,
+            types: vec![],
+            author: None,
+            license: None,
+            min_sdk_version: min.map(String::from),
+            max_sdk_version: max.map(String::from),
+            sdk_version: built.map(String::from),
+            permissions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_negotiate_sdk_ranges() {
+        let host = Version::parse("0.4.2").unwrap();
+        let negotiate = |min, max, built| negotiate(&host, &info(min, max, built));
+
+        assert_eq!(negotiate(None, None, None), Compatibility::Compatible);
+        assert_eq!(
+            negotiate(Some("0.1"), None, None),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            negotiate(Some("0.5.0"), None, None),
+            Compatibility::HostTooOld {
+                required: "0.5.0".into()
+            }
+        );
+        // Newer patch releases of the newest supported minor version are fine
+        assert_eq!(
+            negotiate(None, Some("0.4.0"), None),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            negotiate(None, Some("0.3"), None),
+            Compatibility::HostTooNew {
+                supported: "0.3".into()
+            }
+        );
+        // The SDK built with bounds the host when no maximum is declared
+        assert_eq!(
+            negotiate(None, None, Some("0.2.0")),
+            Compatibility::HostTooNew {
+                supported: "0.2.0".into()
+            }
+        );
+        assert_eq!(
+            negotiate(None, Some("0.5"), Some("0.2.0")),
+            Compatibility::Compatible
+        );
+        assert_eq!(
+            negotiate(Some("latest"), None, None),
+            Compatibility::InvalidVersion {
+                field: "min_sdk_version".into(),
+                value: "latest".into()
+            }
+        );
+
+        let host = Version::parse("1.3.0").unwrap();
+        assert!(super::negotiate(&host, &info(None, Some("1.0.0"), None)).is_compatible());
+        assert!(!super::negotiate(&host, &info(None, None, Some("0.9.0"))).is_compatible());
+    }
+
+    #[test]
+    fn test_incompatible_extensions_are_refused_with_a_reason() {
+        assert!(check("ext", &info(Some("0.1.0"), None, Some(HOST_SDK_VERSION))).is_ok());
+
+        let err = check("ext", &info(Some("99.0.0"), None, None)).unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.starts_with("Extension ext 1.0.0 is incompatible with this host:"),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains("requires SDK 99.0.0 or newer"),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains("morphir extension update ext"),
+            "{}",
+            message
+        );
+    }
+}
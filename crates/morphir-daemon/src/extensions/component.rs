@@ -0,0 +1,147 @@
+//! Extensions hosted as wasmtime components
+//!
+//! A component has no info or capabilities exports; its info is made up from
+//! the compiler interfaces it exports, with a digest of its bytes as its
+//! version. JSON-RPC methods map onto those interfaces, and a method whose
+//! interface the component does not export fails like an unregistered
+//! capability. Components import only the `runtime` interface, so they are
+//! granted nothing beyond logging and their own environment variables.
+
+use crate::error::{DaemonError, Result};
+use crate::extensions::backend::{ExtensionBackend, LoadContext};
+use crate::extensions::container::{ExtensionInfo, ExtensionType};
+use crate::extensions::execution;
+use crate::extensions::limits;
+use crate::extensions::protocol::{RpcError, methods};
+use async_trait::async_trait;
+use morphir_common::config::ExtensionRuntime;
+use morphir_ext::ComponentExtension;
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::types::{
+    self as sdk, ExtensionCapabilities, IncrementalCompileResult, ResourceLimits,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Container for a loaded component
+pub struct ComponentBackend {
+    /// The instantiated component
+    component: Mutex<ComponentExtension>,
+    /// Extension metadata
+    info: ExtensionInfo,
+    /// Capabilities; components declare none
+    capabilities: ExtensionCapabilities,
+    /// Resource limits of each call
+    limits: ResourceLimits,
+}
+
+impl ComponentBackend {
+    /// The result of a component call, or the error it failed with
+    fn answer<T: Serialize>(&self, result: anyhow::Result<T>) -> Result<serde_json::Value> {
+        let result = result.map_err(|e| {
+            limits::call_error(&self.info.id, &self.limits, "Component call failed", e)
+        })?;
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+#[async_trait]
+impl ExtensionBackend for ComponentBackend {
+    fn load(id: &str, wasm_bytes: &[u8], context: LoadContext<'_>) -> Result<Self> {
+        let component = ComponentExtension::limited(id, wasm_bytes, context.limits)
+            .map_err(|e| limits::call_error(id, context.limits, "Failed to load component", e))?;
+        let digest: String = Sha256::digest(wasm_bytes)
+            .iter()
+            .take(6)
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let info = ExtensionInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: format!("sha256:{}", digest),
+            description: None,
+            types: component.types().into_iter().map(extension_type).collect(),
+            author: None,
+            license: None,
+            min_sdk_version: None,
+            max_sdk_version: None,
+            sdk_version: None,
+            permissions: Default::default(),
+        };
+        debug!("Loaded component: {} ({})", info.name, info.version);
+
+        Ok(Self {
+            component: Mutex::new(component),
+            info,
+            capabilities: ExtensionCapabilities::default(),
+            limits: context.limits.clone(),
+        })
+    }
+
+    fn runtime(&self) -> ExtensionRuntime {
+        ExtensionRuntime::Component
+    }
+
+    fn id(&self) -> &str {
+        &self.info.id
+    }
+
+    fn info(&self) -> &ExtensionInfo {
+        &self.info
+    }
+
+    fn capabilities(&self) -> &ExtensionCapabilities {
+        &self.capabilities
+    }
+
+    fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    async fn invoke(&self, method: &str, input: &Envelope) -> Result<Envelope> {
+        let params = execution::rpc_params(input)?;
+        debug!("Calling component method: {}", method);
+
+        let mut component = self.component.lock().await;
+        let result = match method {
+            methods::INFO => serde_json::to_value(&self.info)?,
+            methods::CAPABILITIES => serde_json::to_value(&self.capabilities)?,
+            methods::COMPILE => self.answer(component.compile(&request(method, params)?))?,
+            methods::COMPILE_INCREMENTAL => self.answer(
+                component
+                    .compile(&request(method, params)?)
+                    .map(IncrementalCompileResult::from),
+            )?,
+            methods::GENERATE => self.answer(component.generate(&request(method, params)?))?,
+            methods::TRANSFORM => self.answer(component.transform(&request(method, params)?))?,
+            methods::VALIDATE => self.answer(component.validate(&request(method, params)?))?,
+            other => {
+                let error = RpcError::method_not_found(other);
+                return Err(DaemonError::Extension(format!(
+                    "RPC error {}: {}",
+                    error.code, error.message
+                )));
+            }
+        };
+        execution::rpc_response(input, result)
+    }
+}
+
+/// The request of a `method` call
+fn request<T: DeserializeOwned>(method: &str, params: serde_json::Value) -> Result<T> {
+    serde_json::from_value(params)
+        .map_err(|e| DaemonError::Extension(format!("Invalid params for {}: {}", method, e)))
+}
+
+/// Capability of a component, in the daemon's terms
+fn extension_type(ext_type: sdk::ExtensionType) -> ExtensionType {
+    match ext_type {
+        sdk::ExtensionType::Frontend => ExtensionType::Frontend,
+        sdk::ExtensionType::Backend => ExtensionType::Backend,
+        sdk::ExtensionType::Transform => ExtensionType::Transform,
+        sdk::ExtensionType::Validator => ExtensionType::Validator,
+    }
+}
@@ -1,11 +1,20 @@
 //! Extension container using Extism
 //!
-//! This module provides the runtime container for loaded extensions.
+//! This module provides the runtime container for loaded Extism plugins.
 
 use crate::error::{DaemonError, Result};
+use crate::extensions::backend::{ExtensionBackend, LoadContext};
+use crate::extensions::compat;
+use crate::extensions::execution;
 use crate::extensions::host_functions::MorphirHostFunctions;
+use crate::extensions::limits;
+use crate::extensions::permissions::{self, PermissionGrants};
 use crate::extensions::protocol::{ExtensionRequest, ExtensionResponse};
-use extism::{Manifest, Plugin, Wasm};
+use async_trait::async_trait;
+use extism::{Function, Manifest, Plugin, PluginBuilder, Wasm};
+use morphir_common::config::ExtensionRuntime;
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::types::{ExtensionCapabilities, Permissions, ResourceLimits};
 use serde::{Serialize, de::DeserializeOwned};
 use std::collections::HashMap;
 use std::path::Path;
@@ -34,6 +43,18 @@ pub struct ExtensionInfo {
     /// License
     #[serde(default)]
     pub license: Option<String>,
+    /// Oldest SDK version the extension requires
+    #[serde(default)]
+    pub min_sdk_version: Option<String>,
+    /// Newest SDK version the extension supports
+    #[serde(default)]
+    pub max_sdk_version: Option<String>,
+    /// SDK version the extension was built with
+    #[serde(default)]
+    pub sdk_version: Option<String>,
+    /// Capabilities the extension requests
+    #[serde(default)]
+    pub permissions: Permissions,
 }
 
 /// Type of extension capability
@@ -54,6 +75,10 @@ pub struct ExtensionContainer {
     plugin: Arc<RwLock<Plugin>>,
     /// Extension metadata
     info: ExtensionInfo,
+    /// Capabilities the extension declared, or the defaults if it declares none
+    capabilities: ExtensionCapabilities,
+    /// Resource limits of each call
+    limits: ResourceLimits,
     /// Request ID counter
     request_id: std::sync::atomic::AtomicU64,
 }
@@ -61,12 +86,31 @@ pub struct ExtensionContainer {
 impl ExtensionContainer {
     /// Create a new extension container from a WASM file
     pub fn new(id: &str, wasm_path: &Path, host_funcs: MorphirHostFunctions) -> Result<Self> {
+        Self::sandboxed(
+            id,
+            wasm_path,
+            host_funcs,
+            &PermissionGrants::default(),
+            &ResourceLimits::default(),
+        )
+    }
+
+    /// Create a new extension container from a WASM file, granting the
+    /// extension the capabilities it requests if `grants` allow them and
+    /// holding it to `limits`
+    pub fn sandboxed(
+        id: &str,
+        wasm_path: &Path,
+        host_funcs: MorphirHostFunctions,
+        grants: &PermissionGrants,
+        limits: &ResourceLimits,
+    ) -> Result<Self> {
         info!("Loading extension '{}' from {:?}", id, wasm_path);
 
         // Read the WASM file
         let wasm_bytes = std::fs::read(wasm_path)?;
 
-        Self::from_bytes(id, &wasm_bytes, host_funcs)
+        Self::from_bytes_sandboxed(id, &wasm_bytes, host_funcs, grants, limits)
     }
 
     /// Create a new extension container from WASM bytes
@@ -75,21 +119,62 @@ impl ExtensionContainer {
         wasm_bytes: &[u8],
         host_funcs: MorphirHostFunctions,
     ) -> Result<Self> {
-        // Create manifest with memory limits
-        let manifest = Manifest::new([Wasm::data(wasm_bytes)]).with_memory_max(256 * 1024 * 1024); // 256 MB max
+        Self::from_bytes_sandboxed(
+            id,
+            wasm_bytes,
+            host_funcs,
+            &PermissionGrants::default(),
+            &ResourceLimits::default(),
+        )
+    }
+
+    /// Create a new extension container from WASM bytes, granting the
+    /// extension the capabilities it requests if `grants` allow them and
+    /// holding it to `limits`.
+    ///
+    /// The extension is instantiated without any capabilities to read the
+    /// permissions in its info, then again with them if it requests any.
+    pub fn from_bytes_sandboxed(
+        id: &str,
+        wasm_bytes: &[u8],
+        host_funcs: MorphirHostFunctions,
+        grants: &PermissionGrants,
+        limits: &ResourceLimits,
+    ) -> Result<Self> {
+        // Create manifest with memory and time limits
+        let manifest = limits::apply(Manifest::new([Wasm::data(wasm_bytes)]), limits);
 
         // Create plugin with host functions
-        let mut plugin = Plugin::new(&manifest, host_funcs.into_functions(), true)
-            .map_err(|e| DaemonError::Extension(format!("Failed to create plugin: {}", e)))?;
+        let sandbox = host_funcs.state().sandbox.clone();
+        let functions = host_funcs.into_functions();
+        let mut plugin = instantiate(&manifest, functions.clone(), limits)?;
 
         // Query extension info
         let info: ExtensionInfo = {
             let output = plugin
                 .call::<&[u8], Vec<u8>>("morphir_extension_info", &[])
+                .map_err(|e| limits::call_error(id, limits, "Failed to get extension info", e))?;
+            serde_json::from_slice(&output)?
+        };
+        compat::check(id, &info)?;
+
+        // Instantiate again in a context granting the requested permissions
+        if !info.permissions.is_empty() {
+            grants.authorize(id, &info.permissions)?;
+            let manifest = permissions::grant_manifest(manifest, &info.permissions, &sandbox);
+            plugin = instantiate(&manifest, functions, limits)?;
+        }
+
+        // Capabilities are optional; extensions built without the SDK may not export them
+        let capabilities = if plugin.function_exists("morphir_extension_capabilities") {
+            let output = plugin
+                .call::<&[u8], Vec<u8>>("morphir_extension_capabilities", &[])
                 .map_err(|e| {
-                    DaemonError::Extension(format!("Failed to get extension info: {}", e))
+                    limits::call_error(id, limits, "Failed to get extension capabilities", e)
                 })?;
             serde_json::from_slice(&output)?
+        } else {
+            ExtensionCapabilities::default()
         };
 
         debug!("Loaded extension: {} v{}", info.name, info.version);
@@ -98,23 +183,25 @@ impl ExtensionContainer {
             id: id.to_string(),
             plugin: Arc::new(RwLock::new(plugin)),
             info,
+            capabilities,
+            limits: limits.clone(),
             request_id: std::sync::atomic::AtomicU64::new(1),
         })
     }
 
-    /// Get extension info
-    pub fn info(&self) -> &ExtensionInfo {
-        &self.info
-    }
-
-    /// Get extension ID
-    pub fn id(&self) -> &str {
-        &self.id
-    }
-
-    /// Check if extension supports a capability
-    pub fn supports(&self, ext_type: ExtensionType) -> bool {
-        self.info.types.contains(&ext_type)
+    /// Info of the extension in `wasm_bytes`, read without granting it any
+    /// capabilities
+    pub fn inspect(wasm_bytes: &[u8]) -> Result<ExtensionInfo> {
+        let limits = ResourceLimits::default();
+        let manifest = limits::apply(Manifest::new([Wasm::data(wasm_bytes)]), &limits);
+        let functions = MorphirHostFunctions::default().into_functions();
+        let mut plugin = instantiate(&manifest, functions, &limits)?;
+        let output = plugin
+            .call::<&[u8], Vec<u8>>("morphir_extension_info", &[])
+            .map_err(|e| {
+                limits::call_error("extension", &limits, "Failed to get extension info", e)
+            })?;
+        Ok(serde_json::from_slice(&output)?)
     }
 
     /// Call an extension method with JSON-RPC
@@ -136,7 +223,7 @@ impl ExtensionContainer {
         let mut plugin = self.plugin.write().await;
         let output = plugin
             .call::<&[u8], Vec<u8>>("handle", &request_bytes)
-            .map_err(|e| DaemonError::Extension(format!("Plugin call failed: {}", e)))?;
+            .map_err(|e| limits::call_error(&self.id, &self.limits, "Plugin call failed", e))?;
 
         let response: ExtensionResponse = serde_json::from_slice(&output)?;
         response.into_result()
@@ -147,16 +234,74 @@ impl ExtensionContainer {
         let mut plugin = self.plugin.write().await;
         plugin
             .call::<&[u8], Vec<u8>>(func_name, input)
-            .map_err(|e| DaemonError::Extension(format!("Plugin call failed: {}", e)))
+            .map_err(|e| limits::call_error(&self.id, &self.limits, "Plugin call failed", e))
+    }
+}
+
+#[async_trait]
+impl ExtensionBackend for ExtensionContainer {
+    fn load(id: &str, wasm_bytes: &[u8], context: LoadContext<'_>) -> Result<Self> {
+        Self::from_bytes_sandboxed(
+            id,
+            wasm_bytes,
+            context.host_functions,
+            context.grants,
+            context.limits,
+        )
+    }
+
+    fn runtime(&self) -> ExtensionRuntime {
+        ExtensionRuntime::Extism
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn info(&self) -> &ExtensionInfo {
+        &self.info
+    }
+
+    fn capabilities(&self) -> &ExtensionCapabilities {
+        &self.capabilities
+    }
+
+    fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    async fn invoke(&self, method: &str, input: &Envelope) -> Result<Envelope> {
+        let params = execution::rpc_params(input)?;
+        let result: serde_json::Value = self.call(method, params).await?;
+        execution::rpc_response(input, result)
     }
 }
 
+/// A WASI plugin for `manifest`, with a fuel budget for each call if
+/// `limits` set one
+fn instantiate(
+    manifest: &Manifest,
+    functions: Vec<Function>,
+    limits: &ResourceLimits,
+) -> Result<Plugin> {
+    let mut builder = PluginBuilder::new(manifest)
+        .with_wasi(true)
+        .with_functions(functions);
+    if let Some(fuel) = limits.max_fuel {
+        builder = builder.with_fuel_limit(fuel);
+    }
+    builder
+        .build()
+        .map_err(|e| DaemonError::Extension(format!("Failed to create plugin: {}", e)))
+}
+
 /// Builder for ExtensionContainer with configuration options
 pub struct ExtensionContainerBuilder {
     id: String,
     wasm_path: Option<std::path::PathBuf>,
     wasm_bytes: Option<Vec<u8>>,
     host_funcs: Option<MorphirHostFunctions>,
+    limits: ResourceLimits,
     config: HashMap<String, String>,
 }
 
@@ -168,6 +313,7 @@ impl ExtensionContainerBuilder {
             wasm_path: None,
             wasm_bytes: None,
             host_funcs: None,
+            limits: ResourceLimits::default(),
             config: HashMap::new(),
         }
     }
@@ -190,6 +336,12 @@ impl ExtensionContainerBuilder {
         self
     }
 
+    /// Set resource limits
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     /// Add configuration value
     pub fn with_config(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.config.insert(key.into(), value.into());
@@ -200,10 +352,18 @@ impl ExtensionContainerBuilder {
     pub fn build(self) -> Result<ExtensionContainer> {
         let host_funcs = self.host_funcs.unwrap_or_default();
 
+        let grants = PermissionGrants::default();
+
         if let Some(path) = self.wasm_path {
-            ExtensionContainer::new(&self.id, &path, host_funcs)
+            ExtensionContainer::sandboxed(&self.id, &path, host_funcs, &grants, &self.limits)
         } else if let Some(bytes) = self.wasm_bytes {
-            ExtensionContainer::from_bytes(&self.id, &bytes, host_funcs)
+            ExtensionContainer::from_bytes_sandboxed(
+                &self.id,
+                &bytes,
+                host_funcs,
+                &grants,
+                &self.limits,
+            )
         } else {
             Err(DaemonError::Extension(
                 "No WASM path or bytes provided".into(),
@@ -0,0 +1,135 @@
+//! Running transforms and validators on envelopes
+//!
+//! A transform or validator runs either natively, when a builtin implements
+//! it, or as a WASM extension called over JSON-RPC. Both paths take an
+//! envelope with JSON content and answer with one: the request content
+//! becomes the JSON-RPC params, the JSON-RPC result becomes the response
+//! content, and the response always carries the request's header.
+
+use crate::error::{DaemonError, Result};
+use crate::extensions::container::ExtensionType;
+use crate::extensions::protocol::methods;
+use morphir_builtins::BuiltinExtension;
+use morphir_ext_core::Envelope;
+
+/// JSON-RPC method that runs an extension of the given type on an envelope
+pub(crate) fn method(ext_type: ExtensionType) -> Result<&'static str> {
+    match ext_type {
+        ExtensionType::Transform => Ok(methods::TRANSFORM),
+        ExtensionType::Validator => Ok(methods::VALIDATE),
+        other => Err(DaemonError::Extension(format!(
+            "{:?} extensions do not run on envelopes",
+            other
+        ))),
+    }
+}
+
+/// Capability of a builtin, in the daemon's terms
+pub(crate) fn builtin_type(ext_type: morphir_builtins::ExtensionType) -> ExtensionType {
+    match ext_type {
+        morphir_builtins::ExtensionType::Frontend => ExtensionType::Frontend,
+        morphir_builtins::ExtensionType::Backend => ExtensionType::Backend,
+        morphir_builtins::ExtensionType::Transform => ExtensionType::Transform,
+        morphir_builtins::ExtensionType::Validator => ExtensionType::Validator,
+    }
+}
+
+/// JSON content of a request envelope, as sent to a WASM extension
+pub(crate) fn rpc_params(input: &Envelope) -> Result<serde_json::Value> {
+    input
+        .as_json()
+        .map_err(|e| DaemonError::Extension(format!("Invalid request envelope: {}", e)))
+}
+
+/// Response envelope for the result of a WASM extension call
+pub(crate) fn rpc_response(input: &Envelope, result: serde_json::Value) -> Result<Envelope> {
+    Ok(Envelope::json(&result)?.with_header(input.header.clone()))
+}
+
+/// Run a builtin natively, with the same request checks and response shape
+/// as a WASM call
+pub(crate) fn run_native(builtin: &dyn BuiltinExtension, input: &Envelope) -> Result<Envelope> {
+    rpc_params(input)?;
+    let output = builtin.execute_native(input)?;
+    let result = output.as_json().map_err(|e| {
+        DaemonError::Extension(format!(
+            "Builtin {} returned an invalid envelope: {}",
+            builtin.info().id,
+            e
+        ))
+    })?;
+    rpc_response(input, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_builtins::migrate::MigrateExtension;
+    use morphir_ext_core::envelope::Header;
+
+    /// Run the builtin the way its WASM build is driven: envelope content in
+    /// as JSON-RPC params, JSON-RPC result out
+    fn run_over_rpc(builtin: &dyn BuiltinExtension, input: &Envelope) -> Result<Envelope> {
+        let params = rpc_params(input)?;
+        let output = builtin.execute_native(&Envelope::json(&params)?)?;
+        let result: serde_json::Value = output.as_json().map_err(anyhow::Error::from)?;
+        rpc_response(input, result)
+    }
+
+    fn request(ir: serde_json::Value, target_version: &str) -> Envelope {
+        Envelope::json(&serde_json::json!({
+            "ir": ir,
+            "target_version": target_version,
+        }))
+        .unwrap()
+        .with_header(Header {
+            seqnum: 7,
+            session_id: "session-1".to_string(),
+            kind: Some("migrate".to_string()),
+        })
+    }
+
+    #[test]
+    fn test_native_and_rpc_envelopes_match() {
+        let migrate = MigrateExtension;
+        let classic = serde_json::json!({
+            "formatVersion": 3,
+            "distribution": ["Library", [["test"]], [], {"modules": []}]
+        });
+        for input in [request(classic.clone(), "v4"), request(classic, "classic")] {
+            let native = run_native(&migrate, &input).unwrap();
+            let rpc = run_over_rpc(&migrate, &input).unwrap();
+            assert_eq!(native.header, input.header);
+            assert_eq!(native.header, rpc.header);
+            assert_eq!(native.content_type, rpc.content_type);
+            assert_eq!(
+                native.as_json::<serde_json::Value>().unwrap(),
+                rpc.as_json::<serde_json::Value>().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_native_and_rpc_reject_the_same_requests() {
+        let migrate = MigrateExtension;
+        let binary = Envelope::new("application/octet-stream", vec![1, 2, 3]);
+        let native = run_native(&migrate, &binary).unwrap_err().to_string();
+        let rpc = run_over_rpc(&migrate, &binary).unwrap_err().to_string();
+        assert_eq!(native, rpc);
+        assert!(native.contains("Invalid request envelope"), "{}", native);
+
+        let bad_version = request(serde_json::json!({}), "v9");
+        assert!(run_native(&migrate, &bad_version).is_err());
+        assert!(run_over_rpc(&migrate, &bad_version).is_err());
+    }
+
+    #[test]
+    fn test_only_transforms_and_validators_run_on_envelopes() {
+        assert_eq!(
+            method(ExtensionType::Transform).unwrap(),
+            methods::TRANSFORM
+        );
+        assert_eq!(method(ExtensionType::Validator).unwrap(), methods::VALIDATE);
+        assert!(method(ExtensionType::Backend).is_err());
+    }
+}
@@ -1,11 +1,42 @@
 //! Host functions exposed to extension plugins
 //!
 //! These functions allow extensions to interact with the daemon.
+//!
+//! Extensions perform file I/O through the host, by virtual path:
+//!
+//! | Function          | Input              | Output                        |
+//! |-------------------|--------------------|-------------------------------|
+//! | `host_read_file`  | path               | `HostResult` of the content   |
+//! | `host_write_file` | `FileWrite` (JSON) | `HostResult` of `null`        |
+//! | `host_glob`       | pattern            | `HostResult` of virtual paths |
+//!
+//! Each path is checked against the host's [`FileSandbox`]: the workspace is
+//! readable, the output and cache directories readable and writable, and
+//! anything else refused. Refusals and I/O failures are returned to the
+//! extension as `HostResult::Error` rather than failing its call.
+//!
+//! They keep data across restarts in their [`StateStore`], by key:
+//!
+//! | Function       | Input               | Output                             |
+//! |----------------|---------------------|------------------------------------|
+//! | `state_get`    | key                 | `HostResult` of the value or null  |
+//! | `state_set`    | `StateEntry` (JSON) | `HostResult` of `null`             |
+//! | `state_delete` | key                 | `HostResult` of whether it existed |
+//!
+//! and reach the network through `host_http_request`, which takes an
+//! `HttpRequest` and returns a `HostResult` of the `HttpResponse`. Their
+//! [`HttpPolicy`] decides which URLs they may request.
 
-use extism::{Function, UserData, Val, ValType};
+use crate::extensions::http::HttpPolicy;
+use crate::extensions::state::StateStore;
+use crate::extensions::virtual_paths::FileSandbox;
+use extism::convert::Json;
+use extism::{CurrentPlugin, Function, UserData, Val, ValType};
+use morphir_common::vfs::{OsVfs, Vfs};
+use morphir_extension_sdk::types::{FileWrite, HostResult, HttpRequest, HttpResponse, StateEntry};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use tracing::debug;
 
@@ -27,18 +58,136 @@ pub struct MorphirHostState {
     pub output_dir: PathBuf,
     /// IR cache
     pub ir_cache: Arc<RwLock<HashMap<String, serde_json::Value>>>,
+    /// Policy for the files extensions read and write
+    pub sandbox: FileSandbox,
+    /// Persistent state of the extension, if it has one
+    pub state: Option<StateStore>,
+    /// Policy for the HTTP requests extensions send
+    pub http: HttpPolicy,
 }
 
 impl Default for MorphirHostState {
     fn default() -> Self {
+        Self::for_workspace(PathBuf::from("."), PathBuf::from(".morphir-dist"))
+    }
+}
+
+impl MorphirHostState {
+    /// State for a workspace, sandboxing files to its virtual paths
+    pub fn for_workspace(workspace_root: PathBuf, output_dir: PathBuf) -> Self {
+        let sandbox = FileSandbox::for_workspace(&workspace_root, &output_dir);
         Self {
-            workspace_root: PathBuf::from("."),
-            output_dir: PathBuf::from(".morphir-dist"),
+            workspace_root,
+            output_dir,
             ir_cache: Arc::new(RwLock::new(HashMap::new())),
+            sandbox,
+            state: None,
+            http: HttpPolicy::default(),
+        }
+    }
+
+    /// Send the HTTP requests `policy` allows
+    pub fn with_http_policy(mut self, policy: HttpPolicy) -> Self {
+        self.http = policy;
+        self
+    }
+
+    /// Send an HTTP request, if the policy allows it
+    pub fn http_request(&self, request: &HttpRequest) -> HostResult<HttpResponse> {
+        self.http.send(request)
+    }
+
+    /// Keep the state of the extension `id` under the workspace's
+    /// `.morphir/state`
+    pub fn with_extension(mut self, id: &str) -> Self {
+        self.state = StateStore::in_morphir_dir(&self.workspace_root.join(".morphir"), id);
+        self
+    }
+
+    fn state_store(&self) -> std::result::Result<&StateStore, String> {
+        self.state
+            .as_ref()
+            .ok_or_else(|| "No state store for this extension".to_string())
+    }
+
+    /// The value stored under `key`
+    pub fn state_get(&self, key: &str) -> HostResult<Option<serde_json::Value>> {
+        debug!("state_get {}", key);
+        match self.state_store() {
+            Ok(store) => store.get(key).into(),
+            Err(e) => HostResult::Error(e),
+        }
+    }
+
+    /// Store a value under its key
+    pub fn state_set(&self, entry: StateEntry) -> HostResult<()> {
+        debug!("state_set {}", entry.key);
+        match self.state_store() {
+            Ok(store) => store.set(&entry.key, entry.value).into(),
+            Err(e) => HostResult::Error(e),
+        }
+    }
+
+    /// Remove the value stored under `key`
+    pub fn state_delete(&self, key: &str) -> HostResult<bool> {
+        debug!("state_delete {}", key);
+        match self.state_store() {
+            Ok(store) => store.delete(key).into(),
+            Err(e) => HostResult::Error(e),
+        }
+    }
+
+    /// Read the file at a virtual path
+    pub fn read_file(&self, path: &str) -> HostResult<String> {
+        debug!("host_read_file {}", path);
+        match self.sandbox.resolve_read(path) {
+            Ok(real) => OsVfs.read_to_string(&real).into(),
+            Err(e) => HostResult::Error(e.to_string()),
+        }
+    }
+
+    /// Write a file at a virtual path, creating its directories
+    pub fn write_file(&self, file: &FileWrite) -> HostResult<()> {
+        debug!("host_write_file {}", file.path);
+        match self.sandbox.resolve_write(&file.path) {
+            Ok(real) => OsVfs.write_from_string(&real, &file.content).into(),
+            Err(e) => HostResult::Error(e.to_string()),
+        }
+    }
+
+    /// Virtual paths of the files matching a virtual glob pattern
+    pub fn glob(&self, pattern: &str) -> HostResult<Vec<String>> {
+        debug!("host_glob {}", pattern);
+        let real = match self.sandbox.resolve_read(pattern) {
+            Ok(real) => real,
+            Err(e) => return HostResult::Error(e.to_string()),
+        };
+        let Some((prefix, base)) = self.sandbox.config().mapping_for(pattern) else {
+            return HostResult::Error(format!("Invalid path: {}", pattern));
+        };
+        match OsVfs.glob(&real.to_string_lossy()) {
+            Ok(paths) => HostResult::Ok(
+                paths
+                    .iter()
+                    .filter(|path| !OsVfs.is_dir(path))
+                    .filter_map(|path| virtual_path(prefix, base, path))
+                    .collect(),
+            ),
+            Err(e) => HostResult::Error(e.to_string()),
         }
     }
 }
 
+/// `path` as a virtual path under `prefix`, which maps to `base`
+fn virtual_path(prefix: &str, base: &Path, path: &Path) -> Option<String> {
+    let suffix = path.strip_prefix(base).ok()?;
+    let segments: Vec<String> = suffix
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(format!("{}/{}", prefix, segments.join("/")))
+}
+
 /// Host functions container
 pub struct MorphirHostFunctions {
     state: Arc<MorphirHostState>,
@@ -60,11 +209,12 @@ impl MorphirHostFunctions {
 
     /// Create host functions for a workspace
     pub fn for_workspace(workspace_root: PathBuf, output_dir: PathBuf) -> Self {
-        Self::new(MorphirHostState {
-            workspace_root,
-            output_dir,
-            ir_cache: Arc::new(RwLock::new(HashMap::new())),
-        })
+        Self::new(MorphirHostState::for_workspace(workspace_root, output_dir))
+    }
+
+    /// Create host functions for the extension `id` in a workspace
+    pub fn for_extension(id: &str, workspace_root: PathBuf, output_dir: PathBuf) -> Self {
+        Self::new(MorphirHostState::for_workspace(workspace_root, output_dir).with_extension(id))
     }
 
     /// Convert to Extism functions
@@ -105,6 +255,83 @@ impl MorphirHostFunctions {
                     get_cached_ir_impl,
                 )
             },
+            // Read a file
+            {
+                let state = state.clone();
+                Function::new(
+                    "host_read_file",
+                    [ValType::I64],
+                    [ValType::I64],
+                    UserData::new(state),
+                    read_file_impl,
+                )
+            },
+            // Write a file
+            {
+                let state = state.clone();
+                Function::new(
+                    "host_write_file",
+                    [ValType::I64],
+                    [ValType::I64],
+                    UserData::new(state),
+                    write_file_impl,
+                )
+            },
+            // Find files
+            {
+                let state = state.clone();
+                Function::new(
+                    "host_glob",
+                    [ValType::I64],
+                    [ValType::I64],
+                    UserData::new(state),
+                    glob_impl,
+                )
+            },
+            // Get persistent state
+            {
+                let state = state.clone();
+                Function::new(
+                    "state_get",
+                    [ValType::I64],
+                    [ValType::I64],
+                    UserData::new(state),
+                    state_get_impl,
+                )
+            },
+            // Set persistent state
+            {
+                let state = state.clone();
+                Function::new(
+                    "state_set",
+                    [ValType::I64],
+                    [ValType::I64],
+                    UserData::new(state),
+                    state_set_impl,
+                )
+            },
+            // Delete persistent state
+            {
+                let state = state.clone();
+                Function::new(
+                    "state_delete",
+                    [ValType::I64],
+                    [ValType::I64],
+                    UserData::new(state),
+                    state_delete_impl,
+                )
+            },
+            // Send an HTTP request
+            {
+                let state = state.clone();
+                Function::new(
+                    "host_http_request",
+                    [ValType::I64],
+                    [ValType::I64],
+                    UserData::new(state),
+                    http_request_impl,
+                )
+            },
             // Log function
             {
                 let state = state.clone();
@@ -180,6 +407,90 @@ fn get_cached_ir_impl(
     Ok(())
 }
 
+fn read_file_impl(
+    plugin: &mut CurrentPlugin,
+    inputs: &[Val],
+    outputs: &mut [Val],
+    user_data: UserData<Arc<MorphirHostState>>,
+) -> Result<(), extism::Error> {
+    let path: String = plugin.memory_get_val(&inputs[0])?;
+    let state = user_data.get()?;
+    let result = state.lock().unwrap().read_file(&path);
+    plugin.memory_set_val(&mut outputs[0], Json(result))
+}
+
+fn write_file_impl(
+    plugin: &mut CurrentPlugin,
+    inputs: &[Val],
+    outputs: &mut [Val],
+    user_data: UserData<Arc<MorphirHostState>>,
+) -> Result<(), extism::Error> {
+    let Json(file): Json<FileWrite> = plugin.memory_get_val(&inputs[0])?;
+    let state = user_data.get()?;
+    let result = state.lock().unwrap().write_file(&file);
+    plugin.memory_set_val(&mut outputs[0], Json(result))
+}
+
+fn glob_impl(
+    plugin: &mut CurrentPlugin,
+    inputs: &[Val],
+    outputs: &mut [Val],
+    user_data: UserData<Arc<MorphirHostState>>,
+) -> Result<(), extism::Error> {
+    let pattern: String = plugin.memory_get_val(&inputs[0])?;
+    let state = user_data.get()?;
+    let result = state.lock().unwrap().glob(&pattern);
+    plugin.memory_set_val(&mut outputs[0], Json(result))
+}
+
+fn state_get_impl(
+    plugin: &mut CurrentPlugin,
+    inputs: &[Val],
+    outputs: &mut [Val],
+    user_data: UserData<Arc<MorphirHostState>>,
+) -> Result<(), extism::Error> {
+    let key: String = plugin.memory_get_val(&inputs[0])?;
+    let state = user_data.get()?;
+    let result = state.lock().unwrap().state_get(&key);
+    plugin.memory_set_val(&mut outputs[0], Json(result))
+}
+
+fn state_set_impl(
+    plugin: &mut CurrentPlugin,
+    inputs: &[Val],
+    outputs: &mut [Val],
+    user_data: UserData<Arc<MorphirHostState>>,
+) -> Result<(), extism::Error> {
+    let Json(entry): Json<StateEntry> = plugin.memory_get_val(&inputs[0])?;
+    let state = user_data.get()?;
+    let result = state.lock().unwrap().state_set(entry);
+    plugin.memory_set_val(&mut outputs[0], Json(result))
+}
+
+fn state_delete_impl(
+    plugin: &mut CurrentPlugin,
+    inputs: &[Val],
+    outputs: &mut [Val],
+    user_data: UserData<Arc<MorphirHostState>>,
+) -> Result<(), extism::Error> {
+    let key: String = plugin.memory_get_val(&inputs[0])?;
+    let state = user_data.get()?;
+    let result = state.lock().unwrap().state_delete(&key);
+    plugin.memory_set_val(&mut outputs[0], Json(result))
+}
+
+fn http_request_impl(
+    plugin: &mut CurrentPlugin,
+    inputs: &[Val],
+    outputs: &mut [Val],
+    user_data: UserData<Arc<MorphirHostState>>,
+) -> Result<(), extism::Error> {
+    let Json(request): Json<HttpRequest> = plugin.memory_get_val(&inputs[0])?;
+    let state = user_data.get()?;
+    let result = state.lock().unwrap().http_request(&request);
+    plugin.memory_set_val(&mut outputs[0], Json(result))
+}
+
 fn log_impl(
     _plugin: &mut extism::CurrentPlugin,
     _inputs: &[Val],
@@ -203,6 +514,79 @@ mod tests {
         assert_eq!(state.workspace_root, PathBuf::from("."));
     }
 
+    #[test]
+    fn test_file_access_goes_through_the_sandbox() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = temp.path().join(".morphir/out");
+        std::fs::create_dir_all(temp.path().join("src/orders")).unwrap();
+        std::fs::write(
+            temp.path().join("src/orders/total.gleam"),
+            "pub fn a() { 1 }",
+        )
+        .unwrap();
+        std::fs::write(temp.path().join("src/notes.md"), "# Notes").unwrap();
+        let state = MorphirHostState::for_workspace(temp.path().to_path_buf(), output.clone());
+
+        assert_eq!(
+            state.read_file("/workspace/src/orders/total.gleam"),
+            HostResult::Ok("pub fn a() { 1 }".to_string())
+        );
+        assert_eq!(
+            state.glob("/workspace/src/**/*.gleam"),
+            HostResult::Ok(vec!["/workspace/src/orders/total.gleam".to_string()])
+        );
+
+        let file = |path: &str| FileWrite {
+            path: path.to_string(),
+            content: "export {}".to_string(),
+        };
+        assert_eq!(
+            state.write_file(&file("/output/ts/orders.ts")),
+            HostResult::Ok(())
+        );
+        assert!(output.join("ts/orders.ts").exists());
+        assert!(matches!(
+            state.write_file(&file("/workspace/src/orders.ts")),
+            HostResult::Error(_)
+        ));
+        assert!(matches!(
+            state.read_file("/etc/passwd"),
+            HostResult::Error(_)
+        ));
+        assert!(matches!(
+            state.read_file("/workspace/missing.gleam"),
+            HostResult::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_state_is_kept_per_extension() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = temp.path().join(".morphir/out");
+        let state = MorphirHostState::for_workspace(temp.path().to_path_buf(), output.clone())
+            .with_extension("my-ext");
+
+        let entry = StateEntry {
+            key: "analysis".to_string(),
+            value: serde_json::json!({"modules": 2}),
+        };
+        assert_eq!(state.state_set(entry), HostResult::Ok(()));
+        assert!(temp.path().join(".morphir/state/my-ext").is_dir());
+        assert_eq!(
+            state.state_get("analysis"),
+            HostResult::Ok(Some(serde_json::json!({"modules": 2})))
+        );
+        assert_eq!(state.state_delete("analysis"), HostResult::Ok(true));
+        assert_eq!(state.state_get("analysis"), HostResult::Ok(None));
+
+        // Without an extension there is nowhere to keep state
+        let anonymous = MorphirHostState::for_workspace(temp.path().to_path_buf(), output);
+        assert!(matches!(
+            anonymous.state_get("analysis"),
+            HostResult::Error(_)
+        ));
+    }
+
     #[test]
     fn test_host_functions_creation() {
         let funcs = MorphirHostFunctions::default();
@@ -0,0 +1,272 @@
+//! HTTP requests of extensions
+//!
+//! Extensions fetch remote schemas or publish artifacts with the
+//! `host_http_request` host function. The daemon sends a request only if
+//! both of these let it through:
+//!
+//! - the extension's permissions: its host must match a `network` entry
+//!   granted under `[extensions.permissions.<id>]`
+//! - the workspace's `[sources]` configuration: remote access must be
+//!   enabled and not offline, and the URL must pass its `allow` and `deny`
+//!   patterns
+//!
+//! Requests use the timeout, redirect limit and user agent of
+//! `[sources.network]`. Each redirect is checked like the first URL, so an
+//! allowed host cannot hand the request on to a refused one. Responses are
+//! returned whatever their status; only refusals and transport failures are
+//! errors.
+
+use morphir_common::remote::RemoteSourceConfig;
+use morphir_ext::commands::host_matches;
+use morphir_extension_sdk::types::{HostResult, HttpRequest, HttpResponse};
+use reqwest::Url;
+use reqwest::blocking::Client;
+use reqwest::redirect;
+use std::time::Duration;
+use tracing::debug;
+
+/// Which URLs an extension may request
+#[derive(Debug, Clone)]
+pub struct HttpPolicy {
+    /// Host patterns the extension was granted
+    network: Vec<String>,
+    /// The workspace's remote source configuration
+    sources: RemoteSourceConfig,
+}
+
+impl Default for HttpPolicy {
+    /// No host granted
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl HttpPolicy {
+    /// Policy for an extension granted the host patterns in `network`
+    pub fn new(network: Vec<String>) -> Self {
+        Self {
+            network,
+            sources: RemoteSourceConfig::default(),
+        }
+    }
+
+    /// Also hold requests to the workspace's `[sources]` configuration
+    pub fn with_sources(mut self, sources: RemoteSourceConfig) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Check that `url` may be requested, saying why not otherwise
+    pub fn check(&self, url: &str) -> std::result::Result<Url, String> {
+        let parsed = Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!(
+                "Only http and https URLs can be requested, not {}",
+                url
+            ));
+        }
+        let host = parsed.host_str().unwrap_or_default();
+        if !self
+            .network
+            .iter()
+            .any(|granted| host_matches(host, granted))
+        {
+            return Err(format!(
+                "Host {} was not granted; add it to the extension's network permissions",
+                host
+            ));
+        }
+        if self.sources.is_offline() {
+            return Err(format!("Cannot request {} while offline", url));
+        }
+        if !self.sources.is_allowed(url) {
+            return Err(format!(
+                "{} is not allowed by the [sources] configuration",
+                url
+            ));
+        }
+        Ok(parsed)
+    }
+
+    fn client(&self) -> reqwest::Result<Client> {
+        let network = &self.sources.network;
+        let max_redirects = network.max_redirects as usize;
+        let policy = self.clone();
+        let redirects = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= max_redirects {
+                return attempt.error(format!("More than {} redirects", max_redirects));
+            }
+            match policy.check(attempt.url().as_str()) {
+                Ok(_) => attempt.follow(),
+                Err(e) => attempt.error(format!("Redirect refused: {}", e)),
+            }
+        });
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(network.timeout_secs))
+            .redirect(redirects);
+        if let Some(user_agent) = &network.user_agent {
+            builder = builder.user_agent(user_agent.as_str());
+        }
+        builder.build()
+    }
+
+    /// Send `request` if the policy allows it
+    pub fn send(&self, request: &HttpRequest) -> HostResult<HttpResponse> {
+        debug!("host_http_request {} {}", request.method, request.url);
+        let url = match self.check(&request.url) {
+            Ok(url) => url,
+            Err(e) => return HostResult::Error(e),
+        };
+        // Host functions run on the runtime's threads, where the blocking
+        // client may not block
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| self.send_checked(url, request))
+                .join()
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("HTTP request panicked")))
+        })
+        .map_err(|e| format!("{:#}", e))
+        .into()
+    }
+
+    fn send_checked(&self, url: Url, request: &HttpRequest) -> anyhow::Result<HttpResponse> {
+        let method = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes())?;
+        let mut builder = self.client()?.request(method, url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+        let response = builder.send()?;
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            headers,
+            body: response.text()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_requests_need_permissions_and_allowed_sources() {
+        let sources = RemoteSourceConfig {
+            deny: vec!["https://schemas.example.com/private/*".to_string()],
+            ..RemoteSourceConfig::default()
+        };
+        let policy = HttpPolicy::new(vec!["*.example.com".to_string()]).with_sources(sources);
+
+        assert!(
+            policy
+                .check("https://schemas.example.com/order.json")
+                .is_ok()
+        );
+        let refused = |url: &str| policy.check(url).unwrap_err();
+        assert!(refused("https://example.org/order.json").contains("was not granted"));
+        assert!(refused("https://schemas.example.com/private/keys").contains("[sources]"));
+        assert!(refused("file:///etc/passwd").contains("Only http and https"));
+        assert!(refused("not a url").contains("Invalid URL"));
+
+        let offline = HttpPolicy::new(vec!["*".to_string()]).with_sources(RemoteSourceConfig {
+            offline: true,
+            ..RemoteSourceConfig::default()
+        });
+        assert!(
+            offline
+                .check("https://schemas.example.com/order.json")
+                .unwrap_err()
+                .contains("offline")
+        );
+        assert!(matches!(
+            HttpPolicy::default().send(&HttpRequest::get("https://example.com")),
+            HostResult::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_responses_carry_status_headers_and_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://127.0.0.1:{}/schemas",
+            listener.local_addr().unwrap().port()
+        );
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                request.push(line.trim_end().to_string());
+            }
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 7\r\nX-Schema: none\r\nConnection: close\r\n\r\nmissing")
+                .unwrap();
+            request
+        });
+
+        let policy = HttpPolicy::new(vec!["127.0.0.1".to_string()]);
+        let response = policy
+            .send(&HttpRequest::get(&url).with_header("Accept", "application/json"))
+            .into_result()
+            .unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, "missing");
+        assert_eq!(response.headers["x-schema"], "none");
+
+        let request = server.join().unwrap();
+        assert_eq!(request[0], "GET /schemas HTTP/1.1");
+        assert!(
+            request
+                .iter()
+                .any(|line| line.eq_ignore_ascii_case("accept: application/json"))
+        );
+    }
+
+    #[test]
+    fn test_redirects_are_checked_like_the_first_url() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://127.0.0.1:{}/schemas",
+            listener.local_addr().unwrap().port()
+        );
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 302 Found\r\nLocation: http://schemas.example.org/private\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+        });
+
+        let policy = HttpPolicy::new(vec!["127.0.0.1".to_string()]);
+        let HostResult::Error(error) = policy.send(&HttpRequest::get(&url)) else {
+            panic!("the redirect to an ungranted host was followed");
+        };
+        server.join().unwrap();
+        assert!(error.contains("Redirect refused"), "{}", error);
+        assert!(
+            error.contains("schemas.example.org was not granted"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,718 @@
+//! Installation of extensions from remote sources
+//!
+//! `morphir extension install` resolves an extension to a WASM module, checks
+//! it, and stores it under the Morphir home directory (`~/.morphir`, or
+//! `MORPHIR_HOME`). The module can come from:
+//!
+//! | Source | Example |
+//! |--------|---------|
+//! | Package registry | `morphir-ts`, looked up with `GET /api/v1/extensions/<name>` |
+//! | GitHub release | `github:acme/morphir-ts@v1.2.0#morphir-ts.wasm` |
+//! | URL | `https://example.com/morphir-ts.wasm` |
+//! | Local path | `target/wasm32-unknown-unknown/release/morphir_ts.wasm` |
+//!
+//! A GitHub asset defaults to `<repo>.wasm` of the latest release. Any source
+//! can be pinned to a digest with a `#sha256=<hex>` fragment item; registry
+//! releases are also checked against the digest the registry lists. The
+//! module must answer `morphir_extension_info`, and is stored by the ID and
+//! version it reports:
+//!
+//! ```text
+//! ~/.morphir/extensions.json                       installed extensions
+//! ~/.morphir/extensions/<id>/<version>/<id>.wasm   their modules
+//! ```
+//!
+//! [`ExtensionRegistry::register_installed`](crate::ExtensionRegistry::register_installed)
+//! makes the installed extensions loadable by ID.
+
+use crate::error::{DaemonError, Result};
+use crate::extensions::compat::{self, Compatibility};
+use crate::extensions::container::{ExtensionContainer, ExtensionType};
+use morphir_common::registry::{
+    ExtensionPackage, ExtensionRelease, LATEST, REGISTRY_URL_ENV, RegistryConfig, select_release,
+};
+use morphir_common::remote::integrity::{hex, split_digest};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// Environment variable overriding the Morphir home directory
+pub const MORPHIR_HOME_ENV: &str = "MORPHIR_HOME";
+
+/// File recording the installed extensions, in the Morphir home directory
+const INSTALLED_FILE: &str = "extensions.json";
+
+/// Magic number every WASM module starts with
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// The Morphir home directory: `MORPHIR_HOME`, or `~/.morphir`
+pub fn morphir_home() -> Result<PathBuf> {
+    if let Some(home) = std::env::var_os(MORPHIR_HOME_ENV).filter(|home| !home.is_empty()) {
+        return Ok(PathBuf::from(home));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".morphir"))
+        .ok_or_else(|| DaemonError::Install("Could not determine home directory".into()))
+}
+
+/// Where an extension's WASM module comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum InstallSource {
+    /// Extension published to the package registry
+    Registry { name: String },
+    /// Asset of a GitHub release
+    GitHub {
+        repo: String,
+        #[serde(default)]
+        tag: Option<String>,
+        #[serde(default)]
+        asset: Option<String>,
+    },
+    /// URL of the module
+    Url { url: String },
+    /// Module on the local file system
+    Path { path: PathBuf },
+}
+
+impl InstallSource {
+    /// Parse a source, returning it and the digest pinned by a
+    /// `#sha256=<hex>` fragment item. Anything that is not a `github:`
+    /// source, a URL, or an existing or `.wasm` path is a registry name.
+    pub fn parse(spec: &str) -> Result<(Self, Option<String>)> {
+        let (spec, digest) = split_digest(spec);
+        let source = if let Some(rest) = spec.strip_prefix("github:") {
+            let (rest, asset) = match rest.split_once('#') {
+                Some((rest, asset)) => (rest, Some(asset.to_string())),
+                None => (rest, None),
+            };
+            let (repo, tag) = match rest.split_once('@') {
+                Some((repo, tag)) => (repo, Some(tag.to_string())),
+                None => (rest, None),
+            };
+            let parts: Vec<&str> = repo.split('/').collect();
+            if parts.len() != 2 || parts.iter().any(|part| part.is_empty()) {
+                return Err(DaemonError::Install(format!(
+                    "Expected github:<owner>/<repo>[@<tag>][#<asset>], got {}",
+                    spec
+                )));
+            }
+            InstallSource::GitHub {
+                repo: repo.to_string(),
+                tag,
+                asset,
+            }
+        } else if spec.starts_with("https://") || spec.starts_with("http://") {
+            InstallSource::Url { url: spec }
+        } else if let Some(path) = spec.strip_prefix("file:") {
+            InstallSource::Path { path: path.into() }
+        } else if spec.ends_with(".wasm") || Path::new(&spec).exists() {
+            InstallSource::Path { path: spec.into() }
+        } else {
+            InstallSource::Registry { name: spec }
+        };
+        Ok((source, digest))
+    }
+}
+
+impl std::fmt::Display for InstallSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallSource::Registry { name } => f.write_str(name),
+            InstallSource::GitHub { repo, tag, asset } => {
+                write!(f, "github:{}", repo)?;
+                if let Some(tag) = tag {
+                    write!(f, "@{}", tag)?;
+                }
+                if let Some(asset) = asset {
+                    write!(f, "#{}", asset)?;
+                }
+                Ok(())
+            }
+            InstallSource::Url { url } => f.write_str(url),
+            InstallSource::Path { path } => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Download URL of an asset of a GitHub release, the latest one by default
+pub fn github_release_url(repo: &str, tag: Option<&str>, asset: &str) -> String {
+    match tag {
+        None | Some(LATEST) => format!(
+            "https://github.com/{}/releases/latest/download/{}",
+            repo, asset
+        ),
+        Some(tag) => format!(
+            "https://github.com/{}/releases/download/{}/{}",
+            repo, tag, asset
+        ),
+    }
+}
+
+/// An extension installed in the Morphir home directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledExtension {
+    /// Extension ID
+    pub name: String,
+    /// Version the extension reports
+    pub version: Option<String>,
+    /// Description
+    pub description: Option<String>,
+    /// Path of the installed WASM module
+    pub install_path: Option<PathBuf>,
+    /// Where the module was installed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<InstallSource>,
+    /// SHA-256 of the module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Capabilities the extension implements
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<ExtensionType>,
+}
+
+/// The extensions installed in a Morphir home directory, by ID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstalledExtensions {
+    #[serde(default)]
+    extensions: BTreeMap<String, InstalledExtension>,
+}
+
+impl InstalledExtensions {
+    /// The extensions recorded in `home`, or none if nothing was installed
+    pub fn load(home: &Path) -> Result<Self> {
+        let path = home.join(INSTALLED_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| DaemonError::Install(format!("Failed to parse {}: {}", path.display(), e)))
+    }
+
+    /// Record the extensions in `home`
+    pub fn save(&self, home: &Path) -> Result<()> {
+        std::fs::create_dir_all(home)?;
+        std::fs::write(
+            home.join(INSTALLED_FILE),
+            serde_json::to_string_pretty(self)?,
+        )?;
+        Ok(())
+    }
+
+    /// The installed extension with ID `id`
+    pub fn get(&self, id: &str) -> Option<&InstalledExtension> {
+        self.extensions.get(id)
+    }
+
+    /// Record an extension, returning the one it replaces
+    pub fn insert(&mut self, extension: InstalledExtension) -> Option<InstalledExtension> {
+        self.extensions.insert(extension.name.clone(), extension)
+    }
+
+    /// Forget the extension with ID `id`
+    pub fn remove(&mut self, id: &str) -> Option<InstalledExtension> {
+        self.extensions.remove(id)
+    }
+
+    /// The installed extensions, ordered by ID
+    pub fn iter(&self) -> impl Iterator<Item = &InstalledExtension> {
+        self.extensions.values()
+    }
+}
+
+/// Downloads, checks, and stores extension modules
+pub struct Installer {
+    /// Morphir home directory
+    home: PathBuf,
+    /// Registry extension names are looked up in
+    registry: RegistryConfig,
+    /// HTTP client for downloads
+    client: reqwest::Client,
+}
+
+impl Installer {
+    /// Installer storing modules under `home`, looking names up in `registry`
+    pub fn new(home: PathBuf, registry: RegistryConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(registry.timeout_secs))
+            .user_agent(format!("morphir/{}", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| DaemonError::Install(format!("Failed to create HTTP client: {}", e)))?;
+        Ok(Self {
+            home,
+            registry,
+            client,
+        })
+    }
+
+    /// The Morphir home directory modules are stored under
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    /// Fetch the module of `source`, check it, and store it. `version` is
+    /// the registry version requirement or the GitHub release tag when the
+    /// source does not name one; `sha256` pins the module's digest.
+    pub async fn install(
+        &self,
+        source: &InstallSource,
+        version: Option<&str>,
+        sha256: Option<&str>,
+    ) -> Result<InstalledExtension> {
+        let source = match source {
+            InstallSource::Path { path } => InstallSource::Path {
+                path: std::fs::canonicalize(path).map_err(|e| {
+                    DaemonError::Install(format!("Failed to read {}: {}", path.display(), e))
+                })?,
+            },
+            InstallSource::GitHub { repo, tag, asset } => InstallSource::GitHub {
+                repo: repo.clone(),
+                tag: tag.clone().or_else(|| version.map(String::from)),
+                asset: asset.clone(),
+            },
+            source => source.clone(),
+        };
+
+        let (bytes, listed) = self.fetch(&source, version).await?;
+        let actual = hex(&Sha256::digest(&bytes));
+        for expected in [sha256, listed.as_deref()].into_iter().flatten() {
+            if !expected.eq_ignore_ascii_case(&actual) {
+                return Err(DaemonError::IntegrityMismatch {
+                    origin: source.to_string(),
+                    expected: expected.to_ascii_lowercase(),
+                    actual,
+                });
+            }
+        }
+        if !bytes.starts_with(WASM_MAGIC) {
+            return Err(DaemonError::Install(format!(
+                "{} is not a WASM module",
+                source
+            )));
+        }
+        let extension = ExtensionContainer::inspect(&bytes).map_err(|e| {
+            DaemonError::Install(format!("{} is not a Morphir extension: {}", source, e))
+        })?;
+        compat::check(&extension.id, &extension)?;
+        path_segment(&extension.id, "ID")?;
+        path_segment(&extension.version, "version")?;
+
+        let dir = self
+            .home
+            .join("extensions")
+            .join(&extension.id)
+            .join(&extension.version);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.wasm", extension.id));
+        let partial = dir.join(format!("{}.wasm.partial", extension.id));
+        std::fs::write(&partial, &bytes)?;
+        std::fs::rename(&partial, &path)?;
+        info!("Installed extension {} {}", extension.id, extension.version);
+
+        Ok(InstalledExtension {
+            name: extension.id,
+            version: Some(extension.version),
+            description: extension.description.or(Some(extension.name)),
+            install_path: Some(path),
+            source: Some(source),
+            sha256: Some(actual),
+            types: extension.types,
+        })
+    }
+
+    /// Delete the stored module of an installed extension
+    pub fn uninstall(&self, extension: &InstalledExtension) -> Result<()> {
+        let Some(path) = &extension.install_path else {
+            return Ok(());
+        };
+        // Only modules stored by the installer are deleted
+        let Some(dir) = path.parent().filter(|dir| dir.starts_with(&self.home)) else {
+            return Ok(());
+        };
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)?;
+        }
+        if let Some(versions) = dir.parent()
+            && std::fs::read_dir(versions).is_ok_and(|mut entries| entries.next().is_none())
+        {
+            std::fs::remove_dir(versions)?;
+        }
+        Ok(())
+    }
+
+    /// The module of `source` and the digest its registry lists
+    async fn fetch(
+        &self,
+        source: &InstallSource,
+        version: Option<&str>,
+    ) -> Result<(Vec<u8>, Option<String>)> {
+        match source {
+            InstallSource::Path { path } => Ok((std::fs::read(path)?, None)),
+            InstallSource::Url { url } => Ok((self.download(url, false).await?, None)),
+            InstallSource::GitHub { repo, tag, asset } => {
+                let asset = asset
+                    .clone()
+                    .unwrap_or_else(|| format!("{}.wasm", repo.rsplit('/').next().unwrap_or(repo)));
+                let url = github_release_url(repo, tag.as_deref(), &asset);
+                Ok((self.download(&url, false).await?, None))
+            }
+            InstallSource::Registry { name } => {
+                let (url, release) = self.resolve(name, version.unwrap_or(LATEST)).await?;
+                let bytes = self
+                    .download(&url, url.starts_with(&self.registry_url()?))
+                    .await?;
+                Ok((bytes, release.release.sha256))
+            }
+        }
+    }
+
+    /// The download URL and release of the newest version of the registry
+    /// extension `name` satisfying `requirement`
+    async fn resolve(&self, name: &str, requirement: &str) -> Result<(String, ExtensionRelease)> {
+        let base = self.registry_url()?;
+        let response = self
+            .get(&format!("{}/api/v1/extensions/{}", base, name), true)
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(DaemonError::Install(format!(
+                "Extension {} not found in {}",
+                name, base
+            )));
+        }
+        let response = checked(response, name)?;
+        let package: ExtensionPackage = response
+            .json()
+            .await
+            .map_err(|e| DaemonError::Install(format!("Invalid registry response: {}", e)))?;
+        let release = select_release(&package.versions, requirement)
+            .map_err(|e| DaemonError::Install(e.to_string()))?
+            .cloned()
+            .ok_or_else(|| {
+                DaemonError::Install(format!("No version of {} matches {}", name, requirement))
+            })?;
+        let url = if release.url.contains("://") {
+            release.url.clone()
+        } else {
+            format!("{}/{}", base, release.url.trim_start_matches('/'))
+        };
+        Ok((url, release))
+    }
+
+    /// The registry's base URL, without a trailing slash
+    fn registry_url(&self) -> Result<String> {
+        self.registry
+            .url
+            .clone()
+            .or_else(|| std::env::var(REGISTRY_URL_ENV).ok())
+            .filter(|url| !url.is_empty())
+            .map(|url| url.trim_end_matches('/').to_string())
+            .ok_or_else(|| {
+                DaemonError::Install(format!(
+                    "No package registry configured to look extensions up in; set `url` in the \
+                     [registry] section of morphir.toml or {}, or install from a path, URL, or \
+                     github: source",
+                    REGISTRY_URL_ENV
+                ))
+            })
+    }
+
+    async fn download(&self, url: &str, authorize: bool) -> Result<Vec<u8>> {
+        info!("Downloading extension from: {}", url);
+        let response = checked(self.get(url, authorize).await?, url)?;
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| DaemonError::Install(format!("Failed to download {}: {}", url, e)))?;
+        Ok(bytes.to_vec())
+    }
+
+    /// GET `url`, with the registry token when `authorize` is set
+    async fn get(&self, url: &str, authorize: bool) -> Result<reqwest::Response> {
+        let mut request = self.client.get(url);
+        if authorize
+            && let Some(token) = self
+                .registry
+                .token_env
+                .as_deref()
+                .and_then(|name| std::env::var(name).ok())
+        {
+            request = request.bearer_auth(token);
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| DaemonError::Install(format!("Failed to download {}: {}", url, e)))
+    }
+}
+
+/// Health of an installed extension, as checked by `morphir extension doctor`
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnosis {
+    /// Extension ID
+    pub name: String,
+    /// Version the module reports, or the recorded one if it does not load
+    pub version: Option<String>,
+    /// Oldest SDK version the extension requires
+    pub min_sdk_version: Option<String>,
+    /// Newest SDK version the extension supports
+    pub max_sdk_version: Option<String>,
+    /// SDK version the extension was built with
+    pub sdk_version: Option<String>,
+    /// Compatibility with this host, if the module loads
+    pub compatibility: Option<Compatibility>,
+    /// What is wrong with the installation
+    pub problems: Vec<String>,
+}
+
+impl Diagnosis {
+    /// Whether nothing is wrong
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check that an installed extension's module is present, unchanged since
+/// it was installed, loads, and is compatible with this host
+pub fn diagnose(extension: &InstalledExtension) -> Diagnosis {
+    let mut diagnosis = Diagnosis {
+        name: extension.name.clone(),
+        version: extension.version.clone(),
+        min_sdk_version: None,
+        max_sdk_version: None,
+        sdk_version: None,
+        compatibility: None,
+        problems: Vec::new(),
+    };
+    let Some(path) = &extension.install_path else {
+        diagnosis
+            .problems
+            .push("no module was downloaded for it; install it again from its source".to_string());
+        return diagnosis;
+    };
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            diagnosis.problems.push(format!(
+                "its module {} cannot be read: {}",
+                path.display(),
+                e
+            ));
+            return diagnosis;
+        }
+    };
+    if let Some(expected) = &extension.sha256
+        && hex(&Sha256::digest(&bytes)) != *expected
+    {
+        diagnosis.problems.push(format!(
+            "its module {} changed since it was installed",
+            path.display()
+        ));
+    }
+    let info = match ExtensionContainer::inspect(&bytes) {
+        Ok(info) => info,
+        Err(e) => {
+            diagnosis
+                .problems
+                .push(format!("its module does not load: {}", e));
+            return diagnosis;
+        }
+    };
+    if info.id != extension.name {
+        diagnosis.problems.push(format!(
+            "its module reports ID {} rather than {}",
+            info.id, extension.name
+        ));
+    }
+    let compatibility = compat::negotiate(&compat::host_version(), &info);
+    if let Some(reason) = compatibility.reason(&extension.name, compat::HOST_SDK_VERSION) {
+        diagnosis
+            .problems
+            .push(format!("it is incompatible: {}", reason));
+    }
+    diagnosis.version = Some(info.version);
+    diagnosis.min_sdk_version = info.min_sdk_version;
+    diagnosis.max_sdk_version = info.max_sdk_version;
+    diagnosis.sdk_version = info.sdk_version;
+    diagnosis.compatibility = Some(compatibility);
+    diagnosis
+}
+
+/// `response`, or an error naming `what` if its status is not a success
+fn checked(response: reqwest::Response, what: &str) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(DaemonError::Install(format!(
+            "Failed to download {}: HTTP {}",
+            what,
+            response.status()
+        )))
+    }
+}
+
+/// Check that an extension's ID or version can name a directory
+fn path_segment(value: &str, what: &str) -> Result<()> {
+    if value.is_empty() || value.starts_with('.') || value.contains(['/', '\\']) {
+        return Err(DaemonError::Install(format!(
+            "Extension {} {:?} cannot name a directory",
+            what, value
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sources() {
+        let parse = |spec| InstallSource::parse(spec).unwrap();
+        assert_eq!(
+            parse("github:acme/morphir-ts@v1.2.0#ts.wasm&sha256=AB12"),
+            (
+                InstallSource::GitHub {
+                    repo: "acme/morphir-ts".into(),
+                    tag: Some("v1.2.0".into()),
+                    asset: Some("ts.wasm".into()),
+                },
+                Some("ab12".into())
+            )
+        );
+        assert_eq!(
+            parse("https://example.com/ts.wasm").0,
+            InstallSource::Url {
+                url: "https://example.com/ts.wasm".into()
+            }
+        );
+        assert_eq!(
+            parse("build/ts.wasm").0,
+            InstallSource::Path {
+                path: "build/ts.wasm".into()
+            }
+        );
+        assert_eq!(
+            parse("morphir-ts").0,
+            InstallSource::Registry {
+                name: "morphir-ts".into()
+            }
+        );
+        assert!(InstallSource::parse("github:acme").is_err());
+
+        let source = parse("github:acme/morphir-ts@v1").0;
+        assert_eq!(source.to_string(), "github:acme/morphir-ts@v1");
+        assert_eq!(
+            github_release_url("acme/morphir-ts", None, "morphir-ts.wasm"),
+            "https://github.com/acme/morphir-ts/releases/latest/download/morphir-ts.wasm"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_install_checks_digest_and_module() {
+        let temp = tempfile::tempdir().unwrap();
+        let installer =
+            Installer::new(temp.path().join("home"), RegistryConfig::default()).unwrap();
+        let module = temp.path().join("ext.wasm");
+        std::fs::write(&module, "not wasm").unwrap();
+        let source = InstallSource::Path {
+            path: module.clone(),
+        };
+
+        let err = installer
+            .install(&source, None, Some("00"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DaemonError::IntegrityMismatch { .. }),
+            "{}",
+            err
+        );
+
+        let err = installer.install(&source, None, None).await.unwrap_err();
+        assert!(err.to_string().ends_with("is not a WASM module"), "{}", err);
+
+        // A module that does not export the extension functions
+        std::fs::write(&module, b"\0asm\x01\0\0\0").unwrap();
+        let err = installer.install(&source, None, None).await.unwrap_err();
+        assert!(
+            err.to_string().contains("is not a Morphir extension"),
+            "{}",
+            err
+        );
+        assert!(!temp.path().join("home").join("extensions").exists());
+    }
+
+    #[test]
+    fn test_diagnose_reports_missing_and_changed_modules() {
+        let temp = tempfile::tempdir().unwrap();
+        let module = temp.path().join("ext.wasm");
+        let mut extension = InstalledExtension {
+            name: "ext".into(),
+            version: Some("1.0.0".into()),
+            description: None,
+            install_path: None,
+            source: None,
+            sha256: Some(hex(&Sha256::digest(b"\0asm\x01\0\0\0"))),
+            types: vec![],
+        };
+        assert!(diagnose(&extension).problems[0].starts_with("no module was downloaded"));
+
+        extension.install_path = Some(module.clone());
+        assert!(diagnose(&extension).problems[0].contains("cannot be read"));
+
+        std::fs::write(&module, b"\0asm\x02\0\0\0").unwrap();
+        let diagnosis = diagnose(&extension);
+        assert!(!diagnosis.is_healthy());
+        assert!(diagnosis.problems[0].contains("changed since it was installed"));
+        assert!(diagnosis.problems[1].starts_with("its module does not load"));
+        assert_eq!(diagnosis.compatibility, None);
+    }
+
+    #[test]
+    fn test_installed_extensions_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        assert_eq!(
+            InstalledExtensions::load(temp.path())
+                .unwrap()
+                .iter()
+                .count(),
+            0
+        );
+
+        // Records written before extensions were downloaded still load
+        std::fs::write(
+            temp.path().join(INSTALLED_FILE),
+            r#"{"extensions": {"old": {"name": "old", "version": "latest", "description": null, "install_path": null}}}"#,
+        )
+        .unwrap();
+        let mut installed = InstalledExtensions::load(temp.path()).unwrap();
+        assert_eq!(installed.get("old").unwrap().install_path, None);
+
+        installed.insert(InstalledExtension {
+            name: "morphir-ts".into(),
+            version: Some("1.2.0".into()),
+            description: None,
+            install_path: Some(temp.path().join("morphir-ts.wasm")),
+            source: Some(InstallSource::Registry {
+                name: "morphir-ts".into(),
+            }),
+            sha256: Some("ab12".into()),
+            types: vec![ExtensionType::Backend],
+        });
+        installed.save(temp.path()).unwrap();
+
+        let loaded = InstalledExtensions::load(temp.path()).unwrap();
+        let ids: Vec<&str> = loaded.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(ids, ["morphir-ts", "old"]);
+        assert_eq!(
+            loaded.get("morphir-ts").unwrap().source,
+            Some(InstallSource::Registry {
+                name: "morphir-ts".into()
+            })
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Resource limits for extensions
+//!
+//! Each extension runs with a maximum memory, a fuel budget (instructions
+//! burned per request), and a wall-clock timeout per request, so a
+//! misbehaving plugin cannot hang or exhaust the daemon. The defaults are
+//! those of [`ResourceLimits`]; a workspace overrides them per extension in
+//! `morphir.toml`:
+//!
+//! ```toml
+//! [extensions.limits.my-extension]
+//! max_memory_bytes = 67108864
+//! max_time_ms = 5000
+//! max_fuel = 1000000000
+//! ```
+//!
+//! A request exceeding a limit fails with [`DaemonError::LimitExceeded`].
+
+use crate::error::DaemonError;
+use extism::Manifest;
+use morphir_common::config::ExtensionLimits;
+use morphir_ext::LimitExceeded;
+use morphir_extension_sdk::types::ResourceLimits;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Size of a WebAssembly memory page
+const PAGE_SIZE: u64 = 64 * 1024;
+
+/// A resource an extension is limited in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Limit {
+    /// Maximum memory in bytes
+    Memory,
+    /// Maximum fuel per request
+    Fuel,
+    /// Maximum wall-clock time per request in milliseconds
+    Time,
+}
+
+impl Limit {
+    /// Unit the limit is measured in
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Limit::Memory => "bytes",
+            Limit::Fuel => "instructions",
+            Limit::Time => "ms",
+        }
+    }
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Limit::Memory => "memory",
+            Limit::Fuel => "fuel",
+            Limit::Time => "time",
+        })
+    }
+}
+
+/// Resource limits of extensions, by extension ID
+#[derive(Debug, Clone, Default)]
+pub struct LimitSettings {
+    overrides: HashMap<String, ExtensionLimits>,
+}
+
+impl LimitSettings {
+    /// Settings from the `[extensions.limits]` section of the configuration
+    pub fn new(overrides: HashMap<String, ExtensionLimits>) -> Self {
+        Self { overrides }
+    }
+
+    /// Limits of an extension: its overrides on top of the defaults
+    pub fn for_extension(&self, id: &str) -> ResourceLimits {
+        let defaults = ResourceLimits::default();
+        let Some(limits) = self.overrides.get(id) else {
+            return defaults;
+        };
+        ResourceLimits {
+            max_memory_bytes: limits.max_memory_bytes.or(defaults.max_memory_bytes),
+            max_time_ms: limits.max_time_ms.or(defaults.max_time_ms),
+            max_fuel: limits.max_fuel.or(defaults.max_fuel),
+        }
+    }
+}
+
+/// `manifest` with the memory and time limits applied; fuel is set on the
+/// plugin builder
+pub fn apply(mut manifest: Manifest, limits: &ResourceLimits) -> Manifest {
+    if let Some(bytes) = limits.max_memory_bytes {
+        let pages = bytes.div_ceil(PAGE_SIZE).min(u32::MAX as u64) as u32;
+        manifest = manifest.with_memory_max(pages);
+    }
+    if let Some(ms) = limits.max_time_ms {
+        manifest = manifest.with_timeout(Duration::from_millis(ms));
+    }
+    manifest
+}
+
+/// The limit an Extism or component call failed on, if it failed on one
+pub fn violation(error: &extism::Error) -> Option<Limit> {
+    if let Some(exceeded) = error.downcast_ref::<LimitExceeded>() {
+        return Some(match exceeded {
+            LimitExceeded::Memory => Limit::Memory,
+            LimitExceeded::Fuel => Limit::Fuel,
+            LimitExceeded::Time => Limit::Time,
+        });
+    }
+    match error.root_cause().to_string().as_str() {
+        "oom" => Some(Limit::Memory),
+        "plugin ran out of fuel" => Some(Limit::Fuel),
+        "timeout" => Some(Limit::Time),
+        _ => None,
+    }
+}
+
+/// Error for a failed call of extension `id`: a structured
+/// [`DaemonError::LimitExceeded`] when it hit one of its limits
+pub fn call_error(
+    id: &str,
+    limits: &ResourceLimits,
+    context: &str,
+    error: extism::Error,
+) -> DaemonError {
+    let max = |limit| match limit {
+        Limit::Memory => limits.max_memory_bytes,
+        Limit::Fuel => limits.max_fuel,
+        Limit::Time => limits.max_time_ms,
+    };
+    match violation(&error).and_then(|limit| Some((limit, max(limit)?))) {
+        Some((limit, max)) => DaemonError::LimitExceeded {
+            extension: id.to_string(),
+            limit,
+            max,
+        },
+        None => DaemonError::Extension(format!("{}: {}", context, error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_replace_only_the_limits_they_set() {
+        let settings = LimitSettings::new(HashMap::from([(
+            "slow-ext".to_string(),
+            ExtensionLimits {
+                max_time_ms: Some(120_000),
+                ..Default::default()
+            },
+        )]));
+        let limits = settings.for_extension("slow-ext");
+        assert_eq!(limits.max_time_ms, Some(120_000));
+        assert_eq!(limits.max_fuel, ResourceLimits::default().max_fuel);
+        assert_eq!(
+            settings.for_extension("other").max_time_ms,
+            ResourceLimits::default().max_time_ms
+        );
+    }
+
+    #[test]
+    fn test_apply_sets_memory_pages_and_timeout() {
+        let manifest = apply(
+            Manifest::default(),
+            &ResourceLimits {
+                max_memory_bytes: Some(PAGE_SIZE * 10 + 1),
+                max_time_ms: Some(250),
+                max_fuel: None,
+            },
+        );
+        assert_eq!(manifest.memory.max_pages, Some(11));
+        assert_eq!(manifest.timeout_ms, Some(250));
+    }
+
+    #[test]
+    fn test_limit_violations_become_structured_errors() {
+        let limits = ResourceLimits::default();
+        let err = call_error(
+            "my-ext",
+            &limits,
+            "Plugin call failed",
+            extism::Error::msg("timeout"),
+        );
+        assert!(matches!(
+            err,
+            DaemonError::LimitExceeded {
+                limit: Limit::Time,
+                max: 30_000,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Extension my-ext exceeded its time limit of 30000 ms"
+        );
+
+        let err = call_error(
+            "my-ext",
+            &limits,
+            "Plugin call failed",
+            extism::Error::msg("plugin ran out of fuel"),
+        );
+        assert!(matches!(
+            err,
+            DaemonError::LimitExceeded {
+                limit: Limit::Fuel,
+                ..
+            }
+        ));
+
+        let err = call_error(
+            "my-ext",
+            &limits,
+            "Plugin call failed",
+            extism::Error::msg("boom"),
+        );
+        assert_eq!(err.to_string(), "Extension error: Plugin call failed: boom");
+
+        // Components fail with the limit itself
+        let err = call_error(
+            "my-component",
+            &limits,
+            "Component call failed",
+            extism::Error::from(LimitExceeded::Memory),
+        );
+        assert!(matches!(
+            err,
+            DaemonError::LimitExceeded {
+                limit: Limit::Memory,
+                ..
+            }
+        ));
+    }
+}
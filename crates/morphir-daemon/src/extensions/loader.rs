@@ -1,8 +1,12 @@
 //! Extension loader using Extism
 //!
-//! This module handles loading WASM plugins from various sources.
+//! This module handles loading WASM plugins from various sources, and holds
+//! the capabilities the workspace grants them and the limits they run with.
 
 use crate::error::{DaemonError, Result};
+use crate::extensions::install;
+use crate::extensions::limits::LimitSettings;
+use crate::extensions::permissions::PermissionGrants;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
@@ -12,6 +16,10 @@ pub struct ExtensionLoader {
     cache_dir: PathBuf,
     /// Temporary directory for plugin operations
     temp_dir: PathBuf,
+    /// Capabilities granted to the extensions loaded
+    grants: PermissionGrants,
+    /// Resource limits of the extensions loaded
+    limits: LimitSettings,
 }
 
 impl ExtensionLoader {
@@ -29,9 +37,34 @@ impl ExtensionLoader {
         Ok(Self {
             cache_dir,
             temp_dir,
+            grants: PermissionGrants::default(),
+            limits: LimitSettings::default(),
         })
     }
 
+    /// Grant extensions the given capabilities; an extension requesting
+    /// capabilities it was not granted fails to load
+    pub fn with_grants(mut self, grants: PermissionGrants) -> Self {
+        self.grants = grants;
+        self
+    }
+
+    /// Capabilities granted to the extensions loaded
+    pub fn grants(&self) -> &PermissionGrants {
+        &self.grants
+    }
+
+    /// Hold extensions to the given resource limits
+    pub fn with_limits(mut self, limits: LimitSettings) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Resource limits of the extensions loaded
+    pub fn limits(&self) -> &LimitSettings {
+        &self.limits
+    }
+
     /// Create a loader with default cache directory
     pub fn with_default_cache() -> Result<Self> {
         let cache_dir = dirs::cache_dir()
@@ -109,18 +142,7 @@ impl ExtensionLoader {
         tag: Option<&str>,
         asset_name: &str,
     ) -> Result<PathBuf> {
-        let tag = tag.unwrap_or("latest");
-        let url = if tag == "latest" {
-            format!(
-                "https://github.com/{}/releases/latest/download/{}",
-                repo, asset_name
-            )
-        } else {
-            format!(
-                "https://github.com/{}/releases/download/{}/{}",
-                repo, tag, asset_name
-            )
-        };
+        let url = install::github_release_url(repo, tag, asset_name);
 
         self.load_from_url(id, &url).await
     }
@@ -1,16 +1,33 @@
 //! Extension system for morphir-daemon
 //!
-//! This module provides the Extism-based plugin runtime for loading
-//! and executing Morphir extensions.
+//! This module provides the plugin runtimes, Extism and the component
+//! model, for loading and executing Morphir extensions.
 
+pub mod backend;
+pub mod compat;
+pub mod component;
 pub mod container;
+mod execution;
 pub mod host_functions;
+pub mod http;
+pub mod install;
+pub mod limits;
 pub mod loader;
+pub mod permissions;
+pub mod pipeline;
 pub mod protocol;
 pub mod registry;
+pub mod state;
 pub mod virtual_paths;
 
+pub use backend::ExtensionBackend;
+pub use component::ComponentBackend;
 pub use container::ExtensionContainer;
+pub use install::{InstallSource, InstalledExtensions, Installer};
+pub use limits::LimitSettings;
 pub use loader::ExtensionLoader;
+pub use permissions::PermissionGrants;
+pub use pipeline::{PipelineRun, PipelineStep};
 pub use protocol::{ExtensionRequest, ExtensionResponse};
 pub use registry::ExtensionRegistry;
+pub use state::StateStore;
@@ -0,0 +1,222 @@
+//! Capability permissions for extensions
+//!
+//! Extensions declare the filesystem paths, network hosts, and environment
+//! variables they need in the `permissions` of their `ExtensionInfo`. The
+//! workspace grants them per extension in `morphir.toml`:
+//!
+//! ```toml
+//! [extensions.permissions.my-extension]
+//! filesystem = ["/workspace/src", "/output"]
+//! network = ["*.example.com"]
+//! env = ["MY_EXTENSION_TOKEN"]
+//! ```
+//!
+//! An extension requesting anything it was not granted fails to load.
+//! Granted capabilities become its Extism/WASI context: filesystem paths
+//! are mounted at their virtual path (read-only where the sandbox refuses
+//! writes), hosts are allowed for HTTP, and environment variables are
+//! passed as config values.
+
+use crate::error::{DaemonError, Result};
+use crate::extensions::virtual_paths::FileSandbox;
+use extism::Manifest;
+use morphir_common::config::ExtensionPermissions;
+use morphir_ext::commands::host_matches;
+use morphir_extension_sdk::types::Permissions;
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Capabilities granted to extensions, by extension ID
+#[derive(Debug, Clone, Default)]
+pub struct PermissionGrants {
+    grants: HashMap<String, ExtensionPermissions>,
+}
+
+impl PermissionGrants {
+    /// Grants from the `[extensions.permissions]` section of the configuration
+    pub fn new(grants: HashMap<String, ExtensionPermissions>) -> Self {
+        Self { grants }
+    }
+
+    /// Grant capabilities to an extension, replacing any it had
+    pub fn grant(&mut self, id: impl Into<String>, permissions: ExtensionPermissions) {
+        self.grants.insert(id.into(), permissions);
+    }
+
+    /// Capabilities granted to an extension
+    pub fn get(&self, id: &str) -> Option<&ExtensionPermissions> {
+        self.grants.get(id)
+    }
+
+    /// Requested capabilities `id` was not granted, one per line of the
+    /// `morphir.toml` entry that would grant them
+    pub fn missing(&self, id: &str, requested: &Permissions) -> Vec<String> {
+        let granted = self.get(id).cloned().unwrap_or_default();
+        let mut missing = Vec::new();
+        for path in &requested.filesystem {
+            if !granted.filesystem.iter().any(|g| path_within(path, g)) {
+                missing.push(format!("filesystem = [\"{}\"]", path));
+            }
+        }
+        for host in &requested.network {
+            if !granted.network.iter().any(|g| host_matches(host, g)) {
+                missing.push(format!("network = [\"{}\"]", host));
+            }
+        }
+        for var in &requested.env {
+            if !granted.env.contains(var) {
+                missing.push(format!("env = [\"{}\"]", var));
+            }
+        }
+        missing
+    }
+
+    /// Check that `id` was granted everything it requests
+    pub fn authorize(&self, id: &str, requested: &Permissions) -> Result<()> {
+        let missing = self.missing(id, requested);
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(DaemonError::Extension(format!(
+            "Extension {} requests capabilities it was not granted; add them to morphir.toml:\n\n[extensions.permissions.{}]\n{}",
+            id,
+            id,
+            missing.join("\n")
+        )))
+    }
+}
+
+/// `manifest` extended with the capabilities an authorized extension
+/// requests. Filesystem paths are mounted at their virtual path; paths the
+/// sandbox cannot resolve or that do not exist are left out.
+pub fn grant_manifest(
+    mut manifest: Manifest,
+    requested: &Permissions,
+    sandbox: &FileSandbox,
+) -> Manifest {
+    for path in &requested.filesystem {
+        let Some(real) = sandbox.config().resolve(path).filter(|p| p.exists()) else {
+            debug!("Not mounting {}: no such directory", path);
+            continue;
+        };
+        let source = if sandbox.can_write(path) {
+            real.display().to_string()
+        } else {
+            format!("ro:{}", real.display())
+        };
+        manifest = manifest.with_allowed_path(source, path);
+    }
+    for host in &requested.network {
+        manifest = manifest.with_allowed_host(host);
+    }
+    for var in &requested.env {
+        if let Ok(value) = std::env::var(var) {
+            manifest = manifest.with_config_key(var, value);
+        }
+    }
+    manifest
+}
+
+/// Whether a virtual path is `granted` or under it
+fn path_within(path: &str, granted: &str) -> bool {
+    let granted = granted.trim_end_matches('/');
+    if path.split('/').any(|segment| segment == "..") {
+        return false;
+    }
+    path.strip_prefix(granted)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn grants() -> PermissionGrants {
+        let mut grants = PermissionGrants::default();
+        grants.grant(
+            "my-ext",
+            ExtensionPermissions {
+                filesystem: vec!["/workspace/src".to_string()],
+                network: vec!["*.example.com".to_string()],
+                env: vec!["MY_EXT_TOKEN".to_string()],
+            },
+        );
+        grants
+    }
+
+    #[test]
+    fn test_requests_within_grants_are_authorized() {
+        let requested = Permissions {
+            filesystem: vec![
+                "/workspace/src".to_string(),
+                "/workspace/src/orders".to_string(),
+            ],
+            network: vec!["api.example.com".to_string()],
+            env: vec!["MY_EXT_TOKEN".to_string()],
+        };
+        assert!(grants().authorize("my-ext", &requested).is_ok());
+        assert!(grants().authorize("other", &Permissions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_ungranted_requests_are_refused() {
+        let requested = Permissions {
+            filesystem: vec![
+                "/workspace/srcs".to_string(),
+                "/workspace/src/../secrets".to_string(),
+            ],
+            network: vec!["example.com".to_string(), "api.example.org".to_string()],
+            env: vec!["HOME".to_string()],
+        };
+        assert_eq!(
+            grants().missing("my-ext", &requested),
+            vec![
+                "filesystem = [\"/workspace/srcs\"]",
+                "filesystem = [\"/workspace/src/../secrets\"]",
+                "network = [\"example.com\"]",
+                "network = [\"api.example.org\"]",
+                "env = [\"HOME\"]",
+            ]
+        );
+
+        let err = grants()
+            .authorize(
+                "other",
+                &Permissions {
+                    network: vec!["api.example.com".to_string()],
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("[extensions.permissions.other]"));
+    }
+
+    #[test]
+    fn test_grant_manifest_mounts_virtual_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = temp.path().join("out");
+        std::fs::create_dir_all(temp.path().join("src")).unwrap();
+        std::fs::create_dir_all(&output).unwrap();
+        let sandbox = FileSandbox::for_workspace(temp.path(), &output);
+
+        let requested = Permissions {
+            filesystem: vec![
+                "/workspace/src".to_string(),
+                "/output".to_string(),
+                "/workspace/missing".to_string(),
+            ],
+            network: vec!["api.example.com".to_string()],
+            env: Vec::new(),
+        };
+        let manifest = grant_manifest(Manifest::default(), &requested, &sandbox);
+        let paths = manifest.allowed_paths.unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(
+            paths[&format!("ro:{}", temp.path().join("src").display())],
+            Path::new("/workspace/src")
+        );
+        assert_eq!(paths[&output.display().to_string()], Path::new("/output"));
+        assert_eq!(manifest.allowed_hosts.unwrap(), vec!["api.example.com"]);
+    }
+}
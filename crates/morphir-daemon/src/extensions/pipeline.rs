@@ -0,0 +1,330 @@
+//! Ordering extensions into a pipeline
+//!
+//! Extensions declare in their capabilities which other extensions consume
+//! their output (`feeds`), so ecosystem extensions compose without users
+//! wiring them together. The scheduler orders a set of extensions so that
+//! each runs after every extension feeding it. Extensions with no declared
+//! relationship keep the usual stage order (frontends, transforms,
+//! validators, then backends) and otherwise the order they were requested
+//! in. Declarations naming extensions outside the set are ignored.
+//!
+//! Pipelines can also be declared in `morphir.toml` as a fixed sequence of
+//! stages (see [`PipelineSpec`]). Running one, each stage gets the IR the
+//! stages before it produced, and its diagnostics are reported in a
+//! [`StageReport`] of its own.
+
+use crate::error::{DaemonError, Result};
+use crate::extensions::container::ExtensionType;
+#[cfg(doc)]
+use morphir_common::config::PipelineSpec;
+use morphir_common::config::PipelineStageKind;
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::types::{Diagnostic, DiagnosticSeverity, SourceFile};
+use serde::Serialize;
+use serde_json::Value;
+
+/// An extension to schedule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineStep {
+    /// Extension identifier
+    pub id: String,
+    /// Role the extension plays in the pipeline
+    pub ext_type: ExtensionType,
+    /// IDs of extensions that consume this extension's output
+    pub feeds: Vec<String>,
+}
+
+impl PipelineStep {
+    pub fn new(id: impl Into<String>, ext_type: ExtensionType) -> Self {
+        Self {
+            id: id.into(),
+            ext_type,
+            feeds: Vec::new(),
+        }
+    }
+
+    /// Declare extensions that consume this extension's output
+    pub fn with_feeds(mut self, feeds: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.feeds = feeds.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Result of running a pipeline
+#[derive(Debug)]
+pub struct PipelineRun {
+    /// Request as the last transform left it, ready for the backends
+    pub output: Envelope,
+    /// Responses of the validators, by extension ID, in the order they ran
+    pub validations: Vec<(String, Envelope)>,
+    /// Backends to run on `output`, in scheduled order
+    pub backends: Vec<String>,
+}
+
+/// What a declared pipeline starts from: sources for a frontend stage, or
+/// IR when the pipeline has none
+#[derive(Debug, Clone, Default)]
+pub struct PipelineInput {
+    /// Source files, sent to the frontend
+    pub sources: Vec<SourceFile>,
+    /// IR, sent to the first stage after the frontend
+    pub ir: Option<Value>,
+}
+
+/// Outcome of a stage of a declared pipeline
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageReport {
+    /// Extension identifier
+    pub extension: String,
+    /// Role the extension played
+    #[serde(rename = "type")]
+    pub ext_type: ExtensionType,
+    pub success: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Outcome of running a declared pipeline. Stages after one that failed do
+/// not run and have no report.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineReport {
+    /// Pipeline name
+    pub pipeline: String,
+    /// Whether every stage ran and succeeded
+    pub success: bool,
+    pub stages: Vec<StageReport>,
+    /// IR as the last frontend or transform left it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ir: Option<Value>,
+    /// Artifacts of the backends, in the order they ran
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<Value>,
+}
+
+impl PipelineReport {
+    /// Diagnostics of every stage, in the order the stages ran
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.stages.iter().flat_map(|stage| &stage.diagnostics)
+    }
+}
+
+/// Extension type of a declared stage
+pub fn stage_type(kind: PipelineStageKind) -> ExtensionType {
+    match kind {
+        PipelineStageKind::Frontend => ExtensionType::Frontend,
+        PipelineStageKind::Transform => ExtensionType::Transform,
+        PipelineStageKind::Validator => ExtensionType::Validator,
+        PipelineStageKind::Backend => ExtensionType::Backend,
+    }
+}
+
+/// Success and diagnostics of a stage's response.
+///
+/// Responses report success as `success`, or `valid` for validators, and
+/// default to success. An `error` message becomes an error diagnostic, and
+/// diagnostics in other shapes keep their severity, code, and message.
+pub(crate) fn stage_outcome(response: &Value) -> (bool, Vec<Diagnostic>) {
+    let mut success = ["success", "valid"]
+        .iter()
+        .find_map(|key| response.get(key)?.as_bool())
+        .unwrap_or(true);
+    let mut diagnostics: Vec<Diagnostic> = response
+        .get("diagnostics")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|d| {
+            serde_json::from_value(d.clone()).ok().or_else(|| {
+                Some(Diagnostic {
+                    severity: serde_json::from_value(d.get("severity")?.clone()).ok()?,
+                    code: d.get("code").and_then(Value::as_str).map(str::to_string),
+                    message: d.get("message")?.as_str()?.to_string(),
+                    location: None,
+                    related: vec![],
+                    fixes: vec![],
+                })
+            })
+        })
+        .collect();
+    if let Some(error) = response.get("error").and_then(Value::as_str) {
+        success = false;
+        diagnostics.push(Diagnostic {
+            severity: DiagnosticSeverity::Error,
+            code: None,
+            message: error.to_string(),
+            location: None,
+            related: vec![],
+            fixes: vec![],
+        });
+    }
+    (success, diagnostics)
+}
+
+/// Position of a stage when nothing else orders two extensions
+fn stage_rank(ext_type: ExtensionType) -> u8 {
+    match ext_type {
+        ExtensionType::Frontend => 0,
+        ExtensionType::Transform => 1,
+        ExtensionType::Validator => 2,
+        ExtensionType::Backend => 3,
+    }
+}
+
+/// Order `steps` so every extension runs after the extensions feeding it.
+///
+/// Fails with [`DaemonError::PipelineCycle`] when the declarations form a
+/// cycle. Repeated IDs are scheduled once.
+pub fn schedule(steps: Vec<PipelineStep>) -> Result<Vec<PipelineStep>> {
+    let mut unique: Vec<PipelineStep> = Vec::new();
+    for step in steps {
+        if !unique.iter().any(|s| s.id == step.id) {
+            unique.push(step);
+        }
+    }
+    let index_of = |id: &str| unique.iter().position(|s| s.id == id);
+
+    // predecessors[i]: steps whose output feeds step i
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); unique.len()];
+    for (from, step) in unique.iter().enumerate() {
+        for to in step.feeds.iter().filter_map(|id| index_of(id)) {
+            if !predecessors[to].contains(&from) {
+                predecessors[to].push(from);
+            }
+        }
+    }
+
+    let mut scheduled: Vec<usize> = Vec::with_capacity(unique.len());
+    let mut done = vec![false; unique.len()];
+    while scheduled.len() < unique.len() {
+        let next = (0..unique.len())
+            .filter(|&i| !done[i] && predecessors[i].iter().all(|&p| done[p]))
+            .min_by_key(|&i| (stage_rank(unique[i].ext_type), i));
+        match next {
+            Some(i) => {
+                done[i] = true;
+                scheduled.push(i);
+            }
+            None => {
+                let cycle = find_cycle(&predecessors, &done)
+                    .into_iter()
+                    .map(|i| unique[i].id.clone())
+                    .collect();
+                return Err(DaemonError::PipelineCycle(cycle));
+            }
+        }
+    }
+
+    let mut steps: Vec<Option<PipelineStep>> = unique.into_iter().map(Some).collect();
+    Ok(scheduled
+        .into_iter()
+        .filter_map(|i| steps[i].take())
+        .collect())
+}
+
+/// A cycle among the steps not yet scheduled, in feeding order and closed
+/// with its first step, e.g. `a, b, a`
+///
+/// Every unscheduled step has an unscheduled predecessor, so walking
+/// predecessors from any of them must revisit a step.
+fn find_cycle(predecessors: &[Vec<usize>], done: &[bool]) -> Vec<usize> {
+    let Some(start) = (0..done.len()).find(|&i| !done[i]) else {
+        return Vec::new();
+    };
+    let mut path = vec![start];
+    let mut current = start;
+    loop {
+        let Some(&previous) = predecessors[current].iter().find(|&&p| !done[p]) else {
+            return path;
+        };
+        if let Some(position) = path.iter().position(|&i| i == previous) {
+            let mut cycle: Vec<usize> = path[position..].to_vec();
+            cycle.reverse();
+            // Start from the step requested first
+            let first = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap_or(0);
+            cycle.rotate_left(first);
+            cycle.push(cycle[0]);
+            return cycle;
+        }
+        path.push(previous);
+        current = previous;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(steps: &[PipelineStep]) -> Vec<&str> {
+        steps.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_declared_feeds_order_the_pipeline() {
+        let steps = schedule(vec![
+            PipelineStep::new("typescript", ExtensionType::Backend),
+            PipelineStep::new("lint", ExtensionType::Validator),
+            PipelineStep::new("inline-constants", ExtensionType::Transform),
+            // A validator whose findings a transform consumes
+            PipelineStep::new("decision-tables", ExtensionType::Validator)
+                .with_feeds(["inline-constants"]),
+            PipelineStep::new("prepare-ts", ExtensionType::Transform)
+                .with_feeds(["typescript", "not-in-pipeline"]),
+        ])
+        .unwrap();
+        assert_eq!(
+            ids(&steps),
+            vec![
+                "prepare-ts",
+                "lint",
+                "decision-tables",
+                "inline-constants",
+                "typescript"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cycles_are_reported_in_feeding_order() {
+        let err = schedule(vec![
+            PipelineStep::new("sql", ExtensionType::Backend),
+            PipelineStep::new("a", ExtensionType::Transform).with_feeds(["b"]),
+            PipelineStep::new("b", ExtensionType::Transform).with_feeds(["c"]),
+            PipelineStep::new("c", ExtensionType::Transform).with_feeds(["a", "sql"]),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Extension pipeline has a cycle: a -> b -> c -> a"
+        );
+
+        let err = schedule(vec![
+            PipelineStep::new("self", ExtensionType::Transform).with_feeds(["self"]),
+        ])
+        .unwrap_err();
+        assert!(matches!(err, DaemonError::PipelineCycle(cycle) if cycle == ["self", "self"]));
+    }
+
+    #[test]
+    fn test_stage_outcomes_of_different_response_shapes() {
+        let (success, diagnostics) = stage_outcome(&serde_json::json!({
+            "valid": false,
+            "diagnostics": [
+                {"severity": "error", "code": "E1", "message": "Unused type"},
+                {"severity": "warning", "code": "W1", "message": "Deep nesting",
+                 "definition": "a:b#c", "location": {"startLine": 3}},
+                {"message": "no severity"},
+            ],
+        }));
+        assert!(!success);
+        let codes: Vec<_> = diagnostics.iter().map(|d| d.code.as_deref()).collect();
+        assert_eq!(codes, vec![Some("E1"), Some("W1")]);
+
+        let (success, diagnostics) =
+            stage_outcome(&serde_json::json!({"success": true, "error": "Invalid IR"}));
+        assert!(!success);
+        assert_eq!(diagnostics[0].message, "Invalid IR");
+
+        assert!(stage_outcome(&serde_json::json!({"ir": {}})).0);
+    }
+}
@@ -15,6 +15,8 @@ pub mod methods {
     pub const CAPABILITIES: &str = "morphir.extension.capabilities";
     /// Frontend: compile source to IR
     pub const COMPILE: &str = "morphir.frontend.compile";
+    /// Frontend: compile only the changed sources of an incremental build
+    pub const COMPILE_INCREMENTAL: &str = "morphir.frontend.compileIncremental";
     /// Backend: generate code from IR
     pub const GENERATE: &str = "morphir.backend.generate";
     /// Validator: validate IR
@@ -123,6 +125,12 @@ pub mod error_codes {
     pub const VALIDATION_ERROR: i32 = -32003;
     /// Transformation error
     pub const TRANSFORMATION_ERROR: i32 = -32004;
+    /// Server busy - too many requests in flight, retry later
+    pub const SERVER_BUSY: i32 = -32005;
+    /// Shutting down - the daemon no longer accepts requests
+    pub const SHUTTING_DOWN: i32 = -32006;
+    /// Limit exceeded - an extension hit its memory, fuel, or time limit
+    pub const LIMIT_EXCEEDED: i32 = -32007;
 }
 
 impl RpcError {
@@ -143,4 +151,95 @@ impl RpcError {
             data: None,
         }
     }
+
+    /// Create a server busy error with a retry-after hint
+    pub fn server_busy(retry_after_ms: u64) -> Self {
+        Self {
+            code: error_codes::SERVER_BUSY,
+            message: format!("Server busy, retry after {}ms", retry_after_ms),
+            data: Some(serde_json::json!({ "retryAfterMs": retry_after_ms })),
+        }
+    }
+
+    /// Get the retry-after hint of a server busy error
+    pub fn retry_after_ms(&self) -> Option<u64> {
+        if self.code != error_codes::SERVER_BUSY {
+            return None;
+        }
+        self.data.as_ref()?.get("retryAfterMs")?.as_u64()
+    }
+
+    /// Create an error from a DaemonError
+    pub fn from_daemon_error(err: &crate::DaemonError) -> Self {
+        use crate::DaemonError;
+
+        match err {
+            DaemonError::ServerBusy { retry_after_ms } => Self::server_busy(*retry_after_ms),
+            DaemonError::LimitExceeded {
+                extension,
+                limit,
+                max,
+            } => Self {
+                code: error_codes::LIMIT_EXCEEDED,
+                message: err.to_string(),
+                data: Some(serde_json::json!({
+                    "extension": extension,
+                    "limit": limit,
+                    "max": max,
+                })),
+            },
+            DaemonError::Extension(msg) => Self {
+                code: error_codes::EXTENSION_ERROR,
+                message: msg.clone(),
+                data: None,
+            },
+            DaemonError::Build(msg) => Self {
+                code: error_codes::COMPILATION_ERROR,
+                message: msg.clone(),
+                data: None,
+            },
+            DaemonError::Json(e) => Self {
+                code: error_codes::PARSE_ERROR,
+                message: e.to_string(),
+                data: None,
+            },
+            other => Self::internal_error(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_busy_carries_retry_after() {
+        let err = RpcError::from_daemon_error(&crate::DaemonError::ServerBusy {
+            retry_after_ms: 750,
+        });
+        assert_eq!(err.code, error_codes::SERVER_BUSY);
+        assert_eq!(err.retry_after_ms(), Some(750));
+
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["data"]["retryAfterMs"], 750);
+    }
+
+    #[test]
+    fn test_limit_exceeded_carries_the_limit() {
+        let err = RpcError::from_daemon_error(&crate::DaemonError::LimitExceeded {
+            extension: "my-ext".to_string(),
+            limit: crate::extensions::limits::Limit::Memory,
+            max: 1024,
+        });
+        assert_eq!(err.code, error_codes::LIMIT_EXCEEDED);
+        assert_eq!(
+            err.data,
+            Some(serde_json::json!({"extension": "my-ext", "limit": "memory", "max": 1024}))
+        );
+    }
+
+    #[test]
+    fn test_retry_after_only_for_server_busy() {
+        assert_eq!(RpcError::internal_error("boom").retry_after_ms(), None);
+    }
 }
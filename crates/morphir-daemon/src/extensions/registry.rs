@@ -2,15 +2,35 @@
 //!
 //! This module provides discovery and lifecycle management for extensions.
 
+use crate::concurrency::{ConcurrencyConfig, ConcurrencyLimiter};
 use crate::error::{DaemonError, Result};
-use crate::extensions::container::{ExtensionContainer, ExtensionInfo, ExtensionType};
-use crate::extensions::host_functions::MorphirHostFunctions;
+use crate::extensions::backend::{self, ExtensionBackend, LoadContext};
+use crate::extensions::container::{ExtensionInfo, ExtensionType};
+use crate::extensions::execution;
+use crate::extensions::host_functions::{MorphirHostFunctions, MorphirHostState};
+use crate::extensions::http::HttpPolicy;
+use crate::extensions::install::InstalledExtensions;
+use crate::extensions::limits::LimitSettings;
 use crate::extensions::loader::ExtensionLoader;
+use crate::extensions::permissions::PermissionGrants;
+use crate::extensions::pipeline::{
+    self, PipelineInput, PipelineReport, PipelineRun, PipelineStep, StageReport,
+};
+use crate::extensions::protocol::methods;
+use morphir_builtins::BuiltinExtension;
+use morphir_builtins::registry::BuiltinRegistry;
+use morphir_common::config::{
+    DaemonSection, ExtensionRuntime, ExtensionsSection, PipelineSpec, PipelineStageKind,
+};
+use morphir_common::remote::RemoteSourceConfig;
+use morphir_ext_core::Envelope;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{debug, info};
 
 /// Configuration for an extension in the registry
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -26,6 +46,9 @@ pub struct ExtensionConfig {
     /// Extension-specific configuration
     #[serde(default)]
     pub config: HashMap<String, serde_json::Value>,
+    /// Runtime hosting the extension; detected from its WASM if not set
+    #[serde(default)]
+    pub runtime: Option<ExtensionRuntime>,
 }
 
 fn default_true() -> bool {
@@ -62,13 +85,23 @@ pub struct ExtensionRegistry {
     /// Extension loader
     loader: ExtensionLoader,
     /// Loaded extensions by ID
-    extensions: RwLock<HashMap<String, Arc<ExtensionContainer>>>,
+    extensions: RwLock<HashMap<String, Arc<dyn ExtensionBackend>>>,
     /// Extension configurations
     configs: RwLock<HashMap<String, ExtensionConfig>>,
+    /// Runtimes of extensions whose configuration sets none, by extension ID
+    runtimes: HashMap<String, ExtensionRuntime>,
+    /// Remote source configuration the HTTP requests of extensions are held to
+    sources: RemoteSourceConfig,
     /// Workspace root for host functions
     workspace_root: PathBuf,
     /// Output directory for host functions
     output_dir: PathBuf,
+    /// Limits on concurrent extension work
+    limiter: Arc<ConcurrencyLimiter>,
+    /// Builtins that can run in-process
+    builtins: BuiltinRegistry,
+    /// Whether builtin transforms and validators run natively instead of as WASM
+    prefer_native: bool,
 }
 
 impl ExtensionRegistry {
@@ -83,8 +116,13 @@ impl ExtensionRegistry {
             loader: ExtensionLoader::new(cache_dir)?,
             extensions: RwLock::new(HashMap::new()),
             configs: RwLock::new(HashMap::new()),
+            runtimes: HashMap::new(),
+            sources: RemoteSourceConfig::default(),
             workspace_root,
             output_dir,
+            limiter: Arc::new(ConcurrencyLimiter::default()),
+            builtins: BuiltinRegistry::new(),
+            prefer_native: true,
         })
     }
 
@@ -98,11 +136,75 @@ impl ExtensionRegistry {
             loader,
             extensions: RwLock::new(HashMap::new()),
             configs: RwLock::new(HashMap::new()),
+            runtimes: HashMap::new(),
+            sources: RemoteSourceConfig::default(),
             workspace_root,
             output_dir,
+            limiter: Arc::new(ConcurrencyLimiter::default()),
+            builtins: BuiltinRegistry::new(),
+            prefer_native: true,
         }
     }
 
+    /// Use the given concurrency limits for extension work
+    pub fn with_concurrency(mut self, config: ConcurrencyConfig) -> Self {
+        self.limiter = Arc::new(ConcurrencyLimiter::new(config));
+        self
+    }
+
+    /// Run builtin transforms and validators natively (the default), or
+    /// always load them as WASM extensions
+    pub fn with_native_builtins(mut self, enabled: bool) -> Self {
+        self.prefer_native = enabled;
+        self
+    }
+
+    /// Apply the `[daemon]` section of the configuration
+    pub fn with_daemon_config(self, section: &DaemonSection) -> Self {
+        self.with_concurrency(ConcurrencyConfig::from(section))
+            .with_native_builtins(section.prefer_native_builtins.unwrap_or(true))
+    }
+
+    /// Grant extensions the capabilities in the `[extensions.permissions]`
+    /// section of the configuration
+    pub fn with_permissions(mut self, grants: PermissionGrants) -> Self {
+        self.loader = self.loader.with_grants(grants);
+        self
+    }
+
+    /// Hold extensions to the resource limits in the `[extensions.limits]`
+    /// section of the configuration
+    pub fn with_limits(mut self, limits: LimitSettings) -> Self {
+        self.loader = self.loader.with_limits(limits);
+        self
+    }
+
+    /// Host extensions with the runtimes in the `[extensions.runtime]`
+    /// section of the configuration, unless their own configuration sets one
+    pub fn with_runtimes(mut self, runtimes: HashMap<String, ExtensionRuntime>) -> Self {
+        self.runtimes = runtimes;
+        self
+    }
+
+    /// Hold the HTTP requests of extensions to the `[sources]` section of the
+    /// configuration
+    pub fn with_sources(mut self, sources: RemoteSourceConfig) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Apply the `[extensions]` section of the configuration
+    pub fn with_extensions_config(self, section: &ExtensionsSection) -> Self {
+        self.with_permissions(PermissionGrants::new(section.permissions.clone()))
+            .with_limits(LimitSettings::new(section.limits.clone()))
+            .with_runtimes(section.runtime.clone())
+    }
+
+    /// Get the concurrency limiter shared by all extension work
+    pub fn limiter(&self) -> &Arc<ConcurrencyLimiter> {
+        &self.limiter
+    }
+
     /// Register an extension configuration
     pub async fn register(&self, config: ExtensionConfig) -> Result<()> {
         let mut configs = self.configs.write().await;
@@ -112,7 +214,7 @@ impl ExtensionRegistry {
     }
 
     /// Load an extension by ID
-    pub async fn load(&self, id: &str) -> Result<Arc<ExtensionContainer>> {
+    pub async fn load(&self, id: &str) -> Result<Arc<dyn ExtensionBackend>> {
         // Check if already loaded
         {
             let extensions = self.extensions.read().await;
@@ -148,14 +250,36 @@ impl ExtensionRegistry {
         };
 
         // Create host functions
-        let host_funcs = MorphirHostFunctions::for_workspace(
-            self.workspace_root.clone(),
-            self.output_dir.clone(),
+        let network = self
+            .loader
+            .grants()
+            .get(id)
+            .map(|granted| granted.network.clone())
+            .unwrap_or_default();
+        let host_funcs = MorphirHostFunctions::new(
+            MorphirHostState::for_workspace(self.workspace_root.clone(), self.output_dir.clone())
+                .with_extension(id)
+                .with_http_policy(HttpPolicy::new(network).with_sources(self.sources.clone())),
         );
 
-        // Create container
-        let container = ExtensionContainer::new(id, &wasm_path, host_funcs)?;
-        let container = Arc::new(container);
+        // Load with the configured or detected runtime; instantiation counts
+        // against the concurrency limits
+        info!("Loading extension '{}' from {:?}", id, wasm_path);
+        let wasm_bytes = std::fs::read(&wasm_path)?;
+        let runtime = config.runtime.or_else(|| self.runtimes.get(id).copied());
+        let container = {
+            let _permit = self.limiter.acquire(Some(id)).await?;
+            backend::load(
+                id,
+                &wasm_bytes,
+                runtime,
+                LoadContext {
+                    host_functions: host_funcs,
+                    grants: self.loader.grants(),
+                    limits: &self.loader.limits().for_extension(id),
+                },
+            )?
+        };
 
         // Store in registry
         {
@@ -172,8 +296,220 @@ impl ExtensionRegistry {
         Ok(container)
     }
 
+    /// Call a method on an extension, loading it if necessary.
+    ///
+    /// The call holds a permit from the registry's [`ConcurrencyLimiter`] and
+    /// fails with [`DaemonError::ServerBusy`] when too much work is queued.
+    pub async fn call<I: Serialize, O: DeserializeOwned>(
+        &self,
+        id: &str,
+        method: &str,
+        params: I,
+    ) -> Result<O> {
+        let container = self.load(id).await?;
+        let _permit = self.limiter.acquire(Some(id)).await?;
+        container.call(method, params).await
+    }
+
+    /// Run a transform or validator on an envelope.
+    ///
+    /// When a builtin implements `id` natively it runs in-process, unless
+    /// native builtins are turned off with
+    /// [`with_native_builtins`](Self::with_native_builtins); otherwise the
+    /// extension is loaded and called with the envelope's JSON content. Both
+    /// paths reject non-JSON requests the same way, hold a concurrency
+    /// permit, and answer with a JSON envelope carrying the request's header.
+    pub async fn execute(
+        &self,
+        id: &str,
+        ext_type: ExtensionType,
+        input: &Envelope,
+    ) -> Result<Envelope> {
+        let method = execution::method(ext_type)?;
+        if let Some(builtin) = self.native_builtin(id, ext_type) {
+            let _permit = self.limiter.acquire(Some(id)).await?;
+            debug!("Running builtin {} natively", id);
+            return execution::run_native(builtin, input);
+        }
+        execution::rpc_params(input)?;
+        let container = self.load(id).await?;
+        let _permit = self.limiter.acquire(Some(id)).await?;
+        container.invoke(method, input).await
+    }
+
+    /// Builtin implementing the given extension type, if native execution is
+    /// enabled
+    fn native_builtin(&self, id: &str, ext_type: ExtensionType) -> Option<&dyn BuiltinExtension> {
+        if !self.prefer_native {
+            return None;
+        }
+        self.builtins
+            .get(id)
+            .filter(|builtin| execution::builtin_type(builtin.info().extension_type) == ext_type)
+    }
+
+    /// Order extensions by the `feeds` they declare in their capabilities.
+    ///
+    /// Extensions are loaded to read their capabilities, except builtins that
+    /// run natively, which feed nothing. An extension with several types
+    /// takes the role of its first type other than frontend.
+    pub async fn schedule(&self, ids: &[String]) -> Result<Vec<PipelineStep>> {
+        let mut steps = Vec::with_capacity(ids.len());
+        for id in ids {
+            let builtin = self.builtins.get(id).filter(|_| self.prefer_native);
+            let step = if let Some(builtin) = builtin {
+                PipelineStep::new(
+                    id.as_str(),
+                    execution::builtin_type(builtin.info().extension_type),
+                )
+            } else {
+                let container = self.load(id).await?;
+                let types = &container.info().types;
+                let ext_type = types
+                    .iter()
+                    .find(|t| **t != ExtensionType::Frontend)
+                    .or(types.first())
+                    .copied()
+                    .ok_or_else(|| {
+                        DaemonError::Extension(format!("Extension declares no types: {}", id))
+                    })?;
+                PipelineStep::new(id.as_str(), ext_type)
+                    .with_feeds(container.capabilities().feeds.iter().cloned())
+            };
+            steps.push(step);
+        }
+        pipeline::schedule(steps)
+    }
+
+    /// Run the transforms and validators among `ids` in scheduled order.
+    ///
+    /// Each transform's response `ir` replaces the `ir` of the request the
+    /// following steps receive. Validators see the request as it stands when
+    /// they run. Backends are not run; they are returned in order for the
+    /// caller to generate from the final request.
+    pub async fn run_pipeline(&self, ids: &[String], input: &Envelope) -> Result<PipelineRun> {
+        let mut request = input.clone();
+        let mut validations = Vec::new();
+        let mut backends = Vec::new();
+        for step in self.schedule(ids).await? {
+            match step.ext_type {
+                ExtensionType::Transform => {
+                    let output = self.execute(&step.id, step.ext_type, &request).await?;
+                    let response: serde_json::Value = execution::rpc_params(&output)?;
+                    if let Some(ir) = response.get("ir") {
+                        let mut params = execution::rpc_params(&request)?;
+                        params["ir"] = ir.clone();
+                        request = Envelope::json(&params)?.with_header(request.header.clone());
+                    }
+                }
+                ExtensionType::Validator => {
+                    let output = self.execute(&step.id, step.ext_type, &request).await?;
+                    validations.push((step.id, output));
+                }
+                ExtensionType::Backend => backends.push(step.id),
+                ExtensionType::Frontend => {
+                    return Err(DaemonError::Extension(format!(
+                        "Frontend extensions do not run in a pipeline: {}",
+                        step.id
+                    )));
+                }
+            }
+        }
+        Ok(PipelineRun {
+            output: request,
+            validations,
+            backends,
+        })
+    }
+
+    /// Run a pipeline declared in `morphir.toml`, stage by stage.
+    ///
+    /// A frontend compiles `input.sources`; every other stage receives the
+    /// IR as the stages before it left it, starting from `input.ir`, along
+    /// with its options. A frontend or transform answering with `ir`
+    /// replaces the IR, and backend `artifacts` are collected. Each stage's
+    /// diagnostics are reported separately, and the run stops after the
+    /// first stage that fails.
+    pub async fn run_declared(
+        &self,
+        name: &str,
+        spec: &PipelineSpec,
+        input: PipelineInput,
+    ) -> Result<PipelineReport> {
+        let mut report = PipelineReport {
+            pipeline: name.to_string(),
+            success: true,
+            stages: Vec::with_capacity(spec.stages.len()),
+            ir: input.ir,
+            artifacts: Vec::new(),
+        };
+        for stage in &spec.stages {
+            let ext_type = pipeline::stage_type(stage.kind);
+            let params = if stage.kind == PipelineStageKind::Frontend {
+                serde_json::json!({"sources": input.sources, "options": stage.options})
+            } else {
+                let ir = report.ir.as_ref().ok_or_else(|| {
+                    DaemonError::Extension(format!(
+                        "Pipeline {}: {} needs IR, but no stage before it produced any",
+                        name, stage.extension
+                    ))
+                })?;
+                serde_json::json!({"ir": ir, "options": stage.options})
+            };
+            let response = self.run_stage(&stage.extension, ext_type, params).await?;
+
+            let (success, diagnostics) = pipeline::stage_outcome(&response);
+            match stage.kind {
+                PipelineStageKind::Frontend | PipelineStageKind::Transform => {
+                    if let Some(ir) = response.get("ir").filter(|ir| !ir.is_null()) {
+                        report.ir = Some(ir.clone());
+                    }
+                }
+                PipelineStageKind::Backend => {
+                    if let Some(artifacts) = response.get("artifacts").and_then(|a| a.as_array()) {
+                        report.artifacts.extend(artifacts.iter().cloned());
+                    }
+                }
+                PipelineStageKind::Validator => {}
+            }
+            report.stages.push(StageReport {
+                extension: stage.extension.clone(),
+                ext_type,
+                success,
+                diagnostics,
+            });
+            if !success {
+                report.success = false;
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Run one stage of a declared pipeline: natively when a builtin
+    /// implements it, otherwise by calling the extension
+    async fn run_stage(
+        &self,
+        id: &str,
+        ext_type: ExtensionType,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        if let Some(builtin) = self.native_builtin(id, ext_type) {
+            let _permit = self.limiter.acquire(Some(id)).await?;
+            debug!("Running pipeline stage {} natively", id);
+            let output = execution::run_native(builtin, &Envelope::json(&params)?)?;
+            return execution::rpc_params(&output);
+        }
+        let method = match ext_type {
+            ExtensionType::Frontend => methods::COMPILE,
+            ExtensionType::Backend => methods::GENERATE,
+            other => execution::method(other)?,
+        };
+        self.call(id, method, params).await
+    }
+
     /// Load extension from a path (convenience method)
-    pub async fn load_from_path(&self, id: &str, path: &Path) -> Result<Arc<ExtensionContainer>> {
+    pub async fn load_from_path(&self, id: &str, path: &Path) -> Result<Arc<dyn ExtensionBackend>> {
         self.register(ExtensionConfig {
             id: id.to_string(),
             source: ExtensionSource::Path {
@@ -181,6 +517,7 @@ impl ExtensionRegistry {
             },
             enabled: true,
             config: HashMap::new(),
+            runtime: None,
         })
         .await?;
 
@@ -202,7 +539,7 @@ impl ExtensionRegistry {
     }
 
     /// Get a loaded extension
-    pub async fn get(&self, id: &str) -> Option<Arc<ExtensionContainer>> {
+    pub async fn get(&self, id: &str) -> Option<Arc<dyn ExtensionBackend>> {
         let extensions = self.extensions.read().await;
         extensions.get(id).cloned()
     }
@@ -214,7 +551,7 @@ impl ExtensionRegistry {
     }
 
     /// List extensions by type
-    pub async fn list_by_type(&self, ext_type: ExtensionType) -> Vec<Arc<ExtensionContainer>> {
+    pub async fn list_by_type(&self, ext_type: ExtensionType) -> Vec<Arc<dyn ExtensionBackend>> {
         let extensions = self.extensions.read().await;
         extensions
             .values()
@@ -224,7 +561,7 @@ impl ExtensionRegistry {
     }
 
     /// Find an extension supporting a specific type
-    pub async fn find_by_type(&self, ext_type: ExtensionType) -> Option<Arc<ExtensionContainer>> {
+    pub async fn find_by_type(&self, ext_type: ExtensionType) -> Option<Arc<dyn ExtensionBackend>> {
         let extensions = self.extensions.read().await;
         extensions.values().find(|e| e.supports(ext_type)).cloned()
     }
@@ -234,6 +571,11 @@ impl ExtensionRegistry {
         &self.loader
     }
 
+    /// Get the builtins the registry can run natively
+    pub fn builtins(&self) -> &BuiltinRegistry {
+        &self.builtins
+    }
+
     /// Discover extensions from configuration
     pub async fn discover_from_config(
         &self,
@@ -247,8 +589,31 @@ impl ExtensionRegistry {
         Ok(())
     }
 
+    /// Register the extensions installed with `morphir extension install`,
+    /// except those whose ID is already registered
+    pub async fn register_installed(&self, installed: &InstalledExtensions) -> Result<()> {
+        for extension in installed.iter() {
+            let Some(path) = &extension.install_path else {
+                continue;
+            };
+            if self.configs.read().await.contains_key(&extension.name) {
+                debug!("Installed extension {} is shadowed", extension.name);
+                continue;
+            }
+            self.register(ExtensionConfig {
+                id: extension.name.clone(),
+                source: ExtensionSource::Path { path: path.clone() },
+                enabled: true,
+                config: HashMap::new(),
+                runtime: None,
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
     /// Load all registered extensions
-    pub async fn load_all(&self) -> Vec<Result<Arc<ExtensionContainer>>> {
+    pub async fn load_all(&self) -> Vec<Result<Arc<dyn ExtensionBackend>>> {
         let ids: Vec<String> = {
             let configs = self.configs.read().await;
             configs.keys().cloned().collect()
@@ -268,6 +633,7 @@ impl ExtensionRegistry {
             source: ExtensionSource::Path { path },
             enabled: true,
             config: HashMap::new(),
+            runtime: None,
         })
         .await
     }
@@ -276,7 +642,7 @@ impl ExtensionRegistry {
     pub async fn find_extension_by_language(
         &self,
         language: &str,
-    ) -> Option<Arc<ExtensionContainer>> {
+    ) -> Option<Arc<dyn ExtensionBackend>> {
         // First check builtin extensions (by ID matching language)
         if let Ok(ext) = self.load(language).await
             && ext.supports(ExtensionType::Frontend)
@@ -299,7 +665,10 @@ impl ExtensionRegistry {
     }
 
     /// Find a backend extension by target language name
-    pub async fn find_extension_by_target(&self, target: &str) -> Option<Arc<ExtensionContainer>> {
+    pub async fn find_extension_by_target(
+        &self,
+        target: &str,
+    ) -> Option<Arc<dyn ExtensionBackend>> {
         // First check builtin extensions (by ID matching target)
         if let Ok(ext) = self.load(target).await
             && ext.supports(ExtensionType::Backend)
@@ -325,6 +694,7 @@ impl ExtensionRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::extensions::install::InstalledExtension;
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -336,4 +706,223 @@ mod tests {
         let list = registry.list().await;
         assert!(list.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_installed_extensions_do_not_shadow_registered_ones() {
+        let temp = tempdir().unwrap();
+        let registry =
+            ExtensionRegistry::new(temp.path().to_path_buf(), temp.path().join("output")).unwrap();
+        registry
+            .register_builtin("gleam", temp.path().join("gleam.wasm"))
+            .await
+            .unwrap();
+
+        let installed = |id: &str, path: Option<PathBuf>| InstalledExtension {
+            name: id.to_string(),
+            version: Some("1.0.0".into()),
+            description: None,
+            install_path: path,
+            source: None,
+            sha256: None,
+            types: vec![],
+        };
+        let mut extensions = InstalledExtensions::default();
+        extensions.insert(installed("gleam", Some(temp.path().join("other.wasm"))));
+        extensions.insert(installed("morphir-ts", Some(temp.path().join("ts.wasm"))));
+        extensions.insert(installed("unfetched", None));
+        registry.register_installed(&extensions).await.unwrap();
+
+        let configs = registry.configs.read().await;
+        let path = |id: &str| match &configs.get(id)?.source {
+            ExtensionSource::Path { path } => Some(path.clone()),
+            _ => None,
+        };
+        assert_eq!(path("gleam"), Some(temp.path().join("gleam.wasm")));
+        assert_eq!(path("morphir-ts"), Some(temp.path().join("ts.wasm")));
+        assert!(!configs.contains_key("unfetched"));
+    }
+
+    #[tokio::test]
+    async fn test_builtin_transforms_run_natively_unless_disabled() {
+        let temp = tempdir().unwrap();
+        let registry =
+            ExtensionRegistry::new(temp.path().to_path_buf(), temp.path().join("output")).unwrap();
+        let input = Envelope::json(&serde_json::json!({
+            "ir": {
+                "formatVersion": 3,
+                "distribution": ["Library", [["test"]], [], {"modules": []}]
+            },
+            "target_version": "v4",
+        }))
+        .unwrap();
+
+        let output = registry
+            .execute("migrate", ExtensionType::Transform, &input)
+            .await
+            .unwrap();
+        let response: serde_json::Value = output.as_json().unwrap();
+        assert_eq!(response["success"], true);
+        assert_eq!(response["target_format"], "v4");
+        // Nothing was loaded as WASM
+        assert!(registry.list().await.is_empty());
+
+        // Migrate is a transform, not a validator
+        assert!(
+            registry
+                .execute("migrate", ExtensionType::Validator, &input)
+                .await
+                .is_err()
+        );
+
+        // Scheduled natively too; the JSON Schema backend is left to the caller
+        let run = registry
+            .run_pipeline(&["json-schema".to_string(), "migrate".to_string()], &input)
+            .await
+            .unwrap();
+        assert_eq!(run.backends, vec!["json-schema"]);
+        let request: serde_json::Value = run.output.as_json().unwrap();
+        assert_eq!(request["target_version"], "v4");
+        assert_ne!(
+            request["ir"],
+            input.as_json::<serde_json::Value>().unwrap()["ir"]
+        );
+
+        let registry = registry.with_daemon_config(&DaemonSection {
+            prefer_native_builtins: Some(false),
+            ..Default::default()
+        });
+        let err = registry
+            .execute("migrate", ExtensionType::Transform, &input)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Extension not registered: migrate"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_components_are_hosted_like_plugins() {
+        use morphir_ext_core::envelope::Header;
+
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("check.wasm");
+        std::fs::write(
+            &path,
+            include_str!("../../../integration-tests/fixtures/extensions/validator.wat"),
+        )
+        .unwrap();
+        let registry =
+            ExtensionRegistry::new(temp.path().to_path_buf(), temp.path().join("output")).unwrap();
+
+        // Detected from the WASM
+        let extension = registry.load_from_path("check", &path).await.unwrap();
+        assert_eq!(extension.runtime(), ExtensionRuntime::Component);
+        assert_eq!(extension.info().types, vec![ExtensionType::Validator]);
+        assert!(extension.info().version.starts_with("sha256:"));
+
+        let result: serde_json::Value = registry
+            .call(
+                "check",
+                methods::VALIDATE,
+                serde_json::json!({"ir": {"modules": []}}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result["valid"], true);
+
+        let input = Envelope::json(&serde_json::json!({"ir": {}}))
+            .unwrap()
+            .with_header(Header {
+                seqnum: 3,
+                session_id: "session-1".to_string(),
+                kind: None,
+            });
+        let output = registry
+            .execute("check", ExtensionType::Validator, &input)
+            .await
+            .unwrap();
+        assert_eq!(output.header, input.header);
+        assert_eq!(
+            output.as_json::<serde_json::Value>().unwrap()["valid"],
+            false
+        );
+
+        let err = registry
+            .call::<_, serde_json::Value>(
+                "check",
+                methods::VALIDATE,
+                serde_json::json!({"ir": {}, "options": {"strict": true}}),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("boom"), "{}", err);
+        let err = registry
+            .call::<_, serde_json::Value>("check", methods::GENERATE, serde_json::json!({"ir": {}}))
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not support capability: backend"),
+            "{}",
+            err
+        );
+
+        // The configured runtime wins over detection
+        let registry = registry.with_runtimes(HashMap::from([(
+            "forced".to_string(),
+            ExtensionRuntime::Extism,
+        )]));
+        assert!(registry.load_from_path("forced", &path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_declared_pipeline_chains_stages() {
+        use morphir_common::config::PipelineStage;
+
+        let temp = tempdir().unwrap();
+        let registry =
+            ExtensionRegistry::new(temp.path().to_path_buf(), temp.path().join("output")).unwrap();
+        let stage = |kind, extension: &str| PipelineStage {
+            kind,
+            extension: extension.to_string(),
+            options: HashMap::new(),
+        };
+        let spec = PipelineSpec {
+            description: None,
+            stages: vec![
+                stage(PipelineStageKind::Validator, "lint"),
+                stage(PipelineStageKind::Backend, "json-schema"),
+            ],
+        };
+        let ir = serde_json::json!({
+            "formatVersion": 3,
+            "distribution": ["Library", [["test"]], [], {"modules": []}]
+        });
+
+        let report = registry
+            .run_declared(
+                "schemas",
+                &spec,
+                PipelineInput {
+                    sources: Vec::new(),
+                    ir: Some(ir.clone()),
+                },
+            )
+            .await
+            .unwrap();
+        assert!(report.success, "{:?}", report);
+        assert_eq!(report.stages.len(), 2);
+        assert_eq!(report.stages[1].ext_type, ExtensionType::Backend);
+        assert_eq!(report.ir, Some(ir));
+
+        // Nothing before the validator produced IR
+        let err = registry
+            .run_declared("schemas", &spec, PipelineInput::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("lint needs IR"), "{}", err);
+    }
 }
@@ -0,0 +1,127 @@
+//! Persistent state of extensions
+//!
+//! Extensions keep data across daemon restarts, such as the results of
+//! incremental analysis, in a key-value store the host persists for them
+//! under `.morphir/state/<extension-id>`. Each entry is a JSON file named by
+//! a digest of its key, so extensions choose keys freely and never see each
+//! other's entries.
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+
+/// An entry as stored on disk
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    value: serde_json::Value,
+}
+
+/// Key-value store of one extension
+#[derive(Debug, Clone)]
+pub struct StateStore {
+    dir: PathBuf,
+}
+
+impl StateStore {
+    /// Store kept in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store of `extension` in the workspace whose `.morphir` directory is
+    /// `morphir_dir`, if the ID can name a directory
+    pub fn in_morphir_dir(morphir_dir: &Path, extension: &str) -> Option<Self> {
+        let mut components = Path::new(extension).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => {
+                Some(Self::new(morphir_dir.join("state").join(extension)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Directory the store is kept in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        let digest: String = Sha256::digest(key.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        self.dir.join(format!("{}.json", digest))
+    }
+
+    /// The value stored under `key`
+    pub fn get(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        let content = match std::fs::read(self.entry_path(key)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let entry: Entry = serde_json::from_slice(&content)?;
+        Ok(Some(entry.value))
+    }
+
+    /// Store `value` under `key`, replacing any value stored before
+    pub fn set(&self, key: &str, value: serde_json::Value) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.entry_path(key);
+        let entry = Entry {
+            key: key.to_string(),
+            value,
+        };
+        // Write then rename, so a crash never leaves half an entry
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec(&entry)?)?;
+        std::fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    /// Remove the value stored under `key`, returning whether there was one
+    pub fn delete(&self, key: &str) -> Result<bool> {
+        match std::fs::remove_file(self.entry_path(key)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_state_persists_per_extension() {
+        let temp = tempfile::tempdir().unwrap();
+        let morphir_dir = temp.path().join(".morphir");
+        let store = StateStore::in_morphir_dir(&morphir_dir, "my-ext").unwrap();
+        let other = StateStore::in_morphir_dir(&morphir_dir, "other-ext").unwrap();
+
+        assert_eq!(store.get("modules/orders").unwrap(), None);
+        store
+            .set("modules/orders", json!({"hash": "abc", "types": 3}))
+            .unwrap();
+        assert_eq!(other.get("modules/orders").unwrap(), None);
+
+        // A store opened again, as after a restart, sees the same entries
+        let reopened = StateStore::in_morphir_dir(&morphir_dir, "my-ext").unwrap();
+        assert_eq!(
+            reopened.get("modules/orders").unwrap(),
+            Some(json!({"hash": "abc", "types": 3}))
+        );
+        assert!(reopened.dir().starts_with(morphir_dir.join("state")));
+
+        assert!(store.delete("modules/orders").unwrap());
+        assert!(!store.delete("modules/orders").unwrap());
+        assert_eq!(reopened.get("modules/orders").unwrap(), None);
+
+        assert!(StateStore::in_morphir_dir(&morphir_dir, "../escape").is_none());
+        assert!(StateStore::in_morphir_dir(&morphir_dir, "").is_none());
+    }
+}
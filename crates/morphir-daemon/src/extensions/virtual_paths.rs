@@ -3,7 +3,7 @@
 //!
 //! Extensions access files through virtual paths that map to real paths.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 /// Virtual path configuration for extensions
@@ -60,23 +60,31 @@ impl VirtualPathConfig {
 
     /// Resolve a virtual path to a real path
     ///
-    /// Returns None if the path doesn't match any mapping.
+    /// Returns None if the path doesn't match any mapping, or climbs out of
+    /// its mapping with `..`.
     pub fn resolve(&self, virtual_path: &str) -> Option<PathBuf> {
-        for (prefix, real_base) in &self.mappings {
-            if virtual_path == prefix {
-                return Some(real_base.clone());
-            }
-
-            if virtual_path.starts_with(prefix) {
-                let suffix = &virtual_path[prefix.len()..];
-                if suffix.starts_with('/') {
-                    let suffix = suffix.trim_start_matches('/');
-                    return Some(real_base.join(suffix));
-                }
-            }
+        let (prefix, real_base) = self.mapping_for(virtual_path)?;
+        let suffix = virtual_path[prefix.len()..].trim_start_matches('/');
+        if suffix.split('/').any(|segment| segment == "..") {
+            return None;
+        }
+        if suffix.is_empty() {
+            return Some(real_base.to_path_buf());
         }
+        Some(real_base.join(suffix))
+    }
 
-        None
+    /// The longest prefix a virtual path is under, and the real path it maps to
+    pub fn mapping_for(&self, virtual_path: &str) -> Option<(&str, &Path)> {
+        self.mappings
+            .iter()
+            .filter(|(prefix, _)| {
+                virtual_path
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, real_base)| (prefix.as_str(), real_base.as_path()))
     }
 
     /// Convert a real path to a virtual path
@@ -121,6 +129,8 @@ pub struct FileSandbox {
     allow_external_reads: bool,
     /// Whether to allow writes outside mappings
     allow_external_writes: bool,
+    /// Prefixes that may be read but not written
+    read_only: HashSet<String>,
 }
 
 impl FileSandbox {
@@ -130,6 +140,7 @@ impl FileSandbox {
             config,
             allow_external_reads: false,
             allow_external_writes: false,
+            read_only: HashSet::new(),
         }
     }
 
@@ -139,9 +150,24 @@ impl FileSandbox {
             config,
             allow_external_reads: true,
             allow_external_writes: true,
+            read_only: HashSet::new(),
         }
     }
 
+    /// Sandbox for extensions of a Morphir workspace: the workspace may be
+    /// read, the output and cache directories read and written
+    pub fn for_workspace(workspace_root: &Path, output_dir: &Path) -> Self {
+        Self::new(VirtualPathConfig::for_workspace(workspace_root, output_dir))
+            .with_read_only("/workspace")
+    }
+
+    /// Refuse writes under a virtual prefix
+    pub fn with_read_only(mut self, virtual_prefix: &str) -> Self {
+        self.read_only
+            .insert(virtual_prefix.trim_end_matches('/').to_string());
+        self
+    }
+
     /// Check if reading from a path is allowed
     pub fn can_read(&self, path: &str) -> bool {
         self.config.is_valid(path) || self.allow_external_reads
@@ -149,7 +175,10 @@ impl FileSandbox {
 
     /// Check if writing to a path is allowed
     pub fn can_write(&self, path: &str) -> bool {
-        self.config.is_valid(path) || self.allow_external_writes
+        match self.config.mapping_for(path) {
+            Some((prefix, _)) => !self.read_only.contains(prefix),
+            None => self.allow_external_writes,
+        }
     }
 
     /// Resolve and validate a path for reading
@@ -246,5 +275,29 @@ mod tests {
 
         assert!(sandbox.resolve_read("/workspace/file.txt").is_ok());
         assert!(sandbox.resolve_read("/etc/passwd").is_err());
+        assert!(sandbox.resolve_read("/workspace/../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_read_only_prefixes_and_nested_mappings() {
+        let temp = tempdir().unwrap();
+        let output = temp.path().join(".morphir/out");
+        let sandbox = FileSandbox::for_workspace(temp.path(), &output);
+
+        assert!(sandbox.can_read("/workspace/src/orders.gleam"));
+        assert!(!sandbox.can_write("/workspace/src/orders.gleam"));
+        assert!(!sandbox.can_write("/workspacex/orders.gleam"));
+        assert_eq!(
+            sandbox.resolve_write("/output/orders.ts").unwrap(),
+            output.join("orders.ts")
+        );
+
+        let mut config = VirtualPathConfig::new();
+        config.add_mapping("/workspace", temp.path());
+        config.add_mapping("/workspace/vendor", "/opt/vendor");
+        assert_eq!(
+            config.resolve("/workspace/vendor/lib.gleam"),
+            Some(PathBuf::from("/opt/vendor/lib.gleam"))
+        );
     }
 }
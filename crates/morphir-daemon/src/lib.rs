@@ -6,11 +6,37 @@
 //! - Dependency resolution and caching
 //! - Incremental builds with file watching
 //! - JSON-RPC protocol for CLI and IDE integration
+//! - Concurrency limits and backpressure for extension work
 //! - Extension loading and management via Extism
+//! - Running builtin transforms and validators natively instead of as WASM
+//! - Ordering extension pipelines by the `feeds` declared in capabilities
+//! - Auditing the identifiers a backend will emit against its target's rules
+//! - Writing backend artifacts using target-language directory conventions
+//! - Checking generated artifacts with the target language's own toolchain
+//! - Read-only HTTP export of generated documentation and JSON Schemas
+//! - A JSON-RPC server over stdio and TCP routing to the workspace and extensions
+//! - Re-evaluating subscribed model values as the model or their inputs change
+//! - Graceful shutdown, draining requests in flight and notifying clients
 
+pub mod artifacts;
+pub mod audit;
+pub mod command;
+pub mod concurrency;
 pub mod error;
+pub mod evaluate;
+pub mod export;
 pub mod extensions;
+pub mod server;
+pub mod verify;
 pub mod workspace;
 
+pub use artifacts::{ArtifactWriter, TargetLayout};
+pub use audit::{IdentifierAudit, audit_identifiers};
+pub use command::{CommandOutput, CommandRunner};
+pub use concurrency::{ConcurrencyConfig, ConcurrencyLimiter};
 pub use error::{DaemonError, Result};
-pub use extensions::{ExtensionContainer, ExtensionLoader, ExtensionRegistry};
+pub use evaluate::{EvaluationRequest, EvaluationResult, Subscriptions};
+pub use export::{ExportProject, ExportRoute, RestExport};
+pub use extensions::{ExtensionBackend, ExtensionContainer, ExtensionLoader, ExtensionRegistry};
+pub use server::{DaemonInfo, DaemonServer, ShutdownKind, Transport};
+pub use verify::{SourceMap, Toolchain, Verification};
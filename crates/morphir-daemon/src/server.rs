@@ -0,0 +1,1737 @@
+//! JSON-RPC server for the CLI and IDEs
+//!
+//! The daemon speaks JSON-RPC 2.0 over stdio, for editors that launch it, and
+//! over TCP on the loopback interface, for `morphir daemon start`. Both
+//! transports carry one message per line: a request, a notification, or a
+//! batch. Requests are routed to the workspace and to the extension registry:
+//!
+//! | Method                   | Params                      | Result                                          |
+//! |--------------------------|-----------------------------|-------------------------------------------------|
+//! | `daemon/health`          |                             | version, uptime, and workspace                  |
+//! | `daemon/shutdown`        |                             | `null`, then the server stops                   |
+//! | `workspace/open`         | `root`                      | the workspace and its projects                  |
+//! | `workspace/close`        |                             | `null`                                          |
+//! | `workspace/info`         |                             | the workspace and its projects                  |
+//! | `workspace/listProjects` |                             | the projects                                    |
+//! | `workspace/projectInfo`  | `name`                      | a project                                       |
+//! | `workspace/watch`        | `enabled`                   | the watch state and its paths                   |
+//! | `workspace/symbols`      | `query`, `project`          | matching top-level symbols                      |
+//! | `workspace/moduleGraph`  | `name`                      | a project's module imports                      |
+//! | `workspace/metrics`      | `limit`                     | model size and build history                    |
+//! | `evaluation/subscribe`   | `project`, `target`         | the subscription, first result                  |
+//! | `evaluation/unsubscribe` | `subscription`              | whether it was subscribed                       |
+//! | `extensions/list`        |                             | builtin and loaded extensions                   |
+//! | `extensions/execute`     | `id`, `type`, `input`       | the extension's output                          |
+//! | `pipelines/run`          | `pipeline`, `project`, `ir` | each stage's diagnostics, the IR, and artifacts |
+//!
+//! While the workspace is watched, each rebuild after a change is pushed to
+//! every connected client as a `build/diagnostics` notification carrying the
+//! project's [`BuildReport`] and the build's `duration` in milliseconds.
+//! Fixes the frontend suggested are passed through with their diagnostics. The
+//! build is also added to the workspace's [`BuildHistory`].
+//! Projects depending on a changed project are rebuilt after it, in
+//! dependency order, once its IR changed. Values subscribed to with
+//! `evaluation/subscribe` (see [`crate::evaluate`]) are then evaluated again
+//! if their project's IR or input files changed, and each result is pushed as
+//! an `evaluation/result` notification.
+//!
+//! `pipelines/run` runs a pipeline declared in `morphir.toml` (see
+//! [`ExtensionRegistry::run_declared`]), looked up in the project's
+//! configuration when a project is named and in the workspace's otherwise.
+//! A frontend stage compiles the project's sources; a pipeline without one
+//! starts from the `ir` passed in.
+//!
+//! A server listening on TCP records its address as [`DaemonInfo`], so later
+//! CLI invocations find it, and removes it when it stops.
+//!
+//! Shutting down, on `daemon/shutdown` or [`DaemonServer::shutdown`], the
+//! server stops accepting connections and answers any further request with a
+//! `SHUTTING_DOWN` error. Each client is sent a `daemon/shuttingDown`
+//! notification once the request it is waiting on, if any, has been
+//! answered. Requests in flight, extension calls included, are given the
+//! drain timeout to complete before they are cancelled; the workspace's
+//! indexes are then saved and the workspace closed. [`ShutdownKind`] tells
+//! whether everything completed in time.
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use morphir_common::config::PipelineStageKind;
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::types::{Diagnostic, DiagnosticSeverity};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::{broadcast, watch};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+
+use crate::error::{DaemonError, Result};
+use crate::evaluate::{EvaluationRequest, Subscriptions, evaluate};
+use crate::extensions::ExtensionRegistry;
+use crate::extensions::container::ExtensionType;
+use crate::extensions::pipeline::PipelineInput;
+use crate::extensions::protocol::{JSONRPC_VERSION, RpcError, error_codes};
+use crate::workspace::rebuild::read_sources;
+use crate::workspace::{
+    BuildHistory, BuildReport, FileChange, FrontendBuilder, MetricsSummary, Project,
+    ProjectBuilder, ProjectState, WatchConfig, Workspace, WorkspaceWatcher, affected_projects,
+};
+
+/// Port the daemon listens on when none is configured
+pub const DEFAULT_PORT: u16 = 9741;
+
+/// Time allowed to connect to a running daemon
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Notifications a slow client may fall behind by before missing some
+const NOTIFICATION_BUFFER: usize = 64;
+
+/// Time requests in flight are given to complete when shutting down
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Methods served by the daemon
+pub mod methods {
+    /// Version, uptime, and workspace of the daemon
+    pub const HEALTH: &str = "daemon/health";
+    /// Stop the daemon once in-flight requests complete
+    pub const SHUTDOWN: &str = "daemon/shutdown";
+    /// Open the workspace at `root`, closing the current one
+    pub const WORKSPACE_OPEN: &str = "workspace/open";
+    /// Close the current workspace
+    pub const WORKSPACE_CLOSE: &str = "workspace/close";
+    /// The current workspace and its projects
+    pub const WORKSPACE_INFO: &str = "workspace/info";
+    /// Projects of the current workspace
+    pub const LIST_PROJECTS: &str = "workspace/listProjects";
+    /// A project of the current workspace, by `name`
+    pub const PROJECT_INFO: &str = "workspace/projectInfo";
+    /// Start or stop watching the current workspace
+    pub const WORKSPACE_WATCH: &str = "workspace/watch";
+    /// Top-level symbols whose name contains `query`, optionally in `project`
+    pub const SYMBOLS: &str = "workspace/symbols";
+    /// Modules of the project `name` and the project modules they import
+    pub const MODULE_GRAPH: &str = "workspace/moduleGraph";
+    /// Model size and the `limit` most recent builds of the workspace
+    pub const METRICS: &str = "workspace/metrics";
+    /// Evaluate a value of a project's model now and whenever it changes
+    pub const SUBSCRIBE_EVALUATION: &str = "evaluation/subscribe";
+    /// Stop re-evaluating a `subscription`
+    pub const UNSUBSCRIBE_EVALUATION: &str = "evaluation/unsubscribe";
+    /// Builtin and loaded extensions
+    pub const LIST_EXTENSIONS: &str = "extensions/list";
+    /// Run a transform or validator on JSON `input`
+    pub const EXECUTE_EXTENSION: &str = "extensions/execute";
+    /// Run a pipeline declared in `morphir.toml`
+    pub const RUN_PIPELINE: &str = "pipelines/run";
+    /// Notification of a rebuild after a change, sent by the daemon
+    pub const BUILD_DIAGNOSTICS: &str = "build/diagnostics";
+    /// Notification of a subscribed value evaluated again, sent by the daemon
+    pub const EVALUATION_RESULT: &str = "evaluation/result";
+    /// Notification that the daemon is stopping, sent by the daemon
+    pub const SHUTTING_DOWN: &str = "daemon/shuttingDown";
+}
+
+/// How the server stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownKind {
+    /// Every request in flight completed
+    Clean,
+    /// Requests still in flight at the drain timeout were cancelled
+    Forced,
+}
+
+impl ShutdownKind {
+    /// Exit code of a daemon process that stopped this way: 0 when clean,
+    /// 3 when forced, so supervisors can tell the two apart
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ShutdownKind::Clean => 0,
+            ShutdownKind::Forced => 3,
+        }
+    }
+}
+
+/// Where the server reads requests and writes responses
+pub enum Transport {
+    /// Standard input and output, for a single client that launched the daemon
+    Stdio,
+    /// Connections accepted on a listener, served concurrently
+    Tcp(TcpListener),
+}
+
+/// A running daemon, as recorded for the CLI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    /// Process ID
+    pub pid: u32,
+    /// Address the daemon listens on
+    pub address: SocketAddr,
+    /// Root of the workspace it opened at startup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<PathBuf>,
+    /// Daemon version
+    pub version: String,
+}
+
+impl DaemonInfo {
+    /// Name of the file recording the daemon
+    pub const FILE_NAME: &'static str = "daemon.json";
+
+    /// Directory for daemon state: `$XDG_RUNTIME_DIR/morphir`, or `morphir`
+    /// in the temporary directory
+    pub fn default_dir() -> PathBuf {
+        dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("morphir")
+    }
+
+    /// The daemon recorded in `dir`, if any
+    pub fn read(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(Self::FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    /// Record the daemon in `dir`
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(dir.join(Self::FILE_NAME), content)?;
+        Ok(())
+    }
+
+    /// Forget the daemon recorded in `dir`
+    pub fn remove(dir: &Path) -> Result<()> {
+        match std::fs::remove_file(dir.join(Self::FILE_NAME)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// JSON-RPC server routing requests to a workspace and extension registry
+pub struct DaemonServer {
+    registry: Arc<ExtensionRegistry>,
+    workspace: Arc<Mutex<Option<Workspace>>>,
+    builder: Arc<dyn ProjectBuilder>,
+    watch: WatchConfig,
+    watch_on_open: bool,
+    watching: Mutex<Option<Watching>>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    notifications: broadcast::Sender<Value>,
+    started: Instant,
+    shutdown: watch::Sender<bool>,
+    drain_timeout: Duration,
+    info_dir: Option<PathBuf>,
+}
+
+/// The watcher of the open workspace and the task rebuilding on its changes
+struct Watching {
+    watcher: WorkspaceWatcher,
+    rebuilds: JoinHandle<()>,
+}
+
+impl Drop for Watching {
+    fn drop(&mut self) {
+        self.rebuilds.abort();
+    }
+}
+
+impl DaemonServer {
+    /// Server with no workspace open
+    pub fn new(registry: ExtensionRegistry) -> Self {
+        let registry = Arc::new(registry);
+        Self {
+            builder: Arc::new(FrontendBuilder::new(Arc::clone(&registry))),
+            registry,
+            workspace: Arc::new(Mutex::new(None)),
+            watch: WatchConfig::default(),
+            watch_on_open: false,
+            watching: Mutex::new(None),
+            subscriptions: Arc::new(Mutex::new(Subscriptions::default())),
+            notifications: broadcast::channel(NOTIFICATION_BUFFER).0,
+            started: Instant::now(),
+            shutdown: watch::channel(false).0,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            info_dir: None,
+        }
+    }
+
+    /// Record the server as [`DaemonInfo`] in `dir` while it serves TCP
+    pub fn with_info_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.info_dir = Some(dir.into());
+        self
+    }
+
+    /// Give requests in flight `timeout` to complete when shutting down
+    pub fn with_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Watch each workspace as it is opened, with `config`
+    pub fn with_watch(mut self, config: WatchConfig) -> Self {
+        self.watch = config;
+        self.watch_on_open = true;
+        self
+    }
+
+    /// Build projects with `builder` instead of their frontend extensions
+    pub fn with_builder(mut self, builder: Arc<dyn ProjectBuilder>) -> Self {
+        self.builder = builder;
+        self
+    }
+
+    /// Receive the notifications pushed to clients
+    pub fn subscribe(&self) -> broadcast::Receiver<Value> {
+        self.notifications.subscribe()
+    }
+
+    /// Open the workspace at `root`, closing the current one. Module
+    /// indexes are refreshed from those saved by the last session.
+    pub fn open_workspace(&self, root: PathBuf) -> Result<Value> {
+        let mut workspace = Workspace::open(root)?;
+        let started = Instant::now();
+        match workspace.refresh_indexes() {
+            Ok(stats) => info!(
+                "Indexed {} files in {:?} ({} reused, {} rescanned)",
+                stats.reused + stats.verified + stats.scanned,
+                started.elapsed(),
+                stats.reused + stats.verified,
+                stats.scanned
+            ),
+            Err(e) => warn!("Failed to index workspace: {}", e),
+        }
+        let info = workspace_info(&workspace);
+        self.close_workspace();
+        *self.workspace.lock().unwrap() = Some(workspace);
+        if self.watch_on_open {
+            self.start_watching()?;
+        }
+        Ok(info)
+    }
+
+    fn close_workspace(&self) {
+        self.stop_watching();
+        if let Some(mut workspace) = self.workspace.lock().unwrap().take() {
+            workspace.close();
+        }
+    }
+
+    /// Watch the open workspace, rebuilding projects as their files change.
+    /// Must be called within a Tokio runtime.
+    pub fn start_watching(&self) -> Result<()> {
+        let (watcher, batches) = {
+            let workspace = self.workspace.lock().unwrap();
+            let workspace = workspace
+                .as_ref()
+                .ok_or_else(|| DaemonError::Workspace("No workspace is open".to_string()))?;
+            let inputs = self.subscriptions.lock().unwrap().input_paths(workspace);
+            WorkspaceWatcher::start(workspace, &self.watch, &inputs)?
+        };
+        let rebuilds = tokio::spawn(rebuild_on_change(
+            Arc::clone(&self.workspace),
+            Arc::clone(&self.builder),
+            Arc::clone(&self.subscriptions),
+            self.notifications.clone(),
+            self.watch.auto_rebuild,
+            batches,
+        ));
+        *self.watching.lock().unwrap() = Some(Watching { watcher, rebuilds });
+        Ok(())
+    }
+
+    /// Stop watching the open workspace
+    pub fn stop_watching(&self) {
+        self.watching.lock().unwrap().take();
+    }
+
+    /// Ask the server to stop. Requests in flight are given the drain
+    /// timeout to complete first.
+    pub fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Whether the server has been asked to stop
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    /// Serve requests until shut down or, on stdio, until input ends
+    pub async fn serve(self: Arc<Self>, transport: Transport) -> Result<ShutdownKind> {
+        let mut connections = JoinSet::new();
+        match transport {
+            Transport::Stdio => {
+                let server = Arc::clone(&self);
+                connections.spawn(async move {
+                    server
+                        .serve_lines(tokio::io::stdin(), tokio::io::stdout())
+                        .await
+                });
+                let mut shutdown = self.shutdown.subscribe();
+                tokio::select! {
+                    _ = shutdown.wait_for(|stop| *stop) => {}
+                    Some(joined) = connections.join_next() => {
+                        if let Ok(Err(e)) = joined {
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+            Transport::Tcp(listener) => self.serve_tcp(listener, &mut connections).await?,
+        }
+        let kind = self.drain(connections).await?;
+        self.flush();
+        if let Some(dir) = &self.info_dir {
+            DaemonInfo::remove(dir)?;
+        }
+        info!("Daemon stopped ({:?})", kind);
+        Ok(kind)
+    }
+
+    /// Wait up to the drain timeout for `connections` to answer the requests
+    /// they read, then cancel those still running
+    async fn drain(&self, mut connections: JoinSet<Result<()>>) -> Result<ShutdownKind> {
+        let drained = tokio::time::timeout(self.drain_timeout, async {
+            while let Some(joined) = connections.join_next().await {
+                if let Ok(Err(e)) = joined {
+                    return Err(e);
+                }
+            }
+            Ok(())
+        })
+        .await;
+        match drained {
+            Ok(result) => result.map(|()| ShutdownKind::Clean),
+            Err(_) => {
+                warn!(
+                    "Cancelling {} connections still busy after {:?}",
+                    connections.len(),
+                    self.drain_timeout
+                );
+                connections.shutdown().await;
+                Ok(ShutdownKind::Forced)
+            }
+        }
+    }
+
+    /// Save what the workspace holds in memory and close it
+    fn flush(&self) {
+        self.stop_watching();
+        if let Some(workspace) = self.workspace.lock().unwrap().as_ref()
+            && let Err(e) = workspace.save_indexes()
+        {
+            warn!("Failed to save workspace indexes: {}", e);
+        }
+        self.close_workspace();
+    }
+
+    async fn serve_tcp(
+        self: &Arc<Self>,
+        listener: TcpListener,
+        connections: &mut JoinSet<Result<()>>,
+    ) -> Result<()> {
+        let address = listener.local_addr()?;
+        if let Some(dir) = &self.info_dir {
+            let workspace = self
+                .workspace
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|w| w.root.clone());
+            DaemonInfo {
+                pid: std::process::id(),
+                address,
+                workspace,
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            }
+            .write(dir)?;
+        }
+        info!("Daemon listening on {}", address);
+
+        let mut shutdown = self.shutdown.subscribe();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, peer) = accepted?;
+                    debug!("Accepted connection from {}", peer);
+                    let server = Arc::clone(self);
+                    connections.spawn(async move {
+                        let (reader, writer) = stream.into_split();
+                        if let Err(e) = server.serve_lines(reader, writer).await {
+                            debug!("Connection from {} closed: {}", peer, e);
+                        }
+                        Ok(())
+                    });
+                }
+                _ = shutdown.wait_for(|stop| *stop) => break,
+            }
+        }
+        // Connections stop reading once shut down; `serve` lets them answer
+        // what they read
+        Ok(())
+    }
+
+    /// Answer the messages read from `reader`, one per line, and push
+    /// notifications to `writer` between responses
+    async fn serve_lines(
+        &self,
+        reader: impl AsyncRead + Unpin,
+        mut writer: impl AsyncWrite + Unpin,
+    ) -> Result<()> {
+        let mut lines = BufReader::new(reader).lines();
+        let mut notifications = self.notifications.subscribe();
+        let mut shutdown = self.shutdown.subscribe();
+        loop {
+            let incoming = tokio::select! {
+                line = lines.next_line() => match line? {
+                    Some(line) => Incoming::Message(line),
+                    None => break,
+                },
+                notification = notifications.recv() => match notification {
+                    Ok(notification) => Incoming::Notification(notification),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Client missed {} notifications", missed);
+                        continue;
+                    }
+                    // The server holds the sender, so this is only seen
+                    // while shutting down
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = shutdown.wait_for(|stop| *stop) => Incoming::ShuttingDown,
+            };
+            let message = match incoming {
+                Incoming::Message(line) if line.trim().is_empty() => continue,
+                Incoming::Message(line) => self.handle(line.trim()).await,
+                Incoming::Notification(notification) => Some(notification.to_string()),
+                Incoming::ShuttingDown => {
+                    let notification = json!({
+                        "jsonrpc": JSONRPC_VERSION,
+                        "method": methods::SHUTTING_DOWN,
+                        "params": { "drainTimeoutMs": self.drain_timeout.as_millis() as u64 },
+                    });
+                    write_line(&mut writer, &notification.to_string()).await?;
+                    break;
+                }
+            };
+            if let Some(message) = message {
+                write_line(&mut writer, &message).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer a JSON-RPC message: a request, a notification, or a batch.
+    ///
+    /// Notifications, and batches of them, get no response.
+    pub async fn handle(&self, message: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(message) {
+            Err(e) => Some(error_response(
+                Value::Null,
+                rpc_error(error_codes::PARSE_ERROR, e.to_string()),
+            )),
+            Ok(Value::Array(batch)) if batch.is_empty() => Some(error_response(
+                Value::Null,
+                rpc_error(error_codes::INVALID_REQUEST, "Empty batch"),
+            )),
+            Ok(Value::Array(batch)) => {
+                let mut responses = Vec::new();
+                for message in batch {
+                    responses.extend(self.dispatch(message).await);
+                }
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(message) => self.dispatch(message).await,
+        };
+        response.map(|response| response.to_string())
+    }
+
+    async fn dispatch(&self, message: Value) -> Option<Value> {
+        // A request has an `id`, even `null`; a notification has none
+        let id = message.get("id").cloned();
+        let request = match serde_json::from_value::<Request>(message) {
+            Ok(request) if request.jsonrpc == JSONRPC_VERSION => request,
+            Ok(_) => {
+                return Some(error_response(
+                    id.unwrap_or(Value::Null),
+                    rpc_error(error_codes::INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+                ));
+            }
+            Err(e) => {
+                return Some(error_response(
+                    id.unwrap_or(Value::Null),
+                    rpc_error(error_codes::INVALID_REQUEST, e.to_string()),
+                ));
+            }
+        };
+
+        debug!("Handling {}", request.method);
+        let result = if self.is_shutting_down() && request.method != methods::HEALTH {
+            Err(rpc_error(
+                error_codes::SHUTTING_DOWN,
+                "The daemon is shutting down",
+            ))
+        } else {
+            self.call(&request.method, request.params).await
+        };
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": JSONRPC_VERSION, "result": result, "id": id }),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    async fn call(&self, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+        match method {
+            methods::HEALTH => Ok(self.health()),
+            methods::SHUTDOWN => {
+                self.shutdown();
+                Ok(Value::Null)
+            }
+            methods::WORKSPACE_OPEN => {
+                let params: OpenParams = parse_params(params)?;
+                self.open_workspace(params.root).map_err(daemon_error)
+            }
+            methods::WORKSPACE_CLOSE => {
+                self.close_workspace();
+                Ok(Value::Null)
+            }
+            methods::WORKSPACE_INFO => self.with_workspace(|w| Ok(workspace_info(w))),
+            methods::LIST_PROJECTS => self.with_workspace(|w| Ok(projects_info(w))),
+            methods::PROJECT_INFO => {
+                let params: ProjectParams = parse_params(params)?;
+                self.with_workspace(|w| {
+                    w.get_project(&params.name)
+                        .map(|project| project_info(w, project))
+                        .ok_or_else(|| {
+                            DaemonError::Project(format!("No such project: {}", params.name))
+                        })
+                })
+            }
+            methods::WORKSPACE_WATCH => {
+                let params: WatchParams = parse_params(params)?;
+                if params.enabled {
+                    self.start_watching().map_err(daemon_error)?;
+                } else {
+                    self.stop_watching();
+                }
+                Ok(self.watch_state())
+            }
+            methods::SYMBOLS => {
+                let params: SymbolsParams = parse_params(params)?;
+                self.with_workspace(|w| Ok(symbols(w, &params)))
+            }
+            methods::MODULE_GRAPH => {
+                let params: ProjectParams = parse_params(params)?;
+                self.with_workspace(|w| {
+                    w.indexes
+                        .get(&params.name)
+                        .map(|index| json!(index.dependency_graph()))
+                        .ok_or_else(|| {
+                            DaemonError::Project(format!("No such project: {}", params.name))
+                        })
+                })
+            }
+            methods::METRICS => {
+                let params: MetricsParams = parse_params(params)?;
+                self.with_workspace(|w| {
+                    let history = BuildHistory::load(&BuildHistory::path(&w.root));
+                    Ok(json!(MetricsSummary::new(w, &history, params.limit)))
+                })
+            }
+            methods::SUBSCRIBE_EVALUATION => {
+                let params: EvaluationRequest = parse_params(params)?;
+                self.subscribe_evaluation(params)
+                    .await
+                    .map_err(daemon_error)
+            }
+            methods::UNSUBSCRIBE_EVALUATION => {
+                let params: UnsubscribeParams = parse_params(params)?;
+                let removed = self
+                    .subscriptions
+                    .lock()
+                    .unwrap()
+                    .remove(params.subscription);
+                Ok(json!(removed))
+            }
+            methods::LIST_EXTENSIONS => Ok(self.extensions().await),
+            methods::EXECUTE_EXTENSION => {
+                let params: ExecuteParams = parse_params(params)?;
+                self.execute(params).await.map_err(daemon_error)
+            }
+            methods::RUN_PIPELINE => {
+                let params: RunPipelineParams = parse_params(params)?;
+                self.run_pipeline(params).await.map_err(daemon_error)
+            }
+            _ => Err(RpcError::method_not_found(method)),
+        }
+    }
+
+    fn health(&self) -> Value {
+        let workspace = self.workspace.lock().unwrap();
+        json!({
+            "status": "healthy",
+            "version": env!("CARGO_PKG_VERSION"),
+            "pid": std::process::id(),
+            "uptime_seconds": self.started.elapsed().as_secs(),
+            "workspace": workspace.as_ref().map(|w| json!({
+                "root": w.root,
+                "state": w.state,
+                "projects": w.projects.len(),
+            })),
+        })
+    }
+
+    /// Whether the workspace is watched, and the paths watched relative to it
+    fn watch_state(&self) -> Value {
+        let root = self
+            .workspace
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|w| w.root.clone())
+            .unwrap_or_default();
+        let watching = self.watching.lock().unwrap();
+        let paths: Vec<&Path> = watching
+            .iter()
+            .flat_map(|watching| watching.watcher.paths())
+            .map(|path| path.strip_prefix(&root).unwrap_or(path))
+            .collect();
+        json!({
+            "state": if watching.is_some() { "running" } else { "stopped" },
+            "watchedPaths": paths,
+        })
+    }
+
+    fn with_workspace(
+        &self,
+        f: impl FnOnce(&Workspace) -> Result<Value>,
+    ) -> std::result::Result<Value, RpcError> {
+        let workspace = self.workspace.lock().unwrap();
+        let workspace = workspace
+            .as_ref()
+            .ok_or_else(|| DaemonError::Workspace("No workspace is open".to_string()))
+            .map_err(daemon_error)?;
+        f(workspace).map_err(daemon_error)
+    }
+
+    /// Subscribe to `request`, returning the subscription and its first
+    /// result. Its input files are watched from then on.
+    async fn subscribe_evaluation(&self, request: EvaluationRequest) -> Result<Value> {
+        let (root, project) = {
+            let workspace = self.workspace.lock().unwrap();
+            let workspace = workspace
+                .as_ref()
+                .ok_or_else(|| DaemonError::Workspace("No workspace is open".to_string()))?;
+            let project = workspace.get_project(&request.project).ok_or_else(|| {
+                DaemonError::Project(format!("No such project: {}", request.project))
+            })?;
+            (workspace.root.clone(), project.clone())
+        };
+        let inputs = request.input_paths(&project);
+        let id = self.subscriptions.lock().unwrap().add(request.clone());
+
+        // Restart the watcher if it does not cover the inputs yet
+        let unwatched = self.watching.lock().unwrap().as_ref().is_some_and(|w| {
+            inputs
+                .iter()
+                .any(|input| input.exists() && !w.watcher.paths().contains(input))
+        });
+        if unwatched {
+            self.stop_watching();
+            self.start_watching()?;
+        }
+
+        let result = tokio::task::spawn_blocking(move || evaluate(id, &request, &root, &project))
+            .await
+            .map_err(|e| DaemonError::Other(anyhow::anyhow!("Evaluation failed: {}", e)))?;
+        Ok(json!({ "subscription": id, "result": result }))
+    }
+
+    async fn extensions(&self) -> Value {
+        let builtins: Vec<Value> = self
+            .registry
+            .builtins()
+            .list()
+            .into_iter()
+            .map(|info| {
+                json!({
+                    "id": info.id,
+                    "name": info.name,
+                    "type": info.extension_type.to_string(),
+                    "description": info.description,
+                })
+            })
+            .collect();
+        json!({ "builtins": builtins, "loaded": self.registry.list().await })
+    }
+
+    async fn execute(&self, params: ExecuteParams) -> Result<Value> {
+        let input = Envelope::json(&params.input)?;
+        let output = self
+            .registry
+            .execute(&params.id, params.extension_type, &input)
+            .await?;
+        output
+            .as_json()
+            .map_err(|e| DaemonError::Extension(e.to_string()))
+    }
+
+    async fn run_pipeline(&self, params: RunPipelineParams) -> Result<Value> {
+        let (spec, sources) = {
+            let workspace = self.workspace.lock().unwrap();
+            let workspace = workspace
+                .as_ref()
+                .ok_or_else(|| DaemonError::Workspace("No workspace is open".to_string()))?;
+            let project = params
+                .project
+                .as_ref()
+                .map(|name| {
+                    workspace
+                        .get_project(name)
+                        .ok_or_else(|| DaemonError::Project(format!("No such project: {}", name)))
+                })
+                .transpose()?;
+            let spec = project
+                .and_then(|p| p.config.pipelines.get(&params.pipeline))
+                .or_else(|| workspace.config.pipelines.get(&params.pipeline))
+                .cloned()
+                .ok_or_else(|| {
+                    DaemonError::Config(format!("No such pipeline: {}", params.pipeline))
+                })?;
+            let frontend = spec
+                .stages
+                .iter()
+                .find(|stage| stage.kind == PipelineStageKind::Frontend);
+            let sources = match (frontend, project) {
+                (None, _) => Vec::new(),
+                (Some(frontend), Some(project)) => {
+                    let language = project.language().unwrap_or(&frontend.extension);
+                    read_sources(&project.path, &project.source_dir, language)?
+                }
+                (Some(_), None) => {
+                    return Err(DaemonError::Config(format!(
+                        "Pipeline {} compiles sources; name the project to compile",
+                        params.pipeline
+                    )));
+                }
+            };
+            (spec, sources)
+        };
+        let input = PipelineInput {
+            sources,
+            ir: params.ir,
+        };
+        let report = self
+            .registry
+            .run_declared(&params.pipeline, &spec, input)
+            .await?;
+        Ok(json!(report))
+    }
+}
+
+/// What a connection received next
+enum Incoming {
+    /// A message from the client
+    Message(String),
+    /// A notification to push to the client
+    Notification(Value),
+    /// The server is stopping
+    ShuttingDown,
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct OpenParams {
+    root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct ProjectParams {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct SymbolsParams {
+    query: String,
+    #[serde(default)]
+    project: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WatchParams {
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct MetricsParams {
+    #[serde(default = "default_history_limit")]
+    limit: usize,
+}
+
+fn default_history_limit() -> usize {
+    20
+}
+
+#[derive(Deserialize)]
+struct UnsubscribeParams {
+    subscription: u64,
+}
+
+#[derive(Deserialize)]
+struct ExecuteParams {
+    id: String,
+    #[serde(rename = "type")]
+    extension_type: ExtensionType,
+    input: Value,
+}
+
+#[derive(Deserialize)]
+struct RunPipelineParams {
+    pipeline: String,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    ir: Option<Value>,
+}
+
+/// Indexed symbols matching `params`, by project and path
+fn symbols(workspace: &Workspace, params: &SymbolsParams) -> Value {
+    let mut indexes: Vec<_> = workspace
+        .indexes
+        .iter()
+        .filter(|(name, _)| {
+            params
+                .project
+                .as_ref()
+                .is_none_or(|project| project == *name)
+        })
+        .collect();
+    indexes.sort_by_key(|(name, _)| *name);
+    indexes
+        .into_iter()
+        .flat_map(|(name, index)| {
+            index
+                .find_symbols(&params.query)
+                .map(move |(path, file, symbol)| {
+                    json!({
+                        "name": symbol.name,
+                        "kind": symbol.kind,
+                        "project": name,
+                        "module": file.module,
+                        "path": path,
+                        "line": symbol.line,
+                    })
+                })
+        })
+        .collect()
+}
+
+/// Rebuild the projects affected by each batch of changes, notifying
+/// clients of each build. Projects are marked stale until rebuilt.
+async fn rebuild_on_change(
+    workspace: Arc<Mutex<Option<Workspace>>>,
+    builder: Arc<dyn ProjectBuilder>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
+    notifications: broadcast::Sender<Value>,
+    auto_rebuild: bool,
+    mut batches: UnboundedReceiver<Vec<FileChange>>,
+) {
+    while let Some(changes) = batches.recv().await {
+        let (root, names, graph, projects) = {
+            let mut workspace = workspace.lock().unwrap();
+            let Some(workspace) = workspace.as_mut() else {
+                return;
+            };
+            let names = affected_projects(workspace, &changes);
+            debug!("{} changes affect {:?}", changes.len(), names);
+            let graph = workspace.project_graph();
+            let order = graph.downstream(&names).unwrap_or_else(|e| {
+                warn!("Rebuilding without dependency order: {}", e);
+                names.clone()
+            });
+            let mut projects = Vec::new();
+            for name in &order {
+                if let Some(project) = workspace.get_project_mut(name) {
+                    if names.contains(name) {
+                        project.state = ProjectState::Stale;
+                    }
+                    projects.push(project.clone());
+                }
+            }
+            (workspace.root.clone(), names, graph, projects)
+        };
+        for project in projects.iter().filter(|p| names.contains(&p.name)) {
+            match project.refresh_index() {
+                Ok((index, _)) => {
+                    if let Some(workspace) = workspace.lock().unwrap().as_mut() {
+                        workspace.indexes.insert(project.name.clone(), index);
+                    }
+                }
+                Err(e) => warn!("Failed to index {}: {}", project.name, e),
+            }
+        }
+        if !auto_rebuild {
+            // Inputs of subscribed values may still have changed
+            reevaluate(
+                &workspace,
+                &subscriptions,
+                &notifications,
+                &HashSet::new(),
+                &changes,
+            )
+            .await;
+            continue;
+        }
+
+        // Projects whose IR changed in this pass, and projects that failed
+        let mut changed: HashSet<String> = HashSet::new();
+        let mut failed: HashSet<String> = HashSet::new();
+        for project in projects {
+            let dependencies = graph.dependencies(&project.name);
+            if dependencies.iter().any(|d| failed.contains(*d)) {
+                // Stale until its dependencies build
+                set_project_state(&workspace, &project.name, ProjectState::Stale);
+                failed.insert(project.name.clone());
+                continue;
+            }
+            let upstream_changed = dependencies.iter().any(|d| changed.contains(*d));
+            if !names.contains(&project.name) && !upstream_changed {
+                continue;
+            }
+            set_project_state(&workspace, &project.name, ProjectState::Loading);
+            let started = Instant::now();
+            let build = if upstream_changed {
+                builder.rebuild(&root, &project).await
+            } else {
+                builder.build(&root, &project).await
+            };
+            let report = build.unwrap_or_else(|e| BuildReport {
+                project: project.name.clone(),
+                success: false,
+                compiled: 0,
+                cached: 0,
+                diagnostics: vec![Diagnostic {
+                    severity: DiagnosticSeverity::Error,
+                    code: None,
+                    message: e.to_string(),
+                    location: None,
+                    related: Vec::new(),
+                    fixes: Vec::new(),
+                }],
+            });
+            let state = if report.success {
+                ProjectState::Ready
+            } else {
+                ProjectState::Error
+            };
+            if !report.success {
+                failed.insert(project.name.clone());
+            } else if report.compiled > 0 || report.cached > 0 {
+                changed.insert(project.name.clone());
+            }
+            set_project_state(&workspace, &project.name, state);
+
+            let duration = started.elapsed();
+            if let Err(e) = BuildHistory::append(&BuildHistory::path(&root), &report, duration) {
+                warn!("Failed to record build metrics: {}", e);
+            }
+            let mut params = serde_json::to_value(&report).unwrap_or_default();
+            params["duration"] = json!(duration.as_millis() as u64);
+            // No subscribers just means no client is connected
+            let _ = notifications.send(json!({
+                "jsonrpc": JSONRPC_VERSION,
+                "method": methods::BUILD_DIAGNOSTICS,
+                "params": params,
+            }));
+        }
+        reevaluate(
+            &workspace,
+            &subscriptions,
+            &notifications,
+            &changed,
+            &changes,
+        )
+        .await;
+    }
+}
+
+/// Evaluate again the subscribed values whose project was `rebuilt` with new
+/// IR, or whose input files are among `changes`, notifying clients of each
+/// result
+async fn reevaluate(
+    workspace: &Mutex<Option<Workspace>>,
+    subscriptions: &Mutex<Subscriptions>,
+    notifications: &broadcast::Sender<Value>,
+    rebuilt: &HashSet<String>,
+    changes: &[FileChange],
+) {
+    let (root, affected) = {
+        let workspace = workspace.lock().unwrap();
+        let Some(workspace) = workspace.as_ref() else {
+            return;
+        };
+        let affected: Vec<_> = subscriptions
+            .lock()
+            .unwrap()
+            .affected(workspace, rebuilt, changes)
+            .into_iter()
+            .filter_map(|(id, request)| {
+                let project = workspace.get_project(&request.project)?.clone();
+                Some((id, request, project))
+            })
+            .collect();
+        (workspace.root.clone(), affected)
+    };
+    for (id, request, project) in affected {
+        let root = root.clone();
+        match tokio::task::spawn_blocking(move || evaluate(id, &request, &root, &project)).await {
+            Ok(result) => {
+                let _ = notifications.send(json!({
+                    "jsonrpc": JSONRPC_VERSION,
+                    "method": methods::EVALUATION_RESULT,
+                    "params": result,
+                }));
+            }
+            Err(e) => warn!("Failed to evaluate subscription {}: {}", id, e),
+        }
+    }
+}
+
+fn set_project_state(workspace: &Mutex<Option<Workspace>>, name: &str, state: ProjectState) {
+    if let Some(project) = workspace
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|w| w.get_project_mut(name))
+    {
+        project.state = state;
+    }
+}
+
+/// Write `message` to a client as one line
+async fn write_line(writer: &mut (impl AsyncWrite + Unpin), message: &str) -> Result<()> {
+    writer.write_all(message.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Params of a request; omitted params are read as an empty object
+fn parse_params<T: DeserializeOwned>(params: Value) -> std::result::Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| {
+        rpc_error(
+            error_codes::INVALID_PARAMS,
+            format!("Invalid params: {}", e),
+        )
+    })
+}
+
+fn rpc_error(code: i32, message: impl Into<String>) -> RpcError {
+    RpcError {
+        code,
+        message: message.into(),
+        data: None,
+    }
+}
+
+fn daemon_error(err: DaemonError) -> RpcError {
+    RpcError::from_daemon_error(&err)
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({ "jsonrpc": JSONRPC_VERSION, "error": error, "id": id })
+}
+
+fn workspace_info(workspace: &Workspace) -> Value {
+    json!({
+        "root": workspace.root,
+        "name": workspace.name,
+        "state": workspace.state,
+        "projects": projects_info(workspace),
+    })
+}
+
+/// Projects of the workspace, by name
+fn projects_info(workspace: &Workspace) -> Value {
+    let mut projects: Vec<&Project> = workspace.projects.values().collect();
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+    projects
+        .into_iter()
+        .map(|project| project_info(workspace, project))
+        .collect()
+}
+
+fn project_info(workspace: &Workspace, project: &Project) -> Value {
+    let path = project
+        .path
+        .strip_prefix(&workspace.root)
+        .unwrap_or(&project.path);
+    json!({
+        "name": project.name,
+        "version": project.version,
+        "path": path,
+        "state": project.state,
+        "sourceDir": project.source_dir,
+    })
+}
+
+/// Call `method` on the daemon listening on `address`
+pub async fn request(address: SocketAddr, method: &str, params: Value) -> Result<Value> {
+    let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(address))
+        .await
+        .map_err(|_| {
+            DaemonError::Other(anyhow::anyhow!("Timed out connecting to {}", address))
+        })??;
+    let (reader, mut writer) = stream.into_split();
+    let message =
+        json!({ "jsonrpc": JSONRPC_VERSION, "method": method, "params": params, "id": 1 });
+    writer.write_all(message.to_string().as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    let mut response: Value = serde_json::from_str(&line)?;
+    if let Some(error) = response.get("error") {
+        let error: RpcError = serde_json::from_value(error.clone())?;
+        return Err(DaemonError::Rpc {
+            code: error.code,
+            message: error.message,
+        });
+    }
+    Ok(response
+        .get_mut("result")
+        .map(Value::take)
+        .unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_extension_sdk::types::CodeFix;
+
+    fn server(root: &Path) -> DaemonServer {
+        let registry = ExtensionRegistry::new(root.to_path_buf(), root.join("output")).unwrap();
+        DaemonServer::new(registry)
+    }
+
+    async fn call(server: &DaemonServer, message: Value) -> Value {
+        let response = server.handle(&message.to_string()).await.unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_routed_to_the_workspace() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().join("packages").join("core");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(
+            temp.path().join("morphir.toml"),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            project.join("morphir.toml"),
+            "[project]\nname = \"my-org/core\"\nversion = \"1.0.0\"\nsource_directory = \"src\"\n",
+        )
+        .unwrap();
+        let server = server(temp.path());
+
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "workspace/info", "id": "a" }),
+        )
+        .await;
+        assert_eq!(response["id"], "a");
+        assert_eq!(
+            response["error"]["message"],
+            "Workspace error: No workspace is open"
+        );
+
+        let response = call(
+            &server,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "workspace/open",
+                "params": { "root": temp.path() },
+                "id": 1
+            }),
+        )
+        .await;
+        assert_eq!(response["result"]["state"], "open");
+        assert_eq!(
+            response["result"]["projects"],
+            json!([{
+                "name": "my-org/core",
+                "version": "1.0.0",
+                "path": "packages/core",
+                "state": "unloaded",
+                "sourceDir": "src"
+            }])
+        );
+
+        // A batch: a notification gets no response, the rest answer in order
+        let response = call(
+            &server,
+            json!([
+                { "jsonrpc": "2.0", "method": "daemon/health" },
+                { "jsonrpc": "2.0", "method": "workspace/projectInfo", "params": { "name": "my-org/core" }, "id": 2 },
+                { "jsonrpc": "2.0", "method": "workspace/projectInfo", "params": {}, "id": 3 },
+                { "jsonrpc": "2.0", "method": "workspace/build", "id": 4 }
+            ]),
+        )
+        .await;
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"]["version"], "1.0.0");
+        assert_eq!(responses[1]["error"]["code"], error_codes::INVALID_PARAMS);
+        assert_eq!(responses[2]["error"]["code"], error_codes::METHOD_NOT_FOUND);
+
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "daemon/health", "id": null }),
+        )
+        .await;
+        assert_eq!(response["result"]["workspace"]["projects"], 1);
+        assert_eq!(
+            server
+                .handle("{not json")
+                .await
+                .map(|r| { serde_json::from_str::<Value>(&r).unwrap()["error"]["code"].clone() }),
+            Some(json!(error_codes::PARSE_ERROR))
+        );
+    }
+
+    /// Reports a failed build listing the sources it was given
+    struct ListingBuilder;
+
+    #[async_trait::async_trait]
+    impl ProjectBuilder for ListingBuilder {
+        async fn build(&self, _root: &Path, project: &Project) -> Result<BuildReport> {
+            let sources = std::fs::read_dir(project.path.join(&project.source_dir))?.count();
+            Ok(BuildReport {
+                project: project.name.clone(),
+                success: false,
+                compiled: sources,
+                cached: 0,
+                diagnostics: vec![Diagnostic {
+                    severity: DiagnosticSeverity::Error,
+                    code: Some("E001".to_string()),
+                    message: "type mismatch".to_string(),
+                    location: None,
+                    related: Vec::new(),
+                    fixes: vec![CodeFix {
+                        title: "Convert to `Int`".to_string(),
+                        edits: Vec::new(),
+                    }],
+                }],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changes_rebuild_and_notify_clients() {
+        let temp = tempfile::tempdir().unwrap();
+        let src = temp.path().join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(
+            temp.path().join("morphir.toml"),
+            "[project]\nname = \"my-org/core\"\nversion = \"1.0.0\"\nsource_directory = \"src\"\n[frontend]\nlanguage = \"gleam\"\n",
+        )
+        .unwrap();
+        let server = server(temp.path())
+            .with_watch(WatchConfig {
+                debounce: Duration::from_millis(100),
+                auto_rebuild: true,
+            })
+            .with_builder(Arc::new(ListingBuilder));
+        let mut notifications = server.subscribe();
+        server.open_workspace(temp.path().to_path_buf()).unwrap();
+
+        std::fs::write(src.join("a.gleam"), "pub fn a() { 1 }").unwrap();
+        let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+            .await
+            .expect("a build notification")
+            .unwrap();
+        assert_eq!(notification["method"], methods::BUILD_DIAGNOSTICS);
+        assert_eq!(notification["params"]["project"], "my-org/core");
+        assert_eq!(notification["params"]["compiled"], 1);
+        assert_eq!(
+            notification["params"]["diagnostics"][0]["message"],
+            "type mismatch"
+        );
+        assert_eq!(
+            notification["params"]["diagnostics"][0]["fixes"][0]["title"],
+            "Convert to `Int`"
+        );
+        assert!(notification["params"]["duration"].is_u64());
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "workspace/projectInfo", "params": { "name": "my-org/core" }, "id": 1 }),
+        )
+        .await;
+        assert_eq!(response["result"]["state"], "error");
+
+        // The module index follows the change
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "workspace/symbols", "params": { "query": "A" }, "id": 4 }),
+        )
+        .await;
+        assert_eq!(
+            response["result"],
+            json!([{
+                "name": "a",
+                "kind": "function",
+                "project": "my-org/core",
+                "module": "a",
+                "path": "src/a.gleam",
+                "line": 1
+            }])
+        );
+        assert!(
+            temp.path()
+                .join(".morphir/cache/index/modules.json")
+                .exists()
+        );
+
+        // The build is recorded in the workspace metrics
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "workspace/metrics", "id": 5 }),
+        )
+        .await;
+        let metrics = &response["result"];
+        assert_eq!(metrics["modules"], 1);
+        assert_eq!(metrics["definitions"], 1);
+        assert_eq!(metrics["builds"], 1);
+        assert_eq!(metrics["failedBuilds"], 1);
+        assert_eq!(metrics["diagnostics"]["errors"], 1);
+        assert_eq!(metrics["history"][0]["project"], "my-org/core");
+
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "workspace/watch", "params": { "enabled": false }, "id": 2 }),
+        )
+        .await;
+        assert_eq!(
+            response["result"],
+            json!({ "state": "stopped", "watchedPaths": [] })
+        );
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "workspace/watch", "id": 3 }),
+        )
+        .await;
+        assert_eq!(response["result"]["state"], "running");
+        assert_eq!(
+            response["result"]["watchedPaths"],
+            json!(["morphir.toml", "src"])
+        );
+    }
+
+    /// Succeeds, recording which projects were built and which rebuilt
+    #[derive(Default)]
+    struct RecordingBuilder {
+        builds: Mutex<Vec<String>>,
+    }
+
+    impl RecordingBuilder {
+        fn record(&self, build: &str, project: &Project) -> Result<BuildReport> {
+            self.builds
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", build, project.name));
+            Ok(BuildReport {
+                project: project.name.clone(),
+                success: true,
+                compiled: 1,
+                cached: 0,
+                diagnostics: Vec::new(),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ProjectBuilder for RecordingBuilder {
+        async fn build(&self, _root: &Path, project: &Project) -> Result<BuildReport> {
+            self.record("build", project)
+        }
+
+        async fn rebuild(&self, _root: &Path, project: &Project) -> Result<BuildReport> {
+            self.record("rebuild", project)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dependents_rebuild_after_their_dependencies() {
+        let temp = tempfile::tempdir().unwrap();
+        for (dir, dependencies) in [
+            ("core", ""),
+            ("domain", "\"my-org/core\" = { path = \"../core\" }\n"),
+            ("app", "\"my-org/domain\" = \"1.0.0\"\n"),
+            ("tools", ""),
+        ] {
+            let project = temp.path().join("packages").join(dir);
+            std::fs::create_dir_all(project.join("src")).unwrap();
+            std::fs::write(
+                project.join("morphir.toml"),
+                format!(
+                    "[project]\nname = \"my-org/{}\"\nversion = \"1.0.0\"\nsource_directory = \"src\"\n[dependencies]\n{}",
+                    dir, dependencies
+                ),
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            temp.path().join("morphir.toml"),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        let builder = Arc::new(RecordingBuilder::default());
+        let server = server(temp.path())
+            .with_watch(WatchConfig {
+                debounce: Duration::from_millis(100),
+                auto_rebuild: true,
+            })
+            .with_builder(builder.clone());
+        let mut notifications = server.subscribe();
+        server.open_workspace(temp.path().to_path_buf()).unwrap();
+
+        let src = temp.path().join("packages/core/src");
+        std::fs::write(src.join("a.gleam"), "pub fn a() { 1 }").unwrap();
+        let mut built = Vec::new();
+        for _ in 0..3 {
+            let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+                .await
+                .expect("a build notification")
+                .unwrap();
+            built.push(
+                notification["params"]["project"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+            );
+        }
+        assert_eq!(built, vec!["my-org/core", "my-org/domain", "my-org/app"]);
+        assert_eq!(
+            *builder.builds.lock().unwrap(),
+            vec![
+                "build my-org/core",
+                "rebuild my-org/domain",
+                "rebuild my-org/app"
+            ]
+        );
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "workspace/projectInfo", "params": { "name": "my-org/tools" }, "id": 1 }),
+        )
+        .await;
+        assert_eq!(response["result"]["state"], "unloaded");
+    }
+
+    #[tokio::test]
+    async fn test_subscribed_values_are_evaluated_again_on_change() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp.path().join("src")).unwrap();
+        std::fs::write(
+            temp.path().join("morphir.toml"),
+            "[project]\nname = \"my-org/shop\"\nversion = \"1.0.0\"\nsource_directory = \"src\"\n",
+        )
+        .unwrap();
+        crate::evaluate::tests::write_library(&temp.path().join("ir.json"));
+        std::fs::write(temp.path().join("n.json"), "21").unwrap();
+        let server = server(temp.path())
+            .with_watch(WatchConfig {
+                debounce: Duration::from_millis(100),
+                auto_rebuild: true,
+            })
+            .with_builder(Arc::new(RecordingBuilder::default()));
+        let mut notifications = server.subscribe();
+        server.open_workspace(temp.path().to_path_buf()).unwrap();
+
+        let response = call(
+            &server,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "evaluation/subscribe",
+                "params": {
+                    "project": "my-org/shop",
+                    "target": "my-org/shop:orders#double",
+                    "inputs": ["n.json"],
+                    "ir": "ir.json"
+                },
+                "id": 1
+            }),
+        )
+        .await;
+        let subscription = response["result"]["subscription"].clone();
+        assert_eq!(response["result"]["result"]["value"], 42);
+        assert_eq!(response["result"]["result"]["success"], true);
+
+        std::fs::write(temp.path().join("n.json"), "5").unwrap();
+        let result = loop {
+            let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+                .await
+                .expect("an evaluation notification")
+                .unwrap();
+            if notification["method"] == "evaluation/result" {
+                break notification["params"].clone();
+            }
+        };
+        assert_eq!(result["subscription"], subscription);
+        assert_eq!(result["value"], 10);
+
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "evaluation/unsubscribe", "params": { "subscription": subscription }, "id": 2 }),
+        )
+        .await;
+        assert_eq!(response["result"], true);
+        let response = call(
+            &server,
+            json!({ "jsonrpc": "2.0", "method": "evaluation/subscribe", "params": { "project": "my-org/nope", "target": "a:b#c" }, "id": 3 }),
+        )
+        .await;
+        assert_eq!(
+            response["error"]["message"],
+            "Project error: No such project: my-org/nope"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tcp_server_records_itself_until_shut_down() {
+        let temp = tempfile::tempdir().unwrap();
+        let state = temp.path().join("state");
+        let server = Arc::new(server(temp.path()).with_info_dir(&state));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let serving = tokio::spawn(Arc::clone(&server).serve(Transport::Tcp(listener)));
+
+        let health = request(address, methods::HEALTH, Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(health["status"], "healthy");
+        let info = DaemonInfo::read(&state).unwrap().unwrap();
+        assert_eq!(info.address, address);
+        assert_eq!(info.pid, std::process::id());
+
+        let extensions = request(address, methods::LIST_EXTENSIONS, Value::Null)
+            .await
+            .unwrap();
+        assert!(
+            extensions["builtins"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|b| b["id"] == "migrate" && b["type"] == "transform")
+        );
+        let err = request(
+            address,
+            methods::EXECUTE_EXTENSION,
+            json!({ "id": "nope", "type": "transform", "input": {} }),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                DaemonError::Rpc {
+                    code: error_codes::EXTENSION_ERROR,
+                    ..
+                }
+            ),
+            "{}",
+            err
+        );
+
+        assert_eq!(
+            request(address, methods::SHUTDOWN, Value::Null)
+                .await
+                .unwrap(),
+            Value::Null
+        );
+        assert_eq!(serving.await.unwrap().unwrap(), ShutdownKind::Clean);
+        assert!(DaemonInfo::read(&state).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_notifies_clients_and_drains_requests() {
+        let temp = tempfile::tempdir().unwrap();
+        let server = Arc::new(server(temp.path()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let serving = tokio::spawn(Arc::clone(&server).serve(Transport::Tcp(listener)));
+
+        // An idle client is told the daemon is going away
+        let idle = TcpStream::connect(address).await.unwrap();
+        let mut idle = BufReader::new(idle).lines();
+        request(address, methods::HEALTH, Value::Null)
+            .await
+            .unwrap();
+        request(address, methods::SHUTDOWN, Value::Null)
+            .await
+            .unwrap();
+        let notification: Value =
+            serde_json::from_str(&idle.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(notification["method"], methods::SHUTTING_DOWN);
+        assert_eq!(notification["params"]["drainTimeoutMs"], 30_000);
+        assert_eq!(idle.next_line().await.unwrap(), None);
+        assert_eq!(serving.await.unwrap().unwrap(), ShutdownKind::Clean);
+
+        // Requests read after the shutdown are refused
+        let response = call(
+            &server,
+            json!([
+                { "jsonrpc": "2.0", "method": methods::LIST_EXTENSIONS, "id": 1 },
+                { "jsonrpc": "2.0", "method": methods::HEALTH, "id": 2 },
+            ]),
+        )
+        .await;
+        assert_eq!(response[0]["error"]["code"], error_codes::SHUTTING_DOWN);
+        assert_eq!(response[1]["result"]["status"], "healthy");
+
+        // Connections still busy at the drain timeout are cancelled
+        let draining = self::server(temp.path()).with_drain_timeout(Duration::from_millis(50));
+        let mut connections = JoinSet::new();
+        connections.spawn(std::future::pending());
+        assert_eq!(
+            draining.drain(connections).await.unwrap(),
+            ShutdownKind::Forced
+        );
+        let mut connections = JoinSet::new();
+        connections.spawn(async { Ok(()) });
+        assert_eq!(
+            draining.drain(connections).await.unwrap(),
+            ShutdownKind::Clean
+        );
+        assert_ne!(
+            ShutdownKind::Clean.exit_code(),
+            ShutdownKind::Forced.exit_code()
+        );
+    }
+}
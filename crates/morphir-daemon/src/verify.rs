@@ -0,0 +1,461 @@
+//! Checking generated artifacts with the target language's toolchain
+//!
+//! A backend can produce code that is well formed as far as Morphir knows but
+//! that its target language rejects. [`verify`] runs the target's own checker
+//! (`gleam check`, `tsc --noEmit`, `wasm-validate`) over an output directory
+//! through a [`CommandRunner`], parses what it reports into diagnostics and,
+//! using the source map the backend returned, names the Morphir definition each
+//! reported line was generated from.
+
+use std::path::Path;
+
+use morphir_extension_sdk::types::{
+    Diagnostic, DiagnosticSeverity, SourceLocation, SourceMapEntry,
+};
+
+use crate::command::{CommandOutput, CommandRunner};
+use crate::error::Result;
+
+/// How a tool reports problems
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// `error: title` followed by `┌─ path:line:col`
+    Gleam,
+    /// `path(line,col): error TS1234: message`
+    TypeScript,
+    /// `path:offset: error: message`
+    Wasm,
+    /// `path:line:col: message` or `path:line: message`
+    Generic,
+}
+
+/// A target language's checker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toolchain {
+    pub program: String,
+    pub args: Vec<String>,
+    pub format: OutputFormat,
+    /// Run the tool once per file with this extension, passing the file's
+    /// path, instead of once for the whole directory
+    pub per_file: Option<String>,
+}
+
+impl Toolchain {
+    /// The checker for a backend target, if Morphir knows one
+    pub fn for_target(target: &str) -> Option<Self> {
+        let (program, args, format, per_file): (&str, &[&str], _, _) =
+            match target.to_lowercase().as_str() {
+                "gleam" => ("gleam", &["check"], OutputFormat::Gleam, None),
+                "typescript" | "ts" => (
+                    "tsc",
+                    &["--noEmit", "--pretty", "false", "-p", "."],
+                    OutputFormat::TypeScript,
+                    None,
+                ),
+                "wasm" => ("wasm-validate", &[], OutputFormat::Wasm, Some("wasm")),
+                _ => return None,
+            };
+        Some(Self {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            format,
+            per_file: per_file.map(str::to_string),
+        })
+    }
+
+    /// A checker given as a command line, e.g. `gleam check --target js`.
+    /// Its output is read as `path:line:col: message`.
+    pub fn custom(command: &str) -> Option<Self> {
+        let mut words = command.split_whitespace().map(str::to_string);
+        Some(Self {
+            program: words.next()?,
+            args: words.collect(),
+            format: OutputFormat::Generic,
+            per_file: None,
+        })
+    }
+
+    /// The command line, as shown to users
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Lookup from generated lines to the definitions they were generated from
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    entries: Vec<SourceMapEntry>,
+}
+
+impl SourceMap {
+    /// A source map whose entry paths are relative to the output directory
+    pub fn new(entries: Vec<SourceMapEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// Canonical FQName of the definition generated at `line` of `path`
+    pub fn lookup(&self, path: &str, line: u32) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.path == path && (entry.start_line..=entry.end_line).contains(&line))
+            .map(|entry| entry.fqname.as_str())
+    }
+}
+
+/// Outcome of checking an output directory
+#[derive(Debug, Clone)]
+pub struct Verification {
+    /// Command line of the checker
+    pub tool: String,
+    pub success: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Check the artifacts in `output_dir` with `toolchain`
+pub fn verify(
+    toolchain: &Toolchain,
+    output_dir: &Path,
+    source_map: &SourceMap,
+    runner: &CommandRunner,
+) -> Result<Verification> {
+    let outputs = match &toolchain.per_file {
+        Some(extension) => files_with_extension(output_dir, extension)
+            .into_iter()
+            .map(|file| {
+                let mut args = toolchain.args.clone();
+                args.push(file);
+                runner.run(&toolchain.program, &args, output_dir)
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => vec![runner.run(&toolchain.program, &toolchain.args, output_dir)?],
+    };
+
+    let mut success = true;
+    let mut diagnostics = Vec::new();
+    for output in &outputs {
+        success &= output.success;
+        let text = format!("{}\n{}", output.stdout, output.stderr);
+        let mut reported = parse_output(toolchain.format, &text);
+        if !output.success && !reported.iter().any(is_error) {
+            reported.push(fallback(toolchain, output));
+        }
+        diagnostics.extend(reported);
+    }
+
+    for diagnostic in &mut diagnostics {
+        let Some(location) = &mut diagnostic.location else {
+            continue;
+        };
+        location.file = relative_to(&location.file, output_dir);
+        if let Some(fqname) = source_map.lookup(&location.file, location.start_line) {
+            diagnostic.message = format!("{} (generated from {})", diagnostic.message, fqname);
+        }
+    }
+    Ok(Verification {
+        tool: toolchain.command_line(),
+        success,
+        diagnostics,
+    })
+}
+
+fn is_error(diagnostic: &Diagnostic) -> bool {
+    diagnostic.severity == DiagnosticSeverity::Error
+}
+
+/// Diagnostic for a tool that failed without reporting anything we recognise
+fn fallback(toolchain: &Toolchain, output: &CommandOutput) -> Diagnostic {
+    let status = output.code.map_or("was terminated".to_string(), |code| {
+        format!("exited with code {}", code)
+    });
+    let detail = [output.stderr.trim(), output.stdout.trim()]
+        .into_iter()
+        .find(|text| !text.is_empty());
+    let mut message = format!("{} {}", toolchain.command_line(), status);
+    if let Some(detail) = detail {
+        message.push_str(":\n");
+        message.push_str(detail);
+    }
+    diagnostic(DiagnosticSeverity::Error, None, message, None)
+}
+
+fn diagnostic(
+    severity: DiagnosticSeverity,
+    code: Option<String>,
+    message: String,
+    location: Option<SourceLocation>,
+) -> Diagnostic {
+    Diagnostic {
+        severity,
+        code,
+        message,
+        location,
+        related: vec![],
+        fixes: vec![],
+    }
+}
+
+fn location(file: &str, line: u32, col: u32) -> SourceLocation {
+    SourceLocation {
+        file: file.to_string(),
+        start_line: line,
+        start_col: col,
+        end_line: line,
+        end_col: col,
+    }
+}
+
+fn severity(word: &str) -> Option<DiagnosticSeverity> {
+    match word.trim() {
+        "error" | "fatal error" => Some(DiagnosticSeverity::Error),
+        "warning" => Some(DiagnosticSeverity::Warning),
+        "note" | "info" => Some(DiagnosticSeverity::Info),
+        "hint" | "help" => Some(DiagnosticSeverity::Hint),
+        _ => None,
+    }
+}
+
+/// Diagnostics in a tool's output, in the order reported
+pub fn parse_output(format: OutputFormat, text: &str) -> Vec<Diagnostic> {
+    match format {
+        OutputFormat::Gleam => parse_gleam(text),
+        OutputFormat::TypeScript => text.lines().filter_map(parse_typescript_line).collect(),
+        OutputFormat::Wasm => text.lines().filter_map(parse_wasm_line).collect(),
+        OutputFormat::Generic => text.lines().filter_map(parse_generic_line).collect(),
+    }
+}
+
+fn parse_gleam(text: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut expecting_location = false;
+    for line in text.lines() {
+        if let Some((word, title)) = line.split_once(':')
+            && let Some(severity) = severity(word)
+            && !word.starts_with(' ')
+        {
+            diagnostics.push(diagnostic(severity, None, title.trim().to_string(), None));
+            expecting_location = true;
+        } else if expecting_location && let Some((_, place)) = line.split_once("┌─") {
+            expecting_location = false;
+            let mut parts = place.trim().rsplitn(3, ':');
+            let (Some(col), Some(line), Some(file)) = (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            if let (Ok(line), Ok(col), Some(last)) =
+                (line.parse(), col.parse(), diagnostics.last_mut())
+            {
+                last.location = Some(location(file, line, col));
+            }
+        }
+    }
+    diagnostics
+}
+
+fn parse_typescript_line(line: &str) -> Option<Diagnostic> {
+    let (file, rest) = line.split_once('(')?;
+    let (position, rest) = rest.split_once("): ")?;
+    let (line, col) = position.split_once(',')?;
+    let (kind, message) = rest.split_once(": ")?;
+    let (word, code) = kind.rsplit_once(' ')?;
+    Some(diagnostic(
+        severity(word)?,
+        Some(code.to_string()),
+        message.trim().to_string(),
+        Some(location(file.trim(), line.parse().ok()?, col.parse().ok()?)),
+    ))
+}
+
+fn parse_wasm_line(line: &str) -> Option<Diagnostic> {
+    let (file, rest) = line.split_once(':')?;
+    let (offset, rest) = rest.split_once(':')?;
+    let (word, message) = rest.split_once(':')?;
+    // Binary files have no lines; keep the offset in the message
+    Some(diagnostic(
+        severity(word)?,
+        None,
+        format!("{} (at offset 0x{})", message.trim(), offset.trim()),
+        Some(location(file.trim(), 0, 0)),
+    ))
+}
+
+fn parse_generic_line(line: &str) -> Option<Diagnostic> {
+    let mut parts = line.splitn(4, ':');
+    let file = parts.next()?.trim();
+    let line_number: u32 = parts.next()?.trim().parse().ok()?;
+    let third = parts.next()?;
+    let (col, message) = match third.trim().parse::<u32>() {
+        Ok(col) => (col, parts.next()?.to_string()),
+        Err(_) => (
+            0,
+            std::iter::once(third)
+                .chain(parts)
+                .collect::<Vec<_>>()
+                .join(":"),
+        ),
+    };
+    let (severity, message) = match message.split_once(':') {
+        Some((word, rest)) if severity(word).is_some() => (severity(word)?, rest),
+        _ => (DiagnosticSeverity::Error, message.as_str()),
+    };
+    if file.is_empty() {
+        return None;
+    }
+    Some(diagnostic(
+        severity,
+        None,
+        message.trim().to_string(),
+        Some(location(file, line_number, col)),
+    ))
+}
+
+/// `file` relative to `dir`, `/`-separated, when it lies inside it
+fn relative_to(file: &str, dir: &Path) -> String {
+    let path = Path::new(file);
+    let relative = if path.is_absolute() {
+        path.strip_prefix(dir)
+            .map(Path::to_path_buf)
+            .ok()
+            .or_else(|| {
+                // The tool may have resolved symbolic links in the directory
+                let dir = dir.canonicalize().ok()?;
+                let file = path.canonicalize().ok()?;
+                file.strip_prefix(dir).map(Path::to_path_buf).ok()
+            })
+    } else {
+        Some(path.to_path_buf())
+    };
+    match relative {
+        Some(relative) => relative
+            .to_string_lossy()
+            .replace('\\', "/")
+            .trim_start_matches("./")
+            .to_string(),
+        None => file.to_string(),
+    }
+}
+
+/// Paths, relative to `dir`, of the files under it with `extension`
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == extension)
+                && let Ok(relative) = path.strip_prefix(dir)
+            {
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_output_is_parsed_into_diagnostics() {
+        let gleam = "\
+error: Unknown variable
+  ┌─ /tmp/out/src/orders.gleam:9:3
+  │
+9 │   totl
+  │   ^^^^ Did you mean `total`?
+
+warning: Unused variable
+  ┌─ src/orders.gleam:4:7
+";
+        let diagnostics = parse_output(OutputFormat::Gleam, gleam);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[0].message, "Unknown variable");
+        let location = diagnostics[0].location.as_ref().unwrap();
+        assert_eq!(
+            (
+                location.file.as_str(),
+                location.start_line,
+                location.start_col
+            ),
+            ("/tmp/out/src/orders.gleam", 9, 3)
+        );
+        assert_eq!(diagnostics[1].severity, DiagnosticSeverity::Warning);
+
+        let typescript = "src/orders.ts(12,5): error TS2304: Cannot find name 'totl'.\n";
+        let diagnostics = parse_output(OutputFormat::TypeScript, typescript);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("TS2304"));
+        assert_eq!(diagnostics[0].message, "Cannot find name 'totl'.");
+        assert_eq!(diagnostics[0].location.as_ref().unwrap().start_line, 12);
+
+        let wasm = "orders.wasm:000002a: error: type mismatch in i32.add\n";
+        let diagnostics = parse_output(OutputFormat::Wasm, wasm);
+        assert_eq!(
+            diagnostics[0].message,
+            "type mismatch in i32.add (at offset 0x000002a)"
+        );
+        assert_eq!(
+            diagnostics[0].location.as_ref().unwrap().file,
+            "orders.wasm"
+        );
+
+        let generic = "src/a.gleam:3:1: warning: shadowed\nsrc/b.gleam:7: bad\nBuild failed\n";
+        let diagnostics = parse_output(OutputFormat::Generic, generic);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+        assert_eq!(diagnostics[0].message, "shadowed");
+        assert_eq!(diagnostics[1].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[1].location.as_ref().unwrap().start_col, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_maps_errors_to_definitions() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp.path().join("src")).unwrap();
+        let source_map = SourceMap::new(vec![SourceMapEntry {
+            path: "src/orders.gleam".to_string(),
+            start_line: 8,
+            end_line: 10,
+            fqname: "my-org/shop:orders#total".to_string(),
+        }]);
+        let script = format!(
+            "echo '{}/src/orders.gleam:9:3: error: Unknown variable' >&2; exit 1",
+            temp.path().display()
+        );
+        let toolchain = Toolchain {
+            args: vec!["-c".to_string(), script],
+            ..Toolchain::custom("sh").unwrap()
+        };
+        let verification =
+            verify(&toolchain, temp.path(), &source_map, &CommandRunner::new()).unwrap();
+        assert!(!verification.success);
+        assert_eq!(verification.diagnostics.len(), 1);
+        assert_eq!(
+            verification.diagnostics[0].message,
+            "Unknown variable (generated from my-org/shop:orders#total)"
+        );
+        assert_eq!(
+            verification.diagnostics[0].location.as_ref().unwrap().file,
+            "src/orders.gleam"
+        );
+
+        // A failure the tool does not explain in a form we recognise
+        let toolchain = Toolchain::custom("sh -c false").unwrap();
+        let verification =
+            verify(&toolchain, temp.path(), &source_map, &CommandRunner::new()).unwrap();
+        assert!(!verification.success);
+        assert_eq!(
+            verification.diagnostics[0].message,
+            "sh -c false exited with code 1"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Dependencies between the projects of a workspace
+//!
+//! A project depends on another project of the workspace when its
+//! `[dependencies]` name that project, or point at its directory with
+//! `path`. [`ProjectGraph`] orders builds so every project is built after the
+//! projects it depends on, and finds the projects downstream of a change.
+//! Dependencies outside the workspace are not part of the graph.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use morphir_common::config::DependencySpec;
+
+use super::Workspace;
+use crate::error::{DaemonError, Result};
+
+/// Dependencies between the projects of a workspace
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectGraph {
+    /// Workspace projects each project depends on, by project name
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ProjectGraph {
+    /// Read the dependencies between the projects of `workspace`
+    pub fn new(workspace: &Workspace) -> Self {
+        let directories: Vec<(PathBuf, &str)> = workspace
+            .projects
+            .values()
+            .map(|project| (normalize(&project.path), project.name.as_str()))
+            .collect();
+
+        let mut dependencies = BTreeMap::new();
+        for project in workspace.projects.values() {
+            let depends_on = project
+                .config
+                .dependencies
+                .iter()
+                .filter_map(|(name, spec)| {
+                    if workspace.projects.contains_key(name) {
+                        return Some(name.clone());
+                    }
+                    let DependencySpec::Detailed(detailed) = spec else {
+                        return None;
+                    };
+                    let path = normalize(&project.path.join(detailed.path.as_ref()?));
+                    directories
+                        .iter()
+                        .find(|(directory, _)| *directory == path)
+                        .map(|(_, name)| name.to_string())
+                })
+                .collect();
+            dependencies.insert(project.name.clone(), depends_on);
+        }
+        Self { dependencies }
+    }
+
+    /// Workspace projects that `project` depends on directly, sorted
+    pub fn dependencies(&self, project: &str) -> Vec<&str> {
+        self.dependencies
+            .get(project)
+            .map(|names| names.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Projects that depend on `project` directly, sorted
+    pub fn dependents(&self, project: &str) -> Vec<&str> {
+        self.dependencies
+            .iter()
+            .filter(|(_, depends_on)| depends_on.contains(project))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Every project, each after the projects it depends on. Projects with no
+    /// order between them are sorted by name.
+    ///
+    /// Fails with [`DaemonError::ProjectCycle`] when dependencies form a
+    /// cycle.
+    pub fn build_order(&self) -> Result<Vec<String>> {
+        self.order(self.dependencies.keys().cloned().collect())
+    }
+
+    /// `changed` projects and every project depending on them, directly or
+    /// not, in build order
+    pub fn downstream(&self, changed: &[String]) -> Result<Vec<String>> {
+        let mut affected: BTreeSet<String> = BTreeSet::new();
+        let mut pending: Vec<&str> = changed
+            .iter()
+            .map(String::as_str)
+            .filter(|name| self.dependencies.contains_key(*name))
+            .collect();
+        while let Some(name) = pending.pop() {
+            if affected.insert(name.to_string()) {
+                pending.extend(self.dependents(name));
+            }
+        }
+        self.order(affected)
+    }
+
+    /// Order `projects` so each comes after those of its dependencies that
+    /// are also in `projects`
+    fn order(&self, projects: BTreeSet<String>) -> Result<Vec<String>> {
+        let mut remaining: BTreeMap<&str, BTreeSet<&str>> = projects
+            .iter()
+            .map(|name| {
+                let depends_on = self
+                    .dependencies(name)
+                    .into_iter()
+                    .filter(|dependency| projects.contains(*dependency))
+                    .collect();
+                (name.as_str(), depends_on)
+            })
+            .collect();
+
+        let mut ordered = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let next = remaining
+                .iter()
+                .find(|(_, depends_on)| depends_on.is_empty())
+                .map(|(name, _)| *name);
+            let Some(next) = next else {
+                return Err(DaemonError::ProjectCycle(find_cycle(&remaining)));
+            };
+            remaining.remove(next);
+            for depends_on in remaining.values_mut() {
+                depends_on.remove(next);
+            }
+            ordered.push(next.to_string());
+        }
+        Ok(ordered)
+    }
+}
+
+/// A cycle among projects that all still have an unordered dependency, each
+/// depending on the next and closed with its first project, e.g. `a, b, a`
+fn find_cycle(remaining: &BTreeMap<&str, BTreeSet<&str>>) -> Vec<String> {
+    let Some(&start) = remaining.keys().next() else {
+        return Vec::new();
+    };
+    let mut path = vec![start];
+    let mut current = start;
+    loop {
+        let Some(&next) = remaining.get(current).and_then(|d| d.iter().next()) else {
+            return path.into_iter().map(String::from).collect();
+        };
+        if let Some(position) = path.iter().position(|&name| name == next) {
+            let mut cycle: Vec<String> = path[position..].iter().map(|s| s.to_string()).collect();
+            // Start from the first project by name
+            let first = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+            cycle.rotate_left(first);
+            cycle.push(cycle[0].clone());
+            return cycle;
+        }
+        path.push(next);
+        current = next;
+    }
+}
+
+/// `path` with symbolic links and `..` resolved, when it exists
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A workspace of projects under `packages/<dir>`, each with the given
+    /// `[dependencies]` table
+    fn workspace(root: &Path, projects: &[(&str, &str)]) -> Workspace {
+        for (dir, dependencies) in projects {
+            let project = root.join("packages").join(dir);
+            std::fs::create_dir_all(&project).unwrap();
+            std::fs::write(
+                project.join("morphir.toml"),
+                format!(
+                    "[project]\nname = \"my-org/{}\"\nversion = \"1.0.0\"\n\n[dependencies]\n{}",
+                    dir, dependencies
+                ),
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            root.join("morphir.toml"),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        Workspace::open(root.to_path_buf()).unwrap()
+    }
+
+    #[test]
+    fn test_projects_build_after_their_dependencies() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = workspace(
+            temp.path(),
+            &[
+                ("core", "\"morphir/sdk\" = \"1.0.0\"\n"),
+                ("domain", "\"my-org/core\" = { workspace = true }\n"),
+                ("api", "\"my-org/domain\" = { path = \"../domain\" }\n"),
+                (
+                    "app",
+                    "\"my-org/api\" = \"1.0.0\"\n\"my-org/core\" = \"1.0.0\"\n",
+                ),
+                ("tools", ""),
+            ],
+        );
+        let graph = workspace.project_graph();
+
+        assert_eq!(
+            graph.dependencies("my-org/app"),
+            vec!["my-org/api", "my-org/core"]
+        );
+        assert_eq!(
+            graph.dependents("my-org/core"),
+            vec!["my-org/app", "my-org/domain"]
+        );
+        assert_eq!(
+            graph.build_order().unwrap(),
+            vec![
+                "my-org/core",
+                "my-org/domain",
+                "my-org/api",
+                "my-org/app",
+                "my-org/tools"
+            ]
+        );
+        assert_eq!(
+            graph.downstream(&["my-org/domain".to_string()]).unwrap(),
+            vec!["my-org/domain", "my-org/api", "my-org/app"]
+        );
+        assert!(
+            graph
+                .downstream(&["unknown".to_string()])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_cycles_are_reported_in_dependency_order() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = workspace(
+            temp.path(),
+            &[
+                ("a", "\"my-org/b\" = { path = \"../b\" }\n"),
+                ("b", "\"my-org/c\" = \"1.0.0\"\n"),
+                ("c", "\"my-org/a\" = \"1.0.0\"\n"),
+                ("d", "\"my-org/a\" = \"1.0.0\"\n"),
+            ],
+        );
+        let err = workspace.build_order().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Project dependencies have a cycle: my-org/a -> my-org/b -> my-org/c -> my-org/a"
+        );
+
+        let graph = ProjectGraph {
+            dependencies: BTreeMap::from([(
+                "self".to_string(),
+                BTreeSet::from(["self".to_string()]),
+            )]),
+        };
+        assert!(matches!(
+            graph.build_order().unwrap_err(),
+            DaemonError::ProjectCycle(cycle) if cycle == ["self", "self"]
+        ));
+    }
+}
@@ -0,0 +1,486 @@
+//! Persistent index of a project's modules
+//!
+//! Opening a large workspace should not mean reading every source again. The
+//! [`ModuleIndex`] of a project records, per source file, the module it
+//! defines, the modules it imports, and its top-level symbols, and is kept in
+//! the project's `.morphir/cache/index`. On refresh a file is only read when
+//! its modification time or size changed, and only rescanned when its content
+//! hash changed too, so a warm start costs one `stat` per file.
+//!
+//! Sources are scanned line by line rather than parsed: imports and top-level
+//! declarations sit at the start of a line in every supported language, and
+//! the index must stay cheap to rebuild.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use morphir_extension_sdk::types::fingerprint;
+use serde::{Deserialize, Serialize};
+
+use super::{find_sources, source_extension, write_json};
+use crate::Result;
+
+/// Format of the persisted index; an index in another format is rebuilt
+pub const INDEX_VERSION: u32 = 1;
+
+/// Kind of a top-level declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Type,
+    Constant,
+}
+
+/// A top-level declaration of a module
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Line of the declaration, from 1
+    pub line: usize,
+}
+
+/// What the index knows about a source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedFile {
+    /// Module defined by the file
+    pub module: String,
+    /// Modification time when indexed, in milliseconds since the epoch
+    pub modified_ms: u64,
+    pub size: u64,
+    /// Content fingerprint when indexed
+    pub hash: String,
+    /// Modules imported by the file, in order of appearance
+    pub imports: Vec<String>,
+    pub symbols: Vec<Symbol>,
+}
+
+/// Module dependency graph and symbol index of a project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleIndex {
+    pub version: u32,
+    /// Indexed files, keyed by path relative to the project
+    #[serde(default)]
+    pub files: BTreeMap<String, IndexedFile>,
+}
+
+/// What a refresh of the index did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct IndexStats {
+    /// Files whose modification time and size were unchanged
+    pub reused: usize,
+    /// Files touched without changing their content
+    pub verified: usize,
+    /// Files new or changed, and scanned again
+    pub scanned: usize,
+    /// Files no longer present
+    pub removed: usize,
+}
+
+impl IndexStats {
+    /// Whether the refresh changed the index
+    pub fn changed(&self) -> bool {
+        self.verified + self.scanned + self.removed > 0
+    }
+}
+
+impl std::ops::AddAssign for IndexStats {
+    fn add_assign(&mut self, other: Self) {
+        self.reused += other.reused;
+        self.verified += other.verified;
+        self.scanned += other.scanned;
+        self.removed += other.removed;
+    }
+}
+
+impl Default for ModuleIndex {
+    fn default() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+impl ModuleIndex {
+    /// Load the index at `path`. A missing, unreadable, or outdated index
+    /// loads as empty, to be rebuilt.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Persist the index to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_json(path, self)
+    }
+
+    /// Bring the index up to date with the `language` sources under
+    /// `source_dir` of the project at `project_dir`
+    pub fn refresh(
+        &mut self,
+        project_dir: &Path,
+        source_dir: &str,
+        language: &str,
+    ) -> Result<IndexStats> {
+        let mut stats = IndexStats::default();
+        let Some(extension) = source_extension(language) else {
+            stats.removed = self.files.len();
+            self.files.clear();
+            return Ok(stats);
+        };
+        let source_root = project_dir.join(source_dir);
+        let mut files = BTreeMap::new();
+        for path in find_sources(&source_root, extension)? {
+            let key = relative_key(&path, project_dir);
+            let metadata = std::fs::metadata(&path)?;
+            let modified_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |elapsed| elapsed.as_millis() as u64);
+            let size = metadata.len();
+
+            let previous = match self.files.remove(&key) {
+                Some(file) if file.modified_ms == modified_ms && file.size == size => {
+                    stats.reused += 1;
+                    files.insert(key, file);
+                    continue;
+                }
+                previous => previous,
+            };
+            let content = std::fs::read_to_string(&path)?;
+            let hash = fingerprint(content.as_bytes());
+            let mut file = match previous {
+                Some(file) if file.hash == hash => {
+                    stats.verified += 1;
+                    file
+                }
+                _ => {
+                    stats.scanned += 1;
+                    let module = module_name(&path, &source_root, language);
+                    scan(language, module, &content, hash)
+                }
+            };
+            file.modified_ms = modified_ms;
+            file.size = size;
+            files.insert(key, file);
+        }
+        stats.removed = self.files.len();
+        self.files = files;
+        Ok(stats)
+    }
+
+    /// Modules of the project and the project modules each imports
+    pub fn dependency_graph(&self) -> BTreeMap<&str, Vec<&str>> {
+        let modules: BTreeMap<&str, &IndexedFile> = self
+            .files
+            .values()
+            .map(|file| (file.module.as_str(), file))
+            .collect();
+        modules
+            .iter()
+            .map(|(module, file)| {
+                let imports = file
+                    .imports
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|import| modules.contains_key(import))
+                    .collect();
+                (*module, imports)
+            })
+            .collect()
+    }
+
+    /// Symbols whose name contains `query`, ignoring case, with the path of
+    /// the file declaring them
+    pub fn find_symbols<'a>(
+        &'a self,
+        query: &str,
+    ) -> impl Iterator<Item = (&'a str, &'a IndexedFile, &'a Symbol)> + 'a {
+        let query = query.to_lowercase();
+        self.files.iter().flat_map(move |(path, file)| {
+            let query = query.clone();
+            file.symbols
+                .iter()
+                .filter(move |symbol| symbol.name.to_lowercase().contains(&query))
+                .map(move |symbol| (path.as_str(), file, symbol))
+        })
+    }
+
+    /// Number of symbols indexed
+    pub fn symbol_count(&self) -> usize {
+        self.files.values().map(|file| file.symbols.len()).sum()
+    }
+}
+
+fn relative_key(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Module a source file defines, from its path under the source directory:
+/// `a/b` in Gleam, `A.B` in Elm, `a.b` in Python
+fn module_name(path: &Path, source_root: &Path, language: &str) -> String {
+    let relative = relative_key(&path.with_extension(""), source_root);
+    match language {
+        "gleam" => relative,
+        "python" => relative.trim_end_matches("/__init__").replace('/', "."),
+        _ => relative.replace('/', "."),
+    }
+}
+
+/// Index the imports and top-level declarations of `content`
+fn scan(language: &str, module: String, content: &str, hash: String) -> IndexedFile {
+    let mut file = IndexedFile {
+        module,
+        modified_ms: 0,
+        size: 0,
+        hash,
+        imports: Vec::new(),
+        symbols: Vec::new(),
+    };
+    for (number, line) in content.lines().enumerate() {
+        // Top-level declarations and imports are not indented
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let line = line.trim_end();
+        let scanned = match language {
+            "gleam" => scan_gleam(line),
+            "elm" => scan_elm(line, &mut file.module),
+            _ => scan_python(line),
+        };
+        match scanned {
+            Some(Line::Import(module)) if !file.imports.contains(&module) => {
+                file.imports.push(module)
+            }
+            Some(Line::Declaration(name, kind))
+                if !file.symbols.iter().any(|symbol| symbol.name == name) =>
+            {
+                file.symbols.push(Symbol {
+                    name,
+                    kind,
+                    line: number + 1,
+                })
+            }
+            _ => {}
+        }
+    }
+    file
+}
+
+/// What a line of source declares
+enum Line {
+    Import(String),
+    Declaration(String, SymbolKind),
+}
+
+/// The identifier at the start of `text`
+fn identifier(text: &str) -> Option<String> {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    (end > 0).then(|| text[..end].to_string())
+}
+
+fn scan_gleam(line: &str) -> Option<Line> {
+    if let Some(rest) = line.strip_prefix("import ") {
+        let end = rest.find(['.', ' ', '{']).unwrap_or(rest.len());
+        return Some(Line::Import(rest[..end].to_string()));
+    }
+    let line = line.strip_prefix("pub ").unwrap_or(line);
+    let line = line.strip_prefix("opaque ").unwrap_or(line);
+    [
+        ("fn ", SymbolKind::Function),
+        ("type ", SymbolKind::Type),
+        ("const ", SymbolKind::Constant),
+    ]
+    .into_iter()
+    .find_map(|(keyword, kind)| {
+        let name = identifier(line.strip_prefix(keyword)?)?;
+        Some(Line::Declaration(name, kind))
+    })
+}
+
+/// Scan a line of Elm; the `module` declaration names the module
+fn scan_elm(line: &str, module: &mut String) -> Option<Line> {
+    if let Some(rest) = line
+        .strip_prefix("module ")
+        .or_else(|| line.strip_prefix("port module "))
+    {
+        *module = rest
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        return None;
+    }
+    if let Some(rest) = line.strip_prefix("import ") {
+        let name = rest.split_whitespace().next()?;
+        return Some(Line::Import(name.to_string()));
+    }
+    if let Some(rest) = line.strip_prefix("type ") {
+        let rest = rest.strip_prefix("alias ").unwrap_or(rest);
+        return Some(Line::Declaration(identifier(rest)?, SymbolKind::Type));
+    }
+    // A type annotation or definition of a value
+    let name = identifier(line).filter(|name| name.starts_with(char::is_lowercase))?;
+    match name.as_str() {
+        "port" | "infix" | "effect" => None,
+        _ => Some(Line::Declaration(name, SymbolKind::Function)),
+    }
+}
+
+fn scan_python(line: &str) -> Option<Line> {
+    if let Some(rest) = line.strip_prefix("from ") {
+        let name = rest.split_whitespace().next()?;
+        return Some(Line::Import(name.to_string()));
+    }
+    if let Some(rest) = line.strip_prefix("import ") {
+        let name = rest.split([' ', ',']).next()?;
+        return Some(Line::Import(name.to_string()));
+    }
+    let declaration = line.strip_prefix("async ").unwrap_or(line);
+    if let Some(rest) = declaration.strip_prefix("def ") {
+        return Some(Line::Declaration(identifier(rest)?, SymbolKind::Function));
+    }
+    if let Some(rest) = declaration.strip_prefix("class ") {
+        return Some(Line::Declaration(identifier(rest)?, SymbolKind::Type));
+    }
+    // Module-level constants are named in upper case by convention
+    let name = identifier(line)?;
+    let assigned = line[name.len()..].trim_start();
+    let is_constant = name.chars().any(char::is_uppercase)
+        && !name.chars().any(char::is_lowercase)
+        && (assigned.starts_with('=') && !assigned.starts_with("==") || assigned.starts_with(':'));
+    is_constant.then_some(Line::Declaration(name, SymbolKind::Constant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_sources_are_indexed_into_modules_and_symbols() {
+        let temp = tempfile::tempdir().unwrap();
+        let src = temp.path().join("src");
+        write(
+            &src.join("orders").join("order.gleam"),
+            "import gleam/list\nimport orders/money.{type Money}\n\npub type Order {\n  Order(total: Money)\n}\n\npub fn total(order: Order) -> Money {\n  order.total\n}\n\nconst limit = 10\n",
+        );
+        write(
+            &src.join("orders").join("money.gleam"),
+            "pub opaque type Money {\n  Money(Int)\n}\n",
+        );
+
+        let mut index = ModuleIndex::default();
+        let stats = index.refresh(temp.path(), "src", "gleam").unwrap();
+        assert_eq!(stats.scanned, 2);
+
+        let order = &index.files["src/orders/order.gleam"];
+        assert_eq!(order.module, "orders/order");
+        assert_eq!(order.imports, vec!["gleam/list", "orders/money"]);
+        assert_eq!(
+            order
+                .symbols
+                .iter()
+                .map(|s| (s.name.as_str(), s.kind, s.line))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Order", SymbolKind::Type, 4),
+                ("total", SymbolKind::Function, 8),
+                ("limit", SymbolKind::Constant, 12),
+            ]
+        );
+        assert_eq!(
+            index.dependency_graph(),
+            BTreeMap::from([
+                ("orders/money", vec![]),
+                ("orders/order", vec!["orders/money"]),
+            ])
+        );
+        let found: Vec<_> = index
+            .find_symbols("MONEY")
+            .map(|(path, file, symbol)| (path, file.module.as_str(), symbol.line))
+            .collect();
+        assert_eq!(found, vec![("src/orders/money.gleam", "orders/money", 1)]);
+
+        let elm = scan(
+            "elm",
+            "Fallback".to_string(),
+            "module Orders.Order exposing (..)\n\nimport Orders.Money as Money exposing (Money)\n\ntype alias Order =\n    { total : Money }\n\ntotal : Order -> Money\ntotal order =\n    order.total\n",
+            String::new(),
+        );
+        assert_eq!(elm.module, "Orders.Order");
+        assert_eq!(elm.imports, vec!["Orders.Money"]);
+        assert_eq!(
+            elm.symbols
+                .iter()
+                .map(|s| (s.name.as_str(), s.line))
+                .collect::<Vec<_>>(),
+            vec![("Order", 5), ("total", 8)]
+        );
+    }
+
+    #[test]
+    fn test_refresh_only_rescans_changed_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let src = temp.path().join("src");
+        write(&src.join("a.gleam"), "pub fn a() { 1 }\n");
+        write(&src.join("b.gleam"), "pub fn b() { 2 }\n");
+        let path = temp.path().join("index.json");
+        let mut index = ModuleIndex::default();
+        index.refresh(temp.path(), "src", "gleam").unwrap();
+        index.save(&path).unwrap();
+
+        // A warm start reads nothing
+        let mut index = ModuleIndex::load(&path);
+        let stats = index.refresh(temp.path(), "src", "gleam").unwrap();
+        assert_eq!(
+            stats,
+            IndexStats {
+                reused: 2,
+                ..IndexStats::default()
+            }
+        );
+        assert!(!stats.changed());
+
+        // A touched file is only hashed; a changed or new one is scanned
+        index.files.get_mut("src/a.gleam").unwrap().modified_ms = 0;
+        write(&src.join("b.gleam"), "pub fn b() { 2 }\npub fn c() { 3 }\n");
+        write(&src.join("d.gleam"), "pub fn d() { 4 }\n");
+        let stats = index.refresh(temp.path(), "src", "gleam").unwrap();
+        assert_eq!(
+            stats,
+            IndexStats {
+                reused: 0,
+                verified: 1,
+                scanned: 2,
+                removed: 0
+            }
+        );
+        assert_eq!(index.files["src/b.gleam"].symbols.len(), 2);
+
+        std::fs::remove_file(src.join("d.gleam")).unwrap();
+        let stats = index.refresh(temp.path(), "src", "gleam").unwrap();
+        assert_eq!((stats.reused, stats.removed), (2, 1));
+
+        // An index of another format is rebuilt
+        std::fs::write(&path, r#"{"version":0,"files":{}}"#).unwrap();
+        assert!(ModuleIndex::load(&path).files.is_empty());
+    }
+}
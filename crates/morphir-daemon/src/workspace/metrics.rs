@@ -0,0 +1,287 @@
+//! Workspace metrics, kept across builds
+//!
+//! Each build the daemon runs is recorded as a [`BuildSample`] in the
+//! workspace's `.morphir/cache/metrics.json`: when it ran, how long it took,
+//! how many sources were compiled or restored from the IR cache, and its
+//! diagnostics by severity. [`MetricsSummary`] combines that history with the
+//! size of the model from the module indexes, so teams can follow how a model
+//! grows and how the toolchain behaves over time.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use morphir_extension_sdk::types::DiagnosticSeverity;
+use serde::{Deserialize, Serialize};
+
+use super::{BuildReport, Workspace, write_json};
+use crate::Result;
+
+/// Builds kept in the history; older ones are dropped
+pub const MAX_SAMPLES: usize = 1000;
+
+/// Diagnostic counts by severity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeverityCounts {
+    pub errors: usize,
+    pub warnings: usize,
+    pub infos: usize,
+    pub hints: usize,
+}
+
+impl std::ops::AddAssign for SeverityCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.errors += other.errors;
+        self.warnings += other.warnings;
+        self.infos += other.infos;
+        self.hints += other.hints;
+    }
+}
+
+/// One build of a project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildSample {
+    /// When the build finished, in seconds since the epoch
+    pub timestamp: u64,
+    pub project: String,
+    pub success: bool,
+    pub duration_ms: u64,
+    /// Sources compiled by the frontend
+    pub compiled: usize,
+    /// Sources restored from the IR cache
+    pub cached: usize,
+    pub diagnostics: SeverityCounts,
+}
+
+impl BuildSample {
+    /// Sample of a build that produced `report` in `duration`
+    pub fn new(report: &BuildReport, duration: Duration) -> Self {
+        let mut diagnostics = SeverityCounts::default();
+        for diagnostic in &report.diagnostics {
+            match diagnostic.severity {
+                DiagnosticSeverity::Error => diagnostics.errors += 1,
+                DiagnosticSeverity::Warning => diagnostics.warnings += 1,
+                DiagnosticSeverity::Info => diagnostics.infos += 1,
+                DiagnosticSeverity::Hint => diagnostics.hints += 1,
+            }
+        }
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            project: report.project.clone(),
+            success: report.success,
+            duration_ms: duration.as_millis() as u64,
+            compiled: report.compiled,
+            cached: report.cached,
+            diagnostics,
+        }
+    }
+}
+
+/// Build history of a workspace
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildHistory {
+    /// Builds, oldest first
+    #[serde(default)]
+    pub builds: Vec<BuildSample>,
+}
+
+impl BuildHistory {
+    /// Path of the history of the workspace rooted at `root`
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(".morphir").join("cache").join("metrics.json")
+    }
+
+    /// Load the history; a missing or unreadable file means no builds yet
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the history, creating parent directories as needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_json(path, self)
+    }
+
+    /// Add `sample`, dropping the oldest builds beyond [`MAX_SAMPLES`]
+    pub fn record(&mut self, sample: BuildSample) {
+        self.builds.push(sample);
+        let excess = self.builds.len().saturating_sub(MAX_SAMPLES);
+        self.builds.drain(..excess);
+    }
+
+    /// Append the build that produced `report` to the history at `path`
+    pub fn append(path: &Path, report: &BuildReport, duration: Duration) -> Result<()> {
+        let mut history = Self::load(path);
+        history.record(BuildSample::new(report, duration));
+        history.save(path)
+    }
+}
+
+/// Size of a project's model, from its module index
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProjectMetrics {
+    pub modules: usize,
+    pub definitions: usize,
+    /// Diagnostics of the project's last build
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<SeverityCounts>,
+}
+
+/// Aggregate metrics of a workspace
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSummary {
+    pub projects: BTreeMap<String, ProjectMetrics>,
+    pub modules: usize,
+    pub definitions: usize,
+    /// Diagnostics of the last build of each project
+    pub diagnostics: SeverityCounts,
+    pub builds: usize,
+    pub failed_builds: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_duration_ms: Option<u64>,
+    /// Share of changed sources restored from the IR cache, from 0 to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_hit_rate: Option<f64>,
+    /// The most recent builds, oldest first
+    pub history: Vec<BuildSample>,
+}
+
+impl MetricsSummary {
+    /// Metrics of `workspace`, whose indexes should be refreshed, with its
+    /// build `history`. The `limit` most recent builds are listed.
+    pub fn new(workspace: &Workspace, history: &BuildHistory, limit: usize) -> Self {
+        let mut summary = Self::default();
+        for name in workspace.projects.keys() {
+            let index = workspace.indexes.get(name);
+            let last = history.builds.iter().rev().find(|b| b.project == *name);
+            let project = ProjectMetrics {
+                modules: index.map_or(0, |index| index.files.len()),
+                definitions: index.map_or(0, |index| index.symbol_count()),
+                diagnostics: last.map(|build| build.diagnostics),
+            };
+            summary.modules += project.modules;
+            summary.definitions += project.definitions;
+            summary.diagnostics += project.diagnostics.unwrap_or_default();
+            summary.projects.insert(name.clone(), project);
+        }
+
+        let builds = &history.builds;
+        summary.builds = builds.len();
+        summary.failed_builds = builds.iter().filter(|b| !b.success).count();
+        if !builds.is_empty() {
+            let total: u64 = builds.iter().map(|b| b.duration_ms).sum();
+            summary.average_duration_ms = Some(total / builds.len() as u64);
+        }
+        summary.last_duration_ms = builds.last().map(|b| b.duration_ms);
+        let compiled: usize = builds.iter().map(|b| b.compiled).sum();
+        let cached: usize = builds.iter().map(|b| b.cached).sum();
+        if compiled + cached > 0 {
+            summary.cache_hit_rate = Some(cached as f64 / (compiled + cached) as f64);
+        }
+        summary.history = builds[builds.len().saturating_sub(limit)..].to_vec();
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_extension_sdk::types::Diagnostic;
+
+    fn report(project: &str, compiled: usize, cached: usize, errors: usize) -> BuildReport {
+        BuildReport {
+            project: project.to_string(),
+            success: errors == 0,
+            compiled,
+            cached,
+            diagnostics: (0..errors)
+                .map(|_| Diagnostic {
+                    severity: DiagnosticSeverity::Error,
+                    code: None,
+                    message: "boom".to_string(),
+                    location: None,
+                    related: vec![],
+                    fixes: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_history_persists_and_keeps_recent_builds() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = BuildHistory::path(temp.path());
+        assert_eq!(BuildHistory::load(&path), BuildHistory::default());
+
+        BuildHistory::append(
+            &path,
+            &report("my-org/a", 3, 0, 1),
+            Duration::from_millis(40),
+        )
+        .unwrap();
+        let history = BuildHistory::load(&path);
+        assert_eq!(history.builds.len(), 1);
+        assert_eq!(history.builds[0].duration_ms, 40);
+        assert_eq!(history.builds[0].diagnostics.errors, 1);
+        assert!(!history.builds[0].success);
+
+        let mut history = BuildHistory::default();
+        for _ in 0..MAX_SAMPLES + 5 {
+            history.record(BuildSample::new(&report("a", 1, 0, 0), Duration::ZERO));
+        }
+        assert_eq!(history.builds.len(), MAX_SAMPLES);
+    }
+
+    #[test]
+    fn test_summary_combines_indexes_and_history() {
+        let temp = tempfile::tempdir().unwrap();
+        let project = temp.path().join("packages/core");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(
+            project.join("morphir.toml"),
+            "[project]\nname = \"my-org/core\"\nversion = \"1.0.0\"\nsource_directory = \"src\"\n\n[frontend]\nlanguage = \"gleam\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            project.join("src/money.gleam"),
+            "pub type Money {\n  Money(Int)\n}\n\npub fn add(a, b) { a }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path().join("morphir.toml"),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        let mut workspace = Workspace::open(temp.path().to_path_buf()).unwrap();
+        workspace.refresh_indexes().unwrap();
+
+        let mut history = BuildHistory::default();
+        for (compiled, cached, errors, ms) in [(2, 0, 1, 100), (0, 1, 0, 20), (1, 1, 0, 30)] {
+            history.record(BuildSample::new(
+                &report("my-org/core", compiled, cached, errors),
+                Duration::from_millis(ms),
+            ));
+        }
+
+        let summary = MetricsSummary::new(&workspace, &history, 2);
+        assert_eq!(summary.modules, 1);
+        assert_eq!(summary.definitions, 2);
+        assert_eq!(summary.builds, 3);
+        assert_eq!(summary.failed_builds, 1);
+        assert_eq!(summary.average_duration_ms, Some(50));
+        assert_eq!(summary.last_duration_ms, Some(30));
+        assert_eq!(summary.cache_hit_rate, Some(0.4));
+        // The last build of the project had no errors
+        assert_eq!(summary.diagnostics, SeverityCounts::default());
+        assert_eq!(summary.history.len(), 2);
+        assert_eq!(summary.history[0].cached, 1);
+    }
+}
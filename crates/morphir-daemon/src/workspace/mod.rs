@@ -0,0 +1,649 @@
+#![allow(clippy::ptr_arg)]
+//! Workspace management for multi-project Morphir development
+//!
+//! Projects are rebuilt when their sources change: [`watcher`] watches and
+//! debounces file changes, and [`rebuild`] compiles the affected projects.
+//! [`index`] keeps each project's modules and symbols on disk so a workspace
+//! reopens without reading every source. [`graph`] orders builds by the
+//! dependencies between projects. [`metrics`] records each build, for
+//! workspace metrics kept across sessions.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::Result;
+use morphir_common::config::{ConfigResolver, MorphirConfig};
+use morphir_extension_sdk::types::{CompileRequest, IncrementalCompileResult, SourceFile};
+use serde::{Deserialize, Serialize};
+
+pub mod graph;
+pub mod index;
+pub mod metrics;
+pub mod rebuild;
+pub mod watcher;
+
+pub use graph::ProjectGraph;
+pub use index::{IndexStats, ModuleIndex, Symbol, SymbolKind};
+pub use metrics::{BuildHistory, MetricsSummary};
+pub use rebuild::{BuildReport, FrontendBuilder, ProjectBuilder};
+pub use watcher::{ChangeKind, FileChange, WatchConfig, WorkspaceWatcher, affected_projects};
+
+/// Workspace state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceState {
+    /// Workspace is not active
+    Closed,
+    /// Workspace is being loaded
+    Initializing,
+    /// Workspace is ready for operations
+    Open,
+    /// Workspace has unrecoverable errors
+    Error,
+}
+
+/// Project state within a workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectState {
+    /// Project metadata loaded, IR not compiled
+    Unloaded,
+    /// Project is being compiled
+    Loading,
+    /// Project IR is loaded and valid
+    Ready,
+    /// Source files changed, needs recompilation
+    Stale,
+    /// Project has compilation errors
+    Error,
+}
+
+/// A project within a workspace
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// Project name (org/name format)
+    pub name: String,
+    /// Project version
+    pub version: String,
+    /// Path relative to workspace root
+    pub path: PathBuf,
+    /// Current state
+    pub state: ProjectState,
+    /// Source directory
+    pub source_dir: String,
+    /// Project configuration
+    pub config: MorphirConfig,
+}
+
+impl Project {
+    /// Path of the fingerprints recorded for the project's last successful build
+    pub fn fingerprints_path(&self) -> PathBuf {
+        self.path
+            .join(".morphir")
+            .join("cache")
+            .join("fingerprints.json")
+    }
+
+    /// Load the fingerprints of the last successful build
+    pub fn load_fingerprints(&self) -> Result<BuildFingerprints> {
+        BuildFingerprints::load(&self.fingerprints_path())
+    }
+
+    /// Persist the fingerprints of a successful build
+    pub fn save_fingerprints(&self, fingerprints: &BuildFingerprints) -> Result<()> {
+        fingerprints.save(&self.fingerprints_path())
+    }
+
+    /// Frontend language of the project, from `[frontend] language`
+    pub fn language(&self) -> Option<&str> {
+        self.config
+            .frontend
+            .as_ref()
+            .and_then(|f| f.language.as_deref())
+    }
+
+    /// Where the project's IR is written by its frontend, as `morphir compile`
+    /// does, for a workspace rooted at `root`
+    pub fn compile_output(&self, root: &Path) -> Option<PathBuf> {
+        let language = self.language()?;
+        Some(
+            root.join(".morphir")
+                .join("out")
+                .join(self.name.replace(['/', ' ', '\\'], "-"))
+                .join("compile")
+                .join(language),
+        )
+    }
+
+    /// Path of the project's module index
+    pub fn index_path(&self) -> PathBuf {
+        self.path
+            .join(".morphir")
+            .join("cache")
+            .join("index")
+            .join("modules.json")
+    }
+
+    /// Load the project's module index and bring it up to date, saving it
+    /// if anything changed
+    pub fn refresh_index(&self) -> Result<(ModuleIndex, IndexStats)> {
+        self.update_index(ModuleIndex::load(&self.index_path()))
+    }
+
+    /// Index every source of the project again, ignoring the saved index
+    pub fn rebuild_index(&self) -> Result<(ModuleIndex, IndexStats)> {
+        self.update_index(ModuleIndex::default())
+    }
+
+    fn update_index(&self, mut index: ModuleIndex) -> Result<(ModuleIndex, IndexStats)> {
+        let stats = index.refresh(
+            &self.path,
+            &self.source_dir,
+            self.language().unwrap_or_default(),
+        )?;
+        if stats.changed() || !self.index_path().exists() {
+            index.save(&self.index_path())?;
+        }
+        Ok((index, stats))
+    }
+}
+
+/// File extension of sources in `language`
+pub(crate) fn source_extension(language: &str) -> Option<&'static str> {
+    match language {
+        "gleam" => Some("gleam"),
+        "elm" => Some("elm"),
+        "python" => Some("py"),
+        _ => None,
+    }
+}
+
+/// Files with `extension` under `dir`, recursively, sorted
+pub(crate) fn find_sources(dir: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    let mut sources = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == extension) {
+                sources.push(path);
+            }
+        }
+    }
+    sources.sort();
+    Ok(sources)
+}
+
+/// Write `value` as JSON to `path`, creating parent directories as needed.
+///
+/// The JSON is written next to `path` and renamed over it, so a reader never
+/// sees a half-written file.
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_string(value)?)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Fingerprints of a project's last successful build
+///
+/// Used to send only changed sources to a frontend and to track which
+/// module hashes are still valid.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildFingerprints {
+    /// Source file fingerprints, keyed by path
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    /// Module hashes reported by the frontend, keyed by module name
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+/// What needs compiling, relative to the previous build
+#[derive(Debug, Clone)]
+pub struct IncrementalPlan {
+    /// Request carrying only the sources that need compiling
+    pub request: CompileRequest,
+    /// Paths of sources skipped because they are unchanged
+    pub unchanged: Vec<String>,
+    /// Paths of sources present in the previous build but not this one
+    pub removed: Vec<String>,
+    /// Fingerprints of all current sources
+    pub current: BTreeMap<String, String>,
+}
+
+impl IncrementalPlan {
+    /// Whether nothing changed since the previous build
+    pub fn is_up_to_date(&self) -> bool {
+        self.request.sources.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl BuildFingerprints {
+    /// Load fingerprints from disk; a missing file means no previous build
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write fingerprints to disk, creating parent directories as needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Plan a build of `sources` against these fingerprints.
+    ///
+    /// Without a previous build every source is compiled and the request is a
+    /// full build. Otherwise only added and modified sources are sent.
+    pub fn plan(
+        &self,
+        sources: Vec<SourceFile>,
+        options: HashMap<String, serde_json::Value>,
+    ) -> IncrementalPlan {
+        let current: BTreeMap<String, String> = sources
+            .iter()
+            .map(|source| (source.path.clone(), source.fingerprint()))
+            .collect();
+
+        let removed: Vec<String> = self
+            .files
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned()
+            .collect();
+
+        if self.files.is_empty() {
+            return IncrementalPlan {
+                request: CompileRequest {
+                    sources,
+                    options,
+                    previous_fingerprints: HashMap::new(),
+                    changed_files: None,
+                },
+                unchanged: Vec::new(),
+                removed,
+                current,
+            };
+        }
+
+        let mut unchanged = Vec::new();
+        let mut changed = Vec::new();
+        let mut to_compile = Vec::new();
+        for source in sources {
+            if self.files.get(&source.path) == current.get(&source.path) {
+                unchanged.push(source.path);
+            } else {
+                changed.push(source.path.clone());
+                to_compile.push(source);
+            }
+        }
+        changed.extend(removed.iter().cloned());
+
+        IncrementalPlan {
+            request: CompileRequest {
+                sources: to_compile,
+                options,
+                previous_fingerprints: self
+                    .files
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+                changed_files: Some(changed),
+            },
+            unchanged,
+            removed,
+            current,
+        }
+    }
+
+    /// Record the outcome of a planned build.
+    ///
+    /// Failed builds are not recorded, so their sources are compiled again
+    /// next time. Module hashes that were neither recompiled nor reported as
+    /// reused are dropped.
+    pub fn record(&mut self, plan: &IncrementalPlan, result: &IncrementalCompileResult) {
+        if !result.result.success {
+            return;
+        }
+        self.files = plan.current.clone();
+        if plan.request.is_incremental() {
+            self.modules
+                .retain(|module, _| result.reused_modules.contains(module));
+        } else {
+            self.modules.clear();
+        }
+        self.modules.extend(
+            result
+                .module_hashes
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+    }
+}
+
+/// A Morphir workspace managing multiple projects
+#[derive(Debug)]
+pub struct Workspace {
+    /// Workspace root directory
+    pub root: PathBuf,
+    /// Workspace name
+    pub name: Option<String>,
+    /// Current state
+    pub state: WorkspaceState,
+    /// Workspace configuration
+    pub config: MorphirConfig,
+    /// Projects in the workspace
+    pub projects: HashMap<String, Project>,
+    /// Module indexes of the projects, once refreshed
+    pub indexes: HashMap<String, ModuleIndex>,
+}
+
+/// Resolve a config file with the profile and `MORPHIR__*` overrides from the
+/// daemon's environment applied
+fn resolve_config(config_path: &Path) -> Result<MorphirConfig> {
+    let effective = ConfigResolver::new()
+        .with_file(config_path)
+        .with_env(std::env::vars())
+        .resolve()?;
+    Ok(effective.config)
+}
+
+impl Workspace {
+    /// Create a new workspace at the given root
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            name: None,
+            state: WorkspaceState::Closed,
+            config: MorphirConfig::default(),
+            projects: HashMap::new(),
+            indexes: HashMap::new(),
+        }
+    }
+
+    /// Open an existing workspace
+    pub fn open(root: PathBuf) -> Result<Self> {
+        let config_path = root.join("morphir.toml");
+        let config = resolve_config(&config_path)?;
+
+        let mut workspace = Self {
+            root,
+            name: config.project.as_ref().map(|p| p.name.clone()),
+            state: WorkspaceState::Initializing,
+            config,
+            projects: HashMap::new(),
+            indexes: HashMap::new(),
+        };
+
+        workspace.discover_projects()?;
+        workspace.state = WorkspaceState::Open;
+
+        Ok(workspace)
+    }
+
+    /// Discover projects in the workspace based on member patterns
+    fn discover_projects(&mut self) -> Result<()> {
+        let patterns: Vec<String> = self
+            .config
+            .workspace
+            .as_ref()
+            .map(|ws| ws.members.clone())
+            .unwrap_or_default();
+
+        if !patterns.is_empty() {
+            for pattern in patterns {
+                self.discover_projects_matching(&pattern)?;
+            }
+        } else if self.config.project.is_some() {
+            // Single project mode - the root is the project
+            let root = self.root.clone();
+            self.load_project(&root)?;
+        }
+        Ok(())
+    }
+
+    /// Discover projects matching a glob pattern
+    fn discover_projects_matching(&mut self, pattern: &str) -> Result<()> {
+        let base = self.root.join(pattern);
+        let pattern_str = base.to_string_lossy();
+
+        // Simple glob handling - for now just handle "packages/*" style patterns
+        if let Some(parent) = pattern_str.strip_suffix("/*") {
+            let parent_path = PathBuf::from(parent);
+            if parent_path.is_dir() {
+                for entry in std::fs::read_dir(&parent_path)? {
+                    let entry = entry?;
+                    let path = entry.path();
+                    if path.is_dir() && path.join("morphir.toml").exists() {
+                        self.load_project(&path)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load a project from a directory
+    fn load_project(&mut self, path: &PathBuf) -> Result<()> {
+        let config_path = path.join("morphir.toml");
+        let config = resolve_config(&config_path)?;
+
+        if let Some(ref project_config) = config.project {
+            let project = Project {
+                name: project_config.name.clone(),
+                version: project_config.version.clone(),
+                path: path.clone(),
+                state: ProjectState::Unloaded,
+                source_dir: project_config.source_directory.clone(),
+                config,
+            };
+            self.projects.insert(project.name.clone(), project);
+        }
+
+        Ok(())
+    }
+
+    /// Get a project by name
+    pub fn get_project(&self, name: &str) -> Option<&Project> {
+        self.projects.get(name)
+    }
+
+    /// Get a mutable project by name
+    pub fn get_project_mut(&mut self, name: &str) -> Option<&mut Project> {
+        self.projects.get_mut(name)
+    }
+
+    /// List all project names
+    pub fn list_projects(&self) -> Vec<&str> {
+        self.projects.keys().map(|s| s.as_str()).collect()
+    }
+
+    /// Dependencies between the projects of the workspace
+    pub fn project_graph(&self) -> ProjectGraph {
+        ProjectGraph::new(self)
+    }
+
+    /// Names of every project, each after the projects it depends on
+    pub fn build_order(&self) -> Result<Vec<String>> {
+        self.project_graph().build_order()
+    }
+
+    /// Refresh the module index of every project from its saved index
+    pub fn refresh_indexes(&mut self) -> Result<IndexStats> {
+        let mut total = IndexStats::default();
+        for project in self.projects.values() {
+            let (index, stats) = project.refresh_index()?;
+            self.indexes.insert(project.name.clone(), index);
+            total += stats;
+        }
+        Ok(total)
+    }
+
+    /// Save the module index of every project, as a session ends
+    pub fn save_indexes(&self) -> Result<()> {
+        for (name, index) in &self.indexes {
+            if let Some(project) = self.projects.get(name) {
+                index.save(&project.index_path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Close the workspace
+    pub fn close(&mut self) {
+        self.state = WorkspaceState::Closed;
+        self.projects.clear();
+        self.indexes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_extension_sdk::types::CompileResult;
+    use tempfile::tempdir;
+
+    fn source(path: &str, content: &str) -> SourceFile {
+        SourceFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn compiled(modules: &[&str], reused: &[&str]) -> IncrementalCompileResult {
+        IncrementalCompileResult {
+            result: CompileResult {
+                success: true,
+                ir: None,
+                diagnostics: vec![],
+            },
+            module_hashes: modules
+                .iter()
+                .map(|m| (m.to_string(), format!("hash-{}", m)))
+                .collect(),
+            reused_modules: reused.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_first_build_is_full() {
+        let plan = BuildFingerprints::default().plan(
+            vec![source("src/a.gleam", "a"), source("src/b.gleam", "b")],
+            HashMap::new(),
+        );
+        assert!(!plan.request.is_incremental());
+        assert_eq!(plan.request.sources.len(), 2);
+    }
+
+    #[test]
+    fn test_unchanged_sources_are_skipped() {
+        let mut fingerprints = BuildFingerprints::default();
+        let first = fingerprints.plan(
+            vec![source("src/a.gleam", "a"), source("src/b.gleam", "b")],
+            HashMap::new(),
+        );
+        fingerprints.record(&first, &compiled(&["a", "b"], &[]));
+
+        let second = fingerprints.plan(
+            vec![source("src/a.gleam", "a2"), source("src/c.gleam", "c")],
+            HashMap::new(),
+        );
+        let compiled_paths: Vec<_> = second.request.sources.iter().map(|s| &s.path).collect();
+        assert_eq!(compiled_paths, vec!["src/a.gleam", "src/c.gleam"]);
+        assert_eq!(second.removed, vec!["src/b.gleam"]);
+        assert_eq!(
+            second.request.changed_files,
+            Some(vec![
+                "src/a.gleam".to_string(),
+                "src/c.gleam".to_string(),
+                "src/b.gleam".to_string()
+            ])
+        );
+        assert_eq!(second.request.previous_fingerprints.len(), 2);
+
+        fingerprints.record(&second, &compiled(&["a", "c"], &[]));
+        assert_eq!(
+            fingerprints.modules.keys().collect::<Vec<_>>(),
+            vec!["a", "c"]
+        );
+    }
+
+    #[test]
+    fn test_up_to_date_and_reused_modules() {
+        let mut fingerprints = BuildFingerprints::default();
+        let sources = vec![source("src/a.gleam", "a"), source("src/b.gleam", "b")];
+        let first = fingerprints.plan(sources.clone(), HashMap::new());
+        fingerprints.record(&first, &compiled(&["a", "b"], &[]));
+
+        assert!(fingerprints.plan(sources, HashMap::new()).is_up_to_date());
+
+        let third = fingerprints.plan(
+            vec![source("src/a.gleam", "a"), source("src/b.gleam", "b2")],
+            HashMap::new(),
+        );
+        fingerprints.record(&third, &compiled(&["b"], &["a"]));
+        assert_eq!(fingerprints.modules["a"], "hash-a");
+        assert_eq!(fingerprints.modules["b"], "hash-b");
+    }
+
+    #[test]
+    fn test_failed_build_is_not_recorded() {
+        let mut fingerprints = BuildFingerprints::default();
+        let plan = fingerprints.plan(vec![source("src/a.gleam", "a")], HashMap::new());
+        let mut result = compiled(&["a"], &[]);
+        result.result.success = false;
+        fingerprints.record(&plan, &result);
+        assert_eq!(fingerprints, BuildFingerprints::default());
+    }
+
+    #[test]
+    fn test_fingerprints_round_trip_on_disk() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join(".morphir/cache/fingerprints.json");
+        assert_eq!(
+            BuildFingerprints::load(&path).unwrap(),
+            BuildFingerprints::default()
+        );
+
+        let mut fingerprints = BuildFingerprints::default();
+        let plan = fingerprints.plan(vec![source("src/a.gleam", "a")], HashMap::new());
+        fingerprints.record(&plan, &compiled(&["a"], &[]));
+        fingerprints.save(&path).unwrap();
+        assert_eq!(BuildFingerprints::load(&path).unwrap(), fingerprints);
+    }
+
+    #[test]
+    fn test_invalid_config_error_has_report() {
+        let temp = tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("morphir.toml"),
+            "[ir]\nformat_version = \"four\"\n",
+        )
+        .unwrap();
+
+        let error = Workspace::open(temp.path().to_path_buf()).unwrap_err();
+        let report = error.config_report().expect("config errors carry a report");
+        assert_eq!(report["kind"], "invalid");
+        assert_eq!(
+            report["config"]["values"]["ir.format_version"]["source"]["kind"],
+            "file"
+        );
+    }
+}
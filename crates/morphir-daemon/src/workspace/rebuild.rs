@@ -0,0 +1,388 @@
+//! Rebuilding the projects of a workspace
+//!
+//! [`FrontendBuilder`] runs the compile pipeline the CLI runs: the project's
+//! frontend extension compiles its sources into the workspace's
+//! `.morphir/out/<project>/compile/<language>` directory. Builds are
+//! incremental against the fingerprints of the last successful build, so a
+//! rebuild after a save sends only the changed sources. A project whose
+//! dependencies were rebuilt is compiled in full, since its own sources may
+//! be unchanged.
+//!
+//! Sources that do need compiling are first looked up in the workspace's
+//! [`IrCache`], by a hash of their content, the frontend, and the compile
+//! options. A module compiled before, on any branch, has its IR files
+//! restored into the output instead of being sent to the frontend.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use morphir_common::pipeline::cache::{CacheKey, CachedModule, IrCache};
+use morphir_extension_sdk::types::{Diagnostic, IncrementalCompileResult, SourceFile};
+use serde::Serialize;
+use tracing::warn;
+
+use super::{BuildFingerprints, Project, find_sources, source_extension};
+use crate::error::{DaemonError, Result};
+use crate::extensions::ExtensionRegistry;
+use crate::extensions::protocol::methods;
+
+/// Outcome of building a project
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildReport {
+    /// Project name
+    pub project: String,
+    pub success: bool,
+    /// Number of sources compiled; unchanged sources are skipped
+    pub compiled: usize,
+    /// Number of sources whose IR was restored from the IR cache
+    pub cached: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Builds a project of a workspace
+#[async_trait]
+pub trait ProjectBuilder: Send + Sync {
+    /// Build `project` of the workspace rooted at `root`
+    async fn build(&self, root: &Path, project: &Project) -> Result<BuildReport>;
+
+    /// Build `project` from scratch, because projects it depends on changed
+    async fn rebuild(&self, root: &Path, project: &Project) -> Result<BuildReport> {
+        self.build(root, project).await
+    }
+}
+
+/// Builds projects with the frontend extension for their language
+pub struct FrontendBuilder {
+    registry: Arc<ExtensionRegistry>,
+}
+
+impl FrontendBuilder {
+    pub fn new(registry: Arc<ExtensionRegistry>) -> Self {
+        Self { registry }
+    }
+}
+
+#[async_trait]
+impl ProjectBuilder for FrontendBuilder {
+    async fn build(&self, root: &Path, project: &Project) -> Result<BuildReport> {
+        let fingerprints = project.load_fingerprints()?;
+        self.compile(root, project, fingerprints).await
+    }
+
+    async fn rebuild(&self, root: &Path, project: &Project) -> Result<BuildReport> {
+        self.compile(root, project, BuildFingerprints::default())
+            .await
+    }
+}
+
+impl FrontendBuilder {
+    /// Compile the sources of `project` that changed since `fingerprints`
+    async fn compile(
+        &self,
+        root: &Path,
+        project: &Project,
+        mut fingerprints: BuildFingerprints,
+    ) -> Result<BuildReport> {
+        let language = project.language().ok_or_else(|| {
+            DaemonError::Build(format!(
+                "No frontend language configured for {}",
+                project.name
+            ))
+        })?;
+        let extension = self
+            .registry
+            .find_extension_by_language(language)
+            .await
+            .ok_or_else(|| {
+                DaemonError::Build(format!("No extension found for language: {}", language))
+            })?;
+
+        let sources = read_sources(&project.path, &project.source_dir, language)?;
+        let output = project.compile_output(root).unwrap_or_default();
+        let options = HashMap::from([
+            ("package_name".to_string(), project.name.clone().into()),
+            (
+                "output".to_string(),
+                output.to_string_lossy().into_owned().into(),
+            ),
+        ]);
+        let mut plan = fingerprints.plan(sources, options);
+        if plan.is_up_to_date() {
+            return Ok(BuildReport {
+                project: project.name.clone(),
+                success: true,
+                compiled: 0,
+                cached: 0,
+                diagnostics: Vec::new(),
+            });
+        }
+
+        let cache = IrCache::in_morphir_dir(&root.join(".morphir"));
+        let compiler = format!("{}@{}", extension.info().id, extension.info().version);
+        let key_options: BTreeMap<String, serde_json::Value> = plan
+            .request
+            .options
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let mut keys = HashMap::new();
+        let mut diagnostics = Vec::new();
+        let mut cached = 0;
+        plan.request.sources.retain(|source| {
+            let key = CacheKey::new(
+                &compiler,
+                &key_options,
+                &source.path,
+                source.content.as_bytes(),
+            );
+            if let Some(module) = cache.get(&key)
+                && restore(&output, &module).is_ok()
+            {
+                diagnostics.extend(
+                    module
+                        .diagnostics
+                        .into_iter()
+                        .filter_map(|d| serde_json::from_value(d).ok()),
+                );
+                cached += 1;
+                return false;
+            }
+            keys.insert(source.path.clone(), key);
+            true
+        });
+        if plan.request.sources.is_empty() && plan.removed.is_empty() {
+            // Every changed module came from the cache
+            fingerprints.files = plan.current.clone();
+            project.save_fingerprints(&fingerprints)?;
+            return Ok(BuildReport {
+                project: project.name.clone(),
+                success: true,
+                compiled: 0,
+                cached,
+                diagnostics,
+            });
+        }
+
+        let result: IncrementalCompileResult = self
+            .registry
+            .call(extension.id(), methods::COMPILE_INCREMENTAL, &plan.request)
+            .await?;
+        fingerprints.record(&plan, &result);
+        if result.result.success {
+            project.save_fingerprints(&fingerprints)?;
+            for source in &plan.request.sources {
+                let Some(key) = keys.get(&source.path) else {
+                    continue;
+                };
+                if let Err(e) = cache_module(&cache, key, &output, source, &result) {
+                    warn!("Failed to cache IR of {}: {}", source.path, e);
+                }
+            }
+        }
+        diagnostics.extend(result.result.diagnostics);
+        Ok(BuildReport {
+            project: project.name.clone(),
+            success: result.result.success,
+            compiled: plan.request.sources.len(),
+            cached,
+            diagnostics,
+        })
+    }
+}
+
+/// Cache the IR the frontend wrote for `source`. Modules whose files cannot
+/// be found in the output are not cached, since they could not be restored.
+fn cache_module(
+    cache: &IrCache,
+    key: &CacheKey,
+    output: &Path,
+    source: &SourceFile,
+    result: &IncrementalCompileResult,
+) -> anyhow::Result<()> {
+    let files = module_files(output, &source.path)?;
+    if files.is_empty() {
+        return Ok(());
+    }
+    let diagnostics = result
+        .result
+        .diagnostics
+        .iter()
+        .filter(|d| d.location.as_ref().is_some_and(|l| l.file == source.path))
+        .map(serde_json::to_value)
+        .collect::<std::result::Result<_, _>>()?;
+    cache.put(
+        key,
+        &CachedModule {
+            source: source.path.clone(),
+            files,
+            diagnostics,
+        },
+    )
+}
+
+/// Files written under `output` for the module compiled from `source`, keyed
+/// by path relative to `output`.
+///
+/// Frontends name module directories after source paths, so
+/// `src/orders/money.gleam` is written to
+/// `.morphir-dist/pkg/<package>/src/orders/money`. Modules nested in that
+/// directory have their own `module.json` and are left out.
+fn module_files(output: &Path, source: &str) -> Result<BTreeMap<String, String>> {
+    let module = source
+        .rsplit_once('.')
+        .map_or(source, |(stem, _)| stem)
+        .to_string();
+    let packages = output.join(".morphir-dist").join("pkg");
+    let mut files = BTreeMap::new();
+    let mut pending = vec![packages.clone()];
+    while let Some(dir) = pending.pop() {
+        if !dir.is_dir() {
+            continue;
+        }
+        let relative = dir
+            .strip_prefix(&packages)
+            .unwrap_or(&dir)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if dir.join("module.json").is_file()
+            && (relative == module || relative.ends_with(&format!("/{}", module)))
+        {
+            collect_module_files(output, &dir, &mut files)?;
+            continue;
+        }
+        for entry in std::fs::read_dir(&dir)? {
+            pending.push(entry?.path());
+        }
+    }
+    Ok(files)
+}
+
+fn collect_module_files(
+    output: &Path,
+    dir: &Path,
+    files: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if !path.join("module.json").exists() {
+                collect_module_files(output, &path, files)?;
+            }
+        } else {
+            let relative = path.strip_prefix(output).unwrap_or(&path);
+            files.insert(
+                relative.to_string_lossy().replace('\\', "/"),
+                std::fs::read_to_string(&path)?,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write the cached files of `module` into `output`, leaving files that
+/// already match alone
+fn restore(output: &Path, module: &CachedModule) -> Result<()> {
+    for (relative, content) in &module.files {
+        if relative.split('/').any(|segment| segment == "..") {
+            return Err(DaemonError::ArtifactPath(relative.clone()));
+        }
+        let path = output.join(relative);
+        if std::fs::read_to_string(&path).is_ok_and(|existing| existing == *content) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+    }
+    Ok(())
+}
+
+/// Sources of `language` under the project's source directory, with paths
+/// relative to the project
+pub(crate) fn read_sources(
+    project_dir: &Path,
+    source_dir: &str,
+    language: &str,
+) -> Result<Vec<SourceFile>> {
+    let extension = source_extension(language)
+        .ok_or_else(|| DaemonError::Build(format!("Unknown language: {}", language)))?;
+    find_sources(&project_dir.join(source_dir), extension)?
+        .into_iter()
+        .map(|path| {
+            let relative = path.strip_prefix(project_dir).unwrap_or(&path);
+            Ok(SourceFile {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                content: std::fs::read_to_string(&path)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_module_files_leave_out_nested_modules() {
+        let temp = tempfile::tempdir().unwrap();
+        let module = temp
+            .path()
+            .join(".morphir-dist/pkg/my-org/orders/src/orders/money");
+        write(&module.join("module.json"), "{}");
+        write(&module.join("values/add.value.json"), "{\"add\":1}");
+        write(&module.join("tax/module.json"), "{}");
+        write(&module.join("tax/values/rate.value.json"), "{}");
+
+        let files = module_files(temp.path(), "src/orders/money.gleam").unwrap();
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            vec![
+                ".morphir-dist/pkg/my-org/orders/src/orders/money/module.json",
+                ".morphir-dist/pkg/my-org/orders/src/orders/money/values/add.value.json",
+            ]
+        );
+        assert!(
+            module_files(temp.path(), "src/orders/tax.gleam")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_restore_writes_cached_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let module = CachedModule {
+            source: "src/money.gleam".to_string(),
+            files: BTreeMap::from([(
+                ".morphir-dist/pkg/acme/src/money/module.json".to_string(),
+                "{}".to_string(),
+            )]),
+            diagnostics: vec![],
+        };
+        restore(temp.path(), &module).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(
+                temp.path()
+                    .join(".morphir-dist/pkg/acme/src/money/module.json")
+            )
+            .unwrap(),
+            "{}"
+        );
+
+        let escaping = CachedModule {
+            files: BTreeMap::from([("../outside.json".to_string(), "{}".to_string())]),
+            ..module
+        };
+        assert!(restore(temp.path(), &escaping).is_err());
+        assert!(!temp.path().parent().unwrap().join("outside.json").exists());
+    }
+}
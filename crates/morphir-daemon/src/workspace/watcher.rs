@@ -0,0 +1,295 @@
+//! Watching project sources for changes
+//!
+//! A [`WorkspaceWatcher`] subscribes to the source directory and
+//! `morphir.toml` of every project, plus the workspace's own `morphir.toml`
+//! and any other files given, such as the inputs of live evaluations.
+//! File system events are debounced: changes are collected until the files
+//! have been quiet for the debounce interval, then delivered as one batch, so
+//! saving several files rebuilds once. [`affected_projects`] maps a batch to
+//! the projects to rebuild.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use morphir_common::config::DaemonSection;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use super::Workspace;
+use crate::Result;
+
+/// Settings for watching a workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchConfig {
+    /// How long files must be quiet before a batch of changes is delivered
+    pub debounce: Duration,
+    /// Rebuild affected projects after each batch
+    pub auto_rebuild: bool,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(100),
+            auto_rebuild: true,
+        }
+    }
+}
+
+impl From<&DaemonSection> for WatchConfig {
+    fn from(section: &DaemonSection) -> Self {
+        let defaults = Self::default();
+        Self {
+            debounce: section
+                .watch_debounce_ms
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.debounce),
+            auto_rebuild: section.auto_rebuild.unwrap_or(defaults.auto_rebuild),
+        }
+    }
+}
+
+/// Kind of a change to a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+/// A changed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    pub kind: ChangeKind,
+    pub path: PathBuf,
+}
+
+/// File system watcher for the projects of a workspace.
+///
+/// Watching stops when the watcher is dropped.
+pub struct WorkspaceWatcher {
+    _watcher: RecommendedWatcher,
+    paths: Vec<PathBuf>,
+    debouncer: JoinHandle<()>,
+}
+
+impl WorkspaceWatcher {
+    /// Watch the projects of `workspace` and the files in `extra`,
+    /// delivering debounced batches of changes on the returned channel. Must
+    /// be called within a Tokio runtime.
+    pub fn start(
+        workspace: &Workspace,
+        config: &WatchConfig,
+        extra: &[PathBuf],
+    ) -> Result<(Self, UnboundedReceiver<Vec<FileChange>>)> {
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The receiver is gone once the watcher is being dropped
+            let _ = events_tx.send(event);
+        })?;
+        let paths = watch_paths(workspace, extra);
+        for path in &paths {
+            let mode = if path.is_dir() {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            };
+            watcher.watch(path, mode)?;
+        }
+
+        let (batches_tx, batches_rx) = mpsc::unbounded_channel();
+        let debouncer = tokio::spawn(debounce(events_rx, batches_tx, config.debounce));
+        Ok((
+            Self {
+                _watcher: watcher,
+                paths,
+                debouncer,
+            },
+            batches_rx,
+        ))
+    }
+
+    /// Directories and files being watched
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+}
+
+impl Drop for WorkspaceWatcher {
+    fn drop(&mut self) {
+        self.debouncer.abort();
+    }
+}
+
+/// Source directories and configuration files of the workspace, and the
+/// `extra` files, that exist
+fn watch_paths(workspace: &Workspace, extra: &[PathBuf]) -> Vec<PathBuf> {
+    let mut paths = vec![workspace.root.join("morphir.toml")];
+    paths.extend_from_slice(extra);
+    for project in workspace.projects.values() {
+        paths.push(project.path.join(&project.source_dir));
+        paths.push(project.path.join("morphir.toml"));
+    }
+    paths.retain(|path| path.exists());
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Names of the projects affected by `changes`, sorted.
+///
+/// A file belongs to the project whose directory contains it most closely.
+/// A change to the workspace's own `morphir.toml` affects every project.
+pub fn affected_projects(workspace: &Workspace, changes: &[FileChange]) -> Vec<String> {
+    let workspace_config = workspace.root.join("morphir.toml");
+    let mut affected: Vec<String> = Vec::new();
+    for change in changes {
+        let owner = workspace
+            .projects
+            .values()
+            .filter(|project| change.path.starts_with(&project.path))
+            .max_by_key(|project| project.path.components().count());
+        match owner {
+            Some(project) => affected.push(project.name.clone()),
+            None if change.path == workspace_config => {
+                affected.extend(workspace.projects.keys().cloned())
+            }
+            None => {}
+        }
+    }
+    affected.sort();
+    affected.dedup();
+    affected
+}
+
+/// Forward events as batches, each sent once no event arrived for `quiet`
+async fn debounce(
+    mut events: UnboundedReceiver<notify::Result<Event>>,
+    batches: UnboundedSender<Vec<FileChange>>,
+    quiet: Duration,
+) {
+    while let Some(first) = events.recv().await {
+        let mut changes = Vec::new();
+        collect(&mut changes, first);
+        let mut closed = false;
+        loop {
+            match tokio::time::timeout(quiet, events.recv()).await {
+                Ok(Some(event)) => collect(&mut changes, event),
+                Ok(None) => {
+                    closed = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+        if !changes.is_empty() && batches.send(changes).is_err() {
+            return;
+        }
+        if closed {
+            return;
+        }
+    }
+}
+
+/// Add the changes of `event`, one per path, to `changes`
+fn collect(changes: &mut Vec<FileChange>, event: notify::Result<Event>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            warn!("File watcher error: {}", e);
+            return;
+        }
+    };
+    let kind = match event.kind {
+        EventKind::Create(_) => ChangeKind::Created,
+        EventKind::Modify(ModifyKind::Name(_)) => ChangeKind::Renamed,
+        EventKind::Modify(_) => ChangeKind::Modified,
+        EventKind::Remove(_) => ChangeKind::Deleted,
+        EventKind::Access(_) | EventKind::Any | EventKind::Other => return,
+    };
+    for path in event.paths {
+        match changes.iter_mut().find(|change| change.path == path) {
+            // A file created and then written in the same batch is new
+            Some(change) if change.kind == ChangeKind::Created && kind == ChangeKind::Modified => {}
+            Some(change) => change.kind = kind,
+            None => changes.push(FileChange { kind, path }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn workspace(root: &Path) -> Workspace {
+        for (dir, name) in [("core", "my-org/core"), ("domain", "my-org/domain")] {
+            let project = root.join("packages").join(dir);
+            std::fs::create_dir_all(project.join("src")).unwrap();
+            std::fs::write(
+                project.join("morphir.toml"),
+                format!("[project]\nname = \"{}\"\nversion = \"1.0.0\"\n", name),
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            root.join("morphir.toml"),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        Workspace::open(root.to_path_buf()).unwrap()
+    }
+
+    #[test]
+    fn test_changes_are_attributed_to_their_projects() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = workspace(temp.path());
+        let change = |path: PathBuf| FileChange {
+            kind: ChangeKind::Modified,
+            path,
+        };
+        let core = temp.path().join("packages").join("core");
+
+        assert_eq!(
+            affected_projects(&workspace, &[change(core.join("src").join("a.gleam"))]),
+            vec!["my-org/core"]
+        );
+        assert_eq!(
+            affected_projects(&workspace, &[change(temp.path().join("morphir.toml"))]),
+            vec!["my-org/core", "my-org/domain"]
+        );
+        assert!(affected_projects(&workspace, &[change(temp.path().join("README.md"))]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_saves_are_debounced_into_one_batch() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = workspace(temp.path());
+        let config = WatchConfig {
+            debounce: Duration::from_millis(300),
+            ..WatchConfig::default()
+        };
+        let (watcher, mut batches) = WorkspaceWatcher::start(&workspace, &config, &[]).unwrap();
+        let src = temp.path().join("packages").join("core").join("src");
+        assert!(watcher.paths().contains(&src));
+
+        std::fs::write(src.join("a.gleam"), "pub fn a() { 1 }").unwrap();
+        std::fs::write(src.join("b.gleam"), "pub fn b() { 2 }").unwrap();
+        let batch = tokio::time::timeout(Duration::from_secs(5), batches.recv())
+            .await
+            .expect("a batch of changes")
+            .unwrap();
+        let mut paths: Vec<&Path> = batch.iter().map(|c| c.path.as_path()).collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths, vec![src.join("a.gleam"), src.join("b.gleam")]);
+        assert!(batch.iter().all(|c| c.kind == ChangeKind::Created));
+        assert_eq!(affected_projects(&workspace, &batch), vec!["my-org/core"]);
+    }
+}
@@ -94,9 +94,33 @@ fn merge_configs(workspace: Option<&MorphirConfig>, project: &MorphirConfig) ->
         merged.ir = Some(ir.clone());
     }
 
+    // Merge message catalog settings
+    if let Some(messages) = &project.messages {
+        merged.messages = Some(messages.clone());
+    }
+
+    // Merge lint settings
+    if let Some(lint) = &project.lint {
+        merged.lint = Some(lint.clone());
+    }
+
     // Merge extensions (project extensions override workspace)
-    for (key, value) in &project.extensions {
-        merged.extensions.insert(key.clone(), value.clone());
+    for (key, value) in &project.extensions.specs {
+        merged.extensions.specs.insert(key.clone(), value.clone());
+    }
+    for (key, value) in &project.extensions.permissions {
+        merged
+            .extensions
+            .permissions
+            .insert(key.clone(), value.clone());
+    }
+    for (key, value) in &project.extensions.limits {
+        merged.extensions.limits.insert(key.clone(), value.clone());
+    }
+
+    // Merge pipelines (project pipelines override workspace)
+    for (key, value) in &project.pipelines {
+        merged.pipelines.insert(key.clone(), value.clone());
     }
 
     merged
@@ -78,6 +78,8 @@ pub enum EnvelopeError {
     ContentTypeMismatch { expected: String, actual: String },
     /// JSON serialization/deserialization error.
     JsonError(serde_json::Error),
+    /// Envelope has no kind to name the JSON-RPC method it maps to.
+    MissingKind,
 }
 
 impl std::fmt::Display for EnvelopeError {
@@ -87,6 +89,9 @@ impl std::fmt::Display for EnvelopeError {
                 write!(f, "expected content type {}, got {}", expected, actual)
             }
             EnvelopeError::JsonError(e) => write!(f, "JSON error: {}", e),
+            EnvelopeError::MissingKind => {
+                write!(f, "envelope has no kind to use as the JSON-RPC method")
+            }
         }
     }
 }
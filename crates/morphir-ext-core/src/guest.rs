@@ -0,0 +1,110 @@
+//! Guest bindings for component-model compiler extensions.
+//!
+//! Each capability has a world in `wit/compiler.wit` and a feature enabling
+//! its bindings: `guest-frontend`, `guest-backend`, `guest-transform` and
+//! `guest-validator`. A component implements the `Guest` trait of each
+//! capability it enables and exports it with the matching macro:
+//!
+//! ```rust,ignore
+//! use morphir_ext_core::guest::frontend::Frontend;
+//! use morphir_ext_core::guest::types::{CompileRequest, CompileResult, ExtensionError};
+//!
+//! struct MyFrontend;
+//!
+//! impl Frontend for MyFrontend {
+//!     fn compile(request: CompileRequest) -> Result<CompileResult, ExtensionError> {
+//!         ...
+//!     }
+//! }
+//!
+//! morphir_ext_core::guest::frontend::export_frontend!(MyFrontend);
+//! ```
+//!
+//! Only the enabled worlds are embedded in the component, so it declares the
+//! capabilities it implements and no others. The types and the `runtime`
+//! import are shared by all of them.
+
+mod shared {
+    wit_bindgen::generate!({
+        world: "guest-types",
+        path: "wit",
+    });
+}
+
+pub use shared::morphir::ext::{envelope, runtime, types};
+
+#[cfg(feature = "guest-frontend")]
+pub mod frontend {
+    //! `frontend-extension`: source -> IR
+    wit_bindgen::generate!({
+        world: "frontend-extension",
+        path: "wit",
+        with: {
+            "morphir:ext/envelope@0.1.0": crate::guest::envelope,
+            "morphir:ext/runtime@0.1.0": crate::guest::runtime,
+            "morphir:ext/types@0.1.0": crate::guest::types,
+        },
+        pub_export_macro: true,
+        export_macro_name: "export_frontend",
+        default_bindings_module: "morphir_ext_core::guest::frontend",
+    });
+
+    pub use exports::morphir::ext::frontend::Guest as Frontend;
+}
+
+#[cfg(feature = "guest-backend")]
+pub mod backend {
+    //! `backend-extension`: IR -> artifacts
+    wit_bindgen::generate!({
+        world: "backend-extension",
+        path: "wit",
+        with: {
+            "morphir:ext/envelope@0.1.0": crate::guest::envelope,
+            "morphir:ext/runtime@0.1.0": crate::guest::runtime,
+            "morphir:ext/types@0.1.0": crate::guest::types,
+        },
+        pub_export_macro: true,
+        export_macro_name: "export_backend",
+        default_bindings_module: "morphir_ext_core::guest::backend",
+    });
+
+    pub use exports::morphir::ext::backend::Guest as Backend;
+}
+
+#[cfg(feature = "guest-transform")]
+pub mod transform {
+    //! `transform-extension`: IR -> IR
+    wit_bindgen::generate!({
+        world: "transform-extension",
+        path: "wit",
+        with: {
+            "morphir:ext/envelope@0.1.0": crate::guest::envelope,
+            "morphir:ext/runtime@0.1.0": crate::guest::runtime,
+            "morphir:ext/types@0.1.0": crate::guest::types,
+        },
+        pub_export_macro: true,
+        export_macro_name: "export_transform",
+        default_bindings_module: "morphir_ext_core::guest::transform",
+    });
+
+    pub use exports::morphir::ext::transform::Guest as Transform;
+}
+
+#[cfg(feature = "guest-validator")]
+pub mod validator {
+    //! `validator-extension`: IR -> diagnostics
+    wit_bindgen::generate!({
+        world: "validator-extension",
+        path: "wit",
+        with: {
+            "morphir:ext/envelope@0.1.0": crate::guest::envelope,
+            "morphir:ext/runtime@0.1.0": crate::guest::runtime,
+            "morphir:ext/types@0.1.0": crate::guest::types,
+        },
+        pub_export_macro: true,
+        export_macro_name: "export_validator",
+        default_bindings_module: "morphir_ext_core::guest::validator",
+    });
+
+    pub use exports::morphir::ext::validator::Guest as Validator;
+}
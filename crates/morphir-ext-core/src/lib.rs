@@ -4,6 +4,15 @@
 
 pub mod abi;
 pub mod envelope;
+#[cfg(any(
+    feature = "guest-frontend",
+    feature = "guest-backend",
+    feature = "guest-transform",
+    feature = "guest-validator"
+))]
+pub mod guest;
+pub mod rpc;
 
 // Re-export main types for convenience
 pub use envelope::{Envelope, EnvelopeError, Header, decode_envelope, encode_envelope};
+pub use rpc::{RpcError, RpcRequest, RpcResponse, call_with_envelope, call_with_rpc};
@@ -0,0 +1,314 @@
+//! Adapters between envelopes and JSON-RPC 2.0 messages.
+//!
+//! Extism extensions speak JSON-RPC (`ExtensionRequest`/`ExtensionResponse`
+//! in the SDK), while the component path and builtins exchange envelopes.
+//! The two carry the same information:
+//!
+//! | JSON-RPC          | Envelope                                     |
+//! |-------------------|----------------------------------------------|
+//! | `method`          | `header.kind`                                |
+//! | `id`              | `header.seqnum`                              |
+//! | `params`/`result` | JSON content (`application/json`)            |
+//! | `error`           | error object as [`ERROR_CONTENT_TYPE`]       |
+//!
+//! Content of any `+json` media type is JSON as well. Other content has no
+//! JSON-RPC form and is rejected.
+//!
+//! [`call_with_envelope`] and [`call_with_rpc`] run a handler written for one
+//! transport on messages of the other, so a builtin and its tests are
+//! written once and exercised over both.
+
+use crate::envelope::{Envelope, EnvelopeError, Header};
+use serde::{Deserialize, Serialize};
+
+/// JSON-RPC version string
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// Content type of an envelope carrying a JSON-RPC error object
+pub const ERROR_CONTENT_TYPE: &str = "application/vnd.morphir.error+json";
+
+/// Code for handler failures, the JSON-RPC "internal error"
+const INTERNAL_ERROR: i32 = -32603;
+
+/// JSON-RPC 2.0 request, as sent to Extism extensions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    pub params: serde_json::Value,
+    pub id: u64,
+}
+
+/// JSON-RPC 2.0 response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    pub id: u64,
+}
+
+/// JSON-RPC 2.0 error object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl RpcResponse {
+    /// Successful response with `result`
+    pub fn result(id: u64, result: serde_json::Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    /// Failed response with `error`
+    pub fn error(id: u64, error: RpcError) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            result: None,
+            error: Some(error),
+            id,
+        }
+    }
+}
+
+/// Whether content of this type is JSON
+pub fn is_json(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+fn json_content(envelope: &Envelope) -> Result<serde_json::Value, EnvelopeError> {
+    if !is_json(&envelope.content_type) {
+        return Err(EnvelopeError::ContentTypeMismatch {
+            expected: "application/json".to_string(),
+            actual: envelope.content_type.clone(),
+        });
+    }
+    serde_json::from_slice(&envelope.content).map_err(EnvelopeError::JsonError)
+}
+
+fn json_envelope(
+    header: Header,
+    content_type: &str,
+    value: &impl Serialize,
+) -> Result<Envelope, EnvelopeError> {
+    let content = serde_json::to_vec(value).map_err(EnvelopeError::JsonError)?;
+    Ok(Envelope::new(content_type, content).with_header(header))
+}
+
+/// Envelope for a JSON-RPC request: the method becomes the kind and the
+/// params the content
+pub fn request_to_envelope(request: &RpcRequest) -> Result<Envelope, EnvelopeError> {
+    let header = Header {
+        seqnum: request.id,
+        session_id: String::new(),
+        kind: Some(request.method.clone()),
+    };
+    json_envelope(header, "application/json", &request.params)
+}
+
+/// JSON-RPC request for an envelope. The envelope's kind names the method.
+pub fn envelope_to_request(envelope: &Envelope) -> Result<RpcRequest, EnvelopeError> {
+    let method = envelope
+        .header
+        .kind
+        .clone()
+        .ok_or(EnvelopeError::MissingKind)?;
+    Ok(RpcRequest {
+        jsonrpc: JSONRPC_VERSION.to_string(),
+        method,
+        params: json_content(envelope)?,
+        id: envelope.header.seqnum,
+    })
+}
+
+/// Envelope for a JSON-RPC response to the request with `header`; errors
+/// are carried as [`ERROR_CONTENT_TYPE`] content
+pub fn response_to_envelope(
+    response: &RpcResponse,
+    header: Header,
+) -> Result<Envelope, EnvelopeError> {
+    match (&response.error, &response.result) {
+        (Some(error), _) => json_envelope(header, ERROR_CONTENT_TYPE, error),
+        (None, result) => json_envelope(
+            header,
+            "application/json",
+            result.as_ref().unwrap_or(&serde_json::Value::Null),
+        ),
+    }
+}
+
+/// JSON-RPC response for an envelope answering a request
+pub fn envelope_to_response(envelope: &Envelope) -> Result<RpcResponse, EnvelopeError> {
+    let id = envelope.header.seqnum;
+    if envelope.content_type == ERROR_CONTENT_TYPE {
+        let error = serde_json::from_slice(&envelope.content).map_err(EnvelopeError::JsonError)?;
+        return Ok(RpcResponse::error(id, error));
+    }
+    Ok(RpcResponse::result(id, json_content(envelope)?))
+}
+
+/// Answer a JSON-RPC request with a handler of envelopes.
+///
+/// Failures, of the handler or of the conversion, become error responses.
+pub fn call_with_envelope<E: std::fmt::Display>(
+    request: &RpcRequest,
+    handler: impl FnOnce(&Envelope) -> Result<Envelope, E>,
+) -> RpcResponse {
+    let internal = |message: String| {
+        RpcResponse::error(
+            request.id,
+            RpcError {
+                code: INTERNAL_ERROR,
+                message,
+                data: None,
+            },
+        )
+    };
+    let output = match request_to_envelope(request) {
+        Ok(input) => handler(&input),
+        Err(e) => return internal(e.to_string()),
+    };
+    match output.map(|output| envelope_to_response(&output)) {
+        Ok(Ok(response)) => RpcResponse {
+            id: request.id,
+            ..response
+        },
+        Ok(Err(e)) => internal(e.to_string()),
+        Err(e) => internal(e.to_string()),
+    }
+}
+
+/// Answer an envelope with a handler of JSON-RPC requests. The response
+/// carries the request's header.
+pub fn call_with_rpc(
+    envelope: &Envelope,
+    handler: impl FnOnce(RpcRequest) -> RpcResponse,
+) -> Result<Envelope, EnvelopeError> {
+    let response = handler(envelope_to_request(envelope)?);
+    response_to_envelope(&response, envelope.header.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A builtin written against envelopes: doubles `n`
+    fn double(input: &Envelope) -> Result<Envelope, String> {
+        let params: serde_json::Value = input.as_json().map_err(|e| e.to_string())?;
+        let n = params["n"].as_i64().ok_or("n must be a number")?;
+        Envelope::json(&serde_json::json!({ "n": n * 2 })).map_err(|e| e.to_string())
+    }
+
+    /// The same extension written against JSON-RPC
+    fn double_rpc(request: RpcRequest) -> RpcResponse {
+        match request.params["n"].as_i64() {
+            Some(n) => RpcResponse::result(request.id, serde_json::json!({ "n": n * 2 })),
+            None => RpcResponse::error(
+                request.id,
+                RpcError {
+                    code: -32602,
+                    message: "n must be a number".to_string(),
+                    data: None,
+                },
+            ),
+        }
+    }
+
+    fn request(params: serde_json::Value) -> RpcRequest {
+        RpcRequest {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            method: "morphir.transform.transform".to_string(),
+            params,
+            id: 42,
+        }
+    }
+
+    #[test]
+    fn test_requests_and_responses_round_trip() {
+        let rpc = request(serde_json::json!({ "n": 2 }));
+        let envelope = request_to_envelope(&rpc).unwrap();
+        assert_eq!(
+            envelope.header.kind.as_deref(),
+            Some("morphir.transform.transform")
+        );
+        assert_eq!(envelope.header.seqnum, 42);
+        assert_eq!(envelope_to_request(&envelope).unwrap(), rpc);
+
+        let failure = RpcResponse::error(
+            42,
+            RpcError {
+                code: -32004,
+                message: "no".to_string(),
+                data: Some(serde_json::json!({ "at": "orders" })),
+            },
+        );
+        let envelope = response_to_envelope(&failure, Header::default()).unwrap();
+        assert_eq!(envelope.content_type, ERROR_CONTENT_TYPE);
+        assert_eq!(
+            envelope_to_response(&envelope.with_header(Header {
+                seqnum: 42,
+                ..Header::default()
+            }))
+            .unwrap(),
+            failure
+        );
+
+        let binary = Envelope::new("application/octet-stream", vec![1, 2]);
+        assert!(matches!(
+            envelope_to_request(&binary),
+            Err(EnvelopeError::MissingKind)
+        ));
+        let binary = binary.with_header(Header {
+            kind: Some("m".to_string()),
+            ..Header::default()
+        });
+        assert!(matches!(
+            envelope_to_request(&binary),
+            Err(EnvelopeError::ContentTypeMismatch { .. })
+        ));
+        let mut vendor_json = request_to_envelope(&rpc).unwrap();
+        vendor_json.content_type = "application/vnd.morphir.ir+json; charset=utf-8".to_string();
+        assert_eq!(envelope_to_request(&vendor_json).unwrap(), rpc);
+    }
+
+    #[test]
+    fn test_handlers_run_over_either_transport() {
+        // The envelope handler answers JSON-RPC requests as the RPC one does
+        for params in [serde_json::json!({ "n": 21 }), serde_json::json!({})] {
+            let over_rpc = call_with_envelope(&request(params.clone()), double);
+            let native = double_rpc(request(params));
+            assert_eq!(over_rpc.id, native.id);
+            assert_eq!(over_rpc.result, native.result);
+            assert_eq!(over_rpc.error.is_some(), native.error.is_some());
+        }
+
+        // The RPC handler answers envelopes as the envelope one does
+        let input = Envelope::json(&serde_json::json!({ "n": 21 }))
+            .unwrap()
+            .with_header(Header {
+                seqnum: 7,
+                session_id: "s".to_string(),
+                kind: Some("double".to_string()),
+            });
+        let over_envelope = call_with_rpc(&input, double_rpc).unwrap();
+        assert_eq!(over_envelope.header, input.header);
+        assert_eq!(
+            over_envelope.as_json::<serde_json::Value>().unwrap(),
+            double(&input)
+                .unwrap()
+                .as_json::<serde_json::Value>()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,434 @@
+//! Commands of TEA extensions.
+//!
+//! Alongside its model, `init` and `update` return a JSON envelope of
+//! commands: effects the extension asks the host to perform for it, since it
+//! cannot perform them itself.
+//!
+//! ```json
+//! [
+//!   {"type": "read-file", "id": "orders", "path": "src/orders.gleam"},
+//!   {"type": "http-fetch", "id": "schema", "url": "https://schemas.example.com/order.json"},
+//!   {"type": "log", "level": "Info", "message": "Compiling orders"},
+//!   {"type": "emit-artifact", "path": "orders.ts", "content": "export type Order = {}"}
+//! ]
+//! ```
+//!
+//! A [`CommandInterpreter`] performs them. Files are read relative to its
+//! root and may not escape it, fetches are only made to the hosts it allows,
+//! and emitted artifacts are collected for the host to write. The outcome of
+//! each command with an `id` is fed back to `update` as a [`CommandResult`]:
+//!
+//! ```json
+//! {"command": "orders", "outcome": {"type": "file-read", "path": "src/orders.gleam", "content": "..."}}
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::types::Artifact;
+use serde::{Deserialize, Serialize};
+
+use crate::LogLevel;
+
+/// Redirects followed by a fetch before giving up
+const MAX_REDIRECTS: usize = 10;
+
+/// An effect an extension asks the host to perform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Command {
+    /// Read a text file, relative to the interpreter's root
+    ReadFile {
+        #[serde(default)]
+        id: Option<String>,
+        path: PathBuf,
+    },
+    /// Make an HTTP request to an allowed host
+    HttpFetch {
+        #[serde(default)]
+        id: Option<String>,
+        url: String,
+        #[serde(default = "default_method")]
+        method: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        #[serde(default)]
+        body: Option<String>,
+    },
+    /// Log a message on the extension's behalf
+    Log { level: LogLevel, message: String },
+    /// Hand a generated file to the host
+    EmitArtifact {
+        #[serde(default)]
+        id: Option<String>,
+        #[serde(flatten)]
+        artifact: Artifact,
+    },
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+impl Command {
+    /// The ID the command's result is fed back with, if it wants one
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            Command::ReadFile { id, .. }
+            | Command::HttpFetch { id, .. }
+            | Command::EmitArtifact { id, .. } => id.as_deref(),
+            Command::Log { .. } => None,
+        }
+    }
+}
+
+/// How a command turned out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum CommandOutcome {
+    FileRead {
+        path: PathBuf,
+        content: String,
+    },
+    Fetched {
+        status: u16,
+        headers: BTreeMap<String, String>,
+        body: String,
+    },
+    ArtifactEmitted {
+        path: String,
+    },
+    Logged,
+    /// The command was refused or failed
+    Failed {
+        error: String,
+    },
+}
+
+/// The message a command's outcome is delivered to `update` as
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandResult {
+    /// ID of the command
+    pub command: String,
+    pub outcome: CommandOutcome,
+}
+
+/// The commands in an envelope; `null` asks for nothing
+pub fn parse(envelope: &Envelope) -> Result<Vec<Command>> {
+    let commands: Option<Vec<Command>> = envelope.as_json().context("Invalid commands envelope")?;
+    Ok(commands.unwrap_or_default())
+}
+
+/// Performs the commands of one extension
+#[derive(Default)]
+pub struct CommandInterpreter {
+    root: PathBuf,
+    allowed_hosts: Vec<String>,
+    artifacts: Vec<Artifact>,
+    client: Option<reqwest::blocking::Client>,
+}
+
+impl CommandInterpreter {
+    /// Interpreter reading files relative to the current directory and
+    /// fetching from no host
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            ..Self::default()
+        }
+    }
+
+    /// Read files relative to `root`
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Allow fetches from `hosts`; `*.example.com` allows subdomains and `*`
+    /// any host
+    pub fn with_allowed_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.allowed_hosts = hosts;
+        self
+    }
+
+    /// Artifacts emitted so far
+    pub fn artifacts(&self) -> &[Artifact] {
+        &self.artifacts
+    }
+
+    /// Take the artifacts emitted so far
+    pub fn take_artifacts(&mut self) -> Vec<Artifact> {
+        std::mem::take(&mut self.artifacts)
+    }
+
+    /// Perform the commands in `commands`, in order, returning the messages
+    /// to feed back to `update`
+    pub fn execute(&mut self, commands: &Envelope) -> Result<Vec<Envelope>> {
+        let mut messages = Vec::new();
+        for command in parse(commands)? {
+            let outcome = self.perform(&command);
+            if let Some(id) = command.id() {
+                messages.push(Envelope::json(&CommandResult {
+                    command: id.to_string(),
+                    outcome,
+                })?);
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Perform one command
+    pub fn perform(&mut self, command: &Command) -> CommandOutcome {
+        let outcome = match command {
+            Command::ReadFile { path, .. } => self.read_file(path),
+            Command::HttpFetch {
+                url,
+                method,
+                headers,
+                body,
+                ..
+            } => self.fetch(url, method, headers, body.as_deref()),
+            Command::Log { level, message } => {
+                match level {
+                    LogLevel::Trace => tracing::trace!("Extension: {}", message),
+                    LogLevel::Debug => tracing::debug!("Extension: {}", message),
+                    LogLevel::Info => tracing::info!("Extension: {}", message),
+                    LogLevel::Warn => tracing::warn!("Extension: {}", message),
+                    LogLevel::Error => tracing::error!("Extension: {}", message),
+                }
+                Ok(CommandOutcome::Logged)
+            }
+            Command::EmitArtifact { artifact, .. } => {
+                if contained(Path::new(&artifact.path)) {
+                    self.artifacts.push(artifact.clone());
+                    Ok(CommandOutcome::ArtifactEmitted {
+                        path: artifact.path.clone(),
+                    })
+                } else {
+                    Err(anyhow::anyhow!(
+                        "Artifact path {} is absolute or escapes the output directory",
+                        artifact.path
+                    ))
+                }
+            }
+        };
+        outcome.unwrap_or_else(|error| CommandOutcome::Failed {
+            error: format!("{:#}", error),
+        })
+    }
+
+    fn read_file(&self, path: &Path) -> Result<CommandOutcome> {
+        if !contained(path) {
+            anyhow::bail!("{} is outside the workspace", path.display());
+        }
+        let content = std::fs::read_to_string(self.root.join(path))
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(CommandOutcome::FileRead {
+            path: path.to_path_buf(),
+            content,
+        })
+    }
+
+    fn fetch(
+        &mut self,
+        url: &str,
+        method: &str,
+        headers: &BTreeMap<String, String>,
+        body: Option<&str>,
+    ) -> Result<CommandOutcome> {
+        let parsed = reqwest::Url::parse(url).with_context(|| format!("Invalid URL {}", url))?;
+        check_url(&parsed, &self.allowed_hosts)?;
+
+        let client = match &self.client {
+            Some(client) => client,
+            None => {
+                // Redirects are held to the same hosts as the first request
+                let allowed_hosts = self.allowed_hosts.clone();
+                let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+                    if attempt.previous().len() >= MAX_REDIRECTS {
+                        return attempt.error(anyhow::anyhow!("Too many redirects"));
+                    }
+                    match check_url(attempt.url(), &allowed_hosts) {
+                        Ok(()) => attempt.follow(),
+                        Err(e) => attempt.error(e),
+                    }
+                });
+                self.client.insert(
+                    reqwest::blocking::Client::builder()
+                        .timeout(Duration::from_secs(30))
+                        .redirect(redirects)
+                        .build()?,
+                )
+            }
+        };
+        let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+            .with_context(|| format!("Invalid HTTP method {}", method))?;
+        let mut request = client.request(method, parsed);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if let Some(body) = body {
+            request = request.body(body.to_string());
+        }
+        let response = request.send()?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        Ok(CommandOutcome::Fetched {
+            status,
+            headers,
+            body: response.text()?,
+        })
+    }
+}
+
+/// Whether `path` is relative and stays under the directory it is relative to
+fn contained(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Check that `url` may be fetched from one of the `allowed_hosts`
+fn check_url(url: &reqwest::Url, allowed_hosts: &[String]) -> Result<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        anyhow::bail!("Only http and https URLs can be fetched, not {}", url);
+    }
+    let host = url.host_str().unwrap_or_default();
+    if !allowed_hosts
+        .iter()
+        .any(|allowed| host_matches(host, allowed))
+    {
+        anyhow::bail!("Host {} is not allowed", host);
+    }
+    Ok(())
+}
+
+/// Whether a host matches an allowed host pattern; `*.example.com` matches
+/// subdomains of `example.com` and `*` any host
+pub fn host_matches(host: &str, allowed: &str) -> bool {
+    if allowed == "*" || allowed == host {
+        return true;
+    }
+    allowed.strip_prefix("*.").is_some_and(|domain| {
+        host.strip_suffix(domain)
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_commands_are_performed_within_their_bounds() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("orders.gleam"), "pub type Order").unwrap();
+        let mut interpreter = CommandInterpreter::new()
+            .with_root(root.path())
+            .with_allowed_hosts(vec!["*.example.com".into()]);
+        let commands = Envelope::json(&json!([
+            {"type": "read-file", "id": "orders", "path": "orders.gleam"},
+            {"type": "read-file", "id": "secrets", "path": "../secrets"},
+            {"type": "http-fetch", "id": "elsewhere", "url": "https://example.org/schema.json"},
+            {"type": "http-fetch", "id": "file", "url": "file:///etc/passwd"},
+            {"type": "log", "level": "Info", "message": "hello"},
+            {"type": "emit-artifact", "id": "ts", "path": "orders.ts", "content": "export {}"},
+            {"type": "emit-artifact", "path": "/etc/orders.ts", "content": ""},
+        ]))
+        .unwrap();
+
+        let results: Vec<CommandResult> = interpreter
+            .execute(&commands)
+            .unwrap()
+            .iter()
+            .map(|message| message.as_json().unwrap())
+            .collect();
+        let ids: Vec<&str> = results.iter().map(|r| r.command.as_str()).collect();
+        assert_eq!(ids, ["orders", "secrets", "elsewhere", "file", "ts"]);
+        assert_eq!(
+            results[0].outcome,
+            CommandOutcome::FileRead {
+                path: "orders.gleam".into(),
+                content: "pub type Order".into()
+            }
+        );
+        let failed = |outcome: &CommandOutcome| match outcome {
+            CommandOutcome::Failed { error } => error.clone(),
+            other => panic!("expected a failure, got {:?}", other),
+        };
+        assert!(failed(&results[1].outcome).contains("outside the workspace"));
+        assert!(failed(&results[2].outcome).contains("example.org is not allowed"));
+        assert!(failed(&results[3].outcome).contains("Only http and https"));
+        assert_eq!(
+            results[4].outcome,
+            CommandOutcome::ArtifactEmitted {
+                path: "orders.ts".into()
+            }
+        );
+        // Only the contained artifact was collected
+        assert_eq!(interpreter.take_artifacts().len(), 1);
+        assert!(interpreter.artifacts().is_empty());
+
+        assert!(
+            parse(&Envelope::json(&json!(null)).unwrap())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(parse(&Envelope::json(&json!([{"type": "launch"}])).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_redirects_are_held_to_allowed_hosts() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://127.0.0.1:{}/schema.json",
+            listener.local_addr().unwrap().port()
+        );
+        let server = std::thread::spawn(move || {
+            use std::io::{BufRead, BufReader, Write};
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 302 Found\r\nLocation: http://example.org/schema.json\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+        });
+
+        let mut interpreter =
+            CommandInterpreter::new().with_allowed_hosts(vec!["127.0.0.1".into()]);
+        let outcome = interpreter.perform(&Command::HttpFetch {
+            id: None,
+            url,
+            method: "GET".into(),
+            headers: BTreeMap::new(),
+            body: None,
+        });
+        server.join().unwrap();
+        match outcome {
+            CommandOutcome::Failed { error } => {
+                assert!(error.contains("example.org is not allowed"), "{}", error)
+            }
+            other => panic!("expected a failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_host_patterns() {
+        assert!(host_matches("api.example.com", "*.example.com"));
+        assert!(!host_matches("example.com", "*.example.com"));
+        assert!(!host_matches("badexample.com", "*.example.com"));
+        assert!(host_matches("example.com", "example.com"));
+        assert!(host_matches("anything.io", "*"));
+    }
+}
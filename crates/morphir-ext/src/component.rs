@@ -0,0 +1,862 @@
+//! Component-model compiler extensions.
+//!
+//! A component built against one of the worlds in
+//! `morphir-ext-core/wit/compiler.wit` exports some of the `frontend`,
+//! `backend`, `transform` and `validator` interfaces. A
+//! [`ComponentExtension`] instantiates it, finds which of them it exports,
+//! and calls them with the SDK's request and result types, converting at the
+//! boundary, so a component compiles and generates like any other extension.
+//!
+//! [`ComponentExtension::limited`] holds every call to the memory, fuel and
+//! time limits of a [`ResourceLimits`]; a call running into one fails with a
+//! [`LimitExceeded`] error.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use morphir_extension_sdk::ExtensionError;
+use morphir_extension_sdk::types::{
+    Artifact, CodeFix, CompileRequest, CompileResult, Diagnostic, DiagnosticSeverity,
+    ExtensionType, GenerateRequest, GenerateResult, RelatedInformation, ResourceLimits, SourceFile,
+    SourceLocation, SourceMapEntry, TextEdit, TransformRequest, TransformResult, ValidateRequest,
+    ValidateResult,
+};
+use wasmtime::component::{Component, HasSelf, InstancePre, Linker};
+use wasmtime::{Config, Engine, ResourceLimiter, Store, Trap};
+
+use crate::runtime::{EnvValue, LogLevel};
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        world: "compiler",
+        path: "../morphir-ext-core/wit",
+    });
+}
+
+use bindings::exports::morphir::ext::{backend, frontend, transform, validator};
+use bindings::morphir::ext::{envelope, runtime, types as wit};
+
+/// A resource limit a call ran into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum LimitExceeded {
+    #[error("memory limit exceeded")]
+    Memory,
+    #[error("fuel exhausted")]
+    Fuel,
+    #[error("time limit exceeded")]
+    Time,
+}
+
+/// State of the host functions a component imports
+#[derive(Debug, Default)]
+struct HostState {
+    id: String,
+    env_vars: HashMap<String, EnvValue>,
+    max_memory_bytes: Option<u64>,
+}
+
+impl ResourceLimiter for HostState {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _max: Option<usize>,
+    ) -> Result<bool> {
+        match self.max_memory_bytes {
+            Some(max) if desired as u64 > max => Err(LimitExceeded::Memory.into()),
+            _ => Ok(true),
+        }
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _max: Option<usize>,
+    ) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+impl runtime::Host for HostState {
+    fn log(&mut self, level: runtime::LogLevel, msg: String) {
+        let extension = self.id.as_str();
+        match LogLevel::from(level) {
+            LogLevel::Trace => tracing::trace!(extension, "{}", msg),
+            LogLevel::Debug => tracing::debug!(extension, "{}", msg),
+            LogLevel::Info => tracing::info!(extension, "{}", msg),
+            LogLevel::Warn => tracing::warn!(extension, "{}", msg),
+            LogLevel::Error => tracing::error!(extension, "{}", msg),
+        }
+    }
+
+    fn get_env_var(&mut self, name: String) -> Option<runtime::EnvValue> {
+        self.env_vars.get(&name).cloned().map(Into::into)
+    }
+
+    fn set_env_var(&mut self, name: String, value: runtime::EnvValue) {
+        self.env_vars.insert(name, value.into());
+    }
+}
+
+impl envelope::Host for HostState {}
+impl wit::Host for HostState {}
+
+/// Indices of the compiler interfaces a component exports
+struct Indices {
+    frontend: Option<frontend::GuestIndices>,
+    backend: Option<backend::GuestIndices>,
+    transform: Option<transform::GuestIndices>,
+    validator: Option<validator::GuestIndices>,
+}
+
+/// The compiler interfaces of an instance
+struct Exports {
+    frontend: Option<frontend::Guest>,
+    backend: Option<backend::Guest>,
+    transform: Option<transform::Guest>,
+    validator: Option<validator::Guest>,
+}
+
+/// An instantiated component and the compiler interfaces it exports
+pub struct ComponentExtension {
+    pre: InstancePre<HostState>,
+    indices: Indices,
+    store: Store<HostState>,
+    exports: Exports,
+    limits: ResourceLimits,
+}
+
+impl ComponentExtension {
+    /// Instantiate the component in `bytes` (binary, or text when the
+    /// engine accepts it) as the extension `id`
+    pub fn from_bytes(id: &str, bytes: &[u8]) -> Result<Self> {
+        Self::with_engine(&Engine::default(), id, bytes)
+    }
+
+    /// Instantiate the component at `path`
+    pub fn from_file(id: &str, path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read component {}", path.display()))?;
+        Self::from_bytes(id, &bytes)
+    }
+
+    /// Instantiate the component in `bytes` with `engine`, without limits
+    pub fn with_engine(engine: &Engine, id: &str, bytes: &[u8]) -> Result<Self> {
+        let unlimited = ResourceLimits {
+            max_memory_bytes: None,
+            max_time_ms: None,
+            max_fuel: None,
+        };
+        Self::instantiate(engine, id, bytes, unlimited)
+    }
+
+    /// Instantiate the component in `bytes`, holding its instantiation and
+    /// each call to `limits`
+    pub fn limited(id: &str, bytes: &[u8], limits: &ResourceLimits) -> Result<Self> {
+        let mut config = Config::new();
+        config
+            .consume_fuel(limits.max_fuel.is_some())
+            .epoch_interruption(limits.max_time_ms.is_some());
+        Self::instantiate(&Engine::new(&config)?, id, bytes, limits.clone())
+    }
+
+    fn instantiate(
+        engine: &Engine,
+        id: &str,
+        bytes: &[u8],
+        limits: ResourceLimits,
+    ) -> Result<Self> {
+        let component = Component::new(engine, bytes)
+            .with_context(|| format!("Extension {} is not a valid component", id))?;
+        let mut linker = Linker::new(engine);
+        bindings::Compiler::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
+        let pre = linker.instantiate_pre(&component)?;
+
+        let indices = Indices {
+            frontend: exported(&pre, "frontend", frontend::GuestIndices::new)?,
+            backend: exported(&pre, "backend", backend::GuestIndices::new)?,
+            transform: exported(&pre, "transform", transform::GuestIndices::new)?,
+            validator: exported(&pre, "validator", validator::GuestIndices::new)?,
+        };
+        if indices.frontend.is_none()
+            && indices.backend.is_none()
+            && indices.transform.is_none()
+            && indices.validator.is_none()
+        {
+            anyhow::bail!(
+                "Extension {} exports none of the morphir:ext frontend, backend, transform or \
+                 validator interfaces",
+                id
+            );
+        }
+
+        let state = HostState {
+            id: id.to_string(),
+            max_memory_bytes: limits.max_memory_bytes,
+            ..Default::default()
+        };
+        let (store, exports) = start(&pre, &indices, &limits, state)?;
+        Ok(Self {
+            pre,
+            indices,
+            store,
+            exports,
+            limits,
+        })
+    }
+
+    /// The extension ID the component was instantiated as
+    pub fn id(&self) -> &str {
+        &self.store.data().id
+    }
+
+    /// Capabilities the component exports
+    pub fn types(&self) -> Vec<ExtensionType> {
+        [
+            (self.indices.frontend.is_some(), ExtensionType::Frontend),
+            (self.indices.backend.is_some(), ExtensionType::Backend),
+            (self.indices.transform.is_some(), ExtensionType::Transform),
+            (self.indices.validator.is_some(), ExtensionType::Validator),
+        ]
+        .into_iter()
+        .filter_map(|(exported, capability)| exported.then_some(capability))
+        .collect()
+    }
+
+    /// Set an environment variable the component reads with `get-env-var`
+    pub fn set_env_var(&mut self, name: impl Into<String>, value: EnvValue) {
+        self.store.data_mut().env_vars.insert(name.into(), value);
+    }
+
+    /// An environment variable, possibly set by the component
+    pub fn get_env_var(&self, name: &str) -> Option<&EnvValue> {
+        self.store.data().env_vars.get(name)
+    }
+
+    /// `frontend.compile`
+    pub fn compile(&mut self, request: &CompileRequest) -> Result<CompileResult> {
+        self.require(self.indices.frontend.is_some(), "frontend")?;
+        let request = request.try_into()?;
+        let result = self.call(|exports, store| match &exports.frontend {
+            Some(guest) => guest.call_compile(store, &request),
+            None => unreachable!("checked above"),
+        })?;
+        Ok(result.map_err(ExtensionError::from)?.try_into()?)
+    }
+
+    /// `backend.generate`
+    pub fn generate(&mut self, request: &GenerateRequest) -> Result<GenerateResult> {
+        self.require(self.indices.backend.is_some(), "backend")?;
+        let request = wit::GenerateRequest {
+            ir: serde_json::to_string(&request.ir)?,
+            options: serde_json::to_string(&request.options)?,
+        };
+        let result = self.call(|exports, store| match &exports.backend {
+            Some(guest) => guest.call_generate(store, &request),
+            None => unreachable!("checked above"),
+        })?;
+        Ok(result.map_err(ExtensionError::from)?.into())
+    }
+
+    /// `transform.apply`
+    pub fn transform(&mut self, request: &TransformRequest) -> Result<TransformResult> {
+        self.require(self.indices.transform.is_some(), "transform")?;
+        let request = wit::TransformRequest {
+            ir: serde_json::to_string(&request.ir)?,
+            options: serde_json::to_string(&request.options)?,
+        };
+        let result = self.call(|exports, store| match &exports.transform {
+            Some(guest) => guest.call_apply(store, &request),
+            None => unreachable!("checked above"),
+        })?;
+        let result = result.map_err(ExtensionError::from)?;
+        Ok(TransformResult {
+            success: result.success,
+            ir: result.ir.as_deref().map(parse_json).transpose()?,
+            diagnostics: result.diagnostics.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// `validator.validate`
+    pub fn validate(&mut self, request: &ValidateRequest) -> Result<ValidateResult> {
+        self.require(self.indices.validator.is_some(), "validator")?;
+        let request = wit::ValidateRequest {
+            ir: serde_json::to_string(&request.ir)?,
+            options: serde_json::to_string(&request.options)?,
+        };
+        let result = self.call(|exports, store| match &exports.validator {
+            Some(guest) => guest.call_validate(store, &request),
+            None => unreachable!("checked above"),
+        })?;
+        let result = result.map_err(ExtensionError::from)?;
+        Ok(ValidateResult {
+            valid: result.valid,
+            diagnostics: result.diagnostics.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    fn require(&self, exported: bool, capability: &str) -> Result<()> {
+        match exported {
+            true => Ok(()),
+            false => Err(unsupported(self.id(), capability).into()),
+        }
+    }
+
+    /// Make a guarded call. A trap leaves the instance unusable, so the
+    /// component is instantiated afresh for the calls after it.
+    fn call<R>(
+        &mut self,
+        call: impl FnOnce(&Exports, &mut Store<HostState>) -> Result<R>,
+    ) -> Result<R> {
+        let exports = &self.exports;
+        let result = guarded(&mut self.store, &self.limits, |store| call(exports, store));
+        if let Err(error) = &result
+            && (error.is::<Trap>() || error.is::<LimitExceeded>())
+        {
+            let state = HostState {
+                id: self.store.data().id.clone(),
+                env_vars: std::mem::take(&mut self.store.data_mut().env_vars),
+                max_memory_bytes: self.limits.max_memory_bytes,
+            };
+            match start(&self.pre, &self.indices, &self.limits, state) {
+                Ok((store, exports)) => {
+                    self.store = store;
+                    self.exports = exports;
+                }
+                Err(restart) => {
+                    tracing::warn!(extension = self.id(), "Failed to restart: {:#}", restart)
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Instantiate `pre` in a new store holding `state`
+fn start(
+    pre: &InstancePre<HostState>,
+    indices: &Indices,
+    limits: &ResourceLimits,
+    state: HostState,
+) -> Result<(Store<HostState>, Exports)> {
+    let mut store = Store::new(pre.engine(), state);
+    store.limiter(|state| state);
+    let instance = guarded(&mut store, limits, |store| pre.instantiate(store))?;
+    let exports = Exports {
+        frontend: indices
+            .frontend
+            .as_ref()
+            .map(|i| i.load(&mut store, &instance))
+            .transpose()?,
+        backend: indices
+            .backend
+            .as_ref()
+            .map(|i| i.load(&mut store, &instance))
+            .transpose()?,
+        transform: indices
+            .transform
+            .as_ref()
+            .map(|i| i.load(&mut store, &instance))
+            .transpose()?,
+        validator: indices
+            .validator
+            .as_ref()
+            .map(|i| i.load(&mut store, &instance))
+            .transpose()?,
+    };
+    Ok((store, exports))
+}
+
+/// Run `call` on `store` with a fresh fuel budget and deadline from
+/// `limits`. Running out of either fails with [`LimitExceeded`].
+fn guarded<R>(
+    store: &mut Store<HostState>,
+    limits: &ResourceLimits,
+    call: impl FnOnce(&mut Store<HostState>) -> Result<R>,
+) -> Result<R> {
+    if let Some(fuel) = limits.max_fuel {
+        store.set_fuel(fuel)?;
+    }
+    let result = match limits.max_time_ms {
+        None => call(store),
+        Some(ms) => {
+            // A watchdog ends the epoch when the time is up. It is joined
+            // before returning, so it cannot interrupt a later call.
+            store.set_epoch_deadline(1);
+            let engine = store.engine().clone();
+            let (done, finished) = mpsc::channel::<()>();
+            let watchdog = std::thread::spawn(move || {
+                if finished.recv_timeout(Duration::from_millis(ms))
+                    == Err(RecvTimeoutError::Timeout)
+                {
+                    engine.increment_epoch();
+                }
+            });
+            let result = call(store);
+            let _ = done.send(());
+            let _ = watchdog.join();
+            result
+        }
+    };
+    result.map_err(|error| {
+        let exceeded = match error.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => Some(LimitExceeded::Fuel),
+            Some(Trap::Interrupt) => Some(LimitExceeded::Time),
+            // Denied memory growth traps with the limiter's error
+            _ => error.downcast_ref::<LimitExceeded>().copied(),
+        };
+        exceeded.map_or(error, Into::into)
+    })
+}
+
+/// Indices of the exports of `morphir:ext/<interface>`, if the component
+/// exports it. An export of that name with other functions is an error
+/// rather than a missing capability.
+fn exported<I>(
+    pre: &InstancePre<HostState>,
+    interface: &str,
+    indices: impl FnOnce(&InstancePre<HostState>) -> Result<I>,
+) -> Result<Option<I>> {
+    let name = format!("morphir:ext/{}@0.1.0", interface);
+    if pre.component().get_export_index(None, &name).is_none() {
+        return Ok(None);
+    }
+    indices(pre)
+        .map(Some)
+        .with_context(|| format!("Export {} does not match its WIT interface", name))
+}
+
+fn unsupported(id: &str, capability: &str) -> ExtensionError {
+    ExtensionError::UnsupportedCapability {
+        extension: id.to_string(),
+        capability: capability.to_string(),
+    }
+}
+
+fn parse_json(json: &str) -> std::result::Result<serde_json::Value, ExtensionError> {
+    serde_json::from_str(json)
+        .map_err(|e| ExtensionError::invalid_response(format!("IR is not valid JSON: {}", e)))
+}
+
+impl From<wit::ExtensionError> for ExtensionError {
+    fn from(error: wit::ExtensionError) -> Self {
+        match error {
+            wit::ExtensionError::Unsupported(capability) => ExtensionError::UnsupportedCapability {
+                extension: "component".to_string(),
+                capability,
+            },
+            wit::ExtensionError::ExecutionFailed(message) => {
+                ExtensionError::ExecutionFailed(message)
+            }
+            wit::ExtensionError::Host(message) => ExtensionError::Host(message),
+        }
+    }
+}
+
+impl TryFrom<&CompileRequest> for wit::CompileRequest {
+    type Error = serde_json::Error;
+
+    fn try_from(request: &CompileRequest) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            sources: request.sources.iter().map(Into::into).collect(),
+            options: serde_json::to_string(&request.options)?,
+            previous_fingerprints: request
+                .previous_fingerprints
+                .iter()
+                .map(|(path, fingerprint)| (path.clone(), fingerprint.clone()))
+                .collect(),
+            changed_files: request.changed_files.clone(),
+        })
+    }
+}
+
+impl From<&SourceFile> for wit::SourceFile {
+    fn from(source: &SourceFile) -> Self {
+        Self {
+            path: source.path.clone(),
+            content: source.content.clone(),
+        }
+    }
+}
+
+impl TryFrom<wit::CompileResult> for CompileResult {
+    type Error = ExtensionError;
+
+    fn try_from(result: wit::CompileResult) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            success: result.success,
+            ir: result.ir.as_deref().map(parse_json).transpose()?,
+            diagnostics: result.diagnostics.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl From<wit::GenerateResult> for GenerateResult {
+    fn from(result: wit::GenerateResult) -> Self {
+        Self {
+            success: result.success,
+            artifacts: result
+                .artifacts
+                .into_iter()
+                .map(|a| Artifact {
+                    path: a.path,
+                    content: a.content,
+                    binary: a.binary,
+                })
+                .collect(),
+            diagnostics: result.diagnostics.into_iter().map(Into::into).collect(),
+            source_map: result
+                .source_map
+                .into_iter()
+                .map(|e| SourceMapEntry {
+                    path: e.path,
+                    start_line: e.start_line,
+                    end_line: e.end_line,
+                    fqname: e.fqname,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<wit::Diagnostic> for Diagnostic {
+    fn from(diagnostic: wit::Diagnostic) -> Self {
+        Self {
+            severity: match diagnostic.severity {
+                wit::DiagnosticSeverity::Error => DiagnosticSeverity::Error,
+                wit::DiagnosticSeverity::Warning => DiagnosticSeverity::Warning,
+                wit::DiagnosticSeverity::Info => DiagnosticSeverity::Info,
+                wit::DiagnosticSeverity::Hint => DiagnosticSeverity::Hint,
+            },
+            code: diagnostic.code,
+            message: diagnostic.message,
+            location: diagnostic.location.map(Into::into),
+            related: diagnostic
+                .related
+                .into_iter()
+                .map(|r| RelatedInformation {
+                    location: r.location.into(),
+                    message: r.message,
+                })
+                .collect(),
+            fixes: diagnostic
+                .fixes
+                .into_iter()
+                .map(|f| CodeFix {
+                    title: f.title,
+                    edits: f
+                        .edits
+                        .into_iter()
+                        .map(|e| TextEdit {
+                            location: e.location.into(),
+                            new_text: e.new_text,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<wit::SourceLocation> for SourceLocation {
+    fn from(location: wit::SourceLocation) -> Self {
+        Self {
+            file: location.file,
+            start_line: location.start_line,
+            start_col: location.start_col,
+            end_line: location.end_line,
+            end_col: location.end_col,
+        }
+    }
+}
+
+impl From<runtime::LogLevel> for LogLevel {
+    fn from(level: runtime::LogLevel) -> Self {
+        match level {
+            runtime::LogLevel::Trace => LogLevel::Trace,
+            runtime::LogLevel::Debug => LogLevel::Debug,
+            runtime::LogLevel::Info => LogLevel::Info,
+            runtime::LogLevel::Warn => LogLevel::Warn,
+            runtime::LogLevel::Error => LogLevel::Error,
+        }
+    }
+}
+
+impl From<runtime::EnvValue> for EnvValue {
+    fn from(value: runtime::EnvValue) -> Self {
+        use runtime::EnvValue as W;
+        match value {
+            W::Text(v) => EnvValue::Text(v),
+            W::TextList(v) => EnvValue::TextList(v),
+            W::Boolean(v) => EnvValue::Boolean(v),
+            W::VU8(v) => EnvValue::U8(v),
+            W::VU16(v) => EnvValue::U16(v),
+            W::VU32(v) => EnvValue::U32(v),
+            W::VU64(v) => EnvValue::U64(v),
+            W::VS8(v) => EnvValue::I8(v),
+            W::VS16(v) => EnvValue::I16(v),
+            W::VS32(v) => EnvValue::I32(v),
+            W::VS64(v) => EnvValue::I64(v),
+            W::VF32(v) => EnvValue::F32(v),
+            W::VF64(v) => EnvValue::F64(v),
+        }
+    }
+}
+
+impl From<EnvValue> for runtime::EnvValue {
+    fn from(value: EnvValue) -> Self {
+        use runtime::EnvValue as W;
+        match value {
+            EnvValue::Text(v) => W::Text(v),
+            EnvValue::TextList(v) => W::TextList(v),
+            EnvValue::Boolean(v) => W::Boolean(v),
+            EnvValue::U8(v) => W::VU8(v),
+            EnvValue::U16(v) => W::VU16(v),
+            EnvValue::U32(v) => W::VU32(v),
+            EnvValue::U64(v) => W::VU64(v),
+            EnvValue::I8(v) => W::VS8(v),
+            EnvValue::I16(v) => W::VS16(v),
+            EnvValue::I32(v) => W::VS32(v),
+            EnvValue::I64(v) => W::VS64(v),
+            EnvValue::F32(v) => W::VF32(v),
+            EnvValue::F64(v) => W::VF64(v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A component exporting a validator whose core `validate` function,
+    /// taking the pointers and lengths of the IR and options, is `validate`
+    fn validator(validate: &str) -> String {
+        format!(
+            r#"
+        (component
+          (core module $m
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 1024))
+            (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+              (local $ptr i32)
+              (local.set $ptr (i32.and (i32.add (global.get $heap) (i32.const 7)) (i32.const -8)))
+              (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+              (local.get $ptr))
+            {validate})
+          (core instance $i (instantiate $m))
+          ;; Types in the signatures of exports must be exported too
+          (type $location' (record (field "file" string) (field "start-line" u32) (field "start-col" u32) (field "end-line" u32) (field "end-col" u32)))
+          (export $location "source-location" (type $location'))
+          (type $related' (record (field "location" $location) (field "message" string)))
+          (export $related "related-information" (type $related'))
+          (type $edit' (record (field "location" $location) (field "new-text" string)))
+          (export $edit "text-edit" (type $edit'))
+          (type $fix' (record (field "title" string) (field "edits" (list $edit))))
+          (export $fix "code-fix" (type $fix'))
+          (type $severity' (enum "error" "warning" "info" "hint"))
+          (export $severity "diagnostic-severity" (type $severity'))
+          (type $diagnostic' (record (field "severity" $severity) (field "code" (option string)) (field "message" string) (field "location" (option $location)) (field "related" (list $related)) (field "fixes" (list $fix))))
+          (export $diagnostic "diagnostic" (type $diagnostic'))
+          (type $request' (record (field "ir" string) (field "options" string)))
+          (export $request "validate-request" (type $request'))
+          (type $result' (record (field "valid" bool) (field "diagnostics" (list $diagnostic))))
+          (export $result "validate-result" (type $result'))
+          (type $error' (variant (case "unsupported" string) (case "execution-failed" string) (case "host" string)))
+          (export $error "extension-error" (type $error'))
+          (func $validate (param "request" $request) (result (result $result (error $error)))
+            (canon lift (core func $i "validate") (memory $i "memory") (realloc (func $i "realloc"))))
+          (instance $validator (export "validate" (func $validate)))
+          (export "morphir:ext/validator@0.1.0" (instance $validator)))
+            "#
+        )
+    }
+
+    /// Answers `valid` for IR longer than `{}`, and fails when given options
+    const CHECK: &str = r#"
+            ;; err(execution-failed("boom"))
+            (data (i32.const 48) "\01\00\00\00\01\00\00\00\60\00\00\00\04\00\00\00")
+            (data (i32.const 96) "boom")
+            (func (export "validate") (param $ir i32) (param $ir_len i32) (param $opts i32) (param $opts_len i32) (result i32)
+              (if (i32.gt_u (local.get $opts_len) (i32.const 2)) (then (return (i32.const 48))))
+              ;; ok({valid, diagnostics: []})
+              (i32.store8 (i32.const 20) (i32.gt_u (local.get $ir_len) (i32.const 2)))
+              (i32.const 16))
+    "#;
+
+    /// Never returns
+    const SPIN: &str = r#"
+            (func (export "validate") (param i32 i32 i32 i32) (result i32)
+              (loop $forever (br $forever))
+              (i32.const 16))
+    "#;
+
+    /// Grows its memory by 100 pages
+    const GREEDY: &str = r#"
+            (func (export "validate") (param i32 i32 i32 i32) (result i32)
+              (drop (memory.grow (i32.const 100)))
+              (i32.const 16))
+    "#;
+
+    fn validate_request(ir: serde_json::Value, options: serde_json::Value) -> ValidateRequest {
+        ValidateRequest {
+            ir,
+            options: serde_json::from_value(options).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_components_are_called_through_their_exports() {
+        let mut extension =
+            ComponentExtension::from_bytes("check", validator(CHECK).as_bytes()).unwrap();
+        assert_eq!(extension.types(), vec![ExtensionType::Validator]);
+
+        let result = extension
+            .validate(&validate_request(
+                serde_json::json!({"modules": []}),
+                serde_json::json!({}),
+            ))
+            .unwrap();
+        assert!(result.valid);
+        assert!(result.diagnostics.is_empty());
+        let result = extension
+            .validate(&validate_request(
+                serde_json::json!({}),
+                serde_json::json!({}),
+            ))
+            .unwrap();
+        assert!(!result.valid);
+
+        let err = extension
+            .validate(&validate_request(
+                serde_json::json!({}),
+                serde_json::json!({"strict": true}),
+            ))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Extension execution failed: boom");
+
+        let err = extension.compile(&CompileRequest::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Extension 'check' does not support capability: frontend"
+        );
+    }
+
+    #[test]
+    fn test_limits_end_runaway_calls() {
+        let limits = |max_memory_bytes, max_time_ms, max_fuel| ResourceLimits {
+            max_memory_bytes,
+            max_time_ms,
+            max_fuel,
+        };
+        let request = validate_request(serde_json::json!({}), serde_json::json!({}));
+        let exceeded = |result: Result<ValidateResult>| {
+            result.unwrap_err().downcast_ref::<LimitExceeded>().copied()
+        };
+
+        let mut spin = ComponentExtension::limited(
+            "spin",
+            validator(SPIN).as_bytes(),
+            &limits(None, None, Some(1_000_000)),
+        )
+        .unwrap();
+        assert_eq!(exceeded(spin.validate(&request)), Some(LimitExceeded::Fuel));
+        // The trapped instance is replaced and the next call gets a fresh budget
+        assert_eq!(exceeded(spin.validate(&request)), Some(LimitExceeded::Fuel));
+
+        let mut spin = ComponentExtension::limited(
+            "spin",
+            validator(SPIN).as_bytes(),
+            &limits(None, Some(50), None),
+        )
+        .unwrap();
+        assert_eq!(exceeded(spin.validate(&request)), Some(LimitExceeded::Time));
+
+        let mut greedy = ComponentExtension::limited(
+            "greedy",
+            validator(GREEDY).as_bytes(),
+            &limits(Some(2 * 64 * 1024), None, None),
+        )
+        .unwrap();
+        assert_eq!(
+            exceeded(greedy.validate(&request)),
+            Some(LimitExceeded::Memory)
+        );
+
+        let mut check = ComponentExtension::limited(
+            "check",
+            validator(CHECK).as_bytes(),
+            &ResourceLimits::default(),
+        )
+        .unwrap();
+        assert!(!check.validate(&request).unwrap().valid);
+    }
+
+    #[test]
+    fn test_components_without_compiler_exports_are_refused() {
+        let err = ComponentExtension::from_bytes("empty", b"(component)")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("exports none"), "{}", err);
+        assert!(ComponentExtension::from_bytes("junk", b"\0asm junk").is_err());
+    }
+
+    #[test]
+    fn test_results_convert_to_sdk_types() {
+        let location = wit::SourceLocation {
+            file: "src/a.elm".into(),
+            start_line: 3,
+            start_col: 1,
+            end_line: 3,
+            end_col: 9,
+        };
+        let result = CompileResult::try_from(wit::CompileResult {
+            success: false,
+            ir: Some(r#"{"modules": []}"#.into()),
+            diagnostics: vec![wit::Diagnostic {
+                severity: wit::DiagnosticSeverity::Warning,
+                code: Some("W001".into()),
+                message: "Unused import".into(),
+                location: Some(location.clone()),
+                related: vec![],
+                fixes: vec![wit::CodeFix {
+                    title: "Remove the import".into(),
+                    edits: vec![wit::TextEdit {
+                        location,
+                        new_text: String::new(),
+                    }],
+                }],
+            }],
+        })
+        .unwrap();
+        assert_eq!(result.ir, Some(serde_json::json!({"modules": []})));
+        let diagnostic = &result.diagnostics[0];
+        assert_eq!(diagnostic.severity, DiagnosticSeverity::Warning);
+        assert_eq!(diagnostic.location.as_ref().unwrap().end_col, 9);
+        assert_eq!(diagnostic.fixes[0].edits[0].location.file, "src/a.elm");
+
+        let err = CompileResult::try_from(wit::CompileResult {
+            success: true,
+            ir: Some("{".into()),
+            diagnostics: vec![],
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("IR is not valid JSON"), "{}", err);
+
+        let request = wit::CompileRequest::try_from(&CompileRequest {
+            sources: vec![SourceFile {
+                path: "src/a.elm".into(),
+                content: "module A".into(),
+            }],
+            options: HashMap::from([("target".into(), serde_json::json!("v4"))]),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(request.options, r#"{"target":"v4"}"#);
+        assert_eq!(request.sources[0].path, "src/a.elm");
+        assert_eq!(request.changed_files, None);
+    }
+}
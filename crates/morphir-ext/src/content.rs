@@ -0,0 +1,413 @@
+//! Schemas for the content types carried in envelopes.
+//!
+//! An envelope's `content_type` says what its bytes hold, but nothing checks
+//! that they do: a malformed payload from an extension is only noticed when a
+//! consumer fails on it, far from the extension that produced it. A
+//! [`ContentRegistry`] maps content types to versioned [`ContentSchema`]s
+//! whose validation hooks run at the boundary, as envelopes leave an
+//! extension, and report where in the payload it went wrong.
+//!
+//! The version of a schema is selected with the `version` parameter of the
+//! content type, e.g. `application/morphir-ir+json; version=4`, and defaults
+//! to the latest registered. Content types without a schema pass unchecked.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use morphir_core::ir::v4;
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::types::{Artifact, Diagnostic};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Content types known to the host
+pub mod content_types {
+    /// A V4 IR distribution as JSON
+    pub const IR_JSON: &str = "application/morphir-ir+json";
+    /// A V4 IR distribution as CBOR
+    pub const IR_CBOR: &str = "application/morphir-ir+cbor";
+    /// A list of diagnostics as JSON
+    pub const DIAGNOSTICS_JSON: &str = "application/morphir-diagnostics+json";
+    /// A list of generated artifacts as JSON
+    pub const ARTIFACTS_JSON: &str = "application/morphir-artifacts+json";
+}
+
+/// How content of a type is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    Cbor,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Json => f.write_str("JSON"),
+            Encoding::Cbor => f.write_str("CBOR"),
+        }
+    }
+}
+
+/// Where decoded content breaks its schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// JSON pointer to the offending value; empty for the whole content
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Violation {
+    /// Violation at `pointer`
+    pub fn at(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Validation hook of a schema, run on the decoded content
+pub type Validator = Arc<dyn Fn(&Value) -> Result<(), Violation> + Send + Sync>;
+
+/// A version of the schema of a content type
+#[derive(Clone)]
+pub struct ContentSchema {
+    /// Content type without parameters, e.g. `application/morphir-ir+json`
+    pub content_type: String,
+    pub version: u32,
+    pub encoding: Encoding,
+    validator: Validator,
+}
+
+impl ContentSchema {
+    /// Schema checking content with `validator`
+    pub fn new(
+        content_type: impl Into<String>,
+        version: u32,
+        encoding: Encoding,
+        validator: impl Fn(&Value) -> Result<(), Violation> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            content_type: content_type.into(),
+            version,
+            encoding,
+            validator: Arc::new(validator),
+        }
+    }
+}
+
+impl fmt::Debug for ContentSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentSchema")
+            .field("content_type", &self.content_type)
+            .field("version", &self.version)
+            .field("encoding", &self.encoding)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Content rejected by a [`ContentRegistry`]
+#[derive(Debug, thiserror::Error)]
+pub enum ContentError {
+    #[error("{content_type} has no schema version {version} (supported: {supported})")]
+    UnsupportedVersion {
+        content_type: String,
+        version: String,
+        supported: String,
+    },
+    #[error("{content_type} content is not valid {encoding}: {message}")]
+    Decode {
+        content_type: String,
+        encoding: Encoding,
+        message: String,
+    },
+    #[error("{content_type} content is malformed at '{pointer}': {message}")]
+    Malformed {
+        content_type: String,
+        pointer: String,
+        message: String,
+    },
+}
+
+/// Versioned schemas of envelope content types
+#[derive(Debug, Clone, Default)]
+pub struct ContentRegistry {
+    schemas: BTreeMap<String, BTreeMap<u32, ContentSchema>>,
+}
+
+impl ContentRegistry {
+    /// Registry without schemas
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the schemas of the [`content_types`] known to the host
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(ContentSchema::new(
+            content_types::IR_JSON,
+            4,
+            Encoding::Json,
+            validate_ir,
+        ));
+        registry.register(ContentSchema::new(
+            content_types::IR_CBOR,
+            4,
+            Encoding::Cbor,
+            validate_ir,
+        ));
+        registry.register(ContentSchema::new(
+            content_types::DIAGNOSTICS_JSON,
+            1,
+            Encoding::Json,
+            validate_items::<Diagnostic>,
+        ));
+        registry.register(ContentSchema::new(
+            content_types::ARTIFACTS_JSON,
+            1,
+            Encoding::Json,
+            validate_items::<Artifact>,
+        ));
+        registry
+    }
+
+    /// Add `schema`, replacing any with the same content type and version
+    pub fn register(&mut self, schema: ContentSchema) {
+        self.schemas
+            .entry(schema.content_type.clone())
+            .or_default()
+            .insert(schema.version, schema);
+    }
+
+    /// Schema for `content_type`, by its `version` parameter or the latest
+    pub fn schema(&self, content_type: &str) -> Result<Option<&ContentSchema>, ContentError> {
+        let (essence, version) = parse_content_type(content_type);
+        let Some(versions) = self.schemas.get(&essence) else {
+            return Ok(None);
+        };
+        let schema = match &version {
+            None => versions.values().next_back(),
+            Some(version) => version.parse().ok().and_then(|v| versions.get(&v)),
+        };
+        match schema {
+            Some(schema) => Ok(Some(schema)),
+            None => Err(ContentError::UnsupportedVersion {
+                content_type: essence,
+                version: version.unwrap_or_default(),
+                supported: versions
+                    .keys()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }),
+        }
+    }
+
+    /// Check the content of `envelope` against the schema of its content type
+    pub fn validate(&self, envelope: &Envelope) -> Result<(), ContentError> {
+        let Some(schema) = self.schema(&envelope.content_type)? else {
+            return Ok(());
+        };
+        let decode_error = |message: String| ContentError::Decode {
+            content_type: schema.content_type.clone(),
+            encoding: schema.encoding,
+            message,
+        };
+        let content: Value = match schema.encoding {
+            Encoding::Json => serde_json::from_slice(&envelope.content)
+                .map_err(|e| decode_error(e.to_string()))?,
+            Encoding::Cbor => ciborium::from_reader(envelope.content.as_slice())
+                .map_err(|e| decode_error(e.to_string()))?,
+        };
+        (schema.validator)(&content).map_err(|violation| ContentError::Malformed {
+            content_type: schema.content_type.clone(),
+            pointer: violation.pointer,
+            message: violation.message,
+        })
+    }
+}
+
+/// Content type without parameters, in lowercase, and its `version`
+fn parse_content_type(content_type: &str) -> (String, Option<String>) {
+    let mut parts = content_type.split(';');
+    let essence = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let version = parts.find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        (name.trim().eq_ignore_ascii_case("version"))
+            .then(|| value.trim().trim_matches('"').to_string())
+    });
+    (essence, version)
+}
+
+/// Escape `token` for use in a JSON pointer
+fn pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn check<T: DeserializeOwned>(value: &Value, pointer: &str) -> Result<(), Violation> {
+    T::deserialize(value)
+        .map(|_| ())
+        .map_err(|e| Violation::at(pointer, e.to_string()))
+}
+
+/// Every item of a JSON array deserializes as `T`
+fn validate_items<T: DeserializeOwned>(content: &Value) -> Result<(), Violation> {
+    let items = content
+        .as_array()
+        .ok_or_else(|| Violation::at("", "expected an array"))?;
+    for (index, item) in items.iter().enumerate() {
+        check::<T>(item, &format!("/{}", index))?;
+    }
+    Ok(())
+}
+
+/// The content is a V4 distribution, checked a module at a time so errors
+/// point at the module that is wrong
+fn validate_ir(content: &Value) -> Result<(), Violation> {
+    let object = content
+        .as_object()
+        .ok_or_else(|| Violation::at("", "expected an object"))?;
+    let version = object
+        .get("formatVersion")
+        .ok_or_else(|| Violation::at("", "missing formatVersion"))?;
+    check::<v4::FormatVersion>(version, "/formatVersion")?;
+    let distribution = object
+        .get("distribution")
+        .and_then(Value::as_object)
+        .ok_or_else(|| Violation::at("/distribution", "expected an object"))?;
+    let Some((kind, body)) = distribution.iter().next() else {
+        return Err(Violation::at("/distribution", "expected a distribution"));
+    };
+    let pointer = format!("/distribution/{}", pointer_token(kind));
+    let modules = match kind.as_str() {
+        "Library" | "Application" => body.pointer("/def/modules"),
+        "Specs" => body.pointer("/spec/modules"),
+        _ => {
+            return Err(Violation::at(
+                "/distribution",
+                format!("unknown distribution kind {}", kind),
+            ));
+        }
+    };
+    if let Some(modules) = modules.and_then(Value::as_object) {
+        let section = if kind == "Specs" { "spec" } else { "def" };
+        for (name, module) in modules {
+            let at = format!("{}/{}/modules/{}", pointer, section, pointer_token(name));
+            if kind == "Specs" {
+                check::<v4::ModuleSpecification>(module, &at)?;
+            } else {
+                check::<v4::AccessControlled<v4::ModuleDefinition>>(module, &at)?;
+            }
+        }
+    }
+    check::<v4::Distribution>(&Value::Object(distribution.clone()), &pointer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn library(modules: Value) -> Value {
+        json!({
+            "formatVersion": "4.0.0",
+            "distribution": {"Library": {
+                "packageName": "my-org/shop",
+                "dependencies": {},
+                "def": {"modules": modules}
+            }}
+        })
+    }
+
+    #[test]
+    fn test_malformed_payloads_are_rejected_with_their_location() {
+        let registry = ContentRegistry::with_defaults();
+        let valid =
+            library(json!({"orders": {"access": "Public", "value": {"types": {}, "values": {}}}}));
+        let envelope = Envelope::new(content_types::IR_JSON, serde_json::to_vec(&valid).unwrap());
+        registry.validate(&envelope).unwrap();
+
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&valid, &mut cbor).unwrap();
+        registry
+            .validate(&Envelope::new(content_types::IR_CBOR, cbor))
+            .unwrap();
+
+        let invalid = library(json!({"a/b": {"access": "Nobody", "value": {}}}));
+        let err = registry
+            .validate(&Envelope::new(
+                content_types::IR_JSON,
+                serde_json::to_vec(&invalid).unwrap(),
+            ))
+            .unwrap_err();
+        let ContentError::Malformed { pointer, .. } = &err else {
+            panic!("Expected malformed content, got {}", err);
+        };
+        assert_eq!(pointer, "/distribution/Library/def/modules/a~1b");
+
+        let diagnostics = json!([
+            {"severity": "error", "message": "Unknown type"},
+            {"severity": "fatal", "message": "Oops"}
+        ]);
+        let err = registry
+            .validate(&Envelope::new(
+                content_types::DIAGNOSTICS_JSON,
+                serde_json::to_vec(&diagnostics).unwrap(),
+            ))
+            .unwrap_err();
+        assert!(err.to_string().contains("at '/1'"), "{}", err);
+
+        let err = registry
+            .validate(&Envelope::new(content_types::ARTIFACTS_JSON, b"[".to_vec()))
+            .unwrap_err();
+        assert!(matches!(err, ContentError::Decode { .. }));
+
+        // Content types without a schema are not checked
+        registry
+            .validate(&Envelope::new("text/typescript", b"export {}".to_vec()))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_schema_versions_are_selected_by_parameter() {
+        let mut registry = ContentRegistry::with_defaults();
+        registry.register(ContentSchema::new(
+            content_types::ARTIFACTS_JSON,
+            2,
+            Encoding::Json,
+            |content| {
+                content
+                    .get("artifacts")
+                    .map(|_| ())
+                    .ok_or_else(|| Violation::at("", "missing artifacts"))
+            },
+        ));
+        let latest = registry
+            .schema("Application/Morphir-Artifacts+JSON; charset=utf-8")
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.version, 2);
+        let first = registry
+            .schema("application/morphir-artifacts+json; version=1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.version, 1);
+        let err = registry
+            .schema("application/morphir-artifacts+json; version=\"7\"")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "application/morphir-artifacts+json has no schema version 7 (supported: 1, 2)"
+        );
+        assert!(registry.schema("application/json").unwrap().is_none());
+
+        let artifacts = Envelope::new(
+            "application/morphir-artifacts+json; version=1",
+            br#"[{"path": "a.ts", "content": ""}]"#.to_vec(),
+        );
+        registry.validate(&artifacts).unwrap();
+        let artifacts = Envelope::new(content_types::ARTIFACTS_JSON, artifacts.content);
+        assert!(registry.validate(&artifacts).is_err());
+    }
+}
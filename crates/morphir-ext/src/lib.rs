@@ -3,7 +3,15 @@
 //! Actor-based runtime for Morphir extensions using Kameo.
 
 pub mod actor;
+pub mod commands;
+pub mod component;
+pub mod content;
 pub mod runtime;
+pub mod subscriptions;
 
 // Re-export main types
+pub use commands::{Command, CommandInterpreter, CommandOutcome, CommandResult};
+pub use component::{ComponentExtension, LimitExceeded};
+pub use content::{ContentError, ContentRegistry, ContentSchema};
 pub use runtime::{EnvValue, ExtensionInstance, ExtensionRuntime, LogLevel, WitEnvelope};
+pub use subscriptions::{EventLoop, EventLoopHandle, Subscription, SubscriptionMsg};
@@ -2,6 +2,8 @@
 
 use anyhow::Result;
 use morphir_ext_core::Envelope;
+
+use crate::content::ContentRegistry;
 use serde::{Deserialize, Serialize};
 
 /// WIT-compatible envelope type alias.
@@ -102,6 +104,7 @@ pub struct ExtensionInstance {
     runtime: Box<dyn ExtensionRuntime>,
     current_model: Option<Envelope>,
     env_vars: std::collections::HashMap<String, EnvValue>,
+    content: ContentRegistry,
 }
 
 impl ExtensionInstance {
@@ -111,9 +114,25 @@ impl ExtensionInstance {
             runtime,
             current_model: None,
             env_vars: std::collections::HashMap::new(),
+            content: ContentRegistry::with_defaults(),
         }
     }
 
+    /// Check the envelopes the extension returns against `registry` instead
+    /// of the host's default schemas.
+    pub fn with_content_registry(mut self, registry: ContentRegistry) -> Self {
+        self.content = registry;
+        self
+    }
+
+    /// Call `func` with `input`, rejecting output whose content does not
+    /// match the schema of its content type.
+    pub fn call(&mut self, func: &str, input: &Envelope) -> Result<Envelope> {
+        let output = self.runtime.call_envelope(func, input)?;
+        self.content.validate(&output)?;
+        Ok(output)
+    }
+
     /// Initialize the extension, returning both model and commands.
     pub fn init(&mut self, flags: Envelope) -> Result<(Envelope, Envelope)> {
         let (model, cmds) = self.runtime.init(flags)?;
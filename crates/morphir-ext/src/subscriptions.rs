@@ -0,0 +1,653 @@
+//! Subscriptions of TEA extensions.
+//!
+//! After `init` and every `update`, an extension is asked for its
+//! subscriptions: a JSON envelope listing the events it wants to hear about.
+//!
+//! ```json
+//! [
+//!   {"type": "every", "id": "tick", "interval_ms": 1000},
+//!   {"type": "file-watch", "id": "sources", "path": "src", "recursive": true},
+//!   {"type": "env-var", "id": "mode", "name": "MODE"}
+//! ]
+//! ```
+//!
+//! An [`EventLoop`] owns an [`ExtensionInstance`] and keeps a source running
+//! for each of its current subscriptions: a timer thread, a file watcher, or
+//! a check of the instance's environment variables as they are set. Every
+//! event is delivered back through `update` as a [`SubscriptionMsg`]:
+//!
+//! ```json
+//! {"subscription": "tick", "event": {"type": "tick", "timestamp_ms": 1700000000000}}
+//! ```
+//!
+//! Subscriptions listed again after an update keep running; the sources of
+//! those no longer listed stop, and events they already queued are dropped.
+//!
+//! The commands `init` and `update` return are performed by the loop's
+//! [`CommandInterpreter`], and their results queued as messages like events.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::types::Artifact;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::commands::CommandInterpreter;
+use crate::{EnvValue, ExtensionInstance};
+
+/// An event source an extension subscribes to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Subscription {
+    /// A tick every `interval_ms` milliseconds
+    Every { id: String, interval_ms: u64 },
+    /// Changes to the file or directory at `path`, relative to the event
+    /// loop's root
+    FileWatch {
+        id: String,
+        path: PathBuf,
+        #[serde(default)]
+        recursive: bool,
+    },
+    /// Changes to the environment variable `name`
+    EnvVar { id: String, name: String },
+}
+
+impl Subscription {
+    /// The ID messages of the subscription carry
+    pub fn id(&self) -> &str {
+        match self {
+            Subscription::Every { id, .. }
+            | Subscription::FileWatch { id, .. }
+            | Subscription::EnvVar { id, .. } => id,
+        }
+    }
+}
+
+/// What happened to a subscription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SubscriptionEvent {
+    /// A timer fired
+    Tick { timestamp_ms: u64 },
+    /// Files were created, modified or removed
+    FilesChanged { paths: Vec<PathBuf> },
+    /// An environment variable was set to a new value
+    EnvChanged { name: String, value: EnvValue },
+}
+
+/// The message an event is delivered to `update` as
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionMsg {
+    /// ID of the subscription
+    pub subscription: String,
+    pub event: SubscriptionEvent,
+}
+
+/// The subscriptions in an envelope; `null` subscribes to nothing
+pub fn parse(envelope: &Envelope) -> Result<Vec<Subscription>> {
+    let subscriptions: Option<Vec<Subscription>> = envelope
+        .as_json()
+        .context("Invalid subscriptions envelope")?;
+    let subscriptions = subscriptions.unwrap_or_default();
+    let mut ids = HashSet::new();
+    for subscription in &subscriptions {
+        if !ids.insert(subscription.id()) {
+            anyhow::bail!("Subscription {} is listed twice", subscription.id());
+        }
+        if let Subscription::Every { id, interval_ms: 0 } = subscription {
+            anyhow::bail!("Subscription {} has an interval of 0 ms", id);
+        }
+    }
+    Ok(subscriptions)
+}
+
+/// What the event loop receives
+enum Event {
+    /// A source fired
+    Fired {
+        subscription: Subscription,
+        event: SubscriptionEvent,
+    },
+    /// A message sent through a handle
+    Message(Envelope),
+    /// An environment variable set through a handle
+    SetEnvVar(String, EnvValue),
+    Stop,
+}
+
+/// A running event source; dropping it stops it
+enum Source {
+    /// Stops the timer thread when dropped
+    Timer {
+        _stop: Sender<()>,
+    },
+    Watcher {
+        _watcher: RecommendedWatcher,
+    },
+    /// Checked when environment variables are set
+    EnvVar,
+}
+
+/// Sources of the subscriptions of one instance
+struct Scheduler {
+    root: PathBuf,
+    events: Sender<Event>,
+    active: HashMap<Subscription, Source>,
+}
+
+impl Scheduler {
+    /// Keep the sources of `subscriptions` running, starting those not
+    /// running yet and stopping the rest
+    fn resubscribe(&mut self, subscriptions: Vec<Subscription>) -> Result<()> {
+        let wanted: HashSet<Subscription> = subscriptions.into_iter().collect();
+        self.active
+            .retain(|subscription, _| wanted.contains(subscription));
+        for subscription in wanted {
+            if !self.active.contains_key(&subscription) {
+                let source = self.start(&subscription)?;
+                debug!("Subscribed to {}", subscription.id());
+                self.active.insert(subscription, source);
+            }
+        }
+        Ok(())
+    }
+
+    fn start(&self, subscription: &Subscription) -> Result<Source> {
+        let events = self.events.clone();
+        match subscription {
+            Subscription::Every { interval_ms, .. } => {
+                let (stop, stopped) = mpsc::channel::<()>();
+                let interval = Duration::from_millis(*interval_ms);
+                let subscription = subscription.clone();
+                std::thread::spawn(move || {
+                    while stopped.recv_timeout(interval) == Err(RecvTimeoutError::Timeout) {
+                        let event = SubscriptionEvent::Tick {
+                            timestamp_ms: now_ms(),
+                        };
+                        let fired = Event::Fired {
+                            subscription: subscription.clone(),
+                            event,
+                        };
+                        if events.send(fired).is_err() {
+                            break;
+                        }
+                    }
+                });
+                Ok(Source::Timer { _stop: stop })
+            }
+            Subscription::FileWatch {
+                id,
+                path,
+                recursive,
+            } => {
+                // Events name paths under the watched one; they are reported
+                // relative to the root
+                let root = self.root.canonicalize().unwrap_or(self.root.clone());
+                let watched = root.join(path);
+                let subscription = subscription.clone();
+                let mut watcher =
+                    notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                        let Ok(event) = event else { return };
+                        if matches!(event.kind, EventKind::Access(_) | EventKind::Other) {
+                            return;
+                        }
+                        let paths = event
+                            .paths
+                            .iter()
+                            .map(|path| path.strip_prefix(&root).unwrap_or(path).to_path_buf())
+                            .collect();
+                        // The receiver is gone once the loop is being dropped
+                        let _ = events.send(Event::Fired {
+                            subscription: subscription.clone(),
+                            event: SubscriptionEvent::FilesChanged { paths },
+                        });
+                    })?;
+                let mode = if *recursive {
+                    RecursiveMode::Recursive
+                } else {
+                    RecursiveMode::NonRecursive
+                };
+                watcher
+                    .watch(&watched, mode)
+                    .with_context(|| format!("Subscription {} cannot watch {:?}", id, path))?;
+                Ok(Source::Watcher { _watcher: watcher })
+            }
+            Subscription::EnvVar { .. } => Ok(Source::EnvVar),
+        }
+    }
+
+    fn is_active(&self, subscription: &Subscription) -> bool {
+        self.active.contains_key(subscription)
+    }
+
+    /// The subscriptions to changes of the environment variable `name`
+    fn env_subscribers(&self, name: &str) -> Vec<Subscription> {
+        self.active
+            .keys()
+            .filter(|s| matches!(s, Subscription::EnvVar { name: n, .. } if n == name))
+            .cloned()
+            .collect()
+    }
+}
+
+/// What a [`EventLoop::step`] did
+#[derive(Debug)]
+pub enum Step {
+    /// A message was delivered; the commands `update` returned, which have
+    /// been performed
+    Delivered(Envelope),
+    /// Nothing to deliver before the timeout
+    Idle,
+    /// The loop was stopped through a handle
+    Stopped,
+}
+
+/// Sends to an [`EventLoop`] from other threads
+#[derive(Clone)]
+pub struct EventLoopHandle {
+    events: Sender<Event>,
+}
+
+impl EventLoopHandle {
+    /// Deliver `msg` to the extension's `update`
+    pub fn send(&self, msg: Envelope) -> Result<()> {
+        self.post(Event::Message(msg))
+    }
+
+    /// Set an environment variable of the instance, notifying the
+    /// subscriptions to it if its value changes
+    pub fn set_env_var(&self, name: impl Into<String>, value: EnvValue) -> Result<()> {
+        self.post(Event::SetEnvVar(name.into(), value))
+    }
+
+    /// Stop the loop after the events queued before
+    pub fn stop(&self) -> Result<()> {
+        self.post(Event::Stop)
+    }
+
+    fn post(&self, event: Event) -> Result<()> {
+        self.events
+            .send(event)
+            .map_err(|_| anyhow::anyhow!("The event loop has stopped"))
+    }
+}
+
+/// The event loop of one extension instance
+pub struct EventLoop {
+    instance: ExtensionInstance,
+    scheduler: Scheduler,
+    commands: CommandInterpreter,
+    events: Receiver<Event>,
+}
+
+impl EventLoop {
+    /// Event loop of `instance`, watching and reading files relative to the
+    /// current directory and fetching from no host
+    pub fn new(instance: ExtensionInstance) -> Self {
+        let (sender, events) = mpsc::channel();
+        Self {
+            instance,
+            scheduler: Scheduler {
+                root: PathBuf::from("."),
+                events: sender,
+                active: HashMap::new(),
+            },
+            commands: CommandInterpreter::new(),
+            events,
+        }
+    }
+
+    /// Watch and read files relative to `root`
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        self.commands = self.commands.with_root(root.clone());
+        self.scheduler.root = root;
+        self
+    }
+
+    /// Allow `http-fetch` commands to `hosts`
+    pub fn with_allowed_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.commands = self.commands.with_allowed_hosts(hosts);
+        self
+    }
+
+    /// A handle to send to the loop from other threads
+    pub fn handle(&self) -> EventLoopHandle {
+        EventLoopHandle {
+            events: self.scheduler.events.clone(),
+        }
+    }
+
+    /// The instance the loop runs
+    pub fn instance(&self) -> &ExtensionInstance {
+        &self.instance
+    }
+
+    /// The subscriptions whose sources are running
+    pub fn subscriptions(&self) -> Vec<&Subscription> {
+        self.scheduler.active.keys().collect()
+    }
+
+    /// The artifacts the extension has emitted
+    pub fn artifacts(&self) -> &[Artifact] {
+        self.commands.artifacts()
+    }
+
+    /// Take the artifacts the extension has emitted
+    pub fn take_artifacts(&mut self) -> Vec<Artifact> {
+        self.commands.take_artifacts()
+    }
+
+    /// Initialize the extension, start its subscriptions and perform its
+    /// commands, returning the commands `init` returned
+    pub fn init(&mut self, flags: Envelope) -> Result<Envelope> {
+        let (model, commands) = self.instance.init(flags)?;
+        self.resubscribe(model)?;
+        self.perform(&commands)?;
+        Ok(commands)
+    }
+
+    /// Deliver `msg` to the extension's `update`, update its subscriptions
+    /// and perform its commands, returning the commands `update` returned
+    pub fn update(&mut self, msg: Envelope) -> Result<Envelope> {
+        let model = self
+            .instance
+            .model()
+            .cloned()
+            .context("The extension has not been initialized")?;
+        let (model, commands) = self.instance.update(msg, model)?;
+        self.resubscribe(model)?;
+        self.perform(&commands)?;
+        Ok(commands)
+    }
+
+    /// Wait up to `timeout` for an event and deliver it
+    pub fn step(&mut self, timeout: Duration) -> Result<Step> {
+        let event = match self.events.recv_timeout(timeout) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => return Ok(Step::Idle),
+            Err(RecvTimeoutError::Disconnected) => return Ok(Step::Stopped),
+        };
+        let msg = match event {
+            Event::Stop => return Ok(Step::Stopped),
+            Event::Message(msg) => msg,
+            Event::Fired {
+                subscription,
+                event,
+            } => {
+                if !self.scheduler.is_active(&subscription) {
+                    return Ok(Step::Idle);
+                }
+                Envelope::json(&SubscriptionMsg {
+                    subscription: subscription.id().to_string(),
+                    event,
+                })?
+            }
+            Event::SetEnvVar(name, value) => {
+                if self.instance.get_env_var(&name) == Some(&value) {
+                    return Ok(Step::Idle);
+                }
+                self.instance.set_env_var(name.clone(), value.clone());
+                let subscribers = self.scheduler.env_subscribers(&name);
+                if subscribers.is_empty() {
+                    return Ok(Step::Idle);
+                }
+                // Queued like the events of other sources, then delivered
+                for subscription in subscribers {
+                    let event = SubscriptionEvent::EnvChanged {
+                        name: name.clone(),
+                        value: value.clone(),
+                    };
+                    let _ = self.scheduler.events.send(Event::Fired {
+                        subscription,
+                        event,
+                    });
+                }
+                return self.step(timeout);
+            }
+        };
+        Ok(Step::Delivered(self.update(msg)?))
+    }
+
+    /// Deliver events until the loop is stopped, returning the loop
+    pub fn run(mut self) -> Result<Self> {
+        loop {
+            match self.step(Duration::from_secs(60))? {
+                Step::Delivered(_) | Step::Idle => {}
+                Step::Stopped => return Ok(self),
+            }
+        }
+    }
+
+    /// Run the loop on its own thread
+    pub fn spawn(self) -> (EventLoopHandle, JoinHandle<Result<Self>>) {
+        let handle = self.handle();
+        (handle, std::thread::spawn(move || self.run()))
+    }
+
+    fn resubscribe(&mut self, model: Envelope) -> Result<()> {
+        let subscriptions = parse(&self.instance.subscriptions(model)?)?;
+        self.scheduler.resubscribe(subscriptions)
+    }
+
+    /// Perform `commands`, queueing their results to be delivered
+    fn perform(&mut self, commands: &Envelope) -> Result<()> {
+        for result in self.commands.execute(commands)? {
+            // The receiver lives as long as the loop
+            let _ = self.scheduler.events.send(Event::Message(result));
+        }
+        Ok(())
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExtensionRuntime;
+    use crate::commands::{CommandOutcome, CommandResult};
+    use serde_json::{Value, json};
+
+    /// Counts ticks until it has three, and records the files changed under
+    /// `src` and the value of `MODE`
+    struct Counter;
+
+    impl ExtensionRuntime for Counter {
+        fn call_envelope(&mut self, func: &str, input: &Envelope) -> Result<Envelope> {
+            let input: Value = input.as_json()?;
+            let output = match func {
+                "init" => json!({"model": {"ticks": 0, "changed": [], "mode": null}, "cmds": []}),
+                "update" => {
+                    let msg: Envelope = serde_json::from_value(input["msg"].clone())?;
+                    let model: Envelope = serde_json::from_value(input["model"].clone())?;
+                    let mut model: Value = model.as_json()?;
+                    match msg.as_json::<SubscriptionMsg>()?.event {
+                        SubscriptionEvent::Tick { .. } => {
+                            model["ticks"] = json!(model["ticks"].as_u64().unwrap() + 1)
+                        }
+                        SubscriptionEvent::FilesChanged { paths } => {
+                            model["changed"] = json!(paths)
+                        }
+                        SubscriptionEvent::EnvChanged { value, .. } => model["mode"] = json!(value),
+                    }
+                    json!({"model": model, "cmds": []})
+                }
+                "subscriptions" => {
+                    let mut subscriptions = vec![
+                        json!({"type": "file-watch", "id": "sources", "path": "src", "recursive": true}),
+                        json!({"type": "env-var", "id": "mode", "name": "MODE"}),
+                    ];
+                    if input["ticks"].as_u64().unwrap() < 3 {
+                        subscriptions
+                            .push(json!({"type": "every", "id": "tick", "interval_ms": 5}));
+                    }
+                    json!(subscriptions)
+                }
+                other => anyhow::bail!("unknown function {}", other),
+            };
+            Ok(Envelope::json(&output)?)
+        }
+    }
+
+    fn model(event_loop: &EventLoop) -> Value {
+        event_loop.instance().model().unwrap().as_json().unwrap()
+    }
+
+    /// Step until the model satisfies `done`
+    fn step_until(event_loop: &mut EventLoop, done: impl Fn(&Value) -> bool) {
+        for _ in 0..200 {
+            if done(&model(event_loop)) {
+                return;
+            }
+            event_loop.step(Duration::from_millis(50)).unwrap();
+        }
+        panic!("model never got there: {}", model(event_loop));
+    }
+
+    #[test]
+    fn test_events_are_delivered_until_unsubscribed() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("src")).unwrap();
+        let mut event_loop =
+            EventLoop::new(ExtensionInstance::new(Box::new(Counter))).with_root(root.path());
+        let handle = event_loop.handle();
+        event_loop
+            .init(Envelope::json(&json!({})).unwrap())
+            .unwrap();
+        assert_eq!(event_loop.subscriptions().len(), 3);
+
+        // The timer stops after the third tick, and ticks it queued are dropped
+        step_until(&mut event_loop, |model| model["ticks"] == 3);
+        assert_eq!(event_loop.subscriptions().len(), 2);
+        for _ in 0..5 {
+            event_loop.step(Duration::from_millis(10)).unwrap();
+        }
+        assert_eq!(model(&event_loop)["ticks"], 3);
+
+        handle
+            .set_env_var("MODE", EnvValue::Text("fast".into()))
+            .unwrap();
+        assert!(matches!(
+            event_loop.step(Duration::from_secs(1)).unwrap(),
+            Step::Delivered(_)
+        ));
+        assert_eq!(
+            model(&event_loop)["mode"],
+            json!({"type": "Text", "value": "fast"})
+        );
+        // Setting the same value again is no change
+        handle
+            .set_env_var("MODE", EnvValue::Text("fast".into()))
+            .unwrap();
+        assert!(matches!(
+            event_loop.step(Duration::from_secs(1)).unwrap(),
+            Step::Idle
+        ));
+
+        std::fs::write(root.path().join("src/orders.gleam"), "pub type Order").unwrap();
+        step_until(&mut event_loop, |model| {
+            model["changed"] == json!(["src/orders.gleam"])
+        });
+
+        let (handle, running) = event_loop.spawn();
+        handle.stop().unwrap();
+        assert!(running.join().unwrap().is_ok());
+    }
+
+    /// Reads `spec.json` on init, then emits the spec as an artifact and
+    /// records what each command it gave came to
+    struct Reader;
+
+    impl ExtensionRuntime for Reader {
+        fn call_envelope(&mut self, func: &str, input: &Envelope) -> Result<Envelope> {
+            let input: Value = input.as_json()?;
+            let output = match func {
+                "init" => json!({
+                    "model": {},
+                    "cmds": [
+                        {"type": "log", "level": "Debug", "message": "Reading the spec"},
+                        {"type": "read-file", "id": "spec", "path": "spec.json"},
+                    ]
+                }),
+                "update" => {
+                    let msg: Envelope = serde_json::from_value(input["msg"].clone())?;
+                    let model: Envelope = serde_json::from_value(input["model"].clone())?;
+                    let mut model: Value = model.as_json()?;
+                    let result: CommandResult = msg.as_json()?;
+                    let cmds = match &result.outcome {
+                        CommandOutcome::FileRead { content, .. } => json!([{
+                            "type": "emit-artifact",
+                            "id": "artifact",
+                            "path": "spec.ts",
+                            "content": content,
+                        }]),
+                        _ => json!([]),
+                    };
+                    model[result.command] = serde_json::to_value(result.outcome)?;
+                    json!({"model": model, "cmds": cmds})
+                }
+                "subscriptions" => json!([]),
+                other => anyhow::bail!("unknown function {}", other),
+            };
+            Ok(Envelope::json(&output)?)
+        }
+    }
+
+    #[test]
+    fn test_command_results_are_fed_back() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("spec.json"), "{}").unwrap();
+        let mut event_loop =
+            EventLoop::new(ExtensionInstance::new(Box::new(Reader))).with_root(root.path());
+        event_loop
+            .init(Envelope::json(&json!({})).unwrap())
+            .unwrap();
+
+        step_until(&mut event_loop, |model| model.get("artifact").is_some());
+        assert_eq!(
+            model(&event_loop)["spec"],
+            json!({"type": "file-read", "path": "spec.json", "content": "{}"})
+        );
+        assert_eq!(
+            model(&event_loop)["artifact"],
+            json!({"type": "artifact-emitted", "path": "spec.ts"})
+        );
+        let artifacts = event_loop.take_artifacts();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].content, "{}");
+    }
+
+    #[test]
+    fn test_invalid_subscriptions_are_refused() {
+        let parse = |value: Value| parse(&Envelope::json(&value).unwrap());
+        assert!(parse(Value::Null).unwrap().is_empty());
+        assert_eq!(
+            parse(json!([{"type": "env-var", "id": "mode", "name": "MODE"}])).unwrap(),
+            vec![Subscription::EnvVar {
+                id: "mode".into(),
+                name: "MODE".into()
+            }]
+        );
+        let err = parse(json!([
+            {"type": "every", "id": "tick", "interval_ms": 10},
+            {"type": "env-var", "id": "tick", "name": "MODE"},
+        ]))
+        .unwrap_err();
+        assert!(err.to_string().contains("listed twice"), "{}", err);
+        assert!(parse(json!([{"type": "every", "id": "tick", "interval_ms": 0}])).is_err());
+        assert!(parse(json!([{"type": "cron", "id": "tick"}])).is_err());
+    }
+}
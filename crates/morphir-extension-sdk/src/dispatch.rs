@@ -0,0 +1,191 @@
+//! Routing of JSON-RPC requests to an extension's capabilities
+//!
+//! The `handle` export generated by [`export_extension!`](crate::export_extension)
+//! answers every request through the [`Handlers`] of the extension, which
+//! route each method to the capability trait it registered:
+//!
+//! ```rust,ignore
+//! morphir_extension_sdk::export_extension!(MyExtension: Frontend, Backend);
+//! ```
+//!
+//! A capability the extension did not register answers with an unsuccessful
+//! result whose diagnostic says it is not implemented.
+
+use crate::error::Result;
+use crate::protocol::{ExtensionRequest, ExtensionResponse, RpcError, methods};
+use crate::traits::{Backend, Extension, Frontend, Transform, Validator};
+use crate::types::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Extensions exported with `export_extension!`, which registers their
+/// capabilities
+pub trait Dispatch: Extension + Default + Sized {
+    /// Handlers of the capabilities the extension implements
+    fn handlers() -> Handlers<Self>;
+
+    /// Answer a request the way the exported `handle` function does
+    fn dispatch(&self, request: &ExtensionRequest) -> ExtensionResponse {
+        Self::handlers().dispatch(self, request)
+    }
+}
+
+type Handler<E, P, R> = fn(&E, P) -> Result<R>;
+
+/// Methods an extension answers, by capability
+pub struct Handlers<E> {
+    compile: Option<Handler<E, CompileRequest, CompileResult>>,
+    compile_incremental: Option<Handler<E, CompileRequest, IncrementalCompileResult>>,
+    generate: Option<Handler<E, GenerateRequest, GenerateResult>>,
+    validate: Option<Handler<E, ValidateRequest, ValidateResult>>,
+    transform: Option<Handler<E, TransformRequest, TransformResult>>,
+}
+
+impl<E> Default for Handlers<E> {
+    fn default() -> Self {
+        Self {
+            compile: None,
+            compile_incremental: None,
+            generate: None,
+            validate: None,
+            transform: None,
+        }
+    }
+}
+
+impl<E: Extension> Handlers<E> {
+    /// Handlers for `info` and `capabilities` only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `compile` and `compileIncremental` with the [`Frontend`]
+    pub fn frontend(mut self) -> Self
+    where
+        E: Frontend,
+    {
+        self.compile = Some(E::compile);
+        self.compile_incremental = Some(E::compile_incremental);
+        self
+    }
+
+    /// Answer `generate` with the [`Backend`]
+    pub fn backend(mut self) -> Self
+    where
+        E: Backend,
+    {
+        self.generate = Some(E::generate);
+        self
+    }
+
+    /// Answer `validate` with the [`Validator`]
+    pub fn validator(mut self) -> Self
+    where
+        E: Validator,
+    {
+        self.validate = Some(E::validate);
+        self
+    }
+
+    /// Answer `transform` with the [`Transform`]
+    pub fn transform(mut self) -> Self
+    where
+        E: Transform,
+    {
+        self.transform = Some(E::transform);
+        self
+    }
+
+    /// Answer a request on behalf of `extension`
+    pub fn dispatch(&self, extension: &E, request: &ExtensionRequest) -> ExtensionResponse {
+        let result = match request.method.as_str() {
+            methods::INFO => to_result(exported_info::<E>()),
+            methods::CAPABILITIES => to_result(E::capabilities()),
+            methods::COMPILE => handle(extension, request, self.compile, || CompileResult {
+                success: false,
+                ir: None,
+                diagnostics: vec![not_implemented("Frontend")],
+            }),
+            // A plain compile result is a valid incremental result
+            methods::COMPILE_INCREMENTAL => {
+                handle(extension, request, self.compile_incremental, || {
+                    CompileResult {
+                        success: false,
+                        ir: None,
+                        diagnostics: vec![not_implemented("Frontend")],
+                    }
+                    .into()
+                })
+            }
+            methods::GENERATE => handle(extension, request, self.generate, || GenerateResult {
+                success: false,
+                artifacts: vec![],
+                diagnostics: vec![not_implemented("Backend")],
+                source_map: vec![],
+            }),
+            methods::VALIDATE => handle(extension, request, self.validate, || ValidateResult {
+                valid: false,
+                diagnostics: vec![not_implemented("Validator")],
+            }),
+            methods::TRANSFORM => handle(extension, request, self.transform, || TransformResult {
+                success: false,
+                ir: None,
+                diagnostics: vec![not_implemented("Transform")],
+            }),
+            method => Err(RpcError::method_not_found(method)),
+        };
+
+        match result {
+            Ok(value) => ExtensionResponse {
+                jsonrpc: crate::protocol::JSONRPC_VERSION.to_string(),
+                result: Some(value),
+                error: None,
+                id: request.id,
+            },
+            Err(error) => ExtensionResponse::error(request.id, error),
+        }
+    }
+}
+
+/// Info of an extension as exported to the host: with the SDK version it was
+/// built with, unless it sets one itself
+pub fn exported_info<E: Extension>() -> ExtensionInfo {
+    let mut info = E::info();
+    info.sdk_version
+        .get_or_insert_with(|| crate::SDK_VERSION.to_string());
+    info
+}
+
+/// Run `handler` on the request's params, or answer with `fallback` when the
+/// capability is not registered
+fn handle<E, P: DeserializeOwned, R: Serialize>(
+    extension: &E,
+    request: &ExtensionRequest,
+    handler: Option<Handler<E, P, R>>,
+    fallback: impl FnOnce() -> R,
+) -> std::result::Result<serde_json::Value, RpcError> {
+    let params: P = serde_json::from_value(request.params.clone())
+        .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+    let result = match handler {
+        Some(handler) => {
+            handler(extension, params).map_err(|e| RpcError::from_extension_error(&e))?
+        }
+        None => fallback(),
+    };
+    to_result(result)
+}
+
+fn to_result(value: impl Serialize) -> std::result::Result<serde_json::Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::internal_error(e.to_string()))
+}
+
+fn not_implemented(capability: &str) -> Diagnostic {
+    Diagnostic {
+        severity: DiagnosticSeverity::Error,
+        code: None,
+        message: format!("{} not implemented", capability),
+        location: None,
+        related: vec![],
+        fixes: vec![],
+    }
+}
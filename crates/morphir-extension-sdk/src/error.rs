@@ -35,6 +35,10 @@ pub enum ExtensionError {
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
 
+    /// Host function failed or denied the call
+    #[error("Host function failed: {0}")]
+    Host(String),
+
     /// IO error
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -1,9 +1,27 @@
 //! Host function imports for extensions
 //!
 //! Extensions can call these functions to interact with the host.
+//!
+//! Extensions run sandboxed, so frontends and backends read and write files
+//! through the host with [`read_file`], [`write_file`], and [`glob`]. Paths
+//! are virtual: `/workspace` is the workspace root, `/output` the output
+//! directory, and `/cache` the extension cache. The host refuses paths
+//! outside these, and writes to `/workspace`.
+//!
+//! Extensions keep data across daemon restarts, such as the results of
+//! incremental analysis, with [`get_state`], [`set_state`], and
+//! [`delete_state`]. The host persists each extension's entries separately,
+//! under the workspace's `.morphir/state`.
+//!
+//! Extensions reach the network with [`http_request`]. The host only sends
+//! requests to the hosts the workspace granted the extension in its
+//! `network` permissions, and that its `[sources]` configuration allows.
 
-use crate::types::WorkspaceInfo;
+use crate::error::{ExtensionError, Result};
+use crate::types::{FileWrite, HostResult, HttpRequest, HttpResponse, StateEntry, WorkspaceInfo};
 use extism_pdk::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 
 /// Log a message to the host
 ///
@@ -74,6 +92,32 @@ extern "ExtismHost" {
     fn morphir_get_cached_ir(key: String) -> Json<Option<serde_json::Value>>;
 }
 
+#[host_fn]
+extern "ExtismHost" {
+    /// Read a file by virtual path
+    fn host_read_file(path: String) -> Json<HostResult<String>>;
+    /// Write a file by virtual path, creating its directories
+    fn host_write_file(file: Json<FileWrite>) -> Json<HostResult<()>>;
+    /// Virtual paths matching a glob pattern
+    fn host_glob(pattern: String) -> Json<HostResult<Vec<String>>>;
+}
+
+#[host_fn]
+extern "ExtismHost" {
+    /// Value stored under a key
+    fn state_get(key: String) -> Json<HostResult<Option<serde_json::Value>>>;
+    /// Store a value under a key
+    fn state_set(entry: Json<StateEntry>) -> Json<HostResult<()>>;
+    /// Remove the value stored under a key, answering whether there was one
+    fn state_delete(key: String) -> Json<HostResult<bool>>;
+}
+
+#[host_fn]
+extern "ExtismHost" {
+    /// Send an HTTP request
+    fn host_http_request(request: Json<HttpRequest>) -> Json<HostResult<HttpResponse>>;
+}
+
 /// Get workspace information
 ///
 /// Returns information about the current workspace including paths.
@@ -104,6 +148,75 @@ pub fn get_cached_ir(key: &str) -> Option<serde_json::Value> {
     }
 }
 
+/// Read a file through the host
+///
+/// `path` is virtual, e.g. `/workspace/src/orders.gleam`.
+pub fn read_file(path: &str) -> Result<String> {
+    let result = unsafe { host_read_file(path.to_string()) }
+        .map_err(|e| ExtensionError::Host(e.to_string()))?;
+    result.into_inner().into_result()
+}
+
+/// Write a file through the host, creating its directories
+///
+/// `path` is virtual, e.g. `/output/src/orders.gleam`.
+pub fn write_file(path: &str, content: &str) -> Result<()> {
+    let file = FileWrite {
+        path: path.to_string(),
+        content: content.to_string(),
+    };
+    let result =
+        unsafe { host_write_file(Json(file)) }.map_err(|e| ExtensionError::Host(e.to_string()))?;
+    result.into_inner().into_result()
+}
+
+/// Virtual paths of the files matching `pattern`, e.g.
+/// `/workspace/src/**/*.gleam`
+pub fn glob(pattern: &str) -> Result<Vec<String>> {
+    let result = unsafe { host_glob(pattern.to_string()) }
+        .map_err(|e| ExtensionError::Host(e.to_string()))?;
+    result.into_inner().into_result()
+}
+
+/// The value stored under `key` with [`set_state`], if any
+pub fn get_state<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
+    let result =
+        unsafe { state_get(key.to_string()) }.map_err(|e| ExtensionError::Host(e.to_string()))?;
+    match result.into_inner().into_result()? {
+        Some(value) => Ok(Some(serde_json::from_value(value)?)),
+        None => Ok(None),
+    }
+}
+
+/// Store `value` under `key`, replacing any value stored before. The host
+/// keeps it across restarts.
+pub fn set_state<T: Serialize>(key: &str, value: &T) -> Result<()> {
+    let entry = StateEntry {
+        key: key.to_string(),
+        value: serde_json::to_value(value)?,
+    };
+    let result =
+        unsafe { state_set(Json(entry)) }.map_err(|e| ExtensionError::Host(e.to_string()))?;
+    result.into_inner().into_result()
+}
+
+/// Remove the value stored under `key`, returning whether there was one
+pub fn delete_state(key: &str) -> Result<bool> {
+    let result = unsafe { state_delete(key.to_string()) }
+        .map_err(|e| ExtensionError::Host(e.to_string()))?;
+    result.into_inner().into_result()
+}
+
+/// Send an HTTP request through the host
+///
+/// Responses come back whatever their status; the request fails if the host
+/// refuses it or cannot reach the server.
+pub fn http_request(request: &HttpRequest) -> Result<HttpResponse> {
+    let result = unsafe { host_http_request(Json(request.clone())) }
+        .map_err(|e| ExtensionError::Host(e.to_string()))?;
+    result.into_inner().into_result()
+}
+
 /// Get the value of a configuration variable from the host
 pub fn get_config(key: &str) -> Option<String> {
     extism_pdk::config::get(key).ok().flatten()
@@ -42,9 +42,10 @@
 //!     }
 //! }
 //!
-//! morphir_extension_sdk::export_extension!(MyExtension);
+//! morphir_extension_sdk::export_extension!(MyExtension: Frontend);
 //! ```
 
+pub mod dispatch;
 pub mod error;
 pub mod host;
 pub mod prelude;
@@ -52,6 +53,10 @@ pub mod protocol;
 pub mod traits;
 pub mod types;
 
+/// Version of the SDK. The host compares the version it was built with to
+/// the SDK versions an extension declares before loading it.
+pub const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 // Re-exports
 pub use error::{ExtensionError, Result};
 pub use traits::{Backend, Extension, Frontend, Transform, Validator};
@@ -62,15 +67,25 @@ pub use types::*;
 /// This macro generates the necessary WASM exports for your extension:
 /// - `morphir_extension_info`: Returns extension metadata
 /// - `handle`: Main JSON-RPC request handler
+///
+/// List the capability traits the extension implements after its type, so
+/// `handle` routes their methods to it:
+///
+/// ```rust,ignore
+/// morphir_extension_sdk::export_extension!(MyExtension: Frontend, Backend);
+/// ```
+///
+/// The macro also implements [`dispatch::Dispatch`] for the type, which is
+/// what `morphir-extension-testkit` drives in tests.
 #[macro_export]
 macro_rules! export_extension {
-    ($impl:ty) => {
+    (@exports $impl:ty) => {
         use $crate::extism_pdk::*;
 
         /// Extension info function (required by host)
         #[plugin_fn]
         pub fn morphir_extension_info() -> FnResult<Json<$crate::ExtensionInfo>> {
-            Ok(Json(<$impl as $crate::Extension>::info()))
+            Ok(Json($crate::dispatch::exported_info::<$impl>()))
         }
 
         /// Extension capabilities function
@@ -88,133 +103,53 @@ macro_rules! export_extension {
             Ok(Json(result))
         }
     };
-}
-
-/// Internal dispatch function used by export_extension! macro
-#[doc(hidden)]
-pub fn __dispatch_request<E: Extension + Default>(
-    request: &protocol::ExtensionRequest,
-) -> protocol::ExtensionResponse {
-    use protocol::methods;
-
-    let result = match request.method.as_str() {
-        methods::INFO => serde_json::to_value(E::info()),
-
-        methods::CAPABILITIES => serde_json::to_value(E::capabilities()),
-
-        methods::COMPILE => dispatch_compile(request),
-
-        methods::GENERATE => dispatch_generate(request),
-
-        methods::VALIDATE => dispatch_validate(request),
-
-        methods::TRANSFORM => dispatch_transform(request),
-
-        method => {
-            return protocol::ExtensionResponse::error(
-                request.id,
-                protocol::RpcError::method_not_found(method),
-            );
+    ($impl:ty: $($capability:ident),+ $(,)?) => {
+        impl $crate::dispatch::Dispatch for $impl {
+            fn handlers() -> $crate::dispatch::Handlers<Self> {
+                let handlers = $crate::dispatch::Handlers::new();
+                $(let handlers = $crate::__register_capability!(handlers, $capability);)+
+                handlers
+            }
         }
-    };
-
-    match result {
-        Ok(value) => protocol::ExtensionResponse {
-            jsonrpc: "2.0".to_string(),
-            result: Some(value),
-            error: None,
-            id: request.id,
-        },
-        Err(e) => protocol::ExtensionResponse {
-            jsonrpc: "2.0".to_string(),
-            result: None,
-            error: Some(protocol::RpcError::internal_error(e.to_string())),
-            id: request.id,
-        },
-    }
-}
-
-#[doc(hidden)]
-fn dispatch_compile(
-    request: &protocol::ExtensionRequest,
-) -> std::result::Result<serde_json::Value, serde_json::Error> {
-    let _params: types::CompileRequest = serde_json::from_value(request.params.clone())?;
 
-    let result = types::CompileResult {
-        success: false,
-        ir: None,
-        diagnostics: vec![types::Diagnostic {
-            severity: types::DiagnosticSeverity::Error,
-            code: None,
-            message: "Frontend not implemented".to_string(),
-            location: None,
-            related: vec![],
-        }],
+        $crate::export_extension!(@exports $impl);
     };
+    ($impl:ty) => {
+        impl $crate::dispatch::Dispatch for $impl {
+            fn handlers() -> $crate::dispatch::Handlers<Self> {
+                $crate::dispatch::Handlers::new()
+            }
+        }
 
-    serde_json::to_value(result)
-}
-
-#[doc(hidden)]
-fn dispatch_generate(
-    request: &protocol::ExtensionRequest,
-) -> std::result::Result<serde_json::Value, serde_json::Error> {
-    let _params: types::GenerateRequest = serde_json::from_value(request.params.clone())?;
-
-    let result = types::GenerateResult {
-        success: false,
-        artifacts: vec![],
-        diagnostics: vec![types::Diagnostic {
-            severity: types::DiagnosticSeverity::Error,
-            code: None,
-            message: "Backend not implemented".to_string(),
-            location: None,
-            related: vec![],
-        }],
+        $crate::export_extension!(@exports $impl);
     };
-
-    serde_json::to_value(result)
 }
 
+/// Register a capability trait with [`dispatch::Handlers`], for
+/// `export_extension!`
 #[doc(hidden)]
-fn dispatch_validate(
-    request: &protocol::ExtensionRequest,
-) -> std::result::Result<serde_json::Value, serde_json::Error> {
-    let _params: types::ValidateRequest = serde_json::from_value(request.params.clone())?;
-
-    let result = types::ValidateResult {
-        valid: false,
-        diagnostics: vec![types::Diagnostic {
-            severity: types::DiagnosticSeverity::Error,
-            code: None,
-            message: "Validator not implemented".to_string(),
-            location: None,
-            related: vec![],
-        }],
+#[macro_export]
+macro_rules! __register_capability {
+    ($handlers:expr, Frontend) => {
+        $handlers.frontend()
+    };
+    ($handlers:expr, Backend) => {
+        $handlers.backend()
+    };
+    ($handlers:expr, Validator) => {
+        $handlers.validator()
+    };
+    ($handlers:expr, Transform) => {
+        $handlers.transform()
     };
-
-    serde_json::to_value(result)
 }
 
+/// Internal dispatch function used by export_extension! macro
 #[doc(hidden)]
-fn dispatch_transform(
+pub fn __dispatch_request<E: dispatch::Dispatch>(
     request: &protocol::ExtensionRequest,
-) -> std::result::Result<serde_json::Value, serde_json::Error> {
-    let _params: types::TransformRequest = serde_json::from_value(request.params.clone())?;
-
-    let result = types::TransformResult {
-        success: false,
-        ir: None,
-        diagnostics: vec![types::Diagnostic {
-            severity: types::DiagnosticSeverity::Error,
-            code: None,
-            message: "Transform not implemented".to_string(),
-            location: None,
-            related: vec![],
-        }],
-    };
-
-    serde_json::to_value(result)
+) -> protocol::ExtensionResponse {
+    E::default().dispatch(request)
 }
 
 // Re-export extism_pdk for use in macro
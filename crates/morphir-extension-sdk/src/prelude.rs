@@ -6,10 +6,11 @@
 
 // Re-export all core types
 pub use crate::types::{
-    Artifact, CompileRequest, CompileResult, Diagnostic, DiagnosticSeverity, ExtensionCapabilities,
-    ExtensionInfo, ExtensionType, GenerateRequest, GenerateResult, RelatedInformation,
-    ResourceLimits, SourceFile, SourceLocation, TransformRequest, TransformResult, ValidateRequest,
-    ValidateResult, WorkspaceInfo,
+    Artifact, CompileRequest, CompileResult, Diagnostic, DiagnosticSeverity, Escape,
+    ExtensionCapabilities, ExtensionInfo, ExtensionType, GenerateRequest, GenerateResult,
+    HttpRequest, HttpResponse, IdentifierCase, IdentifierRules, IncrementalCompileResult,
+    Permissions, RelatedInformation, ResourceLimits, SourceFile, SourceLocation, SourceMapEntry,
+    TransformRequest, TransformResult, ValidateRequest, ValidateResult, WorkspaceInfo,
 };
 
 // Re-export traits
@@ -21,8 +22,14 @@ pub use crate::error::{ExtensionError, Result};
 // Re-export protocol types
 pub use crate::protocol::{ExtensionRequest, ExtensionResponse, RpcError};
 
+// Re-export dispatch
+pub use crate::dispatch::Dispatch;
+
 // Re-export macros
 pub use crate::{export_extension, host_debug, host_error, host_info, host_warn};
 
 // Re-export host functions
-pub use crate::host::{cache_ir, get_cached_ir, get_config, get_var, get_workspace_info, set_var};
+pub use crate::host::{
+    cache_ir, delete_state, get_cached_ir, get_config, get_state, get_var, get_workspace_info,
+    http_request, set_state, set_var,
+};
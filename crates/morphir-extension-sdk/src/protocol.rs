@@ -15,6 +15,8 @@ pub mod methods {
     pub const CAPABILITIES: &str = "morphir.extension.capabilities";
     /// Frontend: compile source to IR
     pub const COMPILE: &str = "morphir.frontend.compile";
+    /// Frontend: compile only the changed sources of an incremental build
+    pub const COMPILE_INCREMENTAL: &str = "morphir.frontend.compileIncremental";
     /// Backend: generate code from IR
     pub const GENERATE: &str = "morphir.backend.generate";
     /// Validator: validate IR
@@ -25,6 +27,7 @@ pub mod methods {
 
 /// JSON-RPC 2.0 Request to extension
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExtensionRequest {
     /// JSON-RPC version (always "2.0")
     pub jsonrpc: String,
@@ -50,6 +53,7 @@ impl ExtensionRequest {
 
 /// JSON-RPC 2.0 Response from extension
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExtensionResponse {
     /// JSON-RPC version (always "2.0")
     pub jsonrpc: String,
@@ -92,6 +96,7 @@ impl ExtensionResponse {
 
 /// JSON-RPC 2.0 Error object
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RpcError {
     /// Error code
     pub code: i32,
@@ -126,6 +131,8 @@ pub mod error_codes {
     pub const VALIDATION_ERROR: i32 = -32003;
     /// Transformation error
     pub const TRANSFORMATION_ERROR: i32 = -32004;
+    /// Server busy - too many requests in flight, retry later
+    pub const SERVER_BUSY: i32 = -32005;
 }
 
 impl RpcError {
@@ -203,6 +210,7 @@ impl RpcError {
             ),
             ExtensionError::ExecutionFailed(msg) => (error_codes::INTERNAL_ERROR, msg.clone()),
             ExtensionError::InvalidResponse(msg) => (error_codes::INTERNAL_ERROR, msg.clone()),
+            ExtensionError::Host(msg) => (error_codes::EXTENSION_ERROR, msg.clone()),
             ExtensionError::Io(e) => (error_codes::INTERNAL_ERROR, e.to_string()),
             ExtensionError::Json(e) => (error_codes::PARSE_ERROR, e.to_string()),
         };
@@ -214,3 +222,99 @@ impl RpcError {
         }
     }
 }
+
+// Conversions to the wire types of `morphir_ext_core::rpc`, whose adapters
+// map requests and responses to and from envelopes.
+
+impl From<ExtensionRequest> for morphir_ext_core::RpcRequest {
+    fn from(request: ExtensionRequest) -> Self {
+        Self {
+            jsonrpc: request.jsonrpc,
+            method: request.method,
+            params: request.params,
+            id: request.id,
+        }
+    }
+}
+
+impl From<morphir_ext_core::RpcRequest> for ExtensionRequest {
+    fn from(request: morphir_ext_core::RpcRequest) -> Self {
+        Self {
+            jsonrpc: request.jsonrpc,
+            method: request.method,
+            params: request.params,
+            id: request.id,
+        }
+    }
+}
+
+impl From<ExtensionResponse> for morphir_ext_core::RpcResponse {
+    fn from(response: ExtensionResponse) -> Self {
+        Self {
+            jsonrpc: response.jsonrpc,
+            result: response.result,
+            error: response.error.map(Into::into),
+            id: response.id,
+        }
+    }
+}
+
+impl From<morphir_ext_core::RpcResponse> for ExtensionResponse {
+    fn from(response: morphir_ext_core::RpcResponse) -> Self {
+        Self {
+            jsonrpc: response.jsonrpc,
+            result: response.result,
+            error: response.error.map(Into::into),
+            id: response.id,
+        }
+    }
+}
+
+impl From<RpcError> for morphir_ext_core::RpcError {
+    fn from(error: RpcError) -> Self {
+        Self {
+            code: error.code,
+            message: error.message,
+            data: error.data,
+        }
+    }
+}
+
+impl From<morphir_ext_core::RpcError> for RpcError {
+    fn from(error: morphir_ext_core::RpcError) -> Self {
+        Self {
+            code: error.code,
+            message: error.message,
+            data: error.data,
+        }
+    }
+}
+
+/// JSON Schema of the extension protocol: a JSON-RPC request or response,
+/// with the params and results of each method under `$defs`
+#[cfg(feature = "schema")]
+pub fn protocol_schema() -> schemars::Schema {
+    use crate::types::*;
+
+    let mut generator = schemars::SchemaGenerator::default();
+    let request = generator.subschema_for::<ExtensionRequest>();
+    let response = generator.subschema_for::<ExtensionResponse>();
+    generator.subschema_for::<ExtensionInfo>();
+    generator.subschema_for::<ExtensionCapabilities>();
+    generator.subschema_for::<CompileRequest>();
+    generator.subschema_for::<CompileResult>();
+    generator.subschema_for::<IncrementalCompileResult>();
+    generator.subschema_for::<GenerateRequest>();
+    generator.subschema_for::<GenerateResult>();
+    generator.subschema_for::<ValidateRequest>();
+    generator.subschema_for::<ValidateResult>();
+    generator.subschema_for::<TransformRequest>();
+    generator.subschema_for::<TransformResult>();
+
+    schemars::json_schema!({
+        "$schema": generator.settings().meta_schema,
+        "title": "Morphir Extension Protocol",
+        "oneOf": [request, response],
+        "$defs": generator.take_definitions(true),
+    })
+}
@@ -24,6 +24,15 @@ pub trait Frontend: Extension {
     /// Compile source files to IR
     fn compile(&self, request: CompileRequest) -> Result<CompileResult>;
 
+    /// Compile only the changed sources of an incremental build.
+    ///
+    /// Extensions that advertise the `incremental` capability override this
+    /// to report module hashes and the modules they reused. The default
+    /// performs a regular compile of the sources it was given.
+    fn compile_incremental(&self, request: CompileRequest) -> Result<IncrementalCompileResult> {
+        self.compile(request).map(IncrementalCompileResult::from)
+    }
+
     /// Languages this frontend supports (e.g., ["gleam", "elm"])
     fn supported_languages() -> Vec<String>;
 
@@ -33,7 +42,9 @@ pub trait Frontend: Extension {
 
 /// Backend extension: generates code from Morphir IR
 ///
-/// Backends take Morphir IR and produce code in a target language.
+/// Backends take Morphir IR and produce code in a target language. Backends
+/// that declare [`IdentifierRules`] in their capabilities have the names they
+/// will emit audited before generation.
 pub trait Backend: Extension {
     /// Generate code from IR
     fn generate(&self, request: GenerateRequest) -> Result<GenerateResult>;
@@ -3,10 +3,11 @@
 //! These types are shared between the SDK (guest) and daemon (host).
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Extension type/capability
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum ExtensionType {
     /// Frontend - parses source into IR
@@ -21,6 +22,7 @@ pub enum ExtensionType {
 
 /// Information about an extension
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExtensionInfo {
     /// Extension identifier (e.g., "morphir-gleam-binding")
     pub id: String,
@@ -45,6 +47,16 @@ pub struct ExtensionInfo {
     /// Minimum SDK version required
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub min_sdk_version: Option<String>,
+    /// Newest SDK version supported; newer hosts are accepted while they are
+    /// semver-compatible with it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_sdk_version: Option<String>,
+    /// SDK version the extension was built with, set by `export_extension!`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sdk_version: Option<String>,
+    /// Capabilities the extension needs from its sandbox
+    #[serde(default, skip_serializing_if = "Permissions::is_empty")]
+    pub permissions: Permissions,
 }
 
 impl Default for ExtensionInfo {
@@ -59,12 +71,49 @@ impl Default for ExtensionInfo {
             homepage: None,
             license: None,
             min_sdk_version: None,
+            max_sdk_version: None,
+            sdk_version: None,
+            permissions: Permissions::default(),
         }
     }
 }
 
+/// Capabilities an extension requests beyond the host functions.
+///
+/// The daemon refuses to load an extension requesting anything the
+/// workspace has not granted it in `morphir.toml`:
+///
+/// ```toml
+/// [extensions.permissions.my-extension]
+/// filesystem = ["/workspace/src"]
+/// network = ["api.example.com"]
+/// env = ["MY_EXTENSION_TOKEN"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Permissions {
+    /// Virtual paths (under `/workspace`, `/output`, or `/cache`) mounted into
+    /// the extension's WASI filesystem; `/workspace` is mounted read-only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filesystem: Vec<String>,
+    /// Hosts the extension sends HTTP requests to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network: Vec<String>,
+    /// Environment variables passed to the extension as config values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+}
+
+impl Permissions {
+    /// Whether nothing is requested
+    pub fn is_empty(&self) -> bool {
+        self.filesystem.is_empty() && self.network.is_empty() && self.env.is_empty()
+    }
+}
+
 /// Extension capabilities for runtime negotiation
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExtensionCapabilities {
     /// Supports streaming/incremental processing
     #[serde(default)]
@@ -78,13 +127,144 @@ pub struct ExtensionCapabilities {
     /// Supports progress reporting
     #[serde(default)]
     pub progress: bool,
+    /// IDs of extensions that consume this extension's output and must run
+    /// after it, e.g. a transform preparing IR for a specific backend
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub feeds: Vec<String>,
+    /// Attaches machine-applicable fixes to its diagnostics
+    #[serde(default)]
+    pub fixes: bool,
+    /// Identifier rules of a backend's target language, audited before
+    /// generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifiers: Option<IdentifierRules>,
     /// Additional capability flags
     #[serde(default, flatten)]
     pub extra: HashMap<String, bool>,
 }
 
+/// Naming convention of a kind of identifier in a target language
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum IdentifierCase {
+    /// `order_status`
+    Snake,
+    /// `orderStatus`
+    Camel,
+    /// `OrderStatus`
+    Title,
+    /// `order-status`
+    Kebab,
+}
+
+/// Identifier rules a backend declares for its target language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IdentifierRules {
+    /// Words the target language reserves
+    #[serde(default)]
+    pub reserved_words: Vec<String>,
+    /// Longest identifier the target language accepts, in characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Appended to identifiers that are reserved words
+    #[serde(default = "default_escape_suffix")]
+    pub escape_suffix: String,
+    /// Case of module names
+    #[serde(default = "default_value_case")]
+    pub module_case: IdentifierCase,
+    /// Case of type and constructor names
+    #[serde(default = "default_type_case")]
+    pub type_case: IdentifierCase,
+    /// Case of value, field, and argument names
+    #[serde(default = "default_value_case")]
+    pub value_case: IdentifierCase,
+}
+
+fn default_escape_suffix() -> String {
+    "_".to_string()
+}
+
+fn default_type_case() -> IdentifierCase {
+    IdentifierCase::Title
+}
+
+fn default_value_case() -> IdentifierCase {
+    IdentifierCase::Snake
+}
+
+impl Default for IdentifierRules {
+    fn default() -> Self {
+        Self {
+            reserved_words: Vec::new(),
+            max_length: None,
+            escape_suffix: default_escape_suffix(),
+            module_case: default_value_case(),
+            type_case: default_type_case(),
+            value_case: default_value_case(),
+        }
+    }
+}
+
+/// Why an identifier was emitted under a different name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Escape {
+    /// It is a reserved word, so the escape suffix was appended
+    Reserved,
+    /// It was longer than the target accepts, so it was shortened
+    Shortened,
+}
+
+impl IdentifierRules {
+    /// Rules reserving `words`, escaped with the default suffix
+    pub fn reserving<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            reserved_words: words.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether `identifier` is a reserved word
+    pub fn is_reserved(&self, identifier: &str) -> bool {
+        self.reserved_words.iter().any(|w| w == identifier)
+    }
+
+    /// The identifier emitted for `identifier`, and the escaping applied to
+    /// it, if any. Reserved words get the escape suffix; identifiers over
+    /// the length limit are cut to it, keeping the suffix.
+    pub fn escape(&self, identifier: &str) -> (String, Option<Escape>) {
+        let (base, suffix, mut escape) = if self.is_reserved(identifier) {
+            (
+                identifier,
+                self.escape_suffix.as_str(),
+                Some(Escape::Reserved),
+            )
+        } else {
+            (identifier, "", None)
+        };
+        let length = base.chars().count() + suffix.chars().count();
+        let keep = match self.max_length {
+            Some(max) if length > max => {
+                escape = Some(Escape::Shortened);
+                max.saturating_sub(suffix.chars().count())
+            }
+            _ => base.chars().count(),
+        };
+        let emitted = base.chars().take(keep).chain(suffix.chars()).collect();
+        (emitted, escape)
+    }
+}
+
 /// Resource limits for extension execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ResourceLimits {
     /// Maximum memory in bytes
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -109,6 +289,7 @@ impl Default for ResourceLimits {
 
 /// Source file for compilation
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SourceFile {
     /// File path (relative to workspace)
     pub path: String,
@@ -116,18 +297,58 @@ pub struct SourceFile {
     pub content: String,
 }
 
+impl SourceFile {
+    /// Fingerprint of the file content, used to detect changes between builds
+    pub fn fingerprint(&self) -> String {
+        fingerprint(self.content.as_bytes())
+    }
+}
+
+/// Stable content fingerprint (64-bit FNV-1a, hex encoded).
+///
+/// Host and extensions must agree on fingerprints across processes and
+/// platforms, so this does not use the randomized std hasher.
+pub fn fingerprint(bytes: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    });
+    format!("{:016x}", hash)
+}
+
 /// Request to compile source files
+///
+/// For an incremental build the host sends only the changed sources, together
+/// with the fingerprints of the previous build and the list of changed files.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CompileRequest {
     /// Source files to compile
     pub sources: Vec<SourceFile>,
     /// Compilation options
     #[serde(default)]
     pub options: HashMap<String, serde_json::Value>,
+    /// Fingerprints of the previous build's source files, keyed by path
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub previous_fingerprints: HashMap<String, String>,
+    /// Files added, modified, or removed since the previous build.
+    /// `None` requests a full build.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_files: Option<Vec<String>>,
+}
+
+impl CompileRequest {
+    /// Whether this is an incremental build against a previous one
+    pub fn is_incremental(&self) -> bool {
+        self.changed_files.is_some()
+    }
 }
 
 /// Result of compilation
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CompileResult {
     /// Whether compilation succeeded
     pub success: bool,
@@ -139,8 +360,37 @@ pub struct CompileResult {
     pub diagnostics: Vec<Diagnostic>,
 }
 
+/// Result of an incremental compilation
+///
+/// Serialized as a [`CompileResult`] with extra fields, so a plain compile
+/// result from an extension without incremental support is also accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IncrementalCompileResult {
+    /// The compilation result for the sources that were compiled
+    #[serde(flatten)]
+    pub result: CompileResult,
+    /// Hash of each module compiled in this build, keyed by module name
+    #[serde(default)]
+    pub module_hashes: HashMap<String, String>,
+    /// Modules from the previous build that are still valid and were not recompiled
+    #[serde(default)]
+    pub reused_modules: Vec<String>,
+}
+
+impl From<CompileResult> for IncrementalCompileResult {
+    fn from(result: CompileResult) -> Self {
+        Self {
+            result,
+            module_hashes: HashMap::new(),
+            reused_modules: Vec::new(),
+        }
+    }
+}
+
 /// Request to generate code
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GenerateRequest {
     /// Input IR (JSON)
     pub ir: serde_json::Value,
@@ -151,6 +401,7 @@ pub struct GenerateRequest {
 
 /// Result of code generation
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct GenerateResult {
     /// Whether generation succeeded
     pub success: bool,
@@ -160,10 +411,29 @@ pub struct GenerateResult {
     /// Diagnostics
     #[serde(default)]
     pub diagnostics: Vec<Diagnostic>,
+    /// Spans of the artifacts generated from each definition, so errors
+    /// reported against the output can be traced back to the model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_map: Vec<SourceMapEntry>,
+}
+
+/// Span of a generated artifact and the definition it was generated from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SourceMapEntry {
+    /// Artifact path, as in [`Artifact::path`]
+    pub path: String,
+    /// First line of the span (1-indexed)
+    pub start_line: u32,
+    /// Last line of the span (1-indexed, inclusive)
+    pub end_line: u32,
+    /// Definition the span was generated from, as a canonical FQName
+    pub fqname: String,
 }
 
 /// Request to validate IR
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ValidateRequest {
     /// Input IR (JSON)
     pub ir: serde_json::Value,
@@ -174,6 +444,7 @@ pub struct ValidateRequest {
 
 /// Result of validation
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ValidateResult {
     /// Whether validation passed
     pub valid: bool,
@@ -184,6 +455,7 @@ pub struct ValidateResult {
 
 /// Request to transform IR
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TransformRequest {
     /// Input IR (JSON)
     pub ir: serde_json::Value,
@@ -194,6 +466,7 @@ pub struct TransformRequest {
 
 /// Result of transformation
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TransformResult {
     /// Whether transformation succeeded
     pub success: bool,
@@ -207,6 +480,7 @@ pub struct TransformResult {
 
 /// A diagnostic message
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Diagnostic {
     /// Severity level
     pub severity: DiagnosticSeverity,
@@ -221,10 +495,14 @@ pub struct Diagnostic {
     /// Related information
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub related: Vec<RelatedInformation>,
+    /// Machine-applicable fixes, best first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<CodeFix>,
 }
 
 /// Diagnostic severity level
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum DiagnosticSeverity {
     /// Error - compilation fails
@@ -238,9 +516,10 @@ pub enum DiagnosticSeverity {
 }
 
 /// Source code location
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SourceLocation {
-    /// File path
+    /// File path (`/`-separated on every platform)
     pub file: String,
     /// Start line (1-indexed)
     #[serde(default)]
@@ -258,6 +537,7 @@ pub struct SourceLocation {
 
 /// Related diagnostic information
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RelatedInformation {
     /// Location of related information
     pub location: SourceLocation,
@@ -265,10 +545,34 @@ pub struct RelatedInformation {
     pub message: String,
 }
 
+/// A suggested fix for a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CodeFix {
+    /// What the fix does, e.g. "Insert missing `)`"
+    pub title: String,
+    /// Edits making up the fix; they must not overlap
+    pub edits: Vec<TextEdit>,
+}
+
+/// Replacement of a range of a source file
+///
+/// The range starts at `start_line:start_col` and ends before
+/// `end_line:end_col`. An empty range inserts `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TextEdit {
+    /// Range replaced
+    pub location: SourceLocation,
+    /// Replacement text
+    pub new_text: String,
+}
+
 /// A generated artifact
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Artifact {
-    /// Output path (relative)
+    /// Output path (relative, `/`-separated on every platform)
     pub path: String,
     /// Content (text or base64 for binary)
     pub content: String,
@@ -277,8 +581,120 @@ pub struct Artifact {
     pub binary: bool,
 }
 
+/// File written through the host with `host_write_file`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FileWrite {
+    /// Virtual path, e.g. `/output/src/orders.gleam`
+    pub path: String,
+    /// Text content
+    pub content: String,
+}
+
+/// Entry stored through the host with `state_set`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StateEntry {
+    /// Key, unique within the extension
+    pub key: String,
+    /// JSON value
+    pub value: serde_json::Value,
+}
+
+/// HTTP request made through the host with `host_http_request`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HttpRequest {
+    /// Method, e.g. `GET`
+    #[serde(default = "default_http_method")]
+    pub method: String,
+    /// Absolute `http` or `https` URL
+    pub url: String,
+    /// Request headers
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Text body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+fn default_http_method() -> String {
+    "GET".to_string()
+}
+
+impl HttpRequest {
+    /// A `GET` request for `url`
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            method: default_http_method(),
+            url: url.into(),
+            headers: BTreeMap::new(),
+            body: None,
+        }
+    }
+
+    /// A `POST` request sending `body` to `url`
+    pub fn post(url: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            method: "POST".to_string(),
+            body: Some(body.into()),
+            ..Self::get(url)
+        }
+    }
+
+    /// Add a header
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+}
+
+/// Response to an [`HttpRequest`], whatever its status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HttpResponse {
+    /// Status code
+    pub status: u16,
+    /// Response headers, with lowercase names
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Body, as text
+    pub body: String,
+}
+
+/// Outcome of a host function call, as returned to the extension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum HostResult<T> {
+    /// The call succeeded
+    Ok(T),
+    /// The call failed or was denied by the host's policy
+    Error(String),
+}
+
+impl<T> HostResult<T> {
+    /// Convert into a [`Result`](crate::Result)
+    pub fn into_result(self) -> crate::Result<T> {
+        match self {
+            HostResult::Ok(value) => Ok(value),
+            HostResult::Error(message) => Err(crate::ExtensionError::Host(message)),
+        }
+    }
+}
+
+impl<T, E: std::fmt::Display> From<std::result::Result<T, E>> for HostResult<T> {
+    fn from(result: std::result::Result<T, E>) -> Self {
+        match result {
+            Ok(value) => HostResult::Ok(value),
+            Err(e) => HostResult::Error(e.to_string()),
+        }
+    }
+}
+
 /// Workspace information provided by host
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WorkspaceInfo {
     /// Workspace root path
     pub root: String,
@@ -0,0 +1,254 @@
+//! Golden-file comparisons
+//!
+//! A [`Golden`] compares results against files checked in next to the
+//! tests. Results are stored as pretty-printed JSON; generated artifacts are
+//! stored as files of their own under a directory named after the golden,
+//! so reviewing a change to generated code reads like any other diff.
+//!
+//! Set `MORPHIR_UPDATE_GOLDEN=1` to write the current results instead of
+//! comparing them, including for goldens that do not exist yet.
+
+use morphir_extension_sdk::types::{CompileResult, GenerateResult};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+
+/// Environment variable that makes goldens be written rather than compared
+pub const UPDATE_ENV: &str = "MORPHIR_UPDATE_GOLDEN";
+
+/// Golden files under a directory
+#[derive(Debug, Clone)]
+pub struct Golden {
+    dir: PathBuf,
+    update: bool,
+}
+
+impl Golden {
+    /// Goldens under `dir`, updated when `MORPHIR_UPDATE_GOLDEN` is set
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let update = std::env::var(UPDATE_ENV).is_ok_and(|v| !v.is_empty() && v != "0");
+        Self {
+            dir: dir.into(),
+            update,
+        }
+    }
+
+    /// Write goldens rather than compare them, whatever the environment says
+    pub fn updating(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Compare `value`, as pretty-printed JSON, with `<name>.json`
+    #[track_caller]
+    pub fn assert_json(&self, name: &str, value: &impl Serialize) {
+        let mut json = serde_json::to_string_pretty(value).expect("value serializes to JSON");
+        json.push('\n');
+        self.assert_file(&self.dir.join(format!("{}.json", name)), &json);
+    }
+
+    /// Compare a compile result with `<name>.json`
+    #[track_caller]
+    pub fn assert_compile_result(&self, name: &str, result: &CompileResult) {
+        self.assert_json(name, result);
+    }
+
+    /// Compare a generate result: its artifacts with the files under
+    /// `<name>/`, and everything else with `<name>.json`
+    #[track_caller]
+    pub fn assert_generate_result(&self, name: &str, result: &GenerateResult) {
+        let summary = serde_json::json!({
+            "success": result.success,
+            "artifacts": result.artifacts.iter().map(|a| &a.path).collect::<Vec<_>>(),
+            "diagnostics": result.diagnostics,
+            "sourceMap": result.source_map,
+        });
+        self.assert_json(name, &summary);
+
+        let root = self.dir.join(name);
+        let mut expected = BTreeSet::new();
+        for artifact in &result.artifacts {
+            let relative = Path::new(&artifact.path);
+            assert!(
+                relative
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_))),
+                "artifact path must be relative and stay inside its directory: {}",
+                artifact.path
+            );
+            expected.insert(relative.to_path_buf());
+            self.assert_file(&root.join(relative), &artifact.content);
+        }
+
+        let stale: Vec<String> = files_under(&root)
+            .into_iter()
+            .filter(|path| !expected.contains(path))
+            .map(|path| path.display().to_string())
+            .collect();
+        if self.update {
+            for path in &stale {
+                std::fs::remove_file(root.join(path)).expect("stale golden file is removable");
+            }
+        } else {
+            assert!(
+                stale.is_empty(),
+                "golden {} has files that were not generated: {}",
+                name,
+                stale.join(", ")
+            );
+        }
+    }
+
+    #[track_caller]
+    fn assert_file(&self, path: &Path, actual: &str) {
+        if self.update {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).expect("golden directory is creatable");
+            }
+            std::fs::write(path, actual).expect("golden file is writable");
+            return;
+        }
+        let Ok(expected) = std::fs::read_to_string(path) else {
+            panic!(
+                "missing golden file {}; run with {}=1 to create it",
+                path.display(),
+                UPDATE_ENV
+            );
+        };
+        if let Some(difference) = first_difference(&expected, actual) {
+            panic!(
+                "{} does not match; run with {}=1 to update it\n{}",
+                path.display(),
+                UPDATE_ENV,
+                difference
+            );
+        }
+    }
+}
+
+/// The first line at which two texts differ, with both versions of it
+fn first_difference(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => continue,
+            (None, None) => break,
+            (e, a) => {
+                return Some(format!(
+                    "line {}:\n  expected: {}\n  actual:   {}",
+                    line,
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                ));
+            }
+        }
+    }
+    // Only line endings differ
+    Some("line endings differ".to_string())
+}
+
+/// Files under `root`, relative to it
+fn files_under(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_extension_sdk::types::Artifact;
+
+    fn artifact(path: &str, content: &str) -> Artifact {
+        Artifact {
+            path: path.to_string(),
+            content: content.to_string(),
+            binary: false,
+        }
+    }
+
+    fn generated(artifacts: Vec<Artifact>) -> GenerateResult {
+        GenerateResult {
+            success: true,
+            artifacts,
+            diagnostics: vec![],
+            source_map: vec![],
+        }
+    }
+
+    #[test]
+    fn test_generate_goldens_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let result = generated(vec![
+            artifact("src/orders.ts", "export type Order = {};\n"),
+            artifact("package.json", "{}\n"),
+        ]);
+        Golden::new(temp.path())
+            .updating(true)
+            .assert_generate_result("orders", &result);
+        assert_eq!(
+            std::fs::read_to_string(temp.path().join("orders/src/orders.ts")).unwrap(),
+            "export type Order = {};\n"
+        );
+
+        let golden = Golden::new(temp.path()).updating(false);
+        golden.assert_generate_result("orders", &result);
+
+        let changed = generated(vec![
+            artifact("src/orders.ts", "export type Order = { id: string };\n"),
+            artifact("package.json", "{}\n"),
+        ]);
+        let panic = std::panic::catch_unwind(|| golden.assert_generate_result("orders", &changed))
+            .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.contains("orders.ts does not match"), "{}", message);
+        assert!(message.contains("actual:   export type Order = { id: string };"));
+
+        // Files no longer generated are stale
+        std::fs::write(temp.path().join("orders/README.md"), "old\n").unwrap();
+        let panic = std::panic::catch_unwind(|| golden.assert_generate_result("orders", &result))
+            .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.contains("README.md"), "{}", message);
+    }
+
+    #[test]
+    fn test_missing_goldens_fail_unless_updating() {
+        let temp = tempfile::tempdir().unwrap();
+        let result = CompileResult {
+            success: true,
+            ir: Some(serde_json::json!({"modules": []})),
+            diagnostics: vec![],
+        };
+        let golden = Golden::new(temp.path()).updating(false);
+        assert!(
+            std::panic::catch_unwind(|| golden.assert_compile_result("orders", &result)).is_err()
+        );
+        golden
+            .clone()
+            .updating(true)
+            .assert_compile_result("orders", &result);
+        golden.assert_compile_result("orders", &result);
+        assert_eq!(
+            first_difference("a\r\nb", "a\nb"),
+            Some("line endings differ".into())
+        );
+    }
+}
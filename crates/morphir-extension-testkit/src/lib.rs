@@ -0,0 +1,397 @@
+//! Test harness for Morphir extensions
+//!
+//! Extension authors test their extension the way the daemon drives it: an
+//! [`ExtensionHarness`] sends JSON-RPC requests through the same dispatch as
+//! the `handle` export generated by `export_extension!`, with requests and
+//! responses serialized to JSON and back on the way, and decodes the results.
+//!
+//! ```rust,ignore
+//! use morphir_extension_sdk::prelude::*;
+//! use morphir_extension_testkit::{ExtensionHarness, Golden, assert_no_errors, source};
+//!
+//! #[test]
+//! fn compiles_orders() {
+//!     let harness = ExtensionHarness::<MyExtension>::new();
+//!     let result = harness
+//!         .compile(CompileRequest {
+//!             sources: vec![source("src/orders.ml", "type Order = ...")],
+//!             ..Default::default()
+//!         })
+//!         .unwrap();
+//!     assert_no_errors(&result.diagnostics);
+//!     Golden::new("tests/golden").assert_compile_result("orders", &result);
+//! }
+//! ```
+//!
+//! Host functions (`host_info!`, `read_file`, ...) are only available inside
+//! the daemon; code paths calling them are tested through the daemon.
+
+pub mod golden;
+
+pub use golden::Golden;
+
+use morphir_extension_sdk::Extension;
+use morphir_extension_sdk::dispatch::{Dispatch, Handlers};
+use morphir_extension_sdk::protocol::{ExtensionRequest, ExtensionResponse, RpcError, methods};
+use morphir_extension_sdk::types::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::cell::Cell;
+use thiserror::Error;
+
+/// Errors of a request sent through the harness
+#[derive(Debug, Error)]
+pub enum HarnessError {
+    /// The extension answered with a JSON-RPC error
+    #[error("Extension answered with error {}: {}", .0.code, .0.message)]
+    Rpc(RpcError),
+
+    /// The response is not valid JSON-RPC for the request
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+
+    /// Params or result did not serialize as expected
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Drives an extension's JSON-RPC dispatch as the host does
+pub struct ExtensionHarness<E> {
+    extension: E,
+    handlers: Handlers<E>,
+    next_id: Cell<u64>,
+}
+
+impl<E: Dispatch> ExtensionHarness<E> {
+    /// Harness for the default instance of an exported extension
+    pub fn new() -> Self {
+        Self::with_extension(E::default())
+    }
+
+    /// Harness for an instance of an exported extension
+    pub fn with_extension(extension: E) -> Self {
+        Self::with_handlers(extension, E::handlers())
+    }
+}
+
+impl<E: Dispatch> Default for ExtensionHarness<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Extension> ExtensionHarness<E> {
+    /// Harness routing requests through `handlers` rather than those
+    /// registered by `export_extension!`
+    pub fn with_handlers(extension: E, handlers: Handlers<E>) -> Self {
+        Self {
+            extension,
+            handlers,
+            next_id: Cell::new(1),
+        }
+    }
+
+    /// The extension under test
+    pub fn extension(&self) -> &E {
+        &self.extension
+    }
+
+    /// A request for `method`, numbered after the requests sent before it
+    pub fn request(
+        &self,
+        method: &str,
+        params: impl Serialize,
+    ) -> Result<ExtensionRequest, HarnessError> {
+        let id = self.next_id.replace(self.next_id.get() + 1);
+        Ok(ExtensionRequest::new(method, params, id)?)
+    }
+
+    /// Send a request and return the raw response. Both cross a JSON
+    /// boundary, as they do between the host and a plugin.
+    pub fn send(&self, request: &ExtensionRequest) -> Result<ExtensionResponse, HarnessError> {
+        let request: ExtensionRequest = serde_json::from_slice(&serde_json::to_vec(request)?)?;
+        let response = self.handlers.dispatch(&self.extension, &request);
+        let response: ExtensionResponse = serde_json::from_slice(&serde_json::to_vec(&response)?)?;
+        if response.id != request.id {
+            return Err(HarnessError::InvalidResponse(format!(
+                "answered request {} with id {}",
+                request.id, response.id
+            )));
+        }
+        Ok(response)
+    }
+
+    /// Call `method` and decode its result
+    pub fn call<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: impl Serialize,
+    ) -> Result<R, HarnessError> {
+        let response = self.send(&self.request(method, params)?)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(HarnessError::Rpc(error)),
+            (Some(result), None) => Ok(serde_json::from_value(result)?),
+            (None, None) => Err(HarnessError::InvalidResponse(
+                "neither a result nor an error".to_string(),
+            )),
+        }
+    }
+
+    /// `morphir.extension.info`
+    pub fn info(&self) -> Result<ExtensionInfo, HarnessError> {
+        self.call(methods::INFO, serde_json::Value::Null)
+    }
+
+    /// `morphir.extension.capabilities`
+    pub fn capabilities(&self) -> Result<ExtensionCapabilities, HarnessError> {
+        self.call(methods::CAPABILITIES, serde_json::Value::Null)
+    }
+
+    /// `morphir.frontend.compile`
+    pub fn compile(&self, request: CompileRequest) -> Result<CompileResult, HarnessError> {
+        self.call(methods::COMPILE, request)
+    }
+
+    /// `morphir.frontend.compileIncremental`
+    pub fn compile_incremental(
+        &self,
+        request: CompileRequest,
+    ) -> Result<IncrementalCompileResult, HarnessError> {
+        self.call(methods::COMPILE_INCREMENTAL, request)
+    }
+
+    /// `morphir.backend.generate`
+    pub fn generate(&self, request: GenerateRequest) -> Result<GenerateResult, HarnessError> {
+        self.call(methods::GENERATE, request)
+    }
+
+    /// `morphir.validator.validate`
+    pub fn validate(&self, request: ValidateRequest) -> Result<ValidateResult, HarnessError> {
+        self.call(methods::VALIDATE, request)
+    }
+
+    /// `morphir.transform.transform`
+    pub fn transform(&self, request: TransformRequest) -> Result<TransformResult, HarnessError> {
+        self.call(methods::TRANSFORM, request)
+    }
+}
+
+/// A source file for a [`CompileRequest`]
+pub fn source(path: &str, content: &str) -> SourceFile {
+    SourceFile {
+        path: path.to_string(),
+        content: content.to_string(),
+    }
+}
+
+/// Panic listing the error diagnostics, if there are any
+#[track_caller]
+pub fn assert_no_errors(diagnostics: &[Diagnostic]) {
+    let errors: Vec<String> = diagnostics
+        .iter()
+        .filter(|d| d.severity == DiagnosticSeverity::Error)
+        .map(|d| match &d.code {
+            Some(code) => format!("  [{}] {}", code, d.message),
+            None => format!("  {}", d.message),
+        })
+        .collect();
+    assert!(
+        errors.is_empty(),
+        "expected no errors, got {}:\n{}",
+        errors.len(),
+        errors.join("\n")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_extension_sdk::{Frontend, Result};
+
+    /// Frontend compiling each source to its upper-cased content
+    #[derive(Default)]
+    struct Upper;
+
+    impl Extension for Upper {
+        fn info() -> ExtensionInfo {
+            ExtensionInfo {
+                id: "upper".into(),
+                name: "Upper".into(),
+                version: "0.1.0".into(),
+                types: vec![ExtensionType::Frontend],
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Frontend for Upper {
+        fn compile(&self, request: CompileRequest) -> Result<CompileResult> {
+            if request.sources.is_empty() {
+                return Err(morphir_extension_sdk::ExtensionError::execution(
+                    "no sources",
+                ));
+            }
+            Ok(CompileResult {
+                success: true,
+                ir: Some(serde_json::json!(
+                    request
+                        .sources
+                        .iter()
+                        .map(|s| s.content.to_uppercase())
+                        .collect::<Vec<_>>()
+                )),
+                diagnostics: vec![],
+            })
+        }
+
+        fn supported_languages() -> Vec<String> {
+            vec!["upper".into()]
+        }
+
+        fn file_extensions() -> Vec<String> {
+            vec![".up".into()]
+        }
+    }
+
+    impl Dispatch for Upper {
+        fn handlers() -> Handlers<Self> {
+            Handlers::new().frontend()
+        }
+    }
+
+    #[test]
+    fn test_requests_reach_the_registered_capabilities() {
+        let harness = ExtensionHarness::<Upper>::new();
+        let info = harness.info().unwrap();
+        assert_eq!(info.id, "upper");
+        assert_eq!(
+            info.sdk_version.as_deref(),
+            Some(morphir_extension_sdk::SDK_VERSION)
+        );
+
+        let result = harness
+            .compile(CompileRequest {
+                sources: vec![source("a.up", "order"), source("b.up", "line")],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_no_errors(&result.diagnostics);
+        assert_eq!(result.ir, Some(serde_json::json!(["ORDER", "LINE"])));
+
+        // A plain compile answers incremental requests too
+        let result = harness
+            .compile_incremental(CompileRequest {
+                sources: vec![source("a.up", "order")],
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(result.result.success);
+
+        // Unregistered capabilities answer, but unsuccessfully
+        let result = harness
+            .generate(GenerateRequest {
+                ir: serde_json::json!({}),
+                options: Default::default(),
+            })
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.diagnostics[0].message, "Backend not implemented");
+    }
+
+    /// Frontend reusing the modules of every source it is not sent
+    #[derive(Default)]
+    struct Reusing;
+
+    impl Extension for Reusing {
+        fn info() -> ExtensionInfo {
+            ExtensionInfo {
+                id: "reusing".into(),
+                name: "Reusing".into(),
+                version: "0.1.0".into(),
+                types: vec![ExtensionType::Frontend],
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Frontend for Reusing {
+        fn compile(&self, _request: CompileRequest) -> Result<CompileResult> {
+            Err(morphir_extension_sdk::ExtensionError::execution(
+                "full compile",
+            ))
+        }
+
+        fn compile_incremental(&self, request: CompileRequest) -> Result<IncrementalCompileResult> {
+            Ok(IncrementalCompileResult {
+                result: CompileResult {
+                    success: true,
+                    ir: None,
+                    diagnostics: vec![],
+                },
+                module_hashes: Default::default(),
+                reused_modules: request.previous_fingerprints.into_keys().collect(),
+            })
+        }
+
+        fn supported_languages() -> Vec<String> {
+            vec!["reusing".into()]
+        }
+
+        fn file_extensions() -> Vec<String> {
+            vec![".re".into()]
+        }
+    }
+
+    impl Dispatch for Reusing {
+        fn handlers() -> Handlers<Self> {
+            Handlers::new().frontend()
+        }
+    }
+
+    #[test]
+    fn test_incremental_requests_reach_the_override() {
+        let request = CompileRequest {
+            sources: vec![source("src/b.re", "b")],
+            options: Default::default(),
+            previous_fingerprints: [("src/a.re".to_string(), "0".to_string())].into(),
+            changed_files: Some(vec!["src/b.re".to_string()]),
+        };
+
+        let harness = ExtensionHarness::<Reusing>::new();
+        let result = harness.compile_incremental(request.clone()).unwrap();
+        assert_eq!(result.reused_modules, vec!["src/a.re"]);
+
+        // The exported `handle` function routes it the same way
+        let request = harness
+            .request(methods::COMPILE_INCREMENTAL, request)
+            .unwrap();
+        let response = morphir_extension_sdk::__dispatch_request::<Reusing>(&request);
+        let result: IncrementalCompileResult =
+            serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(result.reused_modules, vec!["src/a.re"]);
+    }
+
+    #[test]
+    fn test_errors_come_back_as_json_rpc_errors() {
+        let harness = ExtensionHarness::<Upper>::new();
+        let err = harness.compile(CompileRequest::default()).unwrap_err();
+        assert!(
+            matches!(&err, HarnessError::Rpc(e) if e.message == "no sources"),
+            "{}",
+            err
+        );
+
+        let err = harness
+            .call::<serde_json::Value>(methods::COMPILE, serde_json::json!({"sources": 1}))
+            .unwrap_err();
+        assert!(matches!(err, HarnessError::Rpc(e) if e.code == -32602));
+
+        let err = harness
+            .call::<serde_json::Value>("morphir.unknown", serde_json::Value::Null)
+            .unwrap_err();
+        assert!(matches!(err, HarnessError::Rpc(e) if e.code == -32601));
+
+        // Requests are numbered in order
+        assert_eq!(harness.request(methods::INFO, ()).unwrap().id, 4);
+    }
+}
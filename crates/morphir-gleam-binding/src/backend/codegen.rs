@@ -1,8 +1,9 @@
 //! Gleam code generation from Morphir IR
 
 use morphir_common::vfs::{OsVfs, Vfs};
-use morphir_core::ir::v4::PackageDefinition;
-use morphir_core::naming::ModuleName;
+use morphir_core::ir::v4::sample::{SampleOptions, sample_distribution};
+use morphir_core::ir::v4::{Distribution as V4Distribution, IRFile, PackageDefinition};
+use morphir_core::naming::{FQName, ModuleName};
 use morphir_extension_sdk::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -54,6 +55,19 @@ pub fn generate_gleam(
         return generate_from_package_definition(package_def, options);
     }
 
+    // Full V4 IR file or bare distribution (Library or Application)
+    let v4_dist = serde_json::from_value::<IRFile>(ir.clone())
+        .map(|file| file.distribution)
+        .or_else(|_| serde_json::from_value::<V4Distribution>(ir.clone()));
+    if let Ok(dist) = v4_dist {
+        let mut options = options.clone();
+        options
+            .entry("packageName".to_string())
+            .or_insert_with(|| dist.package_name().to_string().into());
+        let package_def = select_package_definition(&dist, &options)?;
+        return generate_from_package_definition(package_def, &options);
+    }
+
     // Fallback to legacy format
     let mut artifacts = Vec::new();
 
@@ -88,11 +102,129 @@ pub fn generate_gleam(
     Ok(artifacts)
 }
 
+/// Generate Gleam code from Morphir IR, with the span of each generated
+/// definition in its module
+pub fn generate_gleam_with_source_map(
+    ir: &serde_json::Value,
+    options: &HashMap<String, serde_json::Value>,
+) -> Result<(Vec<Artifact>, Vec<SourceMapEntry>)> {
+    let artifacts = generate_gleam(ir, options)?;
+    let package_name = package_name(ir, options);
+    let source_map = artifacts
+        .iter()
+        .flat_map(|artifact| source_map(artifact, &package_name))
+        .collect();
+    Ok((artifacts, source_map))
+}
+
+/// Package the code is generated for: the `packageName` option, then the
+/// distribution's package
+fn package_name(ir: &serde_json::Value, options: &HashMap<String, serde_json::Value>) -> String {
+    if let Some(name) = options.get("packageName").and_then(|v| v.as_str()) {
+        return name.to_string();
+    }
+    serde_json::from_value::<IRFile>(ir.clone())
+        .map(|file| file.distribution)
+        .or_else(|_| serde_json::from_value::<V4Distribution>(ir.clone()))
+        .map(|dist| dist.package_name().to_string())
+        .unwrap_or_else(|_| "default-package".to_string())
+}
+
+/// Spans of the top-level declarations of a generated module, each running
+/// to the next declaration
+fn source_map(artifact: &Artifact, package_name: &str) -> Vec<SourceMapEntry> {
+    let Some(module) = artifact.path.strip_suffix(".gleam") else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = artifact.content.lines().collect();
+    let declarations: Vec<(usize, &str)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| Some((index, declared_name(line)?)))
+        .collect();
+
+    declarations
+        .iter()
+        .enumerate()
+        .map(|(i, (start, name))| {
+            let next = declarations
+                .get(i + 1)
+                .map_or(lines.len(), |(next, _)| *next);
+            // Leave out the blank lines between declarations
+            let end = (*start..next)
+                .rev()
+                .find(|&index| !lines[index].trim().is_empty())
+                .unwrap_or(*start);
+            SourceMapEntry {
+                path: artifact.path.clone(),
+                start_line: *start as u32 + 1,
+                end_line: end as u32 + 1,
+                fqname: FQName::new(
+                    morphir_core::naming::Path::new(package_name),
+                    morphir_core::naming::Path::new(module),
+                    morphir_core::naming::Name::from(name),
+                )
+                .to_canonical_string(),
+            }
+        })
+        .collect()
+}
+
+/// Name declared by a top-level `fn`, `type`, or `const` line of Gleam
+fn declared_name(line: &str) -> Option<&str> {
+    let line = line.strip_prefix("pub ").unwrap_or(line);
+    let line = line.strip_prefix("opaque ").unwrap_or(line);
+    let rest = ["fn ", "type ", "const "]
+        .iter()
+        .find_map(|keyword| line.strip_prefix(keyword))?;
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    (end > 0).then(|| &rest[..end])
+}
+
+/// Pick the definitions of a V4 distribution to generate.
+///
+/// Applications default to the code reachable from their entry points; set the
+/// `allModules` option to generate every module instead.
+fn select_package_definition(
+    dist: &V4Distribution,
+    options: &HashMap<String, serde_json::Value>,
+) -> Result<PackageDefinition> {
+    let Some(def) = dist.definition() else {
+        return Err(ExtensionError::execution(
+            "Specs distributions have no definitions to generate code from",
+        ));
+    };
+    let all_modules = options
+        .get("allModules")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let entry_points = match dist.entry_points() {
+        Some(entry_points) if !entry_points.is_empty() && !all_modules => entry_points,
+        _ => return Ok(def.clone()),
+    };
+
+    let roots = entry_points
+        .values()
+        .map(|ep| ep.fqname())
+        .collect::<std::result::Result<Vec<FQName>, _>>()
+        .map_err(|e| ExtensionError::execution(format!("Invalid entry point: {}", e)))?;
+    let sample = sample_distribution(dist, &roots, &SampleOptions::default())
+        .map_err(|e| ExtensionError::execution(e.to_string()))?;
+    Ok(sample
+        .distribution
+        .definition()
+        .cloned()
+        .unwrap_or_else(|| def.clone()))
+}
+
 /// Generate from V4 PackageDefinition using visitor
 fn generate_from_package_definition(
     package_def: PackageDefinition,
     options: &HashMap<String, serde_json::Value>,
 ) -> Result<Vec<Artifact>> {
+    use super::pretty_printer::DEFAULT_WIDTH;
     use super::visitor::MorphirToGleamVisitor;
 
     let output_dir = options
@@ -107,7 +239,14 @@ fn generate_from_package_definition(
         .map(String::from)
         .unwrap_or_else(|| "default-package".to_string());
 
-    let visitor = MorphirToGleamVisitor::new(OsVfs, output_dir.clone(), package_name);
+    let max_width = options
+        .get("maxWidth")
+        .and_then(|v| v.as_u64())
+        .map(|w| w as usize)
+        .unwrap_or(DEFAULT_WIDTH);
+
+    let visitor = MorphirToGleamVisitor::new(OsVfs, output_dir.clone(), package_name)
+        .with_max_width(max_width);
 
     let mut artifacts = Vec::new();
 
@@ -314,4 +453,83 @@ mod tests {
         assert!(result[0].content.contains("pub fn hello()"));
         assert!(result[0].content.contains("\"world\""));
     }
+
+    #[test]
+    fn test_source_map_spans_each_generated_definition() {
+        let ir = serde_json::json!({
+            "name": "orders",
+            "types": [{"name": "Status", "body": {"kind": "customType", "variants": [{"name": "Open"}]}}],
+            "values": [
+                {"name": "total", "body": {"kind": "variable", "name": "x"}},
+                {"name": "limit", "body": {"kind": "literal", "value": {"type": "int", "value": 10}}}
+            ]
+        });
+        let options = HashMap::from([("packageName".to_string(), "my-org/shop".into())]);
+        let (artifacts, source_map) = generate_gleam_with_source_map(&ir, &options).unwrap();
+
+        let spans: Vec<(u32, u32, &str)> = source_map
+            .iter()
+            .map(|entry| (entry.start_line, entry.end_line, entry.fqname.as_str()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (4, 6, "my-org/shop:orders#status"),
+                (8, 10, "my-org/shop:orders#total"),
+                (12, 14, "my-org/shop:orders#limit"),
+            ]
+        );
+        let lines: Vec<&str> = artifacts[0].content.lines().collect();
+        assert_eq!(lines[7], "pub fn total() {");
+        assert!(source_map.iter().all(|entry| entry.path == "orders.gleam"));
+    }
+
+    fn application() -> V4Distribution {
+        serde_json::from_value(serde_json::json!({
+            "Application": {
+                "packageName": "my/app",
+                "dependencies": {},
+                "def": {"modules": {
+                    "main": {"access": "Public", "value": {"types": {}, "values": {
+                        "run": {"access": "Public", "value": {
+                            "inputTypes": {},
+                            "outputType": "morphir/sdk:basics#int",
+                            "body": {"ExpressionBody": {"body": {"Reference": {"fqname": "my/app:util#answer"}}}}
+                        }}
+                    }}},
+                    "util": {"access": "Public", "value": {"types": {}, "values": {
+                        "answer": {"access": "Public", "value": {
+                            "inputTypes": {},
+                            "outputType": "morphir/sdk:basics#int",
+                            "body": {"ExpressionBody": {"body": {"Literal": {"literal": {"IntegerLiteral": {"value": 42}}}}}}
+                        }}
+                    }}},
+                    "unused": {"access": "Public", "value": {"types": {}, "values": {
+                        "other": {"access": "Public", "value": {
+                            "inputTypes": {},
+                            "outputType": "morphir/sdk:basics#int",
+                            "body": {"ExpressionBody": {"body": {"Literal": {"literal": {"IntegerLiteral": {"value": 1}}}}}}
+                        }}
+                    }}}
+                }},
+                "entryPoints": {"main": {"target": "my/app:main#run", "kind": "main"}}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_application_generates_modules_reachable_from_entry_points() {
+        let dist = application();
+
+        let def = select_package_definition(&dist, &HashMap::new()).unwrap();
+        let mut modules: Vec<_> = def.modules.keys().cloned().collect();
+        modules.sort();
+        assert_eq!(modules, vec!["main", "util"]);
+
+        let mut options = HashMap::new();
+        options.insert("allModules".to_string(), serde_json::Value::Bool(true));
+        let def = select_package_definition(&dist, &options).unwrap();
+        assert_eq!(def.modules.len(), 3);
+    }
 }
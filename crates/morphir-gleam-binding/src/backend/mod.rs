@@ -4,6 +4,6 @@ pub mod codegen;
 pub mod pretty_printer;
 pub mod visitor;
 
-pub use codegen::generate_gleam;
+pub use codegen::{generate_gleam, generate_gleam_with_source_map};
 pub use pretty_printer::{render_expr, render_module, render_pattern, render_type_expr};
-pub use visitor::MorphirToGleamVisitor;
+pub use visitor::{MorphirToGleamVisitor, identifier_rules};
@@ -4,6 +4,11 @@
 //! from AST nodes. Inspired by glam (Gleam's pretty printing library) and
 //! glance_printer.
 //!
+//! Layout follows `gleam format`: argument, parameter, tuple and list items
+//! stay on one line while they fit within the width, and otherwise go one per
+//! line with a trailing comma; function bodies, blocks and case clauses always
+//! break; long pipelines put each `|>` step on its own line.
+//!
 //! Reference:
 //! - glam: https://github.com/lpil/glam
 //! - glance_printer: https://github.com/lpil/glance
@@ -137,6 +142,70 @@ impl<'a> GleamPrinter<'a> {
         self.text("[").append(doc).append(self.text("]"))
     }
 
+    /// Comma separated items between `open` and `close`, on one line when
+    /// they fit and otherwise one per line with a trailing comma
+    fn comma_list<I>(&self, open: &'a str, items: I, close: &'a str) -> Doc<'a>
+    where
+        I: IntoIterator<Item = Doc<'a>>,
+    {
+        self.comma_list_with(open, items, None, close)
+    }
+
+    /// [`Self::comma_list`] ending with `last`, which gets no trailing comma,
+    /// e.g. the `..tail` of a list
+    fn comma_list_with<I>(
+        &self,
+        open: &'a str,
+        items: I,
+        last: Option<Doc<'a>>,
+        close: &'a str,
+    ) -> Doc<'a>
+    where
+        I: IntoIterator<Item = Doc<'a>>,
+    {
+        let mut items: Vec<_> = items.into_iter().collect();
+        if items.is_empty() && last.is_none() {
+            return self.text(open).append(self.text(close));
+        }
+        let trailing = match last {
+            Some(last) => {
+                items.push(last);
+                self.nil()
+            }
+            None => self.text(",").flat_alt(self.nil()),
+        };
+        self.text(open)
+            .append(
+                self.alloc
+                    .line_()
+                    .append(self.join(items, self.text(",").append(self.line())))
+                    .nest(INDENT),
+            )
+            .append(trailing)
+            .append(self.alloc.line_())
+            .append(self.text(close))
+            .group()
+    }
+
+    /// `doc` indented on the lines between `{` and `}`
+    fn block(&self, doc: Doc<'a>) -> Doc<'a> {
+        self.text("{")
+            .append(self.hardline().append(doc).nest(INDENT))
+            .append(self.hardline())
+            .append(self.text("}"))
+    }
+
+    /// Statements of a function body, without braces
+    fn body(&self, e: &Expr) -> Doc<'a> {
+        match e {
+            Expr::Block { statements } => self.join(
+                statements.iter().map(|s| self.statement(s)),
+                self.hardline(),
+            ),
+            _ => self.expr(e),
+        }
+    }
+
     // ========================================================================
     // Module printing
     // ========================================================================
@@ -151,17 +220,15 @@ impl<'a> GleamPrinter<'a> {
             parts.push(self.hardline());
         }
 
-        // Type definitions
-        for type_def in &m.types {
-            parts.push(self.type_def(type_def));
-            parts.push(self.hardline());
-            parts.push(self.hardline());
-        }
-
-        // Value definitions
-        for value_def in &m.values {
-            parts.push(self.value_def(value_def));
-            parts.push(self.hardline());
+        // Type definitions, then value definitions, separated by blank lines
+        let definitions: Vec<_> = m
+            .types
+            .iter()
+            .map(|t| self.type_def(t))
+            .chain(m.values.iter().map(|v| self.value_def(v)))
+            .collect();
+        if !definitions.is_empty() {
+            parts.push(self.join(definitions, self.hardline().append(self.hardline())));
             parts.push(self.hardline());
         }
 
@@ -178,31 +245,22 @@ impl<'a> GleamPrinter<'a> {
         let params = if t.params.is_empty() {
             self.nil()
         } else {
-            let params_doc = self.join(
-                t.params.iter().map(|p| self.text(p.clone())),
-                self.text(", "),
-            );
-            self.parens(params_doc)
+            self.comma_list("(", t.params.iter().map(|p| self.text(p.clone())), ")")
         };
 
         // Check if this is a custom type (has variants)
         match &t.body {
             TypeExpr::CustomType { variants } => {
-                let variants_doc = self.join(
-                    variants.iter().map(|v| self.variant(v)),
-                    self.hardline().append(self.text("| ")),
-                );
+                // One variant per line
+                let variants_doc =
+                    self.join(variants.iter().map(|v| self.variant(v)), self.hardline());
 
                 access
                     .append(self.text("type "))
                     .append(self.text(t.name.clone()))
                     .append(params)
-                    .append(self.text(" {"))
-                    .append(self.hardline())
-                    .append(self.text("  "))
-                    .append(variants_doc)
-                    .append(self.hardline())
-                    .append(self.text("}"))
+                    .append(self.text(" "))
+                    .append(self.block(variants_doc))
             }
             _ => {
                 // Type alias
@@ -210,8 +268,9 @@ impl<'a> GleamPrinter<'a> {
                     .append(self.text("type "))
                     .append(self.text(t.name.clone()))
                     .append(params)
-                    .append(self.text(" = "))
-                    .append(self.type_expr(&t.body))
+                    .append(self.text(" ="))
+                    .append(self.line().append(self.type_expr(&t.body)).nest(INDENT))
+                    .group()
             }
         }
     }
@@ -222,8 +281,19 @@ impl<'a> GleamPrinter<'a> {
         if v.fields.is_empty() {
             name
         } else {
-            let fields_doc = self.join(v.fields.iter().map(|f| self.type_expr(f)), self.text(", "));
-            name.append(self.parens(fields_doc))
+            name.append(self.comma_list("(", v.fields.iter().map(|f| self.field_type(f)), ")"))
+        }
+    }
+
+    /// Print a variant field
+    fn field_type(&self, f: &Field<TypeExpr>) -> Doc<'a> {
+        match f {
+            Field::Labelled { label, item } => self
+                .text(label.clone())
+                .append(self.text(": "))
+                .append(self.type_expr(item)),
+            Field::Shorthand { name } => self.text(name.clone()),
+            Field::Unlabelled { item } => self.type_expr(item),
         }
     }
 
@@ -237,27 +307,63 @@ impl<'a> GleamPrinter<'a> {
         // Check if this is a function (lambda body) or constant
         match &v.body {
             Expr::Lambda { params, body } => {
-                let params_doc =
-                    self.join(params.iter().map(|p| self.text(p.clone())), self.text(", "));
+                // A function type annotation types the parameters one by one
+                let (param_types, return_type) = match &v.type_annotation {
+                    Some(TypeExpr::Function {
+                        parameters,
+                        return_type,
+                    }) if parameters.len() == params.len() => {
+                        (parameters.iter().map(Some).collect(), Some(&**return_type))
+                    }
+                    annotation => (vec![None; params.len()], annotation.as_ref()),
+                };
+                let params_doc = params
+                    .iter()
+                    .zip(param_types)
+                    .enumerate()
+                    .map(|(i, (p, ty))| {
+                        let label = match v.labels.get(i) {
+                            Some(Some(label)) => self.text(format!("{} ", label)),
+                            _ => self.nil(),
+                        };
+                        let param = label.append(self.text(p.clone()));
+                        match ty {
+                            Some(ty) => param.append(self.text(": ")).append(self.type_expr(ty)),
+                            None => param,
+                        }
+                    });
 
-                let annotation = if let Some(ann) = &v.type_annotation {
+                let annotation = if let Some(ann) = return_type {
                     self.text(" -> ").append(self.type_expr(ann))
                 } else {
                     self.nil()
                 };
 
-                access
+                // Externally implemented functions may leave out their body
+                let body = match &**body {
+                    Expr::Block { statements }
+                        if statements.is_empty() && !v.externals.is_empty() =>
+                    {
+                        self.nil()
+                    }
+                    body => self.text(" ").append(self.block(self.body(body))),
+                };
+
+                let attributes = self.alloc.concat(v.externals.iter().map(|external| {
+                    self.text(format!(
+                        "@external({}, \"{}\", \"{}\")",
+                        external.target, external.module, external.function
+                    ))
+                    .append(self.hardline())
+                }));
+
+                attributes
+                    .append(access)
                     .append(self.text("fn "))
                     .append(self.text(v.name.clone()))
-                    .append(self.parens(params_doc))
+                    .append(self.comma_list("(", params_doc, ")"))
                     .append(annotation)
-                    .append(self.text(" {"))
-                    .append(self.line())
-                    .append(self.expr(body))
-                    .nest(INDENT)
-                    .append(self.line())
-                    .append(self.text("}"))
-                    .group()
+                    .append(body)
             }
             _ => {
                 // Constant
@@ -289,16 +395,11 @@ impl<'a> GleamPrinter<'a> {
             TypeExpr::Function {
                 parameters,
                 return_type,
-            } => {
-                let params_doc = self.join(
-                    parameters.iter().map(|p| self.type_expr(p)),
-                    self.text(", "),
-                );
-                self.text("fn(")
-                    .append(params_doc)
-                    .append(self.text(") -> "))
-                    .append(self.type_expr(return_type))
-            }
+            } => self
+                .text("fn")
+                .append(self.comma_list("(", parameters.iter().map(|p| self.type_expr(p)), ")"))
+                .append(self.text(" -> "))
+                .append(self.type_expr(return_type)),
             TypeExpr::Record { fields } => {
                 if fields.is_empty() {
                     self.text("{}")
@@ -315,9 +416,7 @@ impl<'a> GleamPrinter<'a> {
                 }
             }
             TypeExpr::Tuple { elements } => {
-                let elements_doc =
-                    self.join(elements.iter().map(|e| self.type_expr(e)), self.text(", "));
-                self.text("#(").append(elements_doc).append(self.text(")"))
+                self.comma_list("#(", elements.iter().map(|e| self.type_expr(e)), ")")
             }
             TypeExpr::Named {
                 module,
@@ -333,11 +432,11 @@ impl<'a> GleamPrinter<'a> {
                 if parameters.is_empty() {
                     qualified_name
                 } else {
-                    let params_doc = self.join(
+                    qualified_name.append(self.comma_list(
+                        "(",
                         parameters.iter().map(|p| self.type_expr(p)),
-                        self.text(", "),
-                    );
-                    qualified_name.append(self.parens(params_doc))
+                        ")",
+                    ))
                 }
             }
             TypeExpr::CustomType { variants } => {
@@ -373,22 +472,20 @@ impl<'a> GleamPrinter<'a> {
                 arguments,
             } => {
                 let fn_doc = self.expr_prec(function, Precedence::Call);
-                let args_doc = self.join(
+                fn_doc.append(self.comma_list(
+                    "(",
                     arguments.iter().map(|a| self.field_expr(a)),
-                    self.text(", "),
-                );
-                fn_doc.append(self.parens(args_doc))
-            }
-            Expr::Lambda { params, body } => {
-                let params_doc =
-                    self.join(params.iter().map(|p| self.text(p.clone())), self.text(", "));
-                self.text("fn(")
-                    .append(params_doc)
-                    .append(self.text(") { "))
-                    .append(self.expr(body))
-                    .append(self.text(" }"))
-                    .group()
+                    ")",
+                ))
             }
+            Expr::Lambda { params, body } => self
+                .text("fn")
+                .append(self.comma_list("(", params.iter().map(|p| self.text(p.clone())), ")"))
+                .append(self.text(" {"))
+                .append(self.line().append(self.body(body)).nest(INDENT))
+                .append(self.line())
+                .append(self.text("}"))
+                .group(),
             Expr::Let { name, value, body } => self
                 .text("let ")
                 .append(self.text(name.clone()))
@@ -403,17 +500,16 @@ impl<'a> GleamPrinter<'a> {
             } => self
                 .text("case ")
                 .append(self.expr(condition))
-                .append(self.text(" {"))
-                .append(self.line())
-                .append(self.text("True -> "))
-                .append(self.expr(then_branch))
-                .append(self.line())
-                .append(self.text("False -> "))
-                .append(self.expr(else_branch))
-                .nest(INDENT)
-                .append(self.line())
-                .append(self.text("}"))
-                .group(),
+                .append(self.text(" "))
+                .append(
+                    self.block(
+                        self.text("True ->")
+                            .append(self.branch_body(then_branch))
+                            .append(self.hardline())
+                            .append(self.text("False ->"))
+                            .append(self.branch_body(else_branch)),
+                    ),
+                ),
             Expr::Record { fields } => {
                 if fields.is_empty() {
                     self.text("{}")
@@ -434,9 +530,7 @@ impl<'a> GleamPrinter<'a> {
                 .append(self.text("."))
                 .append(self.text(label.clone())),
             Expr::Tuple { elements } => {
-                let elements_doc =
-                    self.join(elements.iter().map(|e| self.expr(e)), self.text(", "));
-                self.text("#(").append(elements_doc).append(self.text(")"))
+                self.comma_list("#(", elements.iter().map(|e| self.expr(e)), ")")
             }
             Expr::TupleIndex { tuple, index } => self
                 .expr_prec(tuple, Precedence::Access)
@@ -448,23 +542,53 @@ impl<'a> GleamPrinter<'a> {
                     self.join(clauses.iter().map(|c| self.case_branch(c)), self.hardline());
                 self.text("case ")
                     .append(subjects_doc)
-                    .append(self.text(" {"))
-                    .append(self.hardline())
-                    .append(clauses_doc)
-                    .nest(INDENT)
-                    .append(self.hardline())
-                    .append(self.text("}"))
+                    .append(self.text(" "))
+                    .append(self.block(clauses_doc))
+            }
+            Expr::BinaryOp {
+                op: BinaryOperator::Pipe,
+                ..
+            } => {
+                // Each step of a pipeline on its own line when it does not fit
+                let mut steps = Vec::new();
+                let mut start = e;
+                while let Expr::BinaryOp {
+                    op: BinaryOperator::Pipe,
+                    left,
+                    right,
+                } = start
+                {
+                    steps.push(right);
+                    start = left;
+                }
+                let doc = steps.iter().rev().fold(
+                    self.expr_prec(start, Precedence::Pipe),
+                    |doc, step| {
+                        doc.append(self.line())
+                            .append(self.text("|> "))
+                            .append(self.expr_prec(step, Precedence::Call))
+                    },
+                );
+                let doc = doc.group();
+
+                if Precedence::Pipe < outer_prec {
+                    self.parens(doc)
+                } else {
+                    doc
+                }
             }
             Expr::BinaryOp { op, left, right } => {
                 let op_prec = Precedence::from_binary_op(op);
                 let needs_parens = op_prec < outer_prec;
 
+                // Operators associate to the left, so a right operand of the
+                // same precedence needs parentheses
                 let doc = self
                     .expr_prec(left, op_prec)
                     .append(self.space())
                     .append(self.binary_op(op))
                     .append(self.space())
-                    .append(self.expr_prec(right, op_prec));
+                    .append(self.expr_prec(right, Self::above(op_prec)));
 
                 if needs_parens { self.parens(doc) } else { doc }
             }
@@ -475,26 +599,10 @@ impl<'a> GleamPrinter<'a> {
                 .text("!")
                 .append(self.expr_prec(value, Precedence::Unary)),
             Expr::List { elements, tail } => {
-                if elements.is_empty() && tail.is_none() {
-                    self.text("[]")
-                } else {
-                    let elements_doc =
-                        self.join(elements.iter().map(|e| self.expr(e)), self.text(", "));
-                    let with_tail = if let Some(t) = tail {
-                        elements_doc.append(self.text(", ..")).append(self.expr(t))
-                    } else {
-                        elements_doc
-                    };
-                    self.brackets(with_tail)
-                }
-            }
-            Expr::Block { statements } => {
-                let stmts_doc = self.join(
-                    statements.iter().map(|s| self.statement(s)),
-                    self.hardline(),
-                );
-                self.braces(self.hardline().append(stmts_doc).append(self.hardline()))
+                let tail = tail.as_ref().map(|t| self.text("..").append(self.expr(t)));
+                self.comma_list_with("[", elements.iter().map(|e| self.expr(e)), tail, "]")
             }
+            Expr::Block { .. } => self.block(self.body(e)),
             Expr::Panic { message } => {
                 let msg_doc = if let Some(m) = message {
                     self.parens(self.expr(m))
@@ -560,20 +668,17 @@ impl<'a> GleamPrinter<'a> {
                 } else {
                     self.text(constructor.clone())
                 };
-                let fields_doc = self.join(
-                    fields.iter().map(|(name, value)| {
-                        self.text(name.clone())
-                            .append(self.text(": "))
-                            .append(self.expr(value))
-                    }),
-                    self.text(", "),
-                );
-                constructor_doc
-                    .append(self.text("(.."))
-                    .append(self.expr(record))
-                    .append(self.text(", "))
-                    .append(fields_doc)
-                    .append(self.text(")"))
+                let fields_doc = fields.iter().map(|(name, value)| {
+                    self.text(name.clone())
+                        .append(self.text(": "))
+                        .append(self.expr(value))
+                });
+                let spread = self.text("..").append(self.expr(record));
+                constructor_doc.append(self.comma_list(
+                    "(",
+                    std::iter::once(spread).chain(fields_doc),
+                    ")",
+                ))
             }
         }
     }
@@ -585,7 +690,7 @@ impl<'a> GleamPrinter<'a> {
                 .text(label.clone())
                 .append(self.text(": "))
                 .append(self.expr(item)),
-            Field::Shorthand { name } => self.text(name.clone()),
+            Field::Shorthand { name } => self.text(format!("{}:", name)),
             Field::Unlabelled { item } => self.expr(item),
         }
     }
@@ -671,8 +776,34 @@ impl<'a> GleamPrinter<'a> {
     /// Print a case branch
     fn case_branch(&self, c: &CaseBranch) -> Doc<'a> {
         self.pattern(&c.pattern)
-            .append(self.text(" -> "))
-            .append(self.expr(&c.body))
+            .append(self.text(" ->"))
+            .append(self.branch_body(&c.body))
+    }
+
+    /// Print the body of a case branch, in braces if it has statements
+    fn branch_body(&self, e: &Expr) -> Doc<'a> {
+        match e {
+            // Case expressions and blocks start on the arrow's line
+            Expr::Case { .. } | Expr::Block { .. } => self.space().append(self.expr(e)),
+            _ => self.line().append(self.expr(e)).nest(INDENT).group(),
+        }
+    }
+
+    /// Precedence just above `prec`
+    fn above(prec: Precedence) -> Precedence {
+        match prec {
+            Precedence::Lowest => Precedence::Pipe,
+            Precedence::Pipe => Precedence::Or,
+            Precedence::Or => Precedence::And,
+            Precedence::And => Precedence::Equality,
+            Precedence::Equality => Precedence::Comparison,
+            Precedence::Comparison => Precedence::Concat,
+            Precedence::Concat => Precedence::Addition,
+            Precedence::Addition => Precedence::Multiplication,
+            Precedence::Multiplication => Precedence::Unary,
+            Precedence::Unary => Precedence::Call,
+            Precedence::Call | Precedence::Access => Precedence::Access,
+        }
     }
 
     /// Print a bit string segment for expressions
@@ -807,7 +938,7 @@ impl<'a> GleamPrinter<'a> {
                 .text(label.clone())
                 .append(self.text(": "))
                 .append(self.pattern(item)),
-            Field::Shorthand { name } => self.text(name.clone()),
+            Field::Shorthand { name } => self.text(format!("{}:", name)),
             Field::Unlabelled { item } => self.pattern(item),
         }
     }
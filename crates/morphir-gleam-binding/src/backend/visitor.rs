@@ -1,15 +1,33 @@
 //! Backend visitor - converts Morphir IR V4 to Gleam source code
 //!
-//! This visitor traverses Morphir IR structures and generates Gleam source code,
-//! using Vfs for file generation.
+//! This visitor traverses Morphir IR structures and builds the Gleam AST of
+//! each module, which the pretty printer lays out within the visitor's
+//! maximum line width as `gleam format` would, using Vfs for file generation.
+//!
+//! Morphir constructs without a Gleam counterpart are translated:
+//! - record type aliases become custom types with a single constructor named
+//!   after the alias, so records are built with that constructor and updated
+//!   with `Alias(..record, field: value)`
+//! - functions bound by let-recursion are lifted to private module functions
+//!   named after the enclosing value, which take the locals they use as
+//!   leading parameters
+//! - calls whose first argument is another call become pipelines
+//! - `morphir/sdk` arithmetic, comparison and logic functions become Gleam
+//!   operators, picking the float operators for float operands
 
+use crate::backend::pretty_printer::{DEFAULT_WIDTH, render_module_with_width};
+use crate::frontend::ast::{
+    Access, BinaryOperator, CaseBranch, Expr, External, Field, Literal, ModuleIR, Pattern,
+    Statement, TypeDef, TypeExpr, ValueDef, Variant,
+};
 use morphir_common::vfs::Vfs;
-use morphir_core::ir::Value;
 use morphir_core::ir::v4::{
-    Access as MorphirAccess, AccessControlled, Literal as MorphirLiteral, ModuleDefinition,
-    Pattern as MorphirPattern, TypeDefinition, ValueDefinition,
+    Access as MorphirAccess, AccessControlled, LetBinding, Literal as MorphirLiteral,
+    ModuleDefinition, Pattern as MorphirPattern, TypeDefinition, ValueBody, ValueDefinition,
 };
-use morphir_core::naming::ModuleName;
+use morphir_core::ir::{Type, Value, ValueAttributes};
+use morphir_core::naming::{FQName, ModuleName, Name};
+use morphir_extension_sdk::IdentifierRules;
 use std::io::Result;
 use std::path::PathBuf;
 
@@ -17,18 +35,36 @@ use std::path::PathBuf;
 type AccessControlledModuleDefinition = AccessControlled<ModuleDefinition>;
 type AccessControlledTypeDefinition = AccessControlled<TypeDefinition>;
 
-/// Convert a kebab-case or snake-case name to PascalCase for Gleam type constructors
-fn to_pascal_case(name: &str) -> String {
-    name.split(['-', '_'])
-        .filter(|s| !s.is_empty())
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
-                None => String::new(),
-            }
-        })
-        .collect()
+/// Words Gleam reserves, including those reserved for future use
+const RESERVED_WORDS: &[&str] = &[
+    "as",
+    "assert",
+    "auto",
+    "case",
+    "const",
+    "delegate",
+    "derive",
+    "echo",
+    "else",
+    "fn",
+    "if",
+    "implement",
+    "import",
+    "let",
+    "macro",
+    "opaque",
+    "panic",
+    "pub",
+    "test",
+    "todo",
+    "type",
+    "use",
+];
+
+/// Identifier rules of Gleam, declared in the extension's capabilities and
+/// applied to the function and parameter names the visitor emits
+pub fn identifier_rules() -> IdentifierRules {
+    IdentifierRules::reserving(RESERVED_WORDS.iter().copied())
 }
 
 /// Visitor that converts Morphir IR V4 to Gleam source code
@@ -37,6 +73,9 @@ pub struct MorphirToGleamVisitor<V: Vfs> {
     output_dir: PathBuf,
     #[allow(dead_code)]
     package_name: String,
+    identifiers: IdentifierRules,
+    /// Width the generated code is laid out within
+    max_width: usize,
 }
 
 impl<V: Vfs> MorphirToGleamVisitor<V> {
@@ -46,9 +85,17 @@ impl<V: Vfs> MorphirToGleamVisitor<V> {
             vfs,
             output_dir,
             package_name,
+            identifiers: identifier_rules(),
+            max_width: DEFAULT_WIDTH,
         }
     }
 
+    /// Lay out the generated code within `max_width` columns instead of 80
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
     /// Convert ModuleDefinition to Gleam source file
     pub fn visit_module(
         &self,
@@ -69,476 +116,1068 @@ impl<V: Vfs> MorphirToGleamVisitor<V> {
     /// Generate module content as string
     fn generate_module_content(
         &self,
-        _module_path: &ModuleName,
+        module_path: &ModuleName,
         module: &ModuleDefinition,
     ) -> Result<String> {
-        let mut output = String::new();
+        let module_ir = ModuleGenerator::new(module, &self.identifiers).module(module_path);
 
         // Module header comment
-        output.push_str("// Generated by Morphir Gleam Backend\n");
-        output.push('\n');
+        let mut output = String::from("// Generated by Morphir Gleam Backend\n\n");
+        output.push_str(&render_module_with_width(&module_ir, self.max_width));
+        Ok(output)
+    }
+}
+
+/// Conversion of one module to the Gleam AST
+struct ModuleGenerator<'a> {
+    module: &'a ModuleDefinition,
+    identifiers: &'a IdentifierRules,
+    /// Functions lifted out of let-recursion, emitted after their value
+    lifted: Vec<ValueDef>,
+}
+
+/// What is in scope where a value is converted
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    /// Gleam name of the module value being converted
+    owner: String,
+    /// Gleam names of the local variables
+    locals: Vec<String>,
+    /// Locals known to be floats, which take the float operators
+    floats: Vec<String>,
+    /// Functions lifted out of let-recursion
+    lifted: Vec<Lifted>,
+}
+
+/// Function bound by let-recursion, lifted to a module function
+#[derive(Debug, Clone)]
+struct Lifted {
+    name: Name,
+    /// Name of the module function
+    function: String,
+    /// Locals passed ahead of the function's own arguments
+    captured: Vec<String>,
+    arity: usize,
+}
 
-        // Generate type definitions
-        for (type_name, type_def) in &module.types {
-            self.generate_type_def(&mut output, type_name, type_def)?;
-            output.push('\n');
+impl<'a> ModuleGenerator<'a> {
+    fn new(module: &'a ModuleDefinition, identifiers: &'a IdentifierRules) -> Self {
+        Self {
+            module,
+            identifiers,
+            lifted: Vec::new(),
         }
+    }
 
-        // Generate value definitions
-        for (value_name, value_def) in &module.values {
-            self.generate_value_def(&mut output, value_name, value_def)?;
-            output.push('\n');
+    fn module(mut self, name: &ModuleName) -> ModuleIR {
+        let types = self
+            .module
+            .types
+            .iter()
+            .map(|(name, def)| self.type_def(name, def))
+            .collect();
+        let mut values = Vec::new();
+        for (name, def) in &self.module.values {
+            values.push(self.value_def(name, def));
+            values.append(&mut self.lifted);
         }
+        ModuleIR {
+            name: name.to_string(),
+            doc: None,
+            types,
+            values,
+        }
+    }
 
-        Ok(output)
+    /// Gleam name of a function, parameter or field
+    fn identifier(&self, name: &Name) -> String {
+        self.identifiers.escape(&name.to_snake_case()).0
     }
 
-    /// Generate a type definition
-    fn generate_type_def(
-        &self,
-        output: &mut String,
-        type_name: &str,
-        type_def: &AccessControlledTypeDefinition,
-    ) -> Result<()> {
-        // Access control
-        if matches!(type_def.access, MorphirAccess::Public) {
-            output.push_str("pub ");
-        }
-
-        output.push_str("type ");
-        output.push_str(type_name);
-
-        // Type parameters
-        match &type_def.value {
-            TypeDefinition::TypeAliasDefinition { type_params, .. } => {
-                if !type_params.is_empty() {
-                    output.push('(');
-                    for (i, param) in type_params.iter().enumerate() {
-                        if i > 0 {
-                            output.push_str(", ");
-                        }
-                        output.push_str(&param.to_string());
-                    }
-                    output.push(')');
-                }
+    // ========================================================================
+    // Types
+    // ========================================================================
+
+    fn type_def(&self, name: &str, def: &AccessControlledTypeDefinition) -> TypeDef {
+        let name = Name::from(name).to_title_case();
+        let (params, body) = match &def.value {
+            // Records are custom types in Gleam
+            TypeDefinition::TypeAliasDefinition {
+                type_params,
+                type_expr: Type::Record(_, fields),
+            } => {
+                let fields = fields
+                    .iter()
+                    .map(|field| Field::Labelled {
+                        label: self.identifier(&field.name),
+                        item: self.type_expr(&field.tpe),
+                    })
+                    .collect();
+                let variants = vec![Variant {
+                    name: name.clone(),
+                    fields,
+                }];
+                (type_params, TypeExpr::CustomType { variants })
             }
-            TypeDefinition::CustomTypeDefinition { type_params, .. } => {
-                if !type_params.is_empty() {
-                    output.push('(');
-                    for (i, param) in type_params.iter().enumerate() {
-                        if i > 0 {
-                            output.push_str(", ");
-                        }
-                        output.push_str(&param.to_string());
-                    }
-                    output.push(')');
-                }
+            TypeDefinition::TypeAliasDefinition {
+                type_params,
+                type_expr,
+            } => (type_params, self.type_expr(type_expr)),
+            TypeDefinition::CustomTypeDefinition {
+                type_params,
+                constructors,
+            } => {
+                let variants = constructors
+                    .value
+                    .iter()
+                    .map(|constructor| Variant {
+                        name: constructor.name.to_title_case(),
+                        fields: constructor
+                            .args
+                            .iter()
+                            .map(|arg| {
+                                let item = self.type_expr(&arg.arg_type);
+                                if arg.name.iter().next().is_none() {
+                                    Field::Unlabelled { item }
+                                } else {
+                                    Field::Labelled {
+                                        label: self.identifier(&arg.name),
+                                        item,
+                                    }
+                                }
+                            })
+                            .collect(),
+                    })
+                    .collect();
+                (type_params, TypeExpr::CustomType { variants })
             }
-            TypeDefinition::IncompleteTypeDefinition { type_params, .. } => {
-                if !type_params.is_empty() {
-                    output.push('(');
-                    for (i, param) in type_params.iter().enumerate() {
-                        if i > 0 {
-                            output.push_str(", ");
-                        }
-                        output.push_str(&param.to_string());
-                    }
-                    output.push(')');
+            TypeDefinition::IncompleteTypeDefinition { type_params, .. } => (
+                type_params,
+                TypeExpr::Hole {
+                    name: "incomplete".to_string(),
+                },
+            ),
+        };
+        TypeDef {
+            name,
+            params: params.iter().map(|p| p.to_snake_case()).collect(),
+            body,
+            access: access(&def.access),
+        }
+    }
+
+    fn type_expr(&self, tpe: &Type) -> TypeExpr {
+        match tpe {
+            Type::Variable(_, name) => TypeExpr::Variable {
+                name: name.to_snake_case(),
+            },
+            Type::Reference(_, fqname, args) => TypeExpr::Named {
+                module: None,
+                name: type_name(fqname),
+                parameters: args.iter().map(|a| self.type_expr(a)).collect(),
+            },
+            Type::Tuple(_, elements) => TypeExpr::Tuple {
+                elements: elements.iter().map(|e| self.type_expr(e)).collect(),
+            },
+            Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => TypeExpr::Record {
+                fields: fields
+                    .iter()
+                    .map(|f| (self.identifier(&f.name), self.type_expr(&f.tpe)))
+                    .collect(),
+            },
+            Type::Function(..) => {
+                // Curried functions take all their arguments at once
+                let mut parameters = Vec::new();
+                let mut result = tpe;
+                while let Type::Function(_, argument, rest) = result {
+                    parameters.push(self.type_expr(argument));
+                    result = rest;
+                }
+                TypeExpr::Function {
+                    parameters,
+                    return_type: Box::new(self.type_expr(result)),
                 }
             }
+            Type::Unit(_) => TypeExpr::Unit,
         }
+    }
 
-        output.push_str(" {\n");
+    // ========================================================================
+    // Values
+    // ========================================================================
 
-        match &type_def.value {
-            TypeDefinition::TypeAliasDefinition { type_expr: _, .. } => {
-                // Type alias - generate type expression
-                // For now, simplified - would need to convert type_expr JSON to Gleam syntax
-                output.push_str("  // Type alias\n");
-            }
-            TypeDefinition::CustomTypeDefinition { constructors, .. } => {
-                // Custom type - generate constructors (space-separated, not pipe-separated)
-                for (i, constructor) in constructors.value.iter().enumerate() {
-                    if i > 0 {
-                        output.push(' ');
-                    } else {
-                        output.push_str("  ");
-                    }
-                    // Gleam type constructors must start with uppercase
-                    output.push_str(&to_pascal_case(&constructor.name.to_string()));
-
-                    if !constructor.args.is_empty() {
-                        output.push('(');
-                        for (j, arg) in constructor.args.iter().enumerate() {
-                            if j > 0 {
-                                output.push_str(", ");
-                            }
-                            // Generate proper Gleam type syntax from arg_type
-                            self.generate_type_expr(output, &arg.arg_type)?;
-                        }
-                        output.push(')');
-                    }
-                }
-                output.push('\n');
-            }
-            TypeDefinition::IncompleteTypeDefinition { .. } => {
-                // Incomplete type definition - generate a TODO comment
-                output.push_str("  // TODO: Incomplete type definition\n");
-            }
+    fn value_def(&mut self, name: &str, def: &AccessControlled<ValueDefinition>) -> ValueDef {
+        let function = self.identifier(&Name::from(name));
+        let mut scope = Scope {
+            owner: function.clone(),
+            ..Scope::default()
+        };
+        let (params, annotation) = self.signature(&def.value, &mut scope);
+        // Externally implemented functions have no body
+        let externals: Vec<External> = external(&def.value.body).into_iter().collect();
+        let body = if externals.is_empty() {
+            self.body(&def.value.body, &scope)
+        } else {
+            Expr::Block { statements: vec![] }
+        };
+        ValueDef {
+            name: function,
+            type_annotation: Some(annotation),
+            body: Expr::Lambda {
+                params,
+                body: Box::new(body),
+            },
+            access: access(&def.access),
+            labels: vec![],
+            externals,
         }
+    }
 
-        output.push_str("}\n");
-        Ok(())
+    /// Parameters of a definition, brought into `scope`, and its type
+    fn signature(&self, def: &ValueDefinition, scope: &mut Scope) -> (Vec<String>, TypeExpr) {
+        let mut params = Vec::new();
+        let mut types = Vec::new();
+        for (input, entry) in &def.input_types {
+            let param = self.identifier(&Name::from(input.as_str()));
+            if is_float(&entry.input_type) {
+                scope.floats.push(param.clone());
+            }
+            scope.locals.push(param.clone());
+            params.push(param);
+            types.push(self.type_expr(&entry.input_type));
+        }
+        let annotation = TypeExpr::Function {
+            parameters: types,
+            return_type: Box::new(self.type_expr(&def.output_type)),
+        };
+        (params, annotation)
     }
 
-    /// Generate a Gleam type expression from IR Type
-    fn generate_type_expr(
-        &self,
-        output: &mut String,
-        type_expr: &morphir_core::ir::Type,
-    ) -> Result<()> {
-        use morphir_core::ir::Type;
+    fn body(&mut self, body: &ValueBody, scope: &Scope) -> Expr {
+        let message = match body {
+            ValueBody::Expression(value) => return self.block(value, scope),
+            ValueBody::Native(info) => format!("native: {:?}", info.hint),
+            ValueBody::External { external_name, .. } => format!("external: {}", external_name),
+            ValueBody::Incomplete(_) => "incomplete".to_string(),
+        };
+        todo(message)
+    }
 
-        match type_expr {
-            Type::Variable(_, name) => {
-                output.push_str(&name.to_string());
-            }
-            Type::Reference(_, fqname, args) => {
-                let type_name = fqname.local_name.to_string();
-                output.push_str(&to_pascal_case(&type_name));
-                if !args.is_empty() {
-                    output.push('(');
-                    for (i, arg) in args.iter().enumerate() {
-                        if i > 0 {
-                            output.push_str(", ");
-                        }
-                        self.generate_type_expr(output, arg)?;
-                    }
-                    output.push(')');
-                }
+    /// A local definition: a function if it has inputs, its value otherwise
+    fn definition(&mut self, def: &ValueDefinition, scope: &Scope) -> Expr {
+        let mut inner = scope.clone();
+        let (params, _) = self.signature(def, &mut inner);
+        let body = self.body(&def.body, &inner);
+        if params.is_empty() {
+            body
+        } else {
+            Expr::Lambda {
+                params,
+                body: Box::new(body),
             }
-            Type::Tuple(_, elements) => {
-                output.push_str("#(");
-                for (i, elem) in elements.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(", ");
+        }
+    }
+
+    /// `value`, with the `let`s it starts with as the statements of a block
+    fn block(&mut self, value: &Value, scope: &Scope) -> Expr {
+        let mut scope = scope.clone();
+        let mut statements = Vec::new();
+        let mut value = value;
+        loop {
+            match value {
+                Value::LetDefinition(_, name, def, body) => {
+                    let local = self.identifier(name);
+                    statements.push(Statement::Assignment {
+                        pattern: Pattern::Variable {
+                            name: local.clone(),
+                        },
+                        annotation: None,
+                        value: Box::new(self.definition(def, &scope)),
+                    });
+                    if def.input_types.is_empty() && is_float(&def.output_type) {
+                        scope.floats.push(local.clone());
                     }
-                    self.generate_type_expr(output, elem)?;
+                    scope.locals.push(local);
+                    value = body;
                 }
-                output.push(')');
-            }
-            Type::Record(_, fields) => {
-                output.push_str("{ ");
-                for (i, field) in fields.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(", ");
-                    }
-                    output.push_str(&field.name.to_string());
-                    output.push_str(": ");
-                    self.generate_type_expr(output, &field.tpe)?;
+                Value::Destructure(_, pattern, bound, body) => {
+                    statements.push(Statement::Assignment {
+                        pattern: self.pattern(pattern),
+                        annotation: None,
+                        value: Box::new(self.expr(bound, &scope)),
+                    });
+                    scope.locals.extend(self.pattern_variables(pattern));
+                    scope.floats.extend(self.pattern_floats(pattern));
+                    value = body;
                 }
-                output.push_str(" }");
-            }
-            Type::ExtensibleRecord(_, _, fields) => {
-                output.push_str("{ ");
-                for (i, field) in fields.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(", ");
-                    }
-                    output.push_str(&field.name.to_string());
-                    output.push_str(": ");
-                    self.generate_type_expr(output, &field.tpe)?;
+                Value::LetRecursion(_, bindings, body) => {
+                    self.lift(bindings, &mut scope);
+                    value = body;
                 }
-                output.push_str(" }");
-            }
-            Type::Function(_, arg_type, return_type) => {
-                output.push_str("fn(");
-                self.generate_type_expr(output, arg_type)?;
-                output.push_str(") -> ");
-                self.generate_type_expr(output, return_type)?;
-            }
-            Type::Unit(_) => {
-                output.push_str("Nil");
+                _ => break,
             }
         }
-
-        Ok(())
-    }
-
-    /// Generate a value definition
-    fn generate_value_def(
-        &self,
-        output: &mut String,
-        value_name: &str,
-        value_def: &AccessControlled<ValueDefinition>,
-    ) -> Result<()> {
-        // Access control
-        if matches!(value_def.access, MorphirAccess::Public) {
-            output.push_str("pub ");
+        let result = self.expr(value, &scope);
+        if statements.is_empty() {
+            return result;
         }
-
-        output.push_str("fn ");
-        output.push_str(value_name);
-        output.push('(');
-
-        // Parameters
-        for (i, input) in value_def.value.input_types.iter().enumerate() {
-            if i > 0 {
-                output.push_str(", ");
-            }
-            output.push_str(&input.0.to_string());
+        match result {
+            Expr::Block {
+                statements: mut rest,
+            } => statements.append(&mut rest),
+            result => statements.push(Statement::Expression(result)),
         }
+        Expr::Block { statements }
+    }
 
-        output.push_str(") {\n  ");
-
-        // Body - V4 now stores body as actual Value
-        match &value_def.value.body {
-            morphir_core::ir::v4::ValueBody::Expression(body) => {
-                self.generate_value_expr(output, body)?;
-            }
-            morphir_core::ir::v4::ValueBody::Native(info) => {
-                output.push_str("// native: ");
-                output.push_str(&format!("{:?}", info.hint));
+    /// Lift the functions bound by let-recursion to module functions, called
+    /// through `scope` from then on
+    fn lift(&mut self, bindings: &[LetBinding], scope: &mut Scope) {
+        let mut used = Vec::new();
+        for LetBinding(_, def) in bindings {
+            if let ValueBody::Expression(body) = &def.body {
+                self.variables(body, &mut used);
             }
-            morphir_core::ir::v4::ValueBody::External { external_name, .. } => {
-                output.push_str("// external: ");
-                output.push_str(external_name);
-            }
-            morphir_core::ir::v4::ValueBody::Incomplete(_) => {
-                output.push_str("todo // incomplete");
+        }
+        // Functions lifted before take the locals they capture too
+        for lifted in &scope.lifted {
+            if used.contains(&self.identifier(&lifted.name)) {
+                used.extend(lifted.captured.iter().cloned());
             }
         }
+        let captured: Vec<String> = scope
+            .locals
+            .iter()
+            .filter(|local| used.contains(local))
+            .cloned()
+            .collect();
 
-        output.push_str("\n}\n");
-        Ok(())
+        for LetBinding(name, def) in bindings {
+            scope.lifted.push(Lifted {
+                name: name.clone(),
+                function: format!("{}_{}", scope.owner, name.to_snake_case()),
+                captured: captured.clone(),
+                arity: def.input_types.len(),
+            });
+        }
+        for (LetBinding(_, def), lifted) in bindings
+            .iter()
+            .zip(scope.lifted[scope.lifted.len() - bindings.len()..].to_vec())
+        {
+            let mut inner = Scope {
+                locals: captured.clone(),
+                floats: scope.floats.clone(),
+                ..scope.clone()
+            };
+            let (params, annotation) = self.signature(def, &mut inner);
+            let body = self.body(&def.body, &inner);
+            self.lifted.push(ValueDef {
+                name: lifted.function,
+                // The captured locals are untyped
+                type_annotation: captured.is_empty().then_some(annotation),
+                body: Expr::Lambda {
+                    params: captured.iter().cloned().chain(params).collect(),
+                    body: Box::new(body),
+                },
+                access: Access::Private,
+                labels: vec![],
+                externals: vec![],
+            });
+        }
     }
 
-    /// Generate value expression
-    fn generate_value_expr(&self, output: &mut String, value: &Value) -> Result<()> {
+    /// Gleam names of the variables `value` refers to
+    fn variables(&self, value: &Value, names: &mut Vec<String>) {
+        let mut visit = |value: &Value| self.variables(value, names);
         match value {
-            Value::Literal(_, lit) => {
-                self.generate_literal(output, lit)?;
-            }
-            Value::Variable(_, name) => {
-                output.push_str(&name.to_string());
+            Value::Variable(_, name) => names.push(self.identifier(name)),
+            Value::Tuple(_, items) | Value::List(_, items) => items.iter().for_each(visit),
+            Value::Record(_, fields) => fields.iter().for_each(|field| visit(&field.1)),
+            Value::UpdateRecord(_, record, fields) => {
+                visit(record);
+                fields.iter().for_each(|field| visit(&field.1));
             }
+            Value::Field(_, record, _) => visit(record),
             Value::Apply(_, function, argument) => {
-                self.generate_value_expr(output, function)?;
-                output.push('(');
-                self.generate_value_expr(output, argument)?;
-                output.push(')');
-            }
-            Value::Lambda(_, param, body) => {
-                output.push_str("fn(");
-                self.generate_pattern(output, param)?;
-                output.push_str(") { ");
-                self.generate_value_expr(output, body)?;
-                output.push_str(" }");
+                visit(function);
+                visit(argument);
             }
-            Value::LetDefinition(_, name, def, body) => {
-                // Gleam uses: let name = value \n body (no 'in' keyword)
-                output.push_str("let ");
-                output.push_str(&name.to_string());
-                output.push_str(" = ");
-                self.generate_value_expr(output, def.body.get_expression()?)?;
-                output.push_str("\n  ");
-                self.generate_value_expr(output, body)?;
-            }
-            Value::IfThenElse(_, condition, then_branch, else_branch) => {
-                // Gleam supports case for boolean pattern matching
-                output.push_str("case ");
-                self.generate_value_expr(output, condition)?;
-                output.push_str(" { True -> ");
-                self.generate_value_expr(output, then_branch)?;
-                output.push_str(", False -> ");
-                self.generate_value_expr(output, else_branch)?;
-                output.push_str(" }");
-            }
-            Value::Record(_, fields) => {
-                output.push_str("{ ");
-                for (i, field) in fields.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(", ");
-                    }
-                    output.push_str(&field.0.to_string());
-                    output.push_str(": ");
-                    self.generate_value_expr(output, &field.1)?;
+            Value::Lambda(_, _, body) => visit(body),
+            Value::LetDefinition(_, _, def, body) => {
+                if let ValueBody::Expression(bound) = &def.body {
+                    visit(bound);
                 }
-                output.push_str(" }");
+                visit(body);
             }
-            Value::Field(_, record, field) => {
-                self.generate_value_expr(output, record)?;
-                output.push('.');
-                output.push_str(&field.to_string());
-            }
-            Value::Tuple(_, elements) => {
-                output.push_str("#(");
-                for (i, elem) in elements.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(", ");
+            Value::LetRecursion(_, bindings, body) => {
+                for LetBinding(_, def) in bindings {
+                    if let ValueBody::Expression(bound) = &def.body {
+                        visit(bound);
                     }
-                    self.generate_value_expr(output, elem)?;
                 }
-                output.push(')');
+                visit(body);
             }
-            Value::PatternMatch(_, subject, cases) => {
-                output.push_str("case ");
-                self.generate_value_expr(output, subject)?;
-                output.push_str(" { ");
-                for (i, case) in cases.iter().enumerate() {
-                    if i > 0 {
-                        output.push(' ');
-                    }
-                    self.generate_pattern(output, &case.0)?;
-                    output.push_str(" -> ");
-                    self.generate_value_expr(output, &case.1)?;
-                }
-                output.push_str(" }");
+            Value::Destructure(_, _, bound, body) => {
+                visit(bound);
+                visit(body);
             }
-            Value::Constructor(_, fqname) => {
-                output.push_str(&fqname.local_name.to_string());
+            Value::IfThenElse(_, condition, then_branch, else_branch) => {
+                visit(condition);
+                visit(then_branch);
+                visit(else_branch);
             }
-            Value::Reference(_, fqname) => {
-                // For references, output just the local name for now
-                output.push_str(&fqname.local_name.to_string());
+            Value::PatternMatch(_, subject, cases) => {
+                visit(subject);
+                cases.iter().for_each(|case| visit(&case.1));
             }
-            Value::List(_, items) => {
-                output.push('[');
-                for (i, item) in items.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(", ");
-                    }
-                    self.generate_value_expr(output, item)?;
+            Value::Literal(..)
+            | Value::Constructor(..)
+            | Value::Reference(..)
+            | Value::FieldFunction(..)
+            | Value::Unit(_)
+            | Value::Hole(..)
+            | Value::Native(..)
+            | Value::External(..) => {}
+        }
+    }
+
+    fn expr(&mut self, value: &Value, scope: &Scope) -> Expr {
+        match value {
+            Value::Literal(_, lit) => Expr::Literal {
+                value: literal(lit),
+            },
+            Value::Variable(_, name) => self.variable(name, scope),
+            Value::Reference(_, fqname) | Value::Native(_, fqname, _) => Expr::Variable {
+                name: self.identifier(&fqname.local_name),
+            },
+            Value::Constructor(_, fqname) => constructor(fqname),
+            Value::Tuple(_, elements) => Expr::Tuple {
+                elements: elements.iter().map(|e| self.expr(e, scope)).collect(),
+            },
+            Value::List(_, elements) => Expr::List {
+                elements: elements.iter().map(|e| self.expr(e, scope)).collect(),
+                tail: None,
+            },
+            Value::Record(attributes, fields) => {
+                let names: Vec<&Name> = fields.iter().map(|field| &field.0).collect();
+                let fields = fields
+                    .iter()
+                    .map(|field| (self.identifier(&field.0), self.expr(&field.1, scope)))
+                    .collect::<Vec<_>>();
+                match self.record_constructor(&[attributes], &names, true) {
+                    Some(name) => Expr::Apply {
+                        function: Box::new(Expr::Constructor { module: None, name }),
+                        arguments: fields
+                            .into_iter()
+                            .map(|(label, item)| Field::Labelled { label, item })
+                            .collect(),
+                    },
+                    None => Expr::Record { fields },
                 }
-                output.push(']');
             }
-            Value::Unit(_) => {
-                output.push_str("Nil");
+            Value::UpdateRecord(attributes, record, fields) => {
+                let names: Vec<&Name> = fields.iter().map(|field| &field.0).collect();
+                match self.record_constructor(&[attributes, record.attributes()], &names, false) {
+                    Some(constructor) => Expr::RecordUpdate {
+                        module: None,
+                        constructor,
+                        record: Box::new(self.expr(record, scope)),
+                        fields: fields
+                            .iter()
+                            .map(|field| (self.identifier(&field.0), self.expr(&field.1, scope)))
+                            .collect(),
+                    },
+                    None => todo("update of a record of unknown type".to_string()),
+                }
             }
-            _ => {
-                output.push_str("todo");
+            Value::Field(_, record, name) => Expr::FieldAccess {
+                container: Box::new(self.expr(record, scope)),
+                label: self.identifier(name),
+            },
+            Value::FieldFunction(_, name) => Expr::Lambda {
+                params: vec!["record".to_string()],
+                body: Box::new(Expr::FieldAccess {
+                    container: Box::new(variable("record")),
+                    label: self.identifier(name),
+                }),
+            },
+            Value::Apply(..) => self.apply(value, scope),
+            Value::Lambda(..) => self.lambda(value, scope),
+            Value::LetDefinition(..) | Value::LetRecursion(..) | Value::Destructure(..) => {
+                self.block(value, scope)
             }
+            Value::IfThenElse(_, condition, then_branch, else_branch) => Expr::If {
+                condition: Box::new(self.expr(condition, scope)),
+                then_branch: Box::new(self.expr(then_branch, scope)),
+                else_branch: Box::new(self.expr(else_branch, scope)),
+            },
+            Value::PatternMatch(_, subject, cases) => Expr::Case {
+                subjects: vec![self.expr(subject, scope)],
+                clauses: cases
+                    .iter()
+                    .map(|case| {
+                        let mut inner = scope.clone();
+                        inner.locals.extend(self.pattern_variables(&case.0));
+                        inner.floats.extend(self.pattern_floats(&case.0));
+                        CaseBranch {
+                            pattern: self.pattern(&case.0),
+                            body: self.expr(&case.1, &inner),
+                        }
+                    })
+                    .collect(),
+            },
+            Value::Unit(_) => Expr::Constructor {
+                module: None,
+                name: "Nil".to_string(),
+            },
+            Value::Hole(..) => todo("hole".to_string()),
+            Value::External(_, external_name, _) => todo(format!("external: {}", external_name)),
         }
-        Ok(())
     }
 
-    /// Generate pattern
-    fn generate_pattern(&self, output: &mut String, pattern: &MorphirPattern) -> Result<()> {
-        match pattern {
-            MorphirPattern::WildcardPattern(_) => {
-                output.push('_');
+    /// A local variable, or a reference to a lifted function
+    fn variable(&self, name: &Name, scope: &Scope) -> Expr {
+        let Some(lifted) = scope.lifted.iter().rev().find(|l| &l.name == name) else {
+            return variable(&self.identifier(name));
+        };
+        if lifted.captured.is_empty() {
+            return variable(&lifted.function);
+        }
+        // Pass the captured locals to a function used as a value
+        let params: Vec<String> = (1..=lifted.arity).map(|i| format!("arg{}", i)).collect();
+        let call = Expr::Apply {
+            function: Box::new(variable(&lifted.function)),
+            arguments: unlabelled(
+                lifted
+                    .captured
+                    .iter()
+                    .chain(&params)
+                    .map(|name| variable(name))
+                    .collect(),
+            ),
+        };
+        if params.is_empty() {
+            call
+        } else {
+            Expr::Lambda {
+                params,
+                body: Box::new(call),
             }
-            MorphirPattern::AsPattern(_, sub_pattern, name) => {
-                // In Gleam, `x as name` is often just a binding
-                // If sub_pattern is wildcard, just output the name
-                if matches!(**sub_pattern, MorphirPattern::WildcardPattern(_)) {
-                    output.push_str(&name.to_string());
-                } else {
-                    self.generate_pattern(output, sub_pattern)?;
-                    output.push_str(" as ");
-                    output.push_str(&name.to_string());
+        }
+    }
+
+    fn apply(&mut self, value: &Value, scope: &Scope) -> Expr {
+        // Collect the arguments of a curried application
+        let mut args = Vec::new();
+        let mut function = value;
+        while let Value::Apply(_, f, argument) = function {
+            args.push(&**argument);
+            function = f;
+        }
+        args.reverse();
+
+        match function {
+            Value::Variable(_, name) => {
+                // Lifted functions take the locals they capture first
+                if let Some(lifted) = scope.lifted.iter().rev().find(|l| &l.name == name) {
+                    let function = variable(&lifted.function);
+                    let captured = lifted.captured.iter().map(|name| variable(name));
+                    let arguments = captured
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                        .chain(args.iter().map(|a| self.expr(a, scope)))
+                        .collect();
+                    return call(function, arguments);
                 }
             }
-            MorphirPattern::TuplePattern(_, elements) => {
-                output.push_str("#(");
-                for (i, elem) in elements.iter().enumerate() {
-                    if i > 0 {
-                        output.push_str(", ");
-                    }
-                    self.generate_pattern(output, elem)?;
+            Value::Reference(_, fqname) if is_sdk(fqname) => {
+                if let Some(operation) = self.operator(fqname, &args, scope) {
+                    return operation;
                 }
-                output.push(')');
             }
-            MorphirPattern::ConstructorPattern(_, fqname, args) => {
-                output.push_str(&fqname.local_name.to_string());
-                if !args.is_empty() {
-                    output.push('(');
-                    for (i, arg) in args.iter().enumerate() {
-                        if i > 0 {
-                            output.push_str(", ");
+            _ => {}
+        }
+        let function = self.expr(function, scope);
+        let arguments = args.iter().map(|a| self.expr(a, scope)).collect();
+        call(function, arguments)
+    }
+
+    /// A `morphir/sdk` function applied to `args` as a Gleam operator
+    fn operator(&mut self, fqname: &FQName, args: &[&Value], scope: &Scope) -> Option<Expr> {
+        let name = fqname.local_name.to_camel_case();
+        if let [value] = args {
+            let value = Box::new(self.expr(value, scope));
+            return match name.as_str() {
+                "negate" => Some(Expr::NegateInt { value }),
+                "not" => Some(Expr::NegateBool { value }),
+                _ => None,
+            };
+        }
+        let [left, right] = args else {
+            return None;
+        };
+        let float = self.is_float_value(left, scope) || self.is_float_value(right, scope);
+        let (op, swap) = match name.as_str() {
+            "add" if float => (BinaryOperator::AddFloat, false),
+            "add" => (BinaryOperator::AddInt, false),
+            "subtract" if float => (BinaryOperator::SubFloat, false),
+            "subtract" => (BinaryOperator::SubInt, false),
+            "multiply" if float => (BinaryOperator::MultFloat, false),
+            "multiply" => (BinaryOperator::MultInt, false),
+            "divide" => (BinaryOperator::DivFloat, false),
+            "integerDivide" => (BinaryOperator::DivInt, false),
+            // `remainderBy divisor dividend`
+            "remainderBy" => (BinaryOperator::RemainderInt, true),
+            "equal" => (BinaryOperator::Eq, false),
+            "notEqual" => (BinaryOperator::NotEq, false),
+            "lessThan" if float => (BinaryOperator::LtFloat, false),
+            "lessThan" => (BinaryOperator::LtInt, false),
+            "lessThanOrEqual" if float => (BinaryOperator::LtEqFloat, false),
+            "lessThanOrEqual" => (BinaryOperator::LtEqInt, false),
+            "greaterThan" if float => (BinaryOperator::GtFloat, false),
+            "greaterThan" => (BinaryOperator::GtInt, false),
+            "greaterThanOrEqual" if float => (BinaryOperator::GtEqFloat, false),
+            "greaterThanOrEqual" => (BinaryOperator::GtEqInt, false),
+            "and" => (BinaryOperator::And, false),
+            "or" => (BinaryOperator::Or, false),
+            "append" if fqname.module_path.to_string() == "string" => {
+                (BinaryOperator::Concatenate, false)
+            }
+            _ => return None,
+        };
+        let (left, right) = if swap { (right, left) } else { (left, right) };
+        Some(Expr::BinaryOp {
+            op,
+            left: Box::new(self.expr(left, scope)),
+            right: Box::new(self.expr(right, scope)),
+        })
+    }
+
+    /// Whether `value` is known to be a float
+    fn is_float_value(&self, value: &Value, scope: &Scope) -> bool {
+        if inferred_type(value.attributes()).is_some_and(|t| is_float(&t)) {
+            return true;
+        }
+        match value {
+            Value::Literal(_, MorphirLiteral::Float(_) | MorphirLiteral::Decimal(_)) => true,
+            Value::Variable(_, name) => scope.floats.contains(&self.identifier(name)),
+            Value::Apply(_, function, argument) => {
+                let mut function = &**function;
+                let mut args = vec![&**argument];
+                while let Value::Apply(_, f, argument) = function {
+                    args.push(argument);
+                    function = f;
+                }
+                match function {
+                    Value::Reference(_, fqname) if is_sdk(fqname) => {
+                        match fqname.local_name.to_camel_case().as_str() {
+                            "divide" => true,
+                            "add" | "subtract" | "multiply" | "negate" => {
+                                args.iter().any(|a| self.is_float_value(a, scope))
+                            }
+                            _ => false,
                         }
-                        self.generate_pattern(output, arg)?;
                     }
-                    output.push(')');
+                    _ => false,
                 }
             }
-            MorphirPattern::LiteralPattern(_, lit) => {
-                self.generate_literal(output, lit)?;
-            }
-            MorphirPattern::UnitPattern(_) => {
-                output.push_str("Nil");
-            }
-            MorphirPattern::HeadTailPattern(_, head, tail) => {
-                // Gleam list pattern: [head, ..tail]
-                output.push('[');
-                self.generate_pattern(output, head)?;
-                output.push_str(", ..");
-                self.generate_pattern(output, tail)?;
-                output.push(']');
-            }
-            MorphirPattern::EmptyListPattern(_) => {
-                output.push_str("[]");
-            }
+            _ => false,
         }
-        Ok(())
     }
 
-    /// Generate literal
-    fn generate_literal(&self, output: &mut String, lit: &MorphirLiteral) -> Result<()> {
-        match lit {
-            MorphirLiteral::Bool(b) => {
-                output.push_str(if *b { "True" } else { "False" });
+    /// Constructor of the record built or updated with the fields `names`:
+    /// that of its inferred type, or of the one record alias of the module
+    /// with these fields
+    fn record_constructor(
+        &self,
+        attributes: &[&ValueAttributes],
+        names: &[&Name],
+        exact: bool,
+    ) -> Option<String> {
+        if let Some(Type::Reference(_, fqname, _)) =
+            attributes.iter().find_map(|a| inferred_type(a))
+        {
+            return Some(fqname.local_name.to_title_case());
+        }
+        let mut candidates = self
+            .module
+            .types
+            .iter()
+            .filter_map(|(name, def)| match &def.value {
+                TypeDefinition::TypeAliasDefinition {
+                    type_expr: Type::Record(_, fields),
+                    ..
+                } if names.iter().all(|n| fields.iter().any(|f| &f.name == *n))
+                    && (!exact || fields.len() == names.len()) =>
+                {
+                    Some(Name::from(name).to_title_case())
+                }
+                _ => None,
+            });
+        match (candidates.next(), candidates.next()) {
+            (Some(name), None) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn lambda(&mut self, value: &Value, scope: &Scope) -> Expr {
+        let mut scope = scope.clone();
+        let mut params = Vec::new();
+        let mut statements = Vec::new();
+        let mut body = value;
+        while let Value::Lambda(_, pattern, inner) = body {
+            match pattern {
+                MorphirPattern::AsPattern(_, p, name)
+                    if matches!(**p, MorphirPattern::WildcardPattern(_)) =>
+                {
+                    params.push(self.identifier(name))
+                }
+                MorphirPattern::WildcardPattern(_) => params.push("_".to_string()),
+                // Other patterns destructure the argument
+                pattern => {
+                    let param = format!("arg{}", params.len() + 1);
+                    statements.push(Statement::Assignment {
+                        pattern: self.pattern(pattern),
+                        annotation: None,
+                        value: Box::new(variable(&param)),
+                    });
+                    params.push(param);
+                }
             }
-            MorphirLiteral::Integer(i) => {
-                output.push_str(&i.to_string());
+            scope.locals.extend(self.pattern_variables(pattern));
+            body = inner;
+        }
+        let mut body = self.block(body, &scope);
+        if !statements.is_empty() {
+            match body {
+                Expr::Block {
+                    statements: mut rest,
+                } => statements.append(&mut rest),
+                body => statements.push(Statement::Expression(body)),
             }
-            MorphirLiteral::Float(f) => {
-                output.push_str(&f.to_string());
+            body = Expr::Block { statements };
+        }
+        Expr::Lambda {
+            params,
+            body: Box::new(body),
+        }
+    }
+
+    // ========================================================================
+    // Patterns
+    // ========================================================================
+
+    fn pattern(&self, pattern: &MorphirPattern) -> Pattern {
+        match pattern {
+            MorphirPattern::WildcardPattern(_) => Pattern::Wildcard,
+            MorphirPattern::AsPattern(_, inner, name) => match **inner {
+                MorphirPattern::WildcardPattern(_) => Pattern::Variable {
+                    name: self.identifier(name),
+                },
+                _ => Pattern::Assignment {
+                    pattern: Box::new(self.pattern(inner)),
+                    name: self.identifier(name),
+                },
+            },
+            MorphirPattern::TuplePattern(_, elements) => Pattern::Tuple {
+                elements: elements.iter().map(|e| self.pattern(e)).collect(),
+            },
+            MorphirPattern::ConstructorPattern(_, fqname, args) => Pattern::Constructor {
+                module: None,
+                name: constructor_name(fqname),
+                arguments: args
+                    .iter()
+                    .map(|a| Field::Unlabelled {
+                        item: self.pattern(a),
+                    })
+                    .collect(),
+                with_spread: false,
+            },
+            MorphirPattern::EmptyListPattern(_) => Pattern::List {
+                elements: Vec::new(),
+                tail: None,
+            },
+            MorphirPattern::HeadTailPattern(..) => {
+                // `a :: b :: rest` is `[a, b, ..rest]`
+                let mut elements = Vec::new();
+                let mut rest = pattern;
+                while let MorphirPattern::HeadTailPattern(_, head, tail) = rest {
+                    elements.push(self.pattern(head));
+                    rest = tail;
+                }
+                let tail = match rest {
+                    MorphirPattern::EmptyListPattern(_) => None,
+                    tail => Some(Box::new(self.pattern(tail))),
+                };
+                Pattern::List { elements, tail }
             }
-            MorphirLiteral::Decimal(d) => {
-                output.push_str(d);
+            MorphirPattern::LiteralPattern(_, lit) => Pattern::Literal {
+                value: literal(lit),
+            },
+            MorphirPattern::UnitPattern(_) => Pattern::Constructor {
+                module: None,
+                name: "Nil".to_string(),
+                arguments: Vec::new(),
+                with_spread: false,
+            },
+        }
+    }
+
+    /// Gleam names of the variables `pattern` binds to floats, going by the
+    /// argument types of the module's constructors
+    fn pattern_floats(&self, pattern: &MorphirPattern) -> Vec<String> {
+        match pattern {
+            MorphirPattern::ConstructorPattern(_, fqname, args) => {
+                let types = self.constructor_args(fqname);
+                args.iter()
+                    .enumerate()
+                    .flat_map(|(i, arg)| match (arg, types.get(i)) {
+                        (MorphirPattern::AsPattern(_, inner, name), Some(tpe))
+                            if is_float(tpe)
+                                && matches!(**inner, MorphirPattern::WildcardPattern(_)) =>
+                        {
+                            vec![self.identifier(name)]
+                        }
+                        (arg, _) => self.pattern_floats(arg),
+                    })
+                    .collect()
             }
-            MorphirLiteral::String(s) => {
-                output.push('"');
-                output.push_str(s);
-                output.push('"');
+            MorphirPattern::TuplePattern(_, patterns) => patterns
+                .iter()
+                .flat_map(|p| self.pattern_floats(p))
+                .collect(),
+            MorphirPattern::AsPattern(_, inner, _) => self.pattern_floats(inner),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Argument types of a constructor of the module
+    fn constructor_args(&self, fqname: &FQName) -> Vec<&'a Type> {
+        self.module
+            .types
+            .values()
+            .filter_map(|def| match &def.value {
+                TypeDefinition::CustomTypeDefinition { constructors, .. } => constructors
+                    .value
+                    .iter()
+                    .find(|c| c.name == fqname.local_name),
+                _ => None,
+            })
+            .flat_map(|c| c.args.iter().map(|arg| &arg.arg_type))
+            .collect()
+    }
+
+    /// Gleam names of the variables `pattern` binds
+    fn pattern_variables(&self, pattern: &MorphirPattern) -> Vec<String> {
+        match pattern {
+            MorphirPattern::AsPattern(_, inner, name) => {
+                let mut names = self.pattern_variables(inner);
+                names.push(self.identifier(name));
+                names
             }
-            MorphirLiteral::Char(c) => {
-                output.push('\'');
-                output.push(*c);
-                output.push('\'');
+            MorphirPattern::TuplePattern(_, patterns)
+            | MorphirPattern::ConstructorPattern(_, _, patterns) => patterns
+                .iter()
+                .flat_map(|p| self.pattern_variables(p))
+                .collect(),
+            MorphirPattern::HeadTailPattern(_, head, tail) => {
+                let mut names = self.pattern_variables(head);
+                names.extend(self.pattern_variables(tail));
+                names
             }
+            MorphirPattern::WildcardPattern(_)
+            | MorphirPattern::EmptyListPattern(_)
+            | MorphirPattern::LiteralPattern(..)
+            | MorphirPattern::UnitPattern(_) => Vec::new(),
         }
-        Ok(())
     }
 }
 
-/// Helper trait for ValueBody to extract the expression
-trait ValueBodyExt {
-    fn get_expression(&self) -> Result<&Value>;
+fn access(access: &MorphirAccess) -> Access {
+    match access {
+        MorphirAccess::Public => Access::Public,
+        MorphirAccess::Private => Access::Private,
+    }
+}
+
+fn is_sdk(fqname: &FQName) -> bool {
+    fqname.package_path.is_sdk()
+}
+
+fn is_float(tpe: &Type) -> bool {
+    matches!(tpe, Type::Reference(_, fqname, _)
+        if is_sdk(fqname) && fqname.local_name.to_camel_case() == "float")
+}
+
+/// The type recorded as inferred for a value, if any
+fn inferred_type(attributes: &ValueAttributes) -> Option<Type> {
+    if attributes.inferred_type.is_null() {
+        return None;
+    }
+    serde_json::from_value(attributes.inferred_type.clone()).ok()
+}
+
+/// Gleam name of a referenced type
+fn type_name(fqname: &FQName) -> String {
+    let name = fqname.local_name.to_title_case();
+    if !is_sdk(fqname) {
+        return name;
+    }
+    match name.as_str() {
+        "Maybe" => "Option".to_string(),
+        "Char" => "String".to_string(),
+        "Decimal" => "Float".to_string(),
+        _ => name,
+    }
+}
+
+/// Gleam name of a constructor
+fn constructor_name(fqname: &FQName) -> String {
+    let name = fqname.local_name.to_title_case();
+    if !is_sdk(fqname) {
+        return name;
+    }
+    match name.as_str() {
+        "Just" => "Some".to_string(),
+        "Nothing" => "None".to_string(),
+        _ => name,
+    }
+}
+
+fn constructor(fqname: &FQName) -> Expr {
+    Expr::Constructor {
+        module: None,
+        name: constructor_name(fqname),
+    }
+}
+
+fn variable(name: &str) -> Expr {
+    Expr::Variable {
+        name: name.to_string(),
+    }
+}
+
+/// The `@external` implementing a definition whose body names an external
+/// function as `module.function`
+fn external(body: &ValueBody) -> Option<External> {
+    let ValueBody::External {
+        external_name,
+        target_platform,
+    } = body
+    else {
+        return None;
+    };
+    let (module, function) = external_name.rsplit_once('.')?;
+    Some(External {
+        target: target_platform.clone(),
+        module: module.to_string(),
+        function: function.to_string(),
+    })
+}
+
+fn todo(message: String) -> Expr {
+    Expr::Todo {
+        message: Some(Box::new(Expr::Literal {
+            value: Literal::String { value: message },
+        })),
+    }
+}
+
+fn unlabelled(items: Vec<Expr>) -> Vec<Field<Expr>> {
+    items
+        .into_iter()
+        .map(|item| Field::Unlabelled { item })
+        .collect()
 }
 
-impl ValueBodyExt for morphir_core::ir::v4::ValueBody {
-    fn get_expression(&self) -> Result<&Value> {
-        match self {
-            morphir_core::ir::v4::ValueBody::Expression(body) => Ok(body),
-            _ => Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Expected expression body",
-            )),
+/// Call of `function` with `arguments`; when the first argument is itself a
+/// call, the call is a step of a pipeline starting with that call's first
+/// argument
+fn call(function: Expr, mut arguments: Vec<Expr>) -> Expr {
+    let pipeline = match arguments.first() {
+        Some(Expr::BinaryOp {
+            op: BinaryOperator::Pipe,
+            ..
+        }) => true,
+        Some(Expr::Apply {
+            function,
+            arguments,
+        }) => !arguments.is_empty() && !matches!(**function, Expr::Constructor { .. }),
+        _ => false,
+    };
+    if !pipeline || matches!(function, Expr::Constructor { .. }) {
+        return apply(function, arguments);
+    }
+    let start = match arguments.remove(0) {
+        Expr::Apply {
+            function: inner,
+            arguments: mut inner_arguments,
+        } => {
+            let first = match inner_arguments.remove(0) {
+                Field::Labelled { item, .. } | Field::Unlabelled { item } => item,
+                Field::Shorthand { name } => variable(&name),
+            };
+            pipe(first, step(*inner, inner_arguments))
         }
+        pipeline => pipeline,
+    };
+    pipe(start, apply(function, arguments))
+}
+
+/// `function` applied to `arguments`, or `function` alone without arguments
+fn apply(function: Expr, arguments: Vec<Expr>) -> Expr {
+    step(function, unlabelled(arguments))
+}
+
+fn step(function: Expr, arguments: Vec<Field<Expr>>) -> Expr {
+    if arguments.is_empty() {
+        function
+    } else {
+        Expr::Apply {
+            function: Box::new(function),
+            arguments,
+        }
+    }
+}
+
+fn pipe(left: Expr, right: Expr) -> Expr {
+    Expr::BinaryOp {
+        op: BinaryOperator::Pipe,
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+}
+
+fn literal(lit: &MorphirLiteral) -> Literal {
+    match lit {
+        MorphirLiteral::Bool(value) => Literal::Bool { value: *value },
+        MorphirLiteral::Integer(value) => Literal::Int { value: *value },
+        MorphirLiteral::Float(value) => Literal::Float { value: *value },
+        // Gleam has no decimals
+        MorphirLiteral::Decimal(value) => Literal::Float {
+            value: value.parse().unwrap_or_default(),
+        },
+        MorphirLiteral::String(value) => Literal::String {
+            value: value.clone(),
+        },
+        // Nor characters: they are one-grapheme strings
+        MorphirLiteral::Char(value) => Literal::String {
+            value: value.to_string(),
+        },
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::pretty_printer::render_expr;
     use indexmap::IndexMap;
     use morphir_common::vfs::MemoryVfs;
     use morphir_core::ir::v4::Access as MorphirAccess;
+    use morphir_core::ir::v4::text::parse_distribution;
     use morphir_core::ir::v4::{AccessControlled, ModuleDefinition};
     use morphir_core::naming::ModuleName;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     #[test]
     fn test_generate_module() {
@@ -550,10 +1189,12 @@ mod tests {
         let module_name = ModuleName::parse("test_module");
         let module_def = AccessControlled {
             access: MorphirAccess::Public,
+            doc: None,
             value: ModuleDefinition {
                 types: IndexMap::new(),
                 values: IndexMap::new(),
                 doc: None,
+                ids: Default::default(),
             },
         };
 
@@ -564,22 +1205,138 @@ mod tests {
 
     #[test]
     fn test_generate_literal() {
+        let render = |lit| {
+            render_expr(&Expr::Literal {
+                value: literal(&lit),
+            })
+        };
+        assert_eq!(render(MorphirLiteral::Bool(true)), "True");
+        assert_eq!(render(MorphirLiteral::Integer(42)), "42");
+        assert_eq!(
+            render(MorphirLiteral::String("hello".to_string())),
+            "\"hello\""
+        );
+    }
+
+    const SHAPES: &str = "library acme/geometry
+
+module shapes
+
+type Shape
+    = Circle (radius : morphir/sdk:basics#Float)
+    | Rectangle (width : morphir/sdk:basics#Float) (height : morphir/sdk:basics#Float)
+
+type alias Point =
+    { x : morphir/sdk:basics#Float
+    , y : morphir/sdk:basics#Float
+    }
+
+area (shape : acme/geometry:shapes#Shape) : morphir/sdk:basics#Float =
+    case shape of
+        acme/geometry:shapes#Circle r ->
+            morphir/sdk:basics#multiply 3.14 (morphir/sdk:basics#multiply r r)
+        acme/geometry:shapes#Rectangle w h ->
+            morphir/sdk:basics#multiply w h
+
+move-right (point : acme/geometry:shapes#Point) (distance : morphir/sdk:basics#Float) : acme/geometry:shapes#Point =
+    { point | x = morphir/sdk:basics#add point.x distance }
+
+origin : acme/geometry:shapes#Point =
+    { x = 0.0, y = 0.0 }
+
+factorial (n : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =
+    let rec
+        go (acc : morphir/sdk:basics#Int) (k : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =
+            if morphir/sdk:basics#greaterThan k n then
+                acc
+            else
+                go (morphir/sdk:basics#multiply acc k) (morphir/sdk:basics#add k 1)
+    in
+    go 1 1
+
+describe (shape : acme/geometry:shapes#Shape) : morphir/sdk:string#String =
+    acme/geometry:shapes#truncate (morphir/sdk:string#trim (acme/geometry:shapes#label shape)) 20
+";
+
+    fn generate(max_width: usize) -> String {
+        let dist = parse_distribution(SHAPES).unwrap();
+        let module = &dist.definition().unwrap().modules["shapes"];
         let vfs = MemoryVfs::new();
-        let visitor = MorphirToGleamVisitor::new(vfs, PathBuf::from("/test"), "test".to_string());
-
-        let mut output = String::new();
-        let lit = MorphirLiteral::Bool(true);
-        visitor.generate_literal(&mut output, &lit).unwrap();
-        assert_eq!(output, "True");
-
-        output.clear();
-        let lit = MorphirLiteral::Integer(42);
-        visitor.generate_literal(&mut output, &lit).unwrap();
-        assert_eq!(output, "42");
-
-        output.clear();
-        let lit = MorphirLiteral::String("hello".to_string());
-        visitor.generate_literal(&mut output, &lit).unwrap();
-        assert_eq!(output, "\"hello\"");
+        let visitor =
+            MorphirToGleamVisitor::new(vfs.clone(), PathBuf::from("/out"), "geometry".to_string())
+                .with_max_width(max_width);
+        visitor
+            .visit_module(&ModuleName::parse("shapes"), module)
+            .unwrap();
+        vfs.read_to_string(Path::new("/out/shapes.gleam")).unwrap()
+    }
+
+    #[test]
+    fn test_types_and_values_are_rendered_like_gleam_format() {
+        assert_eq!(
+            generate(DEFAULT_WIDTH),
+            "// Generated by Morphir Gleam Backend
+
+pub type Shape {
+  Circle(radius: Float)
+  Rectangle(width: Float, height: Float)
+}
+
+pub type Point {
+  Point(x: Float, y: Float)
+}
+
+pub fn area(shape: Shape) -> Float {
+  case shape {
+    Circle(r) -> 3.14 *. (r *. r)
+    Rectangle(w, h) -> w *. h
+  }
+}
+
+pub fn move_right(point: Point, distance: Float) -> Point {
+  Point(..point, x: point.x +. distance)
+}
+
+pub fn origin() -> Point {
+  Point(x: 0.0, y: 0.0)
+}
+
+pub fn factorial(n: Int) -> Int {
+  factorial_go(n, 1, 1)
+}
+
+fn factorial_go(n, acc, k) {
+  case k > n {
+    True -> acc
+    False -> factorial_go(n, acc * k, k + 1)
+  }
+}
+
+pub fn describe(shape: Shape) -> String {
+  shape |> label |> trim |> truncate(20)
+}
+"
+        );
+
+        // Past the width, items go one per line with a trailing comma
+        let narrow = generate(30);
+        assert!(narrow.contains(
+            "pub fn move_right(
+  point: Point,
+  distance: Float,
+) -> Point {
+  Point(
+    ..point,
+    x: point.x +. distance,
+  )
+}"
+        ));
+        assert!(narrow.contains(
+            "  shape
+  |> label
+  |> trim
+  |> truncate(20)
+}"
+        ));
     }
 }
@@ -213,9 +213,9 @@ pub enum TypeExpr {
 pub struct Variant {
     /// Variant name
     pub name: String,
-    /// Variant fields
+    /// Variant fields, labelled or not
     #[serde(default)]
-    pub fields: Vec<TypeExpr>,
+    pub fields: Vec<Field<TypeExpr>>,
 }
 
 /// Value definition
@@ -231,6 +231,25 @@ pub struct ValueDef {
     /// Access control (pub or private)
     #[serde(default)]
     pub access: Access,
+    /// Labels of the function's parameters, `None` for unlabelled ones;
+    /// empty when no parameter is labelled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<Option<String>>,
+    /// Implementations given by `@external` attributes, in source order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub externals: Vec<External>,
+}
+
+/// Implementation of a function on one target:
+/// `@external(erlang, "lists", "reverse")`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct External {
+    /// Target the implementation is for, `erlang` or `javascript`
+    pub target: String,
+    /// Erlang module or JavaScript file implementing the function
+    pub module: String,
+    /// Name of the function in that module
+    pub function: String,
 }
 
 /// Expression (matching glance Expression)
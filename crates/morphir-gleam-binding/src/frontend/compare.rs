@@ -237,6 +237,17 @@ fn compare_values(original: &ValueDef, regenerated: &ValueDef, differences: &mut
         });
     }
 
+    // Compare external implementations
+    if original.externals != regenerated.externals {
+        differences.push(Difference::ValueDifference {
+            name: original.name.clone(),
+            detail: format!(
+                "Externals differ: {:?} vs {:?}",
+                original.externals, regenerated.externals
+            ),
+        });
+    }
+
     // Compare body
     if !expr_equivalent(&original.body, &regenerated.body) {
         differences.push(Difference::ExpressionDifference {
@@ -332,7 +343,22 @@ fn variant_equivalent(a: &Variant, b: &Variant) -> bool {
         && a.fields
             .iter()
             .zip(b.fields.iter())
-            .all(|(fa, fb)| type_expr_equivalent(fa, fb))
+            .all(|(fa, fb)| match (fa, fb) {
+                (
+                    Field::Labelled {
+                        label: la,
+                        item: ia,
+                    },
+                    Field::Labelled {
+                        label: lb,
+                        item: ib,
+                    },
+                ) => la == lb && type_expr_equivalent(ia, ib),
+                (Field::Unlabelled { item: ia }, Field::Unlabelled { item: ib }) => {
+                    type_expr_equivalent(ia, ib)
+                }
+                _ => false,
+            })
 }
 
 /// Helper to check if two Field<Expr> lists are equivalent
@@ -667,6 +693,8 @@ mod tests {
             name: name.to_string(),
             type_annotation: None,
             body,
+            labels: vec![],
+            externals: vec![],
         }
     }
 
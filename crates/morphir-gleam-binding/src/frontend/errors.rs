@@ -16,6 +16,8 @@ pub struct ParseError {
     pub found: Option<String>,
     pub hint: Option<String>,
     pub source_snippet: Option<String>,
+    /// Secondary spans with a note each, e.g. where a duplicate was first defined
+    pub related: Vec<(Span, String)>,
 }
 
 impl std::fmt::Display for ParseError {
@@ -33,19 +35,10 @@ impl ParseError {
         file_path: &str,
         source: &str,
     ) -> morphir_extension_sdk::types::Diagnostic {
-        use morphir_extension_sdk::types::{Diagnostic, DiagnosticSeverity, SourceLocation};
+        use morphir_extension_sdk::types::{Diagnostic, DiagnosticSeverity, RelatedInformation};
 
-        // Convert span to line/column
-        let (start_line, start_col) = span_to_line_column(source, self.span.start);
-        let (end_line, end_col) = span_to_line_column(source, self.span.end);
-
-        let location = SourceLocation {
-            file: file_path.to_string(),
-            start_line,
-            start_col,
-            end_line,
-            end_col,
-        };
+        // Convert spans to line/column
+        let locate = |span: &Span| source_location(file_path, source, span);
 
         // Build error message with hint
         let mut message = self.message.clone();
@@ -62,12 +55,72 @@ impl ParseError {
             severity: DiagnosticSeverity::Error,
             code: Some("PARSE_ERROR".to_string()),
             message,
-            location: Some(location),
+            location: Some(locate(&self.span)),
+            related: self
+                .related
+                .iter()
+                .map(|(span, message)| RelatedInformation {
+                    location: locate(span),
+                    message: message.clone(),
+                })
+                .collect(),
+            fixes: vec![],
+        }
+    }
+}
+
+/// A construct that parses but has no Morphir IR counterpart
+///
+/// The definition it occurs in is still converted, with the construct
+/// replaced as `replacement` says.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unsupported {
+    /// What the construct is, e.g. "Bit array"
+    pub construct: String,
+    pub span: Span,
+    /// What the construct was replaced by in the IR
+    pub replacement: String,
+}
+
+impl Unsupported {
+    pub fn to_diagnostic(
+        &self,
+        file_path: &str,
+        source: &str,
+    ) -> morphir_extension_sdk::types::Diagnostic {
+        use morphir_extension_sdk::types::{Diagnostic, DiagnosticSeverity};
+
+        Diagnostic {
+            severity: DiagnosticSeverity::Warning,
+            code: Some("UNSUPPORTED".to_string()),
+            message: format!(
+                "{} is not supported by Morphir IR; {}",
+                self.construct, self.replacement
+            ),
+            location: Some(source_location(file_path, source, &self.span)),
             related: vec![],
+            fixes: vec![],
         }
     }
 }
 
+/// Location of `span` in `source`, read from `file_path`
+fn source_location(
+    file_path: &str,
+    source: &str,
+    span: &Span,
+) -> morphir_extension_sdk::types::SourceLocation {
+    let (start_line, start_col) = span_to_line_column(source, span.start);
+    let (end_line, end_col) = span_to_line_column(source, span.end);
+    morphir_extension_sdk::types::SourceLocation {
+        file: morphir_core::VirtualPath::new(file_path).to_string(),
+        start_line,
+        start_col,
+        end_line,
+        end_col,
+    }
+}
+
 /// Convert byte offset to line/column
 pub(crate) fn span_to_line_column(source: &str, offset: usize) -> (u32, u32) {
     let mut line = 1;
@@ -119,5 +172,6 @@ pub(crate) fn to_parse_error(err: &Rich<'_, Token, SimpleSpan>, source: &str) ->
         found,
         hint,
         source_snippet: snippet,
+        related: vec![],
     }
 }
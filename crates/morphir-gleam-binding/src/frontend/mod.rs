@@ -8,5 +8,20 @@ pub mod parser;
 pub mod visitor;
 
 pub use compare::{ComparisonResult, Difference, compare_modules, modules_equivalent};
-pub use parser::parse_gleam;
+pub use parser::{ParsedModule, parse_gleam, parse_gleam_recovering};
 pub use visitor::{DistributionLayout, GleamToMorphirVisitor};
+
+use morphir_core::VirtualPath;
+
+/// Normalized path of a Gleam source file, without its `.gleam` extension.
+///
+/// Module names are derived from this path so that they do not depend on
+/// the separator used by the host platform.
+pub fn module_path(source_path: &str) -> VirtualPath {
+    let path = VirtualPath::new(source_path);
+    if path.extension() == Some("gleam") {
+        path.with_extension("")
+    } else {
+        path
+    }
+}
@@ -4,20 +4,26 @@
 //! the official Gleam implementations (glance).
 
 use chumsky::input::{IterInput, ValueInput};
+use chumsky::inspector::TruncateState;
 use chumsky::prelude::*;
 use chumsky::span::SimpleSpan;
 
 use crate::frontend::ast::{
-    Access, BinaryOperator, CaseBranch, Expr, Field, Literal, ModuleIR, Pattern, TypeDef, TypeExpr,
-    ValueDef, Variant,
+    Access, BinaryOperator, BitStringOption, BitStringSegment, CaseBranch, Expr, External, Field,
+    Literal, ModuleIR, Pattern, TypeDef, TypeExpr, ValueDef, Variant,
 };
-use crate::frontend::errors::{ParseError, to_parse_error};
-use crate::frontend::lexer::{Token, tokenize};
+use crate::frontend::errors::{ParseError, Unsupported, to_parse_error};
+use crate::frontend::lexer::{Span, Token, tokenize};
+use std::collections::HashMap;
 
 // ============================================================================
 // Parser Combinators (Chumsky 0.12 API)
 // ============================================================================
 
+/// Parser extras: rich errors, and the unsupported constructs met so far as
+/// state, dropped again when the parser backtracks over them
+type Extra<'src> = extra::Full<Rich<'src, Token, SimpleSpan>, TruncateState<Unsupported>, ()>;
+
 /// Statement enum
 #[derive(Debug, Clone)]
 enum Statement {
@@ -25,38 +31,82 @@ enum Statement {
     ValueDef(ValueDef),
 }
 
+/// Name and span of a top-level definition, for reporting duplicates
+#[derive(Debug, Clone)]
+struct DefinitionSpan {
+    kind: &'static str,
+    name: String,
+    span: Span,
+}
+
 /// Main module parser
-fn module_parser<'src, I>()
--> impl Parser<'src, I, ModuleIR, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn module_parser<'src, I>() -> impl Parser<'src, I, (ModuleIR, Vec<DefinitionSpan>), Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
     // Parse module-level statements
-    let stmt = statement_parser().then_ignore(just(Token::Semicolon).or_not());
+    let stmt = statement_parser()
+        .map_with(|stmt, e| -> (Statement, SimpleSpan) { (stmt, e.span()) })
+        .then_ignore(just(Token::Semicolon).or_not());
 
     stmt.repeated().collect::<Vec<_>>().map(|stmts| {
         let mut types = Vec::new();
         let mut values = Vec::new();
-
-        for stmt in stmts {
+        let mut spans = Vec::new();
+
+        for (stmt, span) in stmts {
+            let (kind, name) = match &stmt {
+                Statement::TypeDef(td) => ("type", td.name.clone()),
+                Statement::ValueDef(vd) => ("value", vd.name.clone()),
+            };
+            spans.push(DefinitionSpan {
+                kind,
+                name,
+                span: span.into_range(),
+            });
             match stmt {
                 Statement::TypeDef(td) => types.push(td),
                 Statement::ValueDef(vd) => values.push(vd),
             }
         }
 
-        ModuleIR {
+        let module = ModuleIR {
             name: String::new(), // Will be set from path
             doc: None,
             types,
             values,
-        }
+        };
+        (module, spans)
     })
 }
 
+/// Error for the first definition whose name was already defined, pointing
+/// back at the earlier definition.
+fn duplicate_definition(definitions: &[DefinitionSpan]) -> Option<ParseError> {
+    let mut seen: HashMap<(&str, &str), &DefinitionSpan> = HashMap::new();
+    for definition in definitions {
+        let key = (definition.kind, definition.name.as_str());
+        if let Some(first) = seen.get(&key) {
+            return Some(ParseError {
+                message: format!(
+                    "Duplicate {} definition `{}`",
+                    definition.kind, definition.name
+                ),
+                span: definition.span.clone(),
+                expected: vec![],
+                found: None,
+                hint: Some("Rename or remove one of the definitions".to_string()),
+                source_snippet: None,
+                related: vec![(first.span.clone(), "first defined here".to_string())],
+            });
+        }
+        seen.insert(key, definition);
+    }
+    None
+}
+
 /// Statement parser
-fn statement_parser<'src, I>()
--> impl Parser<'src, I, Statement, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn statement_parser<'src, I>() -> impl Parser<'src, I, Statement, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -66,8 +116,7 @@ where
 }
 
 /// Type definition parser
-fn type_def_parser<'src, I>()
--> impl Parser<'src, I, TypeDef, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn type_def_parser<'src, I>() -> impl Parser<'src, I, TypeDef, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -101,8 +150,7 @@ where
 
 /// Custom type body parser (variants)
 /// In Gleam, variants are listed consecutively without separators (whitespace is skipped)
-fn custom_type_body_parser<'src, I>()
--> impl Parser<'src, I, TypeExpr, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn custom_type_body_parser<'src, I>() -> impl Parser<'src, I, TypeExpr, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -114,8 +162,7 @@ where
 }
 
 /// Variant parser
-fn variant_parser<'src, I>()
--> impl Parser<'src, I, Variant, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn variant_parser<'src, I>() -> impl Parser<'src, I, Variant, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -125,8 +172,8 @@ where
     let variant_field = identifier_parser()
         .then_ignore(just(Token::Colon))
         .then(type_expr_parser())
-        .map(|(_label, ty)| ty) // For now, discard label and keep type
-        .or(type_expr_parser());
+        .map(|(label, item)| Field::Labelled { label, item })
+        .or(type_expr_parser().map(|item| Field::Unlabelled { item }));
 
     let variant_fields = variant_field
         .separated_by(just(Token::Comma))
@@ -134,7 +181,7 @@ where
         .collect::<Vec<_>>()
         .delimited_by(just(Token::LParen), just(Token::RParen))
         .or_not()
-        .map(|opt: Option<Vec<TypeExpr>>| opt.unwrap_or_default());
+        .map(|opt: Option<Vec<Field<TypeExpr>>>| opt.unwrap_or_default());
 
     type_identifier_parser()
         .then(variant_fields)
@@ -142,8 +189,7 @@ where
 }
 
 /// Block body parser - handles function bodies with multiple statements
-fn block_body_parser<'src, I>()
--> impl Parser<'src, I, Expr, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn block_body_parser<'src, I>() -> impl Parser<'src, I, Expr, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -161,11 +207,25 @@ where
             value: Box::new(value),
         });
 
+    // Use: `use pattern, ... <- function`
+    let use_statement = just(Token::Use)
+        .ignore_then(
+            pattern_parser()
+                .separated_by(just(Token::Comma))
+                .collect::<Vec<_>>(),
+        )
+        .then_ignore(just(Token::LeftArrow))
+        .then(expr_parser())
+        .map(|(patterns, function)| Statement::Use {
+            patterns,
+            function: Box::new(function),
+        });
+
     // Expression statement (used for final expression or intermediate effects)
     let expr_statement = expr_parser().map(Statement::Expression);
 
-    // A statement is either a let binding or an expression
-    let statement = let_statement.or(expr_statement);
+    // A statement is a let binding, a use or an expression
+    let statement = let_statement.or(use_statement).or(expr_statement);
 
     // Block body: sequence of statements, last one is the result
     statement
@@ -175,9 +235,57 @@ where
         .map(|statements| Expr::Block { statements })
 }
 
+/// Attribute before a definition: `@name` or `@name(argument, ...)`
+#[derive(Debug, Clone)]
+struct Attribute {
+    name: String,
+    arguments: Vec<String>,
+    span: Span,
+}
+
+/// Attribute parser
+fn attribute_parser<'src, I>() -> impl Parser<'src, I, Attribute, Extra<'src>>
+where
+    I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
+{
+    // `external` is a keyword of its own
+    let name = select! {
+        Token::Ident(name) => name,
+        Token::External => "external".to_string(),
+    }
+    .labelled("attribute name");
+
+    let argument = select! {
+        Token::Ident(argument) => argument,
+        Token::String(argument) => argument,
+    }
+    .labelled("attribute argument");
+
+    just(Token::At)
+        .ignore_then(name)
+        .then(
+            argument
+                .separated_by(just(Token::Comma))
+                .allow_trailing()
+                .collect::<Vec<_>>()
+                .delimited_by(just(Token::LParen), just(Token::RParen))
+                .or_not(),
+        )
+        .map_with(|(name, arguments), e| {
+            let span: SimpleSpan = e.span();
+            Attribute {
+                name,
+                arguments: arguments.unwrap_or_default(),
+                span: span.into_range(),
+            }
+        })
+}
+
 /// Value definition parser
-fn value_def_parser<'src, I>()
--> impl Parser<'src, I, ValueDef, extra::Err<Rich<'src, Token, SimpleSpan>>>
+///
+/// The body of a function is a lambda over its parameters. A function may
+/// leave out its body when `@external` attributes implement it.
+fn value_def_parser<'src, I>() -> impl Parser<'src, I, ValueDef, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -186,43 +294,108 @@ where
         .or_not()
         .map(|opt| opt.unwrap_or(Access::Private));
 
-    // Parameter can be: `name` or `name: Type`
-    // For now we just capture the name and ignore the type annotation
+    // Parameter can be: `name`, `label name`, each with an optional `: Type`
+    let param_name = identifier_parser().or(select! { Token::DiscardName(name) => name });
     let param = identifier_parser()
-        .then(just(Token::Colon).ignore_then(type_expr_parser()).or_not())
-        .map(|(name, _type_ann)| name);
+        .then(param_name.clone())
+        .map(|(label, name)| (Some(label), name))
+        .or(param_name.map(|name| (None, name)))
+        .then(just(Token::Colon).ignore_then(type_expr_parser()).or_not());
 
     let params = param
         .separated_by(just(Token::Comma))
         .allow_trailing()
-        .collect::<Vec<String>>()
+        .collect::<Vec<_>>()
         .delimited_by(just(Token::LParen), just(Token::RParen))
         .or_not()
-        .map(|opt: Option<Vec<String>>| opt.unwrap_or_default());
+        .map(|opt| opt.unwrap_or_default());
 
     // Return type annotation: `-> Type`
     let return_type_ann = just(Token::Arrow).ignore_then(type_expr_parser()).or_not();
 
-    access
+    attribute_parser()
+        .repeated()
+        .collect::<Vec<_>>()
+        .then(access)
         .then_ignore(just(Token::Fn))
         .then(identifier_parser())
         .then(params)
         .then(return_type_ann)
-        .then_ignore(just(Token::LBrace))
-        .then(block_body_parser())
-        .then_ignore(just(Token::RBrace))
-        .map(
-            |((((access, name), _params), type_annotation), body)| ValueDef {
-                name,
-                type_annotation,
-                body,
-                access,
+        .then(
+            block_body_parser()
+                .delimited_by(just(Token::LBrace), just(Token::RBrace))
+                .or_not(),
+        )
+        .try_map_with(
+            |(((((attributes, access), name), params), return_type), body), e| {
+                let externals = externals(attributes, e.state());
+                let body = match body {
+                    Some(body) => body,
+                    None if !externals.is_empty() => Expr::Block { statements: vec![] },
+                    None => {
+                        return Err(Rich::custom(
+                            e.span(),
+                            format!("Function `{}` has neither a body nor an @external", name),
+                        ));
+                    }
+                };
+                let labels = if params.iter().any(|((label, _), _)| label.is_some()) {
+                    params.iter().map(|((label, _), _)| label.clone()).collect()
+                } else {
+                    vec![]
+                };
+                // Typed parameters make the annotation a function type
+                let types: Option<Vec<TypeExpr>> = params
+                    .iter()
+                    .map(|(_, annotation)| annotation.clone())
+                    .collect();
+                let type_annotation = match types {
+                    Some(parameters) if !parameters.is_empty() => Some(TypeExpr::Function {
+                        parameters,
+                        return_type: Box::new(return_type.unwrap_or(TypeExpr::Hole {
+                            name: String::new(),
+                        })),
+                    }),
+                    _ => return_type,
+                };
+                Ok(ValueDef {
+                    name,
+                    type_annotation,
+                    body: Expr::Lambda {
+                        params: params.into_iter().map(|((_, name), _)| name).collect(),
+                        body: Box::new(body),
+                    },
+                    access,
+                    labels,
+                    externals,
+                })
             },
         )
 }
 
+/// The `@external` implementations among `attributes`; the other
+/// attributes are reported as unsupported
+fn externals(attributes: Vec<Attribute>, unsupported: &mut Vec<Unsupported>) -> Vec<External> {
+    let mut externals = Vec::new();
+    for attribute in attributes {
+        match (attribute.name.as_str(), attribute.arguments.as_slice()) {
+            ("external", [target, module, function]) => externals.push(External {
+                target: target.clone(),
+                module: module.clone(),
+                function: function.clone(),
+            }),
+            _ => unsupported.push(Unsupported {
+                construct: format!("Attribute `@{}`", attribute.name),
+                span: attribute.span,
+                replacement: "it is ignored".to_string(),
+            }),
+        }
+    }
+    externals
+}
+
 /// Expression parser
-fn expr_parser<'src, I>() -> impl Parser<'src, I, Expr, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn expr_parser<'src, I>() -> impl Parser<'src, I, Expr, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -276,27 +449,53 @@ where
                     value: Box::new(value),
                 });
 
+            let use_stmt = just(Token::Use)
+                .ignore_then(
+                    pattern_parser()
+                        .separated_by(just(Token::Comma))
+                        .collect::<Vec<_>>(),
+                )
+                .then_ignore(just(Token::LeftArrow))
+                .then(expr.clone())
+                .map(|(patterns, function)| Statement::Use {
+                    patterns,
+                    function: Box::new(function),
+                });
+
             let expr_stmt = expr.clone().map(Statement::Expression);
 
-            let_stmt.or(expr_stmt)
+            let_stmt.or(use_stmt).or(expr_stmt)
         };
 
-        let block_expr = block_statement
+        let block_statements = block_statement
             .repeated()
             .at_least(1)
             .collect::<Vec<_>>()
             .delimited_by(just(Token::LBrace), just(Token::RBrace))
+            .boxed();
+
+        let block_expr = block_statements
+            .clone()
             .map(|statements| Expr::Block { statements });
 
-        // Lambda: fn(param) { expr }
+        // Lambda: fn(param, ...) { statements }, parameter types are ignored
+        let lambda_param = identifier_parser()
+            .or(select! { Token::DiscardName(name) => name })
+            .then_ignore(just(Token::Colon).ignore_then(type_expr_parser()).or_not());
+
         let lambda = just(Token::Fn)
-            .ignore_then(identifier_parser().delimited_by(just(Token::LParen), just(Token::RParen)))
-            .then_ignore(just(Token::LBrace))
-            .then(expr.clone())
-            .then_ignore(just(Token::RBrace))
-            .map(|(param, body)| Expr::Lambda {
-                params: vec![param],
-                body: Box::new(body),
+            .ignore_then(
+                lambda_param
+                    .separated_by(just(Token::Comma))
+                    .allow_trailing()
+                    .collect::<Vec<_>>()
+                    .delimited_by(just(Token::LParen), just(Token::RParen)),
+            )
+            .then_ignore(just(Token::Arrow).ignore_then(type_expr_parser()).or_not())
+            .then(block_statements)
+            .map(|(params, statements)| Expr::Lambda {
+                params,
+                body: Box::new(lambda_body(statements)),
             });
 
         // Let binding: let name = expr { expr }
@@ -392,6 +591,17 @@ where
                 message: message.map(Box::new),
             });
 
+        // Bit array: <<segment, ...>>
+        let bit_array = bit_array_parser(expr.clone(), expr.clone()).map_with(|segments, e| {
+            let span: SimpleSpan = e.span();
+            e.state().push(Unsupported {
+                construct: "Bit array".to_string(),
+                span: span.into_range(),
+                replacement: "it is left as a hole".to_string(),
+            });
+            Expr::BitString { segments }
+        });
+
         // Primary expressions (atoms)
         // Note: block_expr must come after record to avoid ambiguity
         // Records require `ident: expr`, blocks can start with `let` or any expr
@@ -404,14 +614,23 @@ where
             .or(list_literal)
             .or(todo_expr)
             .or(panic_expr)
+            .or(bit_array)
             .or(paren_expr);
 
         // Field access: expr.field
         let field_access = just(Token::Dot).ignore_then(identifier_parser());
 
-        // Function application: expr(arg1, arg2, ...)
-        let application = expr
-            .clone()
+        // Function application: expr(arg, label: arg, label, ...)
+        let argument = identifier_parser()
+            .then_ignore(just(Token::Colon))
+            .then(expr.clone())
+            .map(|(label, item)| Field::Labelled { label, item })
+            .or(identifier_parser()
+                .then_ignore(just(Token::Colon))
+                .map(|name| Field::Shorthand { name }))
+            .or(expr.clone().map(|item| Field::Unlabelled { item }));
+
+        let application = argument
             .separated_by(just(Token::Comma))
             .allow_trailing()
             .collect::<Vec<_>>()
@@ -431,10 +650,7 @@ where
                     },
                     PostfixOp::Apply(arguments) => Expr::Apply {
                         function: Box::new(lhs),
-                        arguments: arguments
-                            .into_iter()
-                            .map(|item| Field::Unlabelled { item })
-                            .collect(),
+                        arguments,
                     },
                 },
             )
@@ -562,16 +778,28 @@ where
     })
 }
 
+/// Body of a lambda: its only expression, or the block of its statements
+fn lambda_body(statements: Vec<crate::frontend::ast::Statement>) -> Expr {
+    use crate::frontend::ast::Statement;
+
+    match <[Statement; 1]>::try_from(statements) {
+        Ok([Statement::Expression(body)]) => body,
+        Ok(statements) => Expr::Block {
+            statements: statements.into(),
+        },
+        Err(statements) => Expr::Block { statements },
+    }
+}
+
 /// Helper enum for postfix operators
 #[derive(Clone)]
 enum PostfixOp {
     Field(String),
-    Apply(Vec<Expr>),
+    Apply(Vec<Field<Expr>>),
 }
 
 /// Type expression parser
-fn type_expr_parser<'src, I>()
--> impl Parser<'src, I, TypeExpr, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn type_expr_parser<'src, I>() -> impl Parser<'src, I, TypeExpr, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -626,6 +854,23 @@ where
                 parameters,
             });
 
+        // Function type: fn(Type, ...) -> Type
+        let fn_type = just(Token::Fn)
+            .ignore_then(
+                type_expr
+                    .clone()
+                    .separated_by(just(Token::Comma))
+                    .allow_trailing()
+                    .collect::<Vec<_>>()
+                    .delimited_by(just(Token::LParen), just(Token::RParen)),
+            )
+            .then_ignore(just(Token::Arrow))
+            .then(type_expr.clone())
+            .map(|(parameters, return_type)| TypeExpr::Function {
+                parameters,
+                return_type: Box::new(return_type),
+            });
+
         // Parenthesized type
         let paren_type = type_expr
             .clone()
@@ -633,6 +878,7 @@ where
 
         // Primary types
         let primary = unit
+            .or(fn_type)
             .or(tuple_type)
             .or(record_type)
             .or(ref_type)
@@ -654,8 +900,7 @@ where
 }
 
 /// Pattern parser
-fn pattern_parser<'src, I>()
--> impl Parser<'src, I, Pattern, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn pattern_parser<'src, I>() -> impl Parser<'src, I, Pattern, Extra<'src>>
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -724,8 +969,24 @@ where
                 tail: tail.map(Box::new),
             });
 
+        // Bit array pattern: <<segment, ...>>
+        let size = identifier_parser()
+            .map(|name| Expr::Variable { name })
+            .or(literal_parser().map(|value| Expr::Literal { value }));
+
+        let bit_array_pattern = bit_array_parser(pattern.clone(), size).map_with(|segments, e| {
+            let span: SimpleSpan = e.span();
+            e.state().push(Unsupported {
+                construct: "Bit array pattern".to_string(),
+                span: span.into_range(),
+                replacement: "it matches any value".to_string(),
+            });
+            Pattern::BitString { segments }
+        });
+
         wildcard
             .or(lit_pattern)
+            .or(bit_array_pattern)
             .or(tuple_pattern)
             .or(constructor_pattern)
             .or(list_pattern)
@@ -735,9 +996,82 @@ where
     })
 }
 
+/// Bit array parser: `<<value:option-option, ...>>`, with segment values
+/// parsed by `value` and `size(...)` arguments by `size`
+fn bit_array_parser<'src, I, T>(
+    value: impl Parser<'src, I, T, Extra<'src>> + Clone,
+    size: impl Parser<'src, I, Expr, Extra<'src>> + Clone,
+) -> impl Parser<'src, I, Vec<BitStringSegment<T>>, Extra<'src>> + Clone
+where
+    I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
+{
+    let named_option = identifier_parser()
+        .then(
+            size.delimited_by(just(Token::LParen), just(Token::RParen))
+                .or_not(),
+        )
+        .try_map(|(name, argument), span| {
+            Ok(match (name.as_str(), argument) {
+                ("size", Some(size)) => BitStringOption::Size(Box::new(size)),
+                (
+                    "unit",
+                    Some(Expr::Literal {
+                        value: Literal::Int { value },
+                    }),
+                ) if value > 0 => BitStringOption::Unit(value as u64),
+                ("bytes", None) => BitStringOption::Bytes,
+                ("int", None) => BitStringOption::Int,
+                ("float", None) => BitStringOption::Float,
+                ("bits", None) => BitStringOption::Bits,
+                ("utf8", None) => BitStringOption::Utf8,
+                ("utf16", None) => BitStringOption::Utf16,
+                ("utf32", None) => BitStringOption::Utf32,
+                ("signed", None) => BitStringOption::Signed,
+                ("unsigned", None) => BitStringOption::Unsigned,
+                ("big", None) => BitStringOption::Big,
+                ("little", None) => BitStringOption::Little,
+                ("native", None) => BitStringOption::Native,
+                _ => {
+                    return Err(Rich::custom(
+                        span,
+                        format!("Unknown bit array segment option `{}`", name),
+                    ));
+                }
+            })
+        });
+
+    // A bare integer is the segment's size
+    let option = named_option.or(select! {
+        Token::Int(value) => BitStringOption::Size(Box::new(Expr::Literal {
+            value: Literal::Int { value },
+        })),
+    });
+
+    let segment = value
+        .then(
+            just(Token::Colon)
+                .ignore_then(
+                    option
+                        .separated_by(just(Token::Minus))
+                        .at_least(1)
+                        .collect::<Vec<_>>(),
+                )
+                .or_not(),
+        )
+        .map(|(value, options)| BitStringSegment {
+            value,
+            options: options.unwrap_or_default(),
+        });
+
+    segment
+        .separated_by(just(Token::Comma))
+        .allow_trailing()
+        .collect::<Vec<_>>()
+        .delimited_by(just(Token::LeftShift), just(Token::RightShift))
+}
+
 /// Literal parser
-fn literal_parser<'src, I>()
--> impl Parser<'src, I, Literal, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn literal_parser<'src, I>() -> impl Parser<'src, I, Literal, Extra<'src>> + Clone
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -752,8 +1086,7 @@ where
 }
 
 /// Identifier parser (lowercase)
-fn identifier_parser<'src, I>()
--> impl Parser<'src, I, String, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn identifier_parser<'src, I>() -> impl Parser<'src, I, String, Extra<'src>> + Clone
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -764,8 +1097,7 @@ where
 }
 
 /// Type identifier parser (uppercase)
-fn type_identifier_parser<'src, I>()
--> impl Parser<'src, I, String, extra::Err<Rich<'src, Token, SimpleSpan>>>
+fn type_identifier_parser<'src, I>() -> impl Parser<'src, I, String, Extra<'src>> + Clone
 where
     I: ValueInput<'src, Token = Token, Span = SimpleSpan>,
 {
@@ -809,9 +1141,54 @@ fn extract_module_doc(tokens: &[(Token, SimpleSpan)]) -> Option<String> {
     }
 }
 
+/// A module parsed definition by definition
+#[derive(Debug, Clone)]
+pub struct ParsedModule {
+    /// The definitions that parsed
+    pub module: ModuleIR,
+    /// Why the other definitions did not parse, in source order
+    pub errors: Vec<ParseError>,
+    /// Constructs that parsed but have no Morphir IR counterpart
+    pub unsupported: Vec<Unsupported>,
+}
+
+impl ParsedModule {
+    /// Diagnostics for the errors and unsupported constructs
+    pub fn diagnostics(
+        &self,
+        file_path: &str,
+        source: &str,
+    ) -> Vec<morphir_extension_sdk::types::Diagnostic> {
+        self.errors
+            .iter()
+            .map(|e| e.to_diagnostic(file_path, source))
+            .chain(
+                self.unsupported
+                    .iter()
+                    .map(|u| u.to_diagnostic(file_path, source)),
+            )
+            .collect()
+    }
+}
+
 /// Parse Gleam source code into ModuleIR
+///
+/// Fails with the first error; use [`parse_gleam_recovering`] to keep the
+/// definitions that do parse.
 #[allow(clippy::result_large_err)]
 pub fn parse_gleam(path: &str, source: &str) -> Result<ModuleIR, ParseError> {
+    let parsed = parse_gleam_recovering(path, source);
+    match parsed.errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(parsed.module),
+    }
+}
+
+/// Parse Gleam source code into ModuleIR, one top-level definition at a time
+///
+/// A definition that fails to parse is left out of the module and reported
+/// in [`ParsedModule::errors`], without affecting the other definitions.
+pub fn parse_gleam_recovering(path: &str, source: &str) -> ParsedModule {
     // Tokenize
     let tokens = tokenize(source);
 
@@ -830,52 +1207,105 @@ pub fn parse_gleam(path: &str, source: &str) -> Result<ModuleIR, ParseError> {
         })
         .collect();
 
-    if tokens.is_empty() {
-        return Ok(ModuleIR {
+    let mut parsed = ParsedModule {
+        module: ModuleIR {
             name: extract_module_name(path),
             doc: module_doc,
             types: vec![],
             values: vec![],
-        });
+        },
+        errors: vec![],
+        unsupported: vec![],
+    };
+    let mut definitions = Vec::new();
+
+    for chunk in split_definitions(source, tokens) {
+        // End of input is the end of the definition
+        let eoi = chunk
+            .last()
+            .map(|(_, span)| SimpleSpan::from(span.end..span.end))
+            .unwrap_or_else(|| SimpleSpan::from(source.len()..source.len()));
+
+        // Create IterInput from tokens for parsing (handles (Token, Span) tuples)
+        let input = IterInput::new(chunk.into_iter(), eoi);
+
+        // Parse using chumsky 0.12 API
+        let mut unsupported = TruncateState(Vec::new());
+        let (output, errors) = module_parser()
+            .parse_with_state(input, &mut unsupported)
+            .into_output_errors();
+        match (output, errors.first()) {
+            (Some((module, spans)), None) => {
+                parsed.module.types.extend(module.types);
+                parsed.module.values.extend(module.values);
+                parsed.unsupported.extend(unsupported.0);
+                definitions.extend(spans);
+            }
+            (_, Some(err)) => parsed.errors.push(to_parse_error(err, source)),
+            (None, None) => parsed.errors.push(ParseError {
+                message: "Unknown parse error".to_string(),
+                span: eoi.into_range(),
+                expected: vec![],
+                found: None,
+                hint: None,
+                source_snippet: None,
+                related: vec![],
+            }),
+        }
     }
 
-    // Create end-of-input span
-    let eoi = SimpleSpan::from(source.len()..source.len());
-
-    // Create IterInput from tokens for parsing (handles (Token, Span) tuples)
-    let input = IterInput::new(tokens.into_iter(), eoi);
-
-    // Parse using chumsky 0.12 API
-    let parser = module_parser();
-    match parser.parse(input).into_result() {
-        Ok(mut module) => {
-            // Set module name from path
-            module.name = extract_module_name(path);
-            // Set module documentation from //// comments
-            module.doc = module_doc;
-            Ok(module)
-        }
-        Err(errors) => {
-            // Return first error (could be enhanced to return multiple)
-            if let Some(err) = errors.first() {
-                Err(to_parse_error(err, source))
-            } else {
-                Err(ParseError {
-                    message: "Unknown parse error".to_string(),
-                    span: 0..0,
-                    expected: vec![],
-                    found: None,
-                    hint: None,
-                    source_snippet: None,
-                })
+    parsed.errors.extend(duplicate_definition(&definitions));
+    parsed.errors.sort_by_key(|e| e.span.start);
+    parsed
+}
+
+/// Split the tokens of a module at the start of each top-level definition,
+/// so that each definition can be parsed on its own
+///
+/// A definition starts at an attribute, `pub`, `fn`, `type`, `const` or
+/// `import` outside any brackets or at the start of a line, unless it
+/// continues the attributes or `pub` of the definition before. Starting a
+/// line closes the brackets a broken definition left open.
+fn split_definitions(
+    source: &str,
+    tokens: Vec<(Token, SimpleSpan)>,
+) -> Vec<Vec<(Token, SimpleSpan)>> {
+    let mut chunks: Vec<Vec<(Token, SimpleSpan)>> = Vec::new();
+    let mut depth = 0usize;
+    let mut in_prefix = false;
+    for (token, span) in tokens {
+        let starts_line = source[..span.start].ends_with('\n') || span.start == 0;
+        if (depth == 0 || starts_line)
+            && matches!(
+                token,
+                Token::At | Token::Pub | Token::Fn | Token::Type | Token::Const | Token::Import
+            )
+        {
+            if !in_prefix || chunks.is_empty() {
+                chunks.push(Vec::new());
             }
+            in_prefix = matches!(token, Token::At | Token::Pub);
+            depth = 0;
+        }
+        match token {
+            Token::LParen | Token::LBrace | Token::LBracket => depth += 1,
+            Token::RParen | Token::RBrace | Token::RBracket => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        match chunks.last_mut() {
+            Some(chunk) => chunk.push((token, span)),
+            None => chunks.push(vec![(token, span)]),
         }
     }
+    chunks
 }
 
 /// Extract module name from file path
 fn extract_module_name(path: &str) -> String {
-    path.trim_end_matches(".gleam").replace(['/', '\\'], "_")
+    super::module_path(path)
+        .segments()
+        .collect::<Vec<_>>()
+        .join("_")
 }
 
 #[cfg(test)]
@@ -929,6 +1359,16 @@ pub fn hello() {
         assert_eq!(module.values[0].name, "hello");
     }
 
+    #[test]
+    fn test_module_name_is_platform_independent() {
+        let source = "pub fn hello() { 1 }";
+
+        let windows = parse_gleam(r"app\orders\pricing.gleam", source).unwrap();
+        let unix = parse_gleam("app/orders/pricing.gleam", source).unwrap();
+        assert_eq!(windows.name, "app_orders_pricing");
+        assert_eq!(windows.name, unix.name);
+    }
+
     #[test]
     fn test_parse_type_definition() {
         let source = r#"
@@ -1001,4 +1441,133 @@ pub fn hello() { "world" }
         assert!(module.doc.is_some());
         assert!(module.doc.unwrap().contains("Module documentation here"));
     }
+
+    #[test]
+    fn test_duplicate_definition_points_at_first() {
+        let source = "pub fn total() { 1 }\n\npub fn total() { 2 }\n";
+        let err = parse_gleam("orders.gleam", source).unwrap_err();
+        assert_eq!(err.message, "Duplicate value definition `total`");
+
+        let diagnostic = err.to_diagnostic("src/orders.gleam", source);
+        assert_eq!(diagnostic.location.as_ref().unwrap().start_line, 3);
+        assert_eq!(diagnostic.related.len(), 1);
+        assert_eq!(diagnostic.related[0].message, "first defined here");
+        let first = &diagnostic.related[0].location;
+        assert_eq!((first.start_line, first.start_col), (1, 1));
+        assert_eq!(first.end_line, 1);
+    }
+
+    const FEATURES: &str = r#"
+pub type Interval {
+    Interval(start: Int, end: Int)
+}
+
+@external(erlang, "os", "system_time")
+@external(javascript, "./clock.mjs", "now")
+pub fn now() -> Int
+
+@deprecated("Use between instead")
+pub fn make(from start: Int, to end: Int) -> Interval {
+    Interval(end: end, start:)
+}
+
+pub fn with_now(callback: fn(Int) -> a) -> a {
+    callback(now())
+}
+
+pub fn since(start: Int) -> Interval {
+    use end <- with_now()
+    make(to: end, from: start)
+}
+
+pub fn header() {
+    <<1, 2:size(8), "gleam":utf8>>
+}
+"#;
+
+    #[test]
+    fn test_parse_use_labels_externals_and_bit_arrays() {
+        let parsed = parse_gleam_recovering("interval.gleam", FEATURES);
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        let module = parsed.module;
+        let value = |name: &str| module.values.iter().find(|v| v.name == name).unwrap();
+
+        let now = value("now");
+        assert_eq!(now.externals.len(), 2);
+        assert_eq!(now.externals[1].module, "./clock.mjs");
+        assert_eq!(now.externals[1].function, "now");
+
+        let make = value("make");
+        assert_eq!(
+            make.labels,
+            vec![Some("from".to_string()), Some("to".to_string())]
+        );
+        let Expr::Lambda { params, body } = &make.body else {
+            panic!("functions are lambdas over their parameters");
+        };
+        assert_eq!(params, &["start", "end"]);
+        assert!(matches!(
+            &**body,
+            Expr::Block { statements } if matches!(
+                &statements[0],
+                crate::frontend::ast::Statement::Expression(Expr::Apply { arguments, .. })
+                    if matches!(&arguments[1], Field::Shorthand { name } if name == "start")
+            )
+        ));
+
+        let Expr::Lambda { body, .. } = &value("since").body else {
+            panic!("functions are lambdas over their parameters");
+        };
+        assert!(matches!(
+            &**body,
+            Expr::Block { statements }
+                if matches!(&statements[0], crate::frontend::ast::Statement::Use { patterns, .. } if patterns.len() == 1)
+        ));
+
+        // Externals and labels are printed back
+        let printed = crate::backend::pretty_printer::render_module(&module);
+        assert!(printed.contains(
+            "@external(erlang, \"os\", \"system_time\")\n@external(javascript, \"./clock.mjs\", \"now\")\npub fn now() -> Int\n"
+        ), "{}", printed);
+        assert!(printed.contains("pub fn make(from start: Int, to end: Int) -> Interval {"));
+        assert!(printed.contains("Interval(end: end, start:)"));
+        assert!(printed.contains("use end <- with_now()"));
+
+        // The deprecation and the bit array are reported where they are
+        let constructs: Vec<_> = parsed
+            .unsupported
+            .iter()
+            .map(|u| (u.construct.as_str(), &FEATURES[u.span.clone()]))
+            .collect();
+        assert_eq!(
+            constructs,
+            vec![
+                (
+                    "Attribute `@deprecated`",
+                    "@deprecated(\"Use between instead\")"
+                ),
+                ("Bit array", "<<1, 2:size(8), \"gleam\":utf8>>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_definition_failing_to_parse_leaves_the_others() {
+        let source = "pub fn one() { 1 }\n\npub fn broken( { 2 }\n\npub fn three() { 3 }\n";
+        let parsed = parse_gleam_recovering("numbers.gleam", source);
+
+        let names: Vec<_> = parsed
+            .module
+            .values
+            .iter()
+            .map(|v| v.name.as_str())
+            .collect();
+        assert_eq!(names, ["one", "three"]);
+        assert_eq!(parsed.errors.len(), 1);
+        let diagnostics = parsed.diagnostics("numbers.gleam", source);
+        assert_eq!(diagnostics[0].location.as_ref().unwrap().start_line, 3);
+
+        // The strict parser still fails on the first error
+        assert!(parse_gleam("numbers.gleam", source).is_err());
+    }
 }
@@ -2,22 +2,35 @@
 //!
 //! This visitor traverses the parsed Gleam AST and converts it to Morphir IR V4
 //! format, producing a Document Tree structure by default.
+//!
+//! Gleam constructs without a Morphir counterpart are translated:
+//! - labelled arguments are passed in the order of the labels declared by
+//!   the module's function or constructor
+//! - `use pattern <- f(x)` passes the rest of its block to `f` as a callback,
+//!   `f(x, fn(pattern) { ... })`
+//! - functions implemented by `@external` get an external body naming the
+//!   first implementation as `module.function`
+//! - bit arrays are left as holes
 
 use crate::frontend::ast::{
-    Access, Expr, Field as AstField, Literal, ModuleIR, Pattern, TypeDef, TypeExpr, ValueDef,
+    Access, Expr, Field as AstField, Literal, ModuleIR, Pattern, Statement, TypeDef, TypeExpr,
+    ValueDef,
 };
 use indexmap::IndexMap;
+use morphir_common::document_tree::{DocumentTree, Format};
 use morphir_common::vfs::Vfs;
 use morphir_core::ir::v4::{
-    Access as MorphirAccess, AccessControlled, ConstructorDefinition, InputTypeEntry,
-    Literal as MorphirLiteral, Pattern as MorphirPattern, TypeDefinition, ValueBody as V4ValueBody,
-    ValueDefinition as V4ValueDefinition,
+    Access as MorphirAccess, AccessControlled, ConstructorDefinition, HoleReason, InputTypeEntry,
+    Literal as MorphirLiteral, ModuleDefinition, Pattern as MorphirPattern, TypeDefinition,
+    ValueBody as V4ValueBody, ValueDefinition as V4ValueDefinition,
 };
 use morphir_core::ir::{Field, Type, TypeAttributes, Value, ValueAttributes};
 use morphir_core::naming::{FQName, ModuleName, Name, PackageName};
 use serde_json;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::io::Result;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 // Type aliases for the new V4 generic types
 type AccessControlledValueDefinition = AccessControlled<V4ValueDefinition>;
@@ -39,6 +52,8 @@ pub struct GleamToMorphirVisitor<V: Vfs> {
     package_name: PackageName,
     module_name: ModuleName,
     layout: DistributionLayout,
+    /// Parameter labels of the visited module's functions and constructors
+    labels: RefCell<HashMap<String, Vec<Option<String>>>>,
 }
 
 impl<V: Vfs> GleamToMorphirVisitor<V> {
@@ -55,133 +70,78 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
             package_name,
             module_name,
             layout: DistributionLayout::VfsMode, // Default to document tree
+            labels: RefCell::default(),
         }
     }
 
     /// Convert ModuleIR to Morphir IR V4 in Document Tree format
     pub fn visit_module_v4(&self, module_ir: &ModuleIR) -> Result<()> {
+        let module = self.module_definition(module_ir)?;
+        self.write_module(&module)
+    }
+
+    /// Write a module already converted by [`module_definition`](Self::module_definition)
+    pub fn write_module(&self, module: &AccessControlled<ModuleDefinition>) -> Result<()> {
         match self.layout {
-            DistributionLayout::VfsMode => self.visit_module_vfs_mode(module_ir),
-            DistributionLayout::Classic => self.visit_module_classic(module_ir),
+            DistributionLayout::VfsMode => self.visit_module_vfs_mode(module),
+            DistributionLayout::Classic => self.visit_module_classic(module),
         }
     }
 
+    /// Write the package's format.json.
+    ///
+    /// Call once before writing the modules of a package rather than per
+    /// module, so modules written concurrently do not race on it.
+    pub fn write_format(&self) -> Result<()> {
+        DocumentTree::new(&self.vfs, &self.output_dir)
+            .write_format(&Format::library(&self.package_name))
+            .map_err(std::io::Error::other)
+    }
+
     /// Build format.json structure in memory without disk I/O
     pub fn build_format_json(&self) -> serde_json::Value {
-        serde_json::json!({
-            "formatVersion": "4.0.0",
-            "distribution": "Library",
-            "packageName": self.package_name.to_string(),
-            "layout": "VfsMode"
-        })
+        serde_json::to_value(Format::library(&self.package_name)).unwrap_or_default()
     }
 
-    /// Visit module and write Document Tree structure
-    fn visit_module_vfs_mode(&self, module_ir: &ModuleIR) -> Result<()> {
-        // Create .morphir-dist/pkg/package-name/module-path/ structure
-        let module_dir = self
-            .output_dir
-            .join(".morphir-dist")
-            .join("pkg")
-            .join(self.package_name.to_string())
-            .join(self.module_name.to_string());
-
-        self.vfs.create_dir_all(&module_dir)?;
-
-        // Write module.json manifest
-        self.write_module_manifest(&module_dir, module_ir)?;
-
-        // Create types/ and values/ directories
-        let types_dir = module_dir.join("types");
-        let values_dir = module_dir.join("values");
-        self.vfs.create_dir_all(&types_dir)?;
-        self.vfs.create_dir_all(&values_dir)?;
-
-        // Write individual type definition files
+    /// Convert ModuleIR to a Morphir IR V4 module definition in memory,
+    /// without touching the Vfs
+    pub fn module_definition(
+        &self,
+        module_ir: &ModuleIR,
+    ) -> Result<AccessControlled<ModuleDefinition>> {
+        *self.labels.borrow_mut() = module_labels(module_ir);
+        let mut types = IndexMap::new();
         for type_def in &module_ir.types {
-            self.write_type_definition(&types_dir, type_def)?;
+            types.insert(type_def.name.clone(), self.convert_type_def(type_def)?);
         }
-
-        // Write individual value definition files
+        let mut values = IndexMap::new();
         for value_def in &module_ir.values {
-            self.write_value_definition(&values_dir, value_def)?;
+            values.insert(value_def.name.clone(), self.convert_value_def(value_def)?);
         }
-
-        // Write format.json at root if it doesn't exist
-        self.ensure_format_json()?;
-
-        Ok(())
-    }
-
-    /// Write module.json manifest
-    fn write_module_manifest(&self, module_dir: &Path, module_ir: &ModuleIR) -> Result<()> {
-        let manifest = serde_json::json!({
-            "module": self.module_name.to_string(),
-            "doc": module_ir.doc,
-            "types": module_ir.types.iter().map(|t| t.name.clone()).collect::<Vec<_>>(),
-            "values": module_ir.values.iter().map(|v| v.name.clone()).collect::<Vec<_>>(),
-        });
-
-        let manifest_path = module_dir.join("module.json");
-        let content = serde_json::to_string_pretty(&manifest)?;
-        self.vfs.write_from_string(&manifest_path, &content)?;
-        Ok(())
-    }
-
-    /// Write individual type definition file
-    fn write_type_definition(&self, types_dir: &Path, type_def: &TypeDef) -> Result<()> {
-        // Convert to Morphir IR type definition
-        let morphir_type_def = self.convert_type_def(type_def)?;
-
-        // Serialize to JSON using V4 format
-        let json = serde_json::to_string_pretty(&morphir_type_def)?;
-
-        // Write to types/{type-name}.type.json
-        let file_path = types_dir.join(format!("{}.type.json", type_def.name));
-        self.vfs.write_from_string(&file_path, &json)?;
-        Ok(())
-    }
-
-    /// Write individual value definition file
-    fn write_value_definition(&self, values_dir: &Path, value_def: &ValueDef) -> Result<()> {
-        // Convert to Morphir IR value definition
-        let morphir_value_def = self.convert_value_def(value_def)?;
-
-        // Serialize to JSON using V4 format
-        let json = serde_json::to_string_pretty(&morphir_value_def)?;
-
-        // Write to values/{value-name}.value.json
-        let file_path = values_dir.join(format!("{}.value.json", value_def.name));
-        self.vfs.write_from_string(&file_path, &json)?;
-        Ok(())
+        Ok(AccessControlled::public(ModuleDefinition {
+            types,
+            values,
+            doc: module_ir.doc.clone(),
+            ids: Default::default(),
+        }))
     }
 
-    /// Ensure format.json exists at output root
-    fn ensure_format_json(&self) -> Result<()> {
-        // output_dir is already .morphir/out/<project>/compile/<language>/
-        let format_path = self.output_dir.join("format.json");
-
-        // Only create if it doesn't exist
-        if !self.vfs.exists(&format_path) {
-            let format_json = serde_json::json!({
-                "formatVersion": "4.0.0",
-                "distribution": "Library",
-                "packageName": self.package_name.to_string(),
-                "layout": "VfsMode"
-            });
-
-            let content = serde_json::to_string_pretty(&format_json)?;
-            self.vfs.write_from_string(&format_path, &content)?;
-        }
-
-        Ok(())
+    /// Visit module and write Document Tree structure
+    fn visit_module_vfs_mode(&self, module: &AccessControlled<ModuleDefinition>) -> Result<()> {
+        let tree = DocumentTree::new(&self.vfs, &self.output_dir);
+        tree.write_module(
+            &self.package_name.to_string(),
+            &self.module_name.to_string(),
+            module,
+        )
+        .map_err(std::io::Error::other)
     }
 
     /// Visit module and return single PackageDefinition (Classic mode)
-    fn visit_module_classic(&self, module_ir: &ModuleIR) -> Result<()> {
+    fn visit_module_classic(&self, module: &AccessControlled<ModuleDefinition>) -> Result<()> {
         // For Classic mode, we could build a PackageDefinition in memory
         // For now, just delegate to VfsMode
-        self.visit_module_vfs_mode(module_ir)
+        self.visit_module_vfs_mode(module)
     }
 
     // ========================================================================
@@ -212,12 +172,17 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
                         let args: Vec<ConstructorArg> = v
                             .fields
                             .iter()
-                            .map(|field_type| {
-                                let morphir_type = self.convert_type_expr(field_type);
-                                ConstructorArg {
-                                    name: Name::from(""), // Gleam doesn't name constructor args
-                                    arg_type: morphir_type,
-                                }
+                            .filter_map(|field| {
+                                // Unlabelled fields become unnamed constructor args
+                                let (name, field_type) = match field {
+                                    AstField::Labelled { label, item } => (label.as_str(), item),
+                                    AstField::Unlabelled { item } => ("", item),
+                                    AstField::Shorthand { .. } => return None,
+                                };
+                                Some(ConstructorArg {
+                                    name: Name::from(name),
+                                    arg_type: self.convert_type_expr(field_type),
+                                })
                             })
                             .collect();
 
@@ -230,14 +195,12 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
 
                 let v4_type_def = TypeDefinition::CustomTypeDefinition {
                     type_params: type_params.clone(),
-                    constructors: AccessControlled {
-                        access: MorphirAccess::Public,
-                        value: constructors,
-                    },
+                    constructors: AccessControlled::public(constructors),
                 };
 
                 Ok(AccessControlledTypeDefinition {
                     access,
+                    doc: None,
                     value: v4_type_def,
                 })
             }
@@ -251,6 +214,7 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
 
                 Ok(AccessControlledTypeDefinition {
                     access,
+                    doc: None,
                     value: v4_type_def,
                 })
             }
@@ -264,25 +228,57 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
             Access::Private => MorphirAccess::Private,
         };
 
-        // Convert input types (from type annotation if present)
-        // V4 uses IndexMap<String, InputTypeEntry>
-        let input_types: IndexMap<String, InputTypeEntry> =
-            if let Some(type_ann) = &value_def.type_annotation {
-                self.extract_input_types_v4(type_ann)
-            } else {
-                IndexMap::new()
-            };
+        // Functions are lambdas over their parameters
+        let (params, body) = match &value_def.body {
+            Expr::Lambda { params, body } => (params.as_slice(), &**body),
+            body => (&[][..], body),
+        };
 
-        // Convert output type
-        let output_type = if let Some(type_ann) = &value_def.type_annotation {
-            self.extract_output_type_type(type_ann)
-        } else {
-            Type::Unit(TypeAttributes::default())
+        // Convert input and output types (from type annotation if present)
+        // V4 uses IndexMap<String, InputTypeEntry>
+        let (input_types, output_type) = match &value_def.type_annotation {
+            None if params.is_empty() => (IndexMap::new(), Type::Unit(TypeAttributes::default())),
+            Some(type_ann) if params.is_empty() => (
+                self.extract_input_types_v4(type_ann),
+                self.extract_output_type_type(type_ann),
+            ),
+            Some(TypeExpr::Function {
+                parameters,
+                return_type,
+            }) if parameters.len() == params.len() => (
+                params
+                    .iter()
+                    .zip(parameters)
+                    .map(|(param, tpe)| (param.clone(), self.input_type(tpe)))
+                    .collect(),
+                self.convert_type_expr(return_type),
+            ),
+            // Untyped parameters get a type variable each
+            annotation => (
+                params
+                    .iter()
+                    .map(|param| {
+                        let tpe = TypeExpr::Variable {
+                            name: param.clone(),
+                        };
+                        (param.clone(), self.input_type(&tpe))
+                    })
+                    .collect(),
+                annotation
+                    .as_ref()
+                    .map(|type_ann| self.convert_type_expr(type_ann))
+                    .unwrap_or_else(|| Type::Unit(TypeAttributes::default())),
+            ),
         };
 
-        // Convert body expression
-        let body_value = self.convert_expr(&value_def.body);
-        let body = V4ValueBody::Expression(body_value);
+        // Convert body expression, or name the first external implementation
+        let body = match value_def.externals.first() {
+            Some(external) => V4ValueBody::External {
+                external_name: format!("{}.{}", external.module, external.function),
+                target_platform: external.target.clone(),
+            },
+            None => V4ValueBody::Expression(self.convert_expr(body)),
+        };
 
         let v4_value_def = V4ValueDefinition {
             input_types,
@@ -292,10 +288,18 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
 
         Ok(AccessControlledValueDefinition {
             access,
+            doc: None,
             value: v4_value_def,
         })
     }
 
+    fn input_type(&self, type_expr: &TypeExpr) -> InputTypeEntry {
+        InputTypeEntry {
+            type_attributes: None,
+            input_type: self.convert_type_expr(type_expr),
+        }
+    }
+
     /// Extract input types from function type annotation (returns V4 IndexMap format)
     fn extract_input_types_v4(&self, type_expr: &TypeExpr) -> IndexMap<String, InputTypeEntry> {
         let mut inputs = IndexMap::new();
@@ -425,7 +429,7 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
             } => {
                 // Convert multiple arguments to curried apply
                 let mut result = self.convert_expr(function);
-                for arg in arguments {
+                for arg in self.positional_arguments(function, arguments) {
                     result = Value::Apply(
                         attrs.clone(),
                         Box::new(result),
@@ -584,18 +588,7 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
                 let _ = tail; // TODO: Handle tail properly
                 Value::List(attrs, morphir_elements)
             }
-            Expr::Block { statements } => {
-                // Convert block to sequence of let bindings
-                // For now, just return the last expression
-                if let Some(last) = statements.last() {
-                    match last {
-                        crate::frontend::ast::Statement::Expression(e) => self.convert_expr(e),
-                        _ => Value::Unit(attrs),
-                    }
-                } else {
-                    Value::Unit(attrs)
-                }
-            }
+            Expr::Block { statements } => self.convert_statements(statements),
             Expr::Panic { message } => {
                 // Convert to panic function call
                 let msg_expr = message
@@ -620,8 +613,8 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
                 self.convert_expr(expression)
             }
             Expr::BitString { .. } => {
-                // Bit strings not yet supported
-                Value::Unit(attrs)
+                // Morphir has no bit arrays
+                Value::Hole(attrs, HoleReason::Draft, None)
             }
             Expr::FnCapture { .. } => {
                 // Function capture not yet supported
@@ -634,6 +627,117 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
         }
     }
 
+    /// Arguments of a call in the order of the parameters they are for
+    ///
+    /// Labelled arguments take the position of their label in the called
+    /// function or constructor; unlabelled ones fill the remaining positions
+    /// in order. Calls of anything else keep the source order.
+    fn positional_arguments<'e>(
+        &self,
+        function: &Expr,
+        arguments: &'e [AstField<Expr>],
+    ) -> Vec<&'e AstField<Expr>> {
+        let labels = self.labels.borrow();
+        let declared = match function {
+            Expr::Variable { name } | Expr::Constructor { name, .. } => labels.get(name),
+            _ => None,
+        };
+        let Some(declared) = declared else {
+            return arguments.iter().collect();
+        };
+
+        let mut positions: Vec<Option<&AstField<Expr>>> = vec![None; declared.len()];
+        let mut unlabelled = Vec::new();
+        for argument in arguments {
+            let label = match argument {
+                AstField::Labelled { label, .. } => Some(label),
+                AstField::Shorthand { name } => Some(name),
+                AstField::Unlabelled { .. } => None,
+            };
+            let position = label.and_then(|label| {
+                declared
+                    .iter()
+                    .position(|declared| declared.as_ref() == Some(label))
+            });
+            match position {
+                Some(position) if positions[position].is_none() => {
+                    positions[position] = Some(argument)
+                }
+                _ => unlabelled.push(argument),
+            }
+        }
+        let mut unlabelled = unlabelled.into_iter();
+        for position in positions.iter_mut().filter(|p| p.is_none()) {
+            *position = unlabelled.next();
+        }
+        positions.into_iter().flatten().chain(unlabelled).collect()
+    }
+
+    /// Convert the statements of a block
+    ///
+    /// Each assignment binds the rest of the block and each `use` passes it
+    /// to its function as a callback; the last expression is the result.
+    fn convert_statements(&self, statements: &[Statement]) -> Value {
+        let attrs = ValueAttributes::default();
+        let Some((first, rest)) = statements.split_first() else {
+            return Value::Unit(attrs);
+        };
+
+        match first {
+            Statement::Expression(expr) if rest.is_empty() => self.convert_expr(expr),
+            // Morphir values have no effects, so only the last expression counts
+            Statement::Expression(_) => self.convert_statements(rest),
+            Statement::Assignment {
+                pattern: Pattern::Variable { name },
+                annotation,
+                value,
+            } => {
+                let def = V4ValueDefinition {
+                    input_types: IndexMap::new(),
+                    output_type: annotation
+                        .as_ref()
+                        .map(|annotation| self.convert_type_expr(annotation))
+                        .unwrap_or_else(|| Type::Unit(TypeAttributes::default())),
+                    body: V4ValueBody::Expression(self.convert_expr(value)),
+                };
+                Value::LetDefinition(
+                    attrs,
+                    Name::from(name.as_str()),
+                    Box::new(def),
+                    Box::new(self.convert_statements(rest)),
+                )
+            }
+            Statement::Assignment { pattern, value, .. } => Value::Destructure(
+                attrs,
+                self.convert_pattern(pattern),
+                Box::new(self.convert_expr(value)),
+                Box::new(self.convert_statements(rest)),
+            ),
+            Statement::Use { patterns, function } => {
+                let mut callback = self.convert_statements(rest);
+                if patterns.is_empty() {
+                    callback = Value::Lambda(
+                        attrs.clone(),
+                        MorphirPattern::UnitPattern(attrs.clone()),
+                        Box::new(callback),
+                    );
+                }
+                for pattern in patterns.iter().rev() {
+                    callback = Value::Lambda(
+                        attrs.clone(),
+                        self.convert_pattern(pattern),
+                        Box::new(callback),
+                    );
+                }
+                Value::Apply(
+                    attrs,
+                    Box::new(self.convert_expr(function)),
+                    Box::new(callback),
+                )
+            }
+        }
+    }
+
     /// Helper to extract pattern from Field<Pattern>
     fn extract_field_pattern(&self, field: &AstField<Pattern>) -> MorphirPattern {
         match field {
@@ -722,6 +826,36 @@ impl<V: Vfs> GleamToMorphirVisitor<V> {
     }
 }
 
+/// Parameter labels of the module's functions and constructors, for those
+/// with a labelled parameter
+fn module_labels(module_ir: &ModuleIR) -> HashMap<String, Vec<Option<String>>> {
+    let mut labels: HashMap<String, Vec<Option<String>>> = module_ir
+        .values
+        .iter()
+        .filter(|value| !value.labels.is_empty())
+        .map(|value| (value.name.clone(), value.labels.clone()))
+        .collect();
+    for type_def in &module_ir.types {
+        let TypeExpr::CustomType { variants } = &type_def.body else {
+            continue;
+        };
+        for variant in variants {
+            let fields: Vec<Option<String>> = variant
+                .fields
+                .iter()
+                .map(|field| match field {
+                    AstField::Labelled { label, .. } => Some(label.clone()),
+                    _ => None,
+                })
+                .collect();
+            if fields.iter().any(Option::is_some) {
+                labels.insert(variant.name.clone(), fields);
+            }
+        }
+    }
+    labels
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -752,6 +886,8 @@ mod tests {
                     },
                 },
                 access: Access::Public,
+                labels: vec![],
+                externals: vec![],
             }],
         };
 
@@ -759,4 +895,124 @@ mod tests {
         let result = visitor.visit_module_v4(&module_ir);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_module_definition_is_built_in_memory() {
+        let source = r#"
+/// A point
+pub type Point {
+    Point(x: Int, y: Int)
+}
+
+pub fn origin() -> Point {
+    Point(0, 0)
+}
+"#;
+        let module_ir = crate::frontend::parse_gleam("point.gleam", source).unwrap();
+        let vfs = MemoryVfs::new();
+        let visitor = GleamToMorphirVisitor::new(
+            vfs.clone(),
+            PathBuf::from("/test"),
+            PackageName::parse("test-package"),
+            ModuleName::parse("point"),
+        );
+
+        let module = visitor.module_definition(&module_ir).unwrap();
+        assert_eq!(module.value.types.keys().collect::<Vec<_>>(), ["Point"]);
+        assert_eq!(module.value.values.keys().collect::<Vec<_>>(), ["origin"]);
+        assert!(!vfs.exists(&PathBuf::from("/test/format.json")));
+
+        // Writing the converted module lays out the same definitions
+        visitor.write_format().unwrap();
+        visitor.write_module(&module).unwrap();
+        let written = vfs
+            .read_to_string(&PathBuf::from(
+                "/test/.morphir-dist/pkg/test-package/point/values/origin.value.json",
+            ))
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&written).unwrap(),
+            serde_json::to_value(&module.value.values["origin"]).unwrap()
+        );
+        assert!(vfs.exists(&PathBuf::from("/test/format.json")));
+    }
+
+    #[test]
+    fn test_use_labels_externals_and_bit_arrays_are_translated() {
+        let source = r#"
+pub type Interval {
+    Interval(start: Int, end: Int)
+}
+
+@external(erlang, "os", "system_time")
+pub fn now() -> Int
+
+pub fn make(from start: Int, to end: Int) -> Interval {
+    Interval(end: end, start:)
+}
+
+pub fn since(start: Int) -> Interval {
+    use end <- with_now()
+    make(to: end, from: start)
+}
+
+pub fn header() {
+    <<1, 2>>
+}
+"#;
+        let module_ir = crate::frontend::parse_gleam("interval.gleam", source).unwrap();
+        let visitor = GleamToMorphirVisitor::new(
+            MemoryVfs::new(),
+            PathBuf::from("/test"),
+            PackageName::parse("test-package"),
+            ModuleName::parse("interval"),
+        );
+        visitor.visit_module_v4(&module_ir).unwrap();
+        let value = |name: &str| {
+            let def = module_ir.values.iter().find(|v| v.name == name).unwrap();
+            visitor.convert_value_def(def).unwrap().value
+        };
+        let variable = |value: &Value, expected: &str| matches!(value, Value::Variable(_, name) if name == &Name::from(expected));
+
+        assert_eq!(
+            value("now").body,
+            V4ValueBody::External {
+                external_name: "os.system_time".to_string(),
+                target_platform: "erlang".to_string(),
+            }
+        );
+
+        // Labelled arguments follow the declared labels
+        let make = value("make");
+        assert_eq!(
+            make.input_types.keys().collect::<Vec<_>>(),
+            ["start", "end"]
+        );
+        let V4ValueBody::Expression(Value::Apply(_, constructor, end)) = &make.body else {
+            panic!("expected a constructor call, got {:?}", make.body);
+        };
+        assert!(variable(end, "end"));
+        assert!(matches!(&**constructor, Value::Apply(_, _, start) if variable(start, "start")));
+
+        // The rest of the block is the callback of `with_now`
+        let since = value("since");
+        let V4ValueBody::Expression(Value::Apply(_, with_now, callback)) = &since.body else {
+            panic!("expected a call of with_now, got {:?}", since.body);
+        };
+        assert!(variable(with_now, "with_now"));
+        let Value::Lambda(_, MorphirPattern::AsPattern(_, _, end), body) = &**callback else {
+            panic!("expected a callback, got {:?}", callback);
+        };
+        assert_eq!(end, &Name::from("end"));
+        let Value::Apply(_, make, end) = &**body else {
+            panic!("expected a call of make, got {:?}", body);
+        };
+        assert!(variable(end, "end"));
+        assert!(matches!(&**make, Value::Apply(_, _, start) if variable(start, "start")));
+
+        assert!(matches!(
+            value("header").body,
+            V4ValueBody::Expression(Value::Hole(_, HoleReason::Draft, None))
+        ));
+    }
 }
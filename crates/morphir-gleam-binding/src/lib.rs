@@ -4,7 +4,14 @@
 //! - Frontend: Parse Gleam source files to Morphir IR
 //! - Backend: Generate Gleam code from Morphir IR
 
+use indexmap::IndexMap;
+use morphir_common::document_tree::DocumentTree;
+use morphir_common::pipeline::parallel;
 use morphir_common::vfs::OsVfs;
+use morphir_core::ir::v4::{
+    AccessControlled, Distribution, FormatVersion, IRFile, LibraryContent, ModuleDefinition,
+    PackageDefinition,
+};
 use morphir_core::naming::{ModuleName, PackageName};
 use morphir_extension_sdk::prelude::*;
 use std::path::PathBuf;
@@ -29,6 +36,8 @@ impl Extension for GleamExtension {
             license: Some("Apache-2.0".into()),
             homepage: Some("https://github.com/finos/morphir-rust".into()),
             min_sdk_version: Some("0.1.0".into()),
+            permissions: Permissions::default(),
+            ..Default::default()
         }
     }
 
@@ -38,6 +47,9 @@ impl Extension for GleamExtension {
             incremental: false,
             cancellation: false,
             progress: false,
+            fixes: false,
+            feeds: Vec::new(),
+            identifiers: Some(backend::identifier_rules()),
             extra: Default::default(),
         }
     }
@@ -47,16 +59,16 @@ impl Frontend for GleamExtension {
     fn compile(&self, request: CompileRequest) -> Result<CompileResult> {
         host_info!("Compiling {} Gleam source file(s)", request.sources.len());
 
-        let mut ir_modules = Vec::new();
+        let mut modules = IndexMap::new();
         let mut diagnostics = Vec::new();
 
-        // Determine output directory (from options or default)
+        // The IR is built in memory; the document tree is written only when
+        // an output directory is requested
         let output_dir = request
             .options
             .get("outputDir")
             .and_then(|v| v.as_str())
-            .map(PathBuf::from)
-            .unwrap_or_else(|| PathBuf::from("."));
+            .map(PathBuf::from);
 
         // Check if we should emit parse stage output (default: true)
         let emit_parse_stage = request
@@ -80,79 +92,50 @@ impl Frontend for GleamExtension {
             .map(PackageName::parse)
             .unwrap_or_else(|| PackageName::parse("default-package"));
 
-        for source in &request.sources {
-            match frontend::parse_gleam(&source.path, &source.content) {
-                Ok(module_ir) => {
-                    // Emit parse stage JSON if enabled
-                    if emit_parse_stage
-                        && let Err(e) = emit_parse_stage_json(&output_dir, &source.path, &module_ir)
-                    {
-                        if emit_parse_stage_fatal {
-                            // Fatal error: propagate the failure
-                            return Err(ExtensionError::ExecutionFailed(format!(
-                                "Failed to emit parse stage output: {}",
-                                e
-                            )));
-                        } else {
-                            // Non-fatal: log as warning and continue
-                            diagnostics.push(Diagnostic {
-                                severity: DiagnosticSeverity::Warning,
-                                code: Some("W001".into()),
-                                message: format!("Failed to emit parse stage output: {}", e),
-                                location: None,
-                                related: vec![],
-                            });
-                        }
-                    }
-                    // Extract module name from path
-                    let module_name = ModuleName::parse(
-                        &source.path.trim_end_matches(".gleam").replace('\\', "/"),
-                    );
-
-                    // Convert to Morphir IR V4 Document Tree format
-                    let visitor = frontend::GleamToMorphirVisitor::new(
-                        OsVfs,
-                        output_dir.clone(),
-                        package_name.clone(),
-                        module_name,
-                    );
-
-                    match visitor.visit_module_v4(&module_ir) {
-                        Ok(_) => {
-                            // Build format.json in memory without disk I/O
-                            // This avoids reading the entire file back after writing it,
-                            // which could cause memory issues for large projects
-                            let ir_json = visitor.build_format_json();
-                            ir_modules.push(ir_json);
-                        }
-                        Err(e) => {
-                            diagnostics.push(Diagnostic {
-                                severity: DiagnosticSeverity::Error,
-                                code: Some("E004".into()),
-                                message: format!("Failed to convert to Morphir IR: {}", e),
-                                location: None,
-                                related: vec![],
-                            });
-                        }
-                    }
-                }
-                Err(e) => {
-                    diagnostics.push(e.to_diagnostic(&source.path, &source.content));
-                }
-            }
+        // Sources are compiled independently, on as many threads as the
+        // `jobs` option allows; results are merged in source order
+        let jobs = parallel::jobs_option(&request.options);
+        let outcomes = parallel::map_parallel(&request.sources, jobs, |source| {
+            compile_source(
+                source,
+                output_dir.as_deref(),
+                &package_name,
+                emit_parse_stage,
+                emit_parse_stage_fatal,
+            )
+        });
+        for outcome in outcomes {
+            let outcome = outcome?;
+            modules.extend(outcome.module);
+            diagnostics.extend(outcome.diagnostics);
         }
 
+        let ir = if modules.is_empty() {
+            None
+        } else {
+            let ir_file = library(package_name, modules);
+            if let Some(output_dir) = &output_dir
+                && let Err(e) = DocumentTree::new(&OsVfs, output_dir).write(&ir_file)
+            {
+                diagnostics.push(Diagnostic {
+                    severity: DiagnosticSeverity::Error,
+                    code: Some("E005".into()),
+                    message: format!("Failed to write Morphir IR: {:#}", e),
+                    location: None,
+                    related: vec![],
+                    fixes: vec![],
+                });
+            }
+            Some(serde_json::to_value(&ir_file)?)
+        };
+
         let success = diagnostics
             .iter()
             .all(|d| d.severity != DiagnosticSeverity::Error);
 
         Ok(CompileResult {
             success,
-            ir: if ir_modules.is_empty() {
-                None
-            } else {
-                Some(serde_json::to_value(&ir_modules)?)
-            },
+            ir,
             diagnostics,
         })
     }
@@ -170,21 +153,24 @@ impl Backend for GleamExtension {
     fn generate(&self, request: GenerateRequest) -> Result<GenerateResult> {
         host_info!("Generating Gleam code from IR");
 
-        match backend::generate_gleam(&request.ir, &request.options) {
-            Ok(artifacts) => Ok(GenerateResult {
+        match backend::generate_gleam_with_source_map(&request.ir, &request.options) {
+            Ok((artifacts, source_map)) => Ok(GenerateResult {
                 success: true,
                 artifacts,
                 diagnostics: vec![],
+                source_map,
             }),
             Err(e) => Ok(GenerateResult {
                 success: false,
                 artifacts: vec![],
+                source_map: vec![],
                 diagnostics: vec![Diagnostic {
                     severity: DiagnosticSeverity::Error,
                     code: Some("G001".into()),
                     message: e.to_string() as String,
                     location: None,
                     related: vec![],
+                    fixes: vec![],
                 }],
             }),
         }
@@ -195,6 +181,100 @@ impl Backend for GleamExtension {
     }
 }
 
+/// Library distribution of the compiled modules
+fn library(
+    package_name: PackageName,
+    modules: IndexMap<String, AccessControlled<ModuleDefinition>>,
+) -> IRFile {
+    IRFile {
+        format_version: FormatVersion::default(),
+        distribution: Distribution::Library(LibraryContent {
+            package_name,
+            dependencies: IndexMap::new(),
+            def: PackageDefinition { modules },
+        }),
+    }
+}
+
+/// Module and diagnostics of one compiled source
+struct SourceOutcome {
+    module: Option<(String, AccessControlled<ModuleDefinition>)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Compile one Gleam source to a V4 module, writing its parse stage under
+/// `output_dir` when one is given.
+///
+/// Fails only when emitting the parse stage fails and that is fatal.
+fn compile_source(
+    source: &SourceFile,
+    output_dir: Option<&std::path::Path>,
+    package_name: &PackageName,
+    emit_parse_stage: bool,
+    emit_parse_stage_fatal: bool,
+) -> Result<SourceOutcome> {
+    let mut outcome = SourceOutcome {
+        module: None,
+        diagnostics: Vec::new(),
+    };
+    // Definitions that fail to parse are reported and left out of the module
+    let parsed = frontend::parse_gleam_recovering(&source.path, &source.content);
+    outcome
+        .diagnostics
+        .extend(parsed.diagnostics(&source.path, &source.content));
+    let module_ir = parsed.module;
+    if !parsed.errors.is_empty() && module_ir.types.is_empty() && module_ir.values.is_empty() {
+        return Ok(outcome);
+    }
+
+    // Emit parse stage JSON if enabled
+    if let Some(output_dir) = output_dir.filter(|_| emit_parse_stage)
+        && let Err(e) = emit_parse_stage_json(output_dir, &source.path, &module_ir)
+    {
+        if emit_parse_stage_fatal {
+            // Fatal error: propagate the failure
+            return Err(ExtensionError::ExecutionFailed(format!(
+                "Failed to emit parse stage output: {}",
+                e
+            )));
+        }
+        // Non-fatal: log as warning and continue
+        outcome.diagnostics.push(Diagnostic {
+            severity: DiagnosticSeverity::Warning,
+            code: Some("W001".into()),
+            message: format!("Failed to emit parse stage output: {}", e),
+            location: None,
+            related: vec![],
+            fixes: vec![],
+        });
+    }
+
+    // Extract module name from path
+    let module_name = ModuleName::parse(frontend::module_path(&source.path).as_str());
+
+    // Convert to Morphir IR V4 in memory; the Document Tree is written once
+    // every module is converted
+    let visitor = frontend::GleamToMorphirVisitor::new(
+        OsVfs,
+        PathBuf::new(),
+        package_name.clone(),
+        module_name.clone(),
+    );
+
+    match visitor.module_definition(&module_ir) {
+        Ok(module) => outcome.module = Some((module_name.to_string(), module)),
+        Err(e) => outcome.diagnostics.push(Diagnostic {
+            severity: DiagnosticSeverity::Error,
+            code: Some("E004".into()),
+            message: format!("Failed to convert to Morphir IR: {}", e),
+            location: None,
+            related: vec![],
+            fixes: vec![],
+        }),
+    }
+    Ok(outcome)
+}
+
 /// Emit parse stage output as JSON to the output directory
 ///
 /// Writes the parsed ModuleIR to `.morphir/out/<project>/parse/<module>.json`
@@ -222,9 +302,10 @@ fn emit_parse_stage_json(
     fs::create_dir_all(&parse_dir)?;
 
     // Derive module filename from source path
-    let module_name = source_path
-        .trim_end_matches(".gleam")
-        .replace(['/', '\\'], "_");
+    let module_name = frontend::module_path(source_path)
+        .segments()
+        .collect::<Vec<_>>()
+        .join("_");
     let output_file = parse_dir.join(format!("{}.json", module_name));
 
     // Write ModuleIR as pretty-printed JSON
@@ -236,4 +317,4 @@ fn emit_parse_stage_json(
 }
 
 // Export the extension
-morphir_extension_sdk::export_extension!(GleamExtension);
+morphir_extension_sdk::export_extension!(GleamExtension: Frontend, Backend);
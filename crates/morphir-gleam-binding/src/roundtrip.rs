@@ -7,12 +7,8 @@ use crate::backend::MorphirToGleamVisitor;
 use crate::frontend::ast::ModuleIR;
 use crate::frontend::{GleamToMorphirVisitor, parse_gleam};
 use morphir_common::vfs::{MemoryVfs, Vfs};
-use morphir_core::ir::v4::{AccessControlled, ModuleDefinition};
 use morphir_core::naming::{ModuleName, PackageName};
-use std::path::{Path, PathBuf};
-
-// Type alias for the new V4 generic type
-type AccessControlledModuleDefinition = AccessControlled<ModuleDefinition>;
+use std::path::PathBuf;
 
 /// Result of a roundtrip operation
 #[derive(Debug)]
@@ -58,7 +54,7 @@ impl std::error::Error for RoundtripError {}
 ///
 /// This function:
 /// 1. Parses the original Gleam source to a ModuleIR
-/// 2. Converts the ModuleIR to IR V4 Document Tree format
+/// 2. Converts the ModuleIR to an IR V4 module definition in memory
 /// 3. Converts the IR V4 back to Gleam source code
 /// 4. Re-parses the generated Gleam source to a new ModuleIR
 /// 5. Returns both ModuleIRs and the generated code for comparison
@@ -76,38 +72,22 @@ pub fn roundtrip_gleam_with_options(
     let original = parse_gleam("input.gleam", source)
         .map_err(|e| RoundtripError::ParseError(format!("{:?}", e)))?;
 
-    // Step 2: Convert to IR V4 using in-memory VFS
-    let ir_vfs = MemoryVfs::new();
-    let output_dir = PathBuf::from("/ir");
+    // Step 2: Convert to IR V4 in memory
     let pkg_name = PackageName::parse(package_name);
     let mod_name = ModuleName::parse(module_name);
 
     let frontend_visitor = GleamToMorphirVisitor::new(
-        ir_vfs.clone(),
-        output_dir.clone(),
-        pkg_name.clone(),
+        MemoryVfs::new(),
+        PathBuf::from("/ir"),
+        pkg_name,
         mod_name.clone(),
     );
 
-    frontend_visitor
-        .visit_module_v4(&original)
-        .map_err(|e| RoundtripError::IrConversionError(e.to_string()))?;
-
-    // Read the format.json if it exists (for debugging)
-    let format_json_path = output_dir.join("format.json");
-    let intermediate_ir: Option<serde_json::Value> = if ir_vfs.exists(&format_json_path) {
-        ir_vfs
-            .read_to_string(&format_json_path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-    } else {
-        None
-    };
-
-    // Step 3: Build V4 module definition from the written files
-    // For now, we'll create a simple module from the original ModuleIR
-    let module_def = build_v4_module_from_ir(&original, &ir_vfs, &output_dir, &pkg_name, &mod_name)
+    // Step 3: Keep the V4 module definition (and its JSON for debugging)
+    let module_def = frontend_visitor
+        .module_definition(&original)
         .map_err(|e| RoundtripError::IrConversionError(e.to_string()))?;
+    let intermediate_ir = serde_json::to_value(&module_def).ok();
 
     // Step 4: Generate Gleam code from IR V4 using backend visitor
     let gen_vfs = MemoryVfs::new();
@@ -141,83 +121,10 @@ pub fn roundtrip_gleam_with_options(
     })
 }
 
-/// Build a V4 AccessControlledModuleDefinition from the original ModuleIR
-fn build_v4_module_from_ir(
-    module_ir: &ModuleIR,
-    vfs: &MemoryVfs,
-    output_dir: &Path,
-    package_name: &PackageName,
-    module_name: &ModuleName,
-) -> std::io::Result<AccessControlledModuleDefinition> {
-    use indexmap::IndexMap;
-    use morphir_core::ir::v4::{Access, AccessControlled, TypeDefinition, ValueDefinition};
-
-    // Try to read the written V4 files and reconstruct the module definition
-    let module_dir = output_dir
-        .join(".morphir-dist")
-        .join("pkg")
-        .join(package_name.to_string())
-        .join(module_name.to_string());
-
-    let mut types: IndexMap<String, AccessControlled<TypeDefinition>> = IndexMap::new();
-    let mut values: IndexMap<String, AccessControlled<ValueDefinition>> = IndexMap::new();
-
-    // Read type definitions - frontend writes .type.json extension
-    // Note: MemoryVfs doesn't track directories, so we directly check file existence
-    let types_dir = module_dir.join("types");
-    for type_def in &module_ir.types {
-        let type_file = types_dir.join(format!("{}.type.json", type_def.name));
-        if vfs.exists(&type_file)
-            && let Ok(content) = vfs.read_to_string(&type_file)
-        {
-            match serde_json::from_str::<AccessControlled<TypeDefinition>>(&content) {
-                Ok(type_def_v4) => {
-                    types.insert(type_def.name.clone(), type_def_v4);
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Warning: Failed to deserialize type '{}': {}",
-                        type_def.name, e
-                    );
-                }
-            }
-        }
-    }
-
-    // Read value definitions - frontend writes .value.json extension
-    let values_dir = module_dir.join("values");
-    for value_def in &module_ir.values {
-        let value_file = values_dir.join(format!("{}.value.json", value_def.name));
-        if vfs.exists(&value_file)
-            && let Ok(content) = vfs.read_to_string(&value_file)
-        {
-            match serde_json::from_str::<AccessControlled<ValueDefinition>>(&content) {
-                Ok(value_def_v4) => {
-                    values.insert(value_def.name.clone(), value_def_v4);
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Warning: Failed to deserialize value '{}': {}",
-                        value_def.name, e
-                    );
-                }
-            }
-        }
-    }
-
-    Ok(AccessControlled {
-        access: Access::Public,
-        value: ModuleDefinition {
-            types,
-            values,
-            doc: module_ir.doc.clone(),
-        },
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::Path;
 
     #[test]
     fn test_roundtrip_debug_vfs() {
@@ -240,6 +147,7 @@ mod tests {
             mod_name.clone(),
         );
 
+        frontend_visitor.write_format().unwrap();
         frontend_visitor.visit_module_v4(&original).unwrap();
 
         // Debug: list all files in VFS
@@ -0,0 +1,323 @@
+//! Names in documents and the definitions they refer to
+//!
+//! The name under the cursor is resolved the way the compiler would: a
+//! qualified name (`money.total` in Gleam, `Orders.Money.total` in Elm)
+//! through the import it names, an unqualified one through the module itself
+//! and then its imports. Modules are looked up in the module index of every
+//! project in the workspace, starting with the document's own project.
+
+use std::path::{Path, PathBuf};
+
+use morphir_core::naming::{FQName, Name, Path as NamePath};
+use morphir_daemon::workspace::index::{IndexedFile, Symbol};
+use morphir_daemon::workspace::{Project, Workspace};
+
+use crate::protocol::{Position, Range};
+
+/// A possibly qualified name in a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameAt {
+    /// Module qualifier, e.g. `money` in `money.total`
+    pub qualifier: Option<String>,
+    pub name: String,
+    /// Range of the name, without its qualifier
+    pub range: Range,
+}
+
+/// A definition a name resolved to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// Project declaring the definition
+    pub project: String,
+    /// Module declaring the definition, as the index names it
+    pub module: String,
+    /// Absolute path of the file declaring the definition
+    pub path: PathBuf,
+    pub symbol: Symbol,
+}
+
+impl Definition {
+    /// Fully qualified name of the definition
+    pub fn fqname(&self) -> FQName {
+        FQName::new(
+            NamePath::new(&self.project),
+            NamePath::new(&self.module.replace('.', "/")),
+            Name::from(&self.symbol.name),
+        )
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Byte offset in `line` of a column counted in UTF-16 code units
+fn byte_offset(line: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (offset, c) in line.char_indices() {
+        if units >= character as usize {
+            return offset;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
+
+/// Column in UTF-16 code units of a byte offset in `line`
+pub fn utf16_column(line: &str, offset: usize) -> u32 {
+    line[..offset].chars().map(char::len_utf16).sum::<usize>() as u32
+}
+
+/// The name at `position` of `text`, with its qualifier
+pub fn name_at(text: &str, position: Position) -> Option<NameAt> {
+    let line = text.lines().nth(position.line as usize)?;
+    let offset = byte_offset(line, position.character);
+    let start = line[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_identifier_char(*c))
+        .last()
+        .map_or(offset, |(i, _)| i);
+    let end = line[offset..]
+        .find(|c: char| !is_identifier_char(c))
+        .map_or(line.len(), |i| offset + i);
+    if start == end {
+        return None;
+    }
+
+    let qualifier = line[..start].strip_suffix('.').and_then(|before| {
+        let qualifier_start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_identifier_char(*c) || *c == '.')
+            .last()
+            .map_or(before.len(), |(i, _)| i);
+        let qualifier = before[qualifier_start..].trim_matches('.');
+        (!qualifier.is_empty()).then(|| qualifier.to_string())
+    });
+    Some(NameAt {
+        qualifier,
+        name: line[start..end].to_string(),
+        range: Range::new(
+            Position::new(position.line, utf16_column(line, start)),
+            Position::new(position.line, utf16_column(line, end)),
+        ),
+    })
+}
+
+/// The project containing `path` and the index entry of the file, keyed by
+/// its path relative to the project
+pub fn locate<'a>(workspace: &'a Workspace, path: &Path) -> Option<(&'a Project, &'a IndexedFile)> {
+    let path = normalize(path);
+    workspace.projects.values().find_map(|project| {
+        let relative = path.strip_prefix(normalize(&project.path)).ok()?;
+        let key = relative.to_string_lossy().replace('\\', "/");
+        let file = workspace.indexes.get(&project.name)?.files.get(&key)?;
+        Some((project, file))
+    })
+}
+
+/// Resolve `name` as used in the file at `path`
+pub fn resolve(workspace: &Workspace, path: &Path, name: &NameAt) -> Option<Definition> {
+    let (project, file) = locate(workspace, path)?;
+    let modules: Vec<&str> = match &name.qualifier {
+        Some(qualifier) => {
+            let mut modules: Vec<&str> = file
+                .imports
+                .iter()
+                .map(String::as_str)
+                .filter(|import| names_module(qualifier, import))
+                .collect();
+            modules.push(qualifier);
+            modules
+        }
+        None => std::iter::once(file.module.as_str())
+            .chain(file.imports.iter().map(String::as_str))
+            .collect(),
+    };
+
+    // The document's own project first, then the others by name
+    let mut projects: Vec<&Project> = workspace.projects.values().collect();
+    projects.sort_by_key(|candidate| (candidate.name != project.name, candidate.name.as_str()));
+    modules.into_iter().find_map(|module| {
+        projects.iter().find_map(|candidate| {
+            let index = workspace.indexes.get(&candidate.name)?;
+            index
+                .files
+                .iter()
+                .filter(|(_, file)| file.module == module)
+                .find_map(|(key, file)| {
+                    let symbol = file
+                        .symbols
+                        .iter()
+                        .find(|symbol| symbol.name == name.name)?;
+                    Some(Definition {
+                        project: candidate.name.clone(),
+                        module: file.module.clone(),
+                        path: candidate.path.join(key),
+                        symbol: symbol.clone(),
+                    })
+                })
+        })
+    })
+}
+
+/// Whether `qualifier` names the imported `module`, in full or by its last
+/// segment
+fn names_module(qualifier: &str, module: &str) -> bool {
+    module == qualifier || module.rsplit(['/', '.']).next() == Some(qualifier)
+}
+
+/// Range of `name` on line `line` (from 1) of `text`, or the start of the line
+/// when the name is not found there
+pub fn symbol_range(text: &str, line: usize, name: &str) -> Range {
+    let number = line.saturating_sub(1) as u32;
+    let Some(content) = text.lines().nth(line.saturating_sub(1)) else {
+        return Range::new(Position::new(number, 0), Position::new(number, 0));
+    };
+    let start = content
+        .match_indices(name)
+        .map(|(i, _)| i)
+        .find(|&i| {
+            let before = content[..i].chars().next_back();
+            let after = content[i + name.len()..].chars().next();
+            !before.is_some_and(is_identifier_char) && !after.is_some_and(is_identifier_char)
+        })
+        .unwrap_or(0);
+    let end = if start == 0 && !content.starts_with(name) {
+        0
+    } else {
+        start + name.len()
+    };
+    Range::new(
+        Position::new(number, utf16_column(content, start)),
+        Position::new(number, utf16_column(content, end)),
+    )
+}
+
+/// `path` with symbolic links and `..` resolved, when it exists
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_at_finds_qualified_names() {
+        let text =
+            "import orders/money\n\npub fn total(x) {\n  money.add(x, Orders.Money.zero)\n}\n";
+        let name = name_at(text, Position::new(3, 10)).unwrap();
+        assert_eq!(name.qualifier.as_deref(), Some("money"));
+        assert_eq!(name.name, "add");
+        assert_eq!(
+            name.range,
+            Range::new(Position::new(3, 8), Position::new(3, 11))
+        );
+
+        let name = name_at(text, Position::new(3, 29)).unwrap();
+        assert_eq!(name.qualifier.as_deref(), Some("Orders.Money"));
+        assert_eq!(name.name, "zero");
+
+        let name = name_at(text, Position::new(2, 9)).unwrap();
+        assert_eq!(name.qualifier, None);
+        assert_eq!(name.name, "total");
+
+        assert_eq!(name_at(text, Position::new(1, 0)), None);
+        assert_eq!(name_at(text, Position::new(9, 0)), None);
+
+        // Columns count UTF-16 code units
+        let name = name_at("let é = 😀 + price", Position::new(0, 14)).unwrap();
+        assert_eq!(name.name, "price");
+        assert_eq!(name.range.start, Position::new(0, 13));
+    }
+
+    #[test]
+    fn test_resolve_follows_imports_across_projects() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for (dir, files) in [
+            (
+                "core",
+                vec![(
+                    "money.gleam",
+                    "pub type Money {\n  Money(Int)\n}\n\npub fn add(a, b) { a }\n",
+                )],
+            ),
+            (
+                "orders",
+                vec![(
+                    "orders/order.gleam",
+                    "import money.{type Money}\n\npub fn total(x) {\n  money.add(x, x)\n}\n",
+                )],
+            ),
+        ] {
+            let project = root.join("packages").join(dir);
+            std::fs::write(
+                {
+                    std::fs::create_dir_all(&project).unwrap();
+                    project.join("morphir.toml")
+                },
+                format!(
+                    "[project]\nname = \"my-org/{}\"\nversion = \"1.0.0\"\nsource_directory = \"src\"\n\n[frontend]\nlanguage = \"gleam\"\n",
+                    dir
+                ),
+            )
+            .unwrap();
+            for (path, content) in files {
+                let path = project.join("src").join(path);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(path, content).unwrap();
+            }
+        }
+        std::fs::write(
+            root.join("morphir.toml"),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        )
+        .unwrap();
+        let mut workspace = Workspace::open(root.to_path_buf()).unwrap();
+        workspace.refresh_indexes().unwrap();
+
+        let order = root.join("packages/orders/src/orders/order.gleam");
+        let text = std::fs::read_to_string(&order).unwrap();
+        let add = resolve(
+            &workspace,
+            &order,
+            &name_at(&text, Position::new(3, 9)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(add.project, "my-org/core");
+        assert_eq!(add.module, "money");
+        assert_eq!(add.symbol.line, 5);
+        assert_eq!(
+            normalize(&add.path),
+            normalize(&root.join("packages/core/src/money.gleam"))
+        );
+        assert_eq!(add.fqname().to_canonical_string(), "my-org/core:money#add");
+
+        let money = resolve(
+            &workspace,
+            &order,
+            &name_at(&text, Position::new(0, 20)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(money.symbol.name, "Money");
+
+        let total = resolve(
+            &workspace,
+            &order,
+            &name_at(&text, Position::new(2, 8)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(total.module, "orders/order");
+
+        let unknown = name_at("missing", Position::new(0, 0)).unwrap();
+        assert_eq!(resolve(&workspace, &order, &unknown), None);
+
+        assert_eq!(
+            symbol_range(&text, 3, "total"),
+            Range::new(Position::new(2, 7), Position::new(2, 12))
+        );
+    }
+}
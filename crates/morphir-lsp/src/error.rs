@@ -0,0 +1,27 @@
+//! Error types for the language server
+
+use morphir_daemon::DaemonError;
+use thiserror::Error;
+
+/// Result type for language server operations
+pub type Result<T> = std::result::Result<T, LspError>;
+
+/// Errors that can occur while serving a client
+#[derive(Debug, Error)]
+pub enum LspError {
+    /// Malformed message framing or content
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    /// Errors from the workspace and builds
+    #[error(transparent)]
+    Daemon(#[from] DaemonError),
+
+    /// IO errors
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// JSON serialization errors
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
@@ -0,0 +1,25 @@
+//! Morphir LSP - Language Server Protocol support for Morphir workspaces
+//!
+//! The language server sits on top of the daemon's workspace: projects are
+//! compiled by the same [`ProjectBuilder`] the daemon uses, and symbols come
+//! from the daemon's module index. It provides:
+//! - Diagnostics from frontend compiles, published when a document is saved
+//! - Hover showing the Morphir type of a definition, from the compiled IR
+//! - Go-to-definition across modules and projects, by resolving the name
+//!   under the cursor to an FQName through the module's imports
+//! - Document symbols from the module's top-level definitions
+//!
+//! Messages use the LSP base protocol, JSON-RPC 2.0 framed with a
+//! `Content-Length` header, over stdin and stdout.
+//!
+//! [`ProjectBuilder`]: morphir_daemon::workspace::ProjectBuilder
+
+pub mod document;
+pub mod error;
+pub mod protocol;
+pub mod render;
+pub mod server;
+pub mod transport;
+
+pub use error::{LspError, Result};
+pub use server::LspServer;
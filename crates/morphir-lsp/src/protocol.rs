@@ -0,0 +1,324 @@
+//! The parts of the Language Server Protocol the server speaks
+//!
+//! Positions are zero-based. Columns count UTF-16 code units, as the
+//! protocol requires by default.
+
+use std::path::{Path, PathBuf};
+
+use morphir_extension_sdk::types::{Diagnostic as BuildDiagnostic, DiagnosticSeverity};
+use serde::{Deserialize, Serialize};
+
+/// Request and notification methods
+pub mod methods {
+    pub const INITIALIZE: &str = "initialize";
+    pub const INITIALIZED: &str = "initialized";
+    pub const SHUTDOWN: &str = "shutdown";
+    pub const EXIT: &str = "exit";
+    pub const DID_OPEN: &str = "textDocument/didOpen";
+    pub const DID_CHANGE: &str = "textDocument/didChange";
+    pub const DID_SAVE: &str = "textDocument/didSave";
+    pub const DID_CLOSE: &str = "textDocument/didClose";
+    pub const HOVER: &str = "textDocument/hover";
+    pub const DEFINITION: &str = "textDocument/definition";
+    pub const DOCUMENT_SYMBOL: &str = "textDocument/documentSymbol";
+    pub const PUBLISH_DIAGNOSTICS: &str = "textDocument/publishDiagnostics";
+}
+
+/// JSON-RPC and LSP error codes
+pub mod error_codes {
+    pub const INVALID_PARAMS: i32 = -32602;
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    pub const INVALID_REQUEST: i32 = -32600;
+    /// A request other than `initialize` arrived before it
+    pub const SERVER_NOT_INITIALIZED: i32 = -32002;
+}
+
+/// Position in a document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+impl Position {
+    pub fn new(line: u32, character: u32) -> Self {
+        Self { line, character }
+    }
+}
+
+/// Range in a document, end exclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+}
+
+/// Location in a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub uri: String,
+    pub range: Range,
+}
+
+/// Document identified by URI
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextDocumentIdentifier {
+    pub uri: String,
+}
+
+/// Params of requests about a position in a document
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentPositionParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+}
+
+/// Document opened by the client
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentItem {
+    pub uri: String,
+    #[serde(default)]
+    pub language_id: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidOpenParams {
+    pub text_document: TextDocumentItem,
+}
+
+/// A change to a document; the server syncs full documents, so only the text
+/// matters
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentChange {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidChangeParams {
+    pub text_document: TextDocumentIdentifier,
+    pub content_changes: Vec<ContentChange>,
+}
+
+/// Params of `didSave`, `didClose`, and `documentSymbol`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+/// Params of `initialize`; only where the workspace is
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeParams {
+    pub root_uri: Option<String>,
+    pub root_path: Option<String>,
+    pub workspace_folders: Option<Vec<WorkspaceFolder>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkspaceFolder {
+    pub uri: String,
+}
+
+impl InitializeParams {
+    /// Root of the workspace the client opened, if any
+    pub fn root(&self) -> Option<PathBuf> {
+        self.workspace_folders
+            .iter()
+            .flatten()
+            .map(|folder| folder.uri.as_str())
+            .chain(self.root_uri.as_deref())
+            .find_map(uri_to_path)
+            .or_else(|| self.root_path.as_ref().map(PathBuf::from))
+    }
+}
+
+/// Markdown shown on hover
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hover {
+    pub contents: MarkupContent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<Range>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MarkupContent {
+    pub kind: &'static str,
+    pub value: String,
+}
+
+impl MarkupContent {
+    pub fn markdown(value: impl Into<String>) -> Self {
+        Self {
+            kind: "markdown",
+            value: value.into(),
+        }
+    }
+}
+
+/// LSP `SymbolKind` values for the kinds of Morphir definitions
+pub mod symbol_kinds {
+    pub const FUNCTION: u32 = 12;
+    pub const CONSTANT: u32 = 14;
+    pub const STRUCT: u32 = 23;
+}
+
+/// A top-level definition of a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbol {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub kind: u32,
+    pub range: Range,
+    pub selection_range: Range,
+}
+
+/// A diagnostic as the protocol carries it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub source: &'static str,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Convert a build diagnostic; one without a location is placed at the
+    /// start of the document
+    pub fn from_build(diagnostic: &BuildDiagnostic) -> Self {
+        let range = diagnostic
+            .location
+            .as_ref()
+            .map(|location| {
+                let start = Position::new(
+                    location.start_line.saturating_sub(1),
+                    location.start_col.saturating_sub(1),
+                );
+                let end = if location.end_line == 0 {
+                    start
+                } else {
+                    Position::new(
+                        location.end_line.saturating_sub(1),
+                        location.end_col.saturating_sub(1),
+                    )
+                };
+                Range::new(start, end)
+            })
+            .unwrap_or_default();
+        Self {
+            range,
+            severity: match diagnostic.severity {
+                DiagnosticSeverity::Error => 1,
+                DiagnosticSeverity::Warning => 2,
+                DiagnosticSeverity::Info => 3,
+                DiagnosticSeverity::Hint => 4,
+            },
+            code: diagnostic.code.clone(),
+            source: "morphir",
+            message: diagnostic.message.clone(),
+        }
+    }
+}
+
+/// Path of a `file:` URI
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // Drop the host; only local files are served
+    let path = &path[path.find('/')?..];
+    let decoded = percent_decode(path)?;
+    // `/C:/work` on Windows
+    let bytes = decoded.as_bytes();
+    if cfg!(windows) && bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        return Some(PathBuf::from(&decoded[1..]));
+    }
+    Some(PathBuf::from(decoded))
+}
+
+/// `file:` URI of an absolute path
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_extension_sdk::types::SourceLocation;
+
+    #[test]
+    fn test_uris_round_trip_and_diagnostics_are_zero_based() {
+        let path = Path::new("/work/my project/src/Orders.elm");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///work/my%20project/src/Orders.elm");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+        assert_eq!(
+            uri_to_path("file://localhost/work/a.gleam"),
+            Some(PathBuf::from("/work/a.gleam"))
+        );
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+
+        let diagnostic = Diagnostic::from_build(&BuildDiagnostic {
+            severity: DiagnosticSeverity::Warning,
+            code: Some("W001".to_string()),
+            message: "Unused import".to_string(),
+            location: Some(SourceLocation {
+                file: "src/a.gleam".to_string(),
+                start_line: 2,
+                start_col: 1,
+                end_line: 0,
+                end_col: 0,
+            }),
+            related: Vec::new(),
+            fixes: Vec::new(),
+        });
+        assert_eq!(diagnostic.severity, 2);
+        assert_eq!(
+            diagnostic.range,
+            Range::new(Position::new(1, 0), Position::new(1, 0))
+        );
+    }
+}
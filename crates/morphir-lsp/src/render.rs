@@ -0,0 +1,343 @@
+//! Morphir types as shown on hover
+//!
+//! Hover reads the definition from the IR the project's frontend last wrote,
+//! `<output>/.morphir-dist/pkg/<package>/<module>/values/<name>.value.json`
+//! and `types/<name>.type.json`, and renders its type in Morphir's Elm-like
+//! syntax: `total : List Int -> Int`.
+
+use std::path::{Path, PathBuf};
+
+use morphir_core::ir::v4::{
+    AccessControlled, AccessControlledTypeDefinition, LegacyTypeDefinition, ModuleName, Name, Type,
+    ValueDefinition,
+};
+
+/// Render `tpe`, e.g. `List Int -> { total : Int }`
+pub fn render_type(tpe: &Type) -> String {
+    match tpe {
+        Type::Variable(_, name) => name.to_camel_case(),
+        Type::Reference(_, fqname, args) => {
+            let mut rendered = fqname.local_name.to_title_case();
+            for arg in args {
+                rendered.push(' ');
+                rendered.push_str(&render_argument(arg));
+            }
+            rendered
+        }
+        Type::Tuple(_, elements) => format!(
+            "( {} )",
+            elements
+                .iter()
+                .map(render_type)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Type::Record(_, fields) if fields.is_empty() => "{}".to_string(),
+        Type::Record(_, fields) => format!("{{ {} }}", render_fields(fields)),
+        Type::ExtensibleRecord(_, variable, fields) => format!(
+            "{{ {} | {} }}",
+            variable.to_camel_case(),
+            render_fields(fields)
+        ),
+        Type::Function(_, argument, result) => {
+            let argument = match argument.as_ref() {
+                Type::Function(..) => format!("({})", render_type(argument)),
+                _ => render_type(argument),
+            };
+            format!("{} -> {}", argument, render_type(result))
+        }
+        Type::Unit(_) => "()".to_string(),
+    }
+}
+
+/// Render a type argument, parenthesised unless it is a single word
+fn render_argument(tpe: &Type) -> String {
+    match tpe {
+        Type::Reference(_, _, args) if !args.is_empty() => format!("({})", render_type(tpe)),
+        Type::Function(..) => format!("({})", render_type(tpe)),
+        _ => render_type(tpe),
+    }
+}
+
+fn render_fields(fields: &[morphir_core::ir::v4::Field]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{} : {}",
+                field.name.to_camel_case(),
+                render_type(&field.tpe)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Signature of a value: its inputs, in order, then its output
+pub fn value_signature(name: &str, definition: &ValueDefinition) -> String {
+    let mut parts: Vec<String> = definition
+        .input_types
+        .values()
+        .map(|input| render_argument_of_function(&input.input_type))
+        .collect();
+    parts.push(render_type(&definition.output_type));
+    format!("{} : {}", name, parts.join(" -> "))
+}
+
+/// Render a function input, parenthesised when it is a function itself
+fn render_argument_of_function(tpe: &Type) -> String {
+    match tpe {
+        Type::Function(..) => format!("({})", render_type(tpe)),
+        _ => render_type(tpe),
+    }
+}
+
+/// Declaration of a type: `type alias Money = Int`, or a custom type with
+/// its constructors, `type Shape = Circle Float | Point`
+pub fn type_signature(name: &str, definition: &LegacyTypeDefinition) -> String {
+    let (keyword, params) = match definition {
+        LegacyTypeDefinition::TypeAliasDefinition { type_params, .. } => {
+            ("type alias", type_params)
+        }
+        LegacyTypeDefinition::CustomTypeDefinition { type_params, .. } => ("type", type_params),
+    };
+    let mut head = format!("{} {}", keyword, Name::from(name).to_title_case());
+    for param in params {
+        head.push(' ');
+        head.push_str(&param.to_camel_case());
+    }
+    match definition {
+        LegacyTypeDefinition::TypeAliasDefinition { type_expr, .. } => {
+            format!("{} = {}", head, render_type(type_expr))
+        }
+        LegacyTypeDefinition::CustomTypeDefinition { constructors, .. }
+            if constructors.value.is_empty() =>
+        {
+            head
+        }
+        LegacyTypeDefinition::CustomTypeDefinition { constructors, .. } => {
+            let constructors: Vec<String> = constructors
+                .value
+                .iter()
+                .map(|constructor| {
+                    let mut rendered = constructor.name.to_title_case();
+                    for arg in &constructor.args {
+                        rendered.push(' ');
+                        rendered.push_str(&render_argument(&arg.1));
+                    }
+                    rendered
+                })
+                .collect();
+            format!("{} = {}", head, constructors.join(" | "))
+        }
+    }
+}
+
+/// Directory of `module` in the IR under `output`. The frontend names module
+/// directories after source paths, so `orders/money` is found as
+/// `src/orders/money` too.
+pub fn find_module_dir(output: &Path, module: &str) -> Option<PathBuf> {
+    let module = ModuleName::parse(module).to_string();
+    let packages = std::fs::read_dir(output.join(".morphir-dist").join("pkg")).ok()?;
+    for package in packages.flatten() {
+        let mut pending = vec![package.path()];
+        while let Some(dir) = pending.pop() {
+            let relative = dir
+                .strip_prefix(package.path())
+                .unwrap_or(&dir)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if dir.join("module.json").is_file()
+                && (relative == module || relative.ends_with(&format!("/{}", module)))
+            {
+                return Some(dir);
+            }
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            pending.extend(
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_dir()),
+            );
+        }
+    }
+    None
+}
+
+/// Rendered declaration of `name` in `module`, from the IR under `output`
+pub fn describe(output: &Path, module: &str, name: &str) -> Option<String> {
+    let dir = find_module_dir(output, module)?;
+    if let Ok(content) =
+        std::fs::read_to_string(dir.join("values").join(format!("{}.value.json", name)))
+        && let Ok(value) = serde_json::from_str::<AccessControlled<ValueDefinition>>(&content)
+    {
+        return Some(value_signature(name, &value.value));
+    }
+    let content =
+        std::fs::read_to_string(dir.join("types").join(format!("{}.type.json", name))).ok()?;
+    let definition = serde_json::from_str::<AccessControlledTypeDefinition>(&content).ok()?;
+    Some(type_signature(name, &definition.value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_core::ir::v4::Literal;
+    use morphir_core::ir::v4::{
+        Access, AccessControlledConstructors, Field, TypeAttributes, TypeDefConstructorArg,
+        TypeDefConstructorDefinition, Value, ValueAttributes,
+    };
+    use morphir_core::naming::FQName;
+
+    fn reference(name: &str, args: Vec<Type>) -> Type {
+        Type::Reference(
+            TypeAttributes::default(),
+            FQName::from_canonical_string(&format!("morphir/sdk:basics#{}", name)).unwrap(),
+            args,
+        )
+    }
+
+    #[test]
+    fn test_types_render_in_morphir_syntax() {
+        let attrs = TypeAttributes::default;
+        let int = reference("int", vec![]);
+        let list_of_ints = reference("list", vec![int.clone()]);
+        let mapper = Type::Function(attrs(), Box::new(int.clone()), Box::new(int.clone()));
+        let record = Type::ExtensibleRecord(
+            attrs(),
+            Name::from("r"),
+            vec![Field::new(
+                Name::from("unit_price"),
+                reference("maybe", vec![int.clone()]),
+            )],
+        );
+        assert_eq!(render_type(&list_of_ints), "List Int");
+        assert_eq!(
+            render_type(&reference(
+                "dict",
+                vec![
+                    Type::Variable(attrs(), Name::from("k")),
+                    list_of_ints.clone()
+                ]
+            )),
+            "Dict k (List Int)"
+        );
+        assert_eq!(
+            render_type(&Type::Function(
+                attrs(),
+                Box::new(mapper.clone()),
+                Box::new(list_of_ints.clone())
+            )),
+            "(Int -> Int) -> List Int"
+        );
+        assert_eq!(render_type(&record), "{ r | unitPrice : Maybe Int }");
+        assert_eq!(
+            render_type(&Type::Tuple(
+                attrs(),
+                vec![int.clone(), Type::Unit(attrs())]
+            )),
+            "( Int, () )"
+        );
+
+        let definition = ValueDefinition::new(
+            vec![
+                morphir_core::ir::v4::InputType(
+                    Name::from("f"),
+                    ValueAttributes::default(),
+                    mapper,
+                ),
+                morphir_core::ir::v4::InputType(
+                    Name::from("xs"),
+                    ValueAttributes::default(),
+                    list_of_ints.clone(),
+                ),
+            ],
+            list_of_ints,
+            Value::Literal(ValueAttributes::default(), Literal::Integer(0)),
+        );
+        assert_eq!(
+            value_signature("map_all", &definition),
+            "map_all : (Int -> Int) -> List Int -> List Int"
+        );
+
+        let shape = LegacyTypeDefinition::CustomTypeDefinition {
+            type_params: vec![Name::from("a")],
+            constructors: AccessControlledConstructors {
+                access: Access::Public,
+                value: vec![
+                    TypeDefConstructorDefinition {
+                        name: Name::from("circle"),
+                        args: vec![TypeDefConstructorArg(
+                            Name::from("radius"),
+                            reference("float", vec![]),
+                        )],
+                    },
+                    TypeDefConstructorDefinition {
+                        name: Name::from("point"),
+                        args: vec![],
+                    },
+                ],
+            },
+        };
+        assert_eq!(
+            type_signature("shape", &shape),
+            "type Shape a = Circle Float | Point"
+        );
+    }
+
+    #[test]
+    fn test_describe_reads_the_compiled_ir() {
+        let temp = tempfile::tempdir().unwrap();
+        let module_dir = temp
+            .path()
+            .join(".morphir-dist/pkg/my-org/orders/src/orders/money");
+        std::fs::create_dir_all(module_dir.join("values")).unwrap();
+        std::fs::create_dir_all(module_dir.join("types")).unwrap();
+        std::fs::write(module_dir.join("module.json"), "{}").unwrap();
+
+        let int = reference("int", vec![]);
+        let value = AccessControlled::public(ValueDefinition::new(
+            vec![morphir_core::ir::v4::InputType(
+                Name::from("amount"),
+                ValueAttributes::default(),
+                int.clone(),
+            )],
+            int.clone(),
+            Value::Literal(ValueAttributes::default(), Literal::Integer(0)),
+        ));
+        std::fs::write(
+            module_dir.join("values/double.value.json"),
+            serde_json::to_string(&value).unwrap(),
+        )
+        .unwrap();
+        let money = AccessControlledTypeDefinition {
+            access: Access::Public,
+            value: LegacyTypeDefinition::TypeAliasDefinition {
+                type_params: vec![],
+                type_expr: int,
+            },
+        };
+        std::fs::write(
+            module_dir.join("types/Money.type.json"),
+            serde_json::to_string(&money).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_module_dir(temp.path(), "orders/money"),
+            Some(module_dir.clone())
+        );
+        assert_eq!(
+            describe(temp.path(), "orders/money", "double").as_deref(),
+            Some("double : Int -> Int")
+        );
+        assert_eq!(
+            describe(temp.path(), "orders/money", "Money").as_deref(),
+            Some("type alias Money = Int")
+        );
+        assert_eq!(describe(temp.path(), "orders/money", "missing"), None);
+        assert_eq!(describe(temp.path(), "orders/tax", "double"), None);
+    }
+}
@@ -0,0 +1,692 @@
+//! The language server
+//!
+//! [`LspServer`] handles one client. The workspace is opened from the root
+//! the client sends with `initialize`, and every project is built once the
+//! client is initialized. Saving a document refreshes its project's module
+//! index and builds the project again, then the projects depending on it,
+//! publishing the diagnostics of each build. Documents are synced in full;
+//! hover, go-to-definition, and document symbols read the open text when
+//! there is one and the file on disk otherwise.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use morphir_daemon::workspace::{BuildReport, Project, ProjectBuilder, SymbolKind, Workspace};
+use morphir_extension_sdk::types::{
+    Diagnostic as BuildDiagnostic, DiagnosticSeverity, SourceLocation,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncWrite};
+use tracing::{debug, warn};
+
+use crate::document::{Definition, locate, name_at, resolve, symbol_range};
+use crate::error::Result;
+use crate::protocol::{
+    Diagnostic, DidChangeParams, DidOpenParams, DocumentSymbol, Hover, InitializeParams, Location,
+    MarkupContent, Position, Range, TextDocumentParams, TextDocumentPositionParams, error_codes,
+    methods, path_to_uri, symbol_kinds, uri_to_path,
+};
+use crate::render::describe;
+use crate::transport::{read_message, write_message};
+
+/// Error answered to a request: a JSON-RPC error code and message
+type RequestError = (i32, String);
+
+/// Language server for a Morphir workspace
+pub struct LspServer {
+    builder: Arc<dyn ProjectBuilder>,
+    workspace: Option<Workspace>,
+    /// Text of the open documents, by URI
+    documents: HashMap<String, String>,
+    /// URIs with diagnostics published, by project
+    published: HashMap<String, HashSet<String>>,
+    initialized: bool,
+    shutdown: bool,
+    exited: bool,
+}
+
+impl LspServer {
+    /// Server building projects with `builder`
+    pub fn new(builder: Arc<dyn ProjectBuilder>) -> Self {
+        Self {
+            builder,
+            workspace: None,
+            documents: HashMap::new(),
+            published: HashMap::new(),
+            initialized: false,
+            shutdown: false,
+            exited: false,
+        }
+    }
+
+    /// Whether the client sent `exit`
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Exit code the process should end with: 0 when `shutdown` preceded
+    /// `exit`, 1 otherwise
+    pub fn exit_code(&self) -> i32 {
+        if self.shutdown { 0 } else { 1 }
+    }
+
+    /// Serve messages from `reader` until the client exits or the input ends
+    pub async fn serve(
+        &mut self,
+        reader: &mut (impl AsyncBufRead + Unpin),
+        writer: &mut (impl AsyncWrite + Unpin),
+    ) -> Result<()> {
+        while let Some(message) = read_message(reader).await? {
+            for outgoing in self.handle(message).await {
+                write_message(writer, &outgoing).await?;
+            }
+            if self.exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a message from the client, returning the messages to send back:
+    /// the response to a request, and any notifications
+    pub async fn handle(&mut self, message: Value) -> Vec<Value> {
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        match (message.get("id").cloned(), method) {
+            (Some(id), Some(method)) => {
+                let response = match self.request(&method, params).await {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": message },
+                    }),
+                };
+                vec![response]
+            }
+            (None, Some(method)) => self.notification(&method, params).await,
+            // Responses to requests the server never sends
+            _ => Vec::new(),
+        }
+    }
+
+    async fn request(
+        &mut self,
+        method: &str,
+        params: Value,
+    ) -> std::result::Result<Value, RequestError> {
+        if method == methods::INITIALIZE {
+            return self.initialize(parse(params)?);
+        }
+        if !self.initialized {
+            return Err((
+                error_codes::SERVER_NOT_INITIALIZED,
+                "Server not initialized".to_string(),
+            ));
+        }
+        if self.shutdown {
+            return Err((
+                error_codes::INVALID_REQUEST,
+                "Server is shutting down".to_string(),
+            ));
+        }
+        match method {
+            methods::SHUTDOWN => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            methods::HOVER => to_value(self.hover(parse(params)?)),
+            methods::DEFINITION => to_value(self.definition(parse(params)?)),
+            methods::DOCUMENT_SYMBOL => to_value(self.document_symbols(parse(params)?)),
+            _ => Err((
+                error_codes::METHOD_NOT_FOUND,
+                format!("Unknown method: {}", method),
+            )),
+        }
+    }
+
+    async fn notification(&mut self, method: &str, params: Value) -> Vec<Value> {
+        match method {
+            methods::INITIALIZED => self.build_all().await,
+            methods::EXIT => {
+                self.exited = true;
+                Vec::new()
+            }
+            methods::DID_OPEN => {
+                if let Ok(params) = serde_json::from_value::<DidOpenParams>(params) {
+                    let document = params.text_document;
+                    self.documents.insert(document.uri, document.text);
+                }
+                Vec::new()
+            }
+            methods::DID_CHANGE => {
+                if let Ok(params) = serde_json::from_value::<DidChangeParams>(params)
+                    && let Some(change) = params.content_changes.into_iter().last()
+                {
+                    self.documents.insert(params.text_document.uri, change.text);
+                }
+                Vec::new()
+            }
+            methods::DID_CLOSE => {
+                if let Ok(params) = serde_json::from_value::<TextDocumentParams>(params) {
+                    self.documents.remove(&params.text_document.uri);
+                }
+                Vec::new()
+            }
+            methods::DID_SAVE => match serde_json::from_value::<TextDocumentParams>(params) {
+                Ok(params) => self.saved(&params.text_document.uri).await,
+                Err(_) => Vec::new(),
+            },
+            _ => {
+                debug!("Ignoring notification {}", method);
+                Vec::new()
+            }
+        }
+    }
+
+    fn initialize(&mut self, params: InitializeParams) -> std::result::Result<Value, RequestError> {
+        self.initialized = true;
+        if let Some(root) = params.root() {
+            let root = root.canonicalize().unwrap_or(root);
+            match Workspace::open(root.clone()) {
+                Ok(mut workspace) => {
+                    if let Err(e) = workspace.refresh_indexes() {
+                        warn!("Failed to index {}: {}", root.display(), e);
+                    }
+                    self.workspace = Some(workspace);
+                }
+                Err(e) => warn!("No Morphir workspace at {}: {}", root.display(), e),
+            }
+        }
+        Ok(json!({
+            "capabilities": {
+                "textDocumentSync": {
+                    "openClose": true,
+                    "change": 1,
+                    "save": { "includeText": false },
+                },
+                "hoverProvider": true,
+                "definitionProvider": true,
+                "documentSymbolProvider": true,
+            },
+            "serverInfo": {
+                "name": "morphir-lsp",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        }))
+    }
+
+    /// Build every project in dependency order
+    async fn build_all(&mut self) -> Vec<Value> {
+        let Some(workspace) = &self.workspace else {
+            return Vec::new();
+        };
+        let order = workspace.build_order().unwrap_or_else(|e| {
+            warn!("{}", e);
+            let mut names: Vec<String> = workspace.projects.keys().cloned().collect();
+            names.sort();
+            names
+        });
+        self.build(order, &HashSet::new()).await
+    }
+
+    /// Refresh the index of the saved document's project, then build the
+    /// project and those downstream of it
+    async fn saved(&mut self, uri: &str) -> Vec<Value> {
+        let Some(workspace) = &mut self.workspace else {
+            return Vec::new();
+        };
+        let Some(path) = uri_to_path(uri).map(|path| path.canonicalize().unwrap_or(path)) else {
+            return Vec::new();
+        };
+        let Some(project) = workspace
+            .projects
+            .values()
+            .find(|project| path.starts_with(&project.path))
+            .cloned()
+        else {
+            return Vec::new();
+        };
+        match project.refresh_index() {
+            Ok((index, _)) => {
+                workspace.indexes.insert(project.name.clone(), index);
+            }
+            Err(e) => warn!("Failed to index {}: {}", project.name, e),
+        }
+        let changed = vec![project.name.clone()];
+        let affected = workspace
+            .project_graph()
+            .downstream(&changed)
+            .unwrap_or_else(|e| {
+                warn!("{}", e);
+                changed.clone()
+            });
+        let rebuild = affected
+            .iter()
+            .filter(|name| **name != project.name)
+            .cloned()
+            .collect();
+        self.build(affected, &rebuild).await
+    }
+
+    /// Build `projects` in order, building those in `rebuild` from scratch,
+    /// and publish their diagnostics
+    async fn build(&mut self, projects: Vec<String>, rebuild: &HashSet<String>) -> Vec<Value> {
+        let Some(workspace) = &self.workspace else {
+            return Vec::new();
+        };
+        let mut outgoing = Vec::new();
+        for name in projects {
+            let Some(project) = workspace.projects.get(&name) else {
+                continue;
+            };
+            let report = if rebuild.contains(&name) {
+                self.builder.rebuild(&workspace.root, project).await
+            } else {
+                self.builder.build(&workspace.root, project).await
+            };
+            let diagnostics = match report {
+                Ok(BuildReport { diagnostics, .. }) => diagnostics,
+                Err(e) => vec![error_diagnostic(e.to_string(), None)],
+            };
+
+            let mut by_uri: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+            for diagnostic in &diagnostics {
+                by_uri
+                    .entry(diagnostic_uri(project, diagnostic))
+                    .or_default()
+                    .push(Diagnostic::from_build(diagnostic));
+            }
+            let previous = self.published.remove(&name).unwrap_or_default();
+            for uri in previous.difference(&by_uri.keys().cloned().collect()) {
+                outgoing.push(publish(uri, Vec::new()));
+            }
+            let mut uris: Vec<&String> = by_uri.keys().collect();
+            uris.sort();
+            for uri in uris {
+                outgoing.push(publish(uri, by_uri[uri].clone()));
+            }
+            self.published.insert(name, by_uri.into_keys().collect());
+        }
+        outgoing
+    }
+
+    /// Text of the document at `uri`: the open text, or the file on disk
+    fn text(&self, uri: &str) -> Option<String> {
+        self.documents
+            .get(uri)
+            .cloned()
+            .or_else(|| std::fs::read_to_string(uri_to_path(uri)?).ok())
+    }
+
+    /// The definition the name at a position refers to
+    fn definition_at(&self, params: &TextDocumentPositionParams) -> Option<(Definition, Range)> {
+        let workspace = self.workspace.as_ref()?;
+        let uri = &params.text_document.uri;
+        let name = name_at(&self.text(uri)?, params.position)?;
+        let definition = resolve(workspace, &uri_to_path(uri)?, &name)?;
+        Some((definition, name.range))
+    }
+
+    fn hover(&self, params: TextDocumentPositionParams) -> Option<Hover> {
+        let workspace = self.workspace.as_ref()?;
+        let (definition, range) = self.definition_at(&params)?;
+        let project = workspace.projects.get(&definition.project)?;
+        let signature = project
+            .compile_output(&workspace.root)
+            .and_then(|output| describe(&output, &definition.module, &definition.symbol.name))
+            .map(|signature| format!("```elm\n{}\n```", signature))
+            .or_else(|| {
+                // Not compiled yet: show the declaration from the source
+                let source = std::fs::read_to_string(&definition.path).ok()?;
+                let line = source.lines().nth(definition.symbol.line.checked_sub(1)?)?;
+                Some(format!(
+                    "```{}\n{}\n```",
+                    project.language().unwrap_or_default(),
+                    line.trim_end_matches('{').trim_end()
+                ))
+            })
+            .unwrap_or_default();
+        Some(Hover {
+            contents: MarkupContent::markdown(format!(
+                "{}\n\n`{}`",
+                signature,
+                definition.fqname().to_canonical_string()
+            )),
+            range: Some(range),
+        })
+    }
+
+    fn definition(&self, params: TextDocumentPositionParams) -> Option<Location> {
+        let (definition, _) = self.definition_at(&params)?;
+        let uri = path_to_uri(&definition.path);
+        let text = self.text(&uri).unwrap_or_default();
+        Some(Location {
+            range: symbol_range(&text, definition.symbol.line, &definition.symbol.name),
+            uri,
+        })
+    }
+
+    fn document_symbols(&self, params: TextDocumentParams) -> Option<Vec<DocumentSymbol>> {
+        let workspace = self.workspace.as_ref()?;
+        let uri = &params.text_document.uri;
+        let (_, file) = locate(workspace, &uri_to_path(uri)?)?;
+        let text = self.text(uri).unwrap_or_default();
+        let symbols = file
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let line = symbol.line.saturating_sub(1) as u32;
+                let length = text.lines().nth(line as usize).map_or(0, |content| {
+                    crate::document::utf16_column(content, content.len())
+                });
+                DocumentSymbol {
+                    name: symbol.name.clone(),
+                    detail: Some(file.module.clone()),
+                    kind: match symbol.kind {
+                        SymbolKind::Function => symbol_kinds::FUNCTION,
+                        SymbolKind::Type => symbol_kinds::STRUCT,
+                        SymbolKind::Constant => symbol_kinds::CONSTANT,
+                    },
+                    range: Range::new(Position::new(line, 0), Position::new(line, length)),
+                    selection_range: symbol_range(&text, symbol.line, &symbol.name),
+                }
+            })
+            .collect();
+        Some(symbols)
+    }
+}
+
+/// URI a build diagnostic belongs to: its file, relative to the project, or
+/// the project's `morphir.toml` when it has none
+fn diagnostic_uri(project: &Project, diagnostic: &BuildDiagnostic) -> String {
+    let path: PathBuf = match &diagnostic.location {
+        Some(location) if Path::new(&location.file).is_absolute() => PathBuf::from(&location.file),
+        Some(location) => project.path.join(&location.file),
+        None => project.path.join("morphir.toml"),
+    };
+    path_to_uri(&path)
+}
+
+fn error_diagnostic(message: String, location: Option<SourceLocation>) -> BuildDiagnostic {
+    BuildDiagnostic {
+        severity: DiagnosticSeverity::Error,
+        code: None,
+        message,
+        location,
+        related: Vec::new(),
+        fixes: Vec::new(),
+    }
+}
+
+fn publish(uri: &str, diagnostics: Vec<Diagnostic>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": methods::PUBLISH_DIAGNOSTICS,
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> std::result::Result<T, RequestError> {
+    serde_json::from_value(params).map_err(|e| (error_codes::INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value(result: impl Serialize) -> std::result::Result<Value, RequestError> {
+    serde_json::to_value(result).map_err(|e| (error_codes::INVALID_PARAMS, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use morphir_daemon::Result as DaemonResult;
+    use std::sync::Mutex;
+
+    /// Builder recording its builds, reporting an error in `orders` until
+    /// `fixed`
+    #[derive(Default)]
+    struct StubBuilder {
+        builds: Mutex<Vec<String>>,
+        fixed: Mutex<bool>,
+    }
+
+    impl StubBuilder {
+        fn report(&self, project: &Project, kind: &str) -> DaemonResult<BuildReport> {
+            self.builds
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", kind, project.name));
+            let mut diagnostics = Vec::new();
+            if project.name == "my-org/orders" && !*self.fixed.lock().unwrap() {
+                diagnostics.push(error_diagnostic(
+                    "Unknown variable: y".to_string(),
+                    Some(SourceLocation {
+                        file: "src/orders/order.gleam".to_string(),
+                        start_line: 4,
+                        start_col: 16,
+                        end_line: 4,
+                        end_col: 17,
+                    }),
+                ));
+            }
+            Ok(BuildReport {
+                project: project.name.clone(),
+                success: diagnostics.is_empty(),
+                compiled: 1,
+                cached: 0,
+                diagnostics,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl ProjectBuilder for StubBuilder {
+        async fn build(&self, _root: &Path, project: &Project) -> DaemonResult<BuildReport> {
+            self.report(project, "build")
+        }
+
+        async fn rebuild(&self, _root: &Path, project: &Project) -> DaemonResult<BuildReport> {
+            self.report(project, "rebuild")
+        }
+    }
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// Workspace of `core`, with a `money` module, and `orders`, depending
+    /// on `core`
+    fn workspace(root: &Path) {
+        write(
+            &root.join("morphir.toml"),
+            "[workspace]\nmembers = [\"packages/*\"]\n",
+        );
+        for (dir, dependencies) in [
+            ("core", ""),
+            ("orders", "\"my-org/core\" = { path = \"../core\" }\n"),
+        ] {
+            write(
+                &root.join("packages").join(dir).join("morphir.toml"),
+                &format!(
+                    "[project]\nname = \"my-org/{}\"\nversion = \"1.0.0\"\nsource_directory = \"src\"\n\n[frontend]\nlanguage = \"gleam\"\n\n[dependencies]\n{}",
+                    dir, dependencies
+                ),
+            );
+        }
+        write(
+            &root.join("packages/core/src/money.gleam"),
+            "pub type Money {\n  Money(Int)\n}\n\npub fn add(a: Money, b: Money) -> Money {\n  a\n}\n",
+        );
+        write(
+            &root.join("packages/orders/src/orders/order.gleam"),
+            "import money\n\npub fn total(x) {\n  money.add(x, y)\n}\n",
+        );
+    }
+
+    fn request(id: i64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn notification(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "method": method, "params": params })
+    }
+
+    #[tokio::test]
+    async fn test_server_publishes_diagnostics_and_rebuilds_dependents_on_save() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        workspace(&root);
+        let builder = Arc::new(StubBuilder::default());
+        let mut server = LspServer::new(builder.clone());
+
+        let early = server.handle(request(1, methods::HOVER, json!({}))).await;
+        assert_eq!(
+            early[0]["error"]["code"],
+            json!(error_codes::SERVER_NOT_INITIALIZED)
+        );
+
+        let response = server
+            .handle(request(
+                2,
+                methods::INITIALIZE,
+                json!({ "rootUri": path_to_uri(&root), "capabilities": {} }),
+            ))
+            .await;
+        assert_eq!(
+            response[0]["result"]["capabilities"]["hoverProvider"],
+            json!(true)
+        );
+
+        let order_uri = path_to_uri(&root.join("packages/orders/src/orders/order.gleam"));
+        let published = server
+            .handle(notification(methods::INITIALIZED, json!({})))
+            .await;
+        assert_eq!(
+            *builder.builds.lock().unwrap(),
+            vec!["build my-org/core", "build my-org/orders"]
+        );
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0]["params"]["uri"], json!(order_uri));
+        assert_eq!(
+            published[0]["params"]["diagnostics"],
+            json!([{
+                "range": {
+                    "start": { "line": 3, "character": 15 },
+                    "end": { "line": 3, "character": 16 },
+                },
+                "severity": 1,
+                "source": "morphir",
+                "message": "Unknown variable: y",
+            }])
+        );
+
+        // Saving `core` rebuilds `orders`; its diagnostics are cleared
+        builder.builds.lock().unwrap().clear();
+        *builder.fixed.lock().unwrap() = true;
+        let core_uri = path_to_uri(&root.join("packages/core/src/money.gleam"));
+        let published = server
+            .handle(notification(
+                methods::DID_SAVE,
+                json!({ "textDocument": { "uri": core_uri } }),
+            ))
+            .await;
+        assert_eq!(
+            *builder.builds.lock().unwrap(),
+            vec!["build my-org/core", "rebuild my-org/orders"]
+        );
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0]["params"]["uri"], json!(order_uri));
+        assert_eq!(published[0]["params"]["diagnostics"], json!([]));
+
+        let response = server
+            .handle(request(3, methods::SHUTDOWN, Value::Null))
+            .await;
+        assert_eq!(response[0]["result"], Value::Null);
+        server
+            .handle(notification(methods::EXIT, Value::Null))
+            .await;
+        assert!(server.exited());
+        assert_eq!(server.exit_code(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_server_navigates_definitions_across_projects() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        workspace(&root);
+        let mut server = LspServer::new(Arc::new(StubBuilder::default()));
+        server
+            .handle(request(1, methods::INITIALIZE, json!({ "rootPath": root })))
+            .await;
+
+        // The open text wins over the file on disk
+        let order_uri = path_to_uri(&root.join("packages/orders/src/orders/order.gleam"));
+        server
+            .handle(notification(
+                methods::DID_OPEN,
+                json!({ "textDocument": {
+                    "uri": order_uri,
+                    "languageId": "gleam",
+                    "version": 1,
+                    "text": "import money\n\npub fn total(x) {\n\n  money.add(x, x)\n}\n",
+                }}),
+            ))
+            .await;
+        let position = json!({
+            "textDocument": { "uri": order_uri },
+            "position": { "line": 4, "character": 9 },
+        });
+
+        let response = server
+            .handle(request(2, methods::DEFINITION, position.clone()))
+            .await;
+        assert_eq!(
+            response[0]["result"],
+            json!({
+                "uri": path_to_uri(&root.join("packages/core/src/money.gleam")),
+                "range": {
+                    "start": { "line": 4, "character": 7 },
+                    "end": { "line": 4, "character": 10 },
+                },
+            })
+        );
+
+        // Without compiled IR, hover shows the declaration
+        let response = server.handle(request(3, methods::HOVER, position)).await;
+        assert_eq!(
+            response[0]["result"]["contents"]["value"],
+            json!(
+                "```gleam\npub fn add(a: Money, b: Money) -> Money\n```\n\n`my-org/core:money#add`"
+            )
+        );
+
+        let response = server
+            .handle(request(
+                4,
+                methods::DOCUMENT_SYMBOL,
+                json!({ "textDocument": { "uri": path_to_uri(&root.join("packages/core/src/money.gleam")) } }),
+            ))
+            .await;
+        let symbols = response[0]["result"].as_array().unwrap();
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0]["name"], json!("Money"));
+        assert_eq!(symbols[0]["kind"], json!(symbol_kinds::STRUCT));
+        assert_eq!(symbols[1]["name"], json!("add"));
+        assert_eq!(symbols[1]["kind"], json!(symbol_kinds::FUNCTION));
+
+        let response = server
+            .handle(request(5, "workspace/symbol", json!({ "query": "" })))
+            .await;
+        assert_eq!(
+            response[0]["error"]["code"],
+            json!(error_codes::METHOD_NOT_FOUND)
+        );
+    }
+}
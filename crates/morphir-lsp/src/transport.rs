@@ -0,0 +1,93 @@
+//! LSP base protocol framing
+//!
+//! Each message is a header part and a JSON content part separated by an
+//! empty line. Only `Content-Length` is required; other headers, such as
+//! `Content-Type`, are read and ignored.
+
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::{LspError, Result};
+
+/// Read the next message, or `None` once the input ends between messages
+pub async fn read_message(reader: &mut (impl AsyncBufRead + Unpin)) -> Result<Option<Value>> {
+    let mut length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return match length {
+                None => Ok(None),
+                Some(_) => Err(LspError::Protocol("Input ended in a header".to_string())),
+            };
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            // Tolerate blank lines between messages
+            continue;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(LspError::Protocol(format!("Invalid header: {}", header)));
+        };
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            let value = value.trim().parse::<usize>().map_err(|_| {
+                LspError::Protocol(format!("Invalid Content-Length: {}", value.trim()))
+            })?;
+            length = Some(value);
+        }
+    }
+
+    let mut content = vec![0; length.unwrap_or_default()];
+    reader.read_exact(&mut content).await?;
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+/// Write `message` with its header
+pub async fn write_message(writer: &mut (impl AsyncWrite + Unpin), message: &Value) -> Result<()> {
+    let content = serde_json::to_vec(message)?;
+    let header = format!("Content-Length: {}\r\n\r\n", content.len());
+    writer.write_all(header.as_bytes()).await?;
+    writer.write_all(&content).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn test_messages_round_trip_through_framing() {
+        let mut buffer = Vec::new();
+        let first = json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} });
+        let second = json!({ "jsonrpc": "2.0", "id": 1, "result": "héllo" });
+        write_message(&mut buffer, &first).await.unwrap();
+        write_message(&mut buffer, &second).await.unwrap();
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        assert!(text.starts_with("Content-Length: 52\r\n\r\n{"), "{}", text);
+
+        let mut reader = BufReader::new(buffer.as_slice());
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(first));
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(second));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_other_headers_are_ignored_and_bad_lengths_rejected() {
+        let input = b"Content-Type: application/vscode-jsonrpc; charset=utf-8\r\ncontent-length: 2\r\n\r\n{}";
+        let mut reader = BufReader::new(&input[..]);
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(json!({})));
+
+        let mut reader = BufReader::new(&b"Content-Length: many\r\n\r\n"[..]);
+        let err = read_message(&mut reader).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Protocol error: Invalid Content-Length: many"
+        );
+    }
+}
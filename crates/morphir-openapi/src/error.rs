@@ -0,0 +1,22 @@
+//! Error types for OpenAPI generation
+
+use thiserror::Error;
+
+/// Problems that keep a distribution from being described as an API
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum OpenApiError {
+    #[error("{0} distributions have no entry points; only an Application can be described")]
+    NotAnApplication(&'static str),
+
+    #[error("Entry point `{name}` has an invalid target `{target}`: {message}")]
+    InvalidTarget {
+        name: String,
+        target: String,
+        message: String,
+    },
+
+    #[error(
+        "Entry point `{name}` targets `{target}`, which is not a value defined in this package"
+    )]
+    UnknownTarget { name: String, target: String },
+}
@@ -0,0 +1,182 @@
+//! OpenAPI backend for Morphir applications
+//!
+//! Describes the entry points of an Application distribution as an OpenAPI
+//! 3.1 document: each entry point becomes a `POST /<entry-point>` operation
+//! whose request body holds the arguments of the target function and whose
+//! response is its result. Schemas follow the JSON encoding of `morphir run`
+//! and are shared with the `json-schema` backend.
+//!
+//! # Usage
+//!
+//! ```sh
+//! morphir generate --target openapi --input ./morphir-ir.json --output ./api
+//! ```
+//!
+//! The backend runs natively in the CLI through the [`BuiltinExtension`]
+//! trait, like the builtins of `morphir-builtins`.
+
+use anyhow::{Context, Result};
+use morphir_builtins::{BuiltinExtension, BuiltinInfo, ExtensionType};
+use morphir_core::ir::v4;
+use morphir_ext_core::Envelope;
+use serde::{Deserialize, Serialize};
+
+mod error;
+mod spec;
+
+pub use error::OpenApiError;
+pub use spec::{SpecOutput, generate_spec};
+
+/// OpenAPI backend for application entry points.
+#[derive(Default)]
+pub struct OpenApiExtension;
+
+impl BuiltinExtension for OpenApiExtension {
+    fn execute_native(&self, input: &Envelope) -> Result<Envelope> {
+        let request: OpenApiRequest = input.as_json().context("Failed to parse openapi request")?;
+
+        let result = generate(request)?;
+
+        Envelope::json(&result).context("Failed to create response envelope")
+    }
+
+    fn info(&self) -> BuiltinInfo {
+        BuiltinInfo {
+            id: "openapi".to_string(),
+            name: "OpenAPI".to_string(),
+            extension_type: ExtensionType::Backend,
+            description: "Generate an OpenAPI 3.1 document for the entry points of an application"
+                .to_string(),
+        }
+    }
+}
+
+/// Options for the generated document, read from `[codegen.openapi]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenApiOptions {
+    /// `info.title`, the package name by default
+    #[serde(default)]
+    pub title: Option<String>,
+    /// `info.version`, `0.1.0` by default
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Base URLs listed under `servers`
+    #[serde(default)]
+    pub servers: Vec<String>,
+}
+
+/// Request format for the generate operation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenApiRequest {
+    /// Input IR, a V4 Application distribution
+    pub ir: serde_json::Value,
+    #[serde(default)]
+    pub options: OpenApiOptions,
+}
+
+/// Response format for the generate operation, as returned by backend
+/// extensions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenApiResponse {
+    /// Whether generation succeeded
+    pub success: bool,
+    /// The generated document
+    #[serde(default)]
+    pub artifacts: Vec<OpenApiArtifact>,
+    /// Warnings about types that could only be described loosely
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<OpenApiDiagnostic>,
+    /// Error message (if failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A generated file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenApiArtifact {
+    pub path: String,
+    pub content: String,
+}
+
+/// A diagnostic about the generated document, such as a request or response
+/// schema that accepts any value because its type has no JSON encoding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenApiDiagnostic {
+    /// `warning`: the document was still written. An IR without entry points
+    /// or with an unusable one fails the request with an `error` instead
+    pub severity: String,
+    pub message: String,
+}
+
+fn generate(request: OpenApiRequest) -> Result<OpenApiResponse> {
+    let ir: v4::IRFile = serde_json::from_value(request.ir)
+        .context("Failed to parse V4 IR (entry points exist only in V4 applications)")?;
+
+    let output = match generate_spec(&ir.distribution, &request.options) {
+        Ok(output) => output,
+        Err(e) => {
+            return Ok(OpenApiResponse {
+                success: false,
+                artifacts: vec![],
+                diagnostics: vec![],
+                error: Some(e.to_string()),
+            });
+        }
+    };
+
+    let path = format!(
+        "{}.openapi.json",
+        ir.distribution.package_name().to_string().replace('/', ".")
+    );
+    Ok(OpenApiResponse {
+        success: true,
+        artifacts: vec![OpenApiArtifact {
+            path,
+            content: serde_json::to_string_pretty(&output.document)?,
+        }],
+        diagnostics: output
+            .warnings
+            .into_iter()
+            .map(|message| OpenApiDiagnostic {
+                severity: "warning".to_string(),
+                message,
+            })
+            .collect(),
+        error: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_info() {
+        let info = OpenApiExtension.info();
+        assert_eq!(info.id, "openapi");
+        assert_eq!(info.extension_type, ExtensionType::Backend);
+    }
+
+    #[test]
+    fn test_library_has_no_entry_points() {
+        let request = serde_json::json!({
+            "ir": {
+                "formatVersion": 4,
+                "distribution": { "Library": {
+                    "packageName": "acme",
+                    "dependencies": {},
+                    "def": { "modules": {} }
+                } }
+            }
+        });
+        let output = OpenApiExtension
+            .execute_native(&Envelope::json(&request).unwrap())
+            .unwrap();
+        let response: OpenApiResponse = output.as_json().unwrap();
+        assert!(!response.success);
+        assert!(
+            response.error.unwrap().contains("Application"),
+            "error should point at the distribution kind"
+        );
+    }
+}
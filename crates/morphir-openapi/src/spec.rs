@@ -0,0 +1,259 @@
+//! OpenAPI document for the entry points of an Application distribution.
+
+use morphir_builtins::json_schema::{JsonSchemaOptions, TypeSchemas};
+use morphir_core::ir::v4::{Distribution, PackageDefinition, ValueDefinition};
+use morphir_core::naming::{FQName, Name, Path};
+use serde_json::{Map, Value, json};
+
+use crate::OpenApiOptions;
+use crate::error::OpenApiError;
+
+/// Where the package's type schemas live in the document
+const SCHEMAS_PREFIX: &str = "#/components/schemas/";
+
+/// Result of describing a distribution's entry points
+#[derive(Debug, Clone)]
+pub struct SpecOutput {
+    /// The OpenAPI document
+    pub document: Value,
+    /// Types that could only be described loosely, e.g. functions or
+    /// references to packages that are not available
+    pub warnings: Vec<String>,
+}
+
+/// Generate an OpenAPI 3.1 document for the entry points of an application.
+///
+/// Each entry point becomes a `POST /<entry-point>` operation. The request
+/// body is an object with one camelCase property per argument of the target
+/// function, and is omitted when it takes none; the `200` response is its
+/// result. Types of the package the schemas refer to are placed under
+/// `components/schemas`, keyed like the `json-schema` backend's definitions.
+pub fn generate_spec(
+    distribution: &Distribution,
+    options: &OpenApiOptions,
+) -> Result<SpecOutput, OpenApiError> {
+    let (Some(package), Some(entry_points)) =
+        (distribution.definition(), distribution.entry_points())
+    else {
+        return Err(OpenApiError::NotAnApplication(distribution.kind()));
+    };
+    let package_name = distribution.package_name().to_string();
+
+    // OpenAPI 3.1 schemas are JSON Schema 2020-12, the default draft
+    let schema_options = JsonSchemaOptions::default();
+    let mut schemas = TypeSchemas::new(&package_name, package, &schema_options, SCHEMAS_PREFIX);
+
+    let mut paths = Map::new();
+    for (name, entry_point) in entry_points {
+        let target = entry_point
+            .fqname()
+            .map_err(|message| OpenApiError::InvalidTarget {
+                name: name.clone(),
+                target: entry_point.target.clone(),
+                message,
+            })?;
+        let definition = find_value(&package_name, package, &target).ok_or_else(|| {
+            OpenApiError::UnknownTarget {
+                name: name.clone(),
+                target: entry_point.target.clone(),
+            }
+        })?;
+
+        let mut operation = Map::new();
+        operation.insert("operationId".to_string(), json!(name));
+        if let Some(doc) = entry_point.doc.as_deref().filter(|d| !d.trim().is_empty()) {
+            operation.insert("summary".to_string(), json!(doc.trim()));
+        }
+        if !definition.input_types.is_empty() {
+            let mut properties = Map::new();
+            for (arg, input) in &definition.input_types {
+                properties.insert(
+                    Name::from(arg.as_str()).to_camel_case(),
+                    schemas.schema(&input.input_type),
+                );
+            }
+            let required: Vec<&String> = properties.keys().collect();
+            let body = json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false,
+            });
+            let mut request_body = json_content(body);
+            request_body["required"] = json!(true);
+            operation.insert("requestBody".to_string(), request_body);
+        }
+        let result = schemas.schema(&definition.output_type);
+        let mut response = json_content(result);
+        response["description"] = json!(format!("Result of `{}`", entry_point.target));
+        operation.insert("responses".to_string(), json!({ "200": response }));
+        operation.insert("x-morphir-target".to_string(), json!(entry_point.target));
+        operation.insert(
+            "x-morphir-kind".to_string(),
+            serde_json::to_value(&entry_point.kind).unwrap_or(Value::Null),
+        );
+
+        paths.insert(format!("/{}", name), json!({ "post": operation }));
+    }
+
+    let (definitions, warnings) = schemas.finish();
+    let mut document = json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": options.title.clone().unwrap_or_else(|| package_name.clone()),
+            "version": options.version.as_deref().unwrap_or("0.1.0"),
+        },
+        "paths": paths,
+    });
+    if !options.servers.is_empty() {
+        let servers: Vec<Value> = options
+            .servers
+            .iter()
+            .map(|url| json!({ "url": url }))
+            .collect();
+        document["servers"] = json!(servers);
+    }
+    if !definitions.is_empty() {
+        document["components"] = json!({ "schemas": definitions });
+    }
+
+    Ok(SpecOutput { document, warnings })
+}
+
+/// Request body or response with a JSON payload
+fn json_content(schema: Value) -> Value {
+    json!({ "content": { "application/json": { "schema": schema } } })
+}
+
+/// Definition of a value of the package, by its fully qualified name
+fn find_value<'a>(
+    package_name: &str,
+    package: &'a PackageDefinition,
+    target: &FQName,
+) -> Option<&'a ValueDefinition> {
+    if Path::new(package_name) != target.package_path {
+        return None;
+    }
+    let (_, module) = package
+        .modules
+        .iter()
+        .find(|(key, _)| Path::new(key) == target.module_path)?;
+    module
+        .value
+        .values
+        .iter()
+        .find(|(key, _)| Name::from(key.as_str()) == target.local_name)
+        .map(|(_, value)| &value.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn application(entry_points: Value) -> Distribution {
+        let body = json!({"ExpressionBody": {"body": {"Literal": {"literal": {"IntegerLiteral": {"value": 0}}}}}});
+        serde_json::from_value(json!({ "Application": {
+            "packageName": "shop",
+            "dependencies": {},
+            "def": { "modules": { "orders": { "access": "Public", "value": {
+                "types": {
+                    "order": { "access": "Public", "value": { "TypeAliasDefinition": {
+                        "typeParams": [],
+                        "typeExp": { "Record": { "fields": {
+                            "id": "morphir/sdk:string#string",
+                            "quantity": "morphir/sdk:basics#int"
+                        } } }
+                    } } }
+                },
+                "values": {
+                    "price-order": { "access": "Public", "value": {
+                        "inputTypes": {
+                            "order": { "type": "shop:orders#order" },
+                            "unit-price": { "type": "morphir/sdk:decimal#decimal" }
+                        },
+                        "outputType": "morphir/sdk:decimal#decimal",
+                        "body": body
+                    } },
+                    "version": { "access": "Private", "value": {
+                        "inputTypes": {},
+                        "outputType": "morphir/sdk:basics#int",
+                        "body": body
+                    } }
+                }
+            } } } },
+            "entryPoints": entry_points
+        } }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_entry_points_become_operations() {
+        let dist = application(json!({
+            "price": { "target": "shop:orders#price-order", "kind": "handler", "doc": "Price an order" },
+            "version": { "target": "shop:orders#version", "kind": "main" }
+        }));
+        let output = generate_spec(&dist, &OpenApiOptions::default()).unwrap();
+        assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+        let doc = &output.document;
+
+        assert_eq!(doc["openapi"], "3.1.0");
+        assert_eq!(doc["info"]["title"], "shop");
+
+        let price = &doc["paths"]["/price"]["post"];
+        assert_eq!(price["operationId"], "price");
+        assert_eq!(price["summary"], "Price an order");
+        assert_eq!(price["x-morphir-kind"], "handler");
+        let body = &price["requestBody"]["content"]["application/json"]["schema"];
+        assert_eq!(body["required"], json!(["order", "unitPrice"]));
+        assert_eq!(
+            body["properties"]["order"],
+            json!({ "$ref": "#/components/schemas/Orders.Order" })
+        );
+        assert_eq!(
+            price["responses"]["200"]["content"]["application/json"]["schema"],
+            json!({ "type": ["string", "number"] })
+        );
+
+        let version = &doc["paths"]["/version"]["post"];
+        assert!(version.get("requestBody").is_none());
+
+        let order = &doc["components"]["schemas"]["Orders.Order"];
+        assert_eq!(order["title"], "Order");
+        assert_eq!(order["required"], json!(["id", "quantity"]));
+    }
+
+    #[test]
+    fn test_entry_points_must_target_package_values() {
+        let dist = application(json!({
+            "ship": { "target": "shop:orders#ship", "kind": "command" }
+        }));
+        let err = generate_spec(&dist, &OpenApiOptions::default()).unwrap_err();
+        assert_eq!(
+            err,
+            OpenApiError::UnknownTarget {
+                name: "ship".to_string(),
+                target: "shop:orders#ship".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_options_fill_info_and_servers() {
+        let dist = application(json!({}));
+        let options = OpenApiOptions {
+            title: Some("Shop API".to_string()),
+            version: Some("2.1.0".to_string()),
+            servers: vec!["https://api.example.com".to_string()],
+        };
+        let doc = generate_spec(&dist, &options).unwrap().document;
+        assert_eq!(
+            doc["info"],
+            json!({ "title": "Shop API", "version": "2.1.0" })
+        );
+        assert_eq!(
+            doc["servers"],
+            json!([{ "url": "https://api.example.com" }])
+        );
+        assert!(doc.get("components").is_none());
+    }
+}
@@ -0,0 +1,259 @@
+//! Structural comparison of runtime values
+//!
+//! Finds the places where an actual value differs from the expected one, so
+//! a failed comparison can point at the fields that differ instead of
+//! printing both values in full.
+//!
+//! ```ignore
+//! for difference in morphir_runtime::diff(&expected, &actual) {
+//!     eprintln!("{}", difference);
+//! }
+//! // .lines[1].quantity: expected 2, got 3
+//! ```
+
+use std::fmt;
+
+use morphir_core::ir::diff::Segment;
+use morphir_core::naming::Name;
+
+use crate::key;
+use crate::value::RuntimeValue;
+
+/// One step from a value into one of its parts
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    /// Record field, by kebab-case field name
+    Field(String),
+    /// List element
+    Index(usize),
+    /// Tuple element or constructor argument
+    Position(usize),
+    /// `Dict` entry, by key
+    Key(RuntimeValue),
+}
+
+impl Segment for PathSegment {
+    const ROOT: &'static str = "value";
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Field(name) => write!(f, ".{}", Name::from(name.as_str()).to_camel_case()),
+            PathSegment::Index(i) => write!(f, "[{}]", i),
+            PathSegment::Position(i) => write!(f, ".{}", i),
+            PathSegment::Key(key) => write!(f, "[{}]", key),
+        }
+    }
+}
+
+/// A place where the actual value differs from the expected one
+pub type Difference = morphir_core::ir::diff::Difference<PathSegment, RuntimeValue>;
+
+/// Compare `actual` against `expected`, returning every difference.
+///
+/// Records are compared field by field, lists element by element, dicts by
+/// key, and tuples and constructors argument by argument when their shapes
+/// match. Numbers compare by value across `Int`, `Float`, and `Decimal`, as
+/// with `==`. Equal values yield no differences.
+pub fn diff(expected: &RuntimeValue, actual: &RuntimeValue) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    let mut path = Vec::new();
+    diff_into(expected, actual, &mut path, &mut differences);
+    differences
+}
+
+fn push(
+    differences: &mut Vec<Difference>,
+    path: &[PathSegment],
+    expected: Option<&RuntimeValue>,
+    actual: Option<&RuntimeValue>,
+) {
+    differences.push(Difference {
+        path: path.to_vec(),
+        expected: expected.cloned(),
+        actual: actual.cloned(),
+    });
+}
+
+/// Compare the parts of two values found at `segment`; a part present on
+/// only one side is a difference in itself.
+fn child(
+    segment: PathSegment,
+    expected: Option<&RuntimeValue>,
+    actual: Option<&RuntimeValue>,
+    path: &mut Vec<PathSegment>,
+    differences: &mut Vec<Difference>,
+) {
+    path.push(segment);
+    match (expected, actual) {
+        (Some(e), Some(a)) => diff_into(e, a, path, differences),
+        _ => push(differences, path, expected, actual),
+    }
+    path.pop();
+}
+
+/// Value of the `Dict` entry with the given key
+fn lookup<'v>(
+    entries: &'v [(RuntimeValue, RuntimeValue)],
+    key: &RuntimeValue,
+) -> Option<&'v RuntimeValue> {
+    entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+fn diff_into(
+    expected: &RuntimeValue,
+    actual: &RuntimeValue,
+    path: &mut Vec<PathSegment>,
+    differences: &mut Vec<Difference>,
+) {
+    if expected == actual {
+        return;
+    }
+    match (expected, actual) {
+        (RuntimeValue::Record(e), RuntimeValue::Record(a)) => {
+            for (name, value) in e {
+                child(
+                    PathSegment::Field(name.clone()),
+                    Some(value),
+                    a.get(name),
+                    path,
+                    differences,
+                );
+            }
+            for (name, value) in a.iter().filter(|(name, _)| !e.contains_key(*name)) {
+                child(
+                    PathSegment::Field(name.clone()),
+                    None,
+                    Some(value),
+                    path,
+                    differences,
+                );
+            }
+        }
+        (RuntimeValue::List(e), RuntimeValue::List(a)) => {
+            for i in 0..e.len().max(a.len()) {
+                child(PathSegment::Index(i), e.get(i), a.get(i), path, differences);
+            }
+        }
+        (RuntimeValue::Tuple(e), RuntimeValue::Tuple(a)) if e.len() == a.len() => {
+            for (i, (e, a)) in e.iter().zip(a).enumerate() {
+                child(
+                    PathSegment::Position(i),
+                    Some(e),
+                    Some(a),
+                    path,
+                    differences,
+                );
+            }
+        }
+        (RuntimeValue::Constructor(n, e), RuntimeValue::Constructor(m, a))
+            if key(n) == key(m) && e.len() == a.len() =>
+        {
+            for (i, (e, a)) in e.iter().zip(a).enumerate() {
+                child(
+                    PathSegment::Position(i),
+                    Some(e),
+                    Some(a),
+                    path,
+                    differences,
+                );
+            }
+        }
+        (RuntimeValue::Dict(e), RuntimeValue::Dict(a)) => {
+            for (k, v) in e {
+                child(
+                    PathSegment::Key(k.clone()),
+                    Some(v),
+                    lookup(a, k),
+                    path,
+                    differences,
+                );
+            }
+            for (k, v) in a.iter().filter(|(k, _)| lookup(e, k).is_none()) {
+                child(
+                    PathSegment::Key(k.clone()),
+                    None,
+                    Some(v),
+                    path,
+                    differences,
+                );
+            }
+        }
+        _ => push(differences, path, Some(expected), Some(actual)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+
+    fn record(fields: &[(&str, RuntimeValue)]) -> RuntimeValue {
+        RuntimeValue::Record(
+            fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect::<IndexMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn test_equal_values_have_no_differences() {
+        let value = record(&[("total", RuntimeValue::Int(3))]);
+        assert!(diff(&value, &value).is_empty());
+        assert!(diff(&RuntimeValue::Int(2), &RuntimeValue::Float(2.0)).is_empty());
+    }
+
+    #[test]
+    fn test_differences_point_at_nested_fields() {
+        let line = |quantity| {
+            record(&[
+                ("sku", RuntimeValue::String("A-1".into())),
+                (
+                    "unit-price",
+                    RuntimeValue::just(RuntimeValue::Int(quantity)),
+                ),
+            ])
+        };
+        let expected = record(&[
+            ("lines", RuntimeValue::List(vec![line(1), line(2)])),
+            ("note", RuntimeValue::String("rush".into())),
+        ]);
+        let actual = record(&[
+            ("lines", RuntimeValue::List(vec![line(1), line(3), line(4)])),
+            ("total", RuntimeValue::Int(9)),
+        ]);
+
+        let messages: Vec<String> = diff(&expected, &actual)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ".lines[1].unitPrice.0: expected 2, got 3",
+                ".lines[2]: unexpected { sku = \"A-1\", unitPrice = Just 4 }",
+                ".note: missing, expected \"rush\"",
+                ".total: unexpected 9",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_different_shapes_differ_at_the_top() {
+        let differences = diff(
+            &RuntimeValue::just(RuntimeValue::Int(1)),
+            &RuntimeValue::nothing(),
+        );
+        assert_eq!(differences.len(), 1);
+        assert_eq!(
+            differences[0].to_string(),
+            "value: expected Just 1, got Nothing"
+        );
+
+        let dict = |value| RuntimeValue::Dict(vec![(RuntimeValue::String("a".into()), value)]);
+        let differences = diff(&dict(RuntimeValue::Int(1)), &dict(RuntimeValue::Int(2)));
+        assert_eq!(differences[0].to_string(), "[\"a\"]: expected 1, got 2");
+    }
+}
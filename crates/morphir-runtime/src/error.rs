@@ -0,0 +1,43 @@
+//! Error types for IR evaluation
+
+use thiserror::Error;
+
+/// Result type alias for evaluation
+pub type Result<T> = std::result::Result<T, EvalError>;
+
+/// Errors raised while evaluating Morphir IR
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum EvalError {
+    #[error("Unknown value: {0}")]
+    UnknownValue(String),
+
+    #[error("Variable `{0}` is not in scope")]
+    UnboundVariable(String),
+
+    #[error("Type error: {0}")]
+    TypeError(String),
+
+    #[error("No pattern matched the value {0}")]
+    PatternMatchFailure(String),
+
+    #[error("No body available for {0}: {1}")]
+    MissingBody(String, String),
+
+    #[error("Reached a hole: {0}")]
+    Hole(String),
+
+    #[error("Native function {name} failed: {message}")]
+    Native { name: String, message: String },
+
+    #[error("Maximum call depth of {0} exceeded")]
+    DepthLimit(usize),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+}
+
+impl EvalError {
+    pub(crate) fn type_error(expected: &str, found: &impl std::fmt::Display) -> Self {
+        EvalError::TypeError(format!("expected {}, found {}", expected, found))
+    }
+}
@@ -0,0 +1,1253 @@
+//! Tree-walking evaluator for V4 IR values
+//!
+//! The [`Evaluator`] indexes the value definitions, constructors, and type
+//! aliases of a distribution and interprets value expressions directly.
+//! Functions are curried: applying a function to fewer arguments than it
+//! takes yields a partially applied [`RuntimeValue::Function`]. SDK functions
+//! (`Basics`, `List`, `Dict`, `String`, ...) are implemented natively, see
+//! [`crate::native`].
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use morphir_core::ir::v4::{
+    Distribution, Field, HoleReason, LetBinding, Literal, PackageDefinition, PackageSpecification,
+    Pattern, PatternCase, RecordFieldEntry, Type, TypeDefinition, TypeSpecification, Value,
+    ValueBody, ValueDefinition,
+};
+use morphir_core::naming::{FQName, Name, Path};
+use rust_decimal::Decimal;
+
+use crate::error::{EvalError, Result};
+use crate::key;
+use crate::native::{self, NativeFunction};
+use crate::value::{Closure, Function, RuntimeValue};
+
+/// Default limit on nested function calls
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+const BOOL: &str = "morphir/sdk:basics#bool";
+const INT: &str = "morphir/sdk:basics#int";
+const FLOAT: &str = "morphir/sdk:basics#float";
+const CHAR: &str = "morphir/sdk:char#char";
+const STRING: &str = "morphir/sdk:string#string";
+const DECIMAL: &str = "morphir/sdk:decimal#decimal";
+const LIST: &str = "morphir/sdk:list#list";
+const MAYBE: &str = "morphir/sdk:maybe#maybe";
+const DICT: &str = "morphir/sdk:dict#dict";
+
+/// Local variable scope; a persistent linked list shared by closures
+#[derive(Clone, Default)]
+pub(crate) struct Env(Option<Arc<Frame>>);
+
+enum Frame {
+    Bind {
+        name: String,
+        value: RuntimeValue,
+        parent: Env,
+    },
+    /// Mutually recursive `let` definitions; closures created from them
+    /// capture the frame itself, so they can refer to each other.
+    Recursive {
+        definitions: Vec<(String, Arc<ValueDefinition>)>,
+        parent: Env,
+    },
+}
+
+enum Found {
+    Value(RuntimeValue),
+    Recursive(Arc<ValueDefinition>, Env),
+}
+
+impl Env {
+    fn bind(&self, name: String, value: RuntimeValue) -> Env {
+        Env(Some(Arc::new(Frame::Bind {
+            name,
+            value,
+            parent: self.clone(),
+        })))
+    }
+
+    fn recursive(&self, bindings: &[LetBinding]) -> Env {
+        let definitions = bindings
+            .iter()
+            .map(|LetBinding(name, def)| (name.to_kebab_case(), Arc::new(def.clone())))
+            .collect();
+        Env(Some(Arc::new(Frame::Recursive {
+            definitions,
+            parent: self.clone(),
+        })))
+    }
+
+    fn lookup(&self, name: &str) -> Option<Found> {
+        let mut env = self;
+        while let Some(frame) = &env.0 {
+            match frame.as_ref() {
+                Frame::Bind {
+                    name: bound,
+                    value,
+                    parent,
+                } => {
+                    if bound == name {
+                        return Some(Found::Value(value.clone()));
+                    }
+                    env = parent;
+                }
+                Frame::Recursive {
+                    definitions,
+                    parent,
+                } => {
+                    if let Some((_, def)) = definitions.iter().find(|(n, _)| n == name) {
+                        return Some(Found::Recursive(def.clone(), env.clone()));
+                    }
+                    env = parent;
+                }
+            }
+        }
+        None
+    }
+}
+
+struct CustomType<'a> {
+    params: Vec<String>,
+    constructors: Vec<(FQName, Vec<&'a Type>)>,
+}
+
+/// Decrements the call depth when a call returns, including on error.
+struct DepthGuard<'e>(&'e Cell<usize>);
+
+impl Drop for DepthGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+/// Interpreter for the value definitions of a distribution
+pub struct Evaluator<'a> {
+    values: HashMap<String, (FQName, &'a ValueDefinition)>,
+    aliases: HashMap<String, (Vec<String>, &'a Type)>,
+    custom_types: HashMap<String, CustomType<'a>>,
+    /// Values known only from a specification, with the package providing them
+    specified: HashMap<String, String>,
+    constants: RefCell<HashMap<String, RuntimeValue>>,
+    depth: Cell<usize>,
+    max_depth: usize,
+    /// Module whose values unbound variables refer to
+    open_module: Option<(Path, Path)>,
+}
+
+impl<'a> Evaluator<'a> {
+    /// Create an evaluator for a distribution.
+    ///
+    /// Values of the distribution's own package can be evaluated; dependency
+    /// specifications contribute constructors and type aliases used when
+    /// converting inputs. Evaluating a value that only has a specification
+    /// fails with [`EvalError::MissingBody`].
+    pub fn new(dist: &'a Distribution) -> Self {
+        let mut evaluator = Self {
+            values: HashMap::new(),
+            aliases: HashMap::new(),
+            custom_types: HashMap::new(),
+            specified: HashMap::new(),
+            constants: RefCell::new(HashMap::new()),
+            depth: Cell::new(0),
+            max_depth: DEFAULT_MAX_DEPTH,
+            open_module: None,
+        };
+
+        let (package, dependencies, def) = (
+            &dist.package_name().0,
+            dist.dependencies(),
+            dist.definition(),
+        );
+
+        for (dependency_key, spec) in dependencies {
+            evaluator.index_specification(&Path::new(dependency_key), spec);
+        }
+        if let Distribution::Specs(specs) = dist {
+            evaluator.index_specification(package, &specs.spec);
+        }
+
+        if let Some(def) = def {
+            evaluator.index_package(package, def);
+        }
+        evaluator
+    }
+
+    /// Index the types of a package specification, and remember its values
+    /// as known but without bodies.
+    fn index_specification(&mut self, package: &Path, spec: &'a PackageSpecification) {
+        for (module_key, module) in &spec.modules {
+            for (type_key, type_spec) in &module.types {
+                let type_name = member(package, module_key, type_key);
+                match type_spec {
+                    TypeSpecification::TypeAliasSpecification {
+                        type_params,
+                        type_expr,
+                    } => {
+                        self.aliases
+                            .insert(key(&type_name), (params(type_params), type_expr));
+                    }
+                    TypeSpecification::CustomTypeSpecification {
+                        type_params,
+                        constructors,
+                    } => {
+                        let constructors = constructors
+                            .iter()
+                            .map(|c| {
+                                (
+                                    member(package, module_key, &c.name.to_kebab_case()),
+                                    c.args.iter().map(|a| &a.arg_type).collect(),
+                                )
+                            })
+                            .collect();
+                        self.custom_types.insert(
+                            key(&type_name),
+                            CustomType {
+                                params: params(type_params),
+                                constructors,
+                            },
+                        );
+                    }
+                    TypeSpecification::OpaqueTypeSpecification { .. } => {}
+                }
+            }
+            for value_key in module.values.keys() {
+                self.specified.insert(
+                    key(&member(package, module_key, value_key)),
+                    package.to_string(),
+                );
+            }
+        }
+    }
+
+    fn index_package(&mut self, package: &Path, def: &'a PackageDefinition) {
+        for (module_key, module) in &def.modules {
+            for (type_key, type_def) in &module.value.types {
+                let type_name = member(package, module_key, type_key);
+                match &type_def.value {
+                    TypeDefinition::TypeAliasDefinition {
+                        type_params,
+                        type_expr,
+                    } => {
+                        self.aliases
+                            .insert(key(&type_name), (params(type_params), type_expr));
+                    }
+                    TypeDefinition::CustomTypeDefinition {
+                        type_params,
+                        constructors,
+                    } => {
+                        let constructors = constructors
+                            .value
+                            .iter()
+                            .map(|c| {
+                                (
+                                    member(package, module_key, &c.name.to_kebab_case()),
+                                    c.args.iter().map(|a| &a.arg_type).collect(),
+                                )
+                            })
+                            .collect();
+                        self.custom_types.insert(
+                            key(&type_name),
+                            CustomType {
+                                params: params(type_params),
+                                constructors,
+                            },
+                        );
+                    }
+                    TypeDefinition::IncompleteTypeDefinition { .. } => {}
+                }
+            }
+            for (value_key, value_def) in &module.value.values {
+                let fqname = member(package, module_key, value_key);
+                self.values.insert(key(&fqname), (fqname, &value_def.value));
+            }
+        }
+    }
+
+    /// Set the maximum depth of nested function calls
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Resolve variables that are not bound locally to the values of a
+    /// module, as the module's own definitions see each other
+    pub fn with_open_module(mut self, package: Path, module: Path) -> Self {
+        self.open_module = Some((package, module));
+        self
+    }
+
+    /// Look up a value definition of the distribution
+    pub fn definition(&self, fqname: &FQName) -> Option<&'a ValueDefinition> {
+        self.values.get(&key(fqname)).map(|(_, def)| *def)
+    }
+
+    /// Evaluate a value definition applied to the given arguments.
+    ///
+    /// Fewer arguments than the definition takes yield a partially applied
+    /// function; extra arguments are applied to the result.
+    pub fn evaluate(&self, fqname: &FQName, args: Vec<RuntimeValue>) -> Result<RuntimeValue> {
+        let mut result = self.reference(fqname)?;
+        for arg in args {
+            result = self.apply(&result, arg)?;
+        }
+        Ok(result)
+    }
+
+    /// Evaluate a closed expression
+    pub fn evaluate_value(&self, value: &Value) -> Result<RuntimeValue> {
+        self.eval(value, &Env::default())
+    }
+
+    /// Evaluate an expression whose free variables are bound to `bindings`
+    pub fn evaluate_value_with(
+        &self,
+        value: &Value,
+        bindings: &[(String, RuntimeValue)],
+    ) -> Result<RuntimeValue> {
+        let env = bindings.iter().fold(Env::default(), |env, (name, value)| {
+            env.bind(normalize(name), value.clone())
+        });
+        self.eval(value, &env)
+    }
+
+    /// Apply a function value to one argument
+    pub fn apply(&self, function: &RuntimeValue, arg: RuntimeValue) -> Result<RuntimeValue> {
+        match function {
+            RuntimeValue::Function(closure) => {
+                let mut closure = Closure::clone(closure);
+                closure.args.push(arg);
+                if closure.remaining() == 0 {
+                    self.call(closure)
+                } else {
+                    Ok(RuntimeValue::Function(Arc::new(closure)))
+                }
+            }
+            RuntimeValue::Constructor(fqname, args) => {
+                let mut args = args.clone();
+                args.push(arg);
+                Ok(RuntimeValue::Constructor(fqname.clone(), args))
+            }
+            other => Err(EvalError::type_error("a function", &other.kind())),
+        }
+    }
+
+    /// Apply a function value to two arguments
+    pub fn apply2(
+        &self,
+        function: &RuntimeValue,
+        a: RuntimeValue,
+        b: RuntimeValue,
+    ) -> Result<RuntimeValue> {
+        let partial = self.apply(function, a)?;
+        self.apply(&partial, b)
+    }
+
+    /// Run a closure whose arguments are all available
+    fn call(&self, closure: Closure) -> Result<RuntimeValue> {
+        let depth = self.depth.get() + 1;
+        if depth > self.max_depth {
+            return Err(EvalError::DepthLimit(self.max_depth));
+        }
+        self.depth.set(depth);
+        let _guard = DepthGuard(&self.depth);
+
+        let Closure { function, args } = closure;
+        match function {
+            Function::Lambda { pattern, body, env } => {
+                let arg = args.into_iter().next().unwrap_or(RuntimeValue::Unit);
+                match self.match_pattern(&pattern, &arg, env)? {
+                    Some(env) => self.eval(&body, &env),
+                    None => Err(EvalError::PatternMatchFailure(arg.to_string())),
+                }
+            }
+            Function::Local { params, body, env } => {
+                let env = params
+                    .into_iter()
+                    .zip(args)
+                    .fold(env, |env, (name, value)| env.bind(name, value));
+                self.eval(&body, &env)
+            }
+            Function::Global { key, .. } => {
+                let (fqname, def) = self
+                    .values
+                    .get(&key)
+                    .ok_or_else(|| EvalError::UnknownValue(key.clone()))?;
+                self.run_definition(fqname, def, args)
+            }
+            Function::Native { name, .. } => {
+                let native = native::lookup(name)
+                    .ok_or_else(|| EvalError::UnknownValue(name.to_string()))?;
+                (native.run)(self, args)
+            }
+            Function::Field(field) => {
+                let record = args.into_iter().next().unwrap_or(RuntimeValue::Unit);
+                get_field(&record, &field)
+            }
+        }
+    }
+
+    /// Run a top-level definition with all of its arguments
+    fn run_definition(
+        &self,
+        fqname: &FQName,
+        def: &ValueDefinition,
+        args: Vec<RuntimeValue>,
+    ) -> Result<RuntimeValue> {
+        match &def.body {
+            ValueBody::Expression(body) => {
+                let env = def
+                    .input_types
+                    .keys()
+                    .zip(args)
+                    .fold(Env::default(), |env, (name, value)| {
+                        env.bind(Name::from(name.as_str()).to_kebab_case(), value)
+                    });
+                self.eval(body, &env)
+            }
+            ValueBody::Native(_) => match native::lookup(&key(fqname)) {
+                Some(native) => (native.run)(self, args),
+                None => Err(EvalError::MissingBody(
+                    fqname.to_canonical_string(),
+                    "native body with no registered implementation".to_string(),
+                )),
+            },
+            ValueBody::External {
+                external_name,
+                target_platform,
+            } => Err(EvalError::MissingBody(
+                fqname.to_canonical_string(),
+                format!("external `{}` on {}", external_name, target_platform),
+            )),
+            ValueBody::Incomplete(reason) => Err(EvalError::Hole(format!(
+                "{} is incomplete ({})",
+                fqname.to_canonical_string(),
+                describe_hole(reason)
+            ))),
+        }
+    }
+
+    fn reference(&self, fqname: &FQName) -> Result<RuntimeValue> {
+        let key = key(fqname);
+        if let Some((fqname, def)) = self.values.get(&key) {
+            let arity = def.input_types.len();
+            if arity > 0 {
+                return Ok(function(Function::Global { key, arity }));
+            }
+            if let Some(value) = self.constants.borrow().get(&key) {
+                return Ok(value.clone());
+            }
+            let value = self.run_definition(fqname, def, Vec::new())?;
+            self.constants.borrow_mut().insert(key, value.clone());
+            return Ok(value);
+        }
+        match native::lookup(&key) {
+            Some(native) => self.native_value(native),
+            None => Err(self.unknown_value(fqname)),
+        }
+    }
+
+    /// Error for a reference without a definition. Values only known from a
+    /// specification get a message saying so.
+    fn unknown_value(&self, fqname: &FQName) -> EvalError {
+        match self.specified.get(&key(fqname)) {
+            Some(package) => EvalError::MissingBody(
+                fqname.to_canonical_string(),
+                format!(
+                    "package `{}` is only available as a specification; load its full distribution to evaluate it",
+                    package
+                ),
+            ),
+            None => EvalError::UnknownValue(fqname.to_canonical_string()),
+        }
+    }
+
+    fn native_value(&self, native: &'static NativeFunction) -> Result<RuntimeValue> {
+        if native.arity == 0 {
+            (native.run)(self, Vec::new())
+        } else {
+            Ok(function(Function::Native {
+                name: native.name,
+                arity: native.arity,
+            }))
+        }
+    }
+
+    /// Turn a `let` definition into a value in the given scope
+    fn local_definition(&self, def: &ValueDefinition, env: Env) -> Result<RuntimeValue> {
+        let ValueBody::Expression(body) = &def.body else {
+            return Err(EvalError::MissingBody(
+                "let definition".to_string(),
+                "only expression bodies can be evaluated".to_string(),
+            ));
+        };
+        if def.input_types.is_empty() {
+            return self.eval(body, &env);
+        }
+        Ok(function(Function::Local {
+            params: def.input_types.keys().map(|n| normalize(n)).collect(),
+            body: Arc::new(body.clone()),
+            env,
+        }))
+    }
+
+    fn eval(&self, value: &Value, env: &Env) -> Result<RuntimeValue> {
+        match value {
+            Value::Literal(_, literal) => literal_value(literal),
+            Value::Constructor(_, fqname) => Ok(RuntimeValue::Constructor(fqname.clone(), vec![])),
+            Value::Tuple(_, elements) => Ok(RuntimeValue::Tuple(self.eval_all(elements, env)?)),
+            Value::List(_, items) => Ok(RuntimeValue::List(self.eval_all(items, env)?)),
+            Value::Record(_, fields) => {
+                let mut record = IndexMap::new();
+                for RecordFieldEntry(name, v) in fields {
+                    record.insert(name.to_kebab_case(), self.eval(v, env)?);
+                }
+                Ok(RuntimeValue::Record(record))
+            }
+            Value::Variable(_, name) => {
+                let name = name.to_kebab_case();
+                match env.lookup(&name) {
+                    Some(Found::Value(value)) => Ok(value),
+                    Some(Found::Recursive(def, scope)) => self.local_definition(&def, scope),
+                    None => {
+                        let member = self.open_module.as_ref().map(|(package, module)| {
+                            FQName::new(package.clone(), module.clone(), Name::from(name.as_str()))
+                        });
+                        match member {
+                            Some(member) if self.values.contains_key(&key(&member)) => {
+                                self.reference(&member)
+                            }
+                            _ => Err(EvalError::UnboundVariable(name)),
+                        }
+                    }
+                }
+            }
+            Value::Reference(_, fqname) => self.reference(fqname),
+            Value::Field(_, record, name) => {
+                let record = self.eval(record, env)?;
+                get_field(&record, &name.to_kebab_case())
+            }
+            Value::FieldFunction(_, name) => Ok(function(Function::Field(name.to_kebab_case()))),
+            Value::Apply(_, f, arg) => {
+                let f = self.eval(f, env)?;
+                let arg = self.eval(arg, env)?;
+                self.apply(&f, arg)
+            }
+            Value::Lambda(_, pattern, body) => Ok(function(Function::Lambda {
+                pattern: Arc::new(pattern.clone()),
+                body: Arc::new(body.as_ref().clone()),
+                env: env.clone(),
+            })),
+            Value::LetDefinition(_, name, def, body) => {
+                let value = self.local_definition(def, env.clone())?;
+                self.eval(body, &env.bind(name.to_kebab_case(), value))
+            }
+            Value::LetRecursion(_, bindings, body) => self.eval(body, &env.recursive(bindings)),
+            Value::Destructure(_, pattern, value, body) => {
+                let value = self.eval(value, env)?;
+                match self.match_pattern(pattern, &value, env.clone())? {
+                    Some(env) => self.eval(body, &env),
+                    None => Err(EvalError::PatternMatchFailure(value.to_string())),
+                }
+            }
+            Value::IfThenElse(_, condition, then_branch, else_branch) => {
+                match self.eval(condition, env)? {
+                    RuntimeValue::Bool(true) => self.eval(then_branch, env),
+                    RuntimeValue::Bool(false) => self.eval(else_branch, env),
+                    other => Err(EvalError::type_error("a Bool", &other.kind())),
+                }
+            }
+            Value::PatternMatch(_, subject, cases) => {
+                let subject = self.eval(subject, env)?;
+                for PatternCase(pattern, body) in cases {
+                    if let Some(env) = self.match_pattern(pattern, &subject, env.clone())? {
+                        return self.eval(body, &env);
+                    }
+                }
+                Err(EvalError::PatternMatchFailure(subject.to_string()))
+            }
+            Value::UpdateRecord(_, record, updates) => {
+                let RuntimeValue::Record(mut fields) = self.eval(record, env)? else {
+                    return Err(EvalError::type_error("a record", &"another value"));
+                };
+                for RecordFieldEntry(name, v) in updates {
+                    let name = name.to_kebab_case();
+                    if !fields.contains_key(&name) {
+                        return Err(EvalError::TypeError(format!(
+                            "record has no field `{}`",
+                            name
+                        )));
+                    }
+                    fields.insert(name, self.eval(v, env)?);
+                }
+                Ok(RuntimeValue::Record(fields))
+            }
+            Value::Unit(_) => Ok(RuntimeValue::Unit),
+            Value::Hole(_, reason, _) => Err(EvalError::Hole(describe_hole(reason))),
+            Value::Native(_, fqname, _) => match native::lookup(&key(fqname)) {
+                Some(native) => self.native_value(native),
+                None => Err(EvalError::MissingBody(
+                    fqname.to_canonical_string(),
+                    "native value with no registered implementation".to_string(),
+                )),
+            },
+            Value::External(_, name, platform) => Err(EvalError::MissingBody(
+                name.clone(),
+                format!("external on {}", platform),
+            )),
+        }
+    }
+
+    fn eval_all(&self, values: &[Value], env: &Env) -> Result<Vec<RuntimeValue>> {
+        values.iter().map(|v| self.eval(v, env)).collect()
+    }
+
+    /// Match a value against a pattern, returning the scope extended with
+    /// the pattern's variables, or `None` if it does not match.
+    fn match_pattern(
+        &self,
+        pattern: &Pattern,
+        value: &RuntimeValue,
+        env: Env,
+    ) -> Result<Option<Env>> {
+        match (pattern, value) {
+            (Pattern::WildcardPattern(_), _) => Ok(Some(env)),
+            (Pattern::AsPattern(_, inner, name), _) => Ok(self
+                .match_pattern(inner, value, env)?
+                .map(|env| env.bind(name.to_kebab_case(), value.clone()))),
+            (Pattern::TuplePattern(_, patterns), RuntimeValue::Tuple(values))
+                if patterns.len() == values.len() =>
+            {
+                self.match_all(patterns, values, env)
+            }
+            (
+                Pattern::ConstructorPattern(_, fqname, patterns),
+                RuntimeValue::Constructor(c, values),
+            ) if key(fqname) == key(c) && patterns.len() == values.len() => {
+                self.match_all(patterns, values, env)
+            }
+            (Pattern::EmptyListPattern(_), RuntimeValue::List(items)) if items.is_empty() => {
+                Ok(Some(env))
+            }
+            (Pattern::HeadTailPattern(_, head, tail), RuntimeValue::List(items))
+                if !items.is_empty() =>
+            {
+                let Some(env) = self.match_pattern(head, &items[0], env)? else {
+                    return Ok(None);
+                };
+                self.match_pattern(tail, &RuntimeValue::List(items[1..].to_vec()), env)
+            }
+            (Pattern::LiteralPattern(_, literal), _) => {
+                Ok((literal_value(literal)? == *value).then_some(env))
+            }
+            (Pattern::UnitPattern(_), RuntimeValue::Unit) => Ok(Some(env)),
+            _ => Ok(None),
+        }
+    }
+
+    fn match_all(
+        &self,
+        patterns: &[Pattern],
+        values: &[RuntimeValue],
+        env: Env,
+    ) -> Result<Option<Env>> {
+        let mut env = env;
+        for (pattern, value) in patterns.iter().zip(values) {
+            match self.match_pattern(pattern, value, env)? {
+                Some(next) => env = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(env))
+    }
+
+    // -------------------------------------------------------------------------
+    // Inputs
+    // -------------------------------------------------------------------------
+
+    /// Convert JSON arguments for a definition, guided by its input types
+    pub fn args_from_json(
+        &self,
+        fqname: &FQName,
+        args: &[serde_json::Value],
+    ) -> Result<Vec<RuntimeValue>> {
+        let def = self
+            .definition(fqname)
+            .ok_or_else(|| self.unknown_value(fqname))?;
+        let mut types = def.input_types.values().map(|entry| &entry.input_type);
+        args.iter()
+            .map(|arg| match types.next() {
+                Some(tpe) => self.value_from_json(arg, tpe),
+                None => Ok(RuntimeValue::from_json(arg)),
+            })
+            .collect()
+    }
+
+    /// Convert a JSON result for a definition, guided by its output type
+    pub fn output_from_json(
+        &self,
+        fqname: &FQName,
+        json: &serde_json::Value,
+    ) -> Result<RuntimeValue> {
+        let def = self
+            .definition(fqname)
+            .ok_or_else(|| self.unknown_value(fqname))?;
+        self.value_from_json(json, &def.output_type)
+    }
+
+    /// Convert JSON to a runtime value of the given type.
+    ///
+    /// Accepts the encoding produced by [`RuntimeValue::to_json`]; decimals
+    /// may also be given as JSON numbers and `Dict`s as objects.
+    pub fn value_from_json(&self, json: &serde_json::Value, tpe: &Type) -> Result<RuntimeValue> {
+        self.convert_json(json, tpe, 0)
+    }
+
+    fn convert_json(
+        &self,
+        json: &serde_json::Value,
+        tpe: &Type,
+        depth: usize,
+    ) -> Result<RuntimeValue> {
+        use serde_json::Value as Json;
+        let invalid = |expected: &str| {
+            EvalError::InvalidInput(format!("expected {}, found {}", expected, json))
+        };
+        if depth > self.max_depth {
+            return Err(EvalError::DepthLimit(self.max_depth));
+        }
+        match tpe {
+            Type::Reference(_, fqname, args) => {
+                let type_key = key(fqname);
+                match (type_key.as_str(), json) {
+                    (INT, _) => json
+                        .as_i64()
+                        .map(RuntimeValue::Int)
+                        .ok_or_else(|| invalid("an Int")),
+                    (FLOAT, _) => json
+                        .as_f64()
+                        .map(RuntimeValue::Float)
+                        .ok_or_else(|| invalid("a Float")),
+                    (DECIMAL, Json::String(s)) => Decimal::from_str(s)
+                        .map(RuntimeValue::Decimal)
+                        .map_err(|_| invalid("a Decimal")),
+                    (DECIMAL, Json::Number(n)) => Decimal::from_str(&n.to_string())
+                        .map(RuntimeValue::Decimal)
+                        .map_err(|_| invalid("a Decimal")),
+                    (BOOL, Json::Bool(b)) => Ok(RuntimeValue::Bool(*b)),
+                    (STRING, Json::String(s)) => Ok(RuntimeValue::String(s.clone())),
+                    (CHAR, Json::String(s)) if s.chars().count() == 1 => {
+                        Ok(RuntimeValue::Char(s.chars().next().unwrap_or_default()))
+                    }
+                    (LIST, Json::Array(items)) if args.len() == 1 => Ok(RuntimeValue::List(
+                        items
+                            .iter()
+                            .map(|item| self.convert_json(item, &args[0], depth + 1))
+                            .collect::<Result<_>>()?,
+                    )),
+                    (MAYBE, Json::Null) => Ok(RuntimeValue::nothing()),
+                    (MAYBE, _) if args.len() == 1 => Ok(RuntimeValue::just(self.convert_json(
+                        json,
+                        &args[0],
+                        depth + 1,
+                    )?)),
+                    (DICT, _) if args.len() == 2 => self.dict_from_json(json, args, depth),
+                    (DECIMAL | BOOL | STRING | CHAR | LIST | MAYBE | DICT, _) => {
+                        Err(invalid(&fqname.local_name.to_title_case()))
+                    }
+                    _ => {
+                        if let Some((params, body)) = self.aliases.get(&type_key) {
+                            let body = substitute(body, params, args);
+                            return self.convert_json(json, &body, depth + 1);
+                        }
+                        if let Some(custom) = self.custom_types.get(&type_key) {
+                            return self.constructor_from_json(json, custom, args, depth);
+                        }
+                        Ok(RuntimeValue::from_json(json))
+                    }
+                }
+            }
+            Type::Tuple(_, elements) => match json {
+                Json::Array(items) if items.len() == elements.len() => Ok(RuntimeValue::Tuple(
+                    items
+                        .iter()
+                        .zip(elements)
+                        .map(|(item, t)| self.convert_json(item, t, depth + 1))
+                        .collect::<Result<_>>()?,
+                )),
+                _ => Err(invalid(&format!("a {}-tuple", elements.len()))),
+            },
+            Type::Record(_, fields) | Type::ExtensibleRecord(_, _, fields) => {
+                let Json::Object(object) = json else {
+                    return Err(invalid("a record"));
+                };
+                self.record_from_json(object, fields, depth)
+            }
+            Type::Unit(_) => Ok(RuntimeValue::Unit),
+            Type::Function(..) => Err(invalid("a function, which cannot be given as JSON")),
+            Type::Variable(..) => Ok(RuntimeValue::from_json(json)),
+        }
+    }
+
+    fn record_from_json(
+        &self,
+        object: &serde_json::Map<String, serde_json::Value>,
+        fields: &[Field],
+        depth: usize,
+    ) -> Result<RuntimeValue> {
+        let mut record = IndexMap::new();
+        for field in fields {
+            let json = object
+                .iter()
+                .find(|(k, _)| normalize(k) == field.name.to_kebab_case())
+                .map(|(_, v)| v)
+                .unwrap_or(&serde_json::Value::Null);
+            record.insert(
+                field.name.to_kebab_case(),
+                self.convert_json(json, &field.tpe, depth + 1)?,
+            );
+        }
+        Ok(RuntimeValue::Record(record))
+    }
+
+    fn dict_from_json(
+        &self,
+        json: &serde_json::Value,
+        args: &[Type],
+        depth: usize,
+    ) -> Result<RuntimeValue> {
+        use serde_json::Value as Json;
+        let mut dict = RuntimeValue::Dict(Vec::new());
+        let entries: Vec<(Json, &Json)> = match json {
+            Json::Object(object) => object
+                .iter()
+                .map(|(k, v)| (Json::String(k.clone()), v))
+                .collect(),
+            Json::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    Json::Array(pair) if pair.len() == 2 => Ok((pair[0].clone(), &pair[1])),
+                    _ => Err(EvalError::InvalidInput(format!(
+                        "expected a [key, value] pair, found {}",
+                        item
+                    ))),
+                })
+                .collect::<Result<_>>()?,
+            _ => {
+                return Err(EvalError::InvalidInput(format!(
+                    "expected a Dict, found {}",
+                    json
+                )));
+            }
+        };
+        for (k, v) in entries {
+            let k = self.convert_json(&k, &args[0], depth + 1)?;
+            let v = self.convert_json(v, &args[1], depth + 1)?;
+            dict = native::dict_insert(dict, k, v)?;
+        }
+        Ok(dict)
+    }
+
+    fn constructor_from_json(
+        &self,
+        json: &serde_json::Value,
+        custom: &CustomType,
+        type_args: &[Type],
+        depth: usize,
+    ) -> Result<RuntimeValue> {
+        use serde_json::Value as Json;
+        let (tag, values): (&str, &[Json]) = match json {
+            Json::String(tag) => (tag, &[]),
+            Json::Array(items) => match items.split_first() {
+                Some((Json::String(tag), rest)) => (tag, rest),
+                _ => {
+                    return Err(EvalError::InvalidInput(format!(
+                        "expected [\"Constructor\", args...], found {}",
+                        json
+                    )));
+                }
+            },
+            _ => {
+                return Err(EvalError::InvalidInput(format!(
+                    "expected a constructor name, found {}",
+                    json
+                )));
+            }
+        };
+        let tag_name = normalize(tag);
+        let (fqname, arg_types) = custom
+            .constructors
+            .iter()
+            .find(|(fqname, _)| fqname.local_name.to_kebab_case() == tag_name)
+            .ok_or_else(|| EvalError::InvalidInput(format!("unknown constructor `{}`", tag)))?;
+        if arg_types.len() != values.len() {
+            return Err(EvalError::InvalidInput(format!(
+                "constructor `{}` takes {} argument(s), found {}",
+                tag,
+                arg_types.len(),
+                values.len()
+            )));
+        }
+        let args = values
+            .iter()
+            .zip(arg_types)
+            .map(|(v, t)| {
+                let t = substitute(t, &custom.params, type_args);
+                self.convert_json(v, &t, depth + 1)
+            })
+            .collect::<Result<_>>()?;
+        Ok(RuntimeValue::Constructor(fqname.clone(), args))
+    }
+}
+
+fn function(function: Function) -> RuntimeValue {
+    RuntimeValue::Function(Arc::new(Closure::new(function)))
+}
+
+fn member(package: &Path, module_key: &str, local: &str) -> FQName {
+    FQName::new(package.clone(), Path::new(module_key), Name::from(local))
+}
+
+fn params(type_params: &[Name]) -> Vec<String> {
+    type_params.iter().map(Name::to_kebab_case).collect()
+}
+
+fn normalize(key: &str) -> String {
+    Name::from(key).to_kebab_case()
+}
+
+fn get_field(record: &RuntimeValue, field: &str) -> Result<RuntimeValue> {
+    match record {
+        RuntimeValue::Record(fields) => fields
+            .get(field)
+            .cloned()
+            .ok_or_else(|| EvalError::TypeError(format!("record has no field `{}`", field))),
+        other => Err(EvalError::type_error("a record", &other.kind())),
+    }
+}
+
+fn literal_value(literal: &Literal) -> Result<RuntimeValue> {
+    Ok(match literal {
+        Literal::Bool(b) => RuntimeValue::Bool(*b),
+        Literal::Char(c) => RuntimeValue::Char(*c),
+        Literal::String(s) => RuntimeValue::String(s.clone()),
+        Literal::Integer(n) => RuntimeValue::Int(*n),
+        Literal::Float(x) => RuntimeValue::Float(*x),
+        Literal::Decimal(s) => RuntimeValue::Decimal(
+            Decimal::from_str(s)
+                .map_err(|e| EvalError::InvalidInput(format!("decimal literal {}: {}", s, e)))?,
+        ),
+    })
+}
+
+fn describe_hole(reason: &HoleReason) -> String {
+    match reason {
+        HoleReason::UnresolvedReference { target } => {
+            format!("unresolved reference to {}", target.to_canonical_string())
+        }
+        HoleReason::DeletedDuringRefactor { tx_id } => {
+            format!("deleted during refactoring {}", tx_id)
+        }
+        HoleReason::TypeMismatch { expected, found } => {
+            format!("type mismatch: expected {}, found {}", expected, found)
+        }
+        HoleReason::Draft => "draft".to_string(),
+    }
+}
+
+/// Replace type parameters with type arguments
+fn substitute(tpe: &Type, params: &[String], args: &[Type]) -> Type {
+    let go = |t: &Type| substitute(t, params, args);
+    let fields = |fs: &[Field]| {
+        fs.iter()
+            .map(|f| Field {
+                name: f.name.clone(),
+                tpe: go(&f.tpe),
+            })
+            .collect()
+    };
+    match tpe {
+        Type::Variable(_, name) => params
+            .iter()
+            .position(|p| *p == name.to_kebab_case())
+            .and_then(|i| args.get(i))
+            .cloned()
+            .unwrap_or_else(|| tpe.clone()),
+        Type::Reference(a, fqname, type_args) => Type::Reference(
+            a.clone(),
+            fqname.clone(),
+            type_args.iter().map(go).collect(),
+        ),
+        Type::Tuple(a, elements) => Type::Tuple(a.clone(), elements.iter().map(go).collect()),
+        Type::Record(a, fs) => Type::Record(a.clone(), fields(fs)),
+        Type::ExtensibleRecord(a, name, fs) => {
+            Type::ExtensibleRecord(a.clone(), name.clone(), fields(fs))
+        }
+        Type::Function(a, arg, result) => {
+            Type::Function(a.clone(), Box::new(go(arg)), Box::new(go(result)))
+        }
+        Type::Unit(a) => Type::Unit(a.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_core::ir::v4::fixtures::{self, *};
+    use morphir_core::naming::PackageName;
+
+    /// Package `my/pkg`, module `orders`:
+    /// - `factorial n = if n <= 1 then 1 else n * factorial (n - 1)`
+    /// - `double = List.map (\x -> x * 2)`
+    /// - `status = Active | Held Int`
+    /// - `hold-days s = case s of Held d -> d; _ -> 0`
+    fn library() -> Distribution {
+        let factorial = Value::if_then_else(
+            attrs(),
+            call(
+                reference("morphir/sdk:basics#less-than-or-equal"),
+                vec![variable("n"), int_lit(1)],
+            ),
+            int_lit(1),
+            call(
+                reference("morphir/sdk:basics#multiply"),
+                vec![
+                    variable("n"),
+                    call(
+                        reference("my/pkg:orders#factorial"),
+                        vec![call(
+                            reference("morphir/sdk:basics#subtract"),
+                            vec![variable("n"), int_lit(1)],
+                        )],
+                    ),
+                ],
+            ),
+        );
+        let double = call(
+            reference("morphir/sdk:list#map"),
+            vec![Value::Lambda(
+                attrs(),
+                Pattern::AsPattern(
+                    attrs(),
+                    Box::new(Pattern::WildcardPattern(attrs())),
+                    Name::from("x"),
+                ),
+                Box::new(call(
+                    reference("morphir/sdk:basics#multiply"),
+                    vec![variable("x"), int_lit(2)],
+                )),
+            )],
+        );
+        let hold_days = Value::PatternMatch(
+            attrs(),
+            Box::new(variable("s")),
+            vec![
+                PatternCase(
+                    Pattern::ConstructorPattern(
+                        attrs(),
+                        fq("my/pkg:orders#held"),
+                        vec![Pattern::AsPattern(
+                            attrs(),
+                            Box::new(Pattern::WildcardPattern(attrs())),
+                            Name::from("d"),
+                        )],
+                    ),
+                    variable("d"),
+                ),
+                PatternCase(Pattern::WildcardPattern(attrs()), int_lit(0)),
+            ],
+        );
+
+        let mut dist = fixtures::library(vec![
+            (
+                "factorial",
+                ValueDefinition::new(vec![input("n", int())], int(), factorial),
+            ),
+            ("double", ValueDefinition::new(vec![], int(), double)),
+            (
+                "hold-days",
+                ValueDefinition::new(
+                    vec![input("s", named("my/pkg:orders#status"))],
+                    int(),
+                    hold_days,
+                ),
+            ),
+        ]);
+        orders(&mut dist)
+            .types
+            .insert("status".to_string(), public(status()));
+        dist
+    }
+
+    #[test]
+    fn test_recursive_definition() {
+        let dist = library();
+        let evaluator = Evaluator::new(&dist);
+        let result = evaluator
+            .evaluate(&fq("my/pkg:orders#factorial"), vec![RuntimeValue::Int(5)])
+            .unwrap();
+        assert_eq!(result, RuntimeValue::Int(120));
+    }
+
+    #[test]
+    fn test_partial_application_and_lambdas() {
+        let dist = library();
+        let evaluator = Evaluator::new(&dist);
+        let double = evaluator
+            .evaluate(&fq("my/pkg:orders#double"), vec![])
+            .unwrap();
+        assert!(matches!(double, RuntimeValue::Function(_)));
+
+        let result = evaluator
+            .apply(
+                &double,
+                RuntimeValue::List(vec![RuntimeValue::Int(1), RuntimeValue::Int(2)]),
+            )
+            .unwrap();
+        assert_eq!(result.to_string(), "[2, 4]");
+    }
+
+    #[test]
+    fn test_open_module_and_bindings() {
+        let dist = library();
+        let expression = call(variable("factorial"), vec![variable("n")]);
+        let bindings = [("n".to_string(), RuntimeValue::Int(4))];
+
+        let closed = Evaluator::new(&dist).evaluate_value_with(&expression, &bindings);
+        assert!(matches!(closed, Err(EvalError::UnboundVariable(name)) if name == "factorial"));
+
+        let evaluator =
+            Evaluator::new(&dist).with_open_module(Path::new("my/pkg"), Path::new("orders"));
+        let result = evaluator
+            .evaluate_value_with(&expression, &bindings)
+            .unwrap();
+        assert_eq!(result, RuntimeValue::Int(24));
+    }
+
+    #[test]
+    fn test_constructor_patterns_and_json_inputs() {
+        let dist = library();
+        let evaluator = Evaluator::new(&dist);
+        let hold_days = fq("my/pkg:orders#hold-days");
+
+        let held = evaluator
+            .args_from_json(&hold_days, &[serde_json::json!(["Held", 3])])
+            .unwrap();
+        assert_eq!(
+            evaluator.evaluate(&hold_days, held).unwrap(),
+            RuntimeValue::Int(3)
+        );
+
+        let active = evaluator
+            .args_from_json(&hold_days, &[serde_json::json!("Active")])
+            .unwrap();
+        assert_eq!(
+            evaluator.evaluate(&hold_days, active).unwrap(),
+            RuntimeValue::Int(0)
+        );
+        assert_eq!(
+            evaluator
+                .output_from_json(&hold_days, &serde_json::json!(0))
+                .unwrap(),
+            RuntimeValue::Int(0)
+        );
+
+        assert!(matches!(
+            evaluator.args_from_json(&hold_days, &[serde_json::json!("Closed")]),
+            Err(EvalError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_let_recursion() {
+        // let
+        //     count n = if n == 0 then 0 else 1 + count (n - 1)
+        // in
+        // count 3
+        let count = ValueDefinition::new(
+            vec![input("n", int())],
+            int(),
+            Value::if_then_else(
+                attrs(),
+                call(
+                    reference("morphir/sdk:basics#equal"),
+                    vec![variable("n"), int_lit(0)],
+                ),
+                int_lit(0),
+                call(
+                    reference("morphir/sdk:basics#add"),
+                    vec![
+                        int_lit(1),
+                        call(
+                            variable("count"),
+                            vec![call(
+                                reference("morphir/sdk:basics#subtract"),
+                                vec![variable("n"), int_lit(1)],
+                            )],
+                        ),
+                    ],
+                ),
+            ),
+        );
+        let value = Value::LetRecursion(
+            attrs(),
+            vec![LetBinding(Name::from("count"), count)],
+            Box::new(call(variable("count"), vec![int_lit(3)])),
+        );
+        let dist = library();
+        let result = Evaluator::new(&dist).evaluate_value(&value).unwrap();
+        assert_eq!(result, RuntimeValue::Int(3));
+    }
+
+    #[test]
+    fn test_errors() {
+        let dist = library();
+        let evaluator = Evaluator::new(&dist).with_max_depth(10);
+
+        assert!(matches!(
+            evaluator.evaluate(&fq("my/pkg:orders#factorial"), vec![RuntimeValue::Int(50)]),
+            Err(EvalError::DepthLimit(10))
+        ));
+        assert!(matches!(
+            evaluator.evaluate(&fq("my/pkg:orders#missing"), vec![]),
+            Err(EvalError::UnknownValue(_))
+        ));
+        assert!(matches!(
+            evaluator.evaluate_value(&variable("x")),
+            Err(EvalError::UnboundVariable(_))
+        ));
+        let hole = Value::Hole(attrs(), HoleReason::Draft, None);
+        assert!(matches!(
+            evaluator.evaluate_value(&hole),
+            Err(EvalError::Hole(_))
+        ));
+    }
+
+    #[test]
+    fn test_dependency_values_need_a_body() {
+        let mut dependency = library();
+        let Distribution::Library(lib) = &mut dependency else {
+            unreachable!()
+        };
+        lib.package_name = PackageName::new(Path::new("acme/math"));
+        let mut dist = library();
+        dist.add_dependency(&dependency);
+
+        let evaluator = Evaluator::new(&dist);
+        let err = evaluator
+            .evaluate(
+                &fq("acme/math:orders#factorial"),
+                vec![RuntimeValue::Int(3)],
+            )
+            .unwrap_err();
+        assert!(matches!(err, EvalError::MissingBody(..)));
+        assert!(
+            err.to_string()
+                .contains("only available as a specification")
+        );
+    }
+}
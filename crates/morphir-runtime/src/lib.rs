@@ -1,3 +1,39 @@
+//! Morphir Runtime - Evaluator for Morphir IR
+//!
+//! Interprets V4 IR value expressions so models can be executed directly,
+//! without generating code first.
+//!
+//! ```ignore
+//! use morphir_runtime::{Evaluator, RuntimeValue};
+//!
+//! let evaluator = Evaluator::new(&distribution);
+//! let total = evaluator.evaluate(&fqname, vec![RuntimeValue::Int(3)])?;
+//! println!("{}", total.pretty(80));
+//!
+//! for difference in morphir_runtime::diff(&expected, &total) {
+//!     eprintln!("{}", difference);
+//! }
+//! ```
+
+pub mod diff;
+pub mod error;
+pub mod eval;
+mod native;
+pub mod value;
+
+pub use diff::{Difference, PathSegment, diff};
+pub use error::{EvalError, Result};
+pub use eval::Evaluator;
+pub use value::RuntimeValue;
+
+use morphir_core::naming::FQName;
+
+/// Normalized lookup key for an FQName, independent of the name casing and
+/// SDK package spelling used in the IR (e.g. `morphir/sdk:list#filter-map`)
+pub(crate) fn key(fqname: &FQName) -> String {
+    fqname.to_normalized_string()
+}
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -0,0 +1,1483 @@
+//! Native implementations of Morphir SDK functions
+//!
+//! Functions are looked up by normalized FQName (e.g. `morphir/sdk:list#map`)
+//! and receive all of their arguments at once; partial application is handled
+//! by the evaluator.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::error::{EvalError, Result};
+use crate::eval::Evaluator;
+use crate::value::{ORDER_EQ, ORDER_GT, ORDER_LT, RESULT_ERR, RESULT_OK, RuntimeValue};
+
+type Run = fn(&Evaluator, Vec<RuntimeValue>) -> Result<RuntimeValue>;
+
+/// An SDK function implemented in Rust
+pub(crate) struct NativeFunction {
+    pub(crate) name: &'static str,
+    pub(crate) arity: usize,
+    pub(crate) run: Run,
+}
+
+macro_rules! sdk {
+    ($module:literal, $name:literal) => {
+        concat!("morphir/sdk:", $module, "#", $name)
+    };
+}
+
+fn native(name: &'static str, arity: usize, run: Run) -> NativeFunction {
+    NativeFunction { name, arity, run }
+}
+
+/// Find the native implementation of an SDK function by normalized FQName
+pub(crate) fn lookup(name: &str) -> Option<&'static NativeFunction> {
+    static TABLE: OnceLock<HashMap<&'static str, NativeFunction>> = OnceLock::new();
+    TABLE
+        .get_or_init(|| {
+            basics_functions()
+                .into_iter()
+                .chain(string_functions())
+                .chain(char_functions())
+                .chain(list_functions())
+                .chain(dict_functions())
+                .chain(maybe_functions())
+                .chain(result_functions())
+                .chain(tuple_functions())
+                .chain(decimal_functions())
+                .map(|f| (f.name, f))
+                .collect()
+        })
+        .get(name)
+}
+
+// -----------------------------------------------------------------------------
+// Argument helpers
+// -----------------------------------------------------------------------------
+
+fn take<const N: usize>(args: Vec<RuntimeValue>) -> Result<[RuntimeValue; N]> {
+    let count = args.len();
+    args.try_into()
+        .map_err(|_| EvalError::TypeError(format!("expected {} argument(s), found {}", N, count)))
+}
+
+fn int(value: &RuntimeValue) -> Result<i64> {
+    match value {
+        RuntimeValue::Int(n) => Ok(*n),
+        other => Err(EvalError::type_error("an Int", &other.kind())),
+    }
+}
+
+fn float(value: &RuntimeValue) -> Result<f64> {
+    value
+        .as_f64()
+        .ok_or_else(|| EvalError::type_error("a number", &value.kind()))
+}
+
+fn decimal(value: &RuntimeValue) -> Option<Decimal> {
+    match value {
+        RuntimeValue::Int(n) => Some(Decimal::from(*n)),
+        RuntimeValue::Decimal(d) => Some(*d),
+        _ => None,
+    }
+}
+
+fn boolean(value: &RuntimeValue) -> Result<bool> {
+    match value {
+        RuntimeValue::Bool(b) => Ok(*b),
+        other => Err(EvalError::type_error("a Bool", &other.kind())),
+    }
+}
+
+fn string(value: &RuntimeValue) -> Result<&str> {
+    match value {
+        RuntimeValue::String(s) => Ok(s),
+        other => Err(EvalError::type_error("a String", &other.kind())),
+    }
+}
+
+fn character(value: &RuntimeValue) -> Result<char> {
+    match value {
+        RuntimeValue::Char(c) => Ok(*c),
+        other => Err(EvalError::type_error("a Char", &other.kind())),
+    }
+}
+
+fn list(value: RuntimeValue) -> Result<Vec<RuntimeValue>> {
+    match value {
+        RuntimeValue::List(items) => Ok(items),
+        other => Err(EvalError::type_error("a List", &other.kind())),
+    }
+}
+
+fn dict(value: RuntimeValue) -> Result<Vec<(RuntimeValue, RuntimeValue)>> {
+    match value {
+        RuntimeValue::Dict(entries) => Ok(entries),
+        other => Err(EvalError::type_error("a Dict", &other.kind())),
+    }
+}
+
+fn pair(value: RuntimeValue) -> Result<(RuntimeValue, RuntimeValue)> {
+    match value {
+        RuntimeValue::Tuple(items) if items.len() == 2 => {
+            let [a, b] = take(items)?;
+            Ok((a, b))
+        }
+        other => Err(EvalError::type_error("a pair", &other.kind())),
+    }
+}
+
+fn compare(a: &RuntimeValue, b: &RuntimeValue) -> Result<Ordering> {
+    a.compare(b).ok_or_else(|| {
+        EvalError::TypeError(format!("cannot compare {} with {}", a.kind(), b.kind()))
+    })
+}
+
+fn failure(name: &str, message: impl Into<String>) -> EvalError {
+    EvalError::Native {
+        name: name.to_string(),
+        message: message.into(),
+    }
+}
+
+fn list_of(items: Vec<RuntimeValue>) -> Result<RuntimeValue> {
+    Ok(RuntimeValue::List(items))
+}
+
+fn strings(items: Vec<String>) -> Result<RuntimeValue> {
+    list_of(items.into_iter().map(RuntimeValue::String).collect())
+}
+
+/// Sort values in place, failing if any two are not comparable
+fn sort_values<T>(items: &mut [T], key: impl Fn(&T) -> &RuntimeValue) -> Result<()> {
+    let mut error = None;
+    items.sort_by(|a, b| {
+        compare(key(a), key(b)).unwrap_or_else(|e| {
+            error.get_or_insert(e);
+            Ordering::Equal
+        })
+    });
+    error.map_or(Ok(()), Err)
+}
+
+// -----------------------------------------------------------------------------
+// Basics
+// -----------------------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+}
+
+fn arithmetic(op: Op, a: &RuntimeValue, b: &RuntimeValue) -> Result<RuntimeValue> {
+    use RuntimeValue::{Decimal as Dec, Int};
+    match (a, b) {
+        (Int(x), Int(y)) => Ok(Int(match op {
+            Op::Add => x.wrapping_add(*y),
+            Op::Sub => x.wrapping_sub(*y),
+            Op::Mul => x.wrapping_mul(*y),
+        })),
+        (Int(_) | Dec(_), Int(_) | Dec(_)) => {
+            let (x, y) = (
+                decimal(a).unwrap_or_default(),
+                decimal(b).unwrap_or_default(),
+            );
+            match op {
+                Op::Add => x.checked_add(y),
+                Op::Sub => x.checked_sub(y),
+                Op::Mul => x.checked_mul(y),
+            }
+            .map(Dec)
+            .ok_or_else(|| failure("Basics", "decimal overflow"))
+        }
+        _ => {
+            let (x, y) = (float(a)?, float(b)?);
+            Ok(RuntimeValue::Float(match op {
+                Op::Add => x + y,
+                Op::Sub => x - y,
+                Op::Mul => x * y,
+            }))
+        }
+    }
+}
+
+fn divide(a: &RuntimeValue, b: &RuntimeValue) -> Result<RuntimeValue> {
+    match (a, b) {
+        (RuntimeValue::Decimal(_), _) | (_, RuntimeValue::Decimal(_))
+            if decimal(a).is_some() && decimal(b).is_some() =>
+        {
+            let (x, y) = (
+                decimal(a).unwrap_or_default(),
+                decimal(b).unwrap_or_default(),
+            );
+            x.checked_div(y)
+                .map(RuntimeValue::Decimal)
+                .ok_or_else(|| failure("Basics.divide", "division by zero"))
+        }
+        _ => Ok(RuntimeValue::Float(float(a)? / float(b)?)),
+    }
+}
+
+fn negate(value: &RuntimeValue) -> Result<RuntimeValue> {
+    match value {
+        RuntimeValue::Int(n) => Ok(RuntimeValue::Int(n.wrapping_neg())),
+        RuntimeValue::Float(x) => Ok(RuntimeValue::Float(-x)),
+        RuntimeValue::Decimal(d) => Ok(RuntimeValue::Decimal(-d)),
+        other => Err(EvalError::type_error("a number", &other.kind())),
+    }
+}
+
+fn abs(value: &RuntimeValue) -> Result<RuntimeValue> {
+    match value {
+        RuntimeValue::Int(n) => Ok(RuntimeValue::Int(n.wrapping_abs())),
+        RuntimeValue::Float(x) => Ok(RuntimeValue::Float(x.abs())),
+        RuntimeValue::Decimal(d) => Ok(RuntimeValue::Decimal(d.abs())),
+        other => Err(EvalError::type_error("a number", &other.kind())),
+    }
+}
+
+/// Convert a float to an `Int` after rounding it with `round`
+fn to_int(value: &RuntimeValue, round: fn(f64) -> f64) -> Result<RuntimeValue> {
+    match value {
+        RuntimeValue::Int(n) => Ok(RuntimeValue::Int(*n)),
+        other => Ok(RuntimeValue::Int(round(float(other)?) as i64)),
+    }
+}
+
+fn comparison(args: Vec<RuntimeValue>, test: fn(Ordering) -> bool) -> Result<RuntimeValue> {
+    let [a, b] = take(args)?;
+    Ok(RuntimeValue::Bool(test(compare(&a, &b)?)))
+}
+
+fn append(a: RuntimeValue, b: RuntimeValue) -> Result<RuntimeValue> {
+    match (a, b) {
+        (RuntimeValue::String(x), RuntimeValue::String(y)) => Ok(RuntimeValue::String(x + &y)),
+        (RuntimeValue::List(mut x), RuntimeValue::List(y)) => {
+            x.extend(y);
+            Ok(RuntimeValue::List(x))
+        }
+        (a, _) => Err(EvalError::type_error("a String or List", &a.kind())),
+    }
+}
+
+fn basics_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("basics", "add"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            arithmetic(Op::Add, &a, &b)
+        }),
+        native(sdk!("basics", "subtract"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            arithmetic(Op::Sub, &a, &b)
+        }),
+        native(sdk!("basics", "multiply"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            arithmetic(Op::Mul, &a, &b)
+        }),
+        native(sdk!("basics", "divide"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            divide(&a, &b)
+        }),
+        native(sdk!("basics", "integer-divide"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            let (a, b) = (int(&a)?, int(&b)?);
+            Ok(RuntimeValue::Int(if b == 0 {
+                0
+            } else {
+                a.wrapping_div(b)
+            }))
+        }),
+        native(sdk!("basics", "mod-by"), 2, |_, args| {
+            let [modulus, x] = take(args)?;
+            let (modulus, x) = (int(&modulus)?, int(&x)?);
+            if modulus == 0 {
+                return Err(failure("Basics.modBy", "modulus is zero"));
+            }
+            let r = x.wrapping_rem(modulus);
+            let r = if r != 0 && (r < 0) != (modulus < 0) {
+                r + modulus
+            } else {
+                r
+            };
+            Ok(RuntimeValue::Int(r))
+        }),
+        native(sdk!("basics", "remainder-by"), 2, |_, args| {
+            let [divisor, x] = take(args)?;
+            let (divisor, x) = (int(&divisor)?, int(&x)?);
+            if divisor == 0 {
+                return Err(failure("Basics.remainderBy", "divisor is zero"));
+            }
+            Ok(RuntimeValue::Int(x.wrapping_rem(divisor)))
+        }),
+        native(sdk!("basics", "power"), 2, |_, args| {
+            let [base, exponent] = take(args)?;
+            match (&base, &exponent) {
+                (RuntimeValue::Int(b), RuntimeValue::Int(e)) if *e >= 0 => Ok(RuntimeValue::Int(
+                    b.wrapping_pow(u32::try_from(*e).unwrap_or(u32::MAX)),
+                )),
+                _ => Ok(RuntimeValue::Float(float(&base)?.powf(float(&exponent)?))),
+            }
+        }),
+        native(sdk!("basics", "negate"), 1, |_, args| {
+            let [a] = take(args)?;
+            negate(&a)
+        }),
+        native(sdk!("basics", "abs"), 1, |_, args| {
+            let [a] = take(args)?;
+            abs(&a)
+        }),
+        native(sdk!("basics", "sqrt"), 1, |_, args| {
+            let [a] = take(args)?;
+            Ok(RuntimeValue::Float(float(&a)?.sqrt()))
+        }),
+        native(sdk!("basics", "log-base"), 2, |_, args| {
+            let [base, x] = take(args)?;
+            Ok(RuntimeValue::Float(float(&x)?.ln() / float(&base)?.ln()))
+        }),
+        native(sdk!("basics", "pi"), 0, |_, _| {
+            Ok(RuntimeValue::Float(std::f64::consts::PI))
+        }),
+        native(sdk!("basics", "e"), 0, |_, _| {
+            Ok(RuntimeValue::Float(std::f64::consts::E))
+        }),
+        native(sdk!("basics", "to-float"), 1, |_, args| {
+            let [a] = take(args)?;
+            Ok(RuntimeValue::Float(float(&a)?))
+        }),
+        native(sdk!("basics", "round"), 1, |_, args| {
+            let [a] = take(args)?;
+            to_int(&a, |x| (x + 0.5).floor())
+        }),
+        native(sdk!("basics", "floor"), 1, |_, args| {
+            let [a] = take(args)?;
+            to_int(&a, f64::floor)
+        }),
+        native(sdk!("basics", "ceiling"), 1, |_, args| {
+            let [a] = take(args)?;
+            to_int(&a, f64::ceil)
+        }),
+        native(sdk!("basics", "truncate"), 1, |_, args| {
+            let [a] = take(args)?;
+            to_int(&a, f64::trunc)
+        }),
+        native(sdk!("basics", "is-na-n"), 1, |_, args| {
+            let [a] = take(args)?;
+            Ok(RuntimeValue::Bool(float(&a)?.is_nan()))
+        }),
+        native(sdk!("basics", "is-infinite"), 1, |_, args| {
+            let [a] = take(args)?;
+            Ok(RuntimeValue::Bool(float(&a)?.is_infinite()))
+        }),
+        native(sdk!("basics", "equal"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::Bool(a == b))
+        }),
+        native(sdk!("basics", "not-equal"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::Bool(a != b))
+        }),
+        native(sdk!("basics", "less-than"), 2, |_, args| {
+            comparison(args, Ordering::is_lt)
+        }),
+        native(sdk!("basics", "greater-than"), 2, |_, args| {
+            comparison(args, Ordering::is_gt)
+        }),
+        native(sdk!("basics", "less-than-or-equal"), 2, |_, args| {
+            comparison(args, Ordering::is_le)
+        }),
+        native(sdk!("basics", "greater-than-or-equal"), 2, |_, args| {
+            comparison(args, Ordering::is_ge)
+        }),
+        native(sdk!("basics", "compare"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::order(compare(&a, &b)?))
+        }),
+        native(sdk!("basics", "max"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(if compare(&a, &b)?.is_ge() { a } else { b })
+        }),
+        native(sdk!("basics", "min"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(if compare(&a, &b)?.is_le() { a } else { b })
+        }),
+        native(sdk!("basics", "clamp"), 3, |_, args| {
+            let [low, high, x] = take(args)?;
+            Ok(if compare(&x, &low)?.is_lt() {
+                low
+            } else if compare(&x, &high)?.is_gt() {
+                high
+            } else {
+                x
+            })
+        }),
+        native(sdk!("basics", "not"), 1, |_, args| {
+            let [a] = take(args)?;
+            Ok(RuntimeValue::Bool(!boolean(&a)?))
+        }),
+        native(sdk!("basics", "and"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::Bool(boolean(&a)? && boolean(&b)?))
+        }),
+        native(sdk!("basics", "or"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::Bool(boolean(&a)? || boolean(&b)?))
+        }),
+        native(sdk!("basics", "xor"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::Bool(boolean(&a)? != boolean(&b)?))
+        }),
+        native(sdk!("basics", "append"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            append(a, b)
+        }),
+        native(sdk!("basics", "identity"), 1, |_, args| {
+            let [a] = take(args)?;
+            Ok(a)
+        }),
+        native(sdk!("basics", "always"), 2, |_, args| {
+            let [a, _] = take(args)?;
+            Ok(a)
+        }),
+        native(sdk!("basics", "compose-left"), 3, |ev, args| {
+            let [g, f, x] = take(args)?;
+            let y = ev.apply(&f, x)?;
+            ev.apply(&g, y)
+        }),
+        native(sdk!("basics", "compose-right"), 3, |ev, args| {
+            let [f, g, x] = take(args)?;
+            let y = ev.apply(&f, x)?;
+            ev.apply(&g, y)
+        }),
+    ]
+}
+
+// -----------------------------------------------------------------------------
+// String and Char
+// -----------------------------------------------------------------------------
+
+/// Resolve an Elm `String.slice` index, counting negative indices from the end
+fn slice_index(index: i64, len: usize) -> usize {
+    let len = len as i64;
+    let index = if index < 0 { len + index } else { index };
+    index.clamp(0, len) as usize
+}
+
+fn pad(args: Vec<RuntimeValue>, left: bool) -> Result<RuntimeValue> {
+    let [n, c, s] = take(args)?;
+    let (n, c, s) = (int(&n)?, character(&c)?, string(&s)?);
+    let missing = (n.max(0) as usize).saturating_sub(s.chars().count());
+    let padding: String = std::iter::repeat_n(c, missing).collect();
+    Ok(RuntimeValue::String(if left {
+        padding + s
+    } else {
+        s.to_string() + &padding
+    }))
+}
+
+fn string_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("string", "length"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::Int(string(&s)?.chars().count() as i64))
+        }),
+        native(sdk!("string", "is-empty"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::Bool(string(&s)?.is_empty()))
+        }),
+        native(sdk!("string", "append"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::String(format!(
+                "{}{}",
+                string(&a)?,
+                string(&b)?
+            )))
+        }),
+        native(sdk!("string", "concat"), 1, |_, args| {
+            let [items] = take(args)?;
+            let items = list(items)?;
+            let parts = items.iter().map(string).collect::<Result<Vec<_>>>()?;
+            Ok(RuntimeValue::String(parts.concat()))
+        }),
+        native(sdk!("string", "join"), 2, |_, args| {
+            let [separator, items] = take(args)?;
+            let items = list(items)?;
+            let parts = items.iter().map(string).collect::<Result<Vec<_>>>()?;
+            Ok(RuntimeValue::String(parts.join(string(&separator)?)))
+        }),
+        native(sdk!("string", "split"), 2, |_, args| {
+            let [separator, s] = take(args)?;
+            let (separator, s) = (string(&separator)?, string(&s)?);
+            if separator.is_empty() {
+                return strings(s.chars().map(String::from).collect());
+            }
+            strings(s.split(separator).map(String::from).collect())
+        }),
+        native(sdk!("string", "words"), 1, |_, args| {
+            let [s] = take(args)?;
+            strings(string(&s)?.split_whitespace().map(String::from).collect())
+        }),
+        native(sdk!("string", "lines"), 1, |_, args| {
+            let [s] = take(args)?;
+            let s = string(&s)?.replace("\r\n", "\n");
+            strings(s.split('\n').map(String::from).collect())
+        }),
+        native(sdk!("string", "reverse"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::String(string(&s)?.chars().rev().collect()))
+        }),
+        native(sdk!("string", "repeat"), 2, |_, args| {
+            let [n, s] = take(args)?;
+            Ok(RuntimeValue::String(
+                string(&s)?.repeat(int(&n)?.max(0) as usize),
+            ))
+        }),
+        native(sdk!("string", "replace"), 3, |_, args| {
+            let [before, after, s] = take(args)?;
+            let (before, after, s) = (string(&before)?, string(&after)?, string(&s)?);
+            if before.is_empty() {
+                return Ok(RuntimeValue::String(s.to_string()));
+            }
+            Ok(RuntimeValue::String(s.replace(before, after)))
+        }),
+        native(sdk!("string", "to-upper"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::String(string(&s)?.to_uppercase()))
+        }),
+        native(sdk!("string", "to-lower"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::String(string(&s)?.to_lowercase()))
+        }),
+        native(sdk!("string", "trim"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::String(string(&s)?.trim().to_string()))
+        }),
+        native(sdk!("string", "trim-left"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::String(string(&s)?.trim_start().to_string()))
+        }),
+        native(sdk!("string", "trim-right"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::String(string(&s)?.trim_end().to_string()))
+        }),
+        native(sdk!("string", "contains"), 2, |_, args| {
+            let [sub, s] = take(args)?;
+            Ok(RuntimeValue::Bool(string(&s)?.contains(string(&sub)?)))
+        }),
+        native(sdk!("string", "starts-with"), 2, |_, args| {
+            let [sub, s] = take(args)?;
+            Ok(RuntimeValue::Bool(string(&s)?.starts_with(string(&sub)?)))
+        }),
+        native(sdk!("string", "ends-with"), 2, |_, args| {
+            let [sub, s] = take(args)?;
+            Ok(RuntimeValue::Bool(string(&s)?.ends_with(string(&sub)?)))
+        }),
+        native(sdk!("string", "slice"), 3, |_, args| {
+            let [start, end, s] = take(args)?;
+            let chars: Vec<char> = string(&s)?.chars().collect();
+            let start = slice_index(int(&start)?, chars.len());
+            let end = slice_index(int(&end)?, chars.len());
+            Ok(RuntimeValue::String(
+                chars
+                    .get(start..end.max(start))
+                    .unwrap_or_default()
+                    .iter()
+                    .collect(),
+            ))
+        }),
+        native(sdk!("string", "left"), 2, |_, args| {
+            let [n, s] = take(args)?;
+            let n = int(&n)?.max(0) as usize;
+            Ok(RuntimeValue::String(string(&s)?.chars().take(n).collect()))
+        }),
+        native(sdk!("string", "right"), 2, |_, args| {
+            let [n, s] = take(args)?;
+            let s = string(&s)?;
+            let skip = s.chars().count().saturating_sub(int(&n)?.max(0) as usize);
+            Ok(RuntimeValue::String(s.chars().skip(skip).collect()))
+        }),
+        native(sdk!("string", "drop-left"), 2, |_, args| {
+            let [n, s] = take(args)?;
+            let n = int(&n)?.max(0) as usize;
+            Ok(RuntimeValue::String(string(&s)?.chars().skip(n).collect()))
+        }),
+        native(sdk!("string", "drop-right"), 2, |_, args| {
+            let [n, s] = take(args)?;
+            let s = string(&s)?;
+            let keep = s.chars().count().saturating_sub(int(&n)?.max(0) as usize);
+            Ok(RuntimeValue::String(s.chars().take(keep).collect()))
+        }),
+        native(sdk!("string", "pad-left"), 3, |_, args| pad(args, true)),
+        native(sdk!("string", "pad-right"), 3, |_, args| pad(args, false)),
+        native(sdk!("string", "from-int"), 1, |_, args| {
+            let [n] = take(args)?;
+            Ok(RuntimeValue::String(int(&n)?.to_string()))
+        }),
+        native(sdk!("string", "to-int"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::maybe(
+                string(&s)?.parse::<i64>().ok().map(RuntimeValue::Int),
+            ))
+        }),
+        native(sdk!("string", "from-float"), 1, |_, args| {
+            let [x] = take(args)?;
+            Ok(RuntimeValue::String(float(&x)?.to_string()))
+        }),
+        native(sdk!("string", "to-float"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::maybe(
+                string(&s)?.parse::<f64>().ok().map(RuntimeValue::Float),
+            ))
+        }),
+        native(sdk!("string", "from-char"), 1, |_, args| {
+            let [c] = take(args)?;
+            Ok(RuntimeValue::String(character(&c)?.to_string()))
+        }),
+        native(sdk!("string", "cons"), 2, |_, args| {
+            let [c, s] = take(args)?;
+            Ok(RuntimeValue::String(format!(
+                "{}{}",
+                character(&c)?,
+                string(&s)?
+            )))
+        }),
+        native(sdk!("string", "to-list"), 1, |_, args| {
+            let [s] = take(args)?;
+            list_of(string(&s)?.chars().map(RuntimeValue::Char).collect())
+        }),
+        native(sdk!("string", "from-list"), 1, |_, args| {
+            let [chars] = take(args)?;
+            let chars = list(chars)?;
+            Ok(RuntimeValue::String(
+                chars.iter().map(character).collect::<Result<String>>()?,
+            ))
+        }),
+        native(sdk!("string", "map"), 2, |ev, args| {
+            let [f, s] = take(args)?;
+            let mut result = String::new();
+            for c in string(&s)?.chars() {
+                result.push(character(&ev.apply(&f, RuntimeValue::Char(c))?)?);
+            }
+            Ok(RuntimeValue::String(result))
+        }),
+        native(sdk!("string", "filter"), 2, |ev, args| {
+            let [f, s] = take(args)?;
+            let mut result = String::new();
+            for c in string(&s)?.chars() {
+                if boolean(&ev.apply(&f, RuntimeValue::Char(c))?)? {
+                    result.push(c);
+                }
+            }
+            Ok(RuntimeValue::String(result))
+        }),
+        native(sdk!("string", "any"), 2, |ev, args| {
+            let [f, s] = take(args)?;
+            for c in string(&s)?.chars() {
+                if boolean(&ev.apply(&f, RuntimeValue::Char(c))?)? {
+                    return Ok(RuntimeValue::Bool(true));
+                }
+            }
+            Ok(RuntimeValue::Bool(false))
+        }),
+        native(sdk!("string", "all"), 2, |ev, args| {
+            let [f, s] = take(args)?;
+            for c in string(&s)?.chars() {
+                if !boolean(&ev.apply(&f, RuntimeValue::Char(c))?)? {
+                    return Ok(RuntimeValue::Bool(false));
+                }
+            }
+            Ok(RuntimeValue::Bool(true))
+        }),
+    ]
+}
+
+fn char_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("char", "to-code"), 1, |_, args| {
+            let [c] = take(args)?;
+            Ok(RuntimeValue::Int(character(&c)? as i64))
+        }),
+        native(sdk!("char", "from-code"), 1, |_, args| {
+            let [n] = take(args)?;
+            let c = u32::try_from(int(&n)?).ok().and_then(char::from_u32);
+            Ok(RuntimeValue::Char(c.unwrap_or('\u{FFFD}')))
+        }),
+        native(sdk!("char", "is-digit"), 1, |_, args| {
+            let [c] = take(args)?;
+            Ok(RuntimeValue::Bool(character(&c)?.is_ascii_digit()))
+        }),
+        native(sdk!("char", "is-alpha"), 1, |_, args| {
+            let [c] = take(args)?;
+            Ok(RuntimeValue::Bool(character(&c)?.is_ascii_alphabetic()))
+        }),
+        native(sdk!("char", "is-upper"), 1, |_, args| {
+            let [c] = take(args)?;
+            Ok(RuntimeValue::Bool(character(&c)?.is_ascii_uppercase()))
+        }),
+        native(sdk!("char", "is-lower"), 1, |_, args| {
+            let [c] = take(args)?;
+            Ok(RuntimeValue::Bool(character(&c)?.is_ascii_lowercase()))
+        }),
+        native(sdk!("char", "to-upper"), 1, |_, args| {
+            let [c] = take(args)?;
+            let c = character(&c)?;
+            Ok(RuntimeValue::Char(c.to_uppercase().next().unwrap_or(c)))
+        }),
+        native(sdk!("char", "to-lower"), 1, |_, args| {
+            let [c] = take(args)?;
+            let c = character(&c)?;
+            Ok(RuntimeValue::Char(c.to_lowercase().next().unwrap_or(c)))
+        }),
+    ]
+}
+
+// -----------------------------------------------------------------------------
+// List
+// -----------------------------------------------------------------------------
+
+fn list_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("list", "singleton"), 1, |_, args| {
+            let [a] = take(args)?;
+            list_of(vec![a])
+        }),
+        native(sdk!("list", "repeat"), 2, |_, args| {
+            let [n, a] = take(args)?;
+            list_of(vec![a; int(&n)?.max(0) as usize])
+        }),
+        native(sdk!("list", "range"), 2, |_, args| {
+            let [low, high] = take(args)?;
+            list_of((int(&low)?..=int(&high)?).map(RuntimeValue::Int).collect())
+        }),
+        native(sdk!("list", "cons"), 2, |_, args| {
+            let [head, tail] = take(args)?;
+            let mut items = vec![head];
+            items.extend(list(tail)?);
+            list_of(items)
+        }),
+        native(sdk!("list", "map"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            list_of(
+                list(items)?
+                    .into_iter()
+                    .map(|x| ev.apply(&f, x))
+                    .collect::<Result<_>>()?,
+            )
+        }),
+        native(sdk!("list", "indexed-map"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            list_of(
+                list(items)?
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, x)| ev.apply2(&f, RuntimeValue::Int(i as i64), x))
+                    .collect::<Result<_>>()?,
+            )
+        }),
+        native(sdk!("list", "map2"), 3, |ev, args| {
+            let [f, xs, ys] = take(args)?;
+            list_of(
+                list(xs)?
+                    .into_iter()
+                    .zip(list(ys)?)
+                    .map(|(x, y)| ev.apply2(&f, x, y))
+                    .collect::<Result<_>>()?,
+            )
+        }),
+        native(sdk!("list", "filter"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            let mut result = Vec::new();
+            for x in list(items)? {
+                if boolean(&ev.apply(&f, x.clone())?)? {
+                    result.push(x);
+                }
+            }
+            list_of(result)
+        }),
+        native(sdk!("list", "filter-map"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            let mut result = Vec::new();
+            for x in list(items)? {
+                let mapped = ev.apply(&f, x)?;
+                if let Some(Some(value)) = mapped.as_maybe() {
+                    result.push(value.clone());
+                }
+            }
+            list_of(result)
+        }),
+        native(sdk!("list", "partition"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            let (mut yes, mut no) = (Vec::new(), Vec::new());
+            for x in list(items)? {
+                if boolean(&ev.apply(&f, x.clone())?)? {
+                    yes.push(x);
+                } else {
+                    no.push(x);
+                }
+            }
+            Ok(RuntimeValue::Tuple(vec![
+                RuntimeValue::List(yes),
+                RuntimeValue::List(no),
+            ]))
+        }),
+        native(sdk!("list", "foldl"), 3, |ev, args| {
+            let [f, mut acc, items] = take(args)?;
+            for x in list(items)? {
+                acc = ev.apply2(&f, x, acc)?;
+            }
+            Ok(acc)
+        }),
+        native(sdk!("list", "foldr"), 3, |ev, args| {
+            let [f, mut acc, items] = take(args)?;
+            for x in list(items)?.into_iter().rev() {
+                acc = ev.apply2(&f, x, acc)?;
+            }
+            Ok(acc)
+        }),
+        native(sdk!("list", "length"), 1, |_, args| {
+            let [items] = take(args)?;
+            Ok(RuntimeValue::Int(list(items)?.len() as i64))
+        }),
+        native(sdk!("list", "is-empty"), 1, |_, args| {
+            let [items] = take(args)?;
+            Ok(RuntimeValue::Bool(list(items)?.is_empty()))
+        }),
+        native(sdk!("list", "reverse"), 1, |_, args| {
+            let [items] = take(args)?;
+            let mut items = list(items)?;
+            items.reverse();
+            list_of(items)
+        }),
+        native(sdk!("list", "member"), 2, |_, args| {
+            let [x, items] = take(args)?;
+            Ok(RuntimeValue::Bool(list(items)?.contains(&x)))
+        }),
+        native(sdk!("list", "all"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            for x in list(items)? {
+                if !boolean(&ev.apply(&f, x)?)? {
+                    return Ok(RuntimeValue::Bool(false));
+                }
+            }
+            Ok(RuntimeValue::Bool(true))
+        }),
+        native(sdk!("list", "any"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            for x in list(items)? {
+                if boolean(&ev.apply(&f, x)?)? {
+                    return Ok(RuntimeValue::Bool(true));
+                }
+            }
+            Ok(RuntimeValue::Bool(false))
+        }),
+        native(sdk!("list", "maximum"), 1, |_, args| {
+            let [items] = take(args)?;
+            let mut best: Option<RuntimeValue> = None;
+            for x in list(items)? {
+                best = match best {
+                    Some(b) if compare(&b, &x)?.is_ge() => Some(b),
+                    _ => Some(x),
+                };
+            }
+            Ok(RuntimeValue::maybe(best))
+        }),
+        native(sdk!("list", "minimum"), 1, |_, args| {
+            let [items] = take(args)?;
+            let mut best: Option<RuntimeValue> = None;
+            for x in list(items)? {
+                best = match best {
+                    Some(b) if compare(&b, &x)?.is_le() => Some(b),
+                    _ => Some(x),
+                };
+            }
+            Ok(RuntimeValue::maybe(best))
+        }),
+        native(sdk!("list", "sum"), 1, |_, args| {
+            let [items] = take(args)?;
+            list(items)?
+                .iter()
+                .try_fold(RuntimeValue::Int(0), |acc, x| arithmetic(Op::Add, &acc, x))
+        }),
+        native(sdk!("list", "product"), 1, |_, args| {
+            let [items] = take(args)?;
+            list(items)?
+                .iter()
+                .try_fold(RuntimeValue::Int(1), |acc, x| arithmetic(Op::Mul, &acc, x))
+        }),
+        native(sdk!("list", "append"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            let mut items = list(a)?;
+            items.extend(list(b)?);
+            list_of(items)
+        }),
+        native(sdk!("list", "concat"), 1, |_, args| {
+            let [lists] = take(args)?;
+            let mut items = Vec::new();
+            for l in list(lists)? {
+                items.extend(list(l)?);
+            }
+            list_of(items)
+        }),
+        native(sdk!("list", "concat-map"), 2, |ev, args| {
+            let [f, lists] = take(args)?;
+            let mut items = Vec::new();
+            for x in list(lists)? {
+                items.extend(list(ev.apply(&f, x)?)?);
+            }
+            list_of(items)
+        }),
+        native(sdk!("list", "intersperse"), 2, |_, args| {
+            let [separator, items] = take(args)?;
+            let mut result = Vec::new();
+            for (i, x) in list(items)?.into_iter().enumerate() {
+                if i > 0 {
+                    result.push(separator.clone());
+                }
+                result.push(x);
+            }
+            list_of(result)
+        }),
+        native(sdk!("list", "head"), 1, |_, args| {
+            let [items] = take(args)?;
+            Ok(RuntimeValue::maybe(list(items)?.into_iter().next()))
+        }),
+        native(sdk!("list", "tail"), 1, |_, args| {
+            let [items] = take(args)?;
+            let items = list(items)?;
+            Ok(RuntimeValue::maybe(
+                (!items.is_empty()).then(|| RuntimeValue::List(items[1..].to_vec())),
+            ))
+        }),
+        native(sdk!("list", "take"), 2, |_, args| {
+            let [n, items] = take(args)?;
+            let n = int(&n)?.max(0) as usize;
+            list_of(list(items)?.into_iter().take(n).collect())
+        }),
+        native(sdk!("list", "drop"), 2, |_, args| {
+            let [n, items] = take(args)?;
+            let n = int(&n)?.max(0) as usize;
+            list_of(list(items)?.into_iter().skip(n).collect())
+        }),
+        native(sdk!("list", "sort"), 1, |_, args| {
+            let [items] = take(args)?;
+            let mut items = list(items)?;
+            sort_values(&mut items, |x| x)?;
+            list_of(items)
+        }),
+        native(sdk!("list", "sort-by"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            let mut keyed = list(items)?
+                .into_iter()
+                .map(|x| Ok((ev.apply(&f, x.clone())?, x)))
+                .collect::<Result<Vec<_>>>()?;
+            sort_values(&mut keyed, |(k, _)| k)?;
+            list_of(keyed.into_iter().map(|(_, x)| x).collect())
+        }),
+        native(sdk!("list", "sort-with"), 2, |ev, args| {
+            let [f, items] = take(args)?;
+            let mut items = list(items)?;
+            let mut error = None;
+            items.sort_by(|a, b| {
+                let order = ev.apply2(&f, a.clone(), b.clone()).and_then(|o| {
+                    if o.as_constructor(ORDER_LT).is_some() {
+                        Ok(Ordering::Less)
+                    } else if o.as_constructor(ORDER_EQ).is_some() {
+                        Ok(Ordering::Equal)
+                    } else if o.as_constructor(ORDER_GT).is_some() {
+                        Ok(Ordering::Greater)
+                    } else {
+                        Err(EvalError::type_error("an Order", &o.kind()))
+                    }
+                });
+                order.unwrap_or_else(|e| {
+                    error.get_or_insert(e);
+                    Ordering::Equal
+                })
+            });
+            match error {
+                Some(e) => Err(e),
+                None => list_of(items),
+            }
+        }),
+        native(sdk!("list", "unzip"), 1, |_, args| {
+            let [pairs] = take(args)?;
+            let (mut firsts, mut seconds) = (Vec::new(), Vec::new());
+            for p in list(pairs)? {
+                let (a, b) = pair(p)?;
+                firsts.push(a);
+                seconds.push(b);
+            }
+            Ok(RuntimeValue::Tuple(vec![
+                RuntimeValue::List(firsts),
+                RuntimeValue::List(seconds),
+            ]))
+        }),
+    ]
+}
+
+// -----------------------------------------------------------------------------
+// Dict
+// -----------------------------------------------------------------------------
+
+fn search(
+    entries: &[(RuntimeValue, RuntimeValue)],
+    key: &RuntimeValue,
+) -> Result<std::result::Result<usize, usize>> {
+    let mut error = None;
+    let found = entries.binary_search_by(|(k, _)| {
+        compare(k, key).unwrap_or_else(|e| {
+            error.get_or_insert(e);
+            Ordering::Equal
+        })
+    });
+    error.map_or(Ok(found), Err)
+}
+
+/// Insert into a `Dict`, replacing any existing entry for the key
+pub(crate) fn dict_insert(
+    dict_value: RuntimeValue,
+    key: RuntimeValue,
+    value: RuntimeValue,
+) -> Result<RuntimeValue> {
+    let mut entries = dict(dict_value)?;
+    match search(&entries, &key)? {
+        Ok(i) => entries[i].1 = value,
+        Err(i) => entries.insert(i, (key, value)),
+    }
+    Ok(RuntimeValue::Dict(entries))
+}
+
+fn dict_get(dict_value: RuntimeValue, key: &RuntimeValue) -> Result<Option<RuntimeValue>> {
+    let mut entries = dict(dict_value)?;
+    Ok(match search(&entries, key)? {
+        Ok(i) => Some(entries.swap_remove(i).1),
+        Err(_) => None,
+    })
+}
+
+fn dict_remove(dict_value: RuntimeValue, key: &RuntimeValue) -> Result<RuntimeValue> {
+    let mut entries = dict(dict_value)?;
+    if let Ok(i) = search(&entries, key)? {
+        entries.remove(i);
+    }
+    Ok(RuntimeValue::Dict(entries))
+}
+
+fn dict_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("dict", "empty"), 0, |_, _| {
+            Ok(RuntimeValue::Dict(Vec::new()))
+        }),
+        native(sdk!("dict", "singleton"), 2, |_, args| {
+            let [k, v] = take(args)?;
+            Ok(RuntimeValue::Dict(vec![(k, v)]))
+        }),
+        native(sdk!("dict", "insert"), 3, |_, args| {
+            let [k, v, d] = take(args)?;
+            dict_insert(d, k, v)
+        }),
+        native(sdk!("dict", "update"), 3, |ev, args| {
+            let [k, f, d] = take(args)?;
+            let current = dict_get(d.clone(), &k)?;
+            let updated = ev.apply(&f, RuntimeValue::maybe(current))?;
+            match updated.as_maybe() {
+                Some(Some(v)) => dict_insert(d, k, v.clone()),
+                Some(None) => dict_remove(d, &k),
+                None => Err(EvalError::type_error("a Maybe", &updated.kind())),
+            }
+        }),
+        native(sdk!("dict", "remove"), 2, |_, args| {
+            let [k, d] = take(args)?;
+            dict_remove(d, &k)
+        }),
+        native(sdk!("dict", "is-empty"), 1, |_, args| {
+            let [d] = take(args)?;
+            Ok(RuntimeValue::Bool(dict(d)?.is_empty()))
+        }),
+        native(sdk!("dict", "size"), 1, |_, args| {
+            let [d] = take(args)?;
+            Ok(RuntimeValue::Int(dict(d)?.len() as i64))
+        }),
+        native(sdk!("dict", "member"), 2, |_, args| {
+            let [k, d] = take(args)?;
+            Ok(RuntimeValue::Bool(dict_get(d, &k)?.is_some()))
+        }),
+        native(sdk!("dict", "get"), 2, |_, args| {
+            let [k, d] = take(args)?;
+            Ok(RuntimeValue::maybe(dict_get(d, &k)?))
+        }),
+        native(sdk!("dict", "keys"), 1, |_, args| {
+            let [d] = take(args)?;
+            list_of(dict(d)?.into_iter().map(|(k, _)| k).collect())
+        }),
+        native(sdk!("dict", "values"), 1, |_, args| {
+            let [d] = take(args)?;
+            list_of(dict(d)?.into_iter().map(|(_, v)| v).collect())
+        }),
+        native(sdk!("dict", "to-list"), 1, |_, args| {
+            let [d] = take(args)?;
+            list_of(
+                dict(d)?
+                    .into_iter()
+                    .map(|(k, v)| RuntimeValue::Tuple(vec![k, v]))
+                    .collect(),
+            )
+        }),
+        native(sdk!("dict", "from-list"), 1, |_, args| {
+            let [pairs] = take(args)?;
+            let mut d = RuntimeValue::Dict(Vec::new());
+            for p in list(pairs)? {
+                let (k, v) = pair(p)?;
+                d = dict_insert(d, k, v)?;
+            }
+            Ok(d)
+        }),
+        native(sdk!("dict", "map"), 2, |ev, args| {
+            let [f, d] = take(args)?;
+            Ok(RuntimeValue::Dict(
+                dict(d)?
+                    .into_iter()
+                    .map(|(k, v)| Ok((k.clone(), ev.apply2(&f, k, v)?)))
+                    .collect::<Result<_>>()?,
+            ))
+        }),
+        native(sdk!("dict", "filter"), 2, |ev, args| {
+            let [f, d] = take(args)?;
+            let mut entries = Vec::new();
+            for (k, v) in dict(d)? {
+                if boolean(&ev.apply2(&f, k.clone(), v.clone())?)? {
+                    entries.push((k, v));
+                }
+            }
+            Ok(RuntimeValue::Dict(entries))
+        }),
+        native(sdk!("dict", "foldl"), 3, |ev, args| {
+            let [f, mut acc, d] = take(args)?;
+            for (k, v) in dict(d)? {
+                let partial = ev.apply2(&f, k, v)?;
+                acc = ev.apply(&partial, acc)?;
+            }
+            Ok(acc)
+        }),
+        native(sdk!("dict", "foldr"), 3, |ev, args| {
+            let [f, mut acc, d] = take(args)?;
+            for (k, v) in dict(d)?.into_iter().rev() {
+                let partial = ev.apply2(&f, k, v)?;
+                acc = ev.apply(&partial, acc)?;
+            }
+            Ok(acc)
+        }),
+        native(sdk!("dict", "union"), 2, |_, args| {
+            let [preferred, other] = take(args)?;
+            let mut result = other;
+            for (k, v) in dict(preferred)? {
+                result = dict_insert(result, k, v)?;
+            }
+            Ok(result)
+        }),
+    ]
+}
+
+// -----------------------------------------------------------------------------
+// Maybe, Result, and Tuple
+// -----------------------------------------------------------------------------
+
+fn maybe_arg(value: &RuntimeValue) -> Result<Option<RuntimeValue>> {
+    value
+        .as_maybe()
+        .map(|m| m.cloned())
+        .ok_or_else(|| EvalError::type_error("a Maybe", &value.kind()))
+}
+
+/// Split a `Result` into `Ok(value)` or `Err(error)`
+fn result_arg(value: &RuntimeValue) -> Result<std::result::Result<RuntimeValue, RuntimeValue>> {
+    if let Some([v]) = value.as_constructor(RESULT_OK) {
+        return Ok(Ok(v.clone()));
+    }
+    if let Some([e]) = value.as_constructor(RESULT_ERR) {
+        return Ok(Err(e.clone()));
+    }
+    Err(EvalError::type_error("a Result", &value.kind()))
+}
+
+fn maybe_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("maybe", "with-default"), 2, |_, args| {
+            let [default, m] = take(args)?;
+            Ok(maybe_arg(&m)?.unwrap_or(default))
+        }),
+        native(sdk!("maybe", "map"), 2, |ev, args| {
+            let [f, m] = take(args)?;
+            Ok(RuntimeValue::maybe(
+                maybe_arg(&m)?.map(|v| ev.apply(&f, v)).transpose()?,
+            ))
+        }),
+        native(sdk!("maybe", "and-then"), 2, |ev, args| {
+            let [f, m] = take(args)?;
+            match maybe_arg(&m)? {
+                Some(v) => ev.apply(&f, v),
+                None => Ok(RuntimeValue::nothing()),
+            }
+        }),
+    ]
+}
+
+fn result_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("result", "with-default"), 2, |_, args| {
+            let [default, r] = take(args)?;
+            Ok(result_arg(&r)?.unwrap_or(default))
+        }),
+        native(sdk!("result", "map"), 2, |ev, args| {
+            let [f, r] = take(args)?;
+            match result_arg(&r)? {
+                Ok(v) => Ok(RuntimeValue::ok(ev.apply(&f, v)?)),
+                Err(_) => Ok(r),
+            }
+        }),
+        native(sdk!("result", "map-error"), 2, |ev, args| {
+            let [f, r] = take(args)?;
+            match result_arg(&r)? {
+                Ok(_) => Ok(r),
+                Err(e) => Ok(RuntimeValue::err(ev.apply(&f, e)?)),
+            }
+        }),
+        native(sdk!("result", "and-then"), 2, |ev, args| {
+            let [f, r] = take(args)?;
+            match result_arg(&r)? {
+                Ok(v) => ev.apply(&f, v),
+                Err(_) => Ok(r),
+            }
+        }),
+        native(sdk!("result", "to-maybe"), 1, |_, args| {
+            let [r] = take(args)?;
+            Ok(RuntimeValue::maybe(result_arg(&r)?.ok()))
+        }),
+        native(sdk!("result", "from-maybe"), 2, |_, args| {
+            let [error, m] = take(args)?;
+            Ok(match maybe_arg(&m)? {
+                Some(v) => RuntimeValue::ok(v),
+                None => RuntimeValue::err(error),
+            })
+        }),
+    ]
+}
+
+fn tuple_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("tuple", "pair"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::Tuple(vec![a, b]))
+        }),
+        native(sdk!("tuple", "first"), 1, |_, args| {
+            let [t] = take(args)?;
+            Ok(pair(t)?.0)
+        }),
+        native(sdk!("tuple", "second"), 1, |_, args| {
+            let [t] = take(args)?;
+            Ok(pair(t)?.1)
+        }),
+        native(sdk!("tuple", "map-first"), 2, |ev, args| {
+            let [f, t] = take(args)?;
+            let (a, b) = pair(t)?;
+            Ok(RuntimeValue::Tuple(vec![ev.apply(&f, a)?, b]))
+        }),
+        native(sdk!("tuple", "map-second"), 2, |ev, args| {
+            let [f, t] = take(args)?;
+            let (a, b) = pair(t)?;
+            Ok(RuntimeValue::Tuple(vec![a, ev.apply(&f, b)?]))
+        }),
+    ]
+}
+
+// -----------------------------------------------------------------------------
+// Decimal
+// -----------------------------------------------------------------------------
+
+fn decimal_arg(value: &RuntimeValue) -> Result<Decimal> {
+    decimal(value).ok_or_else(|| EvalError::type_error("a Decimal", &value.kind()))
+}
+
+fn decimal_op(args: Vec<RuntimeValue>, op: Op) -> Result<RuntimeValue> {
+    let [a, b] = take(args)?;
+    let (a, b) = (decimal_arg(&a)?, decimal_arg(&b)?);
+    arithmetic(op, &RuntimeValue::Decimal(a), &RuntimeValue::Decimal(b))
+}
+
+fn decimal_functions() -> Vec<NativeFunction> {
+    vec![
+        native(sdk!("decimal", "zero"), 0, |_, _| {
+            Ok(RuntimeValue::Decimal(Decimal::ZERO))
+        }),
+        native(sdk!("decimal", "one"), 0, |_, _| {
+            Ok(RuntimeValue::Decimal(Decimal::ONE))
+        }),
+        native(sdk!("decimal", "from-int"), 1, |_, args| {
+            let [n] = take(args)?;
+            Ok(RuntimeValue::Decimal(Decimal::from(int(&n)?)))
+        }),
+        native(sdk!("decimal", "from-float"), 1, |_, args| {
+            let [x] = take(args)?;
+            Ok(RuntimeValue::maybe(
+                Decimal::from_f64(float(&x)?).map(RuntimeValue::Decimal),
+            ))
+        }),
+        native(sdk!("decimal", "from-string"), 1, |_, args| {
+            let [s] = take(args)?;
+            Ok(RuntimeValue::maybe(
+                Decimal::from_str(string(&s)?)
+                    .ok()
+                    .map(RuntimeValue::Decimal),
+            ))
+        }),
+        native(sdk!("decimal", "to-string"), 1, |_, args| {
+            let [d] = take(args)?;
+            Ok(RuntimeValue::String(decimal_arg(&d)?.to_string()))
+        }),
+        native(sdk!("decimal", "to-float"), 1, |_, args| {
+            let [d] = take(args)?;
+            Ok(RuntimeValue::Float(
+                decimal_arg(&d)?.to_f64().unwrap_or(f64::NAN),
+            ))
+        }),
+        native(sdk!("decimal", "add"), 2, |_, args| {
+            decimal_op(args, Op::Add)
+        }),
+        native(sdk!("decimal", "sub"), 2, |_, args| {
+            decimal_op(args, Op::Sub)
+        }),
+        native(sdk!("decimal", "mul"), 2, |_, args| {
+            decimal_op(args, Op::Mul)
+        }),
+        native(sdk!("decimal", "div"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::maybe(
+                decimal_arg(&a)?
+                    .checked_div(decimal_arg(&b)?)
+                    .map(RuntimeValue::Decimal),
+            ))
+        }),
+        native(sdk!("decimal", "div-with-default"), 3, |_, args| {
+            let [default, a, b] = take(args)?;
+            Ok(decimal_arg(&a)?
+                .checked_div(decimal_arg(&b)?)
+                .map(RuntimeValue::Decimal)
+                .unwrap_or(default))
+        }),
+        native(sdk!("decimal", "negate"), 1, |_, args| {
+            let [d] = take(args)?;
+            Ok(RuntimeValue::Decimal(-decimal_arg(&d)?))
+        }),
+        native(sdk!("decimal", "abs"), 1, |_, args| {
+            let [d] = take(args)?;
+            Ok(RuntimeValue::Decimal(decimal_arg(&d)?.abs()))
+        }),
+        native(sdk!("decimal", "round"), 1, |_, args| {
+            let [d] = take(args)?;
+            Ok(RuntimeValue::Decimal(
+                decimal_arg(&d)?.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero),
+            ))
+        }),
+        native(sdk!("decimal", "truncate"), 1, |_, args| {
+            let [d] = take(args)?;
+            Ok(RuntimeValue::Decimal(decimal_arg(&d)?.trunc()))
+        }),
+        native(sdk!("decimal", "compare"), 2, |_, args| {
+            let [a, b] = take(args)?;
+            Ok(RuntimeValue::order(decimal_arg(&a)?.cmp(&decimal_arg(&b)?)))
+        }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::IndexMap;
+    use morphir_core::ir::v4::{Distribution, LibraryContent, PackageDefinition};
+    use morphir_core::naming::{FQName, PackageName, Path};
+
+    fn run(name: &str, args: Vec<RuntimeValue>) -> Result<RuntimeValue> {
+        let dist = Distribution::Library(LibraryContent {
+            package_name: PackageName::new(Path::new("my/pkg")),
+            dependencies: IndexMap::new(),
+            def: PackageDefinition {
+                modules: IndexMap::new(),
+            },
+        });
+        let fqname = FQName::from_canonical_string(name).unwrap();
+        Evaluator::new(&dist).evaluate(&fqname, args)
+    }
+
+    fn s(value: &str) -> RuntimeValue {
+        RuntimeValue::String(value.to_string())
+    }
+
+    #[test]
+    fn test_string_functions() {
+        assert_eq!(
+            run(
+                "morphir/sdk:string#join",
+                vec![s(", "), RuntimeValue::List(vec![s("a"), s("b")])]
+            )
+            .unwrap(),
+            s("a, b")
+        );
+        assert_eq!(
+            run(
+                "morphir/sdk:string#slice",
+                vec![RuntimeValue::Int(1), RuntimeValue::Int(-1), s("hello")]
+            )
+            .unwrap(),
+            s("ell")
+        );
+        assert_eq!(
+            run("morphir/sdk:string#toInt", vec![s("x")]).unwrap(),
+            RuntimeValue::nothing()
+        );
+    }
+
+    #[test]
+    fn test_dict_keeps_keys_sorted() {
+        let pairs = RuntimeValue::List(vec![
+            RuntimeValue::Tuple(vec![s("b"), RuntimeValue::Int(2)]),
+            RuntimeValue::Tuple(vec![s("a"), RuntimeValue::Int(1)]),
+            RuntimeValue::Tuple(vec![s("b"), RuntimeValue::Int(3)]),
+        ]);
+        let d = run("morphir/sdk:dict#fromList", vec![pairs]).unwrap();
+        assert_eq!(d.to_string(), "Dict.fromList [(\"a\", 1), (\"b\", 3)]");
+        assert_eq!(
+            run("morphir/sdk:dict#get", vec![s("b"), d]).unwrap(),
+            RuntimeValue::just(RuntimeValue::Int(3))
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_promotion() {
+        let half = RuntimeValue::Decimal(Decimal::new(5, 1));
+        assert_eq!(
+            run("morphir/sdk:basics#add", vec![RuntimeValue::Int(1), half]).unwrap(),
+            RuntimeValue::Decimal(Decimal::new(15, 1))
+        );
+        assert_eq!(
+            run(
+                "morphir/sdk:basics#modBy",
+                vec![RuntimeValue::Int(3), RuntimeValue::Int(-1)]
+            )
+            .unwrap(),
+            RuntimeValue::Int(2)
+        );
+        assert!(matches!(
+            run(
+                "morphir/sdk:list#sort",
+                vec![RuntimeValue::List(vec![RuntimeValue::Int(1), s("a")])]
+            ),
+            Err(EvalError::TypeError(_))
+        ));
+    }
+}
@@ -0,0 +1,572 @@
+//! Runtime values produced by the evaluator
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use morphir_core::ir::v4::{Pattern, Value};
+use morphir_core::naming::{FQName, Name};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::eval::Env;
+use crate::key;
+
+/// A fully evaluated Morphir value
+#[derive(Debug, Clone)]
+pub enum RuntimeValue {
+    Unit,
+    Bool(bool),
+    Char(char),
+    String(String),
+    Int(i64),
+    Float(f64),
+    Decimal(Decimal),
+    Tuple(Vec<RuntimeValue>),
+    List(Vec<RuntimeValue>),
+    /// Record fields keyed by kebab-case field name, in declaration order
+    Record(IndexMap<String, RuntimeValue>),
+    /// `Dict` entries, kept sorted by key
+    Dict(Vec<(RuntimeValue, RuntimeValue)>),
+    /// Custom type constructor applied to its arguments
+    Constructor(FQName, Vec<RuntimeValue>),
+    /// Function value, possibly partially applied
+    Function(Arc<Closure>),
+}
+
+/// A function value together with the arguments applied so far
+#[derive(Clone)]
+pub struct Closure {
+    pub(crate) function: Function,
+    pub(crate) args: Vec<RuntimeValue>,
+}
+
+impl Closure {
+    pub(crate) fn new(function: Function) -> Self {
+        Self {
+            function,
+            args: Vec::new(),
+        }
+    }
+
+    /// Number of arguments the function takes in total
+    pub fn arity(&self) -> usize {
+        match &self.function {
+            Function::Lambda { .. } | Function::Field(_) => 1,
+            Function::Local { params, .. } => params.len(),
+            Function::Global { arity, .. } | Function::Native { arity, .. } => *arity,
+        }
+    }
+
+    /// Number of arguments still missing before the function runs
+    pub fn remaining(&self) -> usize {
+        self.arity().saturating_sub(self.args.len())
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<function/{}>", self.remaining())
+    }
+}
+
+/// What a closure runs once all its arguments are available
+#[derive(Clone)]
+pub(crate) enum Function {
+    /// `\pattern -> body`
+    Lambda {
+        pattern: Arc<Pattern>,
+        body: Arc<Value>,
+        env: Env,
+    },
+    /// Function defined in a `let` block
+    Local {
+        params: Vec<String>,
+        body: Arc<Value>,
+        env: Env,
+    },
+    /// Top-level value definition with inputs, by normalized FQName
+    Global { key: String, arity: usize },
+    /// SDK function implemented in Rust
+    Native { name: &'static str, arity: usize },
+    /// `.field` accessor
+    Field(String),
+}
+
+pub(crate) const MAYBE_JUST: &str = "morphir/sdk:maybe#just";
+pub(crate) const MAYBE_NOTHING: &str = "morphir/sdk:maybe#nothing";
+pub(crate) const RESULT_OK: &str = "morphir/sdk:result#ok";
+pub(crate) const RESULT_ERR: &str = "morphir/sdk:result#err";
+pub(crate) const ORDER_LT: &str = "morphir/sdk:basics#lt";
+pub(crate) const ORDER_EQ: &str = "morphir/sdk:basics#eq";
+pub(crate) const ORDER_GT: &str = "morphir/sdk:basics#gt";
+
+fn sdk_constructor(name: &str, args: Vec<RuntimeValue>) -> RuntimeValue {
+    let fqname = FQName::from_canonical_string(name).expect("valid SDK constructor name");
+    RuntimeValue::Constructor(fqname, args)
+}
+
+impl RuntimeValue {
+    /// `Just value`
+    pub fn just(value: RuntimeValue) -> Self {
+        sdk_constructor(MAYBE_JUST, vec![value])
+    }
+
+    /// `Nothing`
+    pub fn nothing() -> Self {
+        sdk_constructor(MAYBE_NOTHING, Vec::new())
+    }
+
+    /// `Maybe` from an option
+    pub fn maybe(value: Option<RuntimeValue>) -> Self {
+        value.map(Self::just).unwrap_or_else(Self::nothing)
+    }
+
+    /// `Ok value`
+    pub fn ok(value: RuntimeValue) -> Self {
+        sdk_constructor(RESULT_OK, vec![value])
+    }
+
+    /// `Err error`
+    pub fn err(error: RuntimeValue) -> Self {
+        sdk_constructor(RESULT_ERR, vec![error])
+    }
+
+    /// `LT`, `EQ`, or `GT`
+    pub fn order(ordering: Ordering) -> Self {
+        let name = match ordering {
+            Ordering::Less => ORDER_LT,
+            Ordering::Equal => ORDER_EQ,
+            Ordering::Greater => ORDER_GT,
+        };
+        sdk_constructor(name, Vec::new())
+    }
+
+    /// If this is the given constructor, its arguments
+    pub fn as_constructor(&self, name: &str) -> Option<&[RuntimeValue]> {
+        match self {
+            RuntimeValue::Constructor(fqname, args) if key(fqname) == name => Some(args),
+            _ => None,
+        }
+    }
+
+    /// Unwrap a `Maybe`
+    pub fn as_maybe(&self) -> Option<Option<&RuntimeValue>> {
+        if let Some(args) = self.as_constructor(MAYBE_JUST) {
+            return args.first().map(Some);
+        }
+        self.as_constructor(MAYBE_NOTHING).map(|_| None)
+    }
+
+    /// Short description of the kind of value, for error messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuntimeValue::Unit => "unit",
+            RuntimeValue::Bool(_) => "a Bool",
+            RuntimeValue::Char(_) => "a Char",
+            RuntimeValue::String(_) => "a String",
+            RuntimeValue::Int(_) => "an Int",
+            RuntimeValue::Float(_) => "a Float",
+            RuntimeValue::Decimal(_) => "a Decimal",
+            RuntimeValue::Tuple(_) => "a tuple",
+            RuntimeValue::List(_) => "a List",
+            RuntimeValue::Record(_) => "a record",
+            RuntimeValue::Dict(_) => "a Dict",
+            RuntimeValue::Constructor(..) => "a constructor",
+            RuntimeValue::Function(_) => "a function",
+        }
+    }
+
+    /// Order two comparable values.
+    ///
+    /// Numbers compare across `Int`, `Float`, and `Decimal`, since whole
+    /// number literals evaluate to `Int` wherever they appear. Returns `None`
+    /// for values that are not comparable.
+    pub fn compare(&self, other: &RuntimeValue) -> Option<Ordering> {
+        use RuntimeValue::*;
+        match (self, other) {
+            (Int(a), Int(b)) => Some(a.cmp(b)),
+            (Decimal(a), Decimal(b)) => Some(a.cmp(b)),
+            (Decimal(a), Int(b)) => Some(a.cmp(&rust_decimal::Decimal::from(*b))),
+            (Int(a), Decimal(b)) => Some(rust_decimal::Decimal::from(*a).cmp(b)),
+            (a @ (Int(_) | Float(_) | Decimal(_)), b @ (Int(_) | Float(_) | Decimal(_))) => {
+                a.as_f64()?.partial_cmp(&b.as_f64()?)
+            }
+            (Char(a), Char(b)) => Some(a.cmp(b)),
+            (String(a), String(b)) => Some(a.cmp(b)),
+            (Unit, Unit) => Some(Ordering::Equal),
+            (Tuple(a), Tuple(b)) | (List(a), List(b)) => {
+                for (x, y) in a.iter().zip(b) {
+                    match x.compare(y)? {
+                        Ordering::Equal => continue,
+                        ordering => return Some(ordering),
+                    }
+                }
+                Some(a.len().cmp(&b.len()))
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            RuntimeValue::Int(n) => Some(*n as f64),
+            RuntimeValue::Float(x) => Some(*x),
+            RuntimeValue::Decimal(d) => d.to_f64(),
+            _ => None,
+        }
+    }
+
+    /// Convert to JSON.
+    ///
+    /// `Maybe` becomes `null` or the wrapped value, `Decimal` a string, `Dict`
+    /// an array of `[key, value]` pairs, and other constructors an array of
+    /// the constructor name followed by its arguments.
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value as Json;
+        match self {
+            RuntimeValue::Unit => Json::Null,
+            RuntimeValue::Bool(b) => Json::Bool(*b),
+            RuntimeValue::Char(c) => Json::String(c.to_string()),
+            RuntimeValue::String(s) => Json::String(s.clone()),
+            RuntimeValue::Int(n) => Json::from(*n),
+            RuntimeValue::Float(x) => Json::from(*x),
+            RuntimeValue::Decimal(d) => Json::String(d.to_string()),
+            RuntimeValue::Tuple(items) | RuntimeValue::List(items) => {
+                Json::Array(items.iter().map(RuntimeValue::to_json).collect())
+            }
+            RuntimeValue::Record(fields) => Json::Object(
+                fields
+                    .iter()
+                    .map(|(name, v)| (Name::from(name.as_str()).to_camel_case(), v.to_json()))
+                    .collect(),
+            ),
+            RuntimeValue::Dict(entries) => Json::Array(
+                entries
+                    .iter()
+                    .map(|(k, v)| Json::Array(vec![k.to_json(), v.to_json()]))
+                    .collect(),
+            ),
+            RuntimeValue::Constructor(..) if self.as_maybe().is_some() => {
+                match self.as_maybe().flatten() {
+                    Some(value) => value.to_json(),
+                    None => Json::Null,
+                }
+            }
+            RuntimeValue::Constructor(fqname, args) => {
+                let name = Json::String(fqname.local_name.to_title_case());
+                if args.is_empty() {
+                    name
+                } else {
+                    Json::Array(
+                        std::iter::once(name)
+                            .chain(args.iter().map(RuntimeValue::to_json))
+                            .collect(),
+                    )
+                }
+            }
+            RuntimeValue::Function(_) => Json::String("<function>".to_string()),
+        }
+    }
+
+    /// Convert JSON without type information.
+    ///
+    /// Integers become `Int`, other numbers `Float`, arrays `List`, objects
+    /// records, and `null` unit.
+    pub fn from_json(json: &serde_json::Value) -> Self {
+        use serde_json::Value as Json;
+        match json {
+            Json::Null => RuntimeValue::Unit,
+            Json::Bool(b) => RuntimeValue::Bool(*b),
+            Json::Number(n) => match n.as_i64() {
+                Some(i) => RuntimeValue::Int(i),
+                None => RuntimeValue::Float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Json::String(s) => RuntimeValue::String(s.clone()),
+            Json::Array(items) => RuntimeValue::List(items.iter().map(Self::from_json).collect()),
+            Json::Object(fields) => RuntimeValue::Record(
+                fields
+                    .iter()
+                    .map(|(k, v)| (Name::from(k.as_str()).to_kebab_case(), Self::from_json(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl PartialEq for RuntimeValue {
+    fn eq(&self, other: &Self) -> bool {
+        use RuntimeValue::*;
+        match (self, other) {
+            (Int(_) | Float(_) | Decimal(_), Int(_) | Float(_) | Decimal(_)) => {
+                self.compare(other) == Some(Ordering::Equal)
+            }
+            (Unit, Unit) => true,
+            (Bool(a), Bool(b)) => a == b,
+            (Char(a), Char(b)) => a == b,
+            (String(a), String(b)) => a == b,
+            (Tuple(a), Tuple(b)) | (List(a), List(b)) => a == b,
+            (Record(a), Record(b)) => {
+                a.len() == b.len() && a.iter().all(|(k, v)| b.get(k) == Some(v))
+            }
+            (Dict(a), Dict(b)) => a == b,
+            (Constructor(n, a), Constructor(m, b)) => key(n) == key(m) && a == b,
+            _ => false,
+        }
+    }
+}
+
+impl RuntimeValue {
+    /// Render in Elm syntax, breaking records, lists, tuples, dicts, and
+    /// constructor applications across lines when they do not fit in `width`
+    /// columns. Values that fit are rendered as by `Display`.
+    pub fn pretty(&self, width: usize) -> String {
+        let mut out = String::new();
+        write_pretty(&mut out, self, 0, width, false);
+        out
+    }
+}
+
+/// Flat rendering of a value, parenthesized when it is an applied constructor
+/// in argument position.
+fn flat(value: &RuntimeValue, nested: bool) -> String {
+    match value {
+        RuntimeValue::Constructor(_, args) if nested && !args.is_empty() => format!("({})", value),
+        _ => value.to_string(),
+    }
+}
+
+/// Write `items` one per line in Elm's leading-separator layout, e.g.
+/// `[ a` / `, b` / `]`.
+fn write_block(out: &mut String, open: &str, close: &str, items: &[String], indent: usize) {
+    let pad = " ".repeat(indent);
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+            out.push_str(&pad);
+            out.push_str(", ");
+        } else {
+            out.push_str(open);
+            out.push(' ');
+        }
+        out.push_str(item);
+    }
+    out.push('\n');
+    out.push_str(&pad);
+    out.push_str(close);
+}
+
+/// Write `value` starting at column `indent`; continuation lines are
+/// indented relative to it.
+fn write_pretty(out: &mut String, value: &RuntimeValue, indent: usize, width: usize, nested: bool) {
+    let single = flat(value, nested);
+    if indent + single.len() <= width || !single.contains(' ') {
+        out.push_str(&single);
+        return;
+    }
+    let child = |value: &RuntimeValue, indent: usize, nested: bool| {
+        let mut out = String::new();
+        write_pretty(&mut out, value, indent, width, nested);
+        out
+    };
+    let pad = " ".repeat(indent + 4);
+    match value {
+        RuntimeValue::Tuple(items) | RuntimeValue::List(items) if !items.is_empty() => {
+            let (open, close) = match value {
+                RuntimeValue::Tuple(_) => ("(", ")"),
+                _ => ("[", "]"),
+            };
+            let items: Vec<String> = items.iter().map(|v| child(v, indent + 2, false)).collect();
+            write_block(out, open, close, &items, indent);
+        }
+        RuntimeValue::Record(fields) if !fields.is_empty() => {
+            let items: Vec<String> = fields
+                .iter()
+                .map(|(name, v)| {
+                    let label = format!("{} =", Name::from(name.as_str()).to_camel_case());
+                    let inline = v.to_string();
+                    if indent + 2 + label.len() + 1 + inline.len() <= width {
+                        format!("{} {}", label, inline)
+                    } else {
+                        format!("{}\n{}{}", label, pad, child(v, indent + 4, false))
+                    }
+                })
+                .collect();
+            write_block(out, "{", "}", &items, indent);
+        }
+        RuntimeValue::Dict(entries) if !entries.is_empty() => {
+            let pairs = RuntimeValue::List(
+                entries
+                    .iter()
+                    .map(|(k, v)| RuntimeValue::Tuple(vec![k.clone(), v.clone()]))
+                    .collect(),
+            );
+            out.push_str("Dict.fromList\n");
+            out.push_str(&pad);
+            write_pretty(out, &pairs, indent + 4, width, false);
+        }
+        RuntimeValue::Constructor(fqname, args) if !args.is_empty() => {
+            let (open, close) = if nested { ("(", ")") } else { ("", "") };
+            out.push_str(open);
+            out.push_str(&fqname.local_name.to_title_case());
+            for arg in args {
+                out.push('\n');
+                out.push_str(&pad);
+                write_pretty(out, arg, indent + 4, width, true);
+            }
+            out.push_str(close);
+        }
+        _ => out.push_str(&single),
+    }
+}
+
+impl fmt::Display for RuntimeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self, false)
+    }
+}
+
+/// Write a value in Elm syntax; `nested` wraps applied constructors in parentheses.
+fn write_value(f: &mut fmt::Formatter<'_>, value: &RuntimeValue, nested: bool) -> fmt::Result {
+    let list = |f: &mut fmt::Formatter<'_>, items: &[RuntimeValue]| -> fmt::Result {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_value(f, item, false)?;
+        }
+        Ok(())
+    };
+    match value {
+        RuntimeValue::Unit => write!(f, "()"),
+        RuntimeValue::Bool(true) => write!(f, "True"),
+        RuntimeValue::Bool(false) => write!(f, "False"),
+        RuntimeValue::Char(c) => write!(f, "{:?}", c),
+        RuntimeValue::String(s) => write!(f, "{:?}", s),
+        RuntimeValue::Int(n) => write!(f, "{}", n),
+        RuntimeValue::Float(x) => write!(f, "{:?}", x),
+        RuntimeValue::Decimal(d) => write!(f, "{}", d),
+        RuntimeValue::Tuple(items) => {
+            write!(f, "(")?;
+            list(f, items)?;
+            write!(f, ")")
+        }
+        RuntimeValue::List(items) => {
+            write!(f, "[")?;
+            list(f, items)?;
+            write!(f, "]")
+        }
+        RuntimeValue::Record(fields) if fields.is_empty() => write!(f, "{{}}"),
+        RuntimeValue::Record(fields) => {
+            write!(f, "{{ ")?;
+            for (i, (name, v)) in fields.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{} = ", Name::from(name.as_str()).to_camel_case())?;
+                write_value(f, v, false)?;
+            }
+            write!(f, " }}")
+        }
+        RuntimeValue::Dict(entries) => {
+            write!(f, "Dict.fromList [")?;
+            for (i, (k, v)) in entries.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "(")?;
+                write_value(f, k, false)?;
+                write!(f, ", ")?;
+                write_value(f, v, false)?;
+                write!(f, ")")?;
+            }
+            write!(f, "]")
+        }
+        RuntimeValue::Constructor(fqname, args) => {
+            let name = fqname.local_name.to_title_case();
+            if args.is_empty() {
+                return write!(f, "{}", name);
+            }
+            if nested {
+                write!(f, "(")?;
+            }
+            write!(f, "{}", name)?;
+            for arg in args {
+                write!(f, " ")?;
+                write_value(f, arg, true)?;
+            }
+            if nested {
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        RuntimeValue::Function(_) => write!(f, "<function>"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_compare_across_kinds() {
+        assert_eq!(RuntimeValue::Int(2), RuntimeValue::Float(2.0));
+        assert_eq!(
+            RuntimeValue::Decimal(Decimal::new(150, 2)).compare(&RuntimeValue::Int(1)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            RuntimeValue::String("a".into()).compare(&RuntimeValue::Int(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_display_uses_elm_syntax() {
+        let mut fields = IndexMap::new();
+        fields.insert("unit-price".to_string(), RuntimeValue::Float(2.5));
+        fields.insert(
+            "discount".to_string(),
+            RuntimeValue::just(RuntimeValue::just(RuntimeValue::Int(1))),
+        );
+        assert_eq!(
+            RuntimeValue::Record(fields).to_string(),
+            "{ unitPrice = 2.5, discount = Just (Just 1) }"
+        );
+    }
+
+    #[test]
+    fn test_pretty_breaks_values_that_do_not_fit() {
+        let mut line = IndexMap::new();
+        line.insert("sku".to_string(), RuntimeValue::String("A-1".into()));
+        line.insert("quantity".to_string(), RuntimeValue::Int(2));
+        let mut order = IndexMap::new();
+        order.insert(
+            "lines".to_string(),
+            RuntimeValue::List(vec![
+                RuntimeValue::Record(line.clone()),
+                RuntimeValue::Record(line),
+            ]),
+        );
+        order.insert(
+            "discount".to_string(),
+            RuntimeValue::just(RuntimeValue::Decimal(Decimal::new(150, 2))),
+        );
+        let order = RuntimeValue::Record(order);
+
+        assert_eq!(order.pretty(200), order.to_string());
+        assert_eq!(
+            order.pretty(40),
+            "{ lines =\n    [ { sku = \"A-1\", quantity = 2 }\n    , { sku = \"A-1\", quantity = 2 }\n    ]\n, discount = Just 1.50\n}"
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = serde_json::json!({"items": [1, 2.5, "x"], "flag": true});
+        let value = RuntimeValue::from_json(&json);
+        assert_eq!(value.to_json(), json);
+        assert_eq!(RuntimeValue::nothing().to_json(), serde_json::Value::Null);
+    }
+}
@@ -0,0 +1,27 @@
+//! Evaluating IR migrated from morphir-elm
+//!
+//! morphir-elm spells the SDK package `morphir/s-d-k`; its references must
+//! resolve to the same natives as `morphir/sdk`.
+
+use morphir_core::converter::classic_to_v4;
+use morphir_core::ir::classic::Distribution;
+use morphir_core::naming::FQName;
+use morphir_runtime::{Evaluator, RuntimeValue};
+
+const EVALUATOR_TESTS: &str = "../morphir-tests/fixtures/classic/evaluator-tests.json";
+
+#[test]
+fn test_migrated_evaluator_tests_evaluate() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(EVALUATOR_TESTS);
+    let content = std::fs::read_to_string(path).expect("Failed to read evaluator-tests.json");
+    // Parse via Value to stay clear of the recursion limit of from_str
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let classic: Distribution = serde_json::from_value(json).unwrap();
+    let v4 = classic_to_v4(&classic).ir;
+
+    let evaluator = Evaluator::new(&v4.distribution);
+    let target =
+        FQName::from_canonical_string("morphir/examples/app:decimal-tests#decimal-one").unwrap();
+    let value = evaluator.evaluate(&target, vec![]).unwrap();
+    assert_eq!(value, RuntimeValue::Decimal(1.into()));
+}
@@ -4,8 +4,7 @@ use cucumber::{World, given, then, when};
 use morphir_common::config::MorphirConfig;
 use morphir_common::loader::{self, LoadedDistribution};
 use morphir_common::vfs::{MemoryVfs, OsVfs, Vfs};
-// Note: converter module is disabled pending update to non-generic V4 types
-// use morphir_core::converter;
+use morphir_core::converter;
 use morphir_core::ir::v4;
 use std::path::{Path, PathBuf};
 
@@ -154,14 +153,37 @@ async fn i_load_distribution_from_file(w: &mut TestWorld) {
     }
 }
 
-// Note: IR migration tests are disabled pending converter module update
+/// Convert a loaded distribution to the target version and serialize it.
+fn migrate_distribution(dist: LoadedDistribution, target_version: &str) -> Result<String> {
+    let content = match (dist, target_version) {
+        (LoadedDistribution::Classic(dist), "v4") => {
+            serde_json::to_string_pretty(&converter::classic_to_v4(&dist).ir)?
+        }
+        (LoadedDistribution::V4(ir), "classic") => {
+            serde_json::to_string_pretty(&converter::v4_to_classic(&ir)?.ir)?
+        }
+        (LoadedDistribution::Classic(dist), _) => serde_json::to_string_pretty(&dist)?,
+        (LoadedDistribution::V4(ir), _) => serde_json::to_string_pretty(&ir)?,
+    };
+    Ok(content)
+}
+
+fn run_migrate(w: &mut TestWorld, vfs: &impl Vfs, path: &Path, target_version: &str) {
+    let result = loader::load_distribution(vfs, path)
+        .and_then(|dist| migrate_distribution(dist, target_version));
+    match result {
+        Ok(content) => {
+            w.loaded_content = Some(content);
+            w.last_result = Some(Ok(()));
+        }
+        Err(e) => w.last_result = Some(Err(e)),
+    }
+}
+
 #[when(expr = "I run \"morphir ir migrate\" to version {string}")]
-async fn i_run_migrate(w: &mut TestWorld, _target_version: String) {
-    // Converter module is disabled pending update to non-generic V4 types
-    // See: crates/morphir-core/src/lib.rs TODO comment
-    w.last_result = Some(Err(anyhow::anyhow!(
-        "IR migration not available: converter module pending update"
-    )));
+async fn i_run_migrate(w: &mut TestWorld, target_version: String) {
+    let path = w.input_path.clone();
+    run_migrate(w, &OsVfs, &path, &target_version);
 }
 
 #[then(expr = "I should get a valid {string} IR distribution")]
@@ -206,14 +228,17 @@ async fn save_result_as_intermediate(w: &mut TestWorld) {
     w.intermediate_content = w.loaded_content.clone();
 }
 
-// Note: IR migration on intermediate tests are disabled pending converter module update
 #[when(expr = "I run \"morphir ir migrate\" on intermediate to version {string}")]
-async fn run_migrate_on_intermediate(w: &mut TestWorld, _target_version: String) {
-    // Converter module is disabled pending update to non-generic V4 types
-    // See: crates/morphir-core/src/lib.rs TODO comment
-    w.last_result = Some(Err(anyhow::anyhow!(
-        "IR migration not available: converter module pending update"
-    )));
+async fn run_migrate_on_intermediate(w: &mut TestWorld, target_version: String) {
+    let content = w
+        .intermediate_content
+        .clone()
+        .expect("No intermediate content saved");
+    let vfs = MemoryVfs::new();
+    let path = Path::new("intermediate.json");
+    vfs.write_from_string(path, &content)
+        .expect("Failed to write intermediate content");
+    run_migrate(w, &vfs, path, &target_version);
 }
 
 // V4 Format Validation Steps
@@ -422,6 +447,8 @@ async fn value_defs_have_body(w: &mut TestWorld) {
         for (mod_name, module) in modules {
             if let Some(values) = module.pointer("/value/values").and_then(|v| v.as_object()) {
                 for (val_name, val_def) in values {
+                    // Definitions are access controlled: {access, doc, value}
+                    let val_def = &val_def["value"];
                     let body = val_def.pointer("/body/ExpressionBody/body");
                     assert!(
                         body.is_some() && !body.unwrap().is_null(),
@@ -447,6 +474,8 @@ async fn value_defs_have_input_types(w: &mut TestWorld) {
         for (_, module) in modules {
             if let Some(values) = module.pointer("/value/values").and_then(|v| v.as_object()) {
                 for (val_name, val_def) in values {
+                    // Definitions are access controlled: {access, doc, value}
+                    let val_def = &val_def["value"];
                     if let Some(input_types) = val_def.get("inputTypes").and_then(|i| i.as_object())
                     {
                         for (param_name, param_def) in input_types {
@@ -478,6 +507,8 @@ async fn value_defs_have_output_type(w: &mut TestWorld) {
         for (mod_name, module) in modules {
             if let Some(values) = module.pointer("/value/values").and_then(|v| v.as_object()) {
                 for (val_name, val_def) in values {
+                    // Definitions are access controlled: {access, doc, value}
+                    let val_def = &val_def["value"];
                     let output_type = val_def.get("outputType");
                     assert!(
                         output_type.is_some() && !output_type.unwrap().is_null(),
@@ -655,19 +686,13 @@ async fn i_visit_expression(w: &mut TestWorld) {
 }
 
 #[then(expr = "the module count should be {int}")]
-async fn module_count_should_be(w: &mut TestWorld, _count: usize) {
+async fn module_count_should_be(_w: &mut TestWorld, _count: usize) {
     // Skip assertion since visitor is disabled
-    if w.last_result.as_ref().is_some_and(|r| r.is_err()) {
-        return; // Visitor was disabled, skip
-    }
 }
 
 #[then(expr = "the variable count should be {int}")]
-async fn variable_count_should_be(w: &mut TestWorld, _count: usize) {
+async fn variable_count_should_be(_w: &mut TestWorld, _count: usize) {
     // Skip assertion since visitor is disabled
-    if w.last_result.as_ref().is_some_and(|r| r.is_err()) {
-        return; // Visitor was disabled, skip
-    }
 }
 
 #[tokio::main]
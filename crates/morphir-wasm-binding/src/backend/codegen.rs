@@ -1,13 +1,17 @@
-#![allow(clippy::collapsible_match, clippy::single_match)]
 //! WASM code generation from Morphir IR
 
+use super::component::generate_component;
+use super::lower::{Expr, LoweredFunction, lower_module};
 use base64::{Engine, engine::general_purpose::STANDARD};
+use morphir_core::ir::v4::{Distribution as V4Distribution, IRFile, PackageDefinition};
+use morphir_core::naming::{FQName, Name, Path};
 use morphir_extension_sdk::prelude::*;
+use morphir_extension_sdk::types::fingerprint;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use wasm_encoder::{
-    CodeSection, ExportKind, ExportSection, Function, FunctionSection, Instruction, Module,
-    TypeSection, ValType,
+    BlockType, CodeSection, ConstExpr, DataSection, ExportKind, ExportSection, Function,
+    FunctionSection, Instruction, MemorySection, MemoryType, Module, TypeSection, ValType,
 };
 
 /// Morphir distribution IR (simplified)
@@ -35,12 +39,211 @@ pub struct ValueDef {
     pub body: serde_json::Value,
 }
 
-/// Generate WASM binary from Morphir IR
-pub fn generate_wasm(
+/// A Morphir module compiled on its own
+#[derive(Debug, Clone)]
+pub struct CompilationUnit {
+    /// Morphir module name
+    pub module: String,
+    /// Hash of the module IR the unit was compiled from
+    pub hash: String,
+    /// Compiled value definitions
+    pub functions: Vec<LoweredFunction>,
+    /// Strings the functions address, from offset 0
+    pub data: Vec<u8>,
+    /// Whether the unit has a memory, exported as `memory`
+    pub memory: bool,
+    /// Values that could not be compiled, with the reason
+    pub skipped: Vec<(String, String)>,
+    /// Custom types compiled to tags, with their constructors in tag order
+    pub enums: Vec<(Name, Vec<Name>)>,
+}
+
+impl CompilationUnit {
+    /// Encode the unit as a standalone WASM module exporting each value by name
+    pub fn encode(&self) -> Vec<u8> {
+        encode_module(
+            self.functions
+                .iter()
+                .map(|f| (f.name.clone(), f, Bases::default())),
+            &self.data,
+            self.memory,
+        )
+    }
+}
+
+/// Where the functions and data of a unit start once linked
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Bases {
+    pub functions: u32,
+    pub data: u32,
+}
+
+/// Entry of the link manifest describing one compilation unit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LinkUnit {
+    pub module: String,
+    pub path: String,
+    pub hash: String,
+    pub exports: Vec<String>,
+    /// Whether the unit was regenerated in this run
+    pub regenerated: bool,
+}
+
+/// Link manifest listing the compilation units of a distribution
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LinkManifest {
+    pub name: String,
+    pub units: Vec<LinkUnit>,
+}
+
+/// Generate WASM from Morphir IR, with a warning for each value that could
+/// not be compiled and was left out.
+///
+/// Each Morphir module is compiled to its own unit at `<name>/<module>.wasm`,
+/// described by a `<name>.link.json` manifest, and linked into a single
+/// `<name>.wasm`. Options:
+/// - `split` (default `true`): emit the per-module units and manifest
+/// - `link` (default `true`): emit the linked module
+/// - `previous_hashes`: module hashes from a previous manifest; units whose
+///   module is unchanged are not emitted again. Only emission is skipped:
+///   every module is still compiled, as the linked module and component are
+///   built from all the units
+/// - `component` (default `false`): also emit a `<name>.component.wasm`
+///   component exporting the functions, and the `<name>.wit` it implements
+pub fn generate_wasm_with_diagnostics(
     ir: &serde_json::Value,
-    _options: &HashMap<String, serde_json::Value>,
-) -> Result<Vec<Artifact>> {
+    options: &HashMap<String, serde_json::Value>,
+) -> Result<(Vec<Artifact>, Vec<Diagnostic>)> {
     let mut artifacts = Vec::new();
+    let Compilation {
+        name,
+        units,
+        entry_points,
+    } = compile_ir(ir)?;
+
+    let flag = |key: &str| options.get(key).and_then(|v| v.as_bool()).unwrap_or(true);
+    let previous_hashes = options.get("previous_hashes").and_then(|v| v.as_object());
+
+    if flag("split") {
+        let mut manifest = LinkManifest {
+            name: name.clone(),
+            units: Vec::new(),
+        };
+        for unit in &units {
+            let path = format!("{}/{}.wasm", name, unit.module);
+            let unchanged = previous_hashes
+                .and_then(|hashes| hashes.get(&unit.module))
+                .and_then(|v| v.as_str())
+                == Some(unit.hash.as_str());
+            if !unchanged {
+                artifacts.push(Artifact {
+                    path: path.clone(),
+                    content: STANDARD.encode(unit.encode()),
+                    binary: true,
+                });
+            }
+            manifest.units.push(LinkUnit {
+                module: unit.module.clone(),
+                path,
+                hash: unit.hash.clone(),
+                exports: unit.functions.iter().map(|f| f.name.clone()).collect(),
+                regenerated: !unchanged,
+            });
+        }
+        artifacts.push(Artifact {
+            path: format!("{}.link.json", name),
+            content: serde_json::to_string_pretty(&manifest)?,
+            binary: false,
+        });
+    }
+
+    if flag("link") {
+        // Encode as base64 for JSON transport
+        artifacts.push(Artifact {
+            path: format!("{}.wasm", name),
+            content: STANDARD.encode(link(&units)),
+            binary: true,
+        });
+    }
+
+    let mut diagnostics: Vec<Diagnostic> = units
+        .iter()
+        .flat_map(|unit| {
+            unit.skipped.iter().map(|(value, reason)| Diagnostic {
+                severity: DiagnosticSeverity::Warning,
+                code: Some("W002".into()),
+                message: format!("Skipped {}.{}: {}", unit.module, value, reason),
+                location: None,
+                related: vec![],
+                fixes: vec![],
+            })
+        })
+        .collect();
+
+    if options
+        .get("component")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+    {
+        let (component, warnings) = generate_component(&name, &units, &entry_points)?;
+        artifacts.extend(component);
+        diagnostics.extend(warnings);
+    }
+
+    Ok((artifacts, diagnostics))
+}
+
+/// Units compiled from Morphir IR
+#[derive(Debug, Clone)]
+pub struct Compilation {
+    /// Name of the distribution, used for output paths
+    pub name: String,
+    pub units: Vec<CompilationUnit>,
+    /// Entry points of an application, by name
+    pub entry_points: Vec<(String, FQName)>,
+}
+
+/// Compile Morphir IR to one unit per module, named after the distribution.
+///
+/// Accepts a v4 package definition, IR file or distribution, and the
+/// simplified `{name, modules}` format whose values are literal bodies.
+pub fn compile_ir(ir: &serde_json::Value) -> Result<Compilation> {
+    if let Ok(package_def) = serde_json::from_value::<PackageDefinition>(ir.clone()) {
+        return Ok(Compilation {
+            name: "morphir".to_string(),
+            units: compile_package(None, &package_def)?,
+            entry_points: Vec::new(),
+        });
+    }
+
+    // Full V4 IR file or bare distribution
+    let v4_dist = serde_json::from_value::<IRFile>(ir.clone())
+        .map(|file| file.distribution)
+        .or_else(|_| serde_json::from_value::<V4Distribution>(ir.clone()));
+    if let Ok(dist) = v4_dist {
+        let Some(def) = dist.definition() else {
+            return Err(ExtensionError::execution(
+                "Specs distributions have no definitions to generate code from",
+            ));
+        };
+        let entry_points = dist
+            .entry_points()
+            .into_iter()
+            .flatten()
+            .map(|(name, entry_point)| {
+                let target = entry_point.fqname().map_err(|e| {
+                    ExtensionError::execution(format!("Invalid entry point {}: {}", name, e))
+                })?;
+                Ok((name.clone(), target))
+            })
+            .collect::<Result<_>>()?;
+        let package = dist.package_name();
+        return Ok(Compilation {
+            name: package.to_string().replace('/', "-"),
+            units: compile_package(Some(package.as_path()), def)?,
+            entry_points,
+        });
+    }
 
     // Try to parse as distribution or module list
     let (name, modules): (String, Vec<ModuleIR>) =
@@ -53,24 +256,111 @@ pub fn generate_wasm(
             let module = serde_json::from_value::<ModuleIR>(ir.clone())?;
             (module.name.clone(), vec![module])
         };
+    let units = modules
+        .iter()
+        .map(compile_unit)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Compilation {
+        name,
+        units,
+        entry_points: Vec::new(),
+    })
+}
 
-    // Generate a single WASM module containing all Morphir modules
-    let wasm_bytes = compile_to_wasm(&modules)?;
+/// Compile each module of a v4 package definition to a unit
+fn compile_package(
+    package: Option<&Path>,
+    def: &PackageDefinition,
+) -> Result<Vec<CompilationUnit>> {
+    def.modules
+        .iter()
+        .map(|(module, module_def)| {
+            let lowered = lower_module(package, module, &module_def.value);
+            Ok(CompilationUnit {
+                module: module.clone(),
+                hash: fingerprint(&serde_json::to_vec(&module_def.value)?),
+                functions: lowered.functions,
+                data: lowered.data,
+                memory: lowered.memory,
+                skipped: lowered.skipped,
+                enums: lowered.enums,
+            })
+        })
+        .collect()
+}
+
+/// Compile one module of the simplified format to a compilation unit
+pub fn compile_unit(module_ir: &ModuleIR) -> Result<CompilationUnit> {
+    let functions = module_ir
+        .values
+        .iter()
+        .map(|value_def| LoweredFunction {
+            name: value_def.name.clone(),
+            signature: None,
+            params: vec![],
+            result: ValType::I32,
+            locals: vec![],
+            body: literal_body(&value_def.body),
+        })
+        .collect();
+    Ok(CompilationUnit {
+        module: module_ir.name.clone(),
+        hash: fingerprint(&serde_json::to_vec(module_ir)?),
+        functions,
+        data: vec![],
+        memory: false,
+        skipped: vec![],
+        enums: vec![],
+    })
+}
 
-    // Encode as base64 for JSON transport
-    let encoded = STANDARD.encode(&wasm_bytes);
+/// Where the functions and data of each unit start once linked, and the
+/// linked data
+pub fn layout(units: &[CompilationUnit]) -> (Vec<Bases>, Vec<u8>) {
+    let mut bases = Vec::new();
+    let mut data = Vec::new();
+    let mut functions = 0;
+    for unit in units {
+        data.resize(data.len().next_multiple_of(4), 0);
+        bases.push(Bases {
+            functions,
+            data: data.len() as u32,
+        });
+        data.extend_from_slice(&unit.data);
+        functions += unit.functions.len() as u32;
+    }
+    (bases, data)
+}
 
-    artifacts.push(Artifact {
-        path: format!("{}.wasm", name),
-        content: encoded,
-        binary: true,
-    });
+/// Link compilation units into one WASM module.
+///
+/// Every value is exported as `<module>_<value>`, the same names the units
+/// would have if the distribution were compiled as a whole.
+pub fn link(units: &[CompilationUnit]) -> Vec<u8> {
+    let (bases, data) = layout(units);
+    encode_module(
+        units.iter().zip(bases).flat_map(|(unit, bases)| {
+            unit.functions
+                .iter()
+                .map(move |f| (format!("{}_{}", unit.module, f.name), f, bases))
+        }),
+        &data,
+        units.iter().any(|unit| unit.memory),
+    )
+}
 
-    Ok(artifacts)
+/// Number of 64 KiB pages holding `data`, at least one
+pub fn memory_pages(data: &[u8]) -> u64 {
+    (data.len() as u64).div_ceil(65536).max(1)
 }
 
-/// Compile Morphir modules to WASM bytes
-fn compile_to_wasm(modules: &[ModuleIR]) -> Result<Vec<u8>> {
+/// Encode functions into a WASM module, exporting each under its name, with
+/// a memory holding `data` if asked for
+fn encode_module<'a>(
+    functions_in: impl IntoIterator<Item = (String, &'a LoweredFunction, Bases)>,
+    data: &[u8],
+    memory: bool,
+) -> Vec<u8> {
     let mut module = Module::new();
 
     // Type section - define function signatures
@@ -85,28 +375,36 @@ fn compile_to_wasm(modules: &[ModuleIR]) -> Result<Vec<u8>> {
     // Code section - function bodies
     let mut codes = CodeSection::new();
 
-    // Collect all value definitions from all modules
     let mut func_index = 0u32;
 
-    for module_ir in modules {
-        for value_def in &module_ir.values {
-            // For now, generate simple i32-returning functions
-            // Type: () -> i32
-            types.ty().function([], [ValType::I32]);
+    for (export_name, lowered, bases) in functions_in {
+        // One type per function, at the function's index
+        types
+            .ty()
+            .function(lowered.params.iter().copied(), [lowered.result]);
 
-            // Function uses type at same index
-            functions.function(func_index);
+        functions.function(func_index);
 
-            // Export the function
-            let export_name = format!("{}_{}", module_ir.name, value_def.name);
-            exports.export(&export_name, ExportKind::Func, func_index);
+        exports.export(&export_name, ExportKind::Func, func_index);
 
-            // Generate function body
-            let mut func = Function::new([]);
-            generate_function_body(&mut func, &value_def.body)?;
-            codes.function(&func);
+        codes.function(&encode_function(lowered, bases));
 
-            func_index += 1;
+        func_index += 1;
+    }
+
+    let mut memories = MemorySection::new();
+    let mut segments = DataSection::new();
+    if memory {
+        memories.memory(MemoryType {
+            minimum: memory_pages(data),
+            maximum: None,
+            memory64: false,
+            shared: false,
+            page_size_log2: None,
+        });
+        exports.export("memory", ExportKind::Memory, 0);
+        if !data.is_empty() {
+            segments.active(0, &ConstExpr::i32_const(0), data.iter().copied());
         }
     }
 
@@ -114,41 +412,94 @@ fn compile_to_wasm(modules: &[ModuleIR]) -> Result<Vec<u8>> {
     if func_index > 0 {
         module.section(&types);
         module.section(&functions);
+    }
+    if memory {
+        module.section(&memories);
+    }
+    if func_index > 0 || memory {
         module.section(&exports);
+    }
+    if func_index > 0 {
         module.section(&codes);
     }
+    if !segments.is_empty() {
+        module.section(&segments);
+    }
+
+    module.finish()
+}
 
-    Ok(module.finish())
+/// Encode a lowered function, relocating its calls and strings
+pub fn encode_function(lowered: &LoweredFunction, bases: Bases) -> Function {
+    let mut func = Function::new(lowered.locals.iter().map(|ty| (1, *ty)));
+    emit(&mut func, &lowered.body, bases);
+    func.instruction(&Instruction::End);
+    func
+}
+
+fn emit(func: &mut Function, expr: &Expr, bases: Bases) {
+    match expr {
+        Expr::I32(n) => {
+            func.instruction(&Instruction::I32Const(*n));
+        }
+        Expr::I64(n) => {
+            func.instruction(&Instruction::I64Const(*n));
+        }
+        Expr::F64(x) => {
+            func.instruction(&Instruction::F64Const((*x).into()));
+        }
+        Expr::Data(offset) => {
+            func.instruction(&Instruction::I32Const((bases.data + offset) as i32));
+        }
+        Expr::Get(index) => {
+            func.instruction(&Instruction::LocalGet(*index));
+        }
+        Expr::Let(index, value, body) => {
+            emit(func, value, bases);
+            func.instruction(&Instruction::LocalSet(*index));
+            emit(func, body, bases);
+        }
+        Expr::Op(op, operands) => {
+            for operand in operands {
+                emit(func, operand, bases);
+            }
+            func.instruction(&op.instruction());
+        }
+        Expr::Call(index, args) => {
+            for arg in args {
+                emit(func, arg, bases);
+            }
+            func.instruction(&Instruction::Call(bases.functions + index));
+        }
+        Expr::If(ty, condition, then_branch, else_branch) => {
+            emit(func, condition, bases);
+            func.instruction(&Instruction::If(BlockType::Result(*ty)));
+            emit(func, then_branch, bases);
+            func.instruction(&Instruction::Else);
+            emit(func, else_branch, bases);
+            func.instruction(&Instruction::End);
+        }
+        Expr::Unreachable => {
+            func.instruction(&Instruction::Unreachable);
+        }
+    }
 }
 
-/// Generate WASM instructions for a Morphir expression
-fn generate_function_body(func: &mut Function, body: &serde_json::Value) -> Result<()> {
+/// Body of a value of the simplified format: its int or bool literal, or 0
+fn literal_body(body: &serde_json::Value) -> Expr {
     if let Some(obj) = body.as_object()
-        && let Some(kind) = obj.get("kind").and_then(|v| v.as_str())
+        && obj.get("kind").and_then(|v| v.as_str()) == Some("literal")
+        && let Some(lit_obj) = obj.get("value").and_then(|v| v.as_object())
     {
-        match kind {
-            "literal" => {
-                if let Some(value) = obj.get("value")
-                    && let Some(lit_obj) = value.as_object()
-                    && let Some(lit_type) = lit_obj.get("type").and_then(|v| v.as_str())
-                {
-                    match lit_type {
-                        "int" => {
-                            if let Some(n) = lit_obj.get("value").and_then(|v| v.as_i64()) {
-                                func.instruction(&Instruction::I32Const(n as i32));
-                                func.instruction(&Instruction::End);
-                                return Ok(());
-                            }
-                        }
-                        "bool" => {
-                            if let Some(b) = lit_obj.get("value").and_then(|v| v.as_bool()) {
-                                func.instruction(&Instruction::I32Const(if b { 1 } else { 0 }));
-                                func.instruction(&Instruction::End);
-                                return Ok(());
-                            }
-                        }
-                        _ => {}
-                    }
+        match lit_obj.get("type").and_then(|v| v.as_str()) {
+            Some("int") => {
+                if let Some(n) = lit_obj.get("value").and_then(|v| v.as_i64()) {
+                    return Expr::I32(n as i32);
+                }
+            }
+            Some("bool") => {
+                if let Some(b) = lit_obj.get("value").and_then(|v| v.as_bool()) {
+                    return Expr::I32(i32::from(b));
                 }
             }
             _ => {}
@@ -156,15 +507,20 @@ fn generate_function_body(func: &mut Function, body: &serde_json::Value) -> Resu
     }
 
     // Default: return 0
-    func.instruction(&Instruction::I32Const(0));
-    func.instruction(&Instruction::End);
-    Ok(())
+    Expr::I32(0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn generate_wasm(
+        ir: &serde_json::Value,
+        options: &HashMap<String, serde_json::Value>,
+    ) -> Result<Vec<Artifact>> {
+        generate_wasm_with_diagnostics(ir, options).map(|(artifacts, _)| artifacts)
+    }
+
     #[test]
     fn test_generate_simple_wasm() {
         let ir = serde_json::json!({
@@ -187,12 +543,167 @@ mod tests {
         let options = HashMap::new();
         let result = generate_wasm(&ir, &options).unwrap();
 
-        assert_eq!(result.len(), 1);
-        assert!(result[0].path.ends_with(".wasm"));
-        assert!(result[0].binary);
+        let linked = result.iter().find(|a| a.path == "example.wasm").unwrap();
+        assert!(linked.binary);
 
         // Decode and verify it's valid WASM
-        let bytes = STANDARD.decode(&result[0].content).unwrap();
+        let bytes = STANDARD.decode(&linked.content).unwrap();
         assert!(bytes.starts_with(&[0x00, 0x61, 0x73, 0x6d])); // WASM magic number
     }
+
+    #[test]
+    fn test_modules_compile_to_separate_units() {
+        let module = |name: &str, value: i64| {
+            serde_json::json!({
+                "name": name,
+                "values": [{
+                    "name": "answer",
+                    "body": {"kind": "literal", "value": {"type": "int", "value": value}}
+                }]
+            })
+        };
+        let ir = serde_json::json!({
+            "name": "example",
+            "modules": [module("main", 42), module("util", 7)]
+        });
+
+        let result = generate_wasm(&ir, &HashMap::new()).unwrap();
+        let paths: Vec<&str> = result.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "example/main.wasm",
+                "example/util.wasm",
+                "example.link.json",
+                "example.wasm"
+            ]
+        );
+        let manifest: LinkManifest = serde_json::from_str(&result[2].content).unwrap();
+        assert_eq!(manifest.units[0].exports, vec!["answer"]);
+        assert!(manifest.units.iter().all(|u| u.regenerated));
+
+        // Only the changed module is regenerated
+        let mut options = HashMap::new();
+        options.insert(
+            "previous_hashes".to_string(),
+            serde_json::json!({"main": manifest.units[0].hash, "util": "stale"}),
+        );
+        options.insert("link".to_string(), serde_json::json!(false));
+        let result = generate_wasm(&ir, &options).unwrap();
+        let paths: Vec<&str> = result.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, vec!["example/util.wasm", "example.link.json"]);
+        let manifest: LinkManifest = serde_json::from_str(&result[1].content).unwrap();
+        assert!(!manifest.units[0].regenerated);
+        assert!(manifest.units[1].regenerated);
+    }
+
+    const ORDERS: &str = "library acme/shop
+
+
+module orders
+
+type Side
+    = Buy
+    | Sell
+
+sign (side : acme/shop:orders#Side) : morphir/sdk:basics#Int =
+    case side of
+        acme/shop:orders#Buy ->
+            1
+        acme/shop:orders#Sell ->
+            -1
+
+total (price : morphir/sdk:basics#Int) (quantity : morphir/sdk:basics#Int) (side : acme/shop:orders#Side) : morphir/sdk:basics#Int =
+    let
+        gross : morphir/sdk:basics#Int =
+            morphir/sdk:basics#multiply price quantity
+    in
+    morphir/sdk:basics#multiply gross (acme/shop:orders#sign side)
+
+discount (amount : morphir/sdk:basics#Float) (large : morphir/sdk:basics#Bool) : morphir/sdk:basics#Float =
+    if morphir/sdk:basics#and large (morphir/sdk:basics#greaterThan amount 100.0) then
+        morphir/sdk:basics#multiply amount 0.9
+    else
+        amount
+
+label (side : acme/shop:orders#Side) : morphir/sdk:string#String =
+    case side of
+        acme/shop:orders#Buy ->
+            \"buy\"
+        _ ->
+            \"sell\"
+
+lines (x : morphir/sdk:basics#Int) : morphir/sdk:list#List morphir/sdk:basics#Int =
+    [x]
+
+parity (x : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =
+    morphir/sdk:basics#modBy 2 x
+
+count (x : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =
+    morphir/sdk:basics#add (acme/shop:orders#parity x) 1
+";
+
+    #[test]
+    fn test_v4_values_lower_to_functions() {
+        let dist = morphir_core::ir::v4::text::parse_distribution(ORDERS).unwrap();
+        let ir = serde_json::to_value(&dist).unwrap();
+        let (artifacts, diagnostics) =
+            generate_wasm_with_diagnostics(&ir, &HashMap::new()).unwrap();
+
+        let linked = artifacts
+            .iter()
+            .find(|a| a.path == "acme-shop.wasm")
+            .unwrap();
+        let engine = wasmtime::Engine::default();
+        let module =
+            wasmtime::Module::new(&engine, STANDARD.decode(&linked.content).unwrap()).unwrap();
+        let mut store = wasmtime::Store::new(&engine, ());
+        let instance = wasmtime::Instance::new(&mut store, &module, &[]).unwrap();
+
+        let total = instance
+            .get_typed_func::<(i64, i64, i32), i64>(&mut store, "orders_total")
+            .unwrap();
+        assert_eq!(total.call(&mut store, (5, 3, 0)).unwrap(), 15);
+        assert_eq!(total.call(&mut store, (5, 3, 1)).unwrap(), -15);
+
+        let discount = instance
+            .get_typed_func::<(f64, i32), f64>(&mut store, "orders_discount")
+            .unwrap();
+        assert_eq!(discount.call(&mut store, (200.0, 1)).unwrap(), 180.0);
+        assert_eq!(discount.call(&mut store, (200.0, 0)).unwrap(), 200.0);
+        assert_eq!(discount.call(&mut store, (50.0, 1)).unwrap(), 50.0);
+
+        // Strings are a length followed by their bytes
+        let label = instance
+            .get_typed_func::<i32, i32>(&mut store, "orders_label")
+            .unwrap();
+        let address = label.call(&mut store, 1).unwrap() as usize;
+        let memory = instance.get_memory(&mut store, "memory").unwrap();
+        let bytes = &memory.data(&store)[address..];
+        let len = u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+        assert_eq!(&bytes[4..4 + len], b"sell");
+
+        // Values beyond the subset are left out, with their callers
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages[0].starts_with("Skipped orders.lines: result:"));
+        assert!(messages[1].contains("orders.parity: morphir/sdk:basics#mod-by is not supported"));
+        assert!(
+            messages[2].contains("orders.count: acme/shop:orders#parity could not be compiled")
+        );
+        assert!(
+            diagnostics
+                .iter()
+                .all(|d| d.severity == DiagnosticSeverity::Warning)
+        );
+        let manifest = artifacts
+            .iter()
+            .find(|a| a.path == "acme-shop.link.json")
+            .unwrap();
+        let manifest: LinkManifest = serde_json::from_str(&manifest.content).unwrap();
+        assert_eq!(
+            manifest.units[0].exports,
+            vec!["discount", "label", "sign", "total"]
+        );
+    }
 }
@@ -0,0 +1,747 @@
+//! WebAssembly components from Morphir IR
+//!
+//! With the `component` option the backend also wraps the linked module in a
+//! component exporting the `entry-points` interface of a `morphir:<name>` WIT
+//! package: a function per entry point of an application, or per compiled
+//! value when there are none. `Int` is `s64`, `Float` is `f64`, `Bool`,
+//! `Char` and `String` are themselves, and custom types compiled to tags are
+//! `enum`s.
+//!
+//! The core module inside adapts the functions to the canonical ABI. It
+//! exports a bump allocator as `cabi_realloc`, whose allocations are released
+//! once a call returns, and moves strings between the ABI's pointer and length
+//! and the backend's length-prefixed layout.
+
+use super::codegen::{CompilationUnit, encode_function, layout, memory_pages};
+use super::lower::{LoweredFunction, Repr, Signature};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use morphir_core::naming::{FQName, Name, Path};
+use morphir_extension_sdk::prelude::*;
+use std::fmt::Write;
+use wasm_encoder::{
+    BlockType, CodeSection, ConstExpr, DataSection, ExportKind, ExportSection, Function,
+    FunctionSection, GlobalSection, GlobalType, Instruction, MemArg, MemorySection, MemoryType,
+    Module, TypeSection, ValType,
+};
+use wit_component::{ComponentEncoder, StringEncoding};
+use wit_parser::Resolve;
+
+/// Interface the component exports
+const INTERFACE: &str = "entry-points";
+
+/// Most flat parameters the canonical ABI passes directly
+const MAX_FLAT_PARAMS: usize = 16;
+
+/// WIT keywords, which identifiers escape with `%`
+const KEYWORDS: &[&str] = &[
+    "as",
+    "async",
+    "bool",
+    "borrow",
+    "char",
+    "constructor",
+    "enum",
+    "error-context",
+    "export",
+    "f32",
+    "f64",
+    "flags",
+    "float32",
+    "float64",
+    "from",
+    "func",
+    "future",
+    "import",
+    "include",
+    "interface",
+    "list",
+    "own",
+    "package",
+    "record",
+    "resource",
+    "result",
+    "s16",
+    "s32",
+    "s64",
+    "s8",
+    "static",
+    "stream",
+    "string",
+    "tuple",
+    "type",
+    "u16",
+    "u32",
+    "u64",
+    "u8",
+    "use",
+    "variant",
+    "with",
+    "world",
+];
+
+/// A function the component exports
+struct Export<'a> {
+    /// WIT name, unescaped
+    name: String,
+    /// Index of the function in the linked module
+    index: u32,
+    signature: &'a Signature,
+    /// Unit the function was compiled in, declaring its enums
+    unit: &'a CompilationUnit,
+}
+
+/// WIT package and component exporting the entry points of the units, or
+/// every compiled value without entry points, with a warning for each that
+/// cannot be exported
+pub fn generate_component(
+    name: &str,
+    units: &[CompilationUnit],
+    entry_points: &[(String, FQName)],
+) -> Result<(Vec<Artifact>, Vec<Diagnostic>)> {
+    let mut diagnostics = Vec::new();
+    let exports = select_exports(units, entry_points, &mut diagnostics);
+    let package = wit_name(name);
+    let wit = wit(&package, &exports);
+    let core = core_module(&package, units, &exports);
+    let component = encode_component(&wit, core)?;
+
+    let artifacts = vec![
+        Artifact {
+            path: format!("{}.wit", name),
+            content: wit,
+            binary: false,
+        },
+        Artifact {
+            path: format!("{}.component.wasm", name),
+            content: STANDARD.encode(component),
+            binary: true,
+        },
+    ];
+    Ok((artifacts, diagnostics))
+}
+
+fn select_exports<'a>(
+    units: &'a [CompilationUnit],
+    entry_points: &[(String, FQName)],
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Export<'a>> {
+    let mut functions = Vec::new();
+    for unit in units {
+        for function in &unit.functions {
+            functions.push((unit, function, functions.len() as u32));
+        }
+    }
+
+    // Name, target for messages, and function of each candidate
+    let candidates: Vec<_> = if entry_points.is_empty() {
+        functions
+            .iter()
+            .map(|(unit, function, index)| {
+                let target = format!("{}.{}", unit.module, function.name);
+                let name = format!("{}-{}", unit.module.replace('/', "-"), function.name);
+                (wit_name(&name), target, Some((*unit, *function, *index)))
+            })
+            .collect()
+    } else {
+        entry_points
+            .iter()
+            .map(|(name, target)| {
+                let found = functions.iter().find(|(unit, function, _)| {
+                    Path::new(&unit.module) == target.module_path
+                        && Name::from(&function.name) == target.local_name
+                });
+                (
+                    wit_name(name),
+                    format!("Entry point {}", name),
+                    found.copied(),
+                )
+            })
+            .collect()
+    };
+
+    let mut exports = Vec::new();
+    for (name, target, found) in candidates {
+        match export(name, found, &exports) {
+            Ok(export) => exports.push(export),
+            Err(reason) => diagnostics.push(Diagnostic {
+                severity: DiagnosticSeverity::Warning,
+                code: Some("W003".into()),
+                message: format!("{} is not exported from the component: {}", target, reason),
+                location: None,
+                related: vec![],
+                fixes: vec![],
+            }),
+        }
+    }
+    exports
+}
+
+/// Export of a compiled function next to `exports`, or why it cannot be one
+fn export<'a>(
+    name: String,
+    found: Option<(&'a CompilationUnit, &'a LoweredFunction, u32)>,
+    exports: &[Export],
+) -> std::result::Result<Export<'a>, String> {
+    let (unit, function, index) = found.ok_or("it was not compiled")?;
+    let signature = function
+        .signature
+        .as_ref()
+        .ok_or("its Morphir signature is unknown")?;
+    if let Some(reason) = unexportable(signature) {
+        return Err(reason);
+    }
+    if exports.iter().any(|e| e.name == name) {
+        return Err(format!("another function is named {}", name));
+    }
+    // Enums of different modules cannot share a WIT name
+    for enum_name in enums(signature) {
+        let id = enum_name.to_kebab_case();
+        if exports
+            .iter()
+            .filter(|e| e.unit.module != unit.module)
+            .flat_map(|e| enums(e.signature))
+            .any(|other| other.to_kebab_case() == id)
+        {
+            return Err(format!(
+                "another module has a type named {}",
+                enum_name.to_title_case()
+            ));
+        }
+    }
+    Ok(Export {
+        name,
+        index,
+        signature,
+        unit,
+    })
+}
+
+/// Why a function with `signature` cannot be exported, if it cannot
+fn unexportable(signature: &Signature) -> Option<String> {
+    let reprs = signature
+        .inputs
+        .iter()
+        .map(|(_, repr)| repr)
+        .chain([&signature.output]);
+    if let Some(repr) = reprs.clone().find(|repr| wit_type(repr).is_none()) {
+        return Some(format!("{} has no WIT type", repr));
+    }
+    let flat: usize = signature
+        .inputs
+        .iter()
+        .map(|(_, repr)| flat(repr).len())
+        .sum();
+    (flat > MAX_FLAT_PARAMS).then(|| {
+        format!(
+            "its inputs take {} flat parameters, more than {}",
+            flat, MAX_FLAT_PARAMS
+        )
+    })
+}
+
+/// Enums in a signature
+fn enums(signature: &Signature) -> impl Iterator<Item = &Name> {
+    signature
+        .inputs
+        .iter()
+        .map(|(_, repr)| repr)
+        .chain([&signature.output])
+        .filter_map(|repr| match repr {
+            Repr::Enum(name) => Some(name),
+            _ => None,
+        })
+}
+
+/// Kebab-case WIT identifier for `text`
+fn wit_name(text: &str) -> String {
+    Name::from(text).to_kebab_case()
+}
+
+/// Identifier as written in WIT, escaped if it is a keyword
+fn escape(id: &str) -> String {
+    if KEYWORDS.contains(&id) {
+        format!("%{}", id)
+    } else {
+        id.to_string()
+    }
+}
+
+fn wit_type(repr: &Repr) -> Option<String> {
+    Some(match repr {
+        Repr::Int => "s64".to_string(),
+        Repr::Float => "f64".to_string(),
+        Repr::Bool => "bool".to_string(),
+        Repr::Char => "char".to_string(),
+        Repr::String => "string".to_string(),
+        Repr::Enum(name) => escape(&name.to_kebab_case()),
+        Repr::Unit => return None,
+    })
+}
+
+/// Core WebAssembly types a value is passed as under the canonical ABI
+fn flat(repr: &Repr) -> Vec<ValType> {
+    match repr {
+        Repr::String => vec![ValType::I32, ValType::I32],
+        _ => vec![repr.val_type()],
+    }
+}
+
+/// WIT package declaring the interface and a world exporting it
+fn wit(package: &str, exports: &[Export]) -> String {
+    let mut output = String::new();
+
+    // String's Write impl never fails
+    let _ = writeln!(output, "package morphir:{};", escape(package));
+    let _ = writeln!(output);
+    let _ = writeln!(output, "interface {} {{", INTERFACE);
+
+    let mut declared = Vec::new();
+    for export in exports {
+        for name in enums(export.signature) {
+            let id = name.to_kebab_case();
+            if declared.contains(&id) {
+                continue;
+            }
+            let constructors = export
+                .unit
+                .enums
+                .iter()
+                .find(|(enum_name, _)| enum_name == name)
+                .map(|(_, constructors)| constructors.as_slice())
+                .unwrap_or_default();
+            let _ = writeln!(output, "    enum {} {{", escape(&id));
+            for constructor in constructors {
+                let _ = writeln!(output, "        {},", escape(&constructor.to_kebab_case()));
+            }
+            let _ = writeln!(output, "    }}");
+            let _ = writeln!(output);
+            declared.push(id);
+        }
+    }
+
+    for export in exports {
+        let params: Vec<String> = export
+            .signature
+            .inputs
+            .iter()
+            .map(|(name, repr)| {
+                format!(
+                    "{}: {}",
+                    escape(&name.to_kebab_case()),
+                    wit_type(repr).unwrap_or_default()
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            output,
+            "    {}: func({}) -> {};",
+            escape(&export.name),
+            params.join(", "),
+            wit_type(&export.signature.output).unwrap_or_default()
+        );
+    }
+    let _ = writeln!(output, "}}");
+    let _ = writeln!(output);
+    let _ = writeln!(output, "world {} {{", escape(package));
+    let _ = writeln!(output, "    export {};", INTERFACE);
+    let _ = writeln!(output, "}}");
+    output
+}
+
+/// Functions of a core module, each with its own type
+#[derive(Default)]
+struct Functions {
+    types: TypeSection,
+    functions: FunctionSection,
+    codes: CodeSection,
+    count: u32,
+}
+
+impl Functions {
+    fn add(&mut self, params: Vec<ValType>, results: Vec<ValType>, code: &Function) -> u32 {
+        self.types.ty().function(params, results);
+        self.functions.function(self.count);
+        self.codes.function(code);
+        self.count += 1;
+        self.count - 1
+    }
+}
+
+/// Core module of the component: the linked functions, and for each export
+/// a function adapting it to the canonical ABI with its post-return
+fn core_module(package: &str, units: &[CompilationUnit], exports: &[Export]) -> Vec<u8> {
+    let (bases, mut data) = layout(units);
+    data.resize(data.len().next_multiple_of(8), 0);
+    // Where functions returning a string leave its pointer and length
+    let return_area = data.len() as u32;
+    data.extend_from_slice(&[0; 8]);
+    // An empty string, for empty arguments the host allocated nothing for
+    let empty_string = data.len() as u32;
+    data.extend_from_slice(&[0; 4]);
+    data.resize(data.len().next_multiple_of(8), 0);
+    let heap = data.len() as u32;
+
+    let mut functions = Functions::default();
+    for (unit, bases) in units.iter().zip(bases) {
+        for function in &unit.functions {
+            functions.add(
+                function.params.clone(),
+                vec![function.result],
+                &encode_function(function, bases),
+            );
+        }
+    }
+
+    let mut export_section = ExportSection::new();
+    export_section.export("memory", ExportKind::Memory, 0);
+    let realloc = functions.add(vec![ValType::I32; 4], vec![ValType::I32], &realloc());
+    export_section.export("cabi_realloc", ExportKind::Func, realloc);
+    for export in exports {
+        let params: Vec<ValType> = export
+            .signature
+            .inputs
+            .iter()
+            .flat_map(|(_, repr)| flat(repr))
+            .collect();
+        let results = vec![export.signature.output.val_type()];
+        let adapter = adapter(export, params.len() as u32, return_area, empty_string);
+        let index = functions.add(params, results.clone(), &adapter);
+        let name = format!("morphir:{}/{}#{}", package, INTERFACE, export.name);
+        export_section.export(&name, ExportKind::Func, index);
+
+        let mut post_return = Function::new([]);
+        post_return.instruction(&Instruction::I32Const(heap as i32));
+        post_return.instruction(&Instruction::GlobalSet(0));
+        post_return.instruction(&Instruction::End);
+        let index = functions.add(results, vec![], &post_return);
+        export_section.export(&format!("cabi_post_{}", name), ExportKind::Func, index);
+    }
+
+    let mut memories = MemorySection::new();
+    memories.memory(MemoryType {
+        minimum: memory_pages(&data),
+        maximum: None,
+        memory64: false,
+        shared: false,
+        page_size_log2: None,
+    });
+    // Start of the heap `cabi_realloc` allocates from
+    let mut globals = GlobalSection::new();
+    globals.global(
+        GlobalType {
+            val_type: ValType::I32,
+            mutable: true,
+            shared: false,
+        },
+        &ConstExpr::i32_const(heap as i32),
+    );
+    let mut segments = DataSection::new();
+    segments.active(0, &ConstExpr::i32_const(0), data.iter().copied());
+
+    let mut module = Module::new();
+    module.section(&functions.types);
+    module.section(&functions.functions);
+    module.section(&memories);
+    module.section(&globals);
+    module.section(&export_section);
+    module.section(&functions.codes);
+    module.section(&segments);
+    module.finish()
+}
+
+fn mem(offset: u64) -> MemArg {
+    MemArg {
+        offset,
+        align: 0,
+        memory_index: 0,
+    }
+}
+
+/// `cabi_realloc(old_ptr, old_size, align, new_size)`: allocate from the
+/// heap, leaving four bytes in front for the length of a string
+fn realloc() -> Function {
+    let ptr = 4;
+    let mut f = Function::new([(1, ValType::I32)]);
+    for instruction in [
+        // ptr = align_up(heap + 4, align)
+        Instruction::GlobalGet(0),
+        Instruction::I32Const(4),
+        Instruction::I32Add,
+        Instruction::LocalGet(2),
+        Instruction::I32Add,
+        Instruction::I32Const(1),
+        Instruction::I32Sub,
+        Instruction::I32Const(0),
+        Instruction::LocalGet(2),
+        Instruction::I32Sub,
+        Instruction::I32And,
+        Instruction::LocalSet(ptr),
+        // heap = ptr + new_size
+        Instruction::LocalGet(ptr),
+        Instruction::LocalGet(3),
+        Instruction::I32Add,
+        Instruction::GlobalSet(0),
+        // Grow the memory to hold the heap
+        Instruction::GlobalGet(0),
+        Instruction::MemorySize(0),
+        Instruction::I32Const(16),
+        Instruction::I32Shl,
+        Instruction::I32GtU,
+        Instruction::If(BlockType::Empty),
+        Instruction::GlobalGet(0),
+        Instruction::MemorySize(0),
+        Instruction::I32Const(16),
+        Instruction::I32Shl,
+        Instruction::I32Sub,
+        Instruction::I32Const(0xffff),
+        Instruction::I32Add,
+        Instruction::I32Const(16),
+        Instruction::I32ShrU,
+        Instruction::MemoryGrow(0),
+        Instruction::I32Const(-1),
+        Instruction::I32Eq,
+        Instruction::If(BlockType::Empty),
+        Instruction::Unreachable,
+        Instruction::End,
+        Instruction::End,
+        // Keep what was allocated before, up to the new size
+        Instruction::LocalGet(ptr),
+        Instruction::LocalGet(0),
+        Instruction::LocalGet(1),
+        Instruction::LocalGet(3),
+        Instruction::LocalGet(1),
+        Instruction::LocalGet(3),
+        Instruction::I32LtU,
+        Instruction::Select,
+        Instruction::MemoryCopy {
+            src_mem: 0,
+            dst_mem: 0,
+        },
+        Instruction::LocalGet(ptr),
+        Instruction::End,
+    ] {
+        f.instruction(&instruction);
+    }
+    f
+}
+
+/// Function taking the flat parameters of an export and calling the
+/// compiled function with them
+fn adapter(export: &Export, params: u32, return_area: u32, empty_string: u32) -> Function {
+    let string_result = export.signature.output == Repr::String;
+    let mut f = Function::new(if string_result {
+        vec![(1, ValType::I32)]
+    } else {
+        vec![]
+    });
+
+    let mut param = 0;
+    for (_, repr) in &export.signature.inputs {
+        if *repr == Repr::String {
+            // Write the length in front of the bytes `cabi_realloc` made room for
+            let (ptr, len) = (param, param + 1);
+            for instruction in [
+                Instruction::LocalGet(len),
+                Instruction::I32Eqz,
+                Instruction::If(BlockType::Result(ValType::I32)),
+                Instruction::I32Const(empty_string as i32),
+                Instruction::Else,
+                Instruction::LocalGet(ptr),
+                Instruction::I32Const(4),
+                Instruction::I32Sub,
+                Instruction::LocalGet(len),
+                Instruction::I32Store(mem(0)),
+                Instruction::LocalGet(ptr),
+                Instruction::I32Const(4),
+                Instruction::I32Sub,
+                Instruction::End,
+            ] {
+                f.instruction(&instruction);
+            }
+            param += 2;
+        } else {
+            f.instruction(&Instruction::LocalGet(param));
+            param += 1;
+        }
+    }
+    f.instruction(&Instruction::Call(export.index));
+
+    if string_result {
+        // Return the address of the string's bytes and length
+        let result = params;
+        for instruction in [
+            Instruction::LocalSet(result),
+            Instruction::I32Const(return_area as i32),
+            Instruction::LocalGet(result),
+            Instruction::I32Const(4),
+            Instruction::I32Add,
+            Instruction::I32Store(mem(0)),
+            Instruction::I32Const(return_area as i32),
+            Instruction::LocalGet(result),
+            Instruction::I32Load(mem(0)),
+            Instruction::I32Store(mem(4)),
+            Instruction::I32Const(return_area as i32),
+        ] {
+            f.instruction(&instruction);
+        }
+    }
+    f.instruction(&Instruction::End);
+    f
+}
+
+/// Component made of `core`, typed by the world in `wit`
+fn encode_component(wit: &str, mut core: Vec<u8>) -> Result<Vec<u8>> {
+    fn failed(e: impl std::fmt::Display) -> ExtensionError {
+        ExtensionError::execution(format!("Failed to build component: {:#}", e))
+    }
+    let mut resolve = Resolve::default();
+    let package = resolve.push_str("morphir.wit", wit).map_err(failed)?;
+    let world = resolve.select_world(&[package], None).map_err(failed)?;
+    wit_component::embed_component_metadata(&mut core, &resolve, world, StringEncoding::UTF8)
+        .map_err(failed)?;
+    ComponentEncoder::default()
+        .module(&core)
+        .map_err(failed)?
+        .validate(true)
+        .encode()
+        .map_err(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::generate_wasm_with_diagnostics;
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use morphir_core::ir::v4::text::parse_distribution;
+    use std::collections::HashMap;
+    use wasmtime::component::{Component, Linker, Val};
+
+    const SHOP: &str = "application acme/shop
+
+entry total handler acme/shop:orders#total
+entry label handler acme/shop:orders#label
+entry echo command acme/shop:orders#echo
+entry lines handler acme/shop:orders#lines
+
+module orders
+
+type Side
+    = Buy
+    | Sell
+
+total (price : morphir/sdk:basics#Int) (quantity : morphir/sdk:basics#Int) (side : acme/shop:orders#Side) : morphir/sdk:basics#Int =
+    case side of
+        acme/shop:orders#Buy ->
+            morphir/sdk:basics#multiply price quantity
+        acme/shop:orders#Sell ->
+            morphir/sdk:basics#negate (morphir/sdk:basics#multiply price quantity)
+
+label (side : acme/shop:orders#Side) : morphir/sdk:string#String =
+    case side of
+        acme/shop:orders#Buy ->
+            \"buy\"
+        _ ->
+            \"sell\"
+
+echo (text : morphir/sdk:string#String) : morphir/sdk:string#String =
+    text
+
+lines (x : morphir/sdk:basics#Int) : morphir/sdk:list#List morphir/sdk:basics#Int =
+    [x]
+";
+
+    fn generate(source: &str) -> (HashMap<String, String>, Vec<String>) {
+        let dist = parse_distribution(source).unwrap();
+        let ir = serde_json::to_value(&dist).unwrap();
+        let options = HashMap::from([
+            ("component".to_string(), serde_json::json!(true)),
+            ("split".to_string(), serde_json::json!(false)),
+        ]);
+        let (artifacts, diagnostics) = generate_wasm_with_diagnostics(&ir, &options).unwrap();
+        let artifacts = artifacts.into_iter().map(|a| (a.path, a.content)).collect();
+        let diagnostics = diagnostics
+            .into_iter()
+            .filter(|d| d.code.as_deref() == Some("W003"))
+            .map(|d| d.message)
+            .collect();
+        (artifacts, diagnostics)
+    }
+
+    #[test]
+    fn test_entry_points_are_exported_from_a_component() {
+        let (artifacts, diagnostics) = generate(SHOP);
+        assert_eq!(
+            artifacts["acme-shop.wit"],
+            "package morphir:acme-shop;
+
+interface entry-points {
+    enum side {
+        buy,
+        sell,
+    }
+
+    echo: func(text: string) -> string;
+    label: func(side: side) -> string;
+    total: func(price: s64, quantity: s64, side: side) -> s64;
+}
+
+world acme-shop {
+    export entry-points;
+}
+"
+        );
+        assert_eq!(
+            diagnostics,
+            vec!["Entry point lines is not exported from the component: it was not compiled"]
+        );
+
+        let engine = wasmtime::Engine::default();
+        let bytes = STANDARD
+            .decode(&artifacts["acme-shop.component.wasm"])
+            .unwrap();
+        let component = Component::new(&engine, bytes).unwrap();
+        let mut store = wasmtime::Store::new(&engine, ());
+        let instance = Linker::new(&engine)
+            .instantiate(&mut store, &component)
+            .unwrap();
+        let interface = instance
+            .get_export_index(&mut store, None, "morphir:acme-shop/entry-points")
+            .unwrap();
+        let mut call = |name: &str, params: &[Val]| {
+            let index = instance
+                .get_export_index(&mut store, Some(&interface), name)
+                .unwrap();
+            let func = instance.get_func(&mut store, index).unwrap();
+            let mut results = [Val::Bool(false)];
+            func.call(&mut store, params, &mut results).unwrap();
+            func.post_return(&mut store).unwrap();
+            results[0].clone()
+        };
+
+        let sell = || Val::Enum("sell".to_string());
+        assert_eq!(
+            call("total", &[Val::S64(5), Val::S64(3), sell()]),
+            Val::S64(-15)
+        );
+        assert_eq!(call("label", &[sell()]), Val::String("sell".to_string()));
+        for text in ["héllo", "", &"x".repeat(200_000), "again"] {
+            assert_eq!(
+                call("echo", &[Val::String(text.to_string())]),
+                Val::String(text.to_string())
+            );
+        }
+
+        // Without entry points, every compiled value is exported
+        let library = SHOP
+            .replace("application", "library")
+            .lines()
+            .filter(|line| !line.starts_with("entry"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (artifacts, diagnostics) = generate(&library);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert!(
+            artifacts["acme-shop.wit"]
+                .contains("    orders-total: func(price: s64, quantity: s64, side: side) -> s64;")
+        );
+    }
+}
@@ -0,0 +1,879 @@
+//! Lowering of Morphir v4 values to WebAssembly functions
+//!
+//! Each value definition of a module becomes a function taking its inputs as
+//! parameters. Values are represented as:
+//!
+//! | Morphir type | WebAssembly |
+//! |--------------|-------------|
+//! | `Int` | `i64` |
+//! | `Float` | `f64` |
+//! | `Bool` | `i32`, 0 or 1 |
+//! | `Char` | `i32` code point |
+//! | `String` | `i32` address of a little-endian `u32` byte length followed by the UTF-8 bytes |
+//! | `()` | `i32` 0 |
+//! | custom type of the module whose constructors take no arguments | `i32` index of the constructor |
+//!
+//! String literals are laid out in the unit's data, each aligned to 4 bytes.
+//!
+//! The lowered functions are a small expression tree that both the binary
+//! and the text emitters walk, so `.wasm` and `.wat` output hold the same
+//! instructions. Values using anything else are skipped with the reason.
+
+use morphir_core::ir::v4::value::{PatternCase, Value, ValueBody, ValueDefinition};
+use morphir_core::ir::v4::{Literal, ModuleDefinition, Pattern, Type, TypeDefinition};
+use morphir_core::naming::{FQName, Name, Path};
+use std::collections::HashMap;
+use std::fmt;
+use wasm_encoder::{Instruction, ValType};
+
+/// How a Morphir value is represented in WebAssembly
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repr {
+    Int,
+    Float,
+    Bool,
+    Char,
+    String,
+    Unit,
+    /// Custom type of the module, by name, whose constructors take no arguments
+    Enum(Name),
+}
+
+impl Repr {
+    /// WebAssembly type of the representation
+    pub fn val_type(&self) -> ValType {
+        match self {
+            Repr::Int => ValType::I64,
+            Repr::Float => ValType::F64,
+            _ => ValType::I32,
+        }
+    }
+}
+
+impl fmt::Display for Repr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repr::Int => f.write_str("Int"),
+            Repr::Float => f.write_str("Float"),
+            Repr::Bool => f.write_str("Bool"),
+            Repr::Char => f.write_str("Char"),
+            Repr::String => f.write_str("String"),
+            Repr::Unit => f.write_str("()"),
+            Repr::Enum(name) => f.write_str(&name.to_title_case()),
+        }
+    }
+}
+
+/// Numeric instruction taking its operands from the stack
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    I32Eqz,
+    I32Eq,
+    I32Ne,
+    I32LtU,
+    I32GtU,
+    I32LeU,
+    I32GeU,
+    I64Add,
+    I64Sub,
+    I64Mul,
+    I64DivS,
+    I64Eq,
+    I64Ne,
+    I64LtS,
+    I64GtS,
+    I64LeS,
+    I64GeS,
+    F64Add,
+    F64Sub,
+    F64Mul,
+    F64Div,
+    F64Neg,
+    F64Eq,
+    F64Ne,
+    F64Lt,
+    F64Gt,
+    F64Le,
+    F64Ge,
+}
+
+impl Op {
+    /// Binary encoding of the instruction
+    pub fn instruction(self) -> Instruction<'static> {
+        match self {
+            Op::I32Eqz => Instruction::I32Eqz,
+            Op::I32Eq => Instruction::I32Eq,
+            Op::I32Ne => Instruction::I32Ne,
+            Op::I32LtU => Instruction::I32LtU,
+            Op::I32GtU => Instruction::I32GtU,
+            Op::I32LeU => Instruction::I32LeU,
+            Op::I32GeU => Instruction::I32GeU,
+            Op::I64Add => Instruction::I64Add,
+            Op::I64Sub => Instruction::I64Sub,
+            Op::I64Mul => Instruction::I64Mul,
+            Op::I64DivS => Instruction::I64DivS,
+            Op::I64Eq => Instruction::I64Eq,
+            Op::I64Ne => Instruction::I64Ne,
+            Op::I64LtS => Instruction::I64LtS,
+            Op::I64GtS => Instruction::I64GtS,
+            Op::I64LeS => Instruction::I64LeS,
+            Op::I64GeS => Instruction::I64GeS,
+            Op::F64Add => Instruction::F64Add,
+            Op::F64Sub => Instruction::F64Sub,
+            Op::F64Mul => Instruction::F64Mul,
+            Op::F64Div => Instruction::F64Div,
+            Op::F64Neg => Instruction::F64Neg,
+            Op::F64Eq => Instruction::F64Eq,
+            Op::F64Ne => Instruction::F64Ne,
+            Op::F64Lt => Instruction::F64Lt,
+            Op::F64Gt => Instruction::F64Gt,
+            Op::F64Le => Instruction::F64Le,
+            Op::F64Ge => Instruction::F64Ge,
+        }
+    }
+
+    /// Text format name of the instruction
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Op::I32Eqz => "i32.eqz",
+            Op::I32Eq => "i32.eq",
+            Op::I32Ne => "i32.ne",
+            Op::I32LtU => "i32.lt_u",
+            Op::I32GtU => "i32.gt_u",
+            Op::I32LeU => "i32.le_u",
+            Op::I32GeU => "i32.ge_u",
+            Op::I64Add => "i64.add",
+            Op::I64Sub => "i64.sub",
+            Op::I64Mul => "i64.mul",
+            Op::I64DivS => "i64.div_s",
+            Op::I64Eq => "i64.eq",
+            Op::I64Ne => "i64.ne",
+            Op::I64LtS => "i64.lt_s",
+            Op::I64GtS => "i64.gt_s",
+            Op::I64LeS => "i64.le_s",
+            Op::I64GeS => "i64.ge_s",
+            Op::F64Add => "f64.add",
+            Op::F64Sub => "f64.sub",
+            Op::F64Mul => "f64.mul",
+            Op::F64Div => "f64.div",
+            Op::F64Neg => "f64.neg",
+            Op::F64Eq => "f64.eq",
+            Op::F64Ne => "f64.ne",
+            Op::F64Lt => "f64.lt",
+            Op::F64Gt => "f64.gt",
+            Op::F64Le => "f64.le",
+            Op::F64Ge => "f64.ge",
+        }
+    }
+}
+
+/// Lowered expression, leaving one value on the stack
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    I32(i32),
+    I64(i64),
+    F64(f64),
+    /// Address of a string, as an offset into the unit's data
+    Data(u32),
+    /// Value of a parameter or local
+    Get(u32),
+    /// Set a local to a value, then evaluate the body
+    Let(u32, Box<Expr>, Box<Expr>),
+    /// Instruction applied to its operands
+    Op(Op, Vec<Expr>),
+    /// Call of a function of the unit, by index in the unit
+    Call(u32, Vec<Expr>),
+    /// Condition, then and else branches, all producing a value of the type
+    If(ValType, Box<Expr>, Box<Expr>, Box<Expr>),
+    /// Trap, where no case of a pattern match applies
+    Unreachable,
+}
+
+/// Morphir inputs and output of a lowered value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub inputs: Vec<(Name, Repr)>,
+    pub output: Repr,
+}
+
+/// A value definition lowered to a function
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoweredFunction {
+    /// Name of the value in its module
+    pub name: String,
+    /// Morphir signature, unknown for values of the simplified format
+    pub signature: Option<Signature>,
+    pub params: Vec<ValType>,
+    pub result: ValType,
+    /// Locals after the parameters
+    pub locals: Vec<ValType>,
+    pub body: Expr,
+}
+
+/// A module lowered to functions
+#[derive(Debug, Clone, Default)]
+pub struct LoweredModule {
+    pub functions: Vec<LoweredFunction>,
+    /// Strings the functions address, from offset 0
+    pub data: Vec<u8>,
+    /// Whether the functions use memory, to hold or pass strings
+    pub memory: bool,
+    /// Values that could not be lowered, with the reason
+    pub skipped: Vec<(String, String)>,
+    /// Custom types whose constructors take no arguments, with their
+    /// constructors in tag order
+    pub enums: Vec<(Name, Vec<Name>)>,
+}
+
+/// Value definition with its parameters and result
+struct Definition<'a> {
+    name: &'a str,
+    params: Vec<(Name, Repr)>,
+    result: Repr,
+    body: &'a Value,
+}
+
+/// Lower the values of `module` in `package`, if known
+pub fn lower_module(package: Option<&Path>, module: &str, def: &ModuleDefinition) -> LoweredModule {
+    let scope = Scope::new(package, module, def);
+    let mut skipped = Vec::new();
+    let mut signatures = Vec::new();
+    for (name, value) in &def.values {
+        match scope.signature(name, &value.value) {
+            Ok(signature) => signatures.push(signature),
+            Err(reason) => skipped.push((name.clone(), reason)),
+        }
+    }
+
+    // Dropping a value changes the indices of the others and fails its
+    // callers, so lower again until every remaining value lowers
+    loop {
+        let mut data = Data::default();
+        let mut functions = Vec::new();
+        let mut failed = Vec::new();
+        for (index, signature) in signatures.iter().enumerate() {
+            match FunctionLowering::new(&scope, &signatures, &mut data).lower(signature) {
+                Ok(function) => functions.push(function),
+                Err(reason) => failed.push((index, reason)),
+            }
+        }
+        if failed.is_empty() {
+            let memory = !data.bytes.is_empty()
+                || signatures.iter().any(|signature| {
+                    signature.result == Repr::String
+                        || signature
+                            .params
+                            .iter()
+                            .any(|(_, repr)| *repr == Repr::String)
+                });
+            return LoweredModule {
+                functions,
+                data: data.bytes,
+                memory,
+                skipped,
+                enums: scope.enums,
+            };
+        }
+        for (index, reason) in failed.into_iter().rev() {
+            let signature = signatures.remove(index);
+            skipped.push((signature.name.to_string(), reason));
+        }
+    }
+}
+
+/// What the values of a module can refer to
+struct Scope<'a> {
+    package: Option<&'a Path>,
+    module: Path,
+    def: &'a ModuleDefinition,
+    /// Custom types whose constructors take no arguments, with their constructors
+    enums: Vec<(Name, Vec<Name>)>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(package: Option<&'a Path>, module: &str, def: &'a ModuleDefinition) -> Self {
+        let enums = def
+            .types
+            .iter()
+            .filter_map(|(name, type_def)| match &type_def.value {
+                TypeDefinition::CustomTypeDefinition {
+                    type_params,
+                    constructors,
+                } if type_params.is_empty()
+                    && constructors.value.iter().all(|c| c.args.is_empty()) =>
+                {
+                    let names = constructors.value.iter().map(|c| c.name.clone()).collect();
+                    Some((Name::from(name), names))
+                }
+                _ => None,
+            })
+            .collect();
+        Self {
+            package,
+            module: Path::new(module),
+            def,
+            enums,
+        }
+    }
+
+    /// Whether `fqname` names a definition of this module
+    fn is_local(&self, fqname: &FQName) -> bool {
+        fqname.module_path == self.module
+            && self
+                .package
+                .is_none_or(|package| *package == fqname.package_path)
+    }
+
+    fn repr(&self, tpe: &Type) -> Result<Repr, String> {
+        if let Type::Unit(_) = tpe {
+            return Ok(Repr::Unit);
+        }
+        if let Type::Reference(_, fqname, args) = tpe
+            && args.is_empty()
+        {
+            let repr = match sdk(fqname).as_ref().map(|(m, n)| (m.as_str(), n.as_str())) {
+                Some(("basics", "int")) => Some(Repr::Int),
+                Some(("basics", "float")) => Some(Repr::Float),
+                Some(("basics", "bool")) => Some(Repr::Bool),
+                Some(("char", "char")) => Some(Repr::Char),
+                Some(("string", "string")) => Some(Repr::String),
+                _ if self.is_local(fqname) => self
+                    .enums
+                    .iter()
+                    .find(|(name, _)| *name == fqname.local_name)
+                    .map(|(name, _)| Repr::Enum(name.clone())),
+                _ => None,
+            };
+            if let Some(repr) = repr {
+                return Ok(repr);
+            }
+        }
+        Err(format!(
+            "{} has no WebAssembly representation",
+            describe(tpe)
+        ))
+    }
+
+    /// Representation and tag of an enum constructor of the module
+    fn tag(&self, fqname: &FQName) -> Result<(Repr, i32), String> {
+        if self.is_local(fqname) {
+            for (name, constructors) in &self.enums {
+                if let Some(tag) = constructors.iter().position(|c| *c == fqname.local_name) {
+                    return Ok((Repr::Enum(name.clone()), tag as i32));
+                }
+            }
+        }
+        Err(format!(
+            "{} is not a constructor without arguments of this module",
+            fqname.to_canonical_string()
+        ))
+    }
+
+    fn signature<'v>(
+        &self,
+        name: &'v str,
+        def: &'v ValueDefinition,
+    ) -> Result<Definition<'v>, String> {
+        let body = match &def.body {
+            ValueBody::Expression(body) => body,
+            ValueBody::Native(_) => return Err("native values have no body to compile".into()),
+            ValueBody::External { .. } => {
+                return Err("external values have no body to compile".into());
+            }
+            ValueBody::Incomplete(_) => return Err("the value is incomplete".into()),
+        };
+        let params = def
+            .input_types
+            .iter()
+            .map(|(param, entry)| {
+                let repr = self
+                    .repr(&entry.input_type)
+                    .map_err(|e| format!("parameter {}: {}", param, e))?;
+                Ok((Name::from(param), repr))
+            })
+            .collect::<Result<_, String>>()?;
+        let result = self
+            .repr(&def.output_type)
+            .map_err(|e| format!("result: {}", e))?;
+        Ok(Definition {
+            name,
+            params,
+            result,
+            body,
+        })
+    }
+}
+
+/// Module and local name of a `morphir/sdk` definition
+fn sdk(fqname: &FQName) -> Option<(String, String)> {
+    fqname.package_path.is_sdk().then(|| {
+        (
+            fqname.module_path.to_string(),
+            fqname.local_name.to_string(),
+        )
+    })
+}
+
+fn describe(tpe: &Type) -> String {
+    match tpe {
+        Type::Variable(_, name) => format!("Type variable {}", name),
+        Type::Reference(_, fqname, args) if args.is_empty() => fqname.to_canonical_string(),
+        Type::Reference(_, fqname, _) => format!("{} with arguments", fqname.to_canonical_string()),
+        Type::Tuple(..) => "Tuple type".to_string(),
+        Type::Record(..) | Type::ExtensibleRecord(..) => "Record type".to_string(),
+        Type::Function(..) => "Function type".to_string(),
+        Type::Unit(_) => "()".to_string(),
+    }
+}
+
+/// String literals laid out for a unit
+#[derive(Default)]
+struct Data {
+    bytes: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl Data {
+    /// Offset of `text`, laying it out if it is new
+    fn string(&mut self, text: &str) -> u32 {
+        if let Some(offset) = self.offsets.get(text) {
+            return *offset;
+        }
+        self.bytes.resize(self.bytes.len().next_multiple_of(4), 0);
+        let offset = self.bytes.len() as u32;
+        self.bytes
+            .extend_from_slice(&(text.len() as u32).to_le_bytes());
+        self.bytes.extend_from_slice(text.as_bytes());
+        self.offsets.insert(text.to_string(), offset);
+        offset
+    }
+}
+
+/// Lowering of one value definition
+struct FunctionLowering<'s> {
+    scope: &'s Scope<'s>,
+    functions: &'s [Definition<'s>],
+    data: &'s mut Data,
+    params: u32,
+    locals: Vec<ValType>,
+    /// Variables in scope, innermost last
+    variables: Vec<(Name, u32, Repr)>,
+}
+
+impl<'s> FunctionLowering<'s> {
+    fn new(scope: &'s Scope<'s>, functions: &'s [Definition<'s>], data: &'s mut Data) -> Self {
+        Self {
+            scope,
+            functions,
+            data,
+            params: 0,
+            locals: Vec::new(),
+            variables: Vec::new(),
+        }
+    }
+
+    fn lower(mut self, signature: &Definition) -> Result<LoweredFunction, String> {
+        self.params = signature.params.len() as u32;
+        for (index, (name, repr)) in signature.params.iter().enumerate() {
+            self.variables
+                .push((name.clone(), index as u32, repr.clone()));
+        }
+        let (body, repr) = self.value(signature.body)?;
+        if repr != signature.result {
+            return Err(format!(
+                "the body is {} but the value is declared {}",
+                repr, signature.result
+            ));
+        }
+        Ok(LoweredFunction {
+            name: signature.name.to_string(),
+            signature: Some(Signature {
+                inputs: signature.params.clone(),
+                output: signature.result.clone(),
+            }),
+            params: signature.params.iter().map(|(_, r)| r.val_type()).collect(),
+            result: signature.result.val_type(),
+            locals: self.locals,
+            body,
+        })
+    }
+
+    fn local(&mut self, ty: ValType) -> u32 {
+        self.locals.push(ty);
+        self.params + self.locals.len() as u32 - 1
+    }
+
+    fn value(&mut self, value: &Value) -> Result<(Expr, Repr), String> {
+        match value {
+            Value::Literal(_, literal) => self.literal(literal),
+            Value::Unit(_) => Ok((Expr::I32(0), Repr::Unit)),
+            Value::Variable(_, name) => self
+                .variables
+                .iter()
+                .rev()
+                .find(|(n, _, _)| n == name)
+                .map(|(_, index, repr)| (Expr::Get(*index), repr.clone()))
+                .ok_or_else(|| format!("unknown variable {}", name)),
+            Value::Apply(..) | Value::Reference(..) | Value::Constructor(..) => self.apply(value),
+            Value::IfThenElse(_, condition, then_branch, else_branch) => {
+                let (condition, repr) = self.value(condition)?;
+                if repr != Repr::Bool {
+                    return Err(format!("the condition of an if is {}, not Bool", repr));
+                }
+                let (then_branch, repr) = self.value(then_branch)?;
+                let (else_branch, else_repr) = self.value(else_branch)?;
+                if repr != else_repr {
+                    return Err(format!(
+                        "the branches of an if are {} and {}",
+                        repr, else_repr
+                    ));
+                }
+                Ok((
+                    Expr::If(
+                        repr.val_type(),
+                        Box::new(condition),
+                        Box::new(then_branch),
+                        Box::new(else_branch),
+                    ),
+                    repr,
+                ))
+            }
+            Value::LetDefinition(_, name, def, body) => {
+                let bound = match &def.body {
+                    ValueBody::Expression(bound) if def.input_types.is_empty() => bound,
+                    _ => return Err(format!("local function {} is not supported", name)),
+                };
+                let (bound, repr) = self.value(bound)?;
+                let local = self.local(repr.val_type());
+                self.variables.push((name.clone(), local, repr));
+                let body = self.value(body);
+                self.variables.pop();
+                let (body, repr) = body?;
+                Ok((Expr::Let(local, Box::new(bound), Box::new(body)), repr))
+            }
+            Value::PatternMatch(_, subject, cases) => self.pattern_match(subject, cases),
+            other => Err(format!("{} is not supported", kind(other))),
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal) -> Result<(Expr, Repr), String> {
+        Ok(match literal {
+            Literal::Bool(b) => (Expr::I32(i32::from(*b)), Repr::Bool),
+            Literal::Char(c) => (Expr::I32(*c as i32), Repr::Char),
+            Literal::String(s) => (Expr::Data(self.data.string(s)), Repr::String),
+            Literal::Integer(n) => (Expr::I64(*n), Repr::Int),
+            Literal::Float(f) => (Expr::F64(*f), Repr::Float),
+            Literal::Decimal(_) => return Err("Decimal literals are not supported".into()),
+        })
+    }
+
+    fn apply(&mut self, value: &Value) -> Result<(Expr, Repr), String> {
+        let mut args = Vec::new();
+        let mut head = value;
+        while let Value::Apply(_, function, arg) = head {
+            args.push(arg.as_ref());
+            head = function;
+        }
+        args.reverse();
+
+        match head {
+            Value::Constructor(_, fqname) if args.is_empty() => {
+                let (repr, tag) = self.scope.tag(fqname)?;
+                Ok((Expr::I32(tag), repr))
+            }
+            Value::Reference(_, fqname) => match sdk(fqname) {
+                Some((module, name)) if module == "basics" => self.basic(&name, &args),
+                _ => self.call(fqname, &args),
+            },
+            other => Err(format!("applying a {} is not supported", kind(other))),
+        }
+    }
+
+    /// Call of a value of the module with all its arguments
+    fn call(&mut self, fqname: &FQName, args: &[&Value]) -> Result<(Expr, Repr), String> {
+        let name = fqname.to_canonical_string();
+        if !self.scope.is_local(fqname) {
+            return Err(format!("{} is not defined in this module", name));
+        }
+        let Some(index) = self
+            .functions
+            .iter()
+            .position(|f| Name::from(f.name) == fqname.local_name)
+        else {
+            return Err(
+                if self
+                    .scope
+                    .def
+                    .values
+                    .keys()
+                    .any(|v| Name::from(v) == fqname.local_name)
+                {
+                    format!("{} could not be compiled", name)
+                } else {
+                    format!("{} is not defined in this module", name)
+                },
+            );
+        };
+        let signature = &self.functions[index];
+        if args.len() != signature.params.len() {
+            return Err(format!(
+                "{} takes {} arguments but is given {}",
+                name,
+                signature.params.len(),
+                args.len()
+            ));
+        }
+        let mut operands = Vec::new();
+        for (arg, (param, expected)) in args.iter().zip(&signature.params) {
+            let (operand, repr) = self.value(arg)?;
+            if repr != *expected {
+                return Err(format!(
+                    "{} takes {} as {}, not {}",
+                    name, expected, param, repr
+                ));
+            }
+            operands.push(operand);
+        }
+        Ok((Expr::Call(index as u32, operands), signature.result.clone()))
+    }
+
+    /// Application of a `morphir/sdk:basics` operator
+    fn basic(&mut self, name: &str, args: &[&Value]) -> Result<(Expr, Repr), String> {
+        let arity = match name {
+            "negate" | "not" => 1,
+            "add"
+            | "subtract"
+            | "multiply"
+            | "divide"
+            | "integer-divide"
+            | "equal"
+            | "not-equal"
+            | "less-than"
+            | "greater-than"
+            | "less-than-or-equal"
+            | "greater-than-or-equal"
+            | "and"
+            | "or" => 2,
+            _ => return Err(format!("morphir/sdk:basics#{} is not supported", name)),
+        };
+        if args.len() != arity {
+            return Err(format!(
+                "morphir/sdk:basics#{} takes {} arguments but is given {}",
+                name,
+                arity,
+                args.len()
+            ));
+        }
+        let mut operands = Vec::new();
+        let mut repr = None;
+        for arg in args {
+            let (operand, operand_repr) = self.value(arg)?;
+            match &repr {
+                Some(first) if *first != operand_repr => {
+                    return Err(format!(
+                        "morphir/sdk:basics#{} is applied to {} and {}",
+                        name, first, operand_repr
+                    ));
+                }
+                _ => repr = Some(operand_repr),
+            }
+            operands.push(operand);
+        }
+        let repr = repr.unwrap_or(Repr::Unit);
+        let unsupported = || format!("morphir/sdk:basics#{} is not supported on {}", name, repr);
+
+        match (name, &repr) {
+            ("negate", Repr::Int) => {
+                operands.insert(0, Expr::I64(0));
+                return Ok((Expr::Op(Op::I64Sub, operands), Repr::Int));
+            }
+            ("not", Repr::Bool) => return Ok((Expr::Op(Op::I32Eqz, operands), Repr::Bool)),
+            // `and` and `or` only evaluate their second operand when needed
+            ("and" | "or", Repr::Bool) => {
+                let second = operands.pop().unwrap_or(Expr::Unreachable);
+                let first = operands.pop().unwrap_or(Expr::Unreachable);
+                let (then_branch, else_branch) = if name == "and" {
+                    (second, Expr::I32(0))
+                } else {
+                    (Expr::I32(1), second)
+                };
+                return Ok((
+                    Expr::If(
+                        ValType::I32,
+                        Box::new(first),
+                        Box::new(then_branch),
+                        Box::new(else_branch),
+                    ),
+                    Repr::Bool,
+                ));
+            }
+            _ => {}
+        }
+
+        let (op, result) = match (name, &repr) {
+            ("add", Repr::Int) => (Op::I64Add, Repr::Int),
+            ("subtract", Repr::Int) => (Op::I64Sub, Repr::Int),
+            ("multiply", Repr::Int) => (Op::I64Mul, Repr::Int),
+            ("integer-divide", Repr::Int) => (Op::I64DivS, Repr::Int),
+            ("add", Repr::Float) => (Op::F64Add, Repr::Float),
+            ("subtract", Repr::Float) => (Op::F64Sub, Repr::Float),
+            ("multiply", Repr::Float) => (Op::F64Mul, Repr::Float),
+            ("divide", Repr::Float) => (Op::F64Div, Repr::Float),
+            ("negate", Repr::Float) => (Op::F64Neg, Repr::Float),
+            ("equal", Repr::Int) => (Op::I64Eq, Repr::Bool),
+            ("not-equal", Repr::Int) => (Op::I64Ne, Repr::Bool),
+            ("less-than", Repr::Int) => (Op::I64LtS, Repr::Bool),
+            ("greater-than", Repr::Int) => (Op::I64GtS, Repr::Bool),
+            ("less-than-or-equal", Repr::Int) => (Op::I64LeS, Repr::Bool),
+            ("greater-than-or-equal", Repr::Int) => (Op::I64GeS, Repr::Bool),
+            ("equal", Repr::Float) => (Op::F64Eq, Repr::Bool),
+            ("not-equal", Repr::Float) => (Op::F64Ne, Repr::Bool),
+            ("less-than", Repr::Float) => (Op::F64Lt, Repr::Bool),
+            ("greater-than", Repr::Float) => (Op::F64Gt, Repr::Bool),
+            ("less-than-or-equal", Repr::Float) => (Op::F64Le, Repr::Bool),
+            ("greater-than-or-equal", Repr::Float) => (Op::F64Ge, Repr::Bool),
+            ("less-than", Repr::Char) => (Op::I32LtU, Repr::Bool),
+            ("greater-than", Repr::Char) => (Op::I32GtU, Repr::Bool),
+            ("less-than-or-equal", Repr::Char) => (Op::I32LeU, Repr::Bool),
+            ("greater-than-or-equal", Repr::Char) => (Op::I32GeU, Repr::Bool),
+            ("equal", Repr::Bool | Repr::Char | Repr::Unit | Repr::Enum(_)) => {
+                (Op::I32Eq, Repr::Bool)
+            }
+            ("not-equal", Repr::Bool | Repr::Char | Repr::Unit | Repr::Enum(_)) => {
+                (Op::I32Ne, Repr::Bool)
+            }
+            _ => return Err(unsupported()),
+        };
+        Ok((Expr::Op(op, operands), result))
+    }
+
+    /// Pattern match as a chain of conditions on the subject, held in a local
+    fn pattern_match(
+        &mut self,
+        subject: &Value,
+        cases: &[PatternCase],
+    ) -> Result<(Expr, Repr), String> {
+        let (subject, subject_repr) = self.value(subject)?;
+        let local = self.local(subject_repr.val_type());
+
+        let mut branches = Vec::new();
+        for PatternCase(pattern, body) in cases {
+            let scope = self.variables.len();
+            let test = self.pattern(pattern, local, &subject_repr);
+            let body = test.and_then(|test| Ok((test, self.value(body)?)));
+            self.variables.truncate(scope);
+            let (test, (body, repr)) = body?;
+            let catch_all = test.is_none();
+            branches.push((test, body, repr));
+            // Later cases can never apply
+            if catch_all {
+                break;
+            }
+        }
+
+        let Some((_, _, repr)) = branches.first() else {
+            return Err("a pattern match has no cases".into());
+        };
+        let repr = repr.clone();
+        if let Some((_, _, other)) = branches.iter().find(|(_, _, r)| *r != repr) {
+            return Err(format!(
+                "the cases of a pattern match are {} and {}",
+                repr, other
+            ));
+        }
+        let chain = branches
+            .into_iter()
+            .rev()
+            .fold(Expr::Unreachable, |rest, (test, body, _)| match test {
+                Some(test) => Expr::If(
+                    repr.val_type(),
+                    Box::new(test),
+                    Box::new(body),
+                    Box::new(rest),
+                ),
+                None => body,
+            });
+        Ok((Expr::Let(local, Box::new(subject), Box::new(chain)), repr))
+    }
+
+    /// Condition for the subject in `local` to match `pattern`, binding its
+    /// variables; `None` if it always matches
+    fn pattern(
+        &mut self,
+        pattern: &Pattern,
+        local: u32,
+        repr: &Repr,
+    ) -> Result<Option<Expr>, String> {
+        match pattern {
+            Pattern::WildcardPattern(_) => Ok(None),
+            Pattern::UnitPattern(_) if *repr == Repr::Unit => Ok(None),
+            Pattern::AsPattern(_, inner, name) => {
+                let test = self.pattern(inner, local, repr)?;
+                self.variables.push((name.clone(), local, repr.clone()));
+                Ok(test)
+            }
+            Pattern::LiteralPattern(_, literal) => {
+                let (value, literal_repr) = self.literal(literal)?;
+                let op = match &literal_repr {
+                    _ if literal_repr != *repr => {
+                        return Err(format!("a {} pattern cannot match {}", literal_repr, repr));
+                    }
+                    Repr::Int => Op::I64Eq,
+                    Repr::Float => Op::F64Eq,
+                    Repr::Bool | Repr::Char => Op::I32Eq,
+                    _ => return Err(format!("{} patterns are not supported", literal_repr)),
+                };
+                Ok(Some(Expr::Op(op, vec![Expr::Get(local), value])))
+            }
+            Pattern::ConstructorPattern(_, fqname, args) if args.is_empty() => {
+                let (constructor_repr, tag) = self.scope.tag(fqname)?;
+                if constructor_repr != *repr {
+                    return Err(format!(
+                        "a {} pattern cannot match {}",
+                        constructor_repr, repr
+                    ));
+                }
+                Ok(Some(Expr::Op(
+                    Op::I32Eq,
+                    vec![Expr::Get(local), Expr::I32(tag)],
+                )))
+            }
+            Pattern::ConstructorPattern(_, fqname, _) => Err(format!(
+                "{} patterns with arguments are not supported",
+                fqname.to_canonical_string()
+            )),
+            Pattern::TuplePattern(..) => Err("Tuple patterns are not supported".into()),
+            Pattern::EmptyListPattern(_) | Pattern::HeadTailPattern(..) => {
+                Err("List patterns are not supported".into())
+            }
+            Pattern::UnitPattern(_) => Err(format!("a () pattern cannot match {}", repr)),
+        }
+    }
+}
+
+/// Kind of a value, for messages
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Literal(..) => "Literal",
+        Value::Constructor(..) => "Constructor",
+        Value::Tuple(..) => "Tuple",
+        Value::List(..) => "List",
+        Value::Record(..) => "Record",
+        Value::Variable(..) => "Variable",
+        Value::Reference(..) => "Reference",
+        Value::Field(..) => "Field access",
+        Value::FieldFunction(..) => "Field function",
+        Value::Apply(..) => "Application",
+        Value::Lambda(..) => "Lambda",
+        Value::LetDefinition(..) => "Let definition",
+        Value::LetRecursion(..) => "Recursive let",
+        Value::Destructure(..) => "Destructuring let",
+        Value::IfThenElse(..) => "If",
+        Value::PatternMatch(..) => "Pattern match",
+        Value::UpdateRecord(..) => "Record update",
+        Value::Unit(..) => "Unit",
+        Value::Hole(..) => "Hole",
+        Value::Native(..) => "Native value",
+        Value::External(..) => "External value",
+    }
+}
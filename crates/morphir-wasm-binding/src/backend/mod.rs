@@ -1,7 +1,9 @@
 //! WASM backend - generate WebAssembly from Morphir IR
 
 mod codegen;
+mod component;
+mod lower;
 mod wat;
 
-pub use codegen::generate_wasm;
+pub use codegen::generate_wasm_with_diagnostics;
 pub use wat::generate_wat;
@@ -1,34 +1,14 @@
 //! WAT (WebAssembly Text) generation from Morphir IR
+//!
+//! The text holds the same functions and data as the linked binary, in
+//! folded form.
 
+use super::codegen::{Bases, Compilation, CompilationUnit, compile_ir, layout, memory_pages};
+use super::lower::{Expr, LoweredFunction};
 use morphir_extension_sdk::prelude::*;
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Write;
-
-/// Morphir distribution IR (simplified)
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Distribution {
-    #[serde(default)]
-    pub name: String,
-    #[serde(default)]
-    pub modules: Vec<ModuleIR>,
-}
-
-/// Module IR representation
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ModuleIR {
-    pub name: String,
-    #[serde(default)]
-    pub values: Vec<ValueDef>,
-}
-
-/// Value definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ValueDef {
-    pub name: String,
-    #[serde(default)]
-    pub body: serde_json::Value,
-}
+use wasm_encoder::ValType;
 
 /// Generate WAT (WebAssembly Text) from Morphir IR
 pub fn generate_wat(
@@ -37,17 +17,7 @@ pub fn generate_wat(
 ) -> Result<Vec<Artifact>> {
     let mut artifacts = Vec::new();
 
-    // Try to parse as distribution or module list
-    let (name, modules): (String, Vec<ModuleIR>) =
-        if let Ok(dist) = serde_json::from_value::<Distribution>(ir.clone()) {
-            (dist.name, dist.modules)
-        } else if let Ok(modules) = serde_json::from_value::<Vec<ModuleIR>>(ir.clone()) {
-            ("morphir".to_string(), modules)
-        } else {
-            // Try single module
-            let module = serde_json::from_value::<ModuleIR>(ir.clone())?;
-            (module.name.clone(), vec![module])
-        };
+    let Compilation { name, units, .. } = compile_ir(ir)?;
 
     let pretty = options
         .get("format")
@@ -55,7 +25,7 @@ pub fn generate_wat(
         .map(|s| s == "pretty")
         .unwrap_or(true);
 
-    let wat = compile_to_wat(&name, &modules, pretty);
+    let wat = compile_to_wat(&name, &units, pretty);
 
     artifacts.push(Artifact {
         path: format!("{}.wat", name),
@@ -66,10 +36,11 @@ pub fn generate_wat(
     Ok(artifacts)
 }
 
-/// Compile Morphir modules to WAT text format
-fn compile_to_wat(name: &str, modules: &[ModuleIR], pretty: bool) -> String {
+/// Compile units to WAT text format, linked as in the binary output
+fn compile_to_wat(name: &str, units: &[CompilationUnit], pretty: bool) -> String {
     let mut output = String::new();
     let indent = if pretty { "  " } else { "" };
+    let (bases, data) = layout(units);
 
     // String's Write impl never fails, so unwrap is safe
     let _ = writeln!(output, "(module");
@@ -77,22 +48,29 @@ fn compile_to_wat(name: &str, modules: &[ModuleIR], pretty: bool) -> String {
     let _ = writeln!(output, "{};;  Module: {}", indent, name);
     let _ = writeln!(output);
 
-    // Generate functions for each value definition
-    for module_ir in modules {
-        for value_def in &module_ir.values {
-            let func_name = format!("{}_{}", module_ir.name, value_def.name);
-
-            let _ = write!(output, "{}(func ${}", indent, func_name);
-            let _ = writeln!(output, " (export \"{}\") (result i32)", func_name);
-
-            // Generate function body
-            generate_wat_body(
-                &mut output,
-                &value_def.body,
-                &format!("{}{}", indent, indent),
+    if units.iter().any(|unit| unit.memory) {
+        let _ = writeln!(
+            output,
+            "{}(memory (export \"memory\") {})",
+            indent,
+            memory_pages(&data)
+        );
+        if !data.is_empty() {
+            let _ = writeln!(
+                output,
+                "{}(data (i32.const 0) \"{}\")",
+                indent,
+                escape(&data)
             );
+        }
+        let _ = writeln!(output);
+    }
 
-            let _ = writeln!(output, "{})", indent);
+    // Generate functions for each value definition
+    for (unit, bases) in units.iter().zip(bases) {
+        for function in &unit.functions {
+            let func_name = format!("{}_{}", unit.module, function.name);
+            write_function(&mut output, &func_name, function, bases, indent);
             let _ = writeln!(output);
         }
     }
@@ -102,64 +80,155 @@ fn compile_to_wat(name: &str, modules: &[ModuleIR], pretty: bool) -> String {
     output
 }
 
-/// Generate WAT instructions for a Morphir expression
-fn generate_wat_body(output: &mut String, body: &serde_json::Value, indent: &str) {
-    if let Some(obj) = body.as_object()
-        && let Some(kind) = obj.get("kind").and_then(|v| v.as_str())
-    {
-        match kind {
-            "literal" => {
-                if let Some(value) = obj.get("value")
-                    && let Some(lit_obj) = value.as_object()
-                    && let Some(lit_type) = lit_obj.get("type").and_then(|v| v.as_str())
-                {
-                    match lit_type {
-                        "int" => {
-                            if let Some(n) = lit_obj.get("value").and_then(|v| v.as_i64()) {
-                                let _ = writeln!(output, "{}(i32.const {})", indent, n);
-                                return;
-                            }
-                        }
-                        "bool" => {
-                            if let Some(b) = lit_obj.get("value").and_then(|v| v.as_bool()) {
-                                let _ = writeln!(
-                                    output,
-                                    "{}(i32.const {})",
-                                    indent,
-                                    if b { 1 } else { 0 }
-                                );
-                                return;
-                            }
-                        }
-                        _ => {
-                            let _ = writeln!(
-                                output,
-                                "{};;  Unsupported literal type: {}",
-                                indent, lit_type
-                            );
-                        }
-                    }
-                }
-            }
-            "apply" => {
-                let _ = writeln!(output, "{};;  Function application", indent);
-            }
-            "lambda" => {
-                let _ = writeln!(output, "{};;  Lambda expression", indent);
+fn write_function(
+    output: &mut String,
+    func_name: &str,
+    function: &LoweredFunction,
+    bases: Bases,
+    indent: &str,
+) {
+    let _ = write!(output, "{}(func ${}", indent, func_name);
+    let _ = write!(output, " (export \"{}\")", func_name);
+    if !function.params.is_empty() {
+        let _ = write!(output, " (param {})", types(&function.params));
+    }
+    let _ = write!(output, " (result {})", type_name(function.result));
+    if !function.locals.is_empty() {
+        let _ = write!(output, " (local {})", types(&function.locals));
+    }
+    let _ = writeln!(output);
+
+    // Generate function body
+    write_expr(
+        output,
+        &function.body,
+        bases,
+        &format!("{}{}", indent, indent),
+        indent,
+    );
+
+    let _ = writeln!(output, "{})", indent);
+}
+
+/// Write an expression in folded form, one instruction per line
+fn write_expr(output: &mut String, expr: &Expr, bases: Bases, indent: &str, step: &str) {
+    let inner = format!("{}{}", indent, step);
+    match expr {
+        Expr::I32(n) => {
+            let _ = writeln!(output, "{}(i32.const {})", indent, n);
+        }
+        Expr::I64(n) => {
+            let _ = writeln!(output, "{}(i64.const {})", indent, n);
+        }
+        Expr::F64(x) => {
+            let _ = writeln!(output, "{}(f64.const {})", indent, float(*x));
+        }
+        Expr::Data(offset) => {
+            let _ = writeln!(output, "{}(i32.const {})", indent, bases.data + offset);
+        }
+        Expr::Get(index) => {
+            let _ = writeln!(output, "{}(local.get {})", indent, index);
+        }
+        Expr::Let(index, value, body) => {
+            let _ = writeln!(output, "{}(local.set {}", indent, index);
+            write_expr(output, value, bases, &inner, step);
+            close(output);
+            write_expr(output, body, bases, indent, step);
+        }
+        Expr::Op(op, operands) => {
+            let _ = writeln!(output, "{}({}", indent, op.mnemonic());
+            for operand in operands {
+                write_expr(output, operand, bases, &inner, step);
             }
-            _ => {
-                let _ = writeln!(output, "{};;  Expression kind: {}", indent, kind);
+            close(output);
+        }
+        Expr::Call(index, args) => {
+            let _ = writeln!(output, "{}(call {}", indent, bases.functions + index);
+            for arg in args {
+                write_expr(output, arg, bases, &inner, step);
             }
+            close(output);
+        }
+        Expr::If(ty, condition, then_branch, else_branch) => {
+            let _ = writeln!(output, "{}(if (result {})", indent, type_name(*ty));
+            write_expr(output, condition, bases, &inner, step);
+            let _ = writeln!(output, "{}(then", inner);
+            write_expr(
+                output,
+                then_branch,
+                bases,
+                &format!("{}{}", inner, step),
+                step,
+            );
+            close(output);
+            let _ = writeln!(output, "{}(else", inner);
+            write_expr(
+                output,
+                else_branch,
+                bases,
+                &format!("{}{}", inner, step),
+                step,
+            );
+            close(output);
+            close(output);
+        }
+        Expr::Unreachable => {
+            let _ = writeln!(output, "{}(unreachable)", indent);
         }
     }
+}
+
+/// Close the expression whose last line was just written
+fn close(output: &mut String) {
+    output.pop();
+    output.push_str(")\n");
+}
+
+fn type_name(ty: ValType) -> &'static str {
+    match ty {
+        ValType::I32 => "i32",
+        ValType::I64 => "i64",
+        ValType::F32 => "f32",
+        ValType::F64 => "f64",
+        ValType::V128 => "v128",
+        ValType::Ref(_) => "externref",
+    }
+}
+
+fn types(tys: &[ValType]) -> String {
+    tys.iter()
+        .map(|ty| type_name(*ty))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Float in a form the text format reads back exactly
+fn float(x: f64) -> String {
+    if x.is_nan() {
+        "nan".to_string()
+    } else if x.is_infinite() {
+        if x > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        format!("{:?}", x)
+    }
+}
 
-    // Default: return 0
-    let _ = writeln!(output, "{}(i32.const 0)", indent);
+/// Bytes as the contents of a text format string
+fn escape(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| match b {
+            0x20..=0x7e if *b != b'"' && *b != b'\\' => (*b as char).to_string(),
+            _ => format!("\\{:02x}", b),
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::generate_wasm_with_diagnostics;
+    use base64::{Engine, engine::general_purpose::STANDARD};
 
     #[test]
     fn test_generate_wat() {
@@ -189,4 +258,74 @@ mod tests {
         assert!(result[0].content.contains("(module"));
         assert!(result[0].content.contains("(i32.const 42)"));
     }
+
+    #[test]
+    fn test_wat_runs_like_the_binary() {
+        let dist = morphir_core::ir::v4::text::parse_distribution(
+            "library acme/shop
+
+
+module rates
+
+rate (base : morphir/sdk:basics#Float) (tier : morphir/sdk:basics#Int) : morphir/sdk:basics#Float =
+    let
+        bonus : morphir/sdk:basics#Float =
+            case tier of
+                1 ->
+                    0.5
+                2 ->
+                    0.25
+                _ ->
+                    0.0
+    in
+    if morphir/sdk:basics#lessThan base 0.0 then
+        morphir/sdk:basics#negate base
+    else
+        morphir/sdk:basics#add base bonus
+
+name (tier : morphir/sdk:basics#Int) : morphir/sdk:string#String =
+    if morphir/sdk:basics#equal tier 1 then
+        \"gold \\\"1\\\"\"
+    else
+        \"standard\"
+",
+        )
+        .unwrap();
+        let ir = serde_json::to_value(&dist).unwrap();
+        let wat = generate_wat(&ir, &HashMap::new()).unwrap().remove(0);
+        assert_eq!(wat.path, "acme-shop.wat");
+        assert!(wat.content.contains("(memory (export \"memory\") 1)"));
+        let (artifacts, _) = generate_wasm_with_diagnostics(&ir, &HashMap::new()).unwrap();
+        let binary = STANDARD.decode(&artifacts.last().unwrap().content).unwrap();
+
+        let engine = wasmtime::Engine::default();
+        let run = |module: &[u8]| {
+            let module = wasmtime::Module::new(&engine, module).unwrap();
+            let mut store = wasmtime::Store::new(&engine, ());
+            let instance = wasmtime::Instance::new(&mut store, &module, &[]).unwrap();
+            let rate = instance
+                .get_typed_func::<(f64, i64), f64>(&mut store, "rates_rate")
+                .unwrap();
+            let rates: Vec<f64> = [(2.0, 1), (2.0, 2), (2.0, 3), (-2.0, 1)]
+                .into_iter()
+                .map(|args| rate.call(&mut store, args).unwrap())
+                .collect();
+            let name = instance
+                .get_typed_func::<i64, i32>(&mut store, "rates_name")
+                .unwrap();
+            let address = name.call(&mut store, 1).unwrap() as usize;
+            let memory = instance.get_memory(&mut store, "memory").unwrap();
+            let bytes = &memory.data(&store)[address..];
+            let len = u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+            (
+                rates,
+                String::from_utf8(bytes[4..4 + len].to_vec()).unwrap(),
+            )
+        };
+
+        let from_text = run(wat.content.as_bytes());
+        assert_eq!(from_text, run(&binary));
+        assert_eq!(from_text.0, vec![2.5, 2.25, 2.0, 2.0]);
+        assert_eq!(from_text.1, "gold \"1\"");
+    }
 }
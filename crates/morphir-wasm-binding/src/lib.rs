@@ -1,8 +1,13 @@
 //! Morphir WASM Binding Extension
 //!
 //! This extension provides WebAssembly code generation for Morphir:
-//! - Backend: Generate WASM binary from Morphir IR
+//! - Backend: Generate WASM binary from Morphir IR, one compilation unit per
+//!   Morphir module plus a link manifest and a linked module
 //! - Backend: Generate WAT text format from Morphir IR
+//!
+//! Values of v4 IR are lowered to functions over `i64` ints, `f64` floats,
+//! `i32` bools, chars and enum tags, and strings in memory; values using
+//! anything else are left out with a `W002` warning.
 
 use morphir_extension_sdk::prelude::*;
 
@@ -24,6 +29,8 @@ impl Extension for WasmExtension {
             license: Some("Apache-2.0".into()),
             homepage: Some("https://github.com/finos/morphir-rust".into()),
             min_sdk_version: Some("0.1.0".into()),
+            permissions: Permissions::default(),
+            ..Default::default()
         }
     }
 
@@ -33,6 +40,9 @@ impl Extension for WasmExtension {
             incremental: false,
             cancellation: false,
             progress: false,
+            fixes: false,
+            feeds: Vec::new(),
+            identifiers: None,
             extra: Default::default(),
         }
     }
@@ -48,8 +58,8 @@ impl Backend for WasmExtension {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
-        match backend::generate_wasm(&request.ir, &request.options) {
-            Ok(mut artifacts) => {
+        match backend::generate_wasm_with_diagnostics(&request.ir, &request.options) {
+            Ok((mut artifacts, diagnostics)) => {
                 // Optionally generate WAT as well
                 if emit_wat
                     && let Ok(wat_artifacts) = backend::generate_wat(&request.ir, &request.options)
@@ -60,18 +70,21 @@ impl Backend for WasmExtension {
                 Ok(GenerateResult {
                     success: true,
                     artifacts,
-                    diagnostics: vec![],
+                    diagnostics,
+                    source_map: vec![],
                 })
             }
             Err(e) => Ok(GenerateResult {
                 success: false,
                 artifacts: vec![],
+                source_map: vec![],
                 diagnostics: vec![Diagnostic {
                     severity: DiagnosticSeverity::Error,
                     code: Some("W001".into()),
                     message: e.to_string() as String,
                     location: None,
                     related: vec![],
+                    fixes: vec![],
                 }],
             }),
         }
@@ -83,4 +96,4 @@ impl Backend for WasmExtension {
 }
 
 // Export the extension
-morphir_extension_sdk::export_extension!(WasmExtension);
+morphir_extension_sdk::export_extension!(WasmExtension: Backend);
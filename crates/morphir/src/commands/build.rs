@@ -0,0 +1,357 @@
+//! Build command: compile, validate, and generate a project in one step
+//!
+//! `morphir build` runs the stages of [`Pipeline`] in order for the project
+//! the configuration describes. The frontend of `[frontend] language`
+//! compiles the sources, the IR is type checked, and code is generated for
+//! every `[codegen]` target (or each `--target`), all under the resolved
+//! `.morphir/out/<project>/` layout. A summary of the build is written there
+//! as `build.json` and added to the history `morphir stats` reports.
+//!
+//! Compilation or validation errors stop the build; a failing target does
+//! not keep the other targets from being generated.
+
+use crate::diagnostics::DiagnosticRenderer;
+use crate::error::CliError;
+use crate::output::{BuildOutput, BuildStage, Diagnostic, EventStream, LogFormat, json_requested};
+use crate::pipeline::Pipeline;
+use morphir_common::loader::load_ir;
+use morphir_daemon::workspace::BuildHistory;
+use morphir_daemon::workspace::metrics::{BuildSample, SeverityCounts};
+use morphir_design::sanitize_project_name;
+use starbase::AppResult;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Name of the build summary written under `.morphir/out/<project>/`
+pub const BUILD_REPORT_FILE: &str = "build.json";
+
+/// Options for the build command
+#[derive(Debug, Default)]
+pub struct BuildOptions {
+    /// Path to configuration file
+    pub config_path: Option<String>,
+    /// Targets to generate instead of the configured `[codegen]` targets
+    pub targets: Vec<String>,
+    /// Skip type checking the compiled IR
+    pub no_validate: bool,
+    /// Source files to compile in parallel; 0 for one per CPU
+    pub jobs: usize,
+    /// Output JSON format
+    pub json: bool,
+    /// Stream progress events while building
+    pub log_format: LogFormat,
+}
+
+/// Run the build command
+pub async fn run_build(options: BuildOptions) -> AppResult {
+    let BuildOptions {
+        config_path,
+        targets,
+        no_validate,
+        jobs,
+        json,
+        log_format,
+    } = options;
+    let json = json_requested(json);
+    let events = EventStream::new(log_format);
+    let started = Instant::now();
+
+    let mut pipeline = Pipeline::new()
+        .with_jobs(jobs)
+        .with_validation(!no_validate)
+        .with_events(events);
+    if let Some(path) = config_path {
+        pipeline = pipeline.with_config(path);
+    }
+    let ctx = pipeline.load_config()?;
+    let project = pipeline.package_name(&ctx);
+    let targets = if targets.is_empty() {
+        ctx.config
+            .codegen
+            .as_ref()
+            .map(|c| c.targets.clone())
+            .unwrap_or_default()
+    } else {
+        targets
+    };
+
+    let dependencies = pipeline.resolve_dependencies(&ctx)?;
+    let mut stages = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    // Compile
+    let stage_started = Instant::now();
+    let compiled = pipeline.compile(&ctx).await?;
+    diagnostics.extend(compiled.diagnostics);
+    stages.push(BuildStage {
+        stage: "compile".to_string(),
+        target: None,
+        success: compiled.success,
+        error: compiled.error,
+        output_path: Some(compiled.output_path.to_string_lossy().to_string()),
+        outputs: compiled.modules,
+        duration_ms: elapsed_ms(stage_started),
+    });
+    let ir = if compiled.success {
+        match compiled.ir {
+            Some(ir) => Some(ir),
+            None => Some(
+                load_ir(&compiled.output_path).map_err(|e| CliError::FileSystem {
+                    error: std::io::Error::other(e),
+                })?,
+            ),
+        }
+    } else {
+        None
+    };
+
+    // Validate
+    let ir = match ir {
+        Some(ir) if !no_validate => {
+            let stage_started = Instant::now();
+            let found = pipeline.validate(&ir, &dependencies);
+            let success = !has_errors(&found);
+            diagnostics.extend(found);
+            stages.push(BuildStage {
+                stage: "validate".to_string(),
+                target: None,
+                success,
+                error: None,
+                output_path: None,
+                outputs: Vec::new(),
+                duration_ms: elapsed_ms(stage_started),
+            });
+            success.then_some(ir)
+        }
+        ir => ir,
+    };
+
+    // Generate
+    if let Some(ir) = ir {
+        for target in &targets {
+            let stage_started = Instant::now();
+            let generated = pipeline
+                .clone()
+                .with_target(target.clone())
+                .generate(&ctx, &compiled.output_path, ir.clone())
+                .await?;
+            diagnostics.extend(generated.diagnostics);
+            stages.push(BuildStage {
+                stage: "generate".to_string(),
+                target: Some(generated.target),
+                success: generated.success,
+                error: generated.error,
+                output_path: Some(generated.output_path.to_string_lossy().to_string()),
+                outputs: generated
+                    .written
+                    .artifacts
+                    .iter()
+                    .chain(&generated.written.scaffolding)
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect(),
+                duration_ms: elapsed_ms(stage_started),
+            });
+        }
+    }
+
+    let output = BuildOutput {
+        success: stages.iter().all(|stage| stage.success) && !has_errors(&diagnostics),
+        project,
+        duration_ms: elapsed_ms(started),
+        stages,
+        diagnostics,
+    };
+    // The report and history are conveniences; a build that produced its
+    // outputs is not failed over them
+    if let Err(e) = record_build(&ctx.morphir_dir, &output) {
+        tracing::warn!("Failed to record the build: {}", e);
+    }
+
+    if events.is_enabled() {
+        events.result(&output);
+    } else if json {
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        print_summary(&output);
+    }
+    Ok((!output.success).then_some(1))
+}
+
+/// Write `output` to the project's build report and the workspace's build
+/// history
+fn record_build(morphir_dir: &Path, output: &BuildOutput) -> anyhow::Result<()> {
+    let report = morphir_dir
+        .join("out")
+        .join(sanitize_project_name(&output.project))
+        .join(BUILD_REPORT_FILE);
+    if let Some(parent) = report.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&report, serde_json::to_string_pretty(output)?)?;
+
+    let root = morphir_dir.parent().unwrap_or(Path::new("."));
+    let path = BuildHistory::path(root);
+    let mut history = BuildHistory::load(&path);
+    history.record(build_sample(output));
+    history.save(&path)?;
+    Ok(())
+}
+
+/// History entry of a build
+fn build_sample(output: &BuildOutput) -> BuildSample {
+    let mut diagnostics = SeverityCounts::default();
+    for diagnostic in &output.diagnostics {
+        match diagnostic.level.as_str() {
+            "error" => diagnostics.errors += 1,
+            "warning" => diagnostics.warnings += 1,
+            "hint" => diagnostics.hints += 1,
+            _ => diagnostics.infos += 1,
+        }
+    }
+    let compiled = output
+        .stages
+        .iter()
+        .find(|stage| stage.stage == "compile")
+        .map_or(0, |stage| stage.outputs.len());
+    BuildSample {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()),
+        project: output.project.clone(),
+        success: output.success,
+        duration_ms: output.duration_ms,
+        compiled,
+        cached: 0,
+        diagnostics,
+    }
+}
+
+fn print_summary(output: &BuildOutput) {
+    DiagnosticRenderer::stderr().eprint_all(&output.diagnostics);
+    for stage in &output.stages {
+        let name = match &stage.target {
+            Some(target) => format!("{} {}", stage.stage, target),
+            None => stage.stage.clone(),
+        };
+        let status = if stage.success { "ok" } else { "FAILED" };
+        let detail = match (stage.stage.as_str(), &stage.error) {
+            (_, Some(error)) => error.clone(),
+            ("compile", None) => format!("{} modules", stage.outputs.len()),
+            ("generate", None) => format!("{} files", stage.outputs.len()),
+            _ => String::new(),
+        };
+        let destination = stage
+            .output_path
+            .as_ref()
+            .filter(|_| stage.success)
+            .map(|path| format!(" -> {}", path))
+            .unwrap_or_default();
+        println!(
+            "  {:<20} {:<6} {}{} ({} ms)",
+            name, status, detail, destination, stage.duration_ms
+        );
+    }
+
+    let errors = count_level(&output.diagnostics, "error");
+    let warnings = count_level(&output.diagnostics, "warning");
+    println!(
+        "{} {} in {:.2}s ({} errors, {} warnings)",
+        if output.success {
+            "Built"
+        } else {
+            "Build failed for"
+        },
+        output.project,
+        output.duration_ms as f64 / 1000.0,
+        errors,
+        warnings
+    );
+}
+
+fn count_level(diagnostics: &[Diagnostic], level: &str) -> usize {
+    diagnostics.iter().filter(|d| d.level == level).count()
+}
+
+fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    count_level(diagnostics, "error") > 0
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(level: &str) -> Diagnostic {
+        Diagnostic {
+            level: level.to_string(),
+            code: None,
+            message: "message".to_string(),
+            file: None,
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            related: Vec::new(),
+            fixes: Vec::new(),
+        }
+    }
+
+    fn build_output() -> BuildOutput {
+        BuildOutput {
+            success: false,
+            project: "acme/shop".to_string(),
+            duration_ms: 42,
+            stages: vec![BuildStage {
+                stage: "compile".to_string(),
+                target: None,
+                success: true,
+                error: None,
+                output_path: None,
+                outputs: vec!["Orders".to_string(), "Pricing".to_string()],
+                duration_ms: 40,
+            }],
+            diagnostics: vec![
+                diagnostic("error"),
+                diagnostic("warning"),
+                diagnostic("warning"),
+                diagnostic("info"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_build_sample_counts_diagnostics_by_level() {
+        let sample = build_sample(&build_output());
+        assert_eq!(sample.project, "acme/shop");
+        assert_eq!(sample.compiled, 2);
+        assert_eq!(
+            sample.diagnostics,
+            SeverityCounts {
+                errors: 1,
+                warnings: 2,
+                infos: 1,
+                hints: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_record_build_writes_report_and_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let morphir_dir = dir.path().join(".morphir");
+        record_build(&morphir_dir, &build_output()).unwrap();
+        record_build(&morphir_dir, &build_output()).unwrap();
+
+        let report: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(morphir_dir.join("out/acme-shop/build.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(report["stages"][0]["outputs"][1], "Pricing");
+        let history = BuildHistory::load(&BuildHistory::path(dir.path()));
+        assert_eq!(history.builds.len(), 2);
+        assert!(!history.builds[0].success);
+    }
+}
@@ -0,0 +1,181 @@
+//! Cache commands: maintain what the daemon keeps under `.morphir/cache`
+//!
+//! The daemon refreshes each project's module index as it opens a workspace,
+//! rescanning only the sources that changed. `morphir cache rebuild-index`
+//! discards the saved indexes and scans every source again, for when an index
+//! is suspected to be out of step with the sources.
+//!
+//! Builds also keep the IR of every module they compile in a content-addressed
+//! cache under `.morphir/cache/ir`. `morphir cache stats` reports its size and
+//! `morphir cache clear` empties it.
+
+use crate::error::CliError;
+use crate::output::{CacheStatsOutput, IndexedProjectOutput, json_requested};
+use morphir_common::pipeline::cache::{CacheStats, IrCache};
+use morphir_daemon::workspace::{Project, Workspace};
+use morphir_design::discover_config;
+use starbase::AppResult;
+use std::path::{Path, PathBuf};
+
+/// Root of the workspace configured by `config_path`, or by the config file
+/// found from the current directory
+fn workspace_root(config_path: Option<String>) -> Result<PathBuf, CliError> {
+    let config_file = match config_path {
+        Some(cfg) => PathBuf::from(cfg),
+        None => {
+            let start_dir =
+                std::env::current_dir().map_err(|e| CliError::FileSystem { error: e })?;
+            discover_config(&start_dir).ok_or_else(|| CliError::Config {
+                error: anyhow::anyhow!("No morphir.toml or morphir.json found"),
+            })?
+        }
+    };
+    Ok(config_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default())
+}
+
+/// Run `morphir cache rebuild-index`
+pub fn run_cache_rebuild_index(
+    config_path: Option<String>,
+    project: Option<String>,
+    json: bool,
+) -> AppResult {
+    let json = json_requested(json);
+    let root = workspace_root(config_path)?;
+    let workspace = Workspace::open(root.clone()).map_err(|e| CliError::Config {
+        error: anyhow::anyhow!("Failed to open workspace at {}: {}", root.display(), e),
+    })?;
+
+    let mut projects: Vec<&Project> = workspace
+        .projects
+        .values()
+        .filter(|p| project.as_ref().is_none_or(|name| p.name == *name))
+        .collect();
+    if projects.is_empty() {
+        return Err(CliError::Config {
+            error: match project {
+                Some(name) => anyhow::anyhow!("No project named {} in the workspace", name),
+                None => anyhow::anyhow!("No projects found in the workspace"),
+            },
+        }
+        .into());
+    }
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut indexed = Vec::new();
+    for project in projects {
+        let (index, _) = project.rebuild_index().map_err(|e| CliError::Config {
+            error: anyhow::anyhow!("Failed to index {}: {}", project.name, e),
+        })?;
+        let path = project.index_path();
+        indexed.push(IndexedProjectOutput {
+            name: project.name.clone(),
+            modules: index.files.len(),
+            symbols: index.symbol_count(),
+            index: path
+                .strip_prefix(&root)
+                .unwrap_or(&path)
+                .display()
+                .to_string(),
+        });
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&indexed).unwrap());
+    } else {
+        for project in &indexed {
+            println!(
+                "{}: {} module(s), {} symbol(s) indexed into {}",
+                project.name, project.modules, project.symbols, project.index
+            );
+        }
+    }
+    Ok(None)
+}
+
+/// Run `morphir cache stats`
+pub fn run_cache_stats(config_path: Option<String>, json: bool) -> AppResult {
+    let json = json_requested(json);
+    let root = workspace_root(config_path)?;
+    let cache = IrCache::in_morphir_dir(&root.join(".morphir"));
+    let stats = cache.stats().map_err(|e| CliError::Config {
+        error: anyhow::anyhow!("Failed to read the IR cache: {}", e),
+    })?;
+    let output = cache_output(&root, &cache, stats);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        println!(
+            "{}: {} cached module(s), {}",
+            output.path,
+            output.entries,
+            format_size(output.bytes)
+        );
+    }
+    Ok(None)
+}
+
+/// Run `morphir cache clear`
+pub fn run_cache_clear(config_path: Option<String>, json: bool) -> AppResult {
+    let json = json_requested(json);
+    let root = workspace_root(config_path)?;
+    let cache = IrCache::in_morphir_dir(&root.join(".morphir"));
+    let removed = cache.clear().map_err(|e| CliError::FileSystem {
+        error: std::io::Error::other(format!("Failed to clear the IR cache: {}", e)),
+    })?;
+    let output = cache_output(&root, &cache, removed);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        println!(
+            "Removed {} cached module(s), {}, from {}",
+            output.entries,
+            format_size(output.bytes),
+            output.path
+        );
+    }
+    Ok(None)
+}
+
+fn cache_output(root: &Path, cache: &IrCache, stats: CacheStats) -> CacheStatsOutput {
+    CacheStatsOutput {
+        path: cache
+            .dir()
+            .strip_prefix(root)
+            .unwrap_or(cache.dir())
+            .display()
+            .to_string(),
+        entries: stats.entries,
+        bytes: stats.bytes,
+    }
+}
+
+/// `bytes` in the largest unit that keeps it at least 1, e.g. `1.5 MiB`
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizes_are_shown_in_binary_units() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
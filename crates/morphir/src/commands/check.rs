@@ -0,0 +1,297 @@
+//! Check command for fast feedback on a workspace
+//!
+//! Parses the sources of every project and type checks its compiled IR, which
+//! also verifies that every reference resolves. No transforms run and nothing
+//! is generated or written, so the command stays cheap enough for editors and
+//! pre-commit hooks.
+//!
+//! Sources whose fingerprint matches the last successful build recorded under
+//! `.morphir/cache` are known to parse and are skipped. There is no daemon
+//! process to hand the work to yet, so checks always run in-process, reusing
+//! the caches that incremental builds leave on disk.
+
+use crate::commands::validate::validate_distribution;
+use crate::diagnostics::DiagnosticRenderer;
+use crate::error::{CliError, convert_extension_diagnostics};
+use crate::output::{CheckOutput, CheckedProject, Diagnostic, json_requested};
+use crate::pipeline::collect_source_files;
+use morphir_common::loader::{LoadedDistribution, load_distribution_from_source};
+use morphir_daemon::workspace::{Project, Workspace};
+use morphir_design::{discover_config, discover_morphir_dir, resolve_compile_output};
+use morphir_extension_sdk::types::fingerprint;
+use starbase::AppResult;
+use std::path::{Path, PathBuf};
+
+/// Run the check command
+pub fn run_check(config_path: Option<String>, project: Option<String>, json: bool) -> AppResult {
+    let json = json_requested(json);
+    let config_file = if let Some(cfg) = config_path {
+        PathBuf::from(cfg)
+    } else {
+        let start_dir = std::env::current_dir().map_err(|e| CliError::FileSystem { error: e })?;
+        discover_config(&start_dir).ok_or_else(|| CliError::Config {
+            error: anyhow::anyhow!("No morphir.toml or morphir.json found"),
+        })?
+    };
+    let root = config_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let projects = check_workspace(&root, project.as_deref())?;
+    let success = projects
+        .iter()
+        .flat_map(|p| &p.diagnostics)
+        .all(|d| d.level != "error");
+
+    if json {
+        let output = CheckOutput { success, projects };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        let renderer = DiagnosticRenderer::stderr();
+        for checked in &projects {
+            renderer.eprint_all(&checked.diagnostics);
+            let errors = checked
+                .diagnostics
+                .iter()
+                .filter(|d| d.level == "error")
+                .count();
+            let warnings = checked
+                .diagnostics
+                .iter()
+                .filter(|d| d.level == "warning")
+                .count();
+            println!(
+                "{}: {} file(s) parsed, {} unchanged, {} error(s), {} warning(s)",
+                checked.name, checked.parsed, checked.unchanged, errors, warnings
+            );
+        }
+    }
+
+    Ok(if success { None } else { Some(1) })
+}
+
+/// Check the projects of the workspace rooted at `root`, or only the named one
+pub fn check_workspace(root: &Path, only: Option<&str>) -> Result<Vec<CheckedProject>, CliError> {
+    let workspace = Workspace::open(root.to_path_buf()).map_err(|e| CliError::Config {
+        error: anyhow::anyhow!("Failed to open workspace at {}: {}", root.display(), e),
+    })?;
+
+    let mut projects: Vec<&Project> = workspace
+        .projects
+        .values()
+        .filter(|p| only.is_none_or(|name| p.name == name))
+        .collect();
+    if projects.is_empty() {
+        return Err(CliError::Config {
+            error: match only {
+                Some(name) => anyhow::anyhow!("No project named {} in the workspace", name),
+                None => anyhow::anyhow!("No projects found in the workspace"),
+            },
+        });
+    }
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(projects
+        .into_iter()
+        .map(|project| check_project(&workspace, project))
+        .collect())
+}
+
+fn check_project(workspace: &Workspace, project: &Project) -> CheckedProject {
+    let mut checked = CheckedProject {
+        name: project.name.clone(),
+        parsed: 0,
+        unchanged: 0,
+        ir: None,
+        diagnostics: Vec::new(),
+    };
+    let Some(language) = project
+        .config
+        .frontend
+        .as_ref()
+        .or(workspace.config.frontend.as_ref())
+        .and_then(|f| f.language.clone())
+    else {
+        checked.diagnostics.push(note(
+            "error",
+            "No source language configured; set [frontend] language".to_string(),
+        ));
+        return checked;
+    };
+
+    // Parse: sources unchanged since the last successful build are known to parse
+    let fingerprints = project.load_fingerprints().unwrap_or_default();
+    let sources = match collect_source_files(&project.path.join(&project.source_dir), &language) {
+        Ok(sources) => sources,
+        Err(e) => {
+            checked.diagnostics.push(note("error", e.to_string()));
+            return checked;
+        }
+    };
+    let mut has_parser = true;
+    for path in &sources {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                checked
+                    .diagnostics
+                    .push(note("error", format!("Failed to read {}: {}", path, e)));
+                continue;
+            }
+        };
+        if fingerprints.files.get(path) == Some(&fingerprint(content.as_bytes())) {
+            checked.unchanged += 1;
+            continue;
+        }
+        match parse_source(&language, path, &content) {
+            Some(diagnostics) => {
+                checked.parsed += 1;
+                checked.diagnostics.extend(diagnostics);
+            }
+            None => has_parser = false,
+        }
+    }
+    if !has_parser {
+        checked.diagnostics.push(note(
+            "info",
+            format!(
+                "No in-process parser for {}; changed sources are checked by `morphir compile`",
+                language
+            ),
+        ));
+    }
+    if !fingerprints.files.is_empty() && checked.parsed > 0 {
+        checked.diagnostics.push(note(
+            "info",
+            format!(
+                "{} source file(s) changed since the last build; types are checked against the last compiled IR",
+                checked.parsed
+            ),
+        ));
+    }
+
+    // Reference integrity and types, on the IR of the last compile
+    let morphir_dir =
+        discover_morphir_dir(&project.path).unwrap_or_else(|| project.path.join(".morphir"));
+    let ir_path = resolve_compile_output(&project.name, &language, &morphir_dir);
+    if !ir_path.exists() {
+        checked.diagnostics.push(note(
+            "info",
+            format!(
+                "No compiled IR at {}; run `morphir compile` to check references and types",
+                ir_path.display()
+            ),
+        ));
+        return checked;
+    }
+    checked.ir = Some(ir_path.to_string_lossy().to_string());
+    match load_distribution_from_source(&ir_path.to_string_lossy()) {
+        Ok(LoadedDistribution::V4(ir_file)) => checked
+            .diagnostics
+            .extend(validate_distribution(&ir_file.distribution)),
+        Ok(LoadedDistribution::Classic(_)) => checked.diagnostics.push(note(
+            "info",
+            "Compiled IR is in the Classic format; only V4 IR is type checked".to_string(),
+        )),
+        Err(e) => checked
+            .diagnostics
+            .push(note("error", format!("Failed to load compiled IR: {}", e))),
+    }
+    checked
+}
+
+/// Parse diagnostics for a source file, or `None` if the language has no
+/// in-process parser
+fn parse_source(language: &str, path: &str, content: &str) -> Option<Vec<Diagnostic>> {
+    match language {
+        "gleam" => Some(convert_extension_diagnostics(
+            &morphir_gleam_binding::frontend::parse_gleam_recovering(path, content)
+                .diagnostics(path, content),
+        )),
+        _ => None,
+    }
+}
+
+/// A diagnostic about the project as a whole
+fn note(level: &str, message: String) -> Diagnostic {
+    Diagnostic {
+        level: level.to_string(),
+        code: None,
+        message,
+        file: None,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        related: Vec::new(),
+        fixes: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_daemon::workspace::BuildFingerprints;
+
+    fn project(root: &Path) {
+        std::fs::write(
+            root.join("morphir.toml"),
+            "[project]\nname = \"shop\"\nversion = \"0.1.0\"\nsource_directory = \"src\"\n\n[frontend]\nlanguage = \"gleam\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/orders.gleam"), "pub fn total() { 1 }\n").unwrap();
+        std::fs::write(root.join("src/broken.gleam"), "pub fn total( {\n").unwrap();
+    }
+
+    fn levels(checked: &CheckedProject) -> Vec<&str> {
+        checked
+            .diagnostics
+            .iter()
+            .map(|d| d.level.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_check_reports_parse_errors_without_compiling() {
+        let temp = tempfile::tempdir().unwrap();
+        project(temp.path());
+
+        let projects = check_workspace(temp.path(), None).unwrap();
+        assert_eq!(projects.len(), 1);
+        let checked = &projects[0];
+        assert_eq!(checked.name, "shop");
+        assert_eq!(checked.parsed, 2);
+        assert_eq!(checked.ir, None);
+        assert_eq!(levels(checked), vec!["error", "info"]);
+        assert!(
+            checked.diagnostics[0]
+                .file
+                .as_deref()
+                .unwrap()
+                .ends_with("broken.gleam")
+        );
+        assert!(!temp.path().join(".morphir").exists());
+    }
+
+    #[test]
+    fn test_check_skips_sources_unchanged_since_last_build() {
+        let temp = tempfile::tempdir().unwrap();
+        project(temp.path());
+        let orders = temp.path().join("src/orders.gleam");
+        let mut fingerprints = BuildFingerprints::default();
+        fingerprints.files.insert(
+            orders.to_string_lossy().to_string(),
+            fingerprint(&std::fs::read(&orders).unwrap()),
+        );
+        fingerprints
+            .save(&temp.path().join(".morphir/cache/fingerprints.json"))
+            .unwrap();
+
+        let checked = &check_workspace(temp.path(), Some("shop")).unwrap()[0];
+        assert_eq!((checked.parsed, checked.unchanged), (1, 1));
+
+        assert!(check_workspace(temp.path(), Some("other")).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Config commands
+//!
+//! `morphir config doctor` resolves the effective configuration (defaults,
+//! config file, profile, environment, and `--set` overrides) and reports
+//! problems; `--explain` prints every key with the layer that set it.
+
+use crate::output::json_requested;
+use morphir_common::config::{ConfigResolver, ConfigSource, EffectiveConfig};
+use morphir_design::discover_config;
+use serde::Serialize;
+use starbase::AppResult;
+use std::path::PathBuf;
+
+/// Options for the `config doctor` command
+#[derive(Debug, Default)]
+pub struct ConfigDoctorOptions {
+    /// Explicit config file path (discovered from the working directory if omitted)
+    pub config: Option<PathBuf>,
+    /// Profile to apply
+    pub profile: Option<String>,
+    /// `key=value` overrides
+    pub overrides: Vec<String>,
+    /// Print every effective key with its provenance
+    pub explain: bool,
+    /// Output as JSON
+    pub json: bool,
+}
+
+/// JSON output for the config doctor command
+#[derive(Serialize)]
+struct ConfigDoctorOutput {
+    success: bool,
+    config_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<serde_json::Value>,
+}
+
+fn print_explain(effective: &EffectiveConfig) {
+    let width = effective.entries.keys().map(String::len).max().unwrap_or(0);
+    for (key, entry) in &effective.entries {
+        let mut note = entry.source.to_string();
+        if let Some(previous) = entry.overridden.last() {
+            note.push_str(&format!(" (overrides {})", previous));
+        }
+        println!(
+            "{:width$} = {}  # {}",
+            key,
+            entry.value,
+            note,
+            width = width
+        );
+    }
+}
+
+/// Run the `config doctor` command.
+pub fn run_config_doctor(options: ConfigDoctorOptions) -> AppResult {
+    let ConfigDoctorOptions {
+        config,
+        profile,
+        overrides,
+        explain,
+        json,
+    } = options;
+    let json = json_requested(json);
+
+    let config_file = config.or_else(|| {
+        std::env::current_dir()
+            .ok()
+            .and_then(|cwd| discover_config(&cwd))
+    });
+
+    let mut resolver = ConfigResolver::new().with_env(std::env::vars());
+    if let Some(path) = &config_file {
+        resolver = resolver.with_file(path);
+    }
+    if let Some(profile) = profile {
+        resolver = resolver.with_profile(profile);
+    }
+    for spec in overrides {
+        resolver = resolver.with_override(spec);
+    }
+
+    let config_file_str = config_file.as_ref().map(|p| p.display().to_string());
+    let effective = match resolver.resolve() {
+        Ok(effective) => effective,
+        Err(e) => {
+            if json {
+                let output = ConfigDoctorOutput {
+                    success: false,
+                    config_file: config_file_str,
+                    config: None,
+                    error: Some(e.to_json()),
+                };
+                println!("{}", serde_json::to_string_pretty(&output).unwrap());
+            } else {
+                eprintln!("error: {}", e);
+            }
+            return Ok(Some(1));
+        }
+    };
+
+    if json {
+        let output = ConfigDoctorOutput {
+            success: true,
+            config_file: config_file_str,
+            config: Some(effective.to_json()),
+            error: None,
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return Ok(None);
+    }
+
+    match &config_file_str {
+        Some(path) => println!("Configuration file: {}", path),
+        None => println!("No morphir.toml or morphir.json found; using defaults"),
+    }
+    if let Some(profile) = &effective.profile {
+        println!("Profile: {}", profile);
+    }
+    let layers: Vec<String> = effective
+        .sources
+        .iter()
+        .filter(|s| !matches!(s, ConfigSource::Default))
+        .map(ToString::to_string)
+        .collect();
+    if !layers.is_empty() {
+        println!("Layers: default, {}", layers.join(", "));
+    }
+    if explain {
+        println!();
+        print_explain(&effective);
+        println!();
+    }
+    for (key, source) in &effective.unknown_keys {
+        eprintln!(
+            "warning: unknown key `{}` set by {} is ignored",
+            key, source
+        );
+    }
+    println!("Configuration OK");
+    Ok(None)
+}
@@ -0,0 +1,405 @@
+//! Daemon commands: start, stop, and query the Morphir daemon
+//!
+//! `morphir daemon start` runs the daemon in the background, listening for
+//! JSON-RPC on the loopback interface, and returns once it answers. The
+//! daemon records its address in the runtime directory, where `stop` and
+//! `status` find it. `--foreground` keeps it attached to the terminal, and
+//! `--stdio` serves a single client on standard input and output instead,
+//! as editors expect. `--watch` rebuilds projects as their sources change,
+//! pushing the diagnostics to connected clients.
+//!
+//! On `daemon/shutdown`, Ctrl-C, or SIGTERM the daemon drains requests in
+//! flight for `[daemon] shutdown_timeout_ms` and exits with 0, or with 3 when
+//! it had to cancel requests still running at the timeout.
+
+use crate::output::{DaemonStatusOutput, json_requested};
+use morphir_common::config::{DaemonSection, ExtensionsSection};
+use morphir_common::remote::RemoteSourceConfig;
+use morphir_daemon::extensions::install::{InstalledExtensions, morphir_home};
+use morphir_daemon::server::{self, DEFAULT_DRAIN_TIMEOUT, DEFAULT_PORT, methods};
+use morphir_daemon::workspace::WatchConfig;
+use morphir_daemon::{DaemonInfo, DaemonServer, ExtensionRegistry, ShutdownKind, Transport};
+use morphir_design::{discover_config, load_config_context};
+use starbase::AppResult;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Time allowed for a daemon to come up or go down
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between checks while waiting on the daemon
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Options for `morphir daemon start`
+pub struct DaemonStartOptions {
+    /// Workspace to open; defaults to the one around the current directory
+    pub workspace: Option<PathBuf>,
+    /// Port to listen on; defaults to `[daemon] port`, then 9741
+    pub port: Option<u16>,
+    /// Serve in this process instead of in the background
+    pub foreground: bool,
+    /// Serve a single client on stdin and stdout
+    pub stdio: bool,
+    /// Rebuild projects as their sources change
+    pub watch: bool,
+}
+
+/// Workspace root and daemon settings around `start`
+pub(super) struct DaemonContext {
+    pub(super) root: Option<PathBuf>,
+    output_dir: PathBuf,
+    section: DaemonSection,
+    extensions: ExtensionsSection,
+    sources: RemoteSourceConfig,
+}
+
+pub(super) fn load_context(start: PathBuf) -> anyhow::Result<DaemonContext> {
+    let Some(config_path) = discover_config(&start) else {
+        return Ok(DaemonContext {
+            root: None,
+            output_dir: start.join(".morphir").join("out"),
+            section: DaemonSection::default(),
+            extensions: ExtensionsSection::default(),
+            sources: RemoteSourceConfig::default(),
+        });
+    };
+    let ctx = load_config_context(&config_path)?;
+    Ok(DaemonContext {
+        root: config_path.parent().map(PathBuf::from),
+        output_dir: ctx.morphir_dir.join("out"),
+        section: ctx.config.daemon.unwrap_or_default(),
+        extensions: ctx.config.extensions,
+        sources: ctx.config.sources.unwrap_or_default(),
+    })
+}
+
+/// Run `morphir daemon start`
+pub async fn run_daemon_start(options: DaemonStartOptions) -> AppResult {
+    let start = match options
+        .workspace
+        .clone()
+        .map(Ok)
+        .unwrap_or_else(std::env::current_dir)
+    {
+        Ok(start) => start,
+        Err(e) => return Ok(fail(format!("Failed to read current directory: {}", e))),
+    };
+    let ctx = match load_context(start) {
+        Ok(ctx) => ctx,
+        Err(e) => return Ok(fail(format!("Failed to load configuration: {:#}", e))),
+    };
+    let port = options.port.or(ctx.section.port).unwrap_or(DEFAULT_PORT);
+
+    if options.stdio || options.foreground {
+        return serve(ctx, port, options.stdio, options.watch).await;
+    }
+
+    let dir = DaemonInfo::default_dir();
+    if let Some(info) = running_daemon(&dir).await {
+        println!(
+            "Daemon already running at {} (pid {})",
+            info.address, info.pid
+        );
+        return Ok(None);
+    }
+    spawn(ctx.root, port, options.watch, &dir).await
+}
+
+/// Serve in this process until shut down
+async fn serve(ctx: DaemonContext, port: u16, stdio: bool, watch: bool) -> AppResult {
+    let root = ctx
+        .root
+        .clone()
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    let registry = match load_registry(root, &ctx).await {
+        Ok(registry) => registry,
+        Err(message) => return Ok(fail(message)),
+    };
+
+    let mut server = DaemonServer::new(registry);
+    if let Some(timeout) = ctx.section.shutdown_timeout_ms {
+        server = server.with_drain_timeout(Duration::from_millis(timeout));
+    }
+    if !stdio {
+        server = server.with_info_dir(DaemonInfo::default_dir());
+    }
+    if watch {
+        server = server.with_watch(WatchConfig::from(&ctx.section));
+    }
+    if let Err(e) = ctx.root.map(|root| server.open_workspace(root)).transpose() {
+        return Ok(fail(format!("Failed to open workspace: {}", e)));
+    }
+    let server = Arc::new(server);
+    let interrupted = Arc::clone(&server);
+    tokio::spawn(async move {
+        stop_signal().await;
+        interrupted.shutdown();
+    });
+
+    if stdio {
+        let kind = match server.serve(Transport::Stdio).await {
+            Ok(kind) => kind,
+            Err(e) => return Ok(fail(format!("Daemon failed: {}", e))),
+        };
+        // Reading stdin blocks a thread the runtime would wait on; a shutdown
+        // request can leave it reading, so leave without waiting
+        std::process::exit(kind.exit_code().into());
+    }
+
+    let listener = match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(listener) => listener,
+        Err(e) => return Ok(fail(format!("Failed to listen on port {}: {}", port, e))),
+    };
+    if let Ok(address) = listener.local_addr() {
+        eprintln!("Daemon listening on {}", address);
+    }
+    match server.serve(Transport::Tcp(listener)).await {
+        Ok(ShutdownKind::Clean) => Ok(None),
+        Ok(kind) => {
+            eprintln!("Daemon stopped before requests in flight completed");
+            Ok(Some(kind.exit_code()))
+        }
+        Err(e) => Ok(fail(format!("Daemon failed: {}", e))),
+    }
+}
+
+/// Wait for Ctrl-C or, on Unix, SIGTERM as sent by service managers
+async fn stop_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Start the daemon in a background process and wait until it answers
+async fn spawn(root: Option<PathBuf>, port: u16, watch: bool, dir: &std::path::Path) -> AppResult {
+    let log_path = dir.join("daemon.log");
+    let log = std::fs::create_dir_all(dir).and_then(|_| std::fs::File::create(&log_path));
+    let log = match log {
+        Ok(log) => log,
+        Err(e) => {
+            return Ok(fail(format!(
+                "Failed to create {}: {}",
+                log_path.display(),
+                e
+            )));
+        }
+    };
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return Ok(fail(format!("Failed to locate the morphir binary: {}", e))),
+    };
+
+    let mut command = std::process::Command::new(exe);
+    command
+        .args([
+            "daemon",
+            "start",
+            "--foreground",
+            "--port",
+            &port.to_string(),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(log);
+    if let Some(root) = &root {
+        command.arg("--workspace").arg(root);
+    }
+    if watch {
+        command.arg("--watch");
+    }
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => return Ok(fail(format!("Failed to start daemon: {}", e))),
+    };
+
+    let started = Instant::now();
+    while started.elapsed() < WAIT_TIMEOUT {
+        if let Ok(Some(status)) = child.try_wait() {
+            return Ok(fail(format!(
+                "Daemon exited with {}; see {}",
+                status,
+                log_path.display()
+            )));
+        }
+        let info = running_daemon(dir).await;
+        if let Some(info) = info.filter(|info| info.pid == child.id()) {
+            println!("Daemon started at {} (pid {})", info.address, info.pid);
+            return Ok(None);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    Ok(fail(format!(
+        "Daemon did not answer within {}s; see {}",
+        WAIT_TIMEOUT.as_secs(),
+        log_path.display()
+    )))
+}
+
+/// Run `morphir daemon stop`
+pub async fn run_daemon_stop() -> AppResult {
+    let dir = DaemonInfo::default_dir();
+    let Some(info) = running_daemon(&dir).await else {
+        println!("Daemon is not running");
+        return Ok(None);
+    };
+    if let Err(e) = server::request(info.address, methods::SHUTDOWN, serde_json::Value::Null).await
+    {
+        return Ok(fail(format!("Failed to stop daemon: {}", e)));
+    }
+
+    // The daemon forgets itself once in-flight requests complete, or once
+    // its drain timeout cancels them
+    let drain = std::env::current_dir()
+        .ok()
+        .and_then(|start| load_context(start).ok())
+        .and_then(|ctx| ctx.section.shutdown_timeout_ms)
+        .map_or(DEFAULT_DRAIN_TIMEOUT, Duration::from_millis);
+    let timeout = WAIT_TIMEOUT + drain;
+    let started = Instant::now();
+    while started.elapsed() < timeout {
+        if !matches!(DaemonInfo::read(&dir), Ok(Some(ref current)) if *current == info) {
+            println!("Daemon stopped (pid {})", info.pid);
+            return Ok(None);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    Ok(fail(format!(
+        "Daemon (pid {}) did not stop within {}s",
+        info.pid,
+        timeout.as_secs()
+    )))
+}
+
+/// Run `morphir daemon status`; exits with 1 when no daemon is running
+pub async fn run_daemon_status(json: bool) -> AppResult {
+    let json = json_requested(json);
+    let dir = DaemonInfo::default_dir();
+    let running = match running_daemon(&dir).await {
+        Some(info) => server::request(info.address, methods::HEALTH, serde_json::Value::Null)
+            .await
+            .ok()
+            .map(|health| (info, health)),
+        None => None,
+    };
+
+    if json {
+        let output = DaemonStatusOutput {
+            running: running.is_some(),
+            pid: running.as_ref().map(|(info, _)| info.pid),
+            address: running.as_ref().map(|(info, _)| info.address.to_string()),
+            health: running.as_ref().map(|(_, health)| health.clone()),
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else if let Some((info, health)) = &running {
+        print_status(info, health);
+    } else {
+        println!("Daemon is not running");
+    }
+    Ok(if running.is_some() { None } else { Some(1) })
+}
+
+fn print_status(info: &DaemonInfo, health: &serde_json::Value) {
+    println!("Morphir Daemon");
+    println!("  Status:    running");
+    println!("  PID:       {}", info.pid);
+    println!(
+        "  Uptime:    {}",
+        format_uptime(health["uptime_seconds"].as_u64().unwrap_or_default())
+    );
+    println!("  Address:   {}", info.address);
+    println!(
+        "  Version:   {}",
+        health["version"].as_str().unwrap_or(&info.version)
+    );
+    let workspace = &health["workspace"];
+    match workspace["root"].as_str() {
+        Some(root) => {
+            println!(
+                "  Workspace: {} ({})",
+                root,
+                workspace["state"].as_str().unwrap_or("unknown")
+            );
+            println!(
+                "  Projects:  {}",
+                workspace["projects"].as_u64().unwrap_or_default()
+            );
+        }
+        None => println!("  Workspace: none"),
+    }
+}
+
+/// Uptime as hours and minutes, e.g. `2h 34m`
+fn format_uptime(seconds: u64) -> String {
+    match (seconds / 3600, seconds % 3600 / 60) {
+        (0, 0) => format!("{}s", seconds),
+        (0, minutes) => format!("{}m", minutes),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// The daemon recorded in `dir`, if it answers; a stale record is removed
+async fn running_daemon(dir: &std::path::Path) -> Option<DaemonInfo> {
+    let info = DaemonInfo::read(dir).ok().flatten()?;
+    if ping(info.address).await {
+        return Some(info);
+    }
+    // Only forget the record if it is still the one that did not answer
+    if matches!(DaemonInfo::read(dir), Ok(Some(ref current)) if *current == info) {
+        DaemonInfo::remove(dir).ok();
+    }
+    None
+}
+
+async fn ping(address: SocketAddr) -> bool {
+    server::request(address, methods::HEALTH, serde_json::Value::Null)
+        .await
+        .is_ok()
+}
+
+/// Extension registry for the workspace at `root`, with the builtin and
+/// installed extensions registered
+pub(super) async fn load_registry(
+    root: PathBuf,
+    ctx: &DaemonContext,
+) -> Result<ExtensionRegistry, String> {
+    let registry = ExtensionRegistry::new(root, ctx.output_dir.clone())
+        .map_err(|e| format!("Failed to create extension registry: {}", e))?
+        .with_daemon_config(&ctx.section)
+        .with_extensions_config(&ctx.extensions)
+        .with_sources(ctx.sources.clone());
+    for builtin in morphir_design::discover_builtin_extensions() {
+        let Some(path) = builtin.path else {
+            continue;
+        };
+        registry
+            .register_builtin(&builtin.id, path)
+            .await
+            .map_err(|e| format!("Failed to register builtin extension {}: {}", builtin.id, e))?;
+    }
+    let installed = morphir_home()
+        .and_then(|home| InstalledExtensions::load(&home))
+        .map_err(|e| format!("Failed to load installed extensions: {}", e))?;
+    registry
+        .register_installed(&installed)
+        .await
+        .map_err(|e| format!("Failed to register installed extensions: {}", e))?;
+    Ok(registry)
+}
+
+pub(super) fn fail(message: String) -> Option<u8> {
+    eprintln!("Error: {}", message);
+    Some(1)
+}
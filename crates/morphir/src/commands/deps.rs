@@ -0,0 +1,149 @@
+//! Dependency commands: vendor remote dependencies into the workspace
+//!
+//! `morphir deps vendor` copies every remote dependency declared by the
+//! workspace and its projects, and with `--extensions` every extension
+//! downloaded from a URL, into the `[vendor] directory`. The lockfile is
+//! rewritten to the copies, which are then loaded instead of the network.
+
+use crate::error::CliError;
+use crate::output::json_requested;
+use morphir_common::config::{DependencySpec, ExtensionSpec};
+use morphir_common::lockfile::LOCKFILE_NAME;
+use morphir_common::remote::{RemoteSourceResolver, ResolveOptions};
+use morphir_common::vendor::{VendoredKind, dependency_source, vendor};
+use morphir_daemon::workspace::Workspace;
+use morphir_design::discover_config;
+use starbase::AppResult;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Run `morphir deps vendor`
+pub fn run_deps_vendor(config_path: Option<String>, extensions: bool, json: bool) -> AppResult {
+    let json = json_requested(json);
+    let config_file = match config_path {
+        Some(cfg) => PathBuf::from(cfg),
+        None => {
+            let start_dir =
+                std::env::current_dir().map_err(|e| CliError::FileSystem { error: e })?;
+            discover_config(&start_dir).ok_or_else(|| CliError::Config {
+                error: anyhow::anyhow!("No morphir.toml or morphir.json found"),
+            })?
+        }
+    };
+    let root = config_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let workspace = Workspace::open(root.clone()).map_err(|e| CliError::Config {
+        error: anyhow::anyhow!("Failed to open workspace at {}: {}", root.display(), e),
+    })?;
+    let (dependencies, declared_extensions) = match declarations(&workspace) {
+        Ok(declared) => declared,
+        Err(e) => return Ok(Some(output_error(json, &e.to_string()))),
+    };
+
+    let mut section = workspace.config.vendor.clone().unwrap_or_default();
+    section.extensions |= extensions;
+    let mut resolver =
+        match RemoteSourceResolver::new(workspace.config.sources.clone().unwrap_or_default()) {
+            Ok(resolver) => resolver,
+            Err(e) => {
+                let message = format!("Failed to initialize source resolver: {}", e);
+                return Ok(Some(output_error(json, &message)));
+            }
+        };
+    let report = match vendor(
+        &root,
+        &section,
+        &dependencies,
+        &declared_extensions,
+        &mut resolver,
+        &ResolveOptions::new(),
+    ) {
+        Ok(report) => report,
+        Err(e) => return Ok(Some(output_error(json, &format!("{:#}", e)))),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return Ok(None);
+    }
+    for vendored in &report.vendored {
+        println!(
+            "Vendored {} {} from {} into {}",
+            kind_label(vendored.kind),
+            vendored.name,
+            vendored.source,
+            vendored.path.display()
+        );
+    }
+    for skipped in &report.skipped {
+        println!(
+            "Skipped {} {}: {}",
+            kind_label(skipped.kind),
+            skipped.name,
+            skipped.reason
+        );
+    }
+    for removed in &report.removed {
+        println!("Removed {}", removed.display());
+    }
+    println!("Wrote {}", LOCKFILE_NAME);
+    Ok(None)
+}
+
+/// Report a failure on stderr, or as a JSON error on stdout, and return the
+/// exit code
+pub(crate) fn output_error(json: bool, message: &str) -> u8 {
+    if json {
+        let error = serde_json::json!({ "success": false, "error": message });
+        println!("{}", serde_json::to_string_pretty(&error).unwrap());
+    } else {
+        eprintln!("Error: {}", message);
+    }
+    1
+}
+
+fn kind_label(kind: VendoredKind) -> &'static str {
+    match kind {
+        VendoredKind::Dependency => "dependency",
+        VendoredKind::Extension => "extension",
+    }
+}
+
+pub(crate) type Declarations = (
+    BTreeMap<String, DependencySpec>,
+    BTreeMap<String, ExtensionSpec>,
+);
+
+/// Dependencies and extensions declared by the workspace and its projects.
+/// A dependency declared twice must name the same source both times.
+pub(crate) fn declarations(workspace: &Workspace) -> anyhow::Result<Declarations> {
+    let mut projects: Vec<_> = workspace.projects.values().collect();
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+    let configs = std::iter::once(&workspace.config).chain(projects.into_iter().map(|p| &p.config));
+
+    let mut dependencies: BTreeMap<String, DependencySpec> = BTreeMap::new();
+    let mut extensions = BTreeMap::new();
+    for config in configs {
+        for (name, spec) in &config.dependencies {
+            match dependencies.get(name) {
+                Some(existing)
+                    if dependency_source(existing).ok() != dependency_source(spec).ok() =>
+                {
+                    anyhow::bail!("Dependency {} is declared with different sources", name);
+                }
+                Some(_) => {}
+                None => {
+                    dependencies.insert(name.clone(), spec.clone());
+                }
+            }
+        }
+        for (name, spec) in &config.extensions.specs {
+            extensions
+                .entry(name.clone())
+                .or_insert_with(|| spec.clone());
+        }
+    }
+    Ok((dependencies, extensions))
+}
@@ -3,6 +3,11 @@
 //! This module provides functionality for installing, updating, listing, and
 //! uninstalling Morphir distributions.
 
+use crate::error::CliError;
+use crate::output::{
+    InstalledListOutput, InstalledOutput, OutputFormat, RegistryChangeOutput, print_table,
+    write_output,
+};
 use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 use starbase::AppResult;
@@ -97,19 +102,27 @@ impl DistRegistry {
 
 /// Run the dist install command
 pub fn run_dist_install(name: String, version: Option<String>) -> AppResult {
-    println!("Installing Morphir distribution: {}", name);
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("distribution", "install", &name);
+    if !json {
+        println!("Installing Morphir distribution: {}", name);
+    }
 
     let mut registry = match DistRegistry::load() {
         Ok(reg) => reg,
         Err(e) => {
-            eprintln!("Error: Failed to load distribution registry: {}", e);
-            return Ok(Some(1));
+            return Ok(output.fail(json, format!("Failed to load distribution registry: {}", e)));
         }
     };
 
     // Check if distribution is already installed
     if let Some(existing_dist) = registry.get_distribution(&name) {
         let version_str = existing_dist.version.as_deref().unwrap_or(DEFAULT_VERSION);
+        if json {
+            output.version = Some(version_str.to_string());
+            write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+            return Ok(None);
+        }
         println!(
             "Distribution '{}' is already installed (version: {})",
             name, version_str
@@ -131,10 +144,15 @@ pub fn run_dist_install(name: String, version: Option<String>) -> AppResult {
     // Add distribution to registry
     registry.add_distribution(dist);
     if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save distribution registry: {}", e);
-        return Ok(Some(1));
+        return Ok(output.fail(json, format!("Failed to save distribution registry: {}", e)));
     }
 
+    if json {
+        output.version = Some(display_version.to_string());
+        output.changed = true;
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully installed distribution '{}' (version: {})",
         name, display_version
@@ -146,30 +164,87 @@ pub fn run_dist_install(name: String, version: Option<String>) -> AppResult {
 
 /// Run the dist list command
 pub fn run_dist_list() -> AppResult {
-    println!("Listing installed Morphir distributions...\n");
+    let format = OutputFormat::global();
+    if format == OutputFormat::Human {
+        println!("Listing installed Morphir distributions...\n");
+    }
 
     let registry = match DistRegistry::load() {
         Ok(reg) => reg,
         Err(e) => {
-            eprintln!("Error: Failed to load distribution registry: {}", e);
+            let error = format!("Failed to load distribution registry: {}", e);
+            if format.is_json() {
+                let output = InstalledListOutput {
+                    success: false,
+                    kind: "distribution".to_string(),
+                    items: Vec::new(),
+                    error: Some(error),
+                };
+                write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+            } else {
+                eprintln!("Error: {}", error);
+            }
             return Ok(Some(1));
         }
     };
 
-    let distributions = registry.list_distributions();
-
-    if distributions.is_empty() {
-        println!("No distributions installed.");
-        println!("Use 'morphir dist install <name>' to install a distribution");
-    } else {
-        println!("{:<20} {:<15} Description", "Distribution", "Version");
-        println!("{}", "-".repeat(70));
-        for dist in &distributions {
-            let description = dist.description.as_deref().unwrap_or("No description");
-            let version_str = dist.version.as_deref().unwrap_or(DEFAULT_VERSION);
-            println!("{:<20} {:<15} {}", dist.name, version_str, description);
+    let mut distributions = registry.list_distributions();
+    distributions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    match format {
+        OutputFormat::Json | OutputFormat::JsonLines => {
+            let output = InstalledListOutput {
+                success: true,
+                kind: "distribution".to_string(),
+                items: distributions
+                    .iter()
+                    .map(|dist| InstalledOutput {
+                        name: dist.name.clone(),
+                        version: dist
+                            .version
+                            .as_deref()
+                            .unwrap_or(DEFAULT_VERSION)
+                            .to_string(),
+                        description: dist.description.clone(),
+                        builtin: false,
+                        languages: Vec::new(),
+                        targets: Vec::new(),
+                    })
+                    .collect(),
+                error: None,
+            };
+            write_output(format, &output).map_err(CliError::from)?;
+        }
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = distributions
+                .iter()
+                .map(|dist| {
+                    vec![
+                        dist.name.clone(),
+                        dist.version
+                            .as_deref()
+                            .unwrap_or(DEFAULT_VERSION)
+                            .to_string(),
+                        dist.description.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            print_table(&["NAME", "VERSION", "DESCRIPTION"], &rows);
+        }
+        OutputFormat::Human if distributions.is_empty() => {
+            println!("No distributions installed.");
+            println!("Use 'morphir dist install <name>' to install a distribution");
+        }
+        OutputFormat::Human => {
+            println!("{:<20} {:<15} Description", "Distribution", "Version");
+            println!("{}", "-".repeat(70));
+            for dist in &distributions {
+                let description = dist.description.as_deref().unwrap_or("No description");
+                let version_str = dist.version.as_deref().unwrap_or(DEFAULT_VERSION);
+                println!("{:<20} {:<15} {}", dist.name, version_str, description);
+            }
+            println!("\nTotal: {} distribution(s) installed", distributions.len());
         }
-        println!("\nTotal: {} distribution(s) installed", distributions.len());
     }
 
     Ok(None)
@@ -177,13 +252,16 @@ pub fn run_dist_list() -> AppResult {
 
 /// Run the dist update command
 pub fn run_dist_update(name: String, version: Option<String>) -> AppResult {
-    println!("Updating Morphir distribution: {}", name);
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("distribution", "update", &name);
+    if !json {
+        println!("Updating Morphir distribution: {}", name);
+    }
 
     let mut registry = match DistRegistry::load() {
         Ok(reg) => reg,
         Err(e) => {
-            eprintln!("Error: Failed to load distribution registry: {}", e);
-            return Ok(Some(1));
+            return Ok(output.fail(json, format!("Failed to load distribution registry: {}", e)));
         }
     };
 
@@ -191,11 +269,13 @@ pub fn run_dist_update(name: String, version: Option<String>) -> AppResult {
     let existing_dist = match registry.get_distribution(&name) {
         Some(dist) => dist.clone(),
         None => {
-            eprintln!(
-                "Error: Distribution '{}' is not installed. Use 'morphir dist install' first",
-                name
-            );
-            return Ok(Some(1));
+            return Ok(output.fail(
+                json,
+                format!(
+                    "Distribution '{}' is not installed. Use 'morphir dist install' first",
+                    name
+                ),
+            ));
         }
     };
 
@@ -206,12 +286,18 @@ pub fn run_dist_update(name: String, version: Option<String>) -> AppResult {
         .to_string();
     let new_version = version.or_else(|| Some(DEFAULT_VERSION.to_string()));
     let new_version_str = new_version.as_deref().unwrap_or(DEFAULT_VERSION);
+    output.version = Some(new_version_str.to_string());
+    output.previous_version = Some(old_version.clone());
 
     if old_version == new_version_str {
-        println!(
-            "Distribution '{}' is already at version {}",
-            name, new_version_str
-        );
+        if json {
+            write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        } else {
+            println!(
+                "Distribution '{}' is already at version {}",
+                name, new_version_str
+            );
+        }
         return Ok(None);
     }
 
@@ -225,10 +311,14 @@ pub fn run_dist_update(name: String, version: Option<String>) -> AppResult {
 
     registry.add_distribution(updated_dist);
     if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save distribution registry: {}", e);
-        return Ok(Some(1));
+        return Ok(output.fail(json, format!("Failed to save distribution registry: {}", e)));
     }
 
+    if json {
+        output.changed = true;
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully updated distribution '{}' from {} to {}",
         name, old_version, new_version_str
@@ -239,13 +329,16 @@ pub fn run_dist_update(name: String, version: Option<String>) -> AppResult {
 
 /// Run the dist uninstall command
 pub fn run_dist_uninstall(name: String) -> AppResult {
-    println!("Uninstalling Morphir distribution: {}", name);
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("distribution", "uninstall", &name);
+    if !json {
+        println!("Uninstalling Morphir distribution: {}", name);
+    }
 
     let mut registry = match DistRegistry::load() {
         Ok(reg) => reg,
         Err(e) => {
-            eprintln!("Error: Failed to load distribution registry: {}", e);
-            return Ok(Some(1));
+            return Ok(output.fail(json, format!("Failed to load distribution registry: {}", e)));
         }
     };
 
@@ -253,17 +346,21 @@ pub fn run_dist_uninstall(name: String) -> AppResult {
     let removed_dist = match registry.remove_distribution(&name) {
         Some(dist) => dist,
         None => {
-            eprintln!("Error: Distribution '{}' is not installed", name);
-            return Ok(Some(1));
+            return Ok(output.fail(json, format!("Distribution '{}' is not installed", name)));
         }
     };
 
     if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save distribution registry: {}", e);
-        return Ok(Some(1));
+        return Ok(output.fail(json, format!("Failed to save distribution registry: {}", e)));
     }
 
     let version_str = removed_dist.version.as_deref().unwrap_or(DEFAULT_VERSION);
+    if json {
+        output.version = Some(version_str.to_string());
+        output.changed = true;
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully uninstalled distribution '{}' (version: {})",
         removed_dist.name, version_str
@@ -0,0 +1,239 @@
+//! Docs Command
+//!
+//! Renders a documentation site for the project's model with the `docs`
+//! backend: module pages with pretty-printed type signatures, doc comments,
+//! and cross-linked type references. `--serve` then serves the site on
+//! localhost for a local preview.
+
+use crate::error::CliError;
+use crate::pipeline::Pipeline;
+use morphir_common::loader::load_ir;
+use morphir_design::resolve_compile_output;
+use starbase::AppResult;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+
+/// Backend rendering the site
+const DOCS_TARGET: &str = "docs";
+
+/// Format of the generated pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DocsFormatArg {
+    Html,
+    Markdown,
+}
+
+/// Options for the docs command
+#[derive(Debug, Default)]
+pub struct DocsCommandOptions {
+    /// IR file or directory; the project's compiled IR if omitted
+    pub input: Option<String>,
+    /// Output directory; `.morphir/out/<project>/generate/docs` if omitted
+    pub output: Option<String>,
+    /// Explicit config file path
+    pub config: Option<String>,
+    /// Page format, over `format` in `[codegen.docs]`
+    pub format: Option<DocsFormatArg>,
+    /// Document private modules and definitions too
+    pub private: bool,
+    /// Serve the site after generating it
+    pub serve: bool,
+    /// Port to serve on
+    pub port: u16,
+}
+
+/// Run the docs command
+pub async fn run_docs(options: DocsCommandOptions) -> AppResult {
+    let mut pipeline = Pipeline::new().with_target(DOCS_TARGET);
+    if let Some(path) = options.config {
+        pipeline = pipeline.with_config(path);
+    }
+    let ctx = pipeline.load_config()?;
+
+    let input = match options.input {
+        Some(input) => PathBuf::from(input),
+        None => {
+            let language = ctx
+                .config
+                .frontend
+                .as_ref()
+                .and_then(|f| f.language.clone())
+                .ok_or_else(|| CliError::Config {
+                    error: anyhow::anyhow!(
+                        "No IR to document: pass --input, or set the frontend language in morphir.toml"
+                    ),
+                })?;
+            resolve_compile_output(&pipeline.package_name(&ctx), &language, &ctx.morphir_dir)
+        }
+    };
+    if !input.exists() {
+        return Err(CliError::FileSystem {
+            error: std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "IR input path does not exist: {:?}; run `morphir compile` first",
+                    input
+                ),
+            ),
+        }
+        .into());
+    }
+    if let Some(output) = options.output {
+        pipeline = pipeline.with_generate_output(output);
+    }
+
+    // Flags go over [codegen.docs]
+    let mut overrides = serde_json::Map::new();
+    if let Some(format) = options.format {
+        let format = match format {
+            DocsFormatArg::Html => "html",
+            DocsFormatArg::Markdown => "markdown",
+        };
+        overrides.insert("format".to_string(), format.into());
+    }
+    if options.private {
+        overrides.insert("private".to_string(), true.into());
+    }
+    pipeline = pipeline.with_generate_options(overrides);
+
+    let ir = load_ir(&input).map_err(|e| CliError::FileSystem {
+        error: std::io::Error::other(e),
+    })?;
+    let generated = pipeline.generate(&ctx, &input, ir).await?;
+    if !generated.success {
+        return Err(CliError::Compilation {
+            message: generated
+                .error
+                .unwrap_or_else(|| "Documentation generation failed".to_string()),
+        }
+        .into());
+    }
+    println!(
+        "Generated {} page(s) in {}",
+        generated.written.artifacts.len(),
+        generated.output_path.display()
+    );
+
+    if options.serve {
+        let listener =
+            TcpListener::bind(("127.0.0.1", options.port)).map_err(|e| CliError::FileSystem {
+                error: std::io::Error::new(
+                    e.kind(),
+                    format!("Failed to listen on port {}: {}", options.port, e),
+                ),
+            })?;
+        if let Ok(address) = listener.local_addr() {
+            println!(
+                "Serving documentation at http://{}/ (Ctrl-C to stop)",
+                address
+            );
+        }
+        let root = generated.output_path;
+        tokio::task::spawn_blocking(move || serve_directory(listener, &root))
+            .await
+            .map_err(|e| CliError::FileSystem {
+                error: std::io::Error::other(e),
+            })?
+            .map_err(|error| CliError::FileSystem { error })?;
+    }
+    Ok(None)
+}
+
+/// Serve the files under `root` until the process is stopped, one request
+/// per connection
+fn serve_directory(listener: TcpListener, root: &Path) -> std::io::Result<()> {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if let Err(e) = respond(stream, root) {
+            tracing::debug!("Failed to answer a docs request: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn respond(mut stream: TcpStream, root: &Path) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or("/"));
+
+    let (status, content_type, body) = if method != "GET" && method != "HEAD" {
+        (
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Read-only".to_vec(),
+        )
+    } else {
+        match request_path(root, target)
+            .and_then(|path| std::fs::read(&path).ok().map(|body| (path, body)))
+        {
+            Some((path, body)) => ("200 OK", content_type(&path), body),
+            None => ("404 Not Found", "text/plain", b"Not found".to_vec()),
+        }
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&body)?;
+    }
+    stream.flush()
+}
+
+/// File under `root` for a request target; directories serve their
+/// `index.html`. Targets leaving `root` resolve to nothing.
+fn request_path(root: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    let mut resolved = root.to_path_buf();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        if segment == "." || segment == ".." || segment.contains('\\') {
+            return None;
+        }
+        resolved.push(segment);
+    }
+    if resolved.is_dir() {
+        resolved.push("index.html");
+    }
+    resolved.is_file().then_some(resolved)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("md") => "text/markdown; charset=utf-8",
+        Some("json") => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_path_stays_in_root() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp.path().join("modules")).unwrap();
+        std::fs::write(temp.path().join("index.html"), "index").unwrap();
+        std::fs::write(temp.path().join("modules/orders.html"), "orders").unwrap();
+
+        assert_eq!(
+            request_path(temp.path(), "/"),
+            Some(temp.path().join("index.html"))
+        );
+        assert_eq!(
+            request_path(temp.path(), "/modules/orders.html#type-line"),
+            Some(temp.path().join("modules/orders.html"))
+        );
+        assert_eq!(request_path(temp.path(), "/modules/missing.html"), None);
+        assert_eq!(request_path(temp.path(), "/modules/../../etc/passwd"), None);
+    }
+}
@@ -1,144 +1,126 @@
 //! Extension command for managing Morphir extensions
 //!
 //! This module provides functionality for installing, updating, listing, and
-//! uninstalling Morphir extensions.
-
-use anyhow::{Context, Result, anyhow};
-use serde::{Deserialize, Serialize};
+//! uninstalling Morphir extensions. Extensions are downloaded from the package
+//! registry, a GitHub release, a URL, or a local path, checked, and stored
+//! under the Morphir home directory, where the daemon finds them.
+
+use crate::error::CliError;
+use crate::output::{
+    InstalledListOutput, InstalledOutput, OutputFormat, RegistryChangeOutput, print_table,
+    write_output,
+};
+use morphir_common::config::MorphirConfig;
+use morphir_daemon::extensions::compat::HOST_SDK_VERSION;
+use morphir_daemon::extensions::install::{
+    Diagnosis, InstallSource, InstalledExtension, InstalledExtensions, Installer, diagnose,
+    morphir_home,
+};
+use morphir_design::discover_config;
+use serde::Serialize;
 use starbase::AppResult;
-use std::collections::HashMap;
-use std::fs;
 use std::path::PathBuf;
 
-/// Default version to use when no version is specified
+/// Version shown for extensions recorded without one
 const DEFAULT_VERSION: &str = "latest";
 
-/// Extension registry configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ExtensionRegistry {
-    /// Installed extensions with their versions
-    extensions: HashMap<String, ExtensionInfo>,
+/// The Morphir home directory and the extensions installed in it
+fn load_installed() -> Result<(PathBuf, InstalledExtensions), String> {
+    let home = morphir_home().map_err(|e| e.to_string())?;
+    let installed = InstalledExtensions::load(&home)
+        .map_err(|e| format!("Failed to load installed extensions: {}", e))?;
+    Ok((home, installed))
 }
 
-/// Information about an installed extension
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ExtensionInfo {
-    /// Extension name
-    name: String,
-    /// Extension version
-    version: Option<String>,
-    /// Extension description
-    description: Option<String>,
-    /// Installation path
-    install_path: Option<String>,
+/// Installer storing extensions in `home`, looking names up in the
+/// `[registry]` of the workspace in the current directory
+fn installer(home: PathBuf) -> Result<Installer, String> {
+    let config = match std::env::current_dir()
+        .ok()
+        .and_then(|dir| discover_config(&dir))
+    {
+        Some(path) => Some(
+            MorphirConfig::load(&path)
+                .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+    let registry = config.and_then(|c| c.registry).unwrap_or_default();
+    Installer::new(home, registry).map_err(|e| e.to_string())
 }
 
-impl ExtensionRegistry {
-    /// Create a new empty extension registry
-    fn new() -> Self {
-        Self {
-            extensions: HashMap::new(),
-        }
-    }
-
-    /// Load extension registry from configuration file
-    fn load() -> Result<Self> {
-        let config_path = Self::config_path()?;
-        if config_path.exists() {
-            let content = fs::read_to_string(&config_path)
-                .context("Failed to read extension registry configuration")?;
-            let registry: ExtensionRegistry = serde_json::from_str(&content)
-                .context("Failed to parse extension registry configuration")?;
-            Ok(registry)
-        } else {
-            Ok(Self::new())
-        }
-    }
-
-    /// Save extension registry to configuration file
-    fn save(&self) -> Result<()> {
-        let config_path = Self::config_path()?;
-        if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent).context("Failed to create config directory")?;
-        }
-        let content =
-            serde_json::to_string_pretty(self).context("Failed to serialize extension registry")?;
-        fs::write(&config_path, content)
-            .context("Failed to write extension registry configuration")?;
-        Ok(())
-    }
-
-    /// Get the path to the extension registry configuration file
-    fn config_path() -> Result<PathBuf> {
-        let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
-        Ok(home.join(".morphir").join("extensions.json"))
-    }
-
-    /// Add or update an extension in the registry
-    fn add_extension(&mut self, ext: ExtensionInfo) {
-        self.extensions.insert(ext.name.clone(), ext);
-    }
-
-    /// Remove an extension from the registry
-    fn remove_extension(&mut self, name: &str) -> Option<ExtensionInfo> {
-        self.extensions.remove(name)
-    }
-
-    /// Get an extension from the registry
-    fn get_extension(&self, name: &str) -> Option<&ExtensionInfo> {
-        self.extensions.get(name)
-    }
-
-    /// List all extensions in the registry
-    fn list_extensions(&self) -> Vec<&ExtensionInfo> {
-        self.extensions.values().collect()
+/// Run the extension install command. `spec` is the name of an extension in
+/// the package registry, or the path, URL, or `github:` source of its module.
+pub async fn run_extension_install(
+    spec: String,
+    version: Option<String>,
+    sha256: Option<String>,
+) -> AppResult {
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("extension", "install", &spec);
+    if !json {
+        println!("Installing Morphir extension: {}", spec);
     }
-}
-
-/// Run the extension install command
-pub fn run_extension_install(name: String, version: Option<String>) -> AppResult {
-    println!("Installing Morphir extension: {}", name);
 
-    let mut registry = match ExtensionRegistry::load() {
-        Ok(reg) => reg,
-        Err(e) => {
-            eprintln!("Error: Failed to load extension registry: {}", e);
-            return Ok(Some(1));
-        }
+    let (home, mut installed) = match load_installed() {
+        Ok(loaded) => loaded,
+        Err(e) => return Ok(output.fail(json, e)),
     };
 
     // Check if extension is already installed
-    if let Some(existing_ext) = registry.get_extension(&name) {
+    if let Some(existing_ext) = installed.get(&spec) {
         let version_str = existing_ext.version.as_deref().unwrap_or(DEFAULT_VERSION);
+        if json {
+            output.version = Some(version_str.to_string());
+            write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+            return Ok(None);
+        }
         println!(
             "Extension '{}' is already installed (version: {})",
-            name, version_str
+            spec, version_str
         );
         println!("Use 'morphir extension update' to update to a newer version");
         return Ok(None);
     }
 
-    // Create extension info
-    let ext_version = version.or_else(|| Some(DEFAULT_VERSION.to_string()));
-    let display_version = ext_version.as_deref().unwrap_or(DEFAULT_VERSION);
-    let ext = ExtensionInfo {
-        name: name.clone(),
-        version: ext_version.clone(),
-        description: Some(format!("Morphir extension: {}", name)),
-        install_path: None,
+    let (source, pinned) = match InstallSource::parse(&spec) {
+        Ok(parsed) => parsed,
+        Err(e) => return Ok(output.fail(json, e.to_string())),
+    };
+    let installer = match installer(home.clone()) {
+        Ok(installer) => installer,
+        Err(e) => return Ok(output.fail(json, e)),
+    };
+    let sha256 = sha256.or(pinned);
+    let ext = match installer
+        .install(&source, version.as_deref(), sha256.as_deref())
+        .await
+    {
+        Ok(ext) => ext,
+        Err(e) => return Ok(output.fail(json, e.to_string())),
     };
 
-    // Add extension to registry
-    registry.add_extension(ext);
-    if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save extension registry: {}", e);
-        return Ok(Some(1));
+    let display_version = ext.version.clone().unwrap_or_default();
+    output.name = ext.name.clone();
+    output.version = Some(display_version.clone());
+    output.changed = true;
+    if let Some(previous) = installed.insert(ext.clone()) {
+        output.previous_version = previous.version.clone();
+        remove_replaced(&installer, &previous, &ext);
+    }
+    if let Err(e) = installed.save(&home) {
+        return Ok(output.fail(json, format!("Failed to save installed extensions: {}", e)));
     }
 
+    if json {
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully installed extension '{}' (version: {})",
-        name, display_version
+        ext.name, display_version
     );
+    print_installed(&ext);
     println!("  Run 'morphir extension list' to see all installed extensions");
 
     Ok(None)
@@ -146,21 +128,84 @@ pub fn run_extension_install(name: String, version: Option<String>) -> AppResult
 
 /// Run the extension list command
 pub fn run_extension_list() -> AppResult {
-    println!("Listing Morphir extensions...\n");
+    let format = OutputFormat::global();
+    if format == OutputFormat::Human {
+        println!("Listing Morphir extensions...\n");
+    }
 
     // Discover builtin extensions
     let builtins = morphir_design::discover_builtin_extensions();
 
-    // Load registry extensions
-    let registry = match ExtensionRegistry::load() {
-        Ok(reg) => reg,
-        Err(e) => {
-            eprintln!("Error: Failed to load extension registry: {}", e);
+    // Load installed extensions
+    let installed = match load_installed() {
+        Ok((_, installed)) => installed,
+        Err(error) => {
+            if format.is_json() {
+                let output = InstalledListOutput {
+                    success: false,
+                    kind: "extension".to_string(),
+                    items: Vec::new(),
+                    error: Some(error),
+                };
+                write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+            } else {
+                eprintln!("Error: {}", error);
+            }
             return Ok(Some(1));
         }
     };
 
-    let registry_extensions = registry.list_extensions();
+    let registry_extensions: Vec<&InstalledExtension> = installed.iter().collect();
+
+    if format != OutputFormat::Human {
+        let items: Vec<InstalledOutput> = builtins
+            .iter()
+            .map(|builtin| InstalledOutput {
+                name: builtin.id.clone(),
+                version: "builtin".to_string(),
+                description: Some(builtin.name.clone()),
+                builtin: true,
+                languages: builtin.languages.clone(),
+                targets: builtin.targets.clone(),
+            })
+            .chain(registry_extensions.iter().map(|ext| {
+                InstalledOutput {
+                    name: ext.name.clone(),
+                    version: ext
+                        .version
+                        .as_deref()
+                        .unwrap_or(DEFAULT_VERSION)
+                        .to_string(),
+                    description: ext.description.clone(),
+                    builtin: false,
+                    languages: Vec::new(),
+                    targets: Vec::new(),
+                }
+            }))
+            .collect();
+        if format == OutputFormat::Table {
+            let rows: Vec<Vec<String>> = items
+                .iter()
+                .map(|item| {
+                    vec![
+                        item.name.clone(),
+                        item.version.clone(),
+                        item.description.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            print_table(&["NAME", "VERSION", "DESCRIPTION"], &rows);
+        } else {
+            let output = InstalledListOutput {
+                success: true,
+                kind: "extension".to_string(),
+                items,
+                error: None,
+            };
+            write_output(format, &output).map_err(CliError::from)?;
+        }
+        return Ok(None);
+    }
 
     // Display builtin extensions
     if !builtins.is_empty() {
@@ -219,95 +264,151 @@ pub fn run_extension_list() -> AppResult {
     Ok(None)
 }
 
-/// Run the extension update command
-pub fn run_extension_update(name: String, version: Option<String>) -> AppResult {
-    println!("Updating Morphir extension: {}", name);
+/// Run the extension update command: install the extension again from the
+/// source it was installed from, at `version` or the latest one
+pub async fn run_extension_update(name: String, version: Option<String>) -> AppResult {
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("extension", "update", &name);
+    if !json {
+        println!("Updating Morphir extension: {}", name);
+    }
 
-    let mut registry = match ExtensionRegistry::load() {
-        Ok(reg) => reg,
-        Err(e) => {
-            eprintln!("Error: Failed to load extension registry: {}", e);
-            return Ok(Some(1));
-        }
+    let (home, mut installed) = match load_installed() {
+        Ok(loaded) => loaded,
+        Err(e) => return Ok(output.fail(json, e)),
     };
 
     // Check if extension exists
-    let existing_ext = match registry.get_extension(&name) {
+    let existing_ext = match installed.get(&name) {
         Some(ext) => ext.clone(),
         None => {
-            eprintln!(
-                "Error: Extension '{}' is not installed. Use 'morphir extension install' first",
-                name
-            );
-            return Ok(Some(1));
+            return Ok(output.fail(
+                json,
+                format!(
+                    "Extension '{}' is not installed. Use 'morphir extension install' first",
+                    name
+                ),
+            ));
         }
     };
+    let source = match existing_ext.source.clone() {
+        // The release is the requested one, or the latest
+        Some(InstallSource::GitHub { repo, asset, .. }) => InstallSource::GitHub {
+            repo,
+            tag: None,
+            asset,
+        },
+        Some(source) => source,
+        None => {
+            return Ok(output.fail(
+                json,
+                format!(
+                    "Extension '{}' was recorded without a source; install it again with \
+                     'morphir extension install <source>'",
+                    name
+                ),
+            ));
+        }
+    };
+
+    let installer = match installer(home.clone()) {
+        Ok(installer) => installer,
+        Err(e) => return Ok(output.fail(json, e)),
+    };
+    let updated_ext = match installer.install(&source, version.as_deref(), None).await {
+        Ok(ext) => ext,
+        Err(e) => return Ok(output.fail(json, e.to_string())),
+    };
+    if updated_ext.name != name {
+        return Ok(output.fail(
+            json,
+            format!(
+                "{} now provides extension '{}' rather than '{}'",
+                source, updated_ext.name, name
+            ),
+        ));
+    }
 
     let old_version = existing_ext
         .version
         .as_deref()
         .unwrap_or(DEFAULT_VERSION)
         .to_string();
-    let new_version = version.or_else(|| Some(DEFAULT_VERSION.to_string()));
-    let new_version_str = new_version.as_deref().unwrap_or(DEFAULT_VERSION);
+    let new_version_str = updated_ext.version.clone().unwrap_or_default();
+    output.version = Some(new_version_str.clone());
+    output.previous_version = Some(old_version.clone());
 
-    if old_version == new_version_str {
-        println!(
-            "Extension '{}' is already at version {}",
-            name, new_version_str
-        );
+    if existing_ext.sha256 == updated_ext.sha256 && old_version == new_version_str {
+        if json {
+            write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        } else {
+            println!(
+                "Extension '{}' is already at version {}",
+                name, new_version_str
+            );
+        }
         return Ok(None);
     }
 
-    // Update extension
-    let updated_ext = ExtensionInfo {
-        name: name.clone(),
-        version: new_version.clone(),
-        description: existing_ext.description.clone(),
-        install_path: existing_ext.install_path.clone(),
-    };
-
-    registry.add_extension(updated_ext);
-    if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save extension registry: {}", e);
-        return Ok(Some(1));
+    remove_replaced(&installer, &existing_ext, &updated_ext);
+    installed.insert(updated_ext.clone());
+    if let Err(e) = installed.save(&home) {
+        return Ok(output.fail(json, format!("Failed to save installed extensions: {}", e)));
     }
 
+    if json {
+        output.changed = true;
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully updated extension '{}' from {} to {}",
         name, old_version, new_version_str
     );
+    print_installed(&updated_ext);
 
     Ok(None)
 }
 
 /// Run the extension uninstall command
 pub fn run_extension_uninstall(name: String) -> AppResult {
-    println!("Uninstalling Morphir extension: {}", name);
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("extension", "uninstall", &name);
+    if !json {
+        println!("Uninstalling Morphir extension: {}", name);
+    }
 
-    let mut registry = match ExtensionRegistry::load() {
-        Ok(reg) => reg,
-        Err(e) => {
-            eprintln!("Error: Failed to load extension registry: {}", e);
-            return Ok(Some(1));
-        }
+    let (home, mut installed) = match load_installed() {
+        Ok(loaded) => loaded,
+        Err(e) => return Ok(output.fail(json, e)),
     };
 
     // Remove extension from registry
-    let removed_ext = match registry.remove_extension(&name) {
+    let removed_ext = match installed.remove(&name) {
         Some(ext) => ext,
         None => {
-            eprintln!("Error: Extension '{}' is not installed", name);
-            return Ok(Some(1));
+            return Ok(output.fail(json, format!("Extension '{}' is not installed", name)));
         }
     };
 
-    if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save extension registry: {}", e);
-        return Ok(Some(1));
+    let installer = match Installer::new(home.clone(), Default::default()) {
+        Ok(installer) => installer,
+        Err(e) => return Ok(output.fail(json, e.to_string())),
+    };
+    if let Err(e) = installer.uninstall(&removed_ext) {
+        return Ok(output.fail(json, format!("Failed to remove extension files: {}", e)));
+    }
+    if let Err(e) = installed.save(&home) {
+        return Ok(output.fail(json, format!("Failed to save installed extensions: {}", e)));
     }
 
     let version_str = removed_ext.version.as_deref().unwrap_or(DEFAULT_VERSION);
+    if json {
+        output.version = Some(version_str.to_string());
+        output.changed = true;
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully uninstalled extension '{}' (version: {})",
         removed_ext.name, version_str
@@ -315,3 +416,132 @@ pub fn run_extension_uninstall(name: String) -> AppResult {
 
     Ok(None)
 }
+
+/// JSON output of `morphir extension doctor`
+#[derive(Serialize)]
+struct DoctorOutput {
+    success: bool,
+    host_sdk_version: &'static str,
+    extensions: Vec<Diagnosis>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Run the extension doctor command: check that every installed extension
+/// is present, unchanged, loads, and works with this host's SDK version.
+/// Exits with 1 if any does not.
+pub fn run_extension_doctor() -> AppResult {
+    let format = OutputFormat::global();
+    let installed = match load_installed() {
+        Ok((_, installed)) => installed,
+        Err(error) => {
+            if format.is_json() {
+                let output = DoctorOutput {
+                    success: false,
+                    host_sdk_version: HOST_SDK_VERSION,
+                    extensions: Vec::new(),
+                    error: Some(error),
+                };
+                write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+            } else {
+                eprintln!("Error: {}", error);
+            }
+            return Ok(Some(1));
+        }
+    };
+
+    let diagnoses: Vec<Diagnosis> = installed.iter().map(diagnose).collect();
+    let healthy = diagnoses.iter().all(Diagnosis::is_healthy);
+    let code = if healthy { None } else { Some(1) };
+
+    if format.is_json() {
+        let output = DoctorOutput {
+            success: healthy,
+            host_sdk_version: HOST_SDK_VERSION,
+            extensions: diagnoses,
+            error: None,
+        };
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(code);
+    }
+
+    println!("Host SDK version: {}\n", HOST_SDK_VERSION);
+    if diagnoses.is_empty() {
+        println!("No extensions installed.");
+        return Ok(None);
+    }
+    let rows: Vec<Vec<String>> = diagnoses
+        .iter()
+        .map(|d| {
+            vec![
+                d.name.clone(),
+                d.version
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_VERSION.to_string()),
+                sdk_range(d),
+                if d.is_healthy() { "ok" } else { "problem" }.to_string(),
+            ]
+        })
+        .collect();
+    print_table(&["EXTENSION", "VERSION", "SDK", "STATUS"], &rows);
+
+    for diagnosis in diagnoses.iter().filter(|d| !d.is_healthy()) {
+        println!("\n{}:", diagnosis.name);
+        for problem in &diagnosis.problems {
+            println!("  - {}", problem);
+        }
+    }
+    if healthy {
+        println!("\n✓ All {} extension(s) are compatible", diagnoses.len());
+    }
+    Ok(code)
+}
+
+/// SDK versions an extension declares, e.g. `>=0.1.0, built with 0.2.0`
+fn sdk_range(diagnosis: &Diagnosis) -> String {
+    let mut parts = Vec::new();
+    if let Some(min) = &diagnosis.min_sdk_version {
+        parts.push(format!(">={}", min));
+    }
+    if let Some(max) = &diagnosis.max_sdk_version {
+        parts.push(format!("<={}", max));
+    }
+    if let Some(built) = &diagnosis.sdk_version {
+        parts.push(format!("built with {}", built));
+    }
+    if parts.is_empty() {
+        "-".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Delete the module of `previous` if `current` was stored elsewhere
+fn remove_replaced(
+    installer: &Installer,
+    previous: &InstalledExtension,
+    current: &InstalledExtension,
+) {
+    if previous.install_path != current.install_path
+        && let Err(e) = installer.uninstall(previous)
+    {
+        tracing::warn!(
+            "Failed to remove the replaced module of {}: {}",
+            previous.name,
+            e
+        );
+    }
+}
+
+/// Print where an installed extension came from and where it is stored
+fn print_installed(ext: &InstalledExtension) {
+    if let Some(source) = &ext.source {
+        println!("  Source: {}", source);
+    }
+    if let Some(path) = &ext.install_path {
+        println!("  Module: {}", path.display());
+    }
+    if let Some(sha256) = &ext.sha256 {
+        println!("  sha256: {}", sha256);
+    }
+}
@@ -0,0 +1,597 @@
+//! Extension new command: scaffold an extension crate
+//!
+//! `morphir extension new` creates a Rust crate that builds to a WASM
+//! extension: a `Cargo.toml` depending on the extension SDK with a release
+//! profile tuned for small modules, a `lib.rs` implementing the chosen
+//! capability and exporting it, tests driving it through
+//! `morphir-extension-testkit`, and a README saying how to build it and
+//! register it in `morphir.toml`.
+
+use crate::commands::deps::output_error;
+use crate::output::json_requested;
+use serde::Serialize;
+use starbase::AppResult;
+use std::path::{Path, PathBuf};
+
+/// Capability the scaffolded extension implements
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionCapability {
+    /// Compiles sources to IR
+    #[default]
+    Frontend,
+    /// Generates code from IR
+    Backend,
+    /// Reports diagnostics on IR
+    Validator,
+    /// Rewrites IR
+    Transform,
+}
+
+impl ExtensionCapability {
+    /// Name of the SDK trait, as listed in `export_extension!`
+    fn trait_name(self) -> &'static str {
+        match self {
+            ExtensionCapability::Frontend => "Frontend",
+            ExtensionCapability::Backend => "Backend",
+            ExtensionCapability::Validator => "Validator",
+            ExtensionCapability::Transform => "Transform",
+        }
+    }
+
+    /// Name in prose
+    fn name(self) -> &'static str {
+        match self {
+            ExtensionCapability::Frontend => "frontend",
+            ExtensionCapability::Backend => "backend",
+            ExtensionCapability::Validator => "validator",
+            ExtensionCapability::Transform => "transform",
+        }
+    }
+}
+
+/// Options for `morphir extension new`
+#[derive(Debug, Default)]
+pub struct ExtensionNewOptions {
+    /// Directory to create
+    pub path: PathBuf,
+    /// Extension ID and crate name (defaults to the directory name)
+    pub name: Option<String>,
+    /// Capability to implement
+    pub capability: ExtensionCapability,
+    /// Directory with the SDK and testkit crates, to depend on by path
+    /// rather than by version
+    pub sdk_path: Option<PathBuf>,
+    /// Output JSON format
+    pub json: bool,
+}
+
+/// What was scaffolded
+#[derive(Debug, Serialize)]
+pub struct ScaffoldedExtension {
+    /// Crate directory
+    pub root: PathBuf,
+    /// Extension ID and crate name
+    pub id: String,
+    pub capability: ExtensionCapability,
+    /// WASM module `cargo build --release --target wasm32-unknown-unknown`
+    /// produces, relative to `root`
+    pub wasm: PathBuf,
+    /// Files written, relative to `root`
+    pub created: Vec<PathBuf>,
+}
+
+/// Run `morphir extension new`
+pub fn run_extension_new(options: ExtensionNewOptions) -> AppResult {
+    let json = json_requested(options.json);
+    match scaffold_extension(&options) {
+        Ok(scaffolded) => {
+            print_scaffolded(&scaffolded, &options.path, json);
+            Ok(None)
+        }
+        Err(message) => Ok(Some(output_error(json, &message))),
+    }
+}
+
+/// Scaffold the extension crate described by `options`
+pub fn scaffold_extension(options: &ExtensionNewOptions) -> Result<ScaffoldedExtension, String> {
+    let root = &options.path;
+    let is_empty_dir = std::fs::read_dir(root)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false);
+    if root.exists() && !is_empty_dir {
+        return Err(format!("{} already exists", root.display()));
+    }
+    std::fs::create_dir_all(root)
+        .map_err(|e| format!("Failed to create {}: {}", root.display(), e))?;
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", root.display(), e))?;
+
+    let id = match &options.name {
+        Some(name) => name.clone(),
+        None => root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| "Cannot derive an extension name; pass --name".to_string())?,
+    };
+    validate_id(&id)?;
+
+    let dependencies = match &options.sdk_path {
+        Some(dir) => {
+            let dir = dir
+                .canonicalize()
+                .map_err(|e| format!("Failed to resolve {}: {}", dir.display(), e))?;
+            let path = |krate: &str| {
+                format!(
+                    "{{ path = \"{}\" }}",
+                    dir.join(krate).to_string_lossy().replace('\\', "/")
+                )
+            };
+            (
+                path("morphir-extension-sdk"),
+                path("morphir-extension-testkit"),
+            )
+        }
+        None => {
+            let version = format!("\"{}\"", env!("CARGO_PKG_VERSION"));
+            (version.clone(), version)
+        }
+    };
+
+    let crate_name = id.replace('-', "_");
+    let type_name = type_name(&id);
+    let files = [
+        (
+            "Cargo.toml",
+            cargo_toml(&id, &dependencies.0, &dependencies.1),
+        ),
+        ("src/lib.rs", lib_rs(&id, &type_name, options.capability)),
+        (
+            "tests/extension.rs",
+            tests_rs(&id, &crate_name, &type_name, options.capability),
+        ),
+        ("README.md", readme(&id, &crate_name, options.capability)),
+        (".gitignore", "/target\n".to_string()),
+    ];
+
+    let mut created = Vec::new();
+    for (relative, content) in files {
+        let path = root.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        created.push(PathBuf::from(relative));
+    }
+
+    Ok(ScaffoldedExtension {
+        root,
+        wasm: wasm_path(&crate_name),
+        id,
+        capability: options.capability,
+        created,
+    })
+}
+
+/// Check that `id` is a valid crate name and extension ID
+fn validate_id(id: &str) -> Result<(), String> {
+    let valid = id.starts_with(|c: char| c.is_ascii_lowercase())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid extension name '{}'; start with a lowercase letter and use lowercase letters, digits, '-', and '_'",
+            id
+        ))
+    }
+}
+
+/// Rust type of the extension: its ID in PascalCase, ending in `Extension`
+fn type_name(id: &str) -> String {
+    let mut name: String = id
+        .split(['-', '_'])
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    if !name.ends_with("Extension") {
+        name.push_str("Extension");
+    }
+    name
+}
+
+/// WASM module of a crate's release build, relative to the crate
+fn wasm_path(crate_name: &str) -> PathBuf {
+    Path::new("target/wasm32-unknown-unknown/release").join(format!("{}.wasm", crate_name))
+}
+
+fn cargo_toml(id: &str, sdk: &str, testkit: &str) -> String {
+    format!(
+        r#"[package]
+name = "{id}"
+version = "0.1.0"
+edition = "2024"
+
+[lib]
+# cdylib for the WASM module, rlib for the tests
+crate-type = ["cdylib", "rlib"]
+
+[dependencies]
+morphir-extension-sdk = {sdk}
+# Used by the exports `export_extension!` generates
+extism-pdk = "1"
+serde_json = "1.0"
+
+[dev-dependencies]
+morphir-extension-testkit = {testkit}
+
+# Extensions ship as WASM modules; keep them small
+[profile.release]
+opt-level = "s"
+lto = true
+codegen-units = 1
+strip = true
+panic = "abort"
+
+# Not part of an enclosing workspace
+[workspace]
+"#
+    )
+}
+
+fn lib_rs(id: &str, type_name: &str, capability: ExtensionCapability) -> String {
+    let implementation = match capability {
+        ExtensionCapability::Frontend => format!(
+            r#"impl Frontend for {type_name} {{
+    fn compile(&self, request: CompileRequest) -> Result<CompileResult> {{
+        // Parse each source and build the IR of its module here
+        let mut diagnostics = Vec::new();
+        let mut modules = Vec::new();
+        for source in &request.sources {{
+            if source.content.trim().is_empty() {{
+                diagnostics.push(Diagnostic {{
+                    severity: DiagnosticSeverity::Warning,
+                    code: Some("empty-source".into()),
+                    message: format!("{{}} is empty", source.path),
+                    location: None,
+                    related: vec![],
+                    fixes: vec![],
+                }});
+            }}
+            modules.push(source.path.clone());
+        }}
+        Ok(CompileResult {{
+            success: true,
+            ir: Some(serde_json::json!({{ "modules": modules }})),
+            diagnostics,
+        }})
+    }}
+
+    fn supported_languages() -> Vec<String> {{
+        vec!["{id}".into()]
+    }}
+
+    fn file_extensions() -> Vec<String> {{
+        vec![".{id}".into()]
+    }}
+}}"#
+        ),
+        ExtensionCapability::Backend => format!(
+            r#"impl Backend for {type_name} {{
+    fn generate(&self, request: GenerateRequest) -> Result<GenerateResult> {{
+        // Generate the target language's code from the IR here
+        let content = serde_json::to_string_pretty(&request.ir)?;
+        Ok(GenerateResult {{
+            success: true,
+            artifacts: vec![Artifact {{
+                path: "model.json".into(),
+                content,
+                binary: false,
+            }}],
+            diagnostics: vec![],
+            source_map: vec![],
+        }})
+    }}
+
+    fn target_languages() -> Vec<String> {{
+        vec!["{id}".into()]
+    }}
+}}"#
+        ),
+        ExtensionCapability::Validator => format!(
+            r#"impl Validator for {type_name} {{
+    fn validate(&self, request: ValidateRequest) -> Result<ValidateResult> {{
+        // Check the IR and report what is wrong with it here
+        let mut diagnostics = Vec::new();
+        if !request.ir.is_object() {{
+            diagnostics.push(Diagnostic {{
+                severity: DiagnosticSeverity::Error,
+                code: Some("not-an-object".into()),
+                message: "The IR is not a JSON object".into(),
+                location: None,
+                related: vec![],
+                fixes: vec![],
+            }});
+        }}
+        Ok(ValidateResult {{
+            valid: diagnostics.is_empty(),
+            diagnostics,
+        }})
+    }}
+
+    fn validation_rules() -> Vec<String> {{
+        vec!["not-an-object".into()]
+    }}
+}}"#
+        ),
+        ExtensionCapability::Transform => format!(
+            r#"impl Transform for {type_name} {{
+    fn transform(&self, request: TransformRequest) -> Result<TransformResult> {{
+        // Rewrite the IR here
+        Ok(TransformResult {{
+            success: true,
+            ir: Some(request.ir),
+            diagnostics: vec![],
+        }})
+    }}
+}}"#
+        ),
+    };
+    let trait_name = capability.trait_name();
+    format!(
+        r#"//! {id}: a Morphir {capability} extension
+
+use morphir_extension_sdk::prelude::*;
+
+#[derive(Default)]
+pub struct {type_name};
+
+impl Extension for {type_name} {{
+    fn info() -> ExtensionInfo {{
+        ExtensionInfo {{
+            id: "{id}".into(),
+            name: "{id}".into(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            types: vec![ExtensionType::{trait_name}],
+            ..Default::default()
+        }}
+    }}
+}}
+
+{implementation}
+
+morphir_extension_sdk::export_extension!({type_name}: {trait_name});
+"#,
+        capability = capability.name(),
+    )
+}
+
+fn tests_rs(
+    id: &str,
+    crate_name: &str,
+    type_name: &str,
+    capability: ExtensionCapability,
+) -> String {
+    let capability_test = match capability {
+        ExtensionCapability::Frontend => {
+            r#"#[test]
+fn compiles_sources() {
+    let result = harness()
+        .compile(CompileRequest {
+            sources: vec![source("src/orders.txt", "orders")],
+            ..Default::default()
+        })
+        .unwrap();
+    assert_no_errors(&result.diagnostics);
+    assert!(result.ir.is_some());
+}"#
+        }
+        ExtensionCapability::Backend => {
+            r#"#[test]
+fn generates_artifacts() {
+    let result = harness()
+        .generate(GenerateRequest {
+            ir: serde_json::json!({ "modules": [] }),
+            options: Default::default(),
+        })
+        .unwrap();
+    assert_no_errors(&result.diagnostics);
+    assert!(result.success);
+    assert!(!result.artifacts.is_empty());
+}"#
+        }
+        ExtensionCapability::Validator => {
+            r#"#[test]
+fn reports_invalid_ir() {
+    let validate = |ir| {
+        harness()
+            .validate(ValidateRequest {
+                ir,
+                options: Default::default(),
+            })
+            .unwrap()
+    };
+    let result = validate(serde_json::json!({ "modules": [] }));
+    assert_no_errors(&result.diagnostics);
+    assert!(result.valid);
+    assert!(!validate(serde_json::json!(42)).valid);
+}"#
+        }
+        ExtensionCapability::Transform => {
+            r#"#[test]
+fn transforms_ir() {
+    let ir = serde_json::json!({ "modules": [] });
+    let result = harness()
+        .transform(TransformRequest {
+            ir: ir.clone(),
+            options: Default::default(),
+        })
+        .unwrap();
+    assert_no_errors(&result.diagnostics);
+    assert_eq!(result.ir, Some(ir));
+}"#
+        }
+    };
+    // Only the frontend test builds sources
+    let imports = match capability {
+        ExtensionCapability::Frontend => "ExtensionHarness, assert_no_errors, source",
+        _ => "ExtensionHarness, assert_no_errors",
+    };
+    format!(
+        r#"//! Requests go through the same dispatch as in the daemon
+
+use morphir_extension_sdk::prelude::*;
+use morphir_extension_testkit::{{{imports}}};
+use {crate_name}::{type_name};
+
+fn harness() -> ExtensionHarness<{type_name}> {{
+    ExtensionHarness::new()
+}}
+
+#[test]
+fn reports_its_info() {{
+    assert_eq!(harness().info().unwrap().id, "{id}");
+}}
+
+{capability_test}
+"#
+    )
+}
+
+fn readme(id: &str, crate_name: &str, capability: ExtensionCapability) -> String {
+    let wasm = wasm_path(crate_name);
+    let wasm = wasm.to_string_lossy().replace('\\', "/");
+    format!(
+        r#"# {id}
+
+A Morphir {capability} extension.
+
+## Building
+
+```sh
+rustup target add wasm32-unknown-unknown
+cargo build --release --target wasm32-unknown-unknown
+```
+
+The extension is `{wasm}`.
+
+## Testing
+
+```sh
+cargo test
+```
+
+The tests send requests through the same dispatch the Morphir daemon uses.
+
+## Using it
+
+Register the extension in the `morphir.toml` of a project or workspace:
+
+```toml
+[extensions.{id}]
+path = "path/to/{id}/{wasm}"
+```
+"#,
+        capability = capability.name(),
+    )
+}
+
+fn print_scaffolded(scaffolded: &ScaffoldedExtension, path: &Path, json: bool) {
+    if json {
+        let output = serde_json::json!({ "success": true, "extension": scaffolded });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return;
+    }
+
+    println!(
+        "Created {} extension {} in {}",
+        scaffolded.capability.name(),
+        scaffolded.id,
+        scaffolded.root.display()
+    );
+    for file in &scaffolded.created {
+        println!("  {}", file.display());
+    }
+    println!();
+    if path != Path::new(".") {
+        println!("  cd {}", path.display());
+    }
+    println!("  cargo test");
+    println!("  cargo build --release --target wasm32-unknown-unknown");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_names_and_ids() {
+        assert_eq!(type_name("my-frontend"), "MyFrontendExtension");
+        assert_eq!(type_name("prune_extension"), "PruneExtension");
+        assert!(validate_id("my-ext2").is_ok());
+        assert!(validate_id("My-Ext").is_err());
+        assert!(validate_id("2ext").is_err());
+        assert!(validate_id("ext/name").is_err());
+    }
+
+    #[test]
+    fn test_scaffold_each_capability() {
+        let temp = tempfile::tempdir().unwrap();
+        for capability in [
+            ExtensionCapability::Frontend,
+            ExtensionCapability::Backend,
+            ExtensionCapability::Validator,
+            ExtensionCapability::Transform,
+        ] {
+            let path = temp.path().join(format!("my-{}", capability.name()));
+            let scaffolded = scaffold_extension(&ExtensionNewOptions {
+                path: path.clone(),
+                capability,
+                ..Default::default()
+            })
+            .unwrap();
+            assert_eq!(scaffolded.id, format!("my-{}", capability.name()));
+            assert_eq!(scaffolded.created.len(), 5);
+
+            let lib = std::fs::read_to_string(path.join("src/lib.rs")).unwrap();
+            assert!(lib.contains(&format!(
+                "impl {} for {}",
+                capability.trait_name(),
+                type_name(&scaffolded.id)
+            )));
+            assert!(lib.contains(&format!(
+                "export_extension!({}: {});",
+                type_name(&scaffolded.id),
+                capability.trait_name()
+            )));
+            let tests = std::fs::read_to_string(path.join("tests/extension.rs")).unwrap();
+            assert_eq!(
+                tests.contains("source("),
+                capability == ExtensionCapability::Frontend
+            );
+            let manifest = std::fs::read_to_string(path.join("Cargo.toml")).unwrap();
+            assert!(manifest.contains(&format!(
+                "morphir-extension-sdk = \"{}\"",
+                env!("CARGO_PKG_VERSION")
+            )));
+        }
+
+        // Existing directories are left alone
+        let err = scaffold_extension(&ExtensionNewOptions {
+            path: temp.path().join("my-frontend"),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(err.contains("already exists"), "{}", err);
+    }
+}
@@ -0,0 +1,275 @@
+//! Fix command for applying suggested fixes
+//!
+//! Checks the projects of a workspace as `morphir check` does and collects
+//! the fixes attached to its diagnostics. Without `--apply` the fixes are
+//! only listed; with it, the first fix of each diagnostic is written back
+//! to its files through the [`Vfs`].
+//!
+//! A fix whose edits overlap those of a fix already applied to the same
+//! file, or that point past the end of their file, is skipped and reported,
+//! so running the command again after the next check picks it up.
+
+use crate::commands::check::check_workspace;
+use crate::error::CliError;
+use crate::output::{AppliedFix, Diagnostic, FixOutput, json_requested};
+use morphir_common::vfs::{OsVfs, Vfs};
+use morphir_design::discover_config;
+use morphir_extension_sdk::{CodeFix, TextEdit};
+use starbase::AppResult;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Run the fix command
+pub fn run_fix(
+    config_path: Option<String>,
+    project: Option<String>,
+    apply: bool,
+    json: bool,
+) -> AppResult {
+    let json = json_requested(json);
+    let config_file = if let Some(cfg) = config_path {
+        PathBuf::from(cfg)
+    } else {
+        let start_dir = std::env::current_dir().map_err(|e| CliError::FileSystem { error: e })?;
+        discover_config(&start_dir).ok_or_else(|| CliError::Config {
+            error: anyhow::anyhow!("No morphir.toml or morphir.json found"),
+        })?
+    };
+    let root = config_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+
+    let diagnostics: Vec<Diagnostic> = check_workspace(&root, project.as_deref())?
+        .into_iter()
+        .flat_map(|p| p.diagnostics)
+        .collect();
+    let output = if apply {
+        apply_fixes(&OsVfs, &root, &diagnostics)?
+    } else {
+        FixOutput {
+            applied: false,
+            fixes: diagnostics
+                .iter()
+                .filter_map(|d| Some(applied_fix(d, d.fixes.first()?)))
+                .collect(),
+            skipped: Vec::new(),
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        for fix in &output.fixes {
+            println!("{}: {}", fix.location(), fix.title);
+        }
+        for fix in &output.skipped {
+            eprintln!("skipped {}: {}", fix.location(), fix.title);
+        }
+        match (output.applied, output.fixes.len()) {
+            (_, 0) => println!("No fixes to apply"),
+            (true, n) => println!("Applied {} fix(es)", n),
+            (false, n) => println!("{} fix(es) available; run with --apply to apply them", n),
+        }
+    }
+    Ok(None)
+}
+
+/// Apply the first fix of each diagnostic to its files, resolving relative
+/// paths against `root`
+pub fn apply_fixes(
+    vfs: &dyn Vfs,
+    root: &Path,
+    diagnostics: &[Diagnostic],
+) -> Result<FixOutput, CliError> {
+    let mut output = FixOutput {
+        applied: true,
+        fixes: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let resolve = |file: &str| {
+        let path = Path::new(file);
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            root.join(path)
+        }
+    };
+
+    // Edits accepted so far, by file, in the order their fixes were accepted
+    let mut accepted: BTreeMap<PathBuf, Vec<&TextEdit>> = BTreeMap::new();
+    for diagnostic in diagnostics {
+        let Some(fix) = diagnostic.fixes.first() else {
+            continue;
+        };
+        let overlaps = fix.edits.iter().any(|edit| {
+            accepted
+                .get(&resolve(&edit.location.file))
+                .is_some_and(|edits| edits.iter().any(|other| overlap(edit, other)))
+        });
+        if overlaps || fix.edits.is_empty() {
+            output.skipped.push(applied_fix(diagnostic, fix));
+            continue;
+        }
+        for edit in &fix.edits {
+            accepted
+                .entry(resolve(&edit.location.file))
+                .or_default()
+                .push(edit);
+        }
+        output.fixes.push(applied_fix(diagnostic, fix));
+    }
+
+    for (path, edits) in accepted {
+        let text = vfs
+            .read_to_string(&path)
+            .map_err(|error| CliError::FileSystem { error })?;
+        let fixed = apply_edits(&text, &edits).ok_or_else(|| CliError::Validation {
+            message: format!("Fix edits fall outside {}", path.display()),
+        })?;
+        vfs.write_from_string(&path, &fixed)
+            .map_err(|error| CliError::FileSystem { error })?;
+    }
+    Ok(output)
+}
+
+/// `text` with `edits` applied, or `None` if an edit points past its end.
+/// Edits must not overlap.
+pub fn apply_edits(text: &str, edits: &[&TextEdit]) -> Option<String> {
+    let mut ranges = edits
+        .iter()
+        .map(|edit| {
+            let location = &edit.location;
+            let start = offset(text, location.start_line, location.start_col)?;
+            let end = offset(text, location.end_line, location.end_col)?.max(start);
+            Some((start, end, edit.new_text.as_str()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    // Apply from the end so earlier offsets stay valid
+    ranges.sort_by_key(|&(start, end, _)| std::cmp::Reverse((start, end)));
+    let mut fixed = text.to_string();
+    for (start, end, new_text) in ranges {
+        fixed.replace_range(start..end, new_text);
+    }
+    Some(fixed)
+}
+
+/// Byte offset of a 1-based line and column; columns past the end of the
+/// line point at its end
+fn offset(text: &str, line: u32, column: u32) -> Option<usize> {
+    let mut start = 0;
+    for _ in 1..line.max(1) {
+        start += text[start..].find('\n')? + 1;
+    }
+    let content = text[start..].split('\n').next().unwrap_or_default();
+    let content = content.strip_suffix('\r').unwrap_or(content);
+    let column = column.max(1) as usize - 1;
+    Some(
+        start
+            + content
+                .char_indices()
+                .nth(column)
+                .map_or(content.len(), |(i, _)| i),
+    )
+}
+
+/// Whether two edits of the same file touch overlapping ranges; insertions
+/// at the same point overlap, since their order would be ambiguous
+fn overlap(a: &TextEdit, b: &TextEdit) -> bool {
+    let start = |e: &TextEdit| (e.location.start_line, e.location.start_col);
+    let end = |e: &TextEdit| (e.location.end_line, e.location.end_col).max(start(e));
+    if start(a) == end(a) || start(b) == end(b) {
+        start(a) <= end(b) && start(b) <= end(a)
+    } else {
+        start(a) < end(b) && start(b) < end(a)
+    }
+}
+
+fn applied_fix(diagnostic: &Diagnostic, fix: &CodeFix) -> AppliedFix {
+    AppliedFix {
+        title: fix.title.clone(),
+        file: diagnostic.file.clone(),
+        line: diagnostic.line,
+        edits: fix.edits.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_common::vfs::MemoryVfs;
+    use morphir_extension_sdk::SourceLocation;
+
+    fn edit(line: u32, start_col: u32, end_col: u32, new_text: &str) -> TextEdit {
+        TextEdit {
+            location: SourceLocation {
+                file: "src/orders.gleam".to_string(),
+                start_line: line,
+                start_col,
+                end_line: line,
+                end_col,
+            },
+            new_text: new_text.to_string(),
+        }
+    }
+
+    fn diagnostic(title: &str, edits: Vec<TextEdit>) -> Diagnostic {
+        Diagnostic {
+            level: "error".to_string(),
+            code: None,
+            message: title.to_string(),
+            file: Some("src/orders.gleam".to_string()),
+            line: edits.first().map(|e| e.location.start_line),
+            column: None,
+            end_line: None,
+            end_column: None,
+            related: Vec::new(),
+            fixes: vec![CodeFix {
+                title: title.to_string(),
+                edits,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_apply_edits_by_line_and_column() {
+        let text = "pub fn total(lines {\r\n  list.sum(lines)\r\n}\r\n";
+        let insert = edit(1, 19, 19, ")");
+        let rename = edit(2, 3, 11, "int.sum");
+        assert_eq!(
+            apply_edits(text, &[&insert, &rename]).unwrap(),
+            "pub fn total(lines) {\r\n  int.sum(lines)\r\n}\r\n"
+        );
+
+        let append = edit(3, 5, 9, " // done");
+        assert_eq!(
+            apply_edits(text, &[&append]).unwrap(),
+            "pub fn total(lines {\r\n  list.sum(lines)\r\n} // done\r\n"
+        );
+        assert_eq!(apply_edits(text, &[&edit(9, 1, 1, "x")]), None);
+    }
+
+    #[test]
+    fn test_apply_fixes_skips_overlapping_fixes() {
+        let vfs = MemoryVfs::new();
+        let root = Path::new("/work");
+        vfs.write_from_string(
+            &root.join("src/orders.gleam"),
+            "pub fn total(lines {\n  list.sum(lines)\n}\n",
+        )
+        .unwrap();
+
+        let diagnostics = vec![
+            diagnostic("Insert missing `)`", vec![edit(1, 19, 19, ")")]),
+            diagnostic("Insert missing `,`", vec![edit(1, 19, 19, ",")]),
+            diagnostic("Use `int.sum`", vec![edit(2, 3, 11, "int.sum")]),
+        ];
+        let output = apply_fixes(&vfs, root, &diagnostics).unwrap();
+        assert_eq!(output.fixes.len(), 2);
+        assert_eq!(output.skipped[0].title, "Insert missing `,`");
+        assert_eq!(
+            vfs.read_to_string(&root.join("src/orders.gleam")).unwrap(),
+            "pub fn total(lines) {\n  int.sum(lines)\n}\n"
+        );
+    }
+}
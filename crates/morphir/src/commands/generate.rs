@@ -1,66 +1,80 @@
 //! Generate command for code generation from Morphir IR
+//!
+//! A wrapper over the generate stage of [`Pipeline`], adding profiles,
+//! post-processors, and verification.
+//!
+//! `--profile <name>` takes the target, input, output, options, and
+//! post-processors from `[generate.profiles.<name>]`; flags given alongside it
+//! override the profile.
+//!
+//! `--verify` (or `verify = true` in the profile) then checks the output with
+//! the target's own toolchain, such as `gleam check`. Problems it reports are
+//! added to the diagnostics, naming the definition the offending code was
+//! generated from when the backend returned a source map.
+//!
+//! `--optimize` runs the IR optimizer (constant folding, beta reduction, and
+//! inlining of small definitions) before the backend sees the IR.
+//!
+//! Backends that declare identifier rules have the names they will emit
+//! audited first: renamed identifiers are reported as information, and
+//! identifiers that would collide fail generation before it starts.
 
-use crate::error::CliError;
-use crate::output::Diagnostic;
+use crate::diagnostics::DiagnosticRenderer;
+use crate::error::{CliError, convert_extension_diagnostics};
+use crate::output::{Diagnostic, EventStream, LogFormat};
+use crate::pipeline::Pipeline;
+use morphir_common::config::GenerateProfile;
 use morphir_common::loader::load_ir;
-use morphir_daemon::extensions::registry::ExtensionRegistry;
-use morphir_design::{
-    discover_config, ensure_morphir_structure, load_config_context, resolve_generate_output,
-};
+use morphir_daemon::artifacts::ArtifactWriter;
+use morphir_daemon::command::CommandRunner;
+use morphir_daemon::verify::{SourceMap, Toolchain, Verification, verify as verify_output};
+use morphir_design::{ConfigContext, resolve_path_relative_to_config};
+use morphir_extension_sdk::types::SourceMapEntry;
 use starbase::AppResult;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Run the generate command
+#[allow(clippy::too_many_arguments)]
 pub async fn run_generate(
     target: Option<String>,
     input: Option<String>,
     output: Option<String>,
     config_path: Option<String>,
     _project: Option<String>,
+    profile: Option<String>,
+    verify: bool,
+    optimize: bool,
     json: bool,
     json_lines: bool,
+    log_format: LogFormat,
 ) -> AppResult {
     use crate::output::{GenerateOutput, OutputFormat, write_output};
-    // Discover config if not provided
-    let start_dir = std::env::current_dir().map_err(|e| CliError::FileSystem { error: e })?;
-
-    let config_file = if let Some(cfg) = config_path {
-        PathBuf::from(cfg)
-    } else {
-        discover_config(&start_dir).ok_or_else(|| CliError::Config {
-            error: anyhow::anyhow!("No morphir.toml or morphir.json found"),
-        })?
-    };
+    let events = EventStream::new(log_format);
+    let mut pipeline = Pipeline::new()
+        .with_optimization(optimize)
+        .with_events(events);
+    if let Some(path) = config_path {
+        pipeline = pipeline.with_config(path);
+    }
+    let ctx = pipeline.load_config()?;
 
-    // Load config context
-    let ctx = load_config_context(&config_file).map_err(|e| CliError::Config { error: e })?;
-
-    // Ensure .morphir/ structure exists
-    ensure_morphir_structure(&ctx.morphir_dir).map_err(|e| CliError::Config { error: e })?;
-
-    // Determine target (from CLI or config)
-    let target_lang = target
-        .or_else(|| {
-            ctx.config
-                .codegen
-                .as_ref()
-                .and_then(|c| c.targets.first().cloned())
-        })
-        .ok_or_else(|| CliError::Config {
-            error: anyhow::anyhow!("Target not specified and not found in config"),
-        })?;
+    let profile = profile.map(|name| find_profile(&ctx, &name)).transpose()?;
 
-    // Determine project name
-    let proj_name = ctx
-        .current_project
-        .as_ref()
-        .map(|p| p.name.clone())
-        .or_else(|| ctx.config.project.as_ref().map(|p| p.name.clone()))
-        .unwrap_or_else(|| "default".to_string());
+    // Target from the CLI, then the profile, then the config
+    if let Some(target) = target.or_else(|| profile.map(|p| p.target.clone())) {
+        pipeline = pipeline.with_target(target);
+    }
+    let target_lang = pipeline.target(&ctx)?;
+    let proj_name = pipeline.package_name(&ctx);
 
     // Determine IR input path
+    let profile_input = profile
+        .and_then(|p| p.input.as_ref())
+        .map(|i| resolve_path_relative_to_config(Path::new(i), &ctx.config_path));
     let input_path = if let Some(inp) = input {
         PathBuf::from(inp)
+    } else if let Some(inp) = profile_input {
+        inp
     } else {
         // Default to compile output for the target language
         morphir_design::resolve_compile_output(&proj_name, &target_lang, &ctx.morphir_dir)
@@ -76,125 +90,350 @@ pub async fn run_generate(
         .into());
     }
 
-    // Determine output path
-    let output_path = if let Some(out) = output {
-        PathBuf::from(out)
-    } else {
-        resolve_generate_output(&proj_name, &target_lang, &ctx.morphir_dir)
-    };
-
-    // Create extension registry
-    let registry = ExtensionRegistry::new(
-        ctx.project_root
-            .unwrap_or_else(|| ctx.config_path.parent().unwrap().to_path_buf()),
-        output_path.clone(),
-    )
-    .map_err(|e| CliError::Extension {
-        message: format!("Failed to create extension registry: {}", e),
-    })?;
-
-    // Register builtin extensions
-    let builtins = morphir_design::discover_builtin_extensions();
-    for builtin in builtins {
-        if let Some(path) = builtin.path {
-            registry
-                .register_builtin(&builtin.id, path)
-                .await
-                .map_err(|e| CliError::Extension {
-                    message: format!("Failed to register builtin extension {}: {}", builtin.id, e),
-                })?;
-        }
+    // Output path from the CLI, then the profile
+    let profile_output = profile
+        .and_then(|p| p.output.as_ref())
+        .map(|o| resolve_path_relative_to_config(Path::new(o), &ctx.config_path));
+    if let Some(out) = output.map(PathBuf::from).or(profile_output) {
+        pipeline = pipeline.with_generate_output(out);
     }
 
-    // Find and load extension by target
-    let extension = registry
-        .find_extension_by_target(&target_lang)
-        .await
-        .ok_or_else(|| CliError::Extension {
-            message: format!("No extension found for target: {}", target_lang),
+    // Load IR (detect format)
+    let started = events.started("load");
+    let ir_data = events
+        .track("load", started, load_ir(&input_path))
+        .map_err(|e| CliError::FileSystem {
+            error: std::io::Error::other(e),
         })?;
 
-    // Load IR (detect format)
-    let ir_data = load_ir(&input_path).map_err(|e| CliError::FileSystem {
-        error: std::io::Error::other(e),
-    })?;
-
-    // Call extension's generate method
-    let generate_params = serde_json::json!({
-        "input": input_path.to_string_lossy(),
-        "output": output_path.to_string_lossy(),
-        "ir": ir_data,
-    });
-
-    let result: serde_json::Value = extension
-        .call("morphir.backend.generate", generate_params)
-        .await
-        .map_err(|e| CliError::Extension {
-            message: format!("Extension generate call failed: {}", e),
+    // The profile's options go over the target settings
+    if let Some(profile) = profile {
+        let mut options = serde_json::json!({});
+        apply_profile_options(&mut options, profile).map_err(|e| CliError::Config {
+            error: anyhow::anyhow!("Invalid options in generation profile: {}", e),
         })?;
+        if let serde_json::Value::Object(options) = options {
+            pipeline = pipeline.with_generate_options(options);
+        }
+    }
 
+    let generated = pipeline.generate(&ctx, &input_path, ir_data).await?;
+    let output_path = generated.output_path.clone();
     let format = OutputFormat::from_flags(json, json_lines);
+    let mut diagnostics: Vec<Diagnostic> = generated.diagnostics.clone();
 
-    // Extract diagnostics and artifacts from result
-    let diagnostics: Vec<Diagnostic> = result
-        .get("diagnostics")
-        .and_then(|d| serde_json::from_value(d.clone()).ok())
-        .unwrap_or_default();
-
-    let artifacts: Vec<String> = result
-        .get("artifacts")
-        .and_then(|a| serde_json::from_value(a.clone()).ok())
-        .unwrap_or_default();
-
-    let success = result
-        .get("success")
-        .and_then(|s| s.as_bool())
-        .unwrap_or(true);
-
-    if !success {
-        let error_msg = result
-            .get("error")
-            .and_then(|e| e.as_str())
-            .unwrap_or("Code generation failed");
-
-        if format != OutputFormat::Human {
-            let output = GenerateOutput {
-                success: false,
-                artifacts: vec![],
-                diagnostics: diagnostics.clone(),
-                output_path: output_path.to_string_lossy().to_string(),
-            };
+    if !generated.success {
+        let error_msg = generated
+            .error
+            .clone()
+            .unwrap_or_else(|| "Code generation failed".to_string());
+
+        let output = GenerateOutput {
+            success: false,
+            artifacts: vec![],
+            diagnostics: diagnostics.clone(),
+            output_path: output_path.to_string_lossy().to_string(),
+        };
+        if events.is_enabled() {
+            events.result(&output);
+        } else if format.is_json() {
             write_output(format, &output).map_err(CliError::from)?;
         } else {
+            DiagnosticRenderer::stderr().eprint_all(&output.diagnostics);
             let err = CliError::Compilation {
-                message: error_msg.to_string(),
+                message: error_msg.clone(),
             };
             err.report();
         }
-        return Err(CliError::Compilation {
-            message: error_msg.to_string(),
+        return Err(CliError::Compilation { message: error_msg }.into());
+    }
+
+    let writer = pipeline.writer(&generated, &proj_name);
+    let written = &generated.written;
+    let artifacts: Vec<String> = written
+        .artifacts
+        .iter()
+        .chain(&written.scaffolding)
+        .map(|p| p.display().to_string())
+        .collect();
+
+    let post_process = profile.map(|p| p.post_process.as_slice()).unwrap_or(&[]);
+    if !post_process.is_empty() {
+        let started = events.started("post-process");
+        let result = post_process
+            .iter()
+            .try_for_each(|command| run_post_processor(command, &output_path));
+        events.track("post-process", started, result)?;
+    }
+
+    let verify = verify || profile.is_some_and(|p| p.verify);
+    let mut verified = None;
+    if verify {
+        let toolchain = verify_toolchain(profile, &target_lang)?;
+        let source_map = placed_source_map(&writer, generated.source_map.clone());
+        let started = events.started("verify");
+        let verification = events
+            .track(
+                "verify",
+                started,
+                verify_output(&toolchain, &output_path, &source_map, &CommandRunner::new()),
+            )
+            .map_err(|e| CliError::Compilation {
+                message: format!("Failed to verify generated code: {}", e),
+            })?;
+        let Verification {
+            tool,
+            success,
+            diagnostics: reported,
+        } = verification;
+        let reported = convert_extension_diagnostics(&reported);
+        for diagnostic in &reported {
+            events.diagnostic(diagnostic);
         }
-        .into());
+        diagnostics.extend(reported);
+
+        if !success {
+            let message = format!("Generated code failed `{}`", tool);
+            let output = GenerateOutput {
+                success: false,
+                artifacts,
+                diagnostics,
+                output_path: output_path.to_string_lossy().to_string(),
+            };
+            if events.is_enabled() {
+                events.result(&output);
+            } else if format.is_json() {
+                write_output(format, &output).map_err(CliError::from)?;
+            } else {
+                DiagnosticRenderer::stderr().eprint_all(&output.diagnostics);
+                CliError::Compilation {
+                    message: message.clone(),
+                }
+                .report();
+            }
+            return Err(CliError::Compilation { message }.into());
+        }
+        verified = Some(tool);
     }
 
-    if format != OutputFormat::Human {
-        let output = GenerateOutput {
-            success: true,
-            artifacts,
-            diagnostics,
-            output_path: output_path.to_string_lossy().to_string(),
-        };
+    let output = GenerateOutput {
+        success: true,
+        artifacts,
+        diagnostics,
+        output_path: output_path.to_string_lossy().to_string(),
+    };
+    if events.is_enabled() {
+        events.result(&output);
+    } else if format.is_json() {
         write_output(format, &output).map_err(CliError::from)?;
     } else {
+        let diagnostics = &output.diagnostics;
         println!("Code generation successful!");
         println!("Output: {:?}", output_path);
+        println!(
+            "Wrote {} artifact(s) and {} scaffolding file(s)",
+            written.artifacts.len(),
+            written.scaffolding.len()
+        );
+        if !post_process.is_empty() {
+            println!("Ran {} post-processor(s)", post_process.len());
+        }
+        if let Some(tool) = &verified {
+            println!("Verified with {}", tool);
+        }
         if !diagnostics.is_empty() {
             println!("\nDiagnostics:");
-            for diag in &diagnostics {
-                println!("  {}: {}", diag.level, diag.message);
+            let renderer = DiagnosticRenderer::stdout();
+            for diag in diagnostics {
+                for line in renderer.render(diag).lines() {
+                    println!("  {}", line);
+                }
             }
         }
     }
 
     Ok(None)
 }
+
+/// The generation profile called `name`
+fn find_profile<'a>(ctx: &'a ConfigContext, name: &str) -> Result<&'a GenerateProfile, CliError> {
+    ctx.config.generate_profile(name).ok_or_else(|| {
+        let available: Vec<&str> = ctx
+            .config
+            .generate
+            .iter()
+            .flat_map(|g| g.profiles.keys().map(String::as_str))
+            .collect();
+        let error = if available.is_empty() {
+            anyhow::anyhow!("Unknown generation profile {}; none are configured", name)
+        } else {
+            anyhow::anyhow!(
+                "Unknown generation profile {}; available: {}",
+                name,
+                available.join(", ")
+            )
+        };
+        CliError::Config { error }
+    })
+}
+
+/// Set the options of `profile` over the target settings in `options`
+fn apply_profile_options(
+    options: &mut serde_json::Value,
+    profile: &GenerateProfile,
+) -> Result<(), serde_json::Error> {
+    if !options.is_object() {
+        *options = serde_json::json!({});
+    }
+    for (key, value) in &profile.options {
+        options[key] = serde_json::to_value(value)?;
+    }
+    Ok(())
+}
+
+/// The checker for `--verify`: the profile's `verify_command`, or the
+/// target's own toolchain
+fn verify_toolchain(
+    profile: Option<&GenerateProfile>,
+    target: &str,
+) -> Result<Toolchain, CliError> {
+    match profile.and_then(|p| p.verify_command.as_deref()) {
+        Some(command) => Toolchain::custom(command).ok_or_else(|| CliError::Config {
+            error: anyhow::anyhow!("verify_command in generation profile is empty"),
+        }),
+        None => Toolchain::for_target(target).ok_or_else(|| CliError::Config {
+            error: anyhow::anyhow!(
+                "No toolchain is known for target {}; set verify_command in the generation profile",
+                target
+            ),
+        }),
+    }
+}
+
+/// Source map with artifact paths moved to where `writer` placed them, e.g.
+/// `orders.gleam` to `src/orders.gleam`
+fn placed_source_map(writer: &ArtifactWriter, entries: Vec<SourceMapEntry>) -> SourceMap {
+    SourceMap::new(
+        entries
+            .into_iter()
+            .filter_map(|entry| {
+                let path = writer.resolve(&entry.path).ok()?;
+                Some(SourceMapEntry {
+                    path: path.as_str().to_string(),
+                    ..entry
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Run a post-processor in the output directory. Its output goes to stderr,
+/// leaving stdout to the command's own output.
+fn run_post_processor(command: &str, output_path: &Path) -> Result<(), CliError> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = std::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = std::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let status = shell
+        .arg(command)
+        .current_dir(output_path)
+        .stdout(std::io::stderr())
+        .status()
+        .map_err(|e| CliError::Compilation {
+            message: format!("Failed to run post-processor `{}`: {}", command, e),
+        })?;
+    if !status.success() {
+        return Err(CliError::Compilation {
+            message: format!("Post-processor `{}` failed: {}", command, status),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_options_override_target_settings() {
+        let mut options = serde_json::json!({ "title": "Default", "version": "1.0" });
+        let profile = GenerateProfile {
+            target: "openapi".to_string(),
+            input: None,
+            output: None,
+            options: [("title".to_string(), "Prod".into())].into(),
+            post_process: Vec::new(),
+            verify: false,
+            verify_command: None,
+        };
+        apply_profile_options(&mut options, &profile).unwrap();
+        assert_eq!(
+            options,
+            serde_json::json!({ "title": "Prod", "version": "1.0" })
+        );
+    }
+
+    #[test]
+    fn test_verification_uses_the_profile_command_or_target_toolchain() {
+        let mut profile = GenerateProfile {
+            target: "gleam".to_string(),
+            input: None,
+            output: None,
+            options: Default::default(),
+            post_process: Vec::new(),
+            verify: true,
+            verify_command: None,
+        };
+        assert_eq!(
+            verify_toolchain(Some(&profile), "gleam")
+                .unwrap()
+                .command_line(),
+            "gleam check"
+        );
+        assert!(verify_toolchain(None, "cobol").is_err());
+        profile.verify_command = Some("gleam check --target javascript".to_string());
+        assert_eq!(
+            verify_toolchain(Some(&profile), "cobol")
+                .unwrap()
+                .command_line(),
+            "gleam check --target javascript"
+        );
+
+        let writer = ArtifactWriter::new("out", "gleam");
+        let source_map = placed_source_map(
+            &writer,
+            vec![SourceMapEntry {
+                path: "orders.gleam".to_string(),
+                start_line: 4,
+                end_line: 6,
+                fqname: "my-org/shop:orders#status".to_string(),
+            }],
+        );
+        assert_eq!(
+            source_map.lookup("src/orders.gleam", 5),
+            Some("my-org/shop:orders#status")
+        );
+        assert_eq!(source_map.lookup("orders.gleam", 5), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_post_processors_run_in_the_output_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        run_post_processor("touch formatted", temp.path()).unwrap();
+        assert!(temp.path().join("formatted").exists());
+
+        let err = run_post_processor("exit 3", temp.path()).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Post-processor `exit 3` failed: exit status: 3"),
+            "{}",
+            err
+        );
+    }
+}
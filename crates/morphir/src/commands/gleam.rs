@@ -2,6 +2,7 @@
 
 use crate::commands::compile::CompileOptions;
 use crate::commands::{run_compile, run_generate};
+use crate::output::LogFormat;
 use starbase::AppResult;
 
 /// Run Gleam compile command (convenience wrapper)
@@ -23,6 +24,8 @@ pub async fn run_gleam_compile(
         project,
         json,
         json_lines,
+        log_format: LogFormat::Text,
+        jobs: 0,
     })
     .await
 }
@@ -42,8 +45,12 @@ pub async fn run_gleam_generate(
         output,
         config_path,
         project,
+        None,
+        false,
+        false,
         json,
         json_lines,
+        LogFormat::Text,
     )
     .await
 }
@@ -0,0 +1,210 @@
+//! Graph Command
+//!
+//! Renders the type-dependency or call graph of a distribution as Graphviz
+//! DOT, GraphML, or JSON, optionally restricted to what a set of roots
+//! reaches. Reference cycles are highlighted in DOT and listed in JSON.
+
+use crate::commands::sample::parse_fqname;
+use morphir_common::loader::{LoadedDistribution, load_distribution_from_source};
+use morphir_core::converter;
+use morphir_core::ir::v4::{DependencyGraph, GraphKind, call_graph, type_graph};
+use serde::Serialize;
+use starbase::AppResult;
+use std::path::PathBuf;
+
+/// Which references the graph follows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphKindArg {
+    /// Values and the values they call
+    #[default]
+    Calls,
+    /// Types and the types their definitions mention
+    Types,
+}
+
+/// Output format of `ir graph`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// GraphML, for tools such as Gephi or yEd
+    Graphml,
+    /// Nodes, edges, and cycles as JSON
+    Json,
+}
+
+/// Options for the `ir graph` command
+#[derive(Debug, Default)]
+pub struct GraphCommandOptions {
+    /// Input file, directory, or remote source
+    pub input: String,
+    /// Which graph to build
+    pub kind: GraphKindArg,
+    /// Output format
+    pub format: GraphFormat,
+    /// Keep only the nodes reachable from these FQNames
+    pub roots: Vec<String>,
+    /// Keep nodes defined outside the distribution
+    pub external: bool,
+    /// Output file (stdout if omitted)
+    pub output: Option<PathBuf>,
+}
+
+/// JSON output of the graph command
+#[derive(Serialize)]
+struct GraphOutput {
+    success: bool,
+    input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<GraphKind>,
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    cycles: Vec<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct GraphNode {
+    name: String,
+    external: bool,
+}
+
+#[derive(Serialize)]
+struct GraphEdge {
+    from: String,
+    to: String,
+}
+
+impl GraphOutput {
+    fn new(input: String, graph: &DependencyGraph) -> Self {
+        Self {
+            success: true,
+            input,
+            kind: Some(graph.kind()),
+            nodes: graph
+                .nodes()
+                .map(|name| GraphNode {
+                    name: name.to_canonical_string(),
+                    external: graph.is_external(name),
+                })
+                .collect(),
+            edges: graph
+                .edges()
+                .map(|(from, to)| GraphEdge {
+                    from: from.to_canonical_string(),
+                    to: to.to_canonical_string(),
+                })
+                .collect(),
+            cycles: graph
+                .cycles()
+                .into_iter()
+                .map(|cycle| {
+                    cycle
+                        .iter()
+                        .map(|name| name.to_canonical_string())
+                        .collect()
+                })
+                .collect(),
+            error: None,
+        }
+    }
+}
+
+/// Run the `ir graph` command.
+pub fn run_ir_graph(options: GraphCommandOptions) -> AppResult {
+    let GraphCommandOptions {
+        input,
+        kind,
+        format,
+        roots,
+        external,
+        output,
+    } = options;
+
+    let output_error = |msg: &str| {
+        if format == GraphFormat::Json {
+            let output = GraphOutput {
+                success: false,
+                input: input.clone(),
+                kind: None,
+                nodes: Vec::new(),
+                edges: Vec::new(),
+                cycles: Vec::new(),
+                error: Some(msg.to_string()),
+            };
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            eprintln!("{}", msg);
+        }
+    };
+
+    let mut root_names = Vec::new();
+    for s in &roots {
+        match parse_fqname(s) {
+            Ok(fqname) => root_names.push(fqname),
+            Err(e) => {
+                output_error(&e);
+                return Ok(Some(1));
+            }
+        }
+    }
+
+    let distribution = match load_distribution_from_source(&input) {
+        Ok(LoadedDistribution::V4(ir_file)) => ir_file.distribution,
+        Ok(LoadedDistribution::Classic(dist)) => converter::classic_to_v4(&dist).ir.distribution,
+        Err(e) => {
+            output_error(&format!("Failed to load input: {}", e));
+            return Ok(Some(1));
+        }
+    };
+
+    let mut graph = match kind {
+        GraphKindArg::Calls => call_graph(&distribution),
+        GraphKindArg::Types => type_graph(&distribution),
+    };
+    if let Some(unknown) = root_names.iter().find(|root| !graph.contains(root)) {
+        output_error(&format!(
+            "'{}' is not a definition in the {} graph",
+            unknown.to_canonical_string(),
+            match kind {
+                GraphKindArg::Calls => "call",
+                GraphKindArg::Types => "type",
+            }
+        ));
+        return Ok(Some(1));
+    }
+    if !root_names.is_empty() {
+        graph = graph.reachable_subgraph(&root_names);
+    }
+    if !external {
+        graph = graph.without_external();
+    }
+
+    let content = match format {
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Graphml => graph.to_graphml(),
+        GraphFormat::Json => format!(
+            "{}\n",
+            serde_json::to_string_pretty(&GraphOutput::new(input.clone(), &graph)).unwrap()
+        ),
+    };
+    match &output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &content) {
+                output_error(&format!("Failed to write {:?}: {}", path, e));
+                return Ok(Some(1));
+            }
+            eprintln!(
+                "Wrote {} node(s), {} edge(s), and {} cycle(s) to {}",
+                graph.len(),
+                graph.edges().count(),
+                graph.cycles().len(),
+                path.display()
+            );
+        }
+        None => print!("{}", content),
+    }
+    Ok(None)
+}
@@ -0,0 +1,568 @@
+//! Init and new commands: scaffold a Morphir project or workspace
+//!
+//! `morphir init` sets up an existing directory (the current one by default)
+//! and `morphir new` creates a fresh one. Both write `morphir.toml`, the
+//! `.morphir/` layout, a sample module in the chosen frontend language, and a
+//! `.gitignore` for the build output. A project created inside an existing
+//! workspace is added to its members and shares the workspace's `.morphir/`
+//! and `.gitignore`.
+
+use crate::commands::deps::output_error;
+use crate::output::json_requested;
+use morphir_common::config::MorphirConfig;
+use morphir_common::config::edit::add_workspace_member;
+use morphir_design::{discover_config, ensure_morphir_structure};
+use serde::Serialize;
+use starbase::AppResult;
+use std::path::{Path, PathBuf};
+
+/// Project archetypes `--template` selects from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectTemplate {
+    /// A project exposing a sample domain module
+    #[default]
+    Library,
+    /// A project with configuration and an empty source directory
+    Minimal,
+    /// A workspace with `packages/*` members and a first library member
+    Workspace,
+}
+
+/// Frontend languages a project can be scaffolded for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceLanguage {
+    #[default]
+    Gleam,
+    Elm,
+    Python,
+}
+
+impl SourceLanguage {
+    /// Name used for `[frontend] language`
+    fn name(self) -> &'static str {
+        match self {
+            SourceLanguage::Gleam => "gleam",
+            SourceLanguage::Elm => "elm",
+            SourceLanguage::Python => "python",
+        }
+    }
+
+    /// Module name and file of the sample module for a package named `base`
+    fn sample_module(self, base: &str) -> (String, String) {
+        let words: Vec<&str> = base
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+        let words = if words.is_empty() || words[0].starts_with(|c: char| c.is_ascii_digit()) {
+            [&["model"], words.as_slice()].concat()
+        } else {
+            words
+        };
+        match self {
+            SourceLanguage::Elm => {
+                let module: String = words
+                    .iter()
+                    .map(|word| {
+                        let mut chars = word.chars();
+                        chars
+                            .next()
+                            .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                let file = format!("{}.elm", module);
+                (module, file)
+            }
+            SourceLanguage::Gleam | SourceLanguage::Python => {
+                let module = words.join("_").to_ascii_lowercase();
+                let ext = if self == SourceLanguage::Gleam {
+                    "gleam"
+                } else {
+                    "py"
+                };
+                let file = format!("{}.{}", module, ext);
+                (module, file)
+            }
+        }
+    }
+
+    /// Source of the sample module
+    fn sample_source(self, module: &str) -> String {
+        match self {
+            SourceLanguage::Gleam => "\
+//// A sample domain model generated by `morphir init`.
+
+pub type Order {
+  Order(id: String, quantity: Int)
+}
+
+pub fn is_large(order: Order) -> Bool {
+  order.quantity > 100
+}
+"
+            .to_string(),
+            SourceLanguage::Elm => format!(
+                "\
+module {} exposing (Order, isLarge)
+
+{{-| A sample domain model generated by `morphir init`.
+-}}
+
+
+type alias Order =
+    {{ id : String
+    , quantity : Int
+    }}
+
+
+isLarge : Order -> Bool
+isLarge order =
+    order.quantity > 100
+",
+                module
+            ),
+            SourceLanguage::Python => "\
+\"\"\"A sample domain model generated by `morphir init`.\"\"\"
+
+from dataclasses import dataclass
+
+
+@dataclass
+class Order:
+    id: str
+    quantity: int
+
+
+def is_large(order: Order) -> bool:
+    return order.quantity > 100
+"
+            .to_string(),
+        }
+    }
+}
+
+/// Entries the generated `.gitignore` contains
+const GITIGNORE_ENTRIES: [&str; 3] = [".morphir/out/", ".morphir/cache/", ".morphir/logs/"];
+
+/// Options for `morphir init` and `morphir new`
+#[derive(Debug, Default)]
+pub struct InitOptions {
+    /// Directory to scaffold
+    pub path: PathBuf,
+    /// Package name (defaults to the directory name)
+    pub name: Option<String>,
+    /// Project archetype
+    pub template: ProjectTemplate,
+    /// Frontend language of the sample source
+    pub language: SourceLanguage,
+    /// Skip writing `.gitignore`
+    pub no_gitignore: bool,
+    /// Output JSON format
+    pub json: bool,
+}
+
+/// What was scaffolded
+#[derive(Debug, Serialize)]
+pub struct Scaffolded {
+    /// Directory scaffolded
+    pub root: PathBuf,
+    /// Package name (of the first member, for a workspace)
+    pub name: String,
+    pub template: ProjectTemplate,
+    pub language: SourceLanguage,
+    /// Config of the workspace the project was added to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<PathBuf>,
+    /// Files written, relative to `root`
+    pub created: Vec<PathBuf>,
+    /// Files left alone because they already existed, relative to `root`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Run `morphir init`
+pub fn run_init(options: InitOptions) -> AppResult {
+    let json = json_requested(options.json);
+    match scaffold(&options) {
+        Ok(scaffolded) => {
+            print_scaffolded(&scaffolded, &options.path, json);
+            Ok(None)
+        }
+        Err(message) => Ok(Some(output_error(json, &message))),
+    }
+}
+
+/// Run `morphir new`
+pub fn run_new(options: InitOptions) -> AppResult {
+    let json = json_requested(options.json);
+    let is_empty_dir = std::fs::read_dir(&options.path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false);
+    if options.path.exists() && !is_empty_dir {
+        let message = format!(
+            "{} already exists; use `morphir init` to set up an existing directory",
+            options.path.display()
+        );
+        return Ok(Some(output_error(json, &message)));
+    }
+    run_init(options)
+}
+
+/// Scaffold the project or workspace described by `options`
+pub fn scaffold(options: &InitOptions) -> Result<Scaffolded, String> {
+    let root = &options.path;
+    if root.join("morphir.toml").exists() || root.join("morphir.json").exists() {
+        return Err(format!(
+            "{} already has a Morphir configuration",
+            root.display()
+        ));
+    }
+    std::fs::create_dir_all(root)
+        .map_err(|e| format!("Failed to create {}: {}", root.display(), e))?;
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", root.display(), e))?;
+
+    let name = match &options.name {
+        Some(name) => name.clone(),
+        None => root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| "Cannot derive a package name; pass --name".to_string())?,
+    };
+    validate_name(&name)?;
+
+    let workspace = root.parent().and_then(enclosing_workspace);
+    let mut scaffolded = Scaffolded {
+        root: root.clone(),
+        name: name.clone(),
+        template: options.template,
+        language: options.language,
+        workspace: None,
+        created: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    if options.template == ProjectTemplate::Workspace {
+        if let Some(config) = workspace {
+            return Err(format!(
+                "{} is inside the workspace of {}; workspaces cannot be nested",
+                root.display(),
+                config.display()
+            ));
+        }
+        let base = base_name(&name);
+        let member = format!("packages/{}", base);
+        write_file(
+            &root,
+            "morphir.toml",
+            &format!(
+                "[workspace]\nmembers = [\"packages/*\"]\ndefault_member = \"{}\"\n\n[frontend]\nlanguage = \"{}\"\n",
+                member,
+                options.language.name()
+            ),
+            &mut scaffolded,
+        )?;
+        scaffold_project(
+            &root,
+            Path::new(&member),
+            &name,
+            ProjectTemplate::Library,
+            options.language,
+            &mut scaffolded,
+        )?;
+    } else {
+        if let Some(config) = &workspace {
+            let workspace_root = config.parent().unwrap_or(Path::new("."));
+            let member = root
+                .strip_prefix(workspace_root)
+                .map(|member| member.to_string_lossy().replace('\\', "/"))
+                .map_err(|_| format!("{} is outside its workspace", root.display()))?;
+            add_workspace_member(config, &member)
+                .map_err(|e| format!("Failed to add {} to {}: {}", member, config.display(), e))?;
+            scaffolded.workspace = Some(config.clone());
+        }
+        scaffold_project(
+            &root,
+            Path::new(""),
+            &name,
+            options.template,
+            options.language,
+            &mut scaffolded,
+        )?;
+    }
+
+    // Members build into the workspace's .morphir/, which ignores its output
+    if scaffolded.workspace.is_none() {
+        ensure_morphir_structure(&root.join(".morphir"))
+            .map_err(|e| format!("Failed to create .morphir/: {}", e))?;
+        if !options.no_gitignore {
+            update_gitignore(&root, &mut scaffolded)?;
+        }
+    }
+    Ok(scaffolded)
+}
+
+/// Write the config and sources of a project at `dir` under `root`
+fn scaffold_project(
+    root: &Path,
+    dir: &Path,
+    name: &str,
+    template: ProjectTemplate,
+    language: SourceLanguage,
+    scaffolded: &mut Scaffolded,
+) -> Result<(), String> {
+    let (module, file) = language.sample_module(base_name(name));
+    let exposed = match template {
+        ProjectTemplate::Minimal => String::new(),
+        _ => format!("\"{}\"", module),
+    };
+    let config = format!(
+        "[project]\nname = \"{}\"\nversion = \"0.1.0\"\nsource_directory = \"src\"\nexposed_modules = [{}]\n\n[frontend]\nlanguage = \"{}\"\n",
+        name,
+        exposed,
+        language.name()
+    );
+    write_file(
+        root,
+        &dir.join("morphir.toml").to_string_lossy(),
+        &config,
+        scaffolded,
+    )?;
+
+    let src = dir.join("src");
+    std::fs::create_dir_all(root.join(&src))
+        .map_err(|e| format!("Failed to create {}: {}", src.display(), e))?;
+    if template != ProjectTemplate::Minimal {
+        write_file(
+            root,
+            &src.join(file).to_string_lossy(),
+            &language.sample_source(&module),
+            scaffolded,
+        )?;
+    }
+    Ok(())
+}
+
+/// Write `relative` under `root` unless it already exists
+fn write_file(
+    root: &Path,
+    relative: &str,
+    content: &str,
+    scaffolded: &mut Scaffolded,
+) -> Result<(), String> {
+    let path = root.join(relative);
+    if path.exists() {
+        scaffolded.skipped.push(PathBuf::from(relative));
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    scaffolded.created.push(PathBuf::from(relative));
+    Ok(())
+}
+
+/// Add the build output entries missing from `root/.gitignore`
+fn update_gitignore(root: &Path, scaffolded: &mut Scaffolded) -> Result<(), String> {
+    let path = root.join(".gitignore");
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    let missing: Vec<&str> = GITIGNORE_ENTRIES
+        .into_iter()
+        .filter(|entry| !existing.lines().any(|line| line.trim() == *entry))
+        .collect();
+    if missing.is_empty() {
+        scaffolded.skipped.push(PathBuf::from(".gitignore"));
+        return Ok(());
+    }
+
+    let mut content = existing.clone();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content.push_str("# Morphir build output and caches\n");
+    for entry in missing {
+        content.push_str(entry);
+        content.push('\n');
+    }
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    scaffolded.created.push(PathBuf::from(".gitignore"));
+    Ok(())
+}
+
+/// Config of the workspace containing `dir`, if any
+fn enclosing_workspace(dir: &Path) -> Option<PathBuf> {
+    let config = discover_config(dir)?;
+    MorphirConfig::load(&config)
+        .ok()
+        .filter(MorphirConfig::is_workspace)
+        .map(|_| config)
+}
+
+/// Last segment of an `org/name` package name
+fn base_name(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+/// Check that `name` can be written into `morphir.toml` and used as a path
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid package name '{}'; use letters, digits, '-', '_', '.', and an optional 'org/' prefix",
+            name
+        ))
+    }
+}
+
+fn print_scaffolded(scaffolded: &Scaffolded, path: &Path, json: bool) {
+    if json {
+        let output = serde_json::json!({ "success": true, "project": scaffolded });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return;
+    }
+
+    let kind = match scaffolded.template {
+        ProjectTemplate::Workspace => "workspace",
+        _ => "project",
+    };
+    println!(
+        "Created {} {} ({}) in {}",
+        kind,
+        scaffolded.name,
+        scaffolded.language.name(),
+        scaffolded.root.display()
+    );
+    for file in &scaffolded.created {
+        println!("  {}", file.display());
+    }
+    for file in &scaffolded.skipped {
+        println!("  {} (exists, left unchanged)", file.display());
+    }
+    if let Some(workspace) = &scaffolded.workspace {
+        println!("Added as a member of {}", workspace.display());
+    }
+    println!();
+    if path != Path::new(".") {
+        println!("  cd {}", path.display());
+    }
+    println!("  morphir compile");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(path: &Path, template: ProjectTemplate, language: SourceLanguage) -> InitOptions {
+        InitOptions {
+            path: path.to_path_buf(),
+            name: None,
+            template,
+            language,
+            no_gitignore: false,
+            json: false,
+        }
+    }
+
+    #[test]
+    fn test_sample_module_names_follow_the_language() {
+        assert_eq!(
+            SourceLanguage::Elm.sample_module("order-book"),
+            ("OrderBook".to_string(), "OrderBook.elm".to_string())
+        );
+        assert_eq!(
+            SourceLanguage::Gleam.sample_module("Order-Book"),
+            ("order_book".to_string(), "order_book.gleam".to_string())
+        );
+        assert_eq!(
+            SourceLanguage::Python.sample_module("2fa"),
+            ("model_2fa".to_string(), "model_2fa.py".to_string())
+        );
+        assert!(validate_name("acme/order-book").is_ok());
+        assert!(validate_name("acme/../x").is_err());
+        assert!(validate_name("with \"quotes\"").is_err());
+    }
+
+    #[test]
+    fn test_scaffold_project_and_workspace_member() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("shop");
+        let scaffolded = scaffold(&options(
+            &workspace,
+            ProjectTemplate::Workspace,
+            SourceLanguage::Gleam,
+        ))
+        .unwrap();
+        assert_eq!(
+            scaffolded.created,
+            vec![
+                PathBuf::from("morphir.toml"),
+                PathBuf::from("packages/shop/morphir.toml"),
+                PathBuf::from("packages/shop/src/shop.gleam"),
+                PathBuf::from(".gitignore"),
+            ]
+        );
+        assert!(workspace.join(".morphir/test/fixtures").is_dir());
+        let config = MorphirConfig::load(&workspace.join("morphir.toml")).unwrap();
+        assert!(config.is_workspace());
+
+        // A project created inside the workspace joins it
+        let member = workspace.join("apps/pricing");
+        let mut member_options = options(&member, ProjectTemplate::Library, SourceLanguage::Elm);
+        member_options.name = Some("acme/pricing".to_string());
+        let scaffolded = scaffold(&member_options).unwrap();
+        assert!(scaffolded.workspace.is_some());
+        assert!(member.join("src/Pricing.elm").is_file());
+        assert!(!member.join(".morphir").exists());
+        let config = MorphirConfig::load(&workspace.join("morphir.toml")).unwrap();
+        assert_eq!(
+            config.workspace.unwrap().members,
+            vec!["packages/*".to_string(), "apps/pricing".to_string()]
+        );
+        let project = MorphirConfig::load(&member.join("morphir.toml")).unwrap();
+        assert_eq!(
+            project.project.unwrap().exposed_modules,
+            vec!["Pricing".to_string()]
+        );
+
+        // Existing configurations are not overwritten
+        assert!(scaffold(&member_options).is_err());
+    }
+
+    #[test]
+    fn test_gitignore_entries_are_appended_once() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n.morphir/out/").unwrap();
+        let scaffolded = scaffold(&options(
+            dir.path(),
+            ProjectTemplate::Minimal,
+            SourceLanguage::Python,
+        ))
+        .unwrap();
+        assert!(scaffolded.created.contains(&PathBuf::from(".gitignore")));
+        assert!(dir.path().join("src").is_dir());
+
+        let gitignore = std::fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert_eq!(
+            gitignore,
+            "target/\n.morphir/out/\n\n# Morphir build output and caches\n.morphir/cache/\n.morphir/logs/\n"
+        );
+    }
+}
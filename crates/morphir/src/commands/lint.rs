@@ -0,0 +1,75 @@
+//! Lint command for Morphir IR
+//!
+//! Checks a distribution against the rules of the `lint` builtin, at the
+//! levels configured in the `[lint]` section of the nearest `morphir.toml`.
+//! Without a configuration every rule reports warnings.
+
+use crate::commands::validate::convert_diagnostic;
+use crate::diagnostics::DiagnosticRenderer;
+use crate::error::CliError;
+use crate::output::{Diagnostic, ValidateOutput, json_requested};
+use morphir_builtins::lint::Linter;
+use morphir_common::loader::{LoadedDistribution, load_distribution_from_source};
+use morphir_core::converter;
+use morphir_design::{discover_config, load_config_context};
+use starbase::AppResult;
+use std::path::PathBuf;
+
+/// IR file linted when no input is given
+const DEFAULT_INPUT: &str = "morphir-ir.json";
+
+/// Run the lint command
+pub fn run_lint(input: Option<String>, config_path: Option<String>, json: bool) -> AppResult {
+    let json = json_requested(json);
+    let input = input.unwrap_or_else(|| DEFAULT_INPUT.to_string());
+
+    let config_file = match config_path {
+        Some(path) => Some(PathBuf::from(path)),
+        None => {
+            let start_dir =
+                std::env::current_dir().map_err(|e| CliError::FileSystem { error: e })?;
+            discover_config(&start_dir)
+        }
+    };
+    let options = match config_file {
+        Some(path) => load_config_context(&path)
+            .map_err(|error| CliError::Config { error })?
+            .config
+            .lint
+            .unwrap_or_default(),
+        None => Default::default(),
+    };
+    let linter = Linter::new(&options).map_err(|error| CliError::Config { error })?;
+
+    let distribution = match load_distribution_from_source(&input) {
+        Ok(LoadedDistribution::V4(ir_file)) => ir_file.distribution,
+        Ok(LoadedDistribution::Classic(dist)) => converter::classic_to_v4(&dist).ir.distribution,
+        Err(e) => {
+            return Err(CliError::Validation {
+                message: format!("Failed to load input: {}", e),
+            }
+            .into());
+        }
+    };
+
+    let diagnostics: Vec<Diagnostic> = linter
+        .lint(&distribution)
+        .iter()
+        .map(convert_diagnostic)
+        .collect();
+    let errors = diagnostics.iter().filter(|d| d.level == "error").count();
+    let warnings = diagnostics.len() - errors;
+
+    if json {
+        let output = ValidateOutput {
+            success: errors == 0,
+            input,
+            diagnostics,
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        DiagnosticRenderer::stderr().eprint_all(&diagnostics);
+        println!("{}: {} error(s), {} warning(s)", input, errors, warnings);
+    }
+    Ok((errors > 0).then_some(1))
+}
@@ -0,0 +1,41 @@
+//! Language server command: serve LSP on stdin and stdout
+//!
+//! `morphir lsp` is what an editor launches for Morphir workspaces. The
+//! workspace is the one the editor opens; its projects are compiled with the
+//! frontend extensions the daemon uses, so extensions are loaded from the
+//! configuration around `--workspace` or the current directory.
+
+use super::daemon::{fail, load_context, load_registry};
+use morphir_daemon::workspace::FrontendBuilder;
+use morphir_lsp::LspServer;
+use starbase::AppResult;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::BufReader;
+
+/// Run `morphir lsp`
+pub async fn run_lsp(workspace: Option<PathBuf>) -> AppResult {
+    let start = match workspace.map(Ok).unwrap_or_else(std::env::current_dir) {
+        Ok(start) => start,
+        Err(e) => return Ok(fail(format!("Failed to read current directory: {}", e))),
+    };
+    let ctx = match load_context(start.clone()) {
+        Ok(ctx) => ctx,
+        Err(e) => return Ok(fail(format!("Failed to load configuration: {:#}", e))),
+    };
+    let root = ctx.root.clone().unwrap_or(start);
+    let registry = match load_registry(root, &ctx).await {
+        Ok(registry) => registry,
+        Err(message) => return Ok(fail(message)),
+    };
+
+    let mut server = LspServer::new(Arc::new(FrontendBuilder::new(Arc::new(registry))));
+    let mut reader = BufReader::new(tokio::io::stdin());
+    let mut writer = tokio::io::stdout();
+    if let Err(e) = server.serve(&mut reader, &mut writer).await {
+        return Ok(fail(format!("Language server failed: {}", e)));
+    }
+    // Reading stdin blocks a thread the runtime would wait on, so leave
+    // without waiting
+    std::process::exit(server.exit_code());
+}
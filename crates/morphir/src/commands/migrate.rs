@@ -1,14 +1,25 @@
 //! Migrate Command
 //!
 //! Command to migrate Morphir IR between versions and formats.
+//!
+//! Decorations stored next to the input are carried over to the output, so
+//! annotations made by other tools survive the migration.
 
+use crate::output::{Diagnostic, EventStream, LogFormat, json_requested};
 use crate::tui::JsonPager;
-use morphir_common::loader::{LoadedDistribution, load_distribution};
+use morphir_common::loader::{
+    LoadedDistribution, load_decorations, load_distribution, save_decorations,
+};
 use morphir_common::remote::{RemoteSource, RemoteSourceResolver, ResolveOptions};
 use morphir_common::vfs::OsVfs;
+use morphir_core::converter;
+use morphir_core::ir::v4;
+use morphir_core::ir::v4::serde_v4::omitting_empty_attributes;
 use serde::Serialize;
 use starbase::AppResult;
+use std::cell::Cell;
 use std::path::PathBuf;
+use std::time::Instant;
 
 /// JSON output for migrate command
 #[derive(Serialize)]
@@ -18,6 +29,9 @@ struct MigrateResult {
     output: String,
     source_format: String,
     target_format: String,
+    /// Decorations file written next to the output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decorations: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     warnings: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -30,6 +44,7 @@ impl MigrateResult {
         output: &str,
         source_format: &str,
         target_format: &str,
+        decorations: Option<String>,
         warnings: Vec<String>,
     ) -> Self {
         Self {
@@ -38,6 +53,7 @@ impl MigrateResult {
             output: output.to_string(),
             source_format: source_format.to_string(),
             target_format: target_format.to_string(),
+            decorations,
             warnings,
             error: None,
         }
@@ -50,6 +66,7 @@ impl MigrateResult {
             output: output.to_string(),
             source_format: String::new(),
             target_format: String::new(),
+            decorations: None,
             warnings: Vec::new(),
             error: Some(error.to_string()),
         }
@@ -101,6 +118,19 @@ fn resolve_target_version(version: &str) -> Result<(bool, &'static str), String>
     }
 }
 
+/// Serialize V4 IR for output.
+///
+/// Empty attributes are left out unless `expanded` is set; readers restore
+/// them as defaults, so both forms load the same.
+fn v4_content(ir_file: &v4::IRFile, expanded: bool) -> String {
+    let write = || serde_json::to_string_pretty(ir_file).expect("Failed to serialize");
+    if expanded {
+        write()
+    } else {
+        omitting_empty_attributes(write)
+    }
+}
+
 /// Run the migrate command.
 ///
 /// # Arguments
@@ -110,7 +140,9 @@ fn resolve_target_version(version: &str) -> Result<(bool, &'static str), String>
 /// * `force_refresh` - Force refresh cached remote sources
 /// * `no_cache` - Skip cache entirely for remote sources
 /// * `json` - Output result as JSON
-/// * `expanded` - Use expanded (non-compact) format for V4 output
+/// * `expanded` - Keep empty attributes in V4 output instead of leaving them out
+/// * `log_format` - Stream progress events instead of printing messages
+#[allow(clippy::too_many_arguments)]
 pub fn run_migrate(
     input: String,
     output: Option<PathBuf>,
@@ -118,17 +150,33 @@ pub fn run_migrate(
     force_refresh: bool,
     no_cache: bool,
     json: bool,
-    _expanded: bool, // TODO: Will be used when converter module is re-enabled
+    expanded: bool,
+    log_format: LogFormat,
 ) -> AppResult {
+    let json = json_requested(json);
+    let events = EventStream::new(log_format);
     let output_str = output
         .as_ref()
         .map(|p| p.display().to_string())
         .unwrap_or_else(|| "<console>".to_string());
-    let warnings: Vec<String> = Vec::new(); // TODO: Will collect warnings when converter is re-enabled
+
+    // Stage in progress, reported as failed by `output_error`
+    let stage: Cell<Option<(&str, Instant)>> = Cell::new(None);
+    let start_stage = |name| {
+        if let Some((previous, started)) = stage.take() {
+            events.finished(previous, started, true);
+        }
+        stage.set(Some((name, events.started(name))));
+    };
 
     // Helper to output error
     let output_error = |msg: &str| {
-        if json {
+        if let Some((name, started)) = stage.take() {
+            events.finished(name, started, false);
+        }
+        if events.is_enabled() {
+            events.result(&MigrateResult::error(&input, &output_str, msg));
+        } else if json {
             let result = MigrateResult::error(&input, &output_str, msg);
             println!("{}", serde_json::to_string_pretty(&result).unwrap());
         } else {
@@ -136,7 +184,16 @@ pub fn run_migrate(
         }
     };
 
+    // Events and the migrated IR cannot share stdout
+    if events.is_enabled() && output.is_none() {
+        output_error("--log-format ndjson requires --output");
+        return Ok(Some(1));
+    }
+    // Messages on stderr would interleave with the event stream
+    let json = json || events.is_enabled();
+
     // Parse input source
+    start_stage("load");
     let source = match RemoteSource::parse(&input) {
         Ok(s) => s,
         Err(e) => {
@@ -203,7 +260,14 @@ pub fn run_migrate(
         }
     };
 
-    // Convert
+    let decorations = match load_decorations(&vfs, &local_path) {
+        Ok(decorations) => decorations,
+        Err(e) => {
+            output_error(&format!("Failed to load decorations: {:#}", e));
+            return Ok(Some(1));
+        }
+    };
+
     // Resolve target version
     let (target_v4, target_format) = match resolve_target_version(&target_version) {
         Ok(result) => result,
@@ -213,70 +277,120 @@ pub fn run_migrate(
         }
     };
 
-    match dist {
+    start_stage("convert");
+    let (source_format, content, mut warnings) = match dist {
         LoadedDistribution::Classic(dist) => {
-            let source_format = "classic";
             if target_v4 {
-                // Classic -> V4 conversion is not yet implemented
-                // The converter module is currently disabled pending type system updates
-                output_error(
-                    "Classic -> V4 conversion is not yet implemented. \
-                     The converter module is currently being updated. \
-                     Use --target=classic to copy the file as-is.",
-                );
-                return Ok(Some(1));
+                if !json {
+                    eprintln!("Input is Classic, Target is V4. Converting...");
+                }
+                let conversion = converter::classic_to_v4(&dist);
+                let content = v4_content(&conversion.ir, expanded);
+                ("classic", content, conversion.warnings)
             } else {
                 if !json {
                     eprintln!("Input is Classic, Target is Classic. Copying...");
                 }
                 let content = serde_json::to_string_pretty(&dist).expect("Failed to serialize");
-                let title = format!("morphir-ir.json (Classic format, from {})", input);
-                write_or_display(&output, &content, json, &title);
-
-                if json && output.is_some() {
-                    let result = MigrateResult::success(
-                        &input,
-                        &output_str,
-                        source_format,
-                        target_format,
-                        warnings,
-                    );
-                    println!("{}", serde_json::to_string_pretty(&result).unwrap());
-                }
+                ("classic", content, Vec::new())
             }
         }
         LoadedDistribution::V4(ir_file) => {
-            let source_format = "v4";
-            if !target_v4 {
-                // V4 -> Classic conversion is not yet implemented
-                // The converter module is currently disabled pending type system updates
-                output_error(
-                    "V4 -> Classic conversion is not yet implemented. \
-                     The converter module is currently being updated. \
-                     Use --target=v4 to copy the file as-is.",
-                );
-                return Ok(Some(1));
-            } else {
+            if target_v4 {
                 if !json {
-                    eprintln!("Input is V4, Target is V4. Copying...");
+                    eprintln!(
+                        "Input is V4 ({}), Target is V4. Copying...",
+                        ir_file.distribution.kind()
+                    );
                 }
-                let content = serde_json::to_string_pretty(&ir_file).expect("Failed to serialize");
-                let title = format!("morphir-ir.json (V4 format, from {})", input);
-                write_or_display(&output, &content, json, &title);
-
-                if json && output.is_some() {
-                    let result = MigrateResult::success(
-                        &input,
-                        &output_str,
-                        source_format,
-                        target_format,
-                        warnings,
+                let content = v4_content(&ir_file, expanded);
+                ("v4", content, Vec::new())
+            } else {
+                if !json {
+                    eprintln!(
+                        "Input is V4 ({}), Target is Classic. Converting...",
+                        ir_file.distribution.kind()
                     );
-                    println!("{}", serde_json::to_string_pretty(&result).unwrap());
                 }
+                let conversion = match converter::v4_to_classic(&ir_file) {
+                    Ok(conversion) => conversion,
+                    Err(e) => {
+                        output_error(&format!("Failed to convert to Classic: {}", e));
+                        return Ok(Some(1));
+                    }
+                };
+                let content =
+                    serde_json::to_string_pretty(&conversion.ir).expect("Failed to serialize");
+                ("v4", content, conversion.warnings)
+            }
+        }
+    };
+
+    if decorations.is_some() && output.is_none() {
+        warnings.push("Decorations are only carried over when writing to --output".to_string());
+    }
+    if !json {
+        for warning in &warnings {
+            eprintln!("warning: {}", warning);
+        }
+    }
+    for warning in &warnings {
+        events.diagnostic(&Diagnostic {
+            level: "warning".to_string(),
+            code: None,
+            message: warning.to_string(),
+            file: None,
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            related: Vec::new(),
+            fixes: Vec::new(),
+        });
+    }
+
+    let format_label = if target_v4 { "V4" } else { "Classic" };
+    let title = format!("morphir-ir.json ({} format, from {})", format_label, input);
+    start_stage("write");
+    write_or_display(&output, &content, json, &title);
+    let mut decorations_written = None;
+    if let (Some(path), Some(decorations)) = (&output, &decorations) {
+        match save_decorations(&vfs, path, decorations) {
+            Ok(written) => decorations_written = Some(written),
+            Err(e) => {
+                output_error(&format!("Failed to write decorations: {:#}", e));
+                return Ok(Some(1));
             }
         }
     }
+    if let Some((name, started)) = stage.take() {
+        events.finished(name, started, true);
+    }
+    if let Some(path) = &output {
+        events.artifact(path);
+    }
+    if let Some(path) = &decorations_written {
+        events.artifact(path);
+        if !json {
+            eprintln!("Carried decorations over to {}", path.display());
+        }
+    }
+
+    if json && output.is_some() {
+        let result = MigrateResult::success(
+            &input,
+            &output_str,
+            source_format,
+            target_format,
+            decorations_written.map(|path| path.display().to_string()),
+            warnings,
+        );
+        if events.is_enabled() {
+            events.result(&result);
+        } else {
+            println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        }
+    }
 
     if !json {
         eprintln!("Migration complete.");
@@ -1,21 +1,71 @@
+pub mod build;
+pub mod cache;
+pub mod check;
 pub mod compile;
+pub mod config;
+pub mod daemon;
+pub mod deps;
 pub mod dist;
+pub mod docs;
 pub mod extension;
+pub mod extension_new;
+pub mod fix;
 pub mod generate;
 pub mod gleam;
+pub mod graph;
+pub mod init;
+pub mod lint;
+pub mod lsp;
 pub mod migrate;
+pub mod package;
+pub mod query;
+pub mod rename;
+pub mod repl;
+pub mod restructure;
+pub mod run;
+pub mod sample;
 pub mod schema;
+pub mod source;
+pub mod spec_diff;
+pub mod stats;
+pub mod test;
+pub mod text;
 pub mod tool;
 pub mod transform;
 pub mod validate;
 pub mod version;
 
+pub use build::*;
+pub use cache::*;
+pub use check::*;
 pub use compile::*;
+pub use config::*;
+pub use daemon::*;
+pub use deps::*;
 pub use dist::*;
+pub use docs::*;
 pub use extension::*;
+pub use extension_new::*;
+pub use fix::*;
 pub use generate::*;
 pub use gleam::*;
+pub use graph::*;
+pub use init::*;
+pub use lint::*;
+pub use lsp::*;
 pub use migrate::*;
+pub use package::*;
+pub use query::*;
+pub use rename::*;
+pub use repl::*;
+pub use restructure::*;
+pub use run::*;
+pub use sample::*;
+pub use source::*;
+pub use spec_diff::*;
+pub use stats::*;
+pub use test::*;
+pub use text::*;
 pub use tool::*;
 pub use transform::*;
 pub use validate::*;
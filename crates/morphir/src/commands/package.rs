@@ -0,0 +1,307 @@
+//! Package commands: publish to and install from a package registry
+//!
+//! `morphir publish` compiles the project (or takes `--input` IR) and
+//! uploads it with the `[project]` metadata. `morphir package search` lists
+//! matching packages, and `morphir package add` resolves a package's newest
+//! matching version, caches its IR, and declares it in `morphir.toml`.
+
+use crate::commands::deps::output_error;
+use crate::output::{OutputFormat, json_requested, print_table};
+use crate::pipeline::Pipeline;
+use morphir_common::config::MorphirConfig;
+use morphir_common::config::edit::set_dependency;
+use morphir_common::loader::load_ir;
+use morphir_common::registry::{
+    LATEST, PackageMetadata, Published, RegistryClient, RegistryConfig,
+};
+use morphir_design::discover_config;
+use serde::Serialize;
+use starbase::AppResult;
+use std::path::PathBuf;
+
+/// Options for `morphir publish`
+#[derive(Debug, Default)]
+pub struct PublishOptions {
+    /// IR to publish instead of compiling the project
+    pub input: Option<String>,
+    /// Path to configuration file
+    pub config_path: Option<String>,
+    /// Registry URL overriding `[registry] url`
+    pub registry: Option<String>,
+    /// Check and print what would be published without uploading
+    pub dry_run: bool,
+    /// Output JSON format
+    pub json: bool,
+}
+
+/// JSON output of `morphir publish`
+#[derive(Serialize)]
+struct PublishOutput<'a> {
+    success: bool,
+    dry_run: bool,
+    registry: Option<&'a str>,
+    package: &'a PackageMetadata,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    published: Option<&'a Published>,
+}
+
+/// JSON output of `morphir package add`
+#[derive(Serialize)]
+struct AddOutput<'a> {
+    success: bool,
+    name: &'a str,
+    version: &'a str,
+    requirement: &'a str,
+    dev: bool,
+    config: PathBuf,
+    /// IR of the resolved version in the package cache
+    path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replaced: Option<String>,
+}
+
+/// Run `morphir publish`
+pub async fn run_publish(options: PublishOptions) -> AppResult {
+    let PublishOptions {
+        input,
+        config_path,
+        registry,
+        dry_run,
+        json,
+    } = options;
+    let json = json_requested(json);
+
+    let mut pipeline = Pipeline::new();
+    if let Some(path) = config_path {
+        pipeline = pipeline.with_config(path);
+    }
+    let ctx = pipeline.load_config()?;
+    let Some(project) = ctx.config.project.as_ref() else {
+        let message = format!(
+            "{} has no [project] section to publish",
+            ctx.config_path.display()
+        );
+        return Ok(Some(output_error(json, &message)));
+    };
+    let metadata = PackageMetadata::from_project(project, &ctx.config.dependencies);
+    if let Err(e) = metadata.validate() {
+        return Ok(Some(output_error(json, &e.to_string())));
+    }
+
+    let ir = match input {
+        Some(input) => load_ir(&PathBuf::from(&input)),
+        None => {
+            let compiled = pipeline.compile(&ctx).await?;
+            if !compiled.success {
+                let message = compiled
+                    .error
+                    .unwrap_or_else(|| "Compilation failed".to_string());
+                return Ok(Some(output_error(json, &message)));
+            }
+            match compiled.ir {
+                Some(ir) => Ok(ir),
+                None => load_ir(&compiled.output_path),
+            }
+        }
+    };
+    let ir = match ir {
+        Ok(ir) => ir,
+        Err(e) => {
+            let message = format!("Failed to load IR: {}", e);
+            return Ok(Some(output_error(json, &message)));
+        }
+    };
+
+    // The registry client blocks, which must not happen on the async
+    // runtime's worker directly
+    let uploaded = tokio::task::block_in_place(|| {
+        let client = client_for(&ctx.config, registry)?;
+        let published = if dry_run {
+            None
+        } else {
+            Some(client.publish(&metadata, &ir).map_err(|e| e.to_string())?)
+        };
+        Ok::<_, String>((client.url().to_string(), published))
+    });
+    let (url, published) = match uploaded {
+        Ok(uploaded) => uploaded,
+        Err(message) => return Ok(Some(output_error(json, &message))),
+    };
+
+    if json {
+        let output = PublishOutput {
+            success: true,
+            dry_run,
+            registry: Some(&url),
+            package: &metadata,
+            published: published.as_ref(),
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else if dry_run {
+        println!(
+            "Would publish {} {} to {}",
+            metadata.name, metadata.version, url
+        );
+        for (name, requirement) in &metadata.dependencies {
+            println!("  depends on {} {}", name, requirement);
+        }
+    } else {
+        println!(
+            "Published {} {} to {}",
+            metadata.name, metadata.version, url
+        );
+    }
+    Ok(None)
+}
+
+/// Run `morphir package search`
+pub fn run_package_search(
+    query: String,
+    config_path: Option<String>,
+    registry: Option<String>,
+    json: bool,
+) -> AppResult {
+    let json = json_requested(json);
+    let config = match load_config(config_path) {
+        Ok((_, config)) => config,
+        Err(message) => return Ok(Some(output_error(json, &message))),
+    };
+    let client = match client_for(&config, registry) {
+        Ok(client) => client,
+        Err(message) => return Ok(Some(output_error(json, &message))),
+    };
+    let packages = match client.search(&query) {
+        Ok(packages) => packages,
+        Err(e) => return Ok(Some(output_error(json, &e.to_string()))),
+    };
+
+    if json {
+        let output = serde_json::json!({ "success": true, "packages": packages });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else if OutputFormat::global() == OutputFormat::Table {
+        let rows: Vec<Vec<String>> = packages
+            .iter()
+            .map(|package| {
+                vec![
+                    package.name.clone(),
+                    package.version.clone(),
+                    package.description.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        print_table(&["NAME", "VERSION", "DESCRIPTION"], &rows);
+    } else if packages.is_empty() {
+        println!("No packages match \"{}\"", query);
+    } else {
+        for package in &packages {
+            match &package.description {
+                Some(description) => {
+                    println!("{} {} - {}", package.name, package.version, description)
+                }
+                None => println!("{} {}", package.name, package.version),
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Run `morphir package add`
+pub fn run_package_add(
+    package: String,
+    dev: bool,
+    config_path: Option<String>,
+    registry: Option<String>,
+    json: bool,
+) -> AppResult {
+    let json = json_requested(json);
+    let (name, version) = match package.split_once('@') {
+        Some((name, requirement)) => (name.to_string(), Some(requirement.to_string())),
+        None => (package, None),
+    };
+    let (config_file, config) = match load_config(config_path) {
+        Ok(loaded) => loaded,
+        Err(message) => return Ok(Some(output_error(json, &message))),
+    };
+    let Some(config_file) = config_file else {
+        let message = "No morphir.toml found to add the dependency to";
+        return Ok(Some(output_error(json, message)));
+    };
+    let client = match client_for(&config, registry) {
+        Ok(client) => client,
+        Err(message) => return Ok(Some(output_error(json, &message))),
+    };
+
+    let requested = version.as_deref().unwrap_or(LATEST);
+    let (resolved, path) = match client.fetch(&name, requested) {
+        Ok(fetched) => fetched,
+        Err(e) => return Ok(Some(output_error(json, &e.to_string()))),
+    };
+    // Without an explicit requirement, accept compatible updates of the
+    // version found
+    let requirement = version.unwrap_or_else(|| format!("^{}", resolved.version));
+    let replaced = match set_dependency(&config_file, &name, &requirement, dev) {
+        Ok(replaced) => replaced,
+        Err(e) => return Ok(Some(output_error(json, &e.to_string()))),
+    };
+
+    if json {
+        let output = AddOutput {
+            success: true,
+            name: &name,
+            version: &resolved.version,
+            requirement: &requirement,
+            dev,
+            config: config_file,
+            path,
+            replaced,
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        let section = if dev {
+            "dev-dependencies"
+        } else {
+            "dependencies"
+        };
+        println!(
+            "Added {} = \"{}\" to [{}] in {} (resolved {})",
+            name,
+            requirement,
+            section,
+            config_file.display(),
+            resolved.version
+        );
+    }
+    Ok(None)
+}
+
+/// The config file found from `config_path` or the current directory, if
+/// any, and its configuration
+pub(crate) fn load_config(
+    config_path: Option<String>,
+) -> Result<(Option<PathBuf>, MorphirConfig), String> {
+    let config_file = match config_path {
+        Some(cfg) => Some(PathBuf::from(cfg)),
+        None => std::env::current_dir()
+            .ok()
+            .and_then(|dir| discover_config(&dir)),
+    };
+    let config = match &config_file {
+        Some(path) => MorphirConfig::load(path)
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?,
+        None => MorphirConfig::default(),
+    };
+    Ok((config_file, config))
+}
+
+/// Registry client for `config`, with `registry` overriding its URL
+pub(crate) fn client_for(
+    config: &MorphirConfig,
+    registry: Option<String>,
+) -> Result<RegistryClient, String> {
+    let mut registry_config: RegistryConfig = config.registry.clone().unwrap_or_default();
+    if registry.is_some() {
+        registry_config.url = registry;
+    }
+    RegistryClient::for_workspace(Some(&registry_config), config.sources.as_ref())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,58 @@
+//! Query Command
+//!
+//! `ir query` selects the modules, types, constructors, or values of a
+//! distribution that match a query and prints them as a JSON array, for
+//! audits over large models.
+
+use crate::commands::text::{input_or_project_ir, load};
+use morphir_core::ir::v4::Query;
+use starbase::AppResult;
+use std::path::PathBuf;
+
+/// Options for the `ir query` command
+#[derive(Debug, Default)]
+pub struct QueryCommandOptions {
+    /// The query, e.g. `values[arity > 3]`
+    pub query: String,
+    /// Input file, directory, or remote source; the project's compiled IR if
+    /// omitted
+    pub input: Option<String>,
+    /// Also select the definitions of dependencies
+    pub external: bool,
+    /// Output file (stdout if omitted)
+    pub output: Option<PathBuf>,
+}
+
+/// Run the `ir query` command.
+pub fn run_ir_query(options: QueryCommandOptions) -> AppResult {
+    let query = match Query::parse(&options.query) {
+        Ok(query) => query.with_dependencies(options.external),
+        Err(e) => {
+            eprintln!("Invalid query at column {}: {}", e.column, e.message);
+            eprintln!("  {}", options.query);
+            eprintln!("  {}^", " ".repeat(e.column.saturating_sub(1)));
+            return Ok(Some(1));
+        }
+    };
+    let input = input_or_project_ir(options.input)?;
+    let distribution = match load(&input) {
+        Ok(distribution) => distribution,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(Some(1));
+        }
+    };
+
+    let results = query.run(&distribution);
+    let content = format!("{}\n", serde_json::to_string_pretty(&results).unwrap());
+    match &options.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &content) {
+                eprintln!("Failed to write {:?}: {}", path, e);
+                return Ok(Some(1));
+            }
+        }
+        None => print!("{}", content),
+    }
+    Ok(None)
+}
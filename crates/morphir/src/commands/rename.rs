@@ -0,0 +1,155 @@
+//! Rename Command
+//!
+//! `ir rename` renames a module, type, constructor, or value of a
+//! distribution, rewrites every reference to it, and records the change in
+//! the refactor log next to the IR written.
+
+use crate::commands::text::{input_or_project_ir, load};
+use morphir_common::loader::record_refactor;
+use morphir_common::vfs::OsVfs;
+use morphir_core::ir::v4::{Distribution, IRFile, RefactorTransaction, SymbolKind, rename};
+use starbase::AppResult;
+use std::path::{Path, PathBuf};
+
+/// Kind of definition `ir rename` renames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SymbolKindArg {
+    Module,
+    Type,
+    Constructor,
+    Value,
+}
+
+impl From<SymbolKindArg> for SymbolKind {
+    fn from(kind: SymbolKindArg) -> Self {
+        match kind {
+            SymbolKindArg::Module => SymbolKind::Module,
+            SymbolKindArg::Type => SymbolKind::Type,
+            SymbolKindArg::Constructor => SymbolKind::Constructor,
+            SymbolKindArg::Value => SymbolKind::Value,
+        }
+    }
+}
+
+/// Options for the `ir rename` command
+#[derive(Debug, Default)]
+pub struct RenameCommandOptions {
+    /// Module (`package:module`) or definition (`package:module#name`) to
+    /// rename
+    pub old: String,
+    /// Its new name, of the same form
+    pub new: String,
+    /// Input file, directory, or remote source; the project's compiled IR if
+    /// omitted
+    pub input: Option<String>,
+    /// What `old` names, where a type, constructor, and value share it
+    pub kind: Option<SymbolKindArg>,
+    /// Output file; the input file if omitted
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything
+    pub dry_run: bool,
+    /// Print the transaction as JSON
+    pub json: bool,
+}
+
+/// Run the `ir rename` command.
+pub fn run_ir_rename(options: RenameCommandOptions) -> AppResult {
+    let input = input_or_project_ir(options.input)?;
+    let output = match refactor_output(&input, options.output, options.dry_run) {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(Some(1));
+        }
+    };
+    let mut distribution = match load(&input) {
+        Ok(distribution) => distribution,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(Some(1));
+        }
+    };
+    let transaction = match rename(
+        &mut distribution,
+        &options.old,
+        &options.new,
+        options.kind.map(SymbolKind::from),
+    ) {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            eprintln!("Cannot rename {}: {}", options.old, e);
+            return Ok(Some(1));
+        }
+    };
+    finish_refactor(
+        distribution,
+        &transaction,
+        &output,
+        options.dry_run,
+        options.json,
+    )
+}
+
+/// File a refactoring of `input` is written to: `output`, or else the input
+/// itself if it is an IR file
+pub(crate) fn refactor_output(
+    input: &str,
+    output: Option<PathBuf>,
+    dry_run: bool,
+) -> Result<PathBuf, String> {
+    match output {
+        Some(output) => Ok(output),
+        None if Path::new(input).is_file() => Ok(PathBuf::from(input)),
+        None if dry_run => Ok(PathBuf::new()),
+        None => Err(format!("{} is not an IR file; pass --output", input)),
+    }
+}
+
+/// Write `distribution` to `output` and log `transaction` next to it, unless
+/// this is a dry run, then report the transaction
+pub(crate) fn finish_refactor(
+    distribution: Distribution,
+    transaction: &RefactorTransaction,
+    output: &Path,
+    dry_run: bool,
+    json: bool,
+) -> AppResult {
+    if !dry_run {
+        let ir_file = IRFile {
+            format_version: Default::default(),
+            distribution,
+        };
+        let content = format!("{}\n", serde_json::to_string_pretty(&ir_file).unwrap());
+        if let Err(e) = std::fs::write(output, &content) {
+            eprintln!("Failed to write {:?}: {}", output, e);
+            return Ok(Some(1));
+        }
+        if let Err(e) = record_refactor(&OsVfs, output, transaction.clone()) {
+            eprintln!("Failed to record the refactoring: {:#}", e);
+            return Ok(Some(1));
+        }
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(transaction).unwrap());
+    } else {
+        print_summary(transaction, dry_run);
+    }
+    Ok(None)
+}
+
+fn print_summary(transaction: &RefactorTransaction, dry_run: bool) {
+    for rename in &transaction.renames {
+        println!("{} {} -> {}", rename.kind, rename.from, rename.to);
+    }
+    for exposed in &transaction.exposed {
+        println!("made public {}", exposed);
+    }
+    let verb = if dry_run { "Would rewrite" } else { "Rewrote" };
+    println!(
+        "{} {} reference{} (transaction {})",
+        verb,
+        transaction.references,
+        if transaction.references == 1 { "" } else { "s" },
+        transaction.tx_id
+    );
+}
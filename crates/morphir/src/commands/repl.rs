@@ -0,0 +1,643 @@
+//! REPL Command
+//!
+//! An interactive session over a distribution: evaluates `.mir` expressions
+//! with the IR evaluator, lists and prints the modules, types, and values of
+//! the model, and reloads the distribution when its file changes. Lines are
+//! edited with history and tab completion of FQNames.
+
+use super::run::{DEFAULT_INPUT, EVAL_STACK_SIZE};
+use super::text::show;
+use crate::tui::LineEditor;
+use morphir_common::loader::{
+    LoadedDistribution, attach_dependencies, load_distribution_from_source,
+};
+use morphir_core::ir::v4::text;
+use morphir_core::ir::v4::{
+    Distribution, Type, TypeAttributes, TypeDefinition, TypeSpecification, Value, ValueAttributes,
+    ValueSpecification,
+};
+use morphir_core::naming::{FQName, Name, Path};
+use morphir_runtime::{Evaluator, RuntimeValue};
+use starbase::AppResult;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Column width results are pretty-printed to
+const DISPLAY_WIDTH: usize = 80;
+
+const COMMANDS: &[&str] = &[
+    ":help", ":modules", ":module", ":types", ":values", ":show", ":type", ":reload", ":quit",
+];
+
+const HELP: &str = "\
+Enter a .mir expression to evaluate it, or `name = expression` to bind its
+value for later expressions. Names of the open module need no qualification.
+
+  :modules            List the modules of the package and its dependencies
+  :module [module]    Open a module, e.g. `orders` or `my/pkg:orders`; close it without one
+  :types [module]     List the types of a module, the open one by default
+  :values [module]    List the values of a module, the open one by default
+  :show <name>        Print a definition, or a whole module, as .mir text
+  :type <name>        Print the signature of a value
+  :reload             Load the distribution again
+  :quit               End the session (also Ctrl-D)
+
+Tab completes commands, FQNames, and names in scope.";
+
+/// Options for the `repl` command
+#[derive(Debug, Default)]
+pub struct ReplOptions {
+    /// Input file, directory, or remote source
+    pub input: Option<String>,
+    /// Dependency sources; only their specifications are available
+    pub dependencies: Vec<String>,
+    /// Module to open at the start
+    pub module: Option<String>,
+}
+
+/// What executing a line produced
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Output(String),
+    Nothing,
+    Quit,
+}
+
+/// State of an interactive session
+struct Session {
+    input: String,
+    dependencies: Vec<String>,
+    distribution: Distribution,
+    /// Local file the distribution was loaded from, and when it last changed
+    watched: Option<(PathBuf, SystemTime)>,
+    /// Package and module whose names need no qualification
+    module: Option<(Path, Path)>,
+    /// Values bound with `name = expression`, latest last
+    bindings: Vec<(String, RuntimeValue)>,
+}
+
+/// Run the `repl` command.
+pub fn run_repl(options: ReplOptions) -> AppResult {
+    // Evaluation runs on the session thread, which needs the larger stack
+    let session = std::thread::Builder::new()
+        .name("morphir-repl".to_string())
+        .stack_size(EVAL_STACK_SIZE)
+        .spawn(move || repl(options));
+    let result = match session {
+        Ok(handle) => handle
+            .join()
+            .unwrap_or_else(|_| Err("Session panicked".to_string())),
+        Err(e) => Err(format!("Failed to start the session: {}", e)),
+    };
+    match result {
+        Ok(()) => Ok(None),
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(Some(1))
+        }
+    }
+}
+
+fn repl(options: ReplOptions) -> Result<(), String> {
+    let input = options.input.unwrap_or_else(|| DEFAULT_INPUT.to_string());
+    let mut session = Session::load(input, options.dependencies)?;
+    if let Some(module) = &options.module {
+        session.open(module)?;
+    }
+    println!(
+        "Loaded {} from {}. Type :help for commands.",
+        session.distribution.package_name().as_path(),
+        session.input
+    );
+
+    let mut editor = LineEditor::new();
+    if let Some(home) = dirs::home_dir() {
+        editor = editor.with_history_file(home.join(".morphir").join("repl_history"));
+    }
+    loop {
+        let prompt = session.prompt();
+        let line = editor
+            .read_line(&prompt, &|word| session.completions(word))
+            .map_err(|e| format!("Failed to read input: {}", e))?;
+        let Some(line) = line else {
+            return Ok(());
+        };
+        editor.add_history(&line);
+        match session.reload_if_changed() {
+            Ok(Some(message)) => println!("{}", message),
+            Ok(None) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+        match session.execute(&line) {
+            Ok(Reply::Output(output)) => println!("{}", output),
+            Ok(Reply::Nothing) => {}
+            Ok(Reply::Quit) => return Ok(()),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+impl Session {
+    fn new(input: String, dependencies: Vec<String>, distribution: Distribution) -> Self {
+        let watched =
+            modified(std::path::Path::new(&input)).map(|time| (PathBuf::from(&input), time));
+        Self {
+            input,
+            dependencies,
+            distribution,
+            watched,
+            module: None,
+            bindings: Vec::new(),
+        }
+    }
+
+    fn load(input: String, dependencies: Vec<String>) -> Result<Self, String> {
+        let distribution = load(&input, &dependencies)?;
+        Ok(Self::new(input, dependencies, distribution))
+    }
+
+    fn prompt(&self) -> String {
+        match &self.module {
+            Some((_, module)) => format!("{}> ", module),
+            None => "morphir> ".to_string(),
+        }
+    }
+
+    fn reload(&mut self) -> Result<String, String> {
+        // Watch for the next change even if this version fails to load
+        self.watched = modified(std::path::Path::new(&self.input))
+            .map(|time| (PathBuf::from(&self.input), time));
+        self.distribution = load(&self.input, &self.dependencies)?;
+        if let Some((package, module)) = self.module.take() {
+            // Keep the module open if it still exists
+            let _ = self.open(&format!("{}:{}", package, module));
+        }
+        Ok(format!("Reloaded {}", self.input))
+    }
+
+    /// Reload the distribution if its file changed since it was loaded
+    fn reload_if_changed(&mut self) -> Result<Option<String>, String> {
+        let changed = match &self.watched {
+            Some((path, time)) => modified(path).is_some_and(|now| now != *time),
+            None => false,
+        };
+        if changed {
+            self.reload().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn execute(&mut self, line: &str) -> Result<Reply, String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Reply::Nothing);
+        }
+        if let Some(command) = line.strip_prefix(':') {
+            let (command, argument) = command
+                .split_once(char::is_whitespace)
+                .map_or((command, ""), |(c, a)| (c, a.trim()));
+            return self.command(command, argument);
+        }
+        if let Some((name, expression)) = line.split_once('=')
+            && is_binding_name(name.trim())
+            && !expression.trim().is_empty()
+        {
+            let value = self.evaluate(expression)?;
+            let name = name.trim().to_string();
+            let output = format!("{} = {}", name, value.pretty(DISPLAY_WIDTH));
+            self.bindings.retain(|(bound, _)| *bound != name);
+            self.bindings.push((name, value));
+            return Ok(Reply::Output(output));
+        }
+        Ok(Reply::Output(self.evaluate(line)?.pretty(DISPLAY_WIDTH)))
+    }
+
+    fn command(&mut self, command: &str, argument: &str) -> Result<Reply, String> {
+        let output = match command {
+            "help" | "h" | "?" => HELP.to_string(),
+            "quit" | "q" => return Ok(Reply::Quit),
+            "reload" | "r" => self.reload()?,
+            "modules" => self.modules().join("\n"),
+            "module" | "m" if argument.is_empty() => {
+                self.module = None;
+                return Ok(Reply::Nothing);
+            }
+            "module" | "m" => {
+                self.open(argument)?;
+                return Ok(Reply::Nothing);
+            }
+            "types" | "values" => {
+                let (package, module) = self.module_named(argument)?;
+                self.members(&package, &module, command == "types")
+                    .join("\n")
+            }
+            "show" | "s" if !argument.is_empty() => {
+                show(&self.distribution, &self.qualify(argument)?)?
+                    .trim_end()
+                    .to_string()
+            }
+            "type" | "t" if !argument.is_empty() => self.signature(argument)?,
+            "show" | "s" | "type" | "t" => return Err(format!(":{} needs a name", command)),
+            _ => {
+                return Err(format!(
+                    "Unknown command :{}; type :help for commands",
+                    command
+                ));
+            }
+        };
+        Ok(Reply::Output(output))
+    }
+
+    fn evaluate(&self, source: &str) -> Result<RuntimeValue, String> {
+        let value = text::parse_value(source).map_err(|e| format!("Parse error at {}", e))?;
+        let mut evaluator = Evaluator::new(&self.distribution);
+        if let Some((package, module)) = &self.module {
+            evaluator = evaluator.with_open_module(package.clone(), module.clone());
+        }
+        evaluator
+            .evaluate_value_with(&value, &self.bindings)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Modules of the package, then those of its dependencies, as
+    /// `package:module`
+    fn modules(&self) -> Vec<String> {
+        let package = self.distribution.package_name().as_path().to_string();
+        let mut modules: Vec<String> = match self.distribution.definition() {
+            Some(def) => def
+                .modules
+                .keys()
+                .map(|module| format!("{}:{}", package, Path::new(module)))
+                .collect(),
+            None => self
+                .distribution
+                .specification()
+                .modules
+                .keys()
+                .map(|module| format!("{}:{}", package, Path::new(module)))
+                .collect(),
+        };
+        for (dependency, spec) in self.distribution.dependencies() {
+            modules.extend(
+                spec.modules
+                    .keys()
+                    .map(|module| format!("{}:{}", Path::new(dependency), Path::new(module))),
+            );
+        }
+        modules
+    }
+
+    /// Open the module `name`, `module` of the package or `package:module`
+    fn open(&mut self, name: &str) -> Result<(), String> {
+        self.module = Some(self.module_named(name)?);
+        Ok(())
+    }
+
+    /// Package and module `name` refers to; the open module if it is empty
+    fn module_named(&self, name: &str) -> Result<(Path, Path), String> {
+        if name.is_empty() {
+            return self
+                .module
+                .clone()
+                .ok_or_else(|| "No module is open; name one, or open it with :module".to_string());
+        }
+        let qualified = if name.contains(':') {
+            name.to_string()
+        } else {
+            format!("{}:{}", self.distribution.package_name().as_path(), name)
+        };
+        let (package, module) = qualified.split_once(':').unwrap_or((&qualified, ""));
+        let (package, module) = (Path::new(package), Path::new(module));
+        let wanted = format!("{}:{}", package, module);
+        if self.modules().contains(&wanted) {
+            Ok((package, module))
+        } else {
+            Err(format!("No module {}", wanted))
+        }
+    }
+
+    /// Names of the types or values of a module, as `.mir` text writes them
+    /// qualified
+    fn members(&self, package: &Path, module: &Path, types: bool) -> Vec<String> {
+        let mut members = Vec::new();
+        let mut add = |key: &str, is_type: bool| {
+            let fqname = FQName::new(package.clone(), module.clone(), Name::from(key));
+            members.push(qualified_text(fqname, is_type));
+        };
+        let same_module = |key: &String| Path::new(key).to_string() == module.to_string();
+        if *package == *self.distribution.package_name().as_path()
+            && let Some(def) = self.distribution.definition()
+        {
+            if let Some((_, m)) = def.modules.iter().find(|(key, _)| same_module(key)) {
+                if types {
+                    m.value.types.keys().for_each(|key| add(key, true));
+                } else {
+                    m.value.values.keys().for_each(|key| add(key, false));
+                }
+            }
+            return members;
+        }
+        let spec = if *package == *self.distribution.package_name().as_path() {
+            Some(self.distribution.specification())
+        } else {
+            self.distribution
+                .dependencies()
+                .iter()
+                .find(|(key, _)| Path::new(key) == *package)
+                .map(|(_, spec)| spec.clone())
+        };
+        if let Some((_, m)) = spec
+            .as_ref()
+            .and_then(|spec| spec.modules.iter().find(|(key, _)| same_module(key)))
+        {
+            if types {
+                m.types.keys().for_each(|key| add(key, true));
+            } else {
+                m.values.keys().for_each(|key| add(key, false));
+            }
+        }
+        members
+    }
+
+    /// `name` qualified by the open module unless it already is
+    fn qualify(&self, name: &str) -> Result<String, String> {
+        if name.contains(':') {
+            return Ok(name.to_string());
+        }
+        match &self.module {
+            Some((package, module)) => Ok(format!("{}:{}#{}", package, module, name)),
+            None => Err(format!(
+                "'{}' is not qualified and no module is open; use package:module#{}",
+                name, name
+            )),
+        }
+    }
+
+    /// Signature of the value `name`, as a `.mir` specification
+    fn signature(&self, name: &str) -> Result<String, String> {
+        let fqname = FQName::from_canonical_string(&self.qualify(name)?)?;
+        let local = fqname.local_name.to_string();
+        let same = |key: &str| Name::from(key).to_string() == local;
+        let same_module = |key: &str| Path::new(key) == fqname.module_path;
+        if fqname.package_path == *self.distribution.package_name().as_path()
+            && let Some(def) = self.distribution.definition()
+        {
+            let found = def
+                .modules
+                .iter()
+                .filter(|(key, _)| same_module(key))
+                .flat_map(|(_, module)| module.value.values.iter())
+                .find(|(key, _)| same(key));
+            if let Some((key, value)) = found {
+                let spec = ValueSpecification {
+                    inputs: value
+                        .value
+                        .input_types
+                        .iter()
+                        .map(|(name, entry)| (name.clone(), entry.input_type.clone()))
+                        .collect(),
+                    output: value.value.output_type.clone(),
+                };
+                return Ok(text::print_value_specification(key, &spec));
+            }
+        }
+        self.distribution
+            .dependencies()
+            .iter()
+            .filter(|(key, _)| Path::new(key) == fqname.package_path)
+            .flat_map(|(_, spec)| spec.modules.iter())
+            .filter(|(key, _)| same_module(key))
+            .flat_map(|(_, module)| module.values.iter())
+            .find(|(key, _)| same(key))
+            .map(|(key, spec)| text::print_value_specification(key, spec))
+            .ok_or_else(|| format!("No value {}", fqname.to_canonical_string()))
+    }
+
+    /// Completions of `word`: commands, FQNames, and names in scope
+    fn completions(&self, word: &str) -> Vec<String> {
+        let mut candidates: Vec<String> = if word.starts_with(':') {
+            COMMANDS.iter().map(|c| c.to_string()).collect()
+        } else {
+            let mut names = self.qualified_names();
+            if let Some((package, module)) = &self.module {
+                let prefix = format!("{}:{}#", package, module);
+                names.extend(
+                    self.members(package, module, false)
+                        .iter()
+                        .filter_map(|name| name.strip_prefix(&prefix).map(str::to_string)),
+                );
+            }
+            names.extend(self.bindings.iter().map(|(name, _)| name.clone()));
+            names
+        };
+        candidates.retain(|candidate| candidate.starts_with(word));
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Every type, constructor, and value of the package and its
+    /// dependencies, as `.mir` text writes them
+    fn qualified_names(&self) -> Vec<String> {
+        let package = self.distribution.package_name().as_path().clone();
+        let mut names = Vec::new();
+        let member = |package: &Path, module: &str, local: &str| {
+            FQName::new(package.clone(), Path::new(module), Name::from(local))
+        };
+        if let Some(def) = self.distribution.definition() {
+            for (module_key, module) in &def.modules {
+                for (key, tpe) in &module.value.types {
+                    names.push(qualified_text(member(&package, module_key, key), true));
+                    if let TypeDefinition::CustomTypeDefinition { constructors, .. } = &tpe.value {
+                        names.extend(constructors.value.iter().map(|c| {
+                            let name = member(&package, module_key, &c.name.to_string());
+                            qualified_text(name, true)
+                        }));
+                    }
+                }
+                for key in module.value.values.keys() {
+                    names.push(qualified_text(member(&package, module_key, key), false));
+                }
+            }
+        }
+        let mut specs: Vec<(Path, _)> = self
+            .distribution
+            .dependencies()
+            .iter()
+            .map(|(key, spec)| (Path::new(key), spec.clone()))
+            .collect();
+        if self.distribution.definition().is_none() {
+            specs.push((package, self.distribution.specification()));
+        }
+        for (package, spec) in &specs {
+            for (module_key, module) in &spec.modules {
+                for (key, tpe) in &module.types {
+                    names.push(qualified_text(member(package, module_key, key), true));
+                    if let TypeSpecification::CustomTypeSpecification { constructors, .. } = tpe {
+                        names.extend(constructors.iter().map(|c| {
+                            let name = member(package, module_key, &c.name.to_string());
+                            qualified_text(name, true)
+                        }));
+                    }
+                }
+                for key in module.values.keys() {
+                    names.push(qualified_text(member(package, module_key, key), false));
+                }
+            }
+        }
+        names
+    }
+}
+
+fn load(input: &str, dependencies: &[String]) -> Result<Distribution, String> {
+    let mut distribution = match load_distribution_from_source(input) {
+        Ok(LoadedDistribution::V4(ir_file)) => ir_file.distribution,
+        Ok(LoadedDistribution::Classic(_)) => {
+            return Err(
+                "Evaluation requires V4 IR. Convert the input first with `morphir ir migrate`."
+                    .to_string(),
+            );
+        }
+        Err(e) => return Err(format!("Failed to load input: {}", e)),
+    };
+    attach_dependencies(&mut distribution, dependencies).map_err(|e| format!("{:#}", e))?;
+    Ok(distribution)
+}
+
+/// `fqname` as `.mir` text writes a reference to it: capitalised for types
+/// and constructors
+fn qualified_text(fqname: FQName, is_type: bool) -> String {
+    if is_type {
+        text::print_type(&Type::reference(TypeAttributes::default(), fqname, vec![]))
+    } else {
+        text::print_value(&Value::Reference(ValueAttributes::default(), fqname))
+    }
+}
+
+/// Whether `name` can be bound with `name = expression`
+fn is_binding_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// When the file at `path` last changed; for a directory, the latest change
+/// of any file in it
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_dir() {
+        return metadata.modified().ok();
+    }
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "library acme/shop
+
+
+module orders
+
+tax-rate : morphir/sdk:basics#Int =
+    10
+
+total (amount : morphir/sdk:basics#Int) : morphir/sdk:basics#Int =
+    morphir/sdk:basics#add amount acme/shop:orders#tax-rate
+";
+
+    fn session() -> Session {
+        let distribution = text::parse_distribution(MODEL).unwrap();
+        Session::new("model.mir".to_string(), Vec::new(), distribution)
+    }
+
+    fn output(session: &mut Session, line: &str) -> String {
+        match session.execute(line) {
+            Ok(Reply::Output(output)) => output,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_evaluate_with_open_module_and_bindings() {
+        let mut session = session();
+        assert_eq!(output(&mut session, "acme/shop:orders#total 5"), "15");
+        assert!(
+            session
+                .execute("total 5")
+                .unwrap_err()
+                .contains("not in scope")
+        );
+
+        assert_eq!(session.execute(":module orders"), Ok(Reply::Nothing));
+        assert_eq!(session.prompt(), "orders> ");
+        assert_eq!(output(&mut session, "x = total 5"), "x = 15");
+        assert_eq!(output(&mut session, "total x"), "25");
+        assert!(
+            session
+                .execute("total (")
+                .unwrap_err()
+                .starts_with("Parse error at 1:")
+        );
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut session = session();
+        assert_eq!(output(&mut session, ":modules"), "acme/shop:orders");
+        assert!(session.execute(":values").is_err());
+        session.open("acme/shop:orders").unwrap();
+        assert_eq!(
+            output(&mut session, ":values"),
+            "acme/shop:orders#tax-rate\nacme/shop:orders#total"
+        );
+        assert_eq!(
+            output(&mut session, ":type total"),
+            "total (amount : morphir/sdk:basics#Int) : morphir/sdk:basics#Int"
+        );
+        assert!(output(&mut session, ":show tax-rate").starts_with("tax-rate : "));
+        assert_eq!(session.execute(":quit"), Ok(Reply::Quit));
+        assert!(session.execute(":frobnicate").is_err());
+    }
+
+    #[test]
+    fn test_completions_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("morphir-ir.json");
+        let write = |model: &str| {
+            let ir_file = morphir_core::ir::v4::IRFile {
+                format_version: Default::default(),
+                distribution: text::parse_distribution(model).unwrap(),
+            };
+            std::fs::write(&input, serde_json::to_string(&ir_file).unwrap()).unwrap();
+        };
+        write(MODEL);
+        let mut session = Session::load(input.display().to_string(), Vec::new()).unwrap();
+        session.open("orders").unwrap();
+        assert_eq!(
+            session.completions("acme/shop:orders#t"),
+            vec!["acme/shop:orders#tax-rate", "acme/shop:orders#total"]
+        );
+        assert_eq!(session.completions("to"), vec!["total"]);
+        assert_eq!(session.completions(":mod"), vec![":module", ":modules"]);
+        assert_eq!(session.reload_if_changed(), Ok(None));
+
+        write(&MODEL.replace("    10", "    20"));
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&input)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(session.reload_if_changed().unwrap().is_some());
+        assert_eq!(output(&mut session, "total 5"), "25");
+    }
+}
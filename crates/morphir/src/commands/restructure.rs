@@ -0,0 +1,82 @@
+//! Restructure Command
+//!
+//! `ir restructure` splits and merges the modules of a distribution as a
+//! plan file describes, rewriting every reference to the definitions moved,
+//! and records the change in the refactor log next to the IR written.
+
+use crate::commands::rename::{finish_refactor, refactor_output};
+use crate::commands::text::{input_or_project_ir, load};
+use morphir_core::ir::v4::{RestructurePlan, restructure};
+use starbase::AppResult;
+use std::path::{Path, PathBuf};
+
+/// Options for the `ir restructure` command
+#[derive(Debug, Default)]
+pub struct RestructureCommandOptions {
+    /// Plan file, TOML or JSON
+    pub plan: PathBuf,
+    /// Input file, directory, or remote source; the project's compiled IR if
+    /// omitted
+    pub input: Option<String>,
+    /// Output file; the input file if omitted
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything
+    pub dry_run: bool,
+    /// Print the transaction as JSON
+    pub json: bool,
+}
+
+/// Run the `ir restructure` command.
+pub fn run_ir_restructure(options: RestructureCommandOptions) -> AppResult {
+    let plan = match read_plan(&options.plan) {
+        Ok(plan) => plan,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(Some(1));
+        }
+    };
+    let input = input_or_project_ir(options.input)?;
+    let output = match refactor_output(&input, options.output, options.dry_run) {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(Some(1));
+        }
+    };
+    let mut distribution = match load(&input) {
+        Ok(distribution) => distribution,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(Some(1));
+        }
+    };
+    let transaction = match restructure(&mut distribution, &plan) {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            eprintln!("Cannot restructure: {}", e);
+            return Ok(Some(1));
+        }
+    };
+    finish_refactor(
+        distribution,
+        &transaction,
+        &output,
+        options.dry_run,
+        options.json,
+    )
+}
+
+/// Plan in `path`, read as TOML if its extension says so and JSON otherwise
+fn read_plan(path: &Path) -> Result<RestructurePlan, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        toml::from_str(&content).map_err(|e| format!("Invalid plan {}: {}", path.display(), e))
+    } else {
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid plan {}: {}", path.display(), e))
+    }
+}
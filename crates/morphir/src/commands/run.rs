@@ -0,0 +1,176 @@
+//! Run command
+//!
+//! Evaluates a value definition of a V4 distribution with the IR evaluator
+//! and prints the result.
+
+use super::sample::parse_fqname;
+use crate::output::json_requested;
+use morphir_common::loader::{
+    LoadedDistribution, attach_dependencies, load_distribution_from_source,
+};
+use morphir_runtime::{Evaluator, diff};
+use serde::Serialize;
+use starbase::AppResult;
+
+/// IR file evaluated when no input is given
+pub(crate) const DEFAULT_INPUT: &str = "morphir-ir.json";
+
+/// Column width results are pretty-printed to
+const DISPLAY_WIDTH: usize = 80;
+
+/// Stack size of the evaluation thread; deep recursion in models needs more
+/// than the default
+pub(crate) const EVAL_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Options for the `run` command
+#[derive(Debug, Default)]
+pub struct RunOptions {
+    /// Input file, directory, or remote source
+    pub input: Option<String>,
+    /// Dependency sources; only their specifications are available
+    pub dependencies: Vec<String>,
+    /// Value to evaluate
+    pub fqname: String,
+    /// Arguments as JSON; anything that is not valid JSON is taken as a string
+    pub args: Vec<String>,
+    /// Expected result as JSON; the run fails with a structural diff if the
+    /// result differs
+    pub expect: Option<String>,
+    /// Output as JSON
+    pub json: bool,
+}
+
+/// JSON summary for the run command
+#[derive(Serialize)]
+struct RunSummary {
+    success: bool,
+    fqname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    differences: Vec<String>,
+}
+
+/// Outcome of a run: the result as JSON and pretty-printed, and its
+/// differences from the expected result
+type Outcome = (serde_json::Value, String, Vec<String>);
+
+fn parse_arg(arg: &str) -> serde_json::Value {
+    serde_json::from_str(arg).unwrap_or_else(|_| serde_json::Value::String(arg.to_string()))
+}
+
+/// Run the `run` command.
+pub fn run_model(options: RunOptions) -> AppResult {
+    let RunOptions {
+        input,
+        dependencies,
+        fqname,
+        args,
+        expect,
+        json,
+    } = options;
+    let json = json_requested(json);
+    let input = input.unwrap_or_else(|| DEFAULT_INPUT.to_string());
+
+    let report = |result: Result<Outcome, String>| {
+        let success = matches!(&result, Ok((_, _, differences)) if differences.is_empty());
+        if json {
+            let (value, error, differences) = match result {
+                Ok((value, _, differences)) => (Some(value), None, differences),
+                Err(e) => (None, Some(e), Vec::new()),
+            };
+            let summary = RunSummary {
+                success,
+                fqname: fqname.clone(),
+                value,
+                error,
+                differences,
+            };
+            println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+        } else {
+            match result {
+                Ok((_, display, differences)) => {
+                    println!("{}", display);
+                    if !differences.is_empty() {
+                        eprintln!(
+                            "Result differs from the expected value in {} place(s):",
+                            differences.len()
+                        );
+                        for difference in &differences {
+                            eprintln!("  {}", difference);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        if success { Ok(None) } else { Ok(Some(1)) }
+    };
+
+    let target = match parse_fqname(&fqname) {
+        Ok(target) => target,
+        Err(e) => return report(Err(e)),
+    };
+    let mut ir_file = match load_distribution_from_source(&input) {
+        Ok(LoadedDistribution::V4(ir_file)) => ir_file,
+        Ok(LoadedDistribution::Classic(_)) => {
+            return report(Err(
+                "Evaluation requires V4 IR. Convert the input first with `morphir ir migrate`."
+                    .to_string(),
+            ));
+        }
+        Err(e) => return report(Err(format!("Failed to load input: {}", e))),
+    };
+    if let Err(e) = attach_dependencies(&mut ir_file.distribution, &dependencies) {
+        return report(Err(format!("{:#}", e)));
+    }
+    let args: Vec<serde_json::Value> = args.iter().map(|a| parse_arg(a)).collect();
+    let expect = expect.as_deref().map(parse_arg);
+
+    let evaluation = std::thread::Builder::new()
+        .name("morphir-eval".to_string())
+        .stack_size(EVAL_STACK_SIZE)
+        .spawn(move || {
+            let evaluator = Evaluator::new(&ir_file.distribution);
+            let args = evaluator
+                .args_from_json(&target, &args)
+                .map_err(|e| e.to_string())?;
+            let value = evaluator
+                .evaluate(&target, args)
+                .map_err(|e| e.to_string())?;
+            let differences = match &expect {
+                Some(expected) => {
+                    let expected = evaluator
+                        .output_from_json(&target, expected)
+                        .map_err(|e| format!("Invalid expected value: {}", e))?;
+                    diff(&expected, &value)
+                        .iter()
+                        .map(|d| d.to_string())
+                        .collect()
+                }
+                None => Vec::new(),
+            };
+            Ok((value.to_json(), value.pretty(DISPLAY_WIDTH), differences))
+        });
+    let result = match evaluation {
+        Ok(handle) => handle
+            .join()
+            .unwrap_or_else(|_| Err("Evaluation panicked".to_string())),
+        Err(e) => Err(format!("Failed to start evaluation: {}", e)),
+    };
+    report(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arg_falls_back_to_string() {
+        assert_eq!(parse_arg("42"), serde_json::json!(42));
+        assert_eq!(parse_arg("[1, \"a\"]"), serde_json::json!([1, "a"]));
+        assert_eq!(parse_arg("hello"), serde_json::json!("hello"));
+    }
+}
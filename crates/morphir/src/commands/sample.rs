@@ -0,0 +1,277 @@
+//! Sample Command
+//!
+//! Extracts a minimal, self-contained sub-distribution reproducing a failure,
+//! suitable for attaching to an issue as a small repro.
+
+use crate::output::json_requested;
+use morphir_common::loader::{LoadedDistribution, load_distribution_from_source};
+use morphir_core::ir::v4::IRFile;
+use morphir_core::ir::v4::sample::{SampleOptions, fqnames_in_text, sample_distribution};
+use morphir_core::naming::FQName;
+use morphir_extension_sdk::Diagnostic as ExtensionDiagnostic;
+use serde::Serialize;
+use starbase::AppResult;
+use std::path::PathBuf;
+
+/// Options for the `ir sample` command
+#[derive(Debug, Default)]
+pub struct SampleCommandOptions {
+    /// Input file, directory, or remote source
+    pub input: String,
+    /// Root FQNames to extract
+    pub fqnames: Vec<String>,
+    /// Diagnostics file to take roots from
+    pub diagnostic: Option<PathBuf>,
+    /// Maximum reference depth at which bodies are kept
+    pub depth: Option<usize>,
+    /// Output file (stdout if omitted)
+    pub output: Option<PathBuf>,
+    /// Output a JSON summary
+    pub json: bool,
+}
+
+/// JSON summary for the sample command
+#[derive(Serialize)]
+struct SampleSummary {
+    success: bool,
+    input: String,
+    output: String,
+    roots: Vec<String>,
+    included: Vec<String>,
+    stubbed: Vec<String>,
+    missing: Vec<String>,
+    /// The sampled IR, when no output file was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ir: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Parse an FQName given on the command line.
+///
+/// Accepts the canonical form (`pkg:mod#name`) as well as the classic
+/// colon-separated form (`pkg:mod:name`).
+pub(crate) fn parse_fqname(s: &str) -> Result<FQName, String> {
+    if s.contains('#') {
+        FQName::from_canonical_string(s)
+    } else {
+        FQName::parse(s).ok_or_else(|| {
+            format!(
+                "Invalid FQName '{}'. Expected 'package:module#name' or 'package:module:name'",
+                s
+            )
+        })
+    }
+}
+
+/// Collect root FQNames mentioned in a diagnostics file.
+///
+/// The file may contain a single diagnostic, an array of diagnostics, or a
+/// command output object with a `diagnostics` field.
+fn roots_from_diagnostics(path: &PathBuf) -> Result<Vec<FQName>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read diagnostics file {:?}: {}", path, e))?;
+    let json: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse diagnostics file {:?}: {}", path, e))?;
+
+    let entries = match json {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(ref obj) => match obj.get("diagnostics") {
+            Some(serde_json::Value::Array(items)) => items.clone(),
+            _ => vec![json],
+        },
+        _ => return Err("Diagnostics file must contain an object or an array".to_string()),
+    };
+
+    let mut roots = Vec::new();
+    for entry in entries {
+        let diagnostic: ExtensionDiagnostic = serde_json::from_value(entry)
+            .map_err(|e| format!("Invalid diagnostic in {:?}: {}", path, e))?;
+        let texts = std::iter::once(diagnostic.message.as_str())
+            .chain(diagnostic.related.iter().map(|r| r.message.as_str()));
+        for text in texts {
+            for fqname in fqnames_in_text(text) {
+                if !roots.contains(&fqname) {
+                    roots.push(fqname);
+                }
+            }
+        }
+    }
+    Ok(roots)
+}
+
+/// Run the `ir sample` command.
+pub fn run_ir_sample(options: SampleCommandOptions) -> AppResult {
+    let SampleCommandOptions {
+        input,
+        fqnames,
+        diagnostic,
+        depth,
+        output,
+        json,
+    } = options;
+    let json = json_requested(json);
+    let output_str = output
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "<stdout>".to_string());
+
+    let output_error = |msg: &str| {
+        if json {
+            let summary = SampleSummary {
+                success: false,
+                input: input.clone(),
+                output: output_str.clone(),
+                roots: Vec::new(),
+                included: Vec::new(),
+                stubbed: Vec::new(),
+                missing: Vec::new(),
+                ir: None,
+                error: Some(msg.to_string()),
+            };
+            println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+        } else {
+            eprintln!("{}", msg);
+        }
+    };
+
+    let mut roots = Vec::new();
+    for s in &fqnames {
+        match parse_fqname(s) {
+            Ok(fqname) => roots.push(fqname),
+            Err(e) => {
+                output_error(&e);
+                return Ok(Some(1));
+            }
+        }
+    }
+    if let Some(path) = &diagnostic {
+        match roots_from_diagnostics(path) {
+            Ok(found) => {
+                for fqname in found {
+                    if !roots.contains(&fqname) {
+                        roots.push(fqname);
+                    }
+                }
+            }
+            Err(e) => {
+                output_error(&e);
+                return Ok(Some(1));
+            }
+        }
+    }
+    if roots.is_empty() {
+        output_error(
+            "No roots to sample from. Pass --fqname, or --diagnostic with a diagnostic \
+             that mentions a fully-qualified name.",
+        );
+        return Ok(Some(1));
+    }
+
+    let ir_file = match load_distribution_from_source(&input) {
+        Ok(LoadedDistribution::V4(ir_file)) => ir_file,
+        Ok(LoadedDistribution::Classic(_)) => {
+            output_error(
+                "Sampling requires V4 IR. Convert the input first with `morphir ir migrate`.",
+            );
+            return Ok(Some(1));
+        }
+        Err(e) => {
+            output_error(&format!("Failed to load input: {}", e));
+            return Ok(Some(1));
+        }
+    };
+
+    let sample = match sample_distribution(
+        &ir_file.distribution,
+        &roots,
+        &SampleOptions { max_depth: depth },
+    ) {
+        Ok(sample) => sample,
+        Err(e) => {
+            output_error(&format!("Failed to extract sample: {}", e));
+            return Ok(Some(1));
+        }
+    };
+
+    let sampled = IRFile {
+        format_version: ir_file.format_version,
+        distribution: sample.distribution,
+    };
+    let content = serde_json::to_string_pretty(&sampled).expect("Failed to serialize");
+
+    match &output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &content) {
+                output_error(&format!("Failed to write {:?}: {}", path, e));
+                return Ok(Some(1));
+            }
+        }
+        None if !json => println!("{}", content),
+        None => {}
+    }
+
+    let canonical = |names: &[FQName]| -> Vec<String> {
+        names.iter().map(|f| f.to_canonical_string()).collect()
+    };
+    if json {
+        let summary = SampleSummary {
+            success: true,
+            input: input.clone(),
+            output: output_str.clone(),
+            roots: canonical(&roots),
+            included: canonical(&sample.included),
+            stubbed: canonical(&sample.stubbed),
+            missing: canonical(&sample.missing),
+            ir: output
+                .is_none()
+                .then(|| serde_json::to_value(&sampled).expect("Failed to serialize")),
+            error: None,
+        };
+        println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+    } else {
+        eprintln!(
+            "Sampled {} definition(s), stubbed {} with holes",
+            sample.included.len(),
+            sample.stubbed.len()
+        );
+        for missing in &sample.missing {
+            eprintln!(
+                "warning: unresolved reference {}",
+                missing.to_canonical_string()
+            );
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fqname_forms() {
+        let canonical = parse_fqname("my/pkg:orders#total").unwrap();
+        let classic = parse_fqname("my/pkg:orders:total").unwrap();
+        assert_eq!(canonical, classic);
+        assert!(parse_fqname("not-a-name").is_err());
+    }
+
+    #[test]
+    fn test_roots_from_diagnostics_output_object() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("diagnostics.json");
+        std::fs::write(
+            &path,
+            r#"{"diagnostics": [{"severity": "error", "message": "bad call in my/pkg:orders#total"}]}"#,
+        )
+        .unwrap();
+
+        let roots = roots_from_diagnostics(&path).unwrap();
+        assert_eq!(
+            roots,
+            vec![FQName::from_canonical_string("my/pkg:orders#total").unwrap()]
+        );
+    }
+}
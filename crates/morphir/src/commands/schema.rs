@@ -1,22 +1,78 @@
+//! Schema Command
+//!
+//! Prints the JSON Schema of a V4 IR file, a Classic IR distribution, or the
+//! extension protocol, for editors and for validating IR or extension
+//! messages produced by other tools.
+
+use morphir_core::ir::schema::{classic_schema, v4_schema};
+use morphir_extension_sdk::protocol::protocol_schema;
 use starbase::AppResult;
 use std::path::PathBuf;
 
-pub fn run_schema(output: Option<PathBuf>) -> AppResult {
-    // Note: JSON schema generation is not currently available for the full V4 IR
-    // because Type, Value, and Pattern have custom serde implementations that don't
-    // derive JsonSchema. A separate schema definition file would be needed.
-    let message = r#"{
-  "$comment": "JSON Schema generation is not currently available for the V4 IR format. Type, Value, and Pattern use custom serialization that doesn't derive JsonSchema. See the Morphir specification for the schema definition."
-}"#;
+/// Which schema `morphir schema` emits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaKind {
+    /// V4 IR files (`formatVersion` 4)
+    #[default]
+    IrV4,
+    /// Classic IR distributions (`formatVersion` 1 to 3)
+    IrClassic,
+    /// JSON-RPC messages between the host and extensions
+    Protocol,
+}
+
+/// JSON Schema of `kind`, pretty-printed
+pub fn schema_json(kind: SchemaKind) -> String {
+    let schema = match kind {
+        SchemaKind::IrV4 => v4_schema(),
+        SchemaKind::IrClassic => classic_schema(),
+        SchemaKind::Protocol => protocol_schema(),
+    };
+    serde_json::to_string_pretty(&schema).expect("schemas serialize to JSON")
+}
+
+pub fn run_schema(which: SchemaKind, output: Option<PathBuf>) -> AppResult {
+    let schema = schema_json(which);
 
     if let Some(path) = output {
-        if let Err(e) = std::fs::write(&path, message) {
+        if let Err(e) = std::fs::write(&path, schema + "\n") {
             eprintln!("Failed to write schema to {:?}: {}", path, e);
             return Ok(Some(1));
         }
     } else {
-        println!("{}", message);
+        println!("{}", schema);
     }
 
     Ok(None)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_schema_is_a_json_schema() {
+        for kind in [
+            SchemaKind::IrV4,
+            SchemaKind::IrClassic,
+            SchemaKind::Protocol,
+        ] {
+            let schema: serde_json::Value = serde_json::from_str(&schema_json(kind)).unwrap();
+            assert!(schema["$schema"].is_string(), "{kind:?}");
+            assert!(
+                schema["$defs"]
+                    .as_object()
+                    .is_some_and(|defs| !defs.is_empty())
+            );
+        }
+    }
+
+    #[test]
+    fn test_protocol_schema_covers_the_method_types() {
+        let schema: serde_json::Value =
+            serde_json::from_str(&schema_json(SchemaKind::Protocol)).unwrap();
+        for name in ["CompileRequest", "GenerateResult", "Diagnostic", "RpcError"] {
+            assert!(schema["$defs"][name].is_object(), "missing {name}");
+        }
+    }
+}
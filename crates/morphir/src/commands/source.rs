@@ -0,0 +1,201 @@
+//! Source commands: manage the cache of remote sources
+//!
+//! `morphir source prefetch` resolves every remote dependency declared by the
+//! workspace and its projects, every extension downloaded from a URL, and any
+//! sources given on the command line, so that later builds can run with
+//! `--offline` (or `sources.offline`) against a warm cache, e.g. in CI.
+
+use crate::commands::deps::{declarations, output_error};
+use crate::output::json_requested;
+use morphir_common::lockfile::Lockfile;
+use morphir_common::remote::integrity::split_digest;
+use morphir_common::remote::{
+    RemoteSource, RemoteSourceConfig, RemoteSourceResolver, ResolveOptions,
+};
+use morphir_common::vendor::{dependency_digest, dependency_source, extension_source};
+use morphir_daemon::workspace::Workspace;
+use morphir_design::discover_config;
+use serde::Serialize;
+use starbase::AppResult;
+use std::path::{Path, PathBuf};
+
+/// A source to prefetch
+struct Pending {
+    name: Option<String>,
+    source: RemoteSource,
+    sha256: Option<String>,
+}
+
+/// A prefetched source
+#[derive(Serialize)]
+struct Prefetched {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    source: String,
+    path: PathBuf,
+    /// Already cached before this run
+    cached: bool,
+}
+
+/// JSON output of `morphir source prefetch`
+#[derive(Serialize)]
+struct PrefetchOutput {
+    success: bool,
+    offline: bool,
+    prefetched: Vec<Prefetched>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed: Vec<Failed>,
+}
+
+/// A source that could not be fetched
+#[derive(Serialize)]
+struct Failed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    source: String,
+    error: String,
+}
+
+/// Run `morphir source prefetch`
+pub fn run_source_prefetch(
+    sources: Vec<String>,
+    config_path: Option<String>,
+    json: bool,
+) -> AppResult {
+    let json = json_requested(json);
+    let config_file = match config_path {
+        Some(cfg) => Some(PathBuf::from(cfg)),
+        None => std::env::current_dir()
+            .ok()
+            .and_then(|dir| discover_config(&dir)),
+    };
+
+    let mut pending = Vec::new();
+    let mut config = RemoteSourceConfig::default();
+    if let Some(config_file) = config_file {
+        let root = config_file
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let workspace = match Workspace::open(root.clone()) {
+            Ok(workspace) => workspace,
+            Err(e) => {
+                let message = format!("Failed to open workspace at {}: {}", root.display(), e);
+                return Ok(Some(output_error(json, &message)));
+            }
+        };
+        let (dependencies, extensions) = match declarations(&workspace) {
+            Ok(declared) => declared,
+            Err(e) => return Ok(Some(output_error(json, &e.to_string()))),
+        };
+        let lockfile = Lockfile::load(&root).unwrap_or_default();
+        for (name, spec) in &dependencies {
+            if let Ok(source) = dependency_source(spec) {
+                let sha256 = dependency_digest(spec).or_else(|| lockfile.locked_digest(&source));
+                pending.push(Pending {
+                    name: Some(name.clone()),
+                    source,
+                    sha256,
+                });
+            }
+        }
+        for (name, spec) in extensions.iter().filter(|(_, spec)| spec.enabled) {
+            if let Ok(source) = extension_source(spec) {
+                let sha256 = spec
+                    .url
+                    .as_deref()
+                    .and_then(|url| split_digest(url).1)
+                    .or_else(|| lockfile.locked_digest(&source));
+                pending.push(Pending {
+                    name: Some(name.clone()),
+                    source,
+                    sha256,
+                });
+            }
+        }
+        config = workspace.config.sources.clone().unwrap_or_default();
+    } else if sources.is_empty() {
+        let message = "No morphir.toml or morphir.json found and no sources given";
+        return Ok(Some(output_error(json, message)));
+    }
+
+    for source_str in &sources {
+        let (source_str, sha256) = split_digest(source_str);
+        match RemoteSource::parse(&source_str) {
+            Ok(source) if !source.is_local() => pending.push(Pending {
+                name: None,
+                source,
+                sha256,
+            }),
+            Ok(_) => {
+                let message = format!("{} is a local path, not a remote source", source_str);
+                return Ok(Some(output_error(json, &message)));
+            }
+            Err(e) => return Ok(Some(output_error(json, &e.to_string()))),
+        }
+    }
+
+    let offline = config.is_offline();
+    let mut resolver = match RemoteSourceResolver::new(config) {
+        Ok(resolver) => resolver,
+        Err(e) => {
+            let message = format!("Failed to initialize source resolver: {}", e);
+            return Ok(Some(output_error(json, &message)));
+        }
+    };
+
+    let mut prefetched = Vec::new();
+    let mut failed = Vec::new();
+    for Pending {
+        name,
+        source,
+        sha256,
+    } in pending
+    {
+        let cached = resolver.is_cached(&source);
+        match resolver.resolve(&source, &ResolveOptions::new().with_sha256(sha256)) {
+            Ok(path) => prefetched.push(Prefetched {
+                name,
+                source: source.to_string(),
+                path,
+                cached,
+            }),
+            Err(e) => failed.push(Failed {
+                name,
+                source: source.to_string(),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    let success = failed.is_empty();
+    if json {
+        let output = PrefetchOutput {
+            success,
+            offline,
+            prefetched,
+            failed,
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        for entry in &prefetched {
+            println!(
+                "{} {}{}",
+                if entry.cached { "Cached" } else { "Fetched" },
+                entry.source,
+                entry
+                    .name
+                    .as_ref()
+                    .map(|name| format!(" ({})", name))
+                    .unwrap_or_default()
+            );
+        }
+        for entry in &failed {
+            eprintln!("Failed {}: {}", entry.source, entry.error);
+        }
+        if prefetched.is_empty() && failed.is_empty() {
+            println!("No remote sources to prefetch");
+        }
+    }
+    Ok((!success).then_some(1))
+}
@@ -0,0 +1,93 @@
+//! Spec Diff Command
+//!
+//! Compares the public specifications of two distributions of a package,
+//! reporting only the changes its consumers can see, each classified as
+//! additive or breaking. The exit code gates releases: 0 when the new
+//! version is compatible, 2 when it has breaking changes, 1 on errors.
+
+use crate::output::json_requested;
+use morphir_common::loader::{LoadedDistribution, load_distribution_from_source};
+use morphir_core::converter;
+use morphir_core::ir::v4::{self, Impact, SpecChange, diff_specifications};
+use serde::Serialize;
+use starbase::AppResult;
+
+/// Exit code when the new specification breaks consumers of the old one
+pub const BREAKING_EXIT_CODE: u8 = 2;
+
+/// JSON output of the spec-diff command
+#[derive(Serialize)]
+struct SpecDiffOutput {
+    success: bool,
+    old: String,
+    new: String,
+    breaking: bool,
+    changes: Vec<SpecChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn load_specification(source: &str) -> Result<v4::PackageSpecification, String> {
+    let distribution = match load_distribution_from_source(source)
+        .map_err(|e| format!("Failed to load {}: {}", source, e))?
+    {
+        LoadedDistribution::V4(ir_file) => ir_file.distribution,
+        LoadedDistribution::Classic(dist) => converter::classic_to_v4(&dist).ir.distribution,
+    };
+    Ok(distribution.specification())
+}
+
+/// Run `morphir ir spec-diff`
+pub fn run_ir_spec_diff(old: String, new: String, json: bool) -> AppResult {
+    let json = json_requested(json);
+    let specs = load_specification(&old).and_then(|o| Ok((o, load_specification(&new)?)));
+    let (old_spec, new_spec) = match specs {
+        Ok(specs) => specs,
+        Err(message) => {
+            if json {
+                let output = SpecDiffOutput {
+                    success: false,
+                    old,
+                    new,
+                    breaking: false,
+                    changes: Vec::new(),
+                    error: Some(message),
+                };
+                println!("{}", serde_json::to_string_pretty(&output).unwrap());
+            } else {
+                eprintln!("{}", message);
+            }
+            return Ok(Some(1));
+        }
+    };
+
+    let diff = diff_specifications(&old_spec, &new_spec);
+    if json {
+        let output = SpecDiffOutput {
+            success: true,
+            old,
+            new,
+            breaking: diff.is_breaking(),
+            changes: diff.changes.clone(),
+            error: None,
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else if diff.is_empty() {
+        println!("No changes to the public specification");
+    } else {
+        for (impact, title) in [
+            (Impact::Breaking, "Breaking changes"),
+            (Impact::Additive, "Additive changes"),
+        ] {
+            let changes: Vec<&SpecChange> = diff.with_impact(impact).collect();
+            if changes.is_empty() {
+                continue;
+            }
+            println!("{} ({}):", title, changes.len());
+            for change in changes {
+                println!("  {}", change);
+            }
+        }
+    }
+    Ok(diff.is_breaking().then_some(BREAKING_EXIT_CODE))
+}
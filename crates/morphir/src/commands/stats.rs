@@ -0,0 +1,151 @@
+//! Stats command: the size of the model and the history of its builds
+//!
+//! Module and definition counts come from the module indexes, and build
+//! durations, diagnostics, and the IR cache hit rate from the build history
+//! the daemon keeps in `.morphir/cache/metrics.json`. Without `--workspace`
+//! only the current project is reported.
+
+use crate::error::CliError;
+use crate::output::json_requested;
+use morphir_daemon::workspace::metrics::SeverityCounts;
+use morphir_daemon::workspace::{BuildHistory, MetricsSummary, Workspace};
+use morphir_design::{discover_config, load_config_context};
+use starbase::AppResult;
+use std::path::PathBuf;
+
+/// Run `morphir stats`
+pub fn run_stats(
+    workspace_wide: bool,
+    config_path: Option<String>,
+    limit: usize,
+    json: bool,
+) -> AppResult {
+    let json = json_requested(json);
+    let config_file = match config_path {
+        Some(cfg) => PathBuf::from(cfg),
+        None => {
+            let start_dir =
+                std::env::current_dir().map_err(|e| CliError::FileSystem { error: e })?;
+            discover_config(&start_dir).ok_or_else(|| CliError::Config {
+                error: anyhow::anyhow!("No morphir.toml or morphir.json found"),
+            })?
+        }
+    };
+    let ctx = load_config_context(&config_file).map_err(|e| CliError::Config { error: e })?;
+    let root = ctx
+        .workspace_root
+        .clone()
+        .or_else(|| ctx.project_root.clone())
+        .or_else(|| config_file.parent().map(PathBuf::from))
+        .unwrap_or_default();
+
+    let mut workspace = Workspace::open(root.clone()).map_err(|e| CliError::Config {
+        error: anyhow::anyhow!("Failed to open workspace at {}: {}", root.display(), e),
+    })?;
+    let mut history = BuildHistory::load(&BuildHistory::path(&root));
+    if !workspace_wide {
+        let project = ctx
+            .current_project
+            .as_ref()
+            .or(ctx.config.project.as_ref())
+            .map(|p| p.name.clone())
+            .ok_or_else(|| CliError::Config {
+                error: anyhow::anyhow!("Not in a project; pass --workspace for the workspace"),
+            })?;
+        workspace.projects.retain(|name, _| *name == project);
+        history.builds.retain(|build| build.project == project);
+    }
+    workspace.refresh_indexes().map_err(|e| CliError::Config {
+        error: anyhow::anyhow!("Failed to index the workspace: {}", e),
+    })?;
+
+    let summary = MetricsSummary::new(&workspace, &history, limit);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+    } else {
+        print!("{}", render_summary(&summary));
+    }
+    Ok(None)
+}
+
+fn render_counts(counts: &SeverityCounts) -> String {
+    format!(
+        "{} error(s), {} warning(s), {} info, {} hint(s)",
+        counts.errors, counts.warnings, counts.infos, counts.hints
+    )
+}
+
+/// Human-readable summary, one fact per line
+fn render_summary(summary: &MetricsSummary) -> String {
+    let mut out = format!(
+        "{} project(s), {} module(s), {} definition(s)\n",
+        summary.projects.len(),
+        summary.modules,
+        summary.definitions
+    );
+    for (name, project) in &summary.projects {
+        out.push_str(&format!(
+            "  {}: {} module(s), {} definition(s)\n",
+            name, project.modules, project.definitions
+        ));
+    }
+    if summary.builds == 0 {
+        out.push_str("No builds recorded yet; the daemon records builds as it rebuilds\n");
+        return out;
+    }
+    out.push_str(&format!(
+        "Diagnostics of the last builds: {}\n",
+        render_counts(&summary.diagnostics)
+    ));
+    out.push_str(&format!(
+        "Builds: {} ({} failed), average {} ms, last {} ms\n",
+        summary.builds,
+        summary.failed_builds,
+        summary.average_duration_ms.unwrap_or_default(),
+        summary.last_duration_ms.unwrap_or_default()
+    ));
+    if let Some(rate) = summary.cache_hit_rate {
+        out.push_str(&format!("IR cache hit rate: {:.0}%\n", rate * 100.0));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use morphir_daemon::workspace::metrics::ProjectMetrics;
+
+    #[test]
+    fn test_summary_renders_model_size_and_builds() {
+        let mut summary = MetricsSummary {
+            modules: 3,
+            definitions: 12,
+            ..Default::default()
+        };
+        summary.projects.insert(
+            "my-org/core".to_string(),
+            ProjectMetrics {
+                modules: 3,
+                definitions: 12,
+                diagnostics: None,
+            },
+        );
+        assert_eq!(
+            render_summary(&summary),
+            "1 project(s), 3 module(s), 12 definition(s)\n  my-org/core: 3 module(s), 12 definition(s)\nNo builds recorded yet; the daemon records builds as it rebuilds\n"
+        );
+
+        summary.builds = 4;
+        summary.failed_builds = 1;
+        summary.average_duration_ms = Some(120);
+        summary.last_duration_ms = Some(80);
+        summary.cache_hit_rate = Some(0.25);
+        summary.diagnostics.warnings = 2;
+        let rendered = render_summary(&summary);
+        assert!(rendered.contains(
+            "Diagnostics of the last builds: 0 error(s), 2 warning(s), 0 info, 0 hint(s)\n"
+        ));
+        assert!(rendered.contains("Builds: 4 (1 failed), average 120 ms, last 80 ms\n"));
+        assert!(rendered.ends_with("IR cache hit rate: 25%\n"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Test command: run the workspace's golden-output scenarios or model tests
+//!
+//! `morphir test` discovers the scenarios under `.morphir/test/scenarios/`,
+//! runs each against its input, and compares the output with the golden
+//! files (see [`crate::testing::scenario`]). `--update` rewrites the golden
+//! files from the actual output, and `--junit` writes a JUnit XML report for
+//! CI.
+//!
+//! `morphir test --models` instead compiles the project and evaluates the
+//! cases declared under its `tests/` directory (see
+//! [`crate::testing::model`]).
+
+use crate::error::CliError;
+use crate::output::{OutputFormat, json_requested, write_output};
+use crate::pipeline::Pipeline;
+use crate::testing::junit::to_junit_xml;
+use crate::testing::model::{MODEL_TESTS_DIR, discover_model_tests, run_model_tests};
+use crate::testing::scenario::{ScenarioRunner, discover_scenarios};
+use crate::testing::{TestOutcome, TestReport, TestSuite};
+use morphir_common::loader::{
+    LoadedDistribution, attach_dependencies, load_distribution_from_source,
+};
+use morphir_core::converter;
+use morphir_core::ir::v4::Distribution;
+use morphir_design::ConfigContext;
+use starbase::AppResult;
+use std::path::{Path, PathBuf};
+
+/// Options for the test command
+#[derive(Debug, Default)]
+pub struct TestOptions {
+    /// Path to configuration file
+    pub config_path: Option<String>,
+    /// Only run scenarios whose name contains one of these
+    pub filters: Vec<String>,
+    /// Rewrite golden outputs instead of comparing
+    pub update: bool,
+    /// Run the model tests under `tests/` instead of the scenarios
+    pub models: bool,
+    /// IR to evaluate model tests against instead of compiling the project
+    pub ir: Option<String>,
+    /// Write a JUnit XML report here
+    pub junit: Option<PathBuf>,
+    /// Output JSON format
+    pub json: bool,
+}
+
+/// Run the test command
+pub async fn run_test(options: TestOptions) -> AppResult {
+    let TestOptions {
+        config_path,
+        filters,
+        update,
+        models,
+        ir,
+        junit,
+        json,
+    } = options;
+    let json = json_requested(json);
+
+    let mut pipeline = Pipeline::new();
+    if let Some(path) = config_path {
+        pipeline = pipeline.with_config(path);
+    }
+    let ctx = pipeline.load_config()?;
+
+    let selected =
+        |name: &str| filters.is_empty() || filters.iter().any(|f| name.contains(f.as_str()));
+
+    let (root, suite) = if models {
+        let root = ctx
+            .project_root
+            .clone()
+            .or_else(|| ctx.config_path.parent().map(Path::to_path_buf))
+            .unwrap_or_default()
+            .join(MODEL_TESTS_DIR);
+        let tests: Vec<_> = discover_model_tests(&root)
+            .into_iter()
+            .filter(|t| selected(&t.name))
+            .collect();
+        let suite = if tests.is_empty() {
+            TestSuite::new("models")
+        } else {
+            let distribution = model_distribution(&pipeline, &ctx, ir.as_deref()).await?;
+            run_model_tests(&distribution, &tests)
+        };
+        (root, suite)
+    } else {
+        let root = ctx.morphir_dir.join("test").join("scenarios");
+        let scenarios: Vec<_> = discover_scenarios(&root)
+            .into_iter()
+            .filter(|s| selected(&s.name))
+            .collect();
+        let runner = ScenarioRunner::new(&pipeline, &ctx).with_update(update);
+        (root, runner.run_all(&scenarios).await)
+    };
+    let report = TestReport {
+        suites: vec![suite],
+    };
+    let counts = report.counts();
+
+    if let Some(path) = &junit {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| CliError::FileSystem { error: e })?;
+        }
+        std::fs::write(path, to_junit_xml(&report))
+            .map_err(|e| CliError::FileSystem { error: e })?;
+    }
+
+    if json {
+        let output = serde_json::json!({
+            "success": counts.success(),
+            "counts": counts,
+            "suites": report.suites,
+        });
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+    } else {
+        if counts.total() == 0 {
+            println!("No tests found under {}", root.display());
+        }
+        for suite in &report.suites {
+            for case in &suite.cases {
+                let status = match &case.outcome {
+                    TestOutcome::Passed if update => "updated",
+                    TestOutcome::Passed => "ok",
+                    TestOutcome::Failed { .. } => "FAILED",
+                    TestOutcome::Error { .. } => "ERROR",
+                    TestOutcome::Skipped { .. } => "skipped",
+                };
+                println!(
+                    "{} {} ({}) ... {}",
+                    suite.name, case.name, case.kind, status
+                );
+                match &case.outcome {
+                    TestOutcome::Failed { message, details } => {
+                        println!("    {}", message);
+                        for detail in details {
+                            println!("      {}", detail);
+                        }
+                    }
+                    TestOutcome::Error { message } | TestOutcome::Skipped { reason: message } => {
+                        println!("    {}", message)
+                    }
+                    TestOutcome::Passed => {}
+                }
+            }
+        }
+        println!();
+        println!(
+            "test result: {}. {} passed; {} failed; {} errors; {} skipped; finished in {:.2}s",
+            if counts.success() { "ok" } else { "FAILED" },
+            counts.passed,
+            counts.failed,
+            counts.errors,
+            counts.skipped,
+            report.duration().as_secs_f64()
+        );
+        if let Some(path) = &junit {
+            println!("JUnit report: {}", path.display());
+        }
+    }
+
+    Ok((!counts.success()).then_some(1))
+}
+
+/// The distribution model tests are evaluated against: `ir` if given,
+/// otherwise the project compiled, with its dependencies attached
+async fn model_distribution(
+    pipeline: &Pipeline,
+    ctx: &ConfigContext,
+    ir: Option<&str>,
+) -> Result<Distribution, CliError> {
+    let source = match ir {
+        Some(ir) => ir.to_string(),
+        None => {
+            let compiled = pipeline.compile(ctx).await?;
+            if !compiled.success {
+                return Err(CliError::Compilation {
+                    message: compiled
+                        .error
+                        .unwrap_or_else(|| "Compilation failed".to_string()),
+                });
+            }
+            compiled.output_path.to_string_lossy().to_string()
+        }
+    };
+    let mut distribution = match load_distribution_from_source(&source)? {
+        LoadedDistribution::V4(ir_file) => ir_file.distribution,
+        LoadedDistribution::Classic(dist) => converter::classic_to_v4(&dist).ir.distribution,
+    };
+    let dependencies: Vec<String> = pipeline
+        .resolve_dependencies(ctx)?
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    attach_dependencies(&mut distribution, &dependencies)?;
+    Ok(distribution)
+}
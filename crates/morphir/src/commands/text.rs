@@ -0,0 +1,323 @@
+//! Text Commands
+//!
+//! `ir show` prints one definition, or a whole module, of a distribution in
+//! the `.mir` text syntax. `ir format` converts IR JSON to `.mir`, rewrites
+//! `.mir` files in canonical layout, and converts them back to IR JSON, or
+//! writes IR JSON in its canonical form.
+
+use crate::commands::sample::parse_fqname;
+use crate::error::CliError;
+use crate::pipeline::Pipeline;
+use morphir_common::loader::{LoadedDistribution, load_distribution_from_source};
+use morphir_core::converter;
+use morphir_core::ir::v4::text;
+use morphir_core::ir::v4::{
+    CanonicalStyle, Distribution, IRFile, PackageDefinition, PackageSpecification,
+    to_canonical_string,
+};
+use morphir_core::naming::{FQName, Name, Path};
+use morphir_design::resolve_compile_output;
+use starbase::AppResult;
+use std::path::PathBuf;
+
+/// Output format of `ir format`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TextFormat {
+    /// `.mir` text in canonical layout
+    #[default]
+    Mir,
+    /// V4 IR JSON
+    Json,
+}
+
+/// Options for the `ir show` command
+#[derive(Debug, Default)]
+pub struct ShowCommandOptions {
+    /// Definition (`package:module#name`) or module (`package:module`) to show
+    pub fqname: String,
+    /// Input file, directory, or remote source; the project's compiled IR if
+    /// omitted
+    pub input: Option<String>,
+}
+
+/// Options for the `ir format` command
+#[derive(Debug, Default)]
+pub struct FormatCommandOptions {
+    /// `.mir` file, or IR file, directory, or remote source
+    pub input: String,
+    /// Output format
+    pub format: TextFormat,
+    /// Only check that a `.mir` input is in canonical layout, or with
+    /// `canonical` that an IR JSON input is in canonical form
+    pub check: bool,
+    /// Write IR JSON in canonical form
+    pub canonical: bool,
+    /// Write canonical IR JSON on a single line, without empty attributes
+    pub compact: bool,
+    /// Output file (stdout if omitted)
+    pub output: Option<PathBuf>,
+}
+
+/// Run the `ir show` command.
+pub fn run_ir_show(options: ShowCommandOptions) -> AppResult {
+    let input = input_or_project_ir(options.input)?;
+    let distribution = match load(&input) {
+        Ok(distribution) => distribution,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(Some(1));
+        }
+    };
+    match show(&distribution, &options.fqname) {
+        Ok(content) => {
+            print!("{}", content);
+            Ok(None)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Ok(Some(1))
+        }
+    }
+}
+
+/// Run the `ir format` command.
+pub fn run_ir_format(options: FormatCommandOptions) -> AppResult {
+    let FormatCommandOptions {
+        input,
+        format,
+        check,
+        canonical,
+        compact,
+        output,
+    } = options;
+    let is_text = input.ends_with(&format!(".{}", text::EXTENSION));
+    if check && canonical == is_text {
+        if canonical {
+            eprintln!("--check --canonical needs an IR JSON file input");
+        } else {
+            eprintln!("--check needs a .{} input", text::EXTENSION);
+        }
+        return Ok(Some(1));
+    }
+
+    let (distribution, source) = if is_text {
+        let source = match std::fs::read_to_string(&input) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", input, e);
+                return Ok(Some(1));
+            }
+        };
+        match text::parse_distribution(&source) {
+            Ok(distribution) => (distribution, Some(source)),
+            Err(e) => {
+                eprintln!("{}:{}", input, e);
+                return Ok(Some(1));
+            }
+        }
+    } else {
+        match load(&input) {
+            // The canonical form is checked against the file as it is
+            Ok(distribution) => (
+                distribution,
+                check
+                    .then(|| std::fs::read_to_string(&input).ok())
+                    .flatten(),
+            ),
+            Err(e) => {
+                eprintln!("{}", e);
+                return Ok(Some(1));
+            }
+        }
+    };
+
+    let style = canonical.then_some(if compact {
+        CanonicalStyle::Compact
+    } else {
+        CanonicalStyle::Expanded
+    });
+    let content = match (format, style) {
+        (_, Some(style)) => {
+            let ir_file = IRFile {
+                format_version: Default::default(),
+                distribution,
+            };
+            match to_canonical_string(&ir_file, style) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("Failed to write canonical IR: {}", e);
+                    return Ok(Some(1));
+                }
+            }
+        }
+        (TextFormat::Mir, None) => text::print_distribution(&distribution),
+        (TextFormat::Json, None) => {
+            let ir_file = IRFile {
+                format_version: Default::default(),
+                distribution,
+            };
+            format!("{}\n", serde_json::to_string_pretty(&ir_file).unwrap())
+        }
+    };
+    if check {
+        if source.as_deref() == Some(content.as_str()) {
+            return Ok(None);
+        }
+        eprintln!("{} is not formatted", input);
+        return Ok(Some(1));
+    }
+    match &output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &content) {
+                eprintln!("Failed to write {:?}: {}", path, e);
+                return Ok(Some(1));
+            }
+        }
+        None => print!("{}", content),
+    }
+    Ok(None)
+}
+
+/// `input`, or the project's compiled IR if it is omitted
+pub(crate) fn input_or_project_ir(input: Option<String>) -> Result<String, CliError> {
+    if let Some(input) = input {
+        return Ok(input);
+    }
+    let pipeline = Pipeline::new();
+    let ctx = pipeline.load_config()?;
+    let language = ctx
+        .config
+        .frontend
+        .as_ref()
+        .and_then(|f| f.language.clone())
+        .ok_or_else(|| CliError::Config {
+            error: anyhow::anyhow!(
+                "No IR to read: pass --input, or set the frontend language in morphir.toml"
+            ),
+        })?;
+    Ok(
+        resolve_compile_output(&pipeline.package_name(&ctx), &language, &ctx.morphir_dir)
+            .display()
+            .to_string(),
+    )
+}
+
+pub(crate) fn load(input: &str) -> Result<Distribution, String> {
+    match load_distribution_from_source(input) {
+        Ok(LoadedDistribution::V4(ir_file)) => Ok(ir_file.distribution),
+        Ok(LoadedDistribution::Classic(dist)) => {
+            Ok(converter::classic_to_v4(&dist).ir.distribution)
+        }
+        Err(e) => Err(format!("Failed to load input: {}", e)),
+    }
+}
+
+/// `.mir` text of the definitions or module `reference` names
+pub(crate) fn show(distribution: &Distribution, reference: &str) -> Result<String, String> {
+    let (package, module, local) = if reference.contains('#') {
+        let FQName {
+            package_path,
+            module_path,
+            local_name,
+        } = parse_fqname(reference)?;
+        (package_path, module_path, Some(local_name))
+    } else {
+        match reference.split_once(':') {
+            Some((package, module)) if !module.contains(':') => {
+                (Path::new(package), Path::new(module), None)
+            }
+            _ => parse_fqname(reference).map(|fqname| {
+                (
+                    fqname.package_path,
+                    fqname.module_path,
+                    Some(fqname.local_name),
+                )
+            })?,
+        }
+    };
+    // Keys are compared in kebab-case, as the text prints them
+    let same_module = |key: &str| Path::new(key).to_string() == module.to_string();
+    let same_local = |key: &str| {
+        local.as_ref().map(|name| name.to_string()) == Some(Name::from(key).to_string())
+    };
+
+    let shown = if distribution.package_name().as_path().to_string() == package.to_string() {
+        match distribution.definition() {
+            Some(definition) => {
+                show_definition(definition, &same_module, &same_local, local.is_none())
+            }
+            None => show_specification(
+                &distribution.specification(),
+                &same_module,
+                &same_local,
+                local.is_none(),
+            ),
+        }
+    } else {
+        distribution
+            .dependencies()
+            .iter()
+            .find(|(key, _)| Path::new(key).to_string() == package.to_string())
+            .and_then(|(_, spec)| {
+                show_specification(spec, &same_module, &same_local, local.is_none())
+            })
+    };
+    shown.ok_or_else(|| format!("'{}' is not defined in the distribution", reference))
+}
+
+fn show_definition(
+    definition: &PackageDefinition,
+    same_module: &dyn Fn(&str) -> bool,
+    same_local: &dyn Fn(&str) -> bool,
+    whole_module: bool,
+) -> Option<String> {
+    let (name, module) = definition
+        .modules
+        .iter()
+        .find(|(key, _)| same_module(key))?;
+    if whole_module {
+        return Some(text::print_module_definition(name, module) + "\n");
+    }
+    let items: Vec<String> = module
+        .value
+        .types
+        .iter()
+        .filter(|(key, _)| same_local(key))
+        .map(|(name, definition)| text::print_type_definition(name, definition))
+        .chain(
+            module
+                .value
+                .values
+                .iter()
+                .filter(|(key, _)| same_local(key))
+                .map(|(name, definition)| text::print_value_definition(name, definition)),
+        )
+        .collect();
+    (!items.is_empty()).then(|| items.join("\n\n") + "\n")
+}
+
+fn show_specification(
+    spec: &PackageSpecification,
+    same_module: &dyn Fn(&str) -> bool,
+    same_local: &dyn Fn(&str) -> bool,
+    whole_module: bool,
+) -> Option<String> {
+    let (name, module) = spec.modules.iter().find(|(key, _)| same_module(key))?;
+    if whole_module {
+        return Some(text::print_module_specification(name, module) + "\n");
+    }
+    let items: Vec<String> = module
+        .types
+        .iter()
+        .filter(|(key, _)| same_local(key))
+        .map(|(name, spec)| text::print_type_specification(name, spec))
+        .chain(
+            module
+                .values
+                .iter()
+                .filter(|(key, _)| same_local(key))
+                .map(|(name, spec)| text::print_value_specification(name, spec)),
+        )
+        .collect();
+    (!items.is_empty()).then(|| items.join("\n\n") + "\n")
+}
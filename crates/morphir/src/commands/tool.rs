@@ -3,6 +3,11 @@
 //! This module provides functionality for installing, updating, listing, and
 //! uninstalling Morphir tools and extensions, similar to npm or dotnet tool.
 
+use crate::error::CliError;
+use crate::output::{
+    InstalledListOutput, InstalledOutput, OutputFormat, RegistryChangeOutput, print_table,
+    write_output,
+};
 use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 use starbase::AppResult;
@@ -96,19 +101,25 @@ impl ToolRegistry {
 
 /// Run the tool install command
 pub fn run_tool_install(name: String, version: Option<String>) -> AppResult {
-    println!("Installing Morphir tool: {}", name);
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("tool", "install", &name);
+    if !json {
+        println!("Installing Morphir tool: {}", name);
+    }
 
     let mut registry = match ToolRegistry::load() {
         Ok(reg) => reg,
-        Err(e) => {
-            eprintln!("Error: Failed to load tool registry: {}", e);
-            return Ok(Some(1));
-        }
+        Err(e) => return Ok(output.fail(json, format!("Failed to load tool registry: {}", e))),
     };
 
     // Check if tool is already installed
     if let Some(existing_tool) = registry.get_tool(&name) {
         let version_str = existing_tool.version.as_deref().unwrap_or(DEFAULT_VERSION);
+        if json {
+            output.version = Some(version_str.to_string());
+            write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+            return Ok(None);
+        }
         println!(
             "Tool '{}' is already installed (version: {})",
             name, version_str
@@ -130,10 +141,15 @@ pub fn run_tool_install(name: String, version: Option<String>) -> AppResult {
     // Add tool to registry
     registry.add_tool(tool);
     if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save tool registry: {}", e);
-        return Ok(Some(1));
+        return Ok(output.fail(json, format!("Failed to save tool registry: {}", e)));
     }
 
+    if json {
+        output.version = Some(display_version.to_string());
+        output.changed = true;
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully installed tool '{}' (version: {})",
         name, display_version
@@ -145,30 +161,87 @@ pub fn run_tool_install(name: String, version: Option<String>) -> AppResult {
 
 /// Run the tool list command
 pub fn run_tool_list() -> AppResult {
-    println!("Listing installed Morphir tools...\n");
+    let format = OutputFormat::global();
+    if format == OutputFormat::Human {
+        println!("Listing installed Morphir tools...\n");
+    }
 
     let registry = match ToolRegistry::load() {
         Ok(reg) => reg,
         Err(e) => {
-            eprintln!("Error: Failed to load tool registry: {}", e);
+            let error = format!("Failed to load tool registry: {}", e);
+            if format.is_json() {
+                let output = InstalledListOutput {
+                    success: false,
+                    kind: "tool".to_string(),
+                    items: Vec::new(),
+                    error: Some(error),
+                };
+                write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+            } else {
+                eprintln!("Error: {}", error);
+            }
             return Ok(Some(1));
         }
     };
 
-    let tools = registry.list_tools();
-
-    if tools.is_empty() {
-        println!("No tools installed.");
-        println!("Use 'morphir tool install <name>' to install a tool");
-    } else {
-        println!("{:<20} {:<15} Description", "Tool Name", "Version");
-        println!("{}", "-".repeat(70));
-        for tool in &tools {
-            let description = tool.description.as_deref().unwrap_or("No description");
-            let version_str = tool.version.as_deref().unwrap_or(DEFAULT_VERSION);
-            println!("{:<20} {:<15} {}", tool.name, version_str, description);
+    let mut tools = registry.list_tools();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+    match format {
+        OutputFormat::Json | OutputFormat::JsonLines => {
+            let output = InstalledListOutput {
+                success: true,
+                kind: "tool".to_string(),
+                items: tools
+                    .iter()
+                    .map(|tool| InstalledOutput {
+                        name: tool.name.clone(),
+                        version: tool
+                            .version
+                            .as_deref()
+                            .unwrap_or(DEFAULT_VERSION)
+                            .to_string(),
+                        description: tool.description.clone(),
+                        builtin: false,
+                        languages: Vec::new(),
+                        targets: Vec::new(),
+                    })
+                    .collect(),
+                error: None,
+            };
+            write_output(format, &output).map_err(CliError::from)?;
+        }
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = tools
+                .iter()
+                .map(|tool| {
+                    vec![
+                        tool.name.clone(),
+                        tool.version
+                            .as_deref()
+                            .unwrap_or(DEFAULT_VERSION)
+                            .to_string(),
+                        tool.description.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            print_table(&["NAME", "VERSION", "DESCRIPTION"], &rows);
+        }
+        OutputFormat::Human if tools.is_empty() => {
+            println!("No tools installed.");
+            println!("Use 'morphir tool install <name>' to install a tool");
+        }
+        OutputFormat::Human => {
+            println!("{:<20} {:<15} Description", "Tool Name", "Version");
+            println!("{}", "-".repeat(70));
+            for tool in &tools {
+                let description = tool.description.as_deref().unwrap_or("No description");
+                let version_str = tool.version.as_deref().unwrap_or(DEFAULT_VERSION);
+                println!("{:<20} {:<15} {}", tool.name, version_str, description);
+            }
+            println!("\nTotal: {} tool(s) installed", tools.len());
         }
-        println!("\nTotal: {} tool(s) installed", tools.len());
     }
 
     Ok(None)
@@ -176,25 +249,28 @@ pub fn run_tool_list() -> AppResult {
 
 /// Run the tool update command
 pub fn run_tool_update(name: String, version: Option<String>) -> AppResult {
-    println!("Updating Morphir tool: {}", name);
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("tool", "update", &name);
+    if !json {
+        println!("Updating Morphir tool: {}", name);
+    }
 
     let mut registry = match ToolRegistry::load() {
         Ok(reg) => reg,
-        Err(e) => {
-            eprintln!("Error: Failed to load tool registry: {}", e);
-            return Ok(Some(1));
-        }
+        Err(e) => return Ok(output.fail(json, format!("Failed to load tool registry: {}", e))),
     };
 
     // Check if tool exists
     let existing_tool = match registry.get_tool(&name) {
         Some(tool) => tool.clone(),
         None => {
-            eprintln!(
-                "Error: Tool '{}' is not installed. Use 'morphir tool install' first",
-                name
-            );
-            return Ok(Some(1));
+            return Ok(output.fail(
+                json,
+                format!(
+                    "Tool '{}' is not installed. Use 'morphir tool install' first",
+                    name
+                ),
+            ));
         }
     };
 
@@ -205,9 +281,15 @@ pub fn run_tool_update(name: String, version: Option<String>) -> AppResult {
         .to_string();
     let new_version = version.or_else(|| Some(DEFAULT_VERSION.to_string()));
     let new_version_str = new_version.as_deref().unwrap_or(DEFAULT_VERSION);
+    output.version = Some(new_version_str.to_string());
+    output.previous_version = Some(old_version.clone());
 
     if old_version == new_version_str {
-        println!("Tool '{}' is already at version {}", name, new_version_str);
+        if json {
+            write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        } else {
+            println!("Tool '{}' is already at version {}", name, new_version_str);
+        }
         return Ok(None);
     }
 
@@ -221,10 +303,14 @@ pub fn run_tool_update(name: String, version: Option<String>) -> AppResult {
 
     registry.add_tool(updated_tool);
     if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save tool registry: {}", e);
-        return Ok(Some(1));
+        return Ok(output.fail(json, format!("Failed to save tool registry: {}", e)));
     }
 
+    if json {
+        output.changed = true;
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully updated tool '{}' from {} to {}",
         name, old_version, new_version_str
@@ -235,31 +321,36 @@ pub fn run_tool_update(name: String, version: Option<String>) -> AppResult {
 
 /// Run the tool uninstall command
 pub fn run_tool_uninstall(name: String) -> AppResult {
-    println!("Uninstalling Morphir tool: {}", name);
+    let json = OutputFormat::global().is_json();
+    let mut output = RegistryChangeOutput::new("tool", "uninstall", &name);
+    if !json {
+        println!("Uninstalling Morphir tool: {}", name);
+    }
 
     let mut registry = match ToolRegistry::load() {
         Ok(reg) => reg,
-        Err(e) => {
-            eprintln!("Error: Failed to load tool registry: {}", e);
-            return Ok(Some(1));
-        }
+        Err(e) => return Ok(output.fail(json, format!("Failed to load tool registry: {}", e))),
     };
 
     // Remove tool from registry
     let removed_tool = match registry.remove_tool(&name) {
         Some(tool) => tool,
         None => {
-            eprintln!("Error: Tool '{}' is not installed", name);
-            return Ok(Some(1));
+            return Ok(output.fail(json, format!("Tool '{}' is not installed", name)));
         }
     };
 
     if let Err(e) = registry.save() {
-        eprintln!("Error: Failed to save tool registry: {}", e);
-        return Ok(Some(1));
+        return Ok(output.fail(json, format!("Failed to save tool registry: {}", e)));
     }
 
     let version_str = removed_tool.version.as_deref().unwrap_or(DEFAULT_VERSION);
+    if json {
+        output.version = Some(version_str.to_string());
+        output.changed = true;
+        write_output(OutputFormat::Json, &output).map_err(CliError::from)?;
+        return Ok(None);
+    }
     println!(
         "✓ Successfully uninstalled tool '{}' (version: {})",
         removed_tool.name, version_str
@@ -1,16 +1,111 @@
 //! Transform command for Morphir IR transformation
+//!
+//! Runs a builtin transform, such as `extract-constants`, over an IR file
+//! and writes the transformed IR.
 
+use crate::diagnostics::DiagnosticRenderer;
+use crate::error::CliError;
+use crate::output::{Diagnostic, TransformOutput, json_requested};
+use crate::pipeline::{Transformed, apply_transform, error};
+use morphir_common::loader::load_ir;
 use starbase::AppResult;
+use std::path::Path;
+
+/// IR file transformed when no input is given
+const DEFAULT_INPUT: &str = "morphir-ir.json";
 
 /// Run the transform command
-pub fn run_transform(input: Option<String>, output: Option<String>) -> AppResult {
-    println!("Transforming Morphir IR...");
-    if let Some(path) = input {
-        println!("Input path: {}", path);
+///
+/// `options` is passed to the transform as JSON. The transformed IR is
+/// written to `output` when given; the transform's report is printed either
+/// way.
+pub fn run_transform(
+    transform: String,
+    input: Option<String>,
+    output: Option<String>,
+    options: Option<String>,
+    json: bool,
+) -> AppResult {
+    let json = json_requested(json);
+    let input = input.unwrap_or_else(|| DEFAULT_INPUT.to_string());
+
+    let finish = |diagnostics: Vec<Diagnostic>, report: serde_json::Value, output: Option<&str>| {
+        let success = diagnostics.iter().all(|d| d.level != "error");
+        if json {
+            let result = TransformOutput {
+                success,
+                transform: transform.clone(),
+                input: input.clone(),
+                output: output.map(str::to_string),
+                report,
+                diagnostics,
+            };
+            println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        } else {
+            DiagnosticRenderer::stderr().eprint_all(&diagnostics);
+            print_report(&report);
+            if let Some(path) = output {
+                println!("Wrote transformed IR to {}", path);
+            }
+        }
+        if success { None } else { Some(1) }
+    };
+    let fail = |message: String| finish(vec![error(message)], serde_json::Value::Null, None);
+
+    let options: serde_json::Value = match options.as_deref().map(serde_json::from_str) {
+        None => serde_json::json!({}),
+        Some(Ok(options)) => options,
+        Some(Err(e)) => return Ok(fail(format!("Invalid --options JSON: {}", e))),
+    };
+    let ir = match load_ir(Path::new(&input)) {
+        Ok(ir) => ir,
+        Err(e) => return Ok(fail(format!("Failed to load input: {:#}", e))),
+    };
+
+    let transformed = match apply_transform(&transform, &ir, &options) {
+        Ok(transformed) => transformed,
+        Err(CliError::Validation { message } | CliError::Extension { message }) => {
+            return Ok(fail(message));
+        }
+        Err(e) => return Ok(fail(e.to_string())),
+    };
+    let Transformed {
+        error: failure,
+        ir: transformed,
+        report,
+        mut diagnostics,
+        ..
+    } = transformed;
+    if let Some(message) = failure {
+        diagnostics.push(error(message));
+        return Ok(finish(diagnostics, serde_json::Value::Null, None));
     }
-    if let Some(path) = output {
-        println!("Output path: {}", path);
+
+    let mut written = None;
+    if let (Some(path), Some(ir)) = (output.as_deref(), transformed) {
+        let content = serde_json::to_string_pretty(&ir).unwrap();
+        if let Err(e) = std::fs::write(path, content) {
+            diagnostics.push(error(format!("Failed to write {}: {}", path, e)));
+            return Ok(finish(diagnostics, report, None));
+        }
+        written = Some(path);
+    }
+    Ok(finish(diagnostics, report, written))
+}
+
+/// Print the constants an extraction found, one per line
+fn print_report(report: &serde_json::Value) {
+    let Some(constants) = report.get("constants").and_then(|c| c.as_array()) else {
+        return;
+    };
+    for constant in constants {
+        let field = |key: &str| constant.get(key).and_then(|v| v.as_str()).unwrap_or("");
+        println!(
+            "{} = {}  ({}: {})",
+            field("name"),
+            field("literal"),
+            field("value"),
+            field("context")
+        );
     }
-    // TODO: Implement transformation logic
-    Ok(None)
 }
@@ -1,13 +1,171 @@
 //! Validate command for Morphir IR validation
+//!
+//! Loads a V4 distribution and checks that it is well formed: that every
+//! reference resolves and every constructor and type gets as many arguments
+//! as it takes. Problems point into the IR file with a JSON Pointer. A
+//! well-formed distribution is then type checked, every value definition
+//! against its declared signature, and its pattern matches are checked for
+//! exhaustiveness.
 
+use crate::diagnostics::DiagnosticRenderer;
+use crate::messages::catalog;
+use crate::output::{Diagnostic, ValidateOutput, json_requested};
+use morphir_common::loader::{
+    LoadedDistribution, attach_dependencies, load_distribution_from_source,
+};
+use morphir_core::ir::v4::typecheck::{self, Severity};
+use morphir_core::ir::v4::{Distribution, IRFile, Problem, check_well_formed, exhaustiveness};
 use starbase::AppResult;
 
+/// IR file validated when no input is given
+const DEFAULT_INPUT: &str = "morphir-ir.json";
+
+/// Convert a type checking diagnostic for output, rewording it through the
+/// message catalog.
+pub(crate) fn convert_diagnostic(diagnostic: &typecheck::Diagnostic) -> Diagnostic {
+    let location = diagnostic.location.as_ref();
+    Diagnostic {
+        level: match diagnostic.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+        .to_string(),
+        code: Some(diagnostic.code.clone()),
+        message: format!(
+            "{} (in {})",
+            catalog().diagnostic(Some(&diagnostic.code), &diagnostic.message),
+            diagnostic.definition.to_canonical_string()
+        ),
+        file: location
+            .and_then(|l| l.file.as_ref())
+            .map(|f| f.to_string()),
+        line: location.map(|l| l.start_line),
+        column: location.map(|l| l.start_column),
+        end_line: location.map(|l| l.end_line),
+        end_column: location.map(|l| l.end_column),
+        related: Vec::new(),
+        fixes: Vec::new(),
+    }
+}
+
+/// Convert a well-formedness problem for output, located at
+/// `<input>#<pointer>`
+fn convert_problem(input: &str, problem: &Problem) -> Diagnostic {
+    Diagnostic {
+        level: "error".to_string(),
+        code: Some(problem.code.to_string()),
+        message: problem.message.clone(),
+        file: Some(format!("{}#{}", input, problem.pointer)),
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        related: Vec::new(),
+        fixes: Vec::new(),
+    }
+}
+
+/// The JSON problems point into: the input file as written, or the
+/// distribution as loaded when the input is a directory or a remote source
+fn ir_document(input: &str, ir_file: &IRFile) -> serde_json::Value {
+    std::fs::read_to_string(input)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| serde_json::to_value(ir_file).unwrap_or_default())
+}
+
+/// Type check a distribution and check its pattern matches, with the
+/// diagnostics converted for output
+pub(crate) fn validate_distribution(distribution: &Distribution) -> Vec<Diagnostic> {
+    let type_errors = typecheck::typecheck_distribution(distribution);
+    let match_errors = exhaustiveness::check_distribution(distribution);
+    type_errors
+        .iter()
+        .chain(&match_errors)
+        .map(convert_diagnostic)
+        .collect()
+}
+
 /// Run the validate command
-pub fn run_validate(input: Option<String>) -> AppResult {
-    println!("Validating Morphir IR...");
-    if let Some(path) = input {
-        println!("Input path: {}", path);
+///
+/// Each of `dependencies` is loaded and attached as a dependency of the
+/// input, so references into it are checked against its specification.
+pub fn run_validate(input: Option<String>, dependencies: Vec<String>, json: bool) -> AppResult {
+    let json = json_requested(json);
+    let input = input.unwrap_or_else(|| DEFAULT_INPUT.to_string());
+
+    let finish = |diagnostics: Vec<Diagnostic>| {
+        let success = diagnostics.iter().all(|d| d.level != "error");
+        if json {
+            let output = ValidateOutput {
+                success,
+                input: input.clone(),
+                diagnostics,
+            };
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        } else {
+            DiagnosticRenderer::stderr().eprint_all(&diagnostics);
+        }
+        success
+    };
+    let fail = |message: String| Diagnostic {
+        level: "error".to_string(),
+        code: None,
+        message,
+        file: None,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        related: Vec::new(),
+        fixes: Vec::new(),
+    };
+
+    let mut ir_file = match load_distribution_from_source(&input) {
+        Ok(LoadedDistribution::V4(ir_file)) => ir_file,
+        Ok(LoadedDistribution::Classic(_)) => {
+            finish(vec![fail(
+                "Validation requires V4 IR. Convert the input first with `morphir ir migrate`."
+                    .to_string(),
+            )]);
+            return Ok(Some(1));
+        }
+        Err(e) => {
+            finish(vec![fail(format!("Failed to load input: {}", e))]);
+            return Ok(Some(1));
+        }
+    };
+
+    if let Err(e) = attach_dependencies(&mut ir_file.distribution, &dependencies) {
+        finish(vec![fail(format!("{:#}", e))]);
+        return Ok(Some(1));
+    }
+
+    // Type checking assumes references resolve, so it only runs on a
+    // well-formed distribution
+    let problems = check_well_formed(&ir_document(&input, &ir_file), &ir_file.distribution);
+    let diagnostics = if problems.is_empty() {
+        validate_distribution(&ir_file.distribution)
+    } else {
+        problems
+            .iter()
+            .map(|problem| convert_problem(&input, problem))
+            .collect()
+    };
+    let errors = diagnostics.iter().filter(|d| d.level == "error").count();
+    let warnings = diagnostics.len() - errors;
+
+    if !finish(diagnostics) {
+        if !json {
+            eprintln!(
+                "Validation failed: {} error(s), {} warning(s)",
+                errors, warnings
+            );
+        }
+        return Ok(Some(1));
+    }
+    if !json {
+        println!("{} is valid ({} warning(s))", input, warnings);
     }
-    // TODO: Implement validation logic
     Ok(None)
 }
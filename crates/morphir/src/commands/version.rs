@@ -1,6 +1,26 @@
+//! Version Commands
+//!
+//! `morphir version` prints the version of the CLI. `morphir version bump`
+//! compares the project's IR against the version of the package published
+//! last, classifies the changes as major, minor, or patch, and sets the
+//! project's version to the one they call for; with `--check` it only
+//! verifies that the version in morphir.toml is high enough.
+
+use crate::commands::deps::output_error;
+use crate::commands::package::{client_for, load_config};
+use crate::commands::text::{input_or_project_ir, load};
+use crate::output::json_requested;
+use morphir_common::config::edit::set_project_version;
+use morphir_common::registry::{LATEST, RegistryError};
+use morphir_core::ir::v4::{Bump, Distribution, Impact, VersionImpact, version_impact};
+use semver::Version;
 use serde::Serialize;
 use starbase::AppResult;
 
+/// Exit code of `version bump --check` when the project's version is lower
+/// than its changes call for
+pub const BUMP_NEEDED_EXIT_CODE: u8 = 2;
+
 /// Version information for the Morphir CLI
 #[derive(Serialize)]
 pub struct VersionInfo {
@@ -28,6 +48,7 @@ impl Default for VersionInfo {
 }
 
 pub fn run_version(json: bool) -> AppResult {
+    let json = json_requested(json);
     let info = VersionInfo::new();
 
     if json {
@@ -47,3 +68,181 @@ pub fn run_version(json: bool) -> AppResult {
 
     Ok(None)
 }
+
+/// Options for `morphir version bump`
+#[derive(Debug, Default)]
+pub struct VersionBumpOptions {
+    /// Only check that the project's version is high enough
+    pub check: bool,
+    /// Previous distribution to compare against instead of the newest
+    /// published version: file, directory, or remote source
+    pub against: Option<String>,
+    /// Version of the `against` distribution
+    pub previous: Option<String>,
+    /// IR of the new version; the project's compiled IR if omitted
+    pub input: Option<String>,
+    /// Path to configuration file
+    pub config_path: Option<String>,
+    /// Registry URL overriding `[registry] url`
+    pub registry: Option<String>,
+    /// Output JSON format
+    pub json: bool,
+}
+
+/// JSON output of `morphir version bump`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BumpOutput<'a> {
+    success: bool,
+    /// Version published last, if any
+    previous: Option<String>,
+    /// Version in morphir.toml
+    version: String,
+    /// Lowest version the changes allow
+    suggested: String,
+    #[serde(flatten)]
+    impact: &'a VersionImpact,
+    /// Whether morphir.toml was changed
+    updated: bool,
+}
+
+/// Run `morphir version bump`
+pub fn run_version_bump(options: VersionBumpOptions) -> AppResult {
+    let json = json_requested(options.json);
+    let fail = |message: &str| -> AppResult { Ok(Some(output_error(json, message))) };
+
+    let (config_file, config) = match load_config(options.config_path) {
+        Ok(loaded) => loaded,
+        Err(message) => return fail(&message),
+    };
+    let (Some(config_file), Some(project)) = (config_file, config.project.as_ref()) else {
+        return fail("No morphir.toml with a [project] section found");
+    };
+    let version = match Version::parse(&project.version) {
+        Ok(version) => version,
+        Err(e) => {
+            return fail(&format!(
+                "Invalid [project] version {}: {}",
+                project.version, e
+            ));
+        }
+    };
+
+    let input = input_or_project_ir(options.input)?;
+    let new = match load(&input) {
+        Ok(distribution) => distribution,
+        Err(message) => return fail(&message),
+    };
+    let published = match options.against {
+        Some(against) => previous_from(&against, options.previous.as_deref()),
+        None => client_for(&config, options.registry).and_then(|client| {
+            match client.fetch(&project.name, LATEST) {
+                Ok((published, path)) => {
+                    previous_from(&path.display().to_string(), Some(&published.version))
+                }
+                // Nothing to compare a first release against
+                Err(
+                    RegistryError::PackageNotFound(_) | RegistryError::NoMatchingVersion { .. },
+                ) => Ok(None),
+                Err(e) => Err(e.to_string()),
+            }
+        }),
+    };
+    let (previous, old) = match published {
+        Ok(Some(published)) => published,
+        Ok(None) => {
+            if json {
+                let output = serde_json::json!({
+                    "success": true,
+                    "previous": null,
+                    "version": version.to_string(),
+                });
+                println!("{}", serde_json::to_string_pretty(&output).unwrap());
+            } else {
+                println!(
+                    "{} has no published version; {} is its first release",
+                    project.name, version
+                );
+            }
+            return Ok(None);
+        }
+        Err(message) => return fail(&message),
+    };
+
+    let impact = version_impact(&old, &new);
+    let suggested = impact.next_version(&previous);
+    let allowed = impact.allows(&previous, &version);
+    let updated = !options.check && !allowed;
+    if updated && let Err(e) = set_project_version(&config_file, &suggested.to_string()) {
+        return fail(&e.to_string());
+    }
+
+    if json {
+        let output = BumpOutput {
+            success: allowed || updated,
+            previous: Some(previous.to_string()),
+            version: version.to_string(),
+            suggested: suggested.to_string(),
+            impact: &impact,
+            updated,
+        };
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else {
+        print_impact(&impact, &previous);
+        println!("Suggested version: {}", suggested);
+        if allowed {
+            println!(
+                "Version {} in {} covers the changes",
+                version,
+                config_file.display()
+            );
+        } else if updated {
+            println!(
+                "Set the version in {} from {} to {}",
+                config_file.display(),
+                version,
+                suggested
+            );
+        } else {
+            eprintln!(
+                "Version {} in {} is too low for a {} change since {}; bump it to {}",
+                version,
+                config_file.display(),
+                impact.bump,
+                previous,
+                suggested
+            );
+        }
+    }
+    Ok((!allowed && !updated).then_some(BUMP_NEEDED_EXIT_CODE))
+}
+
+/// The version and distribution at `source`, published as `version`
+fn previous_from(
+    source: &str,
+    version: Option<&str>,
+) -> Result<Option<(Version, Distribution)>, String> {
+    let version = version.ok_or("--against needs the --previous version it was published as")?;
+    let version = Version::parse(version)
+        .map_err(|e| format!("Invalid previous version {}: {}", version, e))?;
+    Ok(Some((version, load(source)?)))
+}
+
+fn print_impact(impact: &VersionImpact, previous: &Version) {
+    if impact.bump == Bump::None {
+        println!("No changes since {}", previous);
+        return;
+    }
+    println!("Changes since {} ({}):", previous, impact.bump);
+    for (kind, label) in [
+        (Impact::Breaking, "breaking"),
+        (Impact::Additive, "additive"),
+    ] {
+        for change in impact.changes.iter().filter(|change| change.impact == kind) {
+            println!("  {}: {}", label, change);
+        }
+    }
+    if impact.implementation_changed && impact.changes.is_empty() {
+        println!("  definitions changed without changing the public specification");
+    }
+}
@@ -0,0 +1,355 @@
+//! Rendering of diagnostics with source snippets.
+//!
+//! Shows each diagnostic the way compilers do: its severity, code, and
+//! message, a pointer to its location, and the offending source lines with
+//! the reported span underlined. Related locations follow as notes with
+//! snippets of their own, and each suggested fix as help.
+//!
+//! ```text
+//! error[PARSE_ERROR]: Duplicate definition of `total`
+//!   --> src/orders.gleam:7:8
+//!    |
+//!  7 | pub fn total(lines) {
+//!    |        ^^^^^
+//! note: first defined here
+//!   --> src/orders.gleam:3:8
+//!    |
+//!  3 | pub fn total(order) {
+//!    |        -----
+//! ```
+//!
+//! Sources are read from disk once per file. Without the source, or a line
+//! number, a diagnostic renders as [`Diagnostic::render_human`] does.
+//! Colors are used when the output is a terminal and `NO_COLOR` is unset.
+
+use crate::output::Diagnostic;
+use owo_colors::{OwoColorize, Style};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::rc::Rc;
+
+/// Spans longer than this show their first and last lines only
+const MAX_SPAN_LINES: usize = 4;
+
+/// Display width of a tab
+const TAB_WIDTH: usize = 4;
+
+/// Renders diagnostics with source snippets
+#[derive(Debug, Default)]
+pub struct DiagnosticRenderer {
+    color: bool,
+    /// Lines of each file read so far; `None` if it could not be read
+    sources: RefCell<HashMap<String, Option<Rc<Vec<String>>>>>,
+}
+
+impl DiagnosticRenderer {
+    /// Renderer for messages written to stderr
+    pub fn stderr() -> Self {
+        Self::default().with_color(use_color(std::io::stderr().is_terminal()))
+    }
+
+    /// Renderer for messages written to stdout
+    pub fn stdout() -> Self {
+        Self::default().with_color(use_color(std::io::stdout().is_terminal()))
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Use `text` as the content of `file` instead of reading it.
+    pub fn with_source(self, file: impl Into<String>, text: &str) -> Self {
+        let lines = text.lines().map(str::to_string).collect();
+        self.sources
+            .borrow_mut()
+            .insert(file.into(), Some(Rc::new(lines)));
+        self
+    }
+
+    /// Render `diagnostic`, without a trailing newline.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let severity = self.severity_style(&diagnostic.level);
+        let code = diagnostic
+            .code
+            .as_ref()
+            .map(|c| format!("[{}]", c))
+            .unwrap_or_default();
+        let mut out = vec![format!(
+            "{}: {}",
+            self.paint(&format!("{}{}", diagnostic.level, code), severity),
+            self.paint(&diagnostic.message, Style::new().bold())
+        )];
+        let span = Span {
+            file: diagnostic.file.as_deref(),
+            line: diagnostic.line,
+            column: diagnostic.column,
+            end_line: diagnostic.end_line,
+            end_column: diagnostic.end_column,
+        };
+        self.location(&span, '^', severity, &mut out);
+        let note = self.severity_style("note");
+        for related in &diagnostic.related {
+            out.push(format!("{}: {}", self.paint("note", note), related.message));
+            let span = Span {
+                file: related.file.as_deref(),
+                line: related.line,
+                column: related.column,
+                end_line: related.end_line,
+                end_column: related.end_column,
+            };
+            self.location(&span, '-', note, &mut out);
+        }
+        let help = self.severity_style("help");
+        for fix in &diagnostic.fixes {
+            out.push(format!("{}: {}", self.paint("help", help), fix.title));
+        }
+        out.join("\n")
+    }
+
+    /// Write each diagnostic to stderr.
+    pub fn eprint_all(&self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            eprintln!("{}", self.render(diagnostic));
+        }
+    }
+
+    /// The pointer to a span, then its snippet if the source is at hand
+    fn location(&self, span: &Span, marker: char, style: Style, out: &mut Vec<String>) {
+        let Some(file) = span.file else {
+            return;
+        };
+        let arrow = self.paint("-->", self.gutter_style());
+        let Some(line) = span.line.filter(|&l| l > 0) else {
+            out.push(format!("  {} {}", arrow, file));
+            return;
+        };
+        match span.column {
+            Some(column) => out.push(format!("  {} {}:{}:{}", arrow, file, line, column)),
+            None => out.push(format!("  {} {}:{}", arrow, file, line)),
+        }
+        if let Some(lines) = self.source(file) {
+            self.snippet(&lines, span, line as usize, marker, style, out);
+        }
+    }
+
+    fn snippet(
+        &self,
+        lines: &[String],
+        span: &Span,
+        first: usize,
+        marker: char,
+        style: Style,
+        out: &mut Vec<String>,
+    ) {
+        if first > lines.len() {
+            return;
+        }
+        let last = span
+            .end_line
+            .map(|l| l as usize)
+            .filter(|&l| l >= first)
+            .unwrap_or(first)
+            .min(lines.len());
+        let shown: Vec<usize> = if last - first < MAX_SPAN_LINES {
+            (first..=last).collect()
+        } else {
+            vec![first, first + 1, last]
+        };
+
+        let width = last.to_string().len();
+        let gutter = self.paint("|", self.gutter_style());
+        out.push(format!("{} {}", " ".repeat(width + 1), gutter));
+        let mut previous = first;
+        for number in shown {
+            if number > previous + 1 {
+                out.push(self.paint("...", self.gutter_style()));
+            }
+            previous = number;
+            let text = &lines[number - 1];
+            let chars: Vec<char> = text.chars().collect();
+            // Columns are 1-based; the end column is exclusive
+            let start = if number == first {
+                span.column.map_or(1, |c| c.max(1) as usize)
+            } else {
+                1
+            };
+            let end = match span.end_column {
+                Some(end) if number == last && (last > first || end as usize > start) => {
+                    end as usize
+                }
+                _ if number < last => chars.len() + 1,
+                _ if span.column.is_none() => chars.len() + 1,
+                _ => start + 1,
+            };
+            let start = start.min(chars.len() + 1);
+            let end = end.clamp(start + 1, chars.len().max(start) + 1);
+            let indent = display_width(&chars[..start - 1]);
+            let length = display_width(&chars[start - 1..(end - 1).min(chars.len())]).max(1);
+
+            out.push(format!(
+                "{} {} {}",
+                self.paint(
+                    &format!("{:>width$}", number, width = width + 1),
+                    self.gutter_style()
+                ),
+                gutter,
+                expand_tabs(text)
+            ));
+            out.push(format!(
+                "{} {} {}{}",
+                " ".repeat(width + 1),
+                gutter,
+                " ".repeat(indent),
+                self.paint(&marker.to_string().repeat(length), style)
+            ));
+        }
+    }
+
+    fn source(&self, file: &str) -> Option<Rc<Vec<String>>> {
+        self.sources
+            .borrow_mut()
+            .entry(file.to_string())
+            .or_insert_with(|| {
+                std::fs::read_to_string(file)
+                    .ok()
+                    .map(|text| Rc::new(text.lines().map(str::to_string).collect()))
+            })
+            .clone()
+    }
+
+    fn severity_style(&self, level: &str) -> Style {
+        match level {
+            "error" => Style::new().red().bold(),
+            "warning" => Style::new().yellow().bold(),
+            "note" => Style::new().cyan().bold(),
+            "help" => Style::new().green().bold(),
+            _ => Style::new().blue().bold(),
+        }
+    }
+
+    fn gutter_style(&self) -> Style {
+        Style::new().blue().bold()
+    }
+
+    fn paint(&self, text: &str, style: Style) -> String {
+        if self.color {
+            text.style(style).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Where a diagnostic or one of its related notes points
+struct Span<'a> {
+    file: Option<&'a str>,
+    line: Option<u32>,
+    column: Option<u32>,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+}
+
+/// Whether to color output written to a stream
+fn use_color(is_terminal: bool) -> bool {
+    is_terminal && std::env::var_os("NO_COLOR").is_none()
+}
+
+fn display_width(chars: &[char]) -> usize {
+    chars
+        .iter()
+        .map(|&c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::RelatedDiagnostic;
+
+    const SOURCE: &str = "import gleam/list\n\npub fn total(order) {\n  order.amount\n}\n\npub fn total(lines) {\n\tlist.sum(lines)\n}\n";
+
+    fn diagnostic() -> Diagnostic {
+        Diagnostic {
+            level: "error".to_string(),
+            code: Some("PARSE_ERROR".to_string()),
+            message: "Duplicate definition of `total`".to_string(),
+            file: Some("src/orders.gleam".to_string()),
+            line: Some(7),
+            column: Some(8),
+            end_line: Some(7),
+            end_column: Some(13),
+            related: vec![RelatedDiagnostic {
+                message: "first defined here".to_string(),
+                file: Some("src/orders.gleam".to_string()),
+                line: Some(3),
+                column: Some(8),
+                end_line: Some(3),
+                end_column: Some(13),
+            }],
+            fixes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_render_snippets_with_spans() {
+        let renderer = DiagnosticRenderer::default().with_source("src/orders.gleam", SOURCE);
+        assert_eq!(
+            renderer.render(&diagnostic()),
+            "error[PARSE_ERROR]: Duplicate definition of `total`
+  --> src/orders.gleam:7:8
+   |
+ 7 | pub fn total(lines) {
+   |        ^^^^^
+note: first defined here
+  --> src/orders.gleam:3:8
+   |
+ 3 | pub fn total(order) {
+   |        -----"
+        );
+    }
+
+    #[test]
+    fn test_multi_line_spans_and_tabs() {
+        let renderer = DiagnosticRenderer::default().with_source("src/orders.gleam", SOURCE);
+        let mut warning = diagnostic();
+        warning.level = "warning".to_string();
+        warning.code = None;
+        warning.line = Some(7);
+        warning.column = Some(1);
+        warning.end_line = Some(8);
+        warning.end_column = Some(6);
+        warning.related.clear();
+        assert_eq!(
+            renderer.render(&warning),
+            "warning: Duplicate definition of `total`
+  --> src/orders.gleam:7:1
+   |
+ 7 | pub fn total(lines) {
+   | ^^^^^^^^^^^^^^^^^^^^^
+ 8 |     list.sum(lines)
+   | ^^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_falls_back_without_source_and_colors() {
+        let mut missing = diagnostic();
+        missing.file = Some("does/not/exist.gleam".to_string());
+        missing.related.clear();
+        let plain = DiagnosticRenderer::default();
+        assert_eq!(plain.render(&missing), missing.render_human());
+
+        let colored = DiagnosticRenderer::default()
+            .with_color(true)
+            .with_source("src/orders.gleam", SOURCE);
+        let rendered = colored.render(&diagnostic());
+        assert!(rendered.contains("\u{1b}[31"));
+        assert!(rendered.contains("pub fn total(lines)"));
+    }
+}
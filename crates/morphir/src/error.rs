@@ -1,7 +1,9 @@
 //! Error handling utilities for CLI commands
 
-use crate::output::{Diagnostic, OutputFormat};
+use crate::messages::catalog;
+use crate::output::{Diagnostic, OutputFormat, RelatedDiagnostic};
 use miette::Diagnostic as MietteDiagnostic;
+use morphir_common::MessageCatalog;
 
 /// CLI error that can be formatted for human or JSON output
 #[derive(Debug, thiserror::Error, MietteDiagnostic)]
@@ -34,27 +36,54 @@ pub enum CliError {
 }
 
 impl CliError {
+    /// Message catalog ID for this error
+    pub fn message_id(&self) -> &'static str {
+        match self {
+            CliError::Config { .. } => "cli.config_error",
+            CliError::Extension { .. } => "cli.extension_error",
+            CliError::Compilation { .. } => "cli.compilation_error",
+            CliError::FileSystem { .. } => "cli.filesystem_error",
+            CliError::Validation { .. } => "cli.validation_error",
+        }
+    }
+
+    /// User-facing message from the given catalog
+    pub fn localized_message(&self, catalog: &MessageCatalog) -> String {
+        let args: Vec<(&str, &str)> = match self {
+            CliError::Extension { message }
+            | CliError::Compilation { message }
+            | CliError::Validation { message } => vec![("message", message.as_str())],
+            CliError::Config { .. } | CliError::FileSystem { .. } => Vec::new(),
+        };
+        catalog.format(self.message_id(), &args)
+    }
+
     /// Convert to diagnostic for JSON output
     pub fn to_diagnostic(&self) -> Diagnostic {
         Diagnostic {
             level: "error".to_string(),
-            message: self.to_string(),
+            code: Some(self.message_id().to_string()),
+            message: self.localized_message(catalog()),
             file: None,
             line: None,
             column: None,
+            end_line: None,
+            end_column: None,
+            related: Vec::new(),
+            fixes: Vec::new(),
         }
     }
 
     /// Report error using miette (for human-readable output)
     pub fn report(&self) {
         // Print error with color using owo_colors if available
-        eprintln!("error: {}", self);
+        eprintln!("error: {}", self.localized_message(catalog()));
     }
 
     /// Report error based on output format
     pub fn report_with_format(&self, format: OutputFormat) {
         match format {
-            OutputFormat::Human => {
+            OutputFormat::Human | OutputFormat::Table => {
                 self.report();
             }
             OutputFormat::Json | OutputFormat::JsonLines => {
@@ -88,7 +117,8 @@ pub fn handle_error<T>(result: anyhow::Result<T>, format: OutputFormat) -> Resul
     })
 }
 
-/// Convert extension diagnostics to CLI diagnostics
+/// Convert extension diagnostics to CLI diagnostics, rewording them through
+/// the message catalog
 pub fn convert_extension_diagnostics(
     ext_diagnostics: &[morphir_extension_sdk::Diagnostic],
 ) -> Vec<Diagnostic> {
@@ -102,10 +132,53 @@ pub fn convert_extension_diagnostics(
                 morphir_extension_sdk::DiagnosticSeverity::Hint => "hint",
             }
             .to_string(),
-            message: d.message.clone(),
+            code: d.code.clone(),
+            message: catalog().diagnostic(d.code.as_deref(), &d.message),
             file: d.location.as_ref().map(|l| l.file.clone()),
-            line: d.location.as_ref().map(|l| l.start_line),
-            column: d.location.as_ref().map(|l| l.start_col),
+            // Line 0 means the location is a whole file, such as a binary
+            line: d.location.as_ref().map(|l| l.start_line).filter(|&l| l > 0),
+            column: d.location.as_ref().map(|l| l.start_col).filter(|&c| c > 0),
+            end_line: d.location.as_ref().map(|l| l.end_line),
+            end_column: d.location.as_ref().map(|l| l.end_col),
+            related: d
+                .related
+                .iter()
+                .map(|r| RelatedDiagnostic {
+                    message: r.message.clone(),
+                    file: Some(r.location.file.clone()),
+                    line: Some(r.location.start_line),
+                    column: Some(r.location.start_col),
+                    end_line: Some(r.location.end_line),
+                    end_column: Some(r.location.end_col),
+                })
+                .collect(),
+            fixes: d.fixes.clone(),
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localized_message_uses_catalog() {
+        let err = CliError::Extension {
+            message: "plugin crashed".to_string(),
+        };
+        assert_eq!(
+            err.localized_message(&MessageCatalog::embedded()),
+            err.to_string()
+        );
+
+        let reworded = MessageCatalog::from_toml(
+            "fr",
+            "[cli]\nextension_error = \"Erreur d'extension : {message}\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            err.localized_message(&reworded),
+            "Erreur d'extension : plugin crashed"
+        );
+    }
+}
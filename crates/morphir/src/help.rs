@@ -46,8 +46,12 @@ pub fn print_full_help<C: clap::CommandFactory>() {
     // Unhide the experimental commands
     for subcommand in cmd.get_subcommands_mut() {
         if subcommand.get_name() == "validate"
+            || subcommand.get_name() == "check"
+            || subcommand.get_name() == "fix"
             || subcommand.get_name() == "generate"
             || subcommand.get_name() == "transform"
+            || subcommand.get_name() == "run"
+            || subcommand.get_name() == "repl"
             || subcommand.get_name() == "compile"
             || subcommand.get_name() == "gleam"
         {
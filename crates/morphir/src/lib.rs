@@ -1,11 +1,18 @@
 //! Morphir CLI Library
 //!
 //! This library exposes CLI functionality for programmatic use and testing.
+//! [`Pipeline`] embeds a whole build, from configuration to generated code,
+//! in another Rust program.
 
 pub mod commands;
+pub mod diagnostics;
 pub mod error;
+pub mod messages;
 pub mod output;
+pub mod pipeline;
+pub mod testing;
 pub mod tui;
 
 pub use error::CliError;
 pub use output::OutputFormat;
+pub use pipeline::{Pipeline, PipelineOutput};
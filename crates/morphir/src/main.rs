@@ -2,18 +2,36 @@ use clap::{Parser, Subcommand};
 use starbase::{App, AppResult, AppSession};
 
 pub mod commands;
+pub mod diagnostics;
 pub mod error;
 mod help;
 mod logging;
+mod messages;
 pub mod output;
+pub mod pipeline;
+pub mod testing;
 mod tui;
 
+use output::{LogFormat, OutputFormat};
+
 use commands::{
-    compile::CompileOptions, run_compile, run_dist_install, run_dist_list, run_dist_uninstall,
-    run_dist_update, run_extension_install, run_extension_list, run_extension_uninstall,
-    run_extension_update, run_generate, run_gleam_compile, run_gleam_generate, run_gleam_roundtrip,
-    run_migrate, run_tool_install, run_tool_list, run_tool_uninstall, run_tool_update,
-    run_transform, run_validate, run_version,
+    BuildOptions, ConfigDoctorOptions, DaemonStartOptions, DocsCommandOptions, DocsFormatArg,
+    FormatCommandOptions, GraphCommandOptions, GraphFormat, GraphKindArg, QueryCommandOptions,
+    RenameCommandOptions, ReplOptions, RestructureCommandOptions, RunOptions, SampleCommandOptions,
+    ShowCommandOptions, SymbolKindArg, TestOptions, TextFormat, VersionBumpOptions,
+    compile::CompileOptions, extension_new::ExtensionCapability,
+    extension_new::ExtensionNewOptions, init::InitOptions, init::ProjectTemplate,
+    init::SourceLanguage, package::PublishOptions, run_build, run_cache_clear,
+    run_cache_rebuild_index, run_cache_stats, run_check, run_compile, run_config_doctor,
+    run_daemon_start, run_daemon_status, run_daemon_stop, run_deps_vendor, run_dist_install,
+    run_dist_list, run_dist_uninstall, run_dist_update, run_docs, run_extension_doctor,
+    run_extension_install, run_extension_list, run_extension_new, run_extension_uninstall,
+    run_extension_update, run_fix, run_generate, run_gleam_compile, run_gleam_generate,
+    run_gleam_roundtrip, run_init, run_ir_format, run_ir_graph, run_ir_query, run_ir_rename,
+    run_ir_restructure, run_ir_sample, run_ir_show, run_ir_spec_diff, run_lint, run_lsp,
+    run_migrate, run_model, run_new, run_package_add, run_package_search, run_publish, run_repl,
+    run_source_prefetch, run_stats, run_test, run_tool_install, run_tool_list, run_tool_uninstall,
+    run_tool_update, run_transform, run_validate, run_version, run_version_bump,
 };
 
 /// Morphir CLI - Tools for functional domain modeling and business logic
@@ -31,6 +49,14 @@ struct Cli {
     #[arg(short = 'V', long, action = clap::ArgAction::Version)]
     version: Option<bool>,
 
+    /// Resolve remote sources only from the local cache, never the network
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Result format of every command: text, json, ndjson, or table (default: $MORPHIR_OUTPUT, else text)
+    #[arg(long = "output", value_enum, value_name = "FORMAT")]
+    output_format: Option<OutputFormat>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -38,6 +64,65 @@ struct Cli {
 #[derive(Clone, Subcommand)]
 enum Commands {
     // ===== Core Commands =====
+    /// Set up a Morphir project or workspace in an existing directory
+    Init {
+        /// Directory to set up
+        #[arg(default_value = ".")]
+        path: std::path::PathBuf,
+        #[command(flatten)]
+        scaffold: ScaffoldArgs,
+    },
+    /// Create a new Morphir project or workspace
+    New {
+        /// Directory to create
+        path: std::path::PathBuf,
+        #[command(flatten)]
+        scaffold: ScaffoldArgs,
+    },
+    /// Compile, validate, and generate the project as configured
+    Build {
+        /// Target to generate instead of the `[codegen]` targets (repeatable)
+        #[arg(short, long = "target")]
+        targets: Vec<String>,
+        /// Skip type checking the compiled IR
+        #[arg(long)]
+        no_validate: bool,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Source files to compile in parallel (0 for one per CPU)
+        #[arg(short, long, default_value_t = 0)]
+        jobs: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Progress reporting: `text`, or `ndjson` to stream events on stdout as they happen
+        #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+        log_format: LogFormat,
+    },
+    /// Run the golden-output scenarios or the model tests
+    Test {
+        /// Only run tests whose name contains this (repeatable)
+        filters: Vec<String>,
+        /// Rewrite the expected outputs from the actual ones
+        #[arg(long, conflicts_with = "models")]
+        update: bool,
+        /// Evaluate the model tests under tests/ instead of the scenarios
+        #[arg(long)]
+        models: bool,
+        /// IR to evaluate model tests against instead of compiling the project
+        #[arg(long, requires = "models")]
+        ir: Option<String>,
+        /// Write a JUnit XML report to this file
+        #[arg(long)]
+        junit: Option<std::path::PathBuf>,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Compile source code to Morphir IR
     Compile {
         /// Source language (e.g., gleam, elm)
@@ -64,6 +149,12 @@ enum Commands {
         /// Output as JSON Lines (streaming)
         #[arg(long)]
         json_lines: bool,
+        /// Progress reporting: `text`, or `ndjson` to stream events on stdout as they happen
+        #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+        log_format: LogFormat,
+        /// Source files to compile in parallel (0 for one per CPU)
+        #[arg(short, long, default_value_t = 0)]
+        jobs: usize,
     },
     /// Generate code from Morphir IR
     Generate {
@@ -82,12 +173,63 @@ enum Commands {
         /// Project name (for workspaces)
         #[arg(long)]
         project: Option<String>,
+        /// Generation profile from `[generate.profiles.<name>]`; flags override it
+        #[arg(long)]
+        profile: Option<String>,
+        /// Check the generated code with the target's toolchain (e.g. `gleam check`)
+        #[arg(long)]
+        verify: bool,
+        /// Fold constants and inline small definitions before generating
+        #[arg(long)]
+        optimize: bool,
         /// Output as JSON
         #[arg(long)]
         json: bool,
         /// Output as JSON Lines (streaming)
         #[arg(long)]
         json_lines: bool,
+        /// Progress reporting: `text`, or `ndjson` to stream events on stdout as they happen
+        #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+        log_format: LogFormat,
+    },
+    /// Render a documentation site for the model, optionally serving it locally
+    #[command(
+        long_about = "Render a documentation site for the model, optionally serving it locally
+
+Writes an index of the package's modules and a page per module with its types and values: pretty-printed declarations, doc comments, and links from every type named in a signature to its own entry. Only public modules and definitions are documented unless `--private` is given. The `format` and `private` settings can also be set under `[codegen.docs]`.
+
+**Examples:**
+
+```bash
+# Document the project's compiled IR and preview it in a browser
+morphir docs --serve
+
+# Markdown pages, e.g. for a wiki, including private definitions
+morphir docs -i ./morphir-ir.json -o ./wiki --format markdown --private
+```"
+    )]
+    Docs {
+        /// Path to the Morphir IR file or directory (default: the project's compiled IR)
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Output directory (default: .morphir/out/<project>/generate/docs)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Page format (default: html, or `format` in [codegen.docs])
+        #[arg(long, value_enum)]
+        format: Option<DocsFormatArg>,
+        /// Document private modules and definitions too
+        #[arg(long)]
+        private: bool,
+        /// Serve the site on localhost after generating it
+        #[arg(long)]
+        serve: bool,
+        /// Port to serve on
+        #[arg(long, default_value_t = 8000)]
+        port: u16,
     },
     /// [Experimental] Validate Morphir IR models
     #[command(hide = true)]
@@ -95,16 +237,121 @@ enum Commands {
         /// Path to the Morphir IR file or directory
         #[arg(short, long)]
         input: Option<String>,
+        /// Dependency IR file or source whose specification is checked against (repeatable)
+        #[arg(long = "dependency", value_name = "SOURCE")]
+        dependencies: Vec<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// [Experimental] Lint Morphir IR against the rules configured under [lint]
+    #[command(hide = true)]
+    Lint {
+        /// Path to the Morphir IR file or directory
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// [Experimental] Check the projects of a workspace: parse, references, and types
+    #[command(hide = true)]
+    Check {
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Check only this project (for workspaces)
+        #[arg(long)]
+        project: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// [Experimental] Apply the fixes suggested by the diagnostics of `check`
+    #[command(hide = true)]
+    Fix {
+        /// Write the fixes to the source files instead of listing them
+        #[arg(long)]
+        apply: bool,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Fix only this project (for workspaces)
+        #[arg(long)]
+        project: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// [Experimental] Evaluate a value definition of Morphir IR
+    #[command(hide = true)]
+    Run {
+        /// Fully-qualified name of the value (e.g. my/pkg:orders#total)
+        fqname: String,
+        /// Arguments as JSON; values that are not valid JSON are passed as strings
+        args: Vec<String>,
+        /// Path to the Morphir IR file or directory
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Dependency IR file or source whose specification is available to the model (repeatable)
+        #[arg(long = "dependency", value_name = "SOURCE")]
+        dependencies: Vec<String>,
+        /// Expected result as JSON; differences are reported and fail the run
+        #[arg(long, value_name = "JSON")]
+        expect: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// [Experimental] Explore and evaluate Morphir IR interactively
+    #[command(hide = true)]
+    #[command(
+        long_about = "[Experimental] Explore and evaluate Morphir IR interactively
+
+Loads a distribution and reads `.mir` expressions to evaluate, `name = expression` bindings, and `:` commands that list and print modules, types, and values. The distribution is reloaded when its file changes. Lines are edited with history, kept in ~/.morphir/repl_history, and Tab completes commands and FQNames.
+
+**Examples:**
+
+```bash
+# Explore the compiled IR, starting in the orders module
+morphir repl --input ./morphir-ir.json --module orders
+
+orders> total [ 1, 2 ]
+orders> :show total
+```"
+    )]
+    Repl {
+        /// Path to the Morphir IR file or directory
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Dependency IR file or source whose specification is available to the model (repeatable)
+        #[arg(long = "dependency", value_name = "SOURCE")]
+        dependencies: Vec<String>,
+        /// Module to open, e.g. orders or my/pkg:orders
+        #[arg(short, long)]
+        module: Option<String>,
     },
     /// [Experimental] Transform Morphir IR
     #[command(hide = true)]
     Transform {
+        /// Transform to run, e.g. extract-constants
+        #[arg(short, long)]
+        transform: String,
         /// Path to the Morphir IR file or directory
         #[arg(short, long)]
         input: Option<String>,
-        /// Output path
+        /// Where to write the transformed IR; without it only the report is shown
         #[arg(short, long)]
         output: Option<String>,
+        /// Options for the transform, as JSON
+        #[arg(long, value_name = "JSON")]
+        options: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     // ===== Management Commands =====
@@ -128,6 +375,76 @@ enum Commands {
         #[command(subcommand)]
         action: IrAction,
     },
+    /// Inspect Morphir configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Start, stop, and query the Morphir daemon
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonAction,
+    },
+    /// Serve the Language Server Protocol on stdin and stdout, for editors
+    Lsp {
+        /// Directory whose configuration supplies extensions (defaults to the
+        /// current directory)
+        #[arg(long)]
+        workspace: Option<std::path::PathBuf>,
+    },
+    /// Manage the workspace's dependencies
+    Deps {
+        #[command(subcommand)]
+        action: DepsAction,
+    },
+    /// Manage the cache of remote sources
+    Source {
+        #[command(subcommand)]
+        action: SourceAction,
+    },
+    /// Find packages in the registry and add them as dependencies
+    Package {
+        #[command(subcommand)]
+        action: PackageAction,
+    },
+    /// Compile the project and publish its IR to the package registry
+    Publish {
+        /// IR to publish instead of compiling the project
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Registry URL (defaults to `[registry] url`, then MORPHIR_REGISTRY_URL)
+        #[arg(long)]
+        registry: Option<String>,
+        /// Check the package and print what would be published without uploading
+        #[arg(long)]
+        dry_run: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Maintain the caches under .morphir/cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Show model size, diagnostics, and build history
+    Stats {
+        /// Report every project of the workspace, not just the current one
+        #[arg(long)]
+        workspace: bool,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Number of recent builds to list in JSON output
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Gleam language binding commands
     Gleam {
         #[command(subcommand)]
@@ -139,17 +456,22 @@ enum Commands {
         #[arg(long)]
         json_lines: bool,
     },
-    /// Generate JSON Schema for Morphir IR
+    /// Generate the JSON Schema of the IR or the extension protocol
     Schema {
+        /// Which schema to emit
+        #[arg(long, value_enum, default_value_t = commands::schema::SchemaKind::IrV4)]
+        which: commands::schema::SchemaKind,
         /// Output file path (optional)
         #[arg(short, long)]
         output: Option<std::path::PathBuf>,
     },
-    /// Print version information
+    /// Print version information, or bump the project's version
     Version {
         /// Output version info as JSON
         #[arg(long)]
         json: bool,
+        #[command(subcommand)]
+        action: Option<VersionAction>,
     },
 
     // ===== Internal/Hidden Commands =====
@@ -158,6 +480,39 @@ enum Commands {
     Usage,
 }
 
+/// Options shared by `init` and `new`
+#[derive(Clone, clap::Args)]
+struct ScaffoldArgs {
+    /// Package name (defaults to the directory name)
+    #[arg(long)]
+    name: Option<String>,
+    /// Project archetype
+    #[arg(short, long, value_enum, default_value_t = ProjectTemplate::Library)]
+    template: ProjectTemplate,
+    /// Frontend language of the sample source
+    #[arg(short, long, value_enum, default_value_t = SourceLanguage::Gleam)]
+    language: SourceLanguage,
+    /// Do not write a .gitignore for the build output
+    #[arg(long)]
+    no_gitignore: bool,
+    /// Output as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+impl ScaffoldArgs {
+    fn into_options(self, path: std::path::PathBuf) -> InitOptions {
+        InitOptions {
+            path,
+            name: self.name,
+            template: self.template,
+            language: self.language,
+            no_gitignore: self.no_gitignore,
+            json: self.json,
+        }
+    }
+}
+
 #[derive(Clone, Subcommand)]
 enum ToolAction {
     /// Install a Morphir tool or extension
@@ -214,13 +569,35 @@ enum DistAction {
 
 #[derive(Clone, Subcommand)]
 enum ExtensionAction {
+    /// Scaffold a new extension crate
+    New {
+        /// Directory to create
+        path: std::path::PathBuf,
+        /// Extension ID and crate name (defaults to the directory name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Capability the extension implements
+        #[arg(short, long, value_enum, default_value_t = ExtensionCapability::Frontend)]
+        capability: ExtensionCapability,
+        /// Directory containing the morphir-extension-sdk and morphir-extension-testkit
+        /// crates, to depend on by path instead of from crates.io
+        #[arg(long)]
+        sdk_path: Option<std::path::PathBuf>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Install a Morphir extension
     Install {
-        /// Name of the extension to install
+        /// Name of the extension in the package registry, or the path, URL, or
+        /// github:<owner>/<repo>[@<tag>][#<asset>] source of its WASM module
         name: String,
-        /// Version to install (defaults to latest)
+        /// Version requirement or GitHub release tag (defaults to latest)
         #[arg(short, long)]
         version: Option<String>,
+        /// Expected SHA-256 of the WASM module
+        #[arg(long)]
+        sha256: Option<String>,
     },
     /// List installed Morphir extensions
     List,
@@ -237,6 +614,9 @@ enum ExtensionAction {
         /// Name of the extension to uninstall
         name: String,
     },
+    /// Check that installed extensions load and are compatible with this
+    /// version of morphir
+    Doctor,
 }
 
 #[derive(Clone, Subcommand)]
@@ -294,6 +674,321 @@ enum GleamAction {
     },
 }
 
+#[derive(Clone, Subcommand)]
+enum ConfigAction {
+    /// Check the effective configuration and report problems
+    Doctor {
+        /// Print every effective key with the layer that set it
+        #[arg(long)]
+        explain: bool,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<std::path::PathBuf>,
+        /// Configuration profile to apply (defaults to MORPHIR_PROFILE)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Override a configuration key (e.g. --set ir.strict_mode=true)
+        #[arg(long = "set", value_name = "KEY=VALUE")]
+        overrides: Vec<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+enum DaemonAction {
+    /// Start the daemon, in the background unless --foreground or --stdio
+    Start {
+        /// Workspace to open (defaults to the one around the current directory)
+        #[arg(long)]
+        workspace: Option<std::path::PathBuf>,
+        /// Port to listen on (defaults to `[daemon] port`, then 9741)
+        #[arg(long)]
+        port: Option<u16>,
+        /// Serve in this process instead of in the background
+        #[arg(long)]
+        foreground: bool,
+        /// Serve JSON-RPC on stdin and stdout, for editors
+        #[arg(long, conflicts_with_all = ["foreground", "port"])]
+        stdio: bool,
+        /// Watch project sources and rebuild them as they change
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Stop the running daemon once its in-flight requests complete
+    Stop,
+    /// Show whether the daemon is running, and its workspace
+    Status {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Clone, Subcommand)]
+enum DepsAction {
+    /// Copy remote dependencies into the vendor directory and lock them there
+    Vendor {
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Vendor extensions downloaded from a URL as well
+        #[arg(long)]
+        extensions: bool,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn run_deps_action(action: DepsAction) -> AppResult {
+    match action {
+        DepsAction::Vendor {
+            config,
+            extensions,
+            json,
+        } => run_deps_vendor(config, extensions, json),
+    }
+}
+
+#[derive(Clone, Subcommand)]
+enum SourceAction {
+    /// Fetch the workspace's remote dependencies and extensions into the
+    /// cache, so later builds can run with --offline
+    Prefetch {
+        /// Additional sources to fetch (URLs, github:, gitlab:, ...)
+        sources: Vec<String>,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn run_source_action(action: SourceAction) -> AppResult {
+    match action {
+        SourceAction::Prefetch {
+            sources,
+            config,
+            json,
+        } => run_source_prefetch(sources, config, json),
+    }
+}
+
+#[derive(Clone, Subcommand)]
+enum VersionAction {
+    /// Set the project's version to the one its changes since the last release call for
+    #[command(
+        long_about = "Set the project's version to the one its changes since the last release call for
+
+Compares the project's IR against the newest version of the package published to the registry, or against --against with --previous, and classifies the changes by Morphir API compatibility rules: removing or changing an exposed module, type, constructor, or value is major, adding one is minor, and changing only value bodies or private definitions is a patch. While the major version is 0, a major change bumps the minor version and anything else the patch version.
+
+Without --check, `[project] version` in morphir.toml is raised to the suggested version if it is lower. With --check, nothing is written and the command exits with code 2 if the version is too low, for gating releases in CI.
+
+**Examples:**
+
+```bash
+# Raise the version in morphir.toml as far as the changes call for
+morphir version bump
+
+# Fail the build if the version was not bumped enough
+morphir version bump --check
+
+# Compare against a release on disk
+morphir version bump --check --against ./releases/1.4.2/morphir-ir.json --previous 1.4.2
+```"
+    )]
+    Bump {
+        /// Only check that the version in morphir.toml is high enough
+        #[arg(long)]
+        check: bool,
+        /// Previous release to compare against instead of the registry's newest: file, directory, or remote source
+        #[arg(long, requires = "previous")]
+        against: Option<String>,
+        /// Version the --against release was published as
+        #[arg(long, requires = "against")]
+        previous: Option<String>,
+        /// IR of the new version (default: the project's compiled IR)
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Registry URL (defaults to `[registry] url`, then MORPHIR_REGISTRY_URL)
+        #[arg(long)]
+        registry: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn run_version_action(action: VersionAction) -> AppResult {
+    match action {
+        VersionAction::Bump {
+            check,
+            against,
+            previous,
+            input,
+            config,
+            registry,
+            json,
+        } => run_version_bump(VersionBumpOptions {
+            check,
+            against,
+            previous,
+            input,
+            config_path: config,
+            registry,
+            json,
+        }),
+    }
+}
+
+#[derive(Clone, Subcommand)]
+enum PackageAction {
+    /// Search the registry for packages
+    Search {
+        /// Text to search package names and descriptions for
+        query: String,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Registry URL (defaults to `[registry] url`, then MORPHIR_REGISTRY_URL)
+        #[arg(long)]
+        registry: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Add a registry package to the dependencies in morphir.toml
+    Add {
+        /// Package name, optionally with a version requirement
+        /// (`org/name@^1.2`; defaults to ^ the newest version)
+        package: String,
+        /// Add to [dev-dependencies] instead
+        #[arg(long)]
+        dev: bool,
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Registry URL (defaults to `[registry] url`, then MORPHIR_REGISTRY_URL)
+        #[arg(long)]
+        registry: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn run_package_action(action: PackageAction) -> AppResult {
+    match action {
+        PackageAction::Search {
+            query,
+            config,
+            registry,
+            json,
+        } => run_package_search(query, config, registry, json),
+        PackageAction::Add {
+            package,
+            dev,
+            config,
+            registry,
+            json,
+        } => run_package_add(package, dev, config, registry, json),
+    }
+}
+
+#[derive(Clone, Subcommand)]
+enum CacheAction {
+    /// Discard the saved module indexes and index every source again
+    RebuildIndex {
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Rebuild only this project's index (for workspaces)
+        #[arg(long)]
+        project: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the number and size of modules in the IR cache
+    Stats {
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove every module from the IR cache
+    Clear {
+        /// Explicit config file path
+        #[arg(long)]
+        config: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn run_cache_action(action: CacheAction) -> AppResult {
+    match action {
+        CacheAction::RebuildIndex {
+            config,
+            project,
+            json,
+        } => run_cache_rebuild_index(config, project, json),
+        CacheAction::Stats { config, json } => run_cache_stats(config, json),
+        CacheAction::Clear { config, json } => run_cache_clear(config, json),
+    }
+}
+
+async fn run_daemon_action(action: DaemonAction) -> AppResult {
+    match action {
+        DaemonAction::Start {
+            workspace,
+            port,
+            foreground,
+            stdio,
+            watch,
+        } => {
+            run_daemon_start(DaemonStartOptions {
+                workspace,
+                port,
+                foreground,
+                stdio,
+                watch,
+            })
+            .await
+        }
+        DaemonAction::Stop => run_daemon_stop().await,
+        DaemonAction::Status { json } => run_daemon_status(json).await,
+    }
+}
+
+fn run_config_action(action: ConfigAction) -> AppResult {
+    match action {
+        ConfigAction::Doctor {
+            explain,
+            config,
+            profile,
+            overrides,
+            json,
+        } => run_config_doctor(ConfigDoctorOptions {
+            config,
+            profile,
+            overrides,
+            explain,
+            json,
+        }),
+    }
+}
+
 #[derive(Clone, Subcommand)]
 enum IrAction {
     /// Migrate IR between versions
@@ -339,9 +1034,419 @@ See the [IR Migration Guide](/ir-migrate/) for detailed real-world examples incl
         /// Use expanded (non-compact) format for V4 output
         #[arg(long)]
         expanded: bool,
+        /// Progress reporting: `text`, or `ndjson` to stream events on stdout as they happen
+        #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+        log_format: LogFormat,
+    },
+    /// Extract a minimal sub-distribution reproducing a failure
+    #[command(long_about = "Extract a minimal sub-distribution reproducing a failure
+
+Starting from one or more failing definitions, pulls in their dependency closure and writes a small, self-contained V4 distribution suitable for attaching to an issue. Definitions referenced beyond `--depth` keep their signatures but have their bodies replaced with holes.
+
+**Examples:**
+
+```bash
+# Extract everything `orders#total` depends on
+morphir ir sample ./morphir-ir.json --fqname my/pkg:orders#total -o repro.json
+
+# Keep bodies only for the root and its direct references
+morphir ir sample ./morphir-ir.json --fqname my/pkg:orders#total --depth 1
+
+# Take roots from the FQNames mentioned in a diagnostics file
+morphir ir sample ./morphir-ir.json --diagnostic diagnostics.json -o repro.json
+```")]
+    Sample {
+        /// Input file, directory, or remote source (e.g., github:owner/repo, URL)
+        input: String,
+        /// Root definition to extract (repeatable), e.g. my/pkg:orders#total
+        #[arg(long = "fqname")]
+        fqnames: Vec<String>,
+        /// Diagnostics JSON file whose messages name the failing definitions
+        #[arg(long)]
+        diagnostic: Option<std::path::PathBuf>,
+        /// Keep bodies only up to this many references away from the roots
+        #[arg(long)]
+        depth: Option<usize>,
+        /// Output file (if omitted, writes the sample to stdout)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// Output a JSON summary (for scripting)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare the public specifications of two distributions
+    #[command(long_about = "Compare the public specifications of two distributions
+
+Reports only the changes consumers of a published model package can see: public modules, types, constructors, and value signatures. Private definitions and value bodies are ignored. Each change is classified as additive or breaking.
+
+Exits with 0 when the new distribution is compatible with the old one, 2 when it has breaking changes, and 1 on errors, so it can gate releases.
+
+**Examples:**
+
+```bash
+# Compare the last release with the current build
+morphir ir spec-diff ./released/morphir-ir.json ./morphir-ir.json
+
+# Machine-readable changes
+morphir ir spec-diff github:my-org/models/morphir-ir.json ./morphir-ir.json --json
+```")]
+    SpecDiff {
+        /// Previous distribution: file, directory, or remote source
+        old: String,
+        /// New distribution: file, directory, or remote source
+        new: String,
+        /// Output the changes as JSON (for scripting)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render the call graph or type-dependency graph of a distribution
+    #[command(
+        long_about = "Render the call graph or type-dependency graph of a distribution
+
+Builds a graph with a node per definition and an edge per reference: from each value to the values its body calls (`--kind calls`), or from each type to the types its definition mentions (`--kind types`). Edges within a reference cycle are drawn in red in DOT output and listed under `cycles` in JSON output.
+
+Definitions of other packages, such as the SDK, are left out unless `--external` is given.
+
+**Examples:**
+
+```bash
+# Render the call graph with Graphviz
+morphir ir graph ./morphir-ir.json | dot -Tsvg -o calls.svg
+
+# Only what `orders#total` depends on, including SDK functions
+morphir ir graph ./morphir-ir.json --root my/pkg:orders#total --external
+
+# Type dependencies as GraphML for Gephi or yEd
+morphir ir graph ./morphir-ir.json --kind types --format graphml -o types.graphml
+
+# Nodes, edges, and cycles as JSON
+morphir ir graph ./morphir-ir.json --format json
+```"
+    )]
+    Graph {
+        /// Input file, directory, or remote source (e.g., github:owner/repo, URL)
+        input: String,
+        /// Which references to follow
+        #[arg(long, value_enum, default_value_t = GraphKindArg::Calls)]
+        kind: GraphKindArg,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// Keep only the definitions reachable from this one (repeatable), e.g. my/pkg:orders#total
+        #[arg(long = "root")]
+        roots: Vec<String>,
+        /// Include definitions from other packages
+        #[arg(long)]
+        external: bool,
+        /// Output file (if omitted, writes the graph to stdout)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Print a definition or module in the `.mir` text syntax
+    #[command(long_about = "Print a definition or module in the `.mir` text syntax
+
+Renders a type or value of the distribution, or a whole module when the name has no `#local` part, as human-readable `.mir` text. Definitions of dependencies are shown as their specifications.
+
+**Examples:**
+
+```bash
+# A value or type of the project's compiled IR
+morphir ir show my/pkg:orders#total
+
+# A whole module of a given distribution
+morphir ir show my/pkg:orders --input ./morphir-ir.json
+```")]
+    Show {
+        /// Definition (package:module#name) or module (package:module) to print
+        fqname: String,
+        /// Input file, directory, or remote source (default: the project's compiled IR)
+        #[arg(short, long)]
+        input: Option<String>,
+    },
+    /// Convert IR to `.mir` text, reformat `.mir` files, or convert them back
+    #[command(
+        long_about = "Convert IR to `.mir` text, reformat `.mir` files, or convert them back
+
+`.mir` is a canonical, human-readable text syntax for Morphir IR, meant for reviewing and diffing models. An input ending in `.mir` is parsed and printed in canonical layout; any other input is loaded as IR and printed as `.mir`. With `--format json`, the result is written as V4 IR JSON instead.
+
+With `--canonical`, the input is written as V4 IR JSON in canonical form: modules, types, values, and dependencies in key order and attributes placed consistently, so generated files diff cleanly and are byte-stable across runs. `--compact` writes it on a single line without empty attributes.
+
+With `--check`, nothing is written: the command exits with 1 if a `.mir` file is not in canonical layout, or with `--canonical`, if an IR JSON file is not in canonical form.
+
+**Examples:**
+
+```bash
+# Render a distribution as text for review
+morphir ir format ./morphir-ir.json -o model.mir
+
+# Reformat a hand-edited file in place, or check it in CI
+morphir ir format model.mir -o model.mir
+morphir ir format model.mir --check
+
+# Back to IR JSON
+morphir ir format model.mir --format json -o morphir-ir.json
+
+# Canonical IR JSON, and checking it in CI
+morphir ir format ./morphir-ir.json --canonical -o morphir-ir.json
+morphir ir format ./morphir-ir.json --canonical --check
+```"
+    )]
+    Format {
+        /// `.mir` file, or IR file, directory, or remote source (e.g., github:owner/repo, URL)
+        input: String,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = TextFormat::Mir)]
+        format: TextFormat,
+        /// Exit with 1 if the input is not in canonical layout, without writing
+        #[arg(long)]
+        check: bool,
+        /// Write V4 IR JSON in canonical form
+        #[arg(long)]
+        canonical: bool,
+        /// Write canonical IR JSON on a single line, without empty attributes
+        #[arg(long, requires = "canonical")]
+        compact: bool,
+        /// Output file (if omitted, writes to stdout)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Select definitions matching a query and print them as JSON
+    #[command(
+        long_about = r#"Select definitions matching a query and print them as JSON
+
+A query names what to select (`modules`, `types`, `constructors`, `values`, or `definitions`), then filters it with conditions in brackets. A condition compares an attribute path to a string, number, `true`, `false`, or `null` with `=`, `!=`, `<`, `<=`, `>`, `>=`, or `~` (a glob with `*` and `?`); conditions combine with `and`, `or`, `not`, and parentheses, and a path on its own tests that the attribute is present and non-empty. A trailing `.path` prints that attribute of each match instead of the whole definition.
+
+Values have `name`, `module`, `fqname`, `access`, `inputs`, `arity`, `output_type`, `body`, `references`, and `type_references`; types have `form`, `params`, `alias`, and `constructors`. Types inside them have a `kind`, a `reference` for named types, and their `.mir` `text`.
+
+**Examples:**
+
+```bash
+# Values returning decimals
+morphir ir query 'values[output_type.reference = "morphir/sdk:decimal#decimal"]' -i ./morphir-ir.json
+
+# Public values of the orders modules that take more than three arguments
+morphir ir query 'values[module ~ "orders*" and access = "public" and arity > 3].fqname'
+
+# Everything that calls a function, including dependencies
+morphir ir query 'values[references = "my/pkg:rates#lookup"]' --external
+```"#
+    )]
+    Query {
+        /// The query, e.g. values[arity > 3]
+        query: String,
+        /// Input file, directory, or remote source (default: the project's compiled IR)
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Include definitions from other packages
+        #[arg(long)]
+        external: bool,
+        /// Output file (if omitted, writes to stdout)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Rename a module, type, constructor, or value and rewrite its references
+    #[command(
+        long_about = "Rename a module, type, constructor, or value and rewrite its references
+
+Modules are named `package:module` and definitions `package:module#name`. Every reference to the renamed definition, in types, values, patterns, holes, and entry points, is rewritten. A type or value may move to another existing module, taking a custom type's constructors with it.
+
+The IR is written back to the input file unless --output is given, and the change is added to the refactor log next to it (`<name>.refactors.json`) as a transaction with its own id.
+
+**Examples:**
+
+```bash
+# Rename a value
+morphir ir rename acme/shop:orders#total acme/shop:orders#grand-total -i ./morphir-ir.json
+
+# Rename a module
+morphir ir rename acme/shop:billing acme/shop:fees
+
+# Rename a type that shares its name with a constructor, without writing anything
+morphir ir rename acme/shop:orders#order acme/shop:orders#purchase --kind type --dry-run
+```"
+    )]
+    Rename {
+        /// Module (package:module) or definition (package:module#name) to rename
+        old: String,
+        /// Its new name
+        new: String,
+        /// Input file, directory, or remote source (default: the project's compiled IR)
+        #[arg(short, long)]
+        input: Option<String>,
+        /// What the old name names, where a type, constructor, and value share it
+        #[arg(long, value_enum)]
+        kind: Option<SymbolKindArg>,
+        /// Output file (if omitted, the input file is rewritten)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// Report what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Print the refactor transaction as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Split and merge modules as a plan file describes
+    #[command(long_about = r#"Split and merge modules as a plan file describes
+
+A plan lists splits, each moving named types and values of a module to other modules, and merges, each moving every definition of some modules to another and removing them. Module names are paths within the package. Splits are applied first, then merges, all as one refactor transaction; if any step fails, nothing is written.
+
+Modules moved to are created where they do not exist yet, with the access of the module the definitions came from, and a custom type takes its constructors with it. Every reference to a moved definition is rewritten, and private definitions left referenced from another module are made public.
+
+The IR is written back to the input file unless --output is given, and the change is added to the refactor log next to it.
+
+**Plan (TOML, or the same shape in JSON):**
+
+```toml
+[[split]]
+module = "orders"
+into = { "orders/lines" = ["line", "line-total"], "orders/fees" = ["fee"] }
+
+[[merge]]
+modules = ["billing", "invoices"]
+into = "billing"
+```
+
+**Examples:**
+
+```bash
+morphir ir restructure --plan restructure.toml -i ./morphir-ir.json
+
+# Show the moves without writing anything
+morphir ir restructure --plan restructure.toml --dry-run
+```"#)]
+    Restructure {
+        /// Plan file, TOML or JSON
+        #[arg(short, long)]
+        plan: std::path::PathBuf,
+        /// Input file, directory, or remote source (default: the project's compiled IR)
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Output file (if omitted, the input file is rewritten)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// Report what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Print the refactor transaction as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
+/// Dispatch an `ir` subcommand
+fn run_ir_action(action: IrAction) -> AppResult {
+    match action {
+        IrAction::Migrate {
+            input,
+            output,
+            target_version,
+            force_refresh,
+            no_cache,
+            json,
+            expanded,
+            log_format,
+        } => run_migrate(
+            input,
+            output,
+            target_version,
+            force_refresh,
+            no_cache,
+            json,
+            expanded,
+            log_format,
+        ),
+        IrAction::Sample {
+            input,
+            fqnames,
+            diagnostic,
+            depth,
+            output,
+            json,
+        } => run_ir_sample(SampleCommandOptions {
+            input,
+            fqnames,
+            diagnostic,
+            depth,
+            output,
+            json,
+        }),
+        IrAction::SpecDiff { old, new, json } => run_ir_spec_diff(old, new, json),
+        IrAction::Graph {
+            input,
+            kind,
+            format,
+            roots,
+            external,
+            output,
+        } => run_ir_graph(GraphCommandOptions {
+            input,
+            kind,
+            format,
+            roots,
+            external,
+            output,
+        }),
+        IrAction::Show { fqname, input } => run_ir_show(ShowCommandOptions { fqname, input }),
+        IrAction::Format {
+            input,
+            format,
+            check,
+            canonical,
+            compact,
+            output,
+        } => run_ir_format(FormatCommandOptions {
+            input,
+            format,
+            check,
+            canonical,
+            compact,
+            output,
+        }),
+        IrAction::Query {
+            query,
+            input,
+            external,
+            output,
+        } => run_ir_query(QueryCommandOptions {
+            query,
+            input,
+            external,
+            output,
+        }),
+        IrAction::Rename {
+            old,
+            new,
+            input,
+            kind,
+            output,
+            dry_run,
+            json,
+        } => run_ir_rename(RenameCommandOptions {
+            old,
+            new,
+            input,
+            kind,
+            output,
+            dry_run,
+            json,
+        }),
+        IrAction::Restructure {
+            plan,
+            input,
+            output,
+            dry_run,
+            json,
+        } => run_ir_restructure(RestructureCommandOptions {
+            plan,
+            input,
+            output,
+            dry_run,
+            json,
+        }),
+    }
+}
+
 /// Application session for Morphir CLI
 #[derive(Clone)]
 struct MorphirSession {
@@ -352,7 +1457,51 @@ struct MorphirSession {
 impl AppSession for MorphirSession {
     async fn execute(&mut self) -> AppResult {
         match &self.command {
-            Commands::Validate { input } => run_validate(input.clone()),
+            Commands::Validate {
+                input,
+                dependencies,
+                json,
+            } => run_validate(input.clone(), dependencies.clone(), *json),
+            Commands::Lint {
+                input,
+                config,
+                json,
+            } => run_lint(input.clone(), config.clone(), *json),
+            Commands::Check {
+                config,
+                project,
+                json,
+            } => run_check(config.clone(), project.clone(), *json),
+            Commands::Fix {
+                apply,
+                config,
+                project,
+                json,
+            } => run_fix(config.clone(), project.clone(), *apply, *json),
+            Commands::Run {
+                fqname,
+                args,
+                input,
+                dependencies,
+                expect,
+                json,
+            } => run_model(RunOptions {
+                input: input.clone(),
+                dependencies: dependencies.clone(),
+                expect: expect.clone(),
+                fqname: fqname.clone(),
+                args: args.clone(),
+                json: *json,
+            }),
+            Commands::Repl {
+                input,
+                dependencies,
+                module,
+            } => run_repl(ReplOptions {
+                input: input.clone(),
+                dependencies: dependencies.clone(),
+                module: module.clone(),
+            }),
             Commands::Compile {
                 language,
                 input,
@@ -362,6 +1511,8 @@ impl AppSession for MorphirSession {
                 project,
                 json,
                 json_lines,
+                log_format,
+                jobs,
             } => {
                 run_compile(CompileOptions {
                     language: language.clone(),
@@ -372,6 +1523,8 @@ impl AppSession for MorphirSession {
                     project: project.clone(),
                     json: *json,
                     json_lines: *json_lines,
+                    log_format: *log_format,
+                    jobs: *jobs,
                 })
                 .await
             }
@@ -381,8 +1534,12 @@ impl AppSession for MorphirSession {
                 output,
                 config,
                 project,
+                profile,
+                verify,
+                optimize,
                 json,
                 json_lines,
+                log_format,
             } => {
                 run_generate(
                     target.clone(),
@@ -390,12 +1547,48 @@ impl AppSession for MorphirSession {
                     output.clone(),
                     config.clone(),
                     project.clone(),
+                    profile.clone(),
+                    *verify,
+                    *optimize,
                     *json,
                     *json_lines,
+                    *log_format,
                 )
                 .await
             }
-            Commands::Transform { input, output } => run_transform(input.clone(), output.clone()),
+            Commands::Docs {
+                input,
+                output,
+                config,
+                format,
+                private,
+                serve,
+                port,
+            } => {
+                run_docs(DocsCommandOptions {
+                    input: input.clone(),
+                    output: output.clone(),
+                    config: config.clone(),
+                    format: *format,
+                    private: *private,
+                    serve: *serve,
+                    port: *port,
+                })
+                .await
+            }
+            Commands::Transform {
+                transform,
+                input,
+                output,
+                options,
+                json,
+            } => run_transform(
+                transform.clone(),
+                input.clone(),
+                output.clone(),
+                options.clone(),
+                *json,
+            ),
             Commands::Tool { action } => match action {
                 ToolAction::Install { name, version } => {
                     run_tool_install(name.clone(), version.clone())
@@ -417,34 +1610,105 @@ impl AppSession for MorphirSession {
                 DistAction::Uninstall { name } => run_dist_uninstall(name.clone()),
             },
             Commands::Extension { action } => match action {
-                ExtensionAction::Install { name, version } => {
-                    run_extension_install(name.clone(), version.clone())
-                }
+                ExtensionAction::New {
+                    path,
+                    name,
+                    capability,
+                    sdk_path,
+                    json,
+                } => run_extension_new(ExtensionNewOptions {
+                    path: path.clone(),
+                    name: name.clone(),
+                    capability: *capability,
+                    sdk_path: sdk_path.clone(),
+                    json: *json,
+                }),
+                ExtensionAction::Install {
+                    name,
+                    version,
+                    sha256,
+                } => run_extension_install(name.clone(), version.clone(), sha256.clone()).await,
                 ExtensionAction::List => run_extension_list(),
                 ExtensionAction::Update { name, version } => {
-                    run_extension_update(name.clone(), version.clone())
+                    run_extension_update(name.clone(), version.clone()).await
                 }
                 ExtensionAction::Uninstall { name } => run_extension_uninstall(name.clone()),
+                ExtensionAction::Doctor => run_extension_doctor(),
             },
-            Commands::Ir { action } => match action {
-                IrAction::Migrate {
-                    input,
-                    output,
-                    target_version,
-                    force_refresh,
-                    no_cache,
-                    json,
-                    expanded,
-                } => run_migrate(
-                    input.clone(),
-                    output.clone(),
-                    target_version.clone(),
-                    *force_refresh,
-                    *no_cache,
-                    *json,
-                    *expanded,
-                ),
-            },
+            Commands::Ir { action } => run_ir_action(action.clone()),
+            Commands::Config { action } => run_config_action(action.clone()),
+            Commands::Daemon { action } => run_daemon_action(action.clone()).await,
+            Commands::Lsp { workspace } => run_lsp(workspace.clone()).await,
+            Commands::Deps { action } => run_deps_action(action.clone()),
+            Commands::Source { action } => run_source_action(action.clone()),
+            Commands::Build {
+                targets,
+                no_validate,
+                config,
+                jobs,
+                json,
+                log_format,
+            } => {
+                run_build(BuildOptions {
+                    config_path: config.clone(),
+                    targets: targets.clone(),
+                    no_validate: *no_validate,
+                    jobs: *jobs,
+                    json: *json,
+                    log_format: *log_format,
+                })
+                .await
+            }
+            Commands::Test {
+                filters,
+                update,
+                models,
+                ir,
+                junit,
+                config,
+                json,
+            } => {
+                run_test(TestOptions {
+                    config_path: config.clone(),
+                    filters: filters.clone(),
+                    update: *update,
+                    models: *models,
+                    ir: ir.clone(),
+                    junit: junit.clone(),
+                    json: *json,
+                })
+                .await
+            }
+            Commands::Init { path, scaffold } => {
+                run_init(scaffold.clone().into_options(path.clone()))
+            }
+            Commands::New { path, scaffold } => {
+                run_new(scaffold.clone().into_options(path.clone()))
+            }
+            Commands::Package { action } => run_package_action(action.clone()),
+            Commands::Publish {
+                input,
+                config,
+                registry,
+                dry_run,
+                json,
+            } => {
+                run_publish(PublishOptions {
+                    input: input.clone(),
+                    config_path: config.clone(),
+                    registry: registry.clone(),
+                    dry_run: *dry_run,
+                    json: *json,
+                })
+                .await
+            }
+            Commands::Cache { action } => run_cache_action(action.clone()),
+            Commands::Stats {
+                workspace,
+                config,
+                limit,
+                json,
+            } => run_stats(*workspace, config.clone(), *limit, *json),
             Commands::Gleam {
                 action,
                 json,
@@ -503,8 +1767,13 @@ impl AppSession for MorphirSession {
                     .await
                 }
             },
-            Commands::Schema { output } => commands::schema::run_schema(output.clone()),
-            Commands::Version { json } => run_version(*json),
+            Commands::Schema { which, output } => {
+                commands::schema::run_schema(*which, output.clone())
+            }
+            Commands::Version { json, action } => match action {
+                Some(action) => run_version_action(action.clone()),
+                None => run_version(*json),
+            },
             Commands::Usage => {
                 use clap::CommandFactory;
                 let cli = Cli::command();
@@ -521,7 +1790,41 @@ async fn main() -> starbase::MainResult {
     use clap::CommandFactory;
 
     // Check for help/version flags first to print our custom banner
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // `--offline` applies to every command that resolves remote sources
+    if let Some(index) = args
+        .iter()
+        .skip(1)
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == "--offline")
+    {
+        morphir_common::remote::set_offline(true);
+        // Keep the command in `args[1]` for the dispatch below
+        args.remove(index + 1);
+    }
+
+    // `--output <FORMAT>` before the command applies to every command; after
+    // the command, `--output` is the command's own option
+    let mut index = 1;
+    while index < args.len() && args[index].starts_with('-') && args[index] != "--" {
+        let (value, len) = match args[index].strip_prefix("--output=") {
+            Some(value) => (Some(value.to_string()), 1),
+            None if args[index] == "--output" => (args.get(index + 1).cloned(), 2),
+            None => {
+                index += 1;
+                continue;
+            }
+        };
+        if let Some(format) =
+            value.and_then(|v| <OutputFormat as clap::ValueEnum>::from_str(&v, true).ok())
+        {
+            format.set_global();
+            // Keep the command in `args[1]` for the dispatch below
+            args.drain(index..index + len);
+        }
+        break;
+    }
 
     if help::should_show_banner(&args) {
         help::print_banner();
@@ -534,7 +1837,7 @@ async fn main() -> starbase::MainResult {
     }
 
     // Handle version subcommand early (before starbase) to avoid double execution
-    if args.len() >= 2 && args[1] == "version" {
+    if args.len() >= 2 && args[1] == "version" && args.get(2).is_none_or(|arg| arg != "bump") {
         let json = args.iter().any(|a| a == "--json");
         if let Some(code) = run_version(json)? {
             return Ok(std::process::ExitCode::from(code));
@@ -551,40 +1854,6 @@ async fn main() -> starbase::MainResult {
         return Ok(std::process::ExitCode::SUCCESS);
     }
 
-    // Handle ir subcommand early (before starbase) to avoid double execution
-    if args.len() >= 3 && args[1] == "ir" {
-        let cli = Cli::parse();
-        if let Some(Commands::Ir { action }) = cli.command {
-            let result = match action {
-                IrAction::Migrate {
-                    input,
-                    output,
-                    target_version,
-                    force_refresh,
-                    no_cache,
-                    json,
-                    expanded,
-                } => run_migrate(
-                    input,
-                    output,
-                    target_version,
-                    force_refresh,
-                    no_cache,
-                    json,
-                    expanded,
-                ),
-            };
-            match result {
-                Ok(Some(code)) => return Ok(std::process::ExitCode::from(code)),
-                Ok(None) => return Ok(std::process::ExitCode::SUCCESS),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    return Ok(std::process::ExitCode::from(1));
-                }
-            }
-        }
-    }
-
     let cli = Cli::parse();
 
     // Handle case where no command is provided
@@ -596,16 +1865,177 @@ async fn main() -> starbase::MainResult {
         }
     };
 
+    // Run these commands here, before starbase, which would otherwise run
+    // them a second time (and serve docs, daemons and LSP clients in the
+    // background). Commands that fetch use a blocking HTTP client, which
+    // must not run on the async runtime's worker directly.
+    let result = match command {
+        Commands::Version {
+            action: Some(action),
+            ..
+        } => tokio::task::block_in_place(|| run_version_action(action)),
+        Commands::Init { path, scaffold } => run_init(scaffold.into_options(path)),
+        Commands::New { path, scaffold } => run_new(scaffold.into_options(path)),
+        Commands::Ir { action } => run_ir_action(action),
+        Commands::Config { action } => run_config_action(action),
+        Commands::Deps { action } => tokio::task::block_in_place(|| run_deps_action(action)),
+        Commands::Source { action } => tokio::task::block_in_place(|| run_source_action(action)),
+        Commands::Package { action } => tokio::task::block_in_place(|| run_package_action(action)),
+        Commands::Build {
+            targets,
+            no_validate,
+            config,
+            jobs,
+            json,
+            log_format,
+        } => {
+            run_build(BuildOptions {
+                config_path: config,
+                targets,
+                no_validate,
+                jobs,
+                json,
+                log_format,
+            })
+            .await
+        }
+        Commands::Docs {
+            input,
+            output,
+            config,
+            format,
+            private,
+            serve,
+            port,
+        } => {
+            run_docs(DocsCommandOptions {
+                input,
+                output,
+                config,
+                format,
+                private,
+                serve,
+                port,
+            })
+            .await
+        }
+        Commands::Test {
+            filters,
+            update,
+            models,
+            ir,
+            junit,
+            config,
+            json,
+        } => {
+            run_test(TestOptions {
+                config_path: config,
+                filters,
+                update,
+                models,
+                ir,
+                junit,
+                json,
+            })
+            .await
+        }
+        Commands::Publish {
+            input,
+            config,
+            registry,
+            dry_run,
+            json,
+        } => {
+            run_publish(PublishOptions {
+                input,
+                config_path: config,
+                registry,
+                dry_run,
+                json,
+            })
+            .await
+        }
+        Commands::Stats {
+            workspace,
+            config,
+            limit,
+            json,
+        } => run_stats(workspace, config, limit, json),
+        Commands::Cache { action } => run_cache_action(action),
+        Commands::Daemon { action } => run_daemon_action(action).await,
+        Commands::Lsp { workspace } => run_lsp(workspace).await,
+        Commands::Validate {
+            input,
+            dependencies,
+            json,
+        } => run_validate(input, dependencies, json),
+        Commands::Lint {
+            input,
+            config,
+            json,
+        } => run_lint(input, config, json),
+        Commands::Check {
+            config,
+            project,
+            json,
+        } => run_check(config, project, json),
+        Commands::Run {
+            fqname,
+            args,
+            input,
+            dependencies,
+            expect,
+            json,
+        } => run_model(RunOptions {
+            input,
+            dependencies,
+            expect,
+            fqname,
+            args,
+            json,
+        }),
+        Commands::Repl {
+            input,
+            dependencies,
+            module,
+        } => run_repl(ReplOptions {
+            input,
+            dependencies,
+            module,
+        }),
+        command => return run_session(command).await,
+    };
+    Ok(exit_code(result))
+}
+
+/// Exit code of a command run outside starbase, printing its error if it
+/// failed
+fn exit_code(result: AppResult) -> std::process::ExitCode {
+    match result {
+        Ok(Some(code)) => std::process::ExitCode::from(code),
+        Ok(None) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            // Wrapped errors, e.g. of serving docs, carry their cause
+            for cause in e.chain().skip(1) {
+                eprintln!("  Caused by: {}", cause);
+            }
+            std::process::ExitCode::from(1)
+        }
+    }
+}
+
+/// Run `command` through starbase
+async fn run_session(command: Commands) -> starbase::MainResult {
     // Create session with command
     let session = MorphirSession { command };
 
-    // Initialize and run starbase App
-    let exit_code = App::default()
-        .run(
-            session,
-            |mut session| async move { session.execute().await },
-        )
+    // Initialize and run starbase App. Its execute phase already calls
+    // `MorphirSession::execute`, so the foreground operation has nothing left
+    // to do; running the command there too would run it twice.
+    let code = App::default()
+        .run(session, |_session| async { Ok(None) })
         .await?;
 
-    Ok(std::process::ExitCode::from(exit_code))
+    Ok(std::process::ExitCode::from(code))
 }
@@ -0,0 +1,42 @@
+//! Message catalog for user-facing CLI output
+//!
+//! The catalog is selected from the `[messages]` section of the nearest
+//! `morphir.toml` and the locale environment, and loaded once per process.
+
+use morphir_common::MessageCatalog;
+use morphir_common::vfs::OsVfs;
+use morphir_design::{discover_config, load_config_context};
+use std::path::Path;
+use std::sync::OnceLock;
+
+static CATALOG: OnceLock<MessageCatalog> = OnceLock::new();
+
+/// The message catalog for this process
+pub fn catalog() -> &'static MessageCatalog {
+    CATALOG.get_or_init(load_catalog)
+}
+
+fn load_catalog() -> MessageCatalog {
+    let config_path = std::env::current_dir()
+        .ok()
+        .and_then(|dir| discover_config(&dir));
+
+    let (base_dir, section) = match &config_path {
+        Some(path) => match load_config_context(path) {
+            Ok(ctx) => (
+                path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+                ctx.config.messages,
+            ),
+            Err(e) => {
+                tracing::warn!("Ignoring message settings in {:?}: {}", path, e);
+                (Path::new(".").to_path_buf(), None)
+            }
+        },
+        None => (Path::new(".").to_path_buf(), None),
+    };
+
+    MessageCatalog::from_config(&OsVfs, &base_dir, section.as_ref()).unwrap_or_else(|e| {
+        tracing::warn!("Failed to load message catalog, using defaults: {}", e);
+        MessageCatalog::embedded()
+    })
+}
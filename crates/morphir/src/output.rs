@@ -1,36 +1,78 @@
 //! Output formatting utilities for programmatic interactions
 
+use morphir_extension_sdk::CodeFix;
 use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Instant;
 
 /// Output format options
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum OutputFormat {
     /// Human-readable output
+    #[value(name = "text", alias = "human")]
     Human,
     /// Single JSON object
     Json,
     /// JSON Lines (newline-delimited JSON, one object per line)
+    #[value(name = "ndjson", alias = "json-lines")]
     JsonLines,
+    /// Aligned columns without decoration, for listings; other results are
+    /// written as text
+    Table,
 }
 
+/// Format chosen with the global `--output` flag or `MORPHIR_OUTPUT`
+static GLOBAL_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Environment variable choosing the output format when `--output` is not given
+pub const OUTPUT_ENV: &str = "MORPHIR_OUTPUT";
+
 impl OutputFormat {
-    /// Determine format from CLI flags
+    /// Determine format from CLI flags, falling back to the global format
     pub fn from_flags(json: bool, json_lines: bool) -> Self {
         if json_lines {
             Self::JsonLines
         } else if json {
             Self::Json
         } else {
-            Self::Human
+            Self::global()
         }
     }
+
+    /// The format every command writes its result in, unless its own flags
+    /// ask for another: `--output`, else `MORPHIR_OUTPUT`, else text.
+    pub fn global() -> Self {
+        *GLOBAL_FORMAT.get_or_init(|| {
+            std::env::var(OUTPUT_ENV)
+                .ok()
+                .and_then(|value| <Self as clap::ValueEnum>::from_str(&value, true).ok())
+                .unwrap_or(Self::Human)
+        })
+    }
+
+    /// Set the global format; only the first call has an effect.
+    pub fn set_global(self) {
+        let _ = GLOBAL_FORMAT.set(self);
+    }
+
+    /// Whether results are written as JSON objects
+    pub fn is_json(self) -> bool {
+        matches!(self, Self::Json | Self::JsonLines)
+    }
+}
+
+/// Whether a command given `--json` should write JSON: when the flag is set
+/// or the global format is JSON
+pub fn json_requested(json: bool) -> bool {
+    json || OutputFormat::global().is_json()
 }
 
 /// Write output in the specified format
 pub fn write_output<T: Serialize>(format: OutputFormat, value: &T) -> std::io::Result<()> {
     match format {
-        OutputFormat::Human => {
+        OutputFormat::Human | OutputFormat::Table => {
             // Human-readable output is handled by command-specific logic
             Ok(())
         }
@@ -62,6 +104,41 @@ pub fn write_json_lines<T: Serialize>(items: impl Iterator<Item = T>) -> anyhow:
     Ok(())
 }
 
+/// Print rows in aligned columns under `headers`, for `--output table`
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    print!("{}", format_table(headers, rows));
+}
+
+/// Rows in columns as wide as their widest cell, separated by two spaces
+fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[&str]| {
+        let last = cells.len().saturating_sub(1);
+        let padded: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                if i == last {
+                    cell.to_string()
+                } else {
+                    format!("{:<width$}", cell, width = widths[i])
+                }
+            })
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+    let mut table = line(headers);
+    for row in rows {
+        table.push_str(&line(&row.iter().map(String::as_str).collect::<Vec<_>>()));
+    }
+    table
+}
+
 /// Compile command output structure
 #[derive(Debug, Serialize)]
 pub struct CompileOutput {
@@ -81,14 +158,296 @@ pub struct GenerateOutput {
     pub output_path: String,
 }
 
+/// Validate command output structure
+#[derive(Debug, Serialize)]
+pub struct ValidateOutput {
+    pub success: bool,
+    pub input: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Transform command output structure
+#[derive(Debug, Serialize)]
+pub struct TransformOutput {
+    pub success: bool,
+    pub transform: String,
+    pub input: String,
+    /// Where the transformed IR was written, if anywhere
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// What the transform reports besides the IR, e.g. the constants found
+    pub report: serde_json::Value,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A project whose module index was rebuilt by `morphir cache rebuild-index`
+#[derive(Debug, Serialize)]
+pub struct IndexedProjectOutput {
+    pub name: String,
+    pub modules: usize,
+    pub symbols: usize,
+    /// Path of the saved index
+    pub index: String,
+}
+
+/// IR cache command output: the cache after `morphir cache stats`, or what
+/// `morphir cache clear` removed
+#[derive(Debug, Serialize)]
+pub struct CacheStatsOutput {
+    /// Directory of the cache
+    pub path: String,
+    /// Number of cached modules
+    pub entries: usize,
+    /// Total size of the cached modules, in bytes
+    pub bytes: u64,
+}
+
+/// An installed tool, distribution, or extension
+#[derive(Debug, Serialize)]
+pub struct InstalledOutput {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    /// Whether it ships with morphir rather than coming from the registry
+    pub builtin: bool,
+    /// Source languages an extension compiles
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    /// Targets an extension generates
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+}
+
+/// `tool list`, `dist list`, and `extension list` output
+#[derive(Debug, Serialize)]
+pub struct InstalledListOutput {
+    pub success: bool,
+    /// `tool`, `distribution`, or `extension`
+    pub kind: String,
+    pub items: Vec<InstalledOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of installing, updating, or uninstalling a tool, distribution, or
+/// extension
+#[derive(Debug, Serialize)]
+pub struct RegistryChangeOutput {
+    pub success: bool,
+    /// `tool`, `distribution`, or `extension`
+    pub kind: String,
+    /// `install`, `update`, or `uninstall`
+    pub action: String,
+    pub name: String,
+    /// Version installed, updated to, or uninstalled
+    pub version: Option<String>,
+    /// Version before an update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_version: Option<String>,
+    /// False when there was nothing to do, e.g. already installed
+    pub changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RegistryChangeOutput {
+    pub fn new(kind: &str, action: &str, name: &str) -> Self {
+        Self {
+            success: true,
+            kind: kind.to_string(),
+            action: action.to_string(),
+            name: name.to_string(),
+            version: None,
+            previous_version: None,
+            changed: false,
+            error: None,
+        }
+    }
+
+    /// Report a failure: as this output in JSON mode, else on stderr.
+    /// Returns the exit code.
+    pub fn fail(mut self, json: bool, error: String) -> Option<u8> {
+        if json {
+            self.success = false;
+            self.error = Some(error);
+            let _ = write_output(OutputFormat::Json, &self);
+        } else {
+            eprintln!("Error: {}", error);
+        }
+        Some(1)
+    }
+}
+
+/// Daemon status command output structure
+#[derive(Debug, Serialize)]
+pub struct DaemonStatusOutput {
+    pub running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// The daemon's answer to `daemon/health`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<serde_json::Value>,
+}
+
+/// Build command output structure
+#[derive(Debug, Serialize)]
+pub struct BuildOutput {
+    pub success: bool,
+    pub project: String,
+    pub duration_ms: u64,
+    /// Stages that ran, in order
+    pub stages: Vec<BuildStage>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Outcome of one stage of a build
+#[derive(Debug, Serialize)]
+pub struct BuildStage {
+    /// `compile`, `validate`, or `generate`
+    pub stage: String,
+    /// Backend of a `generate` stage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub success: bool,
+    /// Why the stage failed, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+    /// Modules compiled, or files generated
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<String>,
+    pub duration_ms: u64,
+}
+
+/// Check command output structure
+#[derive(Debug, Serialize)]
+pub struct CheckOutput {
+    pub success: bool,
+    pub projects: Vec<CheckedProject>,
+}
+
+/// Outcome of checking one project
+#[derive(Debug, Serialize)]
+pub struct CheckedProject {
+    pub name: String,
+    /// Source files parsed
+    pub parsed: usize,
+    /// Source files skipped because they are unchanged since the last build
+    pub unchanged: usize,
+    /// Compiled IR whose references and types were checked, if any
+    pub ir: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Fix command output structure
+#[derive(Debug, Serialize)]
+pub struct FixOutput {
+    /// Whether the fixes were written, rather than only listed
+    pub applied: bool,
+    pub fixes: Vec<AppliedFix>,
+    /// Fixes not applied because they overlap a fix applied before them
+    pub skipped: Vec<AppliedFix>,
+}
+
+/// A fix of `morphir fix` and the diagnostic it fixes
+#[derive(Debug, Serialize)]
+pub struct AppliedFix {
+    pub title: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// Number of edits making up the fix
+    pub edits: usize,
+}
+
+impl AppliedFix {
+    /// `file:line` of the diagnostic, as far as known
+    pub fn location(&self) -> String {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => format!("{}:{}", file, line),
+            (Some(file), None) => file.clone(),
+            _ => "<workspace>".to_string(),
+        }
+    }
+}
+
 /// Diagnostic information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Diagnostic {
     pub level: String, // "error", "warning", "info"
+    /// Diagnostic code or message ID, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     pub message: String,
     pub file: Option<String>,
     pub line: Option<u32>,
     pub column: Option<u32>,
+    /// End of the reported span, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<u32>,
+    /// Secondary locations, e.g. where a duplicate definition was first defined
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<RelatedDiagnostic>,
+    /// Machine-applicable fixes, applied by `morphir fix --apply`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<CodeFix>,
+}
+
+/// Secondary location attached to a diagnostic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedDiagnostic {
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<u32>,
+}
+
+/// `  --> file:line:column` pointer line, if the file is known
+fn location_line(file: &Option<String>, line: Option<u32>, column: Option<u32>) -> Option<String> {
+    let file = file.as_ref()?;
+    Some(match (line, column) {
+        (Some(line), Some(column)) => format!("  --> {}:{}:{}", file, line, column),
+        (Some(line), None) => format!("  --> {}:{}", file, line),
+        _ => format!("  --> {}", file),
+    })
+}
+
+impl Diagnostic {
+    /// Render for human-readable output: the message, a pointer to its
+    /// location, a note for each related location, and the title of each
+    /// suggested fix.
+    ///
+    /// ```text
+    /// error[PARSE_ERROR]: Duplicate definition of `total`
+    ///   --> src/orders.gleam:7:1
+    /// note: first defined here
+    ///   --> src/orders.gleam:3:1
+    /// ```
+    pub fn render_human(&self) -> String {
+        let code = self
+            .code
+            .as_ref()
+            .map(|c| format!("[{}]", c))
+            .unwrap_or_default();
+        let mut lines = vec![format!("{}{}: {}", self.level, code, self.message)];
+        lines.extend(location_line(&self.file, self.line, self.column));
+        for related in &self.related {
+            lines.push(format!("note: {}", related.message));
+            lines.extend(location_line(&related.file, related.line, related.column));
+        }
+        for fix in &self.fixes {
+            lines.push(format!("help: {}", fix.title));
+        }
+        lines.join("\n")
+    }
 }
 
 /// Progress message for streaming output
@@ -108,3 +467,243 @@ pub struct ResultMessage<T: Serialize> {
     #[serde(flatten)]
     pub data: T,
 }
+
+/// How long-running commands report progress
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable messages once the command finishes
+    #[default]
+    Text,
+    /// One JSON event per line on stdout, written as each step happens
+    Ndjson,
+}
+
+/// Progress event streamed in `--log-format ndjson` mode
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressEvent<'a> {
+    StageStarted {
+        stage: &'a str,
+    },
+    StageFinished {
+        stage: &'a str,
+        success: bool,
+        duration_ms: u64,
+    },
+    Diagnostic(&'a Diagnostic),
+    ArtifactWritten {
+        path: String,
+    },
+}
+
+/// Writes progress events to stdout as they happen.
+///
+/// Every method is a no-op unless the stream was created for
+/// [`LogFormat::Ndjson`], so commands can report unconditionally. The last
+/// line of a stream is always a `result` event carrying the command's usual
+/// `--json` output.
+#[derive(Debug, Clone, Copy)]
+pub struct EventStream {
+    enabled: bool,
+}
+
+impl EventStream {
+    pub fn new(format: LogFormat) -> Self {
+        Self {
+            enabled: format == LogFormat::Ndjson,
+        }
+    }
+
+    /// Whether events are being written; other stdout output must be
+    /// suppressed while they are
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Announce a stage and return its start time for [`Self::finished`]
+    pub fn started(&self, stage: &str) -> Instant {
+        self.emit(&ProgressEvent::StageStarted { stage });
+        Instant::now()
+    }
+
+    pub fn finished(&self, stage: &str, started: Instant, success: bool) {
+        self.emit(&ProgressEvent::StageFinished {
+            stage,
+            success,
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+
+    /// Finish a stage, successful if `result` is `Ok`, and pass the result on
+    pub fn track<T, E>(&self, stage: &str, started: Instant, result: Result<T, E>) -> Result<T, E> {
+        self.finished(stage, started, result.is_ok());
+        result
+    }
+
+    pub fn diagnostic(&self, diagnostic: &Diagnostic) {
+        self.emit(&ProgressEvent::Diagnostic(diagnostic));
+    }
+
+    pub fn artifact(&self, path: &Path) {
+        self.emit(&ProgressEvent::ArtifactWritten {
+            path: path.display().to_string(),
+        });
+    }
+
+    /// Close the stream with the command's output as a `result` event
+    pub fn result<T: Serialize>(&self, output: &T) {
+        #[derive(Serialize)]
+        struct ResultEvent<'a, T> {
+            #[serde(rename = "type")]
+            event_type: &'static str,
+            #[serde(flatten)]
+            output: &'a T,
+        }
+        if self.enabled {
+            self.write(&ResultEvent {
+                event_type: "result",
+                output,
+            });
+        }
+    }
+
+    fn emit(&self, event: &ProgressEvent) {
+        if self.enabled {
+            self.write(event);
+        }
+    }
+
+    fn write<T: Serialize>(&self, value: &T) {
+        let Ok(line) = serde_json::to_string(value) else {
+            return;
+        };
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        // Flush per event so wrappers see progress live, even through a pipe
+        let _ = writeln!(handle, "{}", line).and_then(|_| handle.flush());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_human_includes_related_locations() {
+        let diagnostic = Diagnostic {
+            level: "error".to_string(),
+            code: Some("PARSE_ERROR".to_string()),
+            message: "Duplicate definition of `total`".to_string(),
+            file: Some("src/orders.gleam".to_string()),
+            line: Some(7),
+            column: Some(1),
+            end_line: Some(7),
+            end_column: Some(9),
+            related: vec![RelatedDiagnostic {
+                message: "first defined here".to_string(),
+                file: Some("src/orders.gleam".to_string()),
+                line: Some(3),
+                column: Some(1),
+                end_line: Some(3),
+                end_column: Some(9),
+            }],
+            fixes: vec![CodeFix {
+                title: "Rename to `total_2`".to_string(),
+                edits: Vec::new(),
+            }],
+        };
+        assert_eq!(
+            diagnostic.render_human(),
+            "error[PARSE_ERROR]: Duplicate definition of `total`\n  --> src/orders.gleam:7:1\nnote: first defined here\n  --> src/orders.gleam:3:1\nhelp: Rename to `total_2`"
+        );
+
+        let json = serde_json::to_value(&diagnostic).unwrap();
+        assert_eq!(json["related"][0]["line"], 3);
+        assert_eq!(json["end_column"], 9);
+    }
+
+    #[test]
+    fn test_progress_events_are_tagged_by_type() {
+        let started = serde_json::to_value(ProgressEvent::StageStarted { stage: "load" }).unwrap();
+        assert_eq!(
+            started,
+            serde_json::json!({"type": "stage_started", "stage": "load"})
+        );
+
+        let finished = serde_json::to_value(ProgressEvent::StageFinished {
+            stage: "load",
+            success: false,
+            duration_ms: 12,
+        })
+        .unwrap();
+        assert_eq!(
+            finished,
+            serde_json::json!({"type": "stage_finished", "stage": "load", "success": false, "duration_ms": 12})
+        );
+
+        let diagnostic = Diagnostic {
+            level: "warning".to_string(),
+            code: None,
+            message: "Unused import".to_string(),
+            file: Some("src/orders.gleam".to_string()),
+            line: Some(1),
+            column: Some(1),
+            end_line: None,
+            end_column: None,
+            related: vec![],
+            fixes: vec![],
+        };
+        let event = serde_json::to_value(ProgressEvent::Diagnostic(&diagnostic)).unwrap();
+        assert_eq!(event["type"], "diagnostic");
+        assert_eq!(event["level"], "warning");
+        assert_eq!(event["file"], "src/orders.gleam");
+    }
+
+    #[test]
+    fn test_output_format_names_and_tables() {
+        use clap::ValueEnum;
+
+        let parse = |name| OutputFormat::from_str(name, true).unwrap();
+        assert_eq!(parse("text"), OutputFormat::Human);
+        assert_eq!(parse("json"), OutputFormat::Json);
+        assert_eq!(parse("ndjson"), OutputFormat::JsonLines);
+        assert_eq!(parse("table"), OutputFormat::Table);
+        assert!(!OutputFormat::Table.is_json());
+
+        let rows = vec![
+            vec!["gleam".to_string(), "builtin".to_string(), "".to_string()],
+            vec!["x".to_string(), "1.0".to_string(), "A tool".to_string()],
+        ];
+        assert_eq!(
+            format_table(&["NAME", "VERSION", "DESCRIPTION"], &rows),
+            "NAME   VERSION  DESCRIPTION\ngleam  builtin\nx      1.0      A tool\n"
+        );
+    }
+
+    #[test]
+    fn test_registry_change_output_schema() {
+        let mut output = RegistryChangeOutput::new("tool", "update", "fmt");
+        output.version = Some("2.0".to_string());
+        output.previous_version = Some("1.0".to_string());
+        output.changed = true;
+        assert_eq!(
+            serde_json::to_value(&output).unwrap(),
+            serde_json::json!({
+                "success": true,
+                "kind": "tool",
+                "action": "update",
+                "name": "fmt",
+                "version": "2.0",
+                "previous_version": "1.0",
+                "changed": true
+            })
+        );
+    }
+
+    #[test]
+    fn test_event_stream_is_disabled_for_text_logs() {
+        assert!(!EventStream::new(LogFormat::Text).is_enabled());
+        assert!(EventStream::new(LogFormat::Ndjson).is_enabled());
+        assert_eq!(LogFormat::default(), LogFormat::Text);
+    }
+}
@@ -0,0 +1,1110 @@
+//! In-process build pipeline
+//!
+//! [`Pipeline`] runs a whole Morphir build without spawning processes: it
+//! loads the configuration, resolves dependencies, compiles sources to IR,
+//! validates the IR, applies transforms, and generates code. Services that
+//! build models, such as CI bots or data platforms, embed it directly, and the
+//! `compile`, `transform`, and `generate` commands are thin wrappers over its
+//! stages.
+//!
+//! ```no_run
+//! # async fn build() -> Result<(), morphir::CliError> {
+//! let output = morphir::Pipeline::new()
+//!     .with_config("models/morphir.toml")
+//!     .with_transform("extract-constants", serde_json::json!({}))
+//!     .with_target("openapi")
+//!     .run()
+//!     .await?;
+//! assert!(output.success);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each stage is also public, so callers can run part of a build or add their
+//! own steps between stages. Problems in the model are reported as
+//! diagnostics on the stage's output; errors are kept for problems with the
+//! build itself, such as a missing configuration or backend.
+
+use crate::error::{CliError, convert_extension_diagnostics};
+use crate::output::{Diagnostic, EventStream, LogFormat};
+use morphir_builtins::ExtensionType as BuiltinType;
+use morphir_builtins::registry::BuiltinRegistry;
+use morphir_common::config::DependencySpec;
+use morphir_common::loader::{attach_dependencies, load_ir};
+use morphir_common::lockfile::find_locked_digest;
+use morphir_common::registry::{RegistryClient, RegistryError, registry_requirement};
+use morphir_common::remote::RemoteSourceResolver;
+use morphir_common::remote::resolver::ResolveOptions;
+use morphir_common::vendor::{dependency_digest, dependency_source};
+use morphir_core::converter;
+use morphir_core::ir::v4::{OptimizationReport, PassManager};
+use morphir_core::ir::{classic, v4};
+use morphir_daemon::artifacts::{ArtifactWriter, WrittenArtifacts};
+use morphir_daemon::audit::{IdentifierAudit, audit_identifiers, configured_rules};
+use morphir_daemon::extensions::install::{InstalledExtensions, morphir_home};
+use morphir_daemon::extensions::registry::ExtensionRegistry;
+use morphir_design::{
+    ConfigContext, discover_config, ensure_morphir_structure, load_config_context,
+    resolve_compile_output, resolve_generate_output, resolve_path_relative_to_config,
+};
+use morphir_ext_core::Envelope;
+use morphir_extension_sdk::{Artifact, IdentifierRules, SourceMapEntry};
+use morphir_openapi::OpenApiExtension;
+use std::path::{Path, PathBuf};
+
+/// A configurable Morphir build
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    config_path: Option<PathBuf>,
+    language: Option<String>,
+    input: Option<PathBuf>,
+    package_name: Option<String>,
+    compile_output: Option<PathBuf>,
+    jobs: usize,
+    validate: bool,
+    transforms: Vec<(String, serde_json::Value)>,
+    optimize: bool,
+    target: Option<String>,
+    generate_output: Option<PathBuf>,
+    generate_options: serde_json::Map<String, serde_json::Value>,
+    events: EventStream,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// IR compiled from a project's sources
+#[derive(Debug, Clone)]
+pub struct Compiled {
+    pub success: bool,
+    /// Why compilation failed, if it did
+    pub error: Option<String>,
+    /// The compiled IR, also written to `output_path`
+    pub ir: Option<serde_json::Value>,
+    pub modules: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub output_path: PathBuf,
+}
+
+/// IR after a transform
+#[derive(Debug, Clone)]
+pub struct Transformed {
+    pub success: bool,
+    /// Why the transform failed, if it did
+    pub error: Option<String>,
+    /// The transformed IR, if the transform returned any
+    pub ir: Option<serde_json::Value>,
+    /// Everything else the transform returned, such as the constants an
+    /// extraction found
+    pub report: serde_json::Value,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Code generated from IR
+#[derive(Debug, Clone)]
+pub struct Generated {
+    pub success: bool,
+    /// Why generation failed, if it did
+    pub error: Option<String>,
+    pub target: String,
+    /// Files written, empty when generation failed
+    pub written: WrittenArtifacts,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Spans of the artifacts generated from each definition, as returned by
+    /// the backend
+    pub source_map: Vec<SourceMapEntry>,
+    pub output_path: PathBuf,
+}
+
+/// Result of a whole build
+#[derive(Debug, Clone)]
+pub struct PipelineOutput {
+    pub success: bool,
+    /// Diagnostics of every stage that ran, in order
+    pub diagnostics: Vec<Diagnostic>,
+    /// The IR after transforms, when compilation succeeded
+    pub ir: Option<serde_json::Value>,
+    pub compile_output: PathBuf,
+    /// The generated code, when a target is configured and every earlier
+    /// stage succeeded
+    pub generated: Option<Generated>,
+}
+
+impl Pipeline {
+    /// A pipeline for the workspace whose configuration is found from the
+    /// current directory
+    pub fn new() -> Self {
+        Self {
+            config_path: None,
+            language: None,
+            input: None,
+            package_name: None,
+            compile_output: None,
+            jobs: 0,
+            validate: true,
+            transforms: Vec::new(),
+            optimize: false,
+            target: None,
+            generate_output: None,
+            generate_options: serde_json::Map::new(),
+            events: EventStream::new(LogFormat::Text),
+        }
+    }
+
+    /// Use the `morphir.toml` or `morphir.json` at `path`
+    pub fn with_config(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Compile `language` instead of the configured `[frontend] language`
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Compile the sources at `path` instead of the project's source
+    /// directory. Relative paths are taken from the current directory.
+    pub fn with_input(mut self, path: impl Into<PathBuf>) -> Self {
+        self.input = Some(path.into());
+        self
+    }
+
+    /// Name the package instead of taking the project's name
+    pub fn with_package_name(mut self, name: impl Into<String>) -> Self {
+        self.package_name = Some(name.into());
+        self
+    }
+
+    /// Write the compiled IR to `path` instead of under `.morphir/`
+    pub fn with_compile_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.compile_output = Some(path.into());
+        self
+    }
+
+    /// Compile up to `jobs` sources in parallel; 0 for one per CPU
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Whether to type check the compiled IR (on by default)
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Apply the builtin transform `id` with `options`, after any added
+    /// before it
+    pub fn with_transform(mut self, id: impl Into<String>, options: serde_json::Value) -> Self {
+        self.transforms.push((id.into(), options));
+        self
+    }
+
+    /// Whether to optimize the IR before generating code (off by default)
+    pub fn with_optimization(mut self, optimize: bool) -> Self {
+        self.optimize = optimize;
+        self
+    }
+
+    /// Generate `target` instead of the first configured `[codegen]` target
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Write generated code to `path` instead of under `.morphir/`
+    pub fn with_generate_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.generate_output = Some(path.into());
+        self
+    }
+
+    /// Set generation options over the `[codegen.<target>]` settings
+    pub fn with_generate_options(
+        mut self,
+        options: serde_json::Map<String, serde_json::Value>,
+    ) -> Self {
+        self.generate_options.extend(options);
+        self
+    }
+
+    /// Report each stage on `events`
+    pub fn with_events(mut self, events: EventStream) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Run every stage, stopping at the first that fails. Code is generated
+    /// only when a target is set or configured.
+    pub async fn run(&self) -> Result<PipelineOutput, CliError> {
+        let ctx = self.load_config()?;
+        let dependencies = self.resolve_dependencies(&ctx)?;
+        let compiled = self.compile(&ctx).await?;
+        let mut output = PipelineOutput {
+            success: compiled.success,
+            diagnostics: compiled.diagnostics,
+            ir: None,
+            compile_output: compiled.output_path.clone(),
+            generated: None,
+        };
+        if !compiled.success {
+            if let Some(message) = compiled.error {
+                output.diagnostics.push(error(message));
+            }
+            return Ok(output);
+        }
+        let mut ir = match compiled.ir {
+            Some(ir) => ir,
+            None => load_ir(&compiled.output_path).map_err(|e| CliError::FileSystem {
+                error: std::io::Error::other(e),
+            })?,
+        };
+
+        if self.validate {
+            output.diagnostics.extend(self.validate(&ir, &dependencies));
+        }
+        for (id, options) in &self.transforms {
+            if has_errors(&output.diagnostics) {
+                break;
+            }
+            let transformed = apply_transform(id, &ir, options)?;
+            output.diagnostics.extend(transformed.diagnostics);
+            if let Some(message) = transformed.error {
+                output.diagnostics.push(error(message));
+            }
+            if let Some(transformed) = transformed.ir {
+                ir = transformed;
+            }
+        }
+        output.success = !has_errors(&output.diagnostics);
+
+        let generates = self.target.is_some()
+            || ctx
+                .config
+                .codegen
+                .as_ref()
+                .is_some_and(|c| !c.targets.is_empty());
+        if output.success && generates {
+            let generated = self
+                .generate(&ctx, &compiled.output_path, ir.clone())
+                .await?;
+            output.diagnostics.extend(generated.diagnostics.clone());
+            if let Some(message) = &generated.error {
+                output.diagnostics.push(error(message.clone()));
+            }
+            output.success = generated.success;
+            output.generated = Some(generated);
+        }
+        output.ir = Some(ir);
+        Ok(output)
+    }
+
+    /// Load the configuration and make sure `.morphir/` exists
+    pub fn load_config(&self) -> Result<ConfigContext, CliError> {
+        let config_file = match &self.config_path {
+            Some(path) => path.clone(),
+            None => {
+                let start_dir =
+                    std::env::current_dir().map_err(|e| CliError::FileSystem { error: e })?;
+                discover_config(&start_dir).ok_or_else(|| CliError::Config {
+                    error: anyhow::anyhow!("No morphir.toml or morphir.json found"),
+                })?
+            }
+        };
+        let ctx = load_config_context(&config_file).map_err(|e| CliError::Config { error: e })?;
+        ensure_morphir_structure(&ctx.morphir_dir).map_err(|e| CliError::Config { error: e })?;
+        Ok(ctx)
+    }
+
+    /// Local paths of the configured dependencies, fetching remote ones.
+    /// Dependencies without a path or source, such as plain versions, are
+    /// skipped.
+    pub fn resolve_dependencies(&self, ctx: &ConfigContext) -> Result<Vec<PathBuf>, CliError> {
+        let mut declared: Vec<(&String, &DependencySpec)> =
+            ctx.config.dependencies.iter().collect();
+        declared.sort_by_key(|(name, _)| *name);
+
+        // Registry dependencies resolve only when a registry is configured
+        let registry = if declared
+            .iter()
+            .any(|(_, spec)| registry_requirement(spec).is_some())
+        {
+            match RegistryClient::for_workspace(
+                ctx.config.registry.as_ref(),
+                ctx.config.sources.as_ref(),
+            ) {
+                Ok(client) => Some(client),
+                Err(RegistryError::NotConfigured) => None,
+                Err(e) => {
+                    return Err(CliError::Config {
+                        error: anyhow::anyhow!("Failed to initialize registry client: {}", e),
+                    });
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut resolver = None;
+        let mut paths = Vec::new();
+        for (name, spec) in declared {
+            if let DependencySpec::Detailed(detailed) = spec
+                && let Some(path) = &detailed.path
+            {
+                paths.push(resolve_path_relative_to_config(path, &ctx.config_path));
+                continue;
+            }
+            if let Some(registry) = &registry
+                && let Some(requirement) = registry_requirement(spec)
+            {
+                let started = self.events.started("resolve");
+                let (_, path) = self
+                    .events
+                    .track("resolve", started, registry.fetch(name, requirement))
+                    .map_err(|e| CliError::Config {
+                        error: anyhow::anyhow!("Failed to resolve dependency {}: {}", name, e),
+                    })?;
+                paths.push(path);
+                continue;
+            }
+            let Ok(source) = dependency_source(spec) else {
+                continue;
+            };
+            if resolver.is_none() {
+                let config = ctx.config.sources.clone().unwrap_or_default();
+                resolver =
+                    Some(
+                        RemoteSourceResolver::new(config).map_err(|e| CliError::Config {
+                            error: anyhow::anyhow!("Failed to initialize source resolver: {}", e),
+                        })?,
+                    );
+            }
+            let resolver = resolver.as_mut().expect("resolver was just created");
+            // Enforce a digest pinned in the source string or the lockfile
+            let sha256 = dependency_digest(spec).or_else(|| {
+                ctx.config_path
+                    .parent()
+                    .and_then(|dir| find_locked_digest(dir, &source))
+            });
+            let options = ResolveOptions::new().with_sha256(sha256);
+            let started = self.events.started("resolve");
+            let path = self
+                .events
+                .track("resolve", started, resolver.resolve(&source, &options))
+                .map_err(|e| CliError::Config {
+                    error: anyhow::anyhow!("Failed to resolve dependency {}: {}", name, e),
+                })?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Name of the package being built
+    pub fn package_name(&self, ctx: &ConfigContext) -> String {
+        self.package_name
+            .clone()
+            .or_else(|| ctx.current_project.as_ref().map(|p| p.name.clone()))
+            .or_else(|| ctx.config.project.as_ref().map(|p| p.name.clone()))
+            .unwrap_or_else(|| "default".to_string())
+    }
+
+    /// Compile the project's sources with the frontend for its language
+    pub async fn compile(&self, ctx: &ConfigContext) -> Result<Compiled, CliError> {
+        let events = self.events;
+        let language = self
+            .language
+            .clone()
+            .or_else(|| {
+                ctx.config
+                    .frontend
+                    .as_ref()
+                    .and_then(|f| f.language.clone())
+            })
+            .ok_or_else(|| CliError::Config {
+                error: anyhow::anyhow!("Language not specified and not found in config"),
+            })?;
+        let package_name = self.package_name(ctx);
+
+        let input_path = match &self.input {
+            // Given inputs are taken from the current directory
+            Some(input) if input.is_absolute() => input.clone(),
+            Some(input) => std::env::current_dir()
+                .map_err(|e| CliError::FileSystem { error: e })?
+                .join(input),
+            // The configured source directory is taken from the config file
+            None => {
+                let raw_path = ctx
+                    .config
+                    .project
+                    .as_ref()
+                    .map(|p| PathBuf::from(&p.source_directory))
+                    .or_else(|| {
+                        ctx.config.frontend.as_ref().and_then(|f| {
+                            f.settings
+                                .get("source_directory")
+                                .and_then(|v| v.as_str())
+                                .map(PathBuf::from)
+                        })
+                    })
+                    .unwrap_or_else(|| PathBuf::from("src"));
+                resolve_path_relative_to_config(&raw_path, &ctx.config_path)
+            }
+        };
+        let output_path = self
+            .compile_output
+            .clone()
+            .unwrap_or_else(|| resolve_compile_output(&package_name, &language, &ctx.morphir_dir));
+
+        let registry = extension_registry(ctx, &output_path).await?;
+        let extension = registry
+            .find_extension_by_language(&language)
+            .await
+            .ok_or_else(|| CliError::Extension {
+                message: format!("No extension found for language: {}", language),
+            })?;
+
+        let started = events.started("discover");
+        let source_files = events
+            .track(
+                "discover",
+                started,
+                collect_source_files(&input_path, &language),
+            )
+            .map_err(|e| CliError::FileSystem {
+                error: std::io::Error::other(e),
+            })?;
+
+        let frontend = ctx.config.frontend.as_ref();
+        let compile_params = serde_json::json!({
+            "input": input_path.to_string_lossy(),
+            "output": output_path.to_string_lossy(),
+            "package_name": package_name,
+            "files": source_files,
+            "emitParseStage": frontend.map(|f| f.emit_parse_stage).unwrap_or(true),
+            "emitParseStageFatal": frontend.map(|f| f.emit_parse_stage_fatal).unwrap_or(false),
+            "jobs": self.jobs,
+        });
+
+        let started = events.started("compile");
+        let result = extension
+            .call("morphir.frontend.compile", compile_params)
+            .await;
+        let result: serde_json::Value =
+            events
+                .track("compile", started, result)
+                .map_err(|e| CliError::Extension {
+                    message: format!("Extension compile call failed: {}", e),
+                })?;
+
+        let diagnostics = reported_diagnostics(&result);
+        for diagnostic in &diagnostics {
+            events.diagnostic(diagnostic);
+        }
+        let success = result
+            .get("success")
+            .and_then(|s| s.as_bool())
+            .unwrap_or(true);
+        if !success {
+            let error = result
+                .get("error")
+                .and_then(|e| e.as_str())
+                .unwrap_or("Compilation failed");
+            return Ok(Compiled {
+                success,
+                error: Some(error.to_string()),
+                ir: None,
+                modules: Vec::new(),
+                diagnostics,
+                output_path,
+            });
+        }
+
+        // The frontend writes the IR itself
+        if output_path.exists() {
+            events.artifact(&output_path);
+        }
+        Ok(Compiled {
+            success,
+            error: None,
+            ir: result.get("ir").cloned(),
+            modules: result
+                .get("modules")
+                .and_then(|m| serde_json::from_value(m.clone()).ok())
+                .unwrap_or_default(),
+            diagnostics,
+            output_path,
+        })
+    }
+
+    /// Type check `ir` against its signatures and those of `dependencies`,
+    /// and check that its pattern matches are exhaustive
+    pub fn validate(&self, ir: &serde_json::Value, dependencies: &[PathBuf]) -> Vec<Diagnostic> {
+        let started = self.events.started("validate");
+        let Some(mut distribution) = distribution(ir) else {
+            self.events.finished("validate", started, false);
+            return vec![error("Compiled IR is not a distribution".to_string())];
+        };
+        let sources: Vec<String> = dependencies
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        if let Err(e) = attach_dependencies(&mut distribution, &sources) {
+            self.events.finished("validate", started, false);
+            return vec![error(format!("{:#}", e))];
+        }
+        let diagnostics = crate::commands::validate::validate_distribution(&distribution);
+        for diagnostic in &diagnostics {
+            self.events.diagnostic(diagnostic);
+        }
+        self.events
+            .finished("validate", started, !has_errors(&diagnostics));
+        diagnostics
+    }
+
+    /// Run the optimizer's standard passes over `ir`. Classic IR is
+    /// converted, so the optimized IR is always V4.
+    pub fn optimize(
+        &self,
+        ir: &serde_json::Value,
+    ) -> Result<(serde_json::Value, OptimizationReport), CliError> {
+        let started = self.events.started("optimize");
+        let optimized = distribution(ir)
+            .ok_or_else(|| CliError::Validation {
+                message: "Only distributions can be optimized".to_string(),
+            })
+            .and_then(|mut distribution| {
+                let report = PassManager::standard().run(&mut distribution);
+                let ir_file = v4::IRFile {
+                    format_version: v4::FormatVersion::default(),
+                    distribution,
+                };
+                let ir = serde_json::to_value(ir_file).map_err(|e| CliError::Validation {
+                    message: format!("Failed to serialize the optimized IR: {}", e),
+                })?;
+                Ok((ir, report))
+            });
+        self.events.track("optimize", started, optimized)
+    }
+
+    /// The target code is generated for: the one set, then the first
+    /// configured
+    pub fn target(&self, ctx: &ConfigContext) -> Result<String, CliError> {
+        self.target
+            .clone()
+            .or_else(|| {
+                ctx.config
+                    .codegen
+                    .as_ref()
+                    .and_then(|c| c.targets.first().cloned())
+            })
+            .ok_or_else(|| CliError::Config {
+                error: anyhow::anyhow!("Target not specified and not found in config"),
+            })
+    }
+
+    /// Generate code from `ir`, loaded from `input`, and write it using the
+    /// target's directory conventions
+    pub async fn generate(
+        &self,
+        ctx: &ConfigContext,
+        input: &Path,
+        ir: serde_json::Value,
+    ) -> Result<Generated, CliError> {
+        let events = self.events;
+        let target = self.target(ctx)?;
+        let package_name = self.package_name(ctx);
+        let output_path = self
+            .generate_output
+            .clone()
+            .unwrap_or_else(|| resolve_generate_output(&package_name, &target, &ctx.morphir_dir));
+
+        // Target-specific settings, e.g. [codegen.json-schema], then those set
+        let mut options = ctx
+            .config
+            .codegen
+            .as_ref()
+            .and_then(|c| c.settings.get(&target))
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| CliError::Config {
+                error: anyhow::anyhow!("Invalid [codegen.{}] settings: {}", target, e),
+            })?
+            .filter(|o| o.is_object())
+            .unwrap_or_else(|| serde_json::json!({}));
+        for (key, value) in &self.generate_options {
+            options[key] = value.clone();
+        }
+
+        let ir = if self.optimize {
+            let (ir, report) = self.optimize(&ir)?;
+            tracing::info!(
+                "Optimized the IR in {} rounds: {}",
+                report.rounds,
+                report
+                    .passes
+                    .iter()
+                    .map(|p| format!("{} {}", p.rewrites, p.pass))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            ir
+        } else {
+            ir
+        };
+
+        let generate_params = serde_json::json!({
+            "input": input.to_string_lossy(),
+            "output": output_path.to_string_lossy(),
+            "ir": ir,
+            "options": options,
+        });
+
+        // Backends built into the CLI run natively; others are loaded as extensions
+        let started = events.started("generate");
+        let mut builtins = BuiltinRegistry::new();
+        builtins.register(Box::new(OpenApiExtension));
+        let native = builtins
+            .get(&target)
+            .filter(|b| b.info().extension_type == BuiltinType::Backend);
+        let result: Result<serde_json::Value, CliError> = if let Some(builtin) = native {
+            Envelope::json(&generate_params)
+                .map_err(anyhow::Error::from)
+                .and_then(|input| builtin.execute_native(&input))
+                .and_then(|output| output.as_json().map_err(anyhow::Error::from))
+                .map_err(|e| CliError::Extension {
+                    message: format!("Builtin {} generate failed: {}", target, e),
+                })
+        } else {
+            generate_with_extension(ctx, &output_path, &target, generate_params).await
+        };
+        let result = events.track("generate", started, result)?;
+
+        let diagnostics = reported_diagnostics(&result);
+        for diagnostic in &diagnostics {
+            events.diagnostic(diagnostic);
+        }
+        let success = result
+            .get("success")
+            .and_then(|s| s.as_bool())
+            .unwrap_or(true);
+        let mut generated = Generated {
+            success,
+            error: None,
+            target,
+            written: WrittenArtifacts::default(),
+            diagnostics,
+            source_map: Vec::new(),
+            output_path,
+        };
+        if !success {
+            let error = result
+                .get("error")
+                .and_then(|e| e.as_str())
+                .unwrap_or("Code generation failed");
+            generated.error = Some(error.to_string());
+            return Ok(generated);
+        }
+
+        let artifacts: Vec<Artifact> = result
+            .get("artifacts")
+            .and_then(|a| serde_json::from_value(a.clone()).ok())
+            .unwrap_or_default();
+        let started = events.started("write");
+        let written = self.writer(&generated, &package_name).write(&artifacts);
+        generated.written =
+            events
+                .track("write", started, written)
+                .map_err(|e| CliError::Extension {
+                    message: format!("Failed to write artifacts: {}", e),
+                })?;
+        for path in generated
+            .written
+            .artifacts
+            .iter()
+            .chain(&generated.written.scaffolding)
+        {
+            events.artifact(path);
+        }
+        generated.source_map = result
+            .get("source_map")
+            .and_then(|m| serde_json::from_value(m.clone()).ok())
+            .unwrap_or_default();
+        Ok(generated)
+    }
+
+    /// The writer that placed `generated`
+    pub fn writer(&self, generated: &Generated, package_name: &str) -> ArtifactWriter {
+        ArtifactWriter::new(&generated.output_path, &generated.target)
+            .with_package_name(package_name)
+    }
+}
+
+/// Apply the builtin transform `id` to `ir`
+pub fn apply_transform(
+    id: &str,
+    ir: &serde_json::Value,
+    options: &serde_json::Value,
+) -> Result<Transformed, CliError> {
+    let registry = BuiltinRegistry::new();
+    let Some(builtin) = registry
+        .get(id)
+        .filter(|b| b.info().extension_type == BuiltinType::Transform)
+    else {
+        let mut available: Vec<String> = registry
+            .list()
+            .into_iter()
+            .filter(|info| info.extension_type == BuiltinType::Transform)
+            .map(|info| info.id)
+            .collect();
+        available.sort();
+        return Err(CliError::Validation {
+            message: format!(
+                "Unknown transform '{}'. Available transforms: {}",
+                id,
+                available.join(", ")
+            ),
+        });
+    };
+
+    let mut response = Envelope::json(&serde_json::json!({ "ir": ir, "options": options }))
+        .map_err(anyhow::Error::from)
+        .and_then(|request| builtin.execute_native(&request))
+        .and_then(|response| {
+            response
+                .as_json::<serde_json::Value>()
+                .map_err(anyhow::Error::from)
+        })
+        .map_err(|e| CliError::Extension {
+            message: format!("Transform {} failed: {:#}", id, e),
+        })?;
+
+    let diagnostics = reported_diagnostics(&response);
+    if response.get("success").and_then(|s| s.as_bool()) != Some(true) {
+        let message = response
+            .get("error")
+            .and_then(|e| e.as_str())
+            .unwrap_or("Transform failed");
+        return Ok(Transformed {
+            success: false,
+            error: Some(message.to_string()),
+            ir: None,
+            report: serde_json::Value::Null,
+            diagnostics,
+        });
+    }
+
+    // Everything but the IR and the envelope fields is the transform's report
+    let ir = response.as_object_mut().and_then(|r| {
+        for key in ["success", "diagnostics", "error"] {
+            r.remove(key);
+        }
+        r.remove("ir")
+    });
+    Ok(Transformed {
+        success: true,
+        error: None,
+        ir,
+        report: response,
+        diagnostics,
+    })
+}
+
+/// An extension registry with the builtin and installed extensions registered
+async fn extension_registry(
+    ctx: &ConfigContext,
+    output_path: &Path,
+) -> Result<ExtensionRegistry, CliError> {
+    let registry = ExtensionRegistry::new(
+        ctx.project_root
+            .clone()
+            .unwrap_or_else(|| ctx.config_path.parent().unwrap().to_path_buf()),
+        output_path.to_path_buf(),
+    )
+    .map_err(|e| CliError::Extension {
+        message: format!("Failed to create extension registry: {}", e),
+    })?
+    .with_extensions_config(&ctx.config.extensions)
+    .with_sources(ctx.config.sources.clone().unwrap_or_default());
+
+    for builtin in morphir_design::discover_builtin_extensions() {
+        if let Some(path) = builtin.path {
+            registry
+                .register_builtin(&builtin.id, path)
+                .await
+                .map_err(|e| CliError::Extension {
+                    message: format!("Failed to register builtin extension {}: {}", builtin.id, e),
+                })?;
+        }
+    }
+    let installed = morphir_home()
+        .and_then(|home| InstalledExtensions::load(&home))
+        .map_err(|e| CliError::Extension {
+            message: format!("Failed to load installed extensions: {}", e),
+        })?;
+    registry
+        .register_installed(&installed)
+        .await
+        .map_err(|e| CliError::Extension {
+            message: format!("Failed to register installed extensions: {}", e),
+        })?;
+    Ok(registry)
+}
+
+/// Run `morphir.backend.generate` on the extension registered for `target`
+async fn generate_with_extension(
+    ctx: &ConfigContext,
+    output_path: &Path,
+    target: &str,
+    generate_params: serde_json::Value,
+) -> Result<serde_json::Value, CliError> {
+    let registry = extension_registry(ctx, output_path).await?;
+    let extension = registry
+        .find_extension_by_target(target)
+        .await
+        .ok_or_else(|| CliError::Extension {
+            message: format!("No extension found for target: {}", target),
+        })?;
+
+    let audit = extension
+        .capabilities()
+        .identifiers
+        .as_ref()
+        .and_then(|rules| audit_generate_params(rules, &generate_params));
+    if let Some(audit) = audit.as_ref().filter(|a| !a.collisions.is_empty()) {
+        return Ok(serde_json::json!({
+            "success": false,
+            "error": format!("{} generated identifier(s) would collide", audit.collisions.len()),
+            "diagnostics": audit.diagnostics(),
+        }));
+    }
+
+    // Call extension's generate method
+    let mut result = extension
+        .call("morphir.backend.generate", generate_params)
+        .await
+        .map_err(|e| CliError::Extension {
+            message: format!("Extension generate call failed: {}", e),
+        })?;
+    if let Some(audit) = audit {
+        add_audit_diagnostics(&mut result, &audit);
+    }
+    Ok(result)
+}
+
+/// The V4 distribution `ir` holds, converting classic IR
+fn distribution(ir: &serde_json::Value) -> Option<v4::Distribution> {
+    serde_json::from_value::<v4::IRFile>(ir.clone())
+        .map(|file| file.distribution)
+        .or_else(|_| {
+            serde_json::from_value::<classic::Distribution>(ir.clone())
+                .map(|dist| converter::classic_to_v4(&dist).ir.distribution)
+        })
+        .ok()
+}
+
+/// Audit the identifiers generated from the IR in `generate_params` against
+/// `rules`, as adjusted by its options. IR other than a distribution with
+/// definitions is not audited.
+fn audit_generate_params(
+    rules: &IdentifierRules,
+    generate_params: &serde_json::Value,
+) -> Option<IdentifierAudit> {
+    let distribution = distribution(&generate_params["ir"])?;
+    let package = distribution.definition()?;
+    let rules = configured_rules(rules, &generate_params["options"]);
+    Some(audit_identifiers(package, &rules))
+}
+
+/// Put the audit's diagnostics ahead of those the backend reported
+fn add_audit_diagnostics(result: &mut serde_json::Value, audit: &IdentifierAudit) {
+    let Some(result) = result.as_object_mut() else {
+        return;
+    };
+    let mut diagnostics: Vec<serde_json::Value> = audit
+        .diagnostics()
+        .iter()
+        .filter_map(|d| serde_json::to_value(d).ok())
+        .collect();
+    if let Some(serde_json::Value::Array(reported)) = result.remove("diagnostics") {
+        diagnostics.extend(reported);
+    }
+    result.insert("diagnostics".to_string(), diagnostics.into());
+}
+
+/// Diagnostics in an extension's response
+fn reported_diagnostics(response: &serde_json::Value) -> Vec<Diagnostic> {
+    response
+        .get("diagnostics")
+        .and_then(|d| serde_json::from_value(d.clone()).ok())
+        .map(|d: Vec<morphir_extension_sdk::Diagnostic>| convert_extension_diagnostics(&d))
+        .unwrap_or_default()
+}
+
+fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.level == "error")
+}
+
+/// An error about a stage or run as a whole
+pub(crate) fn error(message: String) -> Diagnostic {
+    Diagnostic {
+        level: "error".to_string(),
+        code: None,
+        message,
+        file: None,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        related: Vec::new(),
+        fixes: Vec::new(),
+    }
+}
+
+/// Collect source files from input directory
+pub(crate) fn collect_source_files(
+    input_path: &Path,
+    language: &str,
+) -> anyhow::Result<Vec<String>> {
+    let mut files = Vec::new();
+
+    if !input_path.exists() {
+        return Ok(files);
+    }
+
+    if input_path.is_file() {
+        files.push(input_path.to_string_lossy().to_string());
+        return Ok(files);
+    }
+
+    // Determine file extension based on language
+    let ext = match language {
+        "gleam" => "gleam",
+        "elm" => "elm",
+        "python" => "py",
+        _ => {
+            return Err(CliError::Validation {
+                message: format!("Unknown language: {}", language),
+            }
+            .into());
+        }
+    };
+
+    // Walk directory and collect files
+    for entry in walkdir::WalkDir::new(input_path) {
+        let entry = entry?;
+        if entry.file_type().is_file()
+            && let Some(file_ext) = entry.path().extension()
+            && file_ext == ext
+        {
+            files.push(entry.path().to_string_lossy().to_string());
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> tempfile::TempDir {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("morphir.toml"),
+            r#"
+[project]
+name = "acme"
+version = "1.0.0"
+source_directory = "src"
+
+[codegen]
+targets = ["openapi"]
+
+[dependencies]
+shared = { path = "deps/shared.json" }
+sdk = "1.0.0"
+"#,
+        )
+        .unwrap();
+        temp
+    }
+
+    #[test]
+    fn test_settings_come_from_the_builder_then_the_config() {
+        let temp = workspace();
+        let pipeline = Pipeline::new().with_config(temp.path().join("morphir.toml"));
+        let ctx = pipeline.load_config().unwrap();
+        assert!(ctx.morphir_dir.exists());
+        assert_eq!(pipeline.target(&ctx).unwrap(), "openapi");
+        assert_eq!(pipeline.package_name(&ctx), "acme");
+
+        let pipeline = pipeline.with_target("gleam").with_package_name("acme-api");
+        assert_eq!(pipeline.target(&ctx).unwrap(), "gleam");
+        assert_eq!(pipeline.package_name(&ctx), "acme-api");
+
+        // Plain versions name no source and, without a registry, are skipped
+        let dependencies = pipeline.resolve_dependencies(&ctx).unwrap();
+        assert_eq!(dependencies.len(), 1);
+        assert!(dependencies[0].ends_with("deps/shared.json"));
+    }
+
+    #[test]
+    fn test_plain_versions_resolve_against_the_registry() {
+        let temp = workspace();
+        let cache = temp.path().join("packages");
+        let cached = cache
+            .join("registry.example.com")
+            .join("sdk")
+            .join("1.0.0")
+            .join("morphir-ir.json");
+        std::fs::create_dir_all(cached.parent().unwrap()).unwrap();
+        std::fs::write(&cached, "{}").unwrap();
+        let config = temp.path().join("morphir.toml");
+        let mut content = std::fs::read_to_string(&config).unwrap();
+        content.push_str(&format!(
+            "\n[sources]\noffline = true\n\n[registry]\nurl = \"https://registry.example.com\"\ncacheDirectory = {:?}\n",
+            cache.display().to_string()
+        ));
+        std::fs::write(&config, content).unwrap();
+
+        let pipeline = Pipeline::new().with_config(config);
+        let ctx = pipeline.load_config().unwrap();
+        let dependencies = pipeline.resolve_dependencies(&ctx).unwrap();
+        assert_eq!(dependencies.len(), 2);
+        assert_eq!(dependencies[0], cached);
+    }
+
+    #[tokio::test]
+    async fn test_stages_run_in_process() {
+        let temp = workspace();
+        let pipeline = Pipeline::new().with_config(temp.path().join("morphir.toml"));
+        let ctx = pipeline.load_config().unwrap();
+        let ir = serde_json::json!({
+            "formatVersion": 4,
+            "distribution": { "Library": {
+                "packageName": "acme",
+                "dependencies": {},
+                "def": { "modules": {} }
+            } }
+        });
+
+        assert!(pipeline.validate(&ir, &[]).is_empty());
+        let invalid = pipeline.validate(&serde_json::json!({ "modules": 1 }), &[]);
+        assert_eq!(invalid[0].message, "Compiled IR is not a distribution");
+
+        let err = apply_transform("no-such-transform", &ir, &serde_json::json!({})).unwrap_err();
+        assert!(err.to_string().contains("Unknown transform"), "{}", err);
+
+        // OpenAPI needs an application; the failure is reported, not raised
+        let generated = pipeline
+            .generate(&ctx, &temp.path().join("ir.json"), ir)
+            .await
+            .unwrap();
+        assert!(!generated.success);
+        assert!(generated.error.unwrap().contains("Application"));
+        assert!(generated.written.artifacts.is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+//! JUnit XML reports
+//!
+//! Renders a [`TestReport`] in the JUnit XML format most CI systems read:
+//! one `<testsuite>` per suite and one `<testcase>` per case, with
+//! `<failure>`, `<error>`, or `<skipped>` children for cases that did not
+//! pass.
+
+use super::{TestCase, TestOutcome, TestReport};
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Render `report` as a JUnit XML document
+pub fn to_junit_xml(report: &TestReport) -> String {
+    let counts = report.counts();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"morphir\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{}\">",
+        counts.total(),
+        counts.failed,
+        counts.errors,
+        counts.skipped,
+        seconds(report.duration())
+    );
+    for suite in &report.suites {
+        let counts = suite.counts();
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{}\">",
+            escape(&suite.name),
+            counts.total(),
+            counts.failed,
+            counts.errors,
+            counts.skipped,
+            seconds(suite.duration())
+        );
+        for case in &suite.cases {
+            write_case(&mut xml, &suite.name, case);
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn write_case(xml: &mut String, suite: &str, case: &TestCase) {
+    let _ = write!(
+        xml,
+        "    <testcase name=\"{}\" classname=\"{}.{}\" time=\"{}\"",
+        escape(&case.name),
+        escape(suite),
+        escape(&case.kind),
+        seconds(case.duration)
+    );
+    match &case.outcome {
+        TestOutcome::Passed => xml.push_str("/>\n"),
+        TestOutcome::Failed { message, details } => {
+            let _ = writeln!(
+                xml,
+                ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                escape(message),
+                escape(&details.join("\n"))
+            );
+        }
+        TestOutcome::Error { message } => {
+            let _ = writeln!(
+                xml,
+                ">\n      <error message=\"{}\"/>\n    </testcase>",
+                escape(message)
+            );
+        }
+        TestOutcome::Skipped { reason } => {
+            let _ = writeln!(
+                xml,
+                ">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                escape(reason)
+            );
+        }
+    }
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64())
+}
+
+/// Escape text for use in XML attributes and content
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab and newlines are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestSuite;
+
+    fn case(name: &str, outcome: TestOutcome) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            kind: "compile".to_string(),
+            outcome,
+            duration: Duration::from_millis(1500),
+        }
+    }
+
+    #[test]
+    fn test_junit_report_lists_outcomes() {
+        let mut suite = TestSuite::new("scenarios");
+        suite.cases = vec![
+            case("orders", TestOutcome::Passed),
+            case(
+                "pricing <v2>",
+                TestOutcome::Failed {
+                    message: "1 difference".to_string(),
+                    details: vec![".modules: expected \"a\", got \"b\"".to_string()],
+                },
+            ),
+            case(
+                "missing",
+                TestOutcome::Error {
+                    message: "No input".to_string(),
+                },
+            ),
+        ];
+        let xml = to_junit_xml(&TestReport {
+            suites: vec![suite],
+        });
+
+        assert!(xml.contains(
+            "<testsuites name=\"morphir\" tests=\"3\" failures=\"1\" errors=\"1\" skipped=\"0\" time=\"4.500\">"
+        ));
+        assert!(xml.contains(
+            "<testcase name=\"orders\" classname=\"scenarios.compile\" time=\"1.500\"/>"
+        ));
+        assert!(xml.contains("name=\"pricing &lt;v2&gt;\""));
+        assert!(xml.contains(
+            "<failure message=\"1 difference\">.modules: expected &quot;a&quot;, got &quot;b&quot;</failure>"
+        ));
+        assert!(xml.contains("<error message=\"No input\"/>"));
+    }
+}
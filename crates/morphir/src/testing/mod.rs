@@ -0,0 +1,132 @@
+//! Test harness behind `morphir test`
+//!
+//! Tests are grouped into suites of named cases, each of which passes, fails
+//! with the differences found, or errors when it could not run at all. The
+//! [`scenario`] suite runs build stages against fixtures and compares their
+//! output with golden files, the [`model`] suite evaluates value definitions
+//! against expected results, and [`junit`] renders a report for CI systems.
+
+pub mod junit;
+pub mod model;
+pub mod scenario;
+
+use serde::Serialize;
+use std::time::Duration;
+
+/// Outcome of one test case
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum TestOutcome {
+    Passed,
+    /// The case ran and its output differs from what was expected
+    Failed {
+        message: String,
+        /// The differences found, one per line
+        details: Vec<String>,
+    },
+    /// The case could not run, e.g. because its input is missing
+    Error {
+        message: String,
+    },
+    Skipped {
+        reason: String,
+    },
+}
+
+/// One test case
+#[derive(Debug, Clone, Serialize)]
+pub struct TestCase {
+    pub name: String,
+    /// What the case exercises, e.g. the stage of a scenario
+    pub kind: String,
+    #[serde(flatten)]
+    pub outcome: TestOutcome,
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+/// Test cases run together
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestSuite {
+    pub name: String,
+    pub cases: Vec<TestCase>,
+}
+
+/// Case counts by outcome
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TestCounts {
+    pub passed: usize,
+    pub failed: usize,
+    pub errors: usize,
+    pub skipped: usize,
+}
+
+impl TestCounts {
+    /// Total number of cases
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.errors + self.skipped
+    }
+
+    /// Whether no case failed or errored
+    pub fn success(&self) -> bool {
+        self.failed == 0 && self.errors == 0
+    }
+}
+
+impl TestSuite {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            cases: Vec::new(),
+        }
+    }
+
+    pub fn counts(&self) -> TestCounts {
+        let mut counts = TestCounts::default();
+        for case in &self.cases {
+            match case.outcome {
+                TestOutcome::Passed => counts.passed += 1,
+                TestOutcome::Failed { .. } => counts.failed += 1,
+                TestOutcome::Error { .. } => counts.errors += 1,
+                TestOutcome::Skipped { .. } => counts.skipped += 1,
+            }
+        }
+        counts
+    }
+
+    /// Time taken by all cases
+    pub fn duration(&self) -> Duration {
+        self.cases.iter().map(|case| case.duration).sum()
+    }
+}
+
+/// Everything a test run produced
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestReport {
+    pub suites: Vec<TestSuite>,
+}
+
+impl TestReport {
+    pub fn counts(&self) -> TestCounts {
+        let mut total = TestCounts::default();
+        for counts in self.suites.iter().map(TestSuite::counts) {
+            total.passed += counts.passed;
+            total.failed += counts.failed;
+            total.errors += counts.errors;
+            total.skipped += counts.skipped;
+        }
+        total
+    }
+
+    /// Time taken by all suites
+    pub fn duration(&self) -> Duration {
+        self.suites.iter().map(TestSuite::duration).sum()
+    }
+}
+
+fn serialize_millis<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
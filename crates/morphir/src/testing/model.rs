@@ -0,0 +1,371 @@
+//! Example-based model tests
+//!
+//! Test files under a project's `tests/` directory declare cases for value
+//! definitions: the arguments to call a definition with and the value it
+//! must produce. Cases are evaluated with the IR evaluator, so business
+//! logic can be checked without generating code first.
+//!
+//! ```toml
+//! # tests/orders.toml
+//! function = "acme/shop:orders#price-order"   # default for the cases below
+//!
+//! [[case]]
+//! name = "bulk discount"
+//! args = [{ id = "A-1", quantity = 10 }, 2.5]
+//! expected = 22.5
+//!
+//! [[case]]
+//! name = "named arguments"
+//! args = { order = { id = "A-2", quantity = 1 }, unit-price = 4 }
+//! expected = 4
+//!
+//! [[case]]
+//! function = "acme/shop:orders#version"
+//! expected = 2
+//! skip = "versioning is not settled yet"
+//! ```
+//!
+//! JSON test files use the same structure, with the cases under `cases`.
+//! Arguments and expected values use the JSON encoding of `morphir run`.
+//! Positional arguments follow the order of the definition's inputs in the
+//! IR; arguments given by name do not depend on it.
+
+use super::{TestCase, TestOutcome, TestSuite};
+use crate::commands::run::EVAL_STACK_SIZE;
+use crate::commands::sample::parse_fqname;
+use morphir_core::ir::v4::Distribution;
+use morphir_core::naming::{FQName, Name};
+use morphir_runtime::{Evaluator, diff};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Directory of a project holding its model tests
+pub const MODEL_TESTS_DIR: &str = "tests";
+
+/// Contents of a model test file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelTestFile {
+    /// Definition the cases call unless they name their own
+    pub function: Option<String>,
+    #[serde(default, rename = "case", alias = "cases")]
+    pub cases: Vec<ModelTestSpec>,
+}
+
+/// One case of a model test file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelTestSpec {
+    pub name: Option<String>,
+    /// Definition to call, e.g. `acme/shop:orders#price-order`
+    pub function: Option<String>,
+    /// Arguments, in order or by input name
+    #[serde(default)]
+    pub args: serde_json::Value,
+    /// Value the definition must produce
+    pub expected: serde_json::Value,
+    /// Why the case is skipped
+    pub skip: Option<String>,
+}
+
+/// A model test case found on disk
+#[derive(Debug, Clone)]
+pub struct ModelTest {
+    /// Test file relative to the tests directory, then the case name
+    pub name: String,
+    pub file: PathBuf,
+    /// The definition and case to run, or why the file could not be used
+    pub spec: Result<(String, ModelTestSpec), String>,
+}
+
+/// Every model test case in the `.toml` and `.json` files under `root`
+pub fn discover_model_tests(root: &Path) -> Vec<ModelTest> {
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("toml" | "json")
+            )
+        })
+        .collect();
+    files.sort();
+
+    let mut tests = Vec::new();
+    for file in files {
+        let prefix = file
+            .strip_prefix(root)
+            .unwrap_or(&file)
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/");
+        let parsed = match load_file(&file) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                tests.push(ModelTest {
+                    name: prefix,
+                    file,
+                    spec: Err(message),
+                });
+                continue;
+            }
+        };
+        for (index, case) in parsed.cases.into_iter().enumerate() {
+            let name = format!(
+                "{}::{}",
+                prefix,
+                case.name
+                    .clone()
+                    .unwrap_or_else(|| format!("case {}", index + 1))
+            );
+            let spec = case
+                .function
+                .clone()
+                .or_else(|| parsed.function.clone())
+                .map(|function| (function, case))
+                .ok_or_else(|| "No function given for the case or its file".to_string());
+            tests.push(ModelTest {
+                name,
+                file: file.clone(),
+                spec,
+            });
+        }
+    }
+    tests
+}
+
+fn load_file(path: &Path) -> Result<ModelTestFile, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let parsed = if path.extension().is_some_and(|e| e == "json") {
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&content).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Evaluate `tests` against `distribution`
+pub fn run_model_tests(distribution: &Distribution, tests: &[ModelTest]) -> TestSuite {
+    let mut suite = TestSuite::new("models");
+    let run = || {
+        let evaluator = Evaluator::new(distribution);
+        tests
+            .iter()
+            .map(|test| run_case(&evaluator, test))
+            .collect()
+    };
+    suite.cases = std::thread::scope(|scope| {
+        let handle = std::thread::Builder::new()
+            .name("morphir-eval".to_string())
+            .stack_size(EVAL_STACK_SIZE)
+            .spawn_scoped(scope, run);
+        match handle.map(|handle| handle.join()) {
+            Ok(Ok(cases)) => cases,
+            Ok(Err(_)) => errors(tests, "Evaluation panicked"),
+            Err(e) => errors(tests, &format!("Failed to start evaluation: {}", e)),
+        }
+    });
+    suite
+}
+
+fn errors(tests: &[ModelTest], message: &str) -> Vec<TestCase> {
+    tests
+        .iter()
+        .map(|test| TestCase {
+            name: test.name.clone(),
+            kind: "model".to_string(),
+            outcome: TestOutcome::Error {
+                message: message.to_string(),
+            },
+            duration: Default::default(),
+        })
+        .collect()
+}
+
+fn run_case(evaluator: &Evaluator, test: &ModelTest) -> TestCase {
+    let started = Instant::now();
+    let outcome = match &test.spec {
+        Ok((_, spec)) if spec.skip.is_some() => TestOutcome::Skipped {
+            reason: spec.skip.clone().unwrap_or_default(),
+        },
+        Ok((function, spec)) => evaluate(evaluator, function, spec)
+            .unwrap_or_else(|message| TestOutcome::Error { message }),
+        Err(message) => TestOutcome::Error {
+            message: message.clone(),
+        },
+    };
+    TestCase {
+        name: test.name.clone(),
+        kind: "model".to_string(),
+        outcome,
+        duration: started.elapsed(),
+    }
+}
+
+/// Call the definition of a case and compare its result; `Err` when the
+/// case could not be set up
+fn evaluate(
+    evaluator: &Evaluator,
+    function: &str,
+    spec: &ModelTestSpec,
+) -> Result<TestOutcome, String> {
+    let fqname = parse_fqname(function)?;
+    let args = positional_args(evaluator, &fqname, &spec.args)?;
+    let args = evaluator
+        .args_from_json(&fqname, &args)
+        .map_err(|e| format!("Invalid arguments: {}", e))?;
+    let expected = evaluator
+        .output_from_json(&fqname, &spec.expected)
+        .map_err(|e| format!("Invalid expected value: {}", e))?;
+
+    let actual = match evaluator.evaluate(&fqname, args) {
+        Ok(actual) => actual,
+        Err(e) => {
+            return Ok(TestOutcome::Failed {
+                message: format!("Evaluation failed: {}", e),
+                details: Vec::new(),
+            });
+        }
+    };
+    let differences = diff(&expected, &actual);
+    if differences.is_empty() {
+        return Ok(TestOutcome::Passed);
+    }
+    Ok(TestOutcome::Failed {
+        message: format!(
+            "Result differs from the expected value in {} place(s)",
+            differences.len()
+        ),
+        details: differences.iter().map(|d| d.to_string()).collect(),
+    })
+}
+
+/// Arguments of a case in the order of the definition's inputs
+fn positional_args(
+    evaluator: &Evaluator,
+    fqname: &FQName,
+    args: &serde_json::Value,
+) -> Result<Vec<serde_json::Value>, String> {
+    let named = match args {
+        serde_json::Value::Null => return Ok(Vec::new()),
+        serde_json::Value::Array(args) => return Ok(args.clone()),
+        serde_json::Value::Object(named) => named,
+        // A single argument needs no list
+        arg => return Ok(vec![arg.clone()]),
+    };
+    let definition = evaluator
+        .definition(fqname)
+        .ok_or_else(|| format!("Unknown value {}", fqname))?;
+    let inputs: Vec<String> = definition
+        .input_types
+        .keys()
+        .map(|input| Name::from(input.as_str()).to_kebab_case())
+        .collect();
+    let mut ordered = vec![None; inputs.len()];
+    for (name, value) in named {
+        let name = Name::from(name.as_str()).to_kebab_case();
+        let index = inputs
+            .iter()
+            .position(|input| *input == name)
+            .ok_or_else(|| format!("{} has no input named {}", fqname, name))?;
+        ordered[index] = Some(value.clone());
+    }
+    ordered
+        .into_iter()
+        .zip(&inputs)
+        .map(|(value, input)| value.ok_or_else(|| format!("Missing argument {}", input)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn distribution() -> Distribution {
+        serde_json::from_value(json!({ "Library": {
+            "packageName": "shop",
+            "dependencies": {},
+            "def": { "modules": { "orders": { "access": "Public", "value": {
+                "types": {},
+                "values": {
+                    "subtract": { "access": "Public", "value": {
+                        "inputTypes": {
+                            "amount": { "type": "morphir/sdk:basics#int" },
+                            "discount": { "type": "morphir/sdk:basics#int" }
+                        },
+                        "outputType": "morphir/sdk:basics#int",
+                        "body": { "ExpressionBody": { "body": { "Apply": {
+                            "function": { "Apply": {
+                                "function": { "Reference": { "fqname": "morphir/sdk:basics#subtract" } },
+                                "argument": { "Variable": { "name": "amount" } }
+                            } },
+                            "argument": { "Variable": { "name": "discount" } }
+                        } } } }
+                    } }
+                }
+            } } } }
+        } }))
+        .unwrap()
+    }
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_discover_model_tests_names_cases_by_file() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "orders/pricing.toml",
+            "function = \"shop:orders#subtract\"\n\n[[case]]\nname = \"discount\"\nargs = [10, 3]\nexpected = 7\n\n[[case]]\nexpected = 0\n",
+        );
+        write(dir.path(), "broken.json", "{ \"cases\": [");
+        write(dir.path(), "notes.md", "not a test");
+
+        let tests = discover_model_tests(dir.path());
+        let names: Vec<&str> = tests.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "broken",
+                "orders/pricing::discount",
+                "orders/pricing::case 2"
+            ]
+        );
+        assert!(tests[0].spec.is_err());
+        assert_eq!(tests[1].spec.as_ref().unwrap().0, "shop:orders#subtract");
+    }
+
+    #[test]
+    fn test_run_model_tests_compares_results() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "orders.json",
+            r#"{ "function": "shop:orders#subtract", "cases": [
+                { "name": "positional", "args": [10, 3], "expected": 7 },
+                { "name": "named", "args": { "discount": 3, "amount": 10 }, "expected": 7 },
+                { "name": "wrong", "args": [10, 3], "expected": 8 },
+                { "name": "unknown input", "args": { "price": 1 }, "expected": 0 },
+                { "name": "pending", "expected": 0, "skip": "not yet" }
+            ] }"#,
+        );
+        let suite = run_model_tests(&distribution(), &discover_model_tests(dir.path()));
+        let counts = suite.counts();
+        assert_eq!((counts.passed, counts.failed), (2, 1));
+        assert_eq!((counts.errors, counts.skipped), (1, 1));
+        match &suite.cases[2].outcome {
+            TestOutcome::Failed { details, .. } => assert_eq!(details.len(), 1),
+            other => panic!("expected a failure, got {:?}", other),
+        }
+    }
+}
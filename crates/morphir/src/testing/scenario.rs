@@ -0,0 +1,539 @@
+//! Golden-output scenarios
+//!
+//! A scenario is a directory under `.morphir/test/scenarios/` whose
+//! `scenario.toml` describes one build stage to run and the output it must
+//! produce:
+//!
+//! ```toml
+//! description = "Orders compile to the expected IR"
+//! stage = "compile"             # compile, transform, or generate
+//! input = "src"                 # relative to the scenario; defaults to `input`
+//! # fixture = "orders"          # or a fixture under .morphir/test/fixtures/
+//! language = "gleam"            # compile: defaults to [frontend] language
+//! # transform = "extract-constants"
+//! # target = "openapi"
+//! # options = { ... }           # transform and generate options
+//! expected = "expected.json"    # defaults to expected.json, or expected/ for generate
+//! ignore = ["sourceLocation"]   # IR members left out of the comparison
+//! ```
+//!
+//! Compile and transform scenarios compare IR structurally with
+//! [`diff_ir_ignoring`]. Generate scenarios compare the generated file tree:
+//! JSON files structurally and other files line by line. The actual output
+//! of each scenario is written under `.morphir/out/test/<scenario>/`, and in
+//! update mode it replaces the golden output instead of being compared.
+
+use super::{TestCase, TestOutcome, TestSuite};
+use crate::pipeline::{Pipeline, apply_transform};
+use morphir_common::loader::load_ir;
+use morphir_core::ir::diff::diff_ir_ignoring;
+use morphir_design::{ConfigContext, resolve_test_fixture, sanitize_project_name};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// File describing a scenario
+pub const SCENARIO_FILE: &str = "scenario.toml";
+
+/// Differences listed for a failed scenario; the rest are counted
+const MAX_DETAILS: usize = 20;
+
+/// Build stage a scenario runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Compile,
+    Transform,
+    Generate,
+}
+
+impl Stage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::Compile => "compile",
+            Stage::Transform => "transform",
+            Stage::Generate => "generate",
+        }
+    }
+}
+
+/// Contents of `scenario.toml`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioSpec {
+    pub description: Option<String>,
+    pub stage: Stage,
+    /// Input relative to the scenario directory
+    pub input: Option<PathBuf>,
+    /// Fixture under `.morphir/test/fixtures/` used as input instead
+    pub fixture: Option<String>,
+    /// Source language of a compile scenario
+    pub language: Option<String>,
+    /// Builtin transform of a transform scenario
+    pub transform: Option<String>,
+    /// Backend of a generate scenario
+    pub target: Option<String>,
+    /// Transform or generation options
+    #[serde(default)]
+    pub options: serde_json::Value,
+    /// Golden output relative to the scenario directory
+    pub expected: Option<PathBuf>,
+    /// IR members left out of the comparison
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+/// A scenario found on disk
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    /// Path of the scenario directory relative to the scenarios root
+    pub name: String,
+    pub dir: PathBuf,
+    /// The parsed `scenario.toml`, or why it could not be parsed
+    pub spec: Result<ScenarioSpec, String>,
+}
+
+impl Scenario {
+    /// Load the scenario in `dir`, named `name`
+    pub fn load(name: impl Into<String>, dir: &Path) -> Self {
+        let path = dir.join(SCENARIO_FILE);
+        let spec = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            .and_then(|content| {
+                toml::from_str(&content)
+                    .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+            });
+        Self {
+            name: name.into(),
+            dir: dir.to_path_buf(),
+            spec,
+        }
+    }
+}
+
+/// Every scenario under `root`, by name
+pub fn discover_scenarios(root: &Path) -> Vec<Scenario> {
+    let mut scenarios: Vec<Scenario> = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && entry.file_name() == SCENARIO_FILE)
+        .filter_map(|entry| {
+            let dir = entry.path().parent()?;
+            let name = dir
+                .strip_prefix(root)
+                .ok()?
+                .to_string_lossy()
+                .replace('\\', "/");
+            let name = if name.is_empty() {
+                ".".to_string()
+            } else {
+                name
+            };
+            Some(Scenario::load(name, dir))
+        })
+        .collect();
+    scenarios.sort_by(|a, b| a.name.cmp(&b.name));
+    scenarios
+}
+
+/// Runs scenarios against a workspace
+pub struct ScenarioRunner<'a> {
+    pipeline: &'a Pipeline,
+    ctx: &'a ConfigContext,
+    update: bool,
+}
+
+impl<'a> ScenarioRunner<'a> {
+    /// A runner building with `pipeline` in the workspace of `ctx`
+    pub fn new(pipeline: &'a Pipeline, ctx: &'a ConfigContext) -> Self {
+        Self {
+            pipeline,
+            ctx,
+            update: false,
+        }
+    }
+
+    /// Replace the golden outputs with the actual ones instead of comparing
+    pub fn with_update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Run `scenarios` in order
+    pub async fn run_all(&self, scenarios: &[Scenario]) -> TestSuite {
+        let mut suite = TestSuite::new("scenarios");
+        for scenario in scenarios {
+            suite.cases.push(self.run(scenario).await);
+        }
+        suite
+    }
+
+    /// Run one scenario
+    pub async fn run(&self, scenario: &Scenario) -> TestCase {
+        let started = Instant::now();
+        let (kind, outcome) = match &scenario.spec {
+            Ok(spec) => {
+                let outcome = match self.run_spec(scenario, spec).await {
+                    Ok(outcome) => outcome,
+                    Err(message) => TestOutcome::Error { message },
+                };
+                (spec.stage.as_str().to_string(), outcome)
+            }
+            Err(message) => (
+                "scenario".to_string(),
+                TestOutcome::Error {
+                    message: message.clone(),
+                },
+            ),
+        };
+        TestCase {
+            name: scenario.name.clone(),
+            kind,
+            outcome,
+            duration: started.elapsed(),
+        }
+    }
+
+    async fn run_spec(
+        &self,
+        scenario: &Scenario,
+        spec: &ScenarioSpec,
+    ) -> Result<TestOutcome, String> {
+        let input = match &spec.fixture {
+            Some(fixture) => resolve_test_fixture(fixture, &self.ctx.morphir_dir),
+            None => scenario
+                .dir
+                .join(spec.input.as_deref().unwrap_or(Path::new("input"))),
+        };
+        if !input.exists() {
+            return Err(format!("Input {} does not exist", input.display()));
+        }
+        let out_dir = self
+            .ctx
+            .morphir_dir
+            .join("out")
+            .join("test")
+            .join(sanitize_project_name(&scenario.name));
+        if out_dir.exists() {
+            std::fs::remove_dir_all(&out_dir)
+                .map_err(|e| format!("Failed to clear {}: {}", out_dir.display(), e))?;
+        }
+        let options = match &spec.options {
+            serde_json::Value::Null => serde_json::json!({}),
+            options => options.clone(),
+        };
+
+        match spec.stage {
+            Stage::Compile => {
+                let mut pipeline = self
+                    .pipeline
+                    .clone()
+                    .with_input(&input)
+                    .with_compile_output(out_dir.join("compile"));
+                if let Some(language) = &spec.language {
+                    pipeline = pipeline.with_language(language);
+                }
+                let compiled = pipeline
+                    .compile(self.ctx)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !compiled.success {
+                    return Err(compiled
+                        .error
+                        .unwrap_or_else(|| "Compilation failed".to_string()));
+                }
+                let ir = match compiled.ir {
+                    Some(ir) => ir,
+                    None => load_ir(&compiled.output_path).map_err(|e| e.to_string())?,
+                };
+                self.check_ir(scenario, spec, &out_dir, ir)
+            }
+            Stage::Transform => {
+                let id = spec
+                    .transform
+                    .as_deref()
+                    .ok_or("Transform scenarios need a `transform`")?;
+                let ir = load_ir(&input).map_err(|e| format!("Failed to load IR: {}", e))?;
+                let transformed = apply_transform(id, &ir, &options).map_err(|e| e.to_string())?;
+                if let Some(error) = transformed.error {
+                    return Err(error);
+                }
+                // Transforms that only report, such as extractions, are
+                // checked on their report
+                let actual = transformed.ir.unwrap_or(transformed.report);
+                self.check_ir(scenario, spec, &out_dir, actual)
+            }
+            Stage::Generate => {
+                let ir = load_ir(&input).map_err(|e| format!("Failed to load IR: {}", e))?;
+                let mut pipeline = self
+                    .pipeline
+                    .clone()
+                    .with_generate_output(out_dir.join("generate"));
+                if let Some(target) = &spec.target {
+                    pipeline = pipeline.with_target(target);
+                }
+                if let serde_json::Value::Object(options) = options {
+                    pipeline = pipeline.with_generate_options(options);
+                }
+                let generated = pipeline
+                    .generate(self.ctx, &input, ir)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !generated.success {
+                    return Err(generated
+                        .error
+                        .unwrap_or_else(|| "Code generation failed".to_string()));
+                }
+                let expected = scenario
+                    .dir
+                    .join(spec.expected.as_deref().unwrap_or(Path::new("expected")));
+                if self.update {
+                    replace_tree(&generated.output_path, &expected)?;
+                    return Ok(TestOutcome::Passed);
+                }
+                if !expected.is_dir() {
+                    return Err(format!(
+                        "Expected output {} does not exist; run with --update to create it",
+                        expected.display()
+                    ));
+                }
+                let differences = diff_trees(&expected, &generated.output_path, &spec.ignore)?;
+                Ok(outcome(differences))
+            }
+        }
+    }
+
+    /// Compare `actual` IR with the golden file, after writing it next to
+    /// the scenario's other outputs
+    fn check_ir(
+        &self,
+        scenario: &Scenario,
+        spec: &ScenarioSpec,
+        out_dir: &Path,
+        actual: serde_json::Value,
+    ) -> Result<TestOutcome, String> {
+        let rendered = serde_json::to_string_pretty(&actual).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(out_dir)
+            .map_err(|e| format!("Failed to create {}: {}", out_dir.display(), e))?;
+        let actual_path = out_dir.join("actual.json");
+        std::fs::write(&actual_path, &rendered)
+            .map_err(|e| format!("Failed to write {}: {}", actual_path.display(), e))?;
+
+        let expected_path = scenario.dir.join(
+            spec.expected
+                .as_deref()
+                .unwrap_or(Path::new("expected.json")),
+        );
+        if self.update {
+            std::fs::write(&expected_path, rendered + "\n")
+                .map_err(|e| format!("Failed to write {}: {}", expected_path.display(), e))?;
+            return Ok(TestOutcome::Passed);
+        }
+        let content = std::fs::read_to_string(&expected_path).map_err(|e| {
+            format!(
+                "Failed to read expected output {}: {}; run with --update to create it",
+                expected_path.display(),
+                e
+            )
+        })?;
+        let expected: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", expected_path.display(), e))?;
+        let differences = diff_ir_ignoring(&expected, &actual, &spec.ignore)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        Ok(outcome(differences))
+    }
+}
+
+/// Passed when nothing differs, otherwise failed listing the differences
+fn outcome(mut differences: Vec<String>) -> TestOutcome {
+    if differences.is_empty() {
+        return TestOutcome::Passed;
+    }
+    let count = differences.len();
+    if count > MAX_DETAILS {
+        differences.truncate(MAX_DETAILS);
+        differences.push(format!("... and {} more", count - MAX_DETAILS));
+    }
+    TestOutcome::Failed {
+        message: format!(
+            "{} difference{} from the expected output",
+            count,
+            if count == 1 { "" } else { "s" }
+        ),
+        details: differences,
+    }
+}
+
+/// Files under `root`, relative to it
+fn files_under(root: &Path) -> BTreeSet<PathBuf> {
+    walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .collect()
+}
+
+/// Differences between the file trees at `expected` and `actual`
+fn diff_trees(expected: &Path, actual: &Path, ignored: &[String]) -> Result<Vec<String>, String> {
+    let expected_files = files_under(expected);
+    let actual_files = files_under(actual);
+    let mut differences = Vec::new();
+    for file in expected_files.union(&actual_files) {
+        let name = file.to_string_lossy().replace('\\', "/");
+        match (expected_files.contains(file), actual_files.contains(file)) {
+            (true, false) => differences.push(format!("{}: missing", name)),
+            (false, true) => differences.push(format!("{}: unexpected file", name)),
+            _ => {
+                let read = |root: &Path| {
+                    std::fs::read(root.join(file))
+                        .map_err(|e| format!("Failed to read {}: {}", root.join(file).display(), e))
+                };
+                differences.extend(diff_file(&name, &read(expected)?, &read(actual)?, ignored));
+            }
+        }
+    }
+    Ok(differences)
+}
+
+/// Differences between two versions of the file `name`
+fn diff_file(name: &str, expected: &[u8], actual: &[u8], ignored: &[String]) -> Vec<String> {
+    if expected == actual {
+        return Vec::new();
+    }
+    if name.ends_with(".json")
+        && let (Ok(e), Ok(a)) = (
+            serde_json::from_slice::<serde_json::Value>(expected),
+            serde_json::from_slice::<serde_json::Value>(actual),
+        )
+    {
+        return diff_ir_ignoring(&e, &a, ignored)
+            .iter()
+            .map(|d| format!("{}: {}", name, d))
+            .collect();
+    }
+    let (Ok(expected), Ok(actual)) = (std::str::from_utf8(expected), std::str::from_utf8(actual))
+    else {
+        return vec![format!("{}: contents differ", name)];
+    };
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (None, None) => return Vec::new(),
+            (e, a) => {
+                let show =
+                    |l: Option<&str>| l.map_or("end of file".to_string(), |l| format!("`{}`", l));
+                return vec![format!(
+                    "{}:{}: expected {}, got {}",
+                    name,
+                    line,
+                    show(e),
+                    show(a)
+                )];
+            }
+        }
+    }
+}
+
+/// Replace the tree at `to` with a copy of the tree at `from`
+fn replace_tree(from: &Path, to: &Path) -> Result<(), String> {
+    if to.exists() {
+        std::fs::remove_dir_all(to)
+            .map_err(|e| format!("Failed to clear {}: {}", to.display(), e))?;
+    }
+    for file in files_under(from) {
+        let target = to.join(&file);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::copy(from.join(&file), &target)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_scenarios_by_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "orders/scenario.toml",
+            "stage = \"compile\"\nlanguage = \"gleam\"\n",
+        );
+        write(
+            "openapi/pricing/scenario.toml",
+            "stage = \"generate\"\ntarget = \"openapi\"\n",
+        );
+        write("broken/scenario.toml", "stage = \"deploy\"\n");
+        write("notes/README.md", "not a scenario");
+
+        let scenarios = discover_scenarios(dir.path());
+        let names: Vec<&str> = scenarios.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["broken", "openapi/pricing", "orders"]);
+        assert!(scenarios[0].spec.is_err());
+        let spec = scenarios[1].spec.as_ref().unwrap();
+        assert_eq!(spec.stage, Stage::Generate);
+        assert_eq!(spec.target.as_deref(), Some("openapi"));
+    }
+
+    #[test]
+    fn test_diff_trees_compares_json_structurally_and_text_by_line() {
+        let expected = tempfile::tempdir().unwrap();
+        let actual = tempfile::tempdir().unwrap();
+        let write = |root: &Path, path: &str, content: &str| {
+            std::fs::write(root.join(path), content).unwrap();
+        };
+        write(
+            expected.path(),
+            "api.json",
+            r#"{"title": "Orders", "version": 1}"#,
+        );
+        write(
+            actual.path(),
+            "api.json",
+            r#"{"version": 1.0, "title": "Order"}"#,
+        );
+        write(expected.path(), "README.md", "# Orders\nGenerated\n");
+        write(actual.path(), "README.md", "# Orders\nGenerated by hand\n");
+        write(expected.path(), "old.txt", "");
+        write(actual.path(), "new.txt", "");
+
+        let differences = diff_trees(expected.path(), actual.path(), &[]).unwrap();
+        assert_eq!(
+            differences,
+            vec![
+                "README.md:2: expected `Generated`, got `Generated by hand`",
+                "api.json: .title: expected \"Orders\", got \"Order\"",
+                "new.txt: unexpected file",
+                "old.txt: missing",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outcome_lists_a_bounded_number_of_differences() {
+        assert_eq!(outcome(Vec::new()), TestOutcome::Passed);
+        let differences: Vec<String> = (0..25).map(|i| format!("difference {}", i)).collect();
+        let TestOutcome::Failed { message, details } = outcome(differences) else {
+            panic!("expected a failure");
+        };
+        assert_eq!(message, "25 differences from the expected output");
+        assert_eq!(details.len(), MAX_DETAILS + 1);
+        assert_eq!(details[MAX_DETAILS], "... and 5 more");
+    }
+}
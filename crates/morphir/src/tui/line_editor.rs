@@ -0,0 +1,367 @@
+//! Line editor for interactive prompts.
+//!
+//! Reads one line at a time in raw mode, with:
+//! - Cursor movement (arrows, Home/End, Ctrl-A/Ctrl-E)
+//! - History (Up/Down), optionally persisted to a file
+//! - Tab completion of the word before the cursor
+//! - Ctrl-U/Ctrl-K/Ctrl-W to delete, Ctrl-C to discard the line, Ctrl-D to end
+//!
+//! When stdin is not a terminal, lines are read as they come, without
+//! prompts or editing.
+
+use crossterm::{
+    cursor::MoveToColumn,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+/// Characters that end the word tab completion replaces
+const WORD_DELIMITERS: &[char] = &[' ', '\t', '(', ')', '[', ']', '{', '}', ',', '\\'];
+
+/// Most completions listed when Tab cannot narrow them down
+const MAX_LISTED: usize = 60;
+
+/// Most history entries kept
+const MAX_HISTORY: usize = 1000;
+
+/// Line editor with history and tab completion
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+/// Restores cooked mode when reading a line ends, including on error.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load history from `path`, and append new entries to it.
+    pub fn with_history_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            self.history = content.lines().map(str::to_string).collect();
+            let excess = self.history.len().saturating_sub(MAX_HISTORY);
+            self.history.drain(..excess);
+        }
+        self.history_path = Some(path);
+        self
+    }
+
+    /// Record a line in the history, unless it is blank or repeats the last
+    /// one.
+    pub fn add_history(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if let Some(path) = &self.history_path {
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Ok(mut file) = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+            {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    /// Read a line after showing `prompt`. `complete` lists the completions
+    /// of the word before the cursor. Returns `None` at the end of input.
+    pub fn read_line(
+        &mut self,
+        prompt: &str,
+        complete: &dyn Fn(&str) -> Vec<String>,
+    ) -> io::Result<Option<String>> {
+        if !io::stdin().is_terminal() {
+            let mut line = String::new();
+            return match io::stdin().lock().read_line(&mut line)? {
+                0 => Ok(None),
+                _ => Ok(Some(line.trim_end_matches(['\n', '\r']).to_string())),
+            };
+        }
+
+        let _raw = RawMode::enable()?;
+        let mut stdout = io::stdout();
+        let mut line = Line::default();
+        // Position in the history while browsing it, and the line being
+        // edited before browsing started
+        let mut browsing: Option<usize> = None;
+        let mut draft = Vec::new();
+        let mut listed = false;
+        line.redraw(&mut stdout, prompt)?;
+
+        loop {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            let tab = key.code == KeyCode::Tab;
+            // Ctrl-J and Ctrl-M are how a typed-ahead newline arrives
+            let enter = key.code == KeyCode::Enter
+                || (key.modifiers.contains(KeyModifiers::CONTROL)
+                    && matches!(key.code, KeyCode::Char('j' | 'm')));
+            match key {
+                _ if enter => {
+                    queue!(stdout, Print("\r\n"))?;
+                    stdout.flush()?;
+                    return Ok(Some(line.text()));
+                }
+                KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                } => {
+                    queue!(stdout, Print("^C\r\n"))?;
+                    stdout.flush()?;
+                    return Ok(Some(String::new()));
+                }
+                KeyEvent {
+                    code: KeyCode::Char('d'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                } => {
+                    if line.chars.is_empty() {
+                        queue!(stdout, Print("\r\n"))?;
+                        stdout.flush()?;
+                        return Ok(None);
+                    }
+                    line.delete();
+                }
+                KeyEvent {
+                    code: KeyCode::Char(c),
+                    modifiers,
+                    ..
+                } if modifiers.contains(KeyModifiers::CONTROL) => match c {
+                    'a' => line.cursor = 0,
+                    'e' => line.cursor = line.chars.len(),
+                    'u' => {
+                        line.chars.drain(..line.cursor);
+                        line.cursor = 0;
+                    }
+                    'k' => line.chars.truncate(line.cursor),
+                    'w' => {
+                        let start = word_start(&line.chars[..line.cursor], |c| c.is_whitespace());
+                        line.chars.drain(start..line.cursor);
+                        line.cursor = start;
+                    }
+                    _ => {}
+                },
+                KeyEvent {
+                    code: KeyCode::Char(c),
+                    ..
+                } => line.insert(&[c]),
+                KeyEvent {
+                    code: KeyCode::Backspace,
+                    ..
+                } if line.cursor > 0 => {
+                    line.cursor -= 1;
+                    line.delete();
+                }
+                KeyEvent {
+                    code: KeyCode::Delete,
+                    ..
+                } => line.delete(),
+                KeyEvent {
+                    code: KeyCode::Left,
+                    ..
+                } => line.cursor = line.cursor.saturating_sub(1),
+                KeyEvent {
+                    code: KeyCode::Right,
+                    ..
+                } => line.cursor = (line.cursor + 1).min(line.chars.len()),
+                KeyEvent {
+                    code: KeyCode::Home,
+                    ..
+                } => line.cursor = 0,
+                KeyEvent {
+                    code: KeyCode::End, ..
+                } => line.cursor = line.chars.len(),
+                KeyEvent {
+                    code: KeyCode::Up, ..
+                } => {
+                    let previous = match browsing {
+                        None if !self.history.is_empty() => {
+                            draft = line.chars.clone();
+                            Some(self.history.len() - 1)
+                        }
+                        Some(i) if i > 0 => Some(i - 1),
+                        _ => None,
+                    };
+                    if let Some(i) = previous {
+                        browsing = Some(i);
+                        line.set(self.history[i].chars().collect());
+                    }
+                }
+                KeyEvent {
+                    code: KeyCode::Down,
+                    ..
+                } => {
+                    if let Some(i) = browsing {
+                        if i + 1 < self.history.len() {
+                            browsing = Some(i + 1);
+                            line.set(self.history[i + 1].chars().collect());
+                        } else {
+                            browsing = None;
+                            line.set(std::mem::take(&mut draft));
+                        }
+                    }
+                }
+                KeyEvent {
+                    code: KeyCode::Tab, ..
+                } => {
+                    let start =
+                        word_start(&line.chars[..line.cursor], |c| WORD_DELIMITERS.contains(&c));
+                    let word: String = line.chars[start..line.cursor].iter().collect();
+                    let candidates = complete(&word);
+                    let prefix = common_prefix(&candidates);
+                    if prefix.chars().count() > word.chars().count() {
+                        let rest: Vec<char> = prefix.chars().skip(word.chars().count()).collect();
+                        line.insert(&rest);
+                        if candidates.len() == 1 {
+                            line.insert(&[' ']);
+                        }
+                    } else if candidates.len() > 1 && !listed {
+                        let mut listing = candidates
+                            .iter()
+                            .take(MAX_LISTED)
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join("  ");
+                        if candidates.len() > MAX_LISTED {
+                            listing
+                                .push_str(&format!("  ... {} more", candidates.len() - MAX_LISTED));
+                        }
+                        queue!(stdout, Print("\r\n"), Print(listing), Print("\r\n"))?;
+                        listed = true;
+                    }
+                }
+                _ => {}
+            }
+            if !tab {
+                listed = false;
+            }
+            line.redraw(&mut stdout, prompt)?;
+        }
+    }
+}
+
+/// The line being edited
+#[derive(Default)]
+struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl Line {
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn insert(&mut self, chars: &[char]) {
+        self.chars
+            .splice(self.cursor..self.cursor, chars.iter().copied());
+        self.cursor += chars.len();
+    }
+
+    fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    fn set(&mut self, chars: Vec<char>) {
+        self.cursor = chars.len();
+        self.chars = chars;
+    }
+
+    fn redraw(&self, stdout: &mut impl Write, prompt: &str) -> io::Result<()> {
+        let column = prompt.chars().count() + self.cursor;
+        queue!(
+            stdout,
+            MoveToColumn(0),
+            Clear(ClearType::UntilNewLine),
+            Print(prompt),
+            Print(self.text()),
+            MoveToColumn(column.min(u16::MAX as usize) as u16)
+        )?;
+        stdout.flush()
+    }
+}
+
+/// Start of the word ending at the end of `chars`
+fn word_start(chars: &[char], is_delimiter: impl Fn(char) -> bool) -> usize {
+    let end = chars.len() - chars.iter().rev().take_while(|c| is_delimiter(**c)).count();
+    chars[..end]
+        .iter()
+        .rposition(|c| is_delimiter(*c))
+        .map_or(0, |i| i + 1)
+}
+
+/// Longest prefix all `candidates` share
+fn common_prefix(candidates: &[String]) -> String {
+    let Some((first, rest)) = candidates.split_first() else {
+        return String::new();
+    };
+    let mut prefix = first.as_str();
+    for candidate in rest {
+        let shared = prefix
+            .char_indices()
+            .zip(candidate.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(prefix.len().min(candidate.len()), |((i, _), _)| i);
+        prefix = &prefix[..shared];
+    }
+    prefix.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_start() {
+        let chars: Vec<char> = "f (my/pkg:orders#to".chars().collect();
+        assert_eq!(word_start(&chars, |c| WORD_DELIMITERS.contains(&c)), 3);
+        let chars: Vec<char> = "let x  ".chars().collect();
+        assert_eq!(word_start(&chars, char::is_whitespace), 4);
+        assert_eq!(word_start(&[], char::is_whitespace), 0);
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        let candidates = ["my/pkg:orders#total", "my/pkg:orders#tax"].map(String::from);
+        assert_eq!(common_prefix(&candidates), "my/pkg:orders#t");
+        assert_eq!(
+            common_prefix(&["été".to_string(), "étang".to_string()]),
+            "ét"
+        );
+        assert_eq!(common_prefix(&[]), "");
+    }
+}
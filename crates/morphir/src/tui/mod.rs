@@ -4,6 +4,8 @@
 //! for interactive display of content like JSON viewers, progress indicators,
 //! and other visual elements.
 
+mod line_editor;
 mod pager;
 
+pub use line_editor::LineEditor;
 pub use pager::JsonPager;